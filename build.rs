@@ -0,0 +1,20 @@
+//! Build script
+//!
+//! Records the version of the compiler building Obsidium. Native plugins
+//! share Rust types with the server, so the plugin loader refuses plugins
+//! built by another compiler.
+
+use std::process::Command;
+
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("-V")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=OBSIDIUM_RUSTC_VERSION={}", version.trim());
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,429 @@
+//! HTTP admin API
+//!
+//! An optional, token-authenticated HTTP interface for dashboards and
+//! scripts that manage the server without RCON. Every request must carry
+//! the configured token, either as `Authorization: Bearer <token>` or, for
+//! browser WebSocket clients that cannot set headers, as `?token=<token>`.
+//!
+//! Endpoints:
+//!
+//! - `GET /api/players`: online players
+//! - `POST /api/players/<name>/kick`: disconnect a player (`{"reason": ...}` optional)
+//! - `POST /api/players/<name>/ban`: ban and disconnect a player (`{"reason": ...}` optional)
+//! - `GET /api/bans`: banned players
+//! - `DELETE /api/bans/<name>`: remove a ban
+//! - `POST /api/command`: run a command (`{"command": "say hi"}`)
+//! - `GET /api/packets`: packets and bytes sent and received, by packet type
+//! - `GET /api/audit`: audit log entries, oldest first (`?player=`, `?type=`,
+//!   `?since=` and `?limit=` filter them)
+//! - `GET /api/events`: WebSocket stream of console log lines and chat
+
+pub mod websocket;
+
+use crate::command::AdminSender;
+use crate::config::secrets::Secret;
+use crate::game::player::Player;
+use crate::network::http::{Request, Response};
+use crate::protocol::types::JsonTextComponent;
+use crate::server::ServerState;
+use crate::server::audit::{AuditEvent, AuditQuery, DEFAULT_QUERY_LIMIT};
+use crate::server::bans::BanEntry;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncWrite, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Name actions taken through the API are attributed to
+const ADMIN_SOURCE: &str = "Admin";
+
+/// How long a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Admin API settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminApiConfig {
+    /// Address the API listens on
+    pub bind_address: SocketAddr,
+    /// Token clients must present
+    pub token: Secret,
+}
+
+/// Start the admin API if it is enabled
+///
+/// Returns `None` if the API is disabled. The task ends once a shutdown has
+/// been requested.
+pub fn spawn(state: Arc<ServerState>) -> Option<JoinHandle<()>> {
+    let config = state.config.admin_api.clone()?;
+
+    Some(tokio::spawn(async move {
+        let listener = match TcpListener::bind(config.bind_address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to bind the admin API to {}: {}",
+                    config.bind_address,
+                    e
+                );
+                return;
+            }
+        };
+        tracing::info!("Admin API listening on {}", config.bind_address);
+
+        let token: Arc<str> = config.token.expose().into();
+        let mut shutdown = state.subscribe_shutdown();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, addr)) => {
+                        let state = Arc::clone(&state);
+                        let token = Arc::clone(&token);
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_client(stream, &state, &token).await {
+                                tracing::debug!("Admin API client {} failed: {}", addr, e);
+                            }
+                        });
+                    }
+                    Err(e) => tracing::warn!("Failed to accept admin API client: {}", e),
+                },
+                _ = shutdown.changed() => break,
+            }
+        }
+    }))
+}
+
+/// Serve a single admin API client
+async fn handle_client(stream: TcpStream, state: &ServerState, token: &str) -> crate::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let request = match tokio::time::timeout(REQUEST_TIMEOUT, Request::read(&mut reader)).await {
+        Ok(Ok(request)) => request,
+        Ok(Err(e)) => {
+            return Response::error(400, &e.to_string())
+                .write(&mut writer)
+                .await;
+        }
+        Err(_) => return Ok(()),
+    };
+
+    if !is_authorized(&request, token) {
+        return Response::error(401, "Missing or invalid token")
+            .write(&mut writer)
+            .await;
+    }
+
+    if request.method == "GET" && request.segments == ["api", "events"] {
+        let key = request
+            .header("upgrade")
+            .filter(|upgrade| upgrade.eq_ignore_ascii_case("websocket"))
+            .and(request.header("sec-websocket-key"));
+        let Some(key) = key else {
+            return Response::error(400, "Expected a WebSocket upgrade")
+                .write(&mut writer)
+                .await;
+        };
+        websocket::accept(&mut writer, key).await?;
+        return stream_events(reader, writer, state).await;
+    }
+
+    route(&request, state).await.write(&mut writer).await
+}
+
+/// Check the token presented by a request
+fn is_authorized(request: &Request, token: &str) -> bool {
+    let presented = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| request.query_param("token"));
+
+    presented.is_some_and(|presented| {
+        // Compare in constant time so the token cannot be guessed byte by byte
+        presented.len() == token.len()
+            && presented
+                .bytes()
+                .zip(token.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    })
+}
+
+/// Handle a REST request
+async fn route(request: &Request, state: &ServerState) -> Response {
+    let segments: Vec<&str> = request.segments.iter().map(String::as_str).collect();
+    let Some(body) = request.json() else {
+        return Response::error(400, "Request body is not valid JSON");
+    };
+    let reason = body["reason"].as_str();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["api", "players"]) => list_players(state),
+        ("POST", ["api", "players", name, "kick"]) => kick(state, name, reason),
+        ("POST", ["api", "players", name, "ban"]) => ban(state, name, reason),
+        ("GET", ["api", "bans"]) => Response::json(
+            200,
+            serde_json::to_value(state.bans.entries()).unwrap_or_default(),
+        ),
+        ("DELETE", ["api", "bans", name]) => match state.bans.pardon(name) {
+            Ok(true) => {
+                tracing::info!("Admin API unbanned {}", name);
+                state.audit.record(AuditEvent::Pardon {
+                    player: name.to_string(),
+                    source: ADMIN_SOURCE.to_string(),
+                });
+                Response::no_content()
+            }
+            Ok(false) => Response::error(404, "Player is not banned"),
+            Err(e) => Response::error(500, &e.to_string()),
+        },
+        ("GET", ["api", "packets"]) => packet_stats(state),
+        ("GET", ["api", "audit"]) => audit_log(request, state),
+        ("POST", ["api", "command"]) => match body["command"].as_str() {
+            Some(command) => execute_command(state, command).await,
+            None => Response::error(400, "Expected {\"command\": \"...\"}"),
+        },
+        _ => Response::error(404, "Not found"),
+    }
+}
+
+/// `GET /api/players`
+fn list_players(state: &ServerState) -> Response {
+    let players: Vec<_> = state
+        .players
+        .get_all_players()
+        .iter()
+        .map(|player| serde_json::json!({ "name": player.username, "uuid": player.uuid }))
+        .collect();
+
+    Response::json(
+        200,
+        serde_json::json!({
+            "online": players.len(),
+            "max": state.settings().max_players,
+            "players": players,
+        }),
+    )
+}
+
+/// `GET /api/packets`
+fn packet_stats(state: &ServerState) -> Response {
+    let totals = state.packet_counters.snapshot();
+    let types: Vec<_> = state
+        .packet_counters
+        .by_type()
+        .iter()
+        .map(|counts| {
+            serde_json::json!({
+                "state": counts.packet_type.state.as_str(),
+                "direction": counts.packet_type.direction.as_str(),
+                "id": counts.packet_type.id,
+                "packets": counts.packets,
+                "bytes": counts.bytes,
+            })
+        })
+        .collect();
+
+    Response::json(
+        200,
+        serde_json::json!({
+            "received": totals.received,
+            "sent": totals.sent,
+            "bytes_received": totals.bytes_received,
+            "bytes_sent": totals.bytes_sent,
+            "types": types,
+        }),
+    )
+}
+
+/// `GET /api/audit`
+fn audit_log(request: &Request, state: &ServerState) -> Response {
+    let param = |name| request.query_param(name).map(str::to_string);
+    let limit = match request.query_param("limit").map(str::parse) {
+        Some(Ok(limit)) => limit,
+        Some(Err(_)) => return Response::error(400, "limit must be a number"),
+        None => DEFAULT_QUERY_LIMIT,
+    };
+    let query = AuditQuery {
+        player: param("player"),
+        kind: param("type"),
+        since: param("since"),
+        limit,
+    };
+    match state.audit.query(&query) {
+        Ok(entries) => Response::json(200, serde_json::to_value(entries).unwrap_or_default()),
+        Err(e) => Response::error(500, &e.to_string()),
+    }
+}
+
+/// `POST /api/players/<name>/kick`
+fn kick(state: &ServerState, name: &str, reason: Option<&str>) -> Response {
+    let Some(player) = find_player(state, name) else {
+        return Response::error(404, "Player is not online");
+    };
+    let reason = reason.map_or_else(
+        || crate::lang::translate("multiplayer.disconnect.kicked", &[]),
+        JsonTextComponent::text,
+    );
+
+    tracing::info!(
+        "Admin API kicked {}: {}",
+        player.username,
+        reason.to_plain_text()
+    );
+    state.audit.record(AuditEvent::Kick {
+        player: player.username.clone(),
+        source: ADMIN_SOURCE.to_string(),
+        reason: reason.to_plain_text(),
+    });
+    state.kick_player(&player.uuid, reason);
+    Response::no_content()
+}
+
+/// `POST /api/players/<name>/ban`
+fn ban(state: &ServerState, name: &str, reason: Option<&str>) -> Response {
+    let Some(player) = find_player(state, name) else {
+        return Response::error(404, "Player is not online");
+    };
+
+    let entry = BanEntry::new(player.uuid, &player.username, ADMIN_SOURCE, reason);
+    if let Err(e) = state.bans.ban(entry.clone()) {
+        return Response::error(500, &e.to_string());
+    }
+    state.audit.record(AuditEvent::Ban {
+        player: player.username.clone(),
+        uuid: player.uuid,
+        source: ADMIN_SOURCE.to_string(),
+        reason: entry.reason.clone(),
+    });
+    state.kick_player(&player.uuid, entry.message());
+    tracing::info!("Admin API banned {}: {}", player.username, entry.reason);
+    Response::json(200, serde_json::to_value(entry).unwrap_or_default())
+}
+
+/// `POST /api/command`
+async fn execute_command(state: &ServerState, command: &str) -> Response {
+    let sender = AdminSender::new();
+    let command = command.trim().trim_start_matches('/');
+    let result = state.execute_command(&sender, command).await;
+
+    Response::json(
+        200,
+        serde_json::json!({
+            "success": result.is_ok(),
+            "result": result.as_ref().ok(),
+            "error": result.as_ref().err().map(ToString::to_string),
+            "output": sender.take_output(),
+        }),
+    )
+}
+
+/// Find an online player by name
+fn find_player(state: &ServerState, name: &str) -> Option<Player> {
+    state
+        .players
+        .get_all_players()
+        .into_iter()
+        .find(|player| player.username.eq_ignore_ascii_case(name))
+}
+
+/// Push log lines and chat messages to a WebSocket client until it leaves
+async fn stream_events<R, W>(mut reader: R, mut writer: W, state: &ServerState) -> crate::Result<()>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut logs = crate::logger::subscribe();
+    let mut chat = state.subscribe_chat();
+    let mut shutdown = state.subscribe_shutdown();
+
+    loop {
+        let event = tokio::select! {
+            line = logs.recv() => match line {
+                Ok(line) => serde_json::json!({ "type": "log", "message": line }),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = chat.recv() => match message {
+                Ok(message) => serde_json::json!({
+                    "type": "chat",
+                    "message": message.to_plain_text(),
+                    "component": serde_json::from_str::<serde_json::Value>(&message.0)
+                        .unwrap_or_default(),
+                }),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            frame = websocket::read_frame(&mut reader) => match frame? {
+                websocket::Frame::Ping(payload) => {
+                    websocket::write_pong(&mut writer, &payload).await?;
+                    continue;
+                }
+                websocket::Frame::Close => break,
+                websocket::Frame::Data(_) | websocket::Frame::Pong => continue,
+            },
+            _ = shutdown.changed() => break,
+        };
+        websocket::write_text(&mut writer, &event.to_string()).await?;
+    }
+
+    websocket::write_close(&mut writer).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            segments: path
+                .split('/')
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect(),
+            query: Vec::new(),
+            headers: vec![("authorization".to_string(), "Bearer secret".to_string())],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes() {
+        let state = ServerState::new(ServerConfig::default());
+
+        let mut unauthorized = request("GET", "/api/players", "");
+        assert!(is_authorized(&unauthorized, "secret"));
+        assert!(!is_authorized(&unauthorized, "secret2"));
+        unauthorized.headers.clear();
+        assert!(!is_authorized(&unauthorized, "secret"));
+
+        let players = route(&request("GET", "/api/players", ""), &state).await;
+        assert_eq!(players.status, 200);
+        assert_eq!(players.body.unwrap()["online"], 0);
+
+        let kick = route(&request("POST", "/api/players/Steve/kick", ""), &state).await;
+        assert_eq!(kick.status, 404);
+
+        let command = r#"{"command": "/seed"}"#;
+        let response = route(&request("POST", "/api/command", command), &state).await;
+        let body = response.body.unwrap();
+        assert_eq!(body["success"], true);
+        assert!(body["output"][0].as_str().unwrap().contains("Seed"));
+
+        state
+            .packet_counters
+            .record_sent(crate::protocol::ConnectionState::Play, 0x27, 100);
+        let packets = route(&request("GET", "/api/packets", ""), &state).await;
+        assert_eq!(packets.body.unwrap()["types"][0]["bytes"], 100);
+
+        let audit = route(&request("GET", "/api/audit", ""), &state).await;
+        assert_eq!(audit.body.unwrap(), serde_json::json!([]));
+
+        let invalid = route(&request("POST", "/api/command", "{"), &state).await;
+        assert_eq!(invalid.status, 400);
+        assert_eq!(route(&request("GET", "/", ""), &state).await.status, 404);
+    }
+}
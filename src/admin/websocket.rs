@@ -0,0 +1,161 @@
+//! Minimal WebSocket (RFC 6455) support for the admin API
+//!
+//! The server only pushes text messages; client frames are read to answer
+//! pings and notice when the client closes the connection.
+
+use crate::error::{Result, ServerError};
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// GUID appended to the client key during the handshake
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Largest accepted client frame payload
+const MAX_FRAME_SIZE: u64 = 64 * 1024;
+
+/// Frame opcodes
+mod opcode {
+    pub const TEXT: u8 = 0x1;
+    pub const CLOSE: u8 = 0x8;
+    pub const PING: u8 = 0x9;
+    pub const PONG: u8 = 0xA;
+}
+
+/// A frame received from the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// A data frame (text, binary or continuation)
+    Data(Vec<u8>),
+    /// A ping that must be answered with a pong
+    Ping(Vec<u8>),
+    /// A pong
+    Pong,
+    /// The client is closing the connection
+    Close,
+}
+
+/// Compute the `Sec-WebSocket-Accept` value for a client key
+pub fn accept_key(key: &str) -> String {
+    let digest = Sha1::new()
+        .chain_update(key.trim())
+        .chain_update(HANDSHAKE_GUID)
+        .finalize();
+    base64::engine::general_purpose::STANDARD.encode(digest)
+}
+
+/// Complete the handshake by sending the `101 Switching Protocols` response
+pub async fn accept<W: AsyncWrite + Unpin>(writer: &mut W, key: &str) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    );
+    writer.write_all(response.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Send a text message
+pub async fn write_text<W: AsyncWrite + Unpin>(writer: &mut W, text: &str) -> Result<()> {
+    write_frame(writer, opcode::TEXT, text.as_bytes()).await
+}
+
+/// Answer a ping
+pub async fn write_pong<W: AsyncWrite + Unpin>(writer: &mut W, payload: &[u8]) -> Result<()> {
+    write_frame(writer, opcode::PONG, payload).await
+}
+
+/// Send a close frame
+pub async fn write_close<W: AsyncWrite + Unpin>(writer: &mut W) -> Result<()> {
+    write_frame(writer, opcode::CLOSE, &[]).await
+}
+
+/// Write a single unmasked, unfragmented frame
+async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    opcode: u8,
+    payload: &[u8],
+) -> Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read a frame sent by the client
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Frame> {
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header).await?;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+
+    let length = match header[1] & 0x7F {
+        126 => u64::from(reader.read_u16().await?),
+        127 => reader.read_u64().await?,
+        len => u64::from(len),
+    };
+    if length > MAX_FRAME_SIZE {
+        return Err(ServerError::Protocol(format!(
+            "WebSocket frame too large ({} bytes)",
+            length
+        )));
+    }
+    if !masked {
+        return Err(ServerError::Protocol(
+            "Client WebSocket frames must be masked".to_string(),
+        ));
+    }
+
+    let mut mask = [0u8; 4];
+    reader.read_exact(&mut mask).await?;
+    let mut payload = vec![0u8; length as usize];
+    reader.read_exact(&mut payload).await?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+
+    Ok(match opcode {
+        opcode::CLOSE => Frame::Close,
+        opcode::PING => Frame::Ping(payload),
+        opcode::PONG => Frame::Pong,
+        _ => Frame::Data(payload),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_handshake_and_frames() {
+        // Example from RFC 6455, section 1.3
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+
+        let mut written = Vec::new();
+        write_text(&mut written, "Hello").await.unwrap();
+        assert_eq!(written, b"\x81\x05Hello");
+
+        // Masked "Hello" from RFC 6455, section 5.7
+        let masked = b"\x81\x85\x37\xfa\x21\x3d\x7f\x9f\x4d\x51\x58";
+        let frame = read_frame(&mut &masked[..]).await.unwrap();
+        assert_eq!(frame, Frame::Data(b"Hello".to_vec()));
+
+        assert!(read_frame(&mut &b"\x81\x05Hello"[..]).await.is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Time sources
+//!
+//! Code that measures elapsed time reads the current time from a [`Clock`]
+//! instead of calling [`Instant::now`] directly. The server uses the
+//! [`SystemClock`]; tests substitute a [`ManualClock`] and move time forward
+//! explicitly, so timeouts and tick statistics can be checked without
+//! sleeping.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A source of the current time
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Get the current time
+    fn now(&self) -> Instant;
+
+    /// Time elapsed since `earlier`, or zero if it is in the future
+    fn since(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+/// A clock shared between the parts of a server
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Create a shared [`SystemClock`]
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// The real time, as reported by the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to
+///
+/// Clones share the same time, so a test can keep one and hand another to
+/// the code under test.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    /// The current time
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    /// Create a clock stopped at the current real time
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        *self.lock() += duration;
+    }
+
+    /// Wrap a clone of this clock for code taking a [`SharedClock`]
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Instant> {
+        self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new();
+        let shared = clock.shared();
+        let start = shared.now();
+        assert_eq!(shared.now(), start);
+
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(shared.since(start), Duration::from_secs(3));
+        assert_eq!(
+            shared.since(start + Duration::from_secs(10)),
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,619 @@
+//! Command argument types
+//!
+//! Arguments are read from an [`ArgumentReader`] that walks over the raw
+//! command input. Each argument type knows how to consume its own tokens,
+//! so multi-token arguments like positions work naturally.
+
+use crate::command::CommandError;
+use crate::command::selector::EntitySelector;
+use crate::game::player::PlayerPosition;
+use crate::game::world::registry::BlockRegistry;
+use crate::protocol::nbt::{self, Compound};
+use crate::protocol::types::{JsonTextComponent, Position};
+
+/// Cursor over the raw argument string of a command
+#[derive(Debug, Clone)]
+pub struct ArgumentReader<'a> {
+    /// Full argument input
+    input: &'a str,
+    /// Current byte offset
+    cursor: usize,
+}
+
+impl<'a> ArgumentReader<'a> {
+    /// Create a reader over the given input
+    pub fn new(input: &'a str) -> Self {
+        Self { input, cursor: 0 }
+    }
+
+    /// Skip leading whitespace
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.cursor..];
+        self.cursor += rest.len() - rest.trim_start().len();
+    }
+
+    /// Get the unread part of the input
+    pub fn remaining(&self) -> &'a str {
+        &self.input[self.cursor..]
+    }
+
+    /// Check whether all arguments have been consumed
+    pub fn is_empty(&self) -> bool {
+        self.remaining().trim().is_empty()
+    }
+
+    /// Read a whitespace-delimited token
+    ///
+    /// Whitespace inside `[...]` or double quotes does not end the token, so
+    /// selectors such as `@a[name="A B"]` are read as a single token.
+    pub fn read_token(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let rest = self.remaining();
+        if rest.is_empty() {
+            return None;
+        }
+
+        let mut depth = 0usize;
+        let mut in_quotes = false;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                '[' if !in_quotes => depth += 1,
+                ']' if !in_quotes => depth = depth.saturating_sub(1),
+                c if c.is_whitespace() && !in_quotes && depth == 0 => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        self.cursor += end;
+        Some(&rest[..end])
+    }
+
+    /// Read the rest of the input as a single string
+    pub fn read_remaining(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = self.remaining();
+        self.cursor = self.input.len();
+        rest.trim_end()
+    }
+}
+
+/// An argument type that can be parsed from an [`ArgumentReader`]
+pub trait ArgumentType {
+    /// The parsed value
+    type Output;
+
+    /// Human-readable name used in error messages (e.g. "integer")
+    fn name(&self) -> &'static str;
+
+    /// Parse the argument from the reader
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<Self::Output, CommandError>;
+}
+
+/// Read a token or report a missing argument
+fn expect_token<'a>(
+    reader: &mut ArgumentReader<'a>,
+    expected: &'static str,
+) -> Result<&'a str, CommandError> {
+    reader
+        .read_token()
+        .ok_or_else(|| CommandError::MissingArgument(expected.to_string()))
+}
+
+/// A bounded integer argument
+#[derive(Debug, Clone, Copy)]
+pub struct IntegerArgument {
+    /// Minimum accepted value
+    pub min: i64,
+    /// Maximum accepted value
+    pub max: i64,
+}
+
+impl IntegerArgument {
+    /// Accept any 32-bit integer
+    pub fn any() -> Self {
+        Self {
+            min: i32::MIN as i64,
+            max: i32::MAX as i64,
+        }
+    }
+
+    /// Accept integers in the inclusive range `min..=max`
+    pub fn range(min: i64, max: i64) -> Self {
+        Self { min, max }
+    }
+}
+
+impl ArgumentType for IntegerArgument {
+    type Output = i64;
+
+    fn name(&self) -> &'static str {
+        "integer"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<i64, CommandError> {
+        let token = expect_token(reader, self.name())?;
+        let value = token
+            .parse::<i64>()
+            .map_err(|_| CommandError::InvalidArgument {
+                expected: self.name().to_string(),
+                found: token.to_string(),
+            })?;
+
+        if value < self.min || value > self.max {
+            return Err(CommandError::OutOfRange {
+                value: token.to_string(),
+                min: self.min,
+                max: self.max,
+            });
+        }
+
+        Ok(value)
+    }
+}
+
+/// How much input a string argument consumes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringArgument {
+    /// A single unquoted word
+    Word,
+    /// A single word, or a double-quoted phrase
+    Phrase,
+    /// Everything up to the end of the input
+    Greedy,
+}
+
+impl ArgumentType for StringArgument {
+    type Output = String;
+
+    fn name(&self) -> &'static str {
+        "string"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<String, CommandError> {
+        match self {
+            StringArgument::Word => expect_token(reader, self.name()).map(str::to_string),
+            StringArgument::Phrase => {
+                let token = expect_token(reader, self.name())?;
+                match token.strip_prefix('"') {
+                    Some(quoted) => quoted
+                        .strip_suffix('"')
+                        .map(|s| s.replace("\\\"", "\""))
+                        .ok_or_else(|| CommandError::InvalidArgument {
+                            expected: "closing quote".to_string(),
+                            found: token.to_string(),
+                        }),
+                    None => Ok(token.to_string()),
+                }
+            }
+            StringArgument::Greedy => {
+                let rest = reader.read_remaining();
+                if rest.is_empty() {
+                    Err(CommandError::MissingArgument(self.name().to_string()))
+                } else {
+                    Ok(rest.to_string())
+                }
+            }
+        }
+    }
+}
+
+/// One of a fixed set of keywords (e.g. `clear|rain|thunder`), matched case-insensitively
+#[derive(Debug, Clone, Copy)]
+pub struct LiteralArgument<'a>(pub &'a [&'static str]);
+
+impl ArgumentType for LiteralArgument<'_> {
+    type Output = &'static str;
+
+    fn name(&self) -> &'static str {
+        "keyword"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<&'static str, CommandError> {
+        let token = expect_token(reader, self.name())?;
+        self.0
+            .iter()
+            .find(|literal| literal.eq_ignore_ascii_case(token))
+            .copied()
+            .ok_or_else(|| CommandError::InvalidArgument {
+                expected: self.0.join("|"),
+                found: token.to_string(),
+            })
+    }
+}
+
+/// A duration in ticks, with an optional unit suffix (`d` days, `s` seconds, `t` ticks)
+#[derive(Debug, Clone, Copy)]
+pub struct TimeArgument;
+
+impl TimeArgument {
+    /// Parse a time token such as `100`, `30s` or `0.5d` into ticks
+    pub fn parse_ticks(token: &str) -> Option<i64> {
+        let (number, multiplier) = match token.char_indices().last()? {
+            (i, 'd') => (&token[..i], 24_000.0),
+            (i, 's') => (&token[..i], 20.0),
+            (i, 't') => (&token[..i], 1.0),
+            _ => (token, 1.0),
+        };
+
+        let ticks = (number.parse::<f64>().ok()? * multiplier).round();
+        (ticks >= 0.0 && ticks <= i32::MAX as f64).then_some(ticks as i64)
+    }
+}
+
+impl ArgumentType for TimeArgument {
+    type Output = i64;
+
+    fn name(&self) -> &'static str {
+        "time"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<i64, CommandError> {
+        let token = expect_token(reader, self.name())?;
+        Self::parse_ticks(token).ok_or_else(|| CommandError::InvalidArgument {
+            expected: self.name().to_string(),
+            found: token.to_string(),
+        })
+    }
+}
+
+/// A single coordinate that may be relative to the sender (`~`, `~5`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    /// Absolute value or offset
+    pub value: f64,
+    /// Whether the value is relative to the origin
+    pub relative: bool,
+}
+
+impl Coordinate {
+    /// Parse a coordinate token
+    pub fn parse(token: &str) -> Option<Self> {
+        match token.strip_prefix('~') {
+            Some("") => Some(Self {
+                value: 0.0,
+                relative: true,
+            }),
+            Some(offset) => offset.parse().ok().map(|value| Self {
+                value,
+                relative: true,
+            }),
+            None => token.parse().ok().map(|value| Self {
+                value,
+                relative: false,
+            }),
+        }
+        .filter(|c| c.value.is_finite())
+    }
+
+    /// Resolve against an origin value
+    pub fn resolve(&self, origin: f64) -> f64 {
+        if self.relative {
+            origin + self.value
+        } else {
+            self.value
+        }
+    }
+}
+
+/// A set of three coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    /// X coordinate
+    pub x: Coordinate,
+    /// Y coordinate
+    pub y: Coordinate,
+    /// Z coordinate
+    pub z: Coordinate,
+}
+
+impl Coordinates {
+    /// Resolve to an absolute position relative to `origin`
+    pub fn resolve(&self, origin: &PlayerPosition) -> PlayerPosition {
+        PlayerPosition {
+            x: self.x.resolve(origin.x),
+            y: self.y.resolve(origin.y),
+            z: self.z.resolve(origin.z),
+        }
+    }
+
+    /// Resolve to a block position relative to `origin`
+    pub fn resolve_block(&self, origin: &PlayerPosition) -> Position {
+        let position = self.resolve(origin);
+        Position::new(
+            position.x.floor() as i32,
+            position.y.floor() as i32,
+            position.z.floor() as i32,
+        )
+    }
+}
+
+/// A position argument (`x y z`, each optionally relative with `~`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PositionArgument;
+
+impl ArgumentType for PositionArgument {
+    type Output = Coordinates;
+
+    fn name(&self) -> &'static str {
+        "position"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<Coordinates, CommandError> {
+        let mut parse_one = || -> Result<Coordinate, CommandError> {
+            let token = expect_token(reader, self.name())?;
+            Coordinate::parse(token).ok_or_else(|| CommandError::InvalidArgument {
+                expected: "coordinate".to_string(),
+                found: token.to_string(),
+            })
+        };
+
+        Ok(Coordinates {
+            x: parse_one()?,
+            y: parse_one()?,
+            z: parse_one()?,
+        })
+    }
+}
+
+/// An angle in degrees, such as the yaw to face at a spawn point
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AngleArgument;
+
+impl ArgumentType for AngleArgument {
+    type Output = f32;
+
+    fn name(&self) -> &'static str {
+        "angle"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<f32, CommandError> {
+        let token = expect_token(reader, self.name())?;
+        token
+            .parse::<f32>()
+            .ok()
+            .filter(|angle| angle.is_finite())
+            .ok_or_else(|| CommandError::InvalidArgument {
+                expected: self.name().to_string(),
+                found: token.to_string(),
+            })
+    }
+}
+
+/// A reference to one or more players
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerTarget {
+    /// An exact player name
+    Name(String),
+    /// A target selector
+    Selector(EntitySelector),
+}
+
+/// A player argument (name or selector)
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerArgument {
+    /// Whether more than one player may be selected
+    pub allow_multiple: bool,
+}
+
+impl PlayerArgument {
+    /// Accept exactly one player
+    pub fn single() -> Self {
+        Self {
+            allow_multiple: false,
+        }
+    }
+
+    /// Accept any number of players
+    pub fn multiple() -> Self {
+        Self {
+            allow_multiple: true,
+        }
+    }
+}
+
+impl ArgumentType for PlayerArgument {
+    type Output = PlayerTarget;
+
+    fn name(&self) -> &'static str {
+        "player"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<PlayerTarget, CommandError> {
+        let token = expect_token(reader, self.name())?;
+
+        if !EntitySelector::is_selector(token) {
+            return Ok(PlayerTarget::Name(token.to_string()));
+        }
+
+        let selector = EntitySelector::parse(token)?;
+        if !selector.players_only() {
+            return Err(CommandError::InvalidArgument {
+                expected: "player selector".to_string(),
+                found: token.to_string(),
+            });
+        }
+        if !self.allow_multiple && selector.effective_limit() > 1 {
+            return Err(CommandError::TooManyTargets);
+        }
+
+        Ok(PlayerTarget::Selector(selector))
+    }
+}
+
+/// A JSON text component taking up the rest of the input
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComponentArgument;
+
+impl ArgumentType for ComponentArgument {
+    type Output = JsonTextComponent;
+
+    fn name(&self) -> &'static str {
+        "component"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<JsonTextComponent, CommandError> {
+        let json = reader.read_remaining();
+        if json.is_empty() {
+            return Err(CommandError::MissingArgument(self.name().to_string()));
+        }
+        JsonTextComponent::parse(json).ok_or_else(|| CommandError::InvalidArgument {
+            expected: "JSON text component".to_string(),
+            found: json.to_string(),
+        })
+    }
+}
+
+/// A stringified NBT compound taking up the rest of the input
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NbtArgument;
+
+impl ArgumentType for NbtArgument {
+    type Output = Compound;
+
+    fn name(&self) -> &'static str {
+        "nbt"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<Compound, CommandError> {
+        let snbt = reader.read_remaining();
+        if snbt.is_empty() {
+            return Err(CommandError::MissingArgument(self.name().to_string()));
+        }
+        nbt::parse_snbt(snbt).map_err(|_| CommandError::InvalidArgument {
+            expected: "NBT compound".to_string(),
+            found: snbt.to_string(),
+        })
+    }
+}
+
+/// A block argument resolved against the block registry
+pub struct BlockArgument<'r> {
+    /// Registry used to look up block names
+    pub registry: &'r BlockRegistry,
+}
+
+impl<'r> BlockArgument<'r> {
+    /// Create a block argument backed by a registry
+    pub fn new(registry: &'r BlockRegistry) -> Self {
+        Self { registry }
+    }
+}
+
+impl ArgumentType for BlockArgument<'_> {
+    type Output = u32;
+
+    fn name(&self) -> &'static str {
+        "block"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<u32, CommandError> {
+        let token = expect_token(reader, self.name())?;
+        let name = if token.contains(':') {
+            token.to_string()
+        } else {
+            format!("minecraft:{}", token)
+        };
+
+        self.registry
+            .get_block_id(&name)
+            .ok_or_else(|| CommandError::InvalidArgument {
+                expected: self.name().to_string(),
+                found: token.to_string(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_tokens() {
+        let mut reader = ArgumentReader::new("  give @a[name=\"A B\"]  stone 64 ");
+        assert_eq!(reader.read_token(), Some("give"));
+        assert_eq!(reader.read_token(), Some("@a[name=\"A B\"]"));
+        assert_eq!(reader.read_remaining(), "stone 64");
+        assert!(reader.is_empty());
+        assert_eq!(reader.read_token(), None);
+    }
+
+    #[test]
+    fn test_integer_argument() {
+        let mut reader = ArgumentReader::new("5 100 abc");
+        let arg = IntegerArgument::range(1, 64);
+        assert_eq!(arg.parse(&mut reader).unwrap(), 5);
+        assert!(matches!(
+            arg.parse(&mut reader),
+            Err(CommandError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            arg.parse(&mut reader),
+            Err(CommandError::InvalidArgument { .. })
+        ));
+        assert!(matches!(
+            arg.parse(&mut reader),
+            Err(CommandError::MissingArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_literal_and_time_arguments() {
+        let mut reader = ArgumentReader::new("RAIN snow 2d 30s 15 -1");
+        let weather = LiteralArgument(&["clear", "rain", "thunder"]);
+        assert_eq!(weather.parse(&mut reader).unwrap(), "rain");
+        assert!(matches!(
+            weather.parse(&mut reader),
+            Err(CommandError::InvalidArgument { .. })
+        ));
+
+        assert_eq!(TimeArgument.parse(&mut reader).unwrap(), 48_000);
+        assert_eq!(TimeArgument.parse(&mut reader).unwrap(), 600);
+        assert_eq!(TimeArgument.parse(&mut reader).unwrap(), 15);
+        assert!(TimeArgument.parse(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_position_argument() {
+        let mut reader = ArgumentReader::new("~ ~-1.5 10");
+        let coordinates = PositionArgument.parse(&mut reader).unwrap();
+        let origin = PlayerPosition {
+            x: 3.0,
+            y: 64.0,
+            z: -2.0,
+        };
+        let resolved = coordinates.resolve(&origin);
+        assert_eq!((resolved.x, resolved.y, resolved.z), (3.0, 62.5, 10.0));
+        assert_eq!(coordinates.resolve_block(&origin), Position::new(3, 62, 10));
+
+        let mut reader = ArgumentReader::new("1 2");
+        assert!(PositionArgument.parse(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_player_and_block_arguments() {
+        let mut reader = ArgumentReader::new("Notch @a @p stone minecraft:bedrock diamond");
+        assert_eq!(
+            PlayerArgument::single().parse(&mut reader).unwrap(),
+            PlayerTarget::Name("Notch".to_string())
+        );
+        assert!(matches!(
+            PlayerArgument::single().parse(&mut reader),
+            Err(CommandError::TooManyTargets)
+        ));
+        assert!(matches!(
+            PlayerArgument::single().parse(&mut reader).unwrap(),
+            PlayerTarget::Selector(_)
+        ));
+
+        let registry = BlockRegistry::new();
+        let block = BlockArgument::new(&registry);
+        assert_eq!(block.parse(&mut reader).unwrap(), 1);
+        assert_eq!(block.parse(&mut reader).unwrap(), 7);
+        assert!(block.parse(&mut reader).is_err());
+    }
+}
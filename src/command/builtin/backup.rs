@@ -0,0 +1,69 @@
+//! `/backup` command
+
+use crate::command::argument::LiteralArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use async_trait::async_trait;
+
+/// Bytes in a mebibyte, for showing backup sizes
+const MEBIBYTE: f64 = 1024.0 * 1024.0;
+
+/// Starts a world backup or shows how the last one went
+pub struct BackupCommand;
+
+#[async_trait]
+impl Command for BackupCommand {
+    fn name(&self) -> &str {
+        "backup"
+    }
+
+    fn usage(&self) -> &str {
+        "[status]"
+    }
+
+    fn description(&self) -> &str {
+        "Starts a world backup or shows how the last one went"
+    }
+
+    fn permission_level(&self) -> u8 {
+        4
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let status = ctx
+            .optional_argument(&LiteralArgument(&["status"]))?
+            .is_some();
+        ctx.expect_end()?;
+
+        let backups = &ctx.server.backups;
+        if !status {
+            if !backups.request() {
+                return Err(CommandError::Failed(
+                    "A backup is already running".to_string(),
+                ));
+            }
+            ctx.reply("Starting a backup; progress is shown by /backup status");
+            return Ok(1);
+        }
+
+        if let Some((done, total)) = backups.progress() {
+            ctx.reply(&format!("Backup in progress: {}/{} files", done, total));
+            return Ok(done.min(i32::MAX as usize) as i32);
+        }
+        match backups.last_result() {
+            Some(Ok(report)) => {
+                ctx.reply(&format!(
+                    "Last backup: {} ({} files, {:.1} MiB, {:.1}s)",
+                    report.path.display(),
+                    report.files,
+                    report.bytes as f64 / MEBIBYTE,
+                    report.duration.as_secs_f64()
+                ));
+                Ok(1)
+            }
+            Some(Err(e)) => Err(CommandError::Failed(format!("Last backup failed: {}", e))),
+            None => Err(CommandError::Failed(
+                "No backup has been taken yet".to_string(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! `/ban` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::server::audit::AuditEvent;
+use crate::server::bans::BanEntry;
+use async_trait::async_trait;
+
+/// Bans a player, online or not, and disconnects them
+pub struct BanCommand;
+
+#[async_trait]
+impl Command for BanCommand {
+    fn name(&self) -> &str {
+        "ban"
+    }
+
+    fn usage(&self) -> &str {
+        "<player> [reason]"
+    }
+
+    fn description(&self) -> &str {
+        "Bans a player, online or not, and disconnects them"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let name = ctx.argument(&StringArgument::Word)?;
+        let reason = ctx.optional_argument(&StringArgument::Greedy)?;
+
+        let profile = ctx.resolve_profile(&name).await?;
+        if ctx.server.bans.get(&profile.uuid).is_some() {
+            return Err(CommandError::translatable("commands.ban.failed", &[]));
+        }
+        let entry = BanEntry::new(
+            profile.uuid,
+            &profile.name,
+            &ctx.sender.name(),
+            reason.as_deref(),
+        );
+        ctx.server
+            .bans
+            .ban(entry.clone())
+            .map_err(|e| CommandError::Failed(format!("Failed to save the ban: {}", e)))?;
+
+        ctx.server.audit.record(AuditEvent::Ban {
+            player: profile.name.clone(),
+            uuid: profile.uuid,
+            source: entry.source.clone(),
+            reason: entry.reason.clone(),
+        });
+        ctx.server.kick_player(&profile.uuid, entry.message());
+        ctx.reply_translatable("commands.ban.success", &[&profile.name, &entry.reason]);
+        Ok(1)
+    }
+}
@@ -0,0 +1,72 @@
+//! `/ban-ip` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::server::bans::IpBanEntry;
+use async_trait::async_trait;
+use std::net::IpAddr;
+
+/// Bans an address, or the address of an online player, and disconnects
+/// everyone connected from it
+pub struct BanIpCommand;
+
+#[async_trait]
+impl Command for BanIpCommand {
+    fn name(&self) -> &str {
+        "ban-ip"
+    }
+
+    fn usage(&self) -> &str {
+        "<target> [reason]"
+    }
+
+    fn description(&self) -> &str {
+        "Bans an IP address, or the address of an online player"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let target = ctx.argument(&StringArgument::Word)?;
+        let reason = ctx.optional_argument(&StringArgument::Greedy)?;
+
+        let players = ctx.server.players.get_all_players();
+        let ip = match target.parse::<IpAddr>() {
+            Ok(ip) => Some(ip.to_canonical()),
+            Err(_) => players
+                .iter()
+                .find(|player| player.username.eq_ignore_ascii_case(&target))
+                .and_then(|player| player.ip),
+        };
+        let Some(ip) = ip else {
+            return Err(CommandError::translatable("commands.banip.invalid", &[]));
+        };
+        if ctx.server.ip_bans.get(&ip).is_some() {
+            return Err(CommandError::translatable("commands.banip.failed", &[]));
+        }
+        let entry = IpBanEntry::new(ip, &ctx.sender.name(), reason.as_deref());
+        ctx.server
+            .ip_bans
+            .ban(entry.clone())
+            .map_err(|e| CommandError::Failed(format!("Failed to save the ban: {}", e)))?;
+
+        let banned: Vec<_> = players
+            .into_iter()
+            .filter(|player| player.ip == Some(ip))
+            .collect();
+        for player in &banned {
+            ctx.server.kick_player(&player.uuid, entry.message());
+        }
+        ctx.reply_translatable("commands.banip.success", &[&ip.to_string(), &entry.reason]);
+        if !banned.is_empty() {
+            let names: Vec<&str> = banned.iter().map(|p| p.username.as_str()).collect();
+            ctx.reply_translatable(
+                "commands.banip.info",
+                &[&banned.len().to_string(), &names.join(", ")],
+            );
+        }
+        Ok(banned.len() as i32)
+    }
+}
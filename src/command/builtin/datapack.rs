@@ -0,0 +1,118 @@
+//! `/datapack` command
+
+use crate::command::argument::{LiteralArgument, StringArgument};
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::game::datapack::PackPosition;
+use async_trait::async_trait;
+
+/// Lists, enables and disables data packs
+pub struct DatapackCommand;
+
+#[async_trait]
+impl Command for DatapackCommand {
+    fn name(&self) -> &str {
+        "datapack"
+    }
+
+    fn usage(&self) -> &str {
+        "list [available|enabled] | enable <name> [first|last] | disable <name>"
+    }
+
+    fn description(&self) -> &str {
+        "Lists, enables and disables data packs"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        match ctx.argument(&LiteralArgument(&["list", "enable", "disable"]))? {
+            "list" => {
+                let filter = ctx.optional_argument(&LiteralArgument(&["available", "enabled"]))?;
+                ctx.expect_end()?;
+                let datapacks = ctx.server.datapacks.read().await;
+                let mut count = 0;
+                if filter != Some("enabled") {
+                    let available: Vec<&str> = datapacks
+                        .available()
+                        .filter(|pack| !datapacks.is_enabled(&pack.id))
+                        .map(|pack| pack.id.as_str())
+                        .collect();
+                    count += available.len();
+                    match available.len() {
+                        0 => ctx.reply_translatable("commands.datapack.list.available.none", &[]),
+                        n => ctx.reply_translatable(
+                            "commands.datapack.list.available.success",
+                            &[&n.to_string(), &names(&available)],
+                        ),
+                    }
+                }
+                if filter != Some("available") {
+                    let enabled: Vec<&str> =
+                        datapacks.enabled().map(|pack| pack.id.as_str()).collect();
+                    count += enabled.len();
+                    match enabled.len() {
+                        0 => ctx.reply_translatable("commands.datapack.list.enabled.none", &[]),
+                        n => ctx.reply_translatable(
+                            "commands.datapack.list.enabled.success",
+                            &[&n.to_string(), &names(&enabled)],
+                        ),
+                    }
+                }
+                Ok(count as i32)
+            }
+            "enable" => {
+                let name = ctx.argument(&StringArgument::Phrase)?;
+                let position = match ctx.optional_argument(&LiteralArgument(&["first", "last"]))? {
+                    Some("first") => PackPosition::First,
+                    _ => PackPosition::Last,
+                };
+                ctx.expect_end()?;
+
+                known(ctx, &name).await?;
+                if !ctx.server.enable_datapack(&name, position).await {
+                    return Err(CommandError::translatable(
+                        "commands.datapack.enable.failed",
+                        &[&name],
+                    ));
+                }
+                ctx.reply_translatable("commands.datapack.modify.enable", &[&names(&[&name])]);
+                Ok(ctx.server.datapacks.read().await.enabled().count() as i32)
+            }
+            _ => {
+                let name = ctx.argument(&StringArgument::Phrase)?;
+                ctx.expect_end()?;
+
+                known(ctx, &name).await?;
+                if !ctx.server.disable_datapack(&name).await {
+                    return Err(CommandError::translatable(
+                        "commands.datapack.disable.failed",
+                        &[&name],
+                    ));
+                }
+                ctx.reply_translatable("commands.datapack.modify.disable", &[&names(&[&name])]);
+                Ok(ctx.server.datapacks.read().await.enabled().count() as i32)
+            }
+        }
+    }
+}
+
+/// Fail unless a pack is known
+async fn known(ctx: &CommandContext<'_>, name: &str) -> Result<(), CommandError> {
+    match ctx.server.datapacks.read().await.get(name) {
+        Some(_) => Ok(()),
+        None => Err(CommandError::translatable(
+            "commands.datapack.unknown",
+            &[name],
+        )),
+    }
+}
+
+/// Format pack IDs as a bracketed list
+fn names(ids: &[&str]) -> String {
+    ids.iter()
+        .map(|id| format!("[{}]", id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
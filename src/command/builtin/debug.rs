@@ -0,0 +1,61 @@
+//! `/debug` command
+
+use crate::command::argument::LiteralArgument;
+use crate::command::{Command, CommandContext, CommandResult};
+use async_trait::async_trait;
+
+/// Packet types listed by `/debug packets`
+const TOP_PACKET_TYPES: usize = 10;
+
+/// Shows diagnostics of the running server
+pub struct DebugCommand;
+
+#[async_trait]
+impl Command for DebugCommand {
+    fn name(&self) -> &str {
+        "debug"
+    }
+
+    fn usage(&self) -> &str {
+        "packets [reset]"
+    }
+
+    fn description(&self) -> &str {
+        "Shows diagnostics of the running server"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        ctx.argument(&LiteralArgument(&["packets"]))?;
+        let reset = ctx
+            .optional_argument(&LiteralArgument(&["reset"]))?
+            .is_some();
+        ctx.expect_end()?;
+
+        let counters = &ctx.server.packet_counters;
+        if reset {
+            counters.reset_types();
+            ctx.reply("Reset the packet statistics");
+            return Ok(1);
+        }
+
+        let totals = counters.snapshot();
+        ctx.reply(&format!(
+            "Received {} packets ({} bytes), sent {} packets ({} bytes)",
+            totals.received, totals.bytes_received, totals.sent, totals.bytes_sent
+        ));
+        let by_type = counters.by_type();
+        for counts in by_type.iter().take(TOP_PACKET_TYPES) {
+            ctx.reply(&format!(
+                "{}: {} packets, {} bytes",
+                counts.label(),
+                counts.packets,
+                counts.bytes
+            ));
+        }
+        Ok(by_type.len().min(i32::MAX as usize) as i32)
+    }
+}
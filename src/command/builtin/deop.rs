@@ -0,0 +1,52 @@
+//! `/deop` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::server::audit::AuditEvent;
+use async_trait::async_trait;
+
+/// Removes a player's operator status
+pub struct DeopCommand;
+
+#[async_trait]
+impl Command for DeopCommand {
+    fn name(&self) -> &str {
+        "deop"
+    }
+
+    fn usage(&self) -> &str {
+        "<player>"
+    }
+
+    fn description(&self) -> &str {
+        "Removes a player's operator status"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let name = ctx.argument(&StringArgument::Word)?;
+        ctx.expect_end()?;
+
+        let profile = ctx.resolve_profile(&name).await?;
+        let removed = ctx
+            .server
+            .ops
+            .deop(&profile.uuid)
+            .map_err(|e| CommandError::Failed(format!("Failed to save the ops: {}", e)))?;
+        if !removed {
+            return Err(CommandError::translatable("commands.deop.failed", &[]));
+        }
+
+        ctx.server.audit.record(AuditEvent::Deop {
+            player: profile.name.clone(),
+            uuid: profile.uuid,
+            source: ctx.sender.name(),
+        });
+        ctx.server.send_commands(&profile.uuid);
+        ctx.reply_translatable("commands.deop.success", &[&profile.name]);
+        Ok(1)
+    }
+}
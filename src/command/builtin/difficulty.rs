@@ -0,0 +1,57 @@
+//! `/difficulty` command
+
+use crate::command::argument::LiteralArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::game::Difficulty;
+use async_trait::async_trait;
+
+/// Queries or changes the world difficulty
+pub struct DifficultyCommand;
+
+#[async_trait]
+impl Command for DifficultyCommand {
+    fn name(&self) -> &str {
+        "difficulty"
+    }
+
+    fn usage(&self) -> &str {
+        "[peaceful|easy|normal|hard]"
+    }
+
+    fn description(&self) -> &str {
+        "Queries or changes the world difficulty"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let names = Difficulty::ALL.map(|d| d.name());
+        let requested = ctx
+            .optional_argument(&LiteralArgument(&names))?
+            .and_then(Difficulty::from_name);
+        ctx.expect_end()?;
+
+        let current = ctx.server.world.read().await.difficulty();
+        let Some(difficulty) = requested else {
+            ctx.reply_translatable("commands.difficulty.query", &[current.name()]);
+            return Ok(current.id() as i32);
+        };
+
+        if difficulty == current {
+            return Err(CommandError::translatable(
+                "commands.difficulty.failure",
+                &[difficulty.name()],
+            ));
+        }
+
+        ctx.server
+            .set_difficulty(difficulty)
+            .await
+            .map_err(|e| CommandError::Failed(format!("Failed to save difficulty: {}", e)))?;
+        ctx.reply_translatable("commands.difficulty.success", &[difficulty.name()]);
+
+        Ok(difficulty.id() as i32)
+    }
+}
@@ -0,0 +1,89 @@
+//! `/help` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use async_trait::async_trait;
+
+/// Commands listed per page
+const PAGE_SIZE: usize = 7;
+
+/// Lists the commands the sender may use, or shows how to use one
+pub struct HelpCommand;
+
+#[async_trait]
+impl Command for HelpCommand {
+    fn name(&self) -> &str {
+        "help"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["?"]
+    }
+
+    fn usage(&self) -> &str {
+        "[page|command]"
+    }
+
+    fn description(&self) -> &str {
+        "Lists commands or shows how to use one"
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let argument = ctx.optional_argument(&StringArgument::Word)?;
+        ctx.expect_end()?;
+
+        let page = match argument.as_deref().map(str::parse::<usize>) {
+            None => 1,
+            Some(Ok(page)) => page,
+            Some(Err(_)) => {
+                let label = argument.unwrap_or_default();
+                return show_command(ctx, label.trim_start_matches('/'));
+            }
+        };
+        let commands = ctx.server.commands.commands_for(ctx.sender);
+        let pages = commands.len().div_ceil(PAGE_SIZE).max(1);
+        if !(1..=pages).contains(&page) {
+            return Err(CommandError::OutOfRange {
+                value: page.to_string(),
+                min: 1,
+                max: pages as i64,
+            });
+        }
+
+        ctx.reply_translatable(
+            "obsidium.help.header",
+            &[&page.to_string(), &pages.to_string()],
+        );
+        let shown = commands.iter().skip((page - 1) * PAGE_SIZE).take(PAGE_SIZE);
+        for (label, command) in shown {
+            let mut line = format!("/{}", label);
+            if !command.usage().is_empty() {
+                line = format!("{} {}", line, command.usage());
+            }
+            if !command.description().is_empty() {
+                line = format!("{} - {}", line, command.description());
+            }
+            ctx.reply(&line);
+        }
+        Ok(commands.len() as i32)
+    }
+}
+
+/// Show the usage and description of one command
+fn show_command(ctx: &CommandContext<'_>, label: &str) -> CommandResult {
+    let command = ctx
+        .server
+        .commands
+        .get(label)
+        .filter(|command| ctx.sender.has_permission(command.permission_level()))
+        .ok_or_else(|| CommandError::translatable("commands.help.failed", &[]))?;
+
+    ctx.reply(format!("/{} {}", label.to_lowercase(), command.usage()).trim_end());
+    if !command.description().is_empty() {
+        ctx.reply(command.description());
+    }
+    if !command.aliases().is_empty() {
+        ctx.reply(&format!("Aliases: {}", command.aliases().join(", ")));
+    }
+    Ok(1)
+}
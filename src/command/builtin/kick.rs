@@ -0,0 +1,51 @@
+//! `/kick` command
+
+use crate::command::argument::{PlayerArgument, StringArgument};
+use crate::command::{Command, CommandContext, CommandResult};
+use crate::protocol::types::JsonTextComponent;
+use crate::server::audit::AuditEvent;
+use async_trait::async_trait;
+
+/// Disconnects players
+pub struct KickCommand;
+
+#[async_trait]
+impl Command for KickCommand {
+    fn name(&self) -> &str {
+        "kick"
+    }
+
+    fn usage(&self) -> &str {
+        "<targets> [reason]"
+    }
+
+    fn description(&self) -> &str {
+        "Disconnects players"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let target = ctx.argument(&PlayerArgument::multiple())?;
+        let reason = ctx.optional_argument(&StringArgument::Greedy)?;
+        let players = ctx.resolve_players(&target).await?;
+
+        let reason = reason.map_or_else(
+            || crate::lang::translate("multiplayer.disconnect.kicked", &[]),
+            |reason| JsonTextComponent::text(&reason),
+        );
+        let reason_text = reason.to_plain_text();
+        for player in &players {
+            ctx.server.kick_player(&player.uuid, reason.clone());
+            ctx.server.audit.record(AuditEvent::Kick {
+                player: player.username.clone(),
+                source: ctx.sender.name(),
+                reason: reason_text.clone(),
+            });
+            ctx.reply_translatable("commands.kick.success", &[&player.username, &reason_text]);
+        }
+        Ok(players.len() as i32)
+    }
+}
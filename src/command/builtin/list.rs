@@ -0,0 +1,62 @@
+//! `/list` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use async_trait::async_trait;
+
+/// Lists the players currently online
+pub struct ListCommand;
+
+#[async_trait]
+impl Command for ListCommand {
+    fn name(&self) -> &str {
+        "list"
+    }
+
+    fn usage(&self) -> &str {
+        "[uuids]"
+    }
+
+    fn description(&self) -> &str {
+        "Lists the players currently online"
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let show_uuids = match ctx.optional_argument(&StringArgument::Word)? {
+            Some(flag) if flag == "uuids" => true,
+            Some(other) => {
+                return Err(CommandError::InvalidArgument {
+                    expected: "'uuids'".to_string(),
+                    found: other,
+                });
+            }
+            None => false,
+        };
+        ctx.expect_end()?;
+
+        let mut players = ctx.server.players.get_all_players();
+        players.sort_by_key(|p| p.username.to_lowercase());
+
+        let names: Vec<String> = players
+            .iter()
+            .map(|p| {
+                if show_uuids {
+                    format!("{} ({})", p.username, p.uuid)
+                } else {
+                    p.username.clone()
+                }
+            })
+            .collect();
+
+        ctx.reply_translatable(
+            "commands.list.players",
+            &[
+                &players.len().to_string(),
+                &ctx.server.settings().max_players.to_string(),
+                &names.join(", "),
+            ],
+        );
+
+        Ok(players.len() as i32)
+    }
+}
@@ -0,0 +1,37 @@
+//! `/me` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandResult};
+use crate::lang;
+use async_trait::async_trait;
+
+/// Broadcasts an action as `* sender action`
+pub struct MeCommand;
+
+#[async_trait]
+impl Command for MeCommand {
+    fn name(&self) -> &str {
+        "me"
+    }
+
+    fn usage(&self) -> &str {
+        "<action>"
+    }
+
+    fn description(&self) -> &str {
+        "Broadcasts an action"
+    }
+
+    fn permission_level(&self) -> u8 {
+        0
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let action = ctx.argument(&StringArgument::Greedy)?;
+        ctx.server.broadcast_message(lang::translate(
+            "chat.type.emote",
+            &[&ctx.sender.name(), &action],
+        ));
+        Ok(1)
+    }
+}
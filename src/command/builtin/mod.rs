@@ -0,0 +1,82 @@
+//! Built-in server commands
+//!
+//! Each command lives in its own submodule and is registered with the
+//! dispatcher by [`register_all`] when the server state is created.
+
+pub mod backup;
+pub mod ban;
+pub mod ban_ip;
+pub mod datapack;
+pub mod debug;
+pub mod deop;
+pub mod difficulty;
+pub mod help;
+pub mod kick;
+pub mod list;
+pub mod me;
+pub mod op;
+pub mod pardon;
+pub mod pardon_ip;
+pub mod ping;
+pub mod plugins;
+pub mod pregen;
+pub mod profile;
+pub mod reloadconfig;
+pub mod save_all;
+pub mod save_off;
+pub mod save_on;
+pub mod say;
+pub mod seed;
+pub mod setworldspawn;
+pub mod simulationdistance;
+pub mod spawnpoint;
+pub mod stop;
+pub mod summon;
+pub mod tellraw;
+pub mod time;
+pub mod tps;
+pub mod viewdistance;
+pub mod weather;
+pub mod whitelist;
+
+use crate::command::CommandDispatcher;
+use std::sync::Arc;
+
+/// Register all built-in commands with a dispatcher
+pub fn register_all(dispatcher: &CommandDispatcher) {
+    dispatcher.register(Arc::new(backup::BackupCommand));
+    dispatcher.register(Arc::new(ban::BanCommand));
+    dispatcher.register(Arc::new(ban_ip::BanIpCommand));
+    dispatcher.register(Arc::new(datapack::DatapackCommand));
+    dispatcher.register(Arc::new(debug::DebugCommand));
+    dispatcher.register(Arc::new(deop::DeopCommand));
+    dispatcher.register(Arc::new(difficulty::DifficultyCommand));
+    dispatcher.register(Arc::new(help::HelpCommand));
+    dispatcher.register(Arc::new(kick::KickCommand));
+    dispatcher.register(Arc::new(list::ListCommand));
+    dispatcher.register(Arc::new(me::MeCommand));
+    dispatcher.register(Arc::new(op::OpCommand));
+    dispatcher.register(Arc::new(pardon::PardonCommand));
+    dispatcher.register(Arc::new(pardon_ip::PardonIpCommand));
+    dispatcher.register(Arc::new(ping::PingCommand));
+    dispatcher.register(Arc::new(plugins::PluginsCommand));
+    dispatcher.register(Arc::new(pregen::PregenCommand));
+    dispatcher.register(Arc::new(profile::ProfileCommand));
+    dispatcher.register(Arc::new(reloadconfig::ReloadConfigCommand));
+    dispatcher.register(Arc::new(save_all::SaveAllCommand));
+    dispatcher.register(Arc::new(save_off::SaveOffCommand));
+    dispatcher.register(Arc::new(save_on::SaveOnCommand));
+    dispatcher.register(Arc::new(say::SayCommand));
+    dispatcher.register(Arc::new(seed::SeedCommand));
+    dispatcher.register(Arc::new(setworldspawn::SetWorldSpawnCommand));
+    dispatcher.register(Arc::new(simulationdistance::SimulationDistanceCommand));
+    dispatcher.register(Arc::new(spawnpoint::SpawnPointCommand));
+    dispatcher.register(Arc::new(stop::StopCommand));
+    dispatcher.register(Arc::new(summon::SummonCommand));
+    dispatcher.register(Arc::new(tellraw::TellrawCommand));
+    dispatcher.register(Arc::new(time::TimeCommand));
+    dispatcher.register(Arc::new(tps::TpsCommand));
+    dispatcher.register(Arc::new(viewdistance::ViewDistanceCommand));
+    dispatcher.register(Arc::new(weather::WeatherCommand));
+    dispatcher.register(Arc::new(whitelist::WhitelistCommand));
+}
@@ -0,0 +1,59 @@
+//! `/op` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::server::audit::AuditEvent;
+use crate::server::ops::OpEntry;
+use async_trait::async_trait;
+
+/// Makes a player a server operator
+pub struct OpCommand;
+
+#[async_trait]
+impl Command for OpCommand {
+    fn name(&self) -> &str {
+        "op"
+    }
+
+    fn usage(&self) -> &str {
+        "<player>"
+    }
+
+    fn description(&self) -> &str {
+        "Makes a player a server operator"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let name = ctx.argument(&StringArgument::Word)?;
+        ctx.expect_end()?;
+
+        let profile = ctx.resolve_profile(&name).await?;
+        if ctx.server.ops.get(&profile.uuid).is_some() {
+            return Err(CommandError::translatable("commands.op.failed", &[]));
+        }
+        let level = ctx.server.config.op_permission_level;
+        ctx.server
+            .ops
+            .op(OpEntry {
+                uuid: profile.uuid,
+                name: profile.name.clone(),
+                level,
+                bypasses_player_limit: false,
+            })
+            .map_err(|e| CommandError::Failed(format!("Failed to save the ops: {}", e)))?;
+
+        ctx.server.audit.record(AuditEvent::Op {
+            player: profile.name.clone(),
+            uuid: profile.uuid,
+            source: ctx.sender.name(),
+            level,
+        });
+        ctx.server.send_commands(&profile.uuid);
+        ctx.reply_translatable("commands.op.success", &[&profile.name]);
+        Ok(1)
+    }
+}
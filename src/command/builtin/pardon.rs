@@ -0,0 +1,48 @@
+//! `/pardon` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::server::audit::AuditEvent;
+use async_trait::async_trait;
+
+/// Removes the ban of a player
+pub struct PardonCommand;
+
+#[async_trait]
+impl Command for PardonCommand {
+    fn name(&self) -> &str {
+        "pardon"
+    }
+
+    fn usage(&self) -> &str {
+        "<player>"
+    }
+
+    fn description(&self) -> &str {
+        "Removes the ban of a player"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let name = ctx.argument(&StringArgument::Word)?;
+        ctx.expect_end()?;
+
+        let pardoned = ctx
+            .server
+            .bans
+            .pardon(&name)
+            .map_err(|e| CommandError::Failed(format!("Failed to save the bans: {}", e)))?;
+        if !pardoned {
+            return Err(CommandError::translatable("commands.pardon.failed", &[]));
+        }
+        ctx.server.audit.record(AuditEvent::Pardon {
+            player: name.clone(),
+            source: ctx.sender.name(),
+        });
+        ctx.reply_translatable("commands.pardon.success", &[&name]);
+        Ok(1)
+    }
+}
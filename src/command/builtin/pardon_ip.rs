@@ -0,0 +1,47 @@
+//! `/pardon-ip` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use async_trait::async_trait;
+use std::net::IpAddr;
+
+/// Removes the ban of an address
+pub struct PardonIpCommand;
+
+#[async_trait]
+impl Command for PardonIpCommand {
+    fn name(&self) -> &str {
+        "pardon-ip"
+    }
+
+    fn usage(&self) -> &str {
+        "<target>"
+    }
+
+    fn description(&self) -> &str {
+        "Removes the ban of an IP address"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let target = ctx.argument(&StringArgument::Word)?;
+        ctx.expect_end()?;
+
+        let Ok(ip) = target.parse::<IpAddr>() else {
+            return Err(CommandError::translatable("commands.pardonip.invalid", &[]));
+        };
+        let pardoned = ctx
+            .server
+            .ip_bans
+            .pardon(&target)
+            .map_err(|e| CommandError::Failed(format!("Failed to save the bans: {}", e)))?;
+        if !pardoned {
+            return Err(CommandError::translatable("commands.pardonip.failed", &[]));
+        }
+        ctx.reply_translatable("commands.pardonip.success", &[&ip.to_string()]);
+        Ok(1)
+    }
+}
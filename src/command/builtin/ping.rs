@@ -0,0 +1,50 @@
+//! `/ping` command
+
+use crate::command::argument::PlayerArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use async_trait::async_trait;
+
+/// Shows the latency of a player, or of the sender
+pub struct PingCommand;
+
+#[async_trait]
+impl Command for PingCommand {
+    fn name(&self) -> &str {
+        "ping"
+    }
+
+    fn usage(&self) -> &str {
+        "[player]"
+    }
+
+    fn description(&self) -> &str {
+        "Shows the latency of a player, or of the sender"
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let target = ctx.optional_argument(&PlayerArgument::single())?;
+        ctx.expect_end()?;
+
+        let player = match target {
+            Some(target) => ctx.resolve_players(&target).await?.remove(0),
+            None => {
+                let uuid = ctx
+                    .sender
+                    .uuid()
+                    .ok_or(CommandError::MissingArgument("player".to_string()))?;
+                ctx.server
+                    .players
+                    .get_player(&uuid)
+                    .ok_or(CommandError::NoPlayerFound)?
+            }
+        };
+
+        let latency = player.latency().as_millis().min(i32::MAX as u128) as i32;
+        if ctx.sender.uuid() == Some(player.uuid) {
+            ctx.reply(&format!("Your latency is {} ms", latency));
+        } else {
+            ctx.reply(&format!("{}'s latency is {} ms", player.username, latency));
+        }
+        Ok(latency)
+    }
+}
@@ -0,0 +1,47 @@
+//! `/plugins` command
+
+use crate::command::{Command, CommandContext, CommandResult};
+use crate::protocol::types::JsonTextComponent;
+use async_trait::async_trait;
+
+/// Lists the loaded plugins
+pub struct PluginsCommand;
+
+#[async_trait]
+impl Command for PluginsCommand {
+    fn name(&self) -> &str {
+        "plugins"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["pl"]
+    }
+
+    fn description(&self) -> &str {
+        "Lists the loaded plugins"
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        ctx.expect_end()?;
+
+        let plugins = ctx.server.plugins.plugins();
+        let mut extra = Vec::new();
+        for (i, plugin) in plugins.iter().enumerate() {
+            if i > 0 {
+                extra.push(serde_json::json!({ "text": ", " }));
+            }
+            let color = if plugin.enabled { "green" } else { "red" };
+            extra.push(serde_json::json!({ "text": plugin.name, "color": color }));
+        }
+        let message = JsonTextComponent(
+            serde_json::json!({
+                "text": format!("Plugins ({}): ", plugins.len()),
+                "extra": extra,
+            })
+            .to_string(),
+        );
+        ctx.send(message);
+
+        Ok(plugins.len() as i32)
+    }
+}
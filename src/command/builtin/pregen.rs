@@ -0,0 +1,109 @@
+//! `/pregen` command
+
+use crate::command::argument::{ArgumentReader, ArgumentType, IntegerArgument};
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::game::world::pregen::{self, MAX_PREGEN_RADIUS, PregenTask};
+use crate::game::world::spawn;
+use async_trait::async_trait;
+
+/// Generates terrain around the world spawn while the server runs
+pub struct PregenCommand;
+
+/// What `/pregen` was asked to do
+enum PregenAction {
+    Start(u32),
+    Status,
+    Cancel,
+}
+
+/// Argument accepting a radius or `status|cancel`
+struct PregenActionArgument;
+
+impl ArgumentType for PregenActionArgument {
+    type Output = PregenAction;
+
+    fn name(&self) -> &'static str {
+        "radius"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<PregenAction, CommandError> {
+        let token = reader
+            .read_token()
+            .ok_or_else(|| CommandError::MissingArgument(self.name().to_string()))?;
+        match token {
+            "status" => Ok(PregenAction::Status),
+            "cancel" => Ok(PregenAction::Cancel),
+            _ => {
+                let radius = IntegerArgument::range(0, i64::from(MAX_PREGEN_RADIUS))
+                    .parse(&mut ArgumentReader::new(token))?;
+                Ok(PregenAction::Start(radius as u32))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Command for PregenCommand {
+    fn name(&self) -> &str {
+        "pregen"
+    }
+
+    fn usage(&self) -> &str {
+        "<radius> | status | cancel"
+    }
+
+    fn description(&self) -> &str {
+        "Generates terrain around the world spawn while the server runs"
+    }
+
+    fn permission_level(&self) -> u8 {
+        4
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let action = ctx.argument(&PregenActionArgument)?;
+        ctx.expect_end()?;
+
+        let manager = &ctx.server.pregen;
+        match action {
+            PregenAction::Start(radius) => {
+                let center = spawn::spawn_chunk(ctx.server.world.read().await.spawn_position());
+                let task =
+                    PregenTask::new(center, radius, ctx.sender.kind(), ctx.server.clock.as_ref());
+                if !manager.start(task) {
+                    return Err(CommandError::translatable(
+                        "obsidium.pregen.already_running",
+                        &[],
+                    ));
+                }
+                let total = pregen::chunk_count(radius);
+                ctx.reply_translatable("obsidium.pregen.started", &[&total.to_string()]);
+                Ok(total.min(i32::MAX as usize) as i32)
+            }
+            PregenAction::Status => {
+                let Some(progress) = manager.progress() else {
+                    return Err(CommandError::translatable(
+                        "obsidium.pregen.not_running",
+                        &[],
+                    ));
+                };
+                ctx.send(progress.status());
+                Ok(progress.done.min(i32::MAX as usize) as i32)
+            }
+            PregenAction::Cancel => {
+                let Some(task) = manager.cancel() else {
+                    return Err(CommandError::translatable(
+                        "obsidium.pregen.not_running",
+                        &[],
+                    ));
+                };
+                let progress = task.progress();
+                ctx.reply_translatable(
+                    "obsidium.pregen.cancelled",
+                    &[&progress.done.to_string(), &progress.total.to_string()],
+                );
+                Ok(progress.done.min(i32::MAX as usize) as i32)
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! `/profile` command
+
+use crate::command::argument::{ArgumentReader, ArgumentType, LiteralArgument, TimeArgument};
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::server::profiler::{self, MAX_PROFILE_WINDOW, ProfileCounters, ReportFormat};
+use crate::server::tick::TICK_DURATION;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Starts and stops the built-in profiler
+pub struct ProfileCommand;
+
+/// An option of `/profile start`: a window length or a report format
+enum StartOption {
+    Window(Duration),
+    Format(ReportFormat),
+}
+
+/// Argument accepting a window length (such as `30s`) or `text|json`
+struct StartOptionArgument;
+
+impl ArgumentType for StartOptionArgument {
+    type Output = StartOption;
+
+    fn name(&self) -> &'static str {
+        "duration or format"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<StartOption, CommandError> {
+        let token = reader
+            .read_token()
+            .ok_or_else(|| CommandError::MissingArgument(self.name().to_string()))?;
+        if let Some(format) = ReportFormat::from_name(token) {
+            return Ok(StartOption::Format(format));
+        }
+
+        let ticks =
+            TimeArgument::parse_ticks(token).ok_or_else(|| CommandError::InvalidArgument {
+                expected: self.name().to_string(),
+                found: token.to_string(),
+            })?;
+        let max_ticks = (MAX_PROFILE_WINDOW.as_millis() / TICK_DURATION.as_millis()) as i64;
+        if !(1..=max_ticks).contains(&ticks) {
+            return Err(CommandError::OutOfRange {
+                value: token.to_string(),
+                min: 1,
+                max: max_ticks,
+            });
+        }
+        Ok(StartOption::Window(TICK_DURATION * ticks as u32))
+    }
+}
+
+#[async_trait]
+impl Command for ProfileCommand {
+    fn name(&self) -> &str {
+        "profile"
+    }
+
+    fn usage(&self) -> &str {
+        "start [<duration>] [text|json] | stop"
+    }
+
+    fn description(&self) -> &str {
+        "Starts and stops the built-in profiler"
+    }
+
+    fn permission_level(&self) -> u8 {
+        4
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        match ctx.argument(&LiteralArgument(&["start", "stop"]))? {
+            "start" => {
+                let mut window = None;
+                let mut format = ReportFormat::default();
+                for _ in 0..2 {
+                    match ctx.optional_argument(&StartOptionArgument)? {
+                        Some(StartOption::Window(duration)) => window = Some(duration),
+                        Some(StartOption::Format(chosen)) => format = chosen,
+                        None => break,
+                    }
+                }
+                ctx.expect_end()?;
+
+                let baseline = ProfileCounters::sample(ctx.server).await;
+                if !ctx.server.profiler.start(window, format, baseline) {
+                    return Err(CommandError::Failed(
+                        "The profiler is already running".to_string(),
+                    ));
+                }
+                match window {
+                    Some(window) => ctx.reply(&format!(
+                        "Started profiling for {} seconds",
+                        window.as_secs_f64()
+                    )),
+                    None => ctx.reply("Started profiling; use /profile stop to finish"),
+                }
+                Ok(1)
+            }
+            _ => {
+                ctx.expect_end()?;
+                let Some((report, saved)) = profiler::finish(ctx.server).await else {
+                    return Err(CommandError::Failed(
+                        "The profiler is not running".to_string(),
+                    ));
+                };
+                for line in report.summary() {
+                    ctx.reply(&line);
+                }
+                match saved {
+                    Ok(path) => ctx.reply(&format!("Profile saved to {}", path.display())),
+                    Err(e) => {
+                        return Err(CommandError::Failed(format!(
+                            "Failed to save the profile: {}",
+                            e
+                        )));
+                    }
+                }
+                Ok(report.ticks.min(i32::MAX as u64) as i32)
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! `/reloadconfig` command
+
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use async_trait::async_trait;
+
+/// Re-reads server.properties and obsidium.toml and applies the settings that can change at runtime
+pub struct ReloadConfigCommand;
+
+#[async_trait]
+impl Command for ReloadConfigCommand {
+    fn name(&self) -> &str {
+        "reloadconfig"
+    }
+
+    fn description(&self) -> &str {
+        "Reloads server.properties and obsidium.toml"
+    }
+
+    fn permission_level(&self) -> u8 {
+        4
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        ctx.expect_end()?;
+
+        let report = ctx.server.reload_config().await.map_err(|e| {
+            CommandError::Failed(format!("Unable to reload server.properties: {}", e))
+        })?;
+
+        if report.is_empty() {
+            ctx.reply("Reloaded the configuration, nothing changed");
+        }
+        if !report.applied.is_empty() {
+            ctx.reply(&format!(
+                "Reloaded the configuration, applied: {}",
+                report.applied.join(", ")
+            ));
+        }
+        if !report.requires_restart.is_empty() {
+            ctx.reply(&format!(
+                "Restart the server to apply: {}",
+                report.requires_restart.join(", ")
+            ));
+        }
+
+        Ok(report.applied.len() as i32)
+    }
+}
@@ -0,0 +1,43 @@
+//! `/save-all` command
+
+use crate::command::argument::LiteralArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use async_trait::async_trait;
+
+/// Saves the world and player data
+pub struct SaveAllCommand;
+
+#[async_trait]
+impl Command for SaveAllCommand {
+    fn name(&self) -> &str {
+        "save-all"
+    }
+
+    fn usage(&self) -> &str {
+        "[flush]"
+    }
+
+    fn description(&self) -> &str {
+        "Saves the world and player data"
+    }
+
+    fn permission_level(&self) -> u8 {
+        4
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let flush = ctx
+            .optional_argument(&LiteralArgument(&["flush"]))?
+            .is_some();
+        ctx.expect_end()?;
+
+        ctx.reply_translatable("commands.save.saving", &[]);
+        let saved = ctx.server.save_all(flush).await.map_err(|e| {
+            tracing::error!("Unable to save the game: {}", e);
+            CommandError::translatable("commands.save.failed", &[])
+        })?;
+        ctx.reply_translatable("commands.save.success", &[]);
+
+        Ok(saved as i32)
+    }
+}
@@ -0,0 +1,33 @@
+//! `/save-off` command
+
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use async_trait::async_trait;
+
+/// Disables automatic saving
+pub struct SaveOffCommand;
+
+#[async_trait]
+impl Command for SaveOffCommand {
+    fn name(&self) -> &str {
+        "save-off"
+    }
+
+    fn description(&self) -> &str {
+        "Disables automatic saving"
+    }
+
+    fn permission_level(&self) -> u8 {
+        4
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        ctx.expect_end()?;
+
+        if !ctx.server.set_saving_enabled(false) {
+            return Err(CommandError::translatable("commands.save.alreadyOff", &[]));
+        }
+        ctx.reply_translatable("commands.save.disabled", &[]);
+
+        Ok(1)
+    }
+}
@@ -0,0 +1,33 @@
+//! `/save-on` command
+
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use async_trait::async_trait;
+
+/// Re-enables automatic saving
+pub struct SaveOnCommand;
+
+#[async_trait]
+impl Command for SaveOnCommand {
+    fn name(&self) -> &str {
+        "save-on"
+    }
+
+    fn description(&self) -> &str {
+        "Re-enables automatic saving"
+    }
+
+    fn permission_level(&self) -> u8 {
+        4
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        ctx.expect_end()?;
+
+        if ctx.server.set_saving_enabled(true) {
+            return Err(CommandError::translatable("commands.save.alreadyOn", &[]));
+        }
+        ctx.reply_translatable("commands.save.enabled", &[]);
+
+        Ok(1)
+    }
+}
@@ -0,0 +1,37 @@
+//! `/say` command
+
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandResult};
+use crate::lang;
+use async_trait::async_trait;
+
+/// Broadcasts a message to all players as `[sender] message`
+pub struct SayCommand;
+
+#[async_trait]
+impl Command for SayCommand {
+    fn name(&self) -> &str {
+        "say"
+    }
+
+    fn usage(&self) -> &str {
+        "<message>"
+    }
+
+    fn description(&self) -> &str {
+        "Broadcasts a message to all players"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let message = ctx.argument(&StringArgument::Greedy)?;
+        ctx.server.broadcast_message(lang::translate(
+            "chat.type.announcement",
+            &[&ctx.sender.name(), &message],
+        ));
+        Ok(1)
+    }
+}
@@ -0,0 +1,31 @@
+//! `/seed` command
+
+use crate::command::{Command, CommandContext, CommandResult};
+use async_trait::async_trait;
+
+/// Shows the world seed
+pub struct SeedCommand;
+
+#[async_trait]
+impl Command for SeedCommand {
+    fn name(&self) -> &str {
+        "seed"
+    }
+
+    fn description(&self) -> &str {
+        "Shows the world seed"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        ctx.expect_end()?;
+
+        let seed = ctx.server.world.read().await.seed();
+        ctx.reply_translatable("commands.seed.success", &[&format!("[{}]", seed)]);
+
+        Ok(seed as i32)
+    }
+}
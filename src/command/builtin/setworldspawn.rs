@@ -0,0 +1,59 @@
+//! `/setworldspawn` command
+
+use crate::command::argument::{AngleArgument, PositionArgument};
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::game::world::chunk::CHUNK_HEIGHT;
+use crate::protocol::types::Position;
+use async_trait::async_trait;
+
+/// Moves the world spawn
+pub struct SetWorldSpawnCommand;
+
+#[async_trait]
+impl Command for SetWorldSpawnCommand {
+    fn name(&self) -> &str {
+        "setworldspawn"
+    }
+
+    fn usage(&self) -> &str {
+        "[pos] [angle]"
+    }
+
+    fn description(&self) -> &str {
+        "Moves the world spawn"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let origin = ctx.origin().await;
+        let position = match ctx.optional_argument(&PositionArgument)? {
+            Some(coordinates) => coordinates.resolve_block(&origin),
+            None => Position::new(
+                origin.x.floor() as i32,
+                origin.y.floor() as i32,
+                origin.z.floor() as i32,
+            ),
+        };
+        let angle = ctx.optional_argument(&AngleArgument)?.unwrap_or(0.0);
+        ctx.expect_end()?;
+
+        if !(0..CHUNK_HEIGHT as i32).contains(&position.y) {
+            return Err(CommandError::translatable("argument.pos.outofworld", &[]));
+        }
+        ctx.server.set_world_spawn(position, angle).await;
+
+        ctx.reply_translatable(
+            "commands.setworldspawn.success",
+            &[
+                &position.x.to_string(),
+                &position.y.to_string(),
+                &position.z.to_string(),
+                &angle.to_string(),
+            ],
+        );
+        Ok(1)
+    }
+}
@@ -0,0 +1,54 @@
+//! `/simulationdistance` command
+
+use crate::command::argument::IntegerArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::config::validate::DISTANCE_RANGE;
+use async_trait::async_trait;
+
+/// Queries or changes the simulation distance until the next restart
+pub struct SimulationDistanceCommand;
+
+#[async_trait]
+impl Command for SimulationDistanceCommand {
+    fn name(&self) -> &str {
+        "simulationdistance"
+    }
+
+    fn usage(&self) -> &str {
+        "[chunks]"
+    }
+
+    fn description(&self) -> &str {
+        "Queries or changes the simulation distance until the next restart"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let argument = IntegerArgument::range(*DISTANCE_RANGE.start(), *DISTANCE_RANGE.end());
+        let requested = ctx.optional_argument(&argument)?;
+        ctx.expect_end()?;
+
+        let current = ctx.server.settings().simulation_distance;
+        let Some(distance) = requested else {
+            ctx.reply_translatable("obsidium.simulationdistance.query", &[&current.to_string()]);
+            return Ok(i32::from(current));
+        };
+        let distance = distance as u8;
+        if distance == current {
+            return Err(CommandError::translatable(
+                "obsidium.simulationdistance.failure",
+                &[&current.to_string()],
+            ));
+        }
+
+        ctx.server.set_simulation_distance(distance);
+        ctx.reply_translatable(
+            "obsidium.simulationdistance.success",
+            &[&distance.to_string()],
+        );
+        Ok(i32::from(distance))
+    }
+}
@@ -0,0 +1,111 @@
+//! `/spawnpoint` command
+
+use crate::command::argument::{AngleArgument, PlayerArgument, PositionArgument};
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::game::player::RespawnPoint;
+use crate::game::world::chunk::CHUNK_HEIGHT;
+use crate::protocol::types::Position;
+use async_trait::async_trait;
+
+/// Sets the respawn point of players
+pub struct SpawnPointCommand;
+
+#[async_trait]
+impl Command for SpawnPointCommand {
+    fn name(&self) -> &str {
+        "spawnpoint"
+    }
+
+    fn usage(&self) -> &str {
+        "[targets] [pos] [angle]"
+    }
+
+    fn description(&self) -> &str {
+        "Sets the respawn point of players"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let targets = match ctx.optional_argument(&PlayerArgument::multiple())? {
+            Some(target) => ctx.resolve_players(&target).await?,
+            None => {
+                let uuid = ctx.sender.uuid().ok_or_else(|| {
+                    CommandError::translatable("permissions.requires.player", &[])
+                })?;
+                ctx.server.players.get_player(&uuid).into_iter().collect()
+            }
+        };
+        let origin = ctx.origin().await;
+        let position = match ctx.optional_argument(&PositionArgument)? {
+            Some(coordinates) => coordinates.resolve_block(&origin),
+            None => Position::new(
+                origin.x.floor() as i32,
+                origin.y.floor() as i32,
+                origin.z.floor() as i32,
+            ),
+        };
+        let angle = ctx.optional_argument(&AngleArgument)?.unwrap_or(0.0);
+        ctx.expect_end()?;
+
+        if !(0..CHUNK_HEIGHT as i32).contains(&position.y) {
+            return Err(CommandError::translatable("argument.pos.outofworld", &[]));
+        }
+
+        // The point is in the dimension the command runs in
+        let dimension = match ctx.sender.uuid() {
+            Some(uuid) => ctx
+                .server
+                .players
+                .with_player(&uuid, |player| player.dimension)
+                .unwrap_or_default(),
+            None => Default::default(),
+        };
+        let world = ctx
+            .server
+            .world_in(dimension)
+            .read()
+            .await
+            .name()
+            .to_string();
+        let point = RespawnPoint {
+            world,
+            position,
+            angle,
+            forced: true,
+        };
+        let uuids: Vec<_> = targets.iter().map(|player| player.uuid).collect();
+        ctx.server.players.for_each_player_mut(|player| {
+            if uuids.contains(&player.uuid) {
+                player.respawn_point = Some(point.clone());
+            }
+        });
+
+        let (x, y, z) = (
+            position.x.to_string(),
+            position.y.to_string(),
+            position.z.to_string(),
+        );
+        let angle = angle.to_string();
+        match targets.as_slice() {
+            [player] => ctx.reply_translatable(
+                "commands.spawnpoint.success.single",
+                &[&x, &y, &z, &angle, dimension.name(), &player.username],
+            ),
+            _ => ctx.reply_translatable(
+                "commands.spawnpoint.success.multiple",
+                &[
+                    &x,
+                    &y,
+                    &z,
+                    &angle,
+                    dimension.name(),
+                    &targets.len().to_string(),
+                ],
+            ),
+        }
+        Ok(targets.len() as i32)
+    }
+}
@@ -0,0 +1,29 @@
+//! `/stop` command
+
+use crate::command::{Command, CommandContext, CommandResult};
+use async_trait::async_trait;
+
+/// Stops the server gracefully
+pub struct StopCommand;
+
+#[async_trait]
+impl Command for StopCommand {
+    fn name(&self) -> &str {
+        "stop"
+    }
+
+    fn description(&self) -> &str {
+        "Stops the server gracefully"
+    }
+
+    fn permission_level(&self) -> u8 {
+        4
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        ctx.expect_end()?;
+        ctx.reply_translatable("commands.stop.stopping", &[]);
+        ctx.server.request_shutdown();
+        Ok(1)
+    }
+}
@@ -0,0 +1,66 @@
+//! `/summon` command
+
+use crate::command::argument::{NbtArgument, PositionArgument, StringArgument};
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::game::entity::EntityPosition;
+use crate::game::entity::summon;
+use crate::game::world::chunk::CHUNK_HEIGHT;
+use async_trait::async_trait;
+
+/// Summons an entity in the overworld
+pub struct SummonCommand;
+
+#[async_trait]
+impl Command for SummonCommand {
+    fn name(&self) -> &str {
+        "summon"
+    }
+
+    fn usage(&self) -> &str {
+        "<entity> [pos] [nbt]"
+    }
+
+    fn description(&self) -> &str {
+        "Summons an entity in the overworld"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let name = ctx.argument(&StringArgument::Word)?;
+        let kind = summon::summonable(&name).ok_or_else(|| {
+            CommandError::translatable(
+                "argument.resource.not_found",
+                &[&name, "minecraft:entity_type"],
+            )
+        })?;
+        let origin = ctx.origin().await;
+        let position = match ctx.optional_argument(&PositionArgument)? {
+            Some(coordinates) => coordinates.resolve(&origin),
+            None => origin,
+        };
+        let nbt = ctx.optional_argument(&NbtArgument)?.unwrap_or_default();
+        ctx.expect_end()?;
+
+        if !(0.0..f64::from(CHUNK_HEIGHT as i32)).contains(&position.y) {
+            return Err(CommandError::translatable(
+                "commands.summon.invalidPosition",
+                &[],
+            ));
+        }
+        let position = EntityPosition {
+            x: position.x,
+            y: position.y,
+            z: position.z,
+        };
+        let summoned = ctx.server.summon(kind, position, &nbt).await;
+        if summoned.is_none() {
+            return Err(CommandError::translatable("commands.summon.failed", &[]));
+        }
+
+        ctx.reply_translatable("commands.summon.success", &[kind.identifier()]);
+        Ok(1)
+    }
+}
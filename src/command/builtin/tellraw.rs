@@ -0,0 +1,45 @@
+//! `/tellraw` command
+
+use crate::command::argument::{ComponentArgument, PlayerArgument};
+use crate::command::{Command, CommandContext, CommandResult};
+use async_trait::async_trait;
+
+/// Sends a JSON text component to players
+pub struct TellrawCommand;
+
+#[async_trait]
+impl Command for TellrawCommand {
+    fn name(&self) -> &str {
+        "tellraw"
+    }
+
+    fn usage(&self) -> &str {
+        "<targets> <message>"
+    }
+
+    fn description(&self) -> &str {
+        "Sends a JSON text component to players"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let target = ctx.argument(&PlayerArgument::multiple())?;
+        let message = ctx.argument(&ComponentArgument)?;
+        let players = ctx.resolve_players(&target).await?;
+
+        let names: Vec<&str> = players.iter().map(|p| p.username.as_str()).collect();
+        tracing::info!(
+            "[{} -> {}] {}",
+            ctx.sender.name(),
+            names.join(", "),
+            message.to_plain_text()
+        );
+        for player in &players {
+            ctx.server.send_message(&player.uuid, message.clone());
+        }
+        Ok(players.len() as i32)
+    }
+}
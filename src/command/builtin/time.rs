@@ -0,0 +1,105 @@
+//! `/time` command
+
+use crate::command::argument::{ArgumentReader, ArgumentType, LiteralArgument, TimeArgument};
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::game::world::TICKS_PER_DAY;
+use async_trait::async_trait;
+
+/// Named times of day accepted by `/time set`
+const NAMED_TIMES: [(&str, i64); 4] = [
+    ("day", 1_000),
+    ("noon", 6_000),
+    ("night", 13_000),
+    ("midnight", 18_000),
+];
+
+/// Queries or changes the world time
+pub struct TimeCommand;
+
+/// Argument accepting a named time of day or a duration
+struct TimeOfDayArgument;
+
+impl ArgumentType for TimeOfDayArgument {
+    type Output = i64;
+
+    fn name(&self) -> &'static str {
+        "time"
+    }
+
+    fn parse(&self, reader: &mut ArgumentReader<'_>) -> Result<i64, CommandError> {
+        let token = reader
+            .read_token()
+            .ok_or_else(|| CommandError::MissingArgument(self.name().to_string()))?;
+
+        NAMED_TIMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(token))
+            .map(|&(_, ticks)| ticks)
+            .or_else(|| TimeArgument::parse_ticks(token))
+            .ok_or_else(|| CommandError::InvalidArgument {
+                expected: self.name().to_string(),
+                found: token.to_string(),
+            })
+    }
+}
+
+#[async_trait]
+impl Command for TimeCommand {
+    fn name(&self) -> &str {
+        "time"
+    }
+
+    fn usage(&self) -> &str {
+        "set <day|noon|night|midnight|time> | add <time> | query <daytime|gametime|day>"
+    }
+
+    fn description(&self) -> &str {
+        "Queries or changes the world time"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        match ctx.argument(&LiteralArgument(&["set", "add", "query"]))? {
+            "query" => {
+                let query = ctx.argument(&LiteralArgument(&["daytime", "gametime", "day"]))?;
+                ctx.expect_end()?;
+
+                let world = ctx.server.world.read().await;
+                let value = match query {
+                    "daytime" => world.time_of_day() % TICKS_PER_DAY,
+                    "gametime" => world.world_age(),
+                    _ => world.time_of_day() / TICKS_PER_DAY,
+                };
+                ctx.reply_translatable("commands.time.query", &[&value.to_string()]);
+
+                Ok(value as i32)
+            }
+            action => {
+                let ticks = if action == "set" {
+                    ctx.argument(&TimeOfDayArgument)?
+                } else {
+                    ctx.argument(&TimeArgument)?
+                };
+                ctx.expect_end()?;
+
+                let mut world = ctx.server.world.write().await;
+                let time = if action == "set" {
+                    // Keep the day count so moon phases are preserved
+                    world.time_of_day() - world.time_of_day() % TICKS_PER_DAY + ticks
+                } else {
+                    world.time_of_day() + ticks
+                };
+                world.set_time_of_day(time);
+                ctx.server.broadcast_time(&world);
+
+                let daytime = time % TICKS_PER_DAY;
+                ctx.reply_translatable("commands.time.set", &[&daytime.to_string()]);
+
+                Ok(daytime as i32)
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! `/tps` command
+
+use crate::command::{Command, CommandContext, CommandResult};
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Windows reported by the command, with their labels
+const WINDOWS: [(&str, u64); 3] = [("1m", 60), ("5m", 300), ("15m", 900)];
+
+/// Shows ticks per second and milliseconds per tick
+pub struct TpsCommand;
+
+#[async_trait]
+impl Command for TpsCommand {
+    fn name(&self) -> &str {
+        "tps"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["mspt"]
+    }
+
+    fn description(&self) -> &str {
+        "Shows ticks per second and milliseconds per tick"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        ctx.expect_end()?;
+
+        let stats = &ctx.server.tick_stats;
+        let tps: Vec<f64> = WINDOWS
+            .iter()
+            .map(|&(_, secs)| stats.tps(Duration::from_secs(secs)))
+            .collect();
+
+        let labels: Vec<&str> = WINDOWS.iter().map(|&(label, _)| label).collect();
+        let values: Vec<String> = tps.iter().map(|tps| format!("{:.2}", tps)).collect();
+        ctx.reply(&format!(
+            "TPS from last {}: {}",
+            labels.join(", "),
+            values.join(", ")
+        ));
+
+        if let Some(mspt) = stats.mspt() {
+            ctx.reply(&format!(
+                "MSPT (avg/min/max over last 5s): {:.2}/{:.2}/{:.2}",
+                mspt.average, mspt.min, mspt.max
+            ));
+        }
+
+        Ok(tps[0].round() as i32)
+    }
+}
@@ -0,0 +1,51 @@
+//! `/weather` command
+
+use crate::command::argument::{LiteralArgument, TimeArgument};
+use crate::command::{Command, CommandContext, CommandResult};
+use crate::game::world::Weather;
+use async_trait::async_trait;
+
+/// Weather duration in ticks when none is given (five minutes)
+const DEFAULT_DURATION: i64 = 6_000;
+
+/// Changes the weather
+pub struct WeatherCommand;
+
+#[async_trait]
+impl Command for WeatherCommand {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    fn usage(&self) -> &str {
+        "<clear|rain|thunder> [duration]"
+    }
+
+    fn description(&self) -> &str {
+        "Changes the weather"
+    }
+
+    fn permission_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let name = ctx.argument(&LiteralArgument(&["clear", "rain", "thunder"]))?;
+        let duration = ctx
+            .optional_argument(&TimeArgument)?
+            .unwrap_or(DEFAULT_DURATION);
+        ctx.expect_end()?;
+
+        let weather = Weather::from_name(name).unwrap_or_default();
+        ctx.server
+            .world
+            .write()
+            .await
+            .set_weather(weather, duration);
+        ctx.server.broadcast_weather(weather);
+
+        ctx.reply_translatable(&format!("commands.weather.set.{}", weather.name()), &[]);
+
+        Ok(duration as i32)
+    }
+}
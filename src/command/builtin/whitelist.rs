@@ -0,0 +1,92 @@
+//! `/whitelist` command
+
+use crate::command::argument::{LiteralArgument, StringArgument};
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::server::whitelist::WhitelistEntry;
+use async_trait::async_trait;
+
+/// Adds, removes and lists whitelisted players
+pub struct WhitelistCommand;
+
+#[async_trait]
+impl Command for WhitelistCommand {
+    fn name(&self) -> &str {
+        "whitelist"
+    }
+
+    fn usage(&self) -> &str {
+        "add <player> | remove <player> | list | reload"
+    }
+
+    fn description(&self) -> &str {
+        "Adds, removes and lists whitelisted players"
+    }
+
+    fn permission_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let whitelist = &ctx.server.whitelist;
+        match ctx.argument(&LiteralArgument(&["add", "remove", "list", "reload"]))? {
+            "add" => {
+                let name = ctx.argument(&StringArgument::Word)?;
+                ctx.expect_end()?;
+
+                let profile = ctx.resolve_profile(&name).await?;
+                let entry = WhitelistEntry {
+                    uuid: profile.uuid,
+                    name: profile.name.clone(),
+                };
+                if !whitelist.add(entry).map_err(failed)? {
+                    return Err(CommandError::translatable(
+                        "commands.whitelist.add.failed",
+                        &[],
+                    ));
+                }
+                ctx.reply_translatable("commands.whitelist.add.success", &[&profile.name]);
+                Ok(1)
+            }
+            "remove" => {
+                let name = ctx.argument(&StringArgument::Word)?;
+                ctx.expect_end()?;
+
+                if !whitelist.remove(&name).map_err(failed)? {
+                    return Err(CommandError::translatable(
+                        "commands.whitelist.remove.failed",
+                        &[],
+                    ));
+                }
+                ctx.reply_translatable("commands.whitelist.remove.success", &[&name]);
+                Ok(1)
+            }
+            "list" => {
+                ctx.expect_end()?;
+                let names: Vec<String> = whitelist
+                    .entries()
+                    .into_iter()
+                    .map(|entry| entry.name)
+                    .collect();
+                match names.len() {
+                    0 => ctx.reply_translatable("commands.whitelist.none", &[]),
+                    n => ctx.reply_translatable(
+                        "commands.whitelist.list",
+                        &[&n.to_string(), &names.join(", ")],
+                    ),
+                }
+                Ok(names.len() as i32)
+            }
+            _ => {
+                ctx.expect_end()?;
+                whitelist.reload().map_err(failed)?;
+                ctx.reply_translatable("commands.whitelist.reloaded", &[]);
+                Ok(1)
+            }
+        }
+    }
+}
+
+/// Report a failure to save or read the whitelist
+fn failed(error: crate::error::ServerError) -> CommandError {
+    CommandError::Failed(format!("Failed to update the whitelist: {}", error))
+}
@@ -0,0 +1,147 @@
+//! Command execution context
+//!
+//! The context gives a running command access to its sender, its arguments
+//! and the shared server state.
+
+use crate::command::argument::{ArgumentReader, ArgumentType, PlayerTarget};
+use crate::command::{CommandError, CommandSender};
+use crate::game::player::{Player, PlayerPosition};
+use crate::protocol::types::JsonTextComponent;
+use crate::server::ServerState;
+use crate::server::profiles::GameProfile;
+
+/// Context passed to a command while it executes
+pub struct CommandContext<'a> {
+    /// Whoever issued the command
+    pub sender: &'a dyn CommandSender,
+    /// Shared server state
+    pub server: &'a ServerState,
+    /// The name the command was invoked with (may be an alias)
+    pub label: &'a str,
+    /// Reader over the remaining arguments
+    reader: ArgumentReader<'a>,
+}
+
+impl<'a> CommandContext<'a> {
+    /// Create a new command context
+    pub fn new(
+        sender: &'a dyn CommandSender,
+        server: &'a ServerState,
+        label: &'a str,
+        arguments: &'a str,
+    ) -> Self {
+        Self {
+            sender,
+            server,
+            label,
+            reader: ArgumentReader::new(arguments),
+        }
+    }
+
+    /// Parse the next argument
+    pub fn argument<A: ArgumentType>(&mut self, argument: &A) -> Result<A::Output, CommandError> {
+        argument.parse(&mut self.reader)
+    }
+
+    /// Parse the next argument if there is any input left
+    pub fn optional_argument<A: ArgumentType>(
+        &mut self,
+        argument: &A,
+    ) -> Result<Option<A::Output>, CommandError> {
+        if self.reader.is_empty() {
+            Ok(None)
+        } else {
+            argument.parse(&mut self.reader).map(Some)
+        }
+    }
+
+    /// Check whether there is unparsed input left
+    pub fn has_remaining(&self) -> bool {
+        !self.reader.is_empty()
+    }
+
+    /// Fail if there is unparsed input left
+    pub fn expect_end(&self) -> Result<(), CommandError> {
+        if self.reader.is_empty() {
+            Ok(())
+        } else {
+            Err(CommandError::TrailingArguments(
+                self.reader.remaining().trim().to_string(),
+            ))
+        }
+    }
+
+    /// Position the command was executed from
+    ///
+    /// Players execute from their own position; the console and RCON
+    /// execute from the world spawn.
+    pub async fn origin(&self) -> PlayerPosition {
+        if let Some(position) = self.sender.position() {
+            return position;
+        }
+
+        let spawn = self.server.world.read().await.spawn_position();
+        PlayerPosition {
+            x: spawn.x as f64,
+            y: spawn.y as f64,
+            z: spawn.z as f64,
+        }
+    }
+
+    /// Resolve a player argument to the matching online players
+    ///
+    /// Returns [`CommandError::NoPlayerFound`] if nothing matched.
+    pub async fn resolve_players(
+        &self,
+        target: &PlayerTarget,
+    ) -> Result<Vec<Player>, CommandError> {
+        let players = self.server.players.get_all_players();
+
+        let matched = match target {
+            PlayerTarget::Name(name) => players
+                .into_iter()
+                .filter(|p| p.username.eq_ignore_ascii_case(name))
+                .collect(),
+            PlayerTarget::Selector(selector) => {
+                let origin = self.origin().await;
+                selector.select_players(players, &origin, self.sender.uuid())
+            }
+        };
+
+        if matched.is_empty() {
+            Err(CommandError::NoPlayerFound)
+        } else {
+            Ok(matched)
+        }
+    }
+
+    /// Find a player by name, whether or not they are online
+    ///
+    /// Players who never joined are looked up as described in
+    /// [`crate::server::profiles`].
+    pub async fn resolve_profile(&self, name: &str) -> Result<GameProfile, CommandError> {
+        match self.server.profiles.find_by_name(name).await {
+            Ok(Some(profile)) => Ok(profile),
+            Ok(None) => Err(CommandError::translatable("argument.player.unknown", &[])),
+            Err(e) => Err(CommandError::Failed(format!(
+                "Could not look up {}: {}",
+                name, e
+            ))),
+        }
+    }
+
+    /// Send a plain text reply to the sender
+    pub fn reply(&self, message: &str) {
+        self.sender.send_message(JsonTextComponent::text(message));
+    }
+
+    /// Send a reply the client translates (see [`crate::lang::translate`])
+    pub fn reply_translatable(&self, key: &str, args: &[&str]) {
+        self.sender.send_message(crate::lang::translate(key, args));
+    }
+
+    /// Send a chat component to the sender
+    pub fn send(&self, message: JsonTextComponent) {
+        self.sender.send_message(message);
+    }
+}
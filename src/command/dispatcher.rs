@@ -0,0 +1,373 @@
+//! Command dispatcher
+//!
+//! The dispatcher owns the command registry and routes raw command input
+//! (e.g. `/say hello`) to the matching [`Command`].
+
+use crate::command::{Command, CommandContext, CommandError, CommandResult, CommandSender};
+use crate::protocol::packets::play::{CommandNode, CommandNodeKind, CommandsPacket};
+use crate::protocol::types::{JsonTextComponent, McString, VarInt};
+use crate::server::ServerState;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// A registered command together with the plugin that owns it
+#[derive(Clone)]
+struct Registration {
+    /// The command implementation
+    command: Arc<dyn Command>,
+    /// Owning plugin, or `None` for built-in commands
+    owner: Option<String>,
+}
+
+/// Routes command input to registered commands
+#[derive(Default)]
+pub struct CommandDispatcher {
+    /// Map of lowercase label (name, alias or `namespace:name`) to command
+    commands: RwLock<HashMap<String, Registration>>,
+}
+
+impl CommandDispatcher {
+    /// Create an empty dispatcher
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a built-in command under its name, aliases and `minecraft:` namespace
+    ///
+    /// Returns `false` if a command with the same name already exists.
+    pub fn register(&self, command: Arc<dyn Command>) -> bool {
+        self.register_with_owner(command, None, "minecraft")
+    }
+
+    /// Register a command on behalf of a plugin
+    ///
+    /// The command is always available as `plugin:name`; the plain name and
+    /// aliases are only registered if they are not already taken, so plugins
+    /// cannot shadow built-in commands.
+    pub fn register_plugin_command(&self, plugin: &str, command: Arc<dyn Command>) -> bool {
+        let namespace = plugin.to_lowercase();
+        self.register_with_owner(command, Some(plugin.to_string()), &namespace)
+    }
+
+    fn register_with_owner(
+        &self,
+        command: Arc<dyn Command>,
+        owner: Option<String>,
+        namespace: &str,
+    ) -> bool {
+        let name = command.name().to_lowercase();
+        let mut commands = self.commands.write().unwrap_or_else(|e| e.into_inner());
+
+        let namespaced = format!("{}:{}", namespace, name);
+        if commands.contains_key(&namespaced) {
+            tracing::warn!("Command '{}' is already registered", namespaced);
+            return false;
+        }
+
+        let registration = Registration { command, owner };
+        let labels = std::iter::once(name.clone()).chain(
+            registration
+                .command
+                .aliases()
+                .iter()
+                .map(|a| a.to_lowercase()),
+        );
+        for label in labels {
+            if commands.contains_key(&label) {
+                tracing::debug!("Label '{}' already taken, skipping", label);
+                continue;
+            }
+            commands.insert(label, registration.clone());
+        }
+        commands.insert(namespaced, registration);
+
+        tracing::debug!("Registered command '{}'", name);
+        true
+    }
+
+    /// Remove all commands registered by a plugin
+    pub fn unregister_plugin(&self, plugin: &str) -> usize {
+        let mut commands = self.commands.write().unwrap_or_else(|e| e.into_inner());
+        let before = commands.len();
+        commands.retain(|_, r| r.owner.as_deref() != Some(plugin));
+        before - commands.len()
+    }
+
+    /// Look up a command by label
+    pub fn get(&self, label: &str) -> Option<Arc<dyn Command>> {
+        let commands = self.commands.read().unwrap_or_else(|e| e.into_inner());
+        commands
+            .get(&label.to_lowercase())
+            .map(|r| Arc::clone(&r.command))
+    }
+
+    /// Get all labels usable by a sender, sorted (used for suggestions)
+    pub fn labels_for(&self, sender: &dyn CommandSender) -> Vec<String> {
+        let commands = self.commands.read().unwrap_or_else(|e| e.into_inner());
+        let mut labels: Vec<String> = commands
+            .iter()
+            .filter(|(_, r)| sender.has_permission(r.command.permission_level()))
+            .map(|(label, _)| label.clone())
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    /// Get the commands usable by a sender, once each, sorted by the label
+    /// they are listed under (used by `/help`)
+    ///
+    /// A command is listed under its name, or under `namespace:name` if a
+    /// different command took the name.
+    pub fn commands_for(&self, sender: &dyn CommandSender) -> Vec<(String, Arc<dyn Command>)> {
+        let commands = self.commands.read().unwrap_or_else(|e| e.into_inner());
+        let mut listed: Vec<(String, Arc<dyn Command>)> = commands
+            .iter()
+            .filter(|(_, r)| sender.has_permission(r.command.permission_level()))
+            .filter(|(label, r)| {
+                let name = r.command.name().to_lowercase();
+                let owns_name = commands
+                    .get(&name)
+                    .is_some_and(|other| Arc::ptr_eq(&other.command, &r.command));
+                if owns_name {
+                    **label == name
+                } else {
+                    label.contains(':')
+                }
+            })
+            .map(|(label, r)| (label.clone(), Arc::clone(&r.command)))
+            .collect();
+        listed.sort_by(|a, b| a.0.cmp(&b.0));
+        listed
+    }
+
+    /// Build the command graph declared to a sender, with every label they
+    /// may use
+    ///
+    /// Arguments are declared as a single optional greedy string, so the
+    /// client completes command names and leaves parsing to the server.
+    pub fn declare_commands(&self, sender: &dyn CommandSender) -> CommandsPacket {
+        let labels = self.labels_for(sender);
+        let root = CommandNode {
+            kind: CommandNodeKind::Root,
+            executable: false,
+            children: (0..labels.len()).map(|i| VarInt(i as i32 + 2)).collect(),
+        };
+        let arguments = CommandNode {
+            kind: CommandNodeKind::StringArgument {
+                name: McString("args".to_string()),
+                behavior: VarInt(2),
+            },
+            executable: true,
+            children: Vec::new(),
+        };
+        let literals = labels.into_iter().map(|label| CommandNode {
+            kind: CommandNodeKind::Literal(McString(label)),
+            executable: true,
+            children: vec![VarInt(1)],
+        });
+        CommandsPacket {
+            nodes: [root, arguments].into_iter().chain(literals).collect(),
+            root_index: VarInt(0),
+        }
+    }
+
+    /// Execute a raw command line without reporting errors to the sender
+    ///
+    /// A leading `/` is optional.
+    pub async fn execute(
+        &self,
+        server: &ServerState,
+        sender: &dyn CommandSender,
+        input: &str,
+    ) -> CommandResult {
+        let (label, arguments) = split_label(input);
+
+        if label.is_empty() {
+            return Err(CommandError::UnknownCommand(String::new()));
+        }
+
+        let command = self
+            .get(label)
+            .ok_or_else(|| CommandError::UnknownCommand(label.to_string()))?;
+
+        if !sender.has_permission(command.permission_level()) {
+            return Err(CommandError::PermissionDenied);
+        }
+
+        let mut ctx = CommandContext::new(sender, server, label, arguments);
+        command.execute(&mut ctx).await
+    }
+
+    /// Execute a raw command line, reporting any error to the sender as a red message
+    pub async fn dispatch(
+        &self,
+        server: &ServerState,
+        sender: &dyn CommandSender,
+        input: &str,
+    ) -> CommandResult {
+        tracing::debug!("{} issued command: {}", sender.name(), input);
+
+        let result = self.execute(server, sender, input).await;
+        if let Err(ref error) = result {
+            sender.send_message(error.to_component());
+
+            let usage_hint = matches!(
+                error,
+                CommandError::MissingArgument(_)
+                    | CommandError::InvalidArgument { .. }
+                    | CommandError::TrailingArguments(_)
+            );
+            if let Some(command) = self.get(split_label(input).0).filter(|_| usage_hint) {
+                if !command.usage().is_empty() {
+                    sender.send_message(JsonTextComponent::colored(
+                        &format!("Usage: /{} {}", command.name(), command.usage()),
+                        "red",
+                    ));
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Split command input into its label and argument string, dropping a leading `/`
+fn split_label(input: &str) -> (&str, &str) {
+    let input = input.trim();
+    let input = input.strip_prefix('/').unwrap_or(input);
+    input.split_once(char::is_whitespace).unwrap_or((input, ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::argument::IntegerArgument;
+    use crate::command::{PlayerSender, RconSender};
+    use crate::config::ServerConfig;
+    use crate::game::player::Player;
+    use crate::protocol::types::McUuid;
+    use async_trait::async_trait;
+
+    struct AddCommand;
+
+    #[async_trait]
+    impl Command for AddCommand {
+        fn name(&self) -> &str {
+            "add"
+        }
+
+        fn aliases(&self) -> &[&str] {
+            &["plus"]
+        }
+
+        fn usage(&self) -> &str {
+            "<a> <b>"
+        }
+
+        async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+            let a = ctx.argument(&IntegerArgument::any())?;
+            let b = ctx.argument(&IntegerArgument::any())?;
+            ctx.expect_end()?;
+            ctx.reply(&format!("{}", a + b));
+            Ok((a + b) as i32)
+        }
+    }
+
+    struct OpCommand;
+
+    #[async_trait]
+    impl Command for OpCommand {
+        fn name(&self) -> &str {
+            "secret"
+        }
+
+        fn permission_level(&self) -> u8 {
+            5
+        }
+
+        async fn execute(&self, _ctx: &mut CommandContext<'_>) -> CommandResult {
+            Ok(1)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch() {
+        let server = ServerState::new(ServerConfig::default());
+        let dispatcher = CommandDispatcher::new();
+        assert!(dispatcher.register(Arc::new(AddCommand)));
+        assert!(!dispatcher.register(Arc::new(AddCommand)));
+        dispatcher.register(Arc::new(OpCommand));
+
+        let sender = RconSender::new();
+        assert_eq!(
+            dispatcher.dispatch(&server, &sender, "/add 2 3").await,
+            Ok(5)
+        );
+        assert_eq!(sender.take_output(), "5");
+        assert_eq!(
+            dispatcher.execute(&server, &sender, "PLUS 1 1").await,
+            Ok(2)
+        );
+        assert_eq!(
+            dispatcher
+                .execute(&server, &sender, "minecraft:add 1 1")
+                .await,
+            Ok(2)
+        );
+
+        assert!(matches!(
+            dispatcher.dispatch(&server, &sender, "add 1").await,
+            Err(CommandError::MissingArgument(_))
+        ));
+        assert!(sender.take_output().contains("Usage: /add <a> <b>"));
+
+        assert_eq!(
+            dispatcher.execute(&server, &sender, "nope").await,
+            Err(CommandError::UnknownCommand("nope".to_string()))
+        );
+        assert_eq!(
+            dispatcher.execute(&server, &sender, "secret").await,
+            Err(CommandError::PermissionDenied)
+        );
+    }
+
+    #[test]
+    fn test_plugin_registration() {
+        let dispatcher = CommandDispatcher::new();
+        dispatcher.register(Arc::new(AddCommand));
+        assert!(dispatcher.register_plugin_command("MathPlugin", Arc::new(AddCommand)));
+        assert!(dispatcher.get("mathplugin:add").is_some());
+
+        assert_eq!(dispatcher.unregister_plugin("MathPlugin"), 1);
+        assert!(dispatcher.get("mathplugin:add").is_none());
+        assert!(dispatcher.get("add").is_some());
+    }
+
+    #[test]
+    fn test_commands_for_sender() {
+        let dispatcher = CommandDispatcher::new();
+        dispatcher.register(Arc::new(AddCommand));
+        dispatcher.register(Arc::new(OpCommand));
+        dispatcher.register_plugin_command("Math", Arc::new(AddCommand));
+
+        let player = Player::new(McUuid::new_v4(), "Steve".to_string());
+        let sender = PlayerSender::new(&player, 0);
+        let listed: Vec<String> = dispatcher
+            .commands_for(&sender)
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect();
+        assert_eq!(listed, ["add", "math:add"]);
+
+        let packet = dispatcher.declare_commands(&sender);
+        let literals: Vec<&str> = packet
+            .nodes
+            .iter()
+            .filter_map(|node| match &node.kind {
+                CommandNodeKind::Literal(label) => Some(label.0.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(literals, ["add", "math:add", "minecraft:add", "plus"]);
+        assert_eq!(packet.nodes[0].children.len(), literals.len());
+    }
+}
@@ -0,0 +1,160 @@
+//! Command system for Obsidium
+//!
+//! This module contains the command framework: the [`Command`] trait
+//! implemented by every command, the [`CommandDispatcher`] that routes input
+//! to registered commands, command senders, argument types and target
+//! selector parsing.
+//!
+//! # Example
+//!
+//! ```rust
+//! use async_trait::async_trait;
+//! use obsidium::command::{Command, CommandContext, CommandResult};
+//!
+//! struct HelloCommand;
+//!
+//! #[async_trait]
+//! impl Command for HelloCommand {
+//!     fn name(&self) -> &str {
+//!         "hello"
+//!     }
+//!
+//!     async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+//!         ctx.expect_end()?;
+//!         ctx.reply(&format!("Hello, {}!", ctx.sender.name()));
+//!         Ok(1)
+//!     }
+//! }
+//! ```
+
+pub mod argument;
+pub mod builtin;
+pub mod context;
+pub mod dispatcher;
+pub mod selector;
+pub mod sender;
+
+pub use context::CommandContext;
+pub use dispatcher::CommandDispatcher;
+pub use selector::{EntitySelector, SelectorError, SelectorTarget};
+pub use sender::{AdminSender, CommandSender, ConsoleSender, PlayerSender, RconSender, SenderKind};
+
+use crate::protocol::types::JsonTextComponent;
+use async_trait::async_trait;
+use thiserror::Error;
+
+/// Result of a command execution
+///
+/// On success, the value is the command's result count (as in vanilla,
+/// e.g. the number of affected players).
+pub type CommandResult = std::result::Result<i32, CommandError>;
+
+/// Errors reported back to the command sender
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CommandError {
+    /// No command is registered under this name
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+
+    /// A required argument is missing
+    #[error("Missing argument: expected {0}")]
+    MissingArgument(String),
+
+    /// An argument could not be parsed
+    #[error("Invalid {expected}: '{found}'")]
+    InvalidArgument {
+        /// What was expected
+        expected: String,
+        /// What was found
+        found: String,
+    },
+
+    /// A numeric argument is outside the allowed range
+    #[error("Value {value} must be between {min} and {max}")]
+    OutOfRange {
+        /// Offending value
+        value: String,
+        /// Minimum accepted value
+        min: i64,
+        /// Maximum accepted value
+        max: i64,
+    },
+
+    /// Extra input after the last argument
+    #[error("Incorrect argument for command: unexpected '{0}'")]
+    TrailingArguments(String),
+
+    /// The selector may match more targets than allowed
+    #[error("Only one player is allowed, but the provided selector allows more than one")]
+    TooManyTargets,
+
+    /// No player matched the target
+    #[error("No player was found")]
+    NoPlayerFound,
+
+    /// The sender lacks the permission level required by the command
+    #[error("You do not have permission to use this command")]
+    PermissionDenied,
+
+    /// The selector could not be parsed
+    #[error(transparent)]
+    Selector(#[from] SelectorError),
+
+    /// The command failed for a command-specific reason
+    #[error("{0}")]
+    Failed(String),
+
+    /// The command failed with a message the client translates
+    #[error("{}", .0.to_plain_text())]
+    Translatable(JsonTextComponent),
+}
+
+impl CommandError {
+    /// Fail with a translated message (see [`crate::lang::translate`])
+    pub fn translatable(key: &str, args: &[&str]) -> Self {
+        CommandError::Translatable(crate::lang::translate(key, args))
+    }
+
+    /// Convert the error into a red chat component for the sender
+    pub fn to_component(&self) -> JsonTextComponent {
+        let message = match self {
+            CommandError::Translatable(message) => message.clone(),
+            CommandError::TooManyTargets => crate::lang::translate("argument.player.toomany", &[]),
+            CommandError::NoPlayerFound => {
+                crate::lang::translate("argument.entity.notfound.player", &[])
+            }
+            other => JsonTextComponent::text(&other.to_string()),
+        };
+        message.with_color("red")
+    }
+}
+
+/// A server command
+#[async_trait]
+pub trait Command: Send + Sync {
+    /// Primary command name (without the leading slash)
+    fn name(&self) -> &str;
+
+    /// Alternative names for the command
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Usage string shown when the command is used incorrectly
+    fn usage(&self) -> &str {
+        ""
+    }
+
+    /// One-line description shown by `/help`
+    fn description(&self) -> &str {
+        ""
+    }
+
+    /// Minimum permission level required to run the command
+    fn permission_level(&self) -> u8 {
+        0
+    }
+
+    /// Execute the command
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult;
+}
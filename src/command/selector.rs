@@ -0,0 +1,662 @@
+//! Entity selector parsing
+//!
+//! This module parses target selectors such as `@a`, `@p[distance=..10]` or
+//! `@e[type=minecraft:cow,limit=3,sort=nearest]` and resolves them against the
+//! players and entities known to the server.
+
+use crate::game::entity::{Entity, EntityId, EntityManager};
+use crate::game::player::{GameMode, Player, PlayerPosition};
+use crate::protocol::types::McUuid;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use thiserror::Error;
+
+/// Names of all supported selector arguments (used for suggestions)
+pub const ARGUMENT_NAMES: &[&str] = &["distance", "type", "name", "limit", "sort", "gamemode"];
+
+/// Errors that can occur while parsing a selector
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum SelectorError {
+    /// The input does not start with `@`
+    #[error("Selector must start with '@'")]
+    MissingPrefix,
+
+    /// The selector variable is not known
+    #[error("Unknown selector type '@{0}'")]
+    UnknownTarget(String),
+
+    /// The argument list was not closed with `]`
+    #[error("Expected ']' to close selector arguments")]
+    Unterminated,
+
+    /// The argument name is not known
+    #[error("Unknown selector argument '{0}'")]
+    UnknownArgument(String),
+
+    /// The argument has no `=value` part
+    #[error("Expected value for selector argument '{0}'")]
+    MissingValue(String),
+
+    /// The argument value could not be parsed
+    #[error("Invalid value '{value}' for selector argument '{argument}'")]
+    InvalidValue {
+        /// Argument name
+        argument: String,
+        /// Offending value
+        value: String,
+    },
+
+    /// The argument cannot be used with this selector type
+    #[error("Selector argument '{argument}' is not applicable to @{target}")]
+    NotApplicable {
+        /// Argument name
+        argument: String,
+        /// Selector variable
+        target: char,
+    },
+}
+
+/// The selector variable following `@`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorTarget {
+    /// `@a` - all players
+    AllPlayers,
+    /// `@p` - nearest player
+    NearestPlayer,
+    /// `@r` - random player
+    RandomPlayer,
+    /// `@s` - the executing entity
+    Executor,
+    /// `@e` - all entities
+    AllEntities,
+}
+
+impl SelectorTarget {
+    /// All selector variables (used for suggestions)
+    pub const ALL: [SelectorTarget; 5] = [
+        SelectorTarget::AllPlayers,
+        SelectorTarget::NearestPlayer,
+        SelectorTarget::RandomPlayer,
+        SelectorTarget::Executor,
+        SelectorTarget::AllEntities,
+    ];
+
+    /// Parse a selector variable from its character
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'a' => Some(SelectorTarget::AllPlayers),
+            'p' => Some(SelectorTarget::NearestPlayer),
+            'r' => Some(SelectorTarget::RandomPlayer),
+            's' => Some(SelectorTarget::Executor),
+            'e' => Some(SelectorTarget::AllEntities),
+            _ => None,
+        }
+    }
+
+    /// Get the character for this selector variable
+    pub fn as_char(&self) -> char {
+        match self {
+            SelectorTarget::AllPlayers => 'a',
+            SelectorTarget::NearestPlayer => 'p',
+            SelectorTarget::RandomPlayer => 'r',
+            SelectorTarget::Executor => 's',
+            SelectorTarget::AllEntities => 'e',
+        }
+    }
+
+    /// Default number of targets when no `limit` is given
+    pub fn default_limit(&self) -> usize {
+        match self {
+            SelectorTarget::NearestPlayer
+            | SelectorTarget::RandomPlayer
+            | SelectorTarget::Executor => 1,
+            SelectorTarget::AllPlayers | SelectorTarget::AllEntities => usize::MAX,
+        }
+    }
+
+    /// Default sort order when no `sort` is given
+    pub fn default_sort(&self) -> SortOrder {
+        match self {
+            SelectorTarget::NearestPlayer => SortOrder::Nearest,
+            SelectorTarget::RandomPlayer => SortOrder::Random,
+            _ => SortOrder::Arbitrary,
+        }
+    }
+}
+
+/// Sort order applied before `limit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Closest to the origin first
+    Nearest,
+    /// Furthest from the origin first
+    Furthest,
+    /// Random order
+    Random,
+    /// No particular order
+    Arbitrary,
+}
+
+impl SortOrder {
+    /// Parse a sort order from its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(SortOrder::Nearest),
+            "furthest" => Some(SortOrder::Furthest),
+            "random" => Some(SortOrder::Random),
+            "arbitrary" => Some(SortOrder::Arbitrary),
+            _ => None,
+        }
+    }
+}
+
+/// A numeric range such as `5`, `..5`, `5..` or `1..5` (both ends inclusive)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    /// Lower bound
+    pub min: Option<f64>,
+    /// Upper bound
+    pub max: Option<f64>,
+}
+
+impl Range {
+    /// Parse a range from its textual form
+    pub fn parse(input: &str) -> Option<Self> {
+        let parse_bound = |s: &str| -> Option<Option<f64>> {
+            if s.is_empty() {
+                Some(None)
+            } else {
+                s.parse::<f64>().ok().filter(|v| v.is_finite()).map(Some)
+            }
+        };
+
+        let range = match input.split_once("..") {
+            Some((min, max)) => Range {
+                min: parse_bound(min)?,
+                max: parse_bound(max)?,
+            },
+            None => {
+                let exact = parse_bound(input)??;
+                Range {
+                    min: Some(exact),
+                    max: Some(exact),
+                }
+            }
+        };
+
+        match (range.min, range.max) {
+            (None, None) => None,
+            (Some(min), Some(max)) if min > max => None,
+            _ => Some(range),
+        }
+    }
+
+    /// Check whether a value lies within the range
+    pub fn contains(&self, value: f64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+/// A filter value that may be negated with `!`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Negatable<T> {
+    /// The value to compare against
+    pub value: T,
+    /// Whether the filter is negated
+    pub negated: bool,
+}
+
+impl<T: PartialEq> Negatable<T> {
+    /// Check whether a candidate value passes this filter
+    pub fn accepts(&self, candidate: &T) -> bool {
+        (self.value == *candidate) != self.negated
+    }
+}
+
+/// A parsed entity selector
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySelector {
+    /// Selector variable
+    pub target: SelectorTarget,
+    /// `distance` argument
+    pub distance: Option<Range>,
+    /// `type` arguments
+    pub types: Vec<Negatable<String>>,
+    /// `name` arguments
+    pub names: Vec<Negatable<String>>,
+    /// `limit` argument
+    pub limit: Option<usize>,
+    /// `sort` argument
+    pub sort: Option<SortOrder>,
+    /// `gamemode` arguments
+    pub gamemodes: Vec<Negatable<GameMode>>,
+}
+
+impl EntitySelector {
+    /// Create a selector without arguments
+    pub fn new(target: SelectorTarget) -> Self {
+        Self {
+            target,
+            distance: None,
+            types: Vec::new(),
+            names: Vec::new(),
+            limit: None,
+            sort: None,
+            gamemodes: Vec::new(),
+        }
+    }
+
+    /// Check whether a command argument looks like a selector
+    pub fn is_selector(input: &str) -> bool {
+        input.starts_with('@')
+    }
+
+    /// Parse a selector such as `@e[type=cow,limit=2]`
+    pub fn parse(input: &str) -> Result<Self, SelectorError> {
+        let rest = input
+            .strip_prefix('@')
+            .ok_or(SelectorError::MissingPrefix)?;
+        let mut chars = rest.chars();
+        let target_char = chars
+            .next()
+            .ok_or_else(|| SelectorError::UnknownTarget(String::new()))?;
+        let target = SelectorTarget::from_char(target_char)
+            .ok_or_else(|| SelectorError::UnknownTarget(target_char.to_string()))?;
+
+        let mut selector = Self::new(target);
+        let arguments = chars.as_str();
+
+        if arguments.is_empty() {
+            return Ok(selector);
+        }
+
+        let body = arguments
+            .strip_prefix('[')
+            .and_then(|a| a.strip_suffix(']'))
+            .ok_or(SelectorError::Unterminated)?;
+
+        for argument in split_arguments(body)? {
+            let argument = argument.trim();
+            if argument.is_empty() {
+                continue;
+            }
+            let (key, value) = argument
+                .split_once('=')
+                .ok_or_else(|| SelectorError::MissingValue(argument.to_string()))?;
+            selector.apply_argument(key.trim(), value.trim())?;
+        }
+
+        Ok(selector)
+    }
+
+    /// Apply a single `key=value` argument
+    fn apply_argument(&mut self, key: &str, value: &str) -> Result<(), SelectorError> {
+        let invalid = || SelectorError::InvalidValue {
+            argument: key.to_string(),
+            value: value.to_string(),
+        };
+        let (negated, raw) = match value.strip_prefix('!') {
+            Some(raw) => (true, raw),
+            None => (false, value),
+        };
+
+        match key {
+            "distance" => {
+                let range = Range::parse(value).ok_or_else(invalid)?;
+                if range.min.is_some_and(|min| min < 0.0) {
+                    return Err(invalid());
+                }
+                self.distance = Some(range);
+            }
+            "type" => {
+                if raw.is_empty() {
+                    return Err(invalid());
+                }
+                self.types.push(Negatable {
+                    value: normalize_identifier(raw),
+                    negated,
+                });
+            }
+            "name" => self.names.push(Negatable {
+                value: unquote(raw).to_string(),
+                negated,
+            }),
+            "limit" => {
+                self.ensure_applicable(key)?;
+                let limit = value.parse::<usize>().map_err(|_| invalid())?;
+                if limit == 0 {
+                    return Err(invalid());
+                }
+                self.limit = Some(limit);
+            }
+            "sort" => {
+                self.ensure_applicable(key)?;
+                self.sort = Some(SortOrder::from_name(value).ok_or_else(invalid)?);
+            }
+            "gamemode" => self.gamemodes.push(Negatable {
+                value: GameMode::from_name(raw).ok_or_else(invalid)?,
+                negated,
+            }),
+            _ => return Err(SelectorError::UnknownArgument(key.to_string())),
+        }
+
+        Ok(())
+    }
+
+    /// `limit` and `sort` make no sense for `@s`
+    fn ensure_applicable(&self, argument: &str) -> Result<(), SelectorError> {
+        if self.target == SelectorTarget::Executor {
+            return Err(SelectorError::NotApplicable {
+                argument: argument.to_string(),
+                target: self.target.as_char(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Effective maximum number of targets
+    pub fn effective_limit(&self) -> usize {
+        self.limit.unwrap_or_else(|| self.target.default_limit())
+    }
+
+    /// Effective sort order
+    pub fn effective_sort(&self) -> SortOrder {
+        self.sort.unwrap_or_else(|| self.target.default_sort())
+    }
+
+    /// Whether this selector can only ever match players
+    pub fn players_only(&self) -> bool {
+        self.target != SelectorTarget::AllEntities
+            || self
+                .types
+                .iter()
+                .any(|t| !t.negated && t.value == "minecraft:player")
+    }
+
+    /// Check whether a player matches all filters of this selector
+    pub fn matches_player(&self, player: &Player, origin: &PlayerPosition) -> bool {
+        self.matches_common(
+            "minecraft:player",
+            &player.username,
+            distance(&player.position, origin),
+        ) && self.gamemodes.iter().all(|g| g.accepts(&player.game_mode))
+    }
+
+    /// Check whether a (non-player) entity matches all filters of this selector
+    pub fn matches_entity(&self, entity: &dyn Entity, origin: &PlayerPosition) -> bool {
+        let position = entity.position();
+        let position = PlayerPosition {
+            x: position.x,
+            y: position.y,
+            z: position.z,
+        };
+        // Entities without a custom name are matched by their type identifier
+        let identifier = entity.entity_type().identifier();
+        self.gamemodes.is_empty()
+            && self.matches_common(identifier, identifier, distance(&position, origin))
+    }
+
+    fn matches_common(&self, identifier: &str, name: &str, distance: f64) -> bool {
+        self.types
+            .iter()
+            .all(|t| t.accepts(&identifier.to_string()))
+            && self.names.iter().all(|n| n.accepts(&name.to_string()))
+            && self.distance.is_none_or(|range| range.contains(distance))
+    }
+
+    /// Resolve this selector against a list of online players
+    ///
+    /// `origin` is the position of the command source and `executor` is the
+    /// UUID of the executing player, if any (required for `@s`).
+    pub fn select_players(
+        &self,
+        players: Vec<Player>,
+        origin: &PlayerPosition,
+        executor: Option<McUuid>,
+    ) -> Vec<Player> {
+        let mut matched: Vec<Player> = players
+            .into_iter()
+            .filter(|p| self.target != SelectorTarget::Executor || Some(p.uuid) == executor)
+            .filter(|p| self.matches_player(p, origin))
+            .collect();
+
+        self.sort_and_limit(&mut matched, |p| distance(&p.position, origin));
+        matched
+    }
+
+    /// Resolve this selector against the non-player entities of a world
+    pub fn select_entities(
+        &self,
+        entities: &EntityManager,
+        origin: &PlayerPosition,
+    ) -> Vec<EntityId> {
+        if self.players_only() {
+            return Vec::new();
+        }
+
+        let mut matched: Vec<(EntityId, f64)> = entities
+            .entities()
+            .filter(|e| self.matches_entity(*e, origin))
+            .map(|e| {
+                let p = e.position();
+                let d = distance(
+                    &PlayerPosition {
+                        x: p.x,
+                        y: p.y,
+                        z: p.z,
+                    },
+                    origin,
+                );
+                (e.entity_id(), d)
+            })
+            .collect();
+
+        self.sort_and_limit(&mut matched, |(_, d)| *d);
+        matched.into_iter().map(|(id, _)| id).collect()
+    }
+
+    fn sort_and_limit<T>(&self, items: &mut Vec<T>, distance_of: impl Fn(&T) -> f64) {
+        match self.effective_sort() {
+            SortOrder::Nearest => items.sort_by(|a, b| distance_of(a).total_cmp(&distance_of(b))),
+            SortOrder::Furthest => items.sort_by(|a, b| distance_of(b).total_cmp(&distance_of(a))),
+            SortOrder::Random => shuffle(items),
+            SortOrder::Arbitrary => {}
+        }
+        items.truncate(self.effective_limit());
+    }
+}
+
+/// Split selector arguments on commas that are not inside quotes
+fn split_arguments(body: &str) -> Result<Vec<&str>, SelectorError> {
+    let mut arguments = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+
+    for (i, c) in body.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                arguments.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if in_quotes {
+        return Err(SelectorError::Unterminated);
+    }
+
+    arguments.push(&body[start..]);
+    Ok(arguments)
+}
+
+/// Strip surrounding double quotes from a value
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Add the `minecraft:` namespace to identifiers that lack one
+fn normalize_identifier(value: &str) -> String {
+    if value.contains(':') {
+        value.to_string()
+    } else {
+        format!("minecraft:{}", value)
+    }
+}
+
+/// Euclidean distance between two positions
+fn distance(a: &PlayerPosition, b: &PlayerPosition) -> f64 {
+    let dx = a.x - b.x;
+    let dy = a.y - b.y;
+    let dz = a.z - b.z;
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+/// Shuffle a slice in place (Fisher-Yates with a randomly seeded xorshift)
+fn shuffle<T>(items: &mut [T]) {
+    let mut state = RandomState::new().build_hasher().finish() | 1;
+    for i in (1..items.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let j = (state % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(name: &str, x: f64, mode: GameMode) -> Player {
+        let mut player = Player::new(McUuid::new_v4(), name.to_string());
+        player.set_position(x, 64.0, 0.0);
+        player.set_game_mode(mode);
+        player
+    }
+
+    fn origin() -> PlayerPosition {
+        PlayerPosition {
+            x: 0.0,
+            y: 64.0,
+            z: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_parse_plain_selectors() {
+        for c in ['a', 'p', 'r', 's', 'e'] {
+            let selector = EntitySelector::parse(&format!("@{}", c)).unwrap();
+            assert_eq!(selector.target.as_char(), c);
+        }
+        assert_eq!(
+            EntitySelector::parse("@x"),
+            Err(SelectorError::UnknownTarget("x".to_string()))
+        );
+        assert_eq!(
+            EntitySelector::parse("Steve"),
+            Err(SelectorError::MissingPrefix)
+        );
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        let selector = EntitySelector::parse(
+            "@e[type=!cow,distance=1..10,limit=3,sort=furthest,name=\"A, B\",gamemode=creative]",
+        )
+        .unwrap();
+
+        assert_eq!(selector.target, SelectorTarget::AllEntities);
+        assert_eq!(
+            selector.types,
+            vec![Negatable {
+                value: "minecraft:cow".to_string(),
+                negated: true
+            }]
+        );
+        assert_eq!(
+            selector.distance,
+            Some(Range {
+                min: Some(1.0),
+                max: Some(10.0)
+            })
+        );
+        assert_eq!(selector.limit, Some(3));
+        assert_eq!(selector.sort, Some(SortOrder::Furthest));
+        assert_eq!(selector.names[0].value, "A, B");
+        assert_eq!(selector.gamemodes[0].value, GameMode::Creative);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            EntitySelector::parse("@a[limit=2"),
+            Err(SelectorError::Unterminated)
+        );
+        assert_eq!(
+            EntitySelector::parse("@a[foo=1]"),
+            Err(SelectorError::UnknownArgument("foo".to_string()))
+        );
+        assert!(matches!(
+            EntitySelector::parse("@a[limit=0]"),
+            Err(SelectorError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            EntitySelector::parse("@s[limit=2]"),
+            Err(SelectorError::NotApplicable { .. })
+        ));
+        assert!(matches!(
+            EntitySelector::parse("@a[distance=5..1]"),
+            Err(SelectorError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_range() {
+        let range = Range::parse("..5").unwrap();
+        assert!(range.contains(0.0) && range.contains(5.0) && !range.contains(5.1));
+        let range = Range::parse("3").unwrap();
+        assert!(range.contains(3.0) && !range.contains(2.9));
+        assert!(Range::parse("..").is_none());
+        assert!(Range::parse("abc").is_none());
+    }
+
+    #[test]
+    fn test_select_players() {
+        let players = vec![
+            player("Near", 2.0, GameMode::Survival),
+            player("Far", 50.0, GameMode::Survival),
+            player("Builder", 10.0, GameMode::Creative),
+        ];
+
+        let nearest =
+            EntitySelector::parse("@p")
+                .unwrap()
+                .select_players(players.clone(), &origin(), None);
+        assert_eq!(nearest.len(), 1);
+        assert_eq!(nearest[0].username, "Near");
+
+        let survival = EntitySelector::parse("@a[gamemode=survival,distance=..20]")
+            .unwrap()
+            .select_players(players.clone(), &origin(), None);
+        assert_eq!(survival.len(), 1);
+        assert_eq!(survival[0].username, "Near");
+
+        let executor = players[2].uuid;
+        let this = EntitySelector::parse("@s").unwrap().select_players(
+            players.clone(),
+            &origin(),
+            Some(executor),
+        );
+        assert_eq!(this[0].username, "Builder");
+
+        let random = EntitySelector::parse("@r")
+            .unwrap()
+            .select_players(players, &origin(), None);
+        assert_eq!(random.len(), 1);
+    }
+}
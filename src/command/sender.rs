@@ -0,0 +1,228 @@
+//! Command senders
+//!
+//! A command sender is whoever issued a command: a player, the server
+//! console, a remote RCON client or an admin API client. Feedback is delivered back to the
+//! sender as chat components.
+
+use crate::game::player::{Player, PlayerPosition};
+use crate::protocol::types::{JsonTextComponent, McUuid};
+use std::sync::Mutex;
+
+/// Permission level granted to the console and RCON (same as a level 4 operator)
+pub const CONSOLE_PERMISSION_LEVEL: u8 = 4;
+
+/// The kind of entity that issued a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderKind {
+    /// An in-game player
+    Player(McUuid),
+    /// The server console
+    Console,
+    /// A remote RCON client
+    Rcon,
+    /// A client of the HTTP admin API
+    Admin,
+}
+
+/// Something that can execute commands and receive feedback
+pub trait CommandSender: Send + Sync {
+    /// Display name of the sender
+    fn name(&self) -> String;
+
+    /// The kind of sender
+    fn kind(&self) -> SenderKind;
+
+    /// Operator permission level (0-4)
+    fn permission_level(&self) -> u8;
+
+    /// Position of the sender in the world, if it has one
+    fn position(&self) -> Option<PlayerPosition> {
+        None
+    }
+
+    /// UUID of the sender if it is a player
+    fn uuid(&self) -> Option<McUuid> {
+        match self.kind() {
+            SenderKind::Player(uuid) => Some(uuid),
+            _ => None,
+        }
+    }
+
+    /// Send a feedback message to the sender
+    fn send_message(&self, message: JsonTextComponent);
+
+    /// Check whether the sender has at least the given permission level
+    fn has_permission(&self, level: u8) -> bool {
+        self.permission_level() >= level
+    }
+}
+
+/// The server console
+#[derive(Debug, Default)]
+pub struct ConsoleSender;
+
+impl CommandSender for ConsoleSender {
+    fn name(&self) -> String {
+        "Server".to_string()
+    }
+
+    fn kind(&self) -> SenderKind {
+        SenderKind::Console
+    }
+
+    fn permission_level(&self) -> u8 {
+        CONSOLE_PERMISSION_LEVEL
+    }
+
+    fn send_message(&self, message: JsonTextComponent) {
+        tracing::info!("{}", message.to_plain_text());
+    }
+}
+
+/// A remote RCON client
+///
+/// Output is buffered so it can be returned in the RCON response.
+#[derive(Debug, Default)]
+pub struct RconSender {
+    /// Buffered output lines
+    output: Mutex<Vec<String>>,
+}
+
+impl RconSender {
+    /// Create a new RCON sender
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take all buffered output, joined by newlines
+    pub fn take_output(&self) -> String {
+        let mut output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *output).join("\n")
+    }
+}
+
+impl CommandSender for RconSender {
+    fn name(&self) -> String {
+        "Rcon".to_string()
+    }
+
+    fn kind(&self) -> SenderKind {
+        SenderKind::Rcon
+    }
+
+    fn permission_level(&self) -> u8 {
+        CONSOLE_PERMISSION_LEVEL
+    }
+
+    fn send_message(&self, message: JsonTextComponent) {
+        self.output
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(message.to_plain_text());
+    }
+}
+
+/// A client of the HTTP admin API
+///
+/// Output is buffered so it can be returned in the HTTP response.
+#[derive(Debug, Default)]
+pub struct AdminSender {
+    /// Buffered output lines
+    output: Mutex<Vec<String>>,
+}
+
+impl AdminSender {
+    /// Create a new admin API sender
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take all buffered output lines
+    pub fn take_output(&self) -> Vec<String> {
+        let mut output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *output)
+    }
+}
+
+impl CommandSender for AdminSender {
+    fn name(&self) -> String {
+        "Admin".to_string()
+    }
+
+    fn kind(&self) -> SenderKind {
+        SenderKind::Admin
+    }
+
+    fn permission_level(&self) -> u8 {
+        CONSOLE_PERMISSION_LEVEL
+    }
+
+    fn send_message(&self, message: JsonTextComponent) {
+        self.output
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(message.to_plain_text());
+    }
+}
+
+/// An in-game player
+///
+/// Messages are queued and sent to the client as system chat messages by
+/// the connection handler once the command has finished.
+#[derive(Debug)]
+pub struct PlayerSender {
+    /// Player UUID
+    uuid: McUuid,
+    /// Player username
+    username: String,
+    /// Player position when the command was issued
+    position: PlayerPosition,
+    /// Operator permission level
+    permission_level: u8,
+    /// Queued feedback messages
+    messages: Mutex<Vec<JsonTextComponent>>,
+}
+
+impl PlayerSender {
+    /// Create a sender for a player with the given permission level
+    pub fn new(player: &Player, permission_level: u8) -> Self {
+        Self {
+            uuid: player.uuid,
+            username: player.username.clone(),
+            position: player.position,
+            permission_level,
+            messages: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Take all queued feedback messages
+    pub fn take_messages(&self) -> Vec<JsonTextComponent> {
+        let mut messages = self.messages.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *messages)
+    }
+}
+
+impl CommandSender for PlayerSender {
+    fn name(&self) -> String {
+        self.username.clone()
+    }
+
+    fn kind(&self) -> SenderKind {
+        SenderKind::Player(self.uuid)
+    }
+
+    fn permission_level(&self) -> u8 {
+        self.permission_level
+    }
+
+    fn position(&self) -> Option<PlayerPosition> {
+        Some(self.position)
+    }
+
+    fn send_message(&self, message: JsonTextComponent) {
+        self.messages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(message);
+    }
+}
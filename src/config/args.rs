@@ -0,0 +1,156 @@
+//! Command-line arguments
+//!
+//! Parsed with clap. Flags follow vanilla's dedicated server: `--port` and
+//! `--world` take a value, toggles such as `--nogui` take none, and the
+//! camelCase spellings vanilla uses (`--safeMode`, `--initSettings`) are
+//! accepted alongside the kebab-case ones.
+
+use crate::config::{OBSIDIUM_TOML_FILE, ServerConfig};
+use crate::game::world::pregen::MAX_PREGEN_RADIUS;
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Default path of the properties file
+pub const DEFAULT_PROPERTIES_FILE: &str = "server.properties";
+
+/// Arguments given on the command line
+///
+/// Every option is optional; options that are set take precedence over the
+/// configuration files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Parser)]
+#[command(
+    name = "obsidium",
+    version,
+    about = "A Minecraft server written in Rust",
+    long_about = None
+)]
+pub struct CliArgs {
+    /// Path of server.properties; obsidium.toml is read from the same directory
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Port to listen on
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// World directory
+    #[arg(long, visible_alias = "world", value_name = "DIR")]
+    pub world_dir: Option<String>,
+    /// Authenticate players with Mojang
+    #[arg(long, value_name = "BOOL")]
+    pub online_mode: Option<bool>,
+    /// Start without loading plugins
+    #[arg(long, visible_alias = "safeMode")]
+    pub safe_mode: bool,
+    /// Record every connection's packets to dump files in this directory
+    #[arg(long, value_name = "DIR")]
+    pub dump_packets: Option<PathBuf>,
+    /// Generate terrain within this many chunks of spawn before accepting players
+    #[arg(
+        long,
+        value_name = "RADIUS",
+        value_parser = clap::value_parser!(u32).range(..=i64::from(MAX_PREGEN_RADIUS)),
+    )]
+    pub pregen: Option<u32>,
+    /// Replay a packet dump against the server on --port and exit
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+    /// Write the configuration files and exit
+    #[arg(long, visible_alias = "initSettings")]
+    pub init_settings: bool,
+    /// Accepted for compatibility; Obsidium has no GUI
+    #[arg(long)]
+    pub nogui: bool,
+    /// Vanilla's bare `nogui` argument
+    #[arg(hide = true, value_parser = ["nogui"])]
+    legacy_nogui: Option<String>,
+}
+
+impl CliArgs {
+    /// Path of server.properties
+    pub fn properties_path(&self) -> PathBuf {
+        self.config
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_PROPERTIES_FILE))
+    }
+
+    /// Path of obsidium.toml, next to server.properties
+    pub fn overrides_path(&self) -> PathBuf {
+        self.properties_path().with_file_name(OBSIDIUM_TOML_FILE)
+    }
+
+    /// Apply the arguments on top of a configuration
+    pub fn apply(&self, mut config: ServerConfig) -> ServerConfig {
+        if let Some(port) = self.port {
+            config.bind_address.set_port(port);
+        }
+        if let Some(ref world_dir) = self.world_dir {
+            config.level_name = world_dir.clone();
+        }
+        if let Some(online_mode) = self.online_mode {
+            config.online_mode = online_mode;
+        }
+        if self.safe_mode {
+            config.plugins_enabled = false;
+        }
+        if let Some(ref dir) = self.dump_packets {
+            config.packet_dump_dir = Some(dir.clone());
+        }
+        if let Some(radius) = self.pregen {
+            config.pregen_radius = Some(radius);
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use clap::error::ErrorKind;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, clap::Error> {
+        CliArgs::try_parse_from(std::iter::once("obsidium").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_parse_and_apply() {
+        let args = parse(&[
+            "--port",
+            "25570",
+            "--world=survival",
+            "--online-mode",
+            "false",
+            "--nogui",
+            "--safeMode",
+            "--config",
+            "conf/server.properties",
+            "--dump-packets=dumps",
+            "--pregen",
+            "8",
+            "nogui",
+        ])
+        .unwrap();
+        assert_eq!(args.overrides_path(), PathBuf::from("conf/obsidium.toml"));
+
+        let config = args.apply(ServerConfig::new());
+        assert_eq!(config.bind_address.port(), 25570);
+        assert_eq!(config.level_name, "survival");
+        assert!(!config.online_mode);
+        assert!(!config.plugins_enabled);
+        assert_eq!(config.packet_dump_dir, Some(PathBuf::from("dumps")));
+        assert_eq!(config.pregen_radius, Some(8));
+
+        let kind = |args: &[&str]| parse(args).unwrap_err().kind();
+        assert_eq!(kind(&["--port"]), ErrorKind::InvalidValue);
+        assert_eq!(kind(&["--port", "high"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["--pregen", "5000"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["--bogus"]), ErrorKind::UnknownArgument);
+        assert_eq!(kind(&["--nogui=yes"]), ErrorKind::TooManyValues);
+        assert_eq!(kind(&["--help"]), ErrorKind::DisplayHelp);
+        assert_eq!(kind(&["-V"]), ErrorKind::DisplayVersion);
+    }
+
+    #[test]
+    fn test_command_definition() {
+        CliArgs::command().debug_assert();
+    }
+}
@@ -2,9 +2,25 @@
 //!
 //! This module handles server configuration including network settings,
 //! game rules, and performance tuning options.
+//!
+//! Settings are layered, later layers taking precedence:
+//!
+//! 1. Built-in defaults ([`ServerConfig::default`])
+//! 2. `server.properties`, the vanilla format
+//! 3. `obsidium.toml`, for settings vanilla has no key for (see
+//!    [`overrides`])
+//! 4. Command-line arguments ([`CliArgs`])
 
+pub mod args;
+pub mod overrides;
 pub mod properties;
+pub mod reload;
+pub mod secrets;
 pub mod server;
+pub mod validate;
 
+pub use args::CliArgs;
+pub use overrides::{ConfigOverrides, OBSIDIUM_TOML_FILE};
 pub use properties::ServerProperties;
+pub use reload::{ReloadReport, RuntimeSettings};
 pub use server::ServerConfig;
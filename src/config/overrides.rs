@@ -0,0 +1,712 @@
+//! obsidium.toml configuration
+//!
+//! `obsidium.toml` holds settings vanilla's server.properties has no key
+//! for, such as network tuning and feature flags, and can override world
+//! settings from server.properties. Every key is optional; a missing file
+//! changes nothing.
+//!
+//! ```toml
+//! [server]
+//! favicon = "server-icon.png"
+//! language = "en_us"           # console language, from lang/<code>.json
+//!
+//! [network]
+//! tcp-nodelay = true
+//! compression-threshold = 256   # -1 disables compression
+//! login-throttle = 4            # seconds between logins per address, 0 disables
+//! login-timeout = 30            # seconds to log in, 0 disables
+//! profile-api = "mojang"        # or the http(s) URL of a proxy to it; mojang in online mode
+//! trusted-proxies = ["127.0.0.1"]  # send a PROXY protocol header
+//! require-forwarding = false    # refuse connections not from a trusted proxy
+//!
+//! [rate-limits]                # packets per player, 0 disables a limit
+//! chat-per-second = 5
+//! movement-per-tick = 5
+//! interactions-per-tick = 8
+//!
+//! [chat]                       # 0 disables a limit
+//! messages-per-window = 10      # chat messages per 10 seconds
+//! duplicate-cooldown = 30       # seconds before a message may be repeated
+//! max-length = 256              # characters, at most 256
+//! kick-after = 5                # warnings per 10 seconds before a kick
+//!
+//! [messages]                   # {player}, {online} and {max}; "" turns a message off
+//! join = "{player} joined ({online}/{max})"
+//! first-join = "Welcome {player}!"  # the join message if not set
+//! quit = "{player} left"
+//!
+//! [world]
+//! view-distance = 10
+//! simulation-distance = 8
+//! autosave-interval = 300       # seconds, 0 disables autosaving
+//! sync-chunk-writes = true
+//!
+//! [chunks]                     # 0 disables a limit
+//! loads-per-tick = 64           # chunks read or generated per tick
+//! queued-per-player = 256       # packets waiting for a player before chunks wait
+//! max-loaded = 10000            # chunks kept loaded before unseen ones unload
+//!
+//! [status]                     # how the server appears in the server list
+//! version-name = "Proxy 1.21.x"
+//! protocol = 767                # shown to clients outside the accepted range
+//! accepted-protocols = "767-771"  # shown as compatible and allowed to log in
+//!
+//! [audit]                      # logins, commands and moderation, see logs/audit.jsonl
+//! enabled = true
+//! file = "logs/audit.jsonl"
+//! max-size = 10                 # MiB before the file is rotated
+//! keep = 5                      # rotated files kept, 0 keeps none
+//!
+//! [features]
+//! plugins = true
+//! hot-reload = true
+//!
+//! [resource-pack]
+//! file = "resources.zip"        # served by the server itself
+//! port = 25566
+//! public-host = "mc.example.com"  # address players download the pack from
+//!
+//! [backups]
+//! directory = "backups"
+//! interval = 3600               # seconds between scheduled backups, 0 disables them
+//! keep = 5                      # backups kept per world, 0 keeps all
+//! max-age = 7                   # days a backup is kept, 0 keeps them forever
+//! format = "tar.gz"             # or "copy" for a plain directory
+//! ```
+
+use crate::config::ServerConfig;
+use crate::error::{Result, ServerError};
+use crate::game::chat::PROTOCOL_MAX_LENGTH;
+use crate::game::join_messages::JoinMessages;
+use crate::server::audit::AuditConfig;
+use crate::server::backup::{BackupConfig, BackupFormat};
+use crate::server::profiles::{MOJANG_API, ProfileApi};
+use crate::server::resource_pack::{DEFAULT_HOST_PORT, ResourcePackHostConfig};
+use crate::server::version::AdvertisedVersion;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File the overrides are read from
+pub const OBSIDIUM_TOML_FILE: &str = "obsidium.toml";
+
+/// Seconds in a day, the unit of the backup age limit
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Allowed view and simulation distances, as enforced by vanilla
+const DISTANCE_RANGE: std::ops::RangeInclusive<u8> = 2..=32;
+
+/// `[server]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ServerSection {
+    /// Path of a 64x64 PNG, or a `data:image/png;base64,` URL
+    pub favicon: Option<String>,
+    /// Language of the console and logs
+    pub language: Option<String>,
+}
+
+/// `[network]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct NetworkSection {
+    /// Disable Nagle's algorithm on player connections
+    pub tcp_nodelay: Option<bool>,
+    /// Packet size compression starts at (-1 disables compression)
+    pub compression_threshold: Option<i32>,
+    /// Seconds between login attempts from one address (0 disables throttling)
+    pub login_throttle: Option<u64>,
+    /// Seconds a connection may take to log in (0 disables the limit)
+    pub login_timeout: Option<u64>,
+    /// `mojang`, or the HTTP or HTTPS base URL of a proxy to the Mojang API
+    pub profile_api: Option<String>,
+    /// Addresses of proxies that send a PROXY protocol header
+    pub trusted_proxies: Option<Vec<String>>,
+    /// Refuse connections that do not come through a trusted proxy
+    pub require_forwarding: Option<bool>,
+}
+
+impl NetworkSection {
+    /// Apply the section on top of a configuration
+    fn apply(&self, config: &mut ServerConfig) {
+        if let Some(nodelay) = self.tcp_nodelay {
+            config.tcp_nodelay = nodelay;
+        }
+        if let Some(threshold) = self.compression_threshold {
+            config.compression_threshold = u32::try_from(threshold).ok();
+        }
+        if let Some(throttle) = self.login_throttle {
+            config.login_throttle = Duration::from_secs(throttle);
+        }
+        if let Some(timeout) = self.login_timeout {
+            config.login_timeout = Duration::from_secs(timeout);
+        }
+        if let Some(ref url) = self.profile_api {
+            config.profile_api = Some(url.clone());
+        }
+        if let Some(ref proxies) = self.trusted_proxies {
+            let proxies = proxies.iter().filter_map(|proxy| proxy.parse().ok());
+            config.forwarding.trusted_proxies = proxies.collect();
+        }
+        if let Some(required) = self.require_forwarding {
+            config.forwarding.required = required;
+        }
+    }
+}
+
+/// `[rate-limits]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RateLimitsSection {
+    /// Chat messages and commands a player may send per second
+    pub chat_per_second: Option<u32>,
+    /// Movement packets a player may send per tick
+    pub movement_per_tick: Option<u32>,
+    /// Interaction packets a player may send per tick
+    pub interactions_per_tick: Option<u32>,
+}
+
+/// `[chat]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ChatSection {
+    /// Chat messages a player may send per 10 seconds
+    pub messages_per_window: Option<u32>,
+    /// Seconds before a player may repeat their previous message
+    pub duplicate_cooldown: Option<u64>,
+    /// Longest chat message in characters
+    pub max_length: Option<usize>,
+    /// Spam warnings per 10 seconds at which a player is kicked
+    pub kick_after: Option<u32>,
+}
+
+/// `[messages]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct MessagesSection {
+    /// Message when a player joins
+    pub join: Option<String>,
+    /// Message when a player joins for the first time
+    pub first_join: Option<String>,
+    /// Message when a player leaves
+    pub quit: Option<String>,
+}
+
+/// `[world]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct WorldSection {
+    /// View distance in chunks
+    pub view_distance: Option<u8>,
+    /// Simulation distance in chunks
+    pub simulation_distance: Option<u8>,
+    /// Seconds between automatic saves (0 disables autosaving)
+    pub autosave_interval: Option<u64>,
+    /// Flush chunk writes to disk before continuing
+    pub sync_chunk_writes: Option<bool>,
+}
+
+/// `[chunks]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ChunksSection {
+    /// Chunks read or generated per tick
+    pub loads_per_tick: Option<u32>,
+    /// Packets that may wait for a player before chunks wait too
+    pub queued_per_player: Option<u32>,
+    /// Chunks kept loaded before unseen ones are unloaded
+    pub max_loaded: Option<u32>,
+}
+
+/// `[status]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct StatusSection {
+    /// Version name shown in the server list
+    pub version_name: Option<String>,
+    /// Protocol shown to clients outside the accepted range
+    pub protocol: Option<i32>,
+    /// Protocols shown as compatible and allowed to log in, such as
+    /// `767-771`
+    pub accepted_protocols: Option<String>,
+}
+
+/// `[audit]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct AuditSection {
+    /// Write the audit log
+    pub enabled: Option<bool>,
+    /// File the audit log is written to
+    pub file: Option<String>,
+    /// MiB the file may grow to before it is rotated
+    pub max_size: Option<u64>,
+    /// Rotated files kept (0 keeps none)
+    pub keep: Option<usize>,
+}
+
+/// `[features]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FeaturesSection {
+    /// Load plugins from the plugins directory
+    pub plugins: Option<bool>,
+    /// Apply edits to the configuration files while running
+    pub hot_reload: Option<bool>,
+}
+
+/// `[resource-pack]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ResourcePackSection {
+    /// Zip file served by the built-in host
+    pub file: Option<String>,
+    /// Port the pack is served on
+    pub port: Option<u16>,
+    /// Host name or address players download the pack from
+    pub public_host: Option<String>,
+}
+
+/// `[backups]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BackupsSection {
+    /// Directory backups are written to
+    pub directory: Option<String>,
+    /// Seconds between scheduled backups (0 disables them)
+    pub interval: Option<u64>,
+    /// Backups kept per world (0 keeps all)
+    pub keep: Option<usize>,
+    /// Days a backup is kept (0 keeps them forever)
+    pub max_age: Option<u64>,
+    /// `copy` or `tar.gz`
+    pub format: Option<String>,
+}
+
+/// Settings read from obsidium.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigOverrides {
+    /// `[server]` section
+    pub server: ServerSection,
+    /// `[network]` section
+    pub network: NetworkSection,
+    /// `[rate-limits]` section
+    pub rate_limits: RateLimitsSection,
+    /// `[chat]` section
+    pub chat: ChatSection,
+    /// `[messages]` section
+    pub messages: MessagesSection,
+    /// `[world]` section
+    pub world: WorldSection,
+    /// `[chunks]` section
+    pub chunks: ChunksSection,
+    /// `[status]` section
+    pub status: StatusSection,
+    /// `[audit]` section
+    pub audit: AuditSection,
+    /// `[features]` section
+    pub features: FeaturesSection,
+    /// `[resource-pack]` section
+    pub resource_pack: ResourcePackSection,
+    /// `[backups]` section
+    pub backups: BackupsSection,
+}
+
+impl ConfigOverrides {
+    /// Parse and validate obsidium.toml contents
+    pub fn parse(contents: &str) -> Result<Self> {
+        let document: toml::Table = contents
+            .parse()
+            .map_err(|e: toml::de::Error| ServerError::Configuration(e.to_string()))?;
+
+        let mut overrides = Self::default();
+        for (name, value) in document {
+            match name.as_str() {
+                "server" => overrides.server = section(&name, value)?,
+                "network" => overrides.network = section(&name, value)?,
+                "rate-limits" => overrides.rate_limits = section(&name, value)?,
+                "chat" => overrides.chat = section(&name, value)?,
+                "messages" => overrides.messages = section(&name, value)?,
+                "world" => overrides.world = section(&name, value)?,
+                "chunks" => overrides.chunks = section(&name, value)?,
+                "status" => overrides.status = section(&name, value)?,
+                "audit" => overrides.audit = section(&name, value)?,
+                "features" => overrides.features = section(&name, value)?,
+                "resource-pack" => overrides.resource_pack = section(&name, value)?,
+                "backups" => overrides.backups = section(&name, value)?,
+                _ => {
+                    return Err(ServerError::Configuration(format!(
+                        "unknown section [{}], expected one of server, network, rate-limits, chat, \
+                         messages, world, chunks, status, audit, features, resource-pack, backups",
+                        name
+                    )));
+                }
+            }
+        }
+
+        overrides.validate()?;
+        Ok(overrides)
+    }
+
+    /// Load overrides from a file, changing nothing if it does not exist
+    pub fn load_file_or_default<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents).map_err(|e| match e {
+                ServerError::Configuration(message) => {
+                    ServerError::Configuration(format!("Invalid {}: {}", path.display(), message))
+                }
+                e => e,
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Check values that deserialize but are out of range
+    fn validate(&self) -> Result<()> {
+        let distances = [
+            ("view-distance", self.world.view_distance),
+            ("simulation-distance", self.world.simulation_distance),
+        ];
+        for (key, distance) in distances {
+            if distance.is_some_and(|d| !DISTANCE_RANGE.contains(&d)) {
+                return Err(ServerError::Configuration(format!(
+                    "[world]: {} must be between {} and {}",
+                    key,
+                    DISTANCE_RANGE.start(),
+                    DISTANCE_RANGE.end()
+                )));
+            }
+        }
+        if self.network.compression_threshold.is_some_and(|t| t < -1) {
+            return Err(ServerError::Configuration(
+                "[network]: compression-threshold must be -1 or at least 0".to_string(),
+            ));
+        }
+        if let Some(ref url) = self.network.profile_api {
+            if ProfileApi::parse(url).is_none() {
+                return Err(ServerError::Configuration(format!(
+                    "[network]: profile-api must be {:?} or an http:// or https:// URL, not {}",
+                    MOJANG_API, url
+                )));
+            }
+        }
+        for proxy in self.network.trusted_proxies.iter().flatten() {
+            if proxy.parse::<IpAddr>().is_err() {
+                return Err(ServerError::Configuration(format!(
+                    "[network]: trusted-proxies must hold IP addresses, not {}",
+                    proxy
+                )));
+            }
+        }
+        if self.network.require_forwarding == Some(true)
+            && self
+                .network
+                .trusted_proxies
+                .as_ref()
+                .is_none_or(Vec::is_empty)
+        {
+            return Err(ServerError::Configuration(
+                "[network]: require-forwarding needs at least one trusted proxy".to_string(),
+            ));
+        }
+        if let Some(ref range) = self.status.accepted_protocols {
+            if AdvertisedVersion::parse_range(range).is_none() {
+                return Err(ServerError::Configuration(format!(
+                    "[status]: accepted-protocols must be a protocol or a range such as \
+                     767-771, not {}",
+                    range
+                )));
+            }
+        }
+        if self
+            .chat
+            .max_length
+            .is_some_and(|length| length > PROTOCOL_MAX_LENGTH)
+        {
+            return Err(ServerError::Configuration(format!(
+                "[chat]: max-length must be at most {}",
+                PROTOCOL_MAX_LENGTH
+            )));
+        }
+        if self.audit.max_size == Some(0) {
+            return Err(ServerError::Configuration(
+                "[audit]: max-size must be at least 1".to_string(),
+            ));
+        }
+        let pack = &self.resource_pack;
+        if pack.file.is_some() && pack.public_host.as_deref().is_none_or(str::is_empty) {
+            return Err(ServerError::Configuration(
+                "[resource-pack]: public-host is required to host a file".to_string(),
+            ));
+        }
+        if let Some(ref format) = self.backups.format {
+            if BackupFormat::from_name(format).is_none() {
+                return Err(ServerError::Configuration(format!(
+                    "[backups]: format must be copy or tar.gz, not {}",
+                    format
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the overrides on top of a configuration
+    pub fn apply(&self, mut config: ServerConfig) -> ServerConfig {
+        if let Some(ref favicon) = self.server.favicon {
+            config.favicon = Some(favicon.clone());
+        }
+        if let Some(ref language) = self.server.language {
+            config.language = language.clone();
+        }
+
+        self.network.apply(&mut config);
+
+        let limits = &mut config.packet_limits;
+        if let Some(chat) = self.rate_limits.chat_per_second {
+            limits.chat_per_second = chat;
+        }
+        if let Some(movement) = self.rate_limits.movement_per_tick {
+            limits.movement_per_tick = movement;
+        }
+        if let Some(interactions) = self.rate_limits.interactions_per_tick {
+            limits.interactions_per_tick = interactions;
+        }
+
+        let chat = &mut config.chat_limits;
+        if let Some(messages) = self.chat.messages_per_window {
+            chat.messages_per_window = messages;
+        }
+        if let Some(cooldown) = self.chat.duplicate_cooldown {
+            chat.duplicate_cooldown = Duration::from_secs(cooldown);
+        }
+        if let Some(length) = self.chat.max_length {
+            chat.max_length = length;
+        }
+        if let Some(warnings) = self.chat.kick_after {
+            chat.kick_after = warnings;
+        }
+
+        if let Some(distance) = self.world.view_distance {
+            config.view_distance = distance;
+        }
+        if let Some(distance) = self.world.simulation_distance {
+            config.simulation_distance = distance;
+        }
+        if let Some(interval) = self.world.autosave_interval {
+            config.autosave_interval = Duration::from_secs(interval);
+        }
+        if let Some(sync) = self.world.sync_chunk_writes {
+            config.sync_chunk_writes = sync;
+        }
+
+        let budgets = &mut config.chunk_budgets;
+        if let Some(loads) = self.chunks.loads_per_tick {
+            budgets.loads_per_tick = loads;
+        }
+        if let Some(queued) = self.chunks.queued_per_player {
+            budgets.queued_per_player = queued;
+        }
+        if let Some(loaded) = self.chunks.max_loaded {
+            budgets.max_loaded = loaded;
+        }
+
+        let version = &mut config.advertised_version;
+        if let Some(ref name) = self.status.version_name {
+            version.name = name.clone();
+        }
+        if let Some(protocol) = self.status.protocol {
+            version.protocol = protocol;
+        }
+        if let Some(accepted) = self
+            .status
+            .accepted_protocols
+            .as_deref()
+            .and_then(AdvertisedVersion::parse_range)
+        {
+            version.accepted = accepted;
+        }
+
+        if let Some(plugins) = self.features.plugins {
+            config.plugins_enabled = plugins;
+        }
+        if let Some(hot_reload) = self.features.hot_reload {
+            config.hot_reload = hot_reload;
+        }
+
+        let pack = &self.resource_pack;
+        if let (Some(file), Some(public_host)) = (&pack.file, &pack.public_host) {
+            let port = pack.port.unwrap_or(DEFAULT_HOST_PORT);
+            config.resource_pack_host = Some(ResourcePackHostConfig {
+                file: PathBuf::from(file),
+                bind_address: SocketAddr::new(config.bind_address.ip(), port),
+                public_host: public_host.clone(),
+            });
+        }
+
+        self.apply_messages(&mut config.join_messages);
+        self.apply_audit(&mut config.audit);
+        self.apply_backups(&mut config.backups);
+        config
+    }
+
+    /// Apply the `[messages]` section
+    fn apply_messages(&self, messages: &mut JoinMessages) {
+        if let Some(ref join) = self.messages.join {
+            messages.join = Some(join.clone());
+        }
+        if let Some(ref first_join) = self.messages.first_join {
+            messages.first_join = Some(first_join.clone());
+        }
+        if let Some(ref quit) = self.messages.quit {
+            messages.quit = Some(quit.clone());
+        }
+    }
+
+    /// Apply the `[audit]` section
+    fn apply_audit(&self, audit: &mut AuditConfig) {
+        if let Some(enabled) = self.audit.enabled {
+            audit.enabled = enabled;
+        }
+        if let Some(ref file) = self.audit.file {
+            audit.path = PathBuf::from(file);
+        }
+        if let Some(mebibytes) = self.audit.max_size {
+            audit.max_size = mebibytes.saturating_mul(1024 * 1024);
+        }
+        if let Some(keep) = self.audit.keep {
+            audit.keep = keep;
+        }
+    }
+
+    /// Apply the `[backups]` section
+    fn apply_backups(&self, backups: &mut BackupConfig) {
+        if let Some(ref directory) = self.backups.directory {
+            backups.directory = PathBuf::from(directory);
+        }
+        if let Some(interval) = self.backups.interval {
+            backups.interval = Duration::from_secs(interval);
+        }
+        if let Some(keep) = self.backups.keep {
+            backups.keep = keep;
+        }
+        if let Some(days) = self.backups.max_age {
+            backups.max_age = Duration::from_secs(days.saturating_mul(SECONDS_PER_DAY));
+        }
+        if let Some(format) = self
+            .backups
+            .format
+            .as_deref()
+            .and_then(BackupFormat::from_name)
+        {
+            backups.format = format;
+        }
+    }
+}
+
+/// Deserialize a section, naming it in errors
+fn section<T: DeserializeOwned>(name: &str, value: toml::Value) -> Result<T> {
+    value
+        .try_into()
+        .map_err(|e: toml::de::Error| ServerError::Configuration(format!("[{}]: {}", name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::PROTOCOL_VERSION;
+
+    #[test]
+    fn test_parse_and_apply() {
+        let overrides = ConfigOverrides::parse(
+            "[network]\ntcp-nodelay = false\ncompression-threshold = -1\nlogin-throttle = 0\n\n\
+             [rate-limits]\nchat-per-second = 0\n\n[world]\nview-distance = 6\n\n\
+             [chunks]\nmax-loaded = 0\n\n[status]\naccepted-protocols = \"767-771\"\n\n\
+             [audit]\nmax-size = 1\nkeep = 0\n\n\
+             [features]\nplugins = false\n",
+        )
+        .unwrap();
+
+        let config = overrides.apply(ServerConfig::new().with_simulation_distance(9));
+        assert!(!config.tcp_nodelay);
+        assert_eq!(config.compression_threshold, None);
+        assert_eq!(config.login_throttle, Duration::ZERO);
+        assert_eq!(config.view_distance, 6);
+        assert_eq!(config.simulation_distance, 9);
+        assert_eq!(config.packet_limits.chat_per_second, 0);
+        assert_eq!(config.packet_limits.movement_per_tick, 5);
+        assert_eq!(config.chunk_budgets.max_loaded(), None);
+        assert_eq!(config.chunk_budgets.loads_per_tick(), Some(64));
+        assert_eq!(config.advertised_version.accepted, 767..=771);
+        assert_eq!(config.advertised_version.protocol, PROTOCOL_VERSION);
+        assert_eq!(config.audit.max_size, 1024 * 1024);
+        assert_eq!(config.audit.keep, 0);
+        assert!(config.audit.enabled);
+        assert!(!config.plugins_enabled);
+        assert!(config.hot_reload);
+        assert_eq!(config.resource_pack_host, None);
+
+        let overrides = ConfigOverrides::parse(
+            "[resource-pack]\nfile = \"pack.zip\"\npublic-host = \"mc.example.com\"\n",
+        )
+        .unwrap();
+        let host = overrides
+            .apply(ServerConfig::new())
+            .resource_pack_host
+            .unwrap();
+        assert_eq!(host.file, PathBuf::from("pack.zip"));
+        assert_eq!(host.bind_address.port(), DEFAULT_HOST_PORT);
+
+        let overrides = ConfigOverrides::parse(
+            "[backups]\ninterval = 3600\nmax-age = 2\nformat = \"tar.gz\"\n",
+        )
+        .unwrap();
+        let backups = overrides.apply(ServerConfig::new()).backups;
+        let forwarding = ConfigOverrides::parse(
+            "[network]\ntrusted-proxies = [\"10.0.0.2\"]\nrequire-forwarding = true\n",
+        )
+        .unwrap()
+        .apply(ServerConfig::new())
+        .forwarding;
+        assert_eq!(
+            forwarding.trusted_proxies,
+            ["10.0.0.2".parse::<IpAddr>().unwrap()]
+        );
+        assert!(forwarding.required);
+        let chat = ConfigOverrides::parse("[chat]\nduplicate-cooldown = 0\nkick-after = 2\n")
+            .unwrap()
+            .apply(ServerConfig::new())
+            .chat_limits;
+        assert_eq!(chat.duplicate_cooldown, Duration::ZERO);
+        assert_eq!(chat.kick_after, 2);
+        assert_eq!(chat.messages_per_window, 10);
+        let messages = ConfigOverrides::parse(
+            "[messages]
+quit = \"\"\n",
+        )
+        .unwrap()
+        .apply(ServerConfig::new())
+        .join_messages;
+        assert_eq!(messages.quit.as_deref(), Some(""));
+        assert_eq!(messages.join, None);
+        assert_eq!(backups.interval, Duration::from_secs(3600));
+        assert_eq!(backups.max_age, Duration::from_secs(2 * SECONDS_PER_DAY));
+        assert_eq!(backups.format, BackupFormat::TarGz);
+        assert_eq!(backups.keep, crate::server::backup::DEFAULT_KEEP);
+
+        let error = |contents: &str| ConfigOverrides::parse(contents).unwrap_err().to_string();
+        assert!(error("[world]\nview-distance = \"far\"").contains("[world]: invalid type"));
+        assert!(error("[network]\nnodelay = true").contains("unknown field `nodelay`"));
+        assert!(error("[world]\nview-distance = 64").contains("between 2 and 32"));
+        assert!(error("[mobs]\nfilter = true").contains("unknown section [mobs]"));
+        assert!(error("[chat]\nfilter = true").contains("unknown field `filter`"));
+        assert!(error("[chat]\nmax-length = 300").contains("at most 256"));
+        assert!(error("[resource-pack]\nfile = \"pack.zip\"").contains("public-host is required"));
+        assert!(error("[backups]\nformat = \"zip\"").contains("copy or tar.gz"));
+        assert!(error("[network]\ntrusted-proxies = [\"proxy\"]").contains("IP addresses"));
+        assert!(error("[network]\nrequire-forwarding = true").contains("trusted proxy"));
+    }
+}
@@ -4,15 +4,21 @@
 //! standard Minecraft format, supporting all Java Edition server properties.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 use std::str::FromStr;
 
+use crate::config::secrets::{REDACTED, SECRET_PROPERTIES};
 use crate::error::ServerError;
+use crate::game::player::GameMode;
+use crate::game::world::anvil::RegionCompression;
+use crate::game::{Difficulty, LevelType};
+use crate::protocol::types::McUuid;
 
 /// Represents a server.properties file with all Minecraft Java Edition properties
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ServerProperties {
     /// Raw properties map for unknown/custom properties
     properties: HashMap<String, String>,
@@ -24,12 +30,16 @@ impl Default for ServerProperties {
 
         // Set all default values from Minecraft 1.21.3
         properties.insert("accepts-transfers".to_string(), "false".to_string());
+        properties.insert("admin-api.port".to_string(), "25580".to_string());
+        properties.insert("admin-api.token".to_string(), String::new());
         properties.insert("allow-flight".to_string(), "false".to_string());
         properties.insert("allow-nether".to_string(), "true".to_string());
+        properties.insert("autosave-interval".to_string(), "300".to_string());
         properties.insert("broadcast-console-to-ops".to_string(), "true".to_string());
         properties.insert("broadcast-rcon-to-ops".to_string(), "true".to_string());
         properties.insert("bug-report-link".to_string(), String::new());
         properties.insert("difficulty".to_string(), "easy".to_string());
+        properties.insert("enable-admin-api".to_string(), "false".to_string());
         properties.insert("enable-command-block".to_string(), "false".to_string());
         properties.insert("enable-jmx-monitoring".to_string(), "false".to_string());
         properties.insert("enable-query".to_string(), "false".to_string());
@@ -53,7 +63,10 @@ impl Default for ServerProperties {
         properties.insert("level-name".to_string(), "world".to_string());
         properties.insert("level-seed".to_string(), String::new());
         properties.insert("level-type".to_string(), "minecraft:normal".to_string());
+        properties.insert("log-file-keep".to_string(), "0".to_string());
+        properties.insert("log-file-max-size".to_string(), "10".to_string());
         properties.insert("log-ips".to_string(), "true".to_string());
+        properties.insert("log-to-file".to_string(), "true".to_string());
         properties.insert(
             "max-chained-neighbor-updates".to_string(),
             "1000000".to_string(),
@@ -70,6 +83,7 @@ impl Default for ServerProperties {
         properties.insert("op-permission-level".to_string(), "4".to_string());
         properties.insert("pause-when-empty-seconds".to_string(), "60".to_string());
         properties.insert("player-idle-timeout".to_string(), "0".to_string());
+        properties.insert("player-sample-size".to_string(), "12".to_string());
         properties.insert("prevent-proxy-connections".to_string(), "false".to_string());
         properties.insert("pvp".to_string(), "true".to_string());
         properties.insert("query.port".to_string(), "25565".to_string());
@@ -92,12 +106,28 @@ impl Default for ServerProperties {
         properties.insert("text-filtering-version".to_string(), "0".to_string());
         properties.insert("use-native-transport".to_string(), "true".to_string());
         properties.insert("view-distance".to_string(), "10".to_string());
+        properties.insert("watchdog-action".to_string(), "crash".to_string());
         properties.insert("white-list".to_string(), "false".to_string());
 
         Self { properties }
     }
 }
 
+impl fmt::Debug for ServerProperties {
+    /// Lists the properties with secrets redacted
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut keys: Vec<_> = self.properties.keys().collect();
+        keys.sort();
+        f.debug_map()
+            .entries(keys.into_iter().map(|key| {
+                let value = &self.properties[key];
+                let redact = SECRET_PROPERTIES.contains(&key.as_str()) && !value.is_empty();
+                (key, if redact { REDACTED } else { value.as_str() })
+            }))
+            .finish()
+    }
+}
+
 impl ServerProperties {
     /// Create a new ServerProperties with default values
     pub fn new() -> Self {
@@ -132,7 +162,7 @@ impl ServerProperties {
             // Parse key-value pairs
             if let Some(equals_pos) = line.find('=') {
                 let key = line[..equals_pos].trim().to_string();
-                let value = line[equals_pos + 1..].trim().to_string();
+                let value = unescape_value(line[equals_pos + 1..].trim());
                 props.properties.insert(key, value);
             }
         }
@@ -295,29 +325,35 @@ impl ServerProperties {
     }
 
     /// Get the difficulty
-    pub fn difficulty(&self) -> &str {
-        self.get_string("difficulty")
-            .map(|s| s.as_str())
-            .unwrap_or("easy")
+    pub fn difficulty(&self) -> Difficulty {
+        self.get("difficulty").unwrap_or_default()
     }
 
     /// Set the difficulty
-    pub fn set_difficulty(&mut self, difficulty: &str) {
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
         self.set("difficulty", difficulty);
     }
 
-    /// Get the gamemode
-    pub fn gamemode(&self) -> &str {
-        self.get_string("gamemode")
-            .map(|s| s.as_str())
-            .unwrap_or("survival")
+    /// Get the game mode new players start in
+    pub fn gamemode(&self) -> GameMode {
+        self.get("gamemode").unwrap_or_default()
     }
 
-    /// Set the gamemode
-    pub fn set_gamemode(&mut self, gamemode: &str) {
+    /// Set the game mode new players start in
+    pub fn set_gamemode(&mut self, gamemode: GameMode) {
         self.set("gamemode", gamemode);
     }
 
+    /// Get the world preset
+    pub fn level_type(&self) -> LevelType {
+        self.get("level-type").unwrap_or_default()
+    }
+
+    /// Set the world preset
+    pub fn set_level_type(&mut self, level_type: LevelType) {
+        self.set("level-type", level_type);
+    }
+
     /// Get the view distance
     pub fn view_distance(&self) -> u8 {
         self.get("view-distance").unwrap_or(10)
@@ -381,6 +417,143 @@ impl ServerProperties {
         self.set("pvp", enabled);
     }
 
+    /// Get the permission level given to new operators
+    pub fn op_permission_level(&self) -> u8 {
+        self.get("op-permission-level").unwrap_or(4)
+    }
+
+    /// Set the permission level given to new operators
+    pub fn set_op_permission_level(&mut self, level: u8) {
+        self.set("op-permission-level", level);
+    }
+
+    /// Get whether the log is also written to `logs/latest.log`
+    pub fn log_to_file(&self) -> bool {
+        self.get_bool("log-to-file").unwrap_or(true)
+    }
+
+    /// Set whether the log is also written to a file
+    pub fn set_log_to_file(&mut self, enabled: bool) {
+        self.set("log-to-file", enabled);
+    }
+
+    /// Get the MiB the log file may grow to before it is rotated (0 rotates
+    /// daily only)
+    pub fn log_file_max_size(&self) -> u64 {
+        self.get("log-file-max-size").unwrap_or(10)
+    }
+
+    /// Set the MiB the log file may grow to before it is rotated
+    pub fn set_log_file_max_size(&mut self, mebibytes: u64) {
+        self.set("log-file-max-size", mebibytes);
+    }
+
+    /// Get the number of compressed logs kept (0 keeps all)
+    pub fn log_file_keep(&self) -> usize {
+        self.get("log-file-keep").unwrap_or(0)
+    }
+
+    /// Set the number of compressed logs kept
+    pub fn set_log_file_keep(&mut self, keep: usize) {
+        self.set("log-file-keep", keep);
+    }
+
+    /// Get whether player addresses are logged
+    pub fn log_ips(&self) -> bool {
+        self.get_bool("log-ips").unwrap_or(true)
+    }
+
+    /// Set whether player addresses are logged
+    pub fn set_log_ips(&mut self, enabled: bool) {
+        self.set("log-ips", enabled);
+    }
+
+    /// Get whether chunk writes are flushed to disk before continuing
+    pub fn sync_chunk_writes(&self) -> bool {
+        self.get_bool("sync-chunk-writes").unwrap_or(true)
+    }
+
+    /// Set whether chunk writes are synchronous
+    pub fn set_sync_chunk_writes(&mut self, enabled: bool) {
+        self.set("sync-chunk-writes", enabled);
+    }
+
+    /// Get the compression used for newly written chunks
+    pub fn region_file_compression(&self) -> RegionCompression {
+        self.get_string("region-file-compression")
+            .and_then(|name| RegionCompression::from_name(name))
+            .unwrap_or_default()
+    }
+
+    /// Set the compression used for newly written chunks
+    pub fn set_region_file_compression(&mut self, compression: RegionCompression) {
+        self.set("region-file-compression", compression.name());
+    }
+
+    /// Get the autosave interval in seconds (0 disables autosaving)
+    pub fn autosave_interval(&self) -> u64 {
+        self.get("autosave-interval").unwrap_or(300)
+    }
+
+    /// Set the autosave interval in seconds
+    pub fn set_autosave_interval(&mut self, seconds: u64) {
+        self.set("autosave-interval", seconds);
+    }
+
+    /// Get the maximum tick time in milliseconds (0 or less disables the watchdog)
+    pub fn max_tick_time(&self) -> i64 {
+        self.get("max-tick-time").unwrap_or(60000)
+    }
+
+    /// Set the maximum tick time in milliseconds
+    pub fn set_max_tick_time(&mut self, millis: i64) {
+        self.set("max-tick-time", millis);
+    }
+
+    /// Get what the watchdog does when a tick takes too long
+    pub fn watchdog_action(&self) -> &str {
+        self.get_string("watchdog-action")
+            .map(|s| s.as_str())
+            .unwrap_or("crash")
+    }
+
+    /// Set what the watchdog does when a tick takes too long
+    pub fn set_watchdog_action(&mut self, action: &str) {
+        self.set("watchdog-action", action);
+    }
+
+    /// Get whether the HTTP admin API is enabled
+    pub fn enable_admin_api(&self) -> bool {
+        self.get_bool("enable-admin-api").unwrap_or(false)
+    }
+
+    /// Set whether the HTTP admin API is enabled
+    pub fn set_enable_admin_api(&mut self, enabled: bool) {
+        self.set("enable-admin-api", enabled);
+    }
+
+    /// Get the port the HTTP admin API listens on
+    pub fn admin_api_port(&self) -> u16 {
+        self.get("admin-api.port").unwrap_or(25580)
+    }
+
+    /// Set the port the HTTP admin API listens on
+    pub fn set_admin_api_port(&mut self, port: u16) {
+        self.set("admin-api.port", port);
+    }
+
+    /// Get the bearer token required by the HTTP admin API
+    pub fn admin_api_token(&self) -> &str {
+        self.get_string("admin-api.token")
+            .map(|s| s.as_str())
+            .unwrap_or("")
+    }
+
+    /// Set the bearer token required by the HTTP admin API
+    pub fn set_admin_api_token(&mut self, token: &str) {
+        self.set("admin-api.token", token);
+    }
+
     /// Get whether the whitelist is enabled
     pub fn whitelist(&self) -> bool {
         self.get_bool("white-list").unwrap_or(false)
@@ -390,6 +563,129 @@ impl ServerProperties {
     pub fn set_whitelist(&mut self, enabled: bool) {
         self.set("white-list", enabled);
     }
+
+    /// Get the data packs enabled when a world is created
+    pub fn initial_enabled_packs(&self) -> Vec<String> {
+        split_pack_list(
+            self.get_string("initial-enabled-packs")
+                .map_or("vanilla", |s| s),
+        )
+    }
+
+    /// Set the data packs enabled when a world is created
+    pub fn set_initial_enabled_packs(&mut self, packs: &[String]) {
+        self.set("initial-enabled-packs", packs.join(","));
+    }
+
+    /// Get the data packs disabled when a world is created
+    pub fn initial_disabled_packs(&self) -> Vec<String> {
+        split_pack_list(self.get_string("initial-disabled-packs").map_or("", |s| s))
+    }
+
+    /// Set the data packs disabled when a world is created
+    pub fn set_initial_disabled_packs(&mut self, packs: &[String]) {
+        self.set("initial-disabled-packs", packs.join(","));
+    }
+
+    /// Get whether players can travel to the nether
+    pub fn allow_nether(&self) -> bool {
+        self.get_bool("allow-nether").unwrap_or(true)
+    }
+
+    /// Set whether players can travel to the nether
+    pub fn set_allow_nether(&mut self, allow: bool) {
+        self.set("allow-nether", allow);
+    }
+
+    /// Get whether online players are hidden from the server list
+    pub fn hide_online_players(&self) -> bool {
+        self.get_bool("hide-online-players").unwrap_or(false)
+    }
+
+    /// Set whether online players are hidden from the server list
+    pub fn set_hide_online_players(&mut self, hide: bool) {
+        self.set("hide-online-players", hide);
+    }
+
+    /// Get the number of online players listed in the server list
+    pub fn player_sample_size(&self) -> u32 {
+        self.get("player-sample-size").unwrap_or(12)
+    }
+
+    /// Set the number of online players listed in the server list
+    pub fn set_player_sample_size(&mut self, size: u32) {
+        self.set("player-sample-size", size);
+    }
+
+    /// Get the file listing text filter patterns (empty disables filtering)
+    pub fn text_filtering_config(&self) -> &str {
+        self.get_string("text-filtering-config").map_or("", |s| s)
+    }
+
+    /// Set the file listing text filter patterns (empty disables filtering)
+    pub fn set_text_filtering_config(&mut self, config: &str) {
+        self.set("text-filtering-config", config);
+    }
+
+    /// Get the URL of the resource pack offered to players (empty for none)
+    pub fn resource_pack(&self) -> &str {
+        self.get_string("resource-pack").map_or("", |s| s)
+    }
+
+    /// Set the URL of the resource pack offered to players
+    pub fn set_resource_pack(&mut self, url: &str) {
+        self.set("resource-pack", url);
+    }
+
+    /// Get the SHA-1 of the resource pack (empty to skip the check)
+    pub fn resource_pack_sha1(&self) -> &str {
+        self.get_string("resource-pack-sha1").map_or("", |s| s)
+    }
+
+    /// Set the SHA-1 of the resource pack
+    pub fn set_resource_pack_sha1(&mut self, sha1: &str) {
+        self.set("resource-pack-sha1", sha1);
+    }
+
+    /// Get the ID of the resource pack, if set
+    pub fn resource_pack_id(&self) -> Option<McUuid> {
+        self.get("resource-pack-id")
+    }
+
+    /// Set the ID of the resource pack
+    pub fn set_resource_pack_id(&mut self, id: McUuid) {
+        self.set("resource-pack-id", id);
+    }
+
+    /// Get the message shown when offering the resource pack (empty for none)
+    pub fn resource_pack_prompt(&self) -> &str {
+        self.get_string("resource-pack-prompt").map_or("", |s| s)
+    }
+
+    /// Set the message shown when offering the resource pack
+    pub fn set_resource_pack_prompt(&mut self, prompt: &str) {
+        self.set("resource-pack-prompt", prompt);
+    }
+
+    /// Get whether players who decline the resource pack are disconnected
+    pub fn require_resource_pack(&self) -> bool {
+        self.get_bool("require-resource-pack").unwrap_or(false)
+    }
+
+    /// Set whether players who decline the resource pack are disconnected
+    pub fn set_require_resource_pack(&mut self, required: bool) {
+        self.set("require-resource-pack", required);
+    }
+}
+
+/// Split a comma-separated list of data pack names
+fn split_pack_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 /// Escape special characters in property values
@@ -403,6 +699,36 @@ fn escape_value(value: &str) -> String {
         .replace('=', "\\=")
 }
 
+/// Undo [`escape_value`], also accepting the `\uXXXX` escapes vanilla writes
+fn unescape_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(decoded) => out.push(decoded),
+                    None => {
+                        out.push_str("\\u");
+                        out.push_str(&hex);
+                    }
+                }
+            }
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -414,8 +740,8 @@ mod tests {
         assert_eq!(props.max_players(), 20);
         assert_eq!(props.motd(), "A Minecraft Server");
         assert!(props.online_mode());
-        assert_eq!(props.difficulty(), "easy");
-        assert_eq!(props.gamemode(), "survival");
+        assert_eq!(props.difficulty(), Difficulty::Easy);
+        assert_eq!(props.gamemode(), GameMode::Survival);
     }
 
     #[test]
@@ -458,8 +784,8 @@ mod tests {
         assert_eq!(props.max_players(), 20);
         assert_eq!(props.motd(), "A Minecraft Server");
         assert!(props.online_mode());
-        assert_eq!(props.difficulty(), "easy");
-        assert_eq!(props.gamemode(), "survival");
+        assert_eq!(props.difficulty(), Difficulty::Easy);
+        assert_eq!(props.gamemode(), GameMode::Survival);
         assert_eq!(props.view_distance(), 10);
         assert_eq!(props.simulation_distance(), 10);
         assert!(props.pvp());
@@ -490,8 +816,15 @@ mod tests {
         props.set_motd("Test Server");
         assert_eq!(props.motd(), "Test Server");
 
-        props.set_difficulty("hard");
-        assert_eq!(props.difficulty(), "hard");
+        props.set_difficulty(Difficulty::Hard);
+        assert_eq!(props.difficulty(), Difficulty::Hard);
+
+        props.set_gamemode(GameMode::Creative);
+        assert_eq!(props.gamemode(), GameMode::Creative);
+
+        props.set_level_type(LevelType::Flat);
+        assert_eq!(props.get_string("level-type").unwrap(), "minecraft:flat");
+        assert_eq!(props.level_type(), LevelType::Flat);
 
         props.set_level_name("testworld");
         assert_eq!(props.level_name(), "testworld");
@@ -557,4 +890,13 @@ mod tests {
         assert_eq!(escape_value("test\\value"), "test\\\\value");
         assert_eq!(escape_value("test\nvalue"), "test\\nvalue");
     }
+
+    #[test]
+    fn test_unescape_value() {
+        for value in ["normal", "a:b=c", "back\\slash", "two\nlines\ttab"] {
+            assert_eq!(unescape_value(&escape_value(value)), value);
+        }
+        assert_eq!(unescape_value("\\u00A7aGreen"), "\u{a7}aGreen");
+        assert_eq!(unescape_value("\\uZZ"), "\\uZZ");
+    }
 }
@@ -0,0 +1,261 @@
+//! Runtime configuration reloading
+//!
+//! Most settings are read once at startup. The ones in [`RuntimeSettings`]
+//! can be changed by editing server.properties while the server runs;
+//! [`ReloadReport`] tells the operator which edits took effect and which
+//! still need a restart.
+
+use crate::config::ServerConfig;
+use crate::protocol::types::JsonTextComponent;
+use crate::server::resource_pack::ResourcePack;
+
+/// Settings that can change without restarting the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeSettings {
+    /// Server description (MOTD)
+    pub motd: String,
+    /// Maximum number of concurrent players
+    pub max_players: u32,
+    /// View distance in chunks
+    pub view_distance: u8,
+    /// Simulation distance in chunks
+    pub simulation_distance: u8,
+    /// Whether only whitelisted players may join
+    pub whitelist: bool,
+    /// Whether the online player sample is omitted from the server list
+    pub hide_online_players: bool,
+    /// Number of online players listed in the server list
+    pub player_sample_size: u32,
+    /// Resource pack offered to players during configuration, if any
+    pub resource_pack: Option<ResourcePack>,
+    /// Whether players must accept the resource pack
+    pub require_resource_pack: bool,
+    /// Message shown with the resource pack prompt
+    pub resource_pack_prompt: Option<JsonTextComponent>,
+}
+
+impl RuntimeSettings {
+    /// Take the changeable settings from a configuration
+    pub fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            motd: config.motd.clone(),
+            max_players: config.max_players,
+            view_distance: config.view_distance,
+            simulation_distance: config.simulation_distance,
+            whitelist: config.whitelist,
+            hide_online_players: config.hide_online_players,
+            player_sample_size: config.player_sample_size,
+            resource_pack: config.resource_pack.clone(),
+            require_resource_pack: config.require_resource_pack,
+            resource_pack_prompt: config.resource_pack_prompt.clone(),
+        }
+    }
+
+    /// Whether players must be offered the resource pack again to switch
+    /// from these settings to `other`
+    pub fn resource_pack_changed(&self, other: &RuntimeSettings) -> bool {
+        self.resource_pack != other.resource_pack
+            || self.require_resource_pack != other.require_resource_pack
+            || self.resource_pack_prompt != other.resource_pack_prompt
+    }
+}
+
+/// What reloading server.properties changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Properties whose new values were applied
+    pub applied: Vec<&'static str>,
+    /// Properties that differ from the running configuration but only take
+    /// effect after a restart
+    pub requires_restart: Vec<&'static str>,
+}
+
+impl ReloadReport {
+    /// Compare reloaded settings with the ones in use
+    ///
+    /// `current` are the settings currently applied and `startup` is the
+    /// configuration the server was started with.
+    pub fn compare(
+        current: &RuntimeSettings,
+        startup: &ServerConfig,
+        reloaded: &ServerConfig,
+    ) -> Self {
+        let mut report = Self::default();
+
+        let applied = [
+            ("motd", current.motd != reloaded.motd),
+            ("max-players", current.max_players != reloaded.max_players),
+            (
+                "view-distance",
+                current.view_distance != reloaded.view_distance,
+            ),
+            (
+                "simulation-distance",
+                current.simulation_distance != reloaded.simulation_distance,
+            ),
+            ("white-list", current.whitelist != reloaded.whitelist),
+            (
+                "hide-online-players",
+                current.hide_online_players != reloaded.hide_online_players,
+            ),
+            (
+                "player-sample-size",
+                current.player_sample_size != reloaded.player_sample_size,
+            ),
+            // A hosted pack is only replaced by restarting
+            (
+                "resource-pack",
+                startup.resource_pack_host.is_none()
+                    && current.resource_pack != reloaded.resource_pack,
+            ),
+            (
+                "require-resource-pack",
+                current.require_resource_pack != reloaded.require_resource_pack,
+            ),
+            (
+                "resource-pack-prompt",
+                current.resource_pack_prompt != reloaded.resource_pack_prompt,
+            ),
+        ];
+        report.applied = changed(&applied);
+        report.requires_restart = requires_restart(startup, reloaded);
+
+        report
+    }
+
+    /// Check whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty() && self.requires_restart.is_empty()
+    }
+}
+
+/// Names of the properties that changed but only take effect after a restart
+fn requires_restart(startup: &ServerConfig, reloaded: &ServerConfig) -> Vec<&'static str> {
+    let (api_port, api_token) = match (&startup.admin_api, &reloaded.admin_api) {
+        (Some(a), Some(b)) => (a.bind_address != b.bind_address, a.token != b.token),
+        _ => (false, false),
+    };
+    let requires_restart = [
+        (
+            "server-ip",
+            startup.bind_address.ip() != reloaded.bind_address.ip(),
+        ),
+        (
+            "server-port",
+            startup.bind_address.port() != reloaded.bind_address.port(),
+        ),
+        ("online-mode", startup.online_mode != reloaded.online_mode),
+        (
+            "network-compression-threshold",
+            startup.compression_threshold != reloaded.compression_threshold,
+        ),
+        ("level-name", startup.level_name != reloaded.level_name),
+        ("level-type", startup.level_type != reloaded.level_type),
+        ("gamemode", startup.game_mode != reloaded.game_mode),
+        (
+            "sync-chunk-writes",
+            startup.sync_chunk_writes != reloaded.sync_chunk_writes,
+        ),
+        (
+            "autosave-interval",
+            startup.autosave_interval != reloaded.autosave_interval,
+        ),
+        (
+            "max-tick-time",
+            startup.max_tick_time != reloaded.max_tick_time,
+        ),
+        (
+            "watchdog-action",
+            startup.watchdog_action != reloaded.watchdog_action,
+        ),
+        (
+            "enable-admin-api",
+            startup.admin_api.is_some() != reloaded.admin_api.is_some(),
+        ),
+        ("admin-api.port", api_port),
+        ("admin-api.token", api_token),
+        (
+            "op-permission-level",
+            startup.op_permission_level != reloaded.op_permission_level,
+        ),
+        ("log-ips", startup.log_ips != reloaded.log_ips),
+        ("log-to-file", startup.log_file != reloaded.log_file),
+        ("audit", startup.audit != reloaded.audit),
+        ("favicon", startup.favicon != reloaded.favicon),
+        ("language", startup.language != reloaded.language),
+        ("tcp-nodelay", startup.tcp_nodelay != reloaded.tcp_nodelay),
+        (
+            "login-throttle",
+            startup.login_throttle != reloaded.login_throttle,
+        ),
+        (
+            "login-timeout",
+            startup.login_timeout != reloaded.login_timeout,
+        ),
+        ("profile-api", startup.profile_api != reloaded.profile_api),
+        (
+            "rate-limits",
+            startup.packet_limits != reloaded.packet_limits,
+        ),
+        ("chat", startup.chat_limits != reloaded.chat_limits),
+        ("messages", startup.join_messages != reloaded.join_messages),
+        ("chunks", startup.chunk_budgets != reloaded.chunk_budgets),
+        (
+            "status",
+            startup.advertised_version != reloaded.advertised_version,
+        ),
+        (
+            "plugins",
+            startup.plugins_enabled != reloaded.plugins_enabled,
+        ),
+        ("hot-reload", startup.hot_reload != reloaded.hot_reload),
+        (
+            "resource-pack",
+            startup.resource_pack_host != reloaded.resource_pack_host,
+        ),
+    ];
+    changed(&requires_restart)
+}
+
+/// Names of the properties that changed
+fn changed(properties: &[(&'static str, bool)]) -> Vec<&'static str> {
+    properties
+        .iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let startup = ServerConfig::default();
+        let current = RuntimeSettings::from_config(&startup);
+        assert!(ReloadReport::compare(&current, &startup, &startup).is_empty());
+
+        let reloaded = startup
+            .clone()
+            .with_motd("Reloaded".to_string())
+            .with_view_distance(6)
+            .with_online_mode(false);
+        let report = ReloadReport::compare(&current, &startup, &reloaded);
+        assert_eq!(report.applied, ["motd", "view-distance"]);
+        assert_eq!(report.requires_restart, ["online-mode"]);
+
+        // Settings already applied by an earlier reload are not reported again
+        let current = RuntimeSettings::from_config(&reloaded);
+        let report = ReloadReport::compare(&current, &startup, &reloaded);
+        assert!(report.applied.is_empty());
+        assert_eq!(report.requires_restart, ["online-mode"]);
+
+        // A new resource pack is offered without a restart
+        let pack = ResourcePack::new("http://example.com/pack.zip", "");
+        let reloaded = reloaded.with_resource_pack(Some(pack), true);
+        let report = ReloadReport::compare(&current, &startup, &reloaded);
+        assert_eq!(report.applied, ["resource-pack", "require-resource-pack"]);
+        assert!(current.resource_pack_changed(&RuntimeSettings::from_config(&reloaded)));
+    }
+}
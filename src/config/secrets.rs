@@ -0,0 +1,123 @@
+//! Secrets
+//!
+//! Credentials are kept out of server.properties where possible:
+//!
+//! - The Velocity forwarding secret is read from `forwarding.secret` next to
+//!   server.properties, falling back to `OBSIDIUM_FORWARDING_SECRET`.
+//! - `rcon.password` and `admin-api.token` may be left empty in
+//!   server.properties and given through `OBSIDIUM_RCON_PASSWORD` and
+//!   `OBSIDIUM_ADMIN_API_TOKEN` instead.
+//!
+//! Secret values are wrapped in [`Secret`], which never prints its value,
+//! and are never written when a configuration is saved.
+
+use crate::error::Result;
+use std::fmt;
+use std::path::Path;
+
+/// File the Velocity forwarding secret is read from
+pub const FORWARDING_SECRET_FILE: &str = "forwarding.secret";
+
+/// Environment variable holding the forwarding secret
+pub const FORWARDING_SECRET_ENV: &str = "OBSIDIUM_FORWARDING_SECRET";
+
+/// Environment variable holding the RCON password
+pub const RCON_PASSWORD_ENV: &str = "OBSIDIUM_RCON_PASSWORD";
+
+/// Environment variable holding the admin API token
+pub const ADMIN_API_TOKEN_ENV: &str = "OBSIDIUM_ADMIN_API_TOKEN";
+
+/// Properties holding credentials
+pub const SECRET_PROPERTIES: &[&str] = &["admin-api.token", "rcon.password"];
+
+/// Placeholder printed instead of a secret
+pub const REDACTED: &str = "<redacted>";
+
+/// A credential that is never printed
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wrap a credential, returning `None` if it is empty
+    pub fn new(value: impl Into<String>) -> Option<Self> {
+        let value = value.into();
+        (!value.is_empty()).then_some(Self(value))
+    }
+
+    /// Read a secret from an environment variable
+    pub fn from_env(var: &str) -> Option<Self> {
+        std::env::var(var).ok().and_then(Self::new)
+    }
+
+    /// Read a secret from a file, ignoring surrounding whitespace
+    ///
+    /// Returns `None` if the file does not exist or is empty.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Ok(Self::new(contents.trim())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The credential itself
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Load the forwarding secret for a server.properties file
+///
+/// `forwarding.secret` in the same directory takes precedence over the
+/// environment variable.
+pub fn load_forwarding_secret<P: AsRef<Path>>(properties_path: P) -> Result<Option<Secret>> {
+    let path = properties_path
+        .as_ref()
+        .with_file_name(FORWARDING_SECRET_FILE);
+    Ok(Secret::from_file(path)?.or_else(|| Secret::from_env(FORWARDING_SECRET_ENV)))
+}
+
+/// Use a property's value, or an environment variable if it is empty
+pub fn property_or_env(value: &str, var: &str) -> Option<Secret> {
+    Secret::new(value).or_else(|| Secret::from_env(var))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let dir = std::env::temp_dir().join(format!("obsidium-secrets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let properties = dir.join("server.properties");
+        assert_eq!(load_forwarding_secret(&properties).unwrap(), None);
+
+        std::fs::write(dir.join(FORWARDING_SECRET_FILE), "hunter2\n").unwrap();
+        let secret = load_forwarding_secret(&properties).unwrap().unwrap();
+        assert_eq!(secret.expose(), "hunter2");
+        assert_eq!(
+            format!("{} {:?}", secret, Some(&secret)),
+            "<redacted> Some(<redacted>)"
+        );
+        assert_eq!(Secret::new(""), None);
+
+        let mut props = crate::config::ServerProperties::new();
+        props.set("rcon.password", "hunter2");
+        assert!(!format!("{:?}", props).contains("hunter2"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -4,11 +4,31 @@
 //! provides sensible defaults for all server settings.
 
 use std::net::SocketAddr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use crate::admin::AdminApiConfig;
+use crate::config::args::CliArgs;
+use crate::config::overrides::ConfigOverrides;
 use crate::config::properties::ServerProperties;
+use crate::config::secrets::{self, ADMIN_API_TOKEN_ENV, RCON_PASSWORD_ENV, Secret};
 use crate::error::ServerError;
+use crate::game::chat::ChatLimits;
+use crate::game::join_messages::JoinMessages;
+use crate::game::player::GameMode;
+use crate::game::world::anvil::RegionCompression;
+use crate::game::world::budget::ChunkBudgets;
+use crate::game::{Difficulty, LevelType};
+use crate::logger::file::LogFileConfig;
+use crate::network::{ForwardingConfig, PacketLimits};
+use crate::protocol::types::JsonTextComponent;
+use crate::server::audit::AuditConfig;
+use crate::server::backup::BackupConfig;
+use crate::server::profiles::MOJANG_API;
+use crate::server::resource_pack::{ResourcePack, ResourcePackHostConfig};
+use crate::server::usercache::USERCACHE_FILE;
+use crate::server::version::AdvertisedVersion;
+use crate::server::watchdog::WatchdogAction;
 
 /// Main server configuration
 #[derive(Debug, Clone)]
@@ -31,6 +51,34 @@ pub struct ServerConfig {
     /// Connection timeout
     pub connection_timeout: Duration,
 
+    /// Shortest time between login attempts from one address (zero disables throttling)
+    pub login_throttle: Duration,
+
+    /// Longest a connection may take to log in (zero disables the limit)
+    pub login_timeout: Duration,
+
+    /// Where the Mojang API is reached, if anywhere: `mojang`, or the HTTP
+    /// or HTTPS base URL of a proxy (see [`ServerConfig::profile_api`])
+    pub profile_api: Option<String>,
+
+    /// Proxies trusted to forward client addresses, and whether they must
+    pub forwarding: ForwardingConfig,
+
+    /// Packets a player may send per second or tick
+    pub packet_limits: PacketLimits,
+
+    /// Chat messages a player may send, and when they are kicked for spam
+    pub chat_limits: ChatLimits,
+
+    /// Templates of the join and quit messages
+    pub join_messages: JoinMessages,
+
+    /// Limits on chunks loaded per tick, queued per player and kept loaded
+    pub chunk_budgets: ChunkBudgets,
+
+    /// Version shown in the server list and the protocols allowed to log in
+    pub advertised_version: AdvertisedVersion,
+
     /// View distance in chunks
     pub view_distance: u8,
     /// Simulation distance in chunks  
@@ -38,6 +86,122 @@ pub struct ServerConfig {
 
     /// Server favicon (path to 64x64 PNG file or base64 data URL)
     pub favicon: Option<String>,
+
+    /// World difficulty
+    pub difficulty: Difficulty,
+
+    /// Game mode new players start in
+    pub game_mode: GameMode,
+
+    /// World preset
+    pub level_type: LevelType,
+
+    /// Path of the server.properties file runtime changes are written back to
+    pub properties_path: Option<PathBuf>,
+
+    /// Path of usercache.json (`None` keeps it next to server.properties)
+    pub usercache_path: Option<PathBuf>,
+
+    /// Name of the world directory
+    pub level_name: String,
+
+    /// Flush chunk writes to disk before continuing
+    pub sync_chunk_writes: bool,
+
+    /// Let players travel to the nether through portals
+    pub allow_nether: bool,
+
+    /// Compression used for newly written chunks
+    pub region_compression: RegionCompression,
+
+    /// Interval between automatic saves (zero disables autosaving)
+    pub autosave_interval: Duration,
+
+    /// World backup settings
+    pub backups: BackupConfig,
+
+    /// Longest a single tick may run before the watchdog steps in (`None` disables it)
+    pub max_tick_time: Option<Duration>,
+
+    /// What the watchdog does when a tick exceeds the maximum tick time
+    pub watchdog_action: WatchdogAction,
+
+    /// HTTP admin API settings (`None` disables the API)
+    pub admin_api: Option<AdminApiConfig>,
+
+    /// Permission level given to players made operators with /op
+    pub op_permission_level: u8,
+
+    /// Show player addresses in logs and the audit log
+    pub log_ips: bool,
+
+    /// Log file settings
+    pub log_file: LogFileConfig,
+
+    /// Audit log settings
+    pub audit: AuditConfig,
+
+    /// Only allow players on the whitelist to join
+    pub whitelist: bool,
+
+    /// Omit the online player sample from the server list
+    pub hide_online_players: bool,
+
+    /// Number of online players listed in the server list
+    pub player_sample_size: u32,
+
+    /// Disable Nagle's algorithm on player connections
+    pub tcp_nodelay: bool,
+
+    /// Load plugins from the plugins directory
+    pub plugins_enabled: bool,
+
+    /// Apply edits to the configuration files while running
+    pub hot_reload: bool,
+
+    /// Path of the obsidium.toml file layered over server.properties
+    pub overrides_path: Option<PathBuf>,
+
+    /// Command-line arguments layered over the configuration files
+    pub cli_args: CliArgs,
+
+    /// RCON password
+    pub rcon_password: Option<Secret>,
+
+    /// Secret shared with a Velocity proxy for modern player info
+    /// forwarding; players are who the proxy says instead of being
+    /// authenticated when set
+    pub forwarding_secret: Option<Secret>,
+
+    /// Directory every connection's packets are recorded to, if any
+    pub packet_dump_dir: Option<PathBuf>,
+
+    /// Radius in chunks around spawn to generate before accepting players
+    pub pregen_radius: Option<u32>,
+
+    /// Data packs enabled when the world is created
+    pub initial_enabled_packs: Vec<String>,
+
+    /// Data packs disabled when the world is created
+    pub initial_disabled_packs: Vec<String>,
+
+    /// Language the console and logs show translated messages in
+    pub language: String,
+
+    /// Resource pack offered to players, from server.properties
+    pub resource_pack: Option<ResourcePack>,
+
+    /// Disconnect players who decline the resource pack
+    pub require_resource_pack: bool,
+
+    /// Message shown when offering the resource pack
+    pub resource_pack_prompt: Option<JsonTextComponent>,
+
+    /// Built-in resource pack host settings (`None` disables the host)
+    pub resource_pack_host: Option<ResourcePackHostConfig>,
+
+    /// File listing text filter patterns (empty disables filtering)
+    pub text_filtering_config: String,
 }
 
 impl Default for ServerConfig {
@@ -50,9 +214,56 @@ impl Default for ServerConfig {
             online_mode: true,
             compression_threshold: Some(256),
             connection_timeout: Duration::from_secs(30),
+            login_throttle: Duration::from_secs(4),
+            login_timeout: Duration::from_secs(30),
+            profile_api: None,
+            forwarding: ForwardingConfig::default(),
+            packet_limits: PacketLimits::default(),
+            chat_limits: ChatLimits::default(),
+            join_messages: JoinMessages::default(),
+            chunk_budgets: ChunkBudgets::default(),
+            advertised_version: AdvertisedVersion::default(),
             view_distance: 12,
             simulation_distance: 12,
             favicon: None,
+            difficulty: Difficulty::Easy,
+            game_mode: GameMode::Survival,
+            level_type: LevelType::Normal,
+            properties_path: None,
+            usercache_path: None,
+            level_name: "world".to_string(),
+            sync_chunk_writes: true,
+            allow_nether: true,
+            region_compression: RegionCompression::default(),
+            autosave_interval: Duration::from_secs(300),
+            backups: BackupConfig::default(),
+            max_tick_time: Some(Duration::from_secs(60)),
+            watchdog_action: WatchdogAction::Crash,
+            admin_api: None,
+            op_permission_level: 4,
+            log_ips: true,
+            log_file: LogFileConfig::default(),
+            audit: AuditConfig::default(),
+            whitelist: false,
+            hide_online_players: false,
+            player_sample_size: 12,
+            tcp_nodelay: true,
+            plugins_enabled: true,
+            hot_reload: true,
+            overrides_path: None,
+            cli_args: CliArgs::default(),
+            rcon_password: None,
+            forwarding_secret: None,
+            packet_dump_dir: None,
+            pregen_radius: None,
+            initial_enabled_packs: vec!["vanilla".to_string()],
+            initial_disabled_packs: Vec::new(),
+            language: crate::lang::DEFAULT_LANGUAGE.to_string(),
+            resource_pack: None,
+            require_resource_pack: false,
+            resource_pack_prompt: None,
+            resource_pack_host: None,
+            text_filtering_config: String::new(),
         }
     }
 }
@@ -64,19 +275,47 @@ impl ServerConfig {
     }
 
     /// Load configuration from server.properties file
+    ///
+    /// The forwarding secret is read from `forwarding.secret` next to it.
     pub fn from_properties_file<P: AsRef<Path>>(path: P) -> Result<Self, ServerError> {
-        let props = ServerProperties::load_from_file(path)?;
-        Self::from_properties(props)
+        let props = ServerProperties::load_from_file(&path)?;
+        Self::from_properties_at(props, path.as_ref())
     }
 
     /// Load configuration from server.properties file, using defaults if file doesn't exist
     pub fn from_properties_file_or_default<P: AsRef<Path>>(path: P) -> Result<Self, ServerError> {
-        let props = ServerProperties::load_from_file_or_default(path)?;
-        Self::from_properties(props)
+        let props = ServerProperties::load_from_file_or_default(&path)?;
+        Self::from_properties_at(props, path.as_ref())
+    }
+
+    /// Create configuration from properties loaded from `path`
+    fn from_properties_at(props: ServerProperties, path: &Path) -> Result<Self, ServerError> {
+        Ok(Self::from_properties(props)?
+            .with_properties_path(Some(path.to_path_buf()))
+            .with_forwarding_secret(secrets::load_forwarding_secret(path)?))
+    }
+
+    /// Apply overrides from an obsidium.toml file, if it exists
+    pub fn with_overrides_file<P: AsRef<Path>>(self, path: P) -> Result<Self, ServerError> {
+        let overrides = ConfigOverrides::load_file_or_default(&path)?;
+        Ok(overrides
+            .apply(self)
+            .with_overrides_path(Some(path.as_ref().to_path_buf())))
+    }
+
+    /// Apply command-line arguments, keeping them for configuration reloads
+    pub fn with_cli_args(self, args: CliArgs) -> Self {
+        let mut config = args.apply(self);
+        config.cli_args = args;
+        config
     }
 
     /// Create configuration from ServerProperties
+    ///
+    /// Fails with every invalid property if any value cannot be used.
     pub fn from_properties(props: ServerProperties) -> Result<Self, ServerError> {
+        props.validate()?;
+
         let server_ip = props.server_ip().unwrap_or(&String::new()).clone();
         let server_port = props.server_port();
 
@@ -90,6 +329,32 @@ impl ServerConfig {
             .parse()
             .map_err(|e| ServerError::Protocol(format!("Invalid bind address: {}", e)))?;
 
+        let admin_api = props
+            .enable_admin_api()
+            .then(|| secrets::property_or_env(props.admin_api_token(), ADMIN_API_TOKEN_ENV))
+            .flatten()
+            .map(|token| AdminApiConfig {
+                bind_address: SocketAddr::new(bind_address.ip(), props.admin_api_port()),
+                token,
+            });
+        let rcon_password = props
+            .get_string("rcon.password")
+            .and_then(|password| secrets::property_or_env(password, RCON_PASSWORD_ENV));
+
+        let resource_pack = Some(props.resource_pack())
+            .filter(|url| !url.is_empty())
+            .map(|url| {
+                let pack = ResourcePack::new(url, props.resource_pack_sha1());
+                match props.resource_pack_id() {
+                    Some(id) => pack.with_id(id),
+                    None => pack,
+                }
+            });
+        // The prompt is a JSON text component, but plain text works too
+        let resource_pack_prompt = Some(props.resource_pack_prompt())
+            .filter(|prompt| !prompt.is_empty())
+            .map(|prompt| JsonTextComponent(prompt.to_string()));
+
         let compression_threshold = match props.network_compression_threshold() {
             -1 => None,
             n if n >= 0 => Some(n as u32),
@@ -106,10 +371,47 @@ impl ServerConfig {
             view_distance: props.view_distance(),
             simulation_distance: props.simulation_distance(),
             favicon: None,
+            difficulty: props.difficulty(),
+            game_mode: props.gamemode(),
+            level_type: props.level_type(),
+            properties_path: None,
+            usercache_path: None,
+            level_name: props.level_name().to_string(),
+            sync_chunk_writes: props.sync_chunk_writes(),
+            allow_nether: props.allow_nether(),
+            region_compression: props.region_file_compression(),
+            autosave_interval: Duration::from_secs(props.autosave_interval()),
+            max_tick_time: u64::try_from(props.max_tick_time())
+                .ok()
+                .filter(|&millis| millis > 0)
+                .map(Duration::from_millis),
+            watchdog_action: WatchdogAction::from_name(props.watchdog_action()).unwrap_or_default(),
+            admin_api,
+            op_permission_level: props.op_permission_level(),
+            log_ips: props.log_ips(),
+            log_file: LogFileConfig {
+                enabled: props.log_to_file(),
+                max_size: props.log_file_max_size().saturating_mul(1024 * 1024),
+                keep: props.log_file_keep(),
+                ..LogFileConfig::default()
+            },
+            whitelist: props.whitelist(),
+            hide_online_players: props.hide_online_players(),
+            player_sample_size: props.player_sample_size(),
+            rcon_password,
+            initial_enabled_packs: props.initial_enabled_packs(),
+            initial_disabled_packs: props.initial_disabled_packs(),
+            resource_pack,
+            require_resource_pack: props.require_resource_pack(),
+            resource_pack_prompt,
+            text_filtering_config: props.text_filtering_config().to_string(),
+            ..Self::default()
         })
     }
 
     /// Convert to ServerProperties
+    ///
+    /// Secrets are left out, so the result is safe to save or show.
     pub fn to_properties(&self) -> ServerProperties {
         let mut props = ServerProperties::new();
 
@@ -121,6 +423,40 @@ impl ServerConfig {
         props.set_online_mode(self.online_mode);
         props.set_view_distance(self.view_distance);
         props.set_simulation_distance(self.simulation_distance);
+        props.set_difficulty(self.difficulty);
+        props.set_gamemode(self.game_mode);
+        props.set_level_type(self.level_type);
+        props.set_level_name(&self.level_name);
+        props.set_sync_chunk_writes(self.sync_chunk_writes);
+        props.set_allow_nether(self.allow_nether);
+        props.set_region_file_compression(self.region_compression);
+        props.set_autosave_interval(self.autosave_interval.as_secs());
+        props.set_max_tick_time(self.max_tick_time.map_or(-1, |t| t.as_millis() as i64));
+        props.set_watchdog_action(self.watchdog_action.name());
+        props.set_enable_admin_api(self.admin_api.is_some());
+        if let Some(admin_api) = &self.admin_api {
+            props.set_admin_api_port(admin_api.bind_address.port());
+        }
+        props.set_op_permission_level(self.op_permission_level);
+        props.set_log_ips(self.log_ips);
+        props.set_log_to_file(self.log_file.enabled);
+        props.set_log_file_max_size(self.log_file.max_size / (1024 * 1024));
+        props.set_log_file_keep(self.log_file.keep);
+        props.set_whitelist(self.whitelist);
+        props.set_hide_online_players(self.hide_online_players);
+        props.set_player_sample_size(self.player_sample_size);
+        props.set_initial_enabled_packs(&self.initial_enabled_packs);
+        props.set_initial_disabled_packs(&self.initial_disabled_packs);
+        if let Some(pack) = &self.resource_pack {
+            props.set_resource_pack(&pack.url);
+            props.set_resource_pack_sha1(&pack.hash);
+            props.set_resource_pack_id(pack.id);
+        }
+        props.set_require_resource_pack(self.require_resource_pack);
+        if let Some(prompt) = &self.resource_pack_prompt {
+            props.set_resource_pack_prompt(&prompt.0);
+        }
+        props.set_text_filtering_config(&self.text_filtering_config);
 
         if let Some(threshold) = self.compression_threshold {
             props.set_network_compression_threshold(threshold as i32);
@@ -183,4 +519,294 @@ impl ServerConfig {
         self.simulation_distance = distance;
         self
     }
+
+    /// Set difficulty
+    pub fn with_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    /// Set the game mode new players start in
+    pub fn with_game_mode(mut self, game_mode: GameMode) -> Self {
+        self.game_mode = game_mode;
+        self
+    }
+
+    /// Set the world preset
+    pub fn with_level_type(mut self, level_type: LevelType) -> Self {
+        self.level_type = level_type;
+        self
+    }
+
+    /// Set the server.properties file that runtime changes are written back to
+    pub fn with_properties_path(mut self, path: Option<PathBuf>) -> Self {
+        self.properties_path = path;
+        self
+    }
+
+    /// Set the path of usercache.json
+    pub fn with_usercache_path(mut self, path: Option<PathBuf>) -> Self {
+        self.usercache_path = path;
+        self
+    }
+
+    /// Path of usercache.json, next to server.properties unless set
+    pub fn usercache_file(&self) -> PathBuf {
+        match self.usercache_path {
+            Some(ref path) => path.clone(),
+            None => self.data_file(USERCACHE_FILE),
+        }
+    }
+
+    /// Path of a file kept next to server.properties, such as the ban
+    /// lists, or in the working directory without one
+    pub fn data_file(&self, name: &str) -> PathBuf {
+        match self.properties_path {
+            Some(ref properties) => properties.with_file_name(name),
+            None => PathBuf::from(name),
+        }
+    }
+
+    /// Set the world directory name
+    pub fn with_level_name(mut self, name: String) -> Self {
+        self.level_name = name;
+        self
+    }
+
+    /// Set whether chunk writes are synchronous
+    pub fn with_sync_chunk_writes(mut self, enabled: bool) -> Self {
+        self.sync_chunk_writes = enabled;
+        self
+    }
+
+    /// Set the compression used for newly written chunks
+    pub fn with_region_compression(mut self, compression: RegionCompression) -> Self {
+        self.region_compression = compression;
+        self
+    }
+
+    /// Set the autosave interval (zero disables autosaving)
+    pub fn with_autosave_interval(mut self, interval: Duration) -> Self {
+        self.autosave_interval = interval;
+        self
+    }
+
+    /// Set the world backup settings
+    pub fn with_backups(mut self, backups: BackupConfig) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    /// Set the maximum tick time (`None` disables the watchdog)
+    pub fn with_max_tick_time(mut self, max_tick_time: Option<Duration>) -> Self {
+        self.max_tick_time = max_tick_time;
+        self
+    }
+
+    /// Set what the watchdog does when a tick takes too long
+    pub fn with_watchdog_action(mut self, action: WatchdogAction) -> Self {
+        self.watchdog_action = action;
+        self
+    }
+
+    /// Set the HTTP admin API settings (`None` disables the API)
+    pub fn with_admin_api(mut self, admin_api: Option<AdminApiConfig>) -> Self {
+        self.admin_api = admin_api;
+        self
+    }
+
+    /// Set whether only whitelisted players may join
+    pub fn with_whitelist(mut self, enabled: bool) -> Self {
+        self.whitelist = enabled;
+        self
+    }
+
+    /// Set whether players can travel to the nether
+    pub fn with_allow_nether(mut self, allow: bool) -> Self {
+        self.allow_nether = allow;
+        self
+    }
+
+    /// Set whether online players are hidden from the server list
+    pub fn with_hide_online_players(mut self, hide: bool) -> Self {
+        self.hide_online_players = hide;
+        self
+    }
+
+    /// Set the number of online players listed in the server list
+    pub fn with_player_sample_size(mut self, size: u32) -> Self {
+        self.player_sample_size = size;
+        self
+    }
+
+    /// Set whether Nagle's algorithm is disabled on player connections
+    pub fn with_tcp_nodelay(mut self, nodelay: bool) -> Self {
+        self.tcp_nodelay = nodelay;
+        self
+    }
+
+    /// Set whether plugins are loaded
+    pub fn with_plugins_enabled(mut self, enabled: bool) -> Self {
+        self.plugins_enabled = enabled;
+        self
+    }
+
+    /// Set whether configuration edits are applied while running
+    pub fn with_hot_reload(mut self, enabled: bool) -> Self {
+        self.hot_reload = enabled;
+        self
+    }
+
+    /// Set the path of the obsidium.toml file
+    pub fn with_overrides_path(mut self, path: Option<PathBuf>) -> Self {
+        self.overrides_path = path;
+        self
+    }
+
+    /// Set the RCON password
+    pub fn with_rcon_password(mut self, password: Option<Secret>) -> Self {
+        self.rcon_password = password;
+        self
+    }
+
+    /// Set the Velocity forwarding secret
+    pub fn with_forwarding_secret(mut self, secret: Option<Secret>) -> Self {
+        self.forwarding_secret = secret;
+        self
+    }
+
+    /// Record every connection's packets to dump files in a directory
+    pub fn with_packet_dump_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.packet_dump_dir = dir;
+        self
+    }
+
+    /// Generate the terrain within a radius in chunks around spawn before
+    /// accepting players
+    pub fn with_pregen_radius(mut self, radius: Option<u32>) -> Self {
+        self.pregen_radius = radius;
+        self
+    }
+
+    /// Set the data packs enabled and disabled when the world is created
+    pub fn with_initial_packs(mut self, enabled: Vec<String>, disabled: Vec<String>) -> Self {
+        self.initial_enabled_packs = enabled;
+        self.initial_disabled_packs = disabled;
+        self
+    }
+
+    /// Set the shortest time between login attempts from one address
+    pub fn with_login_throttle(mut self, throttle: Duration) -> Self {
+        self.login_throttle = throttle;
+        self
+    }
+
+    /// Set how long a connection may take to log in
+    pub fn with_login_timeout(mut self, timeout: Duration) -> Self {
+        self.login_timeout = timeout;
+        self
+    }
+
+    /// Set the base URL the Mojang API is reached through
+    pub fn with_profile_api(mut self, url: Option<String>) -> Self {
+        self.profile_api = url;
+        self
+    }
+
+    /// Where the Mojang API is reached: the configured `profile-api`, or
+    /// Mojang's servers in online mode, which cannot log anyone in without
+    /// the session server
+    pub fn profile_api(&self) -> Option<&str> {
+        match self.profile_api {
+            Some(ref url) => Some(url),
+            None => self.online_mode.then_some(MOJANG_API),
+        }
+    }
+
+    /// Set the proxies trusted to forward client addresses
+    pub fn with_forwarding(mut self, forwarding: ForwardingConfig) -> Self {
+        self.forwarding = forwarding;
+        self
+    }
+
+    /// Set the permission level given to players made operators with /op
+    pub fn with_op_permission_level(mut self, level: u8) -> Self {
+        self.op_permission_level = level;
+        self
+    }
+
+    /// Set whether player addresses are recorded in the audit log
+    pub fn with_log_ips(mut self, enabled: bool) -> Self {
+        self.log_ips = enabled;
+        self
+    }
+
+    /// Set the log file settings
+    pub fn with_log_file(mut self, log_file: LogFileConfig) -> Self {
+        self.log_file = log_file;
+        self
+    }
+
+    /// Set the audit log settings
+    pub fn with_audit(mut self, audit: AuditConfig) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Set the packets a player may send per second or tick
+    pub fn with_packet_limits(mut self, limits: PacketLimits) -> Self {
+        self.packet_limits = limits;
+        self
+    }
+
+    /// Set the chat messages a player may send
+    pub fn with_chat_limits(mut self, limits: ChatLimits) -> Self {
+        self.chat_limits = limits;
+        self
+    }
+
+    /// Set the templates of the join and quit messages
+    pub fn with_join_messages(mut self, messages: JoinMessages) -> Self {
+        self.join_messages = messages;
+        self
+    }
+
+    /// Set the limits on chunks loaded per tick, queued per player and
+    /// kept loaded
+    pub fn with_chunk_budgets(mut self, budgets: ChunkBudgets) -> Self {
+        self.chunk_budgets = budgets;
+        self
+    }
+
+    /// Set the version shown in the server list and the protocols allowed
+    /// to log in
+    pub fn with_advertised_version(mut self, version: AdvertisedVersion) -> Self {
+        self.advertised_version = version;
+        self
+    }
+
+    /// Set the resource pack offered to players
+    pub fn with_resource_pack(mut self, pack: Option<ResourcePack>, required: bool) -> Self {
+        self.resource_pack = pack;
+        self.require_resource_pack = required;
+        self
+    }
+
+    /// Set the built-in resource pack host
+    pub fn with_resource_pack_host(mut self, host: Option<ResourcePackHostConfig>) -> Self {
+        self.resource_pack_host = host;
+        self
+    }
+
+    /// Set the file listing text filter patterns (empty disables filtering)
+    pub fn with_text_filtering_config(mut self, config: &str) -> Self {
+        self.text_filtering_config = config.to_string();
+        self
+    }
+
+    /// Set the language of the console and logs
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = language.to_string();
+        self
+    }
 }
@@ -0,0 +1,361 @@
+//! Configuration validation
+//!
+//! Property accessors fall back to defaults for values they cannot parse,
+//! which hides typos. Validation checks every known key up front and
+//! reports all problems at once, so they can be fixed in one go.
+
+use crate::config::secrets::{ADMIN_API_TOKEN_ENV, RCON_PASSWORD_ENV, Secret};
+use crate::config::{ServerConfig, ServerProperties};
+use crate::error::{Result, ServerError};
+use crate::game::player::{GameMode, OUTBOUND_CHANNEL_CAPACITY};
+use crate::game::world::anvil::RegionCompression;
+use crate::game::{Difficulty, LevelType};
+use crate::protocol::types::McUuid;
+use crate::server::profiles::{MOJANG_API, ProfileApi};
+use crate::server::watchdog::WatchdogAction;
+use std::fmt::Display;
+use std::net::IpAddr;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Allowed view and simulation distances
+pub const DISTANCE_RANGE: RangeInclusive<i64> = 2..=32;
+
+/// Keys holding `true` or `false`
+const BOOLEAN_KEYS: &[&str] = &[
+    "accepts-transfers",
+    "allow-flight",
+    "allow-nether",
+    "broadcast-console-to-ops",
+    "broadcast-rcon-to-ops",
+    "enable-admin-api",
+    "enable-command-block",
+    "enable-jmx-monitoring",
+    "enable-query",
+    "enable-rcon",
+    "enable-status",
+    "enforce-secure-profile",
+    "enforce-whitelist",
+    "force-gamemode",
+    "generate-structures",
+    "hardcore",
+    "hide-online-players",
+    "log-ips",
+    "log-to-file",
+    "online-mode",
+    "prevent-proxy-connections",
+    "pvp",
+    "require-resource-pack",
+    "spawn-monsters",
+    "sync-chunk-writes",
+    "use-native-transport",
+    "white-list",
+];
+
+/// Integer keys and the values they allow
+const INTEGER_KEYS: &[(&str, RangeInclusive<i64>)] = &[
+    ("admin-api.port", 1..=65535),
+    ("autosave-interval", 0..=i64::MAX),
+    ("entity-broadcast-range-percentage", 10..=1000),
+    ("function-permission-level", 1..=4),
+    ("log-file-keep", 0..=u32::MAX as i64),
+    ("log-file-max-size", 0..=1024 * 1024),
+    (
+        "max-chained-neighbor-updates",
+        i32::MIN as i64..=i32::MAX as i64,
+    ),
+    ("max-players", 0..=u32::MAX as i64),
+    ("max-tick-time", -1..=i64::MAX),
+    ("max-world-size", 1..=29_999_984),
+    ("network-compression-threshold", -1..=i32::MAX as i64),
+    ("op-permission-level", 1..=4),
+    (
+        "pause-when-empty-seconds",
+        i32::MIN as i64..=i32::MAX as i64,
+    ),
+    ("player-idle-timeout", 0..=i32::MAX as i64),
+    ("player-sample-size", 0..=u32::MAX as i64),
+    ("query.port", 1..=65535),
+    ("rate-limit", 0..=i32::MAX as i64),
+    ("rcon.port", 1..=65535),
+    ("server-port", 1..=65535),
+    ("simulation-distance", DISTANCE_RANGE),
+    ("spawn-protection", 0..=i32::MAX as i64),
+    ("view-distance", DISTANCE_RANGE),
+];
+
+/// Collects problems found while validating
+#[derive(Debug, Default)]
+struct Problems(Vec<String>);
+
+impl Problems {
+    /// Record a problem with a key
+    fn add(&mut self, key: &str, message: impl Display) {
+        self.0.push(format!("{}: {}", key, message));
+    }
+
+    /// Fail with every recorded problem
+    fn into_result(self, source: &str) -> Result<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        let count = self.0.len();
+        Err(ServerError::Configuration(format!(
+            "{} has {} problem{}:\n  {}",
+            source,
+            count,
+            if count == 1 { "" } else { "s" },
+            self.0.join("\n  ")
+        )))
+    }
+}
+
+impl ServerProperties {
+    /// Check every known property, reporting all invalid values at once
+    ///
+    /// Unknown keys are allowed, and empty values mean the default.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Problems::default();
+        let value = |key: &str| self.get_string(key).filter(|value| !value.is_empty());
+
+        for &key in BOOLEAN_KEYS {
+            if let Some(v) = value(key).filter(|v| *v != "true" && *v != "false") {
+                problems.add(key, format_args!("'{}' is not true or false", v));
+            }
+        }
+        for (key, range) in INTEGER_KEYS {
+            let Some(v) = value(key) else { continue };
+            match v.parse::<i64>() {
+                Ok(n) if range.contains(&n) => {}
+                Ok(n) => problems.add(key, out_of_range(n, range)),
+                Err(_) => problems.add(key, format_args!("'{}' is not a number", v)),
+            }
+        }
+
+        check_name(
+            &mut problems,
+            value("difficulty"),
+            "difficulty",
+            |v| v.parse::<Difficulty>().is_ok(),
+            "peaceful, easy, normal or hard",
+        );
+        check_name(
+            &mut problems,
+            value("gamemode"),
+            "gamemode",
+            |v| v.parse::<GameMode>().is_ok(),
+            "survival, creative, adventure or spectator",
+        );
+        check_name(
+            &mut problems,
+            value("level-type"),
+            "level-type",
+            |v| v.parse::<LevelType>().is_ok(),
+            "a known world preset such as minecraft:normal or minecraft:flat",
+        );
+        check_name(
+            &mut problems,
+            value("watchdog-action"),
+            "watchdog-action",
+            |v| WatchdogAction::from_name(v).is_some(),
+            "report or crash",
+        );
+        check_name(
+            &mut problems,
+            value("region-file-compression"),
+            "region-file-compression",
+            |v| RegionCompression::from_name(v).is_some(),
+            "deflate, gzip, lz4 or none",
+        );
+        check_name(
+            &mut problems,
+            value("server-ip"),
+            "server-ip",
+            |v| IpAddr::from_str(v).is_ok(),
+            "an IP address",
+        );
+        check_name(
+            &mut problems,
+            value("resource-pack-sha1"),
+            "resource-pack-sha1",
+            |v| v.len() == 40 && v.bytes().all(|b| b.is_ascii_hexdigit()),
+            "a SHA-1 of 40 hexadecimal digits",
+        );
+        check_name(
+            &mut problems,
+            value("resource-pack-id"),
+            "resource-pack-id",
+            |v| McUuid::from_str(v).is_ok(),
+            "a UUID",
+        );
+
+        let secret_missing =
+            |key: &str, var: &str| value(key).is_none() && Secret::from_env(var).is_none();
+        if self.get_bool("enable-rcon") == Some(true)
+            && secret_missing("rcon.password", RCON_PASSWORD_ENV)
+        {
+            problems.add(
+                "rcon.password",
+                format_args!(
+                    "required when enable-rcon is true (or set {})",
+                    RCON_PASSWORD_ENV
+                ),
+            );
+        }
+        if self.get_bool("enable-admin-api") == Some(true)
+            && secret_missing("admin-api.token", ADMIN_API_TOKEN_ENV)
+        {
+            problems.add(
+                "admin-api.token",
+                format_args!(
+                    "required when enable-admin-api is true (or set {})",
+                    ADMIN_API_TOKEN_ENV
+                ),
+            );
+        }
+
+        problems.into_result("server.properties")
+    }
+}
+
+impl ServerConfig {
+    /// Check the final configuration after every layer has been applied
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Problems::default();
+
+        let distances = [
+            ("view-distance", self.view_distance),
+            ("simulation-distance", self.simulation_distance),
+        ];
+        for (key, distance) in distances {
+            if !DISTANCE_RANGE.contains(&i64::from(distance)) {
+                problems.add(key, out_of_range(distance, &DISTANCE_RANGE));
+            }
+        }
+        if self
+            .compression_threshold
+            .is_some_and(|t| t > i32::MAX as u32)
+        {
+            problems.add("network-compression-threshold", "too large");
+        }
+        if self.level_name.trim().is_empty() {
+            problems.add("level-name", "must not be empty");
+        }
+        if self.online_mode && self.profile_api().and_then(ProfileApi::parse).is_none() {
+            problems.add(
+                "profile-api",
+                format_args!(
+                    "online-mode needs the session server, so it must be {:?} or an http:// or \
+                     https:// URL",
+                    MOJANG_API
+                ),
+            );
+        }
+        if self.chunk_budgets.queued_per_player as usize > OUTBOUND_CHANNEL_CAPACITY {
+            problems.add(
+                "chunks.queued-per-player",
+                format_args!(
+                    "{} is more than the {} packets a player's queue holds",
+                    self.chunk_budgets.queued_per_player, OUTBOUND_CHANNEL_CAPACITY
+                ),
+            );
+        }
+
+        problems.into_result("The configuration")
+    }
+}
+
+/// Describe a value outside its allowed range
+fn out_of_range(value: impl Display, range: &RangeInclusive<i64>) -> String {
+    if *range.end() == i64::MAX || *range.end() == i64::from(i32::MAX) {
+        format!("{} is less than {}", value, range.start())
+    } else {
+        format!(
+            "{} is not between {} and {}",
+            value,
+            range.start(),
+            range.end()
+        )
+    }
+}
+
+/// Check a value against a set of names
+fn check_name(
+    problems: &mut Problems,
+    value: Option<&String>,
+    key: &str,
+    valid: impl Fn(&str) -> bool,
+    expected: &str,
+) {
+    if let Some(v) = value.filter(|v| !valid(v)) {
+        problems.add(key, format_args!("'{}' is not {}", v, expected));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::budget::ChunkBudgets;
+
+    #[test]
+    fn test_reports_all_problems() {
+        assert!(ServerProperties::new().validate().is_ok());
+
+        let mut props = ServerProperties::new();
+        props.set("server-port", "70000");
+        props.set("view-distance", "forty");
+        props.set("difficulty", "impossible");
+        props.set("pvp", "yes");
+        props.set("enable-rcon", "true");
+        props.set("server-ip", "");
+
+        let error = props.validate().unwrap_err().to_string();
+        assert!(error.contains("has 5 problems"));
+        assert!(error.contains("server-port: 70000 is not between 1 and 65535"));
+        assert!(error.contains("view-distance: 'forty' is not a number"));
+        assert!(error.contains("difficulty: 'impossible' is not peaceful"));
+        assert!(error.contains("pvp: 'yes' is not true or false"));
+        assert!(error.contains("rcon.password: required"));
+
+        let config = ServerConfig::new().with_view_distance(1);
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("1 is not between 2 and 32")
+        );
+
+        let config = ServerConfig::new().with_chunk_budgets(ChunkBudgets {
+            queued_per_player: 5000,
+            ..ChunkBudgets::default()
+        });
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("chunks.queued-per-player: 5000 is more than the 1024 packets")
+        );
+    }
+
+    #[test]
+    fn test_online_mode_needs_session_server() {
+        let config = ServerConfig::new();
+        assert_eq!(config.profile_api(), Some(MOJANG_API));
+        assert!(config.validate().is_ok());
+
+        let config = config.with_profile_api(Some("ftp://127.0.0.1/".to_string()));
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("profile-api: online-mode needs the session server")
+        );
+
+        let config = config.with_online_mode(false);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.with_profile_api(None).profile_api(), None);
+    }
+}
@@ -1,5 +1,7 @@
 //! Error handling for Obsidium
 
+use crate::protocol::ConnectionState;
+use crate::protocol::types::JsonTextComponent;
 use thiserror::Error;
 
 /// Main error type for the server
@@ -13,6 +15,20 @@ pub enum ServerError {
     #[error("Protocol error: {0}")]
     Protocol(String),
 
+    /// A received packet could not be decoded
+    #[error("Failed to decode {} packet 0x{:02X}: {}", .state.as_str(), .id, .reason)]
+    PacketDecode {
+        /// Connection state the packet was received in
+        state: ConnectionState,
+        /// Packet ID
+        id: i32,
+        /// What was wrong with the packet
+        reason: String,
+        /// Whether the packet ended before its fields did, so it is corrupt
+        /// rather than something the server cannot decode yet
+        truncated: bool,
+    },
+
     /// Compression error
     #[error("Compression error: {0}")]
     Compression(#[from] flate2::CompressError),
@@ -20,7 +36,100 @@ pub enum ServerError {
     /// Decompression error
     #[error("Decompression error: {0}")]
     Decompression(#[from] flate2::DecompressError),
+
+    /// A player could not be authenticated
+    #[error("Authentication error: {0}")]
+    Authentication(String),
+
+    /// The server closed the connection on purpose, with a reason for the player
+    #[error("Kicked: {}", .0.to_plain_text())]
+    Kicked(JsonTextComponent),
+
+    /// Malformed or unreadable world data (chunks, regions, level.dat)
+    #[error("World I/O error: {0}")]
+    WorldIo(String),
+
+    /// Malformed or unreadable saved data other than the world
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    /// Invalid or unavailable server configuration
+    #[error("Configuration error: {0}")]
+    Configuration(String),
+
+    /// A plugin could not be loaded or enabled
+    #[error("Plugin error: {0}")]
+    Plugin(String),
+
+    /// A script could not be loaded
+    #[error("Script error: {0}")]
+    Script(String),
+
+    /// The watchdog found the server stalled and stopped it
+    #[error("Server crashed: {0}")]
+    Crashed(String),
+}
+
+impl ServerError {
+    /// Reason to show the player when this error ends their connection
+    ///
+    /// Returns `None` when the connection is already unusable and should
+    /// just be dropped. Details of internal errors stay in the server log.
+    pub fn disconnect_reason(&self) -> Option<JsonTextComponent> {
+        match self {
+            ServerError::Io(_) => None,
+            ServerError::Kicked(reason) => Some(reason.clone()),
+            ServerError::Authentication(_) => Some(crate::lang::translate(
+                "multiplayer.disconnect.unverified_username",
+                &[],
+            )),
+            ServerError::Protocol(_)
+            | ServerError::PacketDecode { .. }
+            | ServerError::Decompression(_) => {
+                Some(crate::lang::translate("disconnect.packetError", &[]))
+            }
+            ServerError::Compression(_)
+            | ServerError::WorldIo(_)
+            | ServerError::Storage(_)
+            | ServerError::Configuration(_)
+            | ServerError::Plugin(_)
+            | ServerError::Script(_)
+            | ServerError::Crashed(_) => Some(JsonTextComponent::text("Internal server error")),
+        }
+    }
 }
 
 /// Convenience type alias
 pub type Result<T> = std::result::Result<T, ServerError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disconnect_reasons() {
+        let eof = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
+        assert!(ServerError::Io(eof).disconnect_reason().is_none());
+
+        let kick = JsonTextComponent::text("Go away");
+        let reason = ServerError::Kicked(kick.clone()).disconnect_reason();
+        assert_eq!(reason, Some(kick));
+
+        let decode = ServerError::PacketDecode {
+            state: ConnectionState::Play,
+            id: 0x1A,
+            reason: "unexpected end of file".to_string(),
+            truncated: true,
+        };
+        assert_eq!(
+            decode.to_string(),
+            "Failed to decode play packet 0x1A: unexpected end of file"
+        );
+        let reason = decode.disconnect_reason().unwrap().to_plain_text();
+        assert_eq!(reason, "Network Protocol Error");
+
+        let internal = ServerError::WorldIo("region file is truncated".to_string());
+        let reason = internal.disconnect_reason().unwrap().to_plain_text();
+        assert!(!reason.contains("region"));
+    }
+}
@@ -0,0 +1,227 @@
+//! Server event bus
+//!
+//! Game code publishes [`ServerEvent`]s to the [`EventBus`], and plugins (or
+//! any other part of the server) subscribe handlers to react to them.
+//! Handlers run synchronously on the publishing task, so they should return
+//! quickly and hand long-running work off to a task.
+
+use crate::game::loot::ItemDrop;
+use crate::game::settings::ClientSettings;
+use crate::protocol::types::McUuid;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Something that happened on the server
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerEvent {
+    /// The server finished starting and accepts connections
+    ServerStarted,
+    /// The server is about to shut down
+    ServerStopping,
+    /// A player entered the play state
+    PlayerJoin {
+        /// Player name
+        username: String,
+        /// Player UUID
+        uuid: McUuid,
+    },
+    /// A player with no saved data entered the play state, published just
+    /// before their [`ServerEvent::PlayerJoin`]
+    PlayerFirstJoin {
+        /// Player name
+        username: String,
+        /// Player UUID
+        uuid: McUuid,
+    },
+    /// A player's client reported new settings
+    PlayerSettingsChanged {
+        /// Player UUID
+        uuid: McUuid,
+        /// The new settings
+        settings: ClientSettings,
+    },
+    /// A player in the play state disconnected
+    PlayerQuit {
+        /// Player name
+        username: String,
+        /// Player UUID
+        uuid: McUuid,
+    },
+    /// Terrain generation ahead of time made progress
+    PregenProgress {
+        /// Chunks visited so far
+        done: usize,
+        /// Chunks to visit in total
+        total: usize,
+        /// Chunks visited per second so far
+        chunks_per_second: f64,
+    },
+    /// A mob died and dropped its loot
+    MobKilled {
+        /// Entity type, such as `minecraft:zombie`
+        entity_type: String,
+        /// UUID of the player credited with the kill
+        killer: Option<McUuid>,
+        /// Items the mob dropped
+        drops: Vec<ItemDrop>,
+        /// Experience the mob left behind
+        experience: u32,
+    },
+}
+
+impl ServerEvent {
+    /// Get the name of this event type
+    pub fn name(&self) -> &'static str {
+        match self {
+            ServerEvent::ServerStarted => "server_started",
+            ServerEvent::ServerStopping => "server_stopping",
+            ServerEvent::PlayerJoin { .. } => "player_join",
+            ServerEvent::PlayerFirstJoin { .. } => "player_first_join",
+            ServerEvent::PlayerSettingsChanged { .. } => "player_settings_changed",
+            ServerEvent::PlayerQuit { .. } => "player_quit",
+            ServerEvent::PregenProgress { .. } => "pregen_progress",
+            ServerEvent::MobKilled { .. } => "mob_killed",
+        }
+    }
+
+    /// Describe this event as a JSON object with its name under `event`
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = match self {
+            ServerEvent::ServerStarted | ServerEvent::ServerStopping => serde_json::json!({}),
+            ServerEvent::PlayerJoin { username, uuid }
+            | ServerEvent::PlayerFirstJoin { username, uuid }
+            | ServerEvent::PlayerQuit { username, uuid } => {
+                serde_json::json!({ "username": username, "uuid": uuid })
+            }
+            ServerEvent::PlayerSettingsChanged { uuid, settings } => serde_json::json!({
+                "uuid": uuid,
+                "locale": settings.locale,
+                "view_distance": settings.view_distance,
+            }),
+            ServerEvent::PregenProgress {
+                done,
+                total,
+                chunks_per_second,
+            } => serde_json::json!({
+                "done": done,
+                "total": total,
+                "chunks_per_second": chunks_per_second,
+            }),
+            ServerEvent::MobKilled {
+                entity_type,
+                killer,
+                drops,
+                experience,
+            } => serde_json::json!({
+                "entity_type": entity_type,
+                "killer": killer,
+                "drops": drops
+                    .iter()
+                    .map(|drop| serde_json::json!({ "item": drop.item, "count": drop.count }))
+                    .collect::<Vec<_>>(),
+                "experience": experience,
+            }),
+        };
+        json["event"] = serde_json::Value::from(self.name());
+        json
+    }
+}
+
+/// A function called for every published event
+pub type EventHandler = Arc<dyn Fn(&ServerEvent) + Send + Sync>;
+
+/// Identifies a subscription so it can be removed again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListenerId(u64);
+
+/// Delivers events to subscribed handlers
+#[derive(Default)]
+pub struct EventBus {
+    /// Subscribed handlers in subscription order
+    listeners: RwLock<Vec<(ListenerId, EventHandler)>>,
+    /// Next listener ID to hand out
+    next_id: AtomicU64,
+}
+
+impl EventBus {
+    /// Create a bus without subscribers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe a handler to all events
+    pub fn subscribe(&self, handler: EventHandler) -> ListenerId {
+        let id = ListenerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.listeners
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((id, handler));
+        id
+    }
+
+    /// Remove a subscription, returning whether it existed
+    pub fn unsubscribe(&self, id: ListenerId) -> bool {
+        let mut listeners = self.listeners.write().unwrap_or_else(|e| e.into_inner());
+        let before = listeners.len();
+        listeners.retain(|(listener, _)| *listener != id);
+        listeners.len() != before
+    }
+
+    /// Number of subscribed handlers
+    pub fn listener_count(&self) -> usize {
+        self.listeners
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    /// Deliver an event to every subscribed handler
+    ///
+    /// A panicking handler is logged and does not affect other handlers.
+    pub fn publish(&self, event: &ServerEvent) {
+        // Clone the handlers so they may subscribe or unsubscribe themselves
+        let handlers: Vec<EventHandler> = self
+            .listeners
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(_, handler)| Arc::clone(handler))
+            .collect();
+
+        for handler in handlers {
+            if catch_unwind(AssertUnwindSafe(|| handler(event))).is_err() {
+                tracing::error!("Event handler panicked while handling {}", event.name());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_publish_and_unsubscribe() {
+        let bus = EventBus::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&calls);
+        let id = bus.subscribe(Arc::new(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }));
+        bus.subscribe(Arc::new(|_| {
+            std::panic::resume_unwind(Box::new("handler failure"))
+        }));
+
+        bus.publish(&ServerEvent::ServerStarted);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        bus.publish(&ServerEvent::ServerStopping);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert_eq!(bus.listener_count(), 1);
+    }
+}
@@ -0,0 +1,188 @@
+//! Chat spam protection
+//!
+//! Every player has a [`ChatGuard`] that checks their chat messages against
+//! the configured [`ChatLimits`] before anyone else sees them. A message
+//! sent too soon, repeating the last one or over the length limit is
+//! dropped and the sender warned; a player collecting too many warnings
+//! within one window is kicked for spamming.
+//!
+//! This is on top of the per-second packet limit of
+//! [`PacketRateLimiter`](crate::network::PacketRateLimiter), which kicks
+//! clients flooding the connection outright. Commands are not counted.
+
+use crate::protocol::types::JsonTextComponent;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Length of the window messages and warnings are counted over
+pub const WINDOW: Duration = Duration::from_secs(10);
+
+/// Longest chat message the protocol allows
+pub const PROTOCOL_MAX_LENGTH: usize = 256;
+
+/// Limits on the chat messages a player may send (zero disables a limit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChatLimits {
+    /// Messages per [`WINDOW`]
+    pub messages_per_window: u32,
+    /// Time within which a message may not repeat the previous one
+    pub duplicate_cooldown: Duration,
+    /// Longest message in characters, at most [`PROTOCOL_MAX_LENGTH`]
+    pub max_length: usize,
+    /// Warnings within one [`WINDOW`] at which the player is kicked
+    pub kick_after: u32,
+}
+
+impl Default for ChatLimits {
+    fn default() -> Self {
+        Self {
+            messages_per_window: 10,
+            duplicate_cooldown: Duration::from_secs(30),
+            max_length: PROTOCOL_MAX_LENGTH,
+            kick_after: 5,
+        }
+    }
+}
+
+/// Reasons a chat message is dropped
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ChatError {
+    /// Too many messages in the current window
+    #[error("Sending messages too quickly")]
+    TooFast,
+    /// Same as the previous message, sent within the cooldown
+    #[error("Repeated the previous message")]
+    Duplicate,
+    /// Longer than the length limit
+    #[error("Message longer than {0} characters")]
+    TooLong(usize),
+}
+
+impl ChatError {
+    /// Warning shown to the sender
+    pub fn warning(&self) -> JsonTextComponent {
+        match self {
+            ChatError::TooFast => crate::lang::translate("chat.spam.too_fast", &[]),
+            ChatError::Duplicate => crate::lang::translate("chat.spam.duplicate", &[]),
+            ChatError::TooLong(max) => {
+                crate::lang::translate("chat.spam.too_long", &[&max.to_string()])
+            }
+        }
+    }
+}
+
+/// What happens to a chat message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatVerdict {
+    /// The message is sent
+    Allowed,
+    /// The message is dropped and the sender warned
+    Warn(ChatError),
+    /// The message is dropped and the sender kicked
+    Kick(ChatError),
+}
+
+/// Chat messages and warnings of one player
+#[derive(Debug, Clone, Default)]
+pub struct ChatGuard {
+    /// Start of the current window, once a message was sent
+    window_start: Option<Instant>,
+    /// Messages sent in the current window
+    messages: u32,
+    /// Warnings given in the current window
+    warnings: u32,
+    /// Last message sent and when
+    last: Option<(String, Instant)>,
+}
+
+impl ChatGuard {
+    /// Check a message sent at `now`, counting it if it is allowed
+    pub fn check(&mut self, limits: &ChatLimits, message: &str, now: Instant) -> ChatVerdict {
+        if self
+            .window_start
+            .is_none_or(|start| now.saturating_duration_since(start) >= WINDOW)
+        {
+            self.window_start = Some(now);
+            self.messages = 0;
+            self.warnings = 0;
+        }
+        let error = match self.violation(limits, message, now) {
+            Some(error) => error,
+            None => {
+                self.messages += 1;
+                self.last = Some((message.to_string(), now));
+                return ChatVerdict::Allowed;
+            }
+        };
+        self.warnings += 1;
+        if limits.kick_after > 0 && self.warnings >= limits.kick_after {
+            ChatVerdict::Kick(error)
+        } else {
+            ChatVerdict::Warn(error)
+        }
+    }
+
+    /// The limit a message breaks, if any
+    fn violation(&self, limits: &ChatLimits, message: &str, now: Instant) -> Option<ChatError> {
+        if limits.max_length > 0 && message.chars().count() > limits.max_length {
+            return Some(ChatError::TooLong(limits.max_length));
+        }
+        if limits.messages_per_window > 0 && self.messages >= limits.messages_per_window {
+            return Some(ChatError::TooFast);
+        }
+        let repeated = self.last.as_ref().is_some_and(|(last, sent)| {
+            now.saturating_duration_since(*sent) < limits.duplicate_cooldown
+                && last.trim().eq_ignore_ascii_case(message.trim())
+        });
+        repeated.then_some(ChatError::Duplicate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_limits() {
+        let limits = ChatLimits {
+            messages_per_window: 2,
+            duplicate_cooldown: Duration::from_secs(30),
+            max_length: 5,
+            kick_after: 3,
+        };
+        let mut guard = ChatGuard::default();
+        let start = Instant::now();
+
+        assert_eq!(guard.check(&limits, "hi", start), ChatVerdict::Allowed);
+        assert_eq!(
+            guard.check(&limits, "HI ", start),
+            ChatVerdict::Warn(ChatError::Duplicate)
+        );
+        assert_eq!(
+            guard.check(&limits, "hello!", start),
+            ChatVerdict::Warn(ChatError::TooLong(5))
+        );
+        assert_eq!(guard.check(&limits, "yo", start), ChatVerdict::Allowed);
+        assert_eq!(
+            guard.check(&limits, "hey", start),
+            ChatVerdict::Kick(ChatError::TooFast)
+        );
+
+        // A new window forgets messages and warnings, but not the last message
+        let later = start + WINDOW;
+        assert_eq!(
+            guard.check(&limits, "yo", later),
+            ChatVerdict::Warn(ChatError::Duplicate)
+        );
+        assert_eq!(guard.check(&limits, "hey", later), ChatVerdict::Allowed);
+        let unlimited = ChatLimits {
+            messages_per_window: 0,
+            duplicate_cooldown: Duration::ZERO,
+            max_length: 0,
+            kick_after: 0,
+        };
+        for _ in 0..100 {
+            assert_eq!(guard.check(&unlimited, "hey", later), ChatVerdict::Allowed);
+        }
+    }
+}
@@ -0,0 +1,668 @@
+//! Data packs
+//!
+//! Data packs are directories in the world's `datapacks` folder holding a
+//! `pack.mcmeta` and a `data/<namespace>/` tree of recipes, loot tables,
+//! tags and functions, in the vanilla layout:
+//!
+//! ```text
+//! world/datapacks/example/pack.mcmeta
+//! world/datapacks/example/data/example/recipe/gold_block.json
+//! world/datapacks/example/data/example/loot_table/blocks/gold_block.json
+//! world/datapacks/example/data/minecraft/tags/block/logs.json
+//! world/datapacks/example/data/example/function/tick.mcfunction
+//! ```
+//!
+//! Packs are known by ID: `vanilla` for the built-in pack and
+//! `file/<directory>` for the others. The [`DataPackManager`] loads the
+//! enabled packs in order, later packs overriding earlier ones, into the
+//! [`DataRegistries`]. Which packs are enabled is stored in `level.dat`;
+//! a new world starts with `initial-enabled-packs` and
+//! `initial-disabled-packs`, and packs added later are enabled unless they
+//! were disabled before.
+//!
+//! Zipped packs are not supported and are skipped with a warning. This
+//! server does not ship the vanilla data, so the `vanilla` pack is empty.
+
+pub mod recipe;
+pub mod tags;
+
+pub use recipe::{Recipe, RecipeRegistry};
+pub use tags::{TagEntry, TagFile, TagRegistry};
+
+use crate::error::{Result, ServerError};
+use crate::protocol::types::Identifier;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Folder of a world holding its data packs
+pub const DATAPACKS_DIR: &str = "datapacks";
+
+/// ID of the built-in pack
+pub const VANILLA_PACK: &str = "vanilla";
+
+/// Data pack format of this version (Minecraft 1.21.6)
+pub const PACK_FORMAT: i32 = 80;
+
+/// Kinds of content and the folder names they are read from, current
+/// name first
+const RECIPE_DIRS: [&str; 2] = ["recipe", "recipes"];
+const LOOT_TABLE_DIRS: [&str; 2] = ["loot_table", "loot_tables"];
+const FUNCTION_DIRS: [&str; 2] = ["function", "functions"];
+
+/// Tag registry folders renamed in 1.21, and their current names
+const LEGACY_TAG_DIRS: [(&str, &str); 6] = [
+    ("blocks", "block"),
+    ("items", "item"),
+    ("entity_types", "entity_type"),
+    ("fluids", "fluid"),
+    ("game_events", "game_event"),
+    ("functions", "function"),
+];
+
+/// Which packs a world has enabled and disabled
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DataPackSelection {
+    /// Enabled pack IDs, in load order
+    pub enabled: Vec<String>,
+    /// Pack IDs that were seen but are disabled
+    pub disabled: Vec<String>,
+}
+
+/// A function: a list of commands
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Function {
+    /// Commands in order, without comments and with continued lines joined
+    pub commands: Vec<String>,
+}
+
+impl Function {
+    /// Parse an `.mcfunction` file
+    pub fn parse(source: &str) -> Self {
+        let mut commands = Vec::new();
+        let mut pending = String::new();
+        for line in source.lines() {
+            let line = line.trim();
+            if pending.is_empty() && (line.is_empty() || line.starts_with('#')) {
+                continue;
+            }
+            match line.strip_suffix('\\') {
+                Some(start) => {
+                    pending.push_str(start.trim_start());
+                }
+                None => {
+                    pending.push_str(line);
+                    commands.push(std::mem::take(&mut pending));
+                }
+            }
+        }
+        if !pending.is_empty() {
+            commands.push(pending);
+        }
+        Self { commands }
+    }
+}
+
+/// Everything one pack provides
+#[derive(Debug, Clone, Default)]
+pub struct PackContent {
+    /// Recipe definitions by ID, as written in the pack
+    pub recipes: BTreeMap<Identifier, Value>,
+    /// Loot tables by ID, as written in the pack
+    pub loot_tables: BTreeMap<Identifier, Value>,
+    /// Tag files by registry and tag ID
+    pub tags: BTreeMap<(String, Identifier), TagFile>,
+    /// Functions by ID
+    pub functions: BTreeMap<Identifier, Function>,
+    /// Files that could not be read, with the reason
+    pub errors: Vec<String>,
+}
+
+impl PackContent {
+    /// Read the `data` folder of a pack
+    fn load(data: &Path) -> Self {
+        let mut content = Self::default();
+        let Ok(namespaces) = std::fs::read_dir(data) else {
+            return content;
+        };
+        for namespace in namespaces.flatten() {
+            let Some(name) = namespace.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            let root = namespace.path();
+            content.load_namespace(&name, &root);
+        }
+        content
+    }
+
+    /// Read the content of one namespace
+    fn load_namespace(&mut self, namespace: &str, root: &Path) {
+        for dir in RECIPE_DIRS {
+            for (path, file) in files(&root.join(dir), "json") {
+                if let Some(json) = self.read_json(&file) {
+                    self.recipes.insert(Identifier::new(namespace, &path), json);
+                }
+            }
+        }
+        for dir in LOOT_TABLE_DIRS {
+            for (path, file) in files(&root.join(dir), "json") {
+                if let Some(json) = self.read_json(&file) {
+                    self.loot_tables
+                        .insert(Identifier::new(namespace, &path), json);
+                }
+            }
+        }
+        for dir in FUNCTION_DIRS {
+            for (path, file) in files(&root.join(dir), "mcfunction") {
+                match std::fs::read_to_string(&file) {
+                    Ok(source) => {
+                        self.functions
+                            .insert(Identifier::new(namespace, &path), Function::parse(&source));
+                    }
+                    Err(e) => self.errors.push(format!("{}: {}", file.display(), e)),
+                }
+            }
+        }
+
+        for (path, file) in files(&root.join("tags"), "json") {
+            let Some((registry, tag)) = split_tag_path(&path) else {
+                continue;
+            };
+            let Some(json) = self.read_json(&file) else {
+                continue;
+            };
+            match TagFile::parse(&json) {
+                Ok(tag_file) => {
+                    self.tags
+                        .insert((registry, Identifier::new(namespace, tag)), tag_file);
+                }
+                Err(e) => self.errors.push(format!("{}: {}", file.display(), e)),
+            }
+        }
+    }
+
+    /// Read a JSON file, recording an error if it is invalid
+    fn read_json(&mut self, file: &Path) -> Option<Value> {
+        let parsed = std::fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(json) => Some(json),
+            Err(e) => {
+                self.errors.push(format!("{}: {}", file.display(), e));
+                None
+            }
+        }
+    }
+}
+
+/// A data pack
+#[derive(Debug, Clone)]
+pub struct DataPack {
+    /// Pack ID (`vanilla` or `file/<directory>`)
+    pub id: String,
+    /// Description from `pack.mcmeta`
+    pub description: String,
+    /// Pack format from `pack.mcmeta`
+    pub pack_format: i32,
+    /// Recipes, loot tables, tags and functions
+    pub content: PackContent,
+}
+
+impl DataPack {
+    /// The built-in pack
+    pub fn vanilla() -> Self {
+        Self {
+            id: VANILLA_PACK.to_string(),
+            description: "The default data for Minecraft".to_string(),
+            pack_format: PACK_FORMAT,
+            content: PackContent::default(),
+        }
+    }
+
+    /// Load a pack from its directory
+    pub fn load(directory: &Path) -> Result<Self> {
+        let name = directory
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                ServerError::Storage(format!("Invalid data pack name {}", directory.display()))
+            })?;
+        let invalid = |reason: &str| {
+            ServerError::Storage(format!(
+                "Invalid data pack {}: {}",
+                directory.display(),
+                reason
+            ))
+        };
+
+        let mcmeta = std::fs::read_to_string(directory.join("pack.mcmeta"))
+            .map_err(|e| invalid(&format!("cannot read pack.mcmeta: {}", e)))?;
+        let mcmeta: Value = serde_json::from_str(&mcmeta).map_err(|e| invalid(&e.to_string()))?;
+        let pack = mcmeta
+            .get("pack")
+            .ok_or_else(|| invalid("pack.mcmeta has no 'pack' section"))?;
+        let pack_format = pack
+            .get("pack_format")
+            .and_then(Value::as_i64)
+            .ok_or_else(|| invalid("pack.mcmeta has no 'pack_format'"))?;
+
+        Ok(Self {
+            id: format!("file/{}", name),
+            description: pack.get("description").map(plain_text).unwrap_or_default(),
+            pack_format: pack_format as i32,
+            content: PackContent::load(&directory.join("data")),
+        })
+    }
+
+    /// Check whether the pack was made for this version
+    pub fn is_compatible(&self) -> bool {
+        self.pack_format == PACK_FORMAT
+    }
+}
+
+/// Content of the enabled packs, merged
+#[derive(Debug, Clone, Default)]
+pub struct DataRegistries {
+    /// Recipes
+    pub recipes: RecipeRegistry,
+    /// Loot tables by ID
+    pub loot_tables: BTreeMap<Identifier, Value>,
+    /// Tags of every registry
+    pub tags: TagRegistry,
+    /// Functions by ID
+    pub functions: BTreeMap<Identifier, Function>,
+}
+
+impl DataRegistries {
+    /// Merge packs, each overriding the ones before it
+    ///
+    /// Returns the registries and the problems found while merging.
+    pub fn merge<'a>(packs: impl IntoIterator<Item = &'a DataPack>) -> (Self, Vec<String>) {
+        let mut registries = Self::default();
+        let mut recipes = BTreeMap::new();
+        let mut tags = BTreeMap::new();
+        let mut errors = Vec::new();
+        for pack in packs {
+            let content = &pack.content;
+            recipes.extend(
+                content
+                    .recipes
+                    .iter()
+                    .map(|(id, recipe)| (id.clone(), recipe.clone())),
+            );
+            registries.loot_tables.extend(
+                content
+                    .loot_tables
+                    .iter()
+                    .map(|(id, table)| (id.clone(), table.clone())),
+            );
+            registries.functions.extend(
+                content
+                    .functions
+                    .iter()
+                    .map(|(id, function)| (id.clone(), function.clone())),
+            );
+            for (key, file) in &content.tags {
+                file.merge_into(tags.entry(key.clone()).or_default());
+            }
+            errors.extend(content.errors.iter().cloned());
+        }
+
+        let (recipes, recipe_errors) = RecipeRegistry::parse(&recipes);
+        registries.recipes = recipes;
+        errors.extend(recipe_errors);
+        let (tags, tag_errors) = TagRegistry::resolve(&tags);
+        registries.tags = tags;
+        errors.extend(tag_errors);
+        (registries, errors)
+    }
+}
+
+/// Where to add a pack to the load order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PackPosition {
+    /// Before every other pack, so everything overrides it
+    First,
+    /// After every other pack, so it overrides everything
+    #[default]
+    Last,
+}
+
+/// Finds, enables and loads data packs
+#[derive(Debug, Default)]
+pub struct DataPackManager {
+    /// Folder packs are loaded from, if any
+    directory: Option<PathBuf>,
+    /// Every known pack by ID
+    available: BTreeMap<String, DataPack>,
+    /// Enabled pack IDs in load order
+    enabled: Vec<String>,
+    /// Pack IDs disabled on purpose
+    disabled: Vec<String>,
+    /// Content of the enabled packs
+    registries: DataRegistries,
+}
+
+impl DataPackManager {
+    /// Create a manager with only the built-in pack, enabled
+    pub fn new() -> Self {
+        let mut manager = Self::default();
+        manager.rescan();
+        manager.enabled.push(VANILLA_PACK.to_string());
+        manager.reload();
+        manager
+    }
+
+    /// Load the packs in `directory`, enabling them as `selection` says
+    ///
+    /// Packs found for the first time are enabled unless the selection
+    /// disables them; selected packs that no longer exist are dropped.
+    pub fn load(directory: impl Into<PathBuf>, selection: &DataPackSelection) -> Self {
+        let mut manager = Self {
+            directory: Some(directory.into()),
+            disabled: selection.disabled.clone(),
+            ..Self::default()
+        };
+        manager.rescan();
+        manager.enabled = selection
+            .enabled
+            .iter()
+            .filter(|id| manager.available.contains_key(*id))
+            .cloned()
+            .collect();
+        let new: Vec<String> = manager
+            .available
+            .keys()
+            .filter(|id| !selection.enabled.contains(id) && !selection.disabled.contains(id))
+            .cloned()
+            .collect();
+        manager.enabled.extend(new);
+        manager.reload();
+        manager
+    }
+
+    /// Which packs are enabled and disabled, for saving
+    pub fn selection(&self) -> DataPackSelection {
+        DataPackSelection {
+            enabled: self.enabled.clone(),
+            disabled: self
+                .available
+                .keys()
+                .filter(|id| !self.enabled.contains(id))
+                .chain(
+                    self.disabled
+                        .iter()
+                        .filter(|id| !self.available.contains_key(*id)),
+                )
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Every known pack, in ID order
+    pub fn available(&self) -> impl Iterator<Item = &DataPack> {
+        self.available.values()
+    }
+
+    /// Enabled packs, in load order
+    pub fn enabled(&self) -> impl Iterator<Item = &DataPack> {
+        self.enabled.iter().filter_map(|id| self.available.get(id))
+    }
+
+    /// Get a known pack
+    pub fn get(&self, id: &str) -> Option<&DataPack> {
+        self.available.get(id)
+    }
+
+    /// Check whether a pack is enabled
+    pub fn is_enabled(&self, id: &str) -> bool {
+        self.enabled.iter().any(|enabled| enabled == id)
+    }
+
+    /// Merged content of the enabled packs
+    pub fn registries(&self) -> &DataRegistries {
+        &self.registries
+    }
+
+    /// Look for new and changed packs, then reload the enabled ones
+    pub fn refresh(&mut self) {
+        self.rescan();
+        let available = &self.available;
+        self.enabled.retain(|id| available.contains_key(id));
+        self.reload();
+    }
+
+    /// Enable a known pack and reload
+    ///
+    /// Returns `false` if the pack is unknown or already enabled.
+    pub fn enable(&mut self, id: &str, position: PackPosition) -> bool {
+        if !self.available.contains_key(id) || self.is_enabled(id) {
+            return false;
+        }
+        match position {
+            PackPosition::First => self.enabled.insert(0, id.to_string()),
+            PackPosition::Last => self.enabled.push(id.to_string()),
+        }
+        self.disabled.retain(|disabled| disabled != id);
+        self.reload();
+        true
+    }
+
+    /// Disable an enabled pack and reload
+    ///
+    /// Returns `false` if the pack is not enabled.
+    pub fn disable(&mut self, id: &str) -> bool {
+        if !self.is_enabled(id) {
+            return false;
+        }
+        self.enabled.retain(|enabled| enabled != id);
+        self.disabled.push(id.to_string());
+        self.reload();
+        true
+    }
+
+    /// Find the packs in the data pack folder
+    fn rescan(&mut self) {
+        self.available.clear();
+        self.available
+            .insert(VANILLA_PACK.to_string(), DataPack::vanilla());
+        let Some(ref directory) = self.directory else {
+            return;
+        };
+        let Ok(entries) = std::fs::read_dir(directory) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                match DataPack::load(&path) {
+                    Ok(pack) => {
+                        if !pack.is_compatible() {
+                            tracing::warn!(
+                                "Data pack {} was made for pack format {}, not {}",
+                                pack.id,
+                                pack.pack_format,
+                                PACK_FORMAT
+                            );
+                        }
+                        self.available.insert(pack.id.clone(), pack);
+                    }
+                    Err(e) => tracing::warn!("Skipping data pack: {}", e),
+                }
+            } else if path.extension().is_some_and(|ext| ext == "zip") {
+                tracing::warn!(
+                    "Skipping zipped data pack {}: extract it into a folder",
+                    path.display()
+                );
+            }
+        }
+    }
+
+    /// Merge the enabled packs into the registries
+    fn reload(&mut self) {
+        let packs = self.enabled.iter().filter_map(|id| self.available.get(id));
+        let (registries, errors) = DataRegistries::merge(packs);
+        for error in &errors {
+            tracing::warn!("Data pack problem: {}", error);
+        }
+        tracing::info!(
+            "Loaded {} data pack(s): {} recipes, {} loot tables, {} tags, {} functions",
+            self.enabled.len(),
+            registries.recipes.len(),
+            registries.loot_tables.len(),
+            registries.tags.len(),
+            registries.functions.len()
+        );
+        self.registries = registries;
+    }
+}
+
+/// Files with an extension below `root`, with their path relative to it
+/// and without the extension, in path order
+fn files(root: &Path, extension: &str) -> Vec<(String, PathBuf)> {
+    let mut found = Vec::new();
+    let mut pending = vec![(String::new(), root.to_path_buf())];
+    while let Some((prefix, dir)) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if path.is_dir() {
+                pending.push((format!("{}{}/", prefix, name), path.clone()));
+            } else if let Some(stem) = name.strip_suffix(&format!(".{}", extension)) {
+                found.push((format!("{}{}", prefix, stem), path.clone()));
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Split a path below `tags/` into the registry and the tag
+///
+/// Registries under `worldgen/` have two path segments.
+fn split_tag_path(path: &str) -> Option<(String, &str)> {
+    let (registry, rest) = path.split_once('/')?;
+    let (registry, tag) = if registry == "worldgen" {
+        let (kind, tag) = rest.split_once('/')?;
+        (format!("worldgen/{}", kind), tag)
+    } else {
+        let registry = LEGACY_TAG_DIRS
+            .iter()
+            .find(|(legacy, _)| *legacy == registry)
+            .map_or(registry, |(_, current)| current);
+        (registry.to_string(), rest)
+    };
+    Some((registry, tag))
+}
+
+/// Plain text of a description, which may be a text component
+fn plain_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts.iter().map(plain_text).collect(),
+        Value::Object(component) => {
+            let text = component
+                .get("text")
+                .or_else(|| component.get("translate"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let extra: String = component
+                .get("extra")
+                .and_then(Value::as_array)
+                .map(|parts| parts.iter().map(plain_text).collect())
+                .unwrap_or_default();
+            format!("{}{}", text, extra)
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a file, creating its parent directories
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_load_and_select_packs() {
+        let dir = std::env::temp_dir().join(format!("obsidium-datapacks-{}", uuid::Uuid::new_v4()));
+        let mcmeta = |description: &str| {
+            format!(
+                r#"{{"pack": {{"pack_format": {}, "description": {}}}}}"#,
+                PACK_FORMAT, description
+            )
+        };
+        let base = dir.join("base");
+        write(&base.join("pack.mcmeta"), &mcmeta(r#"{"text": "Base"}"#));
+        write(
+            &base.join("data/example/recipe/gold_nugget.json"),
+            r#"{"type": "minecraft:crafting_shapeless", "ingredients": ["gold_ingot"],
+                "result": {"id": "minecraft:gold_nugget", "count": 9}}"#,
+        );
+        write(
+            &base.join("data/minecraft/tags/blocks/logs.json"),
+            r#"{"values": ["oak_log"]}"#,
+        );
+        write(
+            &base.join("data/example/function/greet.mcfunction"),
+            "# Greets everyone\nsay hello \\\n  world\n\ntime set day\n",
+        );
+        write(&base.join("data/example/loot_table/broken.json"), "{");
+        let patch = dir.join("patch");
+        write(&patch.join("pack.mcmeta"), &mcmeta(r#""Patch""#));
+        write(
+            &patch.join("data/minecraft/tags/block/logs.json"),
+            r#"{"values": ["birch_log"]}"#,
+        );
+        write(&dir.join("nometa/data/x/recipe/a.json"), "{}");
+
+        let selection = DataPackSelection {
+            enabled: vec!["vanilla".to_string(), "file/base".to_string()],
+            disabled: vec!["file/patch".to_string()],
+        };
+        let mut manager = DataPackManager::load(&dir, &selection);
+        assert_eq!(manager.available().count(), 3);
+        assert_eq!(manager.get("file/base").unwrap().description, "Base");
+        assert_eq!(manager.selection(), selection);
+
+        let registries = manager.registries();
+        assert!(
+            registries
+                .recipes
+                .get(&Identifier::parse("example:gold_nugget"))
+                .is_some()
+        );
+        assert!(registries.loot_tables.is_empty());
+        let greet = &registries.functions[&Identifier::parse("example:greet")];
+        assert_eq!(greet.commands, ["say hello world", "time set day"]);
+        let logs = Identifier::parse("logs");
+        let oak = Identifier::parse("oak_log");
+        let birch = Identifier::parse("birch_log");
+        assert!(registries.tags.contains("block", &logs, &oak));
+
+        assert!(manager.enable("file/patch", PackPosition::Last));
+        assert!(!manager.enable("file/patch", PackPosition::Last));
+        assert!(!manager.enable("file/missing", PackPosition::Last));
+        let tags = &manager.registries().tags;
+        assert_eq!(tags.get("block", &logs).unwrap(), [oak.clone(), birch]);
+
+        assert!(manager.disable("file/base"));
+        assert!(!manager.registries().tags.contains("block", &logs, &oak));
+        assert_eq!(manager.selection().disabled, ["file/base".to_string()]);
+
+        // Packs found for the first time are enabled
+        let fresh = DataPackManager::load(&dir, &DataPackSelection::default());
+        assert!(fresh.is_enabled("file/base") && fresh.is_enabled("file/patch"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,701 @@
+//! Recipes
+//!
+//! Recipe files of the enabled data packs are parsed into a
+//! [`RecipeRegistry`], which crafting grids, furnaces and stonecutters
+//! look their recipes up in. Shaped and shapeless crafting, smelting,
+//! blasting and stonecutting are supported; recipes of other types are
+//! skipped.
+//!
+//! Both the current format (ingredients as `"minecraft:stick"`,
+//! `"#minecraft:planks"` or a list of items, results as `{"id", "count"}`)
+//! and the one used before 1.21.2 (`{"item": ...}` and `{"tag": ...}`
+//! ingredients) are read.
+
+use super::tags::TagRegistry;
+use crate::protocol::packets::play::{IdSet, PropertySet, StonecutterEntry, UpdateRecipesPacket};
+use crate::protocol::types::{Identifier, VarInt};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Largest crafting grid, in slots along each side
+pub const MAX_GRID_SIZE: usize = 3;
+
+/// Ticks a furnace takes to smelt an item by default
+pub const DEFAULT_SMELTING_TIME: u32 = 200;
+
+/// Ticks a blast furnace takes to smelt an item by default
+pub const DEFAULT_BLASTING_TIME: u32 = 100;
+
+/// Items a recipe slot accepts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ingredient {
+    /// Any of the listed items
+    Items(Vec<Identifier>),
+    /// Any item in an item tag
+    Tag(Identifier),
+}
+
+impl Ingredient {
+    /// Parse an ingredient in either format
+    pub fn parse(json: &Value) -> Result<Self, String> {
+        match json {
+            Value::String(id) => Ok(match id.strip_prefix('#') {
+                Some(tag) => Ingredient::Tag(Identifier::parse(tag)),
+                None => Ingredient::Items(vec![Identifier::parse(id)]),
+            }),
+            Value::Object(entry) => {
+                if let Some(item) = entry.get("item").and_then(Value::as_str) {
+                    Ok(Ingredient::Items(vec![Identifier::parse(item)]))
+                } else if let Some(tag) = entry.get("tag").and_then(Value::as_str) {
+                    Ok(Ingredient::Tag(Identifier::parse(tag)))
+                } else {
+                    Err("ingredient without an 'item' or 'tag'".to_string())
+                }
+            }
+            Value::Array(entries) if !entries.is_empty() => {
+                let mut items = Vec::new();
+                for entry in entries {
+                    match Ingredient::parse(entry)? {
+                        Ingredient::Items(listed) => items.extend(listed),
+                        Ingredient::Tag(_) => {
+                            return Err("ingredient lists cannot contain tags".to_string());
+                        }
+                    }
+                }
+                Ok(Ingredient::Items(items))
+            }
+            _ => Err("ingredients must be an item, a tag or a list of items".to_string()),
+        }
+    }
+
+    /// Check whether an item fits this ingredient
+    pub fn matches(&self, item: &Identifier, tags: &TagRegistry) -> bool {
+        match self {
+            Ingredient::Items(items) => items.contains(item),
+            Ingredient::Tag(tag) => tags.contains("item", tag, item),
+        }
+    }
+
+    /// Every item fitting this ingredient
+    pub fn items<'a>(&'a self, tags: &'a TagRegistry) -> &'a [Identifier] {
+        match self {
+            Ingredient::Items(items) => items,
+            Ingredient::Tag(tag) => tags.get("item", tag).unwrap_or_default(),
+        }
+    }
+}
+
+/// Items a recipe makes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipeResult {
+    /// Item ID
+    pub item: Identifier,
+    /// Number of items
+    pub count: u32,
+}
+
+impl RecipeResult {
+    /// Parse a result: an item ID or an object with `id` and `count`
+    fn parse(json: Option<&Value>) -> Result<Self, String> {
+        match json {
+            Some(Value::String(id)) => Ok(RecipeResult {
+                item: Identifier::parse(id),
+                count: 1,
+            }),
+            Some(Value::Object(result)) => {
+                let item = result
+                    .get("id")
+                    .or_else(|| result.get("item"))
+                    .and_then(Value::as_str)
+                    .ok_or("result without an 'id'")?;
+                let count = result.get("count").and_then(Value::as_u64).unwrap_or(1);
+                if !(1..=99).contains(&count) {
+                    return Err(format!("result count {} is not between 1 and 99", count));
+                }
+                Ok(RecipeResult {
+                    item: Identifier::parse(item),
+                    count: count as u32,
+                })
+            }
+            _ => Err("missing 'result'".to_string()),
+        }
+    }
+}
+
+/// Blocks that cook items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CookingKind {
+    /// Furnaces
+    Smelting,
+    /// Blast furnaces
+    Blasting,
+}
+
+/// A recipe
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recipe {
+    /// Crafting with the ingredients in a fixed arrangement
+    Shaped {
+        /// Pattern width in slots
+        width: usize,
+        /// Pattern height in slots
+        height: usize,
+        /// Ingredients row by row, `None` for empty slots
+        ingredients: Vec<Option<Ingredient>>,
+        /// Items made
+        result: RecipeResult,
+    },
+    /// Crafting with the ingredients anywhere in the grid
+    Shapeless {
+        /// One ingredient per item used
+        ingredients: Vec<Ingredient>,
+        /// Items made
+        result: RecipeResult,
+    },
+    /// Cooking one item into another
+    Cooking {
+        /// Block that cooks it
+        kind: CookingKind,
+        /// Item cooked
+        ingredient: Ingredient,
+        /// Items made
+        result: RecipeResult,
+        /// Experience given when the result is taken out
+        experience: f32,
+        /// Ticks the cooking takes
+        cooking_time: u32,
+    },
+    /// Cutting one item into others with a stonecutter
+    Stonecutting {
+        /// Item cut
+        ingredient: Ingredient,
+        /// Items made
+        result: RecipeResult,
+    },
+}
+
+impl Recipe {
+    /// Parse a recipe file
+    ///
+    /// Returns `Ok(None)` for recipe types that are not supported.
+    pub fn parse(json: &Value) -> Result<Option<Self>, String> {
+        let kind = json
+            .get("type")
+            .and_then(Value::as_str)
+            .map(Identifier::parse)
+            .ok_or("missing 'type'")?;
+        let ingredient =
+            || Ingredient::parse(json.get("ingredient").ok_or("missing 'ingredient'")?);
+        let cooking = |kind, default_time| -> Result<Recipe, String> {
+            Ok(Recipe::Cooking {
+                kind,
+                ingredient: ingredient()?,
+                result: RecipeResult::parse(json.get("result"))?,
+                experience: json
+                    .get("experience")
+                    .and_then(Value::as_f64)
+                    .unwrap_or(0.0) as f32,
+                cooking_time: json
+                    .get("cookingtime")
+                    .and_then(Value::as_u64)
+                    .map_or(default_time, |time| time as u32),
+            })
+        };
+
+        let recipe = match kind.0.as_str() {
+            "minecraft:crafting_shaped" => parse_shaped(json)?,
+            "minecraft:crafting_shapeless" => {
+                let ingredients = json
+                    .get("ingredients")
+                    .and_then(Value::as_array)
+                    .ok_or("missing 'ingredients' list")?
+                    .iter()
+                    .map(Ingredient::parse)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if ingredients.is_empty() || ingredients.len() > MAX_GRID_SIZE * MAX_GRID_SIZE {
+                    return Err(format!(
+                        "{} ingredients do not fit a grid",
+                        ingredients.len()
+                    ));
+                }
+                Recipe::Shapeless {
+                    ingredients,
+                    result: RecipeResult::parse(json.get("result"))?,
+                }
+            }
+            "minecraft:smelting" => cooking(CookingKind::Smelting, DEFAULT_SMELTING_TIME)?,
+            "minecraft:blasting" => cooking(CookingKind::Blasting, DEFAULT_BLASTING_TIME)?,
+            "minecraft:stonecutting" => {
+                let mut result = RecipeResult::parse(json.get("result"))?;
+                // Before 1.20.5 the count was next to the result
+                if let Some(count) = json.get("count").and_then(Value::as_u64) {
+                    result.count = count as u32;
+                }
+                Recipe::Stonecutting {
+                    ingredient: ingredient()?,
+                    result,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(recipe))
+    }
+
+    /// Items the recipe makes
+    pub fn result(&self) -> &RecipeResult {
+        match self {
+            Recipe::Shaped { result, .. }
+            | Recipe::Shapeless { result, .. }
+            | Recipe::Cooking { result, .. }
+            | Recipe::Stonecutting { result, .. } => result,
+        }
+    }
+}
+
+/// Parse the pattern and key of a shaped recipe
+fn parse_shaped(json: &Value) -> Result<Recipe, String> {
+    let pattern: Vec<&str> = json
+        .get("pattern")
+        .and_then(Value::as_array)
+        .ok_or("missing 'pattern'")?
+        .iter()
+        .map(|row| row.as_str().ok_or("pattern rows must be strings"))
+        .collect::<Result<_, _>>()?;
+    let height = pattern.len();
+    let width = pattern.first().map_or(0, |row| row.chars().count());
+    if !(1..=MAX_GRID_SIZE).contains(&height)
+        || !(1..=MAX_GRID_SIZE).contains(&width)
+        || pattern.iter().any(|row| row.chars().count() != width)
+    {
+        return Err("the pattern must be a rectangle of at most 3x3".to_string());
+    }
+
+    let key = json
+        .get("key")
+        .and_then(Value::as_object)
+        .ok_or("missing 'key'")?;
+    let mut ingredients = Vec::with_capacity(width * height);
+    for symbol in pattern.iter().flat_map(|row| row.chars()) {
+        if symbol == ' ' {
+            ingredients.push(None);
+            continue;
+        }
+        let ingredient = key
+            .get(symbol.encode_utf8(&mut [0; 4]) as &str)
+            .ok_or_else(|| format!("pattern symbol '{}' is not in the key", symbol))?;
+        ingredients.push(Some(Ingredient::parse(ingredient)?));
+    }
+
+    Ok(Recipe::Shaped {
+        width,
+        height,
+        ingredients,
+        result: RecipeResult::parse(json.get("result"))?,
+    })
+}
+
+/// Items in a crafting grid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CraftingGrid {
+    /// Grid width in slots
+    pub width: usize,
+    /// Item in each slot, row by row
+    pub slots: Vec<Option<Identifier>>,
+}
+
+impl CraftingGrid {
+    /// The smallest rectangle holding every item, as width, height and slots
+    fn trimmed(&self) -> Option<(usize, usize, Vec<Option<&Identifier>>)> {
+        let width = self.width.max(1);
+        let filled: Vec<(usize, usize)> = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_some())
+            .map(|(i, _)| (i % width, i / width))
+            .collect();
+        let (min_x, max_x) = (
+            filled.iter().map(|&(x, _)| x).min()?,
+            filled.iter().map(|&(x, _)| x).max()?,
+        );
+        let (min_y, max_y) = (
+            filled.iter().map(|&(_, y)| y).min()?,
+            filled.iter().map(|&(_, y)| y).max()?,
+        );
+        let slots = (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| (x, y)))
+            .map(|(x, y)| self.slots.get(y * width + x).and_then(Option::as_ref))
+            .collect();
+        Some((max_x - min_x + 1, max_y - min_y + 1, slots))
+    }
+
+    /// Items in the grid, ignoring their arrangement
+    fn items(&self) -> Vec<&Identifier> {
+        self.slots.iter().flatten().collect()
+    }
+}
+
+/// Recipes of the enabled data packs
+#[derive(Debug, Clone, Default)]
+pub struct RecipeRegistry {
+    /// Recipes by ID
+    recipes: BTreeMap<Identifier, Recipe>,
+}
+
+impl RecipeRegistry {
+    /// Parse recipe files
+    ///
+    /// Returns the registry and the recipes that could not be parsed.
+    /// Recipes of unsupported types are left out silently.
+    pub fn parse(files: &BTreeMap<Identifier, Value>) -> (Self, Vec<String>) {
+        let mut registry = Self::default();
+        let mut errors = Vec::new();
+        for (id, json) in files {
+            match Recipe::parse(json) {
+                Ok(Some(recipe)) => {
+                    registry.recipes.insert(id.clone(), recipe);
+                }
+                Ok(None) => {}
+                Err(e) => errors.push(format!("Recipe {}: {}", id, e)),
+            }
+        }
+        (registry, errors)
+    }
+
+    /// Get a recipe
+    pub fn get(&self, id: &Identifier) -> Option<&Recipe> {
+        self.recipes.get(id)
+    }
+
+    /// Every recipe, in ID order
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &Recipe)> {
+        self.recipes.iter()
+    }
+
+    /// Number of recipes
+    pub fn len(&self) -> usize {
+        self.recipes.len()
+    }
+
+    /// Check whether there are no recipes
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+    }
+
+    /// Find the crafting recipe matching a grid
+    pub fn craft(&self, grid: &CraftingGrid, tags: &TagRegistry) -> Option<(&Identifier, &Recipe)> {
+        let (width, height, slots) = grid.trimmed()?;
+        let items = grid.items();
+        self.recipes.iter().find(|(_, recipe)| match recipe {
+            Recipe::Shaped {
+                width: recipe_width,
+                height: recipe_height,
+                ingredients,
+                ..
+            } => {
+                (width, height) == (*recipe_width, *recipe_height)
+                    && (fits_shape(ingredients, &slots, width, false, tags)
+                        || fits_shape(ingredients, &slots, width, true, tags))
+            }
+            Recipe::Shapeless { ingredients, .. } => fits_shapeless(ingredients, &items, tags),
+            _ => false,
+        })
+    }
+
+    /// Find the recipe a furnace or blast furnace uses for an item
+    pub fn cook(
+        &self,
+        kind: CookingKind,
+        input: &Identifier,
+        tags: &TagRegistry,
+    ) -> Option<(&Identifier, &Recipe)> {
+        self.recipes.iter().find(|(_, recipe)| {
+            matches!(recipe, Recipe::Cooking { kind: recipe_kind, ingredient, .. }
+                if *recipe_kind == kind && ingredient.matches(input, tags))
+        })
+    }
+
+    /// Stonecutter recipes for an item, in ID order
+    pub fn stonecutting<'a>(
+        &'a self,
+        input: &'a Identifier,
+        tags: &'a TagRegistry,
+    ) -> impl Iterator<Item = (&'a Identifier, &'a Recipe)> {
+        self.recipes.iter().filter(move |(_, recipe)| {
+            matches!(recipe, Recipe::Stonecutting { ingredient, .. } if ingredient.matches(input, tags))
+        })
+    }
+
+    /// Build the packet telling clients about furnace inputs and stonecutter recipes
+    ///
+    /// Items `item_id` has no protocol ID for are left out.
+    pub fn update_packet(
+        &self,
+        tags: &TagRegistry,
+        item_id: impl Fn(&Identifier) -> Option<u32>,
+    ) -> UpdateRecipesPacket {
+        let ids = |items: &[Identifier]| -> Vec<VarInt> {
+            let mut ids: Vec<VarInt> = items
+                .iter()
+                .filter_map(&item_id)
+                .map(|id| VarInt(id as i32))
+                .collect();
+            ids.sort_by_key(|id| id.0);
+            ids.dedup_by_key(|id| id.0);
+            ids
+        };
+        let cooking_inputs = |kind: CookingKind| -> Vec<Identifier> {
+            self.recipes
+                .values()
+                .filter_map(|recipe| match recipe {
+                    Recipe::Cooking {
+                        kind: recipe_kind,
+                        ingredient,
+                        ..
+                    } if *recipe_kind == kind => Some(ingredient.items(tags)),
+                    _ => None,
+                })
+                .flatten()
+                .cloned()
+                .collect()
+        };
+
+        let property_sets = [
+            (PropertySet::FURNACE_INPUT, CookingKind::Smelting),
+            (PropertySet::BLAST_FURNACE_INPUT, CookingKind::Blasting),
+        ]
+        .into_iter()
+        .map(|(id, kind)| PropertySet {
+            id: Identifier(id.to_string()),
+            items: ids(&cooking_inputs(kind)),
+        })
+        .collect();
+
+        let stonecutter_recipes = self
+            .recipes
+            .values()
+            .filter_map(|recipe| match recipe {
+                Recipe::Stonecutting { ingredient, result } => Some(StonecutterEntry {
+                    ingredient: IdSet::Ids(ids(ingredient.items(tags))),
+                    result_item: VarInt(item_id(&result.item)? as i32),
+                    result_count: VarInt(result.count as i32),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        UpdateRecipesPacket {
+            property_sets,
+            stonecutter_recipes,
+        }
+    }
+}
+
+/// Check a trimmed grid against a shaped pattern, optionally mirrored
+fn fits_shape(
+    ingredients: &[Option<Ingredient>],
+    slots: &[Option<&Identifier>],
+    width: usize,
+    mirrored: bool,
+    tags: &TagRegistry,
+) -> bool {
+    slots.iter().enumerate().all(|(i, slot)| {
+        let (x, y) = (i % width, i / width);
+        let x = if mirrored { width - 1 - x } else { x };
+        match (&ingredients[y * width + x], slot) {
+            (None, None) => true,
+            (Some(ingredient), Some(item)) => ingredient.matches(item, tags),
+            _ => false,
+        }
+    })
+}
+
+/// Check whether every item is used by exactly one ingredient
+fn fits_shapeless(ingredients: &[Ingredient], items: &[&Identifier], tags: &TagRegistry) -> bool {
+    /// Try to give each remaining item an unused ingredient
+    fn assign(
+        ingredients: &[Ingredient],
+        items: &[&Identifier],
+        used: &mut Vec<bool>,
+        tags: &TagRegistry,
+    ) -> bool {
+        let Some((item, rest)) = items.split_first() else {
+            return true;
+        };
+        for (i, ingredient) in ingredients.iter().enumerate() {
+            if !used[i] && ingredient.matches(item, tags) {
+                used[i] = true;
+                if assign(ingredients, rest, used, tags) {
+                    return true;
+                }
+                used[i] = false;
+            }
+        }
+        false
+    }
+
+    ingredients.len() == items.len()
+        && assign(
+            ingredients,
+            items,
+            &mut vec![false; ingredients.len()],
+            tags,
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn registry() -> RecipeRegistry {
+        let files = BTreeMap::from([
+            (
+                Identifier::parse("stick"),
+                json!({
+                    "type": "minecraft:crafting_shaped",
+                    "pattern": ["#", "#"],
+                    "key": { "#": "#minecraft:planks" },
+                    "result": { "id": "minecraft:stick", "count": 4 }
+                }),
+            ),
+            (
+                Identifier::parse("flint_and_steel"),
+                json!({
+                    "type": "minecraft:crafting_shapeless",
+                    "ingredients": ["minecraft:iron_ingot", { "item": "minecraft:flint" }],
+                    "result": { "id": "minecraft:flint_and_steel" }
+                }),
+            ),
+            (
+                Identifier::parse("iron_ingot_from_blasting"),
+                json!({
+                    "type": "minecraft:blasting",
+                    "ingredient": ["minecraft:raw_iron", "minecraft:iron_ore"],
+                    "result": { "id": "minecraft:iron_ingot" },
+                    "experience": 0.7
+                }),
+            ),
+            (
+                Identifier::parse("stone_slab"),
+                json!({
+                    "type": "minecraft:stonecutting",
+                    "ingredient": "minecraft:stone",
+                    "result": { "id": "minecraft:stone_slab", "count": 2 }
+                }),
+            ),
+            (
+                Identifier::parse("armor_dye"),
+                json!({ "type": "minecraft:crafting_special_armordye" }),
+            ),
+            (
+                Identifier::parse("broken"),
+                json!({ "type": "minecraft:crafting_shaped", "pattern": ["ab"], "key": {} }),
+            ),
+        ]);
+        let (registry, errors) = RecipeRegistry::parse(&files);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("'a' is not in the key"));
+        registry
+    }
+
+    fn planks() -> TagRegistry {
+        let mut merged = BTreeMap::new();
+        merged.insert(
+            ("item".to_string(), Identifier::parse("planks")),
+            vec![super::super::TagEntry {
+                id: "minecraft:oak_planks".to_string(),
+                required: true,
+            }],
+        );
+        TagRegistry::resolve(&merged).0
+    }
+
+    #[test]
+    fn test_match_recipes() {
+        let registry = registry();
+        let tags = planks();
+        assert_eq!(registry.len(), 4);
+
+        let grid = |slots: [Option<&str>; 9]| CraftingGrid {
+            width: 3,
+            slots: slots.map(|slot| slot.map(Identifier::parse)).to_vec(),
+        };
+        let sticks = grid([
+            None,
+            None,
+            Some("oak_planks"),
+            None,
+            None,
+            Some("oak_planks"),
+            None,
+            None,
+            None,
+        ]);
+        let (id, recipe) = registry.craft(&sticks, &tags).unwrap();
+        assert_eq!(id, &Identifier::parse("stick"));
+        assert_eq!(recipe.result().count, 4);
+        let crooked = grid([
+            Some("oak_planks"),
+            None,
+            None,
+            None,
+            Some("oak_planks"),
+            None,
+            None,
+            None,
+            None,
+        ]);
+        assert!(registry.craft(&crooked, &tags).is_none());
+        let lighter = grid([
+            Some("flint"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("iron_ingot"),
+        ]);
+        assert_eq!(
+            registry.craft(&lighter, &tags).unwrap().0,
+            &Identifier::parse("flint_and_steel")
+        );
+
+        let ore = Identifier::parse("iron_ore");
+        let (_, blasting) = registry.cook(CookingKind::Blasting, &ore, &tags).unwrap();
+        assert!(matches!(
+            blasting,
+            Recipe::Cooking {
+                cooking_time: DEFAULT_BLASTING_TIME,
+                ..
+            }
+        ));
+        assert!(registry.cook(CookingKind::Smelting, &ore, &tags).is_none());
+        let stone = Identifier::parse("stone");
+        assert_eq!(registry.stonecutting(&stone, &tags).count(), 1);
+
+        let item_ids = [
+            "minecraft:raw_iron",
+            "minecraft:stone",
+            "minecraft:stone_slab",
+        ];
+        let packet = registry.update_packet(&tags, |item| {
+            item_ids
+                .iter()
+                .position(|id| *id == item.0)
+                .map(|i| i as u32)
+        });
+        assert!(packet.property_sets[0].items.is_empty());
+        assert_eq!(packet.property_sets[1].items, [VarInt(0)]);
+        assert_eq!(
+            packet.stonecutter_recipes,
+            [StonecutterEntry {
+                ingredient: IdSet::Ids(vec![VarInt(1)]),
+                result_item: VarInt(2),
+                result_count: VarInt(2),
+            }]
+        );
+    }
+}
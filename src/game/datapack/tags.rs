@@ -0,0 +1,222 @@
+//! Tags
+//!
+//! A tag names a set of registry entries, such as every log block. Tag
+//! files list entries and other tags (prefixed with `#`); files for the
+//! same tag from several packs add up unless a later one sets `replace`.
+//! The [`TagRegistry`] holds every tag with its references expanded.
+
+use crate::protocol::types::Identifier;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// An entry of a tag file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagEntry {
+    /// Entry ID, or tag ID prefixed with `#`
+    pub id: String,
+    /// Whether the tag fails to load when the entry is missing
+    pub required: bool,
+}
+
+impl TagEntry {
+    /// The referenced tag, if this entry is a tag reference
+    pub fn tag(&self) -> Option<Identifier> {
+        self.id.strip_prefix('#').map(Identifier::parse)
+    }
+}
+
+/// A tag file of one data pack
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TagFile {
+    /// Whether the file replaces the entries of lower packs
+    pub replace: bool,
+    /// Entries and tag references, in order
+    pub values: Vec<TagEntry>,
+}
+
+impl TagFile {
+    /// Parse a tag file
+    pub fn parse(json: &Value) -> Result<Self, String> {
+        let replace = json
+            .get("replace")
+            .map_or(Some(false), Value::as_bool)
+            .ok_or("'replace' is not a boolean")?;
+        let values = json
+            .get("values")
+            .and_then(Value::as_array)
+            .ok_or("missing 'values' list")?
+            .iter()
+            .map(|value| match value {
+                Value::String(id) => Ok(TagEntry {
+                    id: id.clone(),
+                    required: true,
+                }),
+                Value::Object(entry) => Ok(TagEntry {
+                    id: entry
+                        .get("id")
+                        .and_then(Value::as_str)
+                        .ok_or("entry without an 'id'")?
+                        .to_string(),
+                    required: entry
+                        .get("required")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(true),
+                }),
+                _ => Err("entries must be strings or objects"),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { replace, values })
+    }
+
+    /// Stack this file on top of the entries of lower packs
+    pub fn merge_into(&self, entries: &mut Vec<TagEntry>) {
+        if self.replace {
+            entries.clear();
+        }
+        entries.extend(self.values.iter().cloned());
+    }
+}
+
+/// Tags of every registry, with references expanded
+#[derive(Debug, Clone, Default)]
+pub struct TagRegistry {
+    /// Entries of each tag by registry (`block`, `item`, ...) and tag ID
+    tags: HashMap<String, BTreeMap<Identifier, Vec<Identifier>>>,
+}
+
+impl TagRegistry {
+    /// Expand merged tag entries
+    ///
+    /// Tags referring to a missing tag without marking it optional, or to
+    /// themselves, are left out and returned as errors.
+    pub fn resolve(merged: &BTreeMap<(String, Identifier), Vec<TagEntry>>) -> (Self, Vec<String>) {
+        let mut registry = Self::default();
+        let mut errors = Vec::new();
+        for (registry_name, tag) in merged.keys() {
+            let mut visiting = BTreeSet::new();
+            match expand(merged, registry_name, tag, &mut visiting) {
+                Ok(values) => {
+                    registry
+                        .tags
+                        .entry(registry_name.clone())
+                        .or_default()
+                        .insert(tag.clone(), values);
+                }
+                Err(e) => errors.push(format!("Tag {} #{}: {}", registry_name, tag, e)),
+            }
+        }
+        (registry, errors)
+    }
+
+    /// Entries of a tag
+    pub fn get(&self, registry: &str, tag: &Identifier) -> Option<&[Identifier]> {
+        self.tags.get(registry)?.get(tag).map(Vec::as_slice)
+    }
+
+    /// Check whether a tag contains an entry
+    pub fn contains(&self, registry: &str, tag: &Identifier, entry: &Identifier) -> bool {
+        self.get(registry, tag)
+            .is_some_and(|entries| entries.contains(entry))
+    }
+
+    /// Tags of a registry, in ID order
+    pub fn tags(&self, registry: &str) -> impl Iterator<Item = (&Identifier, &[Identifier])> {
+        self.tags
+            .get(registry)
+            .into_iter()
+            .flatten()
+            .map(|(tag, entries)| (tag, entries.as_slice()))
+    }
+
+    /// Total number of tags
+    pub fn len(&self) -> usize {
+        self.tags.values().map(BTreeMap::len).sum()
+    }
+
+    /// Check whether there are no tags
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Expand the entries of a tag, following references depth first
+fn expand(
+    merged: &BTreeMap<(String, Identifier), Vec<TagEntry>>,
+    registry: &str,
+    tag: &Identifier,
+    visiting: &mut BTreeSet<Identifier>,
+) -> Result<Vec<Identifier>, String> {
+    if !visiting.insert(tag.clone()) {
+        return Err(format!("#{} refers to itself", tag));
+    }
+    let mut values = Vec::new();
+    let key = (registry.to_string(), tag.clone());
+    for entry in merged.get(&key).into_iter().flatten() {
+        let expanded = match entry.tag() {
+            Some(reference) if merged.contains_key(&(registry.to_string(), reference.clone())) => {
+                expand(merged, registry, &reference, visiting)?
+            }
+            Some(reference) if entry.required => {
+                return Err(format!("missing tag #{}", reference));
+            }
+            Some(_) => Vec::new(),
+            None => vec![Identifier::parse(&entry.id)],
+        };
+        for value in expanded {
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+    }
+    visiting.remove(tag);
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_tags() {
+        let logs = TagFile::parse(&json!({ "values": ["oak_log", "#minecraft:birch"] })).unwrap();
+        let birch = TagFile::parse(&json!({ "values": ["birch_log"] })).unwrap();
+        let extra = TagFile::parse(&json!({
+            "values": [{ "id": "#missing", "required": false }, "minecraft:birch_wood"]
+        }))
+        .unwrap();
+        let broken = TagFile::parse(&json!({ "values": ["#nowhere"] })).unwrap();
+        assert!(TagFile::parse(&json!({ "values": [1] })).is_err());
+
+        let mut merged = BTreeMap::new();
+        let key = |tag: &str| ("block".to_string(), Identifier::parse(tag));
+        for (tag, file) in [
+            ("logs", &logs),
+            ("birch", &birch),
+            ("birch", &extra),
+            ("broken", &broken),
+        ] {
+            file.merge_into(merged.entry(key(tag)).or_default());
+        }
+
+        let (tags, errors) = TagRegistry::resolve(&merged);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("missing tag #minecraft:nowhere"));
+        let logs = tags.get("block", &Identifier::parse("logs")).unwrap();
+        let names: Vec<&str> = logs.iter().map(|id| id.0.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "minecraft:oak_log",
+                "minecraft:birch_log",
+                "minecraft:birch_wood"
+            ]
+        );
+
+        let replacement = TagFile::parse(&json!({ "replace": true, "values": [] })).unwrap();
+        replacement.merge_into(merged.get_mut(&key("logs")).unwrap());
+        let (tags, _) = TagRegistry::resolve(&merged);
+        assert_eq!(tags.get("block", &Identifier::parse("logs")), Some(&[][..]));
+    }
+}
@@ -0,0 +1,111 @@
+//! World difficulty
+//!
+//! This module defines the difficulty levels used by worlds and the
+//! `difficulty` server property, and how they scale the damage mobs deal
+//! and the harm hunger does.
+
+use crate::error::ServerError;
+use std::fmt;
+use std::str::FromStr;
+
+/// World difficulty level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    /// No hostile mobs, no hunger damage
+    Peaceful = 0,
+    /// Easy difficulty
+    #[default]
+    Easy = 1,
+    /// Normal difficulty
+    Normal = 2,
+    /// Hard difficulty
+    Hard = 3,
+}
+
+impl Difficulty {
+    /// All difficulty levels, from easiest to hardest
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Peaceful,
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+    ];
+
+    /// Parse a difficulty from its lowercase name or numeric ID
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "peaceful" | "0" => Some(Difficulty::Peaceful),
+            "easy" | "1" => Some(Difficulty::Easy),
+            "normal" | "2" => Some(Difficulty::Normal),
+            "hard" | "3" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    /// Get the lowercase name of this difficulty
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Peaceful => "peaceful",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// Get the protocol ID of this difficulty
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Scale damage dealt by a mob, as vanilla does
+    ///
+    /// Mobs deal no damage in peaceful, a bit more than half in easy and
+    /// half as much again in hard.
+    pub fn scale_mob_damage(&self, damage: f32) -> f32 {
+        match self {
+            Difficulty::Peaceful => 0.0,
+            Difficulty::Easy => (damage / 2.0 + 1.0).min(damage),
+            Difficulty::Normal => damage,
+            Difficulty::Hard => damage * 1.5,
+        }
+    }
+
+    /// Health starvation stops at, or `None` if players do not starve
+    pub fn starvation_floor(&self) -> Option<f32> {
+        match self {
+            Difficulty::Peaceful => None,
+            Difficulty::Easy => Some(10.0),
+            Difficulty::Normal => Some(1.0),
+            Difficulty::Hard => Some(0.0),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = ServerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s)
+            .ok_or_else(|| ServerError::Configuration(format!("Unknown difficulty '{}'", s)))
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mob_damage_scaling() {
+        assert_eq!(Difficulty::Peaceful.scale_mob_damage(6.0), 0.0);
+        assert_eq!(Difficulty::Easy.scale_mob_damage(6.0), 4.0);
+        assert_eq!(Difficulty::Easy.scale_mob_damage(1.0), 1.0);
+        assert_eq!(Difficulty::Normal.scale_mob_damage(6.0), 6.0);
+        assert_eq!(Difficulty::Hard.scale_mob_damage(6.0), 9.0);
+    }
+}
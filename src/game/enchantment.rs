@@ -0,0 +1,476 @@
+//! Enchantments
+//!
+//! The enchantments the server knows are fixed, each with a maximum level,
+//! the items it can be applied to and the enchantments it cannot be
+//! combined with. An item's enchantments are stored in its components and
+//! change how fast it breaks blocks, how much damage it deals and how much
+//! damage armor absorbs.
+//!
+//! Protocol IDs follow the vanilla enchantment registry, which is sorted
+//! by name.
+
+use crate::game::entity::MobType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Most damage reduction protection can give, out of 25
+const MAX_PROTECTION: u32 = 20;
+
+/// An enchantment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Enchantment {
+    /// Reduces all damage
+    Protection,
+    /// Reduces fire damage
+    FireProtection,
+    /// Reduces explosion damage
+    BlastProtection,
+    /// Reduces projectile damage
+    ProjectileProtection,
+    /// Reduces fall damage
+    FeatherFalling,
+    /// Increases melee damage
+    Sharpness,
+    /// Increases melee damage against undead mobs
+    Smite,
+    /// Increases melee damage against arthropods
+    BaneOfArthropods,
+    /// Increases knockback
+    Knockback,
+    /// Sets the target on fire
+    FireAspect,
+    /// Increases mob drops
+    Looting,
+    /// Increases mining speed
+    Efficiency,
+    /// Mined blocks drop themselves
+    SilkTouch,
+    /// Increases block drops
+    Fortune,
+    /// Makes the item lose durability less often
+    Unbreaking,
+    /// Repairs the item with experience
+    Mending,
+}
+
+/// What an enchantment is and where it applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnchantmentDefinition {
+    /// Enchantment identifier
+    pub name: &'static str,
+    /// Protocol ID
+    pub protocol_id: i32,
+    /// Highest level
+    pub max_level: u8,
+    /// Items it can be applied to
+    pub applies_to: ItemCategory,
+    /// Enchantments it cannot be combined with
+    pub incompatible: &'static [Enchantment],
+}
+
+/// Kinds of items enchantments apply to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemCategory {
+    /// Helmets, chestplates, leggings and boots
+    Armor,
+    /// Boots
+    FootArmor,
+    /// Swords
+    Sword,
+    /// Swords and axes
+    SharpWeapon,
+    /// Pickaxes, shovels, axes and hoes
+    MiningTool,
+    /// Items that lose durability
+    Durability,
+}
+
+impl ItemCategory {
+    /// Whether an item belongs to the category, by item name
+    pub fn contains(self, item: &str) -> bool {
+        let ends_with_any = |suffixes: &[&str]| suffixes.iter().any(|s| item.ends_with(s));
+        match self {
+            ItemCategory::Armor => {
+                ends_with_any(&["_helmet", "_chestplate", "_leggings", "_boots"])
+            }
+            ItemCategory::FootArmor => item.ends_with("_boots"),
+            ItemCategory::Sword => item.ends_with("_sword"),
+            ItemCategory::SharpWeapon => ends_with_any(&["_sword", "_axe"]),
+            ItemCategory::MiningTool => ends_with_any(&["_pickaxe", "_shovel", "_axe", "_hoe"]),
+            ItemCategory::Durability => {
+                ItemCategory::Armor.contains(item)
+                    || ItemCategory::SharpWeapon.contains(item)
+                    || ItemCategory::MiningTool.contains(item)
+            }
+        }
+    }
+}
+
+/// Enchantments of each protection type, which exclude each other
+const PROTECTIONS: [Enchantment; 4] = [
+    Enchantment::Protection,
+    Enchantment::FireProtection,
+    Enchantment::BlastProtection,
+    Enchantment::ProjectileProtection,
+];
+
+/// Enchantments adding melee damage, which exclude each other
+const DAMAGE: [Enchantment; 3] = [
+    Enchantment::Sharpness,
+    Enchantment::Smite,
+    Enchantment::BaneOfArthropods,
+];
+
+/// Enchantments changing block drops, which exclude each other
+const MINING_DROPS: [Enchantment; 2] = [Enchantment::SilkTouch, Enchantment::Fortune];
+
+/// Shorthand for the definitions table
+const fn define(
+    name: &'static str,
+    protocol_id: i32,
+    max_level: u8,
+    applies_to: ItemCategory,
+    incompatible: &'static [Enchantment],
+) -> EnchantmentDefinition {
+    EnchantmentDefinition {
+        name,
+        protocol_id,
+        max_level,
+        applies_to,
+        incompatible,
+    }
+}
+
+impl Enchantment {
+    /// Number of enchantments
+    pub const COUNT: usize = 16;
+
+    /// Every enchantment
+    pub const ALL: [Enchantment; Self::COUNT] = [
+        Enchantment::Protection,
+        Enchantment::FireProtection,
+        Enchantment::BlastProtection,
+        Enchantment::ProjectileProtection,
+        Enchantment::FeatherFalling,
+        Enchantment::Sharpness,
+        Enchantment::Smite,
+        Enchantment::BaneOfArthropods,
+        Enchantment::Knockback,
+        Enchantment::FireAspect,
+        Enchantment::Looting,
+        Enchantment::Efficiency,
+        Enchantment::SilkTouch,
+        Enchantment::Fortune,
+        Enchantment::Unbreaking,
+        Enchantment::Mending,
+    ];
+
+    /// Definition of the enchantment
+    pub fn definition(self) -> EnchantmentDefinition {
+        use ItemCategory::*;
+        match self {
+            Enchantment::Protection => define("minecraft:protection", 27, 4, Armor, &PROTECTIONS),
+            Enchantment::FireProtection => {
+                define("minecraft:fire_protection", 11, 4, Armor, &PROTECTIONS)
+            }
+            Enchantment::BlastProtection => {
+                define("minecraft:blast_protection", 3, 4, Armor, &PROTECTIONS)
+            }
+            Enchantment::ProjectileProtection => define(
+                "minecraft:projectile_protection",
+                26,
+                4,
+                Armor,
+                &PROTECTIONS,
+            ),
+            Enchantment::FeatherFalling => {
+                define("minecraft:feather_falling", 9, 4, FootArmor, &[])
+            }
+            Enchantment::Sharpness => define("minecraft:sharpness", 32, 5, SharpWeapon, &DAMAGE),
+            Enchantment::Smite => define("minecraft:smite", 34, 5, SharpWeapon, &DAMAGE),
+            Enchantment::BaneOfArthropods => {
+                define("minecraft:bane_of_arthropods", 1, 5, SharpWeapon, &DAMAGE)
+            }
+            Enchantment::Knockback => define("minecraft:knockback", 17, 2, Sword, &[]),
+            Enchantment::FireAspect => define("minecraft:fire_aspect", 10, 2, Sword, &[]),
+            Enchantment::Looting => define("minecraft:looting", 18, 3, Sword, &[]),
+            Enchantment::Efficiency => define("minecraft:efficiency", 8, 5, MiningTool, &[]),
+            Enchantment::SilkTouch => {
+                define("minecraft:silk_touch", 33, 1, MiningTool, &MINING_DROPS)
+            }
+            Enchantment::Fortune => define("minecraft:fortune", 13, 3, MiningTool, &MINING_DROPS),
+            Enchantment::Unbreaking => define("minecraft:unbreaking", 39, 3, Durability, &[]),
+            Enchantment::Mending => define("minecraft:mending", 22, 1, Durability, &[]),
+        }
+    }
+
+    /// Look up an enchantment by identifier
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|enchantment| enchantment.definition().name == name)
+    }
+
+    /// Look up an enchantment by protocol ID
+    pub fn from_protocol_id(id: i32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|enchantment| enchantment.definition().protocol_id == id)
+    }
+
+    /// Whether the enchantment can be applied to an item, by item name
+    pub fn applies_to(self, item: &str) -> bool {
+        self.definition().applies_to.contains(item)
+    }
+
+    /// Whether two different enchantments cannot be on the same item
+    pub fn is_incompatible_with(self, other: Enchantment) -> bool {
+        self != other && self.definition().incompatible.contains(&other)
+    }
+
+    /// Index into level arrays
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Why an enchantment cannot be added to an item
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnchantError {
+    /// The item is not something the enchantment applies to
+    #[error("The enchantment cannot be applied to this item")]
+    WrongItem,
+    /// The level is zero or above the maximum
+    #[error("Level {0} is not a valid level of the enchantment")]
+    InvalidLevel(u8),
+    /// The item has an enchantment that excludes this one
+    #[error("The enchantment is incompatible with another enchantment on the item")]
+    Incompatible(Enchantment),
+}
+
+/// The enchantments on an item, with their levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<String, u8>", into = "BTreeMap<String, u8>")]
+pub struct Enchantments {
+    /// Level of each enchantment, zero if absent
+    levels: [u8; Enchantment::COUNT],
+}
+
+impl Enchantments {
+    /// No enchantments
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Level of an enchantment, zero if absent
+    pub fn level(&self, enchantment: Enchantment) -> u8 {
+        self.levels[enchantment.index()]
+    }
+
+    /// Set the level of an enchantment, removing it at zero
+    ///
+    /// No checks are made, as commands and plugins may create items vanilla
+    /// enchanting cannot.
+    pub fn set(&mut self, enchantment: Enchantment, level: u8) {
+        self.levels[enchantment.index()] = level;
+    }
+
+    /// Add an enchantment to an item as enchanting would
+    pub fn add(
+        &mut self,
+        item: &str,
+        enchantment: Enchantment,
+        level: u8,
+    ) -> Result<(), EnchantError> {
+        if !enchantment.applies_to(item) {
+            return Err(EnchantError::WrongItem);
+        }
+        if level == 0 || level > enchantment.definition().max_level {
+            return Err(EnchantError::InvalidLevel(level));
+        }
+        if let Some((other, _)) = self
+            .iter()
+            .find(|&(other, _)| enchantment.is_incompatible_with(other))
+        {
+            return Err(EnchantError::Incompatible(other));
+        }
+        self.set(enchantment, level);
+        Ok(())
+    }
+
+    /// Enchantments present, with their levels
+    pub fn iter(&self) -> impl Iterator<Item = (Enchantment, u8)> + '_ {
+        Enchantment::ALL
+            .into_iter()
+            .map(|enchantment| (enchantment, self.level(enchantment)))
+            .filter(|&(_, level)| level > 0)
+    }
+
+    /// Whether there are no enchantments
+    pub fn is_empty(&self) -> bool {
+        self.levels.iter().all(|&level| level == 0)
+    }
+
+    /// Number of enchantments present
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+}
+
+impl From<BTreeMap<String, u8>> for Enchantments {
+    fn from(saved: BTreeMap<String, u8>) -> Self {
+        let mut enchantments = Self::new();
+        for (name, level) in saved {
+            if let Some(enchantment) = Enchantment::from_name(&name) {
+                enchantments.set(enchantment, level);
+            }
+        }
+        enchantments
+    }
+}
+
+impl From<Enchantments> for BTreeMap<String, u8> {
+    fn from(enchantments: Enchantments) -> Self {
+        enchantments
+            .iter()
+            .map(|(enchantment, level)| (enchantment.definition().name.to_string(), level))
+            .collect()
+    }
+}
+
+/// Causes of damage that protection enchantments treat differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageKind {
+    /// Melee attacks and anything else
+    Generic,
+    /// Explosions
+    Explosion,
+    /// Fire and lava
+    Fire,
+    /// Arrows and other projectiles
+    Projectile,
+    /// Falling
+    Fall,
+}
+
+/// Mining speed added by efficiency to a tool that suits the block
+pub fn efficiency_bonus(level: u8) -> f32 {
+    if level == 0 {
+        0.0
+    } else {
+        f32::from(level) * f32::from(level) + 1.0
+    }
+}
+
+/// Melee damage added by a weapon's enchantments against a mob
+pub fn attack_bonus(weapon: &Enchantments, target: Option<MobType>) -> f32 {
+    let sharpness = weapon.level(Enchantment::Sharpness);
+    let mut bonus = if sharpness > 0 {
+        0.5 * f32::from(sharpness) + 0.5
+    } else {
+        0.0
+    };
+    if target.is_some_and(|mob| mob.is_undead()) {
+        bonus += 2.5 * f32::from(weapon.level(Enchantment::Smite));
+    }
+    if target.is_some_and(|mob| mob.is_arthropod()) {
+        bonus += 2.5 * f32::from(weapon.level(Enchantment::BaneOfArthropods));
+    }
+    bonus
+}
+
+/// Damage left after the protection enchantments on worn armor
+///
+/// Each piece adds to the protection factor by level, more for the
+/// specialised kinds against their kind of damage. Every point of the
+/// factor, up to 20, absorbs 4% of the damage.
+pub fn apply_protection<'a>(
+    damage: f32,
+    kind: DamageKind,
+    armor: impl IntoIterator<Item = &'a Enchantments>,
+) -> f32 {
+    let factor: u32 = armor
+        .into_iter()
+        .map(|piece| {
+            let level = |enchantment| u32::from(piece.level(enchantment));
+            let specialised = match kind {
+                DamageKind::Generic => 0,
+                DamageKind::Explosion => 2 * level(Enchantment::BlastProtection),
+                DamageKind::Fire => 2 * level(Enchantment::FireProtection),
+                DamageKind::Projectile => 2 * level(Enchantment::ProjectileProtection),
+                DamageKind::Fall => 3 * level(Enchantment::FeatherFalling),
+            };
+            level(Enchantment::Protection) + specialised
+        })
+        .sum();
+    damage * (25 - factor.min(MAX_PROTECTION)) as f32 / 25.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions() {
+        for enchantment in Enchantment::ALL {
+            let definition = enchantment.definition();
+            assert_eq!(Enchantment::from_name(definition.name), Some(enchantment));
+            assert_eq!(
+                Enchantment::from_protocol_id(definition.protocol_id),
+                Some(enchantment)
+            );
+            assert!(!enchantment.is_incompatible_with(enchantment));
+        }
+    }
+
+    #[test]
+    fn test_add_checks_items_levels_and_conflicts() {
+        let mut sword = Enchantments::new();
+        assert_eq!(
+            sword.add("minecraft:diamond_sword", Enchantment::Sharpness, 5),
+            Ok(())
+        );
+        assert_eq!(
+            sword.add("minecraft:diamond_sword", Enchantment::Smite, 1),
+            Err(EnchantError::Incompatible(Enchantment::Sharpness))
+        );
+        assert_eq!(
+            sword.add("minecraft:diamond_sword", Enchantment::Looting, 4),
+            Err(EnchantError::InvalidLevel(4))
+        );
+        assert_eq!(
+            sword.add("minecraft:diamond_sword", Enchantment::Efficiency, 1),
+            Err(EnchantError::WrongItem)
+        );
+        assert_eq!(
+            sword.add("minecraft:diamond_sword", Enchantment::Unbreaking, 3),
+            Ok(())
+        );
+        assert_eq!(sword.len(), 2);
+
+        let saved = serde_json::to_string(&sword).unwrap();
+        assert_eq!(
+            saved,
+            r#"{"minecraft:sharpness":5,"minecraft:unbreaking":3}"#
+        );
+        assert_eq!(serde_json::from_str::<Enchantments>(&saved).unwrap(), sword);
+    }
+
+    #[test]
+    fn test_effects() {
+        let mut weapon = Enchantments::new();
+        weapon.set(Enchantment::Smite, 2);
+        assert_eq!(attack_bonus(&weapon, Some(MobType::Zombie)), 5.0);
+        assert_eq!(attack_bonus(&weapon, Some(MobType::Cow)), 0.0);
+        weapon.set(Enchantment::Sharpness, 1);
+        assert_eq!(attack_bonus(&weapon, None), 1.0);
+        assert_eq!(efficiency_bonus(3), 10.0);
+
+        let mut armor = Enchantments::new();
+        armor.set(Enchantment::BlastProtection, 4);
+        let pieces = [armor; 4];
+        assert_eq!(apply_protection(10.0, DamageKind::Generic, &pieces), 10.0);
+        assert_eq!(apply_protection(10.0, DamageKind::Explosion, &pieces), 2.0);
+    }
+}
@@ -0,0 +1,209 @@
+//! Animal breeding
+//!
+//! Feeding an adult animal its favourite food puts it in love mode. Two
+//! animals of the same kind in love close to each other breed: a baby
+//! spawns between them and both parents need a few minutes before they
+//! can breed again. Babies grow up after a game day; feeding them speeds
+//! that up.
+
+use super::mob::MobEntity;
+use super::{Entity, EntityId, EntityManager, EntityPosition, MobType};
+use thiserror::Error;
+
+/// Ticks an animal stays in love mode
+pub const LOVE_TICKS: u32 = 600;
+
+/// Ticks after breeding until a parent may breed again
+pub const BREEDING_COOLDOWN: i32 = 6000;
+
+/// Age of a newborn baby; it grows up once the age reaches 0
+pub const BABY_AGE: i32 = -24000;
+
+/// Furthest apart two animals in love find each other
+pub const PARTNER_DISTANCE: f64 = 8.0;
+
+/// Furthest from an animal a player can feed it, with some leeway
+pub const MAX_FEED_DISTANCE: f64 = 6.0;
+
+/// Reasons an animal cannot be fed
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreedError {
+    /// The entity does not exist or does not breed
+    #[error("That cannot be bred")]
+    NotAnAnimal,
+    /// The animal does not eat the item
+    #[error("The animal does not eat that")]
+    NotFood,
+    /// The animal is in love already or recovers from breeding
+    #[error("The animal is not ready to breed")]
+    NotReady,
+    /// The player is too far away to feed the animal
+    #[error("The animal is too far away")]
+    TooFar,
+    /// Spectators cannot feed animals
+    #[error("Spectators cannot feed animals")]
+    Spectating,
+}
+
+/// What feeding an animal did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fed {
+    /// An adult went into love mode
+    InLove,
+    /// A baby grew a little
+    Grew,
+}
+
+impl MobType {
+    /// Items the mob eats when fed, empty for mobs that do not breed
+    pub fn breeding_food(&self) -> &'static [&'static str] {
+        match self {
+            MobType::Cow | MobType::Sheep => &["minecraft:wheat"],
+            MobType::Pig => &["minecraft:carrot", "minecraft:potato"],
+            MobType::Chicken => &["minecraft:wheat_seeds"],
+            MobType::Zombie
+            | MobType::Skeleton
+            | MobType::Creeper
+            | MobType::Spider
+            | MobType::Villager => &[],
+        }
+    }
+}
+
+/// Feed an animal an item
+///
+/// Like vanilla, a fed baby grows up a tenth of the time it has left
+/// sooner.
+pub fn feed(entities: &mut EntityManager, animal: EntityId, item: &str) -> Result<Fed, BreedError> {
+    let mob = entities
+        .get_entity_mut(animal)
+        .and_then(|entity| entity.as_mob_mut())
+        .filter(|mob| !mob.kind().breeding_food().is_empty())
+        .ok_or(BreedError::NotAnAnimal)?;
+    if !mob.kind().breeding_food().contains(&item) {
+        return Err(BreedError::NotFood);
+    }
+    if mob.is_baby() {
+        mob.set_age(mob.age() - mob.age() / 10);
+        return Ok(Fed::Grew);
+    }
+    if mob.age() > 0 || mob.in_love() {
+        return Err(BreedError::NotReady);
+    }
+    mob.set_love(LOVE_TICKS);
+    Ok(Fed::InLove)
+}
+
+/// Pair up animals in love and spawn their babies
+///
+/// Returns the babies that were born.
+pub fn breed(entities: &mut EntityManager) -> Vec<EntityId> {
+    let in_love: Vec<(EntityId, MobType, EntityPosition)> = entities
+        .entities()
+        .filter_map(|entity| entity.as_mob())
+        .filter(|mob| mob.in_love() && !mob.is_baby())
+        .map(|mob| (mob.entity_id(), mob.kind(), mob.position()))
+        .collect();
+
+    let mut paired = Vec::new();
+    let mut babies = Vec::new();
+    for (i, &(first, kind, a)) in in_love.iter().enumerate() {
+        let partner = in_love[i + 1..].iter().find(|(id, other, b)| {
+            *other == kind && !paired.contains(id) && distance(a, *b) <= PARTNER_DISTANCE
+        });
+        let Some(&(second, _, b)) = partner.filter(|_| !paired.contains(&first)) else {
+            continue;
+        };
+        paired.extend([first, second]);
+        for parent in [first, second] {
+            if let Some(mob) = entities
+                .get_entity_mut(parent)
+                .and_then(|entity| entity.as_mob_mut())
+            {
+                mob.set_love(0);
+                mob.set_age(BREEDING_COOLDOWN);
+            }
+        }
+        let between = EntityPosition {
+            x: (a.x + b.x) / 2.0,
+            y: a.y.min(b.y),
+            z: (a.z + b.z) / 2.0,
+        };
+        babies.push(
+            entities
+                .add_entity(|id| Box::new(MobEntity::new(id, kind, between).with_age(BABY_AGE))),
+        );
+    }
+    babies
+}
+
+/// Distance between two positions
+fn distance(a: EntityPosition, b: EntityPosition) -> f64 {
+    let (dx, dy, dz) = (a.x - b.x, a.y - b.y, a.z - b.z);
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(entities: &mut EntityManager, kind: MobType, x: f64) -> EntityId {
+        let position = EntityPosition { x, y: 64.0, z: 0.0 };
+        entities.add_entity(|id| Box::new(MobEntity::new(id, kind, position)))
+    }
+
+    fn mob(entities: &EntityManager, id: EntityId) -> &MobEntity {
+        entities.get_entity(id).unwrap().as_mob().unwrap()
+    }
+
+    #[test]
+    fn test_breeding_cows() {
+        let mut entities = EntityManager::new();
+        let cow = spawn(&mut entities, MobType::Cow, 0.0);
+        let other = spawn(&mut entities, MobType::Cow, 2.0);
+        let pig = spawn(&mut entities, MobType::Pig, 1.0);
+        let zombie = spawn(&mut entities, MobType::Zombie, 1.0);
+
+        assert_eq!(
+            feed(&mut entities, zombie, "minecraft:wheat"),
+            Err(BreedError::NotAnAnimal)
+        );
+        assert_eq!(
+            feed(&mut entities, cow, "minecraft:carrot"),
+            Err(BreedError::NotFood)
+        );
+        assert_eq!(feed(&mut entities, cow, "minecraft:wheat"), Ok(Fed::InLove));
+        assert_eq!(
+            feed(&mut entities, cow, "minecraft:wheat"),
+            Err(BreedError::NotReady)
+        );
+        assert_eq!(
+            feed(&mut entities, pig, "minecraft:carrot"),
+            Ok(Fed::InLove)
+        );
+        assert!(breed(&mut entities).is_empty());
+
+        assert_eq!(
+            feed(&mut entities, other, "minecraft:wheat"),
+            Ok(Fed::InLove)
+        );
+        let babies = breed(&mut entities);
+        assert_eq!(babies.len(), 1);
+        let calf = mob(&entities, babies[0]);
+        assert_eq!(calf.kind(), MobType::Cow);
+        assert!(calf.is_baby());
+        assert!(!mob(&entities, cow).in_love());
+        assert!(mob(&entities, pig).in_love());
+        assert_eq!(
+            feed(&mut entities, cow, "minecraft:wheat"),
+            Err(BreedError::NotReady)
+        );
+
+        // Feeding the calf makes it grow up sooner
+        assert_eq!(
+            feed(&mut entities, babies[0], "minecraft:wheat"),
+            Ok(Fed::Grew)
+        );
+        assert_eq!(mob(&entities, babies[0]).age(), BABY_AGE - BABY_AGE / 10);
+    }
+}
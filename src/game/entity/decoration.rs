@@ -0,0 +1,888 @@
+//! Armor stands and item frames
+//!
+//! Both are placed from their items and changed by hand. Using an armor
+//! stand with an item dresses it, putting armor in the matching armor slot
+//! and anything else in its main hand; using it with an empty hand takes
+//! back the item at the height that was clicked. Using an empty item frame
+//! puts the held item in it, and using a full one turns the item by an
+//! eighth. Hitting a frame knocks its item out, and hitting an empty frame
+//! or an armor stand breaks it.
+//!
+//! Item entities only carry loot so far, so items taken out of a stand or
+//! frame go straight into the player's inventory instead of being dropped.
+//!
+//! Both are saved with the chunk they stand in, as entity NBT with vanilla's
+//! tag names. Items are stored the way [`ItemStack`] serializes, by registry
+//! ID.
+
+use super::{
+    CustomName, Entity, EntityId, EntityManager, EntityPosition, EntityRotation, EntityType,
+};
+use crate::game::inventory::{EquipmentSlot, Hand, ItemStack};
+use crate::game::loot::ItemDrop;
+use crate::game::player::{GameMode, Player};
+use crate::protocol::nbt::{Compound, Tag};
+use crate::protocol::types::{McUuid, Position};
+use thiserror::Error;
+
+/// Furthest from an armor stand or item frame a player can use or hit it
+pub const MAX_REACH: f64 = 6.0;
+
+/// Reasons a player cannot change an armor stand or item frame
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationError {
+    /// The entity does not exist or is not an armor stand or item frame
+    #[error("That is not an armor stand or item frame")]
+    NotADecoration,
+    /// Only survival and creative players change decorations
+    #[error("Decorations cannot be changed in this game mode")]
+    NotAllowed,
+    /// The player is too far away
+    #[error("The decoration is too far away")]
+    TooFar,
+    /// The armor stand has no arms to hold items with
+    #[error("The armor stand has no arms")]
+    NoArms,
+    /// The slot is taken and the player holds more than one item to swap
+    #[error("The armor stand already holds an item there")]
+    SlotTaken,
+    /// The items taken out do not fit into the player's inventory
+    #[error("The player's inventory is full")]
+    InventoryFull,
+}
+
+/// Angles in degrees of an armor stand's head, body and limbs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArmorStandPose {
+    /// Head rotation around the X, Y and Z axes
+    pub head: [f32; 3],
+    /// Body rotation
+    pub body: [f32; 3],
+    /// Left arm rotation
+    pub left_arm: [f32; 3],
+    /// Right arm rotation
+    pub right_arm: [f32; 3],
+    /// Left leg rotation
+    pub left_leg: [f32; 3],
+    /// Right leg rotation
+    pub right_leg: [f32; 3],
+}
+
+impl Default for ArmorStandPose {
+    fn default() -> Self {
+        Self {
+            head: [0.0; 3],
+            body: [0.0; 3],
+            left_arm: [-10.0, 0.0, -10.0],
+            right_arm: [-15.0, 0.0, 10.0],
+            left_leg: [-1.0, 0.0, -1.0],
+            right_leg: [1.0, 0.0, 1.0],
+        }
+    }
+}
+
+/// An armor stand showing armor and held items
+pub struct ArmorStandEntity {
+    /// Entity ID
+    entity_id: EntityId,
+    /// Entity UUID
+    uuid: McUuid,
+    /// Name shown above the entity
+    custom_name: Option<CustomName>,
+    /// Position
+    position: EntityPosition,
+    /// Yaw the stand faces
+    yaw: f32,
+    /// Angles of the head, body and limbs
+    pose: ArmorStandPose,
+    /// Whether the stand is half size
+    small: bool,
+    /// Whether the stand has arms
+    arms: bool,
+    /// Items in each equipment slot, by protocol ID
+    equipment: [Option<ItemStack>; 6],
+    /// Whether the stand was broken
+    broken: bool,
+}
+
+impl ArmorStandEntity {
+    /// Create an empty armor stand without arms
+    pub fn new(entity_id: EntityId, position: EntityPosition, yaw: f32) -> Self {
+        Self {
+            entity_id,
+            uuid: McUuid::new_v4(),
+            custom_name: None,
+            position,
+            yaw,
+            pose: ArmorStandPose::default(),
+            small: false,
+            arms: false,
+            equipment: [None; 6],
+            broken: false,
+        }
+    }
+
+    /// Angles of the head, body and limbs
+    pub fn pose(&self) -> &ArmorStandPose {
+        &self.pose
+    }
+
+    /// Change the angles of the head, body and limbs
+    pub fn set_pose(&mut self, pose: ArmorStandPose) {
+        self.pose = pose;
+    }
+
+    /// Turn the stand to face a yaw
+    pub fn set_yaw(&mut self, yaw: f32) {
+        self.yaw = yaw;
+    }
+
+    /// Whether the stand is half size
+    pub fn is_small(&self) -> bool {
+        self.small
+    }
+
+    /// Make the stand half or full size
+    pub fn set_small(&mut self, small: bool) {
+        self.small = small;
+    }
+
+    /// Whether the stand has arms and can hold items
+    pub fn has_arms(&self) -> bool {
+        self.arms
+    }
+
+    /// Give the stand arms or take them away
+    pub fn set_arms(&mut self, arms: bool) {
+        self.arms = arms;
+    }
+
+    /// Item in an equipment slot
+    pub fn equipment(&self, slot: EquipmentSlot) -> Option<ItemStack> {
+        self.equipment[slot.id() as usize]
+    }
+
+    /// Put an item into an equipment slot, returning the item that was there
+    pub fn set_equipment(
+        &mut self,
+        slot: EquipmentSlot,
+        item: Option<ItemStack>,
+    ) -> Option<ItemStack> {
+        std::mem::replace(&mut self.equipment[slot.id() as usize], item)
+    }
+
+    /// Equipment that is not empty
+    pub fn visible_equipment(&self) -> Vec<(EquipmentSlot, Option<ItemStack>)> {
+        EquipmentSlot::VISIBLE
+            .into_iter()
+            .map(|slot| (slot, self.equipment(slot)))
+            .filter(|(_, item)| item.is_some())
+            .collect()
+    }
+
+    /// Slot a player clicking the stand `height` blocks above its feet
+    /// takes an item from, as vanilla picks it
+    pub fn clicked_slot(&self, height: f32) -> EquipmentSlot {
+        let small = self.small;
+        let y = if small { height * 2.0 } else { height };
+        let has = |slot| self.equipment(slot).is_some();
+        let feet = if small { 0.1..0.9 } else { 0.1..0.55 };
+        let chest = if small { 1.2..1.9 } else { 0.9..1.6 };
+        let legs = if small { 0.4..1.4 } else { 0.4..1.2 };
+        if feet.contains(&y) && has(EquipmentSlot::Feet) {
+            EquipmentSlot::Feet
+        } else if chest.contains(&y) && has(EquipmentSlot::Chest) {
+            EquipmentSlot::Chest
+        } else if legs.contains(&y) && has(EquipmentSlot::Legs) {
+            EquipmentSlot::Legs
+        } else if y >= 1.6 && has(EquipmentSlot::Head) {
+            EquipmentSlot::Head
+        } else if !has(EquipmentSlot::MainHand) && has(EquipmentSlot::OffHand) {
+            EquipmentSlot::OffHand
+        } else {
+            EquipmentSlot::MainHand
+        }
+    }
+}
+
+impl Entity for ArmorStandEntity {
+    fn entity_id(&self) -> EntityId {
+        self.entity_id
+    }
+
+    fn entity_type(&self) -> EntityType {
+        EntityType::ArmorStand
+    }
+
+    fn position(&self) -> EntityPosition {
+        self.position
+    }
+
+    fn rotation(&self) -> EntityRotation {
+        EntityRotation {
+            yaw: self.yaw,
+            pitch: 0.0,
+        }
+    }
+
+    fn uuid(&self) -> Option<McUuid> {
+        Some(self.uuid)
+    }
+
+    fn custom_name(&self) -> Option<&CustomName> {
+        self.custom_name.as_ref()
+    }
+
+    fn set_custom_name(&mut self, name: Option<CustomName>) {
+        self.custom_name = name;
+    }
+
+    fn is_alive(&self) -> bool {
+        !self.broken
+    }
+
+    fn update(&mut self, _delta_time: f64) {
+        // Armor stands stand still
+    }
+
+    fn as_armor_stand(&self) -> Option<&ArmorStandEntity> {
+        Some(self)
+    }
+
+    fn as_armor_stand_mut(&mut self) -> Option<&mut ArmorStandEntity> {
+        Some(self)
+    }
+}
+
+/// An item frame hanging on a block face
+pub struct ItemFrameEntity {
+    /// Entity ID
+    entity_id: EntityId,
+    /// Entity UUID
+    uuid: McUuid,
+    /// Name shown above the entity
+    custom_name: Option<CustomName>,
+    /// Block the frame occupies
+    block: Position,
+    /// Direction the frame faces, as a block face ID
+    facing: u8,
+    /// Item shown
+    item: Option<ItemStack>,
+    /// Eighths of a turn the item is rotated by
+    item_rotation: u8,
+    /// Whether the frame was broken
+    broken: bool,
+}
+
+impl ItemFrameEntity {
+    /// Create an empty frame in a block, facing away from the block face
+    /// it hangs on
+    pub fn new(entity_id: EntityId, block: Position, facing: u8) -> Self {
+        Self {
+            entity_id,
+            uuid: McUuid::new_v4(),
+            custom_name: None,
+            block,
+            facing,
+            item: None,
+            item_rotation: 0,
+            broken: false,
+        }
+    }
+
+    /// Direction the frame faces, as a block face ID
+    pub fn facing(&self) -> u8 {
+        self.facing
+    }
+
+    /// Item shown
+    pub fn item(&self) -> Option<ItemStack> {
+        self.item
+    }
+
+    /// Eighths of a turn the item is rotated by
+    pub fn item_rotation(&self) -> u8 {
+        self.item_rotation
+    }
+
+    /// Put a single item into the frame, unless it already shows one
+    pub fn put(&mut self, item: ItemStack) -> bool {
+        if self.item.is_some() {
+            return false;
+        }
+        self.item = Some(ItemStack { count: 1, ..item });
+        self.item_rotation = 0;
+        true
+    }
+
+    /// Turn the item shown by an eighth, if there is one
+    pub fn rotate(&mut self) -> bool {
+        if self.item.is_none() {
+            return false;
+        }
+        self.item_rotation = (self.item_rotation + 1) % 8;
+        true
+    }
+
+    /// Take the item shown out of the frame
+    pub fn take(&mut self) -> Option<ItemStack> {
+        self.item_rotation = 0;
+        self.item.take()
+    }
+}
+
+impl Entity for ItemFrameEntity {
+    fn entity_id(&self) -> EntityId {
+        self.entity_id
+    }
+
+    fn entity_type(&self) -> EntityType {
+        EntityType::ItemFrame
+    }
+
+    fn position(&self) -> EntityPosition {
+        EntityPosition {
+            x: f64::from(self.block.x) + 0.5,
+            y: f64::from(self.block.y) + 0.5,
+            z: f64::from(self.block.z) + 0.5,
+        }
+    }
+
+    fn rotation(&self) -> EntityRotation {
+        let (yaw, pitch) = match self.facing {
+            0 => (0.0, 90.0),
+            1 => (0.0, -90.0),
+            2 => (180.0, 0.0),
+            4 => (90.0, 0.0),
+            5 => (270.0, 0.0),
+            _ => (0.0, 0.0),
+        };
+        EntityRotation { yaw, pitch }
+    }
+
+    fn uuid(&self) -> Option<McUuid> {
+        Some(self.uuid)
+    }
+
+    fn custom_name(&self) -> Option<&CustomName> {
+        self.custom_name.as_ref()
+    }
+
+    fn set_custom_name(&mut self, name: Option<CustomName>) {
+        self.custom_name = name;
+    }
+
+    fn is_alive(&self) -> bool {
+        !self.broken
+    }
+
+    fn update(&mut self, _delta_time: f64) {
+        // Item frames hang still
+    }
+
+    fn as_item_frame(&self) -> Option<&ItemFrameEntity> {
+        Some(self)
+    }
+
+    fn as_item_frame_mut(&mut self) -> Option<&mut ItemFrameEntity> {
+        Some(self)
+    }
+}
+
+/// What using or hitting a decoration changed
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DecorationChange {
+    /// Armor stand slots whose item changed
+    pub stand_slots: Vec<EquipmentSlot>,
+    /// Whether an item frame's item or its rotation changed
+    pub frame_changed: bool,
+    /// Item dropped where the decoration was, when it broke
+    pub broken: Option<ItemDrop>,
+}
+
+/// Yaw an armor stand placed by a player facing `player_yaw` faces, turned
+/// towards the player in steps of 45 degrees
+pub fn placed_stand_yaw(player_yaw: f32) -> f32 {
+    let yaw = (player_yaw - 180.0).rem_euclid(360.0);
+    ((yaw + 22.5) / 45.0).floor() * 45.0 % 360.0
+}
+
+/// Use an armor stand or item frame with the item in a hand
+///
+/// `held_slot` is the armor stand slot the held item goes in, and `height`
+/// how far above an armor stand's feet it was clicked.
+pub fn use_decoration(
+    entities: &mut EntityManager,
+    player: &mut Player,
+    target: EntityId,
+    hand: Hand,
+    held_slot: EquipmentSlot,
+    height: f32,
+) -> Result<DecorationChange, DecorationError> {
+    let entity = reachable(entities, player, target)?;
+    let creative = player.game_mode == GameMode::Creative;
+    let held = player.inventory.item_in(hand);
+    let mut change = DecorationChange::default();
+
+    if let Some(frame) = entity.as_item_frame_mut() {
+        match held {
+            Some(item) if frame.put(item) => {
+                if !creative {
+                    player.inventory.take_one(hand);
+                }
+                change.frame_changed = true;
+            }
+            _ => change.frame_changed = frame.rotate(),
+        }
+        return Ok(change);
+    }
+
+    let stand = entity
+        .as_armor_stand_mut()
+        .ok_or(DecorationError::NotADecoration)?;
+    let slot = match held {
+        Some(_) => held_slot,
+        None => stand.clicked_slot(height),
+    };
+    let existing = stand.equipment(slot);
+    if held.is_none() && existing.is_none() {
+        return Ok(change);
+    }
+    let in_hand = matches!(slot, EquipmentSlot::MainHand | EquipmentSlot::OffHand);
+    if in_hand && !stand.has_arms() {
+        return Err(DecorationError::NoArms);
+    }
+    let hand_slot = player.inventory.hand_slot(hand);
+    match (held, existing) {
+        (Some(_), None) if creative => {}
+        (Some(_), None) => {
+            player.inventory.take_one(hand);
+        }
+        (Some(item), Some(_)) if item.count > 1 => return Err(DecorationError::SlotTaken),
+        (_, existing) => {
+            player.inventory.set(hand_slot, existing);
+        }
+    }
+    stand.set_equipment(slot, held.map(|item| ItemStack { count: 1, ..item }));
+    change.stand_slots.push(slot);
+    Ok(change)
+}
+
+/// Hit an armor stand or item frame
+///
+/// A frame showing an item loses the item, and an empty frame or an armor
+/// stand breaks, giving its contents to the player and dropping its item
+/// unless the player is in creative mode. `max_stack_size` gives the stack
+/// size of an item.
+pub fn hit_decoration(
+    entities: &mut EntityManager,
+    player: &mut Player,
+    target: EntityId,
+    max_stack_size: impl Fn(i32) -> i32,
+) -> Result<DecorationChange, DecorationError> {
+    let entity = reachable(entities, player, target)?;
+    let creative = player.game_mode == GameMode::Creative;
+    let mut change = DecorationChange::default();
+
+    let (contents, dropped) = if let Some(frame) = entity.as_item_frame_mut() {
+        if frame.item.is_some() {
+            change.frame_changed = true;
+            (vec![frame.item], None)
+        } else {
+            (Vec::new(), Some("minecraft:item_frame"))
+        }
+    } else if let Some(stand) = entity.as_armor_stand_mut() {
+        change.stand_slots = stand
+            .visible_equipment()
+            .into_iter()
+            .map(|(slot, _)| slot)
+            .collect();
+        (stand.equipment.to_vec(), Some("minecraft:armor_stand"))
+    } else {
+        return Err(DecorationError::NotADecoration);
+    };
+
+    let contents: Vec<ItemStack> = contents.into_iter().flatten().collect();
+    if !creative {
+        let mut inventory = player.inventory.clone();
+        for item in &contents {
+            if inventory.add(*item, max_stack_size(item.item)).is_some() {
+                return Err(DecorationError::InventoryFull);
+            }
+        }
+        player.inventory = inventory;
+    }
+
+    if let Some(frame) = entity.as_item_frame_mut() {
+        frame.take();
+        frame.broken = dropped.is_some();
+    } else if let Some(stand) = entity.as_armor_stand_mut() {
+        stand.equipment = [None; 6];
+        stand.broken = true;
+    }
+    change.broken = dropped
+        .filter(|_| !creative)
+        .map(|item| ItemDrop::new(item, 1));
+    Ok(change)
+}
+
+/// Armor stand equipment tags, by equipment slot protocol ID
+const EQUIPMENT_TAGS: [&str; 6] = ["mainhand", "offhand", "feet", "legs", "chest", "head"];
+
+/// Write an armor stand or item frame as entity NBT
+///
+/// Returns `None` for any other entity, or one that was broken.
+pub fn save(entity: &dyn Entity) -> Option<Compound> {
+    if !entity.is_alive() {
+        return None;
+    }
+    let mut nbt = Compound::new();
+    nbt.insert("id".to_string(), entity.entity_type().identifier().into());
+    if let Some(uuid) = entity.uuid() {
+        let bits = uuid.as_u128();
+        let ints = [96, 64, 32, 0].map(|shift| (bits >> shift) as u32 as i32);
+        nbt.insert("UUID".to_string(), Tag::IntArray(ints.to_vec()));
+    }
+    if let Some(name) = entity.custom_name() {
+        name.write_nbt(&mut nbt);
+    }
+
+    if let Some(stand) = entity.as_armor_stand() {
+        let position = stand.position;
+        let list = |values: &[f32]| Tag::List(values.iter().copied().map(Tag::Float).collect());
+        let pose = &stand.pose;
+        let parts = [
+            ("Head", pose.head),
+            ("Body", pose.body),
+            ("LeftArm", pose.left_arm),
+            ("RightArm", pose.right_arm),
+            ("LeftLeg", pose.left_leg),
+            ("RightLeg", pose.right_leg),
+        ];
+        let equipment: Compound = EQUIPMENT_TAGS
+            .iter()
+            .zip(stand.equipment)
+            .filter_map(|(slot, item)| Some((slot.to_string(), item_tag(item?)?)))
+            .collect();
+        nbt.insert(
+            "Pos".to_string(),
+            Tag::List(vec![
+                Tag::Double(position.x),
+                Tag::Double(position.y),
+                Tag::Double(position.z),
+            ]),
+        );
+        nbt.insert("Rotation".to_string(), list(&[stand.yaw, 0.0]));
+        nbt.insert("Small".to_string(), stand.small.into());
+        nbt.insert("ShowArms".to_string(), stand.arms.into());
+        nbt.insert(
+            "Pose".to_string(),
+            Tag::Compound(
+                parts
+                    .into_iter()
+                    .map(|(part, angles)| (part.to_string(), list(&angles)))
+                    .collect(),
+            ),
+        );
+        nbt.insert("equipment".to_string(), Tag::Compound(equipment));
+    } else if let Some(frame) = entity.as_item_frame() {
+        nbt.insert("TileX".to_string(), frame.block.x.into());
+        nbt.insert("TileY".to_string(), frame.block.y.into());
+        nbt.insert("TileZ".to_string(), frame.block.z.into());
+        nbt.insert("Facing".to_string(), Tag::Byte(frame.facing as i8));
+        nbt.insert(
+            "ItemRotation".to_string(),
+            Tag::Byte(frame.item_rotation as i8),
+        );
+        if let Some(tag) = frame.item.and_then(item_tag) {
+            nbt.insert("Item".to_string(), tag);
+        }
+    } else {
+        return None;
+    }
+    Some(nbt)
+}
+
+/// Read an armor stand or item frame written by [`save`], giving it
+/// `entity_id`
+pub fn load(entity_id: EntityId, nbt: &Compound) -> Option<Box<dyn Entity>> {
+    let uuid = match nbt.get("UUID").and_then(Tag::as_int_array) {
+        Some(&[a, b, c, d]) => McUuid::from_u128(
+            [a, b, c, d]
+                .into_iter()
+                .fold(0, |bits, int| bits << 32 | u128::from(int as u32)),
+        ),
+        _ => McUuid::new_v4(),
+    };
+    let custom_name = CustomName::from_nbt(nbt);
+    let flag = |name| nbt.get(name).and_then(Tag::as_bool).unwrap_or(false);
+
+    let entity: Box<dyn Entity> = match nbt.get("id")?.as_str()? {
+        "minecraft:armor_stand" => {
+            let pos = nbt.get("Pos")?.as_list()?;
+            let coordinate = |i: usize| pos.get(i).and_then(Tag::as_f64);
+            let position = EntityPosition {
+                x: coordinate(0)?,
+                y: coordinate(1)?,
+                z: coordinate(2)?,
+            };
+            let yaw = nbt
+                .get("Rotation")
+                .and_then(Tag::as_list)
+                .and_then(|rotation| rotation.first()?.as_f64())
+                .unwrap_or_default() as f32;
+            let mut stand = ArmorStandEntity::new(entity_id, position, yaw);
+            stand.uuid = uuid;
+            stand.custom_name = custom_name;
+            stand.small = flag("Small");
+            stand.arms = flag("ShowArms");
+            if let Some(pose) = nbt.get("Pose").and_then(Tag::as_compound) {
+                let defaults = ArmorStandPose::default();
+                let angles =
+                    |part: &str, default| pose.get(part).and_then(floats::<3>).unwrap_or(default);
+                stand.pose = ArmorStandPose {
+                    head: angles("Head", defaults.head),
+                    body: angles("Body", defaults.body),
+                    left_arm: angles("LeftArm", defaults.left_arm),
+                    right_arm: angles("RightArm", defaults.right_arm),
+                    left_leg: angles("LeftLeg", defaults.left_leg),
+                    right_leg: angles("RightLeg", defaults.right_leg),
+                };
+            }
+            if let Some(equipment) = nbt.get("equipment").and_then(Tag::as_compound) {
+                for (item, slot) in stand.equipment.iter_mut().zip(EQUIPMENT_TAGS) {
+                    *item = equipment.get(slot).and_then(tag_item);
+                }
+            }
+            Box::new(stand)
+        }
+        "minecraft:item_frame" => {
+            let coordinate = |name| {
+                nbt.get(name)
+                    .and_then(Tag::as_i64)
+                    .and_then(|v| i32::try_from(v).ok())
+            };
+            let block = Position::new(
+                coordinate("TileX")?,
+                coordinate("TileY")?,
+                coordinate("TileZ")?,
+            );
+            let facing = nbt.get("Facing").and_then(Tag::as_i64).unwrap_or(0);
+            let mut frame = ItemFrameEntity::new(entity_id, block, facing.clamp(0, 5) as u8);
+            frame.uuid = uuid;
+            frame.custom_name = custom_name;
+            frame.item = nbt.get("Item").and_then(tag_item);
+            if frame.item.is_some() {
+                let rotation = nbt.get("ItemRotation").and_then(Tag::as_i64).unwrap_or(0);
+                frame.item_rotation = rotation.rem_euclid(8) as u8;
+            }
+            Box::new(frame)
+        }
+        _ => return None,
+    };
+    Some(entity)
+}
+
+/// A list of `N` numbers as floats
+fn floats<const N: usize>(tag: &Tag) -> Option<[f32; N]> {
+    let list = tag.as_list()?;
+    let mut values = [0.0; N];
+    for (value, tag) in values.iter_mut().zip(list) {
+        *value = tag.as_f64()? as f32;
+    }
+    (list.len() == N).then_some(values)
+}
+
+/// An item as NBT
+fn item_tag(item: ItemStack) -> Option<Tag> {
+    Tag::from_json(&serde_json::to_value(item).ok()?)
+}
+
+/// An item read back from [`item_tag`]
+fn tag_item(tag: &Tag) -> Option<ItemStack> {
+    serde_json::from_value::<ItemStack>(tag.to_json())
+        .ok()
+        .filter(ItemStack::is_valid)
+}
+
+/// Find a decoration the player may change and is close enough to
+fn reachable<'a>(
+    entities: &'a mut EntityManager,
+    player: &Player,
+    target: EntityId,
+) -> Result<&'a mut Box<dyn Entity>, DecorationError> {
+    let entity = entities
+        .get_entity_mut(target)
+        .filter(|entity| {
+            matches!(
+                entity.entity_type(),
+                EntityType::ArmorStand | EntityType::ItemFrame
+            )
+        })
+        .ok_or(DecorationError::NotADecoration)?;
+    if !matches!(player.game_mode, GameMode::Survival | GameMode::Creative) {
+        return Err(DecorationError::NotAllowed);
+    }
+    let position = entity.position();
+    let (dx, dy, dz) = (
+        position.x - player.position.x,
+        position.y - player.position.y,
+        position.z - player.position.z,
+    );
+    if (dx * dx + dy * dy + dz * dz).sqrt() > MAX_REACH {
+        return Err(DecorationError::TooFar);
+    }
+    Ok(entity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(entities: &mut EntityManager, held: Option<ItemStack>) -> Player {
+        let mut player = Player::new(McUuid::new_v4(), "Steve".to_string());
+        player.entity_id = entities.next_entity_id();
+        player.set_position(1.0, 64.0, 0.0);
+        player.game_mode = GameMode::Survival;
+        let slot = player.inventory.hand_slot(Hand::Main);
+        player.inventory.set(slot, held);
+        player
+    }
+
+    fn stand(entities: &EntityManager, id: EntityId) -> &ArmorStandEntity {
+        entities.get_entity(id).unwrap().as_armor_stand().unwrap()
+    }
+
+    #[test]
+    fn test_clicked_slot() {
+        let position = EntityPosition {
+            x: 0.0,
+            y: 64.0,
+            z: 0.0,
+        };
+        let mut stand = ArmorStandEntity::new(1, position, 0.0);
+        stand.set_equipment(EquipmentSlot::Head, Some(ItemStack::new(1, 1)));
+        stand.set_equipment(EquipmentSlot::Feet, Some(ItemStack::new(2, 1)));
+        assert_eq!(stand.clicked_slot(0.3), EquipmentSlot::Feet);
+        assert_eq!(stand.clicked_slot(1.8), EquipmentSlot::Head);
+        assert_eq!(stand.clicked_slot(1.0), EquipmentSlot::MainHand);
+        stand.set_small(true);
+        assert_eq!(stand.clicked_slot(0.9), EquipmentSlot::Head);
+    }
+
+    #[test]
+    fn test_placed_stand_yaw() {
+        assert_eq!(placed_stand_yaw(0.0), 180.0);
+        assert_eq!(placed_stand_yaw(180.0), 0.0);
+        assert_eq!(placed_stand_yaw(-100.0), 90.0);
+        assert_eq!(placed_stand_yaw(230.0), 45.0);
+    }
+
+    #[test]
+    fn test_dressing_an_armor_stand() {
+        let mut entities = EntityManager::new();
+        let position = EntityPosition {
+            x: 0.0,
+            y: 64.0,
+            z: 0.0,
+        };
+        let id = entities.add_entity(|id| Box::new(ArmorStandEntity::new(id, position, 0.0)));
+        let helmet = ItemStack::new(7, 1);
+        let mut steve = player(&mut entities, Some(helmet));
+
+        let change = use_decoration(
+            &mut entities,
+            &mut steve,
+            id,
+            Hand::Main,
+            EquipmentSlot::Head,
+            1.8,
+        );
+        assert_eq!(change.unwrap().stand_slots, vec![EquipmentSlot::Head]);
+        assert_eq!(
+            stand(&entities, id).equipment(EquipmentSlot::Head),
+            Some(helmet)
+        );
+        assert_eq!(steve.inventory.held_item(), None);
+
+        assert_eq!(
+            use_decoration(
+                &mut entities,
+                &mut steve,
+                id,
+                Hand::Main,
+                EquipmentSlot::MainHand,
+                1.0
+            ),
+            Ok(DecorationChange::default())
+        );
+        steve.inventory.set(
+            steve.inventory.hand_slot(Hand::Main),
+            Some(ItemStack::new(3, 1)),
+        );
+        assert_eq!(
+            use_decoration(
+                &mut entities,
+                &mut steve,
+                id,
+                Hand::Main,
+                EquipmentSlot::MainHand,
+                1.0
+            ),
+            Err(DecorationError::NoArms)
+        );
+
+        steve
+            .inventory
+            .set(steve.inventory.hand_slot(Hand::Main), None);
+        use_decoration(
+            &mut entities,
+            &mut steve,
+            id,
+            Hand::Main,
+            EquipmentSlot::MainHand,
+            1.8,
+        )
+        .unwrap();
+        assert_eq!(steve.inventory.held_item(), Some(helmet));
+        assert_eq!(stand(&entities, id).equipment(EquipmentSlot::Head), None);
+    }
+
+    #[test]
+    fn test_item_frame() {
+        let mut entities = EntityManager::new();
+        let block = Position::new(0, 64, 0);
+        let id = entities.add_entity(|id| Box::new(ItemFrameEntity::new(id, block, 2)));
+        let mut steve = player(&mut entities, Some(ItemStack::new(5, 3)));
+        let frame = |entities: &EntityManager| {
+            let frame = entities.get_entity(id).unwrap().as_item_frame().unwrap();
+            (frame.item().map(|item| item.count), frame.item_rotation())
+        };
+
+        for _ in 0..2 {
+            use_decoration(
+                &mut entities,
+                &mut steve,
+                id,
+                Hand::Main,
+                EquipmentSlot::MainHand,
+                0.0,
+            )
+            .unwrap();
+        }
+        assert_eq!(frame(&entities), (Some(1), 1));
+        assert_eq!(steve.inventory.held_item().map(|item| item.count), Some(2));
+
+        let change = hit_decoration(&mut entities, &mut steve, id, |_| 64).unwrap();
+        assert!(change.frame_changed && change.broken.is_none());
+        assert_eq!(frame(&entities), (None, 0));
+        assert_eq!(steve.inventory.held_item().map(|item| item.count), Some(3));
+
+        let change = hit_decoration(&mut entities, &mut steve, id, |_| 64).unwrap();
+        assert_eq!(
+            change.broken,
+            Some(ItemDrop::new("minecraft:item_frame", 1))
+        );
+        assert!(!entities.get_entity(id).unwrap().is_alive());
+    }
+}
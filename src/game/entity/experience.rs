@@ -0,0 +1,84 @@
+//! Experience orbs
+//!
+//! Killed mobs leave experience orbs behind. Orbs lie where they were
+//! dropped until they despawn; players cannot collect them yet.
+
+use super::item::DESPAWN_TICKS;
+use super::{CustomName, Entity, EntityId, EntityPosition, EntityRotation, EntityType};
+use crate::protocol::types::McUuid;
+
+/// An orb worth some experience points
+pub struct ExperienceOrbEntity {
+    /// Entity ID
+    entity_id: EntityId,
+    /// Entity UUID
+    uuid: McUuid,
+    /// Name shown above the entity
+    custom_name: Option<CustomName>,
+    /// Experience points the orb is worth
+    value: u32,
+    /// Position
+    position: EntityPosition,
+    /// Ticks since the orb was dropped
+    age: u32,
+}
+
+impl ExperienceOrbEntity {
+    /// Drop an orb worth `value` points at a position
+    pub fn new(entity_id: EntityId, value: u32, position: EntityPosition) -> Self {
+        Self {
+            entity_id,
+            uuid: McUuid::new_v4(),
+            custom_name: None,
+            value,
+            position,
+            age: 0,
+        }
+    }
+
+    /// Experience points the orb is worth
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+impl Entity for ExperienceOrbEntity {
+    fn entity_id(&self) -> EntityId {
+        self.entity_id
+    }
+
+    fn entity_type(&self) -> EntityType {
+        EntityType::ExperienceOrb
+    }
+
+    fn position(&self) -> EntityPosition {
+        self.position
+    }
+
+    fn rotation(&self) -> EntityRotation {
+        EntityRotation {
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    fn uuid(&self) -> Option<McUuid> {
+        Some(self.uuid)
+    }
+
+    fn custom_name(&self) -> Option<&CustomName> {
+        self.custom_name.as_ref()
+    }
+
+    fn set_custom_name(&mut self, name: Option<CustomName>) {
+        self.custom_name = name;
+    }
+
+    fn is_alive(&self) -> bool {
+        self.age < DESPAWN_TICKS
+    }
+
+    fn update(&mut self, _delta_time: f64) {
+        self.age += 1;
+    }
+}
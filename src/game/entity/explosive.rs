@@ -0,0 +1,255 @@
+//! Explosive entities
+//!
+//! Primed TNT blows up once its fuse burns down. A creeper lights its fuse
+//! when a player comes close and puts it out again if they get away before
+//! it blows up. Either leaves an [`Explosion`] for the world to apply when
+//! it dies.
+
+use super::{
+    CustomName, Entity, EntityId, EntityManager, EntityPosition, EntityRotation, EntityType,
+    MobType,
+};
+use crate::game::world::explosion::Explosion;
+use crate::protocol::types::McUuid;
+
+/// Distance at which a creeper lights its fuse
+pub const CREEPER_IGNITE_DISTANCE: f64 = 3.0;
+
+/// Distance at which a lit creeper puts its fuse out again
+pub const CREEPER_DEFUSE_DISTANCE: f64 = 7.0;
+
+/// Kinds of explosive entities
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplosiveKind {
+    /// Primed TNT
+    Tnt,
+    /// Creeper
+    Creeper,
+}
+
+impl ExplosiveKind {
+    /// Ticks from lighting the fuse to the explosion
+    pub fn fuse(&self) -> u32 {
+        match self {
+            ExplosiveKind::Tnt => 80,
+            ExplosiveKind::Creeper => 30,
+        }
+    }
+
+    /// Power of the explosion
+    pub fn power(&self) -> f32 {
+        match self {
+            ExplosiveKind::Tnt => 4.0,
+            ExplosiveKind::Creeper => 3.0,
+        }
+    }
+}
+
+/// Primed TNT or a creeper
+pub struct ExplosiveEntity {
+    /// Entity ID
+    entity_id: EntityId,
+    /// Entity UUID
+    uuid: McUuid,
+    /// Name shown above the entity
+    custom_name: Option<CustomName>,
+    /// Kind of explosive
+    kind: ExplosiveKind,
+    /// Position
+    position: EntityPosition,
+    /// Ticks left until the explosion, while the fuse burns
+    fuse: Option<u32>,
+    /// Whether the entity blew up
+    exploded: bool,
+}
+
+impl ExplosiveEntity {
+    /// Create an explosive; TNT starts out primed, creepers do not
+    pub fn new(entity_id: EntityId, kind: ExplosiveKind, position: EntityPosition) -> Self {
+        Self {
+            entity_id,
+            uuid: McUuid::new_v4(),
+            custom_name: None,
+            kind,
+            position,
+            fuse: (kind == ExplosiveKind::Tnt).then(|| kind.fuse()),
+            exploded: false,
+        }
+    }
+
+    /// Light the fuse with the given number of ticks left
+    pub fn with_fuse(mut self, ticks: u32) -> Self {
+        self.fuse = Some(ticks);
+        self
+    }
+
+    /// Kind of explosive
+    pub fn kind(&self) -> ExplosiveKind {
+        self.kind
+    }
+
+    /// Ticks left until the explosion, while the fuse burns
+    pub fn fuse(&self) -> Option<u32> {
+        self.fuse
+    }
+
+    /// Light the fuse unless it already burns
+    pub fn ignite(&mut self) {
+        if self.fuse.is_none() {
+            self.fuse = Some(self.kind.fuse());
+        }
+    }
+
+    /// Put out the fuse of a creeper; TNT cannot be put out
+    pub fn defuse(&mut self) {
+        if self.kind == ExplosiveKind::Creeper {
+            self.fuse = None;
+        }
+    }
+
+    /// The explosion, once the entity blew up
+    pub fn detonation(&self) -> Option<Explosion> {
+        self.exploded.then(|| Explosion {
+            center: self.position,
+            power: self.kind.power(),
+            source: Some(self.entity_id),
+            by_mob: self.kind == ExplosiveKind::Creeper,
+        })
+    }
+}
+
+impl Entity for ExplosiveEntity {
+    fn entity_id(&self) -> EntityId {
+        self.entity_id
+    }
+
+    fn entity_type(&self) -> EntityType {
+        match self.kind {
+            ExplosiveKind::Tnt => EntityType::PrimedTnt,
+            ExplosiveKind::Creeper => EntityType::Mob(MobType::Creeper),
+        }
+    }
+
+    fn position(&self) -> EntityPosition {
+        self.position
+    }
+
+    fn rotation(&self) -> EntityRotation {
+        EntityRotation {
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    fn uuid(&self) -> Option<McUuid> {
+        Some(self.uuid)
+    }
+
+    fn custom_name(&self) -> Option<&CustomName> {
+        self.custom_name.as_ref()
+    }
+
+    fn set_custom_name(&mut self, name: Option<CustomName>) {
+        self.custom_name = name;
+    }
+
+    fn is_alive(&self) -> bool {
+        !self.exploded
+    }
+
+    fn update(&mut self, _delta_time: f64) {
+        match self.fuse {
+            Some(ticks) if ticks <= 1 => {
+                self.fuse = Some(0);
+                self.exploded = true;
+            }
+            Some(ticks) => self.fuse = Some(ticks - 1),
+            None => {}
+        }
+    }
+
+    fn as_explosive(&self) -> Option<&ExplosiveEntity> {
+        Some(self)
+    }
+
+    fn as_explosive_mut(&mut self) -> Option<&mut ExplosiveEntity> {
+        Some(self)
+    }
+}
+
+/// Light the fuses of creepers near a target and put out those of creepers
+/// every target got away from
+pub fn ignite_creepers(entities: &mut EntityManager, targets: &[EntityPosition]) {
+    let creepers = entities
+        .entities_mut()
+        .filter_map(|entity| entity.as_explosive_mut())
+        .filter(|explosive| explosive.kind == ExplosiveKind::Creeper);
+    for creeper in creepers {
+        let nearest = targets
+            .iter()
+            .map(|target| {
+                let (dx, dy, dz) = (
+                    target.x - creeper.position.x,
+                    target.y - creeper.position.y,
+                    target.z - creeper.position.z,
+                );
+                (dx * dx + dy * dy + dz * dz).sqrt()
+            })
+            .fold(f64::INFINITY, f64::min);
+        if nearest < CREEPER_IGNITE_DISTANCE {
+            creeper.ignite();
+        } else if nearest > CREEPER_DEFUSE_DISTANCE {
+            creeper.defuse();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_creeper_fuse() {
+        let mut entities = EntityManager::new();
+        let origin = EntityPosition {
+            x: 0.0,
+            y: 64.0,
+            z: 0.0,
+        };
+        let creeper = entities
+            .add_entity(|id| Box::new(ExplosiveEntity::new(id, ExplosiveKind::Creeper, origin)));
+        let fuse = |entities: &EntityManager| {
+            entities
+                .get_entity(creeper)
+                .unwrap()
+                .as_explosive()
+                .unwrap()
+                .fuse()
+        };
+        let at = |x| EntityPosition { x, ..origin };
+
+        ignite_creepers(&mut entities, &[at(5.0)]);
+        assert_eq!(fuse(&entities), None);
+        ignite_creepers(&mut entities, &[at(5.0), at(2.0)]);
+        assert_eq!(fuse(&entities), Some(ExplosiveKind::Creeper.fuse()));
+
+        // Backing off a little is not enough to put the fuse out
+        entities.update_all(0.05);
+        ignite_creepers(&mut entities, &[at(5.0)]);
+        assert_eq!(fuse(&entities), Some(ExplosiveKind::Creeper.fuse() - 1));
+        ignite_creepers(&mut entities, &[at(8.0)]);
+        assert_eq!(fuse(&entities), None);
+
+        ignite_creepers(&mut entities, &[at(1.0)]);
+        let mut removed = Vec::new();
+        for _ in 0..ExplosiveKind::Creeper.fuse() {
+            assert!(removed.is_empty());
+            removed = entities.update_all(0.05);
+        }
+        let explosion = removed[0].as_explosive().unwrap().detonation().unwrap();
+        assert_eq!(explosion.source, Some(creeper));
+        assert_eq!(explosion.power, 3.0);
+        assert!(explosion.by_mob);
+        assert!(!entities.contains(creeper));
+    }
+}
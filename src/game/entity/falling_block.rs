@@ -0,0 +1,126 @@
+//! Falling blocks
+//!
+//! Sand, gravel and anvils with nothing below them turn into falling
+//! blocks, which fall under gravity until the
+//! [world](crate::game::world::gravity) turns them back into blocks where
+//! they land. Clients simulate the fall themselves from the block state
+//! sent when the entity spawns.
+
+use super::{CustomName, Entity, EntityId, EntityPosition, EntityRotation, EntityType};
+use crate::protocol::types::{McUuid, Position};
+
+/// Speed in blocks per tick a falling block gains every tick
+pub const GRAVITY: f64 = 0.04;
+
+/// Fraction of its speed a falling block keeps every tick
+pub const DRAG: f64 = 0.98;
+
+/// Height below which a falling block fell out of the world
+pub const VOID_HEIGHT: f64 = -64.0;
+
+/// A block falling under gravity
+pub struct FallingBlockEntity {
+    /// Entity ID
+    entity_id: EntityId,
+    /// Entity UUID
+    uuid: McUuid,
+    /// Name shown above the entity
+    custom_name: Option<CustomName>,
+    /// Block ID of the falling block
+    block: u32,
+    /// Position of the bottom centre of the block
+    position: EntityPosition,
+    /// Vertical speed in blocks per tick, negative when falling
+    velocity: f64,
+    /// Ticks since the block started falling
+    age: u32,
+}
+
+impl FallingBlockEntity {
+    /// Start a block at a position falling
+    pub fn new(entity_id: EntityId, block: u32, from: Position) -> Self {
+        Self {
+            entity_id,
+            uuid: McUuid::new_v4(),
+            custom_name: None,
+            block,
+            position: EntityPosition {
+                x: f64::from(from.x) + 0.5,
+                y: f64::from(from.y),
+                z: f64::from(from.z) + 0.5,
+            },
+            velocity: 0.0,
+            age: 0,
+        }
+    }
+
+    /// Block ID of the falling block
+    pub fn block(&self) -> u32 {
+        self.block
+    }
+
+    /// Ticks since the block started falling
+    pub fn age(&self) -> u32 {
+        self.age
+    }
+
+    /// Block the falling block is inside
+    pub fn block_position(&self) -> Position {
+        Position::new(
+            self.position.x.floor() as i32,
+            self.position.y.floor() as i32,
+            self.position.z.floor() as i32,
+        )
+    }
+}
+
+impl Entity for FallingBlockEntity {
+    fn entity_id(&self) -> EntityId {
+        self.entity_id
+    }
+
+    fn entity_type(&self) -> EntityType {
+        EntityType::FallingBlock
+    }
+
+    fn position(&self) -> EntityPosition {
+        self.position
+    }
+
+    fn rotation(&self) -> EntityRotation {
+        EntityRotation {
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    fn uuid(&self) -> Option<McUuid> {
+        Some(self.uuid)
+    }
+
+    fn custom_name(&self) -> Option<&CustomName> {
+        self.custom_name.as_ref()
+    }
+
+    fn set_custom_name(&mut self, name: Option<CustomName>) {
+        self.custom_name = name;
+    }
+
+    fn is_alive(&self) -> bool {
+        self.position.y >= VOID_HEIGHT
+    }
+
+    fn update(&mut self, _delta_time: f64) {
+        self.age += 1;
+        self.velocity = (self.velocity - GRAVITY) * DRAG;
+        self.position.y += self.velocity;
+    }
+
+    fn as_falling_block(&self) -> Option<&FallingBlockEntity> {
+        Some(self)
+    }
+
+    fn as_falling_block_mut(&mut self) -> Option<&mut FallingBlockEntity> {
+        Some(self)
+    }
+}
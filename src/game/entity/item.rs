@@ -0,0 +1,92 @@
+//! Dropped items
+//!
+//! Items dropped into the world lie where they fell until they despawn.
+//! Players cannot pick them up yet.
+
+use super::{CustomName, Entity, EntityId, EntityPosition, EntityRotation, EntityType};
+use crate::game::loot::ItemDrop;
+use crate::protocol::types::McUuid;
+
+/// Ticks until a dropped item despawns, five minutes
+pub const DESPAWN_TICKS: u32 = 6000;
+
+/// A stack of items lying in the world
+pub struct ItemEntity {
+    /// Entity ID
+    entity_id: EntityId,
+    /// Entity UUID
+    uuid: McUuid,
+    /// Name shown above the entity
+    custom_name: Option<CustomName>,
+    /// The dropped items
+    item: ItemDrop,
+    /// Position
+    position: EntityPosition,
+    /// Ticks since the item was dropped
+    age: u32,
+}
+
+impl ItemEntity {
+    /// Drop items at a position
+    pub fn new(entity_id: EntityId, item: ItemDrop, position: EntityPosition) -> Self {
+        Self {
+            entity_id,
+            uuid: McUuid::new_v4(),
+            custom_name: None,
+            item,
+            position,
+            age: 0,
+        }
+    }
+
+    /// The dropped items
+    pub fn item(&self) -> &ItemDrop {
+        &self.item
+    }
+
+    /// Ticks since the item was dropped
+    pub fn age(&self) -> u32 {
+        self.age
+    }
+}
+
+impl Entity for ItemEntity {
+    fn entity_id(&self) -> EntityId {
+        self.entity_id
+    }
+
+    fn entity_type(&self) -> EntityType {
+        EntityType::Item
+    }
+
+    fn position(&self) -> EntityPosition {
+        self.position
+    }
+
+    fn rotation(&self) -> EntityRotation {
+        EntityRotation {
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    fn uuid(&self) -> Option<McUuid> {
+        Some(self.uuid)
+    }
+
+    fn custom_name(&self) -> Option<&CustomName> {
+        self.custom_name.as_ref()
+    }
+
+    fn set_custom_name(&mut self, name: Option<CustomName>) {
+        self.custom_name = name;
+    }
+
+    fn is_alive(&self) -> bool {
+        self.age < DESPAWN_TICKS
+    }
+
+    fn update(&mut self, _delta_time: f64) {
+        self.age += 1;
+    }
+}
@@ -0,0 +1,240 @@
+//! Mobs
+//!
+//! Mobs stand where they were spawned until they are killed. Players hurt
+//! them by attacking, and the last player to do so is credited with the
+//! kill, which decides what the mob drops.
+//!
+//! Like vanilla, a mob's age counts up to 0 while it is a baby and down to
+//! 0 while it recovers from [breeding](super::breeding).
+
+use super::villager::{self, TradeOffer};
+use super::{CustomName, Entity, EntityId, EntityPosition, EntityRotation, EntityType, MobType};
+use crate::protocol::types::McUuid;
+
+/// Furthest from a mob a player can attack it, with some leeway
+pub const MAX_ATTACK_DISTANCE: f64 = 6.0;
+
+/// Damage dealt by an unarmed attack
+pub const FIST_DAMAGE: f32 = 1.0;
+
+/// A living mob that can be hurt and killed
+pub struct MobEntity {
+    /// Entity ID
+    entity_id: EntityId,
+    /// Entity UUID
+    uuid: McUuid,
+    /// Name shown above the entity
+    custom_name: Option<CustomName>,
+    /// Kind of mob
+    kind: MobType,
+    /// Position
+    position: EntityPosition,
+    /// Rotation
+    rotation: EntityRotation,
+    /// Health left
+    health: f32,
+    /// Player who last hurt the mob
+    attacker: Option<McUuid>,
+    /// Ticks until a baby grows up if negative, or until an adult may
+    /// breed again if positive
+    age: i32,
+    /// Ticks left in love mode
+    love: u32,
+    /// Whether the mob grew up since this was last taken
+    grew_up: bool,
+    /// Trades offered to players, empty for mobs that do not trade
+    offers: Vec<TradeOffer>,
+}
+
+impl MobEntity {
+    /// Create a mob at full health
+    pub fn new(entity_id: EntityId, kind: MobType, position: EntityPosition) -> Self {
+        Self {
+            entity_id,
+            uuid: McUuid::new_v4(),
+            custom_name: None,
+            kind,
+            position,
+            rotation: EntityRotation {
+                yaw: 0.0,
+                pitch: 0.0,
+            },
+            health: kind.max_health(),
+            attacker: None,
+            age: 0,
+            love: 0,
+            grew_up: false,
+            offers: match kind {
+                MobType::Villager => villager::farmer_offers(),
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    /// Set the age; a negative age makes a baby
+    pub fn with_age(mut self, age: i32) -> Self {
+        self.age = age;
+        self
+    }
+
+    /// Set the health, at most the mob's maximum
+    pub fn with_health(mut self, health: f32) -> Self {
+        self.health = health.clamp(0.0, self.kind.max_health());
+        self
+    }
+
+    /// Set the rotation
+    pub fn with_rotation(mut self, rotation: EntityRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Kind of mob
+    pub fn kind(&self) -> MobType {
+        self.kind
+    }
+
+    /// Health left
+    pub fn health(&self) -> f32 {
+        self.health
+    }
+
+    /// Player who last hurt the mob, credited once it dies
+    pub fn attacker(&self) -> Option<McUuid> {
+        self.attacker
+    }
+
+    /// Ticks until a baby grows up if negative, or until an adult may breed
+    /// again if positive
+    pub fn age(&self) -> i32 {
+        self.age
+    }
+
+    /// Set the age; a negative age makes a baby
+    pub fn set_age(&mut self, age: i32) {
+        self.age = age;
+    }
+
+    /// Whether the mob is a baby
+    pub fn is_baby(&self) -> bool {
+        self.age < 0
+    }
+
+    /// Whether the mob looks for a partner
+    pub fn in_love(&self) -> bool {
+        self.love > 0
+    }
+
+    /// Look for a partner for the given number of ticks; zero stops looking
+    pub fn set_love(&mut self, ticks: u32) {
+        self.love = ticks;
+    }
+
+    /// Trades offered to players
+    pub fn offers(&self) -> &[TradeOffer] {
+        &self.offers
+    }
+
+    /// Trades offered to players, for making them
+    pub fn offers_mut(&mut self) -> &mut [TradeOffer] {
+        &mut self.offers
+    }
+
+    /// Whether the mob grew up since the last call
+    pub fn take_grew_up(&mut self) -> bool {
+        std::mem::take(&mut self.grew_up)
+    }
+
+    /// Hurt the mob, crediting `attacker` if a player did it
+    ///
+    /// Returns whether the mob died.
+    pub fn damage(&mut self, amount: f32, attacker: Option<McUuid>) -> bool {
+        if self.health <= 0.0 {
+            return false;
+        }
+        if attacker.is_some() {
+            self.attacker = attacker;
+        }
+        self.health = (self.health - amount.max(0.0)).max(0.0);
+        self.health <= 0.0
+    }
+}
+
+impl Entity for MobEntity {
+    fn entity_id(&self) -> EntityId {
+        self.entity_id
+    }
+
+    fn entity_type(&self) -> EntityType {
+        EntityType::Mob(self.kind)
+    }
+
+    fn position(&self) -> EntityPosition {
+        self.position
+    }
+
+    fn rotation(&self) -> EntityRotation {
+        self.rotation
+    }
+
+    fn uuid(&self) -> Option<McUuid> {
+        Some(self.uuid)
+    }
+
+    fn custom_name(&self) -> Option<&CustomName> {
+        self.custom_name.as_ref()
+    }
+
+    fn set_custom_name(&mut self, name: Option<CustomName>) {
+        self.custom_name = name;
+    }
+
+    fn is_alive(&self) -> bool {
+        self.health > 0.0
+    }
+
+    fn update(&mut self, _delta_time: f64) {
+        // Mobs have no AI yet, they only age
+        match self.age {
+            ..0 => {
+                self.age += 1;
+                self.grew_up = self.age == 0;
+            }
+            1.. => self.age -= 1,
+            0 => {}
+        }
+        self.love = self.love.saturating_sub(1);
+    }
+
+    fn as_mob(&self) -> Option<&MobEntity> {
+        Some(self)
+    }
+
+    fn as_mob_mut(&mut self) -> Option<&mut MobEntity> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_attacker_is_credited() {
+        let origin = EntityPosition {
+            x: 0.0,
+            y: 64.0,
+            z: 0.0,
+        };
+        let mut chicken = MobEntity::new(1, MobType::Chicken, origin);
+        let (steve, alex) = (McUuid::new_v4(), McUuid::new_v4());
+        assert!(!chicken.damage(1.0, Some(steve)));
+        assert!(!chicken.damage(1.0, Some(alex)));
+        assert!(!chicken.damage(1.0, None));
+        assert_eq!(chicken.attacker(), Some(alex));
+        assert!(chicken.damage(5.0, None));
+        assert!(!chicken.is_alive());
+        assert!(!chicken.damage(1.0, Some(steve)));
+        assert_eq!(chicken.attacker(), Some(alex));
+    }
+}
@@ -2,15 +2,48 @@
 //!
 //! This module handles game entities including their properties, behaviors,
 //! and interactions.
+//!
+//! Entities live in the slots of a generational arena. An [`EntityId`]
+//! combines a slot index with the generation of the slot, so IDs of removed
+//! entities can be recycled while lookups with a stale ID find nothing.
 
+pub mod breeding;
+pub mod decoration;
+pub mod experience;
+pub mod explosive;
+pub mod falling_block;
+pub mod item;
+pub mod mob;
 pub mod player;
+pub mod summon;
+pub mod vehicle;
+pub mod villager;
 
-use crate::protocol::types::McUuid;
-use std::collections::HashMap;
+use crate::game::world::region::{self, RegionPosition};
+use crate::protocol::nbt::{Compound, Tag};
+use crate::protocol::types::{JsonTextComponent, McUuid};
+use decoration::{ArmorStandEntity, ItemFrameEntity};
+use explosive::ExplosiveEntity;
+use falling_block::FallingBlockEntity;
+use mob::MobEntity;
+use std::collections::BTreeMap;
+use vehicle::VehicleEntity;
 
-/// Entity ID type
+/// Entity ID type, as sent to clients
 pub type EntityId = i32;
 
+/// Bits of an entity ID holding the slot index
+const INDEX_BITS: u32 = 20;
+
+/// Mask extracting the slot index from an entity ID
+const INDEX_MASK: u32 = (1 << INDEX_BITS) - 1;
+
+/// Largest slot generation, keeping entity IDs positive
+const MAX_GENERATION: u32 = (1 << (31 - INDEX_BITS)) - 1;
+
+/// Most entities that can exist at once
+pub const MAX_ENTITIES: usize = 1 << INDEX_BITS;
+
 /// Base entity trait
 pub trait Entity: Send + Sync {
     /// Get the entity ID
@@ -28,11 +61,79 @@ pub trait Entity: Send + Sync {
     /// Get the entity UUID (if applicable)
     fn uuid(&self) -> Option<McUuid>;
 
+    /// Get the name shown above the entity, if it has one
+    fn custom_name(&self) -> Option<&CustomName>;
+
+    /// Name the entity, or remove its name with `None`
+    ///
+    /// Clients only see the change once the entity's metadata is sent again.
+    fn set_custom_name(&mut self, name: Option<CustomName>);
+
     /// Check if the entity is alive
     fn is_alive(&self) -> bool;
 
     /// Update the entity
     fn update(&mut self, delta_time: f64);
+
+    /// Get the entity as a vehicle, if it can be ridden
+    fn as_vehicle(&self) -> Option<&VehicleEntity> {
+        None
+    }
+
+    /// Get the entity as a mutable vehicle, if it can be ridden
+    fn as_vehicle_mut(&mut self) -> Option<&mut VehicleEntity> {
+        None
+    }
+
+    /// Get the entity as an explosive, if it can blow up
+    fn as_explosive(&self) -> Option<&ExplosiveEntity> {
+        None
+    }
+
+    /// Get the entity as a mutable explosive, if it can blow up
+    fn as_explosive_mut(&mut self) -> Option<&mut ExplosiveEntity> {
+        None
+    }
+
+    /// Get the entity as a mob, if it can be hurt and killed
+    fn as_mob(&self) -> Option<&MobEntity> {
+        None
+    }
+
+    /// Get the entity as a mutable mob, if it can be hurt and killed
+    fn as_mob_mut(&mut self) -> Option<&mut MobEntity> {
+        None
+    }
+
+    /// Get the entity as an armor stand
+    fn as_armor_stand(&self) -> Option<&ArmorStandEntity> {
+        None
+    }
+
+    /// Get the entity as a mutable armor stand
+    fn as_armor_stand_mut(&mut self) -> Option<&mut ArmorStandEntity> {
+        None
+    }
+
+    /// Get the entity as an item frame
+    fn as_item_frame(&self) -> Option<&ItemFrameEntity> {
+        None
+    }
+
+    /// Get the entity as a mutable item frame
+    fn as_item_frame_mut(&mut self) -> Option<&mut ItemFrameEntity> {
+        None
+    }
+
+    /// Get the entity as a falling block
+    fn as_falling_block(&self) -> Option<&FallingBlockEntity> {
+        None
+    }
+
+    /// Get the entity as a mutable falling block
+    fn as_falling_block_mut(&mut self) -> Option<&mut FallingBlockEntity> {
+        None
+    }
 }
 
 /// Entity types
@@ -48,6 +149,66 @@ pub enum EntityType {
     ExperienceOrb,
     /// Projectile
     Projectile(ProjectileType),
+    /// Rideable vehicle
+    Vehicle(VehicleType),
+    /// Primed TNT
+    PrimedTnt,
+    /// Armor stand
+    ArmorStand,
+    /// Item frame
+    ItemFrame,
+    /// Falling block
+    FallingBlock,
+}
+
+impl EntityType {
+    /// Get the namespaced identifier of this entity type (e.g. "minecraft:zombie")
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            EntityType::Player => "minecraft:player",
+            EntityType::Mob(mob) => match mob {
+                MobType::Zombie => "minecraft:zombie",
+                MobType::Skeleton => "minecraft:skeleton",
+                MobType::Creeper => "minecraft:creeper",
+                MobType::Spider => "minecraft:spider",
+                MobType::Cow => "minecraft:cow",
+                MobType::Pig => "minecraft:pig",
+                MobType::Sheep => "minecraft:sheep",
+                MobType::Chicken => "minecraft:chicken",
+                MobType::Villager => "minecraft:villager",
+            },
+            EntityType::Item => "minecraft:item",
+            EntityType::ExperienceOrb => "minecraft:experience_orb",
+            EntityType::Projectile(projectile) => match projectile {
+                ProjectileType::Arrow => "minecraft:arrow",
+                ProjectileType::Snowball => "minecraft:snowball",
+                ProjectileType::Fireball => "minecraft:fireball",
+            },
+            EntityType::Vehicle(vehicle) => match vehicle {
+                VehicleType::Boat => "minecraft:oak_boat",
+                VehicleType::Minecart => "minecraft:minecart",
+            },
+            EntityType::PrimedTnt => "minecraft:tnt",
+            EntityType::ArmorStand => "minecraft:armor_stand",
+            EntityType::ItemFrame => "minecraft:item_frame",
+            EntityType::FallingBlock => "minecraft:falling_block",
+        }
+    }
+
+    /// Get the protocol ID of this entity type, if the server spawns it
+    pub fn protocol_id(&self) -> Option<i32> {
+        match self {
+            EntityType::Vehicle(vehicle) => Some(vehicle.protocol_id()),
+            EntityType::Mob(mob) => Some(mob.protocol_id()),
+            EntityType::Item => Some(71),
+            EntityType::ExperienceOrb => Some(47),
+            EntityType::PrimedTnt => Some(127),
+            EntityType::ArmorStand => Some(5),
+            EntityType::ItemFrame => Some(73),
+            EntityType::FallingBlock => Some(49),
+            _ => None,
+        }
+    }
 }
 
 /// Mob types
@@ -69,6 +230,67 @@ pub enum MobType {
     Sheep,
     /// Chicken
     Chicken,
+    /// Villager, which trades with players
+    Villager,
+}
+
+impl MobType {
+    /// Every kind of mob
+    pub const ALL: [MobType; 9] = [
+        MobType::Zombie,
+        MobType::Skeleton,
+        MobType::Creeper,
+        MobType::Spider,
+        MobType::Cow,
+        MobType::Pig,
+        MobType::Sheep,
+        MobType::Chicken,
+        MobType::Villager,
+    ];
+
+    /// Get the protocol ID of the entity type
+    pub fn protocol_id(&self) -> i32 {
+        match self {
+            MobType::Zombie => 145,
+            MobType::Skeleton => 106,
+            MobType::Creeper => 30,
+            MobType::Spider => 114,
+            MobType::Cow => 28,
+            MobType::Pig => 95,
+            MobType::Sheep => 104,
+            MobType::Chicken => 25,
+            MobType::Villager => 134,
+        }
+    }
+
+    /// Health of a newly spawned mob
+    pub fn max_health(&self) -> f32 {
+        match self {
+            MobType::Zombie | MobType::Skeleton | MobType::Creeper | MobType::Villager => 20.0,
+            MobType::Spider => 16.0,
+            MobType::Cow | MobType::Pig => 10.0,
+            MobType::Sheep => 8.0,
+            MobType::Chicken => 4.0,
+        }
+    }
+
+    /// Whether the mob attacks players
+    pub fn is_hostile(&self) -> bool {
+        matches!(
+            self,
+            MobType::Zombie | MobType::Skeleton | MobType::Creeper | MobType::Spider
+        )
+    }
+
+    /// Whether smite deals extra damage to the mob
+    pub fn is_undead(&self) -> bool {
+        matches!(self, MobType::Zombie | MobType::Skeleton)
+    }
+
+    /// Whether bane of arthropods deals extra damage to the mob
+    pub fn is_arthropod(&self) -> bool {
+        matches!(self, MobType::Spider)
+    }
 }
 
 /// Projectile types
@@ -82,6 +304,40 @@ pub enum ProjectileType {
     Fireball,
 }
 
+/// Vehicle types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VehicleType {
+    /// Oak boat
+    Boat,
+    /// Minecart
+    Minecart,
+}
+
+impl VehicleType {
+    /// Get the protocol ID of the entity type
+    pub fn protocol_id(&self) -> i32 {
+        match self {
+            VehicleType::Boat => 85,
+            VehicleType::Minecart => 82,
+        }
+    }
+
+    /// Most passengers the vehicle carries
+    pub fn max_passengers(&self) -> usize {
+        match self {
+            VehicleType::Boat => 2,
+            VehicleType::Minecart => 1,
+        }
+    }
+
+    /// Whether the passenger in front steers the vehicle
+    ///
+    /// Minecarts follow rails instead.
+    pub fn is_steerable(&self) -> bool {
+        matches!(self, VehicleType::Boat)
+    }
+}
+
 /// Entity position
 #[derive(Debug, Clone, Copy)]
 pub struct EntityPosition {
@@ -102,75 +358,362 @@ pub struct EntityRotation {
     pub pitch: f32,
 }
 
+/// Name shown above an entity, as given by a name tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomName {
+    /// The name
+    pub text: JsonTextComponent,
+    /// Whether the name shows at any distance, not just while looking at
+    /// the entity
+    pub always_visible: bool,
+}
+
+impl CustomName {
+    /// Create a name shown while looking at the entity
+    pub fn new(text: JsonTextComponent) -> Self {
+        Self {
+            text,
+            always_visible: false,
+        }
+    }
+
+    /// Set whether the name shows at any distance
+    pub fn with_always_visible(mut self, always_visible: bool) -> Self {
+        self.always_visible = always_visible;
+        self
+    }
+
+    /// Read the `CustomName` and `CustomNameVisible` tags of entity NBT
+    pub fn from_nbt(nbt: &Compound) -> Option<Self> {
+        let text = JsonTextComponent(nbt.get("CustomName")?.to_json().to_string());
+        let always_visible = nbt
+            .get("CustomNameVisible")
+            .and_then(Tag::as_bool)
+            .unwrap_or(false);
+        Some(Self::new(text).with_always_visible(always_visible))
+    }
+
+    /// Write the `CustomName` and `CustomNameVisible` tags of entity NBT
+    pub fn write_nbt(&self, nbt: &mut Compound) {
+        let value = serde_json::from_str(&self.text.0)
+            .unwrap_or_else(|_| serde_json::Value::String(self.text.0.clone()));
+        if let Some(tag) = Tag::from_json(&value) {
+            nbt.insert("CustomName".to_string(), tag);
+        }
+        if self.always_visible {
+            nbt.insert("CustomNameVisible".to_string(), true.into());
+        }
+    }
+}
+
+/// Contents of an entity slot
+enum SlotEntry {
+    /// Free for a new entity
+    Free,
+    /// Holding an ID for an entity managed elsewhere, such as a player
+    Reserved,
+    /// Holding an entity
+    Occupied(Box<dyn Entity>),
+}
+
+/// A slot of the entity arena
+struct Slot {
+    /// Generation of the current or next ID handed out for this slot
+    generation: u32,
+    /// What the slot holds
+    entry: SlotEntry,
+}
+
 /// Entity manager
 pub struct EntityManager {
-    /// Map of entity ID to entity
-    entities: HashMap<EntityId, Box<dyn Entity>>,
-    /// Next available entity ID
-    next_entity_id: EntityId,
+    /// Entity slots, indexed by the low bits of an entity ID
+    slots: Vec<Slot>,
+    /// Indices of free slots, reused most recent first
+    free: Vec<u32>,
+    /// Number of occupied slots
+    entity_count: usize,
 }
 
 impl EntityManager {
     /// Create a new entity manager
     pub fn new() -> Self {
         Self {
-            entities: HashMap::new(),
-            next_entity_id: 1, // Start from 1, as 0 might be reserved
+            slots: Vec::new(),
+            free: Vec::new(),
+            entity_count: 0,
         }
     }
 
-    /// Generate a new entity ID
+    /// Reserve an entity ID for an entity not stored in this manager
+    ///
+    /// The ID stays in use until it is given back with
+    /// [`release_entity_id`](Self::release_entity_id).
     pub fn next_entity_id(&mut self) -> EntityId {
-        let id = self.next_entity_id;
-        self.next_entity_id += 1;
-        id
+        self.allocate(SlotEntry::Reserved)
     }
 
-    /// Add an entity
-    pub fn add_entity(&mut self, entity: Box<dyn Entity>) -> EntityId {
-        let entity_id = entity.entity_id();
-        self.entities.insert(entity_id, entity);
+    /// Give back an ID reserved with [`next_entity_id`](Self::next_entity_id)
+    ///
+    /// Returns `false` if the ID was not reserved.
+    pub fn release_entity_id(&mut self, entity_id: EntityId) -> bool {
+        match self.slot_mut(entity_id) {
+            Some(slot) if matches!(slot.entry, SlotEntry::Reserved) => {
+                self.free_slot(entity_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Add an entity created with the ID assigned to it
+    pub fn add_entity(&mut self, create: impl FnOnce(EntityId) -> Box<dyn Entity>) -> EntityId {
+        let entity_id = self.allocate(SlotEntry::Reserved);
+        if let Some(slot) = self.slot_mut(entity_id) {
+            slot.entry = SlotEntry::Occupied(create(entity_id));
+            self.entity_count += 1;
+        }
         entity_id
     }
 
+    /// Add an entity created with the ID assigned to it, unless creating
+    /// it fails
+    pub fn try_add_entity(
+        &mut self,
+        create: impl FnOnce(EntityId) -> Option<Box<dyn Entity>>,
+    ) -> Option<EntityId> {
+        let entity_id = self.next_entity_id();
+        let Some(entity) = create(entity_id) else {
+            self.release_entity_id(entity_id);
+            return None;
+        };
+        let slot = self.slot_mut(entity_id)?;
+        slot.entry = SlotEntry::Occupied(entity);
+        self.entity_count += 1;
+        Some(entity_id)
+    }
+
     /// Remove an entity
     pub fn remove_entity(&mut self, entity_id: EntityId) -> Option<Box<dyn Entity>> {
-        self.entities.remove(&entity_id)
+        let slot = self.slot_mut(entity_id)?;
+        if !matches!(slot.entry, SlotEntry::Occupied(_)) {
+            return None;
+        }
+        match self.free_slot(entity_id) {
+            SlotEntry::Occupied(entity) => {
+                self.entity_count -= 1;
+                Some(entity)
+            }
+            SlotEntry::Free | SlotEntry::Reserved => None,
+        }
+    }
+
+    /// Check whether an ID refers to an entity that still exists
+    ///
+    /// IDs of removed entities are stale even after their slot is reused.
+    pub fn contains(&self, entity_id: EntityId) -> bool {
+        self.get_entity(entity_id).is_some()
     }
 
     /// Get an entity
     pub fn get_entity(&self, entity_id: EntityId) -> Option<&dyn Entity> {
-        self.entities.get(&entity_id).map(|e| e.as_ref())
+        match self.slot(entity_id)?.entry {
+            SlotEntry::Occupied(ref entity) => Some(entity.as_ref()),
+            SlotEntry::Free | SlotEntry::Reserved => None,
+        }
     }
 
     /// Get a mutable reference to an entity
     pub fn get_entity_mut(&mut self, entity_id: EntityId) -> Option<&mut Box<dyn Entity>> {
-        self.entities.get_mut(&entity_id)
+        match self.slot_mut(entity_id)?.entry {
+            SlotEntry::Occupied(ref mut entity) => Some(entity),
+            SlotEntry::Free | SlotEntry::Reserved => None,
+        }
     }
 
     /// Get all entities
     pub fn entities(&self) -> impl Iterator<Item = &dyn Entity> {
-        self.entities.values().map(|e| e.as_ref())
+        self.slots.iter().filter_map(|slot| match slot.entry {
+            SlotEntry::Occupied(ref entity) => Some(entity.as_ref()),
+            SlotEntry::Free | SlotEntry::Reserved => None,
+        })
+    }
+
+    /// Get all entities for modification
+    pub fn entities_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Entity>> {
+        self.slots.iter_mut().filter_map(|slot| match slot.entry {
+            SlotEntry::Occupied(ref mut entity) => Some(entity),
+            SlotEntry::Free | SlotEntry::Reserved => None,
+        })
+    }
+
+    /// Update all entities, returning the ones that died
+    pub fn update_all(&mut self, delta_time: f64) -> Vec<Box<dyn Entity>> {
+        self.update_where(delta_time, |_| true)
     }
 
-    /// Update all entities
-    pub fn update_all(&mut self, delta_time: f64) {
-        for entity in self.entities.values_mut() {
-            entity.update(delta_time);
+    /// Update the entities at positions `simulated` accepts, returning the
+    /// ones that died
+    ///
+    /// Entities are grouped by the region they are in and the regions
+    /// updated in parallel (see [`region`](crate::game::world::region)).
+    pub fn update_where(
+        &mut self,
+        delta_time: f64,
+        mut simulated: impl FnMut(EntityPosition) -> bool,
+    ) -> Vec<Box<dyn Entity>> {
+        let mut regions: BTreeMap<RegionPosition, Vec<&mut Box<dyn Entity>>> = BTreeMap::new();
+        for slot in &mut self.slots {
+            if let SlotEntry::Occupied(ref mut entity) = slot.entry {
+                let position = entity.position();
+                if simulated(position) {
+                    regions
+                        .entry(RegionPosition::of_entity(position))
+                        .or_default()
+                        .push(entity);
+                }
+            }
         }
+        let dead: Vec<EntityId> = region::tick_in_phases(regions, |_, entities| {
+            entities
+                .into_iter()
+                .filter_map(|entity| {
+                    entity.update(delta_time);
+                    (!entity.is_alive()).then(|| entity.entity_id())
+                })
+                .collect::<Vec<_>>()
+        })
+        .into_iter()
+        .flatten()
+        .collect();
 
         // Remove dead entities
-        self.entities.retain(|_, entity| entity.is_alive());
+        dead.into_iter()
+            .filter_map(|entity_id| self.remove_entity(entity_id))
+            .collect()
     }
 
     /// Get entity count
     pub fn entity_count(&self) -> usize {
-        self.entities.len()
+        self.entity_count
+    }
+
+    /// Take a free slot, or add one, and fill it
+    fn allocate(&mut self, entry: SlotEntry) -> EntityId {
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index as usize].entry = entry;
+                index
+            }
+            None => {
+                // Running out of IDs means entities are leaking
+                assert!(self.slots.len() < MAX_ENTITIES, "entity limit reached");
+                self.slots.push(Slot {
+                    generation: 1,
+                    entry,
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+        pack_id(index, self.slots[index as usize].generation)
+    }
+
+    /// Empty a slot, so the next entity in it gets a new ID
+    fn free_slot(&mut self, entity_id: EntityId) -> SlotEntry {
+        let index = entity_id as u32 & INDEX_MASK;
+        let slot = &mut self.slots[index as usize];
+        slot.generation = if slot.generation == MAX_GENERATION {
+            1
+        } else {
+            slot.generation + 1
+        };
+        self.free.push(index);
+        std::mem::replace(&mut slot.entry, SlotEntry::Free)
+    }
+
+    /// Find the slot an ID refers to, unless the ID is stale
+    fn slot(&self, entity_id: EntityId) -> Option<&Slot> {
+        let (index, generation) = unpack_id(entity_id)?;
+        self.slots
+            .get(index as usize)
+            .filter(|slot| slot.generation == generation)
+    }
+
+    /// Find the slot an ID refers to for modification, unless the ID is stale
+    fn slot_mut(&mut self, entity_id: EntityId) -> Option<&mut Slot> {
+        let (index, generation) = unpack_id(entity_id)?;
+        self.slots
+            .get_mut(index as usize)
+            .filter(|slot| slot.generation == generation)
     }
 }
 
+/// Combine a slot index and generation into an entity ID
+fn pack_id(index: u32, generation: u32) -> EntityId {
+    ((generation << INDEX_BITS) | index) as EntityId
+}
+
+/// Split an entity ID into its slot index and generation
+fn unpack_id(entity_id: EntityId) -> Option<(u32, u32)> {
+    let id = u32::try_from(entity_id).ok()?;
+    Some((id & INDEX_MASK, id >> INDEX_BITS))
+}
+
 impl Default for EntityManager {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::player::Player;
+    use player::PlayerEntity;
+
+    fn player_entity(entity_id: EntityId) -> Box<dyn Entity> {
+        Box::new(PlayerEntity::new(entity_id, Player::default()))
+    }
+
+    #[test]
+    fn test_entity_ids_are_recycled() {
+        let mut entities = EntityManager::new();
+        let reserved = entities.next_entity_id();
+        let first = entities.add_entity(player_entity);
+        assert_ne!(reserved, 0);
+        assert_ne!(first, reserved);
+        assert_eq!(entities.get_entity(first).unwrap().entity_id(), first);
+        assert!(entities.get_entity(reserved).is_none());
+        assert_eq!(entities.entity_count(), 1);
+
+        assert!(entities.remove_entity(first).is_some());
+        assert!(entities.remove_entity(first).is_none());
+        let second = entities.add_entity(player_entity);
+        assert_ne!(second, first);
+        assert_eq!(second as u32 & INDEX_MASK, first as u32 & INDEX_MASK);
+        assert!(!entities.contains(first));
+        assert!(entities.contains(second));
+
+        assert!(entities.release_entity_id(reserved));
+        assert!(!entities.release_entity_id(reserved));
+        assert!(!entities.release_entity_id(second));
+        assert_eq!(entities.entity_count(), 1);
+        assert!(entities.get_entity(-1).is_none());
+    }
+
+    #[test]
+    fn test_custom_name_nbt() {
+        let mut entities = EntityManager::new();
+        let id = entities.add_entity(player_entity);
+        let name = CustomName::new(JsonTextComponent::colored("Dinnerbone", "gold"))
+            .with_always_visible(true);
+        let entity = entities.get_entity_mut(id).unwrap();
+        entity.set_custom_name(Some(name.clone()));
+        assert_eq!(entity.custom_name(), Some(&name));
+
+        let mut nbt = Compound::new();
+        name.write_nbt(&mut nbt);
+        assert_eq!(nbt.get("CustomNameVisible"), Some(&Tag::Byte(1)));
+        assert_eq!(CustomName::from_nbt(&nbt), Some(name));
+        assert!(CustomName::from_nbt(&Compound::new()).is_none());
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! This module contains the entity implementation for players.
 
-use super::{Entity, EntityId, EntityPosition, EntityRotation, EntityType};
+use super::{CustomName, Entity, EntityId, EntityPosition, EntityRotation, EntityType};
 use crate::game::player::Player;
 use crate::protocol::types::McUuid;
 
@@ -12,12 +12,18 @@ pub struct PlayerEntity {
     entity_id: EntityId,
     /// Player data
     player: Player,
+    /// Name shown above the entity
+    custom_name: Option<CustomName>,
 }
 
 impl PlayerEntity {
     /// Create a new player entity
     pub fn new(entity_id: EntityId, player: Player) -> Self {
-        Self { entity_id, player }
+        Self {
+            entity_id,
+            player,
+            custom_name: None,
+        }
     }
 
     /// Get the player data
@@ -59,6 +65,14 @@ impl Entity for PlayerEntity {
         Some(self.player.uuid)
     }
 
+    fn custom_name(&self) -> Option<&CustomName> {
+        self.custom_name.as_ref()
+    }
+
+    fn set_custom_name(&mut self, name: Option<CustomName>) {
+        self.custom_name = name;
+    }
+
     fn is_alive(&self) -> bool {
         self.player.is_alive()
     }
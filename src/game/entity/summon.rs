@@ -0,0 +1,227 @@
+//! Creating entities by type name
+//!
+//! `/summon` and spawn eggs create entities from a type identifier and
+//! optional entity NBT. Only part of the vanilla NBT applies:
+//! `CustomName` and `CustomNameVisible` on any entity, `Health`, `Age` and
+//! `Rotation` on mobs, `Fuse` on TNT, `ignited` on creepers, `Value` on
+//! experience orbs, `Small`, `ShowArms` and `Rotation` on armor stands,
+//! `Facing` on item frames and `BlockState.Name` on falling blocks. Other
+//! tags are ignored.
+//!
+//! Item entities cannot be summoned, as their item has to be known when
+//! the server starts.
+
+use super::decoration::{ArmorStandEntity, ItemFrameEntity};
+use super::experience::ExperienceOrbEntity;
+use super::explosive::{ExplosiveEntity, ExplosiveKind};
+use super::falling_block::FallingBlockEntity;
+use super::mob::MobEntity;
+use super::vehicle::VehicleEntity;
+use super::{
+    CustomName, Entity, EntityId, EntityPosition, EntityRotation, EntityType, MobType, VehicleType,
+};
+use crate::game::world::gravity::SAND;
+use crate::game::world::registry::BlockRegistry;
+use crate::protocol::nbt::{Compound, Tag};
+use crate::protocol::types::Position;
+
+/// Block face ID an item frame faces when the NBT does not say
+const DEFAULT_FRAME_FACING: u8 = 3;
+
+/// Entity types that can be summoned
+const SUMMONABLE: [EntityType; 7] = [
+    EntityType::Vehicle(VehicleType::Boat),
+    EntityType::Vehicle(VehicleType::Minecart),
+    EntityType::PrimedTnt,
+    EntityType::ExperienceOrb,
+    EntityType::ArmorStand,
+    EntityType::ItemFrame,
+    EntityType::FallingBlock,
+];
+
+/// Type of entity to summon by its identifier, with or without the
+/// `minecraft:` namespace
+pub fn summonable(identifier: &str) -> Option<EntityType> {
+    let identifier = identifier.strip_prefix("minecraft:").unwrap_or(identifier);
+    MobType::ALL
+        .into_iter()
+        .map(EntityType::Mob)
+        .chain(SUMMONABLE)
+        .find(|kind| kind.identifier().strip_prefix("minecraft:") == Some(identifier))
+}
+
+/// Mob a spawn egg spawns, by item name
+pub fn spawn_egg_mob(item: &str) -> Option<MobType> {
+    let mob = item.strip_suffix("_spawn_egg")?;
+    MobType::ALL
+        .into_iter()
+        .find(|&kind| EntityType::Mob(kind).identifier() == mob)
+}
+
+/// Item name of the spawn egg of a mob
+pub fn spawn_egg(mob: MobType) -> String {
+    format!("{}_spawn_egg", EntityType::Mob(mob).identifier())
+}
+
+/// Create an entity of a summonable type at a position, applying the
+/// supported tags of `nbt`
+///
+/// Returns `None` for types that cannot be summoned, or a falling block of
+/// a block that does not exist.
+pub fn create(
+    kind: EntityType,
+    entity_id: EntityId,
+    position: EntityPosition,
+    nbt: &Compound,
+    blocks: &BlockRegistry,
+) -> Option<Box<dyn Entity>> {
+    let block = Position::new(
+        position.x.floor() as i32,
+        position.y.floor() as i32,
+        position.z.floor() as i32,
+    );
+    let rotation = rotation(nbt);
+    let mut entity: Box<dyn Entity> = match kind {
+        EntityType::Mob(MobType::Creeper) => {
+            let mut creeper = ExplosiveEntity::new(entity_id, ExplosiveKind::Creeper, position);
+            if flag(nbt, "ignited") {
+                creeper.ignite();
+            }
+            Box::new(creeper)
+        }
+        EntityType::Mob(mob) => {
+            let mut entity = MobEntity::new(entity_id, mob, position)
+                .with_age(int(nbt, "Age").unwrap_or(0) as i32);
+            if let Some(health) = nbt.get("Health").and_then(Tag::as_f64) {
+                entity = entity.with_health(health as f32);
+            }
+            if let Some(rotation) = rotation {
+                entity = entity.with_rotation(rotation);
+            }
+            Box::new(entity)
+        }
+        EntityType::Vehicle(vehicle) => Box::new(VehicleEntity::new(entity_id, vehicle, position)),
+        EntityType::PrimedTnt => {
+            let tnt = ExplosiveEntity::new(entity_id, ExplosiveKind::Tnt, position);
+            match int(nbt, "Fuse") {
+                Some(fuse) => Box::new(tnt.with_fuse(fuse.max(0) as u32)),
+                None => Box::new(tnt),
+            }
+        }
+        EntityType::ExperienceOrb => {
+            let value = int(nbt, "Value").unwrap_or(1).max(1) as u32;
+            Box::new(ExperienceOrbEntity::new(entity_id, value, position))
+        }
+        EntityType::ArmorStand => {
+            let yaw = rotation.map_or(0.0, |rotation| rotation.yaw);
+            let mut stand = ArmorStandEntity::new(entity_id, position, yaw);
+            stand.set_small(flag(nbt, "Small"));
+            stand.set_arms(flag(nbt, "ShowArms"));
+            Box::new(stand)
+        }
+        EntityType::ItemFrame => {
+            let facing = int(nbt, "Facing")
+                .filter(|facing| (0..6).contains(facing))
+                .map_or(DEFAULT_FRAME_FACING, |facing| facing as u8);
+            Box::new(ItemFrameEntity::new(entity_id, block, facing))
+        }
+        EntityType::FallingBlock => {
+            let name = nbt
+                .get("BlockState")
+                .and_then(Tag::as_compound)
+                .and_then(|state| state.get("Name"))
+                .and_then(Tag::as_str);
+            let falling = match name {
+                Some(name) => blocks.get_block_id(name)?,
+                None => SAND,
+            };
+            Box::new(FallingBlockEntity::new(entity_id, falling, block))
+        }
+        EntityType::Player | EntityType::Item | EntityType::Projectile(_) => return None,
+    };
+    if let Some(name) = CustomName::from_nbt(nbt) {
+        entity.set_custom_name(Some(name));
+    }
+    Some(entity)
+}
+
+/// An integer tag
+fn int(nbt: &Compound, key: &str) -> Option<i64> {
+    nbt.get(key).and_then(Tag::as_i64)
+}
+
+/// A boolean tag, false if missing
+fn flag(nbt: &Compound, key: &str) -> bool {
+    nbt.get(key).and_then(Tag::as_bool).unwrap_or(false)
+}
+
+/// The `Rotation` tag, a list of yaw and pitch
+fn rotation(nbt: &Compound) -> Option<EntityRotation> {
+    match nbt.get("Rotation").and_then(Tag::as_list)? {
+        [yaw, pitch] => Some(EntityRotation {
+            yaw: yaw.as_f64()? as f32,
+            pitch: pitch.as_f64()? as f32,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::nbt::parse_snbt;
+
+    fn summon(kind: EntityType, snbt: &str) -> Option<Box<dyn Entity>> {
+        let position = EntityPosition {
+            x: 0.5,
+            y: 64.0,
+            z: 0.5,
+        };
+        let nbt = parse_snbt(snbt).unwrap();
+        create(kind, 1, position, &nbt, &BlockRegistry::new())
+    }
+
+    #[test]
+    fn test_summonable() {
+        assert_eq!(summonable("pig"), Some(EntityType::Mob(MobType::Pig)));
+        assert_eq!(
+            summonable("minecraft:oak_boat"),
+            Some(EntityType::Vehicle(VehicleType::Boat))
+        );
+        assert_eq!(summonable("minecraft:item"), None);
+        assert_eq!(summonable("player"), None);
+        assert_eq!(
+            spawn_egg_mob(&spawn_egg(MobType::Sheep)),
+            Some(MobType::Sheep)
+        );
+        assert_eq!(spawn_egg_mob("minecraft:stone"), None);
+    }
+
+    #[test]
+    fn test_applies_nbt() {
+        let cow = summon(
+            EntityType::Mob(MobType::Cow),
+            r#"{Age:-24000,Health:3.5f,CustomName:"Daisy",Rotation:[90f,0f]}"#,
+        )
+        .unwrap();
+        let mob = cow.as_mob().unwrap();
+        assert!(mob.is_baby());
+        assert_eq!(mob.health(), 3.5);
+        assert_eq!(cow.rotation().yaw, 90.0);
+        assert!(cow.custom_name().is_some());
+
+        let creeper = summon(EntityType::Mob(MobType::Creeper), "{ignited:1b}").unwrap();
+        assert_eq!(creeper.entity_type(), EntityType::Mob(MobType::Creeper));
+        assert!(creeper.as_explosive().and_then(|e| e.fuse()).is_some());
+
+        let falling = summon(EntityType::FallingBlock, "{}").unwrap();
+        assert_eq!(falling.as_falling_block().map(|f| f.block()), Some(SAND));
+        assert!(
+            summon(
+                EntityType::FallingBlock,
+                r#"{BlockState:{Name:"minecraft:nothing"}}"#
+            )
+            .is_none()
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Vehicles and riding
+//!
+//! Boats and minecarts carry players. A player gets in by using the
+//! vehicle and gets out by sneaking. The passenger in front of a boat
+//! steers it: their client moves the boat and reports where it went, and
+//! every passenger is moved along with it. Minecarts are not steered.
+
+use super::VehicleType;
+use super::{
+    CustomName, Entity, EntityId, EntityManager, EntityPosition, EntityRotation, EntityType,
+};
+use crate::game::movement::MAX_MOVE_DISTANCE;
+use crate::game::player::{GameMode, Player};
+use crate::protocol::types::McUuid;
+use thiserror::Error;
+
+/// Furthest from a vehicle a player can get in
+pub const MAX_MOUNT_DISTANCE: f64 = 5.0;
+
+/// Reasons a player cannot get in or steer a vehicle
+#[derive(Error, Debug, Clone, Copy, PartialEq)]
+pub enum RideError {
+    /// The entity does not exist or cannot be ridden
+    #[error("That cannot be ridden")]
+    NotAVehicle,
+    /// Every seat is taken
+    #[error("The vehicle is full")]
+    Full,
+    /// The player is too far away to get in
+    #[error("The vehicle is too far away")]
+    TooFar,
+    /// The player is not steering the vehicle they report moving
+    #[error("The player is not steering a vehicle")]
+    NotSteering,
+    /// The vehicle moved further than any client could move it at once
+    #[error("Vehicle moved too quickly ({0:.1} blocks)")]
+    TooFast(f64),
+    /// Spectators cannot ride vehicles
+    #[error("Spectators cannot ride vehicles")]
+    Spectating,
+}
+
+/// A boat or minecart
+pub struct VehicleEntity {
+    /// Entity ID
+    entity_id: EntityId,
+    /// Entity UUID
+    uuid: McUuid,
+    /// Name shown above the entity
+    custom_name: Option<CustomName>,
+    /// Kind of vehicle
+    kind: VehicleType,
+    /// Position
+    position: EntityPosition,
+    /// Rotation
+    rotation: EntityRotation,
+    /// Whether the vehicle rests on the ground
+    on_ground: bool,
+    /// Riding entities, the one steering first
+    passengers: Vec<EntityId>,
+}
+
+impl VehicleEntity {
+    /// Create an empty vehicle
+    pub fn new(entity_id: EntityId, kind: VehicleType, position: EntityPosition) -> Self {
+        Self {
+            entity_id,
+            uuid: McUuid::new_v4(),
+            custom_name: None,
+            kind,
+            position,
+            rotation: EntityRotation {
+                yaw: 0.0,
+                pitch: 0.0,
+            },
+            on_ground: true,
+            passengers: Vec::new(),
+        }
+    }
+
+    /// Kind of vehicle
+    pub fn kind(&self) -> VehicleType {
+        self.kind
+    }
+
+    /// Whether the vehicle rests on the ground
+    pub fn on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    /// Riding entities, the one steering first
+    pub fn passengers(&self) -> &[EntityId] {
+        &self.passengers
+    }
+
+    /// The passenger steering the vehicle, if it can be steered
+    pub fn driver(&self) -> Option<EntityId> {
+        self.passengers
+            .first()
+            .copied()
+            .filter(|_| self.kind.is_steerable())
+    }
+
+    /// Move the vehicle
+    pub fn move_to(&mut self, position: EntityPosition, rotation: EntityRotation, on_ground: bool) {
+        self.position = position;
+        self.rotation = rotation;
+        self.on_ground = on_ground;
+    }
+}
+
+impl Entity for VehicleEntity {
+    fn entity_id(&self) -> EntityId {
+        self.entity_id
+    }
+
+    fn entity_type(&self) -> EntityType {
+        EntityType::Vehicle(self.kind)
+    }
+
+    fn position(&self) -> EntityPosition {
+        self.position
+    }
+
+    fn rotation(&self) -> EntityRotation {
+        self.rotation
+    }
+
+    fn uuid(&self) -> Option<McUuid> {
+        Some(self.uuid)
+    }
+
+    fn custom_name(&self) -> Option<&CustomName> {
+        self.custom_name.as_ref()
+    }
+
+    fn set_custom_name(&mut self, name: Option<CustomName>) {
+        self.custom_name = name;
+    }
+
+    fn is_alive(&self) -> bool {
+        true
+    }
+
+    fn update(&mut self, _delta_time: f64) {
+        // Boats are moved by their driver; minecarts stay put until rails exist
+    }
+
+    fn as_vehicle(&self) -> Option<&VehicleEntity> {
+        Some(self)
+    }
+
+    fn as_vehicle_mut(&mut self) -> Option<&mut VehicleEntity> {
+        Some(self)
+    }
+}
+
+/// Put a player in a vehicle, taking them out of any other first
+///
+/// Returns the vehicle the player left, if any.
+pub fn mount(
+    entities: &mut EntityManager,
+    player: &mut Player,
+    vehicle_id: EntityId,
+) -> Result<Option<EntityId>, RideError> {
+    if player.game_mode == GameMode::Spectator {
+        return Err(RideError::Spectating);
+    }
+    let vehicle = entities
+        .get_entity(vehicle_id)
+        .and_then(|entity| entity.as_vehicle())
+        .ok_or(RideError::NotAVehicle)?;
+    if player.vehicle == Some(vehicle_id) {
+        return Ok(None);
+    }
+    if vehicle.passengers.len() >= vehicle.kind.max_passengers() {
+        return Err(RideError::Full);
+    }
+    let position = vehicle.position;
+    let (dx, dy, dz) = (
+        position.x - player.position.x,
+        position.y - player.position.y,
+        position.z - player.position.z,
+    );
+    if (dx * dx + dy * dy + dz * dz).sqrt() > MAX_MOUNT_DISTANCE {
+        return Err(RideError::TooFar);
+    }
+
+    let left = dismount(entities, player);
+    if let Some(vehicle) = entities
+        .get_entity_mut(vehicle_id)
+        .and_then(|entity| entity.as_vehicle_mut())
+    {
+        vehicle.passengers.push(player.entity_id);
+    }
+    player.vehicle = Some(vehicle_id);
+    player.set_position(position.x, position.y, position.z);
+    Ok(left)
+}
+
+/// Take a player out of their vehicle, returning the vehicle they left
+pub fn dismount(entities: &mut EntityManager, player: &mut Player) -> Option<EntityId> {
+    let vehicle_id = player.vehicle.take()?;
+    if let Some(vehicle) = entities
+        .get_entity_mut(vehicle_id)
+        .and_then(|entity| entity.as_vehicle_mut())
+    {
+        vehicle.passengers.retain(|&id| id != player.entity_id);
+    }
+    Some(vehicle_id)
+}
+
+/// Move the vehicle a player steers to where their client moved it
+///
+/// Returns the vehicle, whose passengers are to be moved along.
+pub fn steer(
+    entities: &mut EntityManager,
+    player: &mut Player,
+    position: EntityPosition,
+    rotation: EntityRotation,
+    on_ground: bool,
+) -> Result<EntityId, RideError> {
+    let vehicle_id = player.vehicle.ok_or(RideError::NotSteering)?;
+    let vehicle = entities
+        .get_entity_mut(vehicle_id)
+        .and_then(|entity| entity.as_vehicle_mut())
+        .filter(|vehicle| vehicle.driver() == Some(player.entity_id))
+        .ok_or(RideError::NotSteering)?;
+
+    let (dx, dy, dz) = (
+        position.x - vehicle.position.x,
+        position.y - vehicle.position.y,
+        position.z - vehicle.position.z,
+    );
+    let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+    if !distance.is_finite() || distance > MAX_MOVE_DISTANCE {
+        return Err(RideError::TooFast(distance));
+    }
+
+    vehicle.move_to(position, rotation, on_ground);
+    player.set_position(position.x, position.y, position.z);
+    Ok(vehicle_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(entities: &mut EntityManager, kind: VehicleType, x: f64) -> EntityId {
+        let position = EntityPosition { x, y: 64.0, z: 0.0 };
+        entities.add_entity(|id| Box::new(VehicleEntity::new(id, kind, position)))
+    }
+
+    fn player(entities: &mut EntityManager, name: &str) -> Player {
+        let mut player = Player::new(McUuid::new_v4(), name.to_string());
+        player.entity_id = entities.next_entity_id();
+        player.set_position(1.0, 64.0, 0.0);
+        player
+    }
+
+    fn vehicle(entities: &EntityManager, id: EntityId) -> &VehicleEntity {
+        entities.get_entity(id).unwrap().as_vehicle().unwrap()
+    }
+
+    #[test]
+    fn test_riding_a_boat() {
+        let mut entities = EntityManager::new();
+        let boat = spawn(&mut entities, VehicleType::Boat, 0.0);
+        let minecart = spawn(&mut entities, VehicleType::Minecart, 3.0);
+        let far = spawn(&mut entities, VehicleType::Boat, 20.0);
+        let mut steve = player(&mut entities, "Steve");
+        let mut alex = player(&mut entities, "Alex");
+        let mut notch = player(&mut entities, "Notch");
+
+        assert_eq!(
+            mount(&mut entities, &mut steve, far),
+            Err(RideError::TooFar)
+        );
+        let itself = steve.entity_id;
+        assert_eq!(
+            mount(&mut entities, &mut steve, itself),
+            Err(RideError::NotAVehicle)
+        );
+        assert_eq!(mount(&mut entities, &mut steve, boat), Ok(None));
+        assert_eq!(mount(&mut entities, &mut alex, boat), Ok(None));
+        assert_eq!(mount(&mut entities, &mut notch, boat), Err(RideError::Full));
+        assert_eq!(vehicle(&entities, boat).driver(), Some(steve.entity_id));
+
+        // Only the driver steers
+        let position = EntityPosition {
+            x: 4.0,
+            y: 63.5,
+            z: 2.0,
+        };
+        let rotation = EntityRotation {
+            yaw: 90.0,
+            pitch: 0.0,
+        };
+        assert_eq!(
+            steer(&mut entities, &mut alex, position, rotation, false),
+            Err(RideError::NotSteering)
+        );
+        assert_eq!(
+            steer(&mut entities, &mut steve, position, rotation, false),
+            Ok(boat)
+        );
+        assert_eq!(steve.position.x, 4.0);
+        assert_eq!(vehicle(&entities, boat).position().z, 2.0);
+
+        // Switching vehicles leaves the first one
+        assert_eq!(mount(&mut entities, &mut steve, minecart), Ok(Some(boat)));
+        assert_eq!(vehicle(&entities, boat).passengers(), [alex.entity_id]);
+        assert_eq!(vehicle(&entities, minecart).driver(), None);
+        assert_eq!(dismount(&mut entities, &mut steve), Some(minecart));
+        assert!(vehicle(&entities, minecart).passengers().is_empty());
+        assert_eq!(dismount(&mut entities, &mut steve), None);
+    }
+}
@@ -0,0 +1,310 @@
+//! Villager trading
+//!
+//! Every villager is a farmer with a fixed list of trades. Using a villager
+//! opens its trading screen, where picking a trade moves what it costs from
+//! the player's inventory into the two payment slots. Taking the result
+//! makes the trade, using up the payment, and the result always goes
+//! straight into the inventory as with a shift-click. No trade is made while
+//! the result does not fit. Items cannot be put into the payment slots by
+//! hand.
+//!
+//! Trades run out after a number of uses; villagers do not restock, level
+//! up or change prices with demand yet.
+
+use super::EntityId;
+use crate::game::inventory::{Inventory, ItemStack};
+
+/// Item ID of an emerald
+const EMERALD: i32 = 388;
+
+/// Item ID of wheat
+const WHEAT: i32 = 296;
+
+/// Item ID of a potato
+const POTATO: i32 = 392;
+
+/// Item ID of a carrot
+const CARROT: i32 = 391;
+
+/// Item ID of bread
+const BREAD: i32 = 364;
+
+/// Item ID of an apple
+const APPLE: i32 = 260;
+
+/// Farthest a player can be from a villager to trade with it, in blocks
+pub const MAX_TRADE_DISTANCE: f64 = 8.0;
+
+/// Window slots of the trading screen before the player's inventory
+pub const MERCHANT_SLOTS: usize = 3;
+
+/// Window slot of a trade's result
+pub const RESULT_SLOT: i16 = 2;
+
+/// A trade a villager offers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeOffer {
+    /// First item paid
+    pub cost: ItemStack,
+    /// Second item paid, if any
+    pub second_cost: Option<ItemStack>,
+    /// Item received
+    pub result: ItemStack,
+    /// Times the trade was made
+    pub uses: i32,
+    /// Times the trade can be made
+    pub max_uses: i32,
+    /// Experience the villager gains from the trade
+    pub experience: i32,
+    /// How much demand changes the price
+    pub price_multiplier: f32,
+}
+
+impl TradeOffer {
+    /// Create an offer trading one item for another
+    pub fn new(cost: ItemStack, result: ItemStack, max_uses: i32, experience: i32) -> Self {
+        Self {
+            cost,
+            second_cost: None,
+            result,
+            uses: 0,
+            max_uses,
+            experience,
+            price_multiplier: 0.05,
+        }
+    }
+
+    /// Whether the trade cannot be made any more
+    pub fn is_out_of_stock(&self) -> bool {
+        self.uses >= self.max_uses
+    }
+
+    /// Whether the items in the payment slots pay for the trade
+    pub fn is_paid_by(&self, payment: &[Option<ItemStack>; 2]) -> bool {
+        let covers = |slot: Option<ItemStack>, cost: Option<ItemStack>| match (slot, cost) {
+            (_, None) => true,
+            (Some(slot), Some(cost)) => slot.item == cost.item && slot.count >= cost.count,
+            (None, Some(_)) => false,
+        };
+        covers(payment[0], Some(self.cost)) && covers(payment[1], self.second_cost)
+    }
+}
+
+/// Trades of a farmer
+pub fn farmer_offers() -> Vec<TradeOffer> {
+    vec![
+        TradeOffer::new(ItemStack::new(WHEAT, 20), ItemStack::new(EMERALD, 1), 16, 2),
+        TradeOffer::new(
+            ItemStack::new(POTATO, 26),
+            ItemStack::new(EMERALD, 1),
+            16,
+            2,
+        ),
+        TradeOffer::new(
+            ItemStack::new(CARROT, 22),
+            ItemStack::new(EMERALD, 1),
+            16,
+            2,
+        ),
+        TradeOffer::new(ItemStack::new(EMERALD, 1), ItemStack::new(BREAD, 6), 16, 1),
+        TradeOffer::new(ItemStack::new(EMERALD, 1), ItemStack::new(APPLE, 4), 16, 5),
+    ]
+}
+
+/// A player's open trading screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trading {
+    /// Window ID of the screen
+    pub window_id: i32,
+    /// Villager traded with
+    pub villager: EntityId,
+    /// Trade picked, if any
+    selected: Option<usize>,
+    /// Items in the payment slots
+    payment: [Option<ItemStack>; 2],
+}
+
+impl Trading {
+    /// Start trading with a villager
+    pub fn new(window_id: i32, villager: EntityId) -> Self {
+        Self {
+            window_id,
+            villager,
+            selected: None,
+            payment: [None; 2],
+        }
+    }
+
+    /// Contents of the screen's own slots: both payment slots and the
+    /// result
+    pub fn slots(&self, offers: &[TradeOffer]) -> [Option<ItemStack>; MERCHANT_SLOTS] {
+        [self.payment[0], self.payment[1], self.result(offers)]
+    }
+
+    /// Result of the selected trade, if the payment covers it
+    pub fn result(&self, offers: &[TradeOffer]) -> Option<ItemStack> {
+        let offer = offers.get(self.selected?)?;
+        (!offer.is_out_of_stock() && offer.is_paid_by(&self.payment)).then_some(offer.result)
+    }
+
+    /// Pick a trade, moving what it costs from the inventory into the
+    /// payment slots
+    ///
+    /// Items already in the payment slots go back to the inventory first.
+    /// Returns items that did not fit back into the inventory.
+    pub fn select(
+        &mut self,
+        index: usize,
+        offers: &[TradeOffer],
+        inventory: &mut Inventory,
+        max_stack_size: impl Fn(i32) -> i32,
+    ) -> Vec<ItemStack> {
+        let left_over = self.return_payment(inventory, &max_stack_size);
+        let Some(offer) = offers.get(index) else {
+            self.selected = None;
+            return left_over;
+        };
+        self.selected = Some(index);
+        for (slot, cost) in [Some(offer.cost), offer.second_cost]
+            .into_iter()
+            .enumerate()
+        {
+            if let Some(cost) = cost {
+                let taken = inventory.remove(cost.item, max_stack_size(cost.item));
+                self.payment[slot] = (taken > 0).then(|| ItemStack::new(cost.item, taken));
+            }
+        }
+        left_over
+    }
+
+    /// Make the selected trade once, using up the payment and putting the
+    /// result into the inventory
+    ///
+    /// Returns the result, or `None` if the trade cannot be made or the
+    /// result does not fit, in which case nothing changes.
+    pub fn trade(
+        &mut self,
+        offers: &mut [TradeOffer],
+        inventory: &mut Inventory,
+        max_stack_size: impl Fn(i32) -> i32,
+    ) -> Option<ItemStack> {
+        let result = self.result(offers)?;
+        let stack_size = max_stack_size(result.item);
+        if !inventory.can_add(result, stack_size) {
+            return None;
+        }
+        let offer = offers.get_mut(self.selected?)?;
+        offer.uses += 1;
+        for (slot, cost) in [Some(offer.cost), offer.second_cost]
+            .into_iter()
+            .enumerate()
+        {
+            if let (Some(paid), Some(cost)) = (self.payment[slot].as_mut(), cost) {
+                paid.count -= cost.count;
+                if paid.count <= 0 {
+                    self.payment[slot] = None;
+                }
+            }
+        }
+        inventory.add(result, stack_size);
+        Some(result)
+    }
+
+    /// Put the items in the payment slots back into the inventory
+    ///
+    /// Returns the items that did not fit.
+    pub fn return_payment(
+        &mut self,
+        inventory: &mut Inventory,
+        max_stack_size: impl Fn(i32) -> i32,
+    ) -> Vec<ItemStack> {
+        self.payment
+            .iter_mut()
+            .filter_map(Option::take)
+            .filter_map(|paid| inventory.add(paid, max_stack_size(paid.item)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::inventory::{HOTBAR_START, STORAGE_SLOTS};
+
+    #[test]
+    fn test_trading() {
+        let mut offers = farmer_offers();
+        let mut inventory = Inventory::new();
+        inventory.set(HOTBAR_START, Some(ItemStack::new(WHEAT, 45)));
+        let stack_size = |_| 64;
+
+        let mut trading = Trading::new(1, 7);
+        assert_eq!(trading.result(&offers), None);
+        assert!(
+            trading
+                .select(0, &offers, &mut inventory, stack_size)
+                .is_empty()
+        );
+        assert_eq!(inventory.get(HOTBAR_START), None);
+        assert_eq!(trading.result(&offers), Some(ItemStack::new(EMERALD, 1)));
+
+        let emerald = Some(ItemStack::new(EMERALD, 1));
+        assert_eq!(
+            trading.trade(&mut offers, &mut inventory, stack_size),
+            emerald
+        );
+        assert_eq!(
+            trading.trade(&mut offers, &mut inventory, stack_size),
+            emerald
+        );
+        assert_eq!(trading.trade(&mut offers, &mut inventory, stack_size), None);
+        assert_eq!(offers[0].uses, 2);
+        assert_eq!(
+            inventory.get(HOTBAR_START),
+            Some(ItemStack::new(EMERALD, 2))
+        );
+        assert_eq!(trading.slots(&offers)[0], Some(ItemStack::new(WHEAT, 5)));
+
+        // Picking a trade the player cannot pay for returns the payment
+        trading.select(1, &offers, &mut inventory, stack_size);
+        assert_eq!(
+            inventory.get(HOTBAR_START + 1),
+            Some(ItemStack::new(WHEAT, 5))
+        );
+        assert_eq!(trading.slots(&offers), [None; MERCHANT_SLOTS]);
+
+        offers[0].uses = offers[0].max_uses;
+        inventory.set(HOTBAR_START, Some(ItemStack::new(WHEAT, 64)));
+        trading.select(0, &offers, &mut inventory, stack_size);
+        assert_eq!(trading.trade(&mut offers, &mut inventory, stack_size), None);
+    }
+
+    #[test]
+    fn test_trading_with_full_inventory() {
+        let mut offers = farmer_offers();
+        let mut inventory = Inventory::new();
+        inventory.set(HOTBAR_START, Some(ItemStack::new(WHEAT, 20)));
+        let stack_size = |_| 64;
+        let mut trading = Trading::new(1, 7);
+        trading.select(0, &offers, &mut inventory, stack_size);
+        for slot in STORAGE_SLOTS {
+            inventory.set(slot, Some(ItemStack::new(BREAD, 64)));
+        }
+
+        // The payment stays put until the result fits
+        assert_eq!(trading.trade(&mut offers, &mut inventory, stack_size), None);
+        assert_eq!(offers[0].uses, 0);
+        assert_eq!(trading.slots(&offers)[0], Some(ItemStack::new(WHEAT, 20)));
+
+        inventory.set(HOTBAR_START, None);
+        assert_eq!(
+            trading.trade(&mut offers, &mut inventory, stack_size),
+            Some(ItemStack::new(EMERALD, 1))
+        );
+        assert_eq!(
+            inventory.get(HOTBAR_START),
+            Some(ItemStack::new(EMERALD, 1))
+        );
+        assert_eq!(trading.slots(&offers)[0], None);
+    }
+}
@@ -0,0 +1,48 @@
+//! Food
+//!
+//! Players eat food by holding the use button for [`EAT_TICKS`] ticks.
+//! Status effects of food, such as the poison of spider eyes, are not
+//! applied.
+
+/// Ticks it takes to eat
+pub const EAT_TICKS: u32 = 32;
+
+/// What eating an item restores
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Food {
+    /// Food points restored
+    pub nutrition: i32,
+    /// Saturation gained
+    pub saturation: f32,
+}
+
+/// Food values of edible items, by name
+const FOODS: [(&str, Food); 11] = [
+    ("minecraft:apple", food(4, 2.4)),
+    ("minecraft:beef", food(3, 1.8)),
+    ("minecraft:bread", food(5, 6.0)),
+    ("minecraft:carrot", food(3, 3.6)),
+    ("minecraft:chicken", food(2, 1.2)),
+    ("minecraft:mutton", food(2, 1.2)),
+    ("minecraft:poisonous_potato", food(2, 1.2)),
+    ("minecraft:porkchop", food(3, 1.8)),
+    ("minecraft:potato", food(1, 0.6)),
+    ("minecraft:rotten_flesh", food(4, 0.8)),
+    ("minecraft:spider_eye", food(2, 3.2)),
+];
+
+/// Shorthand for the food table
+const fn food(nutrition: i32, saturation: f32) -> Food {
+    Food {
+        nutrition,
+        saturation,
+    }
+}
+
+/// Food value of an item, if it is edible
+pub fn food_value(item: &str) -> Option<Food> {
+    FOODS
+        .iter()
+        .find(|(name, _)| *name == item)
+        .map(|&(_, food)| food)
+}
@@ -0,0 +1,497 @@
+//! Player inventories
+//!
+//! A player's inventory is laid out like the player window the client
+//! shows: crafting slots, armor, the main inventory, the hotbar and the
+//! offhand. Items are stacks of an item ID with the few item components
+//! the server supports, such as enchantments.
+
+use crate::game::enchantment::Enchantments;
+use serde::{Deserialize, Serialize};
+
+/// Number of slots in the player window
+pub const SLOT_COUNT: usize = 46;
+
+/// Number of hotbar slots
+pub const HOTBAR_SIZE: u8 = 9;
+
+/// Largest stack size an item may have
+pub const MAX_STACK_SIZE: i32 = 99;
+
+/// Window slot of the helmet
+pub const HEAD_SLOT: usize = 5;
+
+/// Window slot of the chestplate
+pub const CHEST_SLOT: usize = 6;
+
+/// Window slot of the leggings
+pub const LEGS_SLOT: usize = 7;
+
+/// Window slot of the boots
+pub const FEET_SLOT: usize = 8;
+
+/// Window slot of the first hotbar slot
+pub const HOTBAR_START: usize = 36;
+
+/// Window slot of the offhand
+pub const OFFHAND_SLOT: usize = 45;
+
+/// Window slots of the main inventory and the hotbar
+pub const STORAGE_SLOTS: std::ops::Range<usize> = 9..45;
+
+/// A stack of items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    /// Item registry ID
+    pub item: i32,
+    /// Number of items, at least one
+    pub count: i32,
+    /// Components that differ from the item's defaults
+    #[serde(default, skip_serializing_if = "ItemComponents::is_empty")]
+    pub components: ItemComponents,
+}
+
+impl ItemStack {
+    /// Create a stack of items
+    pub fn new(item: i32, count: i32) -> Self {
+        Self {
+            item,
+            count,
+            components: ItemComponents::default(),
+        }
+    }
+
+    /// Set the enchantments of the items
+    pub fn with_enchantments(mut self, enchantments: Enchantments) -> Self {
+        self.components.enchantments = enchantments;
+        self
+    }
+
+    /// Enchantments of the items
+    pub fn enchantments(&self) -> &Enchantments {
+        &self.components.enchantments
+    }
+
+    /// Whether the stack could exist in an inventory
+    pub fn is_valid(&self) -> bool {
+        self.item >= 0 && (1..=MAX_STACK_SIZE).contains(&self.count)
+    }
+}
+
+/// Item components that differ from an item's defaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ItemComponents {
+    /// Enchantments on the item
+    #[serde(default, skip_serializing_if = "Enchantments::is_empty")]
+    pub enchantments: Enchantments,
+}
+
+impl ItemComponents {
+    /// Whether no component differs from the defaults
+    pub fn is_empty(&self) -> bool {
+        self.enchantments.is_empty()
+    }
+}
+
+/// A hand a player uses items with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    /// The main hand, holding the selected hotbar item
+    Main,
+    /// The offhand
+    Off,
+}
+
+impl Hand {
+    /// Get a hand from its protocol ID
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            0 => Some(Hand::Main),
+            1 => Some(Hand::Off),
+            _ => None,
+        }
+    }
+
+    /// Equipment slot the hand's item is shown in
+    pub fn equipment_slot(self) -> EquipmentSlot {
+        match self {
+            Hand::Main => EquipmentSlot::MainHand,
+            Hand::Off => EquipmentSlot::OffHand,
+        }
+    }
+}
+
+/// An item in a saved inventory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedItem {
+    /// Window slot the item is in
+    pub slot: u8,
+    /// The item
+    #[serde(flatten)]
+    pub item: ItemStack,
+}
+
+/// Equipment slot shown on an entity, with its protocol ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquipmentSlot {
+    /// Held in the main hand
+    MainHand = 0,
+    /// Held in the offhand
+    OffHand = 1,
+    /// Boots
+    Feet = 2,
+    /// Leggings
+    Legs = 3,
+    /// Chestplate
+    Chest = 4,
+    /// Helmet
+    Head = 5,
+}
+
+impl EquipmentSlot {
+    /// Slots shown on other players
+    pub const VISIBLE: [EquipmentSlot; 6] = [
+        EquipmentSlot::MainHand,
+        EquipmentSlot::OffHand,
+        EquipmentSlot::Feet,
+        EquipmentSlot::Legs,
+        EquipmentSlot::Chest,
+        EquipmentSlot::Head,
+    ];
+
+    /// Protocol ID of the slot
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Slot an item is worn in, by item name, or the main hand for items
+    /// that are not worn
+    pub fn for_item(name: &str) -> Self {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        if name.ends_with("_helmet")
+            || name.ends_with("_head")
+            || name.ends_with("_skull")
+            || name == "carved_pumpkin"
+        {
+            EquipmentSlot::Head
+        } else if name.ends_with("_chestplate") || name == "elytra" {
+            EquipmentSlot::Chest
+        } else if name.ends_with("_leggings") {
+            EquipmentSlot::Legs
+        } else if name.ends_with("_boots") {
+            EquipmentSlot::Feet
+        } else {
+            EquipmentSlot::MainHand
+        }
+    }
+}
+
+/// The items a player carries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inventory {
+    /// Contents of every window slot
+    slots: [Option<ItemStack>; SLOT_COUNT],
+    /// Selected hotbar slot
+    selected: u8,
+    /// Version of the contents last sent to the client
+    state_id: i32,
+}
+
+impl Inventory {
+    /// Create an empty inventory with the first hotbar slot selected
+    pub fn new() -> Self {
+        Self {
+            slots: [None; SLOT_COUNT],
+            selected: 0,
+            state_id: 0,
+        }
+    }
+
+    /// Restore a saved inventory, skipping invalid items
+    pub fn from_saved(items: &[SavedItem], selected: u8) -> Self {
+        let mut inventory = Self::new();
+        for saved in items.iter().filter(|saved| saved.item.is_valid()) {
+            inventory.set(usize::from(saved.slot), Some(saved.item));
+        }
+        inventory.select(selected);
+        inventory
+    }
+
+    /// Items to save, with the slots they are in
+    pub fn saved(&self) -> Vec<SavedItem> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, item)| {
+                Some(SavedItem {
+                    slot: slot as u8,
+                    item: (*item)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Version of the contents last sent to the client
+    pub fn state_id(&self) -> i32 {
+        self.state_id
+    }
+
+    /// Advance the version of the contents, for an update sent to the client
+    pub fn next_state_id(&mut self) -> i32 {
+        self.state_id = self.state_id.wrapping_add(1);
+        self.state_id
+    }
+
+    /// Item in a window slot
+    pub fn get(&self, slot: usize) -> Option<ItemStack> {
+        self.slots.get(slot).copied().flatten()
+    }
+
+    /// Put an item into a window slot
+    ///
+    /// Returns the equipment slot other players see change, if any.
+    /// Slots outside the window are ignored.
+    pub fn set(&mut self, slot: usize, item: Option<ItemStack>) -> Option<EquipmentSlot> {
+        let current = self.slots.get_mut(slot)?;
+        if *current == item {
+            return None;
+        }
+        *current = item;
+        self.equipment_slot_at(slot)
+    }
+
+    /// Selected hotbar slot
+    pub fn selected(&self) -> u8 {
+        self.selected
+    }
+
+    /// Select a hotbar slot
+    ///
+    /// Returns whether the held item changed. Slots outside the hotbar are
+    /// ignored.
+    pub fn select(&mut self, hotbar_slot: u8) -> bool {
+        if hotbar_slot >= HOTBAR_SIZE {
+            return false;
+        }
+        let previous = self.held_item();
+        self.selected = hotbar_slot;
+        self.held_item() != previous
+    }
+
+    /// Item in the main hand
+    pub fn held_item(&self) -> Option<ItemStack> {
+        self.item_in(Hand::Main)
+    }
+
+    /// Window slot holding the item in a hand
+    pub fn hand_slot(&self, hand: Hand) -> usize {
+        match hand {
+            Hand::Main => HOTBAR_START + usize::from(self.selected),
+            Hand::Off => OFFHAND_SLOT,
+        }
+    }
+
+    /// Item in a hand
+    pub fn item_in(&self, hand: Hand) -> Option<ItemStack> {
+        self.get(self.hand_slot(hand))
+    }
+
+    /// Swap the items in both hands
+    ///
+    /// Returns whether anything changed.
+    pub fn swap_hands(&mut self) -> bool {
+        let main = self.hand_slot(Hand::Main);
+        if self.slots[main] == self.slots[OFFHAND_SLOT] {
+            return false;
+        }
+        self.slots.swap(main, OFFHAND_SLOT);
+        true
+    }
+
+    /// Remove one item from a hand
+    ///
+    /// Returns the item that was removed.
+    pub fn take_one(&mut self, hand: Hand) -> Option<ItemStack> {
+        let slot = self.hand_slot(hand);
+        let stack = self.slots[slot].as_mut()?;
+        let taken = ItemStack { count: 1, ..*stack };
+        stack.count -= 1;
+        if stack.count == 0 {
+            self.slots[slot] = None;
+        }
+        Some(taken)
+    }
+
+    /// Put items into the main inventory and hotbar
+    ///
+    /// Stacks of the same item are filled first, then empty slots, hotbar
+    /// first. Returns the items that did not fit.
+    pub fn add(&mut self, mut stack: ItemStack, max_stack_size: i32) -> Option<ItemStack> {
+        let order: Vec<usize> = (HOTBAR_START..STORAGE_SLOTS.end)
+            .chain(STORAGE_SLOTS.start..HOTBAR_START)
+            .collect();
+        for &slot in &order {
+            if let Some(existing) = self.slots[slot].as_mut().filter(|existing| {
+                existing.item == stack.item && existing.components == stack.components
+            }) {
+                let moved = (max_stack_size - existing.count).clamp(0, stack.count);
+                existing.count += moved;
+                stack.count -= moved;
+            }
+            if stack.count == 0 {
+                return None;
+            }
+        }
+        for &slot in &order {
+            if self.slots[slot].is_none() {
+                let moved = stack.count.min(max_stack_size);
+                self.slots[slot] = Some(ItemStack {
+                    count: moved,
+                    ..stack
+                });
+                stack.count -= moved;
+            }
+            if stack.count == 0 {
+                return None;
+            }
+        }
+        Some(stack)
+    }
+
+    /// Whether all of a stack fits into the main inventory and hotbar
+    pub fn can_add(&self, stack: ItemStack, max_stack_size: i32) -> bool {
+        let room: i32 = self.slots[STORAGE_SLOTS]
+            .iter()
+            .map(|slot| match slot {
+                None => max_stack_size,
+                Some(existing)
+                    if existing.item == stack.item && existing.components == stack.components =>
+                {
+                    (max_stack_size - existing.count).max(0)
+                }
+                Some(_) => 0,
+            })
+            .sum();
+        room >= stack.count
+    }
+
+    /// Take up to `max` items of an item out of the main inventory and
+    /// hotbar
+    ///
+    /// Returns the number of items taken.
+    pub fn remove(&mut self, item: i32, max: i32) -> i32 {
+        let mut taken = 0;
+        for slot in STORAGE_SLOTS {
+            let Some(stack) = self.slots[slot].as_mut().filter(|stack| stack.item == item) else {
+                continue;
+            };
+            let moved = stack.count.min(max - taken);
+            stack.count -= moved;
+            taken += moved;
+            if stack.count == 0 {
+                self.slots[slot] = None;
+            }
+            if taken == max {
+                break;
+            }
+        }
+        taken
+    }
+
+    /// Item in an equipment slot
+    pub fn equipment(&self, slot: EquipmentSlot) -> Option<ItemStack> {
+        match slot {
+            EquipmentSlot::MainHand => self.held_item(),
+            EquipmentSlot::OffHand => self.get(OFFHAND_SLOT),
+            EquipmentSlot::Feet => self.get(FEET_SLOT),
+            EquipmentSlot::Legs => self.get(LEGS_SLOT),
+            EquipmentSlot::Chest => self.get(CHEST_SLOT),
+            EquipmentSlot::Head => self.get(HEAD_SLOT),
+        }
+    }
+
+    /// Armor worn, from the helmet down
+    pub fn armor(&self) -> impl Iterator<Item = ItemStack> + '_ {
+        [HEAD_SLOT, CHEST_SLOT, LEGS_SLOT, FEET_SLOT]
+            .into_iter()
+            .filter_map(|slot| self.get(slot))
+    }
+
+    /// Visible equipment that is not empty
+    pub fn visible_equipment(&self) -> Vec<(EquipmentSlot, Option<ItemStack>)> {
+        EquipmentSlot::VISIBLE
+            .into_iter()
+            .map(|slot| (slot, self.equipment(slot)))
+            .filter(|(_, item)| item.is_some())
+            .collect()
+    }
+
+    /// Equipment slot a window slot is shown as
+    fn equipment_slot_at(&self, slot: usize) -> Option<EquipmentSlot> {
+        match slot {
+            HEAD_SLOT => Some(EquipmentSlot::Head),
+            CHEST_SLOT => Some(EquipmentSlot::Chest),
+            LEGS_SLOT => Some(EquipmentSlot::Legs),
+            FEET_SLOT => Some(EquipmentSlot::Feet),
+            OFFHAND_SLOT => Some(EquipmentSlot::OffHand),
+            _ if slot == HOTBAR_START + usize::from(self.selected) => Some(EquipmentSlot::MainHand),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_changes() {
+        let mut inventory = Inventory::new();
+        let sword = Some(ItemStack::new(276, 1));
+        assert_eq!(inventory.set(HEAD_SLOT, sword), Some(EquipmentSlot::Head));
+        assert_eq!(inventory.set(HEAD_SLOT, sword), None);
+        assert_eq!(inventory.set(HOTBAR_START + 1, sword), None);
+        assert_eq!(inventory.set(20, sword), None);
+
+        assert!(inventory.select(1));
+        assert_eq!(inventory.held_item(), sword);
+        assert!(!inventory.select(HOTBAR_SIZE));
+        assert_eq!(
+            inventory.set(HOTBAR_START + 1, None),
+            Some(EquipmentSlot::MainHand)
+        );
+        assert_eq!(inventory.set(SLOT_COUNT, sword), None);
+
+        let visible = inventory.visible_equipment();
+        assert_eq!(visible, vec![(EquipmentSlot::Head, sword)]);
+    }
+
+    #[test]
+    fn test_offhand() {
+        let mut inventory = Inventory::new();
+        let bread = Some(ItemStack::new(364, 2));
+        assert_eq!(
+            inventory.set(OFFHAND_SLOT, bread),
+            Some(EquipmentSlot::OffHand)
+        );
+        assert!(inventory.swap_hands());
+        assert_eq!(inventory.held_item(), bread);
+        assert_eq!(inventory.item_in(Hand::Off), None);
+        assert!(!Inventory::new().swap_hands());
+
+        assert_eq!(inventory.take_one(Hand::Main), Some(ItemStack::new(364, 1)));
+        assert_eq!(inventory.take_one(Hand::Main), Some(ItemStack::new(364, 1)));
+        assert_eq!(inventory.take_one(Hand::Main), None);
+
+        inventory.set(OFFHAND_SLOT, bread);
+        inventory.select(4);
+        let restored = Inventory::from_saved(&inventory.saved(), inventory.selected());
+        assert_eq!(restored.item_in(Hand::Off), bread);
+        assert_eq!(restored.selected(), 4);
+    }
+}
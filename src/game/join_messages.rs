@@ -0,0 +1,102 @@
+//! Join and quit messages
+//!
+//! The messages broadcast when a player joins or leaves follow vanilla's
+//! unless obsidium.toml sets a template. Templates may use `{player}` for
+//! the player's name, `{online}` for the players online afterwards and
+//! `{max}` for the player limit; an empty template turns the message off.
+
+use crate::protocol::types::JsonTextComponent;
+
+/// Color of join and quit messages
+const COLOR: &str = "yellow";
+
+/// Templates of the join and quit messages, `None` for vanilla's
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JoinMessages {
+    /// Message when a player joins
+    pub join: Option<String>,
+    /// Message when a player joins for the first time, the join message if
+    /// not set
+    pub first_join: Option<String>,
+    /// Message when a player leaves
+    pub quit: Option<String>,
+}
+
+impl JoinMessages {
+    /// Message broadcast when a player joins, if any
+    pub fn join_message(
+        &self,
+        player: &str,
+        online: usize,
+        max: u32,
+        first_join: bool,
+    ) -> Option<JsonTextComponent> {
+        let template = match self.first_join {
+            Some(ref template) if first_join => Some(template),
+            _ => self.join.as_ref(),
+        };
+        render(template, "multiplayer.player.joined", player, online, max)
+    }
+
+    /// Message broadcast when a player leaves, if any
+    pub fn quit_message(&self, player: &str, online: usize, max: u32) -> Option<JsonTextComponent> {
+        render(
+            self.quit.as_ref(),
+            "multiplayer.player.left",
+            player,
+            online,
+            max,
+        )
+    }
+}
+
+/// Fill in a template, or translate the vanilla message without one
+fn render(
+    template: Option<&String>,
+    vanilla: &str,
+    player: &str,
+    online: usize,
+    max: u32,
+) -> Option<JsonTextComponent> {
+    let message = match template {
+        None => crate::lang::translate(vanilla, &[player]),
+        Some(template) if template.is_empty() => return None,
+        Some(template) => JsonTextComponent::text(
+            &template
+                .replace("{player}", player)
+                .replace("{online}", &online.to_string())
+                .replace("{max}", &max.to_string()),
+        ),
+    };
+    Some(message.with_color(COLOR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_messages() {
+        let vanilla = JoinMessages::default();
+        let joined = vanilla.join_message("Steve", 1, 20, true).unwrap();
+        assert_eq!(
+            joined,
+            crate::lang::translate("multiplayer.player.joined", &["Steve"]).with_color(COLOR)
+        );
+
+        let custom = JoinMessages {
+            join: Some("{player} is back ({online}/{max})".to_string()),
+            first_join: Some("Welcome {player}!".to_string()),
+            quit: Some(String::new()),
+        };
+        assert_eq!(
+            custom.join_message("Alex", 2, 20, false),
+            Some(JsonTextComponent::text("Alex is back (2/20)").with_color(COLOR))
+        );
+        assert_eq!(
+            custom.join_message("Alex", 2, 20, true),
+            Some(JsonTextComponent::text("Welcome Alex!").with_color(COLOR))
+        );
+        assert_eq!(custom.quit_message("Alex", 1, 20), None);
+    }
+}
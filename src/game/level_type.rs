@@ -0,0 +1,109 @@
+//! World presets
+//!
+//! This module defines the world presets selected by the `level-type`
+//! server property.
+
+use crate::error::ServerError;
+use std::fmt;
+use std::str::FromStr;
+
+/// World preset used to generate a world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LevelType {
+    /// Default terrain
+    #[default]
+    Normal,
+    /// Superflat
+    Flat,
+    /// Biomes 16 times larger
+    LargeBiomes,
+    /// Amplified terrain
+    Amplified,
+    /// Default terrain with a single biome
+    SingleBiomeSurface,
+    /// Every block state laid out in a grid
+    Debug,
+}
+
+impl LevelType {
+    /// Parse a preset from its identifier, with or without the `minecraft:`
+    /// namespace
+    ///
+    /// The names used before 1.19 (`default`, `largeBiomes`, ...) are
+    /// accepted too, case-insensitively, as in vanilla.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        match name.strip_prefix("minecraft:").unwrap_or(&name) {
+            "normal" | "default" => Some(LevelType::Normal),
+            "flat" => Some(LevelType::Flat),
+            "large_biomes" | "largebiomes" => Some(LevelType::LargeBiomes),
+            "amplified" => Some(LevelType::Amplified),
+            "single_biome_surface" => Some(LevelType::SingleBiomeSurface),
+            "debug_all_block_states" | "debug_all_block_states_legacy" => Some(LevelType::Debug),
+            _ => None,
+        }
+    }
+
+    /// Get the namespaced identifier of this preset
+    pub fn name(&self) -> &'static str {
+        match self {
+            LevelType::Normal => "minecraft:normal",
+            LevelType::Flat => "minecraft:flat",
+            LevelType::LargeBiomes => "minecraft:large_biomes",
+            LevelType::Amplified => "minecraft:amplified",
+            LevelType::SingleBiomeSurface => "minecraft:single_biome_surface",
+            LevelType::Debug => "minecraft:debug_all_block_states",
+        }
+    }
+
+    /// Check whether clients should render the world as superflat
+    pub fn is_flat(&self) -> bool {
+        *self == LevelType::Flat
+    }
+
+    /// Check whether this is the debug world
+    pub fn is_debug(&self) -> bool {
+        *self == LevelType::Debug
+    }
+}
+
+impl FromStr for LevelType {
+    type Err = ServerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s)
+            .ok_or_else(|| ServerError::Configuration(format!("Unknown level type '{}'", s)))
+    }
+}
+
+impl fmt::Display for LevelType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "minecraft:flat".parse::<LevelType>().unwrap(),
+            LevelType::Flat
+        );
+        assert_eq!("DEFAULT".parse::<LevelType>().unwrap(), LevelType::Normal);
+        assert_eq!(
+            "largeBiomes".parse::<LevelType>().unwrap(),
+            LevelType::LargeBiomes
+        );
+        assert!("minecraft:caves".parse::<LevelType>().is_err());
+
+        for level_type in [LevelType::Amplified, LevelType::Debug] {
+            assert_eq!(
+                level_type.to_string().parse::<LevelType>().unwrap(),
+                level_type
+            );
+        }
+    }
+}
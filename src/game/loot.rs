@@ -0,0 +1,165 @@
+//! Item drops
+//!
+//! Broken blocks and killed mobs drop items from built-in drop lists. Loot
+//! tables from data packs are loaded but not evaluated yet, so they do not
+//! change drops.
+
+use crate::game::entity::MobType;
+use crate::game::world::simulation::TickRandom;
+
+/// Chance of a zombie killed by a player dropping a rare item, before
+/// looting
+const RARE_DROP_CHANCE: f32 = 0.025;
+
+/// Extra chance of a rare drop per level of looting
+const RARE_DROP_LOOTING_BONUS: f32 = 0.01;
+
+/// Chance of a spider killed by a player dropping a spider eye
+const SPIDER_EYE_CHANCE: f32 = 1.0 / 3.0;
+
+/// A stack of items dropped into the world
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemDrop {
+    /// Item name, such as `minecraft:wheat`
+    pub item: &'static str,
+    /// Number of items
+    pub count: u32,
+}
+
+impl ItemDrop {
+    /// Create a drop of `count` items
+    pub fn new(item: &'static str, count: u32) -> Self {
+        Self { item, count }
+    }
+}
+
+/// How a mob died, as far as its drops are concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KillContext {
+    /// Whether a player killed the mob
+    pub by_player: bool,
+    /// Looting level of the weapon the mob was killed with
+    pub looting: u32,
+}
+
+/// Roll the items a mob drops when it dies
+///
+/// Like vanilla, each level of looting may add one more item to every
+/// stack, and some drops need a player kill.
+pub(crate) fn mob_drops(mob: MobType, kill: KillContext, random: &mut TickRandom) -> Vec<ItemDrop> {
+    let mut roll = |item, min: u32, max: u32| {
+        let count = min
+            + random.below((max - min + 1) as usize) as u32
+            + random.below(kill.looting as usize + 1) as u32;
+        (count > 0).then(|| ItemDrop::new(item, count))
+    };
+    let mut drops: Vec<ItemDrop> = match mob {
+        MobType::Zombie => vec![roll("minecraft:rotten_flesh", 0, 2)],
+        MobType::Skeleton => vec![roll("minecraft:arrow", 0, 2), roll("minecraft:bone", 0, 2)],
+        MobType::Creeper => vec![roll("minecraft:gunpowder", 0, 2)],
+        MobType::Spider => vec![roll("minecraft:string", 0, 2)],
+        MobType::Cow => vec![
+            roll("minecraft:leather", 0, 2),
+            roll("minecraft:beef", 1, 3),
+        ],
+        MobType::Pig => vec![roll("minecraft:porkchop", 1, 3)],
+        MobType::Sheep => vec![
+            roll("minecraft:white_wool", 1, 1),
+            roll("minecraft:mutton", 1, 2),
+        ],
+        MobType::Chicken => vec![
+            roll("minecraft:feather", 0, 2),
+            roll("minecraft:chicken", 1, 1),
+        ],
+        MobType::Villager => vec![],
+    }
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if kill.by_player {
+        let rare_chance = RARE_DROP_CHANCE + RARE_DROP_LOOTING_BONUS * kill.looting as f32;
+        match mob {
+            MobType::Zombie if random.fraction() < rare_chance => {
+                let rare = [
+                    "minecraft:iron_ingot",
+                    "minecraft:carrot",
+                    "minecraft:potato",
+                ];
+                drops.push(ItemDrop::new(rare[random.below(rare.len())], 1));
+            }
+            MobType::Spider if random.fraction() < SPIDER_EYE_CHANCE => {
+                drops.push(ItemDrop::new("minecraft:spider_eye", 1));
+            }
+            _ => {}
+        }
+    }
+    drops
+}
+
+/// Roll the experience a mob leaves behind when it dies
+///
+/// Only mobs killed by a player leave experience: 5 points for hostile
+/// mobs and 1 to 3 for animals. Villagers leave none.
+pub(crate) fn mob_experience(mob: MobType, kill: KillContext, random: &mut TickRandom) -> u32 {
+    match (kill.by_player, mob.is_hostile()) {
+        (false, _) => 0,
+        _ if mob == MobType::Villager => 0,
+        (true, true) => 5,
+        (true, false) => 1 + random.below(3) as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::registry::ItemRegistry;
+
+    const MOBS: [MobType; 8] = [
+        MobType::Zombie,
+        MobType::Skeleton,
+        MobType::Creeper,
+        MobType::Spider,
+        MobType::Cow,
+        MobType::Pig,
+        MobType::Sheep,
+        MobType::Chicken,
+    ];
+
+    #[test]
+    fn test_mob_drops_are_registered_items() {
+        let items = ItemRegistry::new();
+        let mut random = TickRandom::new();
+        let kill = KillContext {
+            by_player: true,
+            looting: 3,
+        };
+        for mob in MOBS {
+            for _ in 0..200 {
+                for drop in mob_drops(mob, kill, &mut random) {
+                    assert!(items.get_item_id(drop.item).is_some(), "{}", drop.item);
+                    assert!((1..=6).contains(&drop.count));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_only_player_kills_leave_experience() {
+        let mut random = TickRandom::new();
+        let natural = KillContext {
+            by_player: false,
+            looting: 0,
+        };
+        let by_player = KillContext {
+            by_player: true,
+            ..natural
+        };
+        assert_eq!(mob_experience(MobType::Zombie, natural, &mut random), 0);
+        assert_eq!(mob_experience(MobType::Zombie, by_player, &mut random), 5);
+        let cow = mob_experience(MobType::Cow, by_player, &mut random);
+        assert!((1..=3).contains(&cow));
+        let chicken = mob_drops(MobType::Chicken, natural, &mut random);
+        assert!(chicken.contains(&ItemDrop::new("minecraft:chicken", 1)));
+    }
+}
@@ -0,0 +1,128 @@
+//! Mining speed
+//!
+//! How long a block takes to break depends on its hardness and on whether
+//! the held tool suits it, as in vanilla. A suitable tool mines at the
+//! speed of its tier, and efficiency adds to that speed. Blocks needing a
+//! tool to drop anything are not modelled, so blocks always break as fast
+//! as if the tool could harvest them.
+
+use crate::game::enchantment;
+use crate::game::world::registry::BlockInfo;
+
+/// Share of the break time a player must have spent digging before the
+/// block breaks, leaving room for latency
+pub const BREAK_TOLERANCE: f32 = 0.7;
+
+/// Kinds of mining tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    /// Pickaxe, for stone
+    Pickaxe,
+    /// Shovel, for dirt and sand
+    Shovel,
+    /// Axe, for wood
+    Axe,
+    /// Hoe, for leaves and plants
+    Hoe,
+}
+
+/// Tool kind and mining speed of an item, by item name
+pub fn tool(item: &str) -> Option<(Tool, f32)> {
+    let (tier, kind) = item.strip_prefix("minecraft:")?.rsplit_once('_')?;
+    let kind = match kind {
+        "pickaxe" => Tool::Pickaxe,
+        "shovel" => Tool::Shovel,
+        "axe" => Tool::Axe,
+        "hoe" => Tool::Hoe,
+        _ => return None,
+    };
+    let speed = match tier {
+        "wooden" => 2.0,
+        "stone" => 4.0,
+        "iron" => 6.0,
+        "diamond" => 8.0,
+        "netherite" => 9.0,
+        "golden" => 12.0,
+        _ => return None,
+    };
+    Some((kind, speed))
+}
+
+/// Tool that mines a block faster, by block name
+pub fn preferred_tool(block: &str) -> Option<Tool> {
+    let name = block.split('[').next().unwrap_or(block);
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    match name {
+        "stone" | "cobblestone" | "stone_button" | "obsidian" | "netherrack" => Some(Tool::Pickaxe),
+        "grass_block" | "dirt" | "farmland" | "sand" | "gravel" => Some(Tool::Shovel),
+        _ if name.starts_with("oak_") && !name.ends_with("_leaves") && name != "oak_sapling" => {
+            Some(Tool::Axe)
+        }
+        _ if name.ends_with("_leaves") => Some(Tool::Hoe),
+        _ => None,
+    }
+}
+
+/// Ticks it takes to break a block holding an item
+///
+/// Zero means the block breaks as soon as digging starts. Returns `None`
+/// for unbreakable blocks.
+pub fn break_ticks(block: &BlockInfo, held: Option<&str>, efficiency: u8) -> Option<u32> {
+    if block.hardness < 0.0 {
+        return None;
+    }
+    if block.hardness == 0.0 {
+        return Some(0);
+    }
+    let suitable = held
+        .and_then(tool)
+        .filter(|&(kind, _)| preferred_tool(&block.name) == Some(kind));
+    let speed = match suitable {
+        Some((_, speed)) => speed + enchantment::efficiency_bonus(efficiency),
+        None => 1.0,
+    };
+    let progress_per_tick = speed / block.hardness / 30.0;
+    if progress_per_tick >= 1.0 {
+        return Some(0);
+    }
+    Some((1.0 / progress_per_tick).ceil() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::registry::BlockRegistry;
+
+    #[test]
+    fn test_break_ticks() {
+        let blocks = BlockRegistry::new();
+        let stone = blocks.get_block_by_name("minecraft:stone").unwrap();
+        assert_eq!(break_ticks(stone, None, 0), Some(45));
+        assert_eq!(
+            break_ticks(stone, Some("minecraft:iron_shovel"), 5),
+            Some(45)
+        );
+        assert_eq!(
+            break_ticks(stone, Some("minecraft:diamond_pickaxe"), 0),
+            Some(6)
+        );
+        assert_eq!(
+            break_ticks(stone, Some("minecraft:diamond_pickaxe"), 5),
+            Some(2)
+        );
+        let dirt = blocks.get_block_by_name("minecraft:dirt").unwrap();
+        assert_eq!(
+            break_ticks(dirt, Some("minecraft:diamond_shovel"), 5),
+            Some(0)
+        );
+
+        let bedrock = blocks.get_block_by_name("minecraft:bedrock").unwrap();
+        assert_eq!(break_ticks(bedrock, None, 0), None);
+        let sapling = blocks.get_block_by_name("minecraft:oak_sapling").unwrap();
+        assert_eq!(break_ticks(sapling, None, 0), Some(0));
+        assert_eq!(
+            preferred_tool("minecraft:oak_door[facing=north]"),
+            Some(Tool::Axe)
+        );
+    }
+}
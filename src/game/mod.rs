@@ -3,9 +3,24 @@
 //! This module contains all the game-related logic including players,
 //! worlds, entities, and game mechanics.
 
+pub mod chat;
+pub mod datapack;
+pub mod difficulty;
+pub mod enchantment;
 pub mod entity;
+pub mod food;
+pub mod inventory;
+pub mod join_messages;
+pub mod level_type;
+pub mod loot;
+pub mod mining;
+pub mod movement;
 pub mod player;
+pub mod playerdata;
+pub mod settings;
 pub mod world;
 
+pub use difficulty::Difficulty;
+pub use level_type::LevelType;
 pub use player::Player;
 pub use world::World;
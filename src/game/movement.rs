@@ -0,0 +1,352 @@
+//! Player movement
+//!
+//! Clients move their players themselves and report the result. The server
+//! follows along: it tracks sneaking and sprinting from the player's input,
+//! refuses to let starving players sprint, ignores moves too long to be
+//! real and makes sprinting and jumping cost food.
+
+use crate::game::player::{GameMode, Player, PlayerRotation};
+use crate::protocol::packets::play::{
+    PlayerInputPacket, PlayerOnGroundPacket, PlayerPositionAndRotationPacket, PlayerPositionPacket,
+    PlayerRotationPacket,
+};
+use thiserror::Error;
+
+/// Food level a player needs to sprint
+pub const MIN_SPRINT_FOOD: i32 = 7;
+
+/// Furthest a player may move with a single movement packet
+pub const MAX_MOVE_DISTANCE: f64 = 10.0;
+
+/// Exhaustion per block sprinted
+pub const SPRINT_EXHAUSTION: f32 = 0.1;
+
+/// Exhaustion per jump
+pub const JUMP_EXHAUSTION: f32 = 0.05;
+
+/// Exhaustion per jump while sprinting
+pub const SPRINT_JUMP_EXHAUSTION: f32 = 0.2;
+
+/// Reasons a move is ignored
+#[derive(Error, Debug, Clone, Copy, PartialEq)]
+pub enum MoveError {
+    /// The player moved further than any client could in one packet
+    #[error("Moved too quickly ({0:.1} blocks)")]
+    TooFast(f64),
+    /// A coordinate is not a finite number
+    #[error("Invalid coordinates")]
+    InvalidCoordinates,
+    /// An angle is not a finite number
+    #[error("Invalid rotation")]
+    InvalidRotation,
+}
+
+/// What a movement packet reports
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Movement {
+    /// Position moved to, if the packet has one
+    pub position: Option<[f64; 3]>,
+    /// Yaw and pitch turned to, if the packet has them
+    pub rotation: Option<(f32, f32)>,
+    /// Whether the player is on the ground
+    pub on_ground: bool,
+}
+
+impl From<&PlayerPositionPacket> for Movement {
+    fn from(packet: &PlayerPositionPacket) -> Self {
+        Self {
+            position: Some([packet.x, packet.y, packet.z]),
+            rotation: None,
+            on_ground: packet.on_ground,
+        }
+    }
+}
+
+impl From<&PlayerPositionAndRotationPacket> for Movement {
+    fn from(packet: &PlayerPositionAndRotationPacket) -> Self {
+        Self {
+            position: Some([packet.x, packet.y, packet.z]),
+            rotation: Some((packet.yaw, packet.pitch)),
+            on_ground: packet.on_ground,
+        }
+    }
+}
+
+impl From<&PlayerRotationPacket> for Movement {
+    fn from(packet: &PlayerRotationPacket) -> Self {
+        Self {
+            position: None,
+            rotation: Some((packet.yaw, packet.pitch)),
+            on_ground: packet.on_ground,
+        }
+    }
+}
+
+impl From<&PlayerOnGroundPacket> for Movement {
+    fn from(packet: &PlayerOnGroundPacket) -> Self {
+        Self {
+            position: None,
+            rotation: None,
+            on_ground: packet.on_ground,
+        }
+    }
+}
+
+/// Effects of an accepted move
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MoveOutcome {
+    /// The player jumped
+    pub jumped: bool,
+    /// The player landed after falling this many blocks
+    pub landed: Option<f32>,
+    /// The player's saturation or food level changed
+    pub food_changed: bool,
+    /// The player ran out of food and stopped sprinting
+    pub stopped_sprinting: bool,
+}
+
+/// Whether a player may sprint right now
+///
+/// Players who can fly sprint freely; others need [`MIN_SPRINT_FOOD`].
+pub fn can_sprint(player: &Player) -> bool {
+    let can_fly = matches!(player.game_mode, GameMode::Creative | GameMode::Spectator);
+    player.sleeping_at.is_none() && (can_fly || player.food >= MIN_SPRINT_FOOD)
+}
+
+/// Apply the keys a player holds, returning whether they started or
+/// stopped sneaking
+pub fn apply_input(player: &mut Player, input: &PlayerInputPacket) -> bool {
+    let sneaking = input.holds(PlayerInputPacket::SNEAK);
+    let changed = player.sneaking != sneaking;
+    player.sneaking = sneaking;
+    changed
+}
+
+/// Start or stop sprinting, returning whether the player is sprinting
+/// as requested
+///
+/// Starting is refused if the player [cannot sprint](can_sprint).
+pub fn set_sprinting(player: &mut Player, sprinting: bool) -> bool {
+    if sprinting && !can_sprint(player) {
+        player.sprinting = false;
+        return false;
+    }
+    player.sprinting = sprinting;
+    true
+}
+
+/// Apply what a movement packet reports to a player
+///
+/// Positions go through [`apply_move`]. A packet without one only tells
+/// whether the player is on the ground, which can still land them. Nothing
+/// is applied from a rejected packet.
+pub fn apply_movement(player: &mut Player, movement: &Movement) -> Result<MoveOutcome, MoveError> {
+    if let Some((yaw, pitch)) = movement.rotation {
+        if !(yaw.is_finite() && pitch.is_finite()) {
+            return Err(MoveError::InvalidRotation);
+        }
+    }
+    let outcome = match movement.position {
+        Some([x, y, z]) => apply_move(player, x, y, z, movement.on_ground)?,
+        None if player.moved => {
+            let position = player.position;
+            apply_move(
+                player,
+                position.x,
+                position.y,
+                position.z,
+                movement.on_ground,
+            )?
+        }
+        // Until the first position arrives the player is where the server
+        // put them
+        None => MoveOutcome::default(),
+    };
+    if let Some((yaw, pitch)) = movement.rotation {
+        player.rotation = PlayerRotation { yaw, pitch };
+    }
+    Ok(outcome)
+}
+
+/// Move a player to a reported position
+///
+/// The first position a client reports after joining is taken as is; later
+/// ones must be within [`MAX_MOVE_DISTANCE`] of the last. Sprinting and
+/// jumping add exhaustion, except for players who can fly. Spectators pass
+/// through blocks, so they neither fall nor land.
+pub fn apply_move(
+    player: &mut Player,
+    x: f64,
+    y: f64,
+    z: f64,
+    on_ground: bool,
+) -> Result<MoveOutcome, MoveError> {
+    if !(x.is_finite() && y.is_finite() && z.is_finite()) {
+        return Err(MoveError::InvalidCoordinates);
+    }
+    let (dx, dy, dz) = (
+        x - player.position.x,
+        y - player.position.y,
+        z - player.position.z,
+    );
+    let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+    let first_move = !player.moved;
+    if !first_move && distance > MAX_MOVE_DISTANCE {
+        return Err(MoveError::TooFast(distance));
+    }
+
+    let mut outcome = MoveOutcome {
+        jumped: !first_move && player.on_ground && !on_ground && dy > 0.0,
+        ..MoveOutcome::default()
+    };
+    if player.game_mode == GameMode::Spectator {
+        // Spectators fly through blocks and never land
+        player.fall_distance = 0.0;
+    } else if !first_move {
+        if dy < 0.0 {
+            player.fall_distance -= dy as f32;
+        }
+        if on_ground {
+            if !player.on_ground && player.fall_distance > 0.0 {
+                outcome.landed = Some(player.fall_distance);
+            }
+            player.fall_distance = 0.0;
+        }
+    }
+    player.set_position(x, y, z);
+    player.on_ground = on_ground;
+    player.moved = true;
+
+    let exhausted = matches!(player.game_mode, GameMode::Survival | GameMode::Adventure);
+    if exhausted && !first_move {
+        let mut exhaustion = 0.0;
+        if player.sprinting {
+            exhaustion += (dx * dx + dz * dz).sqrt() as f32 * SPRINT_EXHAUSTION;
+        }
+        if outcome.jumped {
+            exhaustion += if player.sprinting {
+                SPRINT_JUMP_EXHAUSTION
+            } else {
+                JUMP_EXHAUSTION
+            };
+        }
+        outcome.food_changed = exhaustion > 0.0 && player.add_exhaustion(exhaustion);
+    }
+
+    if player.sprinting && !can_sprint(player) {
+        player.sprinting = false;
+        outcome.stopped_sprinting = true;
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::McUuid;
+
+    #[test]
+    fn test_sneak_and_sprint() {
+        let mut player = Player::new(McUuid::new_v4(), "Steve".to_string());
+        let input = PlayerInputPacket {
+            flags: PlayerInputPacket::FORWARD | PlayerInputPacket::SNEAK,
+        };
+        assert!(apply_input(&mut player, &input));
+        assert!(player.sneaking);
+        assert!(!apply_input(&mut player, &input));
+
+        assert!(set_sprinting(&mut player, true));
+        assert!(player.sprinting);
+        player.set_food(MIN_SPRINT_FOOD - 1);
+        assert!(!set_sprinting(&mut player, true));
+        assert!(!player.sprinting);
+        player.set_game_mode(GameMode::Creative);
+        assert!(set_sprinting(&mut player, true));
+    }
+
+    #[test]
+    fn test_sprinting_costs_food() {
+        let mut player = Player::new(McUuid::new_v4(), "Steve".to_string());
+        let outcome = apply_move(&mut player, 0.0, 100.0, 0.0, true).unwrap();
+        assert_eq!(outcome, MoveOutcome::default());
+        player.saturation = 0.0;
+        player.set_food(MIN_SPRINT_FOOD);
+        set_sprinting(&mut player, true);
+
+        // 40 blocks of sprinting use up one food point
+        for step in 1..=39 {
+            let outcome = apply_move(&mut player, f64::from(step), 100.0, 0.0, true).unwrap();
+            assert_eq!(outcome, MoveOutcome::default());
+        }
+        let outcome = apply_move(&mut player, 40.5, 100.5, 0.0, false).unwrap();
+        assert!(outcome.jumped);
+        assert!(outcome.food_changed);
+        assert!(outcome.stopped_sprinting);
+        assert_eq!(player.food, MIN_SPRINT_FOOD - 1);
+
+        assert!(matches!(
+            apply_move(&mut player, 60.0, 100.0, 0.0, true),
+            Err(MoveError::TooFast(_))
+        ));
+        assert_eq!(player.position.x, 40.5);
+    }
+
+    #[test]
+    fn test_fall_distance() {
+        let mut player = Player::new(McUuid::new_v4(), "Steve".to_string());
+        apply_move(&mut player, 0.0, 100.0, 0.0, true).unwrap();
+        apply_move(&mut player, 0.0, 101.0, 0.0, false).unwrap();
+        for y in [100.0, 98.5, 97.0] {
+            let outcome = apply_move(&mut player, 0.0, y, 0.0, false).unwrap();
+            assert_eq!(outcome.landed, None);
+        }
+        let outcome = apply_move(&mut player, 0.0, 96.0, 0.0, true).unwrap();
+        assert_eq!(outcome.landed, Some(5.0));
+        assert_eq!(player.fall_distance, 0.0);
+    }
+
+    #[test]
+    fn test_rotation_and_on_ground() {
+        let mut player = Player::new(McUuid::new_v4(), "Steve".to_string());
+        let look = PlayerRotationPacket {
+            yaw: 90.0,
+            pitch: -30.0,
+            on_ground: false,
+        };
+        apply_movement(&mut player, &Movement::from(&look)).unwrap();
+        assert_eq!((player.rotation.yaw, player.rotation.pitch), (90.0, -30.0));
+        assert!(!player.moved);
+
+        apply_move(&mut player, 0.0, 100.0, 0.0, false).unwrap();
+        apply_move(&mut player, 0.0, 97.0, 0.0, false).unwrap();
+        let landing = PlayerOnGroundPacket { on_ground: true };
+        let outcome = apply_movement(&mut player, &Movement::from(&landing)).unwrap();
+        assert_eq!(outcome.landed, Some(3.0));
+        assert!(player.on_ground);
+
+        let invalid = PlayerPositionAndRotationPacket {
+            x: 1.0,
+            y: 97.0,
+            z: 0.0,
+            yaw: f32::NAN,
+            pitch: 0.0,
+            on_ground: true,
+        };
+        assert_eq!(
+            apply_movement(&mut player, &Movement::from(&invalid)),
+            Err(MoveError::InvalidRotation)
+        );
+        assert_eq!(player.position.x, 0.0);
+    }
+
+    #[test]
+    fn test_spectators_never_land() {
+        let mut player = Player::new(McUuid::new_v4(), "Steve".to_string());
+        player.set_game_mode(GameMode::Spectator);
+        apply_move(&mut player, 0.0, 100.0, 0.0, false).unwrap();
+        apply_move(&mut player, 0.0, 92.0, 0.0, false).unwrap();
+        let outcome = apply_move(&mut player, 0.0, 90.0, 0.0, true).unwrap();
+        assert_eq!(outcome.landed, None);
+        assert_eq!(player.fall_distance, 0.0);
+    }
+}
@@ -2,11 +2,36 @@
 //!
 //! This module handles player state, authentication, and player-specific logic.
 
-use crate::protocol::types::McUuid;
+use crate::error::ServerError;
+use crate::game::Difficulty;
+use crate::game::chat::ChatGuard;
+use crate::game::entity::EntityId;
+use crate::game::entity::villager::Trading;
+use crate::game::food::Food;
+use crate::game::inventory::{Hand, Inventory};
+use crate::game::settings::ClientSettings;
+use crate::game::world::dimension::Dimension;
+use crate::game::world::portal::PortalTimer;
+use crate::protocol::packets::DynPacket;
+use crate::protocol::packets::configuration::ResourcePackStatus;
+use crate::protocol::types::{McUuid, Position};
 use std::collections::HashMap;
-use std::net::SocketAddr;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+use tokio::sync::{Notify, mpsc};
+
+/// Packets that can be queued for a player before their queue overflows
+/// and they are disconnected
+pub const OUTBOUND_CHANNEL_CAPACITY: usize = 1024;
+
+/// Receiving end of a player's outbound packet queue, drained by their connection
+pub type OutboundReceiver = mpsc::Receiver<Arc<dyn DynPacket>>;
 
 /// Represents a connected player
 #[derive(Debug, Clone)]
@@ -25,12 +50,126 @@ pub struct Player {
     pub health: f32,
     /// Player food level
     pub food: i32,
+    /// Food saturation, used up before the food level
+    pub saturation: f32,
+    /// Exhaustion built up by moving, used up in steps of
+    /// [`EXHAUSTION_PER_FOOD`]
+    pub exhaustion: f32,
+    /// Ticks towards the next time hunger heals or starves the player
+    food_timer: u32,
     /// Player experience
     pub experience: PlayerExperience,
+    /// What the player may do and how fast they move
+    pub abilities: PlayerAbilities,
     /// Whether the player is on ground
     pub on_ground: bool,
+    /// Blocks fallen since last on the ground
+    pub fall_distance: f32,
+    /// Whether the client reported a position since joining
+    pub moved: bool,
+    /// Whether the player is sneaking
+    pub sneaking: bool,
+    /// Whether the player is sprinting
+    pub sprinting: bool,
+    /// Vehicle the player rides
+    pub vehicle: Option<EntityId>,
+    /// Entity a spectator views the world through
+    pub spectating: Option<EntityId>,
+    /// Settings the client reported, once it has sent them
+    settings: Option<ClientSettings>,
+    /// Smoothed keep-alive round trip time
+    latency: Duration,
+    /// Entity ID assigned when the player joined
+    pub entity_id: EntityId,
+    /// Head position of the bed the player is sleeping in
+    pub sleeping_at: Option<Position>,
+    /// Ticks the player has been asleep
+    pub sleep_ticks: u32,
+    /// Where the player respawns, if set by a bed or respawn anchor
+    pub respawn_point: Option<RespawnPoint>,
+    /// Latest progress reported for the server resource pack
+    pub resource_pack_status: Option<ResourcePackStatus>,
+    /// Whether the player was sent back to the configuration state and has
+    /// not returned to play yet
+    pub reconfiguring: bool,
+    /// Whether the player had no saved data when they joined
+    pub first_join: bool,
+    /// Items the player carries
+    pub inventory: Inventory,
+    /// Hand the player is eating from and the ticks left until done
+    pub eating: Option<(Hand, u32)>,
+    /// Block the player is digging and the world age they started at
+    pub digging: Option<(Position, i64)>,
+    /// Trading screen the player has open
+    pub trading: Option<Trading>,
+    /// Dimension the player is in
+    pub dimension: Dimension,
+    /// Time spent standing in a nether portal
+    pub portal: PortalTimer,
+    /// ID of the window opened last
+    window_id: i32,
+    /// Chat messages the player has sent
+    pub messages_sent: i32,
+    /// Recent chat messages, checked for spam
+    pub chat_guard: ChatGuard,
+    /// Chat messages the player has been sent
+    pub messages_received: i32,
+    /// Address the player connects from, as forwarded by a proxy if any
+    pub ip: Option<IpAddr>,
+}
+
+/// A player's personal respawn point
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RespawnPoint {
+    /// Name of the world the respawn point is in
+    pub world: String,
+    /// Block position of the bed or respawn anchor
+    pub position: Position,
+    /// Yaw the player faces after respawning
+    pub angle: f32,
+    /// Whether the point was set by a command and needs no bed or anchor
+    pub forced: bool,
+}
+
+impl RespawnPoint {
+    /// Create a respawn point at a bed or anchor
+    pub fn new(world: &str, position: Position) -> Self {
+        Self {
+            world: world.to_string(),
+            position,
+            angle: 0.0,
+            forced: false,
+        }
+    }
 }
 
+/// Exhaustion that uses up one point of saturation or food
+pub const EXHAUSTION_PER_FOOD: f32 = 4.0;
+
+/// Most exhaustion a player can build up
+const MAX_EXHAUSTION: f32 = 40.0;
+
+/// Ticks between healing or starving steps
+const FOOD_TICKS: u32 = 80;
+
+/// Ticks between healing and feeding steps in peaceful
+const PEACEFUL_FOOD_TICKS: u32 = 10;
+
+/// Food level from which a hurt player heals
+const HEALING_FOOD: i32 = 18;
+
+/// Exhaustion from healing a point of health
+const HEALING_EXHAUSTION: f32 = 6.0;
+
+/// Smallest view distance the server streams chunks for
+pub const MIN_VIEW_DISTANCE: u8 = 2;
+
+/// Flying speed players start with
+pub const DEFAULT_FLY_SPEED: f32 = 0.05;
+
+/// Walking speed players start with
+pub const DEFAULT_WALK_SPEED: f32 = 0.1;
+
 /// Player position in the world
 #[derive(Debug, Clone, Copy)]
 pub struct PlayerPosition {
@@ -42,6 +181,14 @@ pub struct PlayerPosition {
     pub z: f64,
 }
 
+impl PlayerPosition {
+    /// Squared distance to another position
+    pub fn distance_squared(&self, other: &PlayerPosition) -> f64 {
+        let (dx, dy, dz) = (self.x - other.x, self.y - other.y, self.z - other.z);
+        dx * dx + dy * dy + dz * dz
+    }
+}
+
 /// Player rotation (look direction)
 #[derive(Debug, Clone, Copy)]
 pub struct PlayerRotation {
@@ -52,9 +199,10 @@ pub struct PlayerRotation {
 }
 
 /// Player game mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum GameMode {
     /// Survival mode
+    #[default]
     Survival = 0,
     /// Creative mode
     Creative = 1,
@@ -64,6 +212,49 @@ pub enum GameMode {
     Spectator = 3,
 }
 
+impl GameMode {
+    /// Parse a game mode from its lowercase name (e.g. "creative")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "survival" => Some(GameMode::Survival),
+            "creative" => Some(GameMode::Creative),
+            "adventure" => Some(GameMode::Adventure),
+            "spectator" => Some(GameMode::Spectator),
+            _ => None,
+        }
+    }
+
+    /// Get the lowercase name of this game mode
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+            GameMode::Adventure => "adventure",
+            GameMode::Spectator => "spectator",
+        }
+    }
+
+    /// Get the protocol ID of this game mode
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = ServerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s)
+            .ok_or_else(|| ServerError::Configuration(format!("Unknown game mode '{}'", s)))
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// Player experience information
 #[derive(Debug, Clone, Copy)]
 pub struct PlayerExperience {
@@ -75,6 +266,50 @@ pub struct PlayerExperience {
     pub progress: f32,
 }
 
+/// What a player may do and how fast they move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerAbilities {
+    /// Whether the player takes no damage
+    pub invulnerable: bool,
+    /// Whether the player is flying
+    pub flying: bool,
+    /// Whether the player may start flying
+    pub allow_flying: bool,
+    /// Whether the player breaks blocks instantly
+    pub instant_break: bool,
+    /// Flying speed
+    pub fly_speed: f32,
+    /// Walking speed, which the client also scales its field of view by
+    pub walk_speed: f32,
+}
+
+impl PlayerAbilities {
+    /// Set the flags that follow from a game mode, keeping the speeds
+    ///
+    /// Spectators always fly, and creative players keep flying if they
+    /// already were.
+    pub fn apply_game_mode(&mut self, mode: GameMode) {
+        let can_fly = matches!(mode, GameMode::Creative | GameMode::Spectator);
+        self.invulnerable = can_fly;
+        self.allow_flying = can_fly;
+        self.instant_break = mode == GameMode::Creative;
+        self.flying = mode == GameMode::Spectator || (can_fly && self.flying);
+    }
+}
+
+impl Default for PlayerAbilities {
+    fn default() -> Self {
+        Self {
+            invulnerable: false,
+            flying: false,
+            allow_flying: false,
+            instant_break: false,
+            fly_speed: DEFAULT_FLY_SPEED,
+            walk_speed: DEFAULT_WALK_SPEED,
+        }
+    }
+}
+
 impl Player {
     /// Create a new player
     pub fn new(uuid: McUuid, username: String) -> Self {
@@ -90,18 +325,90 @@ impl Player {
                 yaw: 0.0,
                 pitch: 0.0,
             },
-            game_mode: GameMode::Survival,
+            game_mode: GameMode::default(),
             health: 20.0,
             food: 20,
+            saturation: 5.0,
+            exhaustion: 0.0,
+            food_timer: 0,
             experience: PlayerExperience {
                 points: 0,
                 level: 0,
                 progress: 0.0,
             },
+            abilities: PlayerAbilities::default(),
             on_ground: true,
+            fall_distance: 0.0,
+            moved: false,
+            sneaking: false,
+            sprinting: false,
+            vehicle: None,
+            spectating: None,
+            settings: None,
+            latency: Duration::ZERO,
+            entity_id: 0,
+            sleeping_at: None,
+            sleep_ticks: 0,
+            respawn_point: None,
+            resource_pack_status: None,
+            reconfiguring: false,
+            first_join: false,
+            inventory: Inventory::new(),
+            eating: None,
+            digging: None,
+            trading: None,
+            dimension: Dimension::Overworld,
+            portal: PortalTimer::default(),
+            window_id: 0,
+            messages_sent: 0,
+            chat_guard: ChatGuard::default(),
+            messages_received: 0,
+            ip: None,
         }
     }
 
+    /// Settings the client reported, or `None` until it has sent them
+    pub fn settings(&self) -> Option<&ClientSettings> {
+        self.settings.as_ref()
+    }
+
+    /// Store the settings the client reported
+    pub fn set_settings(&mut self, settings: ClientSettings) {
+        self.settings = Some(settings);
+    }
+
+    /// Whether the client allows its name to be listed in the server list
+    pub fn allows_listing(&self) -> bool {
+        self.settings
+            .as_ref()
+            .is_none_or(|settings| settings.allows_listing)
+    }
+
+    /// Round trip time to the client, smoothed over the last keep-alives
+    ///
+    /// Zero until the client has answered its first keep-alive.
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    /// Store the latency measured by the connection
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = latency;
+    }
+
+    /// Chunk streaming radius for this player
+    ///
+    /// This is the smaller of the server's view distance and the distance
+    /// requested by the client, but never less than [`MIN_VIEW_DISTANCE`].
+    pub fn effective_view_distance(&self, server_view_distance: u8) -> u8 {
+        self.settings
+            .as_ref()
+            .map_or(server_view_distance, |settings| {
+                settings.view_distance.min(server_view_distance)
+            })
+            .max(MIN_VIEW_DISTANCE)
+    }
+
     /// Update player position
     pub fn set_position(&mut self, x: f64, y: f64, z: f64) {
         self.position.x = x;
@@ -115,9 +422,10 @@ impl Player {
         self.rotation.pitch = pitch;
     }
 
-    /// Set game mode
+    /// Set game mode, updating the abilities that depend on it
     pub fn set_game_mode(&mut self, mode: GameMode) {
         self.game_mode = mode;
+        self.abilities.apply_game_mode(mode);
     }
 
     /// Set health
@@ -130,6 +438,83 @@ impl Player {
         self.food = food.clamp(0, 20);
     }
 
+    /// Pick the ID of a window to open, cycling from 1 to 100 as vanilla
+    /// does
+    pub fn next_window_id(&mut self) -> i32 {
+        self.window_id = self.window_id % 100 + 1;
+        self.window_id
+    }
+
+    /// Whether the player is hungry enough to eat
+    pub fn can_eat(&self) -> bool {
+        self.food < 20 || self.game_mode == GameMode::Creative
+    }
+
+    /// Restore food and saturation by eating, saturation never exceeding
+    /// the food level
+    pub fn eat(&mut self, food: Food) {
+        self.set_food(self.food + food.nutrition);
+        self.saturation = (self.saturation + food.saturation).min(self.food as f32);
+    }
+
+    /// Add exhaustion, using up saturation or food for every
+    /// [`EXHAUSTION_PER_FOOD`] built up
+    ///
+    /// Returns whether the saturation or food level changed.
+    pub fn add_exhaustion(&mut self, amount: f32) -> bool {
+        self.exhaustion = (self.exhaustion + amount).min(MAX_EXHAUSTION);
+        let mut changed = false;
+        while self.exhaustion >= EXHAUSTION_PER_FOOD {
+            self.exhaustion -= EXHAUSTION_PER_FOOD;
+            if self.saturation > 0.0 {
+                self.saturation = (self.saturation - 1.0).max(0.0);
+            } else {
+                self.set_food(self.food - 1);
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Heal or starve the player as their food level allows, once a tick
+    ///
+    /// Like vanilla, a well fed player heals a point every four seconds
+    /// and a starving one loses a point as often, down to what the
+    /// difficulty allows. In peaceful, health and food both come back by
+    /// themselves. Returns whether the health or food level changed.
+    pub fn tick_hunger(&mut self, difficulty: Difficulty) -> bool {
+        let (health, food) = (self.health, self.food);
+        let Some(floor) = difficulty.starvation_floor() else {
+            self.food_timer += 1;
+            if self.food_timer >= PEACEFUL_FOOD_TICKS {
+                self.food_timer = 0;
+                self.set_health(self.health + 1.0);
+                self.set_food(self.food + 1);
+            }
+            return (health, food) != (self.health, self.food);
+        };
+
+        if self.food >= HEALING_FOOD && self.health < 20.0 {
+            self.food_timer += 1;
+            if self.food_timer >= FOOD_TICKS {
+                self.food_timer = 0;
+                self.set_health(self.health + 1.0);
+                self.add_exhaustion(HEALING_EXHAUSTION);
+            }
+        } else if self.food <= 0 {
+            self.food_timer += 1;
+            if self.food_timer >= FOOD_TICKS {
+                self.food_timer = 0;
+                if self.health > floor {
+                    self.set_health(self.health - 1.0);
+                }
+            }
+        } else {
+            self.food_timer = 0;
+        }
+        (health, food) != (self.health, self.food)
+    }
+
     /// Check if player is alive
     pub fn is_alive(&self) -> bool {
         self.health > 0.0
@@ -142,102 +527,484 @@ impl Default for Player {
     }
 }
 
+/// Number of shards the player and connection maps are split into
+const SHARD_COUNT: usize = 16;
+
+/// A connected player and their outbound packet queue
+struct PlayerSlot {
+    /// Player data, locked on its own so players never contend with each other
+    player: Mutex<Player>,
+    /// Queue of packets for the player's connection, once in the play state
+    outbound: Mutex<Option<mpsc::Sender<Arc<dyn DynPacket>>>>,
+    /// Address of the player's connection, if they have one
+    addr: Option<SocketAddr>,
+}
+
+impl PlayerSlot {
+    /// Queue a packet for the player
+    ///
+    /// Returns `false` if the player has no open queue. A packet that does
+    /// not fit closes the queue instead of being dropped: the client would
+    /// fall out of sync with the world, so its connection is closed once it
+    /// has sent what was queued.
+    fn queue(&self, uuid: &McUuid, packet: Arc<dyn DynPacket>) -> bool {
+        let mut outbound = lock(&self.outbound);
+        let Some(ref sender) = *outbound else {
+            return false;
+        };
+        if let Err(mpsc::error::TrySendError::Full(packet)) = sender.try_send(packet) {
+            tracing::warn!(
+                "Outbound queue of {} is full at {}, disconnecting them",
+                uuid,
+                packet.name()
+            );
+            *outbound = None;
+        }
+        true
+    }
+}
+
+/// Outcome of adding a player
+#[derive(Debug)]
+pub enum AddPlayerOutcome {
+    /// The player was added
+    Added,
+    /// The player took the place of an older session with the same UUID,
+    /// whose connection no longer has a player
+    Replaced {
+        /// Player of the older session
+        player: Box<Player>,
+        /// Connection of the older session
+        addr: Option<SocketAddr>,
+    },
+    /// The server is full
+    Full,
+}
+
+impl AddPlayerOutcome {
+    /// Whether the player was added
+    pub fn is_added(&self) -> bool {
+        !matches!(self, Self::Full)
+    }
+}
+
+/// A map split into shards by key, each behind its own lock
+///
+/// Locks are never held across an await, so std locks are used.
+struct ShardedMap<K, V> {
+    /// The shards
+    shards: Vec<RwLock<HashMap<K, V>>>,
+    /// Hasher picking the shard of a key
+    hasher: RandomState,
+}
+
+impl<K: Eq + Hash, V: Clone> ShardedMap<K, V> {
+    /// Create an empty map
+    fn new() -> Self {
+        Self {
+            shards: (0..SHARD_COUNT)
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Get the shard a key belongs to
+    fn shard(&self, key: &K) -> &RwLock<HashMap<K, V>> {
+        &self.shards[self.hasher.hash_one(key) as usize % SHARD_COUNT]
+    }
+
+    /// Get a copy of the value of a key
+    fn get(&self, key: &K) -> Option<V> {
+        read(self.shard(key)).get(key).cloned()
+    }
+
+    /// Insert a value, returning the one it replaced
+    fn insert(&self, key: K, value: V) -> Option<V> {
+        write(self.shard(&key)).insert(key, value)
+    }
+
+    /// Remove a value
+    fn remove(&self, key: &K) -> Option<V> {
+        write(self.shard(key)).remove(key)
+    }
+
+    /// Remove a value if it is the one expected
+    fn remove_if(&self, key: &K, expected: impl FnOnce(&V) -> bool) -> Option<V> {
+        let mut shard = write(self.shard(key));
+        if shard.get(key).is_some_and(expected) {
+            shard.remove(key)
+        } else {
+            None
+        }
+    }
+
+    /// Copies of every value
+    fn values(&self) -> Vec<V> {
+        self.shards
+            .iter()
+            .flat_map(|shard| read(shard).values().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+}
+
 /// Player manager for handling all connected players
+///
+/// Players are kept in a sharded map, each behind a lock of their own, so
+/// lookups on different players do not contend and only the player being
+/// accessed is locked.
 pub struct PlayerManager {
-    /// Map of UUID to player data
-    players: Arc<RwLock<HashMap<McUuid, Player>>>,
+    /// Map of UUID to player
+    players: ShardedMap<McUuid, Arc<PlayerSlot>>,
     /// Map of connection address to player UUID
-    connections: Arc<RwLock<HashMap<SocketAddr, McUuid>>>,
+    connections: ShardedMap<SocketAddr, McUuid>,
+    /// Number of players
+    count: AtomicUsize,
+    /// Woken whenever a player is removed
+    removed: Notify,
 }
 
 impl PlayerManager {
     /// Create a new player manager
     pub fn new() -> Self {
         Self {
-            players: Arc::new(RwLock::new(HashMap::new())),
-            connections: Arc::new(RwLock::new(HashMap::new())),
+            players: ShardedMap::new(),
+            connections: ShardedMap::new(),
+            count: AtomicUsize::new(0),
+            removed: Notify::new(),
         }
     }
 
     /// Add a new player
-    pub async fn add_player(&self, player: Player, connection_addr: SocketAddr) {
-        let uuid = player.uuid;
-
-        {
-            let mut players = self.players.write().await;
-            players.insert(uuid, player);
-        }
+    pub fn add_player(&self, player: Player, connection_addr: SocketAddr) {
+        self.count.fetch_add(1, Ordering::AcqRel);
+        self.insert(player, connection_addr);
+    }
 
-        {
-            let mut connections = self.connections.write().await;
-            connections.insert(connection_addr, uuid);
+    /// Add a new player unless the server is full
+    ///
+    /// A place is reserved in the player count before the player is added,
+    /// so concurrent logins cannot exceed `max_players`. Players that
+    /// bypass the limit are always added. A player already online under the
+    /// same UUID is replaced in the same step, so concurrent logins of one
+    /// player leave exactly one session.
+    pub fn try_add_player(
+        &self,
+        player: Player,
+        connection_addr: SocketAddr,
+        max_players: usize,
+        bypass_limit: bool,
+    ) -> AddPlayerOutcome {
+        let reserved = self
+            .count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < max_players || bypass_limit).then_some(count + 1)
+            });
+        if reserved.is_err() {
+            return AddPlayerOutcome::Full;
         }
+        self.insert(player, connection_addr)
+    }
 
+    /// Insert a player whose place in the player count is already reserved
+    fn insert(&self, player: Player, connection_addr: SocketAddr) -> AddPlayerOutcome {
+        let uuid = player.uuid;
+        let slot = Arc::new(PlayerSlot {
+            player: Mutex::new(player),
+            outbound: Mutex::new(None),
+            addr: Some(connection_addr),
+        });
+        self.connections.insert(connection_addr, uuid);
+        let replaced = self.players.insert(uuid, slot);
         tracing::info!("Player {} connected from {}", uuid, connection_addr);
+
+        let Some(replaced) = replaced else {
+            return AddPlayerOutcome::Added;
+        };
+        // A player replaced under the same UUID keeps its place, and the
+        // older session's connection no longer has a player
+        self.count.fetch_sub(1, Ordering::AcqRel);
+        if let Some(addr) = replaced.addr {
+            self.connections.remove_if(&addr, |owner| *owner == uuid);
+        }
+        self.removed.notify_waiters();
+        let player = Box::new(lock(&replaced.player).clone());
+        AddPlayerOutcome::Replaced {
+            player,
+            addr: replaced.addr,
+        }
     }
 
     /// Remove a player
-    pub async fn remove_player(&self, connection_addr: SocketAddr) -> Option<Player> {
-        let uuid = {
-            let mut connections = self.connections.write().await;
-            connections.remove(&connection_addr)
-        };
+    ///
+    /// Nothing is removed if the player's slot was taken over by a newer
+    /// session.
+    pub fn remove_player(&self, connection_addr: SocketAddr) -> Option<Player> {
+        let uuid = self.connections.remove(&connection_addr)?;
+        let slot = self
+            .players
+            .remove_if(&uuid, |slot| slot.addr == Some(connection_addr))?;
+        self.count.fetch_sub(1, Ordering::AcqRel);
+        self.removed.notify_waiters();
 
-        if let Some(uuid) = uuid {
-            let mut players = self.players.write().await;
-            let player = players.remove(&uuid);
+        let player = match Arc::try_unwrap(slot) {
+            Ok(slot) => slot.player.into_inner().unwrap_or_else(|e| e.into_inner()),
+            Err(slot) => lock(&slot.player).clone(),
+        };
+        tracing::info!(
+            "Player {} disconnected from {}",
+            player.username,
+            connection_addr
+        );
+        Some(player)
+    }
 
-            if let Some(ref player) = player {
-                tracing::info!(
-                    "Player {} disconnected from {}",
-                    player.username,
-                    connection_addr
-                );
+    /// Wait for a player to be removed, returning whether they were in time
+    pub async fn wait_until_removed(&self, uuid: &McUuid, timeout: Duration) -> bool {
+        let removed = async {
+            loop {
+                // Created before checking, so a removal in between still wakes it
+                let notified = self.removed.notified();
+                if self.players.get(uuid).is_none() {
+                    return;
+                }
+                notified.await;
             }
+        };
+        tokio::time::timeout(timeout, removed).await.is_ok()
+    }
 
-            player
-        } else {
-            None
+    /// Find an online player with either a UUID or a name, ignoring case
+    pub fn find_player(&self, uuid: &McUuid, username: &str) -> Option<Player> {
+        if let Some(player) = self.get_player(uuid) {
+            return Some(player);
         }
+        self.players
+            .values()
+            .into_iter()
+            .map(|slot| lock(&slot.player).clone())
+            .find(|player| player.username.eq_ignore_ascii_case(username))
     }
 
     /// Get a player by UUID
-    pub async fn get_player(&self, uuid: &McUuid) -> Option<Player> {
-        let players = self.players.read().await;
-        players.get(uuid).cloned()
+    pub fn get_player(&self, uuid: &McUuid) -> Option<Player> {
+        self.with_player(uuid, Player::clone)
     }
 
     /// Get a player by connection address
-    pub async fn get_player_by_addr(&self, addr: &SocketAddr) -> Option<Player> {
-        let uuid = {
-            let connections = self.connections.read().await;
-            connections.get(addr).copied()
-        };
+    pub fn get_player_by_addr(&self, addr: &SocketAddr) -> Option<Player> {
+        self.with_player_by_addr(addr, Player::clone)
+    }
 
-        if let Some(uuid) = uuid {
-            self.get_player(&uuid).await
-        } else {
-            None
+    /// Read a player without copying them
+    ///
+    /// Returns `None` if no player has the UUID.
+    pub fn with_player<R>(&self, uuid: &McUuid, f: impl FnOnce(&Player) -> R) -> Option<R> {
+        let slot = self.players.get(uuid)?;
+        let player = lock(&slot.player);
+        Some(f(&player))
+    }
+
+    /// Read the player on a connection without copying them
+    ///
+    /// Returns `None` if no player is associated with the address.
+    pub fn with_player_by_addr<R>(
+        &self,
+        addr: &SocketAddr,
+        f: impl FnOnce(&Player) -> R,
+    ) -> Option<R> {
+        let uuid = self.connections.get(addr)?;
+        self.with_player(&uuid, f)
+    }
+
+    /// Modify the player on a connection in place
+    ///
+    /// Returns `None` if no player is associated with the address.
+    pub fn with_player_mut<R>(
+        &self,
+        addr: &SocketAddr,
+        f: impl FnOnce(&mut Player) -> R,
+    ) -> Option<R> {
+        let uuid = self.connections.get(addr)?;
+        let slot = self.players.get(&uuid)?;
+        let mut player = lock(&slot.player);
+        Some(f(&mut player))
+    }
+
+    /// Modify every connected player in place
+    ///
+    /// Players are locked one at a time, so the closure sees each player in
+    /// turn rather than a snapshot of all of them.
+    pub fn for_each_player_mut(&self, mut f: impl FnMut(&mut Player)) {
+        for slot in self.players.values() {
+            f(&mut lock(&slot.player));
         }
     }
 
     /// Update a player
-    pub async fn update_player(&self, uuid: &McUuid, player: Player) {
-        let mut players = self.players.write().await;
-        players.insert(*uuid, player);
+    pub fn update_player(&self, uuid: &McUuid, player: Player) {
+        match self.players.get(uuid) {
+            Some(slot) => *lock(&slot.player) = player,
+            None => {
+                self.count.fetch_add(1, Ordering::AcqRel);
+                self.players.insert(
+                    *uuid,
+                    Arc::new(PlayerSlot {
+                        player: Mutex::new(player),
+                        outbound: Mutex::new(None),
+                        addr: None,
+                    }),
+                );
+            }
+        }
     }
 
     /// Get all connected players
-    pub async fn get_all_players(&self) -> Vec<Player> {
-        let players = self.players.read().await;
-        players.values().cloned().collect()
+    pub fn get_all_players(&self) -> Vec<Player> {
+        self.map_players(Player::clone)
+    }
+
+    /// Read part of every connected player without copying the rest
+    ///
+    /// Players are locked one at a time, as in
+    /// [`PlayerManager::for_each_player_mut`].
+    pub fn map_players<R>(&self, mut f: impl FnMut(&Player) -> R) -> Vec<R> {
+        self.players
+            .values()
+            .iter()
+            .map(|slot| f(&lock(&slot.player)))
+            .collect()
     }
 
     /// Get player count
-    pub async fn player_count(&self) -> usize {
-        let players = self.players.read().await;
-        players.len()
+    pub fn player_count(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    /// Open the outbound packet queue of the player on a connection
+    ///
+    /// Packets sent to the player from now on are queued for the returned
+    /// receiver; a queue opened earlier is replaced. Returns `None` if no
+    /// player is associated with the address.
+    pub fn open_outbound(&self, addr: &SocketAddr) -> Option<OutboundReceiver> {
+        let uuid = self.connections.get(addr)?;
+        let slot = self.players.get(&uuid)?;
+        let (sender, receiver) = mpsc::channel(OUTBOUND_CHANNEL_CAPACITY);
+        *lock(&slot.outbound) = Some(sender);
+        Some(receiver)
+    }
+
+    /// Close the outbound packet queue of the player on a connection
+    ///
+    /// Packets sent to the player are dropped until a queue is opened
+    /// again.
+    pub fn close_outbound(&self, addr: &SocketAddr) {
+        if let Some(slot) = self
+            .connections
+            .get(addr)
+            .and_then(|uuid| self.players.get(&uuid))
+        {
+            *lock(&slot.outbound) = None;
+        }
+    }
+
+    /// Whether a player is still connected from an address
+    ///
+    /// A connection whose player logged in again from elsewhere no longer
+    /// is.
+    pub fn is_connected(&self, addr: &SocketAddr) -> bool {
+        self.connections.get(addr).is_some()
+    }
+
+    /// Queue a packet for a player
+    ///
+    /// Returns `false` if the player has no open queue, i.e. is not in the
+    /// play state. A player whose queue is full is disconnected rather than
+    /// missing the packet.
+    pub fn send_packet<P: DynPacket + 'static>(&self, uuid: &McUuid, packet: P) -> bool {
+        self.players
+            .get(uuid)
+            .is_some_and(|slot| slot.queue(uuid, Arc::new(packet)))
+    }
+
+    /// Queue a packet for every player in the play state
+    pub fn broadcast<P: DynPacket + 'static>(&self, packet: P) {
+        let packet: Arc<dyn DynPacket> = Arc::new(packet);
+        for slot in self.players.values() {
+            let uuid = lock(&slot.player).uuid;
+            slot.queue(&uuid, Arc::clone(&packet));
+        }
+    }
+
+    /// Number of packets waiting in a player's outbound queue
+    pub fn queued_packets(&self, uuid: &McUuid) -> usize {
+        self.players.get(uuid).map_or(0, |slot| {
+            lock(&slot.outbound)
+                .as_ref()
+                .map_or(0, |sender| sender.max_capacity() - sender.capacity())
+        })
+    }
+
+    /// Number of packets that still fit in a player's outbound queue
+    pub fn outbound_room(&self, uuid: &McUuid) -> usize {
+        self.players.get(uuid).map_or(0, |slot| {
+            lock(&slot.outbound)
+                .as_ref()
+                .map_or(0, mpsc::Sender::capacity)
+        })
     }
+
+    /// Queue packets of different types for a player, in order
+    ///
+    /// Returns `false` if the player has no open queue.
+    pub fn send_packets(&self, uuid: &McUuid, packets: Vec<Box<dyn DynPacket>>) -> bool {
+        let Some(slot) = self.players.get(uuid) else {
+            return false;
+        };
+        packets
+            .into_iter()
+            .all(|packet| slot.queue(uuid, Arc::from(packet)))
+    }
+
+    /// Queue a packet for every player in the play state in a dimension
+    pub fn broadcast_in<P: DynPacket + 'static>(&self, dimension: Dimension, packet: P) {
+        let packet: Arc<dyn DynPacket> = Arc::new(packet);
+        for slot in self.players.values() {
+            let (uuid, in_dimension) = {
+                let player = lock(&slot.player);
+                (player.uuid, player.dimension == dimension)
+            };
+            if in_dimension {
+                slot.queue(&uuid, Arc::clone(&packet));
+            }
+        }
+    }
+
+    /// Queue a packet for every player in the play state except one
+    pub fn broadcast_except<P: DynPacket + 'static>(&self, uuid: &McUuid, packet: P) {
+        let packet: Arc<dyn DynPacket> = Arc::new(packet);
+        for slot in self.players.values() {
+            let player_uuid = lock(&slot.player).uuid;
+            if player_uuid != *uuid {
+                slot.queue(&player_uuid, Arc::clone(&packet));
+            }
+        }
+    }
+}
+
+/// Lock a mutex, ignoring poisoning
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lock a shard for reading, ignoring poisoning
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lock a shard for writing, ignoring poisoning
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
 }
 
 impl Default for PlayerManager {
@@ -245,3 +1012,194 @@ impl Default for PlayerManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::packets::play::KeepAlivePacket;
+
+    #[test]
+    fn test_starvation_depends_on_difficulty() {
+        let starve = |difficulty| {
+            let mut player = Player::new(McUuid::new_v4(), "Steve".to_string());
+            player.set_food(0);
+            for _ in 0..FOOD_TICKS * 30 {
+                player.tick_hunger(difficulty);
+            }
+            player.health
+        };
+        assert_eq!(starve(Difficulty::Easy), 10.0);
+        assert_eq!(starve(Difficulty::Normal), 1.0);
+        assert_eq!(starve(Difficulty::Hard), 0.0);
+        assert_eq!(starve(Difficulty::Peaceful), 20.0);
+
+        let mut player = Player::new(McUuid::new_v4(), "Alex".to_string());
+        player.set_health(10.0);
+        for _ in 0..FOOD_TICKS - 1 {
+            assert!(!player.tick_hunger(Difficulty::Normal));
+        }
+        assert!(player.tick_hunger(Difficulty::Normal));
+        assert_eq!(player.health, 11.0);
+    }
+
+    #[test]
+    fn test_eating_caps_saturation_at_food() {
+        let bread = crate::game::food::food_value("minecraft:bread").unwrap();
+        let mut player = Player::new(McUuid::new_v4(), "Steve".to_string());
+        assert!(!player.can_eat());
+        player.set_food(2);
+        player.saturation = 0.0;
+        assert!(player.can_eat());
+        player.eat(bread);
+        assert_eq!((player.food, player.saturation), (7, 6.0));
+        player.eat(bread);
+        player.eat(bread);
+        assert_eq!((player.food, player.saturation), (17, 17.0));
+    }
+
+    #[test]
+    fn test_abilities_follow_game_mode() {
+        let mut player = Player::new(McUuid::new_v4(), "Flyer".to_string());
+        player.abilities.fly_speed = 0.2;
+        player.set_game_mode(GameMode::Creative);
+        assert!(player.abilities.allow_flying && player.abilities.instant_break);
+        assert!(!player.abilities.flying);
+
+        player.abilities.flying = true;
+        player.set_game_mode(GameMode::Spectator);
+        assert!(player.abilities.flying && !player.abilities.instant_break);
+
+        player.set_game_mode(GameMode::Survival);
+        assert!(!player.abilities.flying && !player.abilities.invulnerable);
+        assert_eq!(player.abilities.fly_speed, 0.2);
+    }
+
+    #[tokio::test]
+    async fn test_outbound_packets() {
+        let manager = PlayerManager::new();
+        let near_addr: SocketAddr = "127.0.0.1:50001".parse().unwrap();
+        let far_addr: SocketAddr = "127.0.0.1:50002".parse().unwrap();
+        let near = Player::new(McUuid::new_v4(), "Near".to_string());
+        let mut far = Player::new(McUuid::new_v4(), "Far".to_string());
+        far.set_position(100.0, 64.0, 0.0);
+        let (near_uuid, far_uuid) = (near.uuid, far.uuid);
+        manager.add_player(near, near_addr);
+        manager.add_player(far, far_addr);
+
+        // Nothing is queued before the connection opens its queue
+        assert!(!manager.send_packet(&near_uuid, KeepAlivePacket { keep_alive_id: 0 }));
+        let mut near_rx = manager.open_outbound(&near_addr).unwrap();
+        let mut far_rx = manager.open_outbound(&far_addr).unwrap();
+
+        assert!(manager.send_packet(&far_uuid, KeepAlivePacket { keep_alive_id: 1 }));
+        manager.broadcast(KeepAlivePacket { keep_alive_id: 2 });
+        manager.broadcast_except(&far_uuid, KeepAlivePacket { keep_alive_id: 3 });
+
+        let ids = |rx: &mut OutboundReceiver| {
+            std::iter::from_fn(|| rx.try_recv().ok())
+                .map(|packet| packet.encode().unwrap().data)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(&mut near_rx),
+            [2i64, 3].map(|id| id.to_be_bytes().to_vec())
+        );
+        assert_eq!(
+            ids(&mut far_rx),
+            [1i64, 2].map(|id| id.to_be_bytes().to_vec())
+        );
+
+        // A packet that does not fit closes the queue rather than being lost
+        for id in 0..=OUTBOUND_CHANNEL_CAPACITY as i64 {
+            assert!(manager.send_packet(&near_uuid, KeepAlivePacket { keep_alive_id: id }));
+        }
+        assert!(!manager.send_packet(&near_uuid, KeepAlivePacket { keep_alive_id: 0 }));
+        assert_eq!(ids(&mut near_rx).len(), OUTBOUND_CHANNEL_CAPACITY);
+        assert!(near_rx.is_closed());
+        assert!(manager.is_connected(&near_addr));
+
+        manager.remove_player(far_addr);
+        assert!(!manager.send_packet(&far_uuid, KeepAlivePacket { keep_alive_id: 4 }));
+        assert!(!manager.is_connected(&far_addr));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_logins_respect_limit() {
+        let manager = Arc::new(PlayerManager::new());
+        let logins: Vec<_> = (0..32u16)
+            .map(|i| {
+                let manager = Arc::clone(&manager);
+                tokio::spawn(async move {
+                    let player = Player::new(McUuid::new_v4(), format!("Player{}", i));
+                    let addr = SocketAddr::from(([127, 0, 0, 1], 40000 + i));
+                    manager.try_add_player(player, addr, 10, false).is_added()
+                })
+            })
+            .collect();
+
+        let mut added = 0;
+        for login in logins {
+            added += usize::from(login.await.unwrap());
+        }
+        assert_eq!(added, 10);
+        assert_eq!(manager.player_count(), 10);
+        assert_eq!(manager.get_all_players().len(), 10);
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], 50000));
+        let op = Player::new(McUuid::new_v4(), "Op".to_string());
+        let added = manager.try_add_player(op.clone(), addr, 10, true);
+        assert!(added.is_added());
+        let name = manager.with_player(&op.uuid, |p| p.username.clone());
+        assert_eq!(name.as_deref(), Some("Op"));
+        assert!(manager.remove_player(addr).is_some());
+        assert_eq!(manager.player_count(), 10);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_logins_of_one_player() {
+        let manager = Arc::new(PlayerManager::new());
+        let uuid = McUuid::new_v4();
+        let addrs: Vec<_> = (0..16u16)
+            .map(|i| SocketAddr::from(([127, 0, 0, 1], 40000 + i)))
+            .collect();
+        let logins: Vec<_> = addrs
+            .iter()
+            .map(|&addr| {
+                let manager = Arc::clone(&manager);
+                tokio::spawn(async move {
+                    let player = Player::new(uuid, "Steve".to_string());
+                    manager.try_add_player(player, addr, 10, false)
+                })
+            })
+            .collect();
+
+        let mut replaced = Vec::new();
+        for login in logins {
+            match login.await.unwrap() {
+                AddPlayerOutcome::Added => {}
+                AddPlayerOutcome::Replaced { addr, .. } => replaced.push(addr.unwrap()),
+                AddPlayerOutcome::Full => unreachable!("a replacing login was refused"),
+            }
+        }
+        assert_eq!(replaced.len(), addrs.len() - 1);
+        assert_eq!(manager.player_count(), 1);
+
+        // Only the surviving session's connection still has the player
+        let mut owners = Vec::new();
+        for addr in &addrs {
+            if manager.get_player_by_addr(addr).is_some() {
+                owners.push(*addr);
+            }
+        }
+        assert_eq!(owners.len(), 1);
+        assert!(!replaced.contains(&owners[0]));
+
+        // Replaced sessions closing do not take the player with them
+        for addr in replaced {
+            assert!(manager.remove_player(addr).is_none());
+        }
+        assert_eq!(manager.player_count(), 1);
+        assert!(manager.remove_player(owners[0]).is_some());
+        assert_eq!(manager.player_count(), 0);
+    }
+}
@@ -0,0 +1,122 @@
+//! Player data persistence
+//!
+//! Per-player state that survives reconnects (the respawn point and the
+//! inventory) is stored as one JSON file per player in the world's
+//! `playerdata` directory.
+
+use crate::error::{Result, ServerError};
+use crate::game::inventory::{Inventory, SavedItem};
+use crate::game::player::{Player, RespawnPoint};
+use crate::protocol::types::McUuid;
+use std::path::{Path, PathBuf};
+
+/// Persisted state of a single player
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlayerData {
+    /// Respawn point set by a bed or respawn anchor
+    #[serde(default)]
+    pub respawn_point: Option<RespawnPoint>,
+    /// Items in the inventory, offhand and armor included
+    #[serde(default)]
+    pub inventory: Vec<SavedItem>,
+    /// Selected hotbar slot
+    #[serde(default)]
+    pub selected_slot: u8,
+}
+
+impl PlayerData {
+    /// Capture the persisted state of a player
+    pub fn from_player(player: &Player) -> Self {
+        Self {
+            respawn_point: player.respawn_point.clone(),
+            inventory: player.inventory.saved(),
+            selected_slot: player.inventory.selected(),
+        }
+    }
+
+    /// Restore the persisted state onto a player
+    pub fn apply_to(self, player: &mut Player) {
+        player.respawn_point = self.respawn_point;
+        player.inventory = Inventory::from_saved(&self.inventory, self.selected_slot);
+    }
+}
+
+/// Reads and writes player data files
+#[derive(Debug, Clone)]
+pub struct PlayerDataStore {
+    /// Directory containing `<uuid>.json` files
+    directory: PathBuf,
+}
+
+impl PlayerDataStore {
+    /// Create a store backed by the given directory
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Get the file path used for a player
+    pub fn path_for(&self, uuid: &McUuid) -> PathBuf {
+        self.directory.join(format!("{}.json", uuid))
+    }
+
+    /// Load a player's data, returning `None` if nothing was saved yet
+    pub fn load(&self, uuid: &McUuid) -> Result<Option<PlayerData>> {
+        let contents = match std::fs::read_to_string(self.path_for(uuid)) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| ServerError::Storage(format!("Invalid player data for {}: {}", uuid, e)))
+    }
+
+    /// Save a player's data
+    ///
+    /// The file is written to a temporary path first and then renamed, so a
+    /// crash never leaves a truncated file behind.
+    pub fn save(&self, uuid: &McUuid, data: &PlayerData) -> Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+
+        let json = serde_json::to_string_pretty(data)
+            .map_err(|e| ServerError::Storage(format!("Failed to serialize player data: {}", e)))?;
+
+        let path = self.path_for(uuid);
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, &path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::Position;
+
+    #[test]
+    fn test_player_data_roundtrip() {
+        let directory =
+            std::env::temp_dir().join(format!("obsidium-playerdata-{}", uuid::Uuid::new_v4()));
+        let store = PlayerDataStore::new(&directory);
+        let uuid = uuid::Uuid::new_v4();
+
+        assert_eq!(store.load(&uuid).unwrap(), None);
+
+        let data = PlayerData {
+            respawn_point: Some(RespawnPoint::new("world", Position::new(4, 64, -9))),
+            inventory: vec![SavedItem {
+                slot: 45,
+                item: crate::game::inventory::ItemStack::new(364, 3),
+            }],
+            selected_slot: 2,
+        };
+        store.save(&uuid, &data).unwrap();
+        assert_eq!(store.load(&uuid).unwrap(), Some(data));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+//! Client settings
+//!
+//! Clients report their settings in a Client Information packet during
+//! configuration and again whenever the player changes them in game. The
+//! latest settings are kept on the [`Player`](super::Player) and announced
+//! with a [`ServerEvent::PlayerSettingsChanged`](crate::event::ServerEvent).
+
+use crate::protocol::packets::configuration::ClientInformationPacket;
+
+/// Which chat messages a client shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChatMode {
+    /// All messages
+    #[default]
+    Enabled,
+    /// Only command feedback
+    CommandsOnly,
+    /// No messages
+    Hidden,
+}
+
+impl ChatMode {
+    /// Get a chat mode from its protocol ID, falling back to `Enabled`
+    pub fn from_id(id: i32) -> Self {
+        match id {
+            1 => ChatMode::CommandsOnly,
+            2 => ChatMode::Hidden,
+            _ => ChatMode::Enabled,
+        }
+    }
+}
+
+/// The hand a player uses for their main actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MainHand {
+    /// Left hand
+    Left = 0,
+    /// Right hand
+    #[default]
+    Right = 1,
+}
+
+impl MainHand {
+    /// Get a hand from its protocol ID, falling back to `Right`
+    pub fn from_id(id: i32) -> Self {
+        if id == 0 {
+            MainHand::Left
+        } else {
+            MainHand::Right
+        }
+    }
+
+    /// Get the protocol ID of this hand
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// How many particles a client shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParticleStatus {
+    /// Every particle
+    #[default]
+    All,
+    /// Fewer particles
+    Decreased,
+    /// Hardly any particles
+    Minimal,
+}
+
+impl ParticleStatus {
+    /// Get a particle status from its protocol ID, falling back to `All`
+    pub fn from_id(id: i32) -> Self {
+        match id {
+            1 => ParticleStatus::Decreased,
+            2 => ParticleStatus::Minimal,
+            _ => ParticleStatus::All,
+        }
+    }
+}
+
+/// The skin layers a player shows, as a bit mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkinParts(pub u8);
+
+impl SkinParts {
+    /// Cape
+    pub const CAPE: u8 = 0x01;
+    /// Jacket
+    pub const JACKET: u8 = 0x02;
+    /// Left sleeve
+    pub const LEFT_SLEEVE: u8 = 0x04;
+    /// Right sleeve
+    pub const RIGHT_SLEEVE: u8 = 0x08;
+    /// Left pants leg
+    pub const LEFT_PANTS_LEG: u8 = 0x10;
+    /// Right pants leg
+    pub const RIGHT_PANTS_LEG: u8 = 0x20;
+    /// Hat
+    pub const HAT: u8 = 0x40;
+
+    /// Whether a part (one of the constants) is shown
+    pub fn shows(&self, part: u8) -> bool {
+        self.0 & part != 0
+    }
+}
+
+impl Default for SkinParts {
+    /// Every part, as vanilla clients default to
+    fn default() -> Self {
+        Self(0x7F)
+    }
+}
+
+/// Settings a client reported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSettings {
+    /// Locale, such as `en_us`
+    pub locale: String,
+    /// Render distance in chunks
+    pub view_distance: u8,
+    /// Which chat messages are shown
+    pub chat_mode: ChatMode,
+    /// Whether chat colors are shown
+    pub chat_colors: bool,
+    /// Skin layers shown
+    pub skin_parts: SkinParts,
+    /// Hand used for main actions
+    pub main_hand: MainHand,
+    /// Whether the client asks for chat to be filtered
+    pub text_filtering: bool,
+    /// Whether the player may appear in the server list sample
+    pub allows_listing: bool,
+    /// How many particles are shown
+    pub particle_status: ParticleStatus,
+}
+
+impl Default for ClientSettings {
+    fn default() -> Self {
+        Self {
+            locale: "en_us".to_string(),
+            view_distance: 10,
+            chat_mode: ChatMode::default(),
+            chat_colors: true,
+            skin_parts: SkinParts::default(),
+            main_hand: MainHand::default(),
+            text_filtering: false,
+            allows_listing: true,
+            particle_status: ParticleStatus::default(),
+        }
+    }
+}
+
+impl From<&ClientInformationPacket> for ClientSettings {
+    fn from(packet: &ClientInformationPacket) -> Self {
+        Self {
+            locale: packet.locale.0.to_ascii_lowercase(),
+            view_distance: packet.view_distance.max(0) as u8,
+            chat_mode: ChatMode::from_id(packet.chat_mode.0),
+            chat_colors: packet.chat_colors,
+            skin_parts: SkinParts(packet.displayed_skin_parts),
+            main_hand: MainHand::from_id(packet.main_hand.0),
+            text_filtering: packet.enable_text_filtering,
+            allows_listing: packet.allow_server_listings,
+            particle_status: ParticleStatus::from_id(packet.particle_status.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+    use crate::protocol::types::{McString, McUuid, VarInt};
+
+    #[test]
+    fn test_settings_from_packet() {
+        let packet = ClientInformationPacket {
+            locale: McString("de_DE".to_string()),
+            view_distance: 6,
+            chat_mode: VarInt(1),
+            chat_colors: false,
+            displayed_skin_parts: SkinParts::CAPE | SkinParts::HAT,
+            main_hand: VarInt(0),
+            enable_text_filtering: false,
+            allow_server_listings: false,
+            particle_status: VarInt(9),
+        };
+        let settings = ClientSettings::from(&packet);
+        assert_eq!(settings.locale, "de_de");
+        assert_eq!(settings.chat_mode, ChatMode::CommandsOnly);
+        assert!(settings.skin_parts.shows(SkinParts::HAT));
+        assert!(!settings.skin_parts.shows(SkinParts::JACKET));
+        assert_eq!(settings.main_hand, MainHand::Left);
+        assert_eq!(settings.particle_status, ParticleStatus::All);
+
+        let mut player = Player::new(McUuid::new_v4(), "Steve".to_string());
+        assert!(player.settings().is_none());
+        assert!(player.allows_listing());
+        assert_eq!(player.effective_view_distance(10), 10);
+        player.set_settings(settings);
+        assert!(!player.allows_listing());
+        assert_eq!(player.effective_view_distance(10), 6);
+    }
+}
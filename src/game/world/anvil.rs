@@ -0,0 +1,422 @@
+//! Anvil region files
+//!
+//! Chunks are stored in region files (`r.<x>.<z>.mca`) holding 32x32 chunks
+//! each. A region file starts with an 8 KiB header: 1024 chunk locations
+//! (3-byte sector offset, 1-byte sector count) followed by 1024 timestamps.
+//! Each chunk is stored as a 4-byte length, a compression type byte and the
+//! compressed NBT data, padded to 4 KiB sectors.
+//!
+//! Chunks too large for a region file are stored in an external
+//! `c.<x>.<z>.mcc` file next to it. Their entry in the region holds only
+//! the compression type with [`EXTERNAL_FLAG`] set.
+
+use super::lz4;
+use crate::error::{Result, ServerError};
+use flate2::Compression as FlateCompression;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Size of a region file sector
+pub const SECTOR_SIZE: usize = 4096;
+
+/// Number of chunks along each side of a region
+pub const REGION_SIZE: i32 = 32;
+
+/// Number of header sectors (locations and timestamps)
+const HEADER_SECTORS: usize = 2;
+
+/// Largest number of sectors a single chunk may occupy
+const MAX_CHUNK_SECTORS: usize = 255;
+
+/// Bit of the compression type byte set for chunks stored externally
+pub const EXTERNAL_FLAG: u8 = 0x80;
+
+/// Compression used for a chunk inside a region file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RegionCompression {
+    /// GZip (RFC1952)
+    Gzip = 1,
+    /// Zlib (RFC1950), the vanilla default
+    #[default]
+    Zlib = 2,
+    /// Uncompressed
+    None = 3,
+    /// LZ4 in lz4-java's block stream format
+    Lz4 = 4,
+}
+
+impl RegionCompression {
+    /// Get the compression from its type byte
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(RegionCompression::Gzip),
+            2 => Some(RegionCompression::Zlib),
+            3 => Some(RegionCompression::None),
+            4 => Some(RegionCompression::Lz4),
+            _ => None,
+        }
+    }
+
+    /// Get the compression from its `region-file-compression` name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "deflate" => Some(RegionCompression::Zlib),
+            "gzip" => Some(RegionCompression::Gzip),
+            "lz4" => Some(RegionCompression::Lz4),
+            "none" => Some(RegionCompression::None),
+            _ => None,
+        }
+    }
+
+    /// Name of the compression in `region-file-compression`
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegionCompression::Gzip => "gzip",
+            RegionCompression::Zlib => "deflate",
+            RegionCompression::None => "none",
+            RegionCompression::Lz4 => "lz4",
+        }
+    }
+
+    /// Compress chunk data
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(match self {
+            RegionCompression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), FlateCompression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+            RegionCompression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), FlateCompression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+            RegionCompression::None => data.to_vec(),
+            RegionCompression::Lz4 => lz4::compress(data),
+        })
+    }
+
+    /// Decompress chunk data
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        match self {
+            RegionCompression::Gzip => {
+                GzDecoder::new(data).read_to_end(&mut output)?;
+            }
+            RegionCompression::Zlib => {
+                ZlibDecoder::new(data).read_to_end(&mut output)?;
+            }
+            RegionCompression::None => output.extend_from_slice(data),
+            RegionCompression::Lz4 => output = lz4::decompress(data)?,
+        }
+        Ok(output)
+    }
+}
+
+/// Get the region file name holding a chunk
+pub fn region_file_name(chunk_x: i32, chunk_z: i32) -> String {
+    format!(
+        "r.{}.{}.mca",
+        chunk_x.div_euclid(REGION_SIZE),
+        chunk_z.div_euclid(REGION_SIZE)
+    )
+}
+
+/// Get the name of the external file holding an oversized chunk
+pub fn external_file_name(chunk_x: i32, chunk_z: i32) -> String {
+    format!("c.{}.{}.mcc", chunk_x, chunk_z)
+}
+
+/// A single open region file
+pub struct RegionFile {
+    /// Path of the file
+    path: PathBuf,
+    /// Open file handle
+    file: File,
+    /// Chunk locations: (first sector, sector count), zero if absent
+    locations: [(u32, u8); 1024],
+}
+
+impl RegionFile {
+    /// Open a region file, creating it if it does not exist
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let length = file.metadata()?.len() as usize;
+        if length < HEADER_SECTORS * SECTOR_SIZE {
+            file.set_len((HEADER_SECTORS * SECTOR_SIZE) as u64)?;
+        }
+
+        let mut header = [0u8; SECTOR_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+
+        let mut locations = [(0u32, 0u8); 1024];
+        for (i, location) in locations.iter_mut().enumerate() {
+            let entry = &header[i * 4..i * 4 + 4];
+            *location = (
+                u32::from_be_bytes([0, entry[0], entry[1], entry[2]]),
+                entry[3],
+            );
+        }
+
+        Ok(Self {
+            path,
+            file,
+            locations,
+        })
+    }
+
+    /// Get the path of this region file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Index of a chunk in the header
+    fn index(chunk_x: i32, chunk_z: i32) -> usize {
+        (chunk_x.rem_euclid(REGION_SIZE) + chunk_z.rem_euclid(REGION_SIZE) * REGION_SIZE) as usize
+    }
+
+    /// Check whether the region contains a chunk
+    pub fn has_chunk(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        self.locations[Self::index(chunk_x, chunk_z)].0 != 0
+    }
+
+    /// Read a chunk's uncompressed NBT data
+    pub fn read_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> Result<Option<Vec<u8>>> {
+        let (offset, sectors) = self.locations[Self::index(chunk_x, chunk_z)];
+        if offset == 0 {
+            return Ok(None);
+        }
+
+        self.file
+            .seek(SeekFrom::Start(offset as u64 * SECTOR_SIZE as u64))?;
+        let mut header = [0u8; 5];
+        self.file.read_exact(&mut header)?;
+
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if length == 0 || length > sectors as usize * SECTOR_SIZE {
+            return Err(ServerError::WorldIo(format!(
+                "Chunk ({}, {}) in {} has invalid length {}",
+                chunk_x,
+                chunk_z,
+                self.path.display(),
+                length
+            )));
+        }
+
+        let compression =
+            RegionCompression::from_id(header[4] & !EXTERNAL_FLAG).ok_or_else(|| {
+                ServerError::WorldIo(format!(
+                    "Chunk ({}, {}) uses unknown compression type {}",
+                    chunk_x, chunk_z, header[4]
+                ))
+            })?;
+
+        let data = if header[4] & EXTERNAL_FLAG != 0 {
+            std::fs::read(self.external_path(chunk_x, chunk_z))?
+        } else {
+            let mut data = vec![0u8; length - 1];
+            self.file.read_exact(&mut data)?;
+            data
+        };
+        compression.decompress(&data).map(Some)
+    }
+
+    /// Write a chunk's uncompressed NBT data
+    ///
+    /// Chunks too large for the region go to an external file. With `sync`
+    /// the data is flushed to disk before returning.
+    pub fn write_chunk(
+        &mut self,
+        chunk_x: i32,
+        chunk_z: i32,
+        data: &[u8],
+        compression: RegionCompression,
+        sync: bool,
+    ) -> Result<()> {
+        let compressed = compression.compress(data)?;
+        let external_path = self.external_path(chunk_x, chunk_z);
+        let mut type_id = compression as u8;
+        let mut stored = compressed.as_slice();
+        if (compressed.len() + 5).div_ceil(SECTOR_SIZE) > MAX_CHUNK_SECTORS {
+            write_external(&external_path, &compressed, sync)?;
+            type_id |= EXTERNAL_FLAG;
+            stored = &[];
+        }
+        let length = stored.len() + 1;
+        let sectors = (length + 4).div_ceil(SECTOR_SIZE);
+
+        let index = Self::index(chunk_x, chunk_z);
+        let offset = self.allocate(index, sectors);
+
+        let mut buffer = Vec::with_capacity(sectors * SECTOR_SIZE);
+        buffer.extend_from_slice(&(length as u32).to_be_bytes());
+        buffer.push(type_id);
+        buffer.extend_from_slice(stored);
+        buffer.resize(sectors * SECTOR_SIZE, 0);
+
+        self.file
+            .seek(SeekFrom::Start(offset as u64 * SECTOR_SIZE as u64))?;
+        self.file.write_all(&buffer)?;
+
+        // Update the location and timestamp entries
+        self.locations[index] = (offset, sectors as u8);
+        let location = offset.to_be_bytes();
+        self.file.seek(SeekFrom::Start(index as u64 * 4))?;
+        self.file
+            .write_all(&[location[1], location[2], location[3], sectors as u8])?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as u32);
+        self.file
+            .seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        self.file.write_all(&timestamp.to_be_bytes())?;
+
+        if sync {
+            self.file.sync_data()?;
+        }
+
+        // A chunk that shrank no longer needs its external file
+        if type_id & EXTERNAL_FLAG == 0 {
+            match std::fs::remove_file(&external_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Path of the external file an oversized chunk is stored in
+    fn external_path(&self, chunk_x: i32, chunk_z: i32) -> PathBuf {
+        self.path
+            .with_file_name(external_file_name(chunk_x, chunk_z))
+    }
+
+    /// Find room for `sectors` sectors for the chunk at `index`
+    ///
+    /// The chunk's current sectors are reused when the new data fits,
+    /// otherwise the first sufficiently large free gap is used.
+    fn allocate(&self, index: usize, sectors: usize) -> u32 {
+        let (current_offset, current_sectors) = self.locations[index];
+        if current_offset != 0 && sectors <= current_sectors as usize {
+            return current_offset;
+        }
+
+        let mut used: Vec<(u32, u32)> = self
+            .locations
+            .iter()
+            .enumerate()
+            .filter(|&(i, &(offset, _))| i != index && offset != 0)
+            .map(|(_, &(offset, count))| (offset, offset + count as u32))
+            .collect();
+        used.sort_unstable();
+
+        let mut candidate = HEADER_SECTORS as u32;
+        for (start, end) in used {
+            if start >= candidate + sectors as u32 {
+                break;
+            }
+            candidate = candidate.max(end);
+        }
+        candidate
+    }
+}
+
+/// Write the compressed data of an oversized chunk to its external file
+///
+/// The data is written to a temporary file first, so a crash leaves the
+/// previous version intact.
+fn write_external(path: &Path, data: &[u8], sync: bool) -> Result<()> {
+    let temporary = path.with_extension("mcc.tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(data)?;
+    if sync {
+        file.sync_data()?;
+    }
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_read_write() {
+        let path = std::env::temp_dir().join(format!("obsidium-{}.mca", uuid::Uuid::new_v4()));
+        let mut region = RegionFile::open(&path).unwrap();
+        assert!(!region.has_chunk(3, 4));
+        assert_eq!(region.read_chunk(3, 4).unwrap(), None);
+
+        let small = vec![7u8; 100];
+        let large: Vec<u8> = (0..20_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        region
+            .write_chunk(3, 4, &small, RegionCompression::Zlib, false)
+            .unwrap();
+        region
+            .write_chunk(-1, -1, &large, RegionCompression::None, true)
+            .unwrap();
+        // Growing a chunk moves it without clobbering its neighbours
+        region
+            .write_chunk(3, 4, &large, RegionCompression::Gzip, false)
+            .unwrap();
+
+        let mut reopened = RegionFile::open(&path).unwrap();
+        assert_eq!(reopened.read_chunk(3, 4).unwrap(), Some(large.clone()));
+        assert_eq!(reopened.read_chunk(31, 31).unwrap(), Some(large));
+        assert_eq!(region_file_name(-1, 33), "r.-1.1.mca");
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_oversized_chunks_are_stored_externally() {
+        let directory = std::env::temp_dir().join(format!("obsidium-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut region = RegionFile::open(directory.join(region_file_name(-3, 5))).unwrap();
+        // Incompressible, so it exceeds the region's sector limit either way
+        let mut state = 0x2545_f491_u32;
+        let huge: Vec<u8> = (0..1_100_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        let external = directory.join(external_file_name(-3, 5));
+
+        for compression in [RegionCompression::None, RegionCompression::Lz4] {
+            region
+                .write_chunk(-3, 5, &huge, compression, false)
+                .unwrap();
+            assert!(external.exists());
+            let mut reopened = RegionFile::open(region.path()).unwrap();
+            assert_eq!(reopened.read_chunk(-3, 5).unwrap(), Some(huge.clone()));
+        }
+
+        region
+            .write_chunk(-3, 5, b"small", RegionCompression::Lz4, false)
+            .unwrap();
+        assert!(!external.exists());
+        assert_eq!(region.read_chunk(-3, 5).unwrap(), Some(b"small".to_vec()));
+        assert_eq!(
+            RegionCompression::from_name("deflate"),
+            Some(RegionCompression::Zlib)
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,258 @@
+//! Beds and sleeping
+//!
+//! Beds are two blocks long: the foot is where the bed was placed and the
+//! head extends one block in the direction the bed faces. Sleeping in a bed
+//! sets the player's respawn point, and once enough players are asleep the
+//! night is skipped.
+
+use super::{Direction, TICKS_PER_DAY, Weather, World};
+use crate::game::player::{GameMode, Player, RespawnPoint};
+use crate::protocol::types::Position;
+use thiserror::Error;
+
+/// Ticks a player must sleep before the night can be skipped
+pub const SLEEP_DURATION: u32 = 100;
+
+/// Maximum horizontal distance from which a bed can be used
+pub const MAX_BED_DISTANCE: f64 = 3.0;
+
+/// Block name of the default bed
+pub const BED_BLOCK: &str = "minecraft:red_bed";
+
+/// Which half of a bed a block is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BedPart {
+    /// The half the bed was placed from
+    Foot,
+    /// The pillow half
+    Head,
+}
+
+/// One half of a placed bed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bed {
+    /// Direction from the foot towards the head
+    pub facing: Direction,
+    /// Which half this is
+    pub part: BedPart,
+    /// Whether someone is sleeping in the bed
+    pub occupied: bool,
+}
+
+impl Bed {
+    /// Create an unoccupied bed half
+    pub fn new(facing: Direction, part: BedPart) -> Self {
+        Self {
+            facing,
+            part,
+            occupied: false,
+        }
+    }
+
+    /// Get the position of the other half, given this half's position
+    pub fn other_half(&self, position: Position) -> Position {
+        match self.part {
+            BedPart::Foot => self.facing.relative(position),
+            BedPart::Head => self.facing.opposite().relative(position),
+        }
+    }
+
+    /// Get the position of the head, given this half's position
+    pub fn head_position(&self, position: Position) -> Position {
+        match self.part {
+            BedPart::Foot => self.other_half(position),
+            BedPart::Head => position,
+        }
+    }
+}
+
+/// Errors when placing a bed
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BedError {
+    /// One of the two blocks is not free
+    #[error("There is no room for the bed")]
+    Obstructed,
+    /// One of the two blocks has nothing below it
+    #[error("The bed needs a block below it")]
+    Unsupported,
+}
+
+/// Reasons a player cannot sleep, as shown to the player
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepError {
+    /// The clicked block is not a bed
+    #[error("That is not a bed")]
+    NotABed,
+    /// It is daytime and there is no thunderstorm
+    #[error("You can sleep only at night or during thunderstorms")]
+    NotPossibleNow,
+    /// The player is too far from the bed
+    #[error("You may not rest now; the bed is too far away")]
+    TooFarAway,
+    /// Someone else is sleeping in the bed
+    #[error("This bed is occupied")]
+    Occupied,
+    /// Spectators cannot sleep
+    #[error("Spectators cannot sleep")]
+    Spectating,
+}
+
+/// Check whether players may currently sleep in a world
+pub fn can_sleep_now(world: &World) -> bool {
+    let time = world.time_of_day() % TICKS_PER_DAY;
+    match world.weather() {
+        Weather::Thunder => true,
+        Weather::Rain => (12_010..=23_991).contains(&time),
+        Weather::Clear => (12_542..=23_459).contains(&time),
+    }
+}
+
+/// Make a player use the bed at `position`
+///
+/// Using a bed always sets the player's respawn point, even if they cannot
+/// sleep right now. On success the player is asleep and the head position
+/// of the bed is returned.
+pub fn try_sleep(
+    world: &mut World,
+    player: &mut Player,
+    position: Position,
+) -> Result<Position, SleepError> {
+    if player.game_mode == GameMode::Spectator {
+        return Err(SleepError::Spectating);
+    }
+    let bed = *world.bed_at(position).ok_or(SleepError::NotABed)?;
+    let head = bed.head_position(position);
+
+    let dx = player.position.x - (head.x as f64 + 0.5);
+    let dz = player.position.z - (head.z as f64 + 0.5);
+    let foot = bed.other_half(head);
+    let fx = player.position.x - (foot.x as f64 + 0.5);
+    let fz = player.position.z - (foot.z as f64 + 0.5);
+    if (dx * dx + dz * dz).min(fx * fx + fz * fz) > MAX_BED_DISTANCE * MAX_BED_DISTANCE {
+        return Err(SleepError::TooFarAway);
+    }
+
+    player.respawn_point = Some(RespawnPoint::new(world.name(), head));
+
+    if !can_sleep_now(world) {
+        return Err(SleepError::NotPossibleNow);
+    }
+    if bed.occupied {
+        return Err(SleepError::Occupied);
+    }
+
+    world.set_bed_occupied(head, true);
+    player.sleeping_at = Some(head);
+    player.sleep_ticks = 0;
+    Ok(head)
+}
+
+/// Wake a player up, freeing their bed
+///
+/// Returns `false` if the player was not asleep.
+pub fn wake_up(world: &mut World, player: &mut Player) -> bool {
+    let Some(head) = player.sleeping_at.take() else {
+        return false;
+    };
+    world.set_bed_occupied(head, false);
+    player.sleep_ticks = 0;
+    true
+}
+
+/// Whether a player has slept long enough to count towards skipping the
+/// night
+pub fn has_slept(player: &Player) -> bool {
+    player.sleeping_at.is_some() && player.sleep_ticks >= SLEEP_DURATION
+}
+
+/// Check whether enough players have slept long enough to skip the night
+///
+/// `slept` holds [`has_slept`] for every player. `percentage` is the
+/// `playersSleepingPercentage` game rule.
+pub fn should_skip_night(slept: impl IntoIterator<Item = bool>, percentage: u32) -> bool {
+    if percentage > 100 {
+        return false;
+    }
+
+    let (mut total, mut asleep) = (0u32, 0u32);
+    for slept in slept {
+        total += 1;
+        if slept {
+            asleep += 1;
+        }
+    }
+
+    let required = (total * percentage).div_ceil(100).max(1);
+    total > 0 && asleep >= required
+}
+
+/// Advance a world to the next morning and clear the weather
+pub fn skip_night(world: &mut World) {
+    let time = world.time_of_day();
+    world.set_time_of_day(time - time % TICKS_PER_DAY + TICKS_PER_DAY);
+    world.set_weather(Weather::Clear, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::McUuid;
+
+    fn world_with_bed() -> (World, Position) {
+        let mut world = World::new("world".to_string(), 0);
+        let foot = Position::new(0, 64, 0);
+        world.load_chunk(super::super::ChunkPosition::new(0, 0));
+        world.place_bed(foot, Direction::South, 8).unwrap();
+        (world, foot)
+    }
+
+    #[test]
+    fn test_bed_placement_and_removal() {
+        let (mut world, foot) = world_with_bed();
+        let head = Position::new(0, 64, 1);
+        assert_eq!(world.bed_at(foot).unwrap().part, BedPart::Foot);
+        assert_eq!(world.bed_at(head).unwrap().part, BedPart::Head);
+        assert_eq!(
+            world.place_bed(foot, Direction::East, 8),
+            Err(BedError::Obstructed)
+        );
+
+        assert_eq!(world.remove_bed(foot), Some(head));
+        assert!(world.bed_at(head).is_none());
+        assert_eq!(world.get_block(head), Some(0));
+    }
+
+    #[test]
+    fn test_sleeping_and_night_skip() {
+        let (mut world, foot) = world_with_bed();
+        let mut player = Player::new(McUuid::nil(), "Sleeper".to_string());
+        player.set_position(0.5, 64.0, -1.0);
+
+        assert_eq!(
+            try_sleep(&mut world, &mut player, foot),
+            Err(SleepError::NotPossibleNow)
+        );
+        assert!(player.respawn_point.is_some());
+
+        world.set_time_of_day(TICKS_PER_DAY + 13_000);
+        let head = try_sleep(&mut world, &mut player, foot).unwrap();
+        assert!(world.bed_at(head).unwrap().occupied);
+
+        let mut other = Player::new(McUuid::nil(), "Other".to_string());
+        other.set_position(0.5, 64.0, -1.0);
+        assert_eq!(
+            try_sleep(&mut world, &mut other, foot),
+            Err(SleepError::Occupied)
+        );
+
+        assert!(!should_skip_night([&player, &other].map(has_slept), 100));
+        player.sleep_ticks = SLEEP_DURATION;
+        assert!(!should_skip_night([&player, &other].map(has_slept), 100));
+        assert!(should_skip_night([&player, &other].map(has_slept), 50));
+
+        skip_night(&mut world);
+        assert_eq!(world.time_of_day(), 2 * TICKS_PER_DAY);
+        assert!(wake_up(&mut world, &mut player));
+        assert!(!world.bed_at(head).unwrap().occupied);
+    }
+}
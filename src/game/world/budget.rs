@@ -0,0 +1,302 @@
+//! Chunk budgets
+//!
+//! Loading a chunk, building its packet and keeping it in memory all cost
+//! something, and a player travelling between dimensions or many players
+//! joining at once can ask for thousands of chunks. [`ChunkBudgets`] caps
+//! the chunks loaded per tick, the chunk packets waiting in a player's
+//! outbound queue and the chunks kept in memory.
+//!
+//! Chunks a player is waiting for go into the [`ChunkQueue`] and are sent
+//! nearest first over the following ticks, as the budgets allow. The queue
+//! follows each player's [`ChunkView`], so moving or changing the view
+//! distance queues the chunks that came into sight. Once more
+//! chunks are loaded than allowed, the ones no player can see are unloaded,
+//! farthest from any player first.
+
+use super::ChunkPosition;
+use super::dimension::Dimension;
+use crate::protocol::types::McUuid;
+use std::collections::{HashMap, VecDeque};
+
+/// Limits on chunk work and memory (zero disables a limit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkBudgets {
+    /// Chunks read or generated per tick, per world
+    pub loads_per_tick: u32,
+    /// Packets that may wait in a player's outbound queue before no more
+    /// chunks are queued for them, at most the queue's capacity
+    pub queued_per_player: u32,
+    /// Chunks kept loaded per world before unseen ones are unloaded
+    pub max_loaded: u32,
+}
+
+impl ChunkBudgets {
+    /// Chunks that may be loaded per tick, or `None` if unlimited
+    pub fn loads_per_tick(&self) -> Option<u32> {
+        (self.loads_per_tick > 0).then_some(self.loads_per_tick)
+    }
+
+    /// Packets that may wait in a player's queue, or `None` if unlimited
+    pub fn queued_per_player(&self) -> Option<usize> {
+        (self.queued_per_player > 0).then_some(self.queued_per_player as usize)
+    }
+
+    /// Chunks that may stay loaded, or `None` if unlimited
+    pub fn max_loaded(&self) -> Option<usize> {
+        (self.max_loaded > 0).then_some(self.max_loaded as usize)
+    }
+}
+
+impl Default for ChunkBudgets {
+    fn default() -> Self {
+        Self {
+            loads_per_tick: 64,
+            queued_per_player: 256,
+            max_loaded: 10_000,
+        }
+    }
+}
+
+/// Chunks within `radius` of `center`, nearest first
+pub fn chunks_around(center: ChunkPosition, radius: i32) -> Vec<ChunkPosition> {
+    let mut positions: Vec<ChunkPosition> = (-radius..=radius)
+        .flat_map(|dx| (-radius..=radius).map(move |dz| (dx, dz)))
+        .map(|(dx, dz)| ChunkPosition::new(center.x + dx, center.z + dz))
+        .collect();
+    positions.sort_by_key(|p| (p.x - center.x).pow(2) + (p.z - center.z).pow(2));
+    positions
+}
+
+/// The chunks a player sees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkView {
+    /// Dimension the player is in
+    pub dimension: Dimension,
+    /// Chunk the player is in
+    pub center: ChunkPosition,
+    /// Effective view distance of the player
+    pub radius: i32,
+}
+
+impl ChunkView {
+    /// Whether a chunk is in view
+    pub fn contains(&self, position: ChunkPosition) -> bool {
+        let distance = (position.x - self.center.x)
+            .abs()
+            .max((position.z - self.center.z).abs());
+        distance <= self.radius
+    }
+
+    /// Chunks in view, nearest first
+    pub fn chunks(&self) -> Vec<ChunkPosition> {
+        chunks_around(self.center, self.radius)
+    }
+}
+
+/// Chunks waiting to be sent to a player
+#[derive(Debug, Clone)]
+struct PendingChunks {
+    /// Dimension the chunks are in
+    dimension: Dimension,
+    /// Chunks not sent yet, nearest first
+    positions: VecDeque<ChunkPosition>,
+}
+
+/// Chunks waiting to be sent to each player
+#[derive(Debug, Clone, Default)]
+pub struct ChunkQueue {
+    /// Pending chunks by player
+    pending: HashMap<McUuid, PendingChunks>,
+    /// Chunks each player was last known to see
+    views: HashMap<McUuid, ChunkView>,
+}
+
+impl ChunkQueue {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the chunks around a player, replacing any still pending
+    pub fn enqueue(
+        &mut self,
+        uuid: McUuid,
+        dimension: Dimension,
+        center: ChunkPosition,
+        radius: i32,
+    ) {
+        let view = ChunkView {
+            dimension,
+            center,
+            radius,
+        };
+        self.views.insert(uuid, view);
+        self.pending.insert(
+            uuid,
+            PendingChunks {
+                dimension,
+                positions: view.chunks().into(),
+            },
+        );
+    }
+
+    /// Record the chunks a player was sent without the queue
+    pub fn set_view(&mut self, uuid: McUuid, view: ChunkView) {
+        self.views.insert(uuid, view);
+    }
+
+    /// Chunks a player was last known to see
+    pub fn view(&self, uuid: &McUuid) -> Option<ChunkView> {
+        self.views.get(uuid).copied()
+    }
+
+    /// Follow a player's view as they move or their view distance changes
+    ///
+    /// Chunks that came into sight are queued, and pending chunks that left
+    /// it are dropped. Returns the chunks the player was sent but no longer
+    /// sees. A player's first view is only recorded, and a view in another
+    /// dimension queues all of its chunks.
+    pub fn follow(&mut self, uuid: McUuid, view: ChunkView) -> Vec<ChunkPosition> {
+        let Some(previous) = self.views.insert(uuid, view) else {
+            return Vec::new();
+        };
+        if previous == view {
+            return Vec::new();
+        }
+        if previous.dimension != view.dimension {
+            self.enqueue(uuid, view.dimension, view.center, view.radius);
+            return Vec::new();
+        }
+
+        let mut positions = self
+            .pending
+            .remove(&uuid)
+            .map(|pending| pending.positions)
+            .unwrap_or_default();
+        let forgotten = previous
+            .chunks()
+            .into_iter()
+            .filter(|p| !view.contains(*p) && !positions.contains(p))
+            .collect();
+        positions.retain(|p| view.contains(*p));
+        positions.extend(view.chunks().into_iter().filter(|p| !previous.contains(*p)));
+        if !positions.is_empty() {
+            let center = view.center;
+            positions
+                .make_contiguous()
+                .sort_by_key(|p| (p.x - center.x).pow(2) + (p.z - center.z).pow(2));
+            self.pending.insert(
+                uuid,
+                PendingChunks {
+                    dimension: view.dimension,
+                    positions,
+                },
+            );
+        }
+        forgotten
+    }
+
+    /// Forget the chunks pending for a player and what they see
+    pub fn remove(&mut self, uuid: &McUuid) {
+        self.pending.remove(uuid);
+        self.views.remove(uuid);
+    }
+
+    /// Players with chunks pending, and the dimension of their chunks
+    pub fn players(&self) -> Vec<(McUuid, Dimension)> {
+        self.pending
+            .iter()
+            .map(|(uuid, pending)| (*uuid, pending.dimension))
+            .collect()
+    }
+
+    /// Next chunk to send to a player
+    pub fn peek(&self, uuid: &McUuid) -> Option<ChunkPosition> {
+        self.pending.get(uuid)?.positions.front().copied()
+    }
+
+    /// Take the next chunk to send to a player
+    ///
+    /// The player leaves the queue once their last chunk is taken.
+    pub fn pop(&mut self, uuid: &McUuid) -> Option<ChunkPosition> {
+        let pending = self.pending.get_mut(uuid)?;
+        let position = pending.positions.pop_front();
+        if pending.positions.is_empty() {
+            self.pending.remove(uuid);
+        }
+        position
+    }
+
+    /// Chunks still pending for a player
+    pub fn pending(&self, uuid: &McUuid) -> usize {
+        self.pending
+            .get(uuid)
+            .map_or(0, |pending| pending.positions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_queue() {
+        let uuid = McUuid::from_u128(1);
+        let mut queue = ChunkQueue::new();
+        queue.enqueue(uuid, Dimension::Nether, ChunkPosition::new(4, -2), 1);
+        assert_eq!(queue.pending(&uuid), 9);
+        assert_eq!(queue.players(), vec![(uuid, Dimension::Nether)]);
+        assert_eq!(queue.pop(&uuid), Some(ChunkPosition::new(4, -2)));
+        assert_eq!(queue.pending(&uuid), 8);
+
+        while queue.pop(&uuid).is_some() {}
+        assert!(queue.players().is_empty());
+        assert_eq!(queue.peek(&uuid), None);
+
+        // A wider view queues only the ring that came into sight
+        let view = |x, radius| ChunkView {
+            dimension: Dimension::Overworld,
+            center: ChunkPosition::new(x, 0),
+            radius,
+        };
+        queue.remove(&uuid);
+        assert!(queue.follow(uuid, view(0, 1)).is_empty());
+        assert!(queue.players().is_empty());
+        assert!(queue.follow(uuid, view(0, 2)).is_empty());
+        assert_eq!(queue.pending(&uuid), 16);
+        assert!(queue.follow(uuid, view(0, 2)).is_empty());
+        assert_eq!(queue.pending(&uuid), 16);
+        while queue.pop(&uuid).is_some() {}
+
+        // Moving a chunk east forgets the west column and queues the east one
+        let forgotten = queue.follow(uuid, view(1, 2));
+        assert_eq!(forgotten.len(), 5);
+        assert!(forgotten.iter().all(|p| p.x == -2));
+        assert_eq!(queue.pending(&uuid), 5);
+        assert_eq!(queue.peek(&uuid), Some(ChunkPosition::new(3, 0)));
+
+        // Chunks still pending are dropped rather than forgotten
+        let forgotten = queue.follow(uuid, view(-1, 2));
+        assert_eq!(forgotten.len(), 5);
+        assert!(forgotten.iter().all(|p| p.x == 2));
+        assert_eq!(queue.pending(&uuid), 10);
+        assert_eq!(queue.follow(uuid, view(-1, 1)).len(), 16 - 7);
+        assert_eq!(queue.pending(&uuid), 3);
+
+        // A view in another dimension queues all of its chunks
+        let nether = ChunkView {
+            dimension: Dimension::Nether,
+            ..view(0, 1)
+        };
+        assert!(queue.follow(uuid, nether).is_empty());
+        assert_eq!(queue.players(), vec![(uuid, Dimension::Nether)]);
+        assert_eq!(queue.pending(&uuid), 9);
+
+        let budgets = ChunkBudgets {
+            max_loaded: 0,
+            ..ChunkBudgets::default()
+        };
+        assert_eq!(budgets.max_loaded(), None);
+        assert_eq!(budgets.queued_per_player(), Some(256));
+    }
+}
@@ -3,6 +3,8 @@
 //! This module handles individual chunks and their block data.
 
 use super::ChunkPosition;
+use super::portal::NETHERRACK;
+use crate::protocol::nbt::Compound;
 
 /// Chunk size constants
 pub const CHUNK_SIZE: usize = 16;
@@ -14,13 +16,18 @@ pub const CHUNK_MIN_Y: i32 = -64;
 pub const CHUNK_MAX_Y: i32 = 319;
 
 /// Represents a single chunk in the world
+#[derive(Clone)]
 pub struct Chunk {
     /// Chunk position
     position: ChunkPosition,
     /// Block data [y][z][x]
     blocks: Vec<Vec<Vec<u32>>>,
+    /// NBT of the entities saved with the chunk
+    entities: Vec<Compound>,
     /// Whether the chunk has been modified
     modified: bool,
+    /// Number of changes to blocks or entities since the chunk was loaded
+    revision: u64,
 }
 
 impl Chunk {
@@ -39,7 +46,9 @@ impl Chunk {
         Self {
             position,
             blocks,
+            entities: Vec::new(),
             modified: false,
+            revision: 0,
         }
     }
 
@@ -72,6 +81,25 @@ impl Chunk {
         chunk
     }
 
+    /// Generate a nether chunk: a cavern between a floor and a ceiling of
+    /// netherrack, sealed with bedrock
+    pub fn generate_nether(position: ChunkPosition) -> Self {
+        let mut chunk = Self::new(position);
+
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.set_block(x, 0, z, 7); // Bedrock
+                for y in (1..41).chain(100..127) {
+                    chunk.set_block(x, y, z, NETHERRACK);
+                }
+                chunk.set_block(x, 127, z, 7); // Bedrock
+            }
+        }
+
+        chunk.modified = false;
+        chunk
+    }
+
     /// Get the chunk position
     pub fn position(&self) -> ChunkPosition {
         self.position
@@ -94,6 +122,7 @@ impl Chunk {
 
         self.blocks[y][z][x] = block_id;
         self.modified = true;
+        self.revision += 1;
         true
     }
 
@@ -102,6 +131,25 @@ impl Chunk {
         self.modified
     }
 
+    /// Number of changes to blocks or entities since the chunk was loaded
+    ///
+    /// Anything derived from the chunk is outdated once this changes.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// NBT of the entities saved with the chunk
+    pub fn entities(&self) -> &[Compound] {
+        &self.entities
+    }
+
+    /// Replace the entities saved with the chunk
+    pub fn set_entities(&mut self, entities: Vec<Compound>) {
+        self.entities = entities;
+        self.modified = true;
+        self.revision += 1;
+    }
+
     /// Mark the chunk as saved (clears modified flag)
     pub fn mark_saved(&mut self) {
         self.modified = false;
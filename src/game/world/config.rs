@@ -0,0 +1,227 @@
+//! Per-world configuration
+//!
+//! A `world.toml` in the world directory overrides server-wide settings for
+//! that world:
+//!
+//! ```toml
+//! seed = "obsidium"          # a number, or text hashed like vanilla's level-seed
+//! generator = "minecraft:flat"
+//! difficulty = "hard"
+//!
+//! [spawn]
+//! x = 0
+//! y = 64
+//! z = 0
+//!
+//! [gamerules]
+//! playersSleepingPercentage = 50
+//! randomTickSpeed = 3
+//! doDaylightCycle = true
+//! spawnChunkRadius = 2        # chunks kept loaded around spawn, 0 keeps none
+//! ```
+//!
+//! The seed only affects worlds created with the file in place, since an
+//! existing world keeps the seed in its level.dat. The other settings are
+//! applied every time the world is loaded and take precedence over
+//! level.dat and server.properties.
+
+use super::World;
+use super::spawn::MAX_SPAWN_CHUNK_RADIUS;
+use crate::error::{Result, ServerError};
+use crate::game::{Difficulty, LevelType};
+use crate::protocol::types::Position;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Name of the per-world configuration file
+pub const WORLD_CONFIG_FILE: &str = "world.toml";
+
+/// A world seed, given as a number or as text
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Seed {
+    /// Numeric seed, used as is
+    Number(i64),
+    /// Text seed, parsed as a number if possible and hashed otherwise
+    Text(String),
+}
+
+impl Seed {
+    /// Resolve the numeric seed, as vanilla does for `level-seed`
+    pub fn value(&self) -> i64 {
+        match self {
+            Seed::Number(seed) => *seed,
+            Seed::Text(text) => text
+                .parse()
+                .unwrap_or_else(|_| i64::from(java_string_hash(text))),
+        }
+    }
+}
+
+/// `[spawn]` section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnSection {
+    /// Block X coordinate
+    pub x: i32,
+    /// Block Y coordinate
+    pub y: i32,
+    /// Block Z coordinate
+    pub z: i32,
+}
+
+/// `[gamerules]` section, using vanilla's rule names
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GameRulesSection {
+    /// Percentage of players that must sleep to skip the night
+    pub players_sleeping_percentage: Option<u32>,
+    /// Blocks per chunk section that receive a random tick each tick
+    pub random_tick_speed: Option<u32>,
+    /// Whether the time of day advances
+    pub do_daylight_cycle: Option<bool>,
+    /// Chunks kept loaded around the spawn chunk
+    pub spawn_chunk_radius: Option<u32>,
+}
+
+/// Settings read from a world's world.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorldConfig {
+    /// Seed for a newly created world
+    pub seed: Option<Seed>,
+    /// World preset
+    pub generator: Option<String>,
+    /// World difficulty
+    pub difficulty: Option<String>,
+    /// World spawn position
+    pub spawn: Option<SpawnSection>,
+    /// Game rules
+    #[serde(default)]
+    pub gamerules: GameRulesSection,
+}
+
+impl WorldConfig {
+    /// Parse and validate world.toml contents
+    pub fn parse(contents: &str) -> Result<Self> {
+        let config: Self =
+            toml::from_str(contents).map_err(|e| ServerError::Configuration(e.to_string()))?;
+
+        // Surface typos now rather than when the world is loaded
+        config.generator()?;
+        config.difficulty()?;
+        if config
+            .gamerules
+            .spawn_chunk_radius
+            .is_some_and(|radius| radius > MAX_SPAWN_CHUNK_RADIUS)
+        {
+            return Err(ServerError::Configuration(format!(
+                "[gamerules]: spawnChunkRadius must be at most {}",
+                MAX_SPAWN_CHUNK_RADIUS
+            )));
+        }
+        Ok(config)
+    }
+
+    /// Load the configuration of the world in `directory`, if it has one
+    pub fn load<P: AsRef<Path>>(directory: P) -> Result<Option<Self>> {
+        let path = directory.as_ref().join(WORLD_CONFIG_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents).map(Some).map_err(|e| match e {
+                ServerError::Configuration(message) => {
+                    ServerError::Configuration(format!("Invalid {}: {}", path.display(), message))
+                }
+                e => e,
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Parsed world preset
+    pub fn generator(&self) -> Result<Option<LevelType>> {
+        self.generator.as_deref().map(str::parse).transpose()
+    }
+
+    /// Parsed difficulty
+    pub fn difficulty(&self) -> Result<Option<Difficulty>> {
+        self.difficulty.as_deref().map(str::parse).transpose()
+    }
+
+    /// Apply the settings that take effect on every load
+    ///
+    /// Call this after level.dat has been restored onto the world.
+    pub fn apply_to(&self, world: &mut World) -> Result<()> {
+        if let Some(ref seed) = self.seed {
+            if seed.value() != world.seed() {
+                tracing::warn!(
+                    "The seed in {} only applies to new worlds; {} keeps seed {}",
+                    WORLD_CONFIG_FILE,
+                    world.name(),
+                    world.seed()
+                );
+            }
+        }
+        if let Some(generator) = self.generator()? {
+            world.set_generator(generator);
+        }
+        if let Some(difficulty) = self.difficulty()? {
+            world.set_difficulty(difficulty);
+        }
+        if let Some(spawn) = self.spawn {
+            world.set_spawn_position(Position::new(spawn.x, spawn.y, spawn.z));
+        }
+        if let Some(percentage) = self.gamerules.players_sleeping_percentage {
+            world.game_rules_mut().players_sleeping_percentage = percentage;
+        }
+        if let Some(speed) = self.gamerules.random_tick_speed {
+            world.game_rules_mut().random_tick_speed = speed;
+        }
+        if let Some(cycle) = self.gamerules.do_daylight_cycle {
+            world.game_rules_mut().do_daylight_cycle = cycle;
+        }
+        if let Some(radius) = self.gamerules.spawn_chunk_radius {
+            world.game_rules_mut().spawn_chunk_radius = radius;
+        }
+        Ok(())
+    }
+}
+
+/// Hash text the way Java's `String.hashCode` does
+fn java_string_hash(text: &str) -> i32 {
+    text.encode_utf16().fold(0i32, |hash, unit| {
+        hash.wrapping_mul(31).wrapping_add(i32::from(unit))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_apply() {
+        let config = WorldConfig::parse(
+            "seed = \"obsidium\"\ngenerator = \"flat\"\ndifficulty = \"hard\"\n\n\
+             [spawn]\nx = 10\ny = 70\nz = -5\n\n\
+             [gamerules]\nplayersSleepingPercentage = 50\nrandomTickSpeed = 10\n\
+             doDaylightCycle = false\nspawnChunkRadius = 0\n",
+        )
+        .unwrap();
+        assert_eq!(config.seed.as_ref().unwrap().value(), 351_872_198);
+        assert_eq!(Seed::Text("-42".to_string()).value(), -42);
+
+        let mut world = World::new("test".to_string(), 0);
+        config.apply_to(&mut world).unwrap();
+        assert_eq!(world.generator(), LevelType::Flat);
+        assert_eq!(world.difficulty(), Difficulty::Hard);
+        assert_eq!(world.spawn_position(), Position::new(10, 70, -5));
+        assert_eq!(world.game_rules().players_sleeping_percentage, 50);
+        assert_eq!(world.game_rules().random_tick_speed, 10);
+        assert!(!world.game_rules().do_daylight_cycle);
+        assert_eq!(world.game_rules().spawn_chunk_radius, 0);
+
+        assert!(WorldConfig::parse("difficulty = \"brutal\"").is_err());
+        assert!(WorldConfig::parse("[gamerules]\nkeepInventory = true").is_err());
+        assert!(WorldConfig::parse("[gamerules]\nspawnChunkRadius = 33").is_err());
+    }
+}
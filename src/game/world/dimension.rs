@@ -0,0 +1,91 @@
+//! Dimensions
+//!
+//! The overworld and the nether are separate [`World`](super::World)s. A
+//! block in the nether stands for eight blocks in the overworld, so nether
+//! portals connect places far apart in the overworld.
+
+/// A dimension a world belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Dimension {
+    /// The overworld
+    #[default]
+    Overworld,
+    /// The nether
+    Nether,
+}
+
+impl Dimension {
+    /// Get the namespaced identifier of this dimension
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dimension::Overworld => "minecraft:overworld",
+            Dimension::Nether => "minecraft:the_nether",
+        }
+    }
+
+    /// Parse a dimension from its identifier, with or without the
+    /// `minecraft:` namespace
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.strip_prefix("minecraft:").unwrap_or(name) {
+            "overworld" => Some(Dimension::Overworld),
+            "the_nether" => Some(Dimension::Nether),
+            _ => None,
+        }
+    }
+
+    /// ID of the dimension's type in the `minecraft:dimension_type`
+    /// registry, as ordered by the vanilla data pack
+    pub fn type_id(&self) -> i32 {
+        match self {
+            Dimension::Overworld => 0,
+            Dimension::Nether => 3,
+        }
+    }
+
+    /// Directory holding the dimension's chunks inside the world directory,
+    /// as vanilla lays them out; the overworld's are at the top level
+    pub fn directory(&self) -> Option<&'static str> {
+        match self {
+            Dimension::Overworld => None,
+            Dimension::Nether => Some("DIM-1"),
+        }
+    }
+
+    /// Overworld blocks one block of this dimension stands for
+    pub fn coordinate_scale(&self) -> f64 {
+        match self {
+            Dimension::Overworld => 1.0,
+            Dimension::Nether => 8.0,
+        }
+    }
+
+    /// Dimension a nether portal in this dimension leads to
+    pub fn portal_destination(&self) -> Self {
+        match self {
+            Dimension::Overworld => Dimension::Nether,
+            Dimension::Nether => Dimension::Overworld,
+        }
+    }
+
+    /// Convert horizontal coordinates in this dimension to the matching
+    /// coordinates in another
+    pub fn scale_to(&self, to: Dimension, x: f64, z: f64) -> (f64, f64) {
+        let factor = self.coordinate_scale() / to.coordinate_scale();
+        (x * factor, z * factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_to() {
+        let overworld = Dimension::Overworld;
+        let nether = Dimension::Nether;
+        assert_eq!(overworld.scale_to(nether, 800.0, -84.0), (100.0, -10.5));
+        assert_eq!(nether.scale_to(overworld, 100.0, -10.5), (800.0, -84.0));
+        assert_eq!(overworld.portal_destination(), nether);
+        assert_eq!(Dimension::from_name("the_nether"), Some(nether));
+    }
+}
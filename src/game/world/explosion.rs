@@ -0,0 +1,327 @@
+//! Explosions
+//!
+//! Like vanilla, an explosion casts rays from its center towards every
+//! point on the surface of a 16×16×16 cube. Each ray starts with an
+//! intensity of 0.7 to 1.3 times the power and loses some of it every
+//! 0.3 blocks, more in blocks with a high blast resistance. Blocks a ray
+//! reaches with intensity left are destroyed.
+//!
+//! Entities within twice the power in blocks are hurt and pushed away. The
+//! impact falls off with distance and with how much of the entity is
+//! hidden from the center behind solid blocks.
+
+use super::World;
+use super::registry::BlockRegistry;
+use super::simulation::TickRandom;
+use crate::game::entity::explosive::{ExplosiveEntity, ExplosiveKind};
+use crate::game::entity::{EntityId, EntityPosition};
+use crate::protocol::types::Position;
+
+/// Rays cast along each edge of the cube
+const RAY_GRID: u32 = 16;
+
+/// Distance a ray advances per step
+const RAY_STEP: f64 = 0.3;
+
+/// Intensity a ray loses per step, besides the resistance of its block
+const STEP_DECAY: f32 = 0.225;
+
+/// Width of the box sampled for exposure, that of a player
+const SAMPLE_WIDTH: f64 = 0.6;
+
+/// Height of the box sampled for exposure, that of a player
+const SAMPLE_HEIGHT: f64 = 1.8;
+
+/// Eye height of a player, which knockback pushes away from the center
+const EYE_HEIGHT: f64 = 1.62;
+
+/// An explosion about to happen
+#[derive(Debug, Clone, Copy)]
+pub struct Explosion {
+    /// Center of the explosion
+    pub center: EntityPosition,
+    /// Power: 4 for TNT, 3 for creepers
+    pub power: f32,
+    /// Entity that exploded, if any
+    pub source: Option<EntityId>,
+    /// Whether a mob caused the explosion, scaling its damage with the
+    /// difficulty
+    pub by_mob: bool,
+}
+
+/// How an explosion affects an entity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Impact {
+    /// Health lost
+    pub damage: f32,
+    /// Velocity added, in blocks per tick
+    pub knockback: [f64; 3],
+}
+
+/// Result of an explosion in a world
+#[derive(Debug, Clone, Default)]
+pub struct ExplosionOutcome {
+    /// Blocks turned into air
+    pub destroyed: Vec<Position>,
+    /// Primed TNT spawned in place of destroyed TNT blocks
+    pub primed: Vec<EntityId>,
+}
+
+impl Explosion {
+    /// Create an explosion not caused by an entity
+    pub fn new(center: EntityPosition, power: f32) -> Self {
+        Self {
+            center,
+            power,
+            source: None,
+            by_mob: false,
+        }
+    }
+
+    /// Distance within which entities are affected
+    pub fn radius(&self) -> f64 {
+        f64::from(self.power) * 2.0
+    }
+
+    /// Find the blocks the explosion destroys
+    fn affected_blocks(
+        &self,
+        world: &World,
+        blocks: &BlockRegistry,
+        random: &mut TickRandom,
+    ) -> Vec<Position> {
+        let mut affected = Vec::new();
+        let last = RAY_GRID - 1;
+        for i in 0..RAY_GRID {
+            for j in 0..RAY_GRID {
+                for k in 0..RAY_GRID {
+                    if ![i, j, k].iter().any(|&n| n == 0 || n == last) {
+                        continue;
+                    }
+                    let direction = [i, j, k].map(|n| f64::from(n) / f64::from(last) * 2.0 - 1.0);
+                    self.cast_ray(world, blocks, direction, random, &mut affected);
+                }
+            }
+        }
+        affected.sort_by_key(|position| (position.x, position.y, position.z));
+        affected.dedup();
+        affected
+    }
+
+    /// Follow one ray until its intensity runs out
+    fn cast_ray(
+        &self,
+        world: &World,
+        blocks: &BlockRegistry,
+        direction: [f64; 3],
+        random: &mut TickRandom,
+        affected: &mut Vec<Position>,
+    ) {
+        let length = direction.iter().map(|d| d * d).sum::<f64>().sqrt();
+        let step = direction.map(|d| d / length * RAY_STEP);
+        let mut intensity = self.power * (0.7 + random.fraction() * 0.6);
+        let mut point = [self.center.x, self.center.y, self.center.z];
+        while intensity > 0.0 {
+            let position = block_at(point);
+            if let Some(block) = world.get_block(position).filter(|&block| block != 0) {
+                let resistance = blocks.get_block(block).map_or(0.0, |info| info.resistance);
+                intensity -= (resistance + 0.3) * 0.3;
+                if intensity > 0.0 {
+                    affected.push(position);
+                }
+            }
+            for (axis, delta) in point.iter_mut().zip(step) {
+                *axis += delta;
+            }
+            intensity -= STEP_DECAY;
+        }
+    }
+
+    /// Work out how the explosion affects a player standing at `position`
+    ///
+    /// Returns `None` for players out of reach.
+    pub fn impact(
+        &self,
+        world: &World,
+        blocks: &BlockRegistry,
+        position: EntityPosition,
+    ) -> Option<Impact> {
+        let radius = self.radius();
+        let offset = [
+            position.x - self.center.x,
+            position.y + EYE_HEIGHT - self.center.y,
+            position.z - self.center.z,
+        ];
+        let distance = offset.iter().map(|d| d * d).sum::<f64>().sqrt();
+        let feet_distance = {
+            let dy = position.y - self.center.y;
+            (offset[0] * offset[0] + dy * dy + offset[2] * offset[2]).sqrt()
+        };
+        if feet_distance / radius > 1.0 || distance == 0.0 {
+            return None;
+        }
+
+        let strength = (1.0 - feet_distance / radius) * self.exposure(world, blocks, position);
+        let damage = ((strength * strength + strength) / 2.0 * 7.0 * radius + 1.0) as f32;
+        Some(Impact {
+            damage,
+            knockback: offset.map(|d| d / distance * strength),
+        })
+    }
+
+    /// Share of a player's box in view of the center, from 0 to 1
+    fn exposure(&self, world: &World, blocks: &BlockRegistry, position: EntityPosition) -> f64 {
+        let (columns, rows) = (3, 5);
+        let mut visible = 0;
+        for x in 0..columns {
+            for y in 0..rows {
+                for z in 0..columns {
+                    let sample = [
+                        position.x + SAMPLE_WIDTH * (f64::from(x) / 2.0 - 0.5),
+                        position.y + SAMPLE_HEIGHT * f64::from(y) / 4.0,
+                        position.z + SAMPLE_WIDTH * (f64::from(z) / 2.0 - 0.5),
+                    ];
+                    if self.in_view(world, blocks, sample) {
+                        visible += 1;
+                    }
+                }
+            }
+        }
+        f64::from(visible) / f64::from(columns * rows * columns)
+    }
+
+    /// Whether no solid block lies between the center and a point
+    fn in_view(&self, world: &World, blocks: &BlockRegistry, point: [f64; 3]) -> bool {
+        let center = [self.center.x, self.center.y, self.center.z];
+        let delta = [0, 1, 2].map(|axis| point[axis] - center[axis]);
+        let length = delta.iter().map(|d| d * d).sum::<f64>().sqrt();
+        let steps = (length / RAY_STEP).ceil() as u32;
+        (1..steps).all(|step| {
+            let along = f64::from(step) / f64::from(steps);
+            let position = block_at([0, 1, 2].map(|axis| center[axis] + delta[axis] * along));
+            world
+                .get_block(position)
+                .and_then(|block| blocks.get_block(block))
+                .is_none_or(|info| !info.solid)
+        })
+    }
+}
+
+impl World {
+    /// Destroy the blocks an explosion reaches
+    ///
+    /// TNT blocks in reach are primed with a short fuse, setting off a chain
+    /// reaction.
+    pub fn explode(&mut self, explosion: &Explosion, blocks: &BlockRegistry) -> ExplosionOutcome {
+        let mut random = self.random.clone();
+        let affected = explosion.affected_blocks(self, blocks, &mut random);
+        self.random = random;
+
+        let tnt = blocks.get_block_id("minecraft:tnt");
+        let mut outcome = ExplosionOutcome::default();
+        for position in affected {
+            let block = self.get_block(position);
+            if self.set_block(position, 0) {
+                if block.is_some() && block == tnt {
+                    let center = EntityPosition {
+                        x: f64::from(position.x) + 0.5,
+                        y: f64::from(position.y),
+                        z: f64::from(position.z) + 0.5,
+                    };
+                    // Like vanilla, a quarter of the usual fuse at most
+                    let fuse = ExplosiveKind::Tnt.fuse() / 8
+                        + self.random.below(ExplosiveKind::Tnt.fuse() as usize / 4) as u32;
+                    outcome.primed.push(self.entities.add_entity(|id| {
+                        Box::new(
+                            ExplosiveEntity::new(id, ExplosiveKind::Tnt, center).with_fuse(fuse),
+                        )
+                    }));
+                }
+                outcome.destroyed.push(position);
+            }
+        }
+        outcome
+    }
+}
+
+/// The block containing a point
+fn block_at(point: [f64; 3]) -> Position {
+    Position::new(
+        point[0].floor() as i32,
+        point[1].floor() as i32,
+        point[2].floor() as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(world: &mut World, from: Position, to: Position, block: u32) {
+        for x in from.x..=to.x {
+            for y in from.y..=to.y {
+                for z in from.z..=to.z {
+                    world.set_block(Position::new(x, y, z), block);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_explosion_destroys_weak_blocks() {
+        let blocks = BlockRegistry::new();
+        let dirt = blocks.get_block_id("minecraft:dirt").unwrap();
+        let bedrock = blocks.get_block_id("minecraft:bedrock").unwrap();
+        let tnt = blocks.get_block_id("minecraft:tnt").unwrap();
+        let mut world = World::new("test".to_string(), 0);
+        fill(
+            &mut world,
+            Position::new(-6, 90, -6),
+            Position::new(6, 100, 6),
+            dirt,
+        );
+        world.set_block(Position::new(0, 94, 0), bedrock);
+        world.set_block(Position::new(-1, 95, 0), tnt);
+
+        let center = EntityPosition {
+            x: 0.5,
+            y: 95.5,
+            z: 0.5,
+        };
+        let outcome = world.explode(&Explosion::new(center, 4.0), &blocks);
+        assert!(outcome.destroyed.contains(&Position::new(0, 95, 0)));
+        assert!(outcome.destroyed.contains(&Position::new(2, 95, 0)));
+        assert!(!outcome.destroyed.contains(&Position::new(6, 95, 6)));
+        assert_eq!(world.get_block(Position::new(0, 94, 0)), Some(bedrock));
+        assert_eq!(world.get_block(Position::new(1, 95, 0)), Some(0));
+        assert_eq!(outcome.primed.len(), 1);
+        let primed = world.entities().get_entity(outcome.primed[0]).unwrap();
+        assert!(primed.as_explosive().unwrap().fuse() < Some(ExplosiveKind::Tnt.fuse() / 2));
+    }
+
+    #[test]
+    fn test_impact_falls_off_with_distance() {
+        let blocks = BlockRegistry::new();
+        let world = World::new("test".to_string(), 0);
+        let explosion = Explosion::new(
+            EntityPosition {
+                x: 0.0,
+                y: 200.0,
+                z: 0.0,
+            },
+            4.0,
+        );
+        let at = |x| EntityPosition {
+            x,
+            y: 200.0,
+            z: 0.0,
+        };
+
+        let near = explosion.impact(&world, &blocks, at(1.0)).unwrap();
+        let far = explosion.impact(&world, &blocks, at(6.0)).unwrap();
+        assert!(near.damage > far.damage);
+        assert!(near.knockback[0] > far.knockback[0]);
+        assert!(far.knockback[0] > 0.0);
+        assert!(explosion.impact(&world, &blocks, at(9.0)).is_none());
+    }
+}
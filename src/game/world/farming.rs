@@ -0,0 +1,285 @@
+//! Farming
+//!
+//! Crops grow one stage at a time on random ticks, faster on hydrated
+//! farmland. Farmland within four blocks of water, or out in the rain, is
+//! hydrated; otherwise it dries out step by step and finally turns back
+//! into dirt unless a crop grows on it. Landing on farmland after a fall
+//! may trample it, uprooting the crop on top, and bone meal makes a crop
+//! grow a few stages at once.
+//!
+//! Each moisture level and crop age has its own block ID, registered in
+//! the [`BlockRegistry`](super::registry::BlockRegistry) with its state in
+//! the name, such as `minecraft:wheat[age=7]`.
+
+use super::region::{BlockView, RegionTick};
+use super::simulation::TickRandom;
+use super::{BrokenBlock, World};
+use crate::game::loot::ItemDrop;
+use crate::protocol::types::Position;
+
+/// Block ID of dirt, as registered in the block registry
+const DIRT: u32 = 3;
+
+/// Block ID of dry farmland; wetter farmland follows up to [`MAX_MOISTURE`]
+pub const FARMLAND: u32 = 10;
+
+/// Moisture of hydrated farmland
+pub const MAX_MOISTURE: u32 = 7;
+
+/// Block ID of water
+pub const WATER: u32 = 18;
+
+/// Horizontal distance from which water hydrates farmland
+const HYDRATION_RANGE: i32 = 4;
+
+/// Chance of each of the three extra seeds or crops dropping from a
+/// mature crop
+const EXTRA_DROP_CHANCE: f32 = 0.571_428_6;
+
+/// Chance of a mature potato crop dropping a poisonous potato
+const POISONOUS_POTATO_CHANCE: f32 = 0.02;
+
+/// Crops that grow on farmland
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crop {
+    /// Wheat, grown from wheat seeds
+    Wheat,
+    /// Carrots
+    Carrots,
+    /// Potatoes
+    Potatoes,
+}
+
+impl Crop {
+    /// Every crop
+    pub const ALL: [Crop; 3] = [Crop::Wheat, Crop::Carrots, Crop::Potatoes];
+
+    /// Age of a fully grown crop
+    pub const MAX_AGE: u32 = 7;
+
+    /// Block name, without the age
+    pub fn name(&self) -> &'static str {
+        match self {
+            Crop::Wheat => "minecraft:wheat",
+            Crop::Carrots => "minecraft:carrots",
+            Crop::Potatoes => "minecraft:potatoes",
+        }
+    }
+
+    /// Block ID of the crop at an age
+    pub fn block(&self, age: u32) -> u32 {
+        let first = match self {
+            Crop::Wheat => 19,
+            Crop::Carrots => 27,
+            Crop::Potatoes => 35,
+        };
+        first + age.min(Self::MAX_AGE)
+    }
+
+    /// Find the crop and age a block ID stands for
+    pub fn from_block(block: u32) -> Option<(Crop, u32)> {
+        Self::ALL.into_iter().find_map(|crop| {
+            let age = block.checked_sub(crop.block(0))?;
+            (age <= Self::MAX_AGE).then_some((crop, age))
+        })
+    }
+
+    /// Roll the items the crop drops when broken
+    fn drops(&self, age: u32, random: &mut TickRandom) -> Vec<ItemDrop> {
+        let mature = age >= Self::MAX_AGE;
+        let extra = if mature {
+            (0..3)
+                .filter(|_| random.fraction() < EXTRA_DROP_CHANCE)
+                .count() as u32
+        } else {
+            0
+        };
+        match self {
+            Crop::Wheat if mature => vec![
+                ItemDrop::new("minecraft:wheat", 1),
+                ItemDrop::new("minecraft:wheat_seeds", 1 + extra),
+            ],
+            Crop::Wheat => vec![ItemDrop::new("minecraft:wheat_seeds", 1)],
+            Crop::Carrots => vec![ItemDrop::new("minecraft:carrot", 1 + extra)],
+            Crop::Potatoes => {
+                let mut drops = vec![ItemDrop::new("minecraft:potato", 1 + extra)];
+                if mature && random.fraction() < POISONOUS_POTATO_CHANCE {
+                    drops.push(ItemDrop::new("minecraft:poisonous_potato", 1));
+                }
+                drops
+            }
+        }
+    }
+}
+
+/// Moisture of a farmland block, or `None` for other blocks
+pub fn farmland_moisture(block: u32) -> Option<u32> {
+    block
+        .checked_sub(FARMLAND)
+        .filter(|&moisture| moisture <= MAX_MOISTURE)
+}
+
+/// Make farmland and crops react to random ticks
+pub(super) fn register(world: &mut World) {
+    for moisture in 0..=MAX_MOISTURE {
+        world.on_random_tick(FARMLAND + moisture, hydrate);
+    }
+    for crop in Crop::ALL {
+        for age in 0..Crop::MAX_AGE {
+            world.on_random_tick(crop.block(age), grow);
+        }
+    }
+}
+
+/// Grow a crop by a stage, if it is lucky
+///
+/// Like vanilla, a crop grows with a chance of one in 7 on hydrated and
+/// one in 13 on dry farmland. Crops not on farmland do not grow.
+fn grow(tick: &mut RegionTick<'_>, position: Position, block: u32) {
+    let Some((crop, age)) = Crop::from_block(block) else {
+        return;
+    };
+    let below = Position::new(position.x, position.y - 1, position.z);
+    let Some(moisture) = tick.get_block(below).and_then(farmland_moisture) else {
+        return;
+    };
+    let odds = if moisture > 0 { 7 } else { 13 };
+    if tick.random().below(odds) == 0 {
+        tick.set_block(position, crop.block(age + 1));
+    }
+}
+
+/// Hydrate farmland near water or in the rain, and dry it out elsewhere
+fn hydrate(tick: &mut RegionTick<'_>, position: Position, block: u32) {
+    let Some(moisture) = farmland_moisture(block) else {
+        return;
+    };
+    if tick.weather().is_raining() || near_water(tick, position) {
+        if moisture < MAX_MOISTURE {
+            tick.set_block(position, FARMLAND + MAX_MOISTURE);
+        }
+    } else if moisture > 0 {
+        tick.set_block(position, block - 1);
+    } else if !has_crop(tick, position) {
+        tick.set_block(position, DIRT);
+    }
+}
+
+/// Whether water is within reach of farmland, level with it or a block up
+fn near_water(blocks: &impl BlockView, farmland: Position) -> bool {
+    let range = -HYDRATION_RANGE..=HYDRATION_RANGE;
+    range.clone().any(|dx| {
+        range.clone().any(|dz| {
+            (0..=1).any(|dy| {
+                let position = Position::new(farmland.x + dx, farmland.y + dy, farmland.z + dz);
+                blocks.get_block(position) == Some(WATER)
+            })
+        })
+    })
+}
+
+/// Whether a crop grows on top of a block
+fn has_crop(blocks: &impl BlockView, position: Position) -> bool {
+    let above = Position::new(position.x, position.y + 1, position.z);
+    blocks.get_block(above).and_then(Crop::from_block).is_some()
+}
+
+/// Roll what a block drops when broken
+pub(super) fn drops(block: u32, random: &mut TickRandom) -> Vec<ItemDrop> {
+    Crop::from_block(block).map_or_else(Vec::new, |(crop, age)| crop.drops(age, random))
+}
+
+impl World {
+    /// Make a crop grow two to five stages, as bone meal does
+    ///
+    /// Returns `false` if there is no crop or it is fully grown.
+    pub fn apply_bone_meal(&mut self, position: Position) -> bool {
+        let Some((crop, age)) = self.get_block(position).and_then(Crop::from_block) else {
+            return false;
+        };
+        if age >= Crop::MAX_AGE {
+            return false;
+        }
+        let stages = 2 + self.random.below(4) as u32;
+        self.set_block(position, crop.block(age + stages))
+    }
+
+    /// Maybe trample the farmland at a position after a fall
+    ///
+    /// Like vanilla, the chance is the fall distance beyond half a block.
+    /// Trampled farmland turns into dirt and the crop on it is uprooted,
+    /// which is returned. Returns `None` if nothing was trampled.
+    pub fn trample(&mut self, position: Position, fall_distance: f32) -> Option<Vec<BrokenBlock>> {
+        self.get_block(position).and_then(farmland_moisture)?;
+        if self.random.fraction() >= fall_distance - 0.5 {
+            return None;
+        }
+        self.set_block(position, DIRT);
+        let above = Position::new(position.x, position.y + 1, position.z);
+        Some(if has_crop(self, position) {
+            self.break_block(above)
+        } else {
+            Vec::new()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::region::run_handler;
+    use crate::game::world::registry::BlockRegistry;
+
+    #[test]
+    fn test_crop_blocks_are_registered() {
+        let blocks = BlockRegistry::new();
+        assert_eq!(
+            blocks.get_block_id("minecraft:wheat[age=7]"),
+            Some(Crop::Wheat.block(7))
+        );
+        assert_eq!(
+            blocks.get_block_id("minecraft:farmland[moisture=0]"),
+            Some(FARMLAND)
+        );
+        assert_eq!(blocks.get_block_id("minecraft:dirt"), Some(DIRT));
+        assert_eq!(blocks.get_block_id("minecraft:water"), Some(WATER));
+        assert_eq!(
+            Crop::from_block(Crop::Potatoes.block(3)),
+            Some((Crop::Potatoes, 3))
+        );
+        assert_eq!(Crop::from_block(FARMLAND), None);
+    }
+
+    #[test]
+    fn test_farmland_and_crops() {
+        let mut world = World::new("test".to_string(), 0);
+        let farmland = Position::new(0, 100, 0);
+        let crop = Position::new(0, 101, 0);
+        world.set_block(farmland, FARMLAND + 1);
+        world.set_block(crop, Crop::Wheat.block(0));
+
+        // Dry farmland with a crop dries out but stays farmland
+        run_handler(&mut world, hydrate, farmland, FARMLAND + 1);
+        run_handler(&mut world, hydrate, farmland, FARMLAND);
+        assert_eq!(world.get_block(farmland), Some(FARMLAND));
+        world.set_block(Position::new(4, 100, -3), WATER);
+        run_handler(&mut world, hydrate, farmland, FARMLAND);
+        assert_eq!(world.get_block(farmland), Some(FARMLAND + MAX_MOISTURE));
+
+        for _ in 0..1000 {
+            let block = world.get_block(crop).unwrap();
+            run_handler(&mut world, grow, crop, block);
+        }
+        assert_eq!(
+            world.get_block(crop),
+            Some(Crop::Wheat.block(Crop::MAX_AGE))
+        );
+        assert!(!world.apply_bone_meal(crop));
+
+        assert_eq!(world.trample(farmland, 0.5), None);
+        let uprooted = world.trample(farmland, 2.0).unwrap();
+        assert_eq!(world.get_block(farmland), Some(DIRT));
+        assert_eq!(world.get_block(crop), Some(0));
+        assert_eq!(uprooted[0].drops[0], ItemDrop::new("minecraft:wheat", 1));
+    }
+}
@@ -0,0 +1,33 @@
+//! Game rules
+//!
+//! Per-world rules that tweak gameplay mechanics.
+
+use super::spawn::DEFAULT_SPAWN_CHUNK_RADIUS;
+
+/// Game rules of a world
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRules {
+    /// Percentage of players that must sleep to skip the night
+    ///
+    /// Values above 100 disable night skipping.
+    pub players_sleeping_percentage: u32,
+    /// Blocks per chunk section that receive a random tick each tick
+    pub random_tick_speed: u32,
+    /// Whether the time of day advances
+    pub do_daylight_cycle: bool,
+    /// Chunks kept loaded around the spawn chunk, at most
+    /// [`MAX_SPAWN_CHUNK_RADIUS`](super::spawn::MAX_SPAWN_CHUNK_RADIUS); 0
+    /// keeps none
+    pub spawn_chunk_radius: u32,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            players_sleeping_percentage: 100,
+            random_tick_speed: 3,
+            do_daylight_cycle: true,
+            spawn_chunk_radius: DEFAULT_SPAWN_CHUNK_RADIUS,
+        }
+    }
+}
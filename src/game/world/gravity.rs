@@ -0,0 +1,151 @@
+//! Blocks that fall
+//!
+//! Sand, gravel and anvils with air or water below them start falling the
+//! tick after a block next to them changes, turning into a
+//! [`FallingBlockEntity`]. A falling block that falls into another block
+//! lands on top of it, becoming a block again, or drops as an item if
+//! something already took its place. Like leaf decay, a column of sand
+//! collapses one block per tick.
+
+use super::World;
+use super::farming::WATER;
+use crate::game::entity::Entity;
+use crate::game::entity::falling_block::FallingBlockEntity;
+use crate::game::loot::ItemDrop;
+use crate::protocol::types::Position;
+
+/// Block ID of sand
+pub const SAND: u32 = 191;
+
+/// Block ID of gravel
+pub const GRAVEL: u32 = 192;
+
+/// Block ID of an anvil
+pub const ANVIL: u32 = 193;
+
+/// Whether a block falls when nothing holds it up
+pub fn falls(block: u32) -> bool {
+    matches!(block, SAND | GRAVEL | ANVIL)
+}
+
+/// Whether falling blocks fall through a block
+pub fn is_passable(block: u32) -> bool {
+    block == 0 || block == WATER
+}
+
+/// Item a falling block drops when it cannot land
+fn item(block: u32) -> Option<&'static str> {
+    match block {
+        SAND => Some("minecraft:sand"),
+        GRAVEL => Some("minecraft:gravel"),
+        ANVIL => Some("minecraft:anvil"),
+        _ => None,
+    }
+}
+
+/// Start the blocks that changed, and those above them, falling if nothing
+/// holds them up any more
+pub(super) fn update_falling(world: &mut World, changed: &[Position]) {
+    let mut to_check: Vec<Position> = changed
+        .iter()
+        .flat_map(|&position| {
+            [
+                position,
+                Position::new(position.x, position.y + 1, position.z),
+            ]
+        })
+        .collect();
+    to_check.sort_by_key(|position| (position.x, position.y, position.z));
+    to_check.dedup();
+    let unsupported: Vec<(Position, u32)> = to_check
+        .into_iter()
+        .filter_map(|position| {
+            let block = world.get_block(position).filter(|&block| falls(block))?;
+            let below = Position::new(position.x, position.y - 1, position.z);
+            world
+                .get_block(below)
+                .is_some_and(is_passable)
+                .then_some((position, block))
+        })
+        .collect();
+    // Blocks resting on these only notice on the next tick
+    for (position, block) in unsupported {
+        world.set_block(position, 0);
+        let entity_id = world
+            .entities
+            .add_entity(|id| Box::new(FallingBlockEntity::new(id, block, position)));
+        world.spawned.push(entity_id);
+    }
+}
+
+/// Turn falling blocks that fell into another block back into blocks on
+/// top of it
+pub(super) fn land_falling_blocks(world: &mut World) {
+    let landed: Vec<_> = world
+        .entities
+        .entities()
+        .filter_map(|entity| entity.as_falling_block())
+        .filter(|falling| {
+            world
+                .get_block(falling.block_position())
+                .is_some_and(|block| !is_passable(block))
+        })
+        .map(|falling| {
+            let inside = falling.block_position();
+            let on_top = Position::new(inside.x, inside.y + 1, inside.z);
+            (falling.entity_id(), falling.block(), on_top)
+        })
+        .collect();
+    for (entity_id, block, position) in landed {
+        world.entities.remove_entity(entity_id);
+        world.despawned.push(entity_id);
+        if world.get_block(position).is_some_and(is_passable) {
+            world.set_block(position, block);
+            if block == ANVIL {
+                world.play_sound(position, "minecraft:block.anvil.land", 1.0);
+            }
+        } else if let Some(item) = item(block) {
+            world.drop_items(position, vec![ItemDrop::new(item, 1)]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::ChunkPosition;
+    use crate::game::world::simulation::SimulationArea;
+
+    #[test]
+    fn test_sand_falls_and_lands() {
+        let mut world = World::new("test".to_string(), 0);
+        let mut area = SimulationArea::new(2);
+        area.add_player(ChunkPosition::new(0, 0));
+        let floor = 100;
+        world.set_block(Position::new(0, floor, 0), 1);
+        for y in floor + 1..floor + 20 {
+            world.set_block(Position::new(0, y, 0), 0);
+        }
+        world.set_block(Position::new(0, floor + 10, 0), SAND);
+        world.set_block(Position::new(0, floor + 11, 0), GRAVEL);
+        world.update(0.05, &area);
+        assert_eq!(world.get_block(Position::new(0, floor + 10, 0)), Some(0));
+        assert_eq!(
+            world.get_block(Position::new(0, floor + 11, 0)),
+            Some(GRAVEL)
+        );
+        assert_eq!(world.take_spawned().len(), 1);
+
+        for _ in 0..100 {
+            world.update(0.05, &area);
+        }
+        assert_eq!(world.get_block(Position::new(0, floor + 1, 0)), Some(SAND));
+        assert_eq!(
+            world.get_block(Position::new(0, floor + 2, 0)),
+            Some(GRAVEL)
+        );
+        assert_eq!(world.entities().entities().count(), 0);
+        assert_eq!(world.take_spawned().len(), 1);
+        assert_eq!(world.take_despawned().len(), 2);
+    }
+}
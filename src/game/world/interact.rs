@@ -0,0 +1,472 @@
+//! Interactive blocks
+//!
+//! Doors and trapdoors open and close when used, levers flip and buttons
+//! stay pressed for a moment. Levers and buttons that are on emit redstone
+//! power into the blocks right next to them, which opens doors and
+//! trapdoors there; longer redstone circuits are not simulated. Door hinges
+//! are not modelled, so every door hinges on its left.
+//!
+//! Each combination of properties has its own block ID, registered in the
+//! [`BlockRegistry`](super::registry::BlockRegistry) with its state in the
+//! name, such as `minecraft:lever[face=wall,facing=north,powered=true]`.
+//! The registry also holds the [`use_block`] handler of every state.
+
+use super::{Direction, World};
+use crate::protocol::types::Position;
+
+/// Redstone power of a lever or button that is on
+pub const MAX_POWER: u8 = 15;
+
+/// Property bit of a powered block
+const POWERED: u32 = 1;
+
+/// Property bit of an open door or trapdoor
+const OPEN: u32 = 2;
+
+/// Facings in the order their states are numbered
+const FACINGS: [Direction; 4] = [
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
+/// Kinds of interactive blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interactive {
+    /// Oak door, two blocks tall
+    OakDoor,
+    /// Oak trapdoor
+    OakTrapdoor,
+    /// Lever
+    Lever,
+    /// Stone button
+    StoneButton,
+    /// Oak button
+    OakButton,
+}
+
+impl Interactive {
+    /// Every kind of interactive block
+    pub const ALL: [Interactive; 5] = [
+        Interactive::OakDoor,
+        Interactive::OakTrapdoor,
+        Interactive::Lever,
+        Interactive::StoneButton,
+        Interactive::OakButton,
+    ];
+
+    /// Block ID of the first state; the other states follow
+    pub fn first_block(self) -> u32 {
+        match self {
+            Interactive::OakDoor => 51,
+            Interactive::OakTrapdoor => 83,
+            Interactive::Lever => 115,
+            Interactive::StoneButton => 139,
+            Interactive::OakButton => 163,
+        }
+    }
+
+    /// Number of states
+    pub fn state_count(self) -> u32 {
+        if self.is_openable() { 32 } else { 24 }
+    }
+
+    /// Block IDs of every state
+    pub fn blocks(self) -> std::ops::Range<u32> {
+        self.first_block()..self.first_block() + self.state_count()
+    }
+
+    /// Block name, without the state
+    pub fn name(self) -> &'static str {
+        match self {
+            Interactive::OakDoor => "minecraft:oak_door",
+            Interactive::OakTrapdoor => "minecraft:oak_trapdoor",
+            Interactive::Lever => "minecraft:lever",
+            Interactive::StoneButton => "minecraft:stone_button",
+            Interactive::OakButton => "minecraft:oak_button",
+        }
+    }
+
+    /// Hardness and explosion resistance
+    pub fn hardness(self) -> f32 {
+        if self.is_openable() { 3.0 } else { 0.5 }
+    }
+
+    /// Whether the block is a door or trapdoor
+    fn is_openable(self) -> bool {
+        matches!(self, Interactive::OakDoor | Interactive::OakTrapdoor)
+    }
+
+    /// Ticks a button stays pressed
+    fn press_ticks(self) -> u32 {
+        match self {
+            Interactive::OakButton => 30,
+            _ => 20,
+        }
+    }
+
+    /// Sounds of opening and closing, or switching on and off
+    fn sounds(self) -> (&'static str, &'static str) {
+        match self {
+            Interactive::OakDoor => (
+                "minecraft:block.wooden_door.open",
+                "minecraft:block.wooden_door.close",
+            ),
+            Interactive::OakTrapdoor => (
+                "minecraft:block.wooden_trapdoor.open",
+                "minecraft:block.wooden_trapdoor.close",
+            ),
+            Interactive::Lever => ("minecraft:block.lever.click", "minecraft:block.lever.click"),
+            Interactive::StoneButton => (
+                "minecraft:block.stone_button.click_on",
+                "minecraft:block.stone_button.click_off",
+            ),
+            Interactive::OakButton => (
+                "minecraft:block.wooden_button.click_on",
+                "minecraft:block.wooden_button.click_off",
+            ),
+        }
+    }
+}
+
+/// Surface a lever or button is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    /// On top of the block below
+    Floor,
+    /// On the side of the block behind
+    Wall,
+    /// Under the block above
+    Ceiling,
+}
+
+impl Face {
+    /// Every face, in the order their states are numbered
+    const ALL: [Face; 3] = [Face::Floor, Face::Wall, Face::Ceiling];
+
+    /// Name of the face in block states
+    fn name(self) -> &'static str {
+        match self {
+            Face::Floor => "floor",
+            Face::Wall => "wall",
+            Face::Ceiling => "ceiling",
+        }
+    }
+}
+
+/// State of an interactive block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InteractiveState {
+    /// Kind of block
+    pub kind: Interactive,
+    /// Direction the block faces
+    pub facing: Direction,
+    /// Whether a door is the upper half or a trapdoor sits at the top of
+    /// its block
+    pub upper: bool,
+    /// Surface a lever or button is attached to
+    pub face: Face,
+    /// Whether a door or trapdoor is open
+    pub open: bool,
+    /// Whether the block is powered, or a lever or button is on
+    pub powered: bool,
+}
+
+impl InteractiveState {
+    /// Decode a block ID, or `None` for other blocks
+    pub fn from_block(block: u32) -> Option<Self> {
+        let kind = Interactive::ALL
+            .into_iter()
+            .find(|kind| kind.blocks().contains(&block))?;
+        let offset = block - kind.first_block();
+        let powered = offset & POWERED != 0;
+        let state = if kind.is_openable() {
+            InteractiveState {
+                kind,
+                facing: FACINGS[(offset / 8) as usize],
+                upper: offset & 4 != 0,
+                face: Face::Wall,
+                open: offset & OPEN != 0,
+                powered,
+            }
+        } else {
+            InteractiveState {
+                kind,
+                facing: FACINGS[(offset / 6) as usize],
+                upper: false,
+                face: Face::ALL[(offset % 6 / 2) as usize],
+                open: false,
+                powered,
+            }
+        };
+        Some(state)
+    }
+
+    /// Block ID of the state
+    pub fn block(&self) -> u32 {
+        let facing = FACINGS
+            .iter()
+            .position(|&facing| facing == self.facing)
+            .unwrap_or(0) as u32;
+        let powered = if self.powered { POWERED } else { 0 };
+        let offset = if self.kind.is_openable() {
+            let open = if self.open { OPEN } else { 0 };
+            facing * 8 + u32::from(self.upper) * 4 + open + powered
+        } else {
+            let face = Face::ALL
+                .iter()
+                .position(|&face| face == self.face)
+                .unwrap_or(0) as u32;
+            facing * 6 + face * 2 + powered
+        };
+        self.kind.first_block() + offset
+    }
+
+    /// Block name with the state, as registered in the block registry
+    pub fn name(&self) -> String {
+        let facing = match self.facing {
+            Direction::North => "north",
+            Direction::South => "south",
+            Direction::West => "west",
+            Direction::East => "east",
+        };
+        match self.kind {
+            Interactive::OakDoor | Interactive::OakTrapdoor => {
+                let half = match (self.kind, self.upper) {
+                    (Interactive::OakDoor, true) => "upper",
+                    (Interactive::OakDoor, false) => "lower",
+                    (_, true) => "top",
+                    (_, false) => "bottom",
+                };
+                format!(
+                    "{}[facing={},half={},open={},powered={}]",
+                    self.kind.name(),
+                    facing,
+                    half,
+                    self.open,
+                    self.powered
+                )
+            }
+            _ => format!(
+                "{}[face={},facing={},powered={}]",
+                self.kind.name(),
+                self.face.name(),
+                facing,
+                self.powered
+            ),
+        }
+    }
+}
+
+/// Redstone power a block emits into the blocks next to it
+pub fn power(block: u32) -> u8 {
+    match InteractiveState::from_block(block) {
+        Some(state) if !state.kind.is_openable() && state.powered => MAX_POWER,
+        _ => 0,
+    }
+}
+
+/// Use an interactive block, as a player right-clicking it
+///
+/// Returns whether the block reacted.
+pub fn use_block(world: &mut World, position: Position, block: u32) -> bool {
+    let Some(mut state) = InteractiveState::from_block(block) else {
+        return false;
+    };
+    let (on, off) = state.kind.sounds();
+    match state.kind {
+        Interactive::OakDoor | Interactive::OakTrapdoor => {
+            state.open = !state.open;
+            set_openable(world, position, state);
+            world.play_sound(position, if state.open { on } else { off }, 1.0);
+        }
+        Interactive::Lever => {
+            state.powered = !state.powered;
+            world.set_block(position, state.block());
+            world.play_sound(position, on, if state.powered { 0.6 } else { 0.5 });
+        }
+        Interactive::StoneButton | Interactive::OakButton => {
+            if !state.powered {
+                state.powered = true;
+                world.set_block(position, state.block());
+                world.play_sound(position, on, 0.6);
+                world.schedule_tick(position, state.kind.press_ticks(), release_button);
+            }
+        }
+    }
+    true
+}
+
+/// Release a button once its time is up
+fn release_button(world: &mut World, position: Position, block: u32) {
+    let Some(mut state) = InteractiveState::from_block(block).filter(|state| state.powered) else {
+        return;
+    };
+    state.powered = false;
+    world.set_block(position, state.block());
+    world.play_sound(position, state.kind.sounds().1, 0.5);
+}
+
+/// Set a door or trapdoor, changing both halves of a door
+fn set_openable(world: &mut World, position: Position, state: InteractiveState) {
+    world.set_block(position, state.block());
+    if state.kind != Interactive::OakDoor {
+        return;
+    }
+    let dy = if state.upper { -1 } else { 1 };
+    let other = Position::new(position.x, position.y + dy, position.z);
+    let other_state = world
+        .get_block(other)
+        .and_then(InteractiveState::from_block)
+        .filter(|other| other.kind == Interactive::OakDoor && other.upper != state.upper);
+    if let Some(other_state) = other_state {
+        world.set_block(
+            other,
+            InteractiveState {
+                upper: other_state.upper,
+                ..state
+            }
+            .block(),
+        );
+    }
+}
+
+/// Whether a block next to a position emits power
+fn receives_power(world: &World, position: Position) -> bool {
+    neighbours(position).any(|neighbour| world.get_block(neighbour).is_some_and(|b| power(b) > 0))
+}
+
+/// The six blocks sharing a face with a position
+fn neighbours(position: Position) -> impl Iterator<Item = Position> {
+    [
+        (1, 0, 0),
+        (-1, 0, 0),
+        (0, 1, 0),
+        (0, -1, 0),
+        (0, 0, 1),
+        (0, 0, -1),
+    ]
+    .into_iter()
+    .map(move |(dx, dy, dz)| Position::new(position.x + dx, position.y + dy, position.z + dz))
+}
+
+/// Open or close doors and trapdoors next to blocks that changed whose
+/// power changed
+pub(super) fn update_power(world: &mut World, changed: &[Position]) {
+    let mut to_update: Vec<Position> = changed.iter().copied().flat_map(neighbours).collect();
+    to_update.sort_by_key(|position| (position.x, position.y, position.z));
+    to_update.dedup();
+    for position in to_update {
+        let Some(mut state) = world
+            .get_block(position)
+            .and_then(InteractiveState::from_block)
+            .filter(|state| state.kind.is_openable())
+        else {
+            continue;
+        };
+        let mut powered = receives_power(world, position);
+        if state.kind == Interactive::OakDoor {
+            let dy = if state.upper { -1 } else { 1 };
+            powered |= receives_power(
+                world,
+                Position::new(position.x, position.y + dy, position.z),
+            );
+        }
+        if powered == state.powered {
+            continue;
+        }
+        let was_open = state.open;
+        state.powered = powered;
+        state.open = powered;
+        set_openable(world, position, state);
+        if was_open != state.open {
+            let (open, close) = state.kind.sounds();
+            world.play_sound(position, if state.open { open } else { close }, 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::registry::BlockRegistry;
+    use crate::game::world::simulation::SimulationArea;
+
+    /// Use the block at a position
+    fn use_at(world: &mut World, position: Position) -> bool {
+        let block = world.get_block(position).unwrap();
+        use_block(world, position, block)
+    }
+
+    #[test]
+    fn test_states_roundtrip() {
+        let blocks = BlockRegistry::new();
+        for kind in Interactive::ALL {
+            for block in kind.blocks() {
+                let state = InteractiveState::from_block(block).unwrap();
+                assert_eq!(state.kind, kind);
+                assert_eq!(state.block(), block);
+                assert_eq!(blocks.get_block_id(&state.name()), Some(block));
+                assert!(blocks.use_handler(block).is_some());
+            }
+        }
+        assert_eq!(InteractiveState::from_block(50), None);
+        assert_eq!(
+            blocks.get_block_id("minecraft:lever[face=wall,facing=east,powered=true]"),
+            Some(Interactive::Lever.first_block() + 3 * 6 + 2 + 1)
+        );
+    }
+
+    #[test]
+    fn test_lever_opens_door_and_button_releases() {
+        let mut world = World::new("test".to_string(), 0);
+        let area = SimulationArea::new(0);
+        let lower = Position::new(0, 100, 0);
+        let upper = Position::new(0, 101, 0);
+        let door = InteractiveState::from_block(Interactive::OakDoor.first_block()).unwrap();
+        world.set_block(lower, door.block());
+        world.set_block(
+            upper,
+            InteractiveState {
+                upper: true,
+                ..door
+            }
+            .block(),
+        );
+
+        // Using either half opens the whole door
+        assert!(use_at(&mut world, upper));
+        let opened = InteractiveState::from_block(world.get_block(lower).unwrap()).unwrap();
+        assert!(opened.open);
+        assert!(!world.take_sounds().is_empty());
+
+        let lever = Position::new(1, 101, 0);
+        world.set_block(lower, door.block());
+        world.set_block(
+            upper,
+            InteractiveState {
+                upper: true,
+                ..door
+            }
+            .block(),
+        );
+        world.set_block(lever, Interactive::Lever.first_block());
+        world.update(0.05, &area);
+        assert!(use_at(&mut world, lever));
+        assert_eq!(power(world.get_block(lever).unwrap()), MAX_POWER);
+        world.update(0.05, &area);
+        let powered = InteractiveState::from_block(world.get_block(lower).unwrap()).unwrap();
+        assert!(powered.open && powered.powered);
+
+        let button = Position::new(5, 100, 5);
+        world.set_block(button, Interactive::StoneButton.first_block());
+        assert!(use_at(&mut world, button));
+        assert_eq!(power(world.get_block(button).unwrap()), MAX_POWER);
+        for _ in 0..Interactive::StoneButton.press_ticks() {
+            world.update(0.05, &area);
+        }
+        assert_eq!(power(world.get_block(button).unwrap()), 0);
+        assert!(!use_block(&mut world, button, 0));
+    }
+}
@@ -0,0 +1,192 @@
+//! Leaf decay
+//!
+//! Like vanilla, every leaf block stores its distance to the nearest log,
+//! counting steps through other leaves, up to [`MAX_DISTANCE`]. When a
+//! block next to a leaf changes, the leaf recomputes its distance on the
+//! following tick, so a change ripples through a tree one block per tick.
+//! Leaves that end up [`MAX_DISTANCE`] away from any log decay on random
+//! ticks, dropping saplings, sticks and apples now and then.
+//!
+//! Each distance has its own block ID, registered in the
+//! [`BlockRegistry`](super::registry::BlockRegistry) with the distance in
+//! the name, such as `minecraft:oak_leaves[distance=7]`.
+
+use super::World;
+use super::region::{BlockView, RegionTick};
+use super::simulation::TickRandom;
+use crate::game::loot::ItemDrop;
+use crate::protocol::types::Position;
+
+/// Block ID of an oak log
+pub const OAK_LOG: u32 = 43;
+
+/// Block ID of oak leaves right next to a log; further leaves follow up
+/// to [`MAX_DISTANCE`]
+pub const OAK_LEAVES: u32 = 44;
+
+/// Distance of leaves cut off from every log
+pub const MAX_DISTANCE: u32 = 7;
+
+/// Chance of decaying or broken leaves dropping a sapling
+const SAPLING_CHANCE: f32 = 0.05;
+
+/// Chance of decaying or broken leaves dropping sticks
+const STICK_CHANCE: f32 = 0.02;
+
+/// Chance of decaying or broken oak leaves dropping an apple
+const APPLE_CHANCE: f32 = 0.005;
+
+/// Block ID of oak leaves at a distance from the nearest log
+pub fn leaves(distance: u32) -> u32 {
+    OAK_LEAVES + distance.clamp(1, MAX_DISTANCE) - 1
+}
+
+/// Distance of a leaf block to the nearest log, or `None` for other blocks
+pub fn leaf_distance(block: u32) -> Option<u32> {
+    block
+        .checked_sub(OAK_LEAVES)
+        .map(|offset| offset + 1)
+        .filter(|&distance| distance <= MAX_DISTANCE)
+}
+
+/// Make cut off leaves decay on random ticks
+pub(super) fn register(world: &mut World) {
+    world.on_random_tick(leaves(MAX_DISTANCE), decay);
+}
+
+/// Break leaves that are cut off from every log
+///
+/// Breaking rolls loot and spawns items, which needs the whole world.
+fn decay(tick: &mut RegionTick<'_>, position: Position, block: u32) {
+    if leaf_distance(block) == Some(MAX_DISTANCE) {
+        tick.defer(position, block, drop_leaves);
+    }
+}
+
+/// Break decayed leaves, dropping their loot, unless they changed since
+fn drop_leaves(world: &mut World, position: Position, block: u32) {
+    if leaf_distance(block) != Some(MAX_DISTANCE) || world.get_block(position) != Some(block) {
+        return;
+    }
+    for broken in world.break_block(position) {
+        world.drop_items(broken.position, broken.drops);
+    }
+}
+
+/// Distance a leaf should have given the blocks around it
+fn distance_from_neighbours(blocks: &impl BlockView, position: Position) -> u32 {
+    neighbours(position)
+        .filter_map(|neighbour| match blocks.get_block(neighbour)? {
+            OAK_LOG => Some(0),
+            block => leaf_distance(block),
+        })
+        .map(|distance| distance + 1)
+        .min()
+        .unwrap_or(MAX_DISTANCE)
+        .min(MAX_DISTANCE)
+}
+
+/// The six blocks sharing a face with a position
+fn neighbours(position: Position) -> impl Iterator<Item = Position> {
+    [
+        (1, 0, 0),
+        (-1, 0, 0),
+        (0, 1, 0),
+        (0, -1, 0),
+        (0, 0, 1),
+        (0, 0, -1),
+    ]
+    .into_iter()
+    .map(move |(dx, dy, dz)| Position::new(position.x + dx, position.y + dy, position.z + dz))
+}
+
+/// Blocks next to blocks that changed, which may be leaves to update
+pub(super) fn positions_to_update(changed: Vec<Position>) -> Vec<Position> {
+    let mut positions: Vec<Position> = changed.into_iter().flat_map(neighbours).collect();
+    positions.sort_by_key(|position| (position.x, position.y, position.z));
+    positions.dedup();
+    positions
+}
+
+/// Recompute the distance of leaves in a region
+///
+/// Leaves whose distance changes are changed blocks themselves, so their
+/// neighbours follow on the next tick.
+pub(super) fn update_distances(tick: &mut RegionTick<'_>, positions: &[Position]) {
+    for &position in positions {
+        let Some(distance) = tick.get_block(position).and_then(leaf_distance) else {
+            continue;
+        };
+        let updated = distance_from_neighbours(tick, position);
+        if updated != distance {
+            tick.set_block(position, leaves(updated));
+        }
+    }
+}
+
+/// Roll what a block drops when broken
+pub(super) fn drops(block: u32, random: &mut TickRandom) -> Vec<ItemDrop> {
+    if leaf_distance(block).is_none() {
+        return Vec::new();
+    }
+    let mut drops = Vec::new();
+    if random.fraction() < SAPLING_CHANCE {
+        drops.push(ItemDrop::new("minecraft:oak_sapling", 1));
+    }
+    if random.fraction() < STICK_CHANCE {
+        drops.push(ItemDrop::new("minecraft:stick", 1 + random.below(2) as u32));
+    }
+    if random.fraction() < APPLE_CHANCE {
+        drops.push(ItemDrop::new("minecraft:apple", 1));
+    }
+    drops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::registry::BlockRegistry;
+    use crate::game::world::simulation::SimulationArea;
+
+    #[test]
+    fn test_leaf_blocks_are_registered() {
+        let blocks = BlockRegistry::new();
+        assert_eq!(blocks.get_block_id("minecraft:oak_log"), Some(OAK_LOG));
+        assert_eq!(
+            blocks.get_block_id("minecraft:oak_leaves[distance=7]"),
+            Some(leaves(MAX_DISTANCE))
+        );
+        assert_eq!(leaf_distance(leaves(3)), Some(3));
+        assert_eq!(leaf_distance(OAK_LOG), None);
+        assert_eq!(leaf_distance(leaves(MAX_DISTANCE) + 1), None);
+    }
+
+    #[test]
+    fn test_leaves_decay_without_logs() {
+        let mut world = World::new("test".to_string(), 0);
+        let log = Position::new(0, 100, 0);
+        let near = Position::new(1, 100, 0);
+        let far = Position::new(2, 100, 0);
+        world.set_block(log, OAK_LOG);
+        world.set_block(near, leaves(MAX_DISTANCE));
+        world.set_block(far, leaves(MAX_DISTANCE));
+
+        let area = SimulationArea::new(0);
+        world.update(0.05, &area);
+        world.update(0.05, &area);
+        assert_eq!(world.get_block(near), Some(leaves(1)));
+        assert_eq!(world.get_block(far), Some(leaves(2)));
+
+        world.break_block(log);
+        for _ in 0..MAX_DISTANCE {
+            world.update(0.05, &area);
+        }
+        assert_eq!(world.get_block(near), Some(leaves(MAX_DISTANCE)));
+        assert_eq!(world.get_block(far), Some(leaves(MAX_DISTANCE)));
+
+        drop_leaves(&mut world, near, leaves(MAX_DISTANCE));
+        assert_eq!(world.get_block(near), Some(0));
+        drop_leaves(&mut world, far, leaves(1));
+        assert_eq!(world.get_block(far), Some(leaves(MAX_DISTANCE)));
+    }
+}
@@ -0,0 +1,293 @@
+//! Level data
+//!
+//! World-wide state (seed, time, weather, spawn, difficulty, game rules and
+//! data packs) is stored in the world's `level.dat`, a gzip-compressed NBT
+//! file whose root compound holds a single `Data` compound.
+
+use super::gamerules::GameRules;
+use super::spawn::MAX_SPAWN_CHUNK_RADIUS;
+use super::{Weather, World};
+use crate::error::{Result, ServerError};
+use crate::game::Difficulty;
+use crate::game::datapack::DataPackSelection;
+use crate::protocol::MINECRAFT_VERSION;
+use crate::protocol::nbt::{Compound, Tag};
+use crate::protocol::types::Position;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Data version written to saved files (Minecraft 1.21.6)
+pub const DATA_VERSION: i32 = 4435;
+
+/// Persisted world-wide state
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelData {
+    /// World name
+    pub level_name: String,
+    /// World seed
+    pub seed: i64,
+    /// Total ticks the world has been running
+    pub world_age: i64,
+    /// Time of day in ticks
+    pub time_of_day: i64,
+    /// World spawn position
+    pub spawn: Position,
+    /// Yaw players face at the world spawn
+    pub spawn_angle: f32,
+    /// World difficulty
+    pub difficulty: Difficulty,
+    /// Whether players may no longer change the difficulty
+    pub difficulty_locked: bool,
+    /// Current weather
+    pub weather: Weather,
+    /// Ticks until the weather clears
+    pub weather_duration: i64,
+    /// Game rules
+    pub game_rules: GameRules,
+    /// Enabled and disabled data packs, once chosen
+    pub data_packs: Option<DataPackSelection>,
+}
+
+impl LevelData {
+    /// Capture the persisted state of a world
+    pub fn from_world(world: &World) -> Self {
+        Self {
+            level_name: world.name().to_string(),
+            seed: world.seed(),
+            world_age: world.world_age(),
+            time_of_day: world.time_of_day(),
+            spawn: world.spawn_position(),
+            spawn_angle: world.spawn_angle(),
+            difficulty: world.difficulty(),
+            difficulty_locked: world.is_difficulty_locked(),
+            weather: world.weather(),
+            weather_duration: world.weather_duration(),
+            game_rules: world.game_rules().clone(),
+            data_packs: world.data_packs().cloned(),
+        }
+    }
+
+    /// Restore the persisted state onto a world
+    pub fn apply_to(&self, world: &mut World) {
+        world.seed = self.seed;
+        world.set_world_age(self.world_age);
+        world.set_time_of_day(self.time_of_day);
+        world.set_spawn_position(self.spawn);
+        world.set_spawn_angle(self.spawn_angle);
+        world.set_difficulty(self.difficulty);
+        world.set_difficulty_locked(self.difficulty_locked);
+        world.set_weather(self.weather, self.weather_duration);
+        *world.game_rules_mut() = self.game_rules.clone();
+        if let Some(ref selection) = self.data_packs {
+            world.set_data_packs(selection.clone());
+        }
+    }
+
+    /// Encode as the root compound of a level.dat file
+    pub fn to_nbt(&self) -> Compound {
+        let last_played = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64);
+
+        let mut world_gen = Compound::new();
+        world_gen.insert("seed".to_string(), self.seed.into());
+
+        let mut version = Compound::new();
+        version.insert("Id".to_string(), DATA_VERSION.into());
+        version.insert("Name".to_string(), MINECRAFT_VERSION.into());
+
+        let mut game_rules = Compound::new();
+        game_rules.insert(
+            "playersSleepingPercentage".to_string(),
+            self.game_rules
+                .players_sleeping_percentage
+                .to_string()
+                .into(),
+        );
+        game_rules.insert(
+            "randomTickSpeed".to_string(),
+            self.game_rules.random_tick_speed.to_string().into(),
+        );
+        game_rules.insert(
+            "doDaylightCycle".to_string(),
+            self.game_rules.do_daylight_cycle.to_string().into(),
+        );
+        game_rules.insert(
+            "spawnChunkRadius".to_string(),
+            self.game_rules.spawn_chunk_radius.to_string().into(),
+        );
+
+        let mut data = Compound::new();
+        data.insert("DataVersion".to_string(), DATA_VERSION.into());
+        data.insert("version".to_string(), 19133.into());
+        data.insert("Version".to_string(), version.into());
+        data.insert("LevelName".to_string(), self.level_name.as_str().into());
+        data.insert("WorldGenSettings".to_string(), world_gen.into());
+        data.insert("Time".to_string(), self.world_age.into());
+        data.insert("DayTime".to_string(), self.time_of_day.into());
+        data.insert("SpawnX".to_string(), self.spawn.x.into());
+        data.insert("SpawnY".to_string(), self.spawn.y.into());
+        data.insert("SpawnZ".to_string(), self.spawn.z.into());
+        data.insert("SpawnAngle".to_string(), Tag::Float(self.spawn_angle));
+        data.insert(
+            "Difficulty".to_string(),
+            Tag::Byte(self.difficulty.id() as i8),
+        );
+        data.insert(
+            "DifficultyLocked".to_string(),
+            self.difficulty_locked.into(),
+        );
+        data.insert("raining".to_string(), self.weather.is_raining().into());
+        data.insert(
+            "thundering".to_string(),
+            (self.weather == Weather::Thunder).into(),
+        );
+        data.insert(
+            "rainTime".to_string(),
+            Tag::Int(self.weather_duration as i32),
+        );
+        data.insert(
+            "thunderTime".to_string(),
+            Tag::Int(self.weather_duration as i32),
+        );
+        data.insert("GameRules".to_string(), game_rules.into());
+        if let Some(ref selection) = self.data_packs {
+            let list =
+                |ids: &[String]| Tag::List(ids.iter().map(|id| Tag::from(id.as_str())).collect());
+            let mut data_packs = Compound::new();
+            data_packs.insert("Enabled".to_string(), list(&selection.enabled));
+            data_packs.insert("Disabled".to_string(), list(&selection.disabled));
+            data.insert("DataPacks".to_string(), data_packs.into());
+        }
+        data.insert("LastPlayed".to_string(), last_played.into());
+
+        let mut root = Compound::new();
+        root.insert("Data".to_string(), data.into());
+        root
+    }
+
+    /// Decode the root compound of a level.dat file
+    ///
+    /// Missing optional fields fall back to the defaults of a new world.
+    pub fn from_nbt(root: &Compound) -> Result<Self> {
+        let data = root
+            .get("Data")
+            .and_then(Tag::as_compound)
+            .ok_or_else(|| ServerError::WorldIo("level.dat has no Data compound".to_string()))?;
+
+        let int = |key: &str| data.get(key).and_then(Tag::as_i64);
+        let flag = |key: &str| data.get(key).and_then(Tag::as_bool).unwrap_or(false);
+
+        let seed = data
+            .get("WorldGenSettings")
+            .and_then(Tag::as_compound)
+            .and_then(|settings| settings.get("seed"))
+            .and_then(Tag::as_i64)
+            .ok_or_else(|| ServerError::WorldIo("level.dat has no world seed".to_string()))?;
+
+        let weather = match (flag("raining"), flag("thundering")) {
+            (true, true) => Weather::Thunder,
+            (true, false) => Weather::Rain,
+            _ => Weather::Clear,
+        };
+
+        let mut game_rules = GameRules::default();
+        if let Some(rules) = data.get("GameRules").and_then(Tag::as_compound) {
+            let rule = |name: &str| rules.get(name).and_then(Tag::as_str);
+            if let Some(value) = rule("playersSleepingPercentage").and_then(|v| v.parse().ok()) {
+                game_rules.players_sleeping_percentage = value;
+            }
+            if let Some(value) = rule("randomTickSpeed").and_then(|v| v.parse().ok()) {
+                game_rules.random_tick_speed = value;
+            }
+            if let Some(value) = rule("doDaylightCycle").and_then(|v| v.parse().ok()) {
+                game_rules.do_daylight_cycle = value;
+            }
+            if let Some(value) = rule("spawnChunkRadius").and_then(|v| v.parse::<u32>().ok()) {
+                game_rules.spawn_chunk_radius = value.min(MAX_SPAWN_CHUNK_RADIUS);
+            }
+        }
+
+        let data_packs = data
+            .get("DataPacks")
+            .and_then(Tag::as_compound)
+            .map(|packs| {
+                let list = |key: &str| -> Vec<String> {
+                    packs
+                        .get(key)
+                        .and_then(Tag::as_list)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Tag::as_str)
+                        .map(str::to_string)
+                        .collect()
+                };
+                DataPackSelection {
+                    enabled: list("Enabled"),
+                    disabled: list("Disabled"),
+                }
+            });
+
+        Ok(Self {
+            level_name: data
+                .get("LevelName")
+                .and_then(Tag::as_str)
+                .unwrap_or("world")
+                .to_string(),
+            seed,
+            world_age: int("Time").unwrap_or(0),
+            time_of_day: int("DayTime").unwrap_or(0),
+            spawn: Position::new(
+                int("SpawnX").unwrap_or(0) as i32,
+                int("SpawnY").unwrap_or(64) as i32,
+                int("SpawnZ").unwrap_or(0) as i32,
+            ),
+            spawn_angle: data.get("SpawnAngle").and_then(Tag::as_f64).unwrap_or(0.0) as f32,
+            difficulty: int("Difficulty")
+                .and_then(|id| Difficulty::ALL.get(id as usize).copied())
+                .unwrap_or_default(),
+            difficulty_locked: flag("DifficultyLocked"),
+            weather,
+            weather_duration: int("rainTime").unwrap_or(0),
+            game_rules,
+            data_packs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_data_roundtrip() {
+        let mut world = World::new("roundtrip".to_string(), -42);
+        world.set_time_of_day(18_000);
+        world.set_weather(Weather::Thunder, 1200);
+        world.set_difficulty(Difficulty::Hard);
+        world.set_difficulty_locked(true);
+        world.set_spawn_position(Position::new(10, 70, -5));
+        world.set_spawn_angle(90.0);
+        world.game_rules_mut().players_sleeping_percentage = 50;
+        world.game_rules_mut().random_tick_speed = 0;
+        world.game_rules_mut().do_daylight_cycle = false;
+        world.game_rules_mut().spawn_chunk_radius = 0;
+        world.set_data_packs(DataPackSelection {
+            enabled: vec!["vanilla".to_string(), "file/extra".to_string()],
+            disabled: vec!["file/old".to_string()],
+        });
+
+        let data = LevelData::from_world(&world);
+        let decoded = LevelData::from_nbt(&data.to_nbt()).unwrap();
+        assert_eq!(decoded, data);
+
+        let mut restored = World::new("roundtrip".to_string(), 0);
+        decoded.apply_to(&mut restored);
+        assert_eq!(restored.seed(), -42);
+        assert_eq!(restored.weather(), Weather::Thunder);
+        assert_eq!(restored.game_rules().players_sleeping_percentage, 50);
+        assert_eq!(restored.game_rules().random_tick_speed, 0);
+        assert!(!restored.game_rules().do_daylight_cycle);
+        assert_eq!(restored.game_rules().spawn_chunk_radius, 0);
+        assert_eq!(restored.data_packs(), data.data_packs.as_ref());
+    }
+}
@@ -0,0 +1,157 @@
+//! LZ4 chunk compression
+//!
+//! Vanilla compresses LZ4 chunks with lz4-java's block stream: a series of
+//! blocks, each with a `LZ4Block` magic, a token byte giving the method
+//! (raw or LZ4) and block size, the compressed and original lengths, and an
+//! XXHash32 checksum of the original data, followed by the block contents.
+//! An empty block ends the stream.
+//!
+//! Blocks use the plain LZ4 block format, compressed with `lz4_flex`;
+//! blocks it cannot shrink are stored raw.
+
+use crate::error::{Result, ServerError};
+use xxhash_rust::xxh32::xxh32;
+
+/// Magic bytes starting every block
+const MAGIC: &[u8; 8] = b"LZ4Block";
+
+/// Length of a block header
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + 4 + 4 + 4;
+
+/// Method of blocks stored uncompressed
+const METHOD_RAW: u8 = 0x10;
+
+/// Method of LZ4-compressed blocks
+const METHOD_LZ4: u8 = 0x20;
+
+/// Largest block written, as in lz4-java
+const BLOCK_SIZE: usize = 1 << 16;
+
+/// Block size exponent stored in the token, relative to 2^10
+const BLOCK_SIZE_LEVEL: u8 = 6;
+
+/// Seed of the block checksums
+const CHECKSUM_SEED: u32 = 0x9747_b28c;
+
+/// Bits of the checksum stored
+const CHECKSUM_MASK: u32 = 0x0fff_ffff;
+
+/// Compress data into an lz4-java block stream
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 2 + HEADER_LENGTH * 2);
+    for block in data.chunks(BLOCK_SIZE) {
+        let compressed = lz4_flex::block::compress(block);
+        let (method, contents) = if compressed.len() < block.len() {
+            (METHOD_LZ4, compressed.as_slice())
+        } else {
+            (METHOD_RAW, block)
+        };
+        write_header(
+            &mut out,
+            method,
+            contents.len(),
+            block.len(),
+            checksum(block),
+        );
+        out.extend_from_slice(contents);
+    }
+    write_header(&mut out, METHOD_RAW, 0, 0, 0);
+    out
+}
+
+/// Decompress an lz4-java block stream
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut rest = data;
+    loop {
+        let header = rest
+            .get(..HEADER_LENGTH)
+            .filter(|header| header.starts_with(MAGIC))
+            .ok_or_else(|| invalid("missing block header"))?;
+        let token = header[MAGIC.len()];
+        let compressed_length = read_length(&header[9..13])?;
+        let original_length = read_length(&header[13..17])?;
+        let expected_checksum =
+            u32::from_le_bytes([header[17], header[18], header[19], header[20]]);
+        if original_length > 1 << (10 + u32::from(token & 0x0f)) {
+            return Err(invalid("block larger than its declared size"));
+        }
+        if original_length == 0 {
+            return Ok(out);
+        }
+
+        let contents = rest
+            .get(HEADER_LENGTH..HEADER_LENGTH + compressed_length)
+            .ok_or_else(|| invalid("truncated block"))?;
+        let start = out.len();
+        match token & 0xf0 {
+            METHOD_RAW if compressed_length == original_length => out.extend_from_slice(contents),
+            METHOD_LZ4 => out.extend(
+                lz4_flex::block::decompress(contents, original_length)
+                    .map_err(|e| invalid(&e.to_string()))?,
+            ),
+            _ => return Err(invalid("unknown block method")),
+        }
+        if out.len() - start != original_length {
+            return Err(invalid("block length mismatch"));
+        }
+        if checksum(&out[start..]) != expected_checksum {
+            return Err(invalid("checksum mismatch"));
+        }
+        rest = &rest[HEADER_LENGTH + compressed_length..];
+    }
+}
+
+/// Error for a malformed LZ4 stream
+fn invalid(reason: &str) -> ServerError {
+    ServerError::WorldIo(format!("Invalid LZ4 chunk data: {}", reason))
+}
+
+/// Read a little-endian block length
+fn read_length(bytes: &[u8]) -> Result<usize> {
+    let length = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    usize::try_from(length).map_err(|_| invalid("negative length"))
+}
+
+/// Write a block header
+fn write_header(
+    out: &mut Vec<u8>,
+    method: u8,
+    compressed_length: usize,
+    original_length: usize,
+    checksum: u32,
+) {
+    out.extend_from_slice(MAGIC);
+    out.push(method | BLOCK_SIZE_LEVEL);
+    out.extend_from_slice(&(compressed_length as i32).to_le_bytes());
+    out.extend_from_slice(&(original_length as i32).to_le_bytes());
+    out.extend_from_slice(&checksum.to_le_bytes());
+}
+
+/// Checksum of a block's original data
+fn checksum(data: &[u8]) -> u32 {
+    xxh32(data, CHECKSUM_SEED) & CHECKSUM_MASK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let repetitive: Vec<u8> = (0..200_000u32).map(|i| (i % 7) as u8).collect();
+        let noisy: Vec<u8> = (0..5_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+            .collect();
+        for data in [&repetitive[..], &noisy[..], b"short", b""] {
+            let compressed = compress(data);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+        assert!(compress(&repetitive).len() < repetitive.len() / 50);
+
+        let mut corrupted = compress(&repetitive);
+        let last = corrupted.len() - HEADER_LENGTH - 1;
+        corrupted[last] ^= 1;
+        assert!(decompress(&corrupted).is_err());
+    }
+}
@@ -2,12 +2,123 @@
 //!
 //! This module handles world state, chunks, blocks, and world generation.
 
+pub mod anvil;
+pub mod bed;
+pub mod budget;
 pub mod chunk;
+pub mod config;
+pub mod dimension;
+pub mod explosion;
+pub mod farming;
+pub mod gamerules;
+pub mod gravity;
+pub mod interact;
+pub mod leaves;
+pub mod level;
+pub mod lz4;
+pub mod multiblock;
+pub mod portal;
+pub mod pregen;
+pub mod region;
 pub mod registry;
+pub mod simulation;
+pub mod spawn;
+pub mod storage;
 
-use crate::game::entity::EntityManager;
-use crate::protocol::types::Position;
-use std::collections::HashMap;
+use crate::error::{Result, ServerError};
+use crate::game::datapack::DataPackSelection;
+use crate::game::entity::experience::ExperienceOrbEntity;
+use crate::game::entity::item::ItemEntity;
+use crate::game::entity::mob::MobEntity;
+use crate::game::entity::{Entity, EntityId, EntityManager, EntityPosition, EntityType, MobType};
+use crate::game::entity::{breeding, decoration};
+use crate::game::loot::{self, ItemDrop, KillContext};
+use crate::game::{Difficulty, LevelType};
+use crate::protocol::frame::EncodedPacket;
+use crate::protocol::nbt::Compound;
+use crate::protocol::packets::RawPacket;
+use crate::protocol::packets::play::ChunkDataPacket;
+use crate::protocol::types::{McUuid, Position};
+use bed::{Bed, BedError, BedPart};
+use budget::ChunkBudgets;
+use dimension::Dimension;
+use explosion::Explosion;
+use gamerules::GameRules;
+use level::LevelData;
+use registry::BlockRegistry;
+use simulation::{RandomTickHandler, ScheduledTickHandler, SimulationArea, TickRandom};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use storage::WorldStorage;
+
+/// Modified chunks and level data copied out of a world, so they can be
+/// encoded and written without holding it
+pub struct WorldSave {
+    /// Storage the world is persisted in
+    storage: Arc<Mutex<WorldStorage>>,
+    /// Registry used to map block IDs to names
+    blocks: Arc<BlockRegistry>,
+    /// Copies of the modified chunks
+    chunks: Vec<chunk::Chunk>,
+    /// Level data at the time of the copy, for the overworld
+    level: Option<LevelData>,
+    /// Flush writes even if the storage does not sync them
+    flush: bool,
+}
+
+impl WorldSave {
+    /// Encode and write the chunks, then the level data
+    ///
+    /// Chunks are encoded without locking the storage, so the world can
+    /// keep loading chunks in the meantime. Stops at the first error.
+    pub fn write(self) -> SaveOutcome {
+        let mut written = Vec::with_capacity(self.chunks.len());
+        let result = self.write_into(&mut written);
+        SaveOutcome { written, result }
+    }
+
+    /// Write the chunks, recording each one written, then the level data
+    fn write_into(&self, written: &mut Vec<(ChunkPosition, u64)>) -> Result<()> {
+        for chunk in &self.chunks {
+            let data = storage::chunk_data(chunk, &self.blocks)?;
+            lock(&self.storage).write_chunk_data(chunk.position(), &data, self.flush)?;
+            written.push((chunk.position(), chunk.revision()));
+        }
+        match self.level {
+            Some(ref level) => lock(&self.storage).save_level(level, self.flush),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What a [`WorldSave`] wrote, for [`World::finish_save`]
+#[derive(Debug)]
+pub struct SaveOutcome {
+    /// Chunks written, with the revision they were copied at
+    written: Vec<(ChunkPosition, u64)>,
+    /// Whether every write succeeded
+    result: Result<()>,
+}
+
+impl SaveOutcome {
+    /// Outcome of a save that could not write anything
+    pub fn failed(error: ServerError) -> Self {
+        Self {
+            written: Vec::new(),
+            result: Err(error),
+        }
+    }
+}
+
+/// Lock a world's storage, ignoring poisoning
+fn lock(storage: &Mutex<WorldStorage>) -> MutexGuard<'_, WorldStorage> {
+    storage.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Chunk an entity stands in
+fn entity_chunk(position: EntityPosition) -> ChunkPosition {
+    ChunkPosition::from_world_coords(position.x.floor(), position.z.floor())
+}
 
 /// Represents a Minecraft world
 pub struct World {
@@ -15,12 +126,224 @@ pub struct World {
     pub name: String,
     /// World seed
     pub seed: i64,
+    /// Dimension the world belongs to
+    dimension: Dimension,
     /// Loaded chunks
     chunks: HashMap<ChunkPosition, chunk::Chunk>,
+    /// Chunk data packets of loaded chunks, shared by every viewer
+    chunk_packets: HashMap<ChunkPosition, CachedChunkPacket>,
     /// Entity manager for this world
     entities: EntityManager,
     /// World spawn position
     spawn_position: Position,
+    /// Yaw players face at the world spawn
+    spawn_angle: f32,
+    /// Total ticks this world has been running
+    world_age: i64,
+    /// Time of day in ticks (0 is sunrise, 6000 is noon)
+    time_of_day: i64,
+    /// Current weather
+    weather: Weather,
+    /// Ticks until the weather clears, if it is not clear
+    weather_duration: i64,
+    /// World difficulty
+    difficulty: Difficulty,
+    /// Whether players may no longer change the difficulty
+    difficulty_locked: bool,
+    /// World preset
+    generator: LevelType,
+    /// Game rules
+    game_rules: GameRules,
+    /// Bed halves by block position
+    beds: HashMap<Position, Bed>,
+    /// On-disk storage, if the world is persisted, shared with saves
+    /// written in the background
+    storage: Option<Arc<Mutex<WorldStorage>>>,
+    /// Chunks copied by a save that is being written, kept loaded until it
+    /// is done so an older copy never overwrites a newer one
+    saving: HashSet<ChunkPosition>,
+    /// Chunks loaded so far
+    chunk_loads: ChunkLoadCounts,
+    /// Limits on chunks loaded per tick and kept in memory
+    budgets: ChunkBudgets,
+    /// Chunks loaded during the current tick
+    loads_this_tick: u32,
+    /// Enabled and disabled data packs, once chosen
+    data_packs: Option<DataPackSelection>,
+    /// How blocks react to random ticks, by block ID
+    random_tick_handlers: HashMap<u32, RandomTickHandler>,
+    /// Picks the blocks that receive random ticks
+    random: TickRandom,
+    /// Explosions of entities that blew up, not yet applied
+    explosions: Vec<Explosion>,
+    /// Positions of blocks changed since they were last taken, gathered
+    /// over a tick so they can be sent a section at a time
+    block_changes: Vec<Position>,
+    /// Entities removed by the world since they were last taken
+    despawned: Vec<EntityId>,
+    /// Entities added by the world since they were last taken
+    spawned: Vec<EntityId>,
+    /// Mobs that died since they were last taken
+    deaths: Vec<MobDeath>,
+    /// Babies that grew up since they were last taken
+    grown_up: Vec<EntityId>,
+    /// Positions of blocks changed since the last update, whose
+    /// neighbours react to the change
+    neighbour_updates: Vec<Position>,
+    /// Block ticks due at a later world age
+    scheduled_ticks: Vec<ScheduledTick>,
+    /// Sounds played by blocks since they were last taken
+    sounds: Vec<BlockSound>,
+}
+
+/// A block tick due at a later world age
+#[derive(Debug, Clone, Copy)]
+struct ScheduledTick {
+    /// World age the tick is due at
+    due: i64,
+    /// Position of the block
+    position: Position,
+    /// How the block reacts
+    handler: ScheduledTickHandler,
+}
+
+/// A sound played by a block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockSound {
+    /// Position of the block
+    pub position: Position,
+    /// Sound event identifier
+    pub sound: &'static str,
+    /// Pitch, where 1.0 is normal
+    pub pitch: f32,
+    /// Seed picking among variants of the sound
+    pub seed: i64,
+}
+
+/// Number of chunks loaded since the world was created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChunkLoadCounts {
+    /// Chunks read from storage
+    pub read: u64,
+    /// Chunks generated because they were never saved
+    pub generated: u64,
+    /// Loads put off to a later tick because the tick's budget was used up
+    pub deferred: u64,
+    /// Chunks unloaded to stay under the loaded chunk limit
+    pub evicted: u64,
+}
+
+/// A framed chunk data packet and the chunk revision it was built from
+struct CachedChunkPacket {
+    /// Revision of the chunk the packet shows
+    revision: u64,
+    /// The framed packet
+    packet: Arc<EncodedPacket>,
+}
+
+/// Result of looking up a chunk's data packet
+pub enum ChunkPacket {
+    /// The packet was cached
+    Cached(Arc<EncodedPacket>),
+    /// The packet must be framed, then cached
+    Uncached {
+        /// The packet to frame
+        packet: RawPacket,
+        /// Revision of the chunk the packet shows
+        revision: u64,
+    },
+}
+
+/// Number of ticks in a full day/night cycle
+pub const TICKS_PER_DAY: i64 = 24_000;
+
+/// Weather in a world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Weather {
+    /// No precipitation
+    #[default]
+    Clear,
+    /// Rain (or snow in cold biomes)
+    Rain,
+    /// Rain with thunder and lightning
+    Thunder,
+}
+
+impl Weather {
+    /// Parse a weather type from its command name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clear" => Some(Weather::Clear),
+            "rain" => Some(Weather::Rain),
+            "thunder" => Some(Weather::Thunder),
+            _ => None,
+        }
+    }
+
+    /// Get the command name of this weather type
+    pub fn name(&self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Rain => "rain",
+            Weather::Thunder => "thunder",
+        }
+    }
+
+    /// Whether it is raining
+    pub fn is_raining(&self) -> bool {
+        !matches!(self, Weather::Clear)
+    }
+}
+
+/// A horizontal direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Direction {
+    /// Towards negative Z
+    North,
+    /// Towards positive Z
+    South,
+    /// Towards negative X
+    West,
+    /// Towards positive X
+    East,
+}
+
+impl Direction {
+    /// Get the opposite direction
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+        }
+    }
+
+    /// Get the (x, z) block offset of one step in this direction
+    pub fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+            Direction::East => (1, 0),
+        }
+    }
+
+    /// Get the position one block away in this direction
+    pub fn relative(&self, position: Position) -> Position {
+        let (dx, dz) = self.offset();
+        Position::new(position.x + dx, position.y, position.z + dz)
+    }
+
+    /// Get the direction an entity with the given yaw is facing
+    pub fn from_yaw(yaw: f32) -> Self {
+        match (((yaw / 90.0).round() as i32) % 4 + 4) % 4 {
+            0 => Direction::South,
+            1 => Direction::West,
+            2 => Direction::North,
+            _ => Direction::East,
+        }
+    }
 }
 
 /// Chunk position (x, z coordinates)
@@ -57,16 +380,193 @@ impl ChunkPosition {
     }
 }
 
+/// Blocks changed in one chunk section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionChanges {
+    /// Section coordinates: chunk X, section Y and chunk Z
+    pub section: [i32; 3],
+    /// Changed blocks with their new block IDs
+    pub blocks: Vec<(Position, u32)>,
+}
+
+/// A block removed by breaking it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenBlock {
+    /// Where the block was
+    pub position: Position,
+    /// Block ID
+    pub block: u32,
+    /// Items the block dropped
+    pub drops: Vec<ItemDrop>,
+}
+
+/// A mob that died, with what it left behind
+#[derive(Debug, Clone)]
+pub struct MobDeath {
+    /// Entity ID the mob had
+    pub entity_id: EntityId,
+    /// Kind of mob
+    pub kind: MobType,
+    /// Where the mob died
+    pub position: EntityPosition,
+    /// Player credited with the kill
+    pub killer: Option<McUuid>,
+    /// Items the mob dropped
+    pub drops: Vec<ItemDrop>,
+    /// Experience the mob left behind
+    pub experience: u32,
+}
+
 impl World {
     /// Create a new world
     pub fn new(name: String, seed: i64) -> Self {
-        Self {
+        let mut world = Self {
             name,
             seed,
+            dimension: Dimension::Overworld,
             chunks: HashMap::new(),
+            chunk_packets: HashMap::new(),
             entities: EntityManager::new(),
             spawn_position: Position::new(0, 64, 0),
+            spawn_angle: 0.0,
+            world_age: 0,
+            time_of_day: 0,
+            weather: Weather::Clear,
+            weather_duration: 0,
+            difficulty: Difficulty::default(),
+            difficulty_locked: false,
+            generator: LevelType::default(),
+            game_rules: GameRules::default(),
+            beds: HashMap::new(),
+            storage: None,
+            saving: HashSet::new(),
+            chunk_loads: ChunkLoadCounts::default(),
+            budgets: ChunkBudgets::default(),
+            loads_this_tick: 0,
+            data_packs: None,
+            random_tick_handlers: HashMap::new(),
+            random: TickRandom::new(),
+            explosions: Vec::new(),
+            block_changes: Vec::new(),
+            despawned: Vec::new(),
+            spawned: Vec::new(),
+            deaths: Vec::new(),
+            grown_up: Vec::new(),
+            neighbour_updates: Vec::new(),
+            scheduled_ticks: Vec::new(),
+            sounds: Vec::new(),
+        };
+        farming::register(&mut world);
+        leaves::register(&mut world);
+        world
+    }
+
+    /// Limit the chunks loaded per tick and kept in memory
+    pub fn with_chunk_budgets(mut self, budgets: ChunkBudgets) -> Self {
+        self.budgets = budgets;
+        self
+    }
+
+    /// Make the world a dimension other than the overworld, changing how
+    /// its chunks are generated
+    pub fn with_dimension(mut self, dimension: Dimension) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    /// Persist the world using the given storage
+    ///
+    /// Saved level data is restored immediately, and chunks are read from
+    /// storage before falling back to generation. Level data belongs to the
+    /// overworld, so other dimensions only read and write their chunks.
+    pub fn with_storage(mut self, storage: WorldStorage) -> Result<Self> {
+        if self.dimension != Dimension::Overworld {
+            self.storage = Some(Arc::new(Mutex::new(storage)));
+            return Ok(self);
+        }
+        let level = storage.load_level()?;
+        if let Some(ref level) = level {
+            level.apply_to(&mut self);
+            tracing::info!(
+                "Loaded world {} from {}",
+                self.name,
+                storage.directory().display()
+            );
+        }
+        self.storage = Some(Arc::new(Mutex::new(storage)));
+        if level.is_none() {
+            // A new world: pick the spawn from its terrain and save it
+            // right away, so it stays put even if the server stops early
+            self.spawn_position = spawn::find_spawn(&mut self);
+            tracing::info!("Chose world spawn at {:?}", self.spawn_position);
+            let level = LevelData::from_world(&self);
+            if let Some(storage) = self.storage() {
+                storage.save_level(&level, true)?;
+            }
         }
+        Ok(self)
+    }
+
+    /// Lock the world's storage, if it is persisted
+    fn storage(&self) -> Option<MutexGuard<'_, WorldStorage>> {
+        self.storage.as_deref().map(lock)
+    }
+
+    /// Save modified chunks and level data, returning the number of chunks written
+    ///
+    /// Only chunks whose modified flag is set are written. With `flush`,
+    /// writes are flushed to disk even if the storage does not sync writes.
+    /// Worlds without storage are not saved. The world is held throughout;
+    /// use [`World::begin_save`] to write without holding it.
+    pub fn save(&mut self, flush: bool) -> Result<usize> {
+        match self.begin_save(flush) {
+            Some(save) => {
+                let outcome = save.write();
+                self.finish_save(outcome)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Copy the modified chunks and level data for a save written with
+    /// [`WorldSave::write`], then handed back to [`World::finish_save`]
+    ///
+    /// The chunks stay loaded, and keep their modified flag, until the save
+    /// is finished. Returns `None` for worlds without storage.
+    pub fn begin_save(&mut self, flush: bool) -> Option<WorldSave> {
+        let storage = Arc::clone(self.storage.as_ref()?);
+        let blocks = Arc::clone(self.storage()?.blocks());
+        self.store_entities(None);
+        let chunks: Vec<chunk::Chunk> = self
+            .chunks
+            .values()
+            .filter(|chunk| chunk.is_modified())
+            .cloned()
+            .collect();
+        self.saving = chunks.iter().map(chunk::Chunk::position).collect();
+        Some(WorldSave {
+            storage,
+            blocks,
+            chunks,
+            level: (self.dimension == Dimension::Overworld).then(|| LevelData::from_world(self)),
+            flush,
+        })
+    }
+
+    /// Mark the chunks a save wrote as saved, returning how many it wrote
+    ///
+    /// Chunks changed since they were copied stay modified, as do the ones
+    /// a failed save did not get to.
+    pub fn finish_save(&mut self, outcome: SaveOutcome) -> Result<usize> {
+        self.saving.clear();
+        for &(position, revision) in &outcome.written {
+            if let Some(chunk) = self.chunks.get_mut(&position)
+                && chunk.revision() == revision
+            {
+                chunk.mark_saved();
+            }
+        }
+        outcome.result.map(|()| outcome.written.len())
     }
 
     /// Get world name
@@ -79,6 +579,11 @@ impl World {
         self.seed
     }
 
+    /// Get the dimension the world belongs to
+    pub fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+
     /// Get spawn position
     pub fn spawn_position(&self) -> Position {
         self.spawn_position
@@ -89,21 +594,395 @@ impl World {
         self.spawn_position = position;
     }
 
+    /// Get the yaw players face at the world spawn
+    pub fn spawn_angle(&self) -> f32 {
+        self.spawn_angle
+    }
+
+    /// Set the yaw players face at the world spawn
+    pub fn set_spawn_angle(&mut self, angle: f32) {
+        self.spawn_angle = angle;
+    }
+
+    /// Get the world age in ticks
+    pub fn world_age(&self) -> i64 {
+        self.world_age
+    }
+
+    /// Set the world age in ticks
+    pub fn set_world_age(&mut self, age: i64) {
+        self.world_age = age;
+    }
+
+    /// Get the time of day in ticks
+    ///
+    /// This keeps counting past [`TICKS_PER_DAY`]; use the remainder for the
+    /// position within the current day.
+    pub fn time_of_day(&self) -> i64 {
+        self.time_of_day
+    }
+
+    /// Set the time of day in ticks
+    pub fn set_time_of_day(&mut self, time: i64) {
+        self.time_of_day = time;
+    }
+
+    /// Get the current weather
+    pub fn weather(&self) -> Weather {
+        self.weather
+    }
+
+    /// Get the number of ticks until the weather clears
+    pub fn weather_duration(&self) -> i64 {
+        self.weather_duration
+    }
+
+    /// Set the weather for the given number of ticks
+    ///
+    /// Once the duration runs out the weather returns to clear.
+    pub fn set_weather(&mut self, weather: Weather, duration: i64) {
+        self.weather = weather;
+        self.weather_duration = duration;
+    }
+
+    /// Get the world difficulty
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// Set the world difficulty
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    /// Whether players may no longer change the difficulty
+    ///
+    /// Commands and server.properties still can.
+    pub fn is_difficulty_locked(&self) -> bool {
+        self.difficulty_locked
+    }
+
+    /// Lock or unlock the difficulty
+    pub fn set_difficulty_locked(&mut self, locked: bool) {
+        self.difficulty_locked = locked;
+    }
+
+    /// Get the world preset
+    pub fn generator(&self) -> LevelType {
+        self.generator
+    }
+
+    /// Set the world preset
+    pub fn set_generator(&mut self, generator: LevelType) {
+        self.generator = generator;
+    }
+
+    /// Get the game rules
+    pub fn game_rules(&self) -> &GameRules {
+        &self.game_rules
+    }
+
+    /// Get mutable game rules
+    pub fn game_rules_mut(&mut self) -> &mut GameRules {
+        &mut self.game_rules
+    }
+
+    /// Get the data packs this world uses, if they were chosen yet
+    pub fn data_packs(&self) -> Option<&DataPackSelection> {
+        self.data_packs.as_ref()
+    }
+
+    /// Set the data packs this world uses
+    pub fn set_data_packs(&mut self, selection: DataPackSelection) {
+        self.data_packs = Some(selection);
+    }
+
+    /// Get the bed half at a position
+    pub fn bed_at(&self, position: Position) -> Option<&Bed> {
+        self.beds.get(&position)
+    }
+
+    /// Place a bed with its foot at `foot`, extending one block towards `facing`
+    ///
+    /// Both halves must be air and rest on a non-air block.
+    pub fn place_bed(
+        &mut self,
+        foot: Position,
+        facing: Direction,
+        block_id: u32,
+    ) -> std::result::Result<(), BedError> {
+        let head = facing.relative(foot);
+        for position in [foot, head] {
+            if self.get_block(position) != Some(0) {
+                return Err(BedError::Obstructed);
+            }
+            let below = Position::new(position.x, position.y - 1, position.z);
+            if matches!(self.get_block(below), Some(0) | None) {
+                return Err(BedError::Unsupported);
+            }
+        }
+
+        for (position, part) in [(foot, BedPart::Foot), (head, BedPart::Head)] {
+            self.set_block(position, block_id);
+            self.beds.insert(position, Bed::new(facing, part));
+        }
+        Ok(())
+    }
+
+    /// Remove the bed that has a half at `position`, returning its head position
+    pub fn remove_bed(&mut self, position: Position) -> Option<Position> {
+        let head = self.beds.get(&position)?.head_position(position);
+        self.set_block(position, 0);
+        Some(head)
+    }
+
+    /// Mark the bed with its head at `head` as occupied or free
+    pub fn set_bed_occupied(&mut self, head: Position, occupied: bool) {
+        let Some(foot) = self.beds.get(&head).map(|bed| bed.other_half(head)) else {
+            return;
+        };
+        for half in [head, foot] {
+            if let Some(bed) = self.beds.get_mut(&half) {
+                bed.occupied = occupied;
+            }
+        }
+    }
+
     /// Load a chunk
+    ///
+    /// Saved chunks are read from storage; missing ones are generated.
     pub fn load_chunk(&mut self, position: ChunkPosition) -> &chunk::Chunk {
-        self.chunks.entry(position).or_insert_with(|| {
+        if !self.chunks.contains_key(&position) {
+            let span = tracing::debug_span!(
+                "load_chunk",
+                x = position.x,
+                z = position.z,
+                generated = tracing::field::Empty,
+            );
+            let _entered = span.enter();
+            let saved = self.storage().and_then(|mut storage| {
+                storage.load_chunk(position).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load chunk {:?}, regenerating: {}", position, e);
+                    None
+                })
+            });
             // For now, generate a simple flat chunk
             // In a real implementation, this would use world generation
-            chunk::Chunk::generate_flat(position)
-        })
+            span.record("generated", saved.is_none());
+            self.loads_this_tick += 1;
+            if saved.is_some() {
+                self.chunk_loads.read += 1;
+            } else {
+                self.chunk_loads.generated += 1;
+            }
+            let chunk = saved.unwrap_or_else(|| match self.dimension {
+                Dimension::Overworld => chunk::Chunk::generate_flat(position),
+                Dimension::Nether => chunk::Chunk::generate_nether(position),
+            });
+            for nbt in chunk.entities() {
+                match self.entities.try_add_entity(|id| decoration::load(id, nbt)) {
+                    Some(entity_id) => self.spawned.push(entity_id),
+                    None => tracing::warn!("Skipping an unknown entity in chunk {:?}", position),
+                }
+            }
+            self.chunks.insert(position, chunk);
+        }
+        &self.chunks[&position]
     }
 
     /// Unload a chunk
+    ///
+    /// Spawn chunks are never unloaded. The armor stands and item frames in
+    /// the chunk are removed with it.
     pub fn unload_chunk(&mut self, position: ChunkPosition) {
+        if self.is_spawn_chunk(position) {
+            return;
+        }
+        let decorations: Vec<EntityId> = self
+            .entities
+            .entities()
+            .filter(|entity| {
+                matches!(
+                    entity.entity_type(),
+                    EntityType::ArmorStand | EntityType::ItemFrame
+                ) && entity_chunk(entity.position()) == position
+            })
+            .map(|entity| entity.entity_id())
+            .collect();
+        for entity_id in decorations {
+            self.entities.remove_entity(entity_id);
+            let pending = self.spawned.len();
+            self.spawned.retain(|&spawned| spawned != entity_id);
+            if self.spawned.len() == pending {
+                self.despawned.push(entity_id);
+            }
+        }
         self.chunks.remove(&position);
+        self.chunk_packets.remove(&position);
         tracing::debug!("Unloaded chunk at {:?}", position);
     }
 
+    /// Whether a chunk can be had this tick: it is loaded already, or the
+    /// tick's load budget is not used up
+    ///
+    /// A chunk that cannot is counted as deferred.
+    pub fn can_load_chunk(&mut self, position: ChunkPosition) -> bool {
+        if self.chunks.contains_key(&position)
+            || self
+                .budgets
+                .loads_per_tick()
+                .is_none_or(|budget| self.loads_this_tick < budget)
+        {
+            return true;
+        }
+        self.chunk_loads.deferred += 1;
+        false
+    }
+
+    /// Write a loaded chunk to storage whether or not it was modified
+    ///
+    /// Returns whether the chunk was written; chunks that are not loaded
+    /// and worlds without storage are skipped.
+    pub fn save_chunk(&mut self, position: ChunkPosition, flush: bool) -> Result<bool> {
+        if self.storage.is_none() || !self.chunks.contains_key(&position) {
+            return Ok(false);
+        }
+        self.store_entities(Some(position));
+        let Some(mut storage) = self.storage() else {
+            return Ok(false);
+        };
+        let Some(chunk) = self.chunks.get(&position) else {
+            return Ok(false);
+        };
+        storage.save_chunk(chunk, flush)?;
+        drop(storage);
+        if let Some(chunk) = self.chunks.get_mut(&position) {
+            chunk.mark_saved();
+        }
+        Ok(true)
+    }
+
+    /// Unload chunks no player can see while more are loaded than the
+    /// budget allows, farthest from any viewer first
+    ///
+    /// `viewers` holds the chunk each player is in and their view distance.
+    /// Spawn chunks stay loaded. Modified chunks are saved first; in a world
+    /// without storage they stay loaded. Returns the number of chunks
+    /// unloaded.
+    pub fn evict_chunks(&mut self, viewers: &[(ChunkPosition, i32)]) -> usize {
+        let Some(max) = self.budgets.max_loaded() else {
+            return 0;
+        };
+        if self.chunks.len() <= max {
+            return 0;
+        }
+        let distance = |chunk: ChunkPosition| {
+            viewers
+                .iter()
+                .map(|&(center, radius)| {
+                    (chunk.x - center.x).abs().max((chunk.z - center.z).abs()) - radius
+                })
+                .min()
+                .unwrap_or(i32::MAX)
+        };
+        let mut candidates: Vec<(ChunkPosition, i32)> = self
+            .chunks
+            .keys()
+            .map(|&chunk| (chunk, distance(chunk)))
+            .filter(|&(chunk, distance)| distance > 0 && !self.is_spawn_chunk(chunk))
+            .collect();
+        candidates.sort_by_key(|&(_, distance)| std::cmp::Reverse(distance));
+
+        let excess = self.chunks.len() - max;
+        let mut evicted = 0;
+        for (position, _) in candidates {
+            if evicted == excess {
+                break;
+            }
+            if self.save_before_eviction(position) {
+                self.unload_chunk(position);
+                evicted += 1;
+            }
+        }
+        self.chunk_loads.evicted += evicted as u64;
+        evicted
+    }
+
+    /// Save a chunk about to be evicted if it was modified
+    ///
+    /// Returns whether the chunk can be unloaded without losing changes.
+    /// Chunks a save is still writing stay loaded.
+    fn save_before_eviction(&mut self, position: ChunkPosition) -> bool {
+        if self.saving.contains(&position) {
+            return false;
+        }
+        self.store_entities(Some(position));
+        let Some(chunk) = self.chunks.get(&position) else {
+            return false;
+        };
+        if !chunk.is_modified() {
+            return true;
+        }
+        let Some(mut storage) = self.storage() else {
+            return false;
+        };
+        let saved = storage.save_chunk(chunk, false);
+        drop(storage);
+        match saved {
+            Ok(()) => {
+                if let Some(chunk) = self.chunks.get_mut(&position) {
+                    chunk.mark_saved();
+                }
+                true
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to save chunk {:?} before unloading: {}",
+                    position,
+                    e
+                );
+                false
+            }
+        }
+    }
+
+    /// Copy the armor stands and item frames into the entity data of the
+    /// loaded chunks they stand in, or only into the chunk at `only`
+    ///
+    /// Chunks whose entities did not change are left unmodified.
+    fn store_entities(&mut self, only: Option<ChunkPosition>) {
+        let mut by_chunk: HashMap<ChunkPosition, Vec<Compound>> = HashMap::new();
+        for entity in self.entities.entities() {
+            let position = entity_chunk(entity.position());
+            if only.is_none_or(|only| only == position)
+                && let Some(nbt) = decoration::save(entity)
+            {
+                by_chunk.entry(position).or_default().push(nbt);
+            }
+        }
+        for (position, chunk) in &mut self.chunks {
+            if only.is_some_and(|only| only != *position) {
+                continue;
+            }
+            let entities = by_chunk.remove(position).unwrap_or_default();
+            if chunk.entities() != entities.as_slice() {
+                chunk.set_entities(entities);
+            }
+        }
+    }
+
+    /// Whether a chunk is kept loaded around the world spawn
+    ///
+    /// Only the overworld has spawn chunks.
+    pub fn is_spawn_chunk(&self, position: ChunkPosition) -> bool {
+        self.dimension == Dimension::Overworld
+            && spawn::is_spawn_chunk(
+                self.spawn_position,
+                self.game_rules.spawn_chunk_radius,
+                position,
+            )
+    }
+
     /// Get a chunk if it's loaded
     pub fn get_chunk(&self, position: ChunkPosition) -> Option<&chunk::Chunk> {
         self.chunks.get(&position)
@@ -114,6 +993,65 @@ impl World {
         self.chunks.get_mut(&position)
     }
 
+    /// Get the chunk data packet of a chunk, loading the chunk if needed
+    ///
+    /// The packet is serialized and compressed once for the given
+    /// compression threshold and shared until the chunk is modified or
+    /// unloaded. Async code should use [`lookup_chunk_packet`](Self::lookup_chunk_packet)
+    /// to compress off the world lock.
+    pub fn chunk_packet(
+        &mut self,
+        position: ChunkPosition,
+        compression_threshold: Option<u32>,
+    ) -> Result<Arc<EncodedPacket>> {
+        match self.lookup_chunk_packet(position, compression_threshold)? {
+            ChunkPacket::Cached(packet) => Ok(packet),
+            ChunkPacket::Uncached { packet, revision } => {
+                let packet = Arc::new(EncodedPacket::encode(&packet, compression_threshold)?);
+                self.cache_chunk_packet(position, revision, Arc::clone(&packet));
+                Ok(packet)
+            }
+        }
+    }
+
+    /// Get the cached chunk data packet of a chunk, or the packet to frame for it
+    ///
+    /// The chunk is loaded if needed. Once framed, an uncached packet should
+    /// be handed to [`cache_chunk_packet`](Self::cache_chunk_packet).
+    pub fn lookup_chunk_packet(
+        &mut self,
+        position: ChunkPosition,
+        compression_threshold: Option<u32>,
+    ) -> Result<ChunkPacket> {
+        let revision = self.load_chunk(position).revision();
+        if let Some(cached) = self.chunk_packets.get(&position) {
+            if cached.revision == revision
+                && cached.packet.compression_threshold() == compression_threshold
+            {
+                return Ok(ChunkPacket::Cached(Arc::clone(&cached.packet)));
+            }
+        }
+
+        let packet = RawPacket::encode(&ChunkDataPacket::from_chunk(&self.chunks[&position])?)?;
+        Ok(ChunkPacket::Uncached { packet, revision })
+    }
+
+    /// Cache the framed chunk data packet of a chunk
+    ///
+    /// The packet is dropped if the chunk was modified or unloaded since
+    /// `revision` was looked up.
+    pub fn cache_chunk_packet(
+        &mut self,
+        position: ChunkPosition,
+        revision: u64,
+        packet: Arc<EncodedPacket>,
+    ) {
+        if self.get_chunk(position).map(chunk::Chunk::revision) == Some(revision) {
+            self.chunk_packets
+                .insert(position, CachedChunkPacket { revision, packet });
+        }
+    }
+
     /// Check if a chunk is loaded
     pub fn is_chunk_loaded(&self, position: ChunkPosition) -> bool {
         self.chunks.contains_key(&position)
@@ -129,6 +1067,11 @@ impl World {
         self.chunks.len()
     }
 
+    /// Get the number of chunks read and generated so far
+    pub fn chunk_loads(&self) -> ChunkLoadCounts {
+        self.chunk_loads
+    }
+
     /// Get the entity manager
     pub fn entities(&self) -> &EntityManager {
         &self.entities
@@ -154,6 +1097,14 @@ impl World {
 
     /// Set block at position
     pub fn set_block(&mut self, position: Position, block_id: u32) -> bool {
+        // Replacing either half of a bed breaks the whole bed
+        if let Some(bed) = self.beds.remove(&position) {
+            let other = bed.other_half(position);
+            if self.beds.remove(&other).is_some() {
+                self.set_block(other, 0);
+            }
+        }
+
         let chunk_pos = ChunkPosition::from_world_coords(position.x as f64, position.z as f64);
 
         // Load chunk if not loaded
@@ -164,21 +1115,567 @@ impl World {
             let local_z = (position.z - chunk_pos.world_z()) as usize;
             let y = position.y as usize;
 
-            chunk.set_block(local_x, y, local_z, block_id)
+            let changed = chunk.set_block(local_x, y, local_z, block_id);
+            if changed {
+                self.block_changes.push(position);
+                self.neighbour_updates.push(position);
+            }
+            changed
         } else {
             false
         }
     }
 
+    /// Take the blocks changed since the last call, grouped by chunk
+    /// section, for sending to players
+    pub fn take_block_changes(&mut self) -> Vec<SectionChanges> {
+        let section = |position: &Position| {
+            [position.x, position.y, position.z].map(|coordinate| coordinate.div_euclid(16))
+        };
+        let mut changes = std::mem::take(&mut self.block_changes);
+        changes.sort_by_key(|position| (section(position), position.x, position.y, position.z));
+        changes.dedup();
+
+        let mut sections: Vec<SectionChanges> = Vec::new();
+        for position in changes {
+            let block = (position, self.get_block(position).unwrap_or(0));
+            match sections.last_mut() {
+                Some(last) if last.section == section(&position) => last.blocks.push(block),
+                _ => sections.push(SectionChanges {
+                    section: section(&position),
+                    blocks: vec![block],
+                }),
+            }
+        }
+        sections
+    }
+
+    /// Break the block at a position, rolling its drops
+    ///
+    /// A crop on top of the block breaks with it, and so do the other parts
+    /// of a [multi-block](multiblock), without drops. Returns every broken
+    /// block, so nothing if the position holds air.
+    pub fn break_block(&mut self, position: Position) -> Vec<BrokenBlock> {
+        let Some(block) = self.get_block(position).filter(|&block| block != 0) else {
+            return Vec::new();
+        };
+        let parts = multiblock::other_parts(self, position);
+        self.set_block(position, 0);
+        let mut broken = vec![BrokenBlock {
+            position,
+            block,
+            drops: self.roll_drops(block),
+        }];
+        for part in parts {
+            let block = self.get_block(part).unwrap_or(0);
+            self.set_block(part, 0);
+            broken.push(BrokenBlock {
+                position: part,
+                block,
+                drops: Vec::new(),
+            });
+        }
+
+        let above = Position::new(position.x, position.y + 1, position.z);
+        if let Some(crop) = self
+            .get_block(above)
+            .filter(|&block| farming::Crop::from_block(block).is_some())
+        {
+            self.set_block(above, 0);
+            broken.push(BrokenBlock {
+                position: above,
+                block: crop,
+                drops: self.roll_drops(crop),
+            });
+        }
+        broken
+    }
+
+    /// Roll what a block drops when broken
+    fn roll_drops(&mut self, block: u32) -> Vec<ItemDrop> {
+        let mut drops = farming::drops(block, &mut self.random);
+        drops.extend(leaves::drops(block, &mut self.random));
+        if let Some(kind) = multiblock::MultiBlockKind::of(block) {
+            drops.push(ItemDrop::new(kind.item(), 1));
+        }
+        drops
+    }
+
+    /// Drop items in the middle of a block as item entities
+    pub fn drop_items(&mut self, position: Position, drops: Vec<ItemDrop>) {
+        let center = EntityPosition {
+            x: f64::from(position.x) + 0.5,
+            y: f64::from(position.y) + 0.25,
+            z: f64::from(position.z) + 0.5,
+        };
+        self.drop_items_at(center, drops);
+    }
+
+    /// Drop items at a position as item entities
+    pub fn drop_items_at(&mut self, position: EntityPosition, drops: Vec<ItemDrop>) {
+        for drop in drops {
+            let entity_id = self
+                .entities
+                .add_entity(|id| Box::new(ItemEntity::new(id, drop, position)));
+            self.spawned.push(entity_id);
+        }
+    }
+
+    /// Roll the loot of a mob that died and leave it where the mob was
+    fn mob_died(&mut self, mob: &MobEntity) {
+        let kill = KillContext {
+            by_player: mob.attacker().is_some(),
+            looting: 0,
+        };
+        let drops = loot::mob_drops(mob.kind(), kill, &mut self.random);
+        let experience = loot::mob_experience(mob.kind(), kill, &mut self.random);
+        let position = mob.position();
+        self.drop_items_at(position, drops.clone());
+        if experience > 0 {
+            let orb = self
+                .entities
+                .add_entity(|id| Box::new(ExperienceOrbEntity::new(id, experience, position)));
+            self.spawned.push(orb);
+        }
+        self.deaths.push(MobDeath {
+            entity_id: mob.entity_id(),
+            kind: mob.kind(),
+            position,
+            killer: mob.attacker(),
+            drops,
+            experience,
+        });
+    }
+
+    /// Take the mobs that died since the last call
+    pub fn take_deaths(&mut self) -> Vec<MobDeath> {
+        std::mem::take(&mut self.deaths)
+    }
+
+    /// Take the babies that grew up since the last call
+    pub fn take_grown_up(&mut self) -> Vec<EntityId> {
+        std::mem::take(&mut self.grown_up)
+    }
+
+    /// Take the IDs of entities the world added since the last call
+    pub fn take_spawned(&mut self) -> Vec<EntityId> {
+        std::mem::take(&mut self.spawned)
+    }
+
+    /// Take the IDs of entities that died or despawned since the last call
+    pub fn take_despawned(&mut self) -> Vec<EntityId> {
+        std::mem::take(&mut self.despawned)
+    }
+
+    /// Take the explosions of entities that blew up since the last call
+    pub fn take_explosions(&mut self) -> Vec<Explosion> {
+        std::mem::take(&mut self.explosions)
+    }
+
+    /// Make blocks with an ID react to random ticks
+    pub fn on_random_tick(&mut self, block_id: u32, handler: RandomTickHandler) {
+        self.random_tick_handlers.insert(block_id, handler);
+    }
+
+    /// Run a handler on the block at a position after a delay in ticks
+    pub fn schedule_tick(&mut self, position: Position, delay: u32, handler: ScheduledTickHandler) {
+        self.scheduled_ticks.push(ScheduledTick {
+            due: self.world_age + i64::from(delay),
+            position,
+            handler,
+        });
+    }
+
+    /// Play a block sound to nearby players
+    pub fn play_sound(&mut self, position: Position, sound: &'static str, pitch: f32) {
+        let seed = self.random.seed();
+        self.sounds.push(BlockSound {
+            position,
+            sound,
+            pitch,
+            seed,
+        });
+    }
+
+    /// Take the sounds played by blocks since the last call
+    pub fn take_sounds(&mut self) -> Vec<BlockSound> {
+        std::mem::take(&mut self.sounds)
+    }
+
     /// Update the world
-    pub fn update(&mut self, delta_time: f64) {
+    ///
+    /// Entities and blocks are only simulated in chunks within `area`.
+    pub fn update(&mut self, delta_time: f64, area: &SimulationArea) {
+        self.loads_this_tick = 0;
         // Update entities
-        self.entities.update_all(delta_time);
+        let removed = self.entities.update_where(delta_time, |position| {
+            area.contains(ChunkPosition::from_world_coords(position.x, position.z))
+        });
+        for entity in removed {
+            self.despawned.push(entity.entity_id());
+            if let Some(explosion) = entity.as_explosive().and_then(|e| e.detonation()) {
+                self.explosions.push(explosion);
+            }
+            if let Some(mob) = entity.as_mob() {
+                self.mob_died(mob);
+            }
+        }
+        let grown_up = self
+            .entities
+            .entities_mut()
+            .filter_map(|entity| entity.as_mob_mut())
+            .filter_map(|mob| mob.take_grew_up().then(|| mob.entity_id()));
+        self.grown_up.extend(grown_up);
+        let babies = breeding::breed(&mut self.entities);
+        self.spawned.extend(babies);
+        if self.difficulty == Difficulty::Peaceful {
+            self.remove_hostile_mobs();
+        }
+        gravity::land_falling_blocks(self);
+        let changed = std::mem::take(&mut self.neighbour_updates);
+        gravity::update_falling(self, &changed);
+        multiblock::update_support(self, &changed);
+        interact::update_power(self, &changed);
+        portal::update_portals(self, &changed);
+        region::tick_blocks(self, area, changed);
+
+        // Day/night cycle
+        self.world_age += 1;
+        if self.game_rules.do_daylight_cycle {
+            self.time_of_day += 1;
+        }
+        self.run_scheduled_ticks();
+
+        // Weather
+        if self.weather != Weather::Clear {
+            self.weather_duration -= 1;
+            if self.weather_duration <= 0 {
+                self.weather = Weather::Clear;
+                tracing::debug!("Weather in {} cleared", self.name);
+            }
+        }
 
         // TODO: Add other world updates like:
         // - Block updates (redstone, water flow, etc.)
-        // - Weather
-        // - Day/night cycle
         // - Chunk generation/unloading based on player positions
     }
+
+    /// Remove hostile mobs, which do not exist in peaceful
+    fn remove_hostile_mobs(&mut self) {
+        let hostile: Vec<EntityId> = self
+            .entities
+            .entities()
+            .filter(
+                |entity| matches!(entity.entity_type(), EntityType::Mob(mob) if mob.is_hostile()),
+            )
+            .map(|entity| entity.entity_id())
+            .collect();
+        for entity_id in hostile {
+            self.entities.remove_entity(entity_id);
+            self.despawned.push(entity_id);
+        }
+    }
+
+    /// Run the handlers of block ticks that are due
+    fn run_scheduled_ticks(&mut self) {
+        let age = self.world_age;
+        let (due, pending) = std::mem::take(&mut self.scheduled_ticks)
+            .into_iter()
+            .partition(|tick| tick.due <= age);
+        self.scheduled_ticks = pending;
+        for tick in due {
+            if let Some(block) = self.get_block(tick.position) {
+                (tick.handler)(self, tick.position, block);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::packets::Packet;
+
+    #[test]
+    fn test_block_changes_by_section() {
+        let mut world = World::new("test".to_string(), 0);
+        for position in [
+            Position::new(17, 70, 3),
+            Position::new(-1, 70, 3),
+            Position::new(16, 79, 15),
+            Position::new(17, 70, 3),
+        ] {
+            world.set_block(position, 1);
+        }
+        world.set_block(Position::new(17, 70, 3), 2);
+
+        let changes = world.take_block_changes();
+        assert_eq!(
+            changes,
+            [
+                SectionChanges {
+                    section: [-1, 4, 0],
+                    blocks: vec![(Position::new(-1, 70, 3), 1)],
+                },
+                SectionChanges {
+                    section: [1, 4, 0],
+                    blocks: vec![
+                        (Position::new(16, 79, 15), 1),
+                        (Position::new(17, 70, 3), 2)
+                    ],
+                },
+            ]
+        );
+        assert!(world.take_block_changes().is_empty());
+    }
+
+    #[test]
+    fn test_daylight_cycle_rule() {
+        let mut world = World::new("test".to_string(), 0);
+        let area = SimulationArea::new(0);
+        world.update(0.05, &area);
+        assert_eq!((world.world_age(), world.time_of_day()), (1, 1));
+
+        world.game_rules_mut().do_daylight_cycle = false;
+        world.update(0.05, &area);
+        assert_eq!((world.world_age(), world.time_of_day()), (2, 1));
+    }
+
+    #[test]
+    fn test_chunk_packet_cache() {
+        let mut world = World::new("test".to_string(), 0);
+        let position = ChunkPosition::new(2, -3);
+        let first = world.chunk_packet(position, Some(256)).unwrap();
+        let again = world.chunk_packet(position, Some(256)).unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+
+        let packet = first.decode().unwrap();
+        let chunk =
+            ChunkDataPacket::decode(crate::protocol::ConnectionState::Play, &packet.data).unwrap();
+        assert_eq!((chunk.chunk_x, chunk.chunk_z), (2, -3));
+        // Block count, then a palette of bedrock and stone with 4 bits per block
+        assert_eq!(chunk.data[..5], [0x10, 0x00, 4, 2, 7]);
+
+        world.set_block(Position::new(32, 100, -48), 1);
+        let changed = world.chunk_packet(position, Some(256)).unwrap();
+        assert!(!Arc::ptr_eq(&first, &changed));
+        assert_ne!(first.as_bytes(), changed.as_bytes());
+
+        let uncompressed = world.chunk_packet(position, None).unwrap();
+        assert_eq!(uncompressed.decode().unwrap(), changed.decode().unwrap());
+
+        // A packet framed while the chunk changed is not cached
+        let other = ChunkPosition::new(0, 0);
+        let ChunkPacket::Uncached { packet, revision } =
+            world.lookup_chunk_packet(other, None).unwrap()
+        else {
+            unreachable!("chunk was never framed");
+        };
+        world.set_block(Position::new(0, 100, 0), 1);
+        let packet = Arc::new(EncodedPacket::encode(&packet, None).unwrap());
+        world.cache_chunk_packet(other, revision, packet);
+        assert!(matches!(
+            world.lookup_chunk_packet(other, None).unwrap(),
+            ChunkPacket::Uncached { .. }
+        ));
+    }
+
+    #[test]
+    fn test_random_ticks_only_in_simulation_distance() {
+        fn crack(tick: &mut region::RegionTick<'_>, position: Position, _block: u32) {
+            tick.set_block(position, 5);
+        }
+        let stone_left = |world: &World, chunk: ChunkPosition| {
+            (0..16).any(|x| {
+                (1..60).any(|y| {
+                    (0..16).any(|z| {
+                        let position = Position::new(chunk.world_x() + x, y, chunk.world_z() + z);
+                        world.get_block(position) == Some(1)
+                    })
+                })
+            })
+        };
+
+        let mut world = World::new("test".to_string(), 0);
+        world.on_random_tick(1, crack);
+        world.game_rules_mut().random_tick_speed = 100_000;
+        let (near, far) = (ChunkPosition::new(0, 0), ChunkPosition::new(6, 0));
+        world.load_chunk(near);
+        world.load_chunk(far);
+
+        world.update(0.05, &SimulationArea::new(4));
+        assert!(stone_left(&world, near));
+
+        let mut area = SimulationArea::new(4);
+        area.add_player(ChunkPosition::new(1, 1));
+        world.update(0.05, &area);
+        assert!(!stone_left(&world, near));
+        assert!(stone_left(&world, far));
+    }
+
+    #[test]
+    fn test_chunk_budgets() {
+        let mut world = World::new("budgets".to_string(), 0).with_chunk_budgets(ChunkBudgets {
+            loads_per_tick: 2,
+            queued_per_player: 0,
+            max_loaded: 30,
+        });
+        let far = ChunkPosition::new(10, 0);
+        world.load_chunk(far);
+        world.load_chunk(ChunkPosition::new(11, 0));
+        assert!(!world.can_load_chunk(ChunkPosition::new(12, 0)));
+        assert!(world.can_load_chunk(far));
+        assert_eq!(world.chunk_loads().deferred, 1);
+        world.update(0.05, &SimulationArea::new(0));
+        assert!(world.can_load_chunk(ChunkPosition::new(12, 0)));
+
+        // Spawn chunks and chunks in view stay; the farthest others go first
+        spawn::load_spawn_chunks(&mut world);
+        for x in 4..9 {
+            world.load_chunk(ChunkPosition::new(x, 0));
+        }
+        assert_eq!(world.loaded_chunk_count(), 32);
+        let viewer = [(ChunkPosition::new(5, 0), 1)];
+        assert_eq!(world.evict_chunks(&viewer), 2);
+        assert!(!world.is_chunk_loaded(far));
+        assert!(!world.is_chunk_loaded(ChunkPosition::new(11, 0)));
+        assert!(world.is_chunk_loaded(ChunkPosition::new(8, 0)));
+        assert_eq!(world.evict_chunks(&viewer), 0);
+    }
+
+    #[test]
+    fn test_save_written_outside_the_world() {
+        let directory = std::env::temp_dir().join(format!("obsidium-{}", uuid::Uuid::new_v4()));
+        let storage =
+            WorldStorage::new(&directory, Arc::new(BlockRegistry::new())).with_sync_writes(false);
+        let mut world = World::new("save".to_string(), 0)
+            .with_chunk_budgets(ChunkBudgets {
+                loads_per_tick: 0,
+                queued_per_player: 0,
+                max_loaded: 1,
+            })
+            .with_storage(storage)
+            .unwrap();
+        let (first, second) = (ChunkPosition::new(20, 0), ChunkPosition::new(21, 0));
+        world.load_chunk(first);
+        world.load_chunk(second);
+        world.set_block(Position::new(320, 100, 0), 1);
+        world.set_block(Position::new(336, 100, 0), 1);
+
+        // Chunks being written stay loaded, even when changed meanwhile
+        let save = world.begin_save(false).unwrap();
+        world.set_block(Position::new(320, 101, 0), 2);
+        assert_eq!(world.evict_chunks(&[]), 0);
+        let outcome = std::thread::spawn(move || save.write()).join().unwrap();
+        assert_eq!(world.finish_save(outcome).unwrap(), 2);
+        assert!(world.get_chunk(first).unwrap().is_modified());
+        assert!(!world.get_chunk(second).unwrap().is_modified());
+
+        // The newer change is saved when the chunk is evicted
+        assert_eq!(world.evict_chunks(&[]), 2);
+        world.load_chunk(first);
+        assert_eq!(world.get_block(Position::new(320, 101, 0)), Some(2));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_decorations_saved_with_their_chunk() {
+        use crate::game::entity::CustomName;
+        use crate::game::entity::decoration::{ArmorStandEntity, ArmorStandPose, ItemFrameEntity};
+        use crate::game::inventory::{EquipmentSlot, ItemStack};
+        use crate::protocol::types::JsonTextComponent;
+
+        let directory = std::env::temp_dir().join(format!("obsidium-{}", uuid::Uuid::new_v4()));
+        let storage = || {
+            WorldStorage::new(&directory, Arc::new(BlockRegistry::new())).with_sync_writes(false)
+        };
+        let mut world = World::new("decorations".to_string(), 0)
+            .with_storage(storage())
+            .unwrap();
+        let chunk = ChunkPosition::new(20, -1);
+        world.load_chunk(chunk);
+
+        let position = EntityPosition {
+            x: 325.5,
+            y: 100.0,
+            z: -2.5,
+        };
+        let pose = ArmorStandPose {
+            head: [10.0, 20.0, 30.0],
+            ..ArmorStandPose::default()
+        };
+        let name = CustomName::new(JsonTextComponent(r#"{"text":"Bob"}"#.to_string()));
+        let id = world
+            .entities
+            .add_entity(|id| Box::new(ArmorStandEntity::new(id, position, 90.0)));
+        let entity = world.entities.get_entity_mut(id).unwrap();
+        entity.set_custom_name(Some(name.clone()));
+        let stand_uuid = entity.uuid();
+        let stand = entity.as_armor_stand_mut().unwrap();
+        stand.set_arms(true);
+        stand.set_small(true);
+        stand.set_pose(pose);
+        stand.set_equipment(EquipmentSlot::Head, Some(ItemStack::new(3, 1)));
+        stand.set_equipment(EquipmentSlot::MainHand, Some(ItemStack::new(4, 1)));
+        let block = Position::new(326, 101, -3);
+        let id = world
+            .entities
+            .add_entity(|id| Box::new(ItemFrameEntity::new(id, block, 3)));
+        let entity = world.entities.get_entity_mut(id).unwrap();
+        let frame_uuid = entity.uuid();
+        let frame = entity.as_item_frame_mut().unwrap();
+        frame.put(ItemStack::new(5, 1));
+        frame.rotate();
+        frame.rotate();
+
+        let save = world.begin_save(false).unwrap();
+        let outcome = save.write();
+        world.finish_save(outcome).unwrap();
+        assert_eq!(world.get_chunk(chunk).unwrap().entities().len(), 2);
+
+        let mut world = World::new("decorations".to_string(), 0)
+            .with_storage(storage())
+            .unwrap();
+        world.load_chunk(chunk);
+        assert_eq!(world.take_spawned().len(), 2);
+        let entities = world.entities();
+        let stand = entities
+            .entities()
+            .find_map(|entity| entity.as_armor_stand())
+            .unwrap();
+        assert_eq!(stand.uuid(), stand_uuid);
+        assert_eq!(stand.custom_name(), Some(&name));
+        assert_eq!(stand.rotation().yaw, 90.0);
+        assert_eq!(stand.position().z, -2.5);
+        assert!(stand.has_arms() && stand.is_small());
+        assert_eq!(stand.pose(), &pose);
+        assert_eq!(
+            stand.equipment(EquipmentSlot::Head),
+            Some(ItemStack::new(3, 1))
+        );
+        assert_eq!(
+            stand.equipment(EquipmentSlot::MainHand),
+            Some(ItemStack::new(4, 1))
+        );
+        assert_eq!(stand.equipment(EquipmentSlot::Feet), None);
+        let frame = entities
+            .entities()
+            .find_map(|entity| entity.as_item_frame())
+            .unwrap();
+        assert_eq!(frame.uuid(), frame_uuid);
+        assert_eq!(frame.facing(), 3);
+        assert_eq!(frame.item(), Some(ItemStack::new(5, 1)));
+        assert_eq!(frame.item_rotation(), 2);
+
+        // Unloading the chunk takes its decorations with it, and loading
+        // it again brings them back once
+        world.unload_chunk(chunk);
+        assert_eq!(world.take_despawned().len(), 2);
+        assert_eq!(world.entities().entities().count(), 0);
+        world.load_chunk(chunk);
+        assert_eq!(world.entities().entities().count(), 2);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
 }
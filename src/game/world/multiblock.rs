@@ -0,0 +1,345 @@
+//! Blocks made of more than one part
+//!
+//! Doors and tall grass are two blocks tall, beds two blocks long, and an
+//! extended piston is its base plus the head in front of it. Placing one
+//! places every part, as long as each part has room and the parts resting
+//! on the ground have something below them. Breaking any part breaks the
+//! others without drops, and a multi-block whose support is removed breaks
+//! on the next tick, dropping its item once.
+//!
+//! Tall grass and piston parts have their own block IDs, registered in the
+//! [`BlockRegistry`](super::registry::BlockRegistry) with their state in
+//! the name, such as `minecraft:piston_head[facing=north]`. Pistons do not
+//! extend or retract on their own yet.
+
+use super::bed::BedError;
+use super::farming::WATER;
+use super::interact::{Face, Interactive, InteractiveState};
+use super::{Direction, World};
+use crate::game::loot::ItemDrop;
+use crate::protocol::types::Position;
+use thiserror::Error;
+
+/// Block ID of a bed placed from an item
+pub const BED: u32 = 8;
+
+/// Block ID of the lower half of tall grass
+pub const TALL_GRASS: u32 = 194;
+
+/// Block ID of the upper half of tall grass
+pub const TALL_GRASS_UPPER: u32 = 195;
+
+/// Block ID of the first extended piston base, facing north; the other
+/// facings follow in [`FACINGS`] order
+pub const EXTENDED_PISTON: u32 = 196;
+
+/// Block ID of the first piston head, facing north; the other facings
+/// follow in [`FACINGS`] order
+pub const PISTON_HEAD: u32 = 200;
+
+/// Facings in the order piston states are numbered
+pub const FACINGS: [Direction; 4] = [
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
+/// Block IDs tall grass grows on
+const GRASS_SOIL: [u32; 2] = [2, 3];
+
+/// Reasons a multi-block cannot be placed
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceError {
+    /// One of the parts is not free
+    #[error("There is no room for the block")]
+    Obstructed,
+    /// A part resting on the ground has nothing suitable below it
+    #[error("The block needs something to stand on")]
+    Unsupported,
+}
+
+impl From<BedError> for PlaceError {
+    fn from(error: BedError) -> Self {
+        match error {
+            BedError::Obstructed => PlaceError::Obstructed,
+            BedError::Unsupported => PlaceError::Unsupported,
+        }
+    }
+}
+
+/// Kinds of blocks made of more than one part
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiBlockKind {
+    /// Oak door, a lower and an upper half
+    Door,
+    /// Bed, a foot and a head
+    Bed,
+    /// Tall grass, a lower and an upper half
+    TallGrass,
+    /// Extended piston, the base and the head in front of it
+    Piston,
+}
+
+impl MultiBlockKind {
+    /// Kind of multi-block an item places, by item name
+    ///
+    /// Piston items place a retracted piston, which is a single block.
+    pub fn from_item(name: &str) -> Option<Self> {
+        match name {
+            "minecraft:oak_door" => Some(MultiBlockKind::Door),
+            "minecraft:red_bed" => Some(MultiBlockKind::Bed),
+            "minecraft:tall_grass" => Some(MultiBlockKind::TallGrass),
+            _ => None,
+        }
+    }
+
+    /// Kind of multi-block a block is part of, by block ID
+    ///
+    /// Beds are tracked by the world rather than their block ID, so this
+    /// is [`MultiBlockKind::Bed`] for any bed block.
+    pub fn of(block: u32) -> Option<Self> {
+        match block {
+            BED => Some(MultiBlockKind::Bed),
+            TALL_GRASS | TALL_GRASS_UPPER => Some(MultiBlockKind::TallGrass),
+            block if (EXTENDED_PISTON..PISTON_HEAD + 4).contains(&block) => {
+                Some(MultiBlockKind::Piston)
+            }
+            _ => InteractiveState::from_block(block)
+                .filter(|state| state.kind == Interactive::OakDoor)
+                .map(|_| MultiBlockKind::Door),
+        }
+    }
+
+    /// Item dropped when the multi-block breaks
+    pub fn item(self) -> &'static str {
+        match self {
+            MultiBlockKind::Door => "minecraft:oak_door",
+            MultiBlockKind::Bed => "minecraft:red_bed",
+            MultiBlockKind::TallGrass => "minecraft:tall_grass",
+            MultiBlockKind::Piston => "minecraft:piston",
+        }
+    }
+
+    /// Parts of the multi-block placed at `origin` facing `facing`, with
+    /// their block IDs, bottom parts first
+    fn parts(self, origin: Position, facing: Direction) -> Vec<(Position, u32)> {
+        let above = Position::new(origin.x, origin.y + 1, origin.z);
+        let facing_index = FACINGS.iter().position(|&f| f == facing).unwrap_or(0) as u32;
+        match self {
+            MultiBlockKind::Door => {
+                let door = |upper| InteractiveState {
+                    kind: Interactive::OakDoor,
+                    facing,
+                    upper,
+                    face: Face::Wall,
+                    open: false,
+                    powered: false,
+                };
+                vec![(origin, door(false).block()), (above, door(true).block())]
+            }
+            MultiBlockKind::Bed => vec![(origin, BED), (facing.relative(origin), BED)],
+            MultiBlockKind::TallGrass => vec![(origin, TALL_GRASS), (above, TALL_GRASS_UPPER)],
+            MultiBlockKind::Piston => vec![
+                (origin, EXTENDED_PISTON + facing_index),
+                (facing.relative(origin), PISTON_HEAD + facing_index),
+            ],
+        }
+    }
+
+    /// Whether a block can hold up a part of the multi-block resting on it
+    fn rests_on(self, block: u32) -> bool {
+        match self {
+            MultiBlockKind::TallGrass => GRASS_SOIL.contains(&block),
+            MultiBlockKind::Piston => true,
+            MultiBlockKind::Door | MultiBlockKind::Bed => {
+                block != 0 && block != WATER && Self::of(block).is_none()
+            }
+        }
+    }
+}
+
+/// Place a multi-block with its first part at `origin`, facing `facing`
+///
+/// Every part needs air where it goes, and every part not resting on
+/// another part needs a block below that holds it up.
+pub fn place(
+    world: &mut World,
+    kind: MultiBlockKind,
+    origin: Position,
+    facing: Direction,
+) -> Result<(), PlaceError> {
+    if kind == MultiBlockKind::Bed {
+        return Ok(world.place_bed(origin, facing, BED)?);
+    }
+    let parts = kind.parts(origin, facing);
+    if parts
+        .iter()
+        .any(|&(position, _)| world.get_block(position) != Some(0))
+    {
+        return Err(PlaceError::Obstructed);
+    }
+    let supported = parts.iter().all(|&(position, _)| {
+        let below = Position::new(position.x, position.y - 1, position.z);
+        parts.iter().any(|&(part, _)| part == below)
+            || world
+                .get_block(below)
+                .is_some_and(|block| kind.rests_on(block))
+    });
+    if !supported {
+        return Err(PlaceError::Unsupported);
+    }
+    for (position, block) in parts {
+        world.set_block(position, block);
+    }
+    Ok(())
+}
+
+/// Positions of the other parts of the multi-block with a part at
+/// `position`, or nothing for other blocks
+pub fn other_parts(world: &World, position: Position) -> Vec<Position> {
+    let Some(block) = world.get_block(position) else {
+        return Vec::new();
+    };
+    let other = match MultiBlockKind::of(block) {
+        Some(MultiBlockKind::Bed) => world.bed_at(position).map(|bed| bed.other_half(position)),
+        Some(MultiBlockKind::Door) => InteractiveState::from_block(block).map(|state| {
+            let dy = if state.upper { -1 } else { 1 };
+            Position::new(position.x, position.y + dy, position.z)
+        }),
+        Some(MultiBlockKind::TallGrass) => {
+            let dy = if block == TALL_GRASS { 1 } else { -1 };
+            Some(Position::new(position.x, position.y + dy, position.z))
+        }
+        Some(MultiBlockKind::Piston) => {
+            let facing = FACINGS[((block - EXTENDED_PISTON) % 4) as usize];
+            if block < PISTON_HEAD {
+                Some(facing.relative(position))
+            } else {
+                Some(facing.opposite().relative(position))
+            }
+        }
+        None => None,
+    };
+    // A part whose partner is gone is left on its own
+    other
+        .filter(|&other| {
+            world
+                .get_block(other)
+                .and_then(MultiBlockKind::of)
+                .is_some_and(|kind| Some(kind) == MultiBlockKind::of(block))
+        })
+        .into_iter()
+        .collect()
+}
+
+/// Break multi-blocks that lost the block holding them up, dropping their
+/// item once
+pub(super) fn update_support(world: &mut World, changed: &[Position]) {
+    let mut unsupported = Vec::new();
+    for position in changed {
+        let above = Position::new(position.x, position.y + 1, position.z);
+        let Some(kind) = world.get_block(above).and_then(MultiBlockKind::of) else {
+            continue;
+        };
+        let rests_on_part = other_parts(world, above).contains(position);
+        let held_up = world
+            .get_block(*position)
+            .is_some_and(|block| kind.rests_on(block));
+        if !rests_on_part && !held_up && kind != MultiBlockKind::Piston {
+            unsupported.push((above, kind));
+        }
+    }
+    for (position, kind) in unsupported {
+        let parts = other_parts(world, position);
+        if world.get_block(position).and_then(MultiBlockKind::of) != Some(kind) {
+            continue;
+        }
+        for part in parts.into_iter().chain([position]) {
+            world.set_block(part, 0);
+        }
+        world.drop_items(position, vec![ItemDrop::new(kind.item(), 1)]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::simulation::SimulationArea;
+
+    fn world_with_floor() -> (World, Position) {
+        let mut world = World::new("test".to_string(), 0);
+        let origin = Position::new(0, 100, 0);
+        for x in -2..=2 {
+            for z in -2..=2 {
+                world.set_block(Position::new(x, 99, z), 2);
+                for y in 100..103 {
+                    world.set_block(Position::new(x, y, z), 0);
+                }
+            }
+        }
+        (world, origin)
+    }
+
+    #[test]
+    fn test_placing_both_halves() {
+        let (mut world, origin) = world_with_floor();
+        let above = Position::new(0, 101, 0);
+        assert_eq!(
+            place(&mut world, MultiBlockKind::Door, origin, Direction::North),
+            Ok(())
+        );
+        let upper = InteractiveState::from_block(world.get_block(above).unwrap()).unwrap();
+        assert!(upper.upper && upper.kind == Interactive::OakDoor);
+        assert_eq!(other_parts(&world, above), vec![origin]);
+        assert_eq!(
+            place(
+                &mut world,
+                MultiBlockKind::TallGrass,
+                origin,
+                Direction::North
+            ),
+            Err(PlaceError::Obstructed)
+        );
+
+        let east = Position::new(1, 100, 0);
+        world.set_block(Position::new(1, 99, 0), 0);
+        assert_eq!(
+            place(
+                &mut world,
+                MultiBlockKind::TallGrass,
+                east,
+                Direction::North
+            ),
+            Err(PlaceError::Unsupported)
+        );
+        assert_eq!(
+            place(&mut world, MultiBlockKind::Piston, east, Direction::South),
+            Ok(())
+        );
+        assert_eq!(other_parts(&world, Position::new(1, 100, 1)), vec![east]);
+    }
+
+    #[test]
+    fn test_breaking_together() {
+        let (mut world, origin) = world_with_floor();
+        place(
+            &mut world,
+            MultiBlockKind::TallGrass,
+            origin,
+            Direction::North,
+        )
+        .unwrap();
+        let broken = world.break_block(Position::new(0, 101, 0));
+        assert_eq!(broken.len(), 2);
+        assert_eq!(world.get_block(origin), Some(0));
+
+        place(&mut world, MultiBlockKind::Door, origin, Direction::East).unwrap();
+        world.take_spawned();
+        world.set_block(Position::new(0, 99, 0), 0);
+        world.update(0.05, &SimulationArea::new(0));
+        assert_eq!(world.get_block(origin), Some(0));
+        assert_eq!(world.get_block(Position::new(0, 101, 0)), Some(0));
+        assert_eq!(world.take_spawned().len(), 1);
+    }
+}
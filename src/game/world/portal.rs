@@ -0,0 +1,484 @@
+//! Nether portals
+//!
+//! Using flint and steel inside an empty obsidian frame fills it with
+//! portal blocks, as long as the frame is between 2x3 and 21x21 blocks
+//! inside. Portal blocks break as soon as the frame or portal around them
+//! is broken. Fire is not modelled, so the frame is lit directly.
+//!
+//! Entities standing in a portal travel through it once their
+//! [`PortalTimer`] runs out. They arrive at the nearest portal around the
+//! matching coordinates of the other dimension, or at a new portal built
+//! there if none is found. The chunks searched are loaded first, so portals
+//! in saved chunks nobody is near are found too.
+
+use super::chunk::{CHUNK_HEIGHT, CHUNK_SIZE};
+use super::dimension::Dimension;
+use super::{ChunkPosition, World};
+use crate::protocol::types::Position;
+
+/// Block ID of obsidian
+pub const OBSIDIAN: u32 = 187;
+
+/// Block ID of a nether portal spanning the X axis
+pub const NETHER_PORTAL_X: u32 = 188;
+
+/// Block ID of a nether portal spanning the Z axis
+pub const NETHER_PORTAL_Z: u32 = 189;
+
+/// Block ID of netherrack
+pub const NETHERRACK: u32 = 190;
+
+/// Ticks a player outside creative mode stands in a portal before
+/// travelling
+pub const PORTAL_WAIT: u32 = 80;
+
+/// Ticks after travelling before a portal takes an entity again
+pub const PORTAL_COOLDOWN: u32 = 10;
+
+/// Smallest inside width of a frame
+const MIN_WIDTH: i32 = 2;
+
+/// Largest inside width or height of a frame
+const MAX_SIZE: i32 = 21;
+
+/// Smallest inside height of a frame
+const MIN_HEIGHT: i32 = 3;
+
+/// Blocks around the destination searched for a portal in the overworld
+const OVERWORLD_SEARCH_RADIUS: i32 = 128;
+
+/// Blocks around the destination searched for a portal in the nether
+const NETHER_SEARCH_RADIUS: i32 = 16;
+
+/// Horizontal axis a portal spans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// Along the X axis
+    X,
+    /// Along the Z axis
+    Z,
+}
+
+impl Axis {
+    /// Get the axis of a portal block
+    pub fn of(block: u32) -> Option<Self> {
+        match block {
+            NETHER_PORTAL_X => Some(Axis::X),
+            NETHER_PORTAL_Z => Some(Axis::Z),
+            _ => None,
+        }
+    }
+
+    /// Block ID of a portal spanning this axis
+    pub fn portal(self) -> u32 {
+        match self {
+            Axis::X => NETHER_PORTAL_X,
+            Axis::Z => NETHER_PORTAL_Z,
+        }
+    }
+
+    /// Position `distance` blocks away along this axis
+    fn step(self, position: Position, distance: i32) -> Position {
+        match self {
+            Axis::X => Position::new(position.x + distance, position.y, position.z),
+            Axis::Z => Position::new(position.x, position.y, position.z + distance),
+        }
+    }
+
+    /// Position `distance` blocks away across this axis
+    fn across(self, position: Position, distance: i32) -> Position {
+        match self {
+            Axis::X => Position::new(position.x, position.y, position.z + distance),
+            Axis::Z => Position::new(position.x + distance, position.y, position.z),
+        }
+    }
+}
+
+/// Whether a block is a nether portal
+pub fn is_portal(block: u32) -> bool {
+    Axis::of(block).is_some()
+}
+
+/// The inside of an obsidian frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortalFrame {
+    /// Axis the frame spans
+    pub axis: Axis,
+    /// Lowest inside block nearest the negative end of the axis
+    pub corner: Position,
+    /// Inside width
+    pub width: i32,
+    /// Inside height
+    pub height: i32,
+}
+
+impl PortalFrame {
+    /// Find the complete, empty frame around a block spanning an axis
+    pub fn find(world: &World, inside: Position, axis: Axis) -> Option<Self> {
+        let below = |position: Position| Position::new(position.x, position.y - 1, position.z);
+        if !is_empty(world, inside) {
+            return None;
+        }
+        let mut corner = inside;
+        while is_empty(world, below(corner)) {
+            corner = below(corner);
+            if inside.y - corner.y >= MAX_SIZE {
+                return None;
+            }
+        }
+        let mut left = 0;
+        while left < MAX_SIZE && is_empty(world, axis.step(corner, -left - 1)) {
+            left += 1;
+        }
+        corner = axis.step(corner, -left);
+        let mut width = 0;
+        while width <= MAX_SIZE
+            && is_empty(world, axis.step(corner, width))
+            && is_obsidian(world, below(axis.step(corner, width)))
+        {
+            width += 1;
+        }
+        if !(MIN_WIDTH..=MAX_SIZE).contains(&width)
+            || !is_obsidian(world, axis.step(corner, -1))
+            || !is_obsidian(world, axis.step(corner, width))
+        {
+            return None;
+        }
+        let height = (1..=MAX_SIZE)
+            .find(|&height| {
+                let row = Position::new(corner.x, corner.y + height, corner.z);
+                (0..width).all(|i| is_obsidian(world, axis.step(row, i)))
+            })
+            .filter(|&height| height >= MIN_HEIGHT)?;
+        let frame = Self {
+            axis,
+            corner,
+            width,
+            height,
+        };
+        let sides_closed = (0..height).all(|dy| {
+            let row = Position::new(corner.x, corner.y + dy, corner.z);
+            is_obsidian(world, axis.step(row, -1)) && is_obsidian(world, axis.step(row, width))
+        });
+        (sides_closed && frame.inside().all(|position| is_empty(world, position))).then_some(frame)
+    }
+
+    /// Every block inside the frame
+    pub fn inside(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.height).flat_map(move |dy| {
+            let row = Position::new(self.corner.x, self.corner.y + dy, self.corner.z);
+            (0..self.width).map(move |i| self.axis.step(row, i))
+        })
+    }
+}
+
+/// Fill the frame around an empty block with portal blocks
+///
+/// Returns whether a complete frame was found.
+pub fn light(world: &mut World, inside: Position) -> bool {
+    let Some(frame) = [Axis::X, Axis::Z]
+        .into_iter()
+        .find_map(|axis| PortalFrame::find(world, inside, axis))
+    else {
+        return false;
+    };
+    let positions: Vec<Position> = frame.inside().collect();
+    for position in positions {
+        world.set_block(position, frame.axis.portal());
+    }
+    world.play_sound(inside, "minecraft:block.portal.trigger", 1.0);
+    true
+}
+
+/// Break portal blocks next to changed blocks that are no longer held in
+/// place by their frame
+///
+/// Breaking a portal block changes it too, so a broken portal falls apart
+/// over the following ticks.
+pub(super) fn update_portals(world: &mut World, changed: &[Position]) {
+    let mut to_update: Vec<Position> = changed.iter().copied().flat_map(neighbours).collect();
+    to_update.sort_by_key(|position| (position.x, position.y, position.z));
+    to_update.dedup();
+    for position in to_update {
+        let Some(axis) = world.get_block(position).and_then(Axis::of) else {
+            continue;
+        };
+        let held = [
+            Position::new(position.x, position.y + 1, position.z),
+            Position::new(position.x, position.y - 1, position.z),
+            axis.step(position, 1),
+            axis.step(position, -1),
+        ]
+        .into_iter()
+        .all(|side| {
+            world
+                .get_block(side)
+                .is_some_and(|block| block == OBSIDIAN || block == axis.portal())
+        });
+        if !held {
+            world.set_block(position, 0);
+        }
+    }
+}
+
+/// Counts down to an entity travelling through a portal it stands in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PortalTimer {
+    /// Ticks spent in the portal so far
+    ticks: u32,
+    /// Ticks until a portal takes the entity again
+    cooldown: u32,
+}
+
+impl PortalTimer {
+    /// Advance the timer by a tick
+    ///
+    /// Standing in a portal during the cooldown keeps the cooldown going, so
+    /// entities arriving in a portal do not travel straight back. Returns
+    /// whether the entity has waited `wait` ticks and travels now.
+    pub fn tick(&mut self, in_portal: bool, wait: u32) -> bool {
+        if !in_portal {
+            self.ticks = 0;
+            self.cooldown = self.cooldown.saturating_sub(1);
+            return false;
+        }
+        if self.cooldown > 0 {
+            self.cooldown = PORTAL_COOLDOWN;
+            return false;
+        }
+        self.ticks += 1;
+        if self.ticks < wait {
+            return false;
+        }
+        self.ticks = 0;
+        self.cooldown = PORTAL_COOLDOWN;
+        true
+    }
+}
+
+/// Find where an entity leaving a portal in another dimension at some
+/// coordinates arrives in a world
+///
+/// The entity arrives at the nearest portal around the scaled coordinates;
+/// if there is none, a portal is built there. Chunks around the coordinates
+/// are loaded, or generated, before they are searched. Returns the lowest
+/// portal block the entity stands in.
+pub fn destination(world: &mut World, from: Dimension, x: f64, y: f64, z: f64) -> Position {
+    let to = world.dimension();
+    let (x, z) = from.scale_to(to, x, z);
+    let target = Position::new(x.floor() as i32, y.floor() as i32, z.floor() as i32);
+    let radius = match to {
+        Dimension::Overworld => OVERWORLD_SEARCH_RADIUS,
+        Dimension::Nether => NETHER_SEARCH_RADIUS,
+    };
+    let chunk = |coordinate: i32| coordinate.div_euclid(CHUNK_SIZE as i32);
+    for chunk_x in chunk(target.x - radius)..=chunk(target.x + radius) {
+        for chunk_z in chunk(target.z - radius)..=chunk(target.z + radius) {
+            world.load_chunk(ChunkPosition::new(chunk_x, chunk_z));
+        }
+    }
+    find_portal(world, target, radius).unwrap_or_else(|| build_portal(world, target))
+}
+
+/// Find the portal block nearest a position among loaded chunks, moving
+/// down to the bottom of its portal
+pub fn find_portal(world: &World, center: Position, radius: i32) -> Option<Position> {
+    let distance = |position: Position| {
+        let (dx, dy, dz) = (
+            i64::from(position.x - center.x),
+            i64::from(position.y - center.y),
+            i64::from(position.z - center.z),
+        );
+        dx * dx + dy * dy + dz * dz
+    };
+    let mut nearest: Option<Position> = None;
+    for (chunk_position, chunk) in world.loaded_chunks() {
+        let (base_x, base_z) = (chunk_position.world_x(), chunk_position.world_z());
+        let chunk_end_x = base_x + CHUNK_SIZE as i32 - 1;
+        let chunk_end_z = base_z + CHUNK_SIZE as i32 - 1;
+        if chunk_end_x < center.x - radius
+            || base_x > center.x + radius
+            || chunk_end_z < center.z - radius
+            || base_z > center.z + radius
+        {
+            continue;
+        }
+        for (y, layer) in chunk.blocks().iter().enumerate() {
+            for (dz, row) in layer.iter().enumerate() {
+                for (dx, &block) in row.iter().enumerate() {
+                    if !is_portal(block) {
+                        continue;
+                    }
+                    let position = Position::new(base_x + dx as i32, y as i32, base_z + dz as i32);
+                    if (position.x - center.x).abs() <= radius
+                        && (position.z - center.z).abs() <= radius
+                        && nearest.is_none_or(|nearest| distance(position) < distance(nearest))
+                    {
+                        nearest = Some(position);
+                    }
+                }
+            }
+        }
+    }
+    let mut bottom = nearest?;
+    while world
+        .get_block(Position::new(bottom.x, bottom.y - 1, bottom.z))
+        .is_some_and(is_portal)
+    {
+        bottom.y -= 1;
+    }
+    Some(bottom)
+}
+
+/// Build a lit 2x3 portal on the first open ground at or around a
+/// position, clearing space on both sides of it over an obsidian platform
+///
+/// Returns the lowest portal block nearest the negative end of the X axis.
+pub fn build_portal(world: &mut World, near: Position) -> Position {
+    let top = CHUNK_HEIGHT as i32 - MIN_HEIGHT - 2;
+    let mut y = near.y.clamp(1, top);
+    let column = |y: i32| Position::new(near.x, y, near.z);
+    if is_empty(world, column(y)) {
+        while y > 1 && is_empty(world, column(y - 1)) {
+            y -= 1;
+        }
+    } else {
+        while y < top && !is_empty(world, column(y)) {
+            y += 1;
+        }
+    }
+    let corner = Position::new(near.x, y, near.z);
+    let axis = Axis::X;
+    for along in -1..=MIN_WIDTH {
+        for across in -1..=1 {
+            for dy in -1..=MIN_HEIGHT {
+                let position = axis.across(axis.step(corner, along), across);
+                let position = Position::new(position.x, position.y + dy, position.z);
+                let in_frame = across == 0
+                    && (along == -1 || along == MIN_WIDTH || dy == -1 || dy == MIN_HEIGHT);
+                let block = if in_frame || dy == -1 {
+                    OBSIDIAN
+                } else if across == 0 {
+                    axis.portal()
+                } else {
+                    0
+                };
+                world.set_block(position, block);
+            }
+        }
+    }
+    corner
+}
+
+/// Whether a loaded block is air
+fn is_empty(world: &World, position: Position) -> bool {
+    world.get_block(position) == Some(0)
+}
+
+/// Whether a block is obsidian
+fn is_obsidian(world: &World, position: Position) -> bool {
+    world.get_block(position) == Some(OBSIDIAN)
+}
+
+/// The six blocks next to a position
+fn neighbours(position: Position) -> [Position; 6] {
+    let Position { x, y, z } = position;
+    [
+        Position::new(x + 1, y, z),
+        Position::new(x - 1, y, z),
+        Position::new(x, y + 1, z),
+        Position::new(x, y - 1, z),
+        Position::new(x, y, z + 1),
+        Position::new(x, y, z - 1),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::registry::BlockRegistry;
+    use crate::game::world::storage::WorldStorage;
+    use std::sync::Arc;
+
+    /// Build an empty obsidian frame spanning the Z axis with a 2x3 inside
+    fn build_frame(world: &mut World, corner: Position) {
+        for dz in -1..=2 {
+            for dy in -1..=3 {
+                let position = Position::new(corner.x, corner.y + dy, corner.z + dz);
+                let edge = dz == -1 || dz == 2 || dy == -1 || dy == 3;
+                world.set_block(position, if edge { OBSIDIAN } else { 0 });
+            }
+        }
+    }
+
+    #[test]
+    fn test_light_and_break() {
+        let mut world = World::new("test".to_string(), 0);
+        let corner = Position::new(3, 64, 5);
+        build_frame(&mut world, corner);
+        let inside = Position::new(3, 66, 6);
+        assert_eq!(PortalFrame::find(&world, inside, Axis::X), None);
+        let frame = PortalFrame::find(&world, inside, Axis::Z).unwrap();
+        assert_eq!((frame.corner, frame.width, frame.height), (corner, 2, 3));
+
+        assert!(light(&mut world, inside));
+        assert_eq!(world.get_block(corner), Some(NETHER_PORTAL_Z));
+        assert!(!light(&mut world, inside));
+
+        // Breaking the frame breaks the portal over the next ticks
+        world.set_block(Position::new(3, 63, 5), 0);
+        for _ in 0..6 {
+            let changed = std::mem::take(&mut world.neighbour_updates);
+            update_portals(&mut world, &changed);
+        }
+        assert!(frame.inside().all(|position| is_empty(&world, position)));
+    }
+
+    #[test]
+    fn test_portal_timer() {
+        let mut timer = PortalTimer::default();
+        assert!(!(0..PORTAL_WAIT - 1).any(|_| timer.tick(true, PORTAL_WAIT)));
+        assert!(timer.tick(true, PORTAL_WAIT));
+        // Arriving in a portal does not send the entity straight back
+        assert!(!(0..PORTAL_WAIT * 2).any(|_| timer.tick(true, PORTAL_WAIT)));
+        assert!(!(0..PORTAL_COOLDOWN).any(|_| timer.tick(false, 1)));
+        assert!(timer.tick(true, 1));
+    }
+
+    #[test]
+    fn test_destination() {
+        let mut nether = World::new("nether".to_string(), 0).with_dimension(Dimension::Nether);
+        let built = destination(&mut nether, Dimension::Overworld, 80.5, 64.0, -40.5);
+        assert_eq!((built.x, built.z), (10, -6));
+        assert!(nether.get_block(built).is_some_and(is_portal));
+        let found = destination(&mut nether, Dimension::Overworld, 40.0, 70.0, -48.0);
+        assert_eq!(found, built);
+    }
+
+    #[test]
+    fn test_destination_after_reload() {
+        let directory = std::env::temp_dir().join(format!("obsidium-{}", uuid::Uuid::new_v4()));
+        let open = || {
+            let storage = WorldStorage::new(&directory, Arc::new(BlockRegistry::new()))
+                .with_dimension(Dimension::Nether)
+                .with_sync_writes(false);
+            World::new("nether".to_string(), 0)
+                .with_dimension(Dimension::Nether)
+                .with_storage(storage)
+                .unwrap()
+        };
+        let mut nether = open();
+        let built = destination(&mut nether, Dimension::Overworld, 80.5, 64.0, -40.5);
+        assert!(nether.save(true).unwrap() > 0);
+        assert!(directory.join("DIM-1").join("region").is_dir());
+        assert!(!directory.join("level.dat").exists());
+        drop(nether);
+
+        // The portal is found in a saved chunk next to the one arrived in
+        let mut nether = open();
+        let found = destination(&mut nether, Dimension::Overworld, -40.0, 64.0, -40.0);
+        assert_eq!(found, built);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
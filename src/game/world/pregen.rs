@@ -0,0 +1,429 @@
+//! Terrain pre-generation
+//!
+//! Chunks can be generated ahead of time so players do not wait for them.
+//! Chunks are visited in a spiral out from the center, and generated ones
+//! are written to storage and unloaded again right away, so memory use does
+//! not grow with the radius. Spawn chunks stay loaded as always. Progress is
+//! reported every [`REPORT_INTERVAL`] and once more when done.
+//!
+//! At startup the whole area is generated in one go. While the server runs,
+//! a [`PregenTask`] started by `/pregen` generates a few chunks per tick
+//! instead, within [`TICK_BUDGET`], and pauses while ticks run slow.
+
+use super::{ChunkPosition, World};
+use crate::clock::Clock;
+use crate::command::SenderKind;
+use crate::error::{Result, ServerError};
+use crate::protocol::types::JsonTextComponent;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often progress is reported while generating
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Largest radius that can be pre-generated, in chunks
+pub const MAX_PREGEN_RADIUS: u32 = 1024;
+
+/// Longest time a running pre-generation may take out of one tick
+pub const TICK_BUDGET: Duration = Duration::from_millis(10);
+
+/// Most chunks a running pre-generation visits in one tick
+pub const MAX_CHUNKS_PER_TICK: usize = 32;
+
+/// Average milliseconds per tick above which a running pre-generation
+/// pauses
+pub const MAX_MSPT: f64 = 40.0;
+
+/// How far along a pre-generation is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PregenProgress {
+    /// Chunks visited so far
+    pub done: usize,
+    /// Chunks to visit in total
+    pub total: usize,
+    /// Chunks that were generated rather than already saved or loaded
+    pub generated: usize,
+    /// Time spent so far
+    pub elapsed: Duration,
+}
+
+impl PregenProgress {
+    /// Share of the chunks visited, from 0 to 100
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        self.done as f64 * 100.0 / self.total as f64
+    }
+
+    /// Chunks visited per second so far
+    pub fn chunks_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0.0;
+        }
+        self.done as f64 / seconds
+    }
+
+    /// Whether every chunk has been visited
+    pub fn is_done(&self) -> bool {
+        self.done >= self.total
+    }
+
+    /// Message telling how far a running pre-generation is
+    pub fn status(&self) -> JsonTextComponent {
+        crate::lang::translate(
+            "obsidium.pregen.progress",
+            &[
+                &format!("{:.0}", self.percent()),
+                &self.done.to_string(),
+                &self.total.to_string(),
+                &format!("{:.1}", self.chunks_per_second()),
+            ],
+        )
+    }
+
+    /// Log the progress of a task, such as preparing the spawn area
+    pub fn log(&self, task: &str) {
+        if self.is_done() {
+            tracing::info!(
+                "{}: done, {} chunk(s) in {:.1}s ({:.1} chunks/s)",
+                task,
+                self.done,
+                self.elapsed.as_secs_f64(),
+                self.chunks_per_second()
+            );
+        } else {
+            tracing::info!(
+                "{}: {:.0}% ({}/{} chunks, {:.1} chunks/s)",
+                task,
+                self.percent(),
+                self.done,
+                self.total,
+                self.chunks_per_second()
+            );
+        }
+    }
+}
+
+/// Chunks within a square radius of a center, in a spiral out from it
+pub fn spiral(center: ChunkPosition, radius: u32) -> impl Iterator<Item = ChunkPosition> {
+    let radius = radius.min(MAX_PREGEN_RADIUS) as i32;
+    std::iter::once(center).chain((1..=radius).flat_map(move |ring| {
+        // Each ring walks its four sides, starting just past a corner
+        let top = (-ring + 1..=ring).map(move |dx| (dx, -ring));
+        let right = (-ring + 1..=ring).map(move |dz| (ring, dz));
+        let bottom = (-ring..ring).rev().map(move |dx| (dx, ring));
+        let left = (-ring..ring).rev().map(move |dz| (-ring, dz));
+        top.chain(right)
+            .chain(bottom)
+            .chain(left)
+            .map(move |(dx, dz)| ChunkPosition::new(center.x + dx, center.z + dz))
+    }))
+}
+
+/// Number of chunks within a square radius
+pub fn chunk_count(radius: u32) -> usize {
+    let side = radius.min(MAX_PREGEN_RADIUS) as usize * 2 + 1;
+    side * side
+}
+
+/// Generate the given chunks, saving and unloading each one that was not
+/// loaded before
+///
+/// `report` is called every [`REPORT_INTERVAL`] and when done. Fails if a
+/// generated chunk cannot be saved.
+pub fn pregenerate(
+    world: &mut World,
+    chunks: impl IntoIterator<Item = ChunkPosition>,
+    total: usize,
+    clock: &dyn Clock,
+    mut report: impl FnMut(&PregenProgress),
+) -> Result<PregenProgress> {
+    let start = clock.now();
+    let mut last_report = start;
+    let mut progress = PregenProgress {
+        done: 0,
+        total,
+        generated: 0,
+        elapsed: Duration::ZERO,
+    };
+
+    for chunk in chunks {
+        visit(world, chunk, &mut progress)?;
+        if clock.since(last_report) >= REPORT_INTERVAL {
+            last_report = clock.now();
+            progress.elapsed = clock.since(start);
+            report(&progress);
+        }
+    }
+
+    progress.total = progress.done;
+    progress.elapsed = clock.since(start);
+    report(&progress);
+    Ok(progress)
+}
+
+/// Generate and save a chunk unless it is loaded, then unload it again
+fn visit(world: &mut World, chunk: ChunkPosition, progress: &mut PregenProgress) -> Result<()> {
+    if !world.is_chunk_loaded(chunk) {
+        let generated = world.chunk_loads().generated;
+        world.load_chunk(chunk);
+        if world.chunk_loads().generated > generated {
+            let saved = world.save_chunk(chunk, false);
+            world.unload_chunk(chunk);
+            saved?;
+            progress.generated += 1;
+        } else {
+            world.unload_chunk(chunk);
+        }
+    }
+    progress.done += 1;
+    Ok(())
+}
+
+/// A pre-generation running alongside the game, a few chunks per tick
+pub struct PregenTask {
+    /// Chunks left to visit
+    chunks: Box<dyn Iterator<Item = ChunkPosition> + Send>,
+    /// Progress so far
+    progress: PregenProgress,
+    /// Who started the task and hears about its progress
+    issuer: SenderKind,
+    /// When the task started
+    started: Instant,
+    /// When progress was last reported
+    last_report: Instant,
+}
+
+impl PregenTask {
+    /// Plan the generation of the chunks within a radius of a center
+    pub fn new(center: ChunkPosition, radius: u32, issuer: SenderKind, clock: &dyn Clock) -> Self {
+        let now = clock.now();
+        Self {
+            chunks: Box::new(spiral(center, radius)),
+            progress: PregenProgress {
+                done: 0,
+                total: chunk_count(radius),
+                generated: 0,
+                elapsed: Duration::ZERO,
+            },
+            issuer,
+            started: now,
+            last_report: now,
+        }
+    }
+
+    /// Who started the task
+    pub fn issuer(&self) -> SenderKind {
+        self.issuer
+    }
+
+    /// Progress so far
+    pub fn progress(&self) -> PregenProgress {
+        self.progress
+    }
+}
+
+impl std::fmt::Debug for PregenTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PregenTask")
+            .field("progress", &self.progress)
+            .field("issuer", &self.issuer)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Progress of a running pre-generation worth telling its issuer about
+#[derive(Debug)]
+pub struct PregenUpdate {
+    /// Who started the task
+    pub issuer: SenderKind,
+    /// Progress so far
+    pub progress: PregenProgress,
+    /// Why the task stopped early, if it failed
+    pub error: Option<ServerError>,
+}
+
+/// Holds the pre-generation started by `/pregen`, if any
+#[derive(Debug, Default)]
+pub struct PregenManager {
+    /// The running task
+    task: Mutex<Option<PregenTask>>,
+}
+
+impl PregenManager {
+    /// Create a manager with nothing running
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lock the running task
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<PregenTask>> {
+        self.task.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start a task
+    ///
+    /// Returns `false` if one is running already.
+    pub fn start(&self, task: PregenTask) -> bool {
+        let mut running = self.lock();
+        if running.is_some() {
+            return false;
+        }
+        *running = Some(task);
+        true
+    }
+
+    /// Stop the running task, returning it
+    pub fn cancel(&self) -> Option<PregenTask> {
+        self.lock().take()
+    }
+
+    /// Progress of the running task
+    pub fn progress(&self) -> Option<PregenProgress> {
+        self.lock().as_ref().map(PregenTask::progress)
+    }
+
+    /// Visit the next chunks of the running task, within [`TICK_BUDGET`]
+    /// and [`MAX_CHUNKS_PER_TICK`]
+    ///
+    /// Returns an update when progress is due to be reported, and when the
+    /// task finishes or fails, which also ends it.
+    pub(crate) fn step(&self, world: &mut World, clock: &dyn Clock) -> Option<PregenUpdate> {
+        let mut running = self.lock();
+        let task = running.as_mut()?;
+        let start = clock.now();
+        let mut error = None;
+        let mut finished = false;
+        for _ in 0..MAX_CHUNKS_PER_TICK {
+            let Some(chunk) = task.chunks.next() else {
+                finished = true;
+                break;
+            };
+            if let Err(e) = visit(world, chunk, &mut task.progress) {
+                error = Some(e);
+                break;
+            }
+            if clock.since(start) >= TICK_BUDGET {
+                break;
+            }
+        }
+        task.progress.elapsed = clock.since(task.started);
+
+        if finished || error.is_some() {
+            if finished {
+                task.progress.total = task.progress.done;
+            }
+            let task = running.take()?;
+            return Some(PregenUpdate {
+                issuer: task.issuer,
+                progress: task.progress,
+                error,
+            });
+        }
+        if clock.since(task.last_report) < REPORT_INTERVAL {
+            return None;
+        }
+        task.last_report = clock.now();
+        Some(PregenUpdate {
+            issuer: task.issuer,
+            progress: task.progress,
+            error: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_spiral() {
+        let center = ChunkPosition::new(3, -2);
+        let chunks: Vec<_> = spiral(center, 2).collect();
+        assert_eq!(chunks.len(), chunk_count(2));
+        assert_eq!(chunks[0], center);
+        let unique: HashSet<_> = chunks.iter().copied().collect();
+        assert_eq!(unique.len(), chunks.len());
+
+        // Every ring is finished before the next one starts
+        let ring =
+            |chunk: &ChunkPosition| (chunk.x - center.x).abs().max((chunk.z - center.z).abs());
+        assert!(
+            chunks
+                .windows(2)
+                .all(|pair| ring(&pair[0]) <= ring(&pair[1]))
+        );
+        assert!(chunks.iter().all(|chunk| ring(chunk) <= 2));
+    }
+
+    #[test]
+    fn test_pregenerate() {
+        let mut world = World::new("pregen".to_string(), 0);
+        world.game_rules_mut().spawn_chunk_radius = 1;
+        let clock = ManualClock::new();
+
+        let mut reports = Vec::new();
+        let progress = pregenerate(
+            &mut world,
+            spiral(ChunkPosition::new(0, 0), 3),
+            chunk_count(3),
+            &clock,
+            |progress| reports.push(*progress),
+        )
+        .unwrap();
+        assert_eq!(progress.done, 49);
+        assert_eq!(progress.generated, 49);
+        assert!(progress.is_done());
+        assert_eq!(reports, vec![progress]);
+
+        // Only the spawn chunks stay loaded
+        assert_eq!(world.loaded_chunk_count(), 9);
+
+        let progress = PregenProgress {
+            done: 50,
+            total: 200,
+            generated: 50,
+            elapsed: Duration::from_secs(2),
+        };
+        assert_eq!(progress.percent(), 25.0);
+        assert_eq!(progress.chunks_per_second(), 25.0);
+    }
+
+    #[test]
+    fn test_pregen_task() {
+        let mut world = World::new("pregen".to_string(), 0);
+        world.game_rules_mut().spawn_chunk_radius = 0;
+        let clock = ManualClock::new();
+        let manager = PregenManager::new();
+        let center = ChunkPosition::new(0, 0);
+        assert!(manager.step(&mut world, &clock).is_none());
+
+        assert!(manager.start(PregenTask::new(center, 4, SenderKind::Console, &clock)));
+        assert!(!manager.start(PregenTask::new(center, 1, SenderKind::Console, &clock)));
+
+        // Each tick visits a bounded batch, reporting only once due
+        assert!(manager.step(&mut world, &clock).is_none());
+        assert_eq!(manager.progress().unwrap().done, MAX_CHUNKS_PER_TICK);
+        assert_eq!(world.loaded_chunk_count(), 0);
+        clock.advance(REPORT_INTERVAL);
+        let update = manager.step(&mut world, &clock).unwrap();
+        assert_eq!(update.progress.done, MAX_CHUNKS_PER_TICK * 2);
+        assert!(!update.progress.is_done());
+
+        // The last batch ends the task
+        let update = manager.step(&mut world, &clock).unwrap();
+        assert!(update.progress.is_done() && update.error.is_none());
+        assert_eq!(update.progress.generated, chunk_count(4));
+        assert!(manager.progress().is_none());
+
+        assert!(manager.start(PregenTask::new(center, 4, SenderKind::Console, &clock)));
+        manager.step(&mut world, &clock);
+        let cancelled = manager.cancel().unwrap();
+        assert_eq!(cancelled.progress().done, MAX_CHUNKS_PER_TICK);
+        assert!(manager.step(&mut world, &clock).is_none());
+    }
+}
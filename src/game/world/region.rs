@@ -0,0 +1,533 @@
+//! Region ticking
+//!
+//! Loaded chunks are grouped into square regions of [`REGION_SIZE`] chunks,
+//! which are ticked in parallel on the rayon pool. Regions are split into
+//! four phases by the parity of their coordinates, so two regions of the
+//! same phase are never next to each other, and the phases run one after
+//! another.
+//!
+//! While its phase runs, a region changes its own chunks directly and may
+//! read the chunks of the regions around it, which stay unchanged until the
+//! phase is over. Everything else a region does is queued and applied on
+//! the calling thread once all phases are done, in region order:
+//!
+//! - changes to blocks outside the region, or to beds and unloaded chunks
+//! - work that needs the whole [`World`], such as breaking blocks and
+//!   dropping their loot, through [`RegionTick::defer`]
+//!
+//! Random ticks and leaf distance updates run this way. Entities only
+//! touch themselves while they move, so their regions are ticked with
+//! [`tick_in_phases`] without any chunks.
+
+use super::bed::Bed;
+use super::chunk::{self, Chunk};
+use super::simulation::{RandomTickHandler, ScheduledTickHandler, SimulationArea, TickRandom};
+use super::{ChunkPosition, Weather, World, leaves};
+use crate::game::entity::EntityPosition;
+use crate::protocol::types::Position;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+
+/// Binary logarithm of the region size in chunks
+const REGION_SHIFT: i32 = 3;
+
+/// Width and depth of a region in chunks
+pub const REGION_SIZE: i32 = 1 << REGION_SHIFT;
+
+/// Number of phases regions are ticked in
+pub const PHASES: u8 = 4;
+
+/// Chunks of a region
+type RegionChunks = HashMap<ChunkPosition, Chunk>;
+
+/// Position of a region, in regions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RegionPosition {
+    /// X coordinate
+    pub x: i32,
+    /// Z coordinate
+    pub z: i32,
+}
+
+impl RegionPosition {
+    /// Create a region position
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// Region a chunk belongs to
+    pub fn of_chunk(chunk: ChunkPosition) -> Self {
+        Self::new(chunk.x >> REGION_SHIFT, chunk.z >> REGION_SHIFT)
+    }
+
+    /// Region a block belongs to
+    pub fn of_block(position: Position) -> Self {
+        Self::of_chunk(chunk_of(position))
+    }
+
+    /// Region an entity is in
+    pub fn of_entity(position: EntityPosition) -> Self {
+        Self::of_chunk(ChunkPosition::from_world_coords(position.x, position.z))
+    }
+
+    /// Phase the region is ticked in, below [`PHASES`]
+    pub fn phase(&self) -> u8 {
+        ((self.x & 1) | ((self.z & 1) << 1)) as u8
+    }
+
+    /// Whether two different regions touch, at a side or a corner
+    pub fn is_adjacent(&self, other: &RegionPosition) -> bool {
+        self != other && (self.x - other.x).abs() <= 1 && (self.z - other.z).abs() <= 1
+    }
+}
+
+/// Chunk a block belongs to
+fn chunk_of(position: Position) -> ChunkPosition {
+    ChunkPosition::from_world_coords(f64::from(position.x), f64::from(position.z))
+}
+
+/// Look a block up in a chunk
+fn block_in(chunk: &Chunk, position: Position) -> Option<u32> {
+    let chunk_position = chunk.position();
+    chunk.get_block(
+        (position.x - chunk_position.world_x()) as usize,
+        position.y as usize,
+        (position.z - chunk_position.world_z()) as usize,
+    )
+}
+
+/// Reads blocks from the whole world or from a region being ticked
+pub trait BlockView {
+    /// Get the block at a position, or `None` if its chunk is not readable
+    fn get_block(&self, position: Position) -> Option<u32>;
+}
+
+impl BlockView for World {
+    fn get_block(&self, position: Position) -> Option<u32> {
+        World::get_block(self, position)
+    }
+}
+
+/// Tick regions in phases, all regions of a phase at once on the rayon pool
+///
+/// Returns the results in phase order, and in region order within a phase.
+pub fn tick_in_phases<T, R, F>(regions: BTreeMap<RegionPosition, T>, tick: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(RegionPosition, T) -> R + Sync,
+{
+    let mut phases: [Vec<(RegionPosition, T)>; PHASES as usize] = Default::default();
+    for (position, region) in regions {
+        phases[usize::from(position.phase())].push((position, region));
+    }
+    phases
+        .into_iter()
+        .flat_map(|phase| {
+            phase
+                .into_par_iter()
+                .map(|(position, region)| tick(position, region))
+                .collect::<Vec<R>>()
+        })
+        .collect()
+}
+
+/// Something a region queued for after the parallel phases
+enum Effect {
+    /// Set a block through [`World::set_block`]
+    SetBlock(Position, u32),
+    /// Run a handler with the whole world
+    Run(ScheduledTickHandler, Position, u32),
+}
+
+/// World state every region reads while blocks are ticked
+struct Shared<'a> {
+    /// Bed halves by block position
+    beds: &'a HashMap<Position, Bed>,
+    /// How blocks react to random ticks, by block ID
+    handlers: &'a HashMap<u32, RandomTickHandler>,
+    /// Current weather
+    weather: Weather,
+    /// Random ticks per chunk section
+    speed: usize,
+    /// Chunks that are simulated
+    area: &'a SimulationArea,
+}
+
+impl<'a> Shared<'a> {
+    /// Borrow the state regions read from the world
+    fn new(world: &'a World, area: &'a SimulationArea) -> Self {
+        Self {
+            beds: &world.beds,
+            handlers: &world.random_tick_handlers,
+            weather: world.weather,
+            speed: world.game_rules.random_tick_speed as usize,
+            area,
+        }
+    }
+}
+
+/// Work for a region this tick
+#[derive(Default)]
+struct RegionWork {
+    /// Leaves whose distance to recompute
+    leaves: Vec<Position>,
+    /// Whether any of its chunks receive random ticks
+    random_ticks: bool,
+}
+
+/// What a region changed and queued during its phase
+struct RegionOutput {
+    /// The region
+    position: RegionPosition,
+    /// Blocks the region changed in its own chunks
+    changed: Vec<Position>,
+    /// Effects to apply afterwards
+    effects: Vec<Effect>,
+}
+
+/// A region while its blocks are ticked
+///
+/// Random tick handlers receive this instead of the [`World`].
+pub struct RegionTick<'a> {
+    /// The region being ticked
+    position: RegionPosition,
+    /// Chunks of the region
+    chunks: &'a mut RegionChunks,
+    /// Chunks of the regions not ticked in this phase
+    others: &'a HashMap<RegionPosition, &'a RegionChunks>,
+    /// State shared by all regions
+    shared: &'a Shared<'a>,
+    /// Randomness for this region
+    random: TickRandom,
+    /// Blocks changed in the region's own chunks
+    changed: Vec<Position>,
+    /// Effects queued for afterwards
+    effects: Vec<Effect>,
+}
+
+impl BlockView for RegionTick<'_> {
+    fn get_block(&self, position: Position) -> Option<u32> {
+        let chunk = chunk_of(position);
+        let region = RegionPosition::of_chunk(chunk);
+        let chunks = if region == self.position {
+            &*self.chunks
+        } else {
+            self.others.get(&region)?
+        };
+        block_in(chunks.get(&chunk)?, position)
+    }
+}
+
+impl RegionTick<'_> {
+    /// The region being ticked
+    pub fn position(&self) -> RegionPosition {
+        self.position
+    }
+
+    /// Get the block at a position in this region or one around it
+    pub fn get_block(&self, position: Position) -> Option<u32> {
+        BlockView::get_block(self, position)
+    }
+
+    /// Set a block, right away if it is in this region and otherwise once
+    /// the parallel phases are over
+    pub fn set_block(&mut self, position: Position, block_id: u32) {
+        let chunk = chunk_of(position);
+        // Only the region's own chunks are in the map; beds need the world
+        // to break their other half
+        match self.chunks.get_mut(&chunk) {
+            Some(loaded) if !self.shared.beds.contains_key(&position) => {
+                let x = (position.x - chunk.world_x()) as usize;
+                let z = (position.z - chunk.world_z()) as usize;
+                if loaded.set_block(x, position.y as usize, z, block_id) {
+                    self.changed.push(position);
+                }
+            }
+            _ => self.effects.push(Effect::SetBlock(position, block_id)),
+        }
+    }
+
+    /// Run a handler with the whole world once the parallel phases are over
+    pub fn defer(&mut self, position: Position, block: u32, handler: ScheduledTickHandler) {
+        self.effects.push(Effect::Run(handler, position, block));
+    }
+
+    /// Current weather
+    pub fn weather(&self) -> Weather {
+        self.shared.weather
+    }
+
+    /// Randomness for this region
+    pub(crate) fn random(&mut self) -> &mut TickRandom {
+        &mut self.random
+    }
+
+    /// Pick random blocks in every simulated chunk and run their handlers
+    fn random_tick(&mut self) {
+        let shared = self.shared;
+        let mut ticked = Vec::new();
+        for (&position, chunk) in self.chunks.iter() {
+            if !shared.area.contains(position) {
+                continue;
+            }
+            for section in 0..chunk::CHUNK_HEIGHT / chunk::CHUNK_SIZE {
+                for _ in 0..shared.speed {
+                    let x = self.random.below(chunk::CHUNK_SIZE);
+                    let y = section * chunk::CHUNK_SIZE + self.random.below(chunk::CHUNK_SIZE);
+                    let z = self.random.below(chunk::CHUNK_SIZE);
+                    let Some(block) = chunk.get_block(x, y, z) else {
+                        continue;
+                    };
+                    if let Some(&handler) = shared.handlers.get(&block) {
+                        let block_position = Position::new(
+                            position.world_x() + x as i32,
+                            y as i32,
+                            position.world_z() + z as i32,
+                        );
+                        ticked.push((handler, block_position, block));
+                    }
+                }
+            }
+        }
+
+        // Handlers change the region, so they run once the chunks are read
+        for (handler, position, block) in ticked {
+            handler(self, position, block);
+        }
+    }
+}
+
+/// Recompute leaf distances around changed blocks and run random ticks,
+/// region by region
+pub(super) fn tick_blocks(world: &mut World, area: &SimulationArea, changed: Vec<Position>) {
+    let speed = world.game_rules.random_tick_speed as usize;
+    let random_ticks = speed > 0 && !area.is_empty() && !world.random_tick_handlers.is_empty();
+    let leaves = leaves::positions_to_update(changed);
+    if leaves.is_empty() && !random_ticks {
+        return;
+    }
+
+    let mut regions = take_regions(world);
+    let mut work: BTreeMap<RegionPosition, RegionWork> = BTreeMap::new();
+    for position in leaves {
+        let region = RegionPosition::of_block(position);
+        regions.entry(region).or_default();
+        work.entry(region).or_default().leaves.push(position);
+    }
+    if random_ticks {
+        for (&region, chunks) in &regions {
+            if chunks.keys().any(|&chunk| area.contains(chunk)) {
+                work.entry(region).or_default().random_ticks = true;
+            }
+        }
+    }
+    // Seeded in region order, so a region's randomness does not depend on
+    // which thread ticks it
+    let mut work: BTreeMap<RegionPosition, (RegionWork, TickRandom)> = work
+        .into_iter()
+        .map(|(region, work)| (region, (work, world.random.fork())))
+        .collect();
+
+    let shared = Shared::new(world, area);
+    let mut outputs = Vec::new();
+    for phase in 0..PHASES {
+        let mut active = Vec::new();
+        let mut others = HashMap::new();
+        for (&position, chunks) in regions.iter_mut() {
+            if position.phase() == phase {
+                if let Some(work) = work.remove(&position) {
+                    active.push((position, chunks, work));
+                    continue;
+                }
+            }
+            others.insert(position, &*chunks);
+        }
+        let others = &others;
+        let shared = &shared;
+        outputs.extend(
+            active
+                .into_par_iter()
+                .map(|(position, chunks, (work, random))| {
+                    let mut tick = RegionTick {
+                        position,
+                        chunks,
+                        others,
+                        shared,
+                        random,
+                        changed: Vec::new(),
+                        effects: Vec::new(),
+                    };
+                    leaves::update_distances(&mut tick, &work.leaves);
+                    if work.random_ticks {
+                        tick.random_tick();
+                    }
+                    RegionOutput {
+                        position,
+                        changed: tick.changed,
+                        effects: tick.effects,
+                    }
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
+    apply(world, regions, outputs);
+}
+
+/// Move the world's chunks out, grouped by region
+fn take_regions(world: &mut World) -> BTreeMap<RegionPosition, RegionChunks> {
+    let mut regions: BTreeMap<RegionPosition, RegionChunks> = BTreeMap::new();
+    for (position, chunk) in world.chunks.drain() {
+        regions
+            .entry(RegionPosition::of_chunk(position))
+            .or_default()
+            .insert(position, chunk);
+    }
+    regions
+}
+
+/// Run a random tick handler on one block, as if it had been picked
+#[cfg(test)]
+pub(super) fn run_handler(
+    world: &mut World,
+    handler: RandomTickHandler,
+    position: Position,
+    block: u32,
+) {
+    let region = RegionPosition::of_block(position);
+    let random = world.random.fork();
+    let mut regions = take_regions(world);
+    let mut chunks = regions.remove(&region).unwrap_or_default();
+    let output = {
+        let others = regions
+            .iter()
+            .map(|(&other, chunks)| (other, chunks))
+            .collect();
+        let area = SimulationArea::new(0);
+        let shared = Shared::new(world, &area);
+        let mut tick = RegionTick {
+            position: region,
+            chunks: &mut chunks,
+            others: &others,
+            shared: &shared,
+            random,
+            changed: Vec::new(),
+            effects: Vec::new(),
+        };
+        handler(&mut tick, position, block);
+        RegionOutput {
+            position: region,
+            changed: tick.changed,
+            effects: tick.effects,
+        }
+    };
+    regions.insert(region, chunks);
+    apply(world, regions, vec![output]);
+}
+
+/// Put the chunks back into the world and apply what the regions changed
+/// and queued, in region order
+fn apply(
+    world: &mut World,
+    regions: BTreeMap<RegionPosition, RegionChunks>,
+    mut outputs: Vec<RegionOutput>,
+) {
+    world.chunks.extend(regions.into_values().flatten());
+    outputs.sort_by_key(|output| output.position);
+    for output in outputs {
+        world.block_changes.extend_from_slice(&output.changed);
+        world.neighbour_updates.extend(output.changed);
+        for effect in output.effects {
+            match effect {
+                Effect::SetBlock(position, block_id) => {
+                    world.set_block(position, block_id);
+                }
+                Effect::Run(handler, position, block) => handler(world, position, block),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regions_in_a_phase_are_apart() {
+        let regions: Vec<RegionPosition> = (-3..=3)
+            .flat_map(|x| (-3..=3).map(move |z| RegionPosition::new(x, z)))
+            .collect();
+        for a in &regions {
+            for b in &regions {
+                assert!(a.phase() != b.phase() || !a.is_adjacent(b));
+            }
+        }
+        assert_eq!(
+            RegionPosition::of_chunk(ChunkPosition::new(-1, REGION_SIZE)),
+            RegionPosition::new(-1, 1)
+        );
+        assert_eq!(
+            RegionPosition::of_block(Position::new(-1, 64, 16 * REGION_SIZE - 1)),
+            RegionPosition::new(-1, 0)
+        );
+
+        let regions: BTreeMap<RegionPosition, ()> = regions.into_iter().map(|r| (r, ())).collect();
+        let phases = tick_in_phases(regions, |position, ()| position.phase());
+        assert_eq!(phases.len(), 49);
+        assert!(phases.is_sorted());
+    }
+
+    #[test]
+    fn test_effects_cross_regions() {
+        const STONE: u32 = 1;
+        /// Mark the block east of a stone block, in the next region
+        fn spread_east(tick: &mut RegionTick<'_>, position: Position, _block: u32) {
+            let east = Position::new(position.x + 16, position.y, position.z);
+            if tick.get_block(east) == Some(STONE) {
+                tick.set_block(east, 5);
+            }
+            tick.set_block(position, 6);
+            tick.defer(position, 6, |world, position, _| {
+                world.set_block(position, 9);
+            });
+        }
+        let count = |world: &World, chunk: ChunkPosition, block: u32| {
+            (0..16)
+                .flat_map(|x| (0..64).flat_map(move |y| (0..16).map(move |z| (x, y, z))))
+                .filter(|&(x, y, z)| {
+                    let position = Position::new(chunk.world_x() + x, y, chunk.world_z() + z);
+                    world.get_block(position) == Some(block)
+                })
+                .count()
+        };
+
+        let mut world = World::new("regions".to_string(), 0);
+        world.on_random_tick(STONE, spread_east);
+        world.game_rules_mut().random_tick_speed = 1_000;
+        let (west, east) = (
+            ChunkPosition::new(REGION_SIZE - 1, 0),
+            ChunkPosition::new(REGION_SIZE, 0),
+        );
+        world.load_chunk(west);
+        world.load_chunk(east);
+        world.take_block_changes();
+
+        let mut area = SimulationArea::new(0);
+        area.add_player(west);
+        world.update(0.05, &area);
+
+        // The west region changed its own blocks, and its changes to the
+        // east region and deferred work were applied afterwards
+        assert_eq!(count(&world, west, 6), 0);
+        let marked = count(&world, west, 9);
+        assert!(marked > 0);
+        assert_eq!(count(&world, east, 5), marked);
+        let changed: usize = world
+            .take_block_changes()
+            .iter()
+            .map(|section| section.blocks.len())
+            .sum();
+        assert_eq!(changed, marked * 2);
+        assert_eq!(world.loaded_chunk_count(), 2);
+    }
+}
@@ -2,14 +2,32 @@
 //!
 //! This module manages the registries for blocks, items, and other game objects.
 
+use super::World;
+use super::farming::{Crop, FARMLAND, MAX_MOISTURE, WATER};
+use super::gravity::{ANVIL, GRAVEL, SAND};
+use super::interact::{self, Interactive, InteractiveState};
+use super::leaves::{self, MAX_DISTANCE, OAK_LOG};
+use super::multiblock::{self, EXTENDED_PISTON, PISTON_HEAD, TALL_GRASS, TALL_GRASS_UPPER};
+use super::portal::{Axis, NETHERRACK, OBSIDIAN};
+use crate::game::entity::MobType;
+use crate::game::entity::summon;
+use crate::protocol::types::Position;
 use std::collections::HashMap;
 
+/// Reacts to a player using a block, given its position and block ID
+///
+/// Returns whether the block reacted, so the use is not passed on to the
+/// held item.
+pub type UseHandler = fn(&mut World, Position, u32) -> bool;
+
 /// Block registry managing block types and their properties
 pub struct BlockRegistry {
     /// Map of block ID to block info
     blocks: HashMap<u32, BlockInfo>,
     /// Map of block name to block ID
     name_to_id: HashMap<String, u32>,
+    /// How blocks react to being used, by block ID
+    use_handlers: HashMap<u32, UseHandler>,
 }
 
 /// Information about a block type
@@ -35,6 +53,7 @@ impl BlockRegistry {
         let mut registry = Self {
             blocks: HashMap::new(),
             name_to_id: HashMap::new(),
+            use_handlers: HashMap::new(),
         };
 
         // Register default blocks
@@ -64,6 +83,16 @@ impl BlockRegistry {
         self.get_block(id)
     }
 
+    /// Make blocks with an ID react to players using them
+    pub fn on_use(&mut self, id: u32, handler: UseHandler) {
+        self.use_handlers.insert(id, handler);
+    }
+
+    /// Get the handler of players using a block, if it reacts to them
+    pub fn use_handler(&self, id: u32) -> Option<UseHandler> {
+        self.use_handlers.get(&id).copied()
+    }
+
     /// Get all registered blocks
     pub fn all_blocks(&self) -> impl Iterator<Item = &BlockInfo> {
         self.blocks.values()
@@ -141,11 +170,193 @@ impl BlockRegistry {
                 hardness: -1.0, // Unbreakable
                 resistance: 3600000.0,
             },
+            BlockInfo {
+                id: 8,
+                name: "minecraft:red_bed".to_string(),
+                solid: false,
+                transparent: true,
+                hardness: 0.2,
+                resistance: 0.2,
+            },
+            BlockInfo {
+                id: 9,
+                name: "minecraft:tnt".to_string(),
+                solid: true,
+                transparent: false,
+                hardness: 0.0,
+                resistance: 0.0,
+            },
         ];
 
         for block in default_blocks {
             self.register_block(block);
         }
+        self.register_farming_blocks();
+        self.register_tree_blocks();
+        self.register_interactive_blocks();
+        self.register_portal_blocks();
+        self.register_gravity_blocks();
+        self.register_multi_blocks();
+    }
+
+    /// Register both halves of tall grass and every facing of extended
+    /// pistons and their heads
+    fn register_multi_blocks(&mut self) {
+        for (id, half) in [(TALL_GRASS, "lower"), (TALL_GRASS_UPPER, "upper")] {
+            self.register_block(BlockInfo {
+                id,
+                name: format!("minecraft:tall_grass[half={}]", half),
+                solid: false,
+                transparent: true,
+                hardness: 0.0,
+                resistance: 0.0,
+            });
+        }
+        for (index, facing) in (0..).zip(multiblock::FACINGS) {
+            let facing = format!("{:?}", facing).to_lowercase();
+            self.register_block(BlockInfo {
+                id: EXTENDED_PISTON + index,
+                name: format!("minecraft:piston[extended=true,facing={}]", facing),
+                solid: true,
+                transparent: false,
+                hardness: 1.5,
+                resistance: 1.5,
+            });
+            self.register_block(BlockInfo {
+                id: PISTON_HEAD + index,
+                name: format!("minecraft:piston_head[facing={}]", facing),
+                solid: true,
+                transparent: true,
+                hardness: 1.5,
+                resistance: 1.5,
+            });
+        }
+    }
+
+    /// Register sand, gravel and anvils, which fall when nothing holds them
+    /// up
+    fn register_gravity_blocks(&mut self) {
+        let blocks = [
+            (SAND, "minecraft:sand", 0.5, 0.5),
+            (GRAVEL, "minecraft:gravel", 0.6, 0.6),
+            (ANVIL, "minecraft:anvil", 5.0, 1200.0),
+        ];
+        for (id, name, hardness, resistance) in blocks {
+            self.register_block(BlockInfo {
+                id,
+                name: name.to_string(),
+                solid: true,
+                transparent: false,
+                hardness,
+                resistance,
+            });
+        }
+    }
+
+    /// Register obsidian, both nether portal axes and netherrack
+    fn register_portal_blocks(&mut self) {
+        self.register_block(BlockInfo {
+            id: OBSIDIAN,
+            name: "minecraft:obsidian".to_string(),
+            solid: true,
+            transparent: false,
+            hardness: 50.0,
+            resistance: 1200.0,
+        });
+        for (axis, name) in [(Axis::X, "x"), (Axis::Z, "z")] {
+            self.register_block(BlockInfo {
+                id: axis.portal(),
+                name: format!("minecraft:nether_portal[axis={}]", name),
+                solid: false,
+                transparent: true,
+                hardness: -1.0, // Unbreakable
+                resistance: 0.0,
+            });
+        }
+        self.register_block(BlockInfo {
+            id: NETHERRACK,
+            name: "minecraft:netherrack".to_string(),
+            solid: true,
+            transparent: false,
+            hardness: 0.4,
+            resistance: 0.4,
+        });
+    }
+
+    /// Register every state of doors, trapdoors, levers and buttons
+    fn register_interactive_blocks(&mut self) {
+        for kind in Interactive::ALL {
+            for id in kind.blocks() {
+                let Some(state) = InteractiveState::from_block(id) else {
+                    continue;
+                };
+                self.register_block(BlockInfo {
+                    id,
+                    name: state.name(),
+                    solid: false,
+                    transparent: true,
+                    hardness: kind.hardness(),
+                    resistance: kind.hardness(),
+                });
+                self.on_use(id, interact::use_block);
+            }
+        }
+    }
+
+    /// Register oak logs and oak leaves at every distance from a log
+    fn register_tree_blocks(&mut self) {
+        self.register_block(BlockInfo {
+            id: OAK_LOG,
+            name: "minecraft:oak_log".to_string(),
+            solid: true,
+            transparent: false,
+            hardness: 2.0,
+            resistance: 2.0,
+        });
+        for distance in 1..=MAX_DISTANCE {
+            self.register_block(BlockInfo {
+                id: leaves::leaves(distance),
+                name: format!("minecraft:oak_leaves[distance={}]", distance),
+                solid: true,
+                transparent: true,
+                hardness: 0.2,
+                resistance: 0.2,
+            });
+        }
+    }
+
+    /// Register farmland, water and every age of each crop
+    fn register_farming_blocks(&mut self) {
+        for moisture in 0..=MAX_MOISTURE {
+            self.register_block(BlockInfo {
+                id: FARMLAND + moisture,
+                name: format!("minecraft:farmland[moisture={}]", moisture),
+                solid: true,
+                transparent: true,
+                hardness: 0.6,
+                resistance: 0.6,
+            });
+        }
+        self.register_block(BlockInfo {
+            id: WATER,
+            name: "minecraft:water".to_string(),
+            solid: false,
+            transparent: true,
+            hardness: 100.0,
+            resistance: 100.0,
+        });
+        for crop in Crop::ALL {
+            for age in 0..=Crop::MAX_AGE {
+                self.register_block(BlockInfo {
+                    id: crop.block(age),
+                    name: format!("{}[age={}]", crop.name(), age),
+                    solid: false,
+                    transparent: true,
+                    hardness: 0.0,
+                    resistance: 0.0,
+                });
+            }
+        }
     }
 }
 
@@ -155,6 +366,67 @@ impl Default for BlockRegistry {
     }
 }
 
+/// Tools and armor, by ID, with their durability
+const DAMAGEABLE_ITEMS: [(u32, &str, u32); 17] = [
+    (256, "minecraft:iron_shovel", 250),
+    (257, "minecraft:iron_pickaxe", 250),
+    (258, "minecraft:iron_axe", 250),
+    (259, "minecraft:flint_and_steel", 64),
+    (267, "minecraft:iron_sword", 250),
+    (277, "minecraft:diamond_shovel", 1561),
+    (278, "minecraft:diamond_pickaxe", 1561),
+    (279, "minecraft:diamond_axe", 1561),
+    (292, "minecraft:iron_hoe", 250),
+    (306, "minecraft:iron_helmet", 165),
+    (307, "minecraft:iron_chestplate", 240),
+    (308, "minecraft:iron_leggings", 225),
+    (309, "minecraft:iron_boots", 195),
+    (310, "minecraft:diamond_helmet", 363),
+    (311, "minecraft:diamond_chestplate", 528),
+    (312, "minecraft:diamond_leggings", 495),
+    (313, "minecraft:diamond_boots", 429),
+];
+
+/// Items that stack to 64 and take no damage, by ID
+const STACKABLE_ITEMS: [(u32, &str); 32] = [
+    (1, "minecraft:stone"),
+    (6, "minecraft:oak_sapling"),
+    (33, "minecraft:piston"),
+    (35, "minecraft:white_wool"),
+    (49, "minecraft:obsidian"),
+    (87, "minecraft:netherrack"),
+    (175, "minecraft:tall_grass"),
+    (260, "minecraft:apple"),
+    (262, "minecraft:arrow"),
+    (265, "minecraft:iron_ingot"),
+    (280, "minecraft:stick"),
+    (287, "minecraft:string"),
+    (288, "minecraft:feather"),
+    (289, "minecraft:gunpowder"),
+    (295, "minecraft:wheat_seeds"),
+    (296, "minecraft:wheat"),
+    (319, "minecraft:porkchop"),
+    (324, "minecraft:oak_door"),
+    (334, "minecraft:leather"),
+    (351, "minecraft:bone_meal"),
+    (352, "minecraft:bone"),
+    (363, "minecraft:beef"),
+    (364, "minecraft:bread"),
+    (365, "minecraft:chicken"),
+    (367, "minecraft:rotten_flesh"),
+    (375, "minecraft:spider_eye"),
+    (388, "minecraft:emerald"),
+    (389, "minecraft:item_frame"),
+    (391, "minecraft:carrot"),
+    (392, "minecraft:potato"),
+    (394, "minecraft:poisonous_potato"),
+    (423, "minecraft:mutton"),
+];
+
+/// ID of the first spawn egg; spawn eggs have no legacy IDs of their own,
+/// so they take one each from here on, in [`MobType::ALL`] order
+const FIRST_SPAWN_EGG: u32 = 1000;
+
 /// Item registry managing item types and their properties
 pub struct ItemRegistry {
     /// Map of item ID to item info
@@ -220,13 +492,6 @@ impl ItemRegistry {
     /// Register default Minecraft items
     fn register_default_items(&mut self) {
         let default_items = [
-            ItemInfo {
-                id: 1,
-                name: "minecraft:stone".to_string(),
-                max_stack_size: 64,
-                damageable: false,
-                max_durability: None,
-            },
             ItemInfo {
                 id: 276,
                 name: "minecraft:diamond_sword".to_string(),
@@ -235,9 +500,16 @@ impl ItemRegistry {
                 max_durability: Some(1561),
             },
             ItemInfo {
-                id: 364,
-                name: "minecraft:bread".to_string(),
-                max_stack_size: 64,
+                id: 355,
+                name: "minecraft:red_bed".to_string(),
+                max_stack_size: 1,
+                damageable: false,
+                max_durability: None,
+            },
+            ItemInfo {
+                id: 416,
+                name: "minecraft:armor_stand".to_string(),
+                max_stack_size: 16,
                 damageable: false,
                 max_durability: None,
             },
@@ -246,6 +518,33 @@ impl ItemRegistry {
         for item in default_items {
             self.register_item(item);
         }
+        for (id, name, durability) in DAMAGEABLE_ITEMS {
+            self.register_item(ItemInfo {
+                id,
+                name: name.to_string(),
+                max_stack_size: 1,
+                damageable: true,
+                max_durability: Some(durability),
+            });
+        }
+        for (id, name) in STACKABLE_ITEMS {
+            self.register_item(ItemInfo {
+                id,
+                name: name.to_string(),
+                max_stack_size: 64,
+                damageable: false,
+                max_durability: None,
+            });
+        }
+        for (id, mob) in (FIRST_SPAWN_EGG..).zip(MobType::ALL) {
+            self.register_item(ItemInfo {
+                id,
+                name: summon::spawn_egg(mob),
+                max_stack_size: 64,
+                damageable: false,
+                max_durability: None,
+            });
+        }
     }
 }
 
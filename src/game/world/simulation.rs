@@ -0,0 +1,133 @@
+//! Simulation distance
+//!
+//! Chunks are sent to players out to their view distance, but only the
+//! chunks within the simulation distance of a player are simulated: their
+//! entities move and their blocks receive random ticks. A
+//! [`SimulationArea`] is built from the players' positions every tick,
+//! along with the spawn chunks of the overworld.
+//!
+//! Random ticks pick `randomTickSpeed` blocks in every 16 blocks high
+//! section of a simulated chunk. Blocks react to them through handlers
+//! registered with [`World::on_random_tick`](super::World::on_random_tick).
+
+use super::region::RegionTick;
+use super::{ChunkPosition, World};
+use crate::protocol::types::Position;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Reacts to a random tick of a block, given its position and block ID
+///
+/// Random ticks run region by region in parallel (see
+/// [`region`](super::region)), so handlers see the region being ticked.
+pub type RandomTickHandler = fn(&mut RegionTick<'_>, Position, u32);
+
+/// Reacts to a tick scheduled for a block, given its position and block ID
+pub type ScheduledTickHandler = fn(&mut World, Position, u32);
+
+/// Chunks within the simulation distance of a player
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationArea {
+    /// Chunks players are in, with the distance simulated around each
+    centers: Vec<(ChunkPosition, i32)>,
+    /// Simulation distance in chunks
+    distance: i32,
+}
+
+impl SimulationArea {
+    /// Create an area without players, simulating nothing
+    pub fn new(distance: u8) -> Self {
+        Self {
+            centers: Vec::new(),
+            distance: i32::from(distance),
+        }
+    }
+
+    /// Simulate the chunks around a player in `chunk`
+    pub fn add_player(&mut self, chunk: ChunkPosition) {
+        self.add_chunks(chunk, self.distance);
+    }
+
+    /// Simulate the chunks within `radius` of `center`, with or without a
+    /// player there
+    pub fn add_chunks(&mut self, center: ChunkPosition, radius: i32) {
+        if !self.centers.contains(&(center, radius)) {
+            self.centers.push((center, radius));
+        }
+    }
+
+    /// Whether a chunk is simulated
+    ///
+    /// Like vanilla's ticket levels, the distance is measured along the
+    /// axis the chunk is furthest on, so the area around a player is square.
+    pub fn contains(&self, chunk: ChunkPosition) -> bool {
+        self.centers.iter().any(|&(center, radius)| {
+            (chunk.x - center.x).abs().max((chunk.z - center.z).abs()) <= radius
+        })
+    }
+
+    /// Whether no chunk is simulated
+    pub fn is_empty(&self) -> bool {
+        self.centers.is_empty()
+    }
+}
+
+/// Source of the positions picked for random ticks
+///
+/// An xorshift generator: random ticks need to be cheap, not unpredictable.
+#[derive(Debug, Clone)]
+pub(crate) struct TickRandom(u64);
+
+impl TickRandom {
+    /// Create a generator with a random seed
+    pub(crate) fn new() -> Self {
+        Self(RandomState::new().build_hasher().finish() | 1)
+    }
+
+    /// Random number below `bound`
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    /// Random number in `0.0..1.0`
+    pub(crate) fn fraction(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Random seed for the client, such as of a sound
+    pub(crate) fn seed(&mut self) -> i64 {
+        self.next() as i64
+    }
+
+    /// Split off a generator with a seed drawn from this one
+    pub(crate) fn fork(&mut self) -> Self {
+        Self(self.next() | 1)
+    }
+
+    /// Advance the generator
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulation_area() {
+        let mut area = SimulationArea::new(2);
+        assert!(area.is_empty());
+        assert!(!area.contains(ChunkPosition::new(0, 0)));
+
+        area.add_player(ChunkPosition::new(0, 0));
+        area.add_player(ChunkPosition::new(10, 0));
+        assert!(area.contains(ChunkPosition::new(2, -2)));
+        assert!(area.contains(ChunkPosition::new(12, 1)));
+        assert!(!area.contains(ChunkPosition::new(3, 0)));
+        assert!(!area.contains(ChunkPosition::new(6, 0)));
+    }
+}
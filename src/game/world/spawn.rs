@@ -0,0 +1,180 @@
+//! World spawn
+//!
+//! A new world picks its spawn from its terrain: the column closest to
+//! 0, 0 whose highest block is something to stand on rather than water or
+//! leaves. Like vanilla, the chunks within the `spawnChunkRadius` game rule
+//! of the spawn stay loaded and simulated whether or not a player is near;
+//! a radius of 0 keeps none.
+
+use super::farming::WATER;
+use super::{ChunkPosition, World, leaves};
+use crate::protocol::types::Position;
+
+/// Chunks kept loaded around the spawn chunk by default, like vanilla's
+/// `spawnChunkRadius`
+pub const DEFAULT_SPAWN_CHUNK_RADIUS: u32 = 2;
+
+/// Largest `spawnChunkRadius` allowed, as in vanilla
+pub const MAX_SPAWN_CHUNK_RADIUS: u32 = 32;
+
+/// Farthest from 0, 0 a new world's spawn is looked for, in blocks
+const SEARCH_RADIUS: i32 = 16;
+
+/// Whether a player can spawn standing on a block
+fn is_spawnable(block: u32) -> bool {
+    block != 0 && block != WATER && leaves::leaf_distance(block).is_none()
+}
+
+/// Find a spawn for a new world: on top of the highest block of the
+/// closest column near 0, 0 that is not water
+///
+/// Falls back to the world's current spawn if every column is water.
+pub fn find_spawn(world: &mut World) -> Position {
+    let mut columns: Vec<(i32, i32)> = (-SEARCH_RADIUS..=SEARCH_RADIUS)
+        .flat_map(|x| (-SEARCH_RADIUS..=SEARCH_RADIUS).map(move |z| (x, z)))
+        .collect();
+    columns.sort_by_key(|&(x, z)| x * x + z * z);
+
+    for (x, z) in columns {
+        let chunk_position = ChunkPosition::from_world_coords(f64::from(x), f64::from(z));
+        let chunk = world.load_chunk(chunk_position);
+        let local_x = (x - chunk_position.world_x()) as usize;
+        let local_z = (z - chunk_position.world_z()) as usize;
+        let Some(height) = chunk.get_height(local_x, local_z) else {
+            continue;
+        };
+        if chunk
+            .get_block(local_x, height, local_z)
+            .is_some_and(is_spawnable)
+        {
+            return Position::new(x, height as i32 + 1, z);
+        }
+    }
+    world.spawn_position()
+}
+
+/// Chunk of a spawn position
+pub fn spawn_chunk(spawn: Position) -> ChunkPosition {
+    ChunkPosition::from_world_coords(f64::from(spawn.x), f64::from(spawn.z))
+}
+
+/// Chunks kept loaded around a spawn position with a `spawnChunkRadius`
+pub fn spawn_chunks(spawn: Position, radius: u32) -> impl Iterator<Item = ChunkPosition> {
+    let center = spawn_chunk(spawn);
+    let radius = radius.min(MAX_SPAWN_CHUNK_RADIUS) as i32;
+    (-radius..=radius)
+        .flat_map(move |dx| {
+            (-radius..=radius).map(move |dz| ChunkPosition::new(center.x + dx, center.z + dz))
+        })
+        // A radius of 0 keeps no chunks rather than just the spawn chunk
+        .filter(move |_| radius > 0)
+}
+
+/// Whether a chunk is kept loaded around a spawn position with a
+/// `spawnChunkRadius`
+pub fn is_spawn_chunk(spawn: Position, radius: u32, chunk: ChunkPosition) -> bool {
+    let center = spawn_chunk(spawn);
+    let distance = (chunk.x - center.x).abs().max((chunk.z - center.z).abs());
+    radius > 0 && distance.unsigned_abs() <= radius.min(MAX_SPAWN_CHUNK_RADIUS)
+}
+
+/// Load the chunks around the world spawn
+///
+/// Returns the number of chunks that were not loaded yet.
+pub fn load_spawn_chunks(world: &mut World) -> usize {
+    let radius = world.game_rules().spawn_chunk_radius;
+    spawn_chunks(world.spawn_position(), radius)
+        .filter(|&chunk| {
+            let loaded = world.is_chunk_loaded(chunk);
+            world.load_chunk(chunk);
+            !loaded
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_spawn() {
+        let mut world = World::new("spawn".to_string(), 0);
+        assert_eq!(find_spawn(&mut world), Position::new(0, 64, 0));
+
+        // Water at 0, 0 moves the spawn to the closest dry column
+        world.set_block(Position::new(0, 63, 0), WATER);
+        let spawn = find_spawn(&mut world);
+        assert_eq!(spawn.y, 64);
+        assert_eq!(spawn.x.abs() + spawn.z.abs(), 1);
+    }
+
+    #[test]
+    fn test_spawn_chunks() {
+        let spawn = Position::new(-20, 70, 40);
+        let chunks: Vec<_> = spawn_chunks(spawn, 2).collect();
+        assert_eq!(chunks.len(), 25);
+        assert!(chunks.iter().all(|&chunk| is_spawn_chunk(spawn, 2, chunk)));
+        assert!(!is_spawn_chunk(spawn, 2, ChunkPosition::new(1, 2)));
+        assert!(is_spawn_chunk(spawn, 2, ChunkPosition::new(0, 4)));
+        assert_eq!(spawn_chunks(spawn, 0).count(), 0);
+        assert!(!is_spawn_chunk(spawn, 0, spawn_chunk(spawn)));
+
+        let mut world = World::new("spawn".to_string(), 0);
+        world.set_spawn_position(spawn);
+        assert_eq!(load_spawn_chunks(&mut world), 25);
+        assert_eq!(load_spawn_chunks(&mut world), 0);
+        world.unload_chunk(ChunkPosition::new(-2, 2));
+        assert!(world.is_chunk_loaded(ChunkPosition::new(-2, 2)));
+
+        // Without spawn chunks, the spawn chunk unloads like any other
+        world.game_rules_mut().spawn_chunk_radius = 0;
+        world.unload_chunk(ChunkPosition::new(-2, 2));
+        assert!(!world.is_chunk_loaded(ChunkPosition::new(-2, 2)));
+    }
+
+    #[test]
+    fn test_spawn_chunk_radius_changes() {
+        let mut world = World::new("spawn".to_string(), 0);
+        assert_eq!(load_spawn_chunks(&mut world), 25);
+        let edge = ChunkPosition::new(2, -2);
+        let inner = ChunkPosition::new(1, 1);
+
+        // A smaller radius lets the old edge unload but keeps the rest
+        world.game_rules_mut().spawn_chunk_radius = 1;
+        assert!(!world.is_spawn_chunk(edge));
+        world.unload_chunk(edge);
+        world.unload_chunk(inner);
+        assert!(!world.is_chunk_loaded(edge));
+        assert!(world.is_chunk_loaded(inner));
+
+        // A larger one loads just the chunks that were not kept yet
+        world.game_rules_mut().spawn_chunk_radius = 3;
+        assert_eq!(load_spawn_chunks(&mut world), 49 - 24);
+        assert!(world.is_spawn_chunk(ChunkPosition::new(-3, 3)));
+        assert!(!world.is_spawn_chunk(ChunkPosition::new(-4, 3)));
+    }
+
+    #[test]
+    fn test_spawn_chunk_radius_clamps() {
+        let spawn = Position::new(0, 64, 0);
+        let side = 2 * MAX_SPAWN_CHUNK_RADIUS as usize + 1;
+        assert_eq!(spawn_chunks(spawn, u32::MAX).count(), side * side);
+        assert_eq!(
+            spawn_chunks(spawn, MAX_SPAWN_CHUNK_RADIUS + 1).count(),
+            spawn_chunks(spawn, MAX_SPAWN_CHUNK_RADIUS).count()
+        );
+
+        let limit = MAX_SPAWN_CHUNK_RADIUS as i32;
+        let far = ChunkPosition::new(limit + 1, 0);
+        assert!(is_spawn_chunk(
+            spawn,
+            u32::MAX,
+            ChunkPosition::new(limit, -limit)
+        ));
+        assert!(!is_spawn_chunk(spawn, u32::MAX, far));
+
+        let mut world = World::new("spawn".to_string(), 0);
+        world.game_rules_mut().spawn_chunk_radius = MAX_SPAWN_CHUNK_RADIUS + 8;
+        assert!(!world.is_spawn_chunk(far));
+    }
+}
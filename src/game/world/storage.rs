@@ -0,0 +1,411 @@
+//! World storage
+//!
+//! Reads and writes a world directory in the vanilla layout: `level.dat`
+//! for world-wide state and Anvil region files under `region/` for chunks.
+//! The nether's region files are under `DIM-1/region/`. Entities are kept
+//! in each chunk's `Entities` list, as vanilla did before separate entity
+//! region files.
+
+use super::ChunkPosition;
+use super::anvil::{RegionCompression, RegionFile, region_file_name};
+use super::chunk::{CHUNK_HEIGHT, CHUNK_MIN_Y, CHUNK_SIZE, Chunk};
+use super::dimension::Dimension;
+use super::level::{DATA_VERSION, LevelData};
+use super::registry::BlockRegistry;
+use crate::error::{Result, ServerError};
+use crate::protocol::nbt::{self, Compound, Tag};
+use flate2::Compression as FlateCompression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Number of blocks in a 16x16x16 chunk section
+const SECTION_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// Name written for blocks missing from the registry
+const AIR: &str = "minecraft:air";
+
+/// Reads and writes the files of a world directory
+pub struct WorldStorage {
+    /// World directory
+    directory: PathBuf,
+    /// Registry used to map block IDs to names
+    blocks: Arc<BlockRegistry>,
+    /// Dimension whose chunks are read and written
+    dimension: Dimension,
+    /// Compression used for newly written chunks
+    compression: RegionCompression,
+    /// Flush every chunk write to disk before continuing
+    sync_writes: bool,
+    /// Open region files by region coordinates
+    regions: HashMap<(i32, i32), RegionFile>,
+}
+
+impl WorldStorage {
+    /// Create storage for the given world directory
+    pub fn new<P: AsRef<Path>>(directory: P, blocks: Arc<BlockRegistry>) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            blocks,
+            dimension: Dimension::Overworld,
+            compression: RegionCompression::default(),
+            sync_writes: true,
+            regions: HashMap::new(),
+        }
+    }
+
+    /// Read and write the chunks of a dimension other than the overworld
+    pub fn with_dimension(mut self, dimension: Dimension) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    /// Set the compression used for newly written chunks
+    ///
+    /// Chunks are read with whatever compression they were written with.
+    pub fn with_compression(mut self, compression: RegionCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set whether chunk writes are flushed to disk before continuing
+    pub fn with_sync_writes(mut self, sync: bool) -> Self {
+        self.sync_writes = sync;
+        self
+    }
+
+    /// Get the world directory
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Get the path of level.dat
+    pub fn level_path(&self) -> PathBuf {
+        self.directory.join("level.dat")
+    }
+
+    /// Load level.dat, returning `None` for a new world
+    pub fn load_level(&self) -> Result<Option<LevelData>> {
+        let file = match std::fs::File::open(self.level_path()) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let (_, root) = nbt::read_named(&mut GzDecoder::new(std::io::BufReader::new(file)))?;
+        LevelData::from_nbt(&root).map(Some)
+    }
+
+    /// Save level.dat
+    ///
+    /// The file is written to a temporary path first and then renamed, so a
+    /// crash never leaves a truncated file behind.
+    pub fn save_level(&self, level: &LevelData, flush: bool) -> Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+
+        let mut encoder = GzEncoder::new(Vec::new(), FlateCompression::default());
+        nbt::write_named("", &level.to_nbt(), &mut encoder)?;
+        let bytes = encoder.finish()?;
+
+        let path = self.level_path();
+        let temp = path.with_extension("dat.tmp");
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(&bytes)?;
+        if self.sync_writes || flush {
+            file.sync_all()?;
+        }
+        std::fs::rename(temp, path)?;
+        Ok(())
+    }
+
+    /// Load a chunk, returning `None` if it was never saved
+    pub fn load_chunk(&mut self, position: ChunkPosition) -> Result<Option<Chunk>> {
+        let path = self.region_path(position);
+        if !self.regions.contains_key(&region_key(position)) && !path.exists() {
+            return Ok(None);
+        }
+
+        let Some(data) = self.region(position)?.read_chunk(position.x, position.z)? else {
+            return Ok(None);
+        };
+        let (_, root) = nbt::read_named(&mut data.as_slice())?;
+        decode_chunk(&root, position, &self.blocks).map(Some)
+    }
+
+    /// Save a chunk
+    ///
+    /// With `flush`, the write is flushed to disk even if sync writes are off.
+    pub fn save_chunk(&mut self, chunk: &Chunk, flush: bool) -> Result<()> {
+        let data = chunk_data(chunk, &self.blocks)?;
+        self.write_chunk_data(chunk.position(), &data, flush)
+    }
+
+    /// Write a chunk already encoded with [`chunk_data`]
+    ///
+    /// With `flush`, the write is flushed to disk even if sync writes are off.
+    pub fn write_chunk_data(
+        &mut self,
+        position: ChunkPosition,
+        data: &[u8],
+        flush: bool,
+    ) -> Result<()> {
+        let (compression, sync) = (self.compression, self.sync_writes || flush);
+        self.region(position)?
+            .write_chunk(position.x, position.z, data, compression, sync)
+    }
+
+    /// Registry used to map block IDs to names
+    pub fn blocks(&self) -> &Arc<BlockRegistry> {
+        &self.blocks
+    }
+
+    /// Get the directory holding the dimension's region files
+    fn region_directory(&self) -> PathBuf {
+        match self.dimension.directory() {
+            Some(dimension) => self.directory.join(dimension).join("region"),
+            None => self.directory.join("region"),
+        }
+    }
+
+    /// Get the path of the region file holding a chunk
+    fn region_path(&self, position: ChunkPosition) -> PathBuf {
+        self.region_directory()
+            .join(region_file_name(position.x, position.z))
+    }
+
+    /// Get the open region file holding a chunk, opening it if needed
+    fn region(&mut self, position: ChunkPosition) -> Result<&mut RegionFile> {
+        let key = region_key(position);
+        if !self.regions.contains_key(&key) {
+            std::fs::create_dir_all(self.region_directory())?;
+            let region = RegionFile::open(self.region_path(position))?;
+            self.regions.insert(key, region);
+        }
+        Ok(self.regions.get_mut(&key).expect("region was just opened"))
+    }
+}
+
+/// Encode a chunk as the uncompressed NBT stored for it in a region file
+pub fn chunk_data(chunk: &Chunk, blocks: &BlockRegistry) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    nbt::write_named("", &encode_chunk(chunk, blocks), &mut data)?;
+    Ok(data)
+}
+
+/// Get the region coordinates of a chunk
+fn region_key(position: ChunkPosition) -> (i32, i32) {
+    (position.x.div_euclid(32), position.z.div_euclid(32))
+}
+
+/// Number of bits used per palette index for a palette of `len` entries
+fn bits_per_entry(len: usize) -> usize {
+    let needed = usize::BITS - (len.max(2) - 1).leading_zeros();
+    (needed as usize).max(4)
+}
+
+/// Encode a chunk as vanilla chunk NBT
+fn encode_chunk(chunk: &Chunk, blocks: &BlockRegistry) -> Compound {
+    let position = chunk.position();
+    let mut sections = Vec::with_capacity(CHUNK_HEIGHT / CHUNK_SIZE);
+
+    for section in 0..CHUNK_HEIGHT / CHUNK_SIZE {
+        let mut palette: Vec<u32> = Vec::new();
+        let mut indices = Vec::with_capacity(SECTION_VOLUME);
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let id = chunk.get_block(x, section * CHUNK_SIZE + y, z).unwrap_or(0);
+                    let index = match palette.iter().position(|&p| p == id) {
+                        Some(index) => index,
+                        None => {
+                            palette.push(id);
+                            palette.len() - 1
+                        }
+                    };
+                    indices.push(index as u64);
+                }
+            }
+        }
+
+        let palette_tags = palette
+            .iter()
+            .map(|&id| {
+                let name = match blocks.get_block(id) {
+                    Some(info) => info.name.as_str(),
+                    None => {
+                        tracing::warn!("Saving unknown block ID {} as air", id);
+                        AIR
+                    }
+                };
+                let mut entry = Compound::new();
+                entry.insert("Name".to_string(), name.into());
+                Tag::Compound(entry)
+            })
+            .collect();
+
+        let mut block_states = Compound::new();
+        block_states.insert("palette".to_string(), Tag::List(palette_tags));
+        if palette.len() > 1 {
+            block_states.insert(
+                "data".to_string(),
+                Tag::LongArray(pack_indices(&indices, bits_per_entry(palette.len()))),
+            );
+        }
+
+        let mut tag = Compound::new();
+        tag.insert(
+            "Y".to_string(),
+            Tag::Byte((section as i32 + CHUNK_MIN_Y / CHUNK_SIZE as i32) as i8),
+        );
+        tag.insert("block_states".to_string(), block_states.into());
+        sections.push(Tag::Compound(tag));
+    }
+
+    let mut root = Compound::new();
+    root.insert("DataVersion".to_string(), DATA_VERSION.into());
+    root.insert("xPos".to_string(), position.x.into());
+    root.insert("zPos".to_string(), position.z.into());
+    root.insert("yPos".to_string(), (CHUNK_MIN_Y / CHUNK_SIZE as i32).into());
+    root.insert("Status".to_string(), "minecraft:full".into());
+    root.insert("sections".to_string(), Tag::List(sections));
+    if !chunk.entities().is_empty() {
+        let entities = chunk.entities().iter().cloned().map(Tag::Compound);
+        root.insert("Entities".to_string(), Tag::List(entities.collect()));
+    }
+    root
+}
+
+/// Decode vanilla chunk NBT
+fn decode_chunk(root: &Compound, position: ChunkPosition, blocks: &BlockRegistry) -> Result<Chunk> {
+    let sections = root.get("sections").and_then(Tag::as_list).ok_or_else(|| {
+        ServerError::WorldIo(format!("Chunk {:?} has no sections list", position))
+    })?;
+
+    let mut chunk = Chunk::new(position);
+    for section in sections.iter().filter_map(Tag::as_compound) {
+        let Some(y) = section.get("Y").and_then(Tag::as_i64) else {
+            continue;
+        };
+        let index = y - (CHUNK_MIN_Y / CHUNK_SIZE as i32) as i64;
+        if index < 0 || index as usize >= CHUNK_HEIGHT / CHUNK_SIZE {
+            continue;
+        }
+        let base_y = index as usize * CHUNK_SIZE;
+
+        let Some(block_states) = section.get("block_states").and_then(Tag::as_compound) else {
+            continue;
+        };
+        let palette: Vec<u32> = block_states
+            .get("palette")
+            .and_then(Tag::as_list)
+            .unwrap_or_default()
+            .iter()
+            .map(|entry| {
+                let name = entry
+                    .as_compound()
+                    .and_then(|entry| entry.get("Name"))
+                    .and_then(Tag::as_str)
+                    .unwrap_or(AIR);
+                blocks.get_block_id(name).unwrap_or_else(|| {
+                    tracing::warn!("Loading unknown block {} as air", name);
+                    0
+                })
+            })
+            .collect();
+
+        let indices = match block_states.get("data").and_then(Tag::as_long_array) {
+            Some(data) if palette.len() > 1 => {
+                unpack_indices(data, bits_per_entry(palette.len()), SECTION_VOLUME)
+            }
+            _ => vec![0; SECTION_VOLUME],
+        };
+
+        for (i, &palette_index) in indices.iter().enumerate() {
+            let id = palette.get(palette_index as usize).copied().unwrap_or(0);
+            if id != 0 {
+                let (x, z, y) = (i % CHUNK_SIZE, (i / CHUNK_SIZE) % CHUNK_SIZE, i / 256);
+                chunk.set_block(x, base_y + y, z, id);
+            }
+        }
+    }
+
+    if let Some(entities) = root.get("Entities").and_then(Tag::as_list) {
+        let entities = entities.iter().filter_map(Tag::as_compound).cloned();
+        chunk.set_entities(entities.collect());
+    }
+    chunk.mark_saved();
+    Ok(chunk)
+}
+
+/// Pack palette indices into longs without spanning entries across longs
+fn pack_indices(indices: &[u64], bits: usize) -> Vec<i64> {
+    let per_long = 64 / bits;
+    indices
+        .chunks(per_long)
+        .map(|group| {
+            group
+                .iter()
+                .enumerate()
+                .fold(0u64, |long, (i, &index)| long | (index << (i * bits))) as i64
+        })
+        .collect()
+}
+
+/// Unpack `count` palette indices packed by [`pack_indices`]
+fn unpack_indices(data: &[i64], bits: usize, count: usize) -> Vec<u64> {
+    let per_long = 64 / bits;
+    let mask = (1u64 << bits) - 1;
+    (0..count)
+        .map(|i| {
+            data.get(i / per_long)
+                .map_or(0, |&long| (long as u64 >> ((i % per_long) * bits)) & mask)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_roundtrip() {
+        let blocks = Arc::new(BlockRegistry::new());
+        let directory = std::env::temp_dir().join(format!("obsidium-{}", uuid::Uuid::new_v4()));
+        let mut storage = WorldStorage::new(&directory, blocks).with_sync_writes(false);
+
+        let position = ChunkPosition::new(-3, 40);
+        let mut chunk = Chunk::generate_flat(position);
+        for (i, id) in (0..9).cycle().take(40).enumerate() {
+            chunk.set_block(i % 16, 100 + i / 16, 5, id);
+        }
+        storage.save_chunk(&chunk, false).unwrap();
+        assert!(
+            storage
+                .load_chunk(ChunkPosition::new(0, 0))
+                .unwrap()
+                .is_none()
+        );
+
+        let mut reopened = WorldStorage::new(&directory, Arc::new(BlockRegistry::new()));
+        let loaded = reopened.load_chunk(position).unwrap().unwrap();
+        assert!(!loaded.is_modified());
+        assert_eq!(loaded.blocks(), chunk.blocks());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_pack_indices() {
+        let indices: Vec<u64> = (0..SECTION_VOLUME as u64).map(|i| i % 17).collect();
+        let bits = bits_per_entry(17);
+        assert_eq!(bits, 5);
+        assert_eq!(
+            unpack_indices(&pack_indices(&indices, bits), bits, SECTION_VOLUME),
+            indices
+        );
+        assert_eq!(bits_per_entry(2), 4);
+    }
+}
@@ -0,0 +1,506 @@
+//! Localization
+//!
+//! Messages sent to players are `translate` components, so every client
+//! shows them in its own language. [`translate`] builds such a component,
+//! carrying the English text as its fallback for keys the client does not
+//! know.
+//!
+//! The console, RCON and the logs render the same components in the
+//! server's language, set with `language` in the `[server]` section of
+//! obsidium.toml. `en_us` is built in; other languages are read from
+//! `lang/<code>.json`, a file in the vanilla format (one object mapping
+//! keys to texts), and fall back to English for missing keys.
+
+use crate::error::{Result, ServerError};
+use crate::protocol::types::JsonTextComponent;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// Folder language files are read from
+pub const LANG_DIR: &str = "lang";
+
+/// Language used when none is configured
+pub const DEFAULT_LANGUAGE: &str = "en_us";
+
+/// English texts of the keys the server uses
+const EN_US: &[(&str, &str)] = &[
+    ("argument.entity.notfound.player", "No player was found"),
+    (
+        "argument.player.toomany",
+        "Only one player is allowed, but the provided selector allows more than one",
+    ),
+    ("argument.player.unknown", "That player does not exist"),
+    (
+        "argument.pos.outofworld",
+        "That position is out of this world!",
+    ),
+    (
+        "argument.resource.not_found",
+        "Can't find element '%s' of type '%s'",
+    ),
+    (
+        "chat.spam.duplicate",
+        "Please don't repeat the same message",
+    ),
+    ("chat.spam.too_fast", "You are sending messages too quickly"),
+    (
+        "chat.spam.too_long",
+        "Messages may be at most %s characters long",
+    ),
+    ("chat.type.announcement", "[%s] %s"),
+    ("chat.type.emote", "* %s %s"),
+    ("chat.type.text", "<%s> %s"),
+    (
+        "commands.ban.failed",
+        "Nothing changed. The player is already banned",
+    ),
+    ("commands.ban.success", "Banned %s: %s"),
+    (
+        "commands.banip.failed",
+        "Nothing changed. That IP is already banned",
+    ),
+    ("commands.banip.info", "This ban affects %s player(s): %s"),
+    (
+        "commands.banip.invalid",
+        "Invalid IP address or unknown player",
+    ),
+    ("commands.banip.success", "Banned IP %s: %s"),
+    (
+        "commands.datapack.disable.failed",
+        "Pack '%s' is not enabled!",
+    ),
+    (
+        "commands.datapack.enable.failed",
+        "Pack '%s' is already enabled!",
+    ),
+    (
+        "commands.datapack.list.available.none",
+        "There are no more data packs available",
+    ),
+    (
+        "commands.datapack.list.available.success",
+        "There are %s data pack(s) available: %s",
+    ),
+    (
+        "commands.datapack.list.enabled.none",
+        "There are no data packs enabled",
+    ),
+    (
+        "commands.datapack.list.enabled.success",
+        "There are %s data pack(s) enabled: %s",
+    ),
+    ("commands.datapack.modify.disable", "Disabling data pack %s"),
+    ("commands.datapack.modify.enable", "Enabling data pack %s"),
+    ("commands.datapack.unknown", "Unknown data pack '%s'"),
+    (
+        "commands.deop.failed",
+        "Nothing changed. The player is not an operator",
+    ),
+    (
+        "commands.deop.success",
+        "Made %s no longer a server operator",
+    ),
+    (
+        "commands.difficulty.failure",
+        "The difficulty did not change; it is already set to %s",
+    ),
+    ("commands.difficulty.query", "The difficulty is %s"),
+    (
+        "commands.difficulty.success",
+        "The difficulty has been set to %s",
+    ),
+    (
+        "commands.help.failed",
+        "Unknown command or insufficient permissions",
+    ),
+    ("commands.kick.success", "Kicked %s: %s"),
+    (
+        "commands.list.players",
+        "There are %s of a max of %s players online: %s",
+    ),
+    (
+        "commands.op.failed",
+        "Nothing changed. The player already is an operator",
+    ),
+    ("commands.op.success", "Made %s a server operator"),
+    (
+        "commands.pardon.failed",
+        "Nothing changed. The player isn't banned",
+    ),
+    ("commands.pardon.success", "Unbanned %s"),
+    (
+        "commands.pardonip.failed",
+        "Nothing changed. That IP isn't banned",
+    ),
+    ("commands.pardonip.invalid", "Invalid IP address"),
+    ("commands.pardonip.success", "Unbanned IP %s"),
+    ("commands.save.alreadyOff", "Saving is already turned off"),
+    ("commands.save.alreadyOn", "Saving is already turned on"),
+    ("commands.save.disabled", "Automatic saving is now disabled"),
+    ("commands.save.enabled", "Automatic saving is now enabled"),
+    (
+        "commands.save.failed",
+        "Unable to save the game (is there enough disk space?)",
+    ),
+    (
+        "commands.save.saving",
+        "Saving the game (this may take a moment!)",
+    ),
+    ("commands.save.success", "Saved the game"),
+    ("commands.seed.success", "Seed: %s"),
+    (
+        "commands.setworldspawn.success",
+        "Set the world spawn point to %s, %s, %s [%s]",
+    ),
+    (
+        "commands.spawnpoint.success.multiple",
+        "Set spawn point to %s, %s, %s [%s] in %s for %s players",
+    ),
+    (
+        "commands.spawnpoint.success.single",
+        "Set spawn point to %s, %s, %s [%s] in %s for %s",
+    ),
+    ("commands.stop.stopping", "Stopping the server"),
+    ("commands.summon.failed", "Unable to summon entity"),
+    (
+        "commands.summon.invalidPosition",
+        "Invalid position for summon",
+    ),
+    ("commands.summon.success", "Summoned new %s"),
+    ("commands.time.query", "The time is %s"),
+    ("commands.time.set", "Set the time to %s"),
+    ("commands.weather.set.clear", "Set the weather to clear"),
+    ("commands.weather.set.rain", "Set the weather to rain"),
+    (
+        "commands.weather.set.thunder",
+        "Set the weather to rain & thunder",
+    ),
+    (
+        "commands.whitelist.add.failed",
+        "Player is already whitelisted",
+    ),
+    (
+        "commands.whitelist.add.success",
+        "Added %s to the whitelist",
+    ),
+    (
+        "commands.whitelist.list",
+        "There are %s whitelisted player(s): %s",
+    ),
+    (
+        "commands.whitelist.none",
+        "There are no whitelisted players",
+    ),
+    ("commands.whitelist.reloaded", "Reloaded the whitelist"),
+    (
+        "commands.whitelist.remove.failed",
+        "Player is not whitelisted",
+    ),
+    (
+        "commands.whitelist.remove.success",
+        "Removed %s from the whitelist",
+    ),
+    ("disconnect.overflow", "Buffer overflow"),
+    ("disconnect.packetError", "Network Protocol Error"),
+    ("disconnect.spam", "Kicked for spamming"),
+    ("disconnect.timeout", "Timed out"),
+    (
+        "multiplayer.disconnect.authservers_down",
+        "Authentication servers are down. Please try again later, sorry!",
+    ),
+    (
+        "multiplayer.disconnect.banned.expiration",
+        "\nYour ban will be removed on %s",
+    ),
+    (
+        "multiplayer.disconnect.banned.reason",
+        "You are banned from this server.\nReason: %s",
+    ),
+    (
+        "multiplayer.disconnect.banned_ip.reason",
+        "Your IP address is banned from this server.\nReason: %s",
+    ),
+    ("multiplayer.disconnect.kicked", "Kicked by an operator"),
+    (
+        "multiplayer.disconnect.duplicate_login",
+        "You logged in from another location",
+    ),
+    (
+        "multiplayer.disconnect.illegal_characters",
+        "Illegal characters in chat",
+    ),
+    (
+        "multiplayer.disconnect.not_whitelisted",
+        "You are not white-listed on this server!",
+    ),
+    (
+        "multiplayer.disconnect.outdated_client",
+        "Outdated client! Please use %s",
+    ),
+    (
+        "multiplayer.disconnect.outdated_server",
+        "Outdated server! I'm still on %s",
+    ),
+    ("multiplayer.disconnect.server_full", "Server is full!"),
+    (
+        "multiplayer.disconnect.slow_login",
+        "Took too long to log in",
+    ),
+    (
+        "multiplayer.disconnect.unverified_username",
+        "Failed to verify username!",
+    ),
+    ("multiplayer.player.joined", "%s joined the game"),
+    ("multiplayer.player.left", "%s left the game"),
+    (
+        "multiplayer.requiredTexturePrompt.disconnect",
+        "Server requires a custom resource pack",
+    ),
+    (
+        "obsidium.disconnect.throttled",
+        "Connection throttled! Please wait before reconnecting.",
+    ),
+    (
+        "obsidium.disconnect.velocity_required",
+        "This server requires you to connect with Velocity.",
+    ),
+    ("obsidium.help.header", "--- Help page %s of %s ---"),
+    (
+        "obsidium.pregen.already_running",
+        "A pre-generation is already running; use /pregen cancel to stop it",
+    ),
+    (
+        "obsidium.pregen.cancelled",
+        "Cancelled the pre-generation after %s of %s chunks",
+    ),
+    (
+        "obsidium.pregen.done",
+        "Pre-generation done: %s chunks in %ss",
+    ),
+    (
+        "obsidium.pregen.failed",
+        "Pre-generation stopped after %s chunks: %s",
+    ),
+    (
+        "obsidium.pregen.not_running",
+        "No pre-generation is running",
+    ),
+    (
+        "obsidium.pregen.progress",
+        "Pre-generation: %s%% (%s of %s chunks, %s chunks/s)",
+    ),
+    (
+        "obsidium.pregen.started",
+        "Pre-generating %s chunks around the world spawn",
+    ),
+    (
+        "obsidium.simulationdistance.failure",
+        "Nothing changed. The simulation distance already is %s chunks",
+    ),
+    (
+        "obsidium.simulationdistance.query",
+        "The simulation distance is %s chunks",
+    ),
+    (
+        "obsidium.simulationdistance.success",
+        "Set the simulation distance to %s chunks",
+    ),
+    (
+        "obsidium.viewdistance.failure",
+        "Nothing changed. The view distance already is %s chunks",
+    ),
+    (
+        "obsidium.viewdistance.query",
+        "The view distance is %s chunks",
+    ),
+    (
+        "obsidium.viewdistance.success",
+        "Set the view distance to %s chunks",
+    ),
+    (
+        "permissions.requires.player",
+        "A player is required to run this command here",
+    ),
+];
+
+/// Language the server renders messages in
+static SERVER_LANGUAGE: RwLock<Option<Arc<Language>>> = RwLock::new(None);
+
+/// Texts of one language by translation key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Language {
+    /// Language code, such as `en_us`
+    code: String,
+    /// Texts by key
+    translations: HashMap<String, String>,
+}
+
+impl Language {
+    /// The built-in English language
+    pub fn en_us() -> Self {
+        Self {
+            code: DEFAULT_LANGUAGE.to_string(),
+            translations: EN_US
+                .iter()
+                .map(|(key, text)| (key.to_string(), text.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Parse a vanilla language file on top of the English texts
+    pub fn parse(code: &str, json: &str) -> Result<Self> {
+        let texts: HashMap<String, String> = serde_json::from_str(json).map_err(|e| {
+            ServerError::Configuration(format!("Invalid language file for {}: {}", code, e))
+        })?;
+        let mut language = Self::en_us();
+        language.code = code.to_string();
+        language.translations.extend(texts);
+        Ok(language)
+    }
+
+    /// Load a language from `<directory>/<code>.json`
+    ///
+    /// `en_us` needs no file; if one exists it overrides the built-in texts.
+    pub fn load(code: &str, directory: &Path) -> Result<Self> {
+        let valid = !code.is_empty()
+            && code
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid {
+            return Err(ServerError::Configuration(format!(
+                "Invalid language code '{}'",
+                code
+            )));
+        }
+
+        let path = directory.join(format!("{}.json", code));
+        match std::fs::read_to_string(&path) {
+            Ok(json) => Self::parse(code, &json),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && code == DEFAULT_LANGUAGE => {
+                Ok(Self::en_us())
+            }
+            Err(e) => Err(ServerError::Configuration(format!(
+                "Unable to read {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    /// Language code
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Text of a key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.translations.get(key).map(String::as_str)
+    }
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Self::en_us()
+    }
+}
+
+/// Make a language the one the server renders messages in
+pub fn set_server_language(language: Language) {
+    tracing::info!("Using language {}", language.code());
+    *SERVER_LANGUAGE.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(language));
+}
+
+/// Language the server renders messages in
+pub fn server_language() -> Arc<Language> {
+    let mut language = SERVER_LANGUAGE.write().unwrap_or_else(|e| e.into_inner());
+    Arc::clone(language.get_or_insert_with(|| Arc::new(Language::en_us())))
+}
+
+/// English text of a key, as built into the server
+pub fn english(key: &str) -> Option<&'static str> {
+    EN_US
+        .iter()
+        .find(|(known, _)| *known == key)
+        .map(|(_, text)| *text)
+}
+
+/// Build a component the client translates, with arguments for its `%s` slots
+///
+/// The English text is sent as the fallback in case the client does not
+/// know the key.
+pub fn translate(key: &str, args: &[&str]) -> JsonTextComponent {
+    let mut component = serde_json::json!({ "translate": key });
+    if let Some(fallback) = english(key) {
+        component["fallback"] = fallback.into();
+    }
+    if !args.is_empty() {
+        component["with"] = args.iter().map(|arg| arg.to_string()).collect();
+    }
+    JsonTextComponent(component.to_string())
+}
+
+/// Fill the `%s` and `%1$s` slots of a text; `%%` is a literal `%`
+///
+/// Slots without an argument are left empty.
+pub fn format(template: &str, args: &[String]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        if let Some(after) = rest.strip_prefix('%') {
+            out.push('%');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('s') {
+            out.push_str(args.get(next).map_or("", String::as_str));
+            next += 1;
+            rest = after;
+        } else if let Some((index, after)) = positional(rest) {
+            out.push_str(args.get(index).map_or("", String::as_str));
+            rest = after;
+        } else {
+            out.push('%');
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Parse the `1$s` of a positional slot, returning the argument index
+fn positional(text: &str) -> Option<(usize, &str)> {
+    let (digits, after) = text.split_once("$s")?;
+    let index: usize = digits.parse().ok()?;
+    Some((index.checked_sub(1)?, after))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(
+            format("%s of %s, %2$s then %1$s: 100%%", &["a".into(), "b".into()]),
+            "a of b, b then a: 100%"
+        );
+        assert_eq!(format("%s and %s", &["only".into()]), "only and ");
+
+        let component = translate("commands.seed.success", &["[42]"]);
+        assert_eq!(component.to_plain_text(), "Seed: [42]");
+        let json: serde_json::Value = serde_json::from_str(&component.0).unwrap();
+        assert_eq!(json["fallback"], "Seed: %s");
+        assert_eq!(json["with"][0], "[42]");
+
+        let german =
+            Language::parse("de_de", r#"{"commands.seed.success": "Startwert: %s"}"#).unwrap();
+        assert_eq!(german.get("commands.seed.success"), Some("Startwert: %s"));
+        assert_eq!(german.get("commands.save.success"), Some("Saved the game"));
+        assert!(Language::load("../secrets", Path::new(LANG_DIR)).is_err());
+        assert_eq!(
+            Language::load(DEFAULT_LANGUAGE, Path::new("missing")).unwrap(),
+            Language::en_us()
+        );
+    }
+}
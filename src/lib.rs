@@ -16,6 +16,15 @@
 //! - [`game`] - Game logic including players, worlds, and entities
 //! - [`server`] - Core server implementation and orchestration
 //! - [`config`] - Configuration management
+//! - [`clock`] - Time sources that tests can control
+//! - [`command`] - Command framework, dispatcher and target selectors
+//! - [`lang`] - Translated server messages and language files
+//! - [`event`] - Server event bus
+//! - [`plugin`] - Plugin API and native plugin loading
+//! - [`script`] - Optional Rhai scripts run from the `scripts/` directory
+//! - [`admin`] - Optional HTTP admin API
+//! - [`telemetry`] - Optional export of tracing spans to OpenTelemetry
+//! - [`testing`] - Headless protocol client for integration tests
 //!
 //! # Example
 //!
@@ -34,13 +43,23 @@
 
 #![deny(clippy::too_many_lines, missing_docs, clippy::panic)]
 
+pub mod admin;
+pub mod clock;
+pub mod command;
 pub mod config;
 pub mod error;
+pub mod event;
 pub mod favicon;
 pub mod game;
+pub mod lang;
 pub mod logger;
 pub mod network;
+pub mod plugin;
 pub mod protocol;
+#[cfg(feature = "scripting")]
+pub mod script;
 pub mod server;
+pub mod telemetry;
+pub mod testing;
 
 pub use error::{Result, ServerError};
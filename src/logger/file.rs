@@ -0,0 +1,217 @@
+//! Log file output
+//!
+//! Like vanilla, the console log is also written to `logs/latest.log`,
+//! without color codes. When the date changes, when the file grows past its
+//! maximum size and when the server starts, the current file is compressed
+//! to `logs/<yyyy-MM-dd>-<n>.log.gz` and a new one is started.
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use time::Date;
+
+/// Name of the file currently written to
+pub const LATEST_LOG: &str = "latest.log";
+
+/// Log file settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFileConfig {
+    /// Whether the log is written to a file
+    pub enabled: bool,
+    /// Directory log files are written to
+    pub directory: PathBuf,
+    /// Bytes the file may grow to before it is rotated (zero rotates daily
+    /// only)
+    pub max_size: u64,
+    /// Compressed logs kept (zero keeps all)
+    pub keep: usize,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            directory: PathBuf::from("logs"),
+            max_size: 10 * 1024 * 1024,
+            keep: 0,
+        }
+    }
+}
+
+/// Date of a moment in the local time zone, or in UTC if it is unknown
+fn local_date(moment: time::OffsetDateTime) -> Date {
+    moment
+        .to_offset(time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC))
+        .date()
+}
+
+/// Today's date in the local time zone
+fn today() -> Date {
+    local_date(time::OffsetDateTime::now_utc())
+}
+
+/// `latest.log` and the date it was started
+#[derive(Debug)]
+pub struct LogFile {
+    /// Settings
+    config: LogFileConfig,
+    /// File being written
+    file: File,
+    /// Bytes in the file
+    size: u64,
+    /// Date the file was started
+    date: Date,
+}
+
+impl LogFile {
+    /// Start a new `latest.log`, compressing the one left by the previous
+    /// run
+    pub fn open(config: LogFileConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.directory)?;
+        let latest = config.directory.join(LATEST_LOG);
+        if let Ok(metadata) = fs::metadata(&latest) {
+            let date = metadata
+                .modified()
+                .map_or_else(|_| today(), |modified| local_date(modified.into()));
+            archive(&config, date)?;
+        }
+        Ok(Self {
+            file: File::create(&latest)?,
+            size: 0,
+            date: today(),
+            config,
+        })
+    }
+
+    /// Append a line, rotating the file first if needed
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.write_line_on(today(), line)
+    }
+
+    /// Append a line written on a given date
+    fn write_line_on(&mut self, date: Date, line: &str) -> io::Result<()> {
+        let length = line.len() as u64 + 1;
+        let too_large =
+            self.config.max_size > 0 && self.size > 0 && self.size + length > self.config.max_size;
+        if date != self.date || too_large {
+            self.rotate(date)?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += length;
+        Ok(())
+    }
+
+    /// Compress the current file and start a new one
+    fn rotate(&mut self, date: Date) -> io::Result<()> {
+        self.file.flush()?;
+        archive(&self.config, self.date)?;
+        self.file = File::create(self.config.directory.join(LATEST_LOG))?;
+        self.size = 0;
+        self.date = date;
+        Ok(())
+    }
+}
+
+/// Date as it appears in compressed log names
+fn date_name(date: Date) -> String {
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day()
+    )
+}
+
+/// Compressed logs in a directory as `(date, n, path)`, oldest first
+fn archives(directory: &Path) -> io::Result<Vec<(String, u32, PathBuf)>> {
+    let mut archives: Vec<_> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let stem = name.strip_suffix(".log.gz")?;
+            let (date, n) = stem.rsplit_once('-')?;
+            Some((date.to_string(), n.parse().ok()?, entry.path()))
+        })
+        .collect();
+    archives.sort();
+    Ok(archives)
+}
+
+/// Compress `latest.log` to the next free name for a date and remove the
+/// oldest compressed logs beyond the number kept
+fn archive(config: &LogFileConfig, date: Date) -> io::Result<()> {
+    let latest = config.directory.join(LATEST_LOG);
+    let date = date_name(date);
+    let mut archives = archives(&config.directory)?;
+    let n = archives
+        .iter()
+        .filter(|(archived, _, _)| *archived == date)
+        .map(|(_, n, _)| n + 1)
+        .max()
+        .unwrap_or(1);
+    let path = config.directory.join(format!("{}-{}.log.gz", date, n));
+
+    let mut encoder = GzEncoder::new(File::create(&path)?, Compression::default());
+    io::copy(&mut File::open(&latest)?, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(&latest)?;
+
+    if config.keep > 0 {
+        archives.push((date, n, path));
+        archives.sort();
+        let excess = archives.len().saturating_sub(config.keep);
+        for (_, _, path) in archives.drain(..excess) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_rotation() {
+        let directory =
+            std::env::temp_dir().join(format!("obsidium-logs-{}", uuid::Uuid::new_v4()));
+        let config = LogFileConfig {
+            enabled: true,
+            directory: directory.clone(),
+            max_size: 16,
+            keep: 2,
+        };
+        let mut log = LogFile::open(config.clone()).unwrap();
+        let date = log.date;
+        log.write_line_on(date, "first line").unwrap();
+        log.write_line_on(date, "second line").unwrap();
+        let tomorrow = date.next_day().unwrap();
+        log.write_line_on(tomorrow, "third").unwrap();
+        drop(log);
+
+        let names: Vec<_> = archives(&directory)
+            .unwrap()
+            .into_iter()
+            .map(|(date, n, _)| format!("{}-{}", date, n))
+            .collect();
+        let day = date_name(date);
+        assert_eq!(names, [format!("{}-1", day), format!("{}-2", day)]);
+
+        let mut text = String::new();
+        GzDecoder::new(File::open(directory.join(format!("{}-1.log.gz", day))).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "first line\n");
+
+        // A restart archives the last file and drops the oldest archive
+        LogFile::open(config).unwrap();
+        assert_eq!(archives(&directory).unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(directory.join(LATEST_LOG)).unwrap(), "");
+
+        let _ = fs::remove_dir_all(directory);
+    }
+}
@@ -0,0 +1,323 @@
+//! Custom logging implementation for Obsidium
+//!
+//! This module provides a beautifully formatted logger with colored output,
+//! custom time formatting, and structured logging capabilities.
+
+pub mod file;
+
+use crate::telemetry::{self, DEFAULT_TRACE_FILTER};
+use file::{LogFile, LogFileConfig};
+use std::fmt;
+use std::io::{self, Write as _};
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::broadcast;
+use tracing_subscriber::Layer as _;
+use tracing_subscriber::fmt::FmtContext;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::FormatTime;
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt as _;
+
+/// Capacity of the log line channel
+const LOG_CHANNEL_CAPACITY: usize = 1024;
+
+/// Log lines copied to subscribers such as the admin API
+static LOG_LINES: OnceLock<broadcast::Sender<String>> = OnceLock::new();
+
+/// Lines kept until the log file is opened or disabled
+const MAX_PENDING_LINES: usize = 1024;
+
+/// Where log lines are written besides the console
+#[derive(Debug)]
+enum FileOutput {
+    /// Not configured yet; lines are kept until it is
+    Pending(Vec<String>),
+    /// Written to a log file
+    Open(LogFile),
+    /// Not written to a file
+    Disabled,
+}
+
+/// Log file output, configured once the server settings are loaded
+static FILE_OUTPUT: Mutex<FileOutput> = Mutex::new(FileOutput::Pending(Vec::new()));
+
+/// Start writing the log to `latest.log`, including the lines logged so
+/// far, or stop keeping them if file output is disabled
+pub fn enable_file_output(config: &LogFileConfig) -> io::Result<()> {
+    let mut output = FILE_OUTPUT.lock().unwrap_or_else(|e| e.into_inner());
+    let pending = match std::mem::replace(&mut *output, FileOutput::Disabled) {
+        FileOutput::Pending(lines) => lines,
+        FileOutput::Open(_) | FileOutput::Disabled => Vec::new(),
+    };
+    if !config.enabled {
+        return Ok(());
+    }
+    let mut file = LogFile::open(config.clone())?;
+    for line in &pending {
+        file.write_line(line)?;
+    }
+    *output = FileOutput::Open(file);
+    Ok(())
+}
+
+/// Write a line to the log file, or keep it until the file is opened
+fn write_to_file(line: &str) {
+    let mut output = FILE_OUTPUT.lock().unwrap_or_else(|e| e.into_inner());
+    match &mut *output {
+        FileOutput::Pending(lines) if lines.len() < MAX_PENDING_LINES => {
+            lines.push(line.to_string());
+        }
+        FileOutput::Open(file) => {
+            if let Err(e) = file.write_line(line) {
+                // Logging the failure would come back here
+                let _ = writeln!(io::stderr(), "Failed to write the log file: {}", e);
+                *output = FileOutput::Disabled;
+            }
+        }
+        FileOutput::Pending(_) | FileOutput::Disabled => {}
+    }
+}
+
+/// Subscribe to log lines as they are written, without color codes
+pub fn subscribe() -> broadcast::Receiver<String> {
+    log_lines().subscribe()
+}
+
+fn log_lines() -> &'static broadcast::Sender<String> {
+    LOG_LINES.get_or_init(|| broadcast::channel(LOG_CHANNEL_CAPACITY).0)
+}
+
+/// Remove ANSI escape sequences from a string
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip up to and including the final byte of the sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Writes one formatted event to stdout and the log file and copies it to
+/// subscribers
+#[derive(Default)]
+struct TeeWriter {
+    buffer: Vec<u8>,
+}
+
+impl io::Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for TeeWriter {
+    fn drop(&mut self) {
+        let _ = io::stdout().write_all(&self.buffer);
+
+        let line = strip_ansi(&String::from_utf8_lossy(&self.buffer));
+        let line = line.trim_end();
+        write_to_file(line);
+        let sender = log_lines();
+        if sender.receiver_count() > 0 {
+            let _ = sender.send(line.to_string());
+        }
+    }
+}
+
+/// Creates a [`TeeWriter`] for every event
+struct TeeMakeWriter;
+
+impl<'a> MakeWriter<'a> for TeeMakeWriter {
+    type Writer = TeeWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        TeeWriter::default()
+    }
+}
+
+/// ANSI color codes for terminal output
+mod colors {
+    pub const RESET: &str = "\x1b[0m";
+    pub const GRAY: &str = "\x1b[90m";
+    pub const RED: &str = "\x1b[31m";
+    pub const YELLOW: &str = "\x1b[33m";
+    pub const GREEN: &str = "\x1b[32m";
+    pub const BLUE: &str = "\x1b[34m";
+    pub const MAGENTA: &str = "\x1b[35m";
+}
+
+/// Formats the current time as HH:MM:SS.mmm
+fn format_current_time() -> String {
+    // Try to get local time, fall back to UTC if local time is not available
+    let now = time::OffsetDateTime::now_utc()
+        .to_offset(time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC));
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        now.hour(),
+        now.minute(),
+        now.second(),
+        now.millisecond()
+    )
+}
+
+/// Custom time formatter for tracing subscriber
+struct CustomTimeFormat;
+
+impl FormatTime for CustomTimeFormat {
+    fn format_time(&self, w: &mut tracing_subscriber::fmt::format::Writer<'_>) -> fmt::Result {
+        write!(w, "{}", format_current_time())
+    }
+}
+
+/// Custom event formatter that provides colored, structured log output with timestamps
+struct CustomFormatWithTime;
+
+/// Custom event formatter that provides colored, structured log output without timestamps
+struct CustomFormat;
+
+/// Returns the appropriate color and formatted level string for a log level
+fn format_level(level: &tracing::Level) -> String {
+    match *level {
+        tracing::Level::ERROR => format!("{}[ERROR]{}", colors::RED, colors::RESET),
+        tracing::Level::WARN => format!("{}[WARN]{}", colors::YELLOW, colors::RESET),
+        tracing::Level::INFO => format!("{}[INFO]{}", colors::GREEN, colors::RESET),
+        tracing::Level::DEBUG => format!("{}[DEBUG]{}", colors::BLUE, colors::RESET),
+        tracing::Level::TRACE => format!("{}[TRACE]{}", colors::MAGENTA, colors::RESET),
+    }
+}
+
+impl<S, N> tracing_subscriber::fmt::FormatEvent<S, N> for CustomFormatWithTime
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> tracing_subscriber::fmt::FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> fmt::Result {
+        let timestamp = format_current_time();
+
+        // Write gray timestamp
+        write!(writer, "{}{timestamp}{} ", colors::GRAY, colors::RESET)?;
+
+        // Write colored log level with consistent spacing
+        let level_formatted = format_level(event.metadata().level());
+        write!(writer, "{level_formatted} ")?;
+
+        // Write the actual log message and fields
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+impl<S, N> tracing_subscriber::fmt::FormatEvent<S, N> for CustomFormat
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> tracing_subscriber::fmt::FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> fmt::Result {
+        // Write colored log level
+        let level_formatted = format_level(event.metadata().level());
+        write!(writer, "{level_formatted} ")?;
+
+        // Write the actual log message and fields
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+/// Initialize the logging system with custom formatting
+///
+/// This function sets up a beautiful, colored logger with optional time formatting.
+/// It respects the `RUST_LOG` environment variable for filtering, falling back to "info" level.
+/// It also respects the `RUST_LOG_TIME` environment variable to enable timestamps.
+/// Set `RUST_LOG_TIME=1` or `RUST_LOG_TIME=true` to enable timestamps in logs.
+///
+/// Spans are also exported to an OpenTelemetry collector when
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` is set; see [`telemetry`](crate::telemetry).
+///
+/// Lines are kept in memory until [`enable_file_output`] opens the log file
+/// or turns file output off, once the server settings are known.
+///
+/// # Examples
+///
+/// ```bash
+/// # Default format without timestamps
+/// RUST_LOG=debug ./Obsidium
+///
+/// # Enable timestamps
+/// RUST_LOG=debug RUST_LOG_TIME=1 ./Obsidium
+///
+/// # Export spans to a local collector
+/// OTEL_EXPORTER_OTLP_ENDPOINT=http://localhost:4318 ./Obsidium
+/// ```
+pub fn init() {
+    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+
+    // Check if timestamps should be enabled via environment variable
+    let enable_time = std::env::var("RUST_LOG_TIME")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_timer(CustomTimeFormat)
+        .with_target(false)
+        .with_thread_ids(false)
+        .with_thread_names(false)
+        .with_file(false)
+        .with_line_number(false)
+        .with_level(true)
+        .with_ansi(true)
+        .with_writer(TeeMakeWriter)
+        .fmt_fields(tracing_subscriber::fmt::format::DefaultFields::new());
+    let fmt_layer = if enable_time {
+        fmt_layer.event_format(CustomFormatWithTime).boxed()
+    } else {
+        fmt_layer.event_format(CustomFormat).boxed()
+    };
+
+    let (otlp_layer, otlp_error) = match telemetry::layer_from_env() {
+        Ok(layer) => (layer, None),
+        Err(e) => (None, Some(e)),
+    };
+    let otlp_layer = otlp_layer.map(|layer| {
+        let filter = std::env::var("OBSIDIUM_TRACE_FILTER")
+            .ok()
+            .and_then(|filter| tracing_subscriber::EnvFilter::try_new(filter).ok())
+            .unwrap_or_else(|| tracing_subscriber::EnvFilter::new(DEFAULT_TRACE_FILTER));
+        layer.with_filter(filter)
+    });
+
+    tracing_subscriber::registry()
+        .with(fmt_layer.with_filter(env_filter))
+        .with(otlp_layer)
+        .init();
+    if let Some(e) = otlp_error {
+        tracing::warn!("Failed to start the trace exporter: {}", e);
+    }
+}
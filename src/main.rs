@@ -4,60 +4,123 @@
 
 #![deny(clippy::too_many_lines, missing_docs, clippy::panic)]
 
+use clap::Parser;
 use obsidium::Result;
+use obsidium::config::CliArgs;
 use obsidium::config::ServerConfig;
 use obsidium::error::ServerError;
 use obsidium::logger;
+use obsidium::network::dump::PacketDump;
 use obsidium::server::MinecraftServer;
+use obsidium::testing::Replayer;
+use std::net::Ipv4Addr;
+use std::path::Path;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Prints usage or the version and exits for --help, --version and bad arguments
+    let args = CliArgs::parse();
+
     // Initialize logger
     logger::init();
 
-    // Try to load configuration from server.properties file
-    let config = match ServerConfig::from_properties_file("server.properties") {
+    // Layer obsidium.toml over server.properties, and the command line over both
+    let config = load_config(&args.properties_path())
+        .and_then(|config| config.with_overrides_file(args.overrides_path()))
+        .map(|config| config.with_cli_args(args.clone()))
+        .and_then(|config| config.validate().map(|()| config));
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("{}", e);
+            return Err(e);
+        }
+    };
+
+    if let Err(e) = logger::enable_file_output(&config.log_file) {
+        tracing::warn!("Failed to open the log file: {}", e);
+    }
+
+    if args.init_settings {
+        tracing::info!("Initialized configuration, exiting");
+        return Ok(());
+    }
+    if let Some(ref dump) = args.replay {
+        return replay(dump, &config).await;
+    }
+
+    // Create and run server
+    let server = MinecraftServer::new(config).await?.with_exit_on_crash(true);
+    server.run().await?;
+
+    Ok(())
+}
+
+/// Load server.properties, creating it with defaults if it does not exist
+fn load_config(path: &Path) -> Result<ServerConfig> {
+    match ServerConfig::from_properties_file(path) {
         Ok(config) => {
-            tracing::info!("Loaded configuration from server.properties");
-            config
+            tracing::info!("Loaded configuration from {}", path.display());
+            Ok(config)
         }
         Err(ServerError::Io(ref e)) if e.kind() == std::io::ErrorKind::NotFound => {
-            tracing::info!("server.properties not found, creating default configuration");
-            let config = ServerConfig::new()
-                .with_motd(
-                    "Welcome to Obsidium - an experimental Minecraft server written in Rust!"
-                        .to_string(),
-                )
-                .with_max_players(999_999_999)
-                .with_compression_threshold(Some(256))
-                .with_favicon(Some("server-icon.png".to_string()));
+            tracing::info!(
+                "{} not found, creating default configuration",
+                path.display()
+            );
+            let config = default_config().with_properties_path(Some(path.to_path_buf()));
 
             // Save the default configuration to server.properties
-            if let Err(e) = config.save_properties_file("server.properties") {
-                tracing::warn!("Failed to save server.properties: {}", e);
+            if let Err(e) = config.save_properties_file(path) {
+                tracing::warn!("Failed to save {}: {}", path.display(), e);
             } else {
-                tracing::info!("Created default server.properties file");
+                tracing::info!("Created default {} file", path.display());
             }
 
-            config
+            Ok(config)
         }
+        Err(e @ ServerError::Configuration(_)) => Err(e),
         Err(e) => {
-            tracing::error!("Failed to load server.properties: {}", e);
+            tracing::error!("Failed to load {}: {}", path.display(), e);
             tracing::info!("Using default configuration");
-            ServerConfig::new()
-                .with_motd(
-                    "Welcome to Obsidium - an experimental Minecraft server written in Rust!"
-                        .to_string(),
-                )
-                .with_max_players(999_999_999)
-                .with_compression_threshold(Some(256))
-                .with_favicon(Some("server-icon.png".to_string()))
+            Ok(default_config())
         }
-    };
+    }
+}
 
-    // Create and run server
-    let server = MinecraftServer::new(config).await?;
-    server.run().await?;
+/// Replay a packet dump against the server the configuration describes
+async fn replay(path: &Path, config: &ServerConfig) -> Result<()> {
+    let mut addr = config.bind_address;
+    if addr.ip().is_unspecified() {
+        addr.set_ip(Ipv4Addr::LOCALHOST.into());
+    }
 
-    Ok(())
+    let dump = PacketDump::load(path)?;
+    tracing::info!(
+        "Replaying {} packet(s) from {} against {}",
+        dump.serverbound().count(),
+        path.display(),
+        addr
+    );
+    match Replayer::new(dump).run(addr).await {
+        Ok(report) => {
+            tracing::info!("Replay finished: {}", report);
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!("Replay failed: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Configuration used when server.properties cannot be read
+fn default_config() -> ServerConfig {
+    ServerConfig::new()
+        .with_motd(
+            "Welcome to Obsidium - an experimental Minecraft server written in Rust!".to_string(),
+        )
+        .with_max_players(999_999_999)
+        .with_compression_threshold(Some(256))
+        .with_favicon(Some("server-icon.png".to_string()))
 }
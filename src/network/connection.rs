@@ -2,42 +2,128 @@
 //!
 //! This module handles individual client connections and their lifecycle.
 
+use crate::clock::{self, SharedClock};
 use crate::error::{Result, ServerError};
+use crate::network::dump::{Direction, PacketRecorder};
+use crate::network::proxy::ProxyHeader;
+use crate::network::stats::PacketCounters;
+use crate::protocol::encryption::{Encryption, SECRET_LENGTH};
+use crate::protocol::frame::{
+    EncodedPacket, OFFLOAD_THRESHOLD, encode_offloaded, write_frame_header,
+};
+use crate::protocol::packets::{DynPacket, RawPacket};
 use crate::protocol::types::VarInt;
 use crate::protocol::{Compression, ConnectionState, ProtocolState};
-use std::net::SocketAddr;
+use std::io::IoSlice;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 
+/// Largest buffer kept between packets; bigger ones are freed after use
+const MAX_POOLED_BUFFER: usize = 64 * 1024;
+
+/// What logs show instead of a client's address when log-ips is off, as in
+/// vanilla
+pub const WITHHELD_ADDR: &str = "<ip address withheld>";
+
 /// Represents a single client connection
 pub struct Connection {
     /// TCP stream
     stream: TcpStream,
     /// Client address
     peer_addr: SocketAddr,
+    /// Address of the client a proxy connects for, if forwarded
+    forwarded_addr: Option<SocketAddr>,
+    /// Whether logs may show the client's address
+    log_ips: bool,
     /// Protocol state
     protocol_state: ProtocolState,
     /// Compression handler
     compression: Option<Compression>,
+    /// Stream cipher, once the login has exchanged a shared secret
+    encryption: Option<Encryption>,
+    /// Bytes received but not yet consumed as a packet
+    read_buffer: Vec<u8>,
     /// Connection start time
     connected_at: Instant,
     /// Last activity time
     last_activity: Instant,
+    /// Dump file every packet is written to, if recording
+    recorder: Option<PacketRecorder>,
+    /// Source of the current time
+    clock: SharedClock,
+    /// Reused buffer packets are encoded into
+    encode_buffer: Vec<u8>,
+    /// Reused buffer packets are compressed into
+    compress_buffer: Vec<u8>,
+    /// Reused buffer bytes are encrypted in before sending
+    encrypt_buffer: Vec<u8>,
+    /// Counters every packet is added to, if counting
+    counters: Option<Arc<PacketCounters>>,
 }
 
 impl Connection {
     /// Create a new connection from a TCP stream
     pub fn new(stream: TcpStream, peer_addr: SocketAddr) -> Self {
-        let now = Instant::now();
+        let clock = clock::system();
+        let now = clock.now();
         Self {
             stream,
             peer_addr,
+            forwarded_addr: None,
+            log_ips: true,
             protocol_state: ProtocolState::new(),
             compression: None,
+            encryption: None,
+            read_buffer: Vec::new(),
             connected_at: now,
             last_activity: now,
+            recorder: None,
+            clock,
+            encode_buffer: Vec::new(),
+            compress_buffer: Vec::new(),
+            encrypt_buffer: Vec::new(),
+            counters: None,
+        }
+    }
+
+    /// Measure uptime and idle time with the given clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        let now = clock.now();
+        self.connected_at = now;
+        self.last_activity = now;
+        self.clock = clock;
+        self
+    }
+
+    /// Show or withhold the client's address in logs, as set by log-ips
+    pub fn with_log_ips(mut self, enabled: bool) -> Self {
+        self.log_ips = enabled;
+        self
+    }
+
+    /// The client's address as logs show it
+    ///
+    /// This is the address forwarded by a proxy if there is one, and the
+    /// peer's address otherwise, withheld unless log-ips is on. Every log
+    /// naming a connection goes through here.
+    pub fn logged_addr(&self) -> String {
+        if !self.log_ips {
+            return WITHHELD_ADDR.to_string();
         }
+        self.forwarded_addr.unwrap_or(self.peer_addr).to_string()
+    }
+
+    /// Record every packet sent and received from now on
+    pub fn record_to(&mut self, recorder: PacketRecorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Add every packet sent and received from now on to `counters`
+    pub fn count_to(&mut self, counters: Arc<PacketCounters>) {
+        self.counters = Some(counters);
     }
 
     /// Get the peer address
@@ -45,47 +131,113 @@ impl Connection {
         self.peer_addr
     }
 
+    /// IP address of the player, used by IP-based features such as the
+    /// login throttle and the audit log
+    ///
+    /// This is the address forwarded by a proxy if there is one, and the
+    /// peer's address otherwise.
+    pub fn client_ip(&self) -> IpAddr {
+        self.forwarded_addr.unwrap_or(self.peer_addr).ip()
+    }
+
+    /// Address forwarded by a proxy, if any
+    pub fn forwarded_addr(&self) -> Option<SocketAddr> {
+        self.forwarded_addr
+    }
+
+    /// Take the client to be at `ip`, as a Velocity proxy forwarded it
+    ///
+    /// Velocity forwards no port, so the proxy's is kept.
+    pub fn set_forwarded_ip(&mut self, ip: IpAddr) {
+        self.forwarded_addr = Some(SocketAddr::new(ip, self.peer_addr.port()));
+    }
+
+    /// Read the PROXY protocol header a proxy starts the connection with
+    ///
+    /// A forwarded address is used by [`client_ip`](Self::client_ip) from
+    /// now on. Bytes after the header stay buffered for the first packet.
+    pub async fn read_proxy_header(&mut self) -> Result<ProxyHeader> {
+        loop {
+            if let Some((header, length)) = ProxyHeader::parse(&self.read_buffer)? {
+                self.read_buffer.drain(..length);
+                if let ProxyHeader::Forwarded(addr) = header {
+                    self.forwarded_addr = Some(addr);
+                }
+                return Ok(header);
+            }
+            if self.stream.read_buf(&mut self.read_buffer).await? == 0 {
+                return Err(ServerError::Io(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "connection closed before the PROXY header",
+                )));
+            }
+        }
+    }
+
     /// Get the current connection state
     pub fn state(&self) -> ConnectionState {
         self.protocol_state.state
     }
 
     /// Transition to a new connection state
-    pub fn set_state(&mut self, new_state: ConnectionState) {
-        self.protocol_state.transition_to(new_state);
+    pub fn set_state(&mut self, new_state: ConnectionState) -> Result<()> {
+        self.protocol_state.transition_to(new_state)
     }
 
     /// Enable compression with the given threshold
     pub fn enable_compression(&mut self, threshold: u32) -> Result<()> {
         self.protocol_state.enable_compression(threshold);
         self.compression = Some(Compression::new(threshold));
-        tracing::debug!("Compression enabled for connection {}", self.peer_addr);
+        tracing::debug!("Compression enabled for connection {}", self.logged_addr());
         Ok(())
     }
 
-    /// Read a packet from the connection
-    pub async fn read_packet(&mut self) -> Result<(VarInt, Vec<u8>)> {
-        self.last_activity = Instant::now();
+    /// Encrypt everything sent and received from now on with a shared
+    /// secret
+    ///
+    /// Bytes already received but not yet read as packets were sent after
+    /// the client switched on encryption, so they are decrypted too.
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; SECRET_LENGTH]) {
+        let mut encryption = Encryption::new(shared_secret);
+        encryption.decrypt(&mut self.read_buffer);
+        self.encryption = Some(encryption);
+        tracing::debug!("Encryption enabled for connection {}", self.logged_addr());
+    }
 
-        // Read packet length
-        let packet_length = self.read_varint().await?;
+    /// Whether the connection is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
 
-        if packet_length.0 < 0 {
-            return Err(ServerError::Protocol("Negative packet length".to_string()));
-        }
+    /// Compression threshold in effect, if compression is enabled
+    pub fn compression_threshold(&self) -> Option<u32> {
+        self.compression.as_ref().map(Compression::threshold)
+    }
 
-        let length = packet_length.0 as usize;
-        if length == 0 {
-            return Err(ServerError::Protocol("Zero packet length".to_string()));
-        }
+    /// Read a packet from the connection
+    ///
+    /// This is cancel safe: partially received packets stay buffered, so it
+    /// can be used as a branch of `tokio::select!`.
+    pub async fn read_packet(&mut self) -> Result<(VarInt, Vec<u8>)> {
+        let data = loop {
+            if let Some(frame) = self.take_frame()? {
+                break frame;
+            }
 
-        if length > crate::protocol::MAX_PACKET_SIZE {
-            return Err(ServerError::Protocol("Packet too large".to_string()));
-        }
+            let received = self.read_buffer.len();
+            if self.stream.read_buf(&mut self.read_buffer).await? == 0 {
+                return Err(ServerError::Io(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "connection closed by peer",
+                )));
+            }
+            if let Some(ref mut encryption) = self.encryption {
+                encryption.decrypt(&mut self.read_buffer[received..]);
+            }
+        };
 
-        // Read packet data
-        let mut data = vec![0u8; length];
-        self.stream.read_exact(&mut data).await?;
+        self.last_activity = self.clock.now();
+        let frame_length = data.len();
 
         // Debug: log the raw packet data
         if data.len() <= 32 {
@@ -95,96 +247,202 @@ impl Connection {
         }
 
         // Handle compression if enabled
-        if let Some(ref mut compression) = self.compression {
-            compression.decompress_packet(&data)
+        let (packet_id, packet_data) = if let Some(ref mut compression) = self.compression {
+            compression.decompress_packet(&data)?
         } else {
             // Uncompressed packet - first VarInt is packet ID
+            let mut data = data;
             let mut cursor = std::io::Cursor::new(&data);
             let packet_id = VarInt::read(&mut cursor)?;
-            let remaining_data = data[cursor.position() as usize..].to_vec();
-            Ok((packet_id, remaining_data))
+            let id_length = cursor.position() as usize;
+            data.drain(..id_length);
+            (packet_id, data)
+        };
+
+        if let Some(ref counters) = self.counters {
+            counters.record_received(self.protocol_state.state, packet_id.0, frame_length);
         }
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.record(
+                Direction::Serverbound,
+                self.protocol_state.state,
+                packet_id.0,
+                &packet_data,
+            );
+        }
+        Ok((packet_id, packet_data))
     }
+
     /// Write a packet to the connection
+    ///
+    /// The packet is encoded into a buffer kept by the connection, so no
+    /// allocation is needed once the buffer has grown to fit.
     pub async fn write_packet<P>(&mut self, packet: &P) -> Result<()>
     where
         P: crate::protocol::packets::Packet,
     {
-        self.last_activity = Instant::now();
+        let mut data = std::mem::take(&mut self.encode_buffer);
+        data.clear();
+        let result = match packet.write(&mut data) {
+            Ok(()) => self.write_frame(P::ID, &data).await,
+            Err(e) => Err(e),
+        };
+        self.encode_buffer = recycle(data);
+        result
+    }
+
+    /// Write a packet whose type has been erased to the connection
+    pub async fn write_dyn_packet(&mut self, packet: &dyn DynPacket) -> Result<()> {
+        self.write_raw_packet(&packet.encode()?).await
+    }
 
-        let mut packet_data = Vec::new();
-        packet.write(&mut packet_data)?;
+    /// Write an already serialized packet to the connection
+    pub async fn write_raw_packet(&mut self, packet: &RawPacket) -> Result<()> {
+        self.write_frame(packet.id, &packet.data).await
+    }
+
+    /// Frame a packet body and write it to the stream
+    ///
+    /// Only the length prefixes and packet ID are assembled here; the body,
+    /// or its compressed form, is written straight from where it lies with
+    /// a vectored write. Packets large enough to stall the runtime while
+    /// being compressed are compressed on the blocking thread pool instead.
+    async fn write_frame(&mut self, id: i32, data: &[u8]) -> Result<()> {
+        self.last_activity = self.clock.now();
+
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.record(Direction::Clientbound, self.protocol_state.state, id, data);
+        }
 
         tracing::debug!(
             "Writing packet ID: 0x{:02X}, data length: {}, compression: {}",
-            P::ID,
-            packet_data.len(),
+            id,
+            data.len(),
             self.compression.is_some()
         );
 
-        let final_packet = if let Some(ref mut compression) = self.compression {
-            // Get the payload (Data Length + Data)
-            let payload = compression.compress_packet(P::id(), &packet_data)?;
+        if self.compression.is_some() && data.len() >= OFFLOAD_THRESHOLD {
+            let packet = RawPacket {
+                id,
+                data: data.to_vec(),
+            };
+            let packet = encode_offloaded(packet, self.compression_threshold()).await?;
+            self.count_sent(id, packet.as_bytes().len());
+            self.send(&[], packet.as_bytes()).await?;
+            return Ok(());
+        }
 
-            // Prepend the Packet Length
-            let mut buffer = Vec::new();
-            VarInt(payload.len() as i32).write(&mut buffer)?;
-            buffer.extend_from_slice(&payload);
-            buffer
+        let mut header = Vec::with_capacity(3 * VarInt::MAX_SIZE);
+        let is_compressed = write_frame_header(
+            self.compression.as_mut(),
+            id,
+            data,
+            &mut header,
+            &mut self.compress_buffer,
+        )?;
+        let body = if is_compressed {
+            self.compress_buffer.as_slice()
         } else {
-            // Prepend the Packet Length to the uncompressed payload (PacketID + Data)
-            let mut uncompressed_payload = Vec::new();
-            P::id().write(&mut uncompressed_payload)?;
-            uncompressed_payload.extend_from_slice(&packet_data);
-
-            let mut buffer = Vec::new();
-            VarInt(uncompressed_payload.len() as i32).write(&mut buffer)?;
-            buffer.extend_from_slice(&uncompressed_payload);
-            buffer
+            data
         };
 
-        tracing::debug!("Final packet size: {} bytes", final_packet.len());
+        tracing::debug!("Final packet size: {} bytes", header.len() + body.len());
+        self.count_sent(id, header.len() + body.len());
 
-        if final_packet.len() <= 32 {
-            tracing::debug!("Packet bytes: {:02X?}", final_packet);
-        } else {
-            tracing::debug!("Packet bytes (first 32): {:02X?}", &final_packet[..32]);
+        send(
+            &mut self.stream,
+            self.encryption.as_mut(),
+            &mut self.encrypt_buffer,
+            &header,
+            body,
+        )
+        .await?;
+
+        if self.compress_buffer.capacity() > MAX_POOLED_BUFFER {
+            self.compress_buffer = Vec::new();
         }
+        Ok(())
+    }
 
-        self.stream.write_all(&final_packet).await?;
-        self.stream.flush().await?;
+    /// Write a packet framed ahead of time, such as a cached chunk
+    ///
+    /// The frame is written as is when it was built for this connection's
+    /// compression threshold, and framed again otherwise.
+    pub async fn write_encoded(&mut self, packet: &EncodedPacket) -> Result<()> {
+        if packet.compression_threshold() != self.compression_threshold() {
+            return self.write_raw_packet(&packet.decode()?).await;
+        }
 
-        Ok(())
+        self.last_activity = self.clock.now();
+        if self.recorder.is_some() {
+            let raw = packet.decode()?;
+            if let Some(ref mut recorder) = self.recorder {
+                let state = self.protocol_state.state;
+                recorder.record(Direction::Clientbound, state, raw.id, &raw.data);
+            }
+        }
+
+        self.count_sent(packet.id(), packet.as_bytes().len());
+        self.send(&[], packet.as_bytes()).await
+    }
+
+    /// Count a sent frame, if counting
+    fn count_sent(&self, id: i32, bytes: usize) {
+        if let Some(ref counters) = self.counters {
+            counters.record_sent(self.protocol_state.state, id, bytes);
+        }
     }
 
     /// Read raw bytes from the connection
     pub async fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize> {
-        self.last_activity = Instant::now();
+        self.last_activity = self.clock.now();
+
+        // Hand out buffered bytes first so nothing is read out of order
+        if !self.read_buffer.is_empty() {
+            let count = buf.len().min(self.read_buffer.len());
+            buf[..count].copy_from_slice(&self.read_buffer[..count]);
+            self.read_buffer.drain(..count);
+            return Ok(count);
+        }
+
         let bytes_read = self.stream.read(buf).await?;
+        if let Some(ref mut encryption) = self.encryption {
+            encryption.decrypt(&mut buf[..bytes_read]);
+        }
         Ok(bytes_read)
     }
 
     /// Write raw bytes to the connection
     pub async fn write_bytes(&mut self, data: &[u8]) -> Result<()> {
-        self.last_activity = Instant::now();
-        self.stream.write_all(data).await?;
-        self.stream.flush().await?;
-        Ok(())
+        self.last_activity = self.clock.now();
+        self.send(&[], data).await
+    }
+
+    /// Write a frame header and body to the stream
+    async fn send(&mut self, header: &[u8], body: &[u8]) -> Result<()> {
+        send(
+            &mut self.stream,
+            self.encryption.as_mut(),
+            &mut self.encrypt_buffer,
+            header,
+            body,
+        )
+        .await
     }
 
     /// Check if the connection has timed out
     pub fn is_timed_out(&self, timeout: Duration) -> bool {
-        self.last_activity.elapsed() > timeout
+        self.clock.since(self.last_activity) > timeout
     }
 
     /// Get connection uptime
     pub fn uptime(&self) -> Duration {
-        self.connected_at.elapsed()
+        self.clock.since(self.connected_at)
     }
 
     /// Get time since last activity
     pub fn idle_time(&self) -> Duration {
-        self.last_activity.elapsed()
+        self.clock.since(self.last_activity)
     }
 
     /// Set protocol version
@@ -200,32 +458,207 @@ impl Connection {
     /// Close the connection
     pub async fn close(&mut self) -> Result<()> {
         self.stream.shutdown().await?;
-        tracing::debug!("Connection {} closed", self.peer_addr);
+        tracing::debug!("Connection {} closed", self.logged_addr());
         Ok(())
     }
 
-    /// Read a VarInt from the connection
-    async fn read_varint(&mut self) -> Result<VarInt> {
-        let mut value = 0i32;
-        let mut position = 0;
+    /// Remove one complete length-prefixed frame from the read buffer
+    ///
+    /// Returns `None` if the buffer does not yet hold a whole frame.
+    fn take_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut length = 0i32;
+        let mut header_size = 0;
 
         loop {
-            let mut byte = [0u8; 1];
-            self.stream.read_exact(&mut byte).await?;
-            let byte = byte[0];
+            let Some(&byte) = self.read_buffer.get(header_size) else {
+                return Ok(None);
+            };
 
-            value |= ((byte & 0x7F) as i32) << position;
+            length |= ((byte & 0x7F) as i32) << (7 * header_size);
+            header_size += 1;
 
             if (byte & 0x80) == 0 {
                 break;
             }
 
-            position += 7;
-            if position >= 32 {
+            if header_size >= crate::protocol::MAX_VARINT_LENGTH_FIELD_SIZE {
                 return Err(ServerError::Protocol("VarInt too long".to_string()));
             }
         }
 
-        Ok(VarInt(value))
+        if length < 0 {
+            return Err(ServerError::Protocol("Negative packet length".to_string()));
+        }
+
+        let length = length as usize;
+        if length == 0 {
+            return Err(ServerError::Protocol("Zero packet length".to_string()));
+        }
+
+        if length > crate::protocol::MAX_PACKET_SIZE {
+            return Err(ServerError::Protocol("Packet too large".to_string()));
+        }
+
+        if self.read_buffer.len() < header_size + length {
+            return Ok(None);
+        }
+
+        let frame = self.read_buffer[header_size..header_size + length].to_vec();
+        self.read_buffer.drain(..header_size + length);
+        Ok(Some(frame))
+    }
+}
+
+/// Keep a buffer for reuse unless a large packet left it oversized
+fn recycle(buffer: Vec<u8>) -> Vec<u8> {
+    if buffer.capacity() > MAX_POOLED_BUFFER {
+        Vec::new()
+    } else {
+        buffer
+    }
+}
+
+/// Write a header and body to the stream and flush it, encrypting them
+/// first if the connection is encrypted
+///
+/// Unencrypted, both are written from where they lie with a vectored
+/// write; encrypted, they are copied into `buffer` to be encrypted.
+async fn send(
+    stream: &mut TcpStream,
+    encryption: Option<&mut Encryption>,
+    buffer: &mut Vec<u8>,
+    header: &[u8],
+    body: &[u8],
+) -> Result<()> {
+    match encryption {
+        Some(encryption) => {
+            buffer.clear();
+            buffer.extend_from_slice(header);
+            buffer.extend_from_slice(body);
+            encryption.encrypt(buffer);
+            stream.write_all(buffer).await?;
+            if buffer.capacity() > MAX_POOLED_BUFFER {
+                *buffer = Vec::new();
+            }
+        }
+        None => {
+            let mut slices = [IoSlice::new(header), IoSlice::new(body)];
+            write_all_vectored(stream, &mut slices).await?;
+        }
+    }
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Write every slice to the stream, retrying partial writes
+async fn write_all_vectored(
+    stream: &mut TcpStream,
+    mut slices: &mut [IoSlice<'_>],
+) -> std::io::Result<()> {
+    IoSlice::advance_slices(&mut slices, 0);
+    while !slices.is_empty() {
+        let written = stream.write_vectored(slices).await?;
+        if written == 0 {
+            return Err(std::io::ErrorKind::WriteZero.into());
+        }
+        IoSlice::advance_slices(&mut slices, written);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_idle_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, addr) = listener.accept().await.unwrap();
+
+        let clock = ManualClock::new();
+        let mut connection = Connection::new(stream, addr).with_clock(clock.shared());
+        let timeout = Duration::from_secs(30);
+        assert!(!connection.is_timed_out(timeout));
+
+        clock.advance(Duration::from_secs(31));
+        assert!(connection.is_timed_out(timeout));
+        assert_eq!(connection.idle_time(), Duration::from_secs(31));
+
+        connection.write_bytes(&[0]).await.unwrap();
+        assert!(!connection.is_timed_out(timeout));
+        assert_eq!(connection.uptime(), Duration::from_secs(31));
+        drop(client);
+    }
+
+    #[tokio::test]
+    async fn test_logged_addr() {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, addr) = listener.accept().await.unwrap();
+        let mut connection = Connection::new(stream, addr);
+        assert_eq!(connection.logged_addr(), addr.to_string());
+
+        client
+            .write_all(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 25565\r\n")
+            .await
+            .unwrap();
+        connection.read_proxy_header().await.unwrap();
+        assert_eq!(connection.logged_addr(), "192.0.2.1:56324");
+
+        let connection = connection.with_log_ips(false);
+        assert_eq!(connection.logged_addr(), WITHHELD_ADDR);
+    }
+
+    #[tokio::test]
+    async fn test_packet_framing() {
+        use crate::protocol::packets::Packet;
+        use crate::protocol::packets::play::KeepAlivePacket;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, addr) = listener.accept().await.unwrap();
+        let mut server = Connection::new(stream, addr);
+        let mut client = Connection::new(client, addr);
+
+        let secret = [0x2Au8; SECRET_LENGTH];
+        for (threshold, encrypted) in [(None, false), (Some(256), false), (Some(256), true)] {
+            if let Some(threshold) = threshold {
+                server.enable_compression(threshold).unwrap();
+                client.enable_compression(threshold).unwrap();
+            }
+            if encrypted {
+                server.enable_encryption(&secret);
+                client.enable_encryption(&secret);
+            }
+
+            let large = RawPacket {
+                id: 0x27,
+                data: (0..200_000u32).map(|i| (i % 7) as u8).collect(),
+            };
+            server.write_raw_packet(&large).await.unwrap();
+            server
+                .write_packet(&KeepAlivePacket { keep_alive_id: 7 })
+                .await
+                .unwrap();
+
+            let (id, data) = client.read_packet().await.unwrap();
+            assert_eq!(id.0, large.id);
+            assert_eq!(data, large.data);
+            let (id, data) = client.read_packet().await.unwrap();
+            assert_eq!(id.0, KeepAlivePacket::ID);
+            assert_eq!(data, 7i64.to_be_bytes());
+        }
+        assert!(server.encode_buffer.capacity() <= MAX_POOLED_BUFFER);
     }
 }
@@ -0,0 +1,300 @@
+//! Packet dumps
+//!
+//! A [`PacketRecorder`] attached to a [`Connection`](crate::network::Connection)
+//! writes every packet it sends and receives to a dump file, which
+//! [`PacketDump`] reads back for replaying (see
+//! [`testing::replay`](crate::testing::replay)).
+//!
+//! Dumps are text, one packet per line after a header:
+//!
+//! ```text
+//! # obsidium packet dump, protocol 771
+//! 0 serverbound handshaking 0x00 gwYJbG9jYWxob3N0Y/8C
+//! 2 clientbound login 0x02 AwvdRAKjSF2vQvD/arMbagVBbGljZQA=
+//! ```
+//!
+//! Each line holds the milliseconds since the connection was opened, the
+//! direction, the connection state, the packet ID and the uncompressed body
+//! in base64 (`-` when empty). Dumps include everything the player sent,
+//! chat included, so treat them as private.
+
+use crate::error::{Result, ServerError};
+use crate::protocol::packets::{Packet, RawPacket};
+use crate::protocol::{ConnectionState, PROTOCOL_VERSION};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Start of the first line of every dump
+const HEADER_PREFIX: &str = "# obsidium packet dump, protocol ";
+
+/// Extension of dump files
+pub const DUMP_EXTENSION: &str = "dump";
+
+/// Which way a packet travelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Sent by the client
+    Serverbound,
+    /// Sent by the server
+    Clientbound,
+}
+
+impl Direction {
+    /// Get the string representation of the direction
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Serverbound => "serverbound",
+            Direction::Clientbound => "clientbound",
+        }
+    }
+
+    /// Parse a direction from its string representation
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "serverbound" => Some(Direction::Serverbound),
+            "clientbound" => Some(Direction::Clientbound),
+            _ => None,
+        }
+    }
+}
+
+/// A packet in a dump
+#[derive(Debug, Clone)]
+pub struct DumpEntry {
+    /// Line of the dump the packet was read from
+    pub line: usize,
+    /// Time since the connection was opened
+    pub elapsed: Duration,
+    /// Which way the packet travelled
+    pub direction: Direction,
+    /// Connection state the packet was sent in
+    pub state: ConnectionState,
+    /// The packet itself
+    pub packet: RawPacket,
+}
+
+impl DumpEntry {
+    /// Decode the packet body
+    ///
+    /// Packet IDs are only unique within a state and direction, so check
+    /// those before decoding.
+    pub fn decode<P: Packet>(&self) -> Result<P> {
+        if self.packet.id != P::ID {
+            return Err(ServerError::Protocol(format!(
+                "Packet 0x{:02X} on line {} is not a 0x{:02X} packet",
+                self.packet.id,
+                self.line,
+                P::ID
+            )));
+        }
+        P::decode(self.state, &self.packet.data)
+    }
+
+    /// Parse a dump line
+    fn parse(line: usize, text: &str) -> Result<Self> {
+        let invalid = |what: &str| ServerError::Protocol(format!("Line {}: {}", line, what));
+
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        let [elapsed, direction, state, id, data] = fields[..] else {
+            return Err(invalid("expected 5 fields"));
+        };
+        let elapsed = elapsed
+            .parse()
+            .map(Duration::from_millis)
+            .map_err(|_| invalid("invalid time"))?;
+        let direction =
+            Direction::from_name(direction).ok_or_else(|| invalid("invalid direction"))?;
+        let state = ConnectionState::from_name(state).ok_or_else(|| invalid("invalid state"))?;
+        let id = id
+            .strip_prefix("0x")
+            .and_then(|id| i32::from_str_radix(id, 16).ok())
+            .ok_or_else(|| invalid("invalid packet ID"))?;
+        let data = match data {
+            "-" => Vec::new(),
+            data => STANDARD
+                .decode(data)
+                .map_err(|_| invalid("invalid base64"))?,
+        };
+
+        Ok(Self {
+            line,
+            elapsed,
+            direction,
+            state,
+            packet: RawPacket { id, data },
+        })
+    }
+}
+
+impl fmt::Display for DumpEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let data = if self.packet.data.is_empty() {
+            "-".to_string()
+        } else {
+            STANDARD.encode(&self.packet.data)
+        };
+        write!(
+            f,
+            "{} {} {} 0x{:02X} {}",
+            self.elapsed.as_millis(),
+            self.direction.as_str(),
+            self.state.as_str(),
+            self.packet.id,
+            data
+        )
+    }
+}
+
+/// A recorded connection
+#[derive(Debug, Clone, Default)]
+pub struct PacketDump {
+    /// Protocol version the dump was recorded with
+    pub protocol: Option<i32>,
+    /// Packets in the order they were sent or received
+    pub entries: Vec<DumpEntry>,
+}
+
+impl PacketDump {
+    /// Parse a dump
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut dump = Self::default();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if let Some(protocol) = line.strip_prefix(HEADER_PREFIX) {
+                dump.protocol = protocol.trim().parse().ok();
+            } else if !line.is_empty() && !line.starts_with('#') {
+                dump.entries.push(DumpEntry::parse(index + 1, line)?);
+            }
+        }
+        Ok(dump)
+    }
+
+    /// Load a dump file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Self::parse(&std::fs::read_to_string(path)?).map_err(|e| match e {
+            ServerError::Protocol(message) => {
+                ServerError::Protocol(format!("Invalid {}: {}", path.display(), message))
+            }
+            e => e,
+        })
+    }
+
+    /// Packets sent by the client
+    pub fn serverbound(&self) -> impl Iterator<Item = &DumpEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.direction == Direction::Serverbound)
+    }
+}
+
+/// Writes the packets of one connection to a dump file
+pub struct PacketRecorder {
+    /// Dump file, or `None` once writing has failed
+    writer: Option<BufWriter<File>>,
+    /// Path of the dump file
+    path: PathBuf,
+    /// When the connection was opened
+    started: Instant,
+}
+
+impl PacketRecorder {
+    /// Create a dump file for a connection in `directory`
+    pub fn create<P: AsRef<Path>>(directory: P, peer_addr: SocketAddr) -> Result<Self> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)?;
+
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let name = format!(
+            "{}-{}_{}.{}",
+            millis,
+            peer_addr.ip().to_string().replace(':', "-"),
+            peer_addr.port(),
+            DUMP_EXTENSION
+        );
+        let path = directory.join(name);
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writeln!(writer, "{}{}", HEADER_PREFIX, PROTOCOL_VERSION)?;
+        Ok(Self {
+            writer: Some(writer),
+            path,
+            started: Instant::now(),
+        })
+    }
+
+    /// Path of the dump file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a packet
+    ///
+    /// A failure to write is logged once and stops the recording rather
+    /// than the connection.
+    pub fn record(&mut self, direction: Direction, state: ConnectionState, id: i32, data: &[u8]) {
+        let Some(ref mut writer) = self.writer else {
+            return;
+        };
+        let entry = DumpEntry {
+            line: 0,
+            elapsed: self.started.elapsed(),
+            direction,
+            state,
+            packet: RawPacket {
+                id,
+                data: data.to_vec(),
+            },
+        };
+        if let Err(e) = writeln!(writer, "{}", entry).and_then(|()| writer.flush()) {
+            tracing::warn!(
+                "Stopped recording packets to {}: {}",
+                self.path.display(),
+                e
+            );
+            self.writer = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::packets::play::KeepAlivePacket;
+
+    #[test]
+    fn test_record_and_parse() {
+        let dir = std::env::temp_dir().join(format!("obsidium-dump-{}", uuid::Uuid::new_v4()));
+        let mut recorder =
+            PacketRecorder::create(&dir, "127.0.0.1:50000".parse().unwrap()).unwrap();
+        let keep_alive = RawPacket::encode(&KeepAlivePacket { keep_alive_id: 42 }).unwrap();
+        recorder.record(Direction::Serverbound, ConnectionState::Login, 0x03, &[]);
+        recorder.record(
+            Direction::Clientbound,
+            ConnectionState::Play,
+            keep_alive.id,
+            &keep_alive.data,
+        );
+
+        let dump = PacketDump::load(recorder.path()).unwrap();
+        assert_eq!(dump.protocol, Some(PROTOCOL_VERSION));
+        assert_eq!(dump.entries.len(), 2);
+        assert_eq!(dump.serverbound().count(), 1);
+        assert_eq!(dump.entries[0].state, ConnectionState::Login);
+        assert!(dump.entries[0].packet.data.is_empty());
+        let decoded: KeepAlivePacket = dump.entries[1].decode().unwrap();
+        assert_eq!(decoded.keep_alive_id, 42);
+
+        assert!(PacketDump::parse("0 sideways play 0x00 -").is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
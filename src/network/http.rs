@@ -0,0 +1,266 @@
+//! Minimal HTTP/1.1 support
+//!
+//! Only what the admin API and the resource pack host need is implemented:
+//! one request per connection, `Content-Length` bodies and JSON or file
+//! responses.
+
+use crate::error::{Result, ServerError};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest accepted request line plus headers
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+
+/// Largest accepted request body
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// A parsed HTTP request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    /// Request method, e.g. `GET`
+    pub method: String,
+    /// Decoded path segments, without empty segments
+    pub segments: Vec<String>,
+    /// Decoded query parameters
+    pub query: Vec<(String, String)>,
+    /// Headers with lowercase names
+    pub headers: Vec<(String, String)>,
+    /// Request body
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Read a request from a stream
+    pub async fn read<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Self> {
+        let mut head_size = 0;
+        let request_line = read_line(reader, &mut head_size).await?;
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target), Some(_version)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(bad_request("Malformed request line"));
+        };
+
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(reader, &mut head_size).await?;
+            if line.is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                return Err(bad_request("Malformed header"));
+            };
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let mut request = Self {
+            method: method.to_string(),
+            segments: path
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(percent_decode)
+                .collect(),
+            query: query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    (percent_decode(key), percent_decode(value))
+                })
+                .collect(),
+            headers,
+            body: Vec::new(),
+        };
+
+        let length: usize = match request.header("content-length") {
+            Some(length) => length
+                .parse()
+                .map_err(|_| bad_request("Invalid Content-Length"))?,
+            None => 0,
+        };
+        if length > MAX_BODY_SIZE {
+            return Err(bad_request("Request body too large"));
+        }
+        request.body = vec![0; length];
+        reader.read_exact(&mut request.body).await?;
+        Ok(request)
+    }
+
+    /// Get a header by its (case-insensitive) name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get a query parameter
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parse the body as JSON, treating an empty body as `null`
+    pub fn json(&self) -> Option<serde_json::Value> {
+        if self.body.is_empty() {
+            return Some(serde_json::Value::Null);
+        }
+        serde_json::from_slice(&self.body).ok()
+    }
+}
+
+/// An HTTP response with a JSON body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    /// Status code
+    pub status: u16,
+    /// Response body (empty for 204)
+    pub body: Option<serde_json::Value>,
+}
+
+impl Response {
+    /// Create a response with a JSON body
+    pub fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            body: Some(body),
+        }
+    }
+
+    /// Create an error response with a message
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::json(status, serde_json::json!({ "error": message }))
+    }
+
+    /// Create an empty `204 No Content` response
+    pub fn no_content() -> Self {
+        Self {
+            status: 204,
+            body: None,
+        }
+    }
+
+    /// Write the response and close the exchange
+    pub async fn write<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> Result<()> {
+        let body = self
+            .body
+            .as_ref()
+            .map(|body| body.to_string())
+            .unwrap_or_default();
+        let content_type = self.body.is_some().then_some("application/json");
+        write_response(writer, self.status, content_type, body.as_bytes()).await
+    }
+}
+
+/// Write a response with any body and close the exchange
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    status: u16,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status, reason(status));
+    if let Some(content_type) = content_type {
+        head.push_str(&format!("Content-Type: {}\r\n", content_type));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reason phrase of a status code
+fn reason(status: u16) -> &'static str {
+    match status {
+        101 => "Switching Protocols",
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+/// Read a CRLF-terminated line, enforcing the head size limit
+async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    head_size: &mut usize,
+) -> Result<String> {
+    let mut line = Vec::new();
+    let limit = (MAX_HEAD_SIZE - *head_size) as u64;
+    let read = (&mut *reader)
+        .take(limit)
+        .read_until(b'\n', &mut line)
+        .await?;
+    *head_size += read;
+    if !line.ends_with(b"\n") {
+        return Err(bad_request("Request head too large or incomplete"));
+    }
+
+    let line = String::from_utf8(line).map_err(|_| bad_request("Request head is not UTF-8"))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Decode `%XX` escapes and `+` in a URL component
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(&[high, low]) if bytes[i] == b'%' => hex_value(high).zip(hex_value(low)),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some((high, low)), _) => {
+                out.push(high << 4 | low);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => out.push(b' '),
+            (None, byte) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Value of a hexadecimal digit
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+fn bad_request(message: &str) -> ServerError {
+    ServerError::Protocol(format!("Bad HTTP request: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let raw = b"POST /api/players/Steve%20X/kick?token=a%2Bb HTTP/1.1\r\n\
+            Host: localhost\r\nContent-Length: 17\r\n\r\n{\"reason\":\"spam\"}";
+        let request = Request::read(&mut &raw[..]).await.unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.segments, ["api", "players", "Steve X", "kick"]);
+        assert_eq!(request.query_param("token"), Some("a+b"));
+        assert_eq!(request.header("HOST"), Some("localhost"));
+        assert_eq!(request.json().unwrap()["reason"], "spam");
+
+        let truncated = b"GET / HTTP/1.1\r\nHost: x";
+        assert!(Request::read(&mut &truncated[..]).await.is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Keep-alives and latency
+//!
+//! Every play connection sends a keep-alive once per [`KEEP_ALIVE_INTERVAL`]
+//! and expects the client to echo its ID. The round trip times are smoothed
+//! like vanilla does into the latency shown in the tab list and by `/ping`.
+//! A client that has not answered by the time the next keep-alive is due
+//! is disconnected.
+
+use crate::clock::{self, SharedClock};
+use crate::error::{Result, ServerError};
+use crate::protocol::packets::play::KeepAlivePacket;
+use std::time::{Duration, Instant};
+
+/// Time between keep-alives, and how long a client has to answer one
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Keep-alive state of one connection
+#[derive(Debug)]
+pub struct KeepAlive {
+    /// Source of the current time
+    clock: SharedClock,
+    /// When the last keep-alive was sent, or the tracker created
+    last_sent: Instant,
+    /// ID of the keep-alive awaiting an answer
+    pending: Option<i64>,
+    /// ID of the next keep-alive
+    next_id: i64,
+    /// Smoothed round trip time, once measured
+    latency: Option<Duration>,
+}
+
+impl KeepAlive {
+    /// Create a tracker whose first keep-alive is due after one interval
+    pub fn new() -> Self {
+        let clock = clock::system();
+        Self {
+            last_sent: clock.now(),
+            clock,
+            pending: None,
+            next_id: 0,
+            latency: None,
+        }
+    }
+
+    /// Measure time with the given clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.last_sent = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Time until [`poll`](Self::poll) has something to do
+    pub fn time_left(&self) -> Duration {
+        KEEP_ALIVE_INTERVAL.saturating_sub(self.clock.since(self.last_sent))
+    }
+
+    /// Get the keep-alive to send if one is due
+    ///
+    /// Fails with a kick if the previous keep-alive was never answered.
+    pub fn poll(&mut self) -> Result<Option<KeepAlivePacket>> {
+        if !self.time_left().is_zero() {
+            return Ok(None);
+        }
+        if self.pending.is_some() {
+            return Err(timed_out());
+        }
+
+        let keep_alive_id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.pending = Some(keep_alive_id);
+        self.last_sent = self.clock.now();
+        Ok(Some(KeepAlivePacket { keep_alive_id }))
+    }
+
+    /// Record the client's answer, returning the new smoothed latency
+    ///
+    /// Like vanilla, an answer to a keep-alive that was not sent is fatal.
+    pub fn receive(&mut self, keep_alive_id: i64) -> Result<Duration> {
+        if self.pending != Some(keep_alive_id) {
+            return Err(timed_out());
+        }
+        self.pending = None;
+
+        let round_trip = self.clock.since(self.last_sent);
+        let latency = self
+            .latency
+            .map_or(round_trip, |latency| (latency * 3 + round_trip) / 4);
+        self.latency = Some(latency);
+        Ok(latency)
+    }
+
+    /// Smoothed round trip time, or zero before the first answer
+    pub fn latency(&self) -> Duration {
+        self.latency.unwrap_or_default()
+    }
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The kick for a client that stopped answering
+fn timed_out() -> ServerError {
+    ServerError::Kicked(crate::lang::translate("disconnect.timeout", &[]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn test_keep_alive_latency() {
+        let clock = ManualClock::new();
+        let mut keep_alive = KeepAlive::new().with_clock(clock.shared());
+        assert_eq!(keep_alive.time_left(), KEEP_ALIVE_INTERVAL);
+        assert!(keep_alive.poll().unwrap().is_none());
+
+        clock.advance(KEEP_ALIVE_INTERVAL);
+        let first = keep_alive.poll().unwrap().unwrap();
+        clock.advance(Duration::from_millis(100));
+        assert!(keep_alive.receive(first.keep_alive_id + 1).is_err());
+        let latency = keep_alive.receive(first.keep_alive_id).unwrap();
+        assert_eq!(latency, Duration::from_millis(100));
+
+        // Later round trips only move the latency part of the way
+        clock.advance(KEEP_ALIVE_INTERVAL);
+        let second = keep_alive.poll().unwrap().unwrap();
+        assert_ne!(second.keep_alive_id, first.keep_alive_id);
+        clock.advance(Duration::from_millis(20));
+        keep_alive.receive(second.keep_alive_id).unwrap();
+        assert_eq!(keep_alive.latency(), Duration::from_millis(80));
+    }
+
+    #[test]
+    fn test_keep_alive_timeout() {
+        let clock = ManualClock::new();
+        let mut keep_alive = KeepAlive::new().with_clock(clock.shared());
+        clock.advance(KEEP_ALIVE_INTERVAL);
+        keep_alive.poll().unwrap().unwrap();
+
+        clock.advance(KEEP_ALIVE_INTERVAL - Duration::from_millis(1));
+        assert!(keep_alive.poll().unwrap().is_none());
+        clock.advance(Duration::from_millis(1));
+        assert!(matches!(keep_alive.poll(), Err(ServerError::Kicked(_))));
+    }
+}
@@ -2,10 +2,14 @@
 //!
 //! This module handles accepting new connections and managing the server socket.
 
+use crate::clock::{self, SharedClock};
 use crate::config::ServerConfig;
 use crate::error::{Result, ServerError};
 use crate::network::Connection;
+use crate::network::dump::PacketRecorder;
+use crate::network::stats::PacketCounters;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::sync::mpsc;
 
@@ -17,6 +21,10 @@ pub struct ServerListener {
     config: ServerConfig,
     /// Channel for sending new connections
     connection_sender: mpsc::UnboundedSender<Connection>,
+    /// Clock handed to new connections
+    clock: SharedClock,
+    /// Counters new connections add their packets to, if any
+    counters: Option<Arc<PacketCounters>>,
 }
 
 impl ServerListener {
@@ -31,17 +39,54 @@ impl ServerListener {
             listener,
             config,
             connection_sender,
+            clock: clock::system(),
+            counters: None,
         })
     }
 
+    /// Hand the given clock to new connections
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Count the packets of new connections in `counters`
+    pub fn with_packet_counters(mut self, counters: Arc<PacketCounters>) -> Self {
+        self.counters = Some(counters);
+        self
+    }
+
     /// Start accepting connections
     pub async fn listen(&self) -> Result<()> {
         loop {
             match self.listener.accept().await {
                 Ok((stream, addr)) => {
-                    tracing::debug!("New connection from {}", addr);
+                    let nodelay = stream.set_nodelay(self.config.tcp_nodelay);
+                    let mut connection = Connection::new(stream, addr)
+                        .with_clock(Arc::clone(&self.clock))
+                        .with_log_ips(self.config.log_ips);
+                    tracing::debug!("New connection from {}", connection.logged_addr());
+                    if let Err(e) = nodelay {
+                        tracing::warn!(
+                            "Failed to set TCP_NODELAY for {}: {}",
+                            connection.logged_addr(),
+                            e
+                        );
+                    }
 
-                    let connection = Connection::new(stream, addr);
+                    if let Some(ref counters) = self.counters {
+                        connection.count_to(Arc::clone(counters));
+                    }
+                    if let Some(ref dir) = self.config.packet_dump_dir {
+                        match PacketRecorder::create(dir, addr) {
+                            Ok(recorder) => connection.record_to(recorder),
+                            Err(e) => tracing::warn!(
+                                "Failed to record packets of {}: {}",
+                                connection.logged_addr(),
+                                e
+                            ),
+                        }
+                    }
 
                     if let Err(e) = self.connection_sender.send(connection) {
                         tracing::error!("Failed to send connection to handler: {}", e);
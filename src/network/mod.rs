@@ -3,9 +3,21 @@
 //! This module handles low-level networking including connection management,
 //! packet framing, and the server listener.
 
-pub mod codec;
 pub mod connection;
+pub mod dump;
+pub mod http;
+pub mod keep_alive;
 pub mod listener;
+pub mod proxy;
+pub mod rate_limit;
+pub mod stats;
+pub mod throttle;
+pub mod velocity;
 
 pub use connection::Connection;
+pub use keep_alive::KeepAlive;
 pub use listener::ServerListener;
+pub use proxy::{ForwardingConfig, ProxyHeader};
+pub use rate_limit::{MalformedPackets, PacketLimits, PacketRateLimiter};
+pub use stats::PacketCounters;
+pub use throttle::LoginThrottle;
@@ -0,0 +1,221 @@
+//! PROXY protocol
+//!
+//! Proxies such as HAProxy, Velocity and BungeeCord can send a [PROXY
+//! protocol] header at the start of each connection with the address of
+//! the client they connect for. Connections from addresses listed as
+//! `trusted-proxies` must start with one, version 1 (text) or 2 (binary);
+//! other connections are taken as direct unless forwarding is required.
+//!
+//! [PROXY protocol]: https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt
+
+use crate::error::{Result, ServerError};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Start of a version 1 header
+const V1_PREFIX: &[u8] = b"PROXY ";
+
+/// Longest version 1 header, including the line ending
+const V1_MAX_LENGTH: usize = 107;
+
+/// Start of a version 2 header
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Length of a version 2 header before its addresses
+const V2_HEADER_LENGTH: usize = 16;
+
+/// Which connections must carry a forwarded client address
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardingConfig {
+    /// Addresses of proxies whose connections start with a PROXY header
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Refuse connections that do not come through a trusted proxy
+    pub required: bool,
+}
+
+impl ForwardingConfig {
+    /// Whether connections from `addr` must start with a PROXY header
+    pub fn is_trusted(&self, addr: IpAddr) -> bool {
+        self.trusted_proxies.contains(&addr.to_canonical())
+    }
+}
+
+/// Header at the start of a proxied connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyHeader {
+    /// The proxy connects on behalf of the client at this address
+    Forwarded(SocketAddr),
+    /// The proxy connects on its own behalf, such as for a health check
+    Local,
+}
+
+impl ProxyHeader {
+    /// Parse a header at the start of `buffer`
+    ///
+    /// Returns the header and its length in bytes, or `None` if the buffer
+    /// does not hold a whole header yet.
+    pub fn parse(buffer: &[u8]) -> Result<Option<(Self, usize)>> {
+        if buffer.len() < V1_PREFIX.len().max(V2_SIGNATURE.len()) {
+            let is_prefix = |header: &[u8]| header.starts_with(buffer);
+            if is_prefix(V1_PREFIX) || is_prefix(&V2_SIGNATURE) {
+                return Ok(None);
+            }
+        }
+        if buffer.starts_with(V1_PREFIX) {
+            Self::parse_v1(buffer)
+        } else if buffer.starts_with(&V2_SIGNATURE) {
+            Self::parse_v2(buffer)
+        } else {
+            Err(invalid("missing PROXY protocol header"))
+        }
+    }
+
+    /// Parse a text header, such as
+    /// `PROXY TCP4 192.0.2.1 198.51.100.1 56324 25565\r\n`
+    fn parse_v1(buffer: &[u8]) -> Result<Option<(Self, usize)>> {
+        let search = &buffer[..buffer.len().min(V1_MAX_LENGTH)];
+        let Some(end) = search.windows(2).position(|w| w == b"\r\n") else {
+            if buffer.len() >= V1_MAX_LENGTH {
+                return Err(invalid("PROXY header too long"));
+            }
+            return Ok(None);
+        };
+        let line = std::str::from_utf8(&buffer[..end]).map_err(|_| invalid("non-ASCII header"))?;
+        let fields: Vec<&str> = line.split(' ').collect();
+        let header = match fields.as_slice() {
+            ["PROXY", "UNKNOWN", ..] => Self::Local,
+            ["PROXY", family @ ("TCP4" | "TCP6"), source, _, port, _] => {
+                let ip: IpAddr = source.parse().map_err(|_| invalid("bad source address"))?;
+                if ip.is_ipv4() != (*family == "TCP4") {
+                    return Err(invalid("source address does not match the family"));
+                }
+                let port = port.parse().map_err(|_| invalid("bad source port"))?;
+                Self::Forwarded(SocketAddr::new(ip, port))
+            }
+            _ => return Err(invalid("malformed PROXY header")),
+        };
+        Ok(Some((header, end + 2)))
+    }
+
+    /// Parse a binary header
+    fn parse_v2(buffer: &[u8]) -> Result<Option<(Self, usize)>> {
+        if buffer.len() < V2_HEADER_LENGTH {
+            return Ok(None);
+        }
+        let version_command = buffer[12];
+        let family = buffer[13];
+        let length = usize::from(u16::from_be_bytes([buffer[14], buffer[15]]));
+        let total = V2_HEADER_LENGTH + length;
+        if version_command >> 4 != 2 {
+            return Err(invalid("unsupported PROXY protocol version"));
+        }
+        if buffer.len() < total {
+            return Ok(None);
+        }
+
+        let addresses = &buffer[V2_HEADER_LENGTH..total];
+        let header = match (version_command & 0x0F, family >> 4) {
+            // LOCAL, or an address family other than IPv4 and IPv6
+            (0, _) | (1, 0 | 3) => Self::Local,
+            (1, 1) if addresses.len() >= 12 => {
+                let ip = Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+                let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+                Self::Forwarded(SocketAddr::new(IpAddr::V4(ip), port))
+            }
+            (1, 2) if addresses.len() >= 36 => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(&addresses[..16]);
+                let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+                Self::Forwarded(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port))
+            }
+            _ => return Err(invalid("malformed PROXY header")),
+        };
+        Ok(Some((header, total)))
+    }
+}
+
+/// Error for a connection that does not start with a valid header
+fn invalid(reason: &str) -> ServerError {
+    ServerError::Protocol(format!("Invalid PROXY protocol header: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_v1() {
+        let header = b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 25565\r\n\x10\x00";
+        let (parsed, length) = ProxyHeader::parse(header).unwrap().unwrap();
+        assert_eq!(
+            parsed,
+            ProxyHeader::Forwarded("192.0.2.1:56324".parse().unwrap())
+        );
+        assert_eq!(length, header.len() - 2);
+
+        let header = b"PROXY TCP6 2001:db8::1 2001:db8::2 4000 25565\r\n";
+        let (parsed, _) = ProxyHeader::parse(header).unwrap().unwrap();
+        assert_eq!(
+            parsed,
+            ProxyHeader::Forwarded("[2001:db8::1]:4000".parse().unwrap())
+        );
+
+        let (parsed, _) = ProxyHeader::parse(b"PROXY UNKNOWN\r\n").unwrap().unwrap();
+        assert_eq!(parsed, ProxyHeader::Local);
+
+        // Incomplete headers wait for more bytes
+        assert!(ProxyHeader::parse(b"PRO").unwrap().is_none());
+        assert!(
+            ProxyHeader::parse(b"PROXY TCP4 192.0.2.1")
+                .unwrap()
+                .is_none()
+        );
+
+        assert!(ProxyHeader::parse(b"PROXY TCP4 2001:db8::1 ::1 1 2\r\n").is_err());
+        assert!(ProxyHeader::parse(b"PROXY TCP4 192.0.2.1\r\n").is_err());
+        assert!(ProxyHeader::parse(&[V1_PREFIX, &[b'x'; 200]].concat()).is_err());
+    }
+
+    #[test]
+    fn test_parse_v2() {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x21, 0x11, 0, 12]);
+        header.extend_from_slice(&[192, 0, 2, 1, 198, 51, 100, 1]);
+        header.extend_from_slice(&[0xDB, 0x04, 0x63, 0xDD]);
+        for end in 0..header.len() {
+            assert!(ProxyHeader::parse(&header[..end]).unwrap().is_none());
+        }
+        let (parsed, length) = ProxyHeader::parse(&header).unwrap().unwrap();
+        assert_eq!(
+            parsed,
+            ProxyHeader::Forwarded("192.0.2.1:56068".parse().unwrap())
+        );
+        assert_eq!(length, header.len());
+
+        let mut local = V2_SIGNATURE.to_vec();
+        local.extend_from_slice(&[0x20, 0x00, 0, 0]);
+        let (parsed, _) = ProxyHeader::parse(&local).unwrap().unwrap();
+        assert_eq!(parsed, ProxyHeader::Local);
+
+        // A truncated address block is malformed
+        let mut short = V2_SIGNATURE.to_vec();
+        short.extend_from_slice(&[0x21, 0x11, 0, 4, 192, 0, 2, 1]);
+        assert!(ProxyHeader::parse(&short).is_err());
+    }
+
+    #[test]
+    fn test_direct_connection_is_not_a_header() {
+        // A handshake starts with its length, not a PROXY header
+        assert!(ProxyHeader::parse(&[0x10, 0x00, 0xFB, 0x05]).is_err());
+    }
+
+    #[test]
+    fn test_trusted_proxies() {
+        let config = ForwardingConfig {
+            trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+            required: true,
+        };
+        assert!(config.is_trusted("127.0.0.1".parse().unwrap()));
+        assert!(config.is_trusted("::ffff:127.0.0.1".parse().unwrap()));
+        assert!(!config.is_trusted("192.0.2.1".parse().unwrap()));
+    }
+}
@@ -0,0 +1,277 @@
+//! Inbound packet rate limiting
+//!
+//! Every play connection has a [`PacketRateLimiter`] that counts the chat,
+//! movement and interaction packets it receives. A client exceeding the
+//! configured [`PacketLimits`] is kicked for spamming.
+//!
+//! Limits are counted over one-second windows. Per-tick limits are scaled
+//! to the twenty ticks of a window, so a client that catches up after lag
+//! and sends a burst of packets is not kicked.
+//!
+//! [`MalformedPackets`] counts the corrupt packets a connection sends the
+//! same way, over longer windows, so only a client that keeps sending them
+//! is disconnected.
+
+use crate::clock::{self, SharedClock};
+use crate::protocol::packets::Packet;
+use crate::protocol::packets::play::{
+    ChatCommandPacket, ChatMessagePacket, InteractPacket, MoveVehiclePacket, PlayerActionPacket,
+    PlayerCommandPacket, PlayerOnGroundPacket, PlayerPositionAndRotationPacket,
+    PlayerPositionPacket, PlayerRotationPacket, SignedChatCommandPacket, SwingArmPacket,
+    UseItemOnPacket, UseItemPacket,
+};
+use std::time::{Duration, Instant};
+
+/// Length of a counting window
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Ticks in a counting window
+const TICKS_PER_WINDOW: u32 = 20;
+
+/// Malformed play packets a connection may send within one
+/// [`MALFORMED_PACKET_WINDOW`] before it is closed
+pub const MAX_MALFORMED_PACKETS: u32 = 3;
+
+/// Length of a window malformed packets are counted over
+pub const MALFORMED_PACKET_WINDOW: Duration = Duration::from_secs(60);
+
+/// Chat command, signed chat command and chat message packets
+const CHAT_PACKETS: [i32; 3] = [
+    ChatCommandPacket::ID,
+    SignedChatCommandPacket::ID,
+    ChatMessagePacket::ID,
+];
+
+/// Player movement and vehicle movement packets
+const MOVEMENT_PACKETS: [i32; 5] = [
+    PlayerPositionPacket::ID,
+    PlayerPositionAndRotationPacket::ID,
+    PlayerRotationPacket::ID,
+    PlayerOnGroundPacket::ID,
+    MoveVehiclePacket::ID,
+];
+
+/// Interact, player action, player command, swing, use item on and use item
+const INTERACTION_PACKETS: [i32; 6] = [
+    InteractPacket::ID,
+    PlayerActionPacket::ID,
+    PlayerCommandPacket::ID,
+    SwingArmPacket::ID,
+    UseItemOnPacket::ID,
+    UseItemPacket::ID,
+];
+
+/// Kinds of packets that are rate limited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketCategory {
+    /// Chat messages and commands
+    Chat,
+    /// Position and rotation updates
+    Movement,
+    /// Attacking, digging, using items and similar actions
+    Interaction,
+}
+
+impl PacketCategory {
+    /// Category of a serverbound play packet, if it is limited
+    pub fn of(packet_id: i32) -> Option<Self> {
+        if CHAT_PACKETS.contains(&packet_id) {
+            Some(Self::Chat)
+        } else if MOVEMENT_PACKETS.contains(&packet_id) {
+            Some(Self::Movement)
+        } else if INTERACTION_PACKETS.contains(&packet_id) {
+            Some(Self::Interaction)
+        } else {
+            None
+        }
+    }
+}
+
+/// Packets a connection may send (zero disables a limit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketLimits {
+    /// Chat messages and commands per second
+    pub chat_per_second: u32,
+    /// Movement packets per tick
+    pub movement_per_tick: u32,
+    /// Interaction packets per tick
+    pub interactions_per_tick: u32,
+}
+
+impl PacketLimits {
+    /// Packets of a category allowed per window, or `None` if unlimited
+    fn per_window(&self, category: PacketCategory) -> Option<u32> {
+        let limit = match category {
+            PacketCategory::Chat => self.chat_per_second,
+            PacketCategory::Movement => self.movement_per_tick.saturating_mul(TICKS_PER_WINDOW),
+            PacketCategory::Interaction => {
+                self.interactions_per_tick.saturating_mul(TICKS_PER_WINDOW)
+            }
+        };
+        (limit > 0).then_some(limit)
+    }
+}
+
+impl Default for PacketLimits {
+    fn default() -> Self {
+        Self {
+            chat_per_second: 5,
+            movement_per_tick: 5,
+            interactions_per_tick: 8,
+        }
+    }
+}
+
+/// Packet counts of one connection
+#[derive(Debug)]
+pub struct PacketRateLimiter {
+    /// Limits enforced
+    limits: PacketLimits,
+    /// Source of the current time
+    clock: SharedClock,
+    /// Start of the current window
+    window_start: Instant,
+    /// Packets of each category received in the current window
+    counts: [u32; 3],
+}
+
+impl PacketRateLimiter {
+    /// Create a limiter enforcing `limits`
+    pub fn new(limits: PacketLimits) -> Self {
+        let clock = clock::system();
+        Self {
+            limits,
+            window_start: clock.now(),
+            clock,
+            counts: [0; 3],
+        }
+    }
+
+    /// Measure windows with the given clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.window_start = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Count a received packet, returning the category whose limit it exceeds
+    pub fn receive(&mut self, packet_id: i32) -> Option<PacketCategory> {
+        let category = PacketCategory::of(packet_id)?;
+        let limit = self.limits.per_window(category)?;
+
+        let now = self.clock.now();
+        if now.saturating_duration_since(self.window_start) >= WINDOW {
+            self.window_start = now;
+            self.counts = [0; 3];
+        }
+        let count = &mut self.counts[category as usize];
+        *count += 1;
+        (*count > limit).then_some(category)
+    }
+}
+
+/// Malformed packets one connection sent recently
+#[derive(Debug)]
+pub struct MalformedPackets {
+    /// Source of the current time
+    clock: SharedClock,
+    /// Start of the current window
+    window_start: Instant,
+    /// Malformed packets received in the current window
+    count: u32,
+}
+
+impl MalformedPackets {
+    /// Create a counter with no malformed packets
+    pub fn new() -> Self {
+        let clock = clock::system();
+        Self {
+            window_start: clock.now(),
+            clock,
+            count: 0,
+        }
+    }
+
+    /// Measure windows with the given clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.window_start = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Count a malformed packet, returning whether the connection may stay
+    pub fn receive(&mut self) -> bool {
+        let now = self.clock.now();
+        if now.saturating_duration_since(self.window_start) >= MALFORMED_PACKET_WINDOW {
+            self.window_start = now;
+            self.count = 0;
+        }
+        self.count += 1;
+        self.count < MAX_MALFORMED_PACKETS
+    }
+}
+
+impl Default for MalformedPackets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::protocol::packets::play::ServerboundKeepAlivePacket;
+
+    #[test]
+    fn test_rate_limits() {
+        let clock = ManualClock::new();
+        let limits = PacketLimits {
+            chat_per_second: 2,
+            movement_per_tick: 1,
+            interactions_per_tick: 0,
+        };
+        let mut limiter = PacketRateLimiter::new(limits).with_clock(clock.shared());
+
+        assert_eq!(limiter.receive(ChatMessagePacket::ID), None);
+        assert_eq!(limiter.receive(ChatCommandPacket::ID), None);
+        assert_eq!(
+            limiter.receive(SignedChatCommandPacket::ID),
+            Some(PacketCategory::Chat)
+        );
+        clock.advance(WINDOW);
+        assert_eq!(limiter.receive(ChatMessagePacket::ID), None);
+
+        // Movement is allowed in bursts as long as it averages out per tick
+        for _ in 0..TICKS_PER_WINDOW {
+            assert_eq!(limiter.receive(PlayerPositionPacket::ID), None);
+        }
+        assert_eq!(
+            limiter.receive(MoveVehiclePacket::ID),
+            Some(PacketCategory::Movement)
+        );
+
+        // Disabled limits and packets outside every category are never refused
+        for _ in 0..1000 {
+            assert_eq!(limiter.receive(UseItemOnPacket::ID), None);
+            assert_eq!(limiter.receive(ServerboundKeepAlivePacket::ID), None);
+        }
+    }
+
+    #[test]
+    fn test_malformed_packets_expire() {
+        let clock = ManualClock::new();
+        let mut malformed = MalformedPackets::new().with_clock(clock.shared());
+
+        for _ in 1..MAX_MALFORMED_PACKETS {
+            assert!(malformed.receive());
+            clock.advance(MALFORMED_PACKET_WINDOW / 2);
+        }
+        clock.advance(MALFORMED_PACKET_WINDOW);
+        assert!(malformed.receive());
+        for _ in 2..MAX_MALFORMED_PACKETS {
+            assert!(malformed.receive());
+        }
+        assert!(!malformed.receive());
+    }
+}
@@ -0,0 +1,179 @@
+//! Packet statistics
+//!
+//! [`PacketCounters`] shared between connections count the packets and
+//! bytes they send and receive, for the profiler and other diagnostics.
+//! They also break the counts down by packet type, which `/debug packets`
+//! and the admin API report to show which packets dominate bandwidth.
+
+use crate::network::dump::Direction;
+use crate::protocol::ConnectionState;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Packet and byte counts of every connection attached to them
+#[derive(Debug, Default)]
+pub struct PacketCounters {
+    received: AtomicU64,
+    sent: AtomicU64,
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    /// Packets and bytes of each packet type
+    by_type: Mutex<HashMap<PacketType, (u64, u64)>>,
+}
+
+/// A packet type, identified by the state and direction its ID belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PacketType {
+    /// Protocol state the packet was sent in
+    pub state: ConnectionState,
+    /// Which way the packet travelled
+    pub direction: Direction,
+    /// Packet ID
+    pub id: i32,
+}
+
+/// Packet and byte counts of one packet type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketTypeCounts {
+    /// The packet type
+    pub packet_type: PacketType,
+    /// Packets counted
+    pub packets: u64,
+    /// Bytes counted, including framing
+    pub bytes: u64,
+}
+
+impl PacketCounters {
+    /// Create counters starting at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a received packet whose frame was `bytes` long
+    pub fn record_received(&self, state: ConnectionState, id: i32, bytes: usize) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.record_type(state, Direction::Serverbound, id, bytes);
+    }
+
+    /// Count a sent packet whose frame was `bytes` long
+    pub fn record_sent(&self, state: ConnectionState, id: i32, bytes: usize) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.record_type(state, Direction::Clientbound, id, bytes);
+    }
+
+    /// Add a packet to the counts of its type
+    fn record_type(&self, state: ConnectionState, direction: Direction, id: i32, bytes: usize) {
+        let packet_type = PacketType {
+            state,
+            direction,
+            id,
+        };
+        let mut by_type = self.by_type.lock().unwrap_or_else(|e| e.into_inner());
+        let (packets, total) = by_type.entry(packet_type).or_default();
+        *packets += 1;
+        *total += bytes as u64;
+    }
+
+    /// Counts of every packet type seen, by most bytes first
+    pub fn by_type(&self) -> Vec<PacketTypeCounts> {
+        let by_type = self.by_type.lock().unwrap_or_else(|e| e.into_inner());
+        let mut counts: Vec<PacketTypeCounts> = by_type
+            .iter()
+            .map(|(&packet_type, &(packets, bytes))| PacketTypeCounts {
+                packet_type,
+                packets,
+                bytes,
+            })
+            .collect();
+        counts.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(b.packets.cmp(&a.packets)));
+        counts
+    }
+
+    /// Forget the counts of each packet type, keeping the totals
+    pub fn reset_types(&self) {
+        self.by_type
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Read the current counts
+    pub fn snapshot(&self) -> PacketCounts {
+        PacketCounts {
+            received: self.received.load(Ordering::Relaxed),
+            sent: self.sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Packet and byte counts at some point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PacketCounts {
+    /// Packets received
+    pub received: u64,
+    /// Packets sent
+    pub sent: u64,
+    /// Bytes received, including framing
+    pub bytes_received: u64,
+    /// Bytes sent, including framing
+    pub bytes_sent: u64,
+}
+
+impl PacketCounts {
+    /// Counts added since an earlier snapshot
+    pub fn since(&self, earlier: &PacketCounts) -> PacketCounts {
+        PacketCounts {
+            received: self.received.saturating_sub(earlier.received),
+            sent: self.sent.saturating_sub(earlier.sent),
+            bytes_received: self.bytes_received.saturating_sub(earlier.bytes_received),
+            bytes_sent: self.bytes_sent.saturating_sub(earlier.bytes_sent),
+        }
+    }
+}
+
+impl PacketTypeCounts {
+    /// Describe the counted packet type, such as `play clientbound 0x27`
+    pub fn label(&self) -> String {
+        format!(
+            "{} {} 0x{:02X}",
+            self.packet_type.state.as_str(),
+            self.packet_type.direction.as_str(),
+            self.packet_type.id
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_by_type() {
+        let counters = PacketCounters::new();
+        counters.record_received(ConnectionState::Play, 0x1D, 10);
+        counters.record_received(ConnectionState::Play, 0x1D, 12);
+        counters.record_sent(ConnectionState::Play, 0x27, 4000);
+        counters.record_sent(ConnectionState::Configuration, 0x07, 30);
+
+        let snapshot = counters.snapshot();
+        assert_eq!(snapshot.received, 2);
+        assert_eq!(snapshot.bytes_sent, 4030);
+
+        let by_type = counters.by_type();
+        assert_eq!(by_type.len(), 3);
+        assert_eq!(by_type[0].label(), "play clientbound 0x27");
+        assert_eq!((by_type[1].packets, by_type[1].bytes), (1, 30));
+        assert_eq!(by_type[2].label(), "play serverbound 0x1D");
+        assert_eq!((by_type[2].packets, by_type[2].bytes), (2, 22));
+
+        counters.reset_types();
+        assert!(counters.by_type().is_empty());
+        assert_eq!(counters.snapshot(), snapshot);
+    }
+}
@@ -0,0 +1,81 @@
+//! Login throttling
+//!
+//! [`LoginThrottle`] refuses logins from an address that tried to log in
+//! less than the throttle interval ago. Every attempt restarts the
+//! interval, so a client retrying in a loop stays locked out until it
+//! backs off.
+
+use crate::clock::{self, SharedClock};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Time of the last login attempt of each address
+#[derive(Debug)]
+pub struct LoginThrottle {
+    /// Shortest time allowed between attempts (zero disables throttling)
+    interval: Duration,
+    /// Source of the current time
+    clock: SharedClock,
+    /// Last attempt of each address still within the interval
+    attempts: Mutex<HashMap<IpAddr, Instant>>,
+}
+
+impl LoginThrottle {
+    /// Create a throttle allowing one attempt per `interval` and address
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            clock: clock::system(),
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Measure the interval with the given clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record a login attempt, returning whether it is allowed
+    pub fn try_attempt(&self, address: IpAddr) -> bool {
+        if self.interval.is_zero() {
+            return true;
+        }
+        let now = self.clock.now();
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        attempts.retain(|_, last| now.saturating_duration_since(*last) < self.interval);
+        attempts.insert(address, now).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn test_throttle_attempts() {
+        let clock = ManualClock::new();
+        let throttle = LoginThrottle::new(Duration::from_secs(4)).with_clock(clock.shared());
+        let alice: IpAddr = "10.0.0.1".parse().unwrap();
+        let bob: IpAddr = "10.0.0.2".parse().unwrap();
+
+        assert!(throttle.try_attempt(alice));
+        assert!(throttle.try_attempt(bob));
+        clock.advance(Duration::from_secs(3));
+        assert!(!throttle.try_attempt(alice));
+
+        // The refused attempt restarted the interval
+        clock.advance(Duration::from_secs(3));
+        assert!(!throttle.try_attempt(alice));
+        assert!(throttle.try_attempt(bob));
+        clock.advance(Duration::from_secs(4));
+        assert!(throttle.try_attempt(alice));
+
+        let disabled = LoginThrottle::new(Duration::ZERO);
+        assert!(disabled.try_attempt(alice));
+        assert!(disabled.try_attempt(alice));
+    }
+}
@@ -0,0 +1,153 @@
+//! Velocity modern forwarding
+//!
+//! A Velocity proxy set to `modern` player info forwarding tells the server
+//! who is logging in and from where. Once a player starts to log in, the
+//! server sends a Login Plugin Request on [`CHANNEL`], and the proxy answers
+//! with the player's address and profile, signed with HMAC-SHA256 using the
+//! secret in `forwarding.secret`. A server with a forwarding secret takes
+//! players to be who the proxy says instead of authenticating them itself,
+//! so it must only be reachable through the proxy.
+
+use crate::config::secrets::Secret;
+use crate::error::{Result, ServerError};
+use crate::protocol::packets::login::Property;
+use crate::protocol::types::{McString, VarInt, read_length, read_uuid};
+use crate::server::profiles::GameProfile;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::Read;
+use std::net::IpAddr;
+
+/// Channel the player info is asked for on
+pub const CHANNEL: &str = "velocity:player_info";
+
+/// Forwarding version asked for, which carries the address and profile
+pub const MODERN_DEFAULT: u8 = 1;
+
+/// Length of the signature in front of the player info
+const SIGNATURE_LENGTH: usize = 32;
+
+/// Longest player name forwarded
+const MAX_NAME_LENGTH: usize = 16;
+
+/// A player as forwarded by the proxy
+#[derive(Debug, Clone)]
+pub struct ForwardedPlayer {
+    /// Address the player connected to the proxy from
+    pub address: IpAddr,
+    /// The player's profile, as authenticated by the proxy
+    pub profile: GameProfile,
+}
+
+impl ForwardedPlayer {
+    /// Check the signature on the proxy's answer and read the player from
+    /// it
+    pub fn verify(secret: &Secret, data: &[u8]) -> Result<Self> {
+        if data.len() < SIGNATURE_LENGTH {
+            return Err(invalid("the player info is not signed"));
+        }
+        let (signature, info) = data.split_at(SIGNATURE_LENGTH);
+        let mut mac = signer(secret);
+        mac.update(info);
+        mac.verify_slice(signature)
+            .map_err(|_| invalid("the player info signature does not match"))?;
+        Self::read(&mut &info[..])
+    }
+
+    /// Read verified player info
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let version = VarInt::read(reader)?.0;
+        if version != i32::from(MODERN_DEFAULT) {
+            return Err(invalid(&format!(
+                "unsupported forwarding version {}",
+                version
+            )));
+        }
+        let address = McString::read(reader)?
+            .0
+            .parse()
+            .map_err(|_| invalid("the forwarded address is not an IP address"))?;
+        let uuid = read_uuid(reader)?;
+        let name = McString::read_with_max_length(reader, MAX_NAME_LENGTH)?.0;
+        let count = read_length(reader, Property::MAX_COUNT)?;
+        let properties = (0..count)
+            .map(|_| Property::read(reader))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            address,
+            profile: GameProfile {
+                uuid,
+                name,
+                properties,
+            },
+        })
+    }
+
+    /// Write the player info and sign it, as the proxy does
+    pub fn sign(&self, secret: &Secret) -> Result<Vec<u8>> {
+        let mut info = Vec::new();
+        VarInt(i32::from(MODERN_DEFAULT)).write(&mut info)?;
+        McString(self.address.to_string()).write(&mut info)?;
+        crate::protocol::types::write_uuid(&self.profile.uuid, &mut info)?;
+        McString(self.profile.name.clone()).write(&mut info)?;
+        VarInt(self.profile.properties.len() as i32).write(&mut info)?;
+        for property in &self.profile.properties {
+            property.write(&mut info)?;
+        }
+
+        let mut mac = signer(secret);
+        mac.update(&info);
+        let mut data = mac.finalize().into_bytes().to_vec();
+        data.append(&mut info);
+        Ok(data)
+    }
+}
+
+/// HMAC keyed with the forwarding secret
+fn signer(secret: &Secret) -> Hmac<Sha256> {
+    // HMAC takes keys of any length
+    Hmac::<Sha256>::new_from_slice(secret.expose().as_bytes())
+        .unwrap_or_else(|_| unreachable!("HMAC-SHA256 rejected a key"))
+}
+
+/// An answer the proxy should not have sent
+fn invalid(reason: &str) -> ServerError {
+    ServerError::Authentication(format!("Invalid Velocity forwarding: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::McUuid;
+
+    #[test]
+    fn test_verify() {
+        let secret = Secret::new("hunter2").unwrap();
+        let player = ForwardedPlayer {
+            address: "192.0.2.1".parse().unwrap(),
+            profile: GameProfile {
+                uuid: McUuid::new_v4(),
+                name: "Steve".to_string(),
+                properties: vec![Property {
+                    name: "textures".into(),
+                    value: "e30=".into(),
+                    signature: Some("c2ln".into()),
+                }],
+            },
+        };
+        let data = player.sign(&secret).unwrap();
+
+        let verified = ForwardedPlayer::verify(&secret, &data).unwrap();
+        assert_eq!(verified.address, player.address);
+        assert_eq!(verified.profile.uuid, player.profile.uuid);
+        assert_eq!(verified.profile.name, "Steve");
+        assert_eq!(verified.profile.properties.len(), 1);
+
+        let other = Secret::new("hunter3").unwrap();
+        assert!(ForwardedPlayer::verify(&other, &data).is_err());
+        let mut tampered = data.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(ForwardedPlayer::verify(&secret, &tampered).is_err());
+        assert!(ForwardedPlayer::verify(&secret, &data[..16]).is_err());
+    }
+}
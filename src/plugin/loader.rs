@@ -0,0 +1,189 @@
+//! Native plugin loading
+//!
+//! Opens `cdylib` plugins with the platform's dynamic loader and reads the
+//! [`PluginDeclaration`] they export. Its [`PluginHeader`] is checked first;
+//! the remaining fields use the Rust ABI and are only read once the header
+//! shows the plugin was built by the same compiler and Obsidium version.
+
+use super::{Plugin, PluginDeclaration, PluginHeader};
+use crate::error::{Result, ServerError};
+use std::path::Path;
+
+/// Name of the symbol exported by [`declare_plugin!`](crate::declare_plugin)
+const DECLARATION_SYMBOL: &str = "OBSIDIUM_PLUGIN";
+
+/// A loaded shared library
+///
+/// Libraries are never unloaded: code from a plugin may still be referenced
+/// after it is disabled (for example by a task it spawned), so unloading
+/// could leave dangling function pointers behind.
+pub struct Library {
+    #[cfg(unix)]
+    handle: *mut std::ffi::c_void,
+}
+
+// SAFETY: the handle is only used to look up symbols, which the dynamic
+// loader allows from any thread.
+unsafe impl Send for Library {}
+// SAFETY: see above.
+unsafe impl Sync for Library {}
+
+/// Load a native plugin, returning the plugin and the library backing it
+pub fn load(path: &Path) -> Result<(Box<dyn Plugin>, Library)> {
+    let library = Library::open(path)?;
+    let header = library.header()?;
+    check_header(header)
+        .map_err(|reason| ServerError::Plugin(format!("{} {}", path.display(), reason)))?;
+    // SAFETY: the header matches, so the plugin was built by the same
+    // compiler against the same Obsidium and the declaration's layout is
+    // the one this build uses. The header is its first field.
+    let declaration = unsafe { &*(header as *const PluginHeader as *const PluginDeclaration) };
+
+    (declaration.init_logging)(tracing::dispatcher::get_default(Clone::clone));
+    Ok(((declaration.create)(), library))
+}
+
+/// Check that a plugin header matches this build, explaining any mismatch
+fn check_header(header: &PluginHeader) -> std::result::Result<(), String> {
+    let expected = PluginHeader::current();
+    if header.magic != expected.magic || header.header_size != expected.header_size {
+        return Err("does not export a valid plugin declaration".to_string());
+    }
+    if header.api_version != expected.api_version {
+        return Err(format!(
+            "was built for plugin API {} (server uses {})",
+            header.api_version, expected.api_version
+        ));
+    }
+    if header.rustc_version != expected.rustc_version {
+        return Err(format!(
+            "was built with {} (server was built with {})",
+            header.rustc_version(),
+            expected.rustc_version()
+        ));
+    }
+    if header.obsidium_version != expected.obsidium_version {
+        return Err(format!(
+            "was built against Obsidium {} (server is {})",
+            header.obsidium_version(),
+            expected.obsidium_version()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+impl Library {
+    /// Open a shared library
+    fn open(path: &Path) -> Result<Self> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| ServerError::Plugin(format!("Invalid plugin path {}", path.display())))?;
+
+        // SAFETY: `c_path` is a valid NUL-terminated string. Loading runs the
+        // library's initializers, which is inherent to loading plugins.
+        let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            return Err(ServerError::Plugin(format!(
+                "Failed to load {}: {}",
+                path.display(),
+                last_error()
+            )));
+        }
+        Ok(Self { handle })
+    }
+
+    /// Look up the header of the plugin declaration
+    fn header(&self) -> Result<&'static PluginHeader> {
+        let name = std::ffi::CString::new(DECLARATION_SYMBOL).expect("symbol name has no NUL");
+
+        // SAFETY: `handle` is a live library handle and `name` is a valid
+        // NUL-terminated string.
+        let symbol = unsafe { libc::dlsym(self.handle, name.as_ptr()) };
+        if symbol.is_null() {
+            return Err(ServerError::Plugin(format!(
+                "Library does not export {} (missing declare_plugin!?)",
+                DECLARATION_SYMBOL
+            )));
+        }
+
+        // SAFETY: the symbol is the static emitted by `declare_plugin!`,
+        // which starts with the C-layout header whatever compiler built it.
+        // The library is never unloaded, so the reference stays valid.
+        Ok(unsafe { &*(symbol as *const PluginHeader) })
+    }
+}
+
+/// Get the dynamic loader's last error message
+#[cfg(unix)]
+fn last_error() -> String {
+    // SAFETY: dlerror returns either null or a valid C string.
+    let message = unsafe { libc::dlerror() };
+    if message.is_null() {
+        return "unknown error".to_string();
+    }
+    // SAFETY: checked for null above.
+    unsafe { std::ffi::CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(not(unix))]
+impl Library {
+    /// Open a shared library
+    fn open(path: &Path) -> Result<Self> {
+        Err(ServerError::Plugin(format!(
+            "Cannot load {}: native plugins are only supported on Unix platforms",
+            path.display()
+        )))
+    }
+
+    /// Look up the header of the plugin declaration
+    fn header(&self) -> Result<&'static PluginHeader> {
+        Err(ServerError::Plugin(
+            "Native plugins are only supported on Unix platforms".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_header() {
+        let current = PluginHeader::current();
+        assert!(check_header(&current).is_ok());
+        assert!(current.rustc_version().starts_with("rustc "));
+
+        let garbage = PluginHeader {
+            magic: 0,
+            ..current
+        };
+        assert!(check_header(&garbage).is_err());
+        let other_api = PluginHeader {
+            api_version: current.api_version + 1,
+            ..current
+        };
+        assert!(check_header(&other_api).unwrap_err().contains("plugin API"));
+        let other_compiler = PluginHeader {
+            rustc_version: crate::plugin::padded("rustc 1.0.0"),
+            ..current
+        };
+        assert!(
+            check_header(&other_compiler)
+                .unwrap_err()
+                .contains("rustc 1.0.0")
+        );
+        let other_version = PluginHeader {
+            obsidium_version: crate::plugin::padded("0.0.1"),
+            ..current
+        };
+        assert!(
+            check_header(&other_version)
+                .unwrap_err()
+                .contains("Obsidium 0.0.1")
+        );
+    }
+}
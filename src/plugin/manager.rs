@@ -0,0 +1,290 @@
+//! Plugin manager
+//!
+//! Keeps track of loaded plugins and drives their lifecycle: plugins are
+//! loaded at startup, enabled once the server is ready and disabled on
+//! shutdown, at which point their event handlers and commands are removed.
+
+use super::loader::{self, Library};
+use super::{PLUGIN_DIRECTORY, Plugin, PluginContext};
+use crate::error::{Result, ServerError};
+use crate::event::ListenerId;
+use crate::server::ServerState;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Extension of WebAssembly plugin modules
+const WASM_EXTENSION: &str = "wasm";
+
+/// Summary of a loaded plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginInfo {
+    /// Plugin name
+    pub name: String,
+    /// Plugin version
+    pub version: String,
+    /// Whether the plugin is enabled
+    pub enabled: bool,
+}
+
+/// A plugin known to the manager
+struct LoadedPlugin {
+    /// The plugin instance
+    plugin: Box<dyn Plugin>,
+    /// Library the plugin was loaded from, if it is a native plugin
+    _library: Option<Library>,
+    /// Whether the plugin is currently enabled
+    enabled: AtomicBool,
+    /// Event subscriptions made by the plugin
+    listeners: Mutex<Vec<ListenerId>>,
+}
+
+/// Loads plugins and manages their lifecycle
+pub struct PluginManager {
+    /// Directory native plugins are loaded from
+    directory: PathBuf,
+    /// Loaded plugins in load order
+    plugins: Mutex<Vec<Arc<LoadedPlugin>>>,
+}
+
+impl PluginManager {
+    /// Create a manager loading native plugins from the given directory
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            plugins: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add a plugin that is linked into the server binary
+    pub fn add(&self, plugin: Box<dyn Plugin>) -> Result<()> {
+        self.insert(plugin, None)
+    }
+
+    /// Load every native and WebAssembly plugin in the plugin directory
+    ///
+    /// Plugins that fail to load are logged and skipped. Returns the number
+    /// of plugins loaded.
+    pub fn load_directory(&self) -> usize {
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return 0,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to read plugin directory {}: {}",
+                    self.directory.display(),
+                    e
+                );
+                return 0;
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some(ext) if ext == std::env::consts::DLL_EXTENSION => paths.push(path),
+                Some(WASM_EXTENSION) if cfg!(feature = "wasm-plugins") => paths.push(path),
+                Some(WASM_EXTENSION) => tracing::warn!(
+                    "Skipping {}: WebAssembly plugins are not supported by this build",
+                    path.display()
+                ),
+                _ => {}
+            }
+        }
+        paths.sort();
+
+        let mut loaded = 0;
+        for path in paths {
+            match self.load_file(&path) {
+                Ok(()) => loaded += 1,
+                Err(e) => tracing::error!("Could not load plugin {}: {}", path.display(), e),
+            }
+        }
+        loaded
+    }
+
+    /// Load a single native or WebAssembly plugin
+    pub fn load_file(&self, path: &Path) -> Result<()> {
+        #[cfg(feature = "wasm-plugins")]
+        if path.extension().is_some_and(|ext| ext == WASM_EXTENSION) {
+            return self.insert(Box::new(super::wasm::WasmPlugin::load(path)?), None);
+        }
+        let (plugin, library) = loader::load(path)?;
+        self.insert(plugin, Some(library))
+    }
+
+    /// Enable all loaded plugins that are not enabled yet
+    pub fn enable_all(&self, server: &Arc<ServerState>) {
+        for loaded in self.snapshot() {
+            if loaded.enabled.load(Ordering::Relaxed) {
+                continue;
+            }
+
+            let name = loaded.plugin.name();
+            tracing::info!("Enabling {} v{}", name, loaded.plugin.version());
+            let ctx = PluginContext::new(server, name, &loaded.listeners);
+            match catch_unwind(AssertUnwindSafe(|| loaded.plugin.on_enable(&ctx))) {
+                Ok(Ok(())) => loaded.enabled.store(true, Ordering::Relaxed),
+                Ok(Err(e)) => {
+                    tracing::error!("Error enabling {}: {}", name, e);
+                    Self::cleanup(server, &loaded);
+                }
+                Err(_) => {
+                    tracing::error!("{} panicked while enabling", name);
+                    Self::cleanup(server, &loaded);
+                }
+            }
+        }
+    }
+
+    /// Disable all enabled plugins, in reverse load order
+    pub fn disable_all(&self, server: &Arc<ServerState>) {
+        for loaded in self.snapshot().into_iter().rev() {
+            if !loaded.enabled.swap(false, Ordering::Relaxed) {
+                continue;
+            }
+
+            let name = loaded.plugin.name();
+            tracing::info!("Disabling {}", name);
+            let ctx = PluginContext::new(server, name, &loaded.listeners);
+            if catch_unwind(AssertUnwindSafe(|| loaded.plugin.on_disable(&ctx))).is_err() {
+                tracing::error!("{} panicked while disabling", name);
+            }
+            Self::cleanup(server, &loaded);
+        }
+    }
+
+    /// Describe all loaded plugins in load order
+    pub fn plugins(&self) -> Vec<PluginInfo> {
+        self.snapshot()
+            .iter()
+            .map(|loaded| PluginInfo {
+                name: loaded.plugin.name().to_string(),
+                version: loaded.plugin.version().to_string(),
+                enabled: loaded.enabled.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    /// Add a plugin unless one with the same name is already loaded
+    fn insert(&self, plugin: Box<dyn Plugin>, library: Option<Library>) -> Result<()> {
+        let mut plugins = self.plugins.lock().unwrap_or_else(|e| e.into_inner());
+        let name = plugin.name();
+        if plugins
+            .iter()
+            .any(|loaded| loaded.plugin.name().eq_ignore_ascii_case(name))
+        {
+            return Err(ServerError::Plugin(format!(
+                "A plugin named {} is already loaded",
+                name
+            )));
+        }
+
+        tracing::info!("Loaded plugin {} v{}", name, plugin.version());
+        plugins.push(Arc::new(LoadedPlugin {
+            plugin,
+            _library: library,
+            enabled: AtomicBool::new(false),
+            listeners: Mutex::new(Vec::new()),
+        }));
+        Ok(())
+    }
+
+    /// Copy the plugin list so plugins can call back into the manager
+    fn snapshot(&self) -> Vec<Arc<LoadedPlugin>> {
+        self.plugins
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Remove the event handlers, commands and tasks a plugin registered
+    fn cleanup(server: &ServerState, loaded: &LoadedPlugin) {
+        let listeners =
+            std::mem::take(&mut *loaded.listeners.lock().unwrap_or_else(|e| e.into_inner()));
+        for id in listeners {
+            server.events.unsubscribe(id);
+        }
+        server.commands.unregister_plugin(loaded.plugin.name());
+        server.scheduler.cancel_plugin(loaded.plugin.name());
+    }
+}
+
+impl Default for PluginManager {
+    fn default() -> Self {
+        Self::new(PLUGIN_DIRECTORY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{Command, CommandContext, CommandResult};
+    use crate::config::ServerConfig;
+    use crate::event::ServerEvent;
+    use async_trait::async_trait;
+    use std::sync::atomic::AtomicUsize;
+
+    struct PingCommand;
+
+    #[async_trait]
+    impl Command for PingCommand {
+        fn name(&self) -> &str {
+            "ping"
+        }
+
+        async fn execute(&self, _ctx: &mut CommandContext<'_>) -> CommandResult {
+            Ok(1)
+        }
+    }
+
+    struct TestPlugin(Arc<AtomicUsize>);
+
+    impl Plugin for TestPlugin {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn on_enable(&self, ctx: &PluginContext<'_>) -> Result<()> {
+            let events = Arc::clone(&self.0);
+            ctx.subscribe(move |_| {
+                events.fetch_add(1, Ordering::Relaxed);
+            });
+            ctx.register_command(Arc::new(PingCommand));
+            ctx.run_repeating(1, 1, |_, _| {});
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_plugin_lifecycle() {
+        let server = Arc::new(ServerState::new(ServerConfig::default()));
+        let events = Arc::new(AtomicUsize::new(0));
+
+        server
+            .plugins
+            .add(Box::new(TestPlugin(Arc::clone(&events))))
+            .unwrap();
+        assert!(
+            server
+                .plugins
+                .add(Box::new(TestPlugin(Arc::clone(&events))))
+                .is_err()
+        );
+
+        server.plugins.enable_all(&server);
+        assert!(server.plugins.plugins()[0].enabled);
+        assert!(server.commands.get("test:ping").is_some());
+        assert_eq!(server.scheduler.pending_count(), 1);
+        server.events.publish(&ServerEvent::ServerStarted);
+        assert_eq!(events.load(Ordering::Relaxed), 1);
+
+        server.plugins.disable_all(&server);
+        assert!(!server.plugins.plugins()[0].enabled);
+        assert!(server.commands.get("test:ping").is_none());
+        assert_eq!(server.scheduler.pending_count(), 0);
+        server.events.publish(&ServerEvent::ServerStopping);
+        assert_eq!(events.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,295 @@
+//! Plugin API
+//!
+//! Plugins extend the server with event handlers and commands. A plugin is
+//! any type implementing [`Plugin`]; it is either registered directly with
+//! the [`PluginManager`] (when embedding Obsidium) or built as a `cdylib`
+//! that exports a declaration with [`declare_plugin!`](crate::declare_plugin)
+//! and dropped into the `plugins/` directory. With the `wasm-plugins`
+//! feature, sandboxed WebAssembly modules in that directory are loaded too
+//! (see [`wasm`]).
+//!
+//! Native plugins share Rust types with the server, so they must be built
+//! with the same compiler and the same Obsidium version. A declaration
+//! starts with a C-layout [`PluginHeader`] recording both, plus
+//! [`PLUGIN_API_VERSION`]. The loader checks the header before reading any
+//! other field, since those are only meaningful to the same compiler.
+//!
+//! # Example
+//!
+//! ```rust
+//! use obsidium::plugin::{Plugin, PluginContext};
+//!
+//! struct GreeterPlugin;
+//!
+//! impl Plugin for GreeterPlugin {
+//!     fn name(&self) -> &str {
+//!         "greeter"
+//!     }
+//!
+//!     fn on_enable(&self, ctx: &PluginContext<'_>) -> obsidium::Result<()> {
+//!         ctx.subscribe(|event| tracing::info!("Greeter saw {}", event.name()));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! obsidium::declare_plugin!(GreeterPlugin);
+//! ```
+
+mod loader;
+pub mod manager;
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm;
+
+pub use manager::{PluginInfo, PluginManager};
+
+use crate::command::Command;
+use crate::error::Result;
+use crate::event::{ListenerId, ServerEvent};
+use crate::game::world::World;
+use crate::server::ServerState;
+use crate::server::scheduler::TaskHandle;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Version of the plugin interface; bumped on every breaking change
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// Obsidium version native plugins must be built against
+pub const OBSIDIUM_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the compiler Obsidium was built with
+pub const RUSTC_VERSION: &str = env!("OBSIDIUM_RUSTC_VERSION");
+
+/// Marks the start of a [`PluginHeader`]
+pub const PLUGIN_MAGIC: u32 = u32::from_be_bytes(*b"OBSP");
+
+/// Directory plugins are loaded from
+pub const PLUGIN_DIRECTORY: &str = "plugins";
+
+/// A server plugin
+pub trait Plugin: Send + Sync {
+    /// Unique plugin name, also used as its command namespace
+    fn name(&self) -> &str;
+
+    /// Plugin version
+    fn version(&self) -> &str {
+        "unknown"
+    }
+
+    /// Called when the plugin is enabled, after the world has loaded
+    ///
+    /// Returning an error leaves the plugin disabled.
+    fn on_enable(&self, ctx: &PluginContext<'_>) -> Result<()>;
+
+    /// Called when the plugin is disabled, before the server shuts down
+    ///
+    /// Event handlers, commands and tasks registered through the context are
+    /// removed automatically afterwards.
+    fn on_disable(&self, _ctx: &PluginContext<'_>) {}
+}
+
+/// Start of a [`PluginDeclaration`], laid out the same by every compiler
+///
+/// Versions are stored as NUL-padded bytes rather than `&str`, whose layout
+/// is not stable.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluginHeader {
+    /// Always [`PLUGIN_MAGIC`]
+    pub magic: u32,
+    /// Size of the header in bytes
+    pub header_size: u32,
+    /// Plugin API version the plugin was built against
+    pub api_version: u32,
+    /// Obsidium version the plugin was built against
+    pub obsidium_version: [u8; 32],
+    /// Version of the compiler the plugin was built with
+    pub rustc_version: [u8; 64],
+}
+
+impl PluginHeader {
+    /// Header describing code built together with this copy of Obsidium
+    pub const fn current() -> Self {
+        Self {
+            magic: PLUGIN_MAGIC,
+            header_size: std::mem::size_of::<Self>() as u32,
+            api_version: PLUGIN_API_VERSION,
+            obsidium_version: padded(OBSIDIUM_VERSION),
+            rustc_version: padded(RUSTC_VERSION),
+        }
+    }
+
+    /// Obsidium version the plugin was built against
+    pub fn obsidium_version(&self) -> String {
+        unpadded(&self.obsidium_version)
+    }
+
+    /// Version of the compiler the plugin was built with
+    pub fn rustc_version(&self) -> String {
+        unpadded(&self.rustc_version)
+    }
+}
+
+/// Copy text into a NUL-padded array, cutting it off if it does not fit
+const fn padded<const N: usize>(text: &str) -> [u8; N] {
+    let bytes = text.as_bytes();
+    let mut out = [0u8; N];
+    let mut i = 0;
+    while i < N && i < bytes.len() {
+        out[i] = bytes[i];
+        i += 1;
+    }
+    out
+}
+
+/// Text of a NUL-padded array
+fn unpadded(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// Exported by native plugins under the symbol `OBSIDIUM_PLUGIN`
+///
+/// Use [`declare_plugin!`](crate::declare_plugin) instead of building this
+/// by hand. Only the header may be read before it has been checked.
+#[repr(C)]
+pub struct PluginDeclaration {
+    /// Versions the plugin was built with; must come first
+    pub header: PluginHeader,
+    /// Install the server's log dispatcher in the plugin
+    ///
+    /// A native plugin links its own copy of `tracing`, whose global
+    /// dispatcher is separate from the server's. The forwarded dispatcher
+    /// was never registered with that copy, so its interest cache has to be
+    /// rebuilt after installing it.
+    pub init_logging: fn(tracing::Dispatch),
+    /// Create the plugin instance
+    pub create: fn() -> Box<dyn Plugin>,
+}
+
+#[doc(hidden)]
+pub use tracing as __tracing;
+
+/// Export a plugin from a `cdylib` crate
+///
+/// The argument is an expression constructing the plugin.
+#[macro_export]
+macro_rules! declare_plugin {
+    ($plugin:expr) => {
+        #[doc(hidden)]
+        #[unsafe(no_mangle)]
+        pub static OBSIDIUM_PLUGIN: $crate::plugin::PluginDeclaration =
+            $crate::plugin::PluginDeclaration {
+                header: $crate::plugin::PluginHeader::current(),
+                init_logging: |dispatch| {
+                    let _ = $crate::plugin::__tracing::dispatcher::set_global_default(dispatch);
+                    $crate::plugin::__tracing::callsite::rebuild_interest_cache();
+                },
+                create: || ::std::boxed::Box::new($plugin),
+            };
+    };
+}
+
+/// Server access handed to a plugin while it is enabled or disabled
+pub struct PluginContext<'a> {
+    /// The running server
+    server: &'a Arc<ServerState>,
+    /// Name of the plugin this context belongs to
+    name: &'a str,
+    /// Event subscriptions made by the plugin
+    listeners: &'a Mutex<Vec<ListenerId>>,
+}
+
+impl<'a> PluginContext<'a> {
+    /// Create a context for a plugin
+    pub(crate) fn new(
+        server: &'a Arc<ServerState>,
+        name: &'a str,
+        listeners: &'a Mutex<Vec<ListenerId>>,
+    ) -> Self {
+        Self {
+            server,
+            name,
+            listeners,
+        }
+    }
+
+    /// Get the server state
+    pub fn server(&self) -> &Arc<ServerState> {
+        self.server
+    }
+
+    /// Get the plugin's name
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Subscribe to server events until the plugin is disabled
+    pub fn subscribe<F>(&self, handler: F) -> ListenerId
+    where
+        F: Fn(&ServerEvent) + Send + Sync + 'static,
+    {
+        let id = self.server.events.subscribe(Arc::new(handler));
+        self.listeners
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(id);
+        id
+    }
+
+    /// Register a command until the plugin is disabled
+    ///
+    /// The command is always available as `<plugin>:<name>`. Returns
+    /// `false` if a command with the same name already exists.
+    pub fn register_command(&self, command: Arc<dyn Command>) -> bool {
+        self.server
+            .commands
+            .register_plugin_command(self.name, command)
+    }
+
+    /// Run a task once after `delay` ticks, unless the plugin is disabled first
+    ///
+    /// See [`Scheduler::run_later`](crate::server::scheduler::Scheduler::run_later).
+    pub fn run_later<F>(&self, delay: u64, task: F) -> TaskHandle
+    where
+        F: FnOnce(&ServerState, &mut World) + Send + 'static,
+    {
+        self.server
+            .scheduler
+            .schedule_later(Some(self.name), delay, task)
+    }
+
+    /// Run a task every `period` ticks until the plugin is disabled
+    ///
+    /// See [`Scheduler::run_repeating`](crate::server::scheduler::Scheduler::run_repeating).
+    pub fn run_repeating<F>(&self, delay: u64, period: u64, task: F) -> TaskHandle
+    where
+        F: FnMut(&ServerState, &mut World) + Send + 'static,
+    {
+        self.server
+            .scheduler
+            .schedule_repeating(Some(self.name), delay, period, task)
+    }
+
+    /// Run a future in the background and its callback on the main thread
+    ///
+    /// See [`Scheduler::run_async`](crate::server::scheduler::Scheduler::run_async).
+    pub fn run_async<T, Fut, F>(&self, future: Fut, callback: F) -> TaskHandle
+    where
+        T: Send + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+        F: FnOnce(&ServerState, &mut World, T) + Send + 'static,
+    {
+        self.server
+            .scheduler
+            .schedule_async(Some(self.name), future, callback)
+    }
+
+    /// Directory for the plugin's own files (`plugins/<name>/`)
+    ///
+    /// The directory is not created automatically.
+    pub fn data_directory(&self) -> PathBuf {
+        PathBuf::from(PLUGIN_DIRECTORY).join(self.name)
+    }
+}
@@ -0,0 +1,559 @@
+//! WebAssembly plugins
+//!
+//! A `.wasm` file in the plugin directory is loaded as a sandboxed plugin
+//! named after the file. Guests get no WASI and no host access beyond the
+//! functions imported from the `obsidium` module, every call into the guest
+//! runs on a fuel budget, and its memory is capped.
+//!
+//! Strings cross the boundary as UTF-8 `(ptr, len)` pairs in the guest's
+//! exported `memory`. To pass a string in, the host calls the guest's
+//! `obsidium_alloc(len) -> ptr` export and copies it there.
+//!
+//! Guest exports, all optional except `memory` and `obsidium_alloc`:
+//!
+//! - `on_enable() -> i32`: nonzero leaves the plugin disabled
+//! - `on_disable()`
+//! - `on_event(ptr, len)`: a subscribed event as JSON (see
+//!   [`ServerEvent::to_json`])
+//! - `on_command(ptr, len) -> i32`: a registered command was run; the JSON
+//!   has `command`, `args`, `sender` and `uuid`. Negative results fail.
+//!
+//! Host imports from `obsidium`:
+//!
+//! - `log(level, ptr, len)`: 0 error, 1 warn, 2 info, 3 debug, else trace
+//! - `broadcast(ptr, len)`: send a chat message to every player
+//! - `send_message(uuid_ptr, uuid_len, ptr, len) -> i32`: message a player
+//! - `reply(ptr, len)`: answer the sender of the running command
+//! - `subscribe(ptr, len)`: receive events by name, or `*` for all
+//! - `register_command(ptr, len) -> i32`: add `<plugin>:<name>`
+//! - `set_block(x, y, z, ptr, len) -> i32`: place a block by name on the
+//!   next tick
+//!
+//! `subscribe` and `register_command` only work during `on_enable`. Calls
+//! that return `i32` report failure as `-1`.
+
+use super::{Plugin, PluginContext};
+use crate::command::argument::StringArgument;
+use crate::command::{Command, CommandContext, CommandError, CommandResult};
+use crate::error::{Result, ServerError};
+use crate::event::ServerEvent;
+use crate::protocol::types::{JsonTextComponent, McUuid, Position};
+use crate::server::ServerState;
+use async_trait::async_trait;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use wasmtime::{
+    Caller, Config, Engine, Extern, Instance, Linker, Module, Store, StoreLimits,
+    StoreLimitsBuilder,
+};
+
+/// Fuel a single call into the guest may burn before it is aborted
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+/// Largest linear memory a guest may grow to, in bytes
+const MAX_MEMORY: usize = 64 * 1024 * 1024;
+
+/// Name of the host import module
+const HOST_MODULE: &str = "obsidium";
+
+/// State the host functions see
+struct HostState {
+    /// Plugin name, used as the owner of scheduled world edits
+    name: String,
+    /// The server, while the plugin is enabled
+    server: Weak<ServerState>,
+    /// Sandbox resource limits
+    limits: StoreLimits,
+    /// Whether `on_enable` is running
+    enabling: bool,
+    /// Event names requested during `on_enable`
+    subscriptions: Vec<String>,
+    /// Command names registered during `on_enable`
+    commands: Vec<String>,
+    /// Replies made by the running command
+    replies: Vec<String>,
+}
+
+/// An instantiated guest module
+struct Guest {
+    /// Store holding the guest's state
+    store: Store<HostState>,
+    /// The module instance
+    instance: Instance,
+}
+
+impl Guest {
+    /// Whether the guest exports a function
+    fn exports(&mut self, name: &str) -> bool {
+        self.instance.get_func(&mut self.store, name).is_some()
+    }
+
+    /// Copy a string into guest memory
+    fn write_string(&mut self, text: &str) -> wasmtime::Result<(i32, i32)> {
+        let len = i32::try_from(text.len())?;
+        let alloc = self
+            .instance
+            .get_typed_func::<i32, i32>(&mut self.store, "obsidium_alloc")?;
+        self.store.set_fuel(FUEL_PER_CALL)?;
+        let ptr = alloc.call(&mut self.store, len)?;
+        let memory = self
+            .instance
+            .get_memory(&mut self.store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("guest exports no memory"))?;
+        memory.write(&mut self.store, usize::try_from(ptr)?, text.as_bytes())?;
+        Ok((ptr, len))
+    }
+
+    /// Call an export without arguments, on a fresh fuel budget
+    fn call<R: wasmtime::WasmResults>(&mut self, name: &str) -> wasmtime::Result<R> {
+        let func = self
+            .instance
+            .get_typed_func::<(), R>(&mut self.store, name)?;
+        self.store.set_fuel(FUEL_PER_CALL)?;
+        func.call(&mut self.store, ())
+    }
+
+    /// Call an export with a string argument, on a fresh fuel budget
+    fn call_with_string<R: wasmtime::WasmResults>(
+        &mut self,
+        name: &str,
+        text: &str,
+    ) -> wasmtime::Result<R> {
+        let func = self
+            .instance
+            .get_typed_func::<(i32, i32), R>(&mut self.store, name)?;
+        let args = self.write_string(text)?;
+        self.store.set_fuel(FUEL_PER_CALL)?;
+        func.call(&mut self.store, args)
+    }
+}
+
+/// A guest shared between the plugin, its event handler and its commands
+#[derive(Clone)]
+struct SharedGuest(Arc<Mutex<Guest>>);
+
+impl SharedGuest {
+    /// Lock the guest
+    fn lock(&self) -> MutexGuard<'_, Guest> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A plugin running in the WebAssembly sandbox
+pub struct WasmPlugin {
+    /// Plugin name, taken from the file name
+    name: String,
+    /// The guest module
+    guest: SharedGuest,
+}
+
+impl WasmPlugin {
+    /// Compile and instantiate a `.wasm` or `.wat` file
+    pub fn load(path: &Path) -> Result<Self> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| ServerError::Plugin("Plugin file name is not valid UTF-8".into()))?;
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(name, &bytes)
+    }
+
+    /// Compile and instantiate a module from its binary or text form
+    pub fn from_bytes(name: &str, bytes: &[u8]) -> Result<Self> {
+        Self::instantiate(name, bytes)
+            .map_err(|e| ServerError::Plugin(format!("Could not load {}: {:#}", name, e)))
+    }
+
+    /// Set up a sandboxed store and instantiate the module in it
+    fn instantiate(name: &str, bytes: &[u8]) -> wasmtime::Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, bytes)?;
+
+        let mut linker = Linker::new(&engine);
+        link_host_functions(&mut linker)?;
+
+        let mut store = Store::new(
+            &engine,
+            HostState {
+                name: name.to_string(),
+                server: Weak::new(),
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(MAX_MEMORY)
+                    .instances(1)
+                    .build(),
+                enabling: false,
+                subscriptions: Vec::new(),
+                commands: Vec::new(),
+                replies: Vec::new(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_CALL)?;
+        let instance = linker.instantiate(&mut store, &module)?;
+        if instance.get_memory(&mut store, "memory").is_none() {
+            return Err(wasmtime::Error::msg("guest exports no memory"));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            guest: SharedGuest(Arc::new(Mutex::new(Guest { store, instance }))),
+        })
+    }
+}
+
+impl Plugin for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_enable(&self, ctx: &PluginContext<'_>) -> Result<()> {
+        let (subscriptions, commands) = {
+            let mut guest = self.guest.lock();
+            let state = guest.store.data_mut();
+            state.server = Arc::downgrade(ctx.server());
+            state.enabling = true;
+            let status = if guest.exports("on_enable") {
+                guest.call::<i32>("on_enable")
+            } else {
+                Ok(0)
+            };
+            let state = guest.store.data_mut();
+            state.enabling = false;
+            match status {
+                Ok(0) => {}
+                Ok(code) => {
+                    return Err(ServerError::Plugin(format!("on_enable returned {}", code)));
+                }
+                Err(e) => return Err(ServerError::Plugin(format!("on_enable failed: {:#}", e))),
+            }
+            (
+                std::mem::take(&mut state.subscriptions),
+                std::mem::take(&mut state.commands),
+            )
+        };
+
+        if !subscriptions.is_empty() {
+            let guest = self.guest.clone();
+            let name = self.name.clone();
+            ctx.subscribe(move |event| {
+                let wanted = subscriptions.iter().any(|s| s == "*" || s == event.name());
+                if wanted {
+                    deliver_event(&guest, &name, event);
+                }
+            });
+        }
+        for command in commands {
+            let registered = ctx.register_command(Arc::new(WasmCommand {
+                name: command.clone(),
+                guest: self.guest.clone(),
+            }));
+            if !registered {
+                tracing::warn!("{} could not register command {}", self.name, command);
+            }
+        }
+        Ok(())
+    }
+
+    fn on_disable(&self, _ctx: &PluginContext<'_>) {
+        let mut guest = self.guest.lock();
+        if guest.exports("on_disable") {
+            if let Err(e) = guest.call::<()>("on_disable") {
+                tracing::error!("{} failed while disabling: {:#}", self.name, e);
+            }
+        }
+        guest.store.data_mut().server = Weak::new();
+    }
+}
+
+/// Hand an event to the guest's `on_event` export
+fn deliver_event(guest: &SharedGuest, name: &str, event: &ServerEvent) {
+    let mut guest = guest.lock();
+    if !guest.exports("on_event") {
+        return;
+    }
+    let json = event.to_json().to_string();
+    if let Err(e) = guest.call_with_string::<()>("on_event", &json) {
+        tracing::error!("{} failed handling {}: {:#}", name, event.name(), e);
+    }
+}
+
+/// A command registered by a guest
+struct WasmCommand {
+    /// Command name without the plugin namespace
+    name: String,
+    /// The guest that handles it
+    guest: SharedGuest,
+}
+
+#[async_trait]
+impl Command for WasmCommand {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn execute(&self, ctx: &mut CommandContext<'_>) -> CommandResult {
+        let args = ctx
+            .optional_argument(&StringArgument::Greedy)?
+            .unwrap_or_default();
+        let input = serde_json::json!({
+            "command": self.name,
+            "args": args,
+            "sender": ctx.sender.name(),
+            "uuid": ctx.sender.uuid(),
+        })
+        .to_string();
+
+        let (result, replies) = {
+            let mut guest = self.guest.lock();
+            let result = guest.call_with_string::<i32>("on_command", &input);
+            (result, std::mem::take(&mut guest.store.data_mut().replies))
+        };
+        for reply in &replies {
+            ctx.reply(reply);
+        }
+        match result {
+            Ok(code) if code >= 0 => Ok(code),
+            Ok(_) => Err(CommandError::Failed(format!("/{} failed", self.name))),
+            Err(e) => Err(CommandError::Failed(format!(
+                "/{} failed: {:#}",
+                self.name, e
+            ))),
+        }
+    }
+}
+
+/// Read a UTF-8 string out of guest memory
+fn read_string(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> wasmtime::Result<String> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmtime::Error::msg("guest exports no memory"))?;
+    let start = usize::try_from(ptr)?;
+    let end = start
+        .checked_add(usize::try_from(len)?)
+        .ok_or_else(|| wasmtime::Error::msg("string out of bounds"))?;
+    let bytes = memory
+        .data(&caller)
+        .get(start..end)
+        .ok_or_else(|| wasmtime::Error::msg("string out of bounds"))?;
+    Ok(std::str::from_utf8(bytes)?.to_string())
+}
+
+/// Add the `obsidium` imports to a linker
+fn link_host_functions(linker: &mut Linker<HostState>) -> wasmtime::Result<()> {
+    linker.func_wrap(
+        HOST_MODULE,
+        "log",
+        |mut caller: Caller<'_, HostState>, level: i32, ptr: i32, len: i32| {
+            let message = read_string(&mut caller, ptr, len)?;
+            let name = &caller.data().name;
+            match level {
+                0 => tracing::error!("[{}] {}", name, message),
+                1 => tracing::warn!("[{}] {}", name, message),
+                2 => tracing::info!("[{}] {}", name, message),
+                3 => tracing::debug!("[{}] {}", name, message),
+                _ => tracing::trace!("[{}] {}", name, message),
+            }
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "broadcast",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let message = read_string(&mut caller, ptr, len)?;
+            if let Some(server) = caller.data().server.upgrade() {
+                server.broadcast_message(JsonTextComponent::text(&message));
+            }
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "send_message",
+        |mut caller: Caller<'_, HostState>, uuid_ptr: i32, uuid_len: i32, ptr: i32, len: i32| {
+            let uuid = read_string(&mut caller, uuid_ptr, uuid_len)?;
+            let message = read_string(&mut caller, ptr, len)?;
+            let (Ok(uuid), Some(server)) = (uuid.parse::<McUuid>(), caller.data().server.upgrade())
+            else {
+                return Ok(-1);
+            };
+            server.send_message(&uuid, JsonTextComponent::text(&message));
+            Ok(0)
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "reply",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let message = read_string(&mut caller, ptr, len)?;
+            caller.data_mut().replies.push(message);
+            Ok(())
+        },
+    )?;
+    link_registration_functions(linker)?;
+    link_world_functions(linker)
+}
+
+/// Add the imports that are only usable during `on_enable`
+fn link_registration_functions(linker: &mut Linker<HostState>) -> wasmtime::Result<()> {
+    linker.func_wrap(
+        HOST_MODULE,
+        "subscribe",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let event = read_string(&mut caller, ptr, len)?;
+            let state = caller.data_mut();
+            if !state.enabling {
+                return Ok(-1);
+            }
+            state.subscriptions.push(event);
+            Ok(0)
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "register_command",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let command = read_string(&mut caller, ptr, len)?;
+            let state = caller.data_mut();
+            let valid = !command.is_empty() && !command.contains(char::is_whitespace);
+            if !state.enabling || !valid {
+                return Ok(-1);
+            }
+            state.commands.push(command);
+            Ok(0)
+        },
+    )?;
+    Ok(())
+}
+
+/// Add the imports that edit the world
+fn link_world_functions(linker: &mut Linker<HostState>) -> wasmtime::Result<()> {
+    linker.func_wrap(
+        HOST_MODULE,
+        "set_block",
+        |mut caller: Caller<'_, HostState>, x: i32, y: i32, z: i32, ptr: i32, len: i32| {
+            let block = read_string(&mut caller, ptr, len)?;
+            let state = caller.data();
+            let Some(server) = state.server.upgrade() else {
+                return Ok(-1);
+            };
+            let Some(block_id) = server.blocks.get_block_id(&block) else {
+                return Ok(-1);
+            };
+            server
+                .scheduler
+                .schedule_later(Some(&state.name), 0, move |_, world| {
+                    world.set_block(Position::new(x, y, z), block_id);
+                });
+            Ok(0)
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::sender::RconSender;
+    use crate::config::ServerConfig;
+
+    /// Subscribes to joins, places stone for each one and answers `/hello`
+    const GREETER: &str = r#"
+        (module
+          (import "obsidium" "subscribe" (func $subscribe (param i32 i32) (result i32)))
+          (import "obsidium" "register_command" (func $register (param i32 i32) (result i32)))
+          (import "obsidium" "reply" (func $reply (param i32 i32)))
+          (import "obsidium" "set_block" (func $set_block (param i32 i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (global $heap (mut i32) (i32.const 1024))
+          (data (i32.const 0) "player_join")
+          (data (i32.const 16) "hello")
+          (data (i32.const 32) "Hello!")
+          (data (i32.const 48) "minecraft:stone")
+          (func (export "obsidium_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+            (local.get $ptr))
+          (func (export "on_enable") (result i32)
+            (drop (call $subscribe (i32.const 0) (i32.const 11)))
+            (drop (call $register (i32.const 16) (i32.const 5)))
+            (i32.const 0))
+          (func (export "on_event") (param i32 i32)
+            (drop (call $set_block (i32.const 0) (i32.const 64) (i32.const 0)
+              (i32.const 48) (i32.const 15))))
+          (func (export "on_command") (param i32 i32) (result i32)
+            (call $reply (i32.const 32) (i32.const 6))
+            (i32.const 1)))
+    "#;
+
+    #[tokio::test]
+    async fn test_wasm_plugin() {
+        let server = Arc::new(ServerState::new(ServerConfig::default()));
+        let plugin = WasmPlugin::from_bytes("greeter", GREETER.as_bytes()).unwrap();
+        server.plugins.add(Box::new(plugin)).unwrap();
+        server.plugins.enable_all(&server);
+        assert!(server.plugins.plugins()[0].enabled);
+
+        let sender = RconSender::new();
+        let result = server
+            .commands
+            .execute(&server, &sender, "greeter:hello there")
+            .await;
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(sender.take_output(), "Hello!");
+
+        server.events.publish(&ServerEvent::ServerStarted);
+        assert_eq!(server.scheduler.pending_count(), 0);
+        server.events.publish(&ServerEvent::PlayerJoin {
+            username: "Steve".to_string(),
+            uuid: McUuid::nil(),
+        });
+        assert_eq!(server.scheduler.pending_count(), 1);
+
+        server.plugins.disable_all(&server);
+        assert!(server.commands.get("greeter:hello").is_none());
+        assert_eq!(server.scheduler.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_wasm_sandbox() {
+        let server = Arc::new(ServerState::new(ServerConfig::default()));
+
+        // A guest that never returns runs out of fuel
+        let spinner = r#"
+            (module
+              (memory (export "memory") 1)
+              (func (export "on_enable") (result i32) (loop (br 0)) (i32.const 0)))
+        "#;
+        let plugin = WasmPlugin::from_bytes("spinner", spinner.as_bytes()).unwrap();
+        server.plugins.add(Box::new(plugin)).unwrap();
+
+        // A guest cannot read host strings outside its memory
+        let reader = r#"
+            (module
+              (import "obsidium" "log" (func $log (param i32 i32 i32)))
+              (memory (export "memory") 1)
+              (func (export "on_enable") (result i32)
+                (call $log (i32.const 2) (i32.const 65530) (i32.const 100))
+                (i32.const 0)))
+        "#;
+        let plugin = WasmPlugin::from_bytes("reader", reader.as_bytes()).unwrap();
+        server.plugins.add(Box::new(plugin)).unwrap();
+
+        server.plugins.enable_all(&server);
+        assert!(server.plugins.plugins().iter().all(|p| !p.enabled));
+
+        // Modules must export their memory and may not import anything else
+        assert!(WasmPlugin::from_bytes("bare", b"(module)").is_err());
+        let wasi = r#"
+            (module
+              (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+              (memory (export "memory") 1))
+        "#;
+        assert!(WasmPlugin::from_bytes("wasi", wasi.as_bytes()).is_err());
+    }
+}
@@ -23,20 +23,49 @@ impl Compression {
     /// Create a new compression instance
     pub fn new(threshold: u32) -> Self {
         Self {
-            compressor: Compress::new(FlateCompression::default(), false),
-            decompressor: Decompress::new(false),
+            compressor: Compress::new(FlateCompression::default(), true),
+            decompressor: Decompress::new(true),
             threshold,
         }
     }
 
     /// Compress packet data if it exceeds the threshold
+    ///
+    /// Returns the Data Length field followed by the (possibly compressed)
+    /// packet ID and data.
     pub fn compress_packet(&mut self, packet_id: VarInt, data: &[u8]) -> Result<Vec<u8>> {
-        // Calculate uncompressed length (packet ID + data)
-        let mut uncompressed_data = Vec::new();
-        packet_id.write(&mut uncompressed_data)?;
-        uncompressed_data.extend_from_slice(data);
+        let mut compressed = Vec::new();
+        let mut result = Vec::new();
+        match self.compress_into(packet_id, data, &mut compressed)? {
+            Some(uncompressed_length) => {
+                VarInt(uncompressed_length as i32).write(&mut result)?;
+                result.extend_from_slice(&compressed);
+            }
+            None => {
+                // Data Length (0 for uncompressed), then Packet ID + Data
+                VarInt(0).write(&mut result)?;
+                packet_id.write(&mut result)?;
+                result.extend_from_slice(data);
+            }
+        }
+        Ok(result)
+    }
 
-        let uncompressed_length = uncompressed_data.len();
+    /// Compress a packet ID and data into `out` if they reach the threshold
+    ///
+    /// Returns the uncompressed length to send as Data Length, or `None`
+    /// if the packet is below the threshold and must be sent uncompressed
+    /// with a Data Length of 0, in which case `out` is left empty. The ID
+    /// and data are streamed into the compressor without being joined
+    /// first, so `out` is the only copy made.
+    pub fn compress_into(
+        &mut self,
+        packet_id: VarInt,
+        data: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        out.clear();
+        let uncompressed_length = packet_id.len() + data.len();
 
         // Validate uncompressed length against protocol limits
         if uncompressed_length > crate::protocol::MAX_UNCOMPRESSED_PACKET_SIZE {
@@ -49,85 +78,53 @@ impl Compression {
 
         // If below threshold, send uncompressed
         if uncompressed_length < self.threshold as usize {
-            let mut result = Vec::new();
-
-            // Data Length (0 for uncompressed)
-            VarInt(0).write(&mut result)?;
-
-            // Packet ID + Data
-            result.extend_from_slice(&uncompressed_data);
-
-            return Ok(result);
+            return Ok(None);
         }
 
-        // Compress the data
-        let mut compressed_data = Vec::with_capacity(uncompressed_length);
+        let mut id = Vec::with_capacity(VarInt::MAX_SIZE);
+        packet_id.write(&mut id)?;
 
         self.compressor.reset();
-
-        let mut input_pos = 0;
-        let mut output_pos = 0;
-
-        loop {
-            let old_input_pos = self.compressor.total_in() as usize;
-            let old_output_pos = self.compressor.total_out() as usize;
-
-            // Ensure we have space in the output buffer
-            if compressed_data.len() < output_pos + 1024 {
-                compressed_data.resize(output_pos + 1024, 0);
-            }
-
-            let status = self.compressor.compress(
-                &uncompressed_data[input_pos..],
-                &mut compressed_data[output_pos..],
-                if input_pos == uncompressed_length {
-                    FlushCompress::Finish
-                } else {
-                    FlushCompress::None
-                },
-            )?;
-
-            let new_input_pos = self.compressor.total_in() as usize;
-            let new_output_pos = self.compressor.total_out() as usize;
-
-            input_pos += new_input_pos - old_input_pos;
-            output_pos += new_output_pos - old_output_pos;
-
-            match status {
-                Status::Ok => {
-                    if input_pos >= uncompressed_length {
-                        break;
-                    }
-                }
-                Status::StreamEnd => break,
-                Status::BufError => {
-                    // Need more output space
-                    compressed_data.resize(compressed_data.len() + 1024, 0);
-                }
-            }
-        }
-
-        compressed_data.truncate(output_pos);
-
-        // Build final packet
-        let mut result = Vec::new();
-
-        // Data Length (uncompressed length)
-        VarInt(uncompressed_length as i32).write(&mut result)?;
-
-        // Compressed data
-        result.extend_from_slice(&compressed_data);
+        self.deflate(&id, out, FlushCompress::None)?;
+        // All input is available up front, so finish the stream in one go
+        self.deflate(data, out, FlushCompress::Finish)?;
 
         // Validate final packet size
-        if result.len() > crate::protocol::MAX_PACKET_SIZE {
+        let packet_size = VarInt(uncompressed_length as i32).len() + out.len();
+        if packet_size > crate::protocol::MAX_PACKET_SIZE {
             return Err(ServerError::Protocol(format!(
                 "Compressed packet too large: {} > {}",
-                result.len(),
+                packet_size,
                 crate::protocol::MAX_PACKET_SIZE
             )));
         }
 
-        Ok(result)
+        Ok(Some(uncompressed_length))
+    }
+
+    /// Feed `input` to the compressor, appending its output to `out`
+    fn deflate(&mut self, input: &[u8], out: &mut Vec<u8>, flush: FlushCompress) -> Result<()> {
+        let mut consumed = 0;
+        loop {
+            // Ensure we have space in the output buffer
+            if out.capacity() - out.len() < 1024 {
+                out.reserve((input.len() - consumed) / 2 + 1024);
+            }
+
+            let total_in = self.compressor.total_in();
+            let status = self
+                .compressor
+                .compress_vec(&input[consumed..], out, flush)?;
+            consumed += (self.compressor.total_in() - total_in) as usize;
+
+            let done = match flush {
+                FlushCompress::Finish => status == Status::StreamEnd,
+                _ => consumed == input.len(),
+            };
+            if done {
+                return Ok(());
+            }
+        }
     }
 
     /// Decompress packet data
@@ -183,7 +180,7 @@ impl Compression {
 
         let mut uncompressed_data = vec![0u8; uncompressed_length];
 
-        self.decompressor.reset(false);
+        self.decompressor.reset(true);
 
         let mut input_pos = 0;
         let mut output_pos = 0;
@@ -226,12 +223,13 @@ impl Compression {
             )));
         }
 
-        // Parse packet ID from decompressed data
+        // Parse packet ID from decompressed data, then drop it in place
         let mut uncompressed_cursor = std::io::Cursor::new(&uncompressed_data);
         let packet_id = VarInt::read(&mut uncompressed_cursor)?;
-        let remaining_data = uncompressed_data[uncompressed_cursor.position() as usize..].to_vec();
+        let id_length = uncompressed_cursor.position() as usize;
+        uncompressed_data.drain(..id_length);
 
-        Ok((packet_id, remaining_data))
+        Ok((packet_id, uncompressed_data))
     }
 
     /// Update compression threshold
@@ -262,4 +260,20 @@ mod tests {
         assert_eq!(packet_id, decoded_id);
         assert_eq!(data, decoded_data);
     }
+
+    #[test]
+    fn test_compression_round_trip() {
+        let mut compression = Compression::new(64);
+        let packet_id = VarInt(0x27);
+        let data: Vec<u8> = (0..4096u32).map(|i| (i % 7) as u8).collect();
+
+        let compressed = compression.compress_packet(packet_id, &data).unwrap();
+        assert!(compressed.len() < data.len());
+        // Vanilla uses zlib streams, which start with a 0x78 header byte
+        assert_eq!(compressed[VarInt(4097).len()], 0x78);
+
+        let (decoded_id, decoded_data) = compression.decompress_packet(&compressed).unwrap();
+        assert_eq!(packet_id, decoded_id);
+        assert_eq!(data, decoded_data);
+    }
 }
@@ -0,0 +1,190 @@
+//! Packet framing
+//!
+//! Every packet is sent as a frame: its length as a VarInt, then the packet
+//! ID and data. Once compression is enabled, the length is followed by the
+//! uncompressed length and the zlib-compressed ID and data, or by a zero
+//! and the plain ID and data for packets below the threshold.
+//!
+//! [`EncodedPacket`] holds a complete frame, so a packet sent to many
+//! players (such as a chunk) is serialized and compressed only once.
+//! Compressing a large packet takes long enough to stall the other
+//! connections served by the same runtime thread, so
+//! [`encode_offloaded`] moves packets of at least [`OFFLOAD_THRESHOLD`]
+//! bytes to the blocking thread pool.
+
+use crate::error::{Result, ServerError};
+use crate::protocol::packets::RawPacket;
+use crate::protocol::types::VarInt;
+use crate::protocol::{Compression, MAX_PACKET_SIZE};
+
+/// Size from which packets are compressed on the blocking thread pool
+pub const OFFLOAD_THRESHOLD: usize = 32 * 1024;
+
+/// Write the header of a packet's frame
+///
+/// Packets reaching the compression threshold are compressed into
+/// `compressed`. Returns whether the frame continues with `compressed`
+/// rather than with `data`.
+pub fn write_frame_header(
+    compression: Option<&mut Compression>,
+    id: i32,
+    data: &[u8],
+    header: &mut Vec<u8>,
+    compressed: &mut Vec<u8>,
+) -> Result<bool> {
+    let packet_id = VarInt(id);
+    match compression {
+        Some(compression) => match compression.compress_into(packet_id, data, compressed)? {
+            // Packet Length, Data Length, then the compressed ID and data
+            Some(uncompressed_length) => {
+                let data_length = VarInt(uncompressed_length as i32);
+                let length = data_length.len() + compressed.len();
+                VarInt(length as i32).write(header)?;
+                data_length.write(header)?;
+                Ok(true)
+            }
+            // Below the threshold: a Data Length of 0, then ID and data
+            None => {
+                let length = 1 + packet_id.len() + data.len();
+                VarInt(length as i32).write(header)?;
+                VarInt(0).write(header)?;
+                packet_id.write(header)?;
+                Ok(false)
+            }
+        },
+        None => {
+            let length = packet_id.len() + data.len();
+            if length > MAX_PACKET_SIZE {
+                return Err(ServerError::Protocol(format!(
+                    "Packet too large: {} > {}",
+                    length, MAX_PACKET_SIZE
+                )));
+            }
+            VarInt(length as i32).write(header)?;
+            packet_id.write(header)?;
+            Ok(false)
+        }
+    }
+}
+
+/// A packet framed for connections with a given compression threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedPacket {
+    /// Packet ID
+    id: i32,
+    /// Compression threshold the frame was built for
+    compression_threshold: Option<u32>,
+    /// The complete frame
+    frame: Vec<u8>,
+}
+
+impl EncodedPacket {
+    /// Frame a packet for connections with the given compression threshold
+    pub fn encode(packet: &RawPacket, compression_threshold: Option<u32>) -> Result<Self> {
+        let mut compression = compression_threshold.map(Compression::new);
+        let mut frame = Vec::new();
+        let mut compressed = Vec::new();
+        let is_compressed = write_frame_header(
+            compression.as_mut(),
+            packet.id,
+            &packet.data,
+            &mut frame,
+            &mut compressed,
+        )?;
+        if is_compressed {
+            frame.extend_from_slice(&compressed);
+        } else {
+            frame.extend_from_slice(&packet.data);
+        }
+        Ok(Self {
+            id: packet.id,
+            compression_threshold,
+            frame,
+        })
+    }
+
+    /// Packet ID
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// Compression threshold the frame was built for
+    pub fn compression_threshold(&self) -> Option<u32> {
+        self.compression_threshold
+    }
+
+    /// The complete frame, ready to be written to a connection
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.frame
+    }
+
+    /// Recover the packet from the frame
+    pub fn decode(&self) -> Result<RawPacket> {
+        let mut cursor = std::io::Cursor::new(self.frame.as_slice());
+        VarInt::read(&mut cursor)?;
+        let body = &self.frame[cursor.position() as usize..];
+        let (id, data) = match self.compression_threshold {
+            Some(threshold) => Compression::new(threshold).decompress_packet(body)?,
+            None => {
+                let mut cursor = std::io::Cursor::new(body);
+                let id = VarInt::read(&mut cursor)?;
+                (id, body[cursor.position() as usize..].to_vec())
+            }
+        };
+        Ok(RawPacket { id: id.0, data })
+    }
+}
+
+/// Frame a packet, compressing large ones on the blocking thread pool
+pub async fn encode_offloaded(
+    packet: RawPacket,
+    compression_threshold: Option<u32>,
+) -> Result<EncodedPacket> {
+    if compression_threshold.is_none() || packet.data.len() < OFFLOAD_THRESHOLD {
+        return EncodedPacket::encode(&packet, compression_threshold);
+    }
+    tokio::task::spawn_blocking(move || EncodedPacket::encode(&packet, compression_threshold))
+        .await
+        .map_err(|e| ServerError::Io(std::io::Error::other(e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoded_packet() {
+        let small = RawPacket {
+            id: 0x26,
+            data: vec![1, 2, 3],
+        };
+        let large = RawPacket {
+            id: 0x27,
+            data: vec![7; 4096],
+        };
+        for threshold in [None, Some(256)] {
+            for packet in [&small, &large] {
+                let encoded = EncodedPacket::encode(packet, threshold).unwrap();
+                assert_eq!(encoded.id(), packet.id);
+                assert_eq!(encoded.decode().unwrap(), *packet);
+            }
+        }
+
+        let compressed = EncodedPacket::encode(&large, Some(256)).unwrap();
+        assert!(compressed.as_bytes().len() < large.data.len());
+    }
+
+    #[tokio::test]
+    async fn test_encode_offloaded() {
+        let packet = RawPacket {
+            id: 0x27,
+            data: (0..OFFLOAD_THRESHOLD as u32 * 2).map(|i| i as u8).collect(),
+        };
+        let offloaded = encode_offloaded(packet.clone(), Some(256)).await.unwrap();
+        assert_eq!(
+            offloaded,
+            EncodedPacket::encode(&packet, Some(256)).unwrap()
+        );
+        assert_eq!(offloaded.decode().unwrap(), packet);
+    }
+}
@@ -0,0 +1,307 @@
+//! Entity metadata
+//!
+//! Entity metadata is a list of indexed, typed values describing entity
+//! state such as pose, custom name or flags. It is sent in the Set Entity
+//! Metadata packet.
+
+use crate::error::{Result, ServerError};
+use crate::game::inventory::ItemStack;
+use crate::protocol::packets::play::{read_slot, write_slot};
+use crate::protocol::types::{
+    JsonTextComponent, McString, Position, VarInt, read_bool, write_bool,
+};
+use std::io::{Read, Write};
+
+/// Marks the end of a metadata list
+pub const METADATA_END: u8 = 0xFF;
+
+/// Well-known metadata indices
+pub mod index {
+    /// Entity flags (on fire, crouching, invisible, ...)
+    pub const FLAGS: u8 = 0;
+    /// Custom name shown above the entity
+    pub const CUSTOM_NAME: u8 = 2;
+    /// Whether the custom name shows at any distance
+    pub const CUSTOM_NAME_VISIBLE: u8 = 3;
+    /// Entity pose
+    pub const POSE: u8 = 6;
+    /// Item shown in an item frame
+    pub const ITEM_FRAME_ITEM: u8 = 8;
+    /// Eighths of a turn the item in an item frame is rotated by
+    pub const ITEM_FRAME_ROTATION: u8 = 9;
+    /// Location of the bed a living entity is sleeping in
+    pub const SLEEPING_POSITION: u8 = 14;
+    /// Armor stand flags (small, arms, no base plate)
+    pub const ARMOR_STAND_FLAGS: u8 = 15;
+    /// Armor stand head rotation, followed by the body, left arm, right
+    /// arm, left leg and right leg rotations
+    pub const ARMOR_STAND_HEAD: u8 = 16;
+    /// Whether an ageable mob is a baby
+    pub const BABY: u8 = 16;
+    /// Skin layers a player shows
+    pub const DISPLAYED_SKIN_PARTS: u8 = 17;
+    /// Main hand of a player
+    pub const MAIN_HAND: u8 = 18;
+}
+
+/// Bits of the entity flags at [`index::FLAGS`]
+pub mod flags {
+    /// On fire
+    pub const ON_FIRE: i8 = 0x01;
+    /// Crouching
+    pub const CROUCHING: i8 = 0x02;
+    /// Sprinting
+    pub const SPRINTING: i8 = 0x08;
+    /// Swimming
+    pub const SWIMMING: i8 = 0x10;
+    /// Invisible
+    pub const INVISIBLE: i8 = 0x20;
+    /// Glowing
+    pub const GLOWING: i8 = 0x40;
+}
+
+/// Bits of the armor stand flags at [`index::ARMOR_STAND_FLAGS`]
+pub mod armor_stand_flags {
+    /// Half size
+    pub const SMALL: i8 = 0x01;
+    /// Has arms
+    pub const ARMS: i8 = 0x04;
+    /// Has no base plate
+    pub const NO_BASE_PLATE: i8 = 0x08;
+}
+
+/// Entity pose
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pose {
+    /// Standing
+    #[default]
+    Standing = 0,
+    /// Elytra flying
+    FallFlying = 1,
+    /// Sleeping in a bed
+    Sleeping = 2,
+    /// Swimming
+    Swimming = 3,
+    /// Riptide spin attack
+    SpinAttack = 4,
+    /// Crouching
+    Sneaking = 5,
+    /// Long jumping (goats)
+    LongJumping = 6,
+    /// Dying
+    Dying = 7,
+}
+
+impl Pose {
+    /// Get a pose from its protocol ID
+    pub fn from_id(id: i32) -> Option<Self> {
+        Some(match id {
+            0 => Pose::Standing,
+            1 => Pose::FallFlying,
+            2 => Pose::Sleeping,
+            3 => Pose::Swimming,
+            4 => Pose::SpinAttack,
+            5 => Pose::Sneaking,
+            6 => Pose::LongJumping,
+            7 => Pose::Dying,
+            _ => return None,
+        })
+    }
+}
+
+/// A typed metadata value
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    /// Signed byte (type 0)
+    Byte(i8),
+    /// VarInt (type 1)
+    VarInt(i32),
+    /// Float (type 3)
+    Float(f32),
+    /// String (type 4)
+    String(String),
+    /// Optional text component (type 6)
+    OptionalTextComponent(Option<JsonTextComponent>),
+    /// Item slot (type 7)
+    Slot(Option<ItemStack>),
+    /// Boolean (type 8)
+    Boolean(bool),
+    /// Rotations around the X, Y and Z axes in degrees (type 9)
+    Rotations([f32; 3]),
+    /// Optional block position (type 11)
+    OptionalPosition(Option<Position>),
+    /// Pose (type 21)
+    Pose(Pose),
+}
+
+impl MetadataValue {
+    /// Get the protocol type ID of this value
+    pub fn type_id(&self) -> i32 {
+        match self {
+            MetadataValue::Byte(_) => 0,
+            MetadataValue::VarInt(_) => 1,
+            MetadataValue::Float(_) => 3,
+            MetadataValue::String(_) => 4,
+            MetadataValue::OptionalTextComponent(_) => 6,
+            MetadataValue::Slot(_) => 7,
+            MetadataValue::Boolean(_) => 8,
+            MetadataValue::Rotations(_) => 9,
+            MetadataValue::OptionalPosition(_) => 11,
+            MetadataValue::Pose(_) => 21,
+        }
+    }
+
+    /// Read a value of the given type
+    pub fn read<R: Read>(type_id: i32, reader: &mut R) -> Result<Self> {
+        Ok(match type_id {
+            0 => MetadataValue::Byte(crate::protocol::types::read_unsigned_byte(reader)? as i8),
+            1 => MetadataValue::VarInt(VarInt::read(reader)?.0),
+            3 => {
+                let mut bytes = [0u8; 4];
+                reader.read_exact(&mut bytes)?;
+                MetadataValue::Float(f32::from_be_bytes(bytes))
+            }
+            4 => MetadataValue::String(McString::read(reader)?.0),
+            6 => MetadataValue::OptionalTextComponent(if read_bool(reader)? {
+                Some(JsonTextComponent::read_nbt(reader)?)
+            } else {
+                None
+            }),
+            7 => MetadataValue::Slot(read_slot(reader)?),
+            8 => MetadataValue::Boolean(read_bool(reader)?),
+            9 => {
+                let mut rotations = [0.0; 3];
+                for rotation in &mut rotations {
+                    let mut bytes = [0u8; 4];
+                    reader.read_exact(&mut bytes)?;
+                    *rotation = f32::from_be_bytes(bytes);
+                }
+                MetadataValue::Rotations(rotations)
+            }
+            11 => MetadataValue::OptionalPosition(if read_bool(reader)? {
+                Some(Position::read(reader)?)
+            } else {
+                None
+            }),
+            21 => {
+                let id = VarInt::read(reader)?.0;
+                MetadataValue::Pose(
+                    Pose::from_id(id)
+                        .ok_or_else(|| ServerError::Protocol(format!("Invalid pose: {}", id)))?,
+                )
+            }
+            other => {
+                return Err(ServerError::Protocol(format!(
+                    "Unsupported metadata type: {}",
+                    other
+                )));
+            }
+        })
+    }
+
+    /// Write the value (without its type ID)
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            MetadataValue::Byte(value) => {
+                crate::protocol::types::write_unsigned_byte(*value as u8, writer)?
+            }
+            MetadataValue::VarInt(value) => VarInt(*value).write(writer)?,
+            MetadataValue::Float(value) => writer.write_all(&value.to_be_bytes())?,
+            MetadataValue::String(value) => McString(value.clone()).write(writer)?,
+            MetadataValue::OptionalTextComponent(component) => {
+                write_bool(component.is_some(), writer)?;
+                if let Some(component) = component {
+                    component.write_nbt(writer)?;
+                }
+            }
+            MetadataValue::Slot(item) => write_slot(item.as_ref(), writer)?,
+            MetadataValue::Boolean(value) => write_bool(*value, writer)?,
+            MetadataValue::Rotations(rotations) => {
+                for rotation in rotations {
+                    writer.write_all(&rotation.to_be_bytes())?;
+                }
+            }
+            MetadataValue::OptionalPosition(position) => {
+                write_bool(position.is_some(), writer)?;
+                if let Some(position) = position {
+                    position.write(writer)?;
+                }
+            }
+            MetadataValue::Pose(pose) => VarInt(*pose as i32).write(writer)?,
+        }
+        Ok(())
+    }
+}
+
+/// A single indexed metadata entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataEntry {
+    /// Metadata index
+    pub index: u8,
+    /// Value at the index
+    pub value: MetadataValue,
+}
+
+impl MetadataEntry {
+    /// Create a metadata entry
+    pub fn new(index: u8, value: MetadataValue) -> Self {
+        Self { index, value }
+    }
+}
+
+/// Read a metadata list terminated by [`METADATA_END`]
+pub fn read_metadata<R: Read>(reader: &mut R) -> Result<Vec<MetadataEntry>> {
+    let mut entries = Vec::new();
+    loop {
+        let index = crate::protocol::types::read_unsigned_byte(reader)?;
+        if index == METADATA_END {
+            return Ok(entries);
+        }
+        let type_id = VarInt::read(reader)?.0;
+        entries.push(MetadataEntry::new(
+            index,
+            MetadataValue::read(type_id, reader)?,
+        ));
+    }
+}
+
+/// Write a metadata list followed by [`METADATA_END`]
+pub fn write_metadata<W: Write>(entries: &[MetadataEntry], writer: &mut W) -> Result<()> {
+    for entry in entries {
+        crate::protocol::types::write_unsigned_byte(entry.index, writer)?;
+        VarInt(entry.value.type_id()).write(writer)?;
+        entry.value.write(writer)?;
+    }
+    crate::protocol::types::write_unsigned_byte(METADATA_END, writer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        let entries = vec![
+            MetadataEntry::new(index::POSE, MetadataValue::Pose(Pose::Sleeping)),
+            MetadataEntry::new(
+                index::SLEEPING_POSITION,
+                MetadataValue::OptionalPosition(Some(Position::new(1, 64, -3))),
+            ),
+            MetadataEntry::new(index::FLAGS, MetadataValue::Byte(0x02)),
+            MetadataEntry::new(
+                index::CUSTOM_NAME,
+                MetadataValue::OptionalTextComponent(Some(JsonTextComponent::text("Grumm"))),
+            ),
+        ];
+
+        let mut buffer = Vec::new();
+        write_metadata(&entries, &mut buffer).unwrap();
+        assert_eq!(&buffer[..3], &[index::POSE, 21, 2]);
+        assert_eq!(buffer.last(), Some(&METADATA_END));
+
+        let decoded = read_metadata(&mut Cursor::new(buffer)).unwrap();
+        assert_eq!(decoded, entries);
+    }
+}
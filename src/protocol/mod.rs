@@ -20,8 +20,14 @@
 //! - Data - Packet-specific data
 
 pub mod compression;
+pub mod encryption;
+pub mod frame;
+pub mod metadata;
+pub mod nbt;
 pub mod packets;
+pub mod rsa;
 pub mod state;
+pub mod text;
 pub mod types;
 
 pub use compression::Compression;
@@ -34,6 +40,14 @@ pub const MINECRAFT_VERSION: &str = "1.21.6";
 /// Protocol version constant
 pub const PROTOCOL_VERSION: i32 = 771;
 
+/// Protocol versions clients may log in with
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[i32] = &[PROTOCOL_VERSION];
+
+/// Whether clients speaking a protocol version may log in
+pub fn is_supported_protocol(version: i32) -> bool {
+    SUPPORTED_PROTOCOL_VERSIONS.contains(&version)
+}
+
 /// Maximum packet size (2^21 - 1 bytes)
 pub const MAX_PACKET_SIZE: usize = 2_097_151;
 
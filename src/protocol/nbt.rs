@@ -0,0 +1,746 @@
+//! Named Binary Tag (NBT) format
+//!
+//! NBT is the binary format Minecraft uses for world saves (level.dat,
+//! region files) and for some protocol fields such as text components.
+//!
+//! Files store a named root compound, while the network format used since
+//! 1.20.2 omits the root name.
+
+use crate::error::{Result, ServerError};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+/// A compound tag's contents
+pub type Compound = BTreeMap<String, Tag>;
+
+/// Maximum nesting depth accepted when reading
+const MAX_DEPTH: usize = 512;
+
+/// Tag type IDs
+mod id {
+    pub const END: u8 = 0;
+    pub const BYTE: u8 = 1;
+    pub const SHORT: u8 = 2;
+    pub const INT: u8 = 3;
+    pub const LONG: u8 = 4;
+    pub const FLOAT: u8 = 5;
+    pub const DOUBLE: u8 = 6;
+    pub const BYTE_ARRAY: u8 = 7;
+    pub const STRING: u8 = 8;
+    pub const LIST: u8 = 9;
+    pub const COMPOUND: u8 = 10;
+    pub const INT_ARRAY: u8 = 11;
+    pub const LONG_ARRAY: u8 = 12;
+}
+
+/// An NBT value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    /// Signed byte (also used for booleans)
+    Byte(i8),
+    /// Signed 16-bit integer
+    Short(i16),
+    /// Signed 32-bit integer
+    Int(i32),
+    /// Signed 64-bit integer
+    Long(i64),
+    /// 32-bit float
+    Float(f32),
+    /// 64-bit float
+    Double(f64),
+    /// Array of bytes
+    ByteArray(Vec<i8>),
+    /// UTF-8 string
+    String(String),
+    /// List of tags that all have the same type
+    List(Vec<Tag>),
+    /// Named tags
+    Compound(Compound),
+    /// Array of 32-bit integers
+    IntArray(Vec<i32>),
+    /// Array of 64-bit integers
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    /// Get the type ID of this tag
+    pub fn type_id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => id::BYTE,
+            Tag::Short(_) => id::SHORT,
+            Tag::Int(_) => id::INT,
+            Tag::Long(_) => id::LONG,
+            Tag::Float(_) => id::FLOAT,
+            Tag::Double(_) => id::DOUBLE,
+            Tag::ByteArray(_) => id::BYTE_ARRAY,
+            Tag::String(_) => id::STRING,
+            Tag::List(_) => id::LIST,
+            Tag::Compound(_) => id::COMPOUND,
+            Tag::IntArray(_) => id::INT_ARRAY,
+            Tag::LongArray(_) => id::LONG_ARRAY,
+        }
+    }
+
+    /// Get any integer tag as an `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(v) => Some(v as i64),
+            Tag::Short(v) => Some(v as i64),
+            Tag::Int(v) => Some(v as i64),
+            Tag::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get any numeric tag as an `f64`
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Tag::Float(v) => Some(v as f64),
+            Tag::Double(v) => Some(v),
+            _ => self.as_i64().map(|v| v as f64),
+        }
+    }
+
+    /// Get a byte tag as a boolean
+    pub fn as_bool(&self) -> Option<bool> {
+        self.as_i64().map(|v| v != 0)
+    }
+
+    /// Get a string tag
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get a list tag
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get a compound tag
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get an int array tag
+    pub fn as_int_array(&self) -> Option<&[i32]> {
+        match self {
+            Tag::IntArray(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get a long array tag
+    pub fn as_long_array(&self) -> Option<&[i64]> {
+        match self {
+            Tag::LongArray(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Convert a JSON value to NBT, the way text components are sent
+    ///
+    /// Returns `None` for `null`. Lists mixing element types wrap each
+    /// non-compound element in a compound with an empty key, as vanilla does.
+    pub fn from_json(value: &serde_json::Value) -> Option<Tag> {
+        use serde_json::Value;
+
+        Some(match value {
+            Value::Null => return None,
+            Value::Bool(b) => Tag::from(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(n) => i32::try_from(n).map_or(Tag::Long(n), Tag::Int),
+                None => Tag::Double(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => Tag::String(s.clone()),
+            Value::Array(values) => {
+                let tags: Vec<Tag> = values.iter().filter_map(Tag::from_json).collect();
+                let mixed = tags.windows(2).any(|w| w[0].type_id() != w[1].type_id());
+                if !mixed {
+                    return Some(Tag::List(tags));
+                }
+                Tag::List(
+                    tags.into_iter()
+                        .map(|tag| match tag {
+                            Tag::Compound(_) => tag,
+                            tag => Tag::Compound(Compound::from([(String::new(), tag)])),
+                        })
+                        .collect(),
+                )
+            }
+            Value::Object(map) => Tag::Compound(
+                map.iter()
+                    .filter_map(|(key, value)| Some((key.clone(), Tag::from_json(value)?)))
+                    .collect(),
+            ),
+        })
+    }
+
+    /// Convert this tag to JSON, reversing [`Tag::from_json`]
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value;
+
+        match self {
+            Tag::Byte(v) => Value::from(*v),
+            Tag::Short(v) => Value::from(*v),
+            Tag::Int(v) => Value::from(*v),
+            Tag::Long(v) => Value::from(*v),
+            Tag::Float(v) => Value::from(*v),
+            Tag::Double(v) => Value::from(*v),
+            Tag::String(s) => Value::String(s.clone()),
+            Tag::ByteArray(v) => Value::from(v.clone()),
+            Tag::IntArray(v) => Value::from(v.clone()),
+            Tag::LongArray(v) => Value::from(v.clone()),
+            Tag::List(tags) => Value::Array(tags.iter().map(Tag::to_json).collect()),
+            Tag::Compound(compound) => match compound.get("") {
+                Some(tag) if compound.len() == 1 => tag.to_json(),
+                _ => Value::Object(
+                    compound
+                        .iter()
+                        .map(|(key, tag)| (key.clone(), tag.to_json()))
+                        .collect(),
+                ),
+            },
+        }
+    }
+
+    /// Read the payload of a tag with the given type ID
+    fn read_payload<R: Read>(type_id: u8, reader: &mut R, depth: usize) -> Result<Self> {
+        if depth > MAX_DEPTH {
+            return Err(ServerError::Storage("NBT nested too deeply".to_string()));
+        }
+
+        Ok(match type_id {
+            id::BYTE => Tag::Byte(read_array::<1, R>(reader)?[0] as i8),
+            id::SHORT => Tag::Short(i16::from_be_bytes(read_array(reader)?)),
+            id::INT => Tag::Int(read_i32(reader)?),
+            id::LONG => Tag::Long(i64::from_be_bytes(read_array(reader)?)),
+            id::FLOAT => Tag::Float(f32::from_be_bytes(read_array(reader)?)),
+            id::DOUBLE => Tag::Double(f64::from_be_bytes(read_array(reader)?)),
+            id::BYTE_ARRAY => {
+                let length = read_length(reader)?;
+                // Read through `take` so a forged length cannot allocate up front
+                let mut bytes = Vec::new();
+                reader
+                    .by_ref()
+                    .take(length as u64)
+                    .read_to_end(&mut bytes)?;
+                if bytes.len() != length {
+                    return Err(ServerError::Storage("Truncated NBT byte array".to_string()));
+                }
+                Tag::ByteArray(bytes.into_iter().map(|b| b as i8).collect())
+            }
+            id::STRING => Tag::String(read_string(reader)?),
+            id::LIST => {
+                let element_type = read_array::<1, R>(reader)?[0];
+                let length = read_length(reader)?;
+                let mut items = Vec::with_capacity(length.min(4096));
+                for _ in 0..length {
+                    items.push(Self::read_payload(element_type, reader, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            id::COMPOUND => Tag::Compound(read_compound_payload(reader, depth + 1)?),
+            id::INT_ARRAY => {
+                let length = read_length(reader)?;
+                let mut values = Vec::with_capacity(length.min(4096));
+                for _ in 0..length {
+                    values.push(read_i32(reader)?);
+                }
+                Tag::IntArray(values)
+            }
+            id::LONG_ARRAY => {
+                let length = read_length(reader)?;
+                let mut values = Vec::with_capacity(length.min(4096));
+                for _ in 0..length {
+                    values.push(i64::from_be_bytes(read_array(reader)?));
+                }
+                Tag::LongArray(values)
+            }
+            other => {
+                return Err(ServerError::Storage(format!(
+                    "Unknown NBT tag type: {}",
+                    other
+                )));
+            }
+        })
+    }
+
+    /// Write the payload of this tag (without type ID or name)
+    fn write_payload<W: Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            Tag::Byte(v) => writer.write_all(&[*v as u8])?,
+            Tag::Short(v) => writer.write_all(&v.to_be_bytes())?,
+            Tag::Int(v) => writer.write_all(&v.to_be_bytes())?,
+            Tag::Long(v) => writer.write_all(&v.to_be_bytes())?,
+            Tag::Float(v) => writer.write_all(&v.to_be_bytes())?,
+            Tag::Double(v) => writer.write_all(&v.to_be_bytes())?,
+            Tag::ByteArray(values) => {
+                writer.write_all(&(values.len() as i32).to_be_bytes())?;
+                let bytes: Vec<u8> = values.iter().map(|b| *b as u8).collect();
+                writer.write_all(&bytes)?;
+            }
+            Tag::String(v) => write_string(v, writer)?,
+            Tag::List(items) => {
+                let element_type = items.first().map_or(id::END, Tag::type_id);
+                if items.iter().any(|item| item.type_id() != element_type) {
+                    return Err(ServerError::Storage(
+                        "NBT list elements must all have the same type".to_string(),
+                    ));
+                }
+                writer.write_all(&[element_type])?;
+                writer.write_all(&(items.len() as i32).to_be_bytes())?;
+                for item in items {
+                    item.write_payload(writer)?;
+                }
+            }
+            Tag::Compound(compound) => write_compound_payload(compound, writer)?,
+            Tag::IntArray(values) => {
+                writer.write_all(&(values.len() as i32).to_be_bytes())?;
+                for v in values {
+                    writer.write_all(&v.to_be_bytes())?;
+                }
+            }
+            Tag::LongArray(values) => {
+                writer.write_all(&(values.len() as i32).to_be_bytes())?;
+                for v in values {
+                    writer.write_all(&v.to_be_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Compound> for Tag {
+    fn from(compound: Compound) -> Self {
+        Tag::Compound(compound)
+    }
+}
+
+impl From<&str> for Tag {
+    fn from(value: &str) -> Self {
+        Tag::String(value.to_string())
+    }
+}
+
+impl From<String> for Tag {
+    fn from(value: String) -> Self {
+        Tag::String(value)
+    }
+}
+
+impl From<bool> for Tag {
+    fn from(value: bool) -> Self {
+        Tag::Byte(value as i8)
+    }
+}
+
+impl From<i32> for Tag {
+    fn from(value: i32) -> Self {
+        Tag::Int(value)
+    }
+}
+
+impl From<i64> for Tag {
+    fn from(value: i64) -> Self {
+        Tag::Long(value)
+    }
+}
+
+/// Read a named root compound (file format)
+pub fn read_named<R: Read>(reader: &mut R) -> Result<(String, Compound)> {
+    let type_id = read_array::<1, R>(reader)?[0];
+    if type_id != id::COMPOUND {
+        return Err(ServerError::Storage(format!(
+            "NBT root must be a compound, found type {}",
+            type_id
+        )));
+    }
+    let name = read_string(reader)?;
+    let compound = read_compound_payload(reader, 0)?;
+    Ok((name, compound))
+}
+
+/// Write a named root compound (file format)
+pub fn write_named<W: Write>(name: &str, compound: &Compound, writer: &mut W) -> Result<()> {
+    writer.write_all(&[id::COMPOUND])?;
+    write_string(name, writer)?;
+    write_compound_payload(compound, writer)
+}
+
+/// Read a nameless root tag (network format)
+pub fn read_network<R: Read>(reader: &mut R) -> Result<Tag> {
+    let type_id = read_array::<1, R>(reader)?[0];
+    Tag::read_payload(type_id, reader, 0)
+}
+
+/// Write a nameless root tag (network format)
+pub fn write_network<W: Write>(tag: &Tag, writer: &mut W) -> Result<()> {
+    writer.write_all(&[tag.type_id()])?;
+    tag.write_payload(writer)
+}
+
+/// Parse stringified NBT, as typed in commands, such as
+/// `{CustomName:'"Bob"',Age:-24000}`
+///
+/// Numbers take their type from a suffix (`b`, `s`, `l`, `f` or `d`), and
+/// are ints without one, or doubles if they have a decimal point. `true`
+/// and `false` are bytes. Strings only need quotes if they contain other
+/// characters than letters, digits and `_-.+`.
+pub fn parse_snbt(input: &str) -> Result<Compound> {
+    let mut parser = SnbtParser { input, position: 0 };
+    let Tag::Compound(compound) = parser.value(0)? else {
+        return Err(ServerError::Storage("SNBT must be a compound".to_string()));
+    };
+    if parser.peek().is_some() {
+        return Err(parser.error("Trailing data"));
+    }
+    Ok(compound)
+}
+
+/// Recursive descent parser for stringified NBT
+struct SnbtParser<'a> {
+    /// The whole input
+    input: &'a str,
+    /// Byte offset of the next character
+    position: usize,
+}
+
+impl SnbtParser<'_> {
+    /// An error at the current position
+    fn error(&self, message: &str) -> ServerError {
+        ServerError::Storage(format!("{} at position {} of SNBT", message, self.position))
+    }
+
+    /// Skip whitespace and look at the next character
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.input[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+        self.input[self.position..].chars().next()
+    }
+
+    /// Consume the next character if it is `expected`
+    fn eat(&mut self, expected: char) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.position += expected.len_utf8();
+        }
+        found
+    }
+
+    /// Consume `expected` or fail
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", expected)))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Tag> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("SNBT nested too deeply"));
+        }
+        match self.peek() {
+            Some('{') => self.compound(depth),
+            Some('[') => self.list(depth),
+            Some('"' | '\'') => self.quoted().map(Tag::String),
+            Some(_) => Ok(scalar(self.unquoted()?)),
+            None => Err(self.error("Unexpected end")),
+        }
+    }
+
+    fn compound(&mut self, depth: usize) -> Result<Tag> {
+        self.expect('{')?;
+        let mut compound = Compound::new();
+        if self.eat('}') {
+            return Ok(Tag::Compound(compound));
+        }
+        loop {
+            let key = match self.peek() {
+                Some('"' | '\'') => self.quoted()?,
+                _ => self.unquoted()?.to_string(),
+            };
+            self.expect(':')?;
+            let value = self.value(depth + 1)?;
+            compound.insert(key, value);
+            if !self.eat(',') {
+                self.expect('}')?;
+                return Ok(Tag::Compound(compound));
+            }
+        }
+    }
+
+    fn list(&mut self, depth: usize) -> Result<Tag> {
+        self.expect('[')?;
+        let rest = self.input[self.position..].trim_start();
+        let array_type = ["B;", "I;", "L;"]
+            .into_iter()
+            .find(|prefix| rest.starts_with(prefix));
+        if let Some(prefix) = array_type {
+            self.peek();
+            self.position += prefix.len();
+        }
+        let mut tags = Vec::new();
+        if !self.eat(']') {
+            loop {
+                tags.push(self.value(depth + 1)?);
+                if !self.eat(',') {
+                    self.expect(']')?;
+                    break;
+                }
+            }
+        }
+        let integers = || {
+            tags.iter()
+                .map(Tag::as_i64)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| self.error("Arrays may only hold integers"))
+        };
+        Ok(match array_type {
+            Some("B;") => Tag::ByteArray(integers()?.into_iter().map(|v| v as i8).collect()),
+            Some("I;") => Tag::IntArray(integers()?.into_iter().map(|v| v as i32).collect()),
+            Some(_) => Tag::LongArray(integers()?),
+            None if tags.windows(2).any(|w| w[0].type_id() != w[1].type_id()) => {
+                return Err(self.error("List elements must all have the same type"));
+            }
+            None => Tag::List(tags),
+        })
+    }
+
+    /// A string in single or double quotes, with backslash escapes
+    fn quoted(&mut self) -> Result<String> {
+        let mut chars = self.input[self.position..].char_indices();
+        let Some((_, quote)) = chars.next() else {
+            return Err(self.error("Unexpected end"));
+        };
+        let mut value = String::new();
+        let mut escaped = false;
+        for (offset, c) in chars {
+            if escaped {
+                value.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                self.position += offset + c.len_utf8();
+                return Ok(value);
+            } else {
+                value.push(c);
+            }
+        }
+        Err(self.error("Unterminated string"))
+    }
+
+    /// A run of letters, digits and `_-.+`
+    fn unquoted(&mut self) -> Result<&str> {
+        self.peek();
+        let rest = &self.input[self.position..];
+        let length = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "_-.+".contains(c)))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("Expected a value"));
+        }
+        self.position += length;
+        Ok(&rest[..length])
+    }
+}
+
+/// Interpret an unquoted SNBT value as a number, boolean or string
+fn scalar(token: &str) -> Tag {
+    match token {
+        "true" => return Tag::Byte(1),
+        "false" => return Tag::Byte(0),
+        _ => {}
+    }
+    let (digits, suffix) = token.split_at(token.len() - 1);
+    let typed = match suffix.to_ascii_lowercase().as_str() {
+        "b" => digits.parse().ok().map(Tag::Byte),
+        "s" => digits.parse().ok().map(Tag::Short),
+        "l" => digits.parse().ok().map(Tag::Long),
+        "f" => digits.parse().ok().map(Tag::Float),
+        "d" => digits.parse().ok().map(Tag::Double),
+        _ => None,
+    };
+    typed
+        .or_else(|| token.parse().ok().map(Tag::Int))
+        .or_else(|| {
+            token
+                .contains('.')
+                .then(|| token.parse().ok().map(Tag::Double))
+                .flatten()
+        })
+        .unwrap_or_else(|| Tag::String(token.to_string()))
+}
+
+/// Read compound entries up to the closing end tag
+fn read_compound_payload<R: Read>(reader: &mut R, depth: usize) -> Result<Compound> {
+    let mut compound = Compound::new();
+    loop {
+        let type_id = read_array::<1, R>(reader)?[0];
+        if type_id == id::END {
+            return Ok(compound);
+        }
+        let name = read_string(reader)?;
+        let tag = Tag::read_payload(type_id, reader, depth)?;
+        compound.insert(name, tag);
+    }
+}
+
+/// Write compound entries followed by an end tag
+fn write_compound_payload<W: Write>(compound: &Compound, writer: &mut W) -> Result<()> {
+    for (name, tag) in compound {
+        writer.write_all(&[tag.type_id()])?;
+        write_string(name, writer)?;
+        tag.write_payload(writer)?;
+    }
+    writer.write_all(&[id::END])?;
+    Ok(())
+}
+
+fn read_array<const N: usize, R: Read>(reader: &mut R) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> Result<i32> {
+    Ok(i32::from_be_bytes(read_array(reader)?))
+}
+
+fn read_length<R: Read>(reader: &mut R) -> Result<usize> {
+    let length = read_i32(reader)?;
+    usize::try_from(length)
+        .map_err(|_| ServerError::Storage(format!("Negative NBT length: {}", length)))
+}
+
+/// Read a length-prefixed string
+///
+/// Java's modified UTF-8 only differs from UTF-8 for NUL and supplementary
+/// characters, which are decoded lossily.
+fn read_string<R: Read>(reader: &mut R) -> Result<String> {
+    let length = u16::from_be_bytes(read_array(reader)?) as usize;
+    let mut bytes = vec![0u8; length];
+    reader.read_exact(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn write_string<W: Write>(value: &str, writer: &mut W) -> Result<()> {
+    let length = u16::try_from(value.len())
+        .map_err(|_| ServerError::Storage("NBT string too long".to_string()))?;
+    writer.write_all(&length.to_be_bytes())?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_named_roundtrip() {
+        let mut inner = Compound::new();
+        inner.insert("name".to_string(), "Bananrama".into());
+        inner.insert("flag".to_string(), true.into());
+
+        let mut root = Compound::new();
+        root.insert("level".to_string(), inner.into());
+        root.insert("time".to_string(), Tag::Long(24_000));
+        root.insert(
+            "list".to_string(),
+            Tag::List(vec![Tag::Int(1), Tag::Int(2)]),
+        );
+        root.insert("longs".to_string(), Tag::LongArray(vec![-1, 0, i64::MAX]));
+        root.insert("bytes".to_string(), Tag::ByteArray(vec![-5, 5]));
+        root.insert("pi".to_string(), Tag::Double(std::f64::consts::PI));
+
+        let mut buffer = Vec::new();
+        write_named("hello world", &root, &mut buffer).unwrap();
+        assert_eq!(&buffer[..3], &[id::COMPOUND, 0, 11]);
+
+        let (name, decoded) = read_named(&mut Cursor::new(buffer)).unwrap();
+        assert_eq!(name, "hello world");
+        assert_eq!(decoded, root);
+        assert_eq!(
+            decoded["level"].as_compound().unwrap()["flag"].as_bool(),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_network_format_and_errors() {
+        let tag = Tag::String("hi".to_string());
+        let mut buffer = Vec::new();
+        write_network(&tag, &mut buffer).unwrap();
+        assert_eq!(buffer, vec![id::STRING, 0, 2, b'h', b'i']);
+        assert_eq!(read_network(&mut Cursor::new(buffer)).unwrap(), tag);
+
+        let mixed = Tag::List(vec![Tag::Int(1), Tag::Byte(1)]);
+        assert!(write_network(&mixed, &mut Vec::new()).is_err());
+        assert!(read_named(&mut Cursor::new(vec![id::INT])).is_err());
+    }
+
+    #[test]
+    fn test_json_conversion() {
+        let json = serde_json::json!({
+            "text": "Kicked",
+            "color": "red",
+            "extra": ["plain", {"text": "!", "bold": 1}],
+            "insertion": null
+        });
+        let tag = Tag::from_json(&json).unwrap();
+        let compound = tag.as_compound().unwrap();
+        assert!(!compound.contains_key("insertion"));
+        assert!(write_network(&tag, &mut Vec::new()).is_ok());
+
+        let expected = serde_json::json!({
+            "text": "Kicked",
+            "color": "red",
+            "extra": ["plain", {"text": "!", "bold": 1}]
+        });
+        assert_eq!(tag.to_json(), expected);
+    }
+
+    #[test]
+    fn test_parse_snbt() {
+        let compound = parse_snbt(
+            r#"{CustomName:'"Bob"', Age:-24000, Small:true, Rotation:[90f,0.5F], "a b":{x:3b,y:[I;1,2]}, id:"minecraft:pig"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            compound.get("CustomName"),
+            Some(&Tag::String("\"Bob\"".to_string()))
+        );
+        assert_eq!(compound.get("Age"), Some(&Tag::Int(-24000)));
+        assert_eq!(compound.get("Small"), Some(&Tag::Byte(1)));
+        assert_eq!(
+            compound.get("Rotation"),
+            Some(&Tag::List(vec![Tag::Float(90.0), Tag::Float(0.5)]))
+        );
+        let nested = compound.get("a b").and_then(Tag::as_compound).unwrap();
+        assert_eq!(nested.get("x"), Some(&Tag::Byte(3)));
+        assert_eq!(nested.get("y"), Some(&Tag::IntArray(vec![1, 2])));
+        assert_eq!(
+            compound.get("id"),
+            Some(&Tag::String("minecraft:pig".to_string()))
+        );
+
+        assert!(parse_snbt("{a:1").is_err());
+        assert!(parse_snbt("{id:minecraft:pig}").is_err());
+        assert!(parse_snbt("{a:1} b").is_err());
+        assert!(parse_snbt("[1,2]").is_err());
+        assert!(parse_snbt("{a:[1,2b]}").is_err());
+        assert!(parse_snbt(&"{a:".repeat(600)).is_err());
+    }
+}
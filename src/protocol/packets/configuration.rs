@@ -4,11 +4,38 @@
 //! between login and play states. This phase allows the server to send
 //! various configuration data to the client before gameplay begins.
 
-use crate::error::Result;
+use crate::error::{Result, ServerError};
+use crate::protocol::ConnectionState;
 use crate::protocol::packets::{ClientboundPacket, Packet, ServerboundPacket};
-use crate::protocol::types::{McString, VarInt};
+use crate::protocol::types::{
+    ByteArray, JsonTextComponent, McString, McUuid, VarInt, read_bool, read_length, read_uuid,
+    write_bool, write_uuid,
+};
 use std::io::{Read, Write};
 
+/// Disconnect packet sent during configuration (clientbound)
+#[derive(Debug, Clone)]
+pub struct ConfigurationDisconnectPacket {
+    /// Disconnect reason
+    pub reason: JsonTextComponent,
+}
+
+impl Packet for ConfigurationDisconnectPacket {
+    const ID: i32 = 0x02;
+    const STATE: ConnectionState = ConnectionState::Configuration;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let reason = JsonTextComponent::read_nbt(reader)?;
+        Ok(ConfigurationDisconnectPacket { reason })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.reason.write_nbt(writer)
+    }
+}
+
+impl ClientboundPacket for ConfigurationDisconnectPacket {}
+
 /// Finish Configuration packet (clientbound)
 ///
 /// Sent by the server to notify the client that the configuration process has finished.
@@ -18,6 +45,7 @@ pub struct FinishConfigurationPacket;
 
 impl Packet for FinishConfigurationPacket {
     const ID: i32 = 0x03;
+    const STATE: ConnectionState = ConnectionState::Configuration;
 
     fn read<R: Read>(_reader: &mut R) -> Result<Self> {
         Ok(FinishConfigurationPacket)
@@ -38,7 +66,8 @@ impl ClientboundPacket for FinishConfigurationPacket {}
 pub struct AcknowledgeFinishConfigurationPacket;
 
 impl Packet for AcknowledgeFinishConfigurationPacket {
-    const ID: i32 = 0x02;
+    const ID: i32 = 0x03;
+    const STATE: ConnectionState = ConnectionState::Configuration;
 
     fn read<R: Read>(_reader: &mut R) -> Result<Self> {
         Ok(AcknowledgeFinishConfigurationPacket)
@@ -75,20 +104,18 @@ pub struct RegistryEntry {
 
 impl Packet for RegistryDataPacket {
     const ID: i32 = 0x07;
+    const STATE: ConnectionState = ConnectionState::Configuration;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let registry_id = McString::read(reader)?;
-        let entry_count = VarInt::read(reader)?;
+        let entry_count = read_length(reader, crate::protocol::MAX_PACKET_SIZE)?;
 
         let mut entries = Vec::new();
-        for _ in 0..entry_count.0 {
+        for _ in 0..entry_count {
             let entry_id = McString::read(reader)?;
             let has_data = crate::protocol::types::read_bool(reader)?;
             let data = if has_data {
-                let data_length = VarInt::read(reader)?;
-                let mut data_bytes = vec![0u8; data_length.0 as usize];
-                reader.read_exact(&mut data_bytes)?;
-                Some(data_bytes)
+                Some(ByteArray::read(reader)?.0)
             } else {
                 None
             };
@@ -125,6 +152,329 @@ impl Packet for RegistryDataPacket {
 
 impl ClientboundPacket for RegistryDataPacket {}
 
+/// Client Information packet (serverbound)
+///
+/// Sent by the client during configuration and whenever its settings change
+/// in play (see [`crate::protocol::packets::play::PlayClientInformationPacket`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientInformationPacket {
+    /// Client locale, e.g. `en_us`
+    pub locale: McString,
+    /// Client-side render distance in chunks
+    pub view_distance: i8,
+    /// Chat mode (0=Enabled, 1=Commands only, 2=Hidden)
+    pub chat_mode: VarInt,
+    /// Whether chat colors are shown
+    pub chat_colors: bool,
+    /// Displayed skin parts bit mask
+    pub displayed_skin_parts: u8,
+    /// Main hand (0=Left, 1=Right)
+    pub main_hand: VarInt,
+    /// Whether text filtering is enabled
+    pub enable_text_filtering: bool,
+    /// Whether the player may appear in the server list sample
+    pub allow_server_listings: bool,
+    /// Particle status (0=All, 1=Decreased, 2=Minimal)
+    pub particle_status: VarInt,
+}
+
+impl Packet for ClientInformationPacket {
+    const ID: i32 = 0x00;
+    const STATE: ConnectionState = ConnectionState::Configuration;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let locale = McString::read_with_max_length(reader, 16)?;
+        let view_distance = crate::protocol::types::read_unsigned_byte(reader)? as i8;
+        let chat_mode = VarInt::read(reader)?;
+        let chat_colors = crate::protocol::types::read_bool(reader)?;
+        let displayed_skin_parts = crate::protocol::types::read_unsigned_byte(reader)?;
+        let main_hand = VarInt::read(reader)?;
+        let enable_text_filtering = crate::protocol::types::read_bool(reader)?;
+        let allow_server_listings = crate::protocol::types::read_bool(reader)?;
+        let particle_status = VarInt::read(reader)?;
+
+        Ok(ClientInformationPacket {
+            locale,
+            view_distance,
+            chat_mode,
+            chat_colors,
+            displayed_skin_parts,
+            main_hand,
+            enable_text_filtering,
+            allow_server_listings,
+            particle_status,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.locale.write(writer)?;
+        crate::protocol::types::write_unsigned_byte(self.view_distance as u8, writer)?;
+        self.chat_mode.write(writer)?;
+        crate::protocol::types::write_bool(self.chat_colors, writer)?;
+        crate::protocol::types::write_unsigned_byte(self.displayed_skin_parts, writer)?;
+        self.main_hand.write(writer)?;
+        crate::protocol::types::write_bool(self.enable_text_filtering, writer)?;
+        crate::protocol::types::write_bool(self.allow_server_listings, writer)?;
+        self.particle_status.write(writer)?;
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for ClientInformationPacket {}
+
+/// Plugin Message packet (serverbound)
+///
+/// Carries mod or plugin data on a named channel. Vanilla clients send their
+/// brand on [`PluginMessagePacket::BRAND_CHANNEL`]. The same contents are used
+/// by [`ClientboundPluginMessagePacket`] and by the play state
+/// [`PlayPluginMessagePacket`](crate::protocol::packets::play::PlayPluginMessagePacket).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginMessagePacket {
+    /// Channel identifier, e.g. `minecraft:brand`
+    pub channel: McString,
+    /// Channel-specific data, taking up the rest of the packet
+    pub data: Vec<u8>,
+}
+
+impl PluginMessagePacket {
+    /// Channel the client and server brands are sent on
+    pub const BRAND_CHANNEL: &str = "minecraft:brand";
+
+    /// Longest body a client may send
+    pub const MAX_SERVERBOUND_LENGTH: usize = 32767;
+
+    /// Longest body the server may send
+    pub const MAX_CLIENTBOUND_LENGTH: usize = 1048576;
+
+    /// Create a brand message
+    pub fn brand(brand: &str) -> Self {
+        let mut data = Vec::new();
+        // Writing to a Vec cannot fail
+        let _ = McString(brand.to_string()).write(&mut data);
+        Self {
+            channel: Self::BRAND_CHANNEL.into(),
+            data,
+        }
+    }
+
+    /// The brand carried by this message, if it is a brand message
+    pub fn read_brand(&self) -> Option<String> {
+        if self.channel.0 != Self::BRAND_CHANNEL {
+            return None;
+        }
+        McString::read(&mut self.data.as_slice()).ok().map(|s| s.0)
+    }
+
+    /// Read a plugin message whose body is at most `max_length` bytes long
+    fn read_with_max_length<R: Read>(reader: &mut R, max_length: usize) -> Result<Self> {
+        let channel = McString::read(reader)?;
+        let mut data = Vec::new();
+        reader.take(max_length as u64 + 1).read_to_end(&mut data)?;
+        if data.len() > max_length {
+            return Err(ServerError::Protocol(format!(
+                "Plugin message on {} is longer than {} bytes",
+                channel.0, max_length
+            )));
+        }
+        Ok(Self { channel, data })
+    }
+}
+
+impl Packet for PluginMessagePacket {
+    const ID: i32 = 0x02;
+    const STATE: ConnectionState = ConnectionState::Configuration;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Self::read_with_max_length(reader, Self::MAX_SERVERBOUND_LENGTH)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.channel.write(writer)?;
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for PluginMessagePacket {}
+
+/// Plugin Message packet (clientbound)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientboundPluginMessagePacket(pub PluginMessagePacket);
+
+impl Packet for ClientboundPluginMessagePacket {
+    const ID: i32 = 0x01;
+    const STATE: ConnectionState = ConnectionState::Configuration;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        PluginMessagePacket::read_with_max_length(
+            reader,
+            PluginMessagePacket::MAX_CLIENTBOUND_LENGTH,
+        )
+        .map(ClientboundPluginMessagePacket)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.0.write(writer)
+    }
+}
+
+impl ClientboundPacket for ClientboundPluginMessagePacket {}
+
+/// Add Resource Pack packet (clientbound)
+///
+/// Asks the client to download and apply a resource pack. The client answers
+/// with [`ResourcePackResponsePacket`]s as the download progresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddResourcePackPacket {
+    /// Pack ID, used to refer to the pack in responses
+    pub uuid: McUuid,
+    /// URL the pack is downloaded from
+    pub url: McString,
+    /// Lowercase hex SHA-1 of the pack, or empty to skip the check
+    pub hash: McString,
+    /// Whether the client is disconnected if it declines the pack
+    pub forced: bool,
+    /// Message shown in the prompt
+    pub prompt: Option<JsonTextComponent>,
+}
+
+impl AddResourcePackPacket {
+    /// Longest hash accepted
+    pub const MAX_HASH_LENGTH: usize = 40;
+}
+
+impl Packet for AddResourcePackPacket {
+    const ID: i32 = 0x09;
+    const STATE: ConnectionState = ConnectionState::Configuration;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let uuid = read_uuid(reader)?;
+        let url = McString::read(reader)?;
+        let hash = McString::read_with_max_length(reader, Self::MAX_HASH_LENGTH)?;
+        let forced = read_bool(reader)?;
+        let prompt = if read_bool(reader)? {
+            Some(JsonTextComponent::read_nbt(reader)?)
+        } else {
+            None
+        };
+        Ok(AddResourcePackPacket {
+            uuid,
+            url,
+            hash,
+            forced,
+            prompt,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_uuid(&self.uuid, writer)?;
+        self.url.write(writer)?;
+        self.hash.write(writer)?;
+        write_bool(self.forced, writer)?;
+        write_bool(self.prompt.is_some(), writer)?;
+        if let Some(prompt) = &self.prompt {
+            prompt.write_nbt(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for AddResourcePackPacket {}
+
+/// Resource Pack Response packet (serverbound)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourcePackResponsePacket {
+    /// ID of the pack the response is about
+    pub uuid: McUuid,
+    /// Progress of the pack (see [`ResourcePackStatus`])
+    pub result: VarInt,
+}
+
+impl ResourcePackResponsePacket {
+    /// The reported status, if it is known
+    pub fn status(&self) -> Option<ResourcePackStatus> {
+        ResourcePackStatus::try_from(self.result).ok()
+    }
+}
+
+impl Packet for ResourcePackResponsePacket {
+    const ID: i32 = 0x06;
+    const STATE: ConnectionState = ConnectionState::Configuration;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let uuid = read_uuid(reader)?;
+        let result = VarInt::read(reader)?;
+        Ok(ResourcePackResponsePacket { uuid, result })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_uuid(&self.uuid, writer)?;
+        self.result.write(writer)
+    }
+}
+
+impl ServerboundPacket for ResourcePackResponsePacket {}
+
+/// Progress of a resource pack reported by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourcePackStatus {
+    /// The pack was downloaded and applied
+    Loaded = 0,
+    /// The player declined the pack
+    Declined = 1,
+    /// The download failed
+    FailedDownload = 2,
+    /// The player accepted the pack
+    Accepted = 3,
+    /// The pack was downloaded
+    Downloaded = 4,
+    /// The URL could not be used
+    InvalidUrl = 5,
+    /// The pack could not be applied
+    FailedReload = 6,
+    /// The pack was removed before it was applied
+    Discarded = 7,
+}
+
+impl ResourcePackStatus {
+    /// Whether the client is done with the pack, successfully or not
+    pub fn is_final(self) -> bool {
+        !matches!(
+            self,
+            ResourcePackStatus::Accepted | ResourcePackStatus::Downloaded
+        )
+    }
+}
+
+impl TryFrom<VarInt> for ResourcePackStatus {
+    type Error = ServerError;
+
+    fn try_from(value: VarInt) -> Result<Self> {
+        Ok(match value.0 {
+            0 => ResourcePackStatus::Loaded,
+            1 => ResourcePackStatus::Declined,
+            2 => ResourcePackStatus::FailedDownload,
+            3 => ResourcePackStatus::Accepted,
+            4 => ResourcePackStatus::Downloaded,
+            5 => ResourcePackStatus::InvalidUrl,
+            6 => ResourcePackStatus::FailedReload,
+            7 => ResourcePackStatus::Discarded,
+            _ => {
+                return Err(ServerError::Protocol(format!(
+                    "Invalid resource pack status: {}",
+                    value.0
+                )));
+            }
+        })
+    }
+}
+
+impl From<ResourcePackStatus> for VarInt {
+    fn from(status: ResourcePackStatus) -> Self {
+        VarInt(status as i32)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,4 +524,46 @@ mod tests {
         // Should be empty packet
         assert_eq!(cursor.position(), 0);
     }
+
+    #[test]
+    fn test_client_information_packet_roundtrip() {
+        let packet = ClientInformationPacket {
+            locale: "en_us".into(),
+            view_distance: 8,
+            chat_mode: VarInt(0),
+            chat_colors: true,
+            displayed_skin_parts: 0x7F,
+            main_hand: VarInt(1),
+            enable_text_filtering: false,
+            allow_server_listings: true,
+            particle_status: VarInt(0),
+        };
+
+        let mut buffer = Vec::new();
+        packet.write(&mut buffer).unwrap();
+
+        let decoded = ClientInformationPacket::read(&mut Cursor::new(buffer)).unwrap();
+        assert_eq!(decoded, packet);
+    }
+
+    #[test]
+    fn test_plugin_message_brand_and_limit() {
+        let brand = PluginMessagePacket::brand("vanilla");
+        assert_eq!(brand.read_brand().as_deref(), Some("vanilla"));
+
+        let other = PluginMessagePacket {
+            channel: "example:data".into(),
+            data: brand.data.clone(),
+        };
+        assert_eq!(other.read_brand(), None);
+
+        let oversized = PluginMessagePacket {
+            channel: "example:data".into(),
+            data: vec![0; PluginMessagePacket::MAX_SERVERBOUND_LENGTH + 1],
+        };
+        let mut buffer = Vec::new();
+        oversized.write(&mut buffer).unwrap();
+        assert!(PluginMessagePacket::read(&mut Cursor::new(&buffer)).is_err());
+        assert!(ClientboundPluginMessagePacket::read(&mut Cursor::new(&buffer)).is_ok());
+    }
 }
@@ -4,6 +4,7 @@
 //! Only one packet is sent in this state.
 
 use crate::error::Result;
+use crate::protocol::ConnectionState;
 use crate::protocol::packets::{Packet, ServerboundPacket};
 use crate::protocol::types::{McString, VarInt};
 use std::io::{Read, Write};
@@ -23,6 +24,7 @@ pub struct HandshakePacket {
 
 impl Packet for HandshakePacket {
     const ID: i32 = 0x00;
+    const STATE: ConnectionState = ConnectionState::Handshaking;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let protocol_version = VarInt::read(reader)?;
@@ -99,6 +101,7 @@ pub struct LegacyServerListPingPacket {
 
 impl Packet for LegacyServerListPingPacket {
     const ID: i32 = 0xFE; // Legacy packet ID
+    const STATE: ConnectionState = ConnectionState::Handshaking;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let payload = crate::protocol::types::read_unsigned_byte(reader)?;
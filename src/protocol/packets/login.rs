@@ -2,11 +2,35 @@
 //!
 //! Login packets handle player authentication and encryption.
 
-use crate::error::Result;
+use crate::error::{Result, ServerError};
+use crate::protocol::ConnectionState;
 use crate::protocol::packets::{ClientboundPacket, Packet, ServerboundPacket};
-use crate::protocol::types::{McString, McUuid, VarInt};
+use crate::protocol::types::{ByteArray, JsonTextComponent, McString, McUuid, VarInt, read_length};
 use std::io::{Read, Write};
 
+/// Disconnect packet sent during login (clientbound)
+#[derive(Debug, Clone)]
+pub struct LoginDisconnectPacket {
+    /// Disconnect reason
+    pub reason: JsonTextComponent,
+}
+
+impl Packet for LoginDisconnectPacket {
+    const ID: i32 = 0x00;
+    const STATE: ConnectionState = ConnectionState::Login;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let reason = JsonTextComponent::read(reader)?;
+        Ok(LoginDisconnectPacket { reason })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.reason.write(writer)
+    }
+}
+
+impl ClientboundPacket for LoginDisconnectPacket {}
+
 /// Login start packet (serverbound)
 #[derive(Debug, Clone)]
 pub struct LoginStartPacket {
@@ -18,6 +42,7 @@ pub struct LoginStartPacket {
 
 impl Packet for LoginStartPacket {
     const ID: i32 = 0x00;
+    const STATE: ConnectionState = ConnectionState::Login;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let name = McString::read(reader)?;
@@ -34,6 +59,95 @@ impl Packet for LoginStartPacket {
 
 impl ServerboundPacket for LoginStartPacket {}
 
+/// Encryption request packet (clientbound)
+///
+/// Sent by an online-mode server after Login Start to begin the key
+/// exchange.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptionRequestPacket {
+    /// Server ID, empty since 1.7
+    pub server_id: McString,
+    /// The server's RSA public key, DER encoded
+    pub public_key: Vec<u8>,
+    /// Random bytes the client must encrypt and send back
+    pub verify_token: Vec<u8>,
+    /// Whether the client should authenticate with the session server
+    pub should_authenticate: bool,
+}
+
+impl EncryptionRequestPacket {
+    /// Longest server ID
+    pub const MAX_SERVER_ID_LENGTH: usize = 20;
+
+    /// Longest public key or verify token accepted
+    pub const MAX_FIELD_LENGTH: usize = 1024;
+}
+
+impl Packet for EncryptionRequestPacket {
+    const ID: i32 = 0x01;
+    const STATE: ConnectionState = ConnectionState::Login;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let server_id = McString::read_with_max_length(reader, Self::MAX_SERVER_ID_LENGTH)?;
+        let public_key = ByteArray::read_with_max_length(reader, Self::MAX_FIELD_LENGTH)?.0;
+        let verify_token = ByteArray::read_with_max_length(reader, Self::MAX_FIELD_LENGTH)?.0;
+        let should_authenticate = crate::protocol::types::read_bool(reader)?;
+        Ok(EncryptionRequestPacket {
+            server_id,
+            public_key,
+            verify_token,
+            should_authenticate,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.server_id.write(writer)?;
+        ByteArray(self.public_key.clone()).write(writer)?;
+        ByteArray(self.verify_token.clone()).write(writer)?;
+        crate::protocol::types::write_bool(self.should_authenticate, writer)
+    }
+}
+
+impl ClientboundPacket for EncryptionRequestPacket {}
+
+/// Encryption response packet (serverbound)
+///
+/// Carries the shared secret and the verify token, both encrypted with the
+/// server's public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptionResponsePacket {
+    /// Shared secret, encrypted
+    pub shared_secret: Vec<u8>,
+    /// Verify token from the request, encrypted
+    pub verify_token: Vec<u8>,
+}
+
+impl EncryptionResponsePacket {
+    /// Longest encrypted value accepted
+    pub const MAX_FIELD_LENGTH: usize = 1024;
+}
+
+impl Packet for EncryptionResponsePacket {
+    const ID: i32 = 0x01;
+    const STATE: ConnectionState = ConnectionState::Login;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let shared_secret = ByteArray::read_with_max_length(reader, Self::MAX_FIELD_LENGTH)?.0;
+        let verify_token = ByteArray::read_with_max_length(reader, Self::MAX_FIELD_LENGTH)?.0;
+        Ok(EncryptionResponsePacket {
+            shared_secret,
+            verify_token,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        ByteArray(self.shared_secret.clone()).write(writer)?;
+        ByteArray(self.verify_token.clone()).write(writer)
+    }
+}
+
+impl ServerboundPacket for EncryptionResponsePacket {}
+
 /// Login success packet (clientbound)
 #[derive(Debug, Clone)]
 pub struct LoginSuccessPacket {
@@ -47,14 +161,15 @@ pub struct LoginSuccessPacket {
 
 impl Packet for LoginSuccessPacket {
     const ID: i32 = 0x02;
+    const STATE: ConnectionState = ConnectionState::Login;
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let uuid = crate::protocol::types::read_uuid(reader)?;
         let username = McString::read(reader)?;
 
-        let properties_count = VarInt::read(reader)?;
+        let properties_count = read_length(reader, Property::MAX_COUNT)?;
         let mut properties = Vec::new();
 
-        for _ in 0..properties_count.0 {
+        for _ in 0..properties_count {
             properties.push(Property::read(reader)?);
         }
 
@@ -91,6 +206,7 @@ pub struct SetCompressionPacket {
 
 impl Packet for SetCompressionPacket {
     const ID: i32 = 0x03;
+    const STATE: ConnectionState = ConnectionState::Login;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let threshold = VarInt::read(reader)?;
@@ -111,6 +227,7 @@ pub struct LoginAcknowledgedPacket;
 
 impl Packet for LoginAcknowledgedPacket {
     const ID: i32 = 0x03;
+    const STATE: ConnectionState = ConnectionState::Login;
 
     fn read<R: Read>(_reader: &mut R) -> Result<Self> {
         Ok(LoginAcknowledgedPacket)
@@ -123,6 +240,91 @@ impl Packet for LoginAcknowledgedPacket {
 
 impl ServerboundPacket for LoginAcknowledgedPacket {}
 
+/// Login plugin request packet (clientbound)
+///
+/// Asks the client something on a custom channel before the login
+/// completes. Vanilla clients answer that they did not understand it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginPluginRequestPacket {
+    /// ID the response must carry
+    pub message_id: VarInt,
+    /// Channel the request is sent on
+    pub channel: McString,
+    /// Request body, specific to the channel
+    pub data: Vec<u8>,
+}
+
+impl LoginPluginRequestPacket {
+    /// Largest request body accepted
+    pub const MAX_DATA_LENGTH: usize = 1_048_576;
+}
+
+impl Packet for LoginPluginRequestPacket {
+    const ID: i32 = 0x04;
+    const STATE: ConnectionState = ConnectionState::Login;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let message_id = VarInt::read(reader)?;
+        let channel = McString::read(reader)?;
+        let data = read_remaining(reader, Self::MAX_DATA_LENGTH)?;
+        Ok(LoginPluginRequestPacket {
+            message_id,
+            channel,
+            data,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.message_id.write(writer)?;
+        self.channel.write(writer)?;
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for LoginPluginRequestPacket {}
+
+/// Login plugin response packet (serverbound)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginPluginResponsePacket {
+    /// ID of the request answered
+    pub message_id: VarInt,
+    /// Response body, or `None` if the client did not understand the
+    /// request
+    pub data: Option<Vec<u8>>,
+}
+
+impl LoginPluginResponsePacket {
+    /// Largest response body accepted
+    pub const MAX_DATA_LENGTH: usize = 1_048_576;
+}
+
+impl Packet for LoginPluginResponsePacket {
+    const ID: i32 = 0x02;
+    const STATE: ConnectionState = ConnectionState::Login;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let message_id = VarInt::read(reader)?;
+        let data = if crate::protocol::types::read_bool(reader)? {
+            Some(read_remaining(reader, Self::MAX_DATA_LENGTH)?)
+        } else {
+            None
+        };
+        Ok(LoginPluginResponsePacket { message_id, data })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.message_id.write(writer)?;
+        crate::protocol::types::write_bool(self.data.is_some(), writer)?;
+        if let Some(ref data) = self.data {
+            writer.write_all(data)?;
+        }
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for LoginPluginResponsePacket {}
+
 /// Player property (used in login success)
 #[derive(Debug, Clone)]
 pub struct Property {
@@ -135,6 +337,9 @@ pub struct Property {
 }
 
 impl Property {
+    /// Most properties a profile may carry
+    pub const MAX_COUNT: usize = 16;
+
     /// Read a property from a reader
     pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let name = McString::read(reader)?;
@@ -167,3 +372,16 @@ impl Property {
         Ok(())
     }
 }
+
+/// Read the rest of a packet body, at most `max_length` bytes long
+fn read_remaining<R: Read>(reader: &mut R, max_length: usize) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(max_length as u64 + 1).read_to_end(&mut data)?;
+    if data.len() > max_length {
+        return Err(ServerError::Protocol(format!(
+            "Login plugin message is longer than {} bytes",
+            max_length
+        )));
+    }
+    Ok(data)
+}
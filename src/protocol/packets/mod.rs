@@ -9,8 +9,10 @@ pub mod login;
 pub mod play;
 pub mod status;
 
-use crate::error::Result;
+use crate::error::{Result, ServerError};
+use crate::protocol::ConnectionState;
 use crate::protocol::types::VarInt;
+use std::fmt;
 use std::io::{Read, Write};
 
 /// Trait for all Minecraft packets
@@ -18,6 +20,9 @@ pub trait Packet: Sized {
     /// The packet ID
     const ID: i32;
 
+    /// Connection state the packet is sent in
+    const STATE: ConnectionState;
+
     /// Read packet data from a reader
     fn read<R: Read>(reader: &mut R) -> Result<Self>;
 
@@ -28,6 +33,60 @@ pub trait Packet: Sized {
     fn id() -> VarInt {
         VarInt(Self::ID)
     }
+
+    /// Decode a received packet body
+    ///
+    /// Any failure is reported as [`ServerError::PacketDecode`], so a
+    /// truncated packet is not mistaken for a closed connection.
+    fn decode(state: ConnectionState, data: &[u8]) -> Result<Self> {
+        Self::read(&mut std::io::Cursor::new(data)).map_err(|e| ServerError::PacketDecode {
+            state,
+            id: Self::ID,
+            truncated: matches!(
+                e,
+                ServerError::Io(ref io) if io.kind() == std::io::ErrorKind::UnexpectedEof
+            ),
+            reason: e.to_string(),
+        })
+    }
+}
+
+/// A packet whose type has been erased
+///
+/// Lets queues and channels hold packets of different types, such as the
+/// broadcasts sent to every player, without making the holder generic. Every
+/// [`Packet`] that is `Debug + Send + Sync` is a `DynPacket`.
+pub trait DynPacket: fmt::Debug + Send + Sync {
+    /// The packet ID
+    fn packet_id(&self) -> i32;
+
+    /// Connection state the packet is sent in
+    fn state(&self) -> ConnectionState;
+
+    /// Name of the packet type, for logs and statistics
+    fn name(&self) -> &'static str;
+
+    /// Serialize the packet
+    fn encode(&self) -> Result<RawPacket>;
+}
+
+impl<P: Packet + fmt::Debug + Send + Sync> DynPacket for P {
+    fn packet_id(&self) -> i32 {
+        P::ID
+    }
+
+    fn state(&self) -> ConnectionState {
+        P::STATE
+    }
+
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<P>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
+    fn encode(&self) -> Result<RawPacket> {
+        RawPacket::encode(self)
+    }
 }
 
 /// Trait for clientbound packets (server -> client)
@@ -36,23 +95,232 @@ pub trait ClientboundPacket: Packet {}
 /// Trait for serverbound packets (client -> server)
 pub trait ServerboundPacket: Packet {}
 
-/// Helper function to read packet length
-pub fn read_packet_length<R: Read>(reader: &mut R) -> Result<VarInt> {
-    VarInt::read(reader)
+/// Serverbound packets of the handshaking state, by ID
+const SERVERBOUND_HANDSHAKING: &[&str] = &["intention"];
+
+/// Serverbound packets of the status state, by ID
+const SERVERBOUND_STATUS: &[&str] = &["status_request", "ping_request"];
+
+/// Serverbound packets of the login state, by ID
+const SERVERBOUND_LOGIN: &[&str] = &[
+    "hello",
+    "key",
+    "custom_query_answer",
+    "login_acknowledged",
+    "cookie_response",
+];
+
+/// Serverbound packets of the configuration state, by ID
+const SERVERBOUND_CONFIGURATION: &[&str] = &[
+    "client_information",
+    "cookie_response",
+    "custom_payload",
+    "finish_configuration",
+    "keep_alive",
+    "pong",
+    "resource_pack",
+    "select_known_packs",
+    "custom_click_action",
+];
+
+/// Serverbound packets of the play state, by ID
+const SERVERBOUND_PLAY: &[&str] = &[
+    "accept_teleportation",
+    "block_entity_tag_query",
+    "bundle_item_selected",
+    "change_difficulty",
+    "change_game_mode",
+    "chat_ack",
+    "chat_command",
+    "chat_command_signed",
+    "chat",
+    "chat_session_update",
+    "chunk_batch_received",
+    "client_command",
+    "client_tick_end",
+    "client_information",
+    "command_suggestion",
+    "configuration_acknowledged",
+    "container_button_click",
+    "container_click",
+    "container_close",
+    "container_slot_state_changed",
+    "cookie_response",
+    "custom_payload",
+    "debug_sample_subscription",
+    "edit_book",
+    "entity_tag_query",
+    "interact",
+    "jigsaw_generate",
+    "keep_alive",
+    "lock_difficulty",
+    "move_player_pos",
+    "move_player_pos_rot",
+    "move_player_rot",
+    "move_player_status_only",
+    "move_vehicle",
+    "paddle_boat",
+    "pick_item_from_block",
+    "pick_item_from_entity",
+    "ping_request",
+    "place_recipe",
+    "player_abilities",
+    "player_action",
+    "player_command",
+    "player_input",
+    "player_loaded",
+    "pong",
+    "recipe_book_change_settings",
+    "recipe_book_seen_recipe",
+    "rename_item",
+    "resource_pack",
+    "seen_advancements",
+    "select_trade",
+    "set_beacon",
+    "set_carried_item",
+    "set_command_block",
+    "set_command_minecart",
+    "set_creative_mode_slot",
+    "set_jigsaw_block",
+    "set_structure_block",
+    "set_test_block",
+    "sign_update",
+    "swing",
+    "teleport_to_entity",
+    "test_instance_block_action",
+    "use_item_on",
+    "use_item",
+    "custom_click_action",
+];
+
+/// Vanilla names of the serverbound packets a state defines in the
+/// supported protocol, indexed by packet ID
+pub fn serverbound_packets(state: ConnectionState) -> &'static [&'static str] {
+    match state {
+        ConnectionState::Handshaking => SERVERBOUND_HANDSHAKING,
+        ConnectionState::Status => SERVERBOUND_STATUS,
+        ConnectionState::Login => SERVERBOUND_LOGIN,
+        ConnectionState::Configuration => SERVERBOUND_CONFIGURATION,
+        ConnectionState::Play => SERVERBOUND_PLAY,
+    }
+}
+
+/// Number of serverbound packet IDs a state defines in the supported protocol
+///
+/// IDs are assigned from zero without gaps, so any ID at or above the count
+/// cannot come from a well-behaved client.
+pub fn serverbound_id_count(state: ConnectionState) -> i32 {
+    serverbound_packets(state).len() as i32
+}
+
+/// Check that a received packet ID is defined for the connection's state
+pub fn check_serverbound_id(state: ConnectionState, id: i32) -> Result<()> {
+    if (0..serverbound_id_count(state)).contains(&id) {
+        return Ok(());
+    }
+    Err(ServerError::Protocol(format!(
+        "Unknown {} packet 0x{:02X}",
+        state.as_str(),
+        id
+    )))
+}
+
+/// An already serialized packet, ready to be queued or broadcast to connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawPacket {
+    /// Packet ID
+    pub id: i32,
+    /// Serialized packet body (without the ID)
+    pub data: Vec<u8>,
 }
 
-/// Helper function to write packet with length prefix
-pub fn write_packet_with_length<W: Write, P: Packet>(packet: &P, writer: &mut W) -> Result<()> {
-    // First, serialize the packet to get its length
-    let mut packet_data = Vec::new();
-    P::id().write(&mut packet_data)?;
-    packet.write(&mut packet_data)?;
+impl RawPacket {
+    /// Serialize a packet
+    pub fn encode<P: Packet>(packet: &P) -> Result<Self> {
+        let mut data = Vec::new();
+        packet.write(&mut data)?;
+        Ok(Self { id: P::ID, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::packets::play::KeepAlivePacket;
+    use crate::protocol::packets::status::PingResponsePacket;
+
+    #[test]
+    fn test_dyn_packets() {
+        let queue: Vec<Box<dyn DynPacket>> = vec![
+            Box::new(KeepAlivePacket { keep_alive_id: 7 }),
+            Box::new(PingResponsePacket { payload: 7 }),
+        ];
+        let names: Vec<_> = queue.iter().map(|packet| packet.name()).collect();
+        assert_eq!(names, ["KeepAlivePacket", "PingResponsePacket"]);
+        assert_eq!(queue[1].state(), ConnectionState::Status);
 
-    // Write length prefix
-    VarInt(packet_data.len() as i32).write(writer)?;
+        let raw = queue[0].encode().unwrap();
+        assert_eq!(raw.id, KeepAlivePacket::ID);
+        assert_eq!(
+            raw,
+            RawPacket::encode(&KeepAlivePacket { keep_alive_id: 7 }).unwrap()
+        );
+        assert_eq!(queue[0].packet_id(), KeepAlivePacket::ID);
+    }
+
+    #[test]
+    fn test_check_serverbound_id() {
+        use crate::protocol::packets::configuration::ResourcePackResponsePacket;
+        use crate::protocol::packets::play::UseItemOnPacket;
 
-    // Write packet data
-    writer.write_all(&packet_data)?;
+        assert!(check_serverbound_id(ConnectionState::Play, UseItemOnPacket::ID).is_ok());
+        assert!(
+            check_serverbound_id(
+                ConnectionState::Configuration,
+                ResourcePackResponsePacket::ID
+            )
+            .is_ok()
+        );
+        assert!(check_serverbound_id(ConnectionState::Status, 0x02).is_err());
+        assert!(check_serverbound_id(ConnectionState::Handshaking, 0xFE).is_err());
+        assert!(check_serverbound_id(ConnectionState::Login, -1).is_err());
+    }
 
-    Ok(())
+    #[test]
+    fn test_serverbound_packet_table() {
+        use crate::protocol::packets::configuration::{
+            AcknowledgeFinishConfigurationPacket, ResourcePackResponsePacket,
+        };
+        use crate::protocol::packets::handshaking::HandshakePacket;
+        use crate::protocol::packets::login::{LoginAcknowledgedPacket, LoginStartPacket};
+        use crate::protocol::packets::play::{
+            ChatCommandPacket, ClickContainerPacket, ConfigurationAcknowledgedPacket,
+            PlayerInputPacket, ServerboundKeepAlivePacket, SetHeldItemPacket, UseItemPacket,
+        };
+        use crate::protocol::packets::status::PingRequestPacket;
+
+        fn name<P: ServerboundPacket>() -> &'static str {
+            serverbound_packets(P::STATE)[P::ID as usize]
+        }
+        assert_eq!(name::<HandshakePacket>(), "intention");
+        assert_eq!(name::<PingRequestPacket>(), "ping_request");
+        assert_eq!(name::<LoginStartPacket>(), "hello");
+        assert_eq!(name::<LoginAcknowledgedPacket>(), "login_acknowledged");
+        assert_eq!(
+            name::<AcknowledgeFinishConfigurationPacket>(),
+            "finish_configuration"
+        );
+        assert_eq!(name::<ResourcePackResponsePacket>(), "resource_pack");
+        assert_eq!(name::<ChatCommandPacket>(), "chat_command");
+        assert_eq!(name::<ClickContainerPacket>(), "container_click");
+        assert_eq!(
+            name::<ConfigurationAcknowledgedPacket>(),
+            "configuration_acknowledged"
+        );
+        assert_eq!(name::<PlayerInputPacket>(), "player_input");
+        assert_eq!(name::<ServerboundKeepAlivePacket>(), "keep_alive");
+        assert_eq!(name::<SetHeldItemPacket>(), "set_carried_item");
+        assert_eq!(name::<UseItemPacket>(), "use_item");
+        assert_eq!(serverbound_id_count(ConnectionState::Play), 0x42);
+    }
 }
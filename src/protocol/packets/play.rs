@@ -4,11 +4,25 @@
 //! This is where the bulk of the game packets are defined.
 
 use crate::error::Result;
+use crate::game::enchantment::Enchantment;
+use crate::game::inventory::ItemStack;
+use crate::game::player::PlayerAbilities;
+use crate::game::world::chunk::{CHUNK_HEIGHT, CHUNK_SIZE, Chunk};
+use crate::protocol::ConnectionState;
+use crate::protocol::metadata::{MetadataEntry, read_metadata, write_metadata};
+use crate::protocol::packets::configuration::{
+    ClientInformationPacket, ClientboundPluginMessagePacket, PluginMessagePacket,
+};
+use crate::protocol::packets::login::Property;
 use crate::protocol::packets::{ClientboundPacket, Packet, ServerboundPacket};
-use crate::protocol::types::{McString, Position, VarInt};
+use crate::protocol::types::{
+    ByteArray, Identifier, JsonTextComponent, McString, McUuid, Position, VarInt, VarLong,
+    read_bool, read_bytes, read_int, read_length, read_long, read_uuid, write_bool, write_int,
+    write_long, write_uuid,
+};
 use std::io::{Read, Write};
 
-/// Keep alive packet (bidirectional)
+/// Keep alive packet (clientbound)
 #[derive(Debug, Clone)]
 pub struct KeepAlivePacket {
     /// Keep alive ID
@@ -16,7 +30,8 @@ pub struct KeepAlivePacket {
 }
 
 impl Packet for KeepAlivePacket {
-    const ID: i32 = 0x26; // Clientbound ID, serverbound is 0x18
+    const ID: i32 = 0x26;
+    const STATE: ConnectionState = ConnectionState::Play;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let mut bytes = [0u8; 8];
@@ -32,26 +47,53 @@ impl Packet for KeepAlivePacket {
 }
 
 impl ClientboundPacket for KeepAlivePacket {}
-impl ServerboundPacket for KeepAlivePacket {}
+
+/// Keep alive response packet (serverbound)
+///
+/// Echoes the ID of the last [`KeepAlivePacket`] the client received.
+#[derive(Debug, Clone)]
+pub struct ServerboundKeepAlivePacket {
+    /// Keep alive ID
+    pub keep_alive_id: i64,
+}
+
+impl Packet for ServerboundKeepAlivePacket {
+    const ID: i32 = 0x1B;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = [0u8; 8];
+        reader.read_exact(&mut bytes)?;
+        let keep_alive_id = i64::from_be_bytes(bytes);
+        Ok(ServerboundKeepAlivePacket { keep_alive_id })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.keep_alive_id.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for ServerboundKeepAlivePacket {}
 
 /// Disconnect packet (clientbound)
 #[derive(Debug, Clone)]
 pub struct DisconnectPacket {
-    /// Disconnect reason (JSON chat component)
-    pub reason: McString,
+    /// Disconnect reason
+    pub reason: JsonTextComponent,
 }
 
 impl Packet for DisconnectPacket {
-    const ID: i32 = 0x1D;
+    const ID: i32 = 0x1C;
+    const STATE: ConnectionState = ConnectionState::Play;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
-        let reason = McString::read(reader)?;
+        let reason = JsonTextComponent::read_nbt(reader)?;
         Ok(DisconnectPacket { reason })
     }
 
     fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
-        self.reason.write(writer)?;
-        Ok(())
+        self.reason.write_nbt(writer)
     }
 }
 
@@ -74,11 +116,21 @@ pub struct ChatMessagePacket {
     pub acknowledged: Vec<u8>,
 }
 
+impl ChatMessagePacket {
+    /// Longest message a client may send, in bytes (256 characters)
+    pub const MAX_MESSAGE_LENGTH: usize = 256 * 4;
+    /// Length of a message signature
+    pub const SIGNATURE_LENGTH: usize = 256;
+    /// Length of the bit set of acknowledged messages (20 bits)
+    pub const ACKNOWLEDGED_LENGTH: usize = 3;
+}
+
 impl Packet for ChatMessagePacket {
-    const ID: i32 = 0x06;
+    const ID: i32 = 0x08;
+    const STATE: ConnectionState = ConnectionState::Play;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
-        let message = McString::read(reader)?;
+        let message = McString::read_with_max_length(reader, Self::MAX_MESSAGE_LENGTH)?;
 
         let mut timestamp_bytes = [0u8; 8];
         reader.read_exact(&mut timestamp_bytes)?;
@@ -90,19 +142,14 @@ impl Packet for ChatMessagePacket {
 
         let has_signature = crate::protocol::types::read_bool(reader)?;
         let signature = if has_signature {
-            let signature_length = VarInt::read(reader)?;
-            let mut signature_bytes = vec![0u8; signature_length.0 as usize];
-            reader.read_exact(&mut signature_bytes)?;
-            Some(signature_bytes)
+            Some(ByteArray::read_with_max_length(reader, Self::SIGNATURE_LENGTH)?.0)
         } else {
             None
         };
 
         let message_count = VarInt::read(reader)?;
 
-        let acknowledged_length = VarInt::read(reader)?;
-        let mut acknowledged = vec![0u8; acknowledged_length.0 as usize];
-        reader.read_exact(&mut acknowledged)?;
+        let acknowledged = ByteArray::read_with_max_length(reader, Self::ACKNOWLEDGED_LENGTH)?.0;
 
         Ok(ChatMessagePacket {
             message,
@@ -135,7 +182,117 @@ impl Packet for ChatMessagePacket {
 
 impl ServerboundPacket for ChatMessagePacket {}
 
+/// Chat command packet (serverbound)
+///
+/// An unsigned command typed by the player, without the leading slash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatCommandPacket {
+    /// Command line
+    pub command: McString,
+}
+
+impl ChatCommandPacket {
+    /// Longest command a client may send, in bytes
+    pub const MAX_COMMAND_LENGTH: usize = 32767;
+}
+
+impl Packet for ChatCommandPacket {
+    const ID: i32 = 0x06;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(ChatCommandPacket {
+            command: McString::read_with_max_length(reader, Self::MAX_COMMAND_LENGTH)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.command.write(writer)
+    }
+}
+
+impl ServerboundPacket for ChatCommandPacket {}
+
+/// Signed chat command packet (serverbound)
+///
+/// A command whose message arguments the player signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedChatCommandPacket {
+    /// Command line
+    pub command: McString,
+    /// Timestamp
+    pub timestamp: i64,
+    /// Salt for argument signing
+    pub salt: i64,
+    /// Signatures of message arguments, by argument name
+    pub argument_signatures: Vec<(McString, Vec<u8>)>,
+    /// Message count
+    pub message_count: VarInt,
+    /// Bit set of acknowledged messages
+    pub acknowledged: [u8; ChatMessagePacket::ACKNOWLEDGED_LENGTH],
+    /// Checksum of the acknowledged messages
+    pub checksum: u8,
+}
+
+impl SignedChatCommandPacket {
+    /// Most argument signatures a command may carry
+    pub const MAX_ARGUMENT_SIGNATURES: usize = 8;
+    /// Longest argument name
+    pub const MAX_ARGUMENT_NAME_LENGTH: usize = 16;
+}
+
+impl Packet for SignedChatCommandPacket {
+    const ID: i32 = 0x07;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let command =
+            McString::read_with_max_length(reader, ChatCommandPacket::MAX_COMMAND_LENGTH)?;
+        let timestamp = read_long(reader)?;
+        let salt = read_long(reader)?;
+        let count = read_length(reader, Self::MAX_ARGUMENT_SIGNATURES)?;
+        let mut argument_signatures = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name = McString::read_with_max_length(reader, Self::MAX_ARGUMENT_NAME_LENGTH)?;
+            let signature = read_bytes(reader, ChatMessagePacket::SIGNATURE_LENGTH)?;
+            argument_signatures.push((name, signature));
+        }
+        let message_count = VarInt::read(reader)?;
+        let mut acknowledged = [0u8; ChatMessagePacket::ACKNOWLEDGED_LENGTH];
+        reader.read_exact(&mut acknowledged)?;
+        let checksum = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(SignedChatCommandPacket {
+            command,
+            timestamp,
+            salt,
+            argument_signatures,
+            message_count,
+            acknowledged,
+            checksum,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.command.write(writer)?;
+        writer.write_all(&self.timestamp.to_be_bytes())?;
+        writer.write_all(&self.salt.to_be_bytes())?;
+        VarInt(self.argument_signatures.len() as i32).write(writer)?;
+        for (name, signature) in &self.argument_signatures {
+            name.write(writer)?;
+            writer.write_all(signature)?;
+        }
+        self.message_count.write(writer)?;
+        writer.write_all(&self.acknowledged)?;
+        writer.write_all(&[self.checksum])?;
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for SignedChatCommandPacket {}
+
 /// Player position packet (serverbound)
+///
+/// Sent when the player moves without turning.
 #[derive(Debug, Clone)]
 pub struct PlayerPositionPacket {
     /// X coordinate
@@ -148,8 +305,14 @@ pub struct PlayerPositionPacket {
     pub on_ground: bool,
 }
 
+impl PlayerPositionPacket {
+    /// Flag set when the player is on the ground
+    const ON_GROUND: u8 = 0x01;
+}
+
 impl Packet for PlayerPositionPacket {
-    const ID: i32 = 0x1A;
+    const ID: i32 = 0x1D;
+    const STATE: ConnectionState = ConnectionState::Play;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let mut x_bytes = [0u8; 8];
@@ -164,7 +327,9 @@ impl Packet for PlayerPositionPacket {
         reader.read_exact(&mut z_bytes)?;
         let z = f64::from_be_bytes(z_bytes);
 
-        let on_ground = crate::protocol::types::read_bool(reader)?;
+        // The other flag tells whether the player is pushing against a wall
+        let flags = crate::protocol::types::read_unsigned_byte(reader)?;
+        let on_ground = flags & Self::ON_GROUND != 0;
 
         Ok(PlayerPositionPacket { x, y, z, on_ground })
     }
@@ -173,13 +338,176 @@ impl Packet for PlayerPositionPacket {
         writer.write_all(&self.x.to_be_bytes())?;
         writer.write_all(&self.y.to_be_bytes())?;
         writer.write_all(&self.z.to_be_bytes())?;
-        crate::protocol::types::write_bool(self.on_ground, writer)?;
+        let flags = if self.on_ground { Self::ON_GROUND } else { 0 };
+        crate::protocol::types::write_unsigned_byte(flags, writer)?;
         Ok(())
     }
 }
 
 impl ServerboundPacket for PlayerPositionPacket {}
 
+/// Set player position and rotation packet (serverbound)
+///
+/// Sent when the player moves and turns at once.
+#[derive(Debug, Clone)]
+pub struct PlayerPositionAndRotationPacket {
+    /// X coordinate
+    pub x: f64,
+    /// Y coordinate
+    pub y: f64,
+    /// Z coordinate
+    pub z: f64,
+    /// Yaw in degrees
+    pub yaw: f32,
+    /// Pitch in degrees
+    pub pitch: f32,
+    /// Whether the player is on ground
+    pub on_ground: bool,
+}
+
+impl Packet for PlayerPositionAndRotationPacket {
+    const ID: i32 = 0x1E;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let (x, y, z) = (read_f64(reader)?, read_f64(reader)?, read_f64(reader)?);
+        let yaw = read_f32(reader)?;
+        let pitch = read_f32(reader)?;
+        let flags = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(PlayerPositionAndRotationPacket {
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            on_ground: flags & PlayerPositionPacket::ON_GROUND != 0,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        for value in [self.x, self.y, self.z] {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        writer.write_all(&self.yaw.to_be_bytes())?;
+        writer.write_all(&self.pitch.to_be_bytes())?;
+        let flags = if self.on_ground {
+            PlayerPositionPacket::ON_GROUND
+        } else {
+            0
+        };
+        crate::protocol::types::write_unsigned_byte(flags, writer)
+    }
+}
+
+impl ServerboundPacket for PlayerPositionAndRotationPacket {}
+
+/// Set player rotation packet (serverbound)
+///
+/// Sent when the player turns without moving.
+#[derive(Debug, Clone)]
+pub struct PlayerRotationPacket {
+    /// Yaw in degrees
+    pub yaw: f32,
+    /// Pitch in degrees
+    pub pitch: f32,
+    /// Whether the player is on ground
+    pub on_ground: bool,
+}
+
+impl Packet for PlayerRotationPacket {
+    const ID: i32 = 0x1F;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let yaw = read_f32(reader)?;
+        let pitch = read_f32(reader)?;
+        let flags = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(PlayerRotationPacket {
+            yaw,
+            pitch,
+            on_ground: flags & PlayerPositionPacket::ON_GROUND != 0,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.yaw.to_be_bytes())?;
+        writer.write_all(&self.pitch.to_be_bytes())?;
+        let flags = if self.on_ground {
+            PlayerPositionPacket::ON_GROUND
+        } else {
+            0
+        };
+        crate::protocol::types::write_unsigned_byte(flags, writer)
+    }
+}
+
+impl ServerboundPacket for PlayerRotationPacket {}
+
+/// Set player movement flags packet (serverbound)
+///
+/// Sent when the player neither moves nor turns but lands or leaves the
+/// ground.
+#[derive(Debug, Clone)]
+pub struct PlayerOnGroundPacket {
+    /// Whether the player is on ground
+    pub on_ground: bool,
+}
+
+impl Packet for PlayerOnGroundPacket {
+    const ID: i32 = 0x20;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let flags = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(PlayerOnGroundPacket {
+            on_ground: flags & PlayerPositionPacket::ON_GROUND != 0,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let flags = if self.on_ground {
+            PlayerPositionPacket::ON_GROUND
+        } else {
+            0
+        };
+        crate::protocol::types::write_unsigned_byte(flags, writer)
+    }
+}
+
+impl ServerboundPacket for PlayerOnGroundPacket {}
+
+/// Player abilities packet (serverbound)
+///
+/// Sent when the player starts or stops flying.
+#[derive(Debug, Clone)]
+pub struct ServerboundPlayerAbilitiesPacket {
+    /// Whether the player is flying
+    pub flying: bool,
+}
+
+impl Packet for ServerboundPlayerAbilitiesPacket {
+    const ID: i32 = 0x27;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let flags = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(ServerboundPlayerAbilitiesPacket {
+            flying: flags & PlayerAbilitiesPacket::FLYING != 0,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let flags = if self.flying {
+            PlayerAbilitiesPacket::FLYING
+        } else {
+            0
+        };
+        crate::protocol::types::write_unsigned_byte(flags, writer)
+    }
+}
+
+impl ServerboundPacket for ServerboundPlayerAbilitiesPacket {}
+
 /// Block change packet (clientbound)
 #[derive(Debug, Clone)]
 pub struct BlockChangePacket {
@@ -190,7 +518,8 @@ pub struct BlockChangePacket {
 }
 
 impl Packet for BlockChangePacket {
-    const ID: i32 = 0x09;
+    const ID: i32 = 0x08;
+    const STATE: ConnectionState = ConnectionState::Play;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let position = Position::read(reader)?;
@@ -207,6 +536,65 @@ impl Packet for BlockChangePacket {
 
 impl ClientboundPacket for BlockChangePacket {}
 
+/// Update section blocks packet (clientbound)
+///
+/// Changes several blocks of one chunk section at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateSectionBlocksPacket {
+    /// Section coordinates: chunk X, section Y and chunk Z
+    pub section: [i32; 3],
+    /// New block state IDs by position within the section, packed as
+    /// `x << 8 | z << 4 | y`
+    pub blocks: Vec<(u16, u32)>,
+}
+
+impl UpdateSectionBlocksPacket {
+    /// Build the packet for blocks of a section, given with their world
+    /// positions
+    pub fn new(section: [i32; 3], blocks: &[(Position, u32)]) -> Self {
+        let blocks = blocks
+            .iter()
+            .map(|&(position, block_id)| {
+                let local = ((position.x & 15) << 8) | ((position.z & 15) << 4) | (position.y & 15);
+                (local as u16, block_id)
+            })
+            .collect();
+        Self { section, blocks }
+    }
+}
+
+impl Packet for UpdateSectionBlocksPacket {
+    const ID: i32 = 0x4D;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let packed = read_long(reader)?;
+        let section = [packed >> 42, packed << 44 >> 44, packed << 22 >> 42].map(|c| c as i32);
+        let count = read_length(reader, crate::protocol::MAX_PACKET_SIZE)?;
+        let mut blocks = Vec::new();
+        for _ in 0..count {
+            let entry = VarLong::read(reader)?.0;
+            blocks.push(((entry & 0xFFF) as u16, (entry >> 12) as u32));
+        }
+        Ok(UpdateSectionBlocksPacket { section, blocks })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let [x, y, z] = self.section.map(i64::from);
+        write_long(
+            ((x & 0x3F_FFFF) << 42) | ((z & 0x3F_FFFF) << 20) | (y & 0xF_FFFF),
+            writer,
+        )?;
+        VarInt(self.blocks.len() as i32).write(writer)?;
+        for &(local, block_id) in &self.blocks {
+            VarLong((i64::from(block_id) << 12) | i64::from(local & 0xFFF)).write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for UpdateSectionBlocksPacket {}
+
 /// Login (play) packet (clientbound)
 ///
 /// This is the first packet sent when transitioning from configuration to play state.
@@ -264,6 +652,7 @@ pub struct LoginPlayPacket {
 
 impl Packet for LoginPlayPacket {
     const ID: i32 = 0x2B;
+    const STATE: ConnectionState = ConnectionState::Play;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let mut entity_id_bytes = [0u8; 4];
@@ -272,9 +661,9 @@ impl Packet for LoginPlayPacket {
 
         let is_hardcore = crate::protocol::types::read_bool(reader)?;
 
-        let dimension_count = VarInt::read(reader)?;
+        let dimension_count = read_length(reader, crate::protocol::MAX_PACKET_SIZE)?;
         let mut dimension_names = Vec::new();
-        for _ in 0..dimension_count.0 {
+        for _ in 0..dimension_count {
             dimension_names.push(McString::read(reader)?);
         }
 
@@ -427,11 +816,11 @@ impl LoginPlayPacket {
             do_limited_crafting: false,
             dimension_type: VarInt(0),
             dimension_name: "minecraft:overworld".into(),
-            hashed_seed: 12345,     // Use world seed hash
-            game_mode: 0,           // Survival mode
+            hashed_seed: 12345, // Use world seed hash
+            game_mode: config.game_mode.id(),
             previous_game_mode: -1, // No previous game mode
-            is_debug: false,
-            is_flat: false,
+            is_debug: config.level_type.is_debug(),
+            is_flat: config.level_type.is_flat(),
             has_death_location: false,
             death_dimension_name: None,
             death_location: None,
@@ -448,46 +837,3737 @@ impl Default for LoginPlayPacket {
     }
 }
 
-// TODO: Add more play packets as needed
-// - Chunk data packets
-// - Entity packets
-// - Inventory packets
-// - etc.
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Cursor;
+/// Respawn packet (clientbound)
+///
+/// Moves the player into a dimension, as after dying or travelling through
+/// a portal. The client drops its chunks and waits for new ones.
+///
+/// Packet ID: 0x4B
+#[derive(Debug, Clone, PartialEq)]
+pub struct RespawnPacket {
+    /// The ID of the dimension type in the minecraft:dimension_type registry
+    pub dimension_type: VarInt,
+    /// Name of the dimension being spawned into
+    pub dimension_name: McString,
+    /// First 8 bytes of SHA-256 hash of world seed
+    pub hashed_seed: i64,
+    /// Current game mode
+    pub game_mode: u8,
+    /// Previous game mode (-1=Undefined)
+    pub previous_game_mode: i8,
+    /// Whether this is a debug world
+    pub is_debug: bool,
+    /// Whether this is a flat/superflat world
+    pub is_flat: bool,
+    /// Dimension and position the player last died at, if any
+    pub death_location: Option<(McString, Position)>,
+    /// Portal cooldown in ticks
+    pub portal_cooldown: VarInt,
+    /// Sea level
+    pub sea_level: VarInt,
+    /// Which data the client keeps ([`KEEP_ALL`](Self::KEEP_ALL) when
+    /// changing dimension)
+    pub data_kept: u8,
+}
 
-    #[test]
-    fn test_login_play_packet_roundtrip() {
-        let packet = LoginPlayPacket::new();
+impl RespawnPacket {
+    /// Keep entity attributes
+    pub const KEEP_ATTRIBUTES: u8 = 0x01;
+    /// Keep entity metadata
+    pub const KEEP_METADATA: u8 = 0x02;
+    /// Keep attributes and metadata
+    pub const KEEP_ALL: u8 = Self::KEEP_ATTRIBUTES | Self::KEEP_METADATA;
+}
 
-        let mut buffer = Vec::new();
-        packet.write(&mut buffer).unwrap();
+impl Packet for RespawnPacket {
+    const ID: i32 = 0x4B;
+    const STATE: ConnectionState = ConnectionState::Play;
 
-        let mut cursor = Cursor::new(buffer);
-        let decoded = LoginPlayPacket::read(&mut cursor).unwrap();
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let dimension_type = VarInt::read(reader)?;
+        let dimension_name = McString::read(reader)?;
+        let hashed_seed = read_long(reader)?;
+        let mut modes = [0u8; 2];
+        reader.read_exact(&mut modes)?;
+        let is_debug = read_bool(reader)?;
+        let is_flat = read_bool(reader)?;
+        let death_location = if read_bool(reader)? {
+            Some((McString::read(reader)?, Position::read(reader)?))
+        } else {
+            None
+        };
+        let portal_cooldown = VarInt::read(reader)?;
+        let sea_level = VarInt::read(reader)?;
+        let mut data_kept = [0u8; 1];
+        reader.read_exact(&mut data_kept)?;
+        Ok(Self {
+            dimension_type,
+            dimension_name,
+            hashed_seed,
+            game_mode: modes[0],
+            previous_game_mode: modes[1] as i8,
+            is_debug,
+            is_flat,
+            death_location,
+            portal_cooldown,
+            sea_level,
+            data_kept: data_kept[0],
+        })
+    }
 
-        assert_eq!(packet.entity_id, decoded.entity_id);
-        assert_eq!(packet.view_distance.0, decoded.view_distance.0);
-        assert_eq!(packet.simulation_distance.0, decoded.simulation_distance.0);
-        assert_eq!(packet.reduced_debug_info, decoded.reduced_debug_info);
-        assert_eq!(packet.enable_respawn_screen, decoded.enable_respawn_screen);
-        assert_eq!(packet.do_limited_crafting, decoded.do_limited_crafting);
-        assert_eq!(packet.dimension_name.0, decoded.dimension_name.0);
-        assert_eq!(packet.hashed_seed, decoded.hashed_seed);
-        assert_eq!(packet.game_mode, decoded.game_mode);
-        assert_eq!(packet.previous_game_mode, decoded.previous_game_mode);
-        assert_eq!(packet.is_debug, decoded.is_debug);
-        assert_eq!(packet.is_flat, decoded.is_flat);
-        assert_eq!(packet.has_death_location, decoded.has_death_location);
-        assert_eq!(packet.portal_cooldown.0, decoded.portal_cooldown.0);
-        assert_eq!(packet.enforces_secure_chat, decoded.enforces_secure_chat);
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.dimension_type.write(writer)?;
+        self.dimension_name.write(writer)?;
+        write_long(self.hashed_seed, writer)?;
+        writer.write_all(&[self.game_mode, self.previous_game_mode as u8])?;
+        write_bool(self.is_debug, writer)?;
+        write_bool(self.is_flat, writer)?;
+        write_bool(self.death_location.is_some(), writer)?;
+        if let Some((dimension, position)) = &self.death_location {
+            dimension.write(writer)?;
+            position.write(writer)?;
+        }
+        self.portal_cooldown.write(writer)?;
+        self.sea_level.write(writer)?;
+        writer.write_all(&[self.data_kept])?;
+        Ok(())
     }
+}
 
-    #[test]
-    fn test_login_play_packet_with_death_location() {
+impl ClientboundPacket for RespawnPacket {}
+
+/// Change difficulty packet (clientbound)
+///
+/// Sent on join and whenever the world difficulty changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeDifficultyPacket {
+    /// Difficulty ID (0=Peaceful, 1=Easy, 2=Normal, 3=Hard)
+    pub difficulty: u8,
+    /// Whether the difficulty is locked
+    pub locked: bool,
+}
+
+impl Packet for ChangeDifficultyPacket {
+    const ID: i32 = 0x0A;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let difficulty = crate::protocol::types::read_unsigned_byte(reader)?;
+        let locked = crate::protocol::types::read_bool(reader)?;
+        Ok(ChangeDifficultyPacket { difficulty, locked })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_unsigned_byte(self.difficulty, writer)?;
+        crate::protocol::types::write_bool(self.locked, writer)?;
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for ChangeDifficultyPacket {}
+
+/// Change difficulty packet (serverbound)
+///
+/// Sent when a player picks a difficulty in the options screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeDifficultyRequestPacket {
+    /// Difficulty ID (0=Peaceful, 1=Easy, 2=Normal, 3=Hard)
+    pub difficulty: u8,
+}
+
+impl Packet for ChangeDifficultyRequestPacket {
+    const ID: i32 = 0x03;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let difficulty = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(ChangeDifficultyRequestPacket { difficulty })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_unsigned_byte(self.difficulty, writer)
+    }
+}
+
+impl ServerboundPacket for ChangeDifficultyRequestPacket {}
+
+/// Lock difficulty packet (serverbound)
+///
+/// Sent when a player locks the difficulty in the options screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockDifficultyPacket {
+    /// Whether to lock the difficulty
+    pub locked: bool,
+}
+
+impl Packet for LockDifficultyPacket {
+    const ID: i32 = 0x1C;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let locked = crate::protocol::types::read_bool(reader)?;
+        Ok(LockDifficultyPacket { locked })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_bool(self.locked, writer)
+    }
+}
+
+impl ServerboundPacket for LockDifficultyPacket {}
+
+/// Update time packet (clientbound)
+///
+/// Synchronizes the world age and time of day with the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateTimePacket {
+    /// World age in ticks
+    pub world_age: i64,
+    /// Time of day in ticks
+    pub time_of_day: i64,
+    /// Whether the client should advance the time of day on its own
+    pub time_of_day_increasing: bool,
+}
+
+impl Packet for UpdateTimePacket {
+    const ID: i32 = 0x6A;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let world_age = read_long(reader)?;
+        let time_of_day = read_long(reader)?;
+        let time_of_day_increasing = crate::protocol::types::read_bool(reader)?;
+        Ok(UpdateTimePacket {
+            world_age,
+            time_of_day,
+            time_of_day_increasing,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_long(self.world_age, writer)?;
+        crate::protocol::types::write_long(self.time_of_day, writer)?;
+        crate::protocol::types::write_bool(self.time_of_day_increasing, writer)?;
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for UpdateTimePacket {}
+
+/// Game event packet (clientbound)
+///
+/// Used for a number of state changes such as weather and game mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameEventPacket {
+    /// Event ID (see the associated constants)
+    pub event: u8,
+    /// Event-specific value
+    pub value: f32,
+}
+
+impl GameEventPacket {
+    /// Rain stops
+    pub const END_RAINING: u8 = 1;
+    /// Rain starts
+    pub const BEGIN_RAINING: u8 = 2;
+    /// The player's game mode changes (value is the game mode ID)
+    pub const CHANGE_GAME_MODE: u8 = 3;
+    /// Rain level changes (value from 0 to 1)
+    pub const RAIN_LEVEL_CHANGE: u8 = 7;
+    /// Thunder level changes (value from 0 to 1)
+    pub const THUNDER_LEVEL_CHANGE: u8 = 8;
+    /// The client should wait for the chunks around it before showing the world
+    pub const START_WAITING_FOR_LEVEL_CHUNKS: u8 = 13;
+
+    /// Create a game event packet
+    pub fn new(event: u8, value: f32) -> Self {
+        Self { event, value }
+    }
+}
+
+impl Packet for GameEventPacket {
+    const ID: i32 = 0x22;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let event = crate::protocol::types::read_unsigned_byte(reader)?;
+        let mut value_bytes = [0u8; 4];
+        reader.read_exact(&mut value_bytes)?;
+        let value = f32::from_be_bytes(value_bytes);
+        Ok(GameEventPacket { event, value })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_unsigned_byte(self.event, writer)?;
+        writer.write_all(&self.value.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for GameEventPacket {}
+
+/// Player abilities packet (clientbound)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerAbilitiesPacket {
+    /// Ability flags (see the associated constants)
+    pub flags: u8,
+    /// Flying speed
+    pub flying_speed: f32,
+    /// Field of view modifier, which is the player's walking speed
+    pub field_of_view_modifier: f32,
+}
+
+impl PlayerAbilitiesPacket {
+    /// The player takes no damage
+    pub const INVULNERABLE: u8 = 0x01;
+    /// The player is flying
+    pub const FLYING: u8 = 0x02;
+    /// The player may start flying
+    pub const ALLOW_FLYING: u8 = 0x04;
+    /// The player breaks blocks instantly
+    pub const INSTANT_BREAK: u8 = 0x08;
+}
+
+impl From<&PlayerAbilities> for PlayerAbilitiesPacket {
+    fn from(abilities: &PlayerAbilities) -> Self {
+        let flags = [
+            (abilities.invulnerable, Self::INVULNERABLE),
+            (abilities.flying, Self::FLYING),
+            (abilities.allow_flying, Self::ALLOW_FLYING),
+            (abilities.instant_break, Self::INSTANT_BREAK),
+        ]
+        .into_iter()
+        .filter(|&(set, _)| set)
+        .fold(0, |flags, (_, flag)| flags | flag);
+        Self {
+            flags,
+            flying_speed: abilities.fly_speed,
+            field_of_view_modifier: abilities.walk_speed,
+        }
+    }
+}
+
+impl Packet for PlayerAbilitiesPacket {
+    const ID: i32 = 0x39;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let flags = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(PlayerAbilitiesPacket {
+            flags,
+            flying_speed: read_f32(reader)?,
+            field_of_view_modifier: read_f32(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_unsigned_byte(self.flags, writer)?;
+        writer.write_all(&self.flying_speed.to_be_bytes())?;
+        writer.write_all(&self.field_of_view_modifier.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for PlayerAbilitiesPacket {}
+
+/// Client information packet (serverbound, play state)
+///
+/// Same contents as the configuration-state
+/// [`ClientInformationPacket`](crate::protocol::packets::configuration::ClientInformationPacket),
+/// sent when the player changes their settings mid-session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayClientInformationPacket(pub ClientInformationPacket);
+
+impl Packet for PlayClientInformationPacket {
+    const ID: i32 = 0x0D;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(PlayClientInformationPacket(ClientInformationPacket::read(
+            reader,
+        )?))
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.0.write(writer)
+    }
+}
+
+impl ServerboundPacket for PlayClientInformationPacket {}
+
+/// Plugin message packet (serverbound, play state)
+///
+/// Same contents as the configuration-state
+/// [`PluginMessagePacket`](crate::protocol::packets::configuration::PluginMessagePacket).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayPluginMessagePacket(pub PluginMessagePacket);
+
+impl Packet for PlayPluginMessagePacket {
+    const ID: i32 = 0x15;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(PlayPluginMessagePacket(PluginMessagePacket::read(reader)?))
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.0.write(writer)
+    }
+}
+
+impl ServerboundPacket for PlayPluginMessagePacket {}
+
+/// Plugin message packet (clientbound, play state)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayClientboundPluginMessagePacket(pub ClientboundPluginMessagePacket);
+
+impl Packet for PlayClientboundPluginMessagePacket {
+    const ID: i32 = 0x18;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(PlayClientboundPluginMessagePacket(
+            ClientboundPluginMessagePacket::read(reader)?,
+        ))
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.0.write(writer)
+    }
+}
+
+impl ClientboundPacket for PlayClientboundPluginMessagePacket {}
+
+/// Set chunk cache radius packet (clientbound)
+///
+/// Tells the client the server's view distance for this player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetChunkCacheRadiusPacket {
+    /// View distance in chunks
+    pub view_distance: VarInt,
+}
+
+impl Packet for SetChunkCacheRadiusPacket {
+    const ID: i32 = 0x58;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let view_distance = VarInt::read(reader)?;
+        Ok(SetChunkCacheRadiusPacket { view_distance })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.view_distance.write(writer)
+    }
+}
+
+impl ClientboundPacket for SetChunkCacheRadiusPacket {}
+
+/// Set simulation distance packet (clientbound)
+///
+/// Tells the client the distance in chunks within which the world ticks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetSimulationDistancePacket {
+    /// Simulation distance in chunks
+    pub simulation_distance: VarInt,
+}
+
+impl Packet for SetSimulationDistancePacket {
+    const ID: i32 = 0x68;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let simulation_distance = VarInt::read(reader)?;
+        Ok(SetSimulationDistancePacket {
+            simulation_distance,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.simulation_distance.write(writer)
+    }
+}
+
+impl ClientboundPacket for SetSimulationDistancePacket {}
+
+/// Set default spawn position packet (clientbound)
+///
+/// Tells the client where the world spawn is, which is where compasses
+/// point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetDefaultSpawnPositionPacket {
+    /// Spawn position
+    pub location: Position,
+    /// Yaw players face when spawning
+    pub angle: f32,
+}
+
+impl Packet for SetDefaultSpawnPositionPacket {
+    const ID: i32 = 0x5A;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let location = Position::read(reader)?;
+        let angle = read_f32(reader)?;
+        Ok(SetDefaultSpawnPositionPacket { location, angle })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.location.write(writer)?;
+        writer.write_all(&self.angle.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for SetDefaultSpawnPositionPacket {}
+
+/// Set entity metadata packet (clientbound)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetEntityMetadataPacket {
+    /// Entity ID
+    pub entity_id: VarInt,
+    /// Changed metadata entries
+    pub metadata: Vec<MetadataEntry>,
+}
+
+impl Packet for SetEntityMetadataPacket {
+    const ID: i32 = 0x5C;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let metadata = read_metadata(reader)?;
+        Ok(SetEntityMetadataPacket {
+            entity_id,
+            metadata,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        write_metadata(&self.metadata, writer)
+    }
+}
+
+impl ClientboundPacket for SetEntityMetadataPacket {}
+
+/// Entity animation packet (clientbound)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityAnimationPacket {
+    /// Entity ID
+    pub entity_id: VarInt,
+    /// Animation ID (see the associated constants)
+    pub animation: u8,
+}
+
+impl EntityAnimationPacket {
+    /// Swing main arm
+    pub const SWING_MAIN_ARM: u8 = 0;
+    /// Leave bed
+    pub const LEAVE_BED: u8 = 2;
+    /// Swing offhand
+    pub const SWING_OFFHAND: u8 = 3;
+}
+
+impl Packet for EntityAnimationPacket {
+    const ID: i32 = 0x02;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let animation = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(EntityAnimationPacket {
+            entity_id,
+            animation,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        crate::protocol::types::write_unsigned_byte(self.animation, writer)?;
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for EntityAnimationPacket {}
+
+/// Entity event packet (clientbound)
+///
+/// Triggers an effect the client plays by itself, such as love hearts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityEventPacket {
+    /// Entity ID
+    pub entity_id: i32,
+    /// Event ID (see the associated constants)
+    pub event: i8,
+}
+
+impl EntityEventPacket {
+    /// Stop the eating animation of a player who finished eating
+    pub const FINISHED_EATING: i8 = 9;
+    /// Show love hearts around an animal
+    pub const IN_LOVE: i8 = 18;
+}
+
+impl Packet for EntityEventPacket {
+    const ID: i32 = 0x1E;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = crate::protocol::types::read_int(reader)?;
+        let event = crate::protocol::types::read_unsigned_byte(reader)? as i8;
+        Ok(EntityEventPacket { entity_id, event })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_int(self.entity_id, writer)?;
+        crate::protocol::types::write_unsigned_byte(self.event as u8, writer)?;
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for EntityEventPacket {}
+
+/// Player command packet (serverbound)
+///
+/// Sent for actions such as leaving a bed or starting to sprint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerCommandPacket {
+    /// The player's entity ID
+    pub entity_id: VarInt,
+    /// Action ID (see the associated constants)
+    pub action: VarInt,
+    /// Jump boost, only used by horse jumps
+    pub jump_boost: VarInt,
+}
+
+impl PlayerCommandPacket {
+    /// Leave bed
+    pub const LEAVE_BED: i32 = 0;
+    /// Start sprinting
+    pub const START_SPRINTING: i32 = 1;
+    /// Stop sprinting
+    pub const STOP_SPRINTING: i32 = 2;
+}
+
+impl Packet for PlayerCommandPacket {
+    const ID: i32 = 0x29;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let action = VarInt::read(reader)?;
+        let jump_boost = VarInt::read(reader)?;
+        Ok(PlayerCommandPacket {
+            entity_id,
+            action,
+            jump_boost,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        self.action.write(writer)?;
+        self.jump_boost.write(writer)?;
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for PlayerCommandPacket {}
+
+/// Player input packet (serverbound)
+///
+/// Sent whenever the movement keys the player holds change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerInputPacket {
+    /// Keys held, as a bit mask of the associated constants
+    pub flags: u8,
+}
+
+impl PlayerInputPacket {
+    /// Moving forward
+    pub const FORWARD: u8 = 0x01;
+    /// Moving backward
+    pub const BACKWARD: u8 = 0x02;
+    /// Strafing left
+    pub const LEFT: u8 = 0x04;
+    /// Strafing right
+    pub const RIGHT: u8 = 0x08;
+    /// Jumping
+    pub const JUMP: u8 = 0x10;
+    /// Sneaking
+    pub const SNEAK: u8 = 0x20;
+    /// Sprinting
+    pub const SPRINT: u8 = 0x40;
+
+    /// Whether a key (one of the constants) is held
+    pub fn holds(&self, key: u8) -> bool {
+        self.flags & key != 0
+    }
+}
+
+impl Packet for PlayerInputPacket {
+    const ID: i32 = 0x2A;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let flags = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(PlayerInputPacket { flags })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_unsigned_byte(self.flags, writer)
+    }
+}
+
+impl ServerboundPacket for PlayerInputPacket {}
+
+/// Set health packet (clientbound)
+///
+/// Updates the health and hunger bars of the player.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetHealthPacket {
+    /// Health, where 20 is full
+    pub health: f32,
+    /// Food level, where 20 is full
+    pub food: VarInt,
+    /// Food saturation
+    pub saturation: f32,
+}
+
+impl Packet for SetHealthPacket {
+    const ID: i32 = 0x61;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = [0u8; 4];
+        reader.read_exact(&mut bytes)?;
+        let health = f32::from_be_bytes(bytes);
+        let food = VarInt::read(reader)?;
+        reader.read_exact(&mut bytes)?;
+        let saturation = f32::from_be_bytes(bytes);
+        Ok(SetHealthPacket {
+            health,
+            food,
+            saturation,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.health.to_be_bytes())?;
+        self.food.write(writer)?;
+        writer.write_all(&self.saturation.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for SetHealthPacket {}
+
+/// Use item on packet (serverbound)
+///
+/// Sent when the player right-clicks a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseItemOnPacket {
+    /// Hand used (0=Main hand, 1=Off hand)
+    pub hand: VarInt,
+    /// Position of the clicked block
+    pub location: Position,
+    /// Clicked face (0=Bottom, 1=Top, 2=North, 3=South, 4=West, 5=East)
+    pub face: VarInt,
+    /// Cursor X position on the face (0 to 1)
+    pub cursor_x: f32,
+    /// Cursor Y position on the face (0 to 1)
+    pub cursor_y: f32,
+    /// Cursor Z position on the face (0 to 1)
+    pub cursor_z: f32,
+    /// Whether the player's head is inside a block
+    pub inside_block: bool,
+    /// Whether the world border was hit
+    pub world_border_hit: bool,
+    /// Block change sequence number
+    pub sequence: VarInt,
+}
+
+impl Packet for UseItemOnPacket {
+    const ID: i32 = 0x3F;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let hand = VarInt::read(reader)?;
+        let location = Position::read(reader)?;
+        let face = VarInt::read(reader)?;
+
+        let mut cursor = [0f32; 3];
+        for value in &mut cursor {
+            let mut bytes = [0u8; 4];
+            reader.read_exact(&mut bytes)?;
+            *value = f32::from_be_bytes(bytes);
+        }
+
+        let inside_block = crate::protocol::types::read_bool(reader)?;
+        let world_border_hit = crate::protocol::types::read_bool(reader)?;
+        let sequence = VarInt::read(reader)?;
+
+        Ok(UseItemOnPacket {
+            hand,
+            location,
+            face,
+            cursor_x: cursor[0],
+            cursor_y: cursor[1],
+            cursor_z: cursor[2],
+            inside_block,
+            world_border_hit,
+            sequence,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.hand.write(writer)?;
+        self.location.write(writer)?;
+        self.face.write(writer)?;
+        writer.write_all(&self.cursor_x.to_be_bytes())?;
+        writer.write_all(&self.cursor_y.to_be_bytes())?;
+        writer.write_all(&self.cursor_z.to_be_bytes())?;
+        crate::protocol::types::write_bool(self.inside_block, writer)?;
+        crate::protocol::types::write_bool(self.world_border_hit, writer)?;
+        self.sequence.write(writer)?;
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for UseItemOnPacket {}
+
+/// A set of item IDs, by tag or listed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdSet {
+    /// Every entry of a tag the client knows
+    Tag(Identifier),
+    /// The listed entries
+    Ids(Vec<VarInt>),
+}
+
+impl IdSet {
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        match VarInt::read(reader)?.0 {
+            0 => Ok(IdSet::Tag(Identifier::read(reader)?)),
+            kind => {
+                let count = usize::try_from(kind)
+                    .ok()
+                    .filter(|&count| count <= MAX_RECIPE_ARRAY_LENGTH)
+                    .ok_or_else(|| {
+                        crate::error::ServerError::Protocol(format!("Invalid ID set type {}", kind))
+                    })?;
+                (1..count)
+                    .map(|_| VarInt::read(reader))
+                    .collect::<Result<_>>()
+                    .map(IdSet::Ids)
+            }
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            IdSet::Tag(tag) => {
+                VarInt(0).write(writer)?;
+                tag.write(writer)
+            }
+            IdSet::Ids(ids) => {
+                VarInt(ids.len() as i32 + 1).write(writer)?;
+                ids.iter().try_for_each(|id| id.write(writer))
+            }
+        }
+    }
+}
+
+/// Items accepted by a kind of block, such as furnace inputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertySet {
+    /// Set ID, such as `minecraft:furnace_input`
+    pub id: Identifier,
+    /// Item IDs in the set
+    pub items: Vec<VarInt>,
+}
+
+impl PropertySet {
+    /// Items furnaces can smelt
+    pub const FURNACE_INPUT: &'static str = "minecraft:furnace_input";
+    /// Items blast furnaces can smelt
+    pub const BLAST_FURNACE_INPUT: &'static str = "minecraft:blast_furnace_input";
+}
+
+/// A stonecutter recipe as shown by the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StonecutterEntry {
+    /// Items the recipe accepts
+    pub ingredient: IdSet,
+    /// Item ID of the result
+    pub result_item: VarInt,
+    /// Number of items made
+    pub result_count: VarInt,
+}
+
+impl StonecutterEntry {
+    /// Slot display type of a full item stack
+    const ITEM_STACK_DISPLAY: i32 = 3;
+}
+
+/// Update recipes packet (clientbound)
+///
+/// Since 1.21.2 the client only needs the items furnaces and similar
+/// blocks accept, and the stonecutter recipes; everything else is matched
+/// on the server.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UpdateRecipesPacket {
+    /// Item sets accepted by blocks
+    pub property_sets: Vec<PropertySet>,
+    /// Stonecutter recipes
+    pub stonecutter_recipes: Vec<StonecutterEntry>,
+}
+
+impl Packet for UpdateRecipesPacket {
+    const ID: i32 = 0x7E;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut property_sets = Vec::new();
+        for _ in 0..read_length(reader, MAX_RECIPE_ARRAY_LENGTH)? {
+            let id = Identifier::read(reader)?;
+            let items = (0..read_length(reader, MAX_RECIPE_ARRAY_LENGTH)?)
+                .map(|_| VarInt::read(reader))
+                .collect::<Result<_>>()?;
+            property_sets.push(PropertySet { id, items });
+        }
+
+        let mut stonecutter_recipes = Vec::new();
+        for _ in 0..read_length(reader, MAX_RECIPE_ARRAY_LENGTH)? {
+            let ingredient = IdSet::read(reader)?;
+            let display = VarInt::read(reader)?.0;
+            let result_count = VarInt::read(reader)?;
+            let result_item = VarInt::read(reader)?;
+            let components = (VarInt::read(reader)?.0, VarInt::read(reader)?.0);
+            if display != StonecutterEntry::ITEM_STACK_DISPLAY || components != (0, 0) {
+                return Err(crate::error::ServerError::Protocol(
+                    "Only plain item stacks are supported as stonecutter results".to_string(),
+                ));
+            }
+            stonecutter_recipes.push(StonecutterEntry {
+                ingredient,
+                result_item,
+                result_count,
+            });
+        }
+        Ok(UpdateRecipesPacket {
+            property_sets,
+            stonecutter_recipes,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        VarInt(self.property_sets.len() as i32).write(writer)?;
+        for set in &self.property_sets {
+            set.id.write(writer)?;
+            VarInt(set.items.len() as i32).write(writer)?;
+            set.items.iter().try_for_each(|item| item.write(writer))?;
+        }
+        VarInt(self.stonecutter_recipes.len() as i32).write(writer)?;
+        for recipe in &self.stonecutter_recipes {
+            recipe.ingredient.write(writer)?;
+            VarInt(StonecutterEntry::ITEM_STACK_DISPLAY).write(writer)?;
+            recipe.result_count.write(writer)?;
+            recipe.result_item.write(writer)?;
+            // No components added or removed
+            VarInt(0).write(writer)?;
+            VarInt(0).write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for UpdateRecipesPacket {}
+
+/// Sections in a chunk column
+const SECTION_COUNT: usize = CHUNK_HEIGHT / SECTION_SIZE;
+
+/// Height of a chunk section
+const SECTION_SIZE: usize = 16;
+
+/// Blocks in a chunk section
+const SECTION_VOLUME: usize = SECTION_SIZE * SECTION_SIZE * SECTION_SIZE;
+
+/// Sections with light data: one below and one above the world besides the chunk's own
+const LIGHT_SECTION_COUNT: usize = SECTION_COUNT + 2;
+
+/// Bytes of light data per section, half a byte per block
+const LIGHT_ARRAY_SIZE: usize = SECTION_VOLUME / 2;
+
+/// Bits per block state once a section has too many for a palette
+const DIRECT_BLOCK_BITS: u32 = 15;
+
+/// Most bits per block state that still use a palette
+const MAX_INDIRECT_BLOCK_BITS: u32 = 8;
+
+/// Fewest bits per block state with a palette
+const MIN_INDIRECT_BLOCK_BITS: u32 = 4;
+
+/// Longest array accepted when reading chunk data, in elements
+const MAX_CHUNK_ARRAY_LENGTH: usize = crate::protocol::MAX_PACKET_SIZE;
+
+/// Longest array accepted when reading recipes, in elements
+const MAX_RECIPE_ARRAY_LENGTH: usize = 1 << 16;
+
+/// A heightmap sent with chunk data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmap {
+    /// Heightmap type (see the associated constants)
+    pub kind: VarInt,
+    /// Column heights, packed into longs
+    pub data: Vec<i64>,
+}
+
+impl Heightmap {
+    /// Highest block that is not air
+    pub const WORLD_SURFACE: i32 = 1;
+    /// Highest block that blocks motion or contains a fluid
+    pub const MOTION_BLOCKING: i32 = 4;
+}
+
+/// Light levels sent with chunk data
+///
+/// Masks hold one bit per section, starting with the section below the
+/// world.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LightData {
+    /// Sections with sky light data
+    pub sky_light_mask: Vec<i64>,
+    /// Sections with block light data
+    pub block_light_mask: Vec<i64>,
+    /// Sections whose sky light is all zero
+    pub empty_sky_light_mask: Vec<i64>,
+    /// Sections whose block light is all zero
+    pub empty_block_light_mask: Vec<i64>,
+    /// Sky light of each section in the sky light mask
+    pub sky_light: Vec<Vec<u8>>,
+    /// Block light of each section in the block light mask
+    pub block_light: Vec<Vec<u8>>,
+}
+
+impl LightData {
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(LightData {
+            sky_light_mask: read_long_array(reader)?,
+            block_light_mask: read_long_array(reader)?,
+            empty_sky_light_mask: read_long_array(reader)?,
+            empty_block_light_mask: read_long_array(reader)?,
+            sky_light: read_light_arrays(reader)?,
+            block_light: read_light_arrays(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_long_array(&self.sky_light_mask, writer)?;
+        write_long_array(&self.block_light_mask, writer)?;
+        write_long_array(&self.empty_sky_light_mask, writer)?;
+        write_long_array(&self.empty_block_light_mask, writer)?;
+        write_light_arrays(&self.sky_light, writer)?;
+        write_light_arrays(&self.block_light, writer)
+    }
+}
+
+/// Chunk data and update light packet (clientbound)
+///
+/// Sends a whole chunk column. Block entities are not supported yet, so
+/// none are sent and packets carrying any are rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkDataPacket {
+    /// Chunk X coordinate
+    pub chunk_x: i32,
+    /// Chunk Z coordinate
+    pub chunk_z: i32,
+    /// Heightmaps of the chunk
+    pub heightmaps: Vec<Heightmap>,
+    /// Serialized chunk sections, from the bottom of the world up
+    pub data: Vec<u8>,
+    /// Light levels
+    pub light: LightData,
+}
+
+impl ChunkDataPacket {
+    /// Build the packet for a chunk
+    ///
+    /// Block IDs are sent as stored, like in [`BlockChangePacket`]. The
+    /// chunk is lit as if every section were open to the sky.
+    pub fn from_chunk(chunk: &Chunk) -> Result<Self> {
+        let mut data = Vec::new();
+        for section in 0..SECTION_COUNT {
+            write_section(chunk, section, &mut data)?;
+        }
+
+        let heights: Vec<u64> = (0..CHUNK_SIZE * CHUNK_SIZE)
+            .map(|i| {
+                chunk
+                    .get_height(i % CHUNK_SIZE, i / CHUNK_SIZE)
+                    .map_or(0, |y| y as u64 + 1)
+            })
+            .collect();
+        let heights = pack_entries(&heights, bits_for(CHUNK_HEIGHT + 1));
+        let heightmaps = [Heightmap::WORLD_SURFACE, Heightmap::MOTION_BLOCKING]
+            .into_iter()
+            .map(|kind| Heightmap {
+                kind: VarInt(kind),
+                data: heights.clone(),
+            })
+            .collect();
+
+        let all_sections = vec![(1 << LIGHT_SECTION_COUNT) - 1];
+        let position = chunk.position();
+        Ok(ChunkDataPacket {
+            chunk_x: position.x,
+            chunk_z: position.z,
+            heightmaps,
+            data,
+            light: LightData {
+                sky_light_mask: all_sections.clone(),
+                empty_block_light_mask: all_sections,
+                sky_light: vec![vec![0xFF; LIGHT_ARRAY_SIZE]; LIGHT_SECTION_COUNT],
+                ..LightData::default()
+            },
+        })
+    }
+}
+
+impl Packet for ChunkDataPacket {
+    const ID: i32 = 0x27;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let chunk_x = crate::protocol::types::read_int(reader)?;
+        let chunk_z = crate::protocol::types::read_int(reader)?;
+        let heightmap_count = read_length(reader, MAX_CHUNK_ARRAY_LENGTH)?;
+        let mut heightmaps = Vec::new();
+        for _ in 0..heightmap_count {
+            let kind = VarInt::read(reader)?;
+            let data = read_long_array(reader)?;
+            heightmaps.push(Heightmap { kind, data });
+        }
+        let length = read_length(reader, MAX_CHUNK_ARRAY_LENGTH)?;
+        let data = read_bytes(reader, length)?;
+        if VarInt::read(reader)?.0 != 0 {
+            return Err(crate::error::ServerError::Protocol(
+                "Block entities in chunk data are not supported".to_string(),
+            ));
+        }
+        let light = LightData::read(reader)?;
+        Ok(ChunkDataPacket {
+            chunk_x,
+            chunk_z,
+            heightmaps,
+            data,
+            light,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_int(self.chunk_x, writer)?;
+        crate::protocol::types::write_int(self.chunk_z, writer)?;
+        VarInt(self.heightmaps.len() as i32).write(writer)?;
+        for heightmap in &self.heightmaps {
+            heightmap.kind.write(writer)?;
+            write_long_array(&heightmap.data, writer)?;
+        }
+        VarInt(self.data.len() as i32).write(writer)?;
+        writer.write_all(&self.data)?;
+        // No block entities
+        VarInt(0).write(writer)?;
+        self.light.write(writer)
+    }
+}
+
+impl ClientboundPacket for ChunkDataPacket {}
+
+/// Set center chunk packet (clientbound)
+///
+/// Tells the client which chunk its view distance is centered on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetCenterChunkPacket {
+    /// Chunk X coordinate
+    pub chunk_x: VarInt,
+    /// Chunk Z coordinate
+    pub chunk_z: VarInt,
+}
+
+impl Packet for SetCenterChunkPacket {
+    const ID: i32 = 0x57;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let chunk_x = VarInt::read(reader)?;
+        let chunk_z = VarInt::read(reader)?;
+        Ok(SetCenterChunkPacket { chunk_x, chunk_z })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.chunk_x.write(writer)?;
+        self.chunk_z.write(writer)
+    }
+}
+
+impl ClientboundPacket for SetCenterChunkPacket {}
+
+/// Unload chunk packet (clientbound)
+///
+/// Tells the client to forget a chunk that left its view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnloadChunkPacket {
+    /// Chunk Z coordinate
+    pub chunk_z: i32,
+    /// Chunk X coordinate
+    pub chunk_x: i32,
+}
+
+impl Packet for UnloadChunkPacket {
+    const ID: i32 = 0x21;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let chunk_z = read_int(reader)?;
+        let chunk_x = read_int(reader)?;
+        Ok(UnloadChunkPacket { chunk_z, chunk_x })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_int(self.chunk_z, writer)?;
+        write_int(self.chunk_x, writer)
+    }
+}
+
+impl ClientboundPacket for UnloadChunkPacket {}
+
+/// A player in a [`PlayerInfoUpdatePacket`]
+///
+/// Only the fields of the actions the packet carries are sent.
+#[derive(Debug, Clone)]
+pub struct PlayerInfoEntry {
+    /// Player UUID
+    pub uuid: McUuid,
+    /// Player name, for [`PlayerInfoUpdatePacket::ADD_PLAYER`]
+    pub name: McString,
+    /// Profile properties such as the skin, for
+    /// [`PlayerInfoUpdatePacket::ADD_PLAYER`]
+    pub properties: Vec<Property>,
+    /// Game mode ID
+    pub game_mode: VarInt,
+    /// Whether the player is shown in the tab list
+    pub listed: bool,
+    /// Latency in milliseconds
+    pub latency: VarInt,
+    /// Name shown in the tab list instead of the player name
+    pub display_name: Option<JsonTextComponent>,
+}
+
+/// Player info update packet (clientbound)
+///
+/// Adds players to the client's player list or updates their entries,
+/// including the latency shown in the tab list.
+#[derive(Debug, Clone)]
+pub struct PlayerInfoUpdatePacket {
+    /// Actions carried, as a bit mask of the associated constants
+    pub actions: u8,
+    /// Players updated
+    pub entries: Vec<PlayerInfoEntry>,
+}
+
+impl PlayerInfoUpdatePacket {
+    /// Add the player with their name and properties
+    pub const ADD_PLAYER: u8 = 0x01;
+    /// Update the game mode
+    pub const UPDATE_GAME_MODE: u8 = 0x04;
+    /// Update whether the player is listed
+    pub const UPDATE_LISTED: u8 = 0x08;
+    /// Update the latency
+    pub const UPDATE_LATENCY: u8 = 0x10;
+    /// Update the display name
+    pub const UPDATE_DISPLAY_NAME: u8 = 0x20;
+
+    /// Actions the server can send; chat sessions, list order and hat
+    /// visibility are not supported
+    const SUPPORTED_ACTIONS: u8 = Self::ADD_PLAYER
+        | Self::UPDATE_GAME_MODE
+        | Self::UPDATE_LISTED
+        | Self::UPDATE_LATENCY
+        | Self::UPDATE_DISPLAY_NAME;
+
+    fn read_entry<R: Read>(&self, reader: &mut R) -> Result<PlayerInfoEntry> {
+        let uuid = read_uuid(reader)?;
+        let mut entry = PlayerInfoEntry {
+            uuid,
+            name: McString(String::new()),
+            properties: Vec::new(),
+            game_mode: VarInt(0),
+            listed: false,
+            latency: VarInt(0),
+            display_name: None,
+        };
+        if self.actions & Self::ADD_PLAYER != 0 {
+            entry.name = McString::read_with_max_length(reader, 16)?;
+            let count = read_length(reader, Property::MAX_COUNT)?;
+            for _ in 0..count {
+                entry.properties.push(Property::read(reader)?);
+            }
+        }
+        if self.actions & Self::UPDATE_GAME_MODE != 0 {
+            entry.game_mode = VarInt::read(reader)?;
+        }
+        if self.actions & Self::UPDATE_LISTED != 0 {
+            entry.listed = read_bool(reader)?;
+        }
+        if self.actions & Self::UPDATE_LATENCY != 0 {
+            entry.latency = VarInt::read(reader)?;
+        }
+        if self.actions & Self::UPDATE_DISPLAY_NAME != 0 && read_bool(reader)? {
+            entry.display_name = Some(JsonTextComponent::read_nbt(reader)?);
+        }
+        Ok(entry)
+    }
+
+    fn write_entry<W: Write>(&self, entry: &PlayerInfoEntry, writer: &mut W) -> Result<()> {
+        write_uuid(&entry.uuid, writer)?;
+        if self.actions & Self::ADD_PLAYER != 0 {
+            entry.name.write(writer)?;
+            VarInt(entry.properties.len() as i32).write(writer)?;
+            for property in &entry.properties {
+                property.write(writer)?;
+            }
+        }
+        if self.actions & Self::UPDATE_GAME_MODE != 0 {
+            entry.game_mode.write(writer)?;
+        }
+        if self.actions & Self::UPDATE_LISTED != 0 {
+            write_bool(entry.listed, writer)?;
+        }
+        if self.actions & Self::UPDATE_LATENCY != 0 {
+            entry.latency.write(writer)?;
+        }
+        if self.actions & Self::UPDATE_DISPLAY_NAME != 0 {
+            write_bool(entry.display_name.is_some(), writer)?;
+            if let Some(display_name) = &entry.display_name {
+                display_name.write_nbt(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Packet for PlayerInfoUpdatePacket {
+    const ID: i32 = 0x3F;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let actions = crate::protocol::types::read_unsigned_byte(reader)?;
+        if actions & !Self::SUPPORTED_ACTIONS != 0 {
+            return Err(crate::error::ServerError::Protocol(format!(
+                "Unsupported player info actions 0x{:02X}",
+                actions
+            )));
+        }
+        let mut packet = PlayerInfoUpdatePacket {
+            actions,
+            entries: Vec::new(),
+        };
+        let count = read_length(reader, crate::protocol::MAX_PACKET_SIZE)?;
+        for _ in 0..count {
+            let entry = packet.read_entry(reader)?;
+            packet.entries.push(entry);
+        }
+        Ok(packet)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        crate::protocol::types::write_unsigned_byte(self.actions, writer)?;
+        VarInt(self.entries.len() as i32).write(writer)?;
+        for entry in &self.entries {
+            self.write_entry(entry, writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for PlayerInfoUpdatePacket {}
+
+/// Player info remove packet (clientbound)
+///
+/// Removes players from the client's player list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerInfoRemovePacket {
+    /// Players removed
+    pub uuids: Vec<McUuid>,
+}
+
+impl Packet for PlayerInfoRemovePacket {
+    const ID: i32 = 0x3E;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let count = read_length(reader, crate::protocol::MAX_PACKET_SIZE)?;
+        let mut uuids = Vec::new();
+        for _ in 0..count {
+            uuids.push(read_uuid(reader)?);
+        }
+        Ok(PlayerInfoRemovePacket { uuids })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        VarInt(self.uuids.len() as i32).write(writer)?;
+        for uuid in &self.uuids {
+            write_uuid(uuid, writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for PlayerInfoRemovePacket {}
+
+/// Spawn entity packet (clientbound)
+///
+/// Shows a new entity other than a player to clients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnEntityPacket {
+    /// Entity ID
+    pub entity_id: VarInt,
+    /// Entity UUID
+    pub uuid: McUuid,
+    /// Entity type ID
+    pub entity_type: VarInt,
+    /// X coordinate
+    pub x: f64,
+    /// Y coordinate
+    pub y: f64,
+    /// Z coordinate
+    pub z: f64,
+    /// Pitch in 256ths of a full turn
+    pub pitch: u8,
+    /// Yaw in 256ths of a full turn
+    pub yaw: u8,
+    /// Head yaw in 256ths of a full turn
+    pub head_yaw: u8,
+    /// Type-specific data, such as the block state of a falling block
+    pub data: VarInt,
+    /// Velocity in 8000ths of a block per tick
+    pub velocity: [i16; 3],
+}
+
+impl Packet for SpawnEntityPacket {
+    const ID: i32 = 0x01;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let uuid = read_uuid(reader)?;
+        let entity_type = VarInt::read(reader)?;
+        let (x, y, z) = (read_f64(reader)?, read_f64(reader)?, read_f64(reader)?);
+        let mut angles = [0u8; 3];
+        reader.read_exact(&mut angles)?;
+        let data = VarInt::read(reader)?;
+        let mut velocity = [0i16; 3];
+        for value in &mut velocity {
+            let mut bytes = [0u8; 2];
+            reader.read_exact(&mut bytes)?;
+            *value = i16::from_be_bytes(bytes);
+        }
+        Ok(SpawnEntityPacket {
+            entity_id,
+            uuid,
+            entity_type,
+            x,
+            y,
+            z,
+            pitch: angles[0],
+            yaw: angles[1],
+            head_yaw: angles[2],
+            data,
+            velocity,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        write_uuid(&self.uuid, writer)?;
+        self.entity_type.write(writer)?;
+        writer.write_all(&self.x.to_be_bytes())?;
+        writer.write_all(&self.y.to_be_bytes())?;
+        writer.write_all(&self.z.to_be_bytes())?;
+        writer.write_all(&[self.pitch, self.yaw, self.head_yaw])?;
+        self.data.write(writer)?;
+        for value in self.velocity {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for SpawnEntityPacket {}
+
+/// Set passengers packet (clientbound)
+///
+/// Replaces the list of entities riding a vehicle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetPassengersPacket {
+    /// Vehicle entity ID
+    pub entity_id: VarInt,
+    /// Entity IDs of the passengers, the controlling one first
+    pub passengers: Vec<VarInt>,
+}
+
+impl Packet for SetPassengersPacket {
+    const ID: i32 = 0x64;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let count = read_length(reader, crate::protocol::MAX_PACKET_SIZE)?;
+        let mut passengers = Vec::new();
+        for _ in 0..count {
+            passengers.push(VarInt::read(reader)?);
+        }
+        Ok(SetPassengersPacket {
+            entity_id,
+            passengers,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        VarInt(self.passengers.len() as i32).write(writer)?;
+        for passenger in &self.passengers {
+            passenger.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for SetPassengersPacket {}
+
+/// Entity position sync packet (clientbound)
+///
+/// Moves an entity to an absolute position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityPositionSyncPacket {
+    /// Entity ID
+    pub entity_id: VarInt,
+    /// X coordinate
+    pub x: f64,
+    /// Y coordinate
+    pub y: f64,
+    /// Z coordinate
+    pub z: f64,
+    /// Velocity in blocks per tick
+    pub velocity: [f64; 3],
+    /// Yaw in degrees
+    pub yaw: f32,
+    /// Pitch in degrees
+    pub pitch: f32,
+    /// Whether the entity is on the ground
+    pub on_ground: bool,
+}
+
+impl Packet for EntityPositionSyncPacket {
+    const ID: i32 = 0x1F;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let (x, y, z) = (read_f64(reader)?, read_f64(reader)?, read_f64(reader)?);
+        let velocity = [read_f64(reader)?, read_f64(reader)?, read_f64(reader)?];
+        let yaw = read_f32(reader)?;
+        let pitch = read_f32(reader)?;
+        let on_ground = read_bool(reader)?;
+        Ok(EntityPositionSyncPacket {
+            entity_id,
+            x,
+            y,
+            z,
+            velocity,
+            yaw,
+            pitch,
+            on_ground,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        for value in [self.x, self.y, self.z] {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        for value in self.velocity {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        writer.write_all(&self.yaw.to_be_bytes())?;
+        writer.write_all(&self.pitch.to_be_bytes())?;
+        write_bool(self.on_ground, writer)
+    }
+}
+
+impl ClientboundPacket for EntityPositionSyncPacket {}
+
+/// Update entity position packet (clientbound)
+///
+/// Moves an entity by less than 8 blocks along each axis without turning
+/// it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateEntityPositionPacket {
+    /// Entity ID
+    pub entity_id: VarInt,
+    /// Change in each coordinate, in 4096ths of a block
+    pub delta: [i16; 3],
+    /// Whether the entity is on the ground
+    pub on_ground: bool,
+}
+
+impl Packet for UpdateEntityPositionPacket {
+    const ID: i32 = 0x2E;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let mut delta = [0i16; 3];
+        for value in &mut delta {
+            let mut bytes = [0u8; 2];
+            reader.read_exact(&mut bytes)?;
+            *value = i16::from_be_bytes(bytes);
+        }
+        let on_ground = read_bool(reader)?;
+        Ok(UpdateEntityPositionPacket {
+            entity_id,
+            delta,
+            on_ground,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        for value in self.delta {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        write_bool(self.on_ground, writer)
+    }
+}
+
+impl ClientboundPacket for UpdateEntityPositionPacket {}
+
+/// Update entity position and rotation packet (clientbound)
+///
+/// Moves an entity by less than 8 blocks along each axis and turns it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateEntityPositionAndRotationPacket {
+    /// Entity ID
+    pub entity_id: VarInt,
+    /// Change in each coordinate, in 4096ths of a block
+    pub delta: [i16; 3],
+    /// Yaw in 256ths of a turn
+    pub yaw: u8,
+    /// Pitch in 256ths of a turn
+    pub pitch: u8,
+    /// Whether the entity is on the ground
+    pub on_ground: bool,
+}
+
+impl Packet for UpdateEntityPositionAndRotationPacket {
+    const ID: i32 = 0x2F;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let mut delta = [0i16; 3];
+        for value in &mut delta {
+            let mut bytes = [0u8; 2];
+            reader.read_exact(&mut bytes)?;
+            *value = i16::from_be_bytes(bytes);
+        }
+        let mut angles = [0u8; 2];
+        reader.read_exact(&mut angles)?;
+        let on_ground = read_bool(reader)?;
+        Ok(UpdateEntityPositionAndRotationPacket {
+            entity_id,
+            delta,
+            yaw: angles[0],
+            pitch: angles[1],
+            on_ground,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        for value in self.delta {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        writer.write_all(&[self.yaw, self.pitch])?;
+        write_bool(self.on_ground, writer)
+    }
+}
+
+impl ClientboundPacket for UpdateEntityPositionAndRotationPacket {}
+
+/// Update entity rotation packet (clientbound)
+///
+/// Turns an entity's body without moving it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateEntityRotationPacket {
+    /// Entity ID
+    pub entity_id: VarInt,
+    /// Yaw in 256ths of a turn
+    pub yaw: u8,
+    /// Pitch in 256ths of a turn
+    pub pitch: u8,
+    /// Whether the entity is on the ground
+    pub on_ground: bool,
+}
+
+impl Packet for UpdateEntityRotationPacket {
+    const ID: i32 = 0x31;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let mut angles = [0u8; 2];
+        reader.read_exact(&mut angles)?;
+        let on_ground = read_bool(reader)?;
+        Ok(UpdateEntityRotationPacket {
+            entity_id,
+            yaw: angles[0],
+            pitch: angles[1],
+            on_ground,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        writer.write_all(&[self.yaw, self.pitch])?;
+        write_bool(self.on_ground, writer)
+    }
+}
+
+impl ClientboundPacket for UpdateEntityRotationPacket {}
+
+/// Set head rotation packet (clientbound)
+///
+/// Turns an entity's head, which the rotation packets leave alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetHeadRotationPacket {
+    /// Entity ID
+    pub entity_id: VarInt,
+    /// Head yaw in 256ths of a turn
+    pub head_yaw: u8,
+}
+
+impl Packet for SetHeadRotationPacket {
+    const ID: i32 = 0x4C;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let head_yaw = crate::protocol::types::read_unsigned_byte(reader)?;
+        Ok(SetHeadRotationPacket {
+            entity_id,
+            head_yaw,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        crate::protocol::types::write_unsigned_byte(self.head_yaw, writer)
+    }
+}
+
+impl ClientboundPacket for SetHeadRotationPacket {}
+
+/// Move vehicle packet (serverbound)
+///
+/// Sent by the player controlling a vehicle after moving it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveVehiclePacket {
+    /// X coordinate
+    pub x: f64,
+    /// Y coordinate
+    pub y: f64,
+    /// Z coordinate
+    pub z: f64,
+    /// Yaw in degrees
+    pub yaw: f32,
+    /// Pitch in degrees
+    pub pitch: f32,
+    /// Whether the vehicle is on the ground
+    pub on_ground: bool,
+}
+
+impl Packet for MoveVehiclePacket {
+    const ID: i32 = 0x21;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let (x, y, z) = (read_f64(reader)?, read_f64(reader)?, read_f64(reader)?);
+        let yaw = read_f32(reader)?;
+        let pitch = read_f32(reader)?;
+        let on_ground = read_bool(reader)?;
+        Ok(MoveVehiclePacket {
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            on_ground,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        for value in [self.x, self.y, self.z] {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        writer.write_all(&self.yaw.to_be_bytes())?;
+        writer.write_all(&self.pitch.to_be_bytes())?;
+        write_bool(self.on_ground, writer)
+    }
+}
+
+impl ServerboundPacket for MoveVehiclePacket {}
+
+/// Interact packet (serverbound)
+///
+/// Sent when the player attacks or right-clicks an entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteractPacket {
+    /// Target entity ID
+    pub entity_id: VarInt,
+    /// Interaction type (see the associated constants)
+    pub kind: VarInt,
+    /// Point on the entity that was clicked, for [`Self::INTERACT_AT`]
+    pub target: Option<[f32; 3]>,
+    /// Hand used (0=Main hand, 1=Off hand), unless attacking
+    pub hand: Option<VarInt>,
+    /// Whether the player is sneaking
+    pub sneaking: bool,
+}
+
+impl InteractPacket {
+    /// Right-click the entity
+    pub const INTERACT: i32 = 0;
+    /// Attack the entity
+    pub const ATTACK: i32 = 1;
+    /// Right-click a point on the entity
+    pub const INTERACT_AT: i32 = 2;
+}
+
+impl Packet for InteractPacket {
+    const ID: i32 = 0x19;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let kind = VarInt::read(reader)?;
+        let target = match kind.0 {
+            Self::INTERACT_AT => Some([read_f32(reader)?, read_f32(reader)?, read_f32(reader)?]),
+            Self::INTERACT | Self::ATTACK => None,
+            other => {
+                return Err(crate::error::ServerError::Protocol(format!(
+                    "Invalid interaction type {}",
+                    other
+                )));
+            }
+        };
+        let hand = if kind.0 == Self::ATTACK {
+            None
+        } else {
+            Some(VarInt::read(reader)?)
+        };
+        let sneaking = read_bool(reader)?;
+        Ok(InteractPacket {
+            entity_id,
+            kind,
+            target,
+            hand,
+            sneaking,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        self.kind.write(writer)?;
+        if let Some(target) = self.target {
+            for value in target {
+                writer.write_all(&value.to_be_bytes())?;
+            }
+        }
+        if let Some(hand) = self.hand {
+            hand.write(writer)?;
+        }
+        write_bool(self.sneaking, writer)
+    }
+}
+
+impl ServerboundPacket for InteractPacket {}
+
+/// Explosion packet (clientbound)
+///
+/// Shows an explosion and pushes the receiving player. Destroyed blocks are
+/// sent separately as block changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplodePacket {
+    /// X coordinate of the center
+    pub x: f64,
+    /// Y coordinate of the center
+    pub y: f64,
+    /// Z coordinate of the center
+    pub z: f64,
+    /// Velocity added to the receiving player, if they were hit
+    pub player_knockback: Option<[f64; 3]>,
+    /// Particle type shown at the center
+    pub particle: VarInt,
+    /// Sound event identifier, sent inline
+    pub sound: McString,
+    /// Fixed range of the sound, instead of one based on its volume
+    pub sound_range: Option<f32>,
+}
+
+impl ExplodePacket {
+    /// Particle of small explosions
+    pub const EXPLOSION: i32 = 22;
+    /// Particle of large explosions
+    pub const EXPLOSION_EMITTER: i32 = 21;
+    /// Sound of explosions
+    pub const EXPLODE_SOUND: &str = "minecraft:entity.generic.explode";
+}
+
+impl Packet for ExplodePacket {
+    const ID: i32 = 0x20;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let (x, y, z) = (read_f64(reader)?, read_f64(reader)?, read_f64(reader)?);
+        let player_knockback = if read_bool(reader)? {
+            Some([read_f64(reader)?, read_f64(reader)?, read_f64(reader)?])
+        } else {
+            None
+        };
+        let particle = VarInt::read(reader)?;
+        // Sound events are either a registry ID plus one or, with zero, inline
+        let sound_id = VarInt::read(reader)?;
+        if sound_id.0 != 0 {
+            return Err(crate::error::ServerError::Protocol(format!(
+                "Unsupported sound event reference {}",
+                sound_id.0
+            )));
+        }
+        let sound = McString::read(reader)?;
+        let sound_range = if read_bool(reader)? {
+            Some(read_f32(reader)?)
+        } else {
+            None
+        };
+        Ok(ExplodePacket {
+            x,
+            y,
+            z,
+            player_knockback,
+            particle,
+            sound,
+            sound_range,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        for value in [self.x, self.y, self.z] {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        write_bool(self.player_knockback.is_some(), writer)?;
+        if let Some(knockback) = self.player_knockback {
+            for value in knockback {
+                writer.write_all(&value.to_be_bytes())?;
+            }
+        }
+        self.particle.write(writer)?;
+        VarInt(0).write(writer)?;
+        self.sound.write(writer)?;
+        write_bool(self.sound_range.is_some(), writer)?;
+        if let Some(range) = self.sound_range {
+            writer.write_all(&range.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for ExplodePacket {}
+
+/// Remove entities packet (clientbound)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoveEntitiesPacket {
+    /// IDs of the removed entities
+    pub entity_ids: Vec<VarInt>,
+}
+
+impl Packet for RemoveEntitiesPacket {
+    const ID: i32 = 0x46;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let count = read_length(reader, crate::protocol::MAX_PACKET_SIZE)?;
+        let mut entity_ids = Vec::new();
+        for _ in 0..count {
+            entity_ids.push(VarInt::read(reader)?);
+        }
+        Ok(RemoveEntitiesPacket { entity_ids })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        VarInt(self.entity_ids.len() as i32).write(writer)?;
+        for entity_id in &self.entity_ids {
+            entity_id.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for RemoveEntitiesPacket {}
+
+/// Player action packet (serverbound)
+///
+/// Sent when the player digs, drops items or swaps hands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerActionPacket {
+    /// Action (see the associated constants)
+    pub status: VarInt,
+    /// Position of the block acted on
+    pub location: Position,
+    /// Face of the block acted on (0=Bottom, 1=Top, 2=North, 3=South, 4=West, 5=East)
+    pub face: u8,
+    /// Block change sequence number
+    pub sequence: VarInt,
+}
+
+impl PlayerActionPacket {
+    /// Start digging a block
+    pub const START_DIGGING: i32 = 0;
+    /// Stop digging before the block broke
+    pub const CANCEL_DIGGING: i32 = 1;
+    /// Finish digging a block
+    pub const FINISH_DIGGING: i32 = 2;
+    /// Drop the held item stack
+    pub const DROP_ITEM_STACK: i32 = 3;
+    /// Drop one held item
+    pub const DROP_ITEM: i32 = 4;
+    /// Stop using an item, such as a bow
+    pub const RELEASE_USE_ITEM: i32 = 5;
+    /// Swap the items in both hands
+    pub const SWAP_HANDS: i32 = 6;
+}
+
+impl Packet for PlayerActionPacket {
+    const ID: i32 = 0x28;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let status = VarInt::read(reader)?;
+        let location = Position::read(reader)?;
+        let face = crate::protocol::types::read_unsigned_byte(reader)?;
+        let sequence = VarInt::read(reader)?;
+        Ok(PlayerActionPacket {
+            status,
+            location,
+            face,
+            sequence,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.status.write(writer)?;
+        self.location.write(writer)?;
+        crate::protocol::types::write_unsigned_byte(self.face, writer)?;
+        self.sequence.write(writer)
+    }
+}
+
+impl ServerboundPacket for PlayerActionPacket {}
+
+/// Acknowledge block change packet (clientbound)
+///
+/// Confirms the block changes a client predicted up to a sequence number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcknowledgeBlockChangePacket {
+    /// Sequence number of the last handled change
+    pub sequence: VarInt,
+}
+
+impl Packet for AcknowledgeBlockChangePacket {
+    const ID: i32 = 0x04;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let sequence = VarInt::read(reader)?;
+        Ok(AcknowledgeBlockChangePacket { sequence })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.sequence.write(writer)
+    }
+}
+
+impl ClientboundPacket for AcknowledgeBlockChangePacket {}
+
+/// Synchronize player position packet (clientbound)
+///
+/// Teleports the player, who confirms with the teleport ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynchronizePlayerPositionPacket {
+    /// Teleport ID the client confirms
+    pub teleport_id: VarInt,
+    /// X coordinate
+    pub x: f64,
+    /// Y coordinate
+    pub y: f64,
+    /// Z coordinate
+    pub z: f64,
+    /// Velocity in blocks per tick
+    pub velocity: [f64; 3],
+    /// Yaw in degrees
+    pub yaw: f32,
+    /// Pitch in degrees
+    pub pitch: f32,
+    /// Bit field of values that are relative instead of absolute
+    pub flags: i32,
+}
+
+impl Packet for SynchronizePlayerPositionPacket {
+    const ID: i32 = 0x41;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let teleport_id = VarInt::read(reader)?;
+        let (x, y, z) = (read_f64(reader)?, read_f64(reader)?, read_f64(reader)?);
+        let velocity = [read_f64(reader)?, read_f64(reader)?, read_f64(reader)?];
+        let yaw = read_f32(reader)?;
+        let pitch = read_f32(reader)?;
+        let flags = crate::protocol::types::read_int(reader)?;
+        Ok(SynchronizePlayerPositionPacket {
+            teleport_id,
+            x,
+            y,
+            z,
+            velocity,
+            yaw,
+            pitch,
+            flags,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.teleport_id.write(writer)?;
+        for value in [self.x, self.y, self.z] {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        for value in self.velocity {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        writer.write_all(&self.yaw.to_be_bytes())?;
+        writer.write_all(&self.pitch.to_be_bytes())?;
+        crate::protocol::types::write_int(self.flags, writer)
+    }
+}
+
+impl ClientboundPacket for SynchronizePlayerPositionPacket {}
+
+/// Set camera packet (clientbound)
+///
+/// Makes the player view the world from another entity, or from
+/// themselves again when given their own entity ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetCameraPacket {
+    /// Entity to view from
+    pub camera_id: VarInt,
+}
+
+impl Packet for SetCameraPacket {
+    const ID: i32 = 0x56;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let camera_id = VarInt::read(reader)?;
+        Ok(SetCameraPacket { camera_id })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.camera_id.write(writer)
+    }
+}
+
+impl ClientboundPacket for SetCameraPacket {}
+
+/// Teleport to entity packet (serverbound)
+///
+/// Sent when a spectator picks a player from the spectator menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeleportToEntityPacket {
+    /// UUID of the player to teleport to
+    pub target: McUuid,
+}
+
+impl Packet for TeleportToEntityPacket {
+    const ID: i32 = 0x3D;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let target = read_uuid(reader)?;
+        Ok(TeleportToEntityPacket { target })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_uuid(&self.target, writer)
+    }
+}
+
+impl ServerboundPacket for TeleportToEntityPacket {}
+
+/// How a chat message was filtered
+///
+/// Partially filtered messages carry a bit set with a bit for every
+/// UTF-16 code unit of the message; the client hides the units whose bit
+/// is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FilterMask {
+    /// Shown as sent
+    #[default]
+    PassThrough,
+    /// Hidden entirely
+    FullyFiltered,
+    /// Hidden where the bit set has a bit set
+    PartiallyFiltered(Vec<i64>),
+}
+
+impl FilterMask {
+    /// Longest bit set accepted, enough for the longest chat message
+    const MAX_BITS_LENGTH: usize = ChatMessagePacket::MAX_MESSAGE_LENGTH / 64 + 1;
+
+    /// Mask hiding the given UTF-16 code units of a message
+    pub fn hiding(units: impl IntoIterator<Item = usize>, length: usize) -> Self {
+        let mut bits = vec![0i64; length.div_ceil(64)];
+        let mut hidden = 0;
+        for unit in units.into_iter().filter(|&unit| unit < length) {
+            let word = &mut bits[unit / 64];
+            if *word & (1 << (unit % 64)) == 0 {
+                *word |= 1 << (unit % 64);
+                hidden += 1;
+            }
+        }
+        match hidden {
+            0 => FilterMask::PassThrough,
+            _ if hidden == length => FilterMask::FullyFiltered,
+            _ => FilterMask::PartiallyFiltered(bits),
+        }
+    }
+
+    /// Whether any of the message is hidden
+    pub fn is_filtered(&self) -> bool {
+        *self != FilterMask::PassThrough
+    }
+
+    /// The message as a player with filtering enabled sees it
+    ///
+    /// Hidden characters are replaced with `#`. Returns `None` when the
+    /// whole message is hidden.
+    pub fn apply(&self, message: &str) -> Option<String> {
+        let bits = match self {
+            FilterMask::PassThrough => return Some(message.to_string()),
+            FilterMask::FullyFiltered => return None,
+            FilterMask::PartiallyFiltered(bits) => bits,
+        };
+        let is_hidden = |unit: usize| {
+            bits.get(unit / 64)
+                .is_some_and(|word| word & (1 << (unit % 64)) != 0)
+        };
+        let mut unit = 0;
+        let mut filtered = String::with_capacity(message.len());
+        for c in message.chars() {
+            filtered.push(if is_hidden(unit) { '#' } else { c });
+            unit += c.len_utf16();
+        }
+        Some(filtered)
+    }
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        match VarInt::read(reader)?.0 {
+            0 => Ok(FilterMask::PassThrough),
+            1 => Ok(FilterMask::FullyFiltered),
+            2 => {
+                let length = read_length(reader, Self::MAX_BITS_LENGTH)?;
+                let bits = (0..length)
+                    .map(|_| read_long(reader))
+                    .collect::<Result<_>>()?;
+                Ok(FilterMask::PartiallyFiltered(bits))
+            }
+            other => Err(crate::error::ServerError::Protocol(format!(
+                "Unknown filter type {}",
+                other
+            ))),
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            FilterMask::PassThrough => VarInt(0).write(writer),
+            FilterMask::FullyFiltered => VarInt(1).write(writer),
+            FilterMask::PartiallyFiltered(bits) => {
+                VarInt(2).write(writer)?;
+                write_long_array(bits, writer)
+            }
+        }
+    }
+}
+
+/// How a chat type decorates messages for display or narration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatDecoration {
+    /// Translation key of the decorated message
+    pub translation_key: McString,
+    /// Parameters of the translation, as [`ChatDecoration::SENDER`] and friends
+    pub parameters: Vec<VarInt>,
+}
+
+impl ChatDecoration {
+    /// Parameter filled with the sender's name
+    pub const SENDER: i32 = 0;
+    /// Parameter filled with the target's name
+    pub const TARGET: i32 = 1;
+    /// Parameter filled with the message
+    pub const CONTENT: i32 = 2;
+    /// Most parameters a decoration may have
+    const MAX_PARAMETERS: usize = 3;
+
+    /// Decoration shown for ordinary chat: `<sender> message`
+    pub fn chat() -> Self {
+        Self::with_sender_and_content("chat.type.text")
+    }
+
+    /// Narration of ordinary chat
+    pub fn chat_narration() -> Self {
+        Self::with_sender_and_content("chat.type.text.narrate")
+    }
+
+    /// Decoration translating a key with the sender and the message
+    fn with_sender_and_content(key: &str) -> Self {
+        ChatDecoration {
+            translation_key: McString(key.to_string()),
+            parameters: vec![VarInt(Self::SENDER), VarInt(Self::CONTENT)],
+        }
+    }
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let translation_key = McString::read(reader)?;
+        let count = read_length(reader, Self::MAX_PARAMETERS)?;
+        let parameters = (0..count)
+            .map(|_| VarInt::read(reader))
+            .collect::<Result<_>>()?;
+        // The style is not kept; decorations are always sent unstyled
+        crate::protocol::nbt::read_network(reader)?;
+        Ok(ChatDecoration {
+            translation_key,
+            parameters,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.translation_key.write(writer)?;
+        VarInt(self.parameters.len() as i32).write(writer)?;
+        for parameter in &self.parameters {
+            parameter.write(writer)?;
+        }
+        crate::protocol::nbt::write_network(
+            &crate::protocol::nbt::Tag::Compound(Default::default()),
+            writer,
+        )
+    }
+}
+
+/// Player chat message packet (clientbound)
+///
+/// Messages are sent unsigned and without previous messages, as the
+/// server does not enforce secure chat. The chat type is always sent
+/// inline, since no chat type registry is sent during configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerChatMessagePacket {
+    /// Number of chat messages the receiving client was sent before this one
+    pub global_index: VarInt,
+    /// Player who sent the message
+    pub sender: McUuid,
+    /// Number of chat messages the sender sent before this one
+    pub index: VarInt,
+    /// Optional signature
+    pub signature: Option<Vec<u8>>,
+    /// Message as sent
+    pub message: McString,
+    /// Timestamp
+    pub timestamp: i64,
+    /// Salt for message signing
+    pub salt: i64,
+    /// Content shown instead of the message, if any
+    pub unsigned_content: Option<JsonTextComponent>,
+    /// Parts of the message hidden from players with filtering enabled
+    pub filter: FilterMask,
+    /// How the message is displayed
+    pub chat: ChatDecoration,
+    /// How the message is narrated
+    pub narration: ChatDecoration,
+    /// Name of the sender
+    pub sender_name: JsonTextComponent,
+    /// Name of the target, for chat types that have one
+    pub target_name: Option<JsonTextComponent>,
+}
+
+impl PlayerChatMessagePacket {
+    /// Chat type ID meaning the chat type follows inline
+    const INLINE_CHAT_TYPE: i32 = 0;
+}
+
+impl Packet for PlayerChatMessagePacket {
+    const ID: i32 = 0x3A;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let global_index = VarInt::read(reader)?;
+        let sender = read_uuid(reader)?;
+        let index = VarInt::read(reader)?;
+        let signature = if read_bool(reader)? {
+            let mut signature = vec![0; ChatMessagePacket::SIGNATURE_LENGTH];
+            reader.read_exact(&mut signature)?;
+            Some(signature)
+        } else {
+            None
+        };
+        let message =
+            McString::read_with_max_length(reader, ChatMessagePacket::MAX_MESSAGE_LENGTH)?;
+        let timestamp = read_long(reader)?;
+        let salt = read_long(reader)?;
+        if VarInt::read(reader)?.0 != 0 {
+            return Err(crate::error::ServerError::Protocol(
+                "Previous chat messages are not supported".to_string(),
+            ));
+        }
+        let unsigned_content = if read_bool(reader)? {
+            Some(JsonTextComponent::read_nbt(reader)?)
+        } else {
+            None
+        };
+        let filter = FilterMask::read(reader)?;
+        if VarInt::read(reader)?.0 != Self::INLINE_CHAT_TYPE {
+            return Err(crate::error::ServerError::Protocol(
+                "Registered chat types are not supported".to_string(),
+            ));
+        }
+        let chat = ChatDecoration::read(reader)?;
+        let narration = ChatDecoration::read(reader)?;
+        let sender_name = JsonTextComponent::read_nbt(reader)?;
+        let target_name = if read_bool(reader)? {
+            Some(JsonTextComponent::read_nbt(reader)?)
+        } else {
+            None
+        };
+        Ok(PlayerChatMessagePacket {
+            global_index,
+            sender,
+            index,
+            signature,
+            message,
+            timestamp,
+            salt,
+            unsigned_content,
+            filter,
+            chat,
+            narration,
+            sender_name,
+            target_name,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.global_index.write(writer)?;
+        write_uuid(&self.sender, writer)?;
+        self.index.write(writer)?;
+        write_bool(self.signature.is_some(), writer)?;
+        if let Some(ref signature) = self.signature {
+            writer.write_all(signature)?;
+        }
+        self.message.write(writer)?;
+        crate::protocol::types::write_long(self.timestamp, writer)?;
+        crate::protocol::types::write_long(self.salt, writer)?;
+        VarInt(0).write(writer)?;
+        write_bool(self.unsigned_content.is_some(), writer)?;
+        if let Some(ref content) = self.unsigned_content {
+            content.write_nbt(writer)?;
+        }
+        self.filter.write(writer)?;
+        VarInt(Self::INLINE_CHAT_TYPE).write(writer)?;
+        self.chat.write(writer)?;
+        self.narration.write(writer)?;
+        self.sender_name.write_nbt(writer)?;
+        write_bool(self.target_name.is_some(), writer)?;
+        if let Some(ref target) = self.target_name {
+            target.write_nbt(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for PlayerChatMessagePacket {}
+
+/// Set equipment packet (clientbound)
+///
+/// Shows what an entity holds and wears.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetEquipmentPacket {
+    /// Entity whose equipment changed
+    pub entity_id: VarInt,
+    /// Protocol slot IDs and the items in them; never empty
+    pub equipment: Vec<(u8, Option<ItemStack>)>,
+}
+
+impl SetEquipmentPacket {
+    /// Bit set on a slot ID when another entry follows
+    const MORE_ENTRIES: u8 = 0x80;
+    /// Most entries a packet may carry, one per equipment slot
+    const MAX_ENTRIES: usize = 8;
+}
+
+impl Packet for SetEquipmentPacket {
+    const ID: i32 = 0x5F;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let entity_id = VarInt::read(reader)?;
+        let mut equipment = Vec::new();
+        loop {
+            if equipment.len() == Self::MAX_ENTRIES {
+                return Err(crate::error::ServerError::Protocol(
+                    "Too many equipment entries".to_string(),
+                ));
+            }
+            let slot = crate::protocol::types::read_unsigned_byte(reader)?;
+            equipment.push((slot & !Self::MORE_ENTRIES, read_slot(reader)?));
+            if slot & Self::MORE_ENTRIES == 0 {
+                return Ok(SetEquipmentPacket {
+                    entity_id,
+                    equipment,
+                });
+            }
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.entity_id.write(writer)?;
+        for (index, (slot, item)) in self.equipment.iter().enumerate() {
+            let more = if index + 1 < self.equipment.len() {
+                Self::MORE_ENTRIES
+            } else {
+                0
+            };
+            crate::protocol::types::write_unsigned_byte(slot | more, writer)?;
+            write_slot(item.as_ref(), writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for SetEquipmentPacket {}
+
+/// Set held item packet (serverbound)
+///
+/// Sent when the player selects another hotbar slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetHeldItemPacket {
+    /// Selected hotbar slot (0-8)
+    pub slot: i16,
+}
+
+impl Packet for SetHeldItemPacket {
+    const ID: i32 = 0x34;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = [0u8; 2];
+        reader.read_exact(&mut bytes)?;
+        Ok(SetHeldItemPacket {
+            slot: i16::from_be_bytes(bytes),
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.slot.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for SetHeldItemPacket {}
+
+/// Set creative mode slot packet (serverbound)
+///
+/// Sent when a player in creative mode changes a slot of their inventory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetCreativeModeSlotPacket {
+    /// Window slot changed, or -1 to drop the item
+    pub slot: i16,
+    /// New contents of the slot
+    pub item: Option<ItemStack>,
+}
+
+impl Packet for SetCreativeModeSlotPacket {
+    const ID: i32 = 0x37;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = [0u8; 2];
+        reader.read_exact(&mut bytes)?;
+        let item = read_untrusted_slot(reader)?;
+        Ok(SetCreativeModeSlotPacket {
+            slot: i16::from_be_bytes(bytes),
+            item,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.slot.to_be_bytes())?;
+        write_untrusted_slot(self.item.as_ref(), writer)
+    }
+}
+
+impl ServerboundPacket for SetCreativeModeSlotPacket {}
+
+/// Swing arm packet (serverbound)
+///
+/// Sent when the player swings an arm, such as when punching.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwingArmPacket {
+    /// Hand swung (0=Main hand, 1=Off hand)
+    pub hand: VarInt,
+}
+
+impl Packet for SwingArmPacket {
+    const ID: i32 = 0x3C;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(SwingArmPacket {
+            hand: VarInt::read(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.hand.write(writer)
+    }
+}
+
+impl ServerboundPacket for SwingArmPacket {}
+
+/// Use item packet (serverbound)
+///
+/// Sent when the player right-clicks with an item without aiming at a
+/// block or entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseItemPacket {
+    /// Hand used (0=Main hand, 1=Off hand)
+    pub hand: VarInt,
+    /// Block change sequence number
+    pub sequence: VarInt,
+    /// Yaw of the player in degrees
+    pub yaw: f32,
+    /// Pitch of the player in degrees
+    pub pitch: f32,
+}
+
+impl Packet for UseItemPacket {
+    const ID: i32 = 0x40;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let hand = VarInt::read(reader)?;
+        let sequence = VarInt::read(reader)?;
+        let yaw = read_f32(reader)?;
+        let pitch = read_f32(reader)?;
+        Ok(UseItemPacket {
+            hand,
+            sequence,
+            yaw,
+            pitch,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.hand.write(writer)?;
+        self.sequence.write(writer)?;
+        writer.write_all(&self.yaw.to_be_bytes())?;
+        writer.write_all(&self.pitch.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for UseItemPacket {}
+
+/// Set container slot packet (clientbound)
+///
+/// Changes one slot of a window the player has open; window 0 is the
+/// player's own inventory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetContainerSlotPacket {
+    /// Window the slot belongs to
+    pub window_id: VarInt,
+    /// Version of the window contents after the change
+    pub state_id: VarInt,
+    /// Slot changed
+    pub slot: i16,
+    /// New contents of the slot
+    pub item: Option<ItemStack>,
+}
+
+impl SetContainerSlotPacket {
+    /// Window ID of the player's inventory
+    pub const PLAYER_INVENTORY: i32 = 0;
+}
+
+impl Packet for SetContainerSlotPacket {
+    const ID: i32 = 0x14;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let window_id = VarInt::read(reader)?;
+        let state_id = VarInt::read(reader)?;
+        let mut bytes = [0u8; 2];
+        reader.read_exact(&mut bytes)?;
+        let item = read_slot(reader)?;
+        Ok(SetContainerSlotPacket {
+            window_id,
+            state_id,
+            slot: i16::from_be_bytes(bytes),
+            item,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.window_id.write(writer)?;
+        self.state_id.write(writer)?;
+        writer.write_all(&self.slot.to_be_bytes())?;
+        write_slot(self.item.as_ref(), writer)
+    }
+}
+
+impl ClientboundPacket for SetContainerSlotPacket {}
+
+/// Sound effect packet (clientbound)
+///
+/// Plays a sound at a position. The sound is always sent inline by its
+/// identifier rather than by registry ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundEffectPacket {
+    /// Sound event identifier
+    pub sound: Identifier,
+    /// Distance the sound is heard from, if fixed
+    pub fixed_range: Option<f32>,
+    /// Sound category
+    pub category: VarInt,
+    /// X coordinate multiplied by 8
+    pub x: i32,
+    /// Y coordinate multiplied by 8
+    pub y: i32,
+    /// Z coordinate multiplied by 8
+    pub z: i32,
+    /// Volume, where 1.0 is normal
+    pub volume: f32,
+    /// Pitch, where 1.0 is normal
+    pub pitch: f32,
+    /// Seed picking among variants of the sound
+    pub seed: i64,
+}
+
+impl SoundEffectPacket {
+    /// Category of sounds made by blocks
+    pub const BLOCKS: i32 = 4;
+
+    /// Play a block sound from the middle of a block
+    pub fn block(sound: &str, position: Position, pitch: f32, seed: i64) -> Self {
+        SoundEffectPacket {
+            sound: Identifier(sound.to_string()),
+            fixed_range: None,
+            category: VarInt(Self::BLOCKS),
+            x: position.x * 8 + 4,
+            y: position.y * 8 + 4,
+            z: position.z * 8 + 4,
+            volume: 1.0,
+            pitch,
+            seed,
+        }
+    }
+}
+
+impl Packet for SoundEffectPacket {
+    const ID: i32 = 0x6E;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        // Registry IDs are offset by one, so zero means an inline sound
+        if VarInt::read(reader)?.0 != 0 {
+            return Err(crate::error::ServerError::Protocol(
+                "Sound effects by registry ID are not supported".to_string(),
+            ));
+        }
+        let sound = Identifier::read(reader)?;
+        let fixed_range = if read_bool(reader)? {
+            Some(read_f32(reader)?)
+        } else {
+            None
+        };
+        Ok(SoundEffectPacket {
+            sound,
+            fixed_range,
+            category: VarInt::read(reader)?,
+            x: read_int(reader)?,
+            y: read_int(reader)?,
+            z: read_int(reader)?,
+            volume: read_f32(reader)?,
+            pitch: read_f32(reader)?,
+            seed: read_long(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        VarInt(0).write(writer)?;
+        self.sound.write(writer)?;
+        write_bool(self.fixed_range.is_some(), writer)?;
+        if let Some(range) = self.fixed_range {
+            writer.write_all(&range.to_be_bytes())?;
+        }
+        self.category.write(writer)?;
+        write_int(self.x, writer)?;
+        write_int(self.y, writer)?;
+        write_int(self.z, writer)?;
+        writer.write_all(&self.volume.to_be_bytes())?;
+        writer.write_all(&self.pitch.to_be_bytes())?;
+        write_long(self.seed, writer)
+    }
+}
+
+impl ClientboundPacket for SoundEffectPacket {}
+
+/// System chat message packet (clientbound)
+///
+/// Shows a message from the server rather than a player, such as `/say`
+/// announcements, in the chat or above the hotbar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemChatMessagePacket {
+    /// Message shown
+    pub content: JsonTextComponent,
+    /// Show the message above the hotbar instead of in the chat
+    pub overlay: bool,
+}
+
+impl Packet for SystemChatMessagePacket {
+    const ID: i32 = 0x72;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let content = JsonTextComponent::read_nbt(reader)?;
+        let overlay = crate::protocol::types::read_bool(reader)?;
+        Ok(SystemChatMessagePacket { content, overlay })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.content.write_nbt(writer)?;
+        crate::protocol::types::write_bool(self.overlay, writer)
+    }
+}
+
+impl ClientboundPacket for SystemChatMessagePacket {}
+
+/// Start configuration packet (clientbound)
+///
+/// Sends a player back to the configuration state, for example to apply a
+/// new resource pack. The client answers with
+/// [`ConfigurationAcknowledgedPacket`] and no more play packets may be sent
+/// to it.
+#[derive(Debug, Clone)]
+pub struct StartConfigurationPacket;
+
+impl Packet for StartConfigurationPacket {
+    const ID: i32 = 0x6F;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(_reader: &mut R) -> Result<Self> {
+        Ok(StartConfigurationPacket)
+    }
+
+    fn write<W: Write>(&self, _writer: &mut W) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ClientboundPacket for StartConfigurationPacket {}
+
+/// Configuration acknowledged packet (serverbound)
+///
+/// The client's answer to [`StartConfigurationPacket`], after which the
+/// connection is in the configuration state.
+#[derive(Debug, Clone)]
+pub struct ConfigurationAcknowledgedPacket;
+
+impl Packet for ConfigurationAcknowledgedPacket {
+    const ID: i32 = 0x0F;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(_reader: &mut R) -> Result<Self> {
+        Ok(ConfigurationAcknowledgedPacket)
+    }
+
+    fn write<W: Write>(&self, _writer: &mut W) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ServerboundPacket for ConfigurationAcknowledgedPacket {}
+
+/// Open screen packet (clientbound)
+///
+/// Opens a window such as a villager's trading screen.
+#[derive(Debug, Clone)]
+pub struct OpenScreenPacket {
+    /// ID of the window, used by later container packets
+    pub window_id: VarInt,
+    /// Kind of window
+    pub window_type: VarInt,
+    /// Title shown at the top of the window
+    pub title: JsonTextComponent,
+}
+
+impl OpenScreenPacket {
+    /// Window type of a villager's trading screen
+    pub const MERCHANT: i32 = 19;
+}
+
+impl Packet for OpenScreenPacket {
+    const ID: i32 = 0x34;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(OpenScreenPacket {
+            window_id: VarInt::read(reader)?,
+            window_type: VarInt::read(reader)?,
+            title: JsonTextComponent::read_nbt(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.window_id.write(writer)?;
+        self.window_type.write(writer)?;
+        self.title.write_nbt(writer)
+    }
+}
+
+impl ClientboundPacket for OpenScreenPacket {}
+
+/// A trade offered in the Merchant Offers packet
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerchantOffer {
+    /// First item the player pays; its components are not checked
+    pub input: ItemStack,
+    /// Item the player gets
+    pub output: ItemStack,
+    /// Second item the player pays, if any
+    pub second_input: Option<ItemStack>,
+    /// Whether the trade is out of stock
+    pub disabled: bool,
+    /// Times the trade was made
+    pub uses: i32,
+    /// Times the trade can be made before the villager restocks
+    pub max_uses: i32,
+    /// Experience the villager gains from the trade
+    pub experience: i32,
+    /// Change to the price of the first input
+    pub special_price: i32,
+    /// How much demand changes the price
+    pub price_multiplier: f32,
+    /// Demand for the trade
+    pub demand: i32,
+}
+
+impl MerchantOffer {
+    /// Read an offer
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let input = read_trade_item(reader)?;
+        let output = read_slot(reader)?.ok_or_else(|| {
+            crate::error::ServerError::Protocol("Trade offer without an output".to_string())
+        })?;
+        let second_input = if read_bool(reader)? {
+            Some(read_trade_item(reader)?)
+        } else {
+            None
+        };
+        Ok(MerchantOffer {
+            input,
+            output,
+            second_input,
+            disabled: read_bool(reader)?,
+            uses: read_int(reader)?,
+            max_uses: read_int(reader)?,
+            experience: read_int(reader)?,
+            special_price: read_int(reader)?,
+            price_multiplier: read_f32(reader)?,
+            demand: read_int(reader)?,
+        })
+    }
+
+    /// Write an offer
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_trade_item(&self.input, writer)?;
+        write_slot(Some(&self.output), writer)?;
+        write_bool(self.second_input.is_some(), writer)?;
+        if let Some(second_input) = &self.second_input {
+            write_trade_item(second_input, writer)?;
+        }
+        write_bool(self.disabled, writer)?;
+        write_int(self.uses, writer)?;
+        write_int(self.max_uses, writer)?;
+        write_int(self.experience, writer)?;
+        write_int(self.special_price, writer)?;
+        writer.write_all(&self.price_multiplier.to_be_bytes())?;
+        write_int(self.demand, writer)
+    }
+}
+
+/// Merchant offers packet (clientbound)
+///
+/// Lists the trades of the villager whose trading screen is open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerchantOffersPacket {
+    /// Window of the trading screen
+    pub window_id: VarInt,
+    /// Trades offered
+    pub offers: Vec<MerchantOffer>,
+    /// Villager level, from 1 (novice) to 5 (master)
+    pub level: VarInt,
+    /// Villager experience
+    pub experience: VarInt,
+    /// Whether the level and experience bar are shown
+    pub regular_villager: bool,
+    /// Whether the villager restocks trades
+    pub can_restock: bool,
+}
+
+impl MerchantOffersPacket {
+    /// Most offers read
+    const MAX_OFFERS: usize = 256;
+}
+
+impl Packet for MerchantOffersPacket {
+    const ID: i32 = 0x2D;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let window_id = VarInt::read(reader)?;
+        let count = read_length(reader, Self::MAX_OFFERS)?;
+        let offers = (0..count)
+            .map(|_| MerchantOffer::read(reader))
+            .collect::<Result<_>>()?;
+        Ok(MerchantOffersPacket {
+            window_id,
+            offers,
+            level: VarInt::read(reader)?,
+            experience: VarInt::read(reader)?,
+            regular_villager: read_bool(reader)?,
+            can_restock: read_bool(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.window_id.write(writer)?;
+        VarInt(self.offers.len() as i32).write(writer)?;
+        for offer in &self.offers {
+            offer.write(writer)?;
+        }
+        self.level.write(writer)?;
+        self.experience.write(writer)?;
+        write_bool(self.regular_villager, writer)?;
+        write_bool(self.can_restock, writer)
+    }
+}
+
+impl ClientboundPacket for MerchantOffersPacket {}
+
+/// Select trade packet (serverbound)
+///
+/// Sent when the player picks a trade on the trading screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectTradePacket {
+    /// Index of the trade in the offer list
+    pub selected: VarInt,
+}
+
+impl Packet for SelectTradePacket {
+    const ID: i32 = 0x32;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(SelectTradePacket {
+            selected: VarInt::read(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.selected.write(writer)
+    }
+}
+
+impl ServerboundPacket for SelectTradePacket {}
+
+/// An item stack as the client describes it in clicks, with its components
+/// reduced to hashes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashedStack {
+    /// Item registry ID
+    pub item: VarInt,
+    /// Number of items
+    pub count: VarInt,
+    /// Added components, by type, with the hash of their value
+    pub added: Vec<(VarInt, i32)>,
+    /// Types of removed components
+    pub removed: Vec<VarInt>,
+}
+
+impl HashedStack {
+    /// Most component changes read
+    const MAX_COMPONENTS: usize = 256;
+
+    /// Read an optional hashed stack
+    fn read<R: Read>(reader: &mut R) -> Result<Option<Self>> {
+        if !read_bool(reader)? {
+            return Ok(None);
+        }
+        let item = VarInt::read(reader)?;
+        let count = VarInt::read(reader)?;
+        let added = (0..read_length(reader, Self::MAX_COMPONENTS)?)
+            .map(|_| Ok((VarInt::read(reader)?, read_int(reader)?)))
+            .collect::<Result<_>>()?;
+        let removed = (0..read_length(reader, Self::MAX_COMPONENTS)?)
+            .map(|_| VarInt::read(reader))
+            .collect::<Result<_>>()?;
+        Ok(Some(HashedStack {
+            item,
+            count,
+            added,
+            removed,
+        }))
+    }
+
+    /// Write an optional hashed stack
+    fn write<W: Write>(stack: Option<&Self>, writer: &mut W) -> Result<()> {
+        write_bool(stack.is_some(), writer)?;
+        let Some(stack) = stack else {
+            return Ok(());
+        };
+        stack.item.write(writer)?;
+        stack.count.write(writer)?;
+        VarInt(stack.added.len() as i32).write(writer)?;
+        for (kind, hash) in &stack.added {
+            kind.write(writer)?;
+            write_int(*hash, writer)?;
+        }
+        VarInt(stack.removed.len() as i32).write(writer)?;
+        for kind in &stack.removed {
+            kind.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Click container packet (serverbound)
+///
+/// Sent when the player clicks a slot of an open window, with the slots
+/// the client expects to have changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClickContainerPacket {
+    /// Window clicked in
+    pub window_id: VarInt,
+    /// Version of the window contents the client last received
+    pub state_id: VarInt,
+    /// Slot clicked, or -999 outside the window
+    pub slot: i16,
+    /// Mouse button or hotbar key used
+    pub button: i8,
+    /// Kind of click
+    pub mode: VarInt,
+    /// Slots the client expects to have changed, with their new contents
+    pub changed_slots: Vec<(i16, Option<HashedStack>)>,
+    /// Item the client expects on the cursor
+    pub carried: Option<HashedStack>,
+}
+
+impl ClickContainerPacket {
+    /// Most changed slots read
+    const MAX_CHANGED_SLOTS: usize = 128;
+}
+
+impl Packet for ClickContainerPacket {
+    const ID: i32 = 0x11;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let window_id = VarInt::read(reader)?;
+        let state_id = VarInt::read(reader)?;
+        let mut slot = [0u8; 2];
+        reader.read_exact(&mut slot)?;
+        let button = crate::protocol::types::read_unsigned_byte(reader)? as i8;
+        let mode = VarInt::read(reader)?;
+        let changed_slots = (0..read_length(reader, Self::MAX_CHANGED_SLOTS)?)
+            .map(|_| {
+                let mut slot = [0u8; 2];
+                reader.read_exact(&mut slot)?;
+                Ok((i16::from_be_bytes(slot), HashedStack::read(reader)?))
+            })
+            .collect::<Result<_>>()?;
+        Ok(ClickContainerPacket {
+            window_id,
+            state_id,
+            slot: i16::from_be_bytes(slot),
+            button,
+            mode,
+            changed_slots,
+            carried: HashedStack::read(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.window_id.write(writer)?;
+        self.state_id.write(writer)?;
+        writer.write_all(&self.slot.to_be_bytes())?;
+        crate::protocol::types::write_unsigned_byte(self.button as u8, writer)?;
+        self.mode.write(writer)?;
+        VarInt(self.changed_slots.len() as i32).write(writer)?;
+        for (slot, stack) in &self.changed_slots {
+            writer.write_all(&slot.to_be_bytes())?;
+            HashedStack::write(stack.as_ref(), writer)?;
+        }
+        HashedStack::write(self.carried.as_ref(), writer)
+    }
+}
+
+impl ServerboundPacket for ClickContainerPacket {}
+
+/// Close container packet (serverbound)
+///
+/// Sent when the player closes a window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseContainerPacket {
+    /// Window closed
+    pub window_id: VarInt,
+}
+
+impl Packet for CloseContainerPacket {
+    const ID: i32 = 0x12;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(CloseContainerPacket {
+            window_id: VarInt::read(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.window_id.write(writer)
+    }
+}
+
+impl ServerboundPacket for CloseContainerPacket {}
+
+/// Set container content packet (clientbound)
+///
+/// Replaces every slot of a window and the item on the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetContainerContentPacket {
+    /// Window the slots belong to
+    pub window_id: VarInt,
+    /// Version of the window contents
+    pub state_id: VarInt,
+    /// Contents of every slot
+    pub slots: Vec<Option<ItemStack>>,
+    /// Item on the cursor
+    pub carried: Option<ItemStack>,
+}
+
+impl SetContainerContentPacket {
+    /// Most slots read
+    const MAX_SLOTS: usize = 256;
+}
+
+impl Packet for SetContainerContentPacket {
+    const ID: i32 = 0x12;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let window_id = VarInt::read(reader)?;
+        let state_id = VarInt::read(reader)?;
+        let slots = (0..read_length(reader, Self::MAX_SLOTS)?)
+            .map(|_| read_slot(reader))
+            .collect::<Result<_>>()?;
+        Ok(SetContainerContentPacket {
+            window_id,
+            state_id,
+            slots,
+            carried: read_slot(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.window_id.write(writer)?;
+        self.state_id.write(writer)?;
+        VarInt(self.slots.len() as i32).write(writer)?;
+        for slot in &self.slots {
+            write_slot(slot.as_ref(), writer)?;
+        }
+        write_slot(self.carried.as_ref(), writer)
+    }
+}
+
+impl ClientboundPacket for SetContainerContentPacket {}
+
+/// Kinds of command graph nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandNodeKind {
+    /// The root every command starts from
+    Root,
+    /// A fixed word, such as a command name
+    Literal(McString),
+    /// A `brigadier:string` argument, with 0 reading a single word, 1 a
+    /// word or quoted phrase and 2 the rest of the input
+    StringArgument {
+        /// Name shown while typing the argument
+        name: McString,
+        /// How much input the argument reads
+        behavior: VarInt,
+    },
+}
+
+/// A node of the command graph
+///
+/// Redirects and suggestion providers are not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandNode {
+    /// Kind of node, with its name
+    pub kind: CommandNodeKind,
+    /// Whether input ending at this node is a complete command
+    pub executable: bool,
+    /// Indices of the child nodes
+    pub children: Vec<VarInt>,
+}
+
+impl CommandNode {
+    /// Node type bits of the flags
+    const TYPE_MASK: u8 = 0x03;
+    /// Flag of executable nodes
+    const EXECUTABLE: u8 = 0x04;
+    /// Registry ID of the `brigadier:string` parser
+    const STRING_PARSER: i32 = 5;
+}
+
+/// Commands packet (clientbound)
+///
+/// Declares the commands a player may use, as a graph of nodes, so the
+/// client can highlight and complete them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandsPacket {
+    /// Every node of the graph
+    pub nodes: Vec<CommandNode>,
+    /// Index of the root node
+    pub root_index: VarInt,
+}
+
+impl CommandsPacket {
+    /// Most nodes read
+    const MAX_NODES: usize = 4096;
+}
+
+impl Packet for CommandsPacket {
+    const ID: i32 = 0x10;
+    const STATE: ConnectionState = ConnectionState::Play;
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let nodes = (0..read_length(reader, Self::MAX_NODES)?)
+            .map(|_| {
+                let flags = crate::protocol::types::read_unsigned_byte(reader)?;
+                if flags & !(CommandNode::TYPE_MASK | CommandNode::EXECUTABLE) != 0 {
+                    return Err(crate::error::ServerError::Protocol(
+                        "Unsupported command node flags".to_string(),
+                    ));
+                }
+                let children = (0..read_length(reader, Self::MAX_NODES)?)
+                    .map(|_| VarInt::read(reader))
+                    .collect::<Result<_>>()?;
+                let kind = match flags & CommandNode::TYPE_MASK {
+                    0 => CommandNodeKind::Root,
+                    1 => CommandNodeKind::Literal(McString::read(reader)?),
+                    2 => {
+                        let name = McString::read(reader)?;
+                        if VarInt::read(reader)?.0 != CommandNode::STRING_PARSER {
+                            return Err(crate::error::ServerError::Protocol(
+                                "Unsupported command argument parser".to_string(),
+                            ));
+                        }
+                        let behavior = VarInt::read(reader)?;
+                        CommandNodeKind::StringArgument { name, behavior }
+                    }
+                    _ => {
+                        return Err(crate::error::ServerError::Protocol(
+                            "Invalid command node type".to_string(),
+                        ));
+                    }
+                };
+                Ok(CommandNode {
+                    kind,
+                    executable: flags & CommandNode::EXECUTABLE != 0,
+                    children,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(CommandsPacket {
+            nodes,
+            root_index: VarInt::read(reader)?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        VarInt(self.nodes.len() as i32).write(writer)?;
+        for node in &self.nodes {
+            let node_type = match node.kind {
+                CommandNodeKind::Root => 0,
+                CommandNodeKind::Literal(_) => 1,
+                CommandNodeKind::StringArgument { .. } => 2,
+            };
+            let executable = if node.executable {
+                CommandNode::EXECUTABLE
+            } else {
+                0
+            };
+            crate::protocol::types::write_unsigned_byte(node_type | executable, writer)?;
+            VarInt(node.children.len() as i32).write(writer)?;
+            for child in &node.children {
+                child.write(writer)?;
+            }
+            match &node.kind {
+                CommandNodeKind::Root => {}
+                CommandNodeKind::Literal(name) => name.write(writer)?,
+                CommandNodeKind::StringArgument { name, behavior } => {
+                    name.write(writer)?;
+                    VarInt(CommandNode::STRING_PARSER).write(writer)?;
+                    behavior.write(writer)?;
+                }
+            }
+        }
+        self.root_index.write(writer)
+    }
+}
+
+impl ClientboundPacket for CommandsPacket {}
+
+/// Read an item a trade costs: an item and count with component checks
+///
+/// Component checks are not supported, so costs carrying any are rejected.
+fn read_trade_item<R: Read>(reader: &mut R) -> Result<ItemStack> {
+    let item = VarInt::read(reader)?.0;
+    let count = VarInt::read(reader)?.0;
+    if VarInt::read(reader)?.0 != 0 {
+        return Err(crate::error::ServerError::Protocol(
+            "Trade item component checks are not supported".to_string(),
+        ));
+    }
+    Ok(ItemStack::new(item, count))
+}
+
+/// Write an item a trade costs, without component checks
+fn write_trade_item<W: Write>(item: &ItemStack, writer: &mut W) -> Result<()> {
+    VarInt(item.item).write(writer)?;
+    VarInt(item.count).write(writer)?;
+    VarInt(0).write(writer)
+}
+
+/// Component type ID of an item's enchantments
+const ENCHANTMENTS_COMPONENT: i32 = 10;
+
+/// Most component changes read from an item slot
+const MAX_SLOT_COMPONENTS: usize = 256;
+
+/// Read an item slot: a count, then the item and its component changes
+///
+/// Only enchantments are kept; removed components are ignored. Added
+/// components carry no length here, so a slot adding any other is
+/// rejected.
+pub(crate) fn read_slot<R: Read>(reader: &mut R) -> Result<Option<ItemStack>> {
+    read_slot_components(reader, false)
+}
+
+/// Read an item slot a client sent, whose added components are each
+/// prefixed with their length
+///
+/// Only enchantments are kept; other components are skipped and removed
+/// ones ignored, so the item is accepted with their defaults.
+pub(crate) fn read_untrusted_slot<R: Read>(reader: &mut R) -> Result<Option<ItemStack>> {
+    read_slot_components(reader, true)
+}
+
+/// Read an item slot, with or without component lengths
+fn read_slot_components<R: Read>(reader: &mut R, prefixed: bool) -> Result<Option<ItemStack>> {
+    let count = VarInt::read(reader)?.0;
+    if count <= 0 {
+        return Ok(None);
+    }
+    let item = VarInt::read(reader)?.0;
+    let added = read_length(reader, MAX_SLOT_COMPONENTS)?;
+    let removed = read_length(reader, MAX_SLOT_COMPONENTS)?;
+    let mut stack = ItemStack::new(item, count);
+    for _ in 0..added {
+        let component = VarInt::read(reader)?.0;
+        if prefixed {
+            let length = read_length(reader, crate::protocol::MAX_PACKET_SIZE)?;
+            let data = read_bytes(reader, length)?;
+            if component == ENCHANTMENTS_COMPONENT {
+                read_enchantments(&mut data.as_slice(), &mut stack)?;
+            }
+        } else if component == ENCHANTMENTS_COMPONENT {
+            read_enchantments(reader, &mut stack)?;
+        } else {
+            return Err(crate::error::ServerError::Protocol(format!(
+                "Unsupported item component {}",
+                component
+            )));
+        }
+    }
+    for _ in 0..removed {
+        VarInt::read(reader)?;
+    }
+    Ok(Some(stack))
+}
+
+/// Read the enchantments component onto a stack
+///
+/// Enchantments that are not defined, or have a level out of range, are
+/// skipped.
+fn read_enchantments<R: Read>(reader: &mut R, stack: &mut ItemStack) -> Result<()> {
+    let entries = read_length(reader, MAX_SLOT_COMPONENTS)?;
+    for _ in 0..entries {
+        let enchantment = Enchantment::from_protocol_id(VarInt::read(reader)?.0);
+        let level = u8::try_from(VarInt::read(reader)?.0).ok();
+        if let (Some(enchantment), Some(level)) = (enchantment, level) {
+            stack.components.enchantments.set(enchantment, level);
+        }
+    }
+    Ok(())
+}
+
+/// Write an item slot with its component changes
+pub(crate) fn write_slot<W: Write>(item: Option<&ItemStack>, writer: &mut W) -> Result<()> {
+    write_slot_components(item, false, writer)
+}
+
+/// Write an item slot as a client sends it, with component lengths
+pub(crate) fn write_untrusted_slot<W: Write>(
+    item: Option<&ItemStack>,
+    writer: &mut W,
+) -> Result<()> {
+    write_slot_components(item, true, writer)
+}
+
+/// Write an item slot, with or without component lengths
+fn write_slot_components<W: Write>(
+    item: Option<&ItemStack>,
+    prefixed: bool,
+    writer: &mut W,
+) -> Result<()> {
+    let Some(item) = item else {
+        return VarInt(0).write(writer);
+    };
+    VarInt(item.count).write(writer)?;
+    VarInt(item.item).write(writer)?;
+    let enchantments = item.enchantments();
+    if enchantments.is_empty() {
+        VarInt(0).write(writer)?;
+        return VarInt(0).write(writer);
+    }
+    VarInt(1).write(writer)?;
+    VarInt(0).write(writer)?;
+    VarInt(ENCHANTMENTS_COMPONENT).write(writer)?;
+    let mut data = Vec::new();
+    VarInt(enchantments.len() as i32).write(&mut data)?;
+    for (enchantment, level) in enchantments.iter() {
+        VarInt(enchantment.definition().protocol_id).write(&mut data)?;
+        VarInt(i32::from(level)).write(&mut data)?;
+    }
+    if prefixed {
+        VarInt(data.len() as i32).write(writer)?;
+    }
+    writer.write_all(&data)?;
+    Ok(())
+}
+
+/// Read a big-endian double
+fn read_f64<R: Read>(reader: &mut R) -> Result<f64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_be_bytes(bytes))
+}
+
+/// Read a big-endian float
+fn read_f32<R: Read>(reader: &mut R) -> Result<f32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_be_bytes(bytes))
+}
+
+/// Serialize a chunk section: block count, block states and biomes
+fn write_section(chunk: &Chunk, section: usize, out: &mut Vec<u8>) -> Result<()> {
+    let base = section * SECTION_SIZE;
+    let mut blocks = Vec::with_capacity(SECTION_VOLUME);
+    for layer in &chunk.blocks()[base..base + SECTION_SIZE] {
+        for row in layer {
+            blocks.extend_from_slice(row);
+        }
+    }
+
+    let non_air = blocks.iter().filter(|&&block| block != 0).count() as i16;
+    out.extend_from_slice(&non_air.to_be_bytes());
+    write_block_states(&blocks, out)?;
+
+    // A single biome for the whole section
+    crate::protocol::types::write_unsigned_byte(0, out)?;
+    VarInt(0).write(out)
+}
+
+/// Serialize block states as a paletted container
+fn write_block_states(blocks: &[u32], out: &mut Vec<u8>) -> Result<()> {
+    if blocks.iter().all(|&block| block == blocks[0]) {
+        crate::protocol::types::write_unsigned_byte(0, out)?;
+        return VarInt(blocks[0] as i32).write(out);
+    }
+
+    let mut palette = Vec::new();
+    let mut indices = std::collections::HashMap::new();
+    for &block in blocks {
+        indices.entry(block).or_insert_with(|| {
+            palette.push(block);
+            palette.len() as u64 - 1
+        });
+    }
+
+    let bits = bits_for(palette.len()).max(MIN_INDIRECT_BLOCK_BITS);
+    let (bits, entries): (u32, Vec<u64>) = if bits <= MAX_INDIRECT_BLOCK_BITS {
+        crate::protocol::types::write_unsigned_byte(bits as u8, out)?;
+        VarInt(palette.len() as i32).write(out)?;
+        for &block in &palette {
+            VarInt(block as i32).write(out)?;
+        }
+        (bits, blocks.iter().map(|block| indices[block]).collect())
+    } else {
+        crate::protocol::types::write_unsigned_byte(DIRECT_BLOCK_BITS as u8, out)?;
+        let entries = blocks.iter().map(|&block| u64::from(block)).collect();
+        (DIRECT_BLOCK_BITS, entries)
+    };
+    for long in pack_entries(&entries, bits) {
+        out.extend_from_slice(&long.to_be_bytes());
+    }
+    Ok(())
+}
+
+/// Bits needed to store values below `count`
+fn bits_for(count: usize) -> u32 {
+    usize::BITS - count.saturating_sub(1).leading_zeros()
+}
+
+/// Pack values of `bits` bits into longs, without spanning longs
+fn pack_entries(values: &[u64], bits: u32) -> Vec<i64> {
+    let per_long = (64 / bits) as usize;
+    let mask = (1u64 << bits) - 1;
+    values
+        .chunks(per_long)
+        .map(|values| {
+            values.iter().enumerate().fold(0u64, |long, (i, &value)| {
+                long | (value & mask) << (i as u32 * bits)
+            }) as i64
+        })
+        .collect()
+}
+
+/// Read a length-prefixed array of longs
+fn read_long_array<R: Read>(reader: &mut R) -> Result<Vec<i64>> {
+    let length = read_length(reader, MAX_CHUNK_ARRAY_LENGTH / 8)?;
+    (0..length).map(|_| read_long(reader)).collect()
+}
+
+/// Write a length-prefixed array of longs
+fn write_long_array<W: Write>(values: &[i64], writer: &mut W) -> Result<()> {
+    VarInt(values.len() as i32).write(writer)?;
+    for &value in values {
+        crate::protocol::types::write_long(value, writer)?;
+    }
+    Ok(())
+}
+
+/// Read the light arrays of the sections in a light mask
+fn read_light_arrays<R: Read>(reader: &mut R) -> Result<Vec<Vec<u8>>> {
+    let count = read_length(reader, MAX_CHUNK_ARRAY_LENGTH / LIGHT_ARRAY_SIZE)?;
+    let mut arrays = Vec::new();
+    for _ in 0..count {
+        let mut array = vec![0; read_length(reader, LIGHT_ARRAY_SIZE)?];
+        reader.read_exact(&mut array)?;
+        arrays.push(array);
+    }
+    Ok(arrays)
+}
+
+/// Write the light arrays of the sections in a light mask
+fn write_light_arrays<W: Write>(arrays: &[Vec<u8>], writer: &mut W) -> Result<()> {
+    VarInt(arrays.len() as i32).write(writer)?;
+    for array in arrays {
+        VarInt(array.len() as i32).write(writer)?;
+        writer.write_all(array)?;
+    }
+    Ok(())
+}
+
+// TODO: Add more play packets as needed
+// - Entity packets
+// - Inventory packets
+// - etc.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_login_play_packet_roundtrip() {
+        let packet = LoginPlayPacket::new();
+
+        let mut buffer = Vec::new();
+        packet.write(&mut buffer).unwrap();
+
+        let mut cursor = Cursor::new(buffer);
+        let decoded = LoginPlayPacket::read(&mut cursor).unwrap();
+
+        assert_eq!(packet.entity_id, decoded.entity_id);
+        assert_eq!(packet.view_distance.0, decoded.view_distance.0);
+        assert_eq!(packet.simulation_distance.0, decoded.simulation_distance.0);
+        assert_eq!(packet.reduced_debug_info, decoded.reduced_debug_info);
+        assert_eq!(packet.enable_respawn_screen, decoded.enable_respawn_screen);
+        assert_eq!(packet.do_limited_crafting, decoded.do_limited_crafting);
+        assert_eq!(packet.dimension_name.0, decoded.dimension_name.0);
+        assert_eq!(packet.hashed_seed, decoded.hashed_seed);
+        assert_eq!(packet.game_mode, decoded.game_mode);
+        assert_eq!(packet.previous_game_mode, decoded.previous_game_mode);
+        assert_eq!(packet.is_debug, decoded.is_debug);
+        assert_eq!(packet.is_flat, decoded.is_flat);
+        assert_eq!(packet.has_death_location, decoded.has_death_location);
+        assert_eq!(packet.portal_cooldown.0, decoded.portal_cooldown.0);
+        assert_eq!(packet.enforces_secure_chat, decoded.enforces_secure_chat);
+    }
+
+    #[test]
+    fn test_login_play_packet_with_death_location() {
         let mut packet = LoginPlayPacket::new();
         packet.has_death_location = true;
         packet.death_dimension_name = Some("minecraft:the_nether".into());
@@ -527,4 +4607,96 @@ mod tests {
         assert!(!packet.is_debug);
         assert_eq!(packet.max_players.0, config.max_players as i32);
     }
+
+    #[test]
+    fn test_world_state_packets_roundtrip() {
+        let difficulty = ChangeDifficultyPacket {
+            difficulty: 3,
+            locked: false,
+        };
+        let mut buffer = Vec::new();
+        difficulty.write(&mut buffer).unwrap();
+        assert_eq!(buffer, vec![3, 0]);
+        assert_eq!(
+            ChangeDifficultyPacket::read(&mut Cursor::new(buffer)).unwrap(),
+            difficulty
+        );
+
+        let time = UpdateTimePacket {
+            world_age: 24_000,
+            time_of_day: 6_000,
+            time_of_day_increasing: true,
+        };
+        let mut buffer = Vec::new();
+        time.write(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), 17);
+        assert_eq!(
+            UpdateTimePacket::read(&mut Cursor::new(buffer)).unwrap(),
+            time
+        );
+
+        let event = GameEventPacket::new(GameEventPacket::RAIN_LEVEL_CHANGE, 1.0);
+        let mut buffer = Vec::new();
+        event.write(&mut buffer).unwrap();
+        assert_eq!(
+            GameEventPacket::read(&mut Cursor::new(buffer)).unwrap(),
+            event
+        );
+    }
+
+    #[test]
+    fn test_use_item_on_packet_roundtrip() {
+        let packet = UseItemOnPacket {
+            hand: VarInt(0),
+            location: Position::new(10, 64, -5),
+            face: VarInt(1),
+            cursor_x: 0.5,
+            cursor_y: 1.0,
+            cursor_z: 0.25,
+            inside_block: false,
+            world_border_hit: false,
+            sequence: VarInt(7),
+        };
+
+        let mut buffer = Vec::new();
+        packet.write(&mut buffer).unwrap();
+        assert_eq!(
+            UseItemOnPacket::read(&mut Cursor::new(buffer)).unwrap(),
+            packet
+        );
+    }
+
+    #[test]
+    fn test_creative_slot_components() {
+        let mut enchantments = crate::game::enchantment::Enchantments::new();
+        enchantments.set(Enchantment::Sharpness, 3);
+        let packet = SetCreativeModeSlotPacket {
+            slot: 36,
+            item: Some(ItemStack::new(5, 1).with_enchantments(enchantments)),
+        };
+        let mut buffer = Vec::new();
+        packet.write(&mut buffer).unwrap();
+        assert_eq!(
+            SetCreativeModeSlotPacket::read(&mut Cursor::new(buffer)).unwrap(),
+            packet
+        );
+
+        // A custom name the server does not model is skipped by its length,
+        // and the enchantments after it are still read
+        let sharpness = Enchantment::Sharpness.definition().protocol_id as u8;
+        let unknown = [
+            0, 36, 1, 5, 2, 0, 5, 3, 0xAA, 0xBB, 0xCC, 10, 3, 1, sharpness, 3,
+        ];
+        let decoded = SetCreativeModeSlotPacket::read(&mut Cursor::new(unknown)).unwrap();
+        assert_eq!(decoded, packet);
+
+        // Removed components leave the item with its defaults
+        let removed = [0, 36, 1, 5, 0, 2, 7, 3];
+        let decoded = SetCreativeModeSlotPacket::read(&mut Cursor::new(removed)).unwrap();
+        assert_eq!(decoded.item, Some(ItemStack::new(5, 1)));
+        assert_eq!(
+            read_slot(&mut Cursor::new(&removed[2..])).unwrap(),
+            Some(ItemStack::new(5, 1))
+        );
+    }
 }
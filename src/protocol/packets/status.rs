@@ -3,6 +3,7 @@
 //! Status packets are used for server list ping functionality.
 
 use crate::error::Result;
+use crate::protocol::ConnectionState;
 use crate::protocol::packets::{ClientboundPacket, Packet, ServerboundPacket};
 use crate::protocol::types::McString;
 use std::io::{Read, Write};
@@ -13,6 +14,7 @@ pub struct StatusRequestPacket;
 
 impl Packet for StatusRequestPacket {
     const ID: i32 = 0x00;
+    const STATE: ConnectionState = ConnectionState::Status;
 
     fn read<R: Read>(_reader: &mut R) -> Result<Self> {
         Ok(StatusRequestPacket)
@@ -34,6 +36,7 @@ pub struct StatusResponsePacket {
 
 impl Packet for StatusResponsePacket {
     const ID: i32 = 0x00;
+    const STATE: ConnectionState = ConnectionState::Status;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let json_response = McString::read(reader)?;
@@ -57,6 +60,7 @@ pub struct PingRequestPacket {
 
 impl Packet for PingRequestPacket {
     const ID: i32 = 0x01;
+    const STATE: ConnectionState = ConnectionState::Status;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let mut bytes = [0u8; 8];
@@ -82,6 +86,7 @@ pub struct PingResponsePacket {
 
 impl Packet for PingResponsePacket {
     const ID: i32 = 0x01;
+    const STATE: ConnectionState = ConnectionState::Status;
 
     fn read<R: Read>(reader: &mut R) -> Result<Self> {
         let mut bytes = [0u8; 8];
@@ -131,6 +136,7 @@ pub struct PlayersInfo {
     /// Current number of online players
     pub online: u32,
     /// Sample of online players (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sample: Option<Vec<PlayerSample>>,
 }
 
@@ -153,6 +159,19 @@ pub enum Description {
     Rich(serde_json::Value),
 }
 
+/// Number of MOTD lines the server list shows
+const MOTD_LINES: usize = 2;
+
+impl Description {
+    /// Build a description from a MOTD with legacy formatting codes
+    ///
+    /// Lines beyond the two the server list shows are dropped.
+    pub fn from_motd(motd: &str) -> Self {
+        let motd = motd.lines().take(MOTD_LINES).collect::<Vec<_>>().join("\n");
+        Description::Rich(crate::protocol::text::legacy_to_json(&motd))
+    }
+}
+
 impl ServerStatus {
     /// Convert to JSON string
     pub fn to_json(&self) -> Result<String> {
@@ -0,0 +1,146 @@
+//! RSA for the login key exchange
+//!
+//! An online-mode server sends each client its public key in the
+//! Encryption Request, and the client encrypts the shared secret and the
+//! verify token with it using PKCS#1 v1.5 padding. Like vanilla, the server
+//! generates a fresh 1024-bit key pair when it starts; the key never leaves
+//! memory.
+//!
+//! The arithmetic and padding come from the [`rsa`](::rsa) crate; this
+//! module keeps the DER encoding clients are sent alongside each key.
+
+use crate::error::{Result, ServerError};
+use ::rsa::pkcs8::{DecodePublicKey, EncodePublicKey};
+use ::rsa::rand_core::OsRng;
+use ::rsa::traits::PublicKeyParts;
+use ::rsa::{Pkcs1v15Encrypt, RsaPrivateKey as PrivateKey, RsaPublicKey as PublicKey};
+
+/// Size of the modulus of generated keys, as in vanilla
+pub const KEY_BITS: usize = 1024;
+
+/// Smallest modulus accepted from a server, in bits
+const MIN_KEY_BITS: usize = 512;
+
+/// The public half of a key pair, as sent to clients
+#[derive(Clone, PartialEq, Eq)]
+pub struct RsaPublicKey {
+    /// The key
+    key: PublicKey,
+    /// X.509 SubjectPublicKeyInfo encoding
+    der: Vec<u8>,
+}
+
+impl RsaPublicKey {
+    /// Wrap a key, encoding it once
+    fn new(key: PublicKey) -> Result<Self> {
+        let der = key
+            .to_public_key_der()
+            .map_err(|e| ServerError::Protocol(format!("Failed to encode public key: {}", e)))?
+            .into_vec();
+        Ok(Self { key, der })
+    }
+
+    /// Read a key in the X.509 SubjectPublicKeyInfo encoding sent in the
+    /// Encryption Request
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let key = PublicKey::from_public_key_der(der)
+            .map_err(|e| ServerError::Protocol(format!("Malformed public key: {}", e)))?;
+        if key.n().bits() < MIN_KEY_BITS {
+            return Err(ServerError::Protocol("Public key is too short".to_string()));
+        }
+        Self::new(key)
+    }
+
+    /// The X.509 SubjectPublicKeyInfo encoding sent in the Encryption
+    /// Request
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Size of the modulus and of every ciphertext, in bytes
+    pub fn size(&self) -> usize {
+        self.key.size()
+    }
+
+    /// Encrypt a short message with PKCS#1 v1.5 padding, as a client does
+    pub fn encrypt(&self, message: &[u8]) -> Result<Vec<u8>> {
+        self.key
+            .encrypt(&mut OsRng, Pkcs1v15Encrypt, message)
+            .map_err(|e| ServerError::Protocol(format!("Failed to encrypt: {}", e)))
+    }
+}
+
+impl std::fmt::Debug for RsaPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RsaPublicKey({} bits)", self.key.n().bits())
+    }
+}
+
+/// A key pair the server decrypts login secrets with
+pub struct RsaPrivateKey {
+    /// The key
+    key: PrivateKey,
+    /// The public half
+    public: RsaPublicKey,
+}
+
+impl RsaPrivateKey {
+    /// Generate a fresh [`KEY_BITS`]-bit key pair
+    pub fn generate() -> Result<Self> {
+        let key = PrivateKey::new(&mut OsRng, KEY_BITS)
+            .map_err(|e| ServerError::Protocol(format!("Failed to generate key pair: {}", e)))?;
+        let public = RsaPublicKey::new(key.to_public_key())?;
+        Ok(Self { key, public })
+    }
+
+    /// The public half, sent to clients
+    pub fn public_key(&self) -> &RsaPublicKey {
+        &self.public
+    }
+
+    /// Decrypt a message a client encrypted with PKCS#1 v1.5 padding
+    ///
+    /// Every failure reads the same, so a client learns nothing about why
+    /// its ciphertext was rejected.
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let invalid = || ServerError::Authentication("Malformed encrypted value".to_string());
+        if ciphertext.len() != self.public.size() {
+            return Err(invalid());
+        }
+        self.key
+            .decrypt(Pkcs1v15Encrypt, ciphertext)
+            .map_err(|_| invalid())
+    }
+}
+
+impl std::fmt::Debug for RsaPrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The private key stays out of logs
+        write!(f, "RsaPrivateKey({:?})", self.public)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_exchange() {
+        let key = RsaPrivateKey::generate().unwrap();
+        assert_eq!(key.public_key().size(), KEY_BITS / 8);
+        let public = RsaPublicKey::from_der(key.public_key().der()).unwrap();
+        assert_eq!(&public, key.public_key());
+
+        let secret = [0x5Au8; 16];
+        let ciphertext = public.encrypt(&secret).unwrap();
+        assert_ne!(ciphertext, public.encrypt(&secret).unwrap());
+        assert_eq!(ciphertext.len(), KEY_BITS / 8);
+        assert_eq!(key.decrypt(&ciphertext).unwrap(), secret);
+
+        // Out of range, truncated and padded ciphertexts are all refused
+        assert!(key.decrypt(&[0xFF; KEY_BITS / 8]).is_err());
+        assert!(key.decrypt(&ciphertext[1..]).is_err());
+        assert!(key.decrypt(&[&[0][..], &ciphertext].concat()).is_err());
+        assert!(RsaPublicKey::from_der(&[0x30, 0x03, 0x02, 0x01, 0x01]).is_err());
+    }
+}
@@ -3,8 +3,10 @@
 //! This module handles the different states a Minecraft connection can be in
 //! and transitions between them.
 
+use crate::error::{Result, ServerError};
+
 /// Represents the current state of a Minecraft connection
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum ConnectionState {
     /// Initial handshaking state
     #[default]
@@ -38,6 +40,35 @@ impl ConnectionState {
             ConnectionState::Play => "play",
         }
     }
+
+    /// Whether a connection in this state may switch to another
+    ///
+    /// The handshake picks status or login, login always ends in
+    /// configuration, and a player in play can be sent back to
+    /// configuration and return.
+    pub fn can_transition_to(self, next: ConnectionState) -> bool {
+        use ConnectionState::*;
+        matches!(
+            (self, next),
+            (Handshaking, Status)
+                | (Handshaking, Login)
+                | (Login, Configuration)
+                | (Configuration, Play)
+                | (Play, Configuration)
+        )
+    }
+
+    /// Parse a state from its string representation
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "handshaking" => ConnectionState::Handshaking,
+            "status" => ConnectionState::Status,
+            "login" => ConnectionState::Login,
+            "configuration" => ConnectionState::Configuration,
+            "play" => ConnectionState::Play,
+            _ => return None,
+        })
+    }
 }
 
 /// Protocol state management for a connection
@@ -64,14 +95,23 @@ impl ProtocolState {
         }
     }
 
-    /// Transition to a new state
-    pub fn transition_to(&mut self, new_state: ConnectionState) {
+    /// Transition to a new state, failing if the current state cannot be
+    /// left for it
+    pub fn transition_to(&mut self, new_state: ConnectionState) -> Result<()> {
+        if !self.state.can_transition_to(new_state) {
+            return Err(ServerError::Protocol(format!(
+                "Illegal connection state transition: {} -> {}",
+                self.state.as_str(),
+                new_state.as_str()
+            )));
+        }
         tracing::debug!(
             "Connection state transition: {} -> {}",
             self.state.as_str(),
             new_state.as_str()
         );
         self.state = new_state;
+        Ok(())
     }
 
     /// Enable compression with the given threshold
@@ -120,3 +160,22 @@ impl Default for ProtocolState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        let mut state = ProtocolState::new();
+        assert!(state.transition_to(ConnectionState::Play).is_err());
+        assert_eq!(state.state, ConnectionState::Handshaking);
+        state.transition_to(ConnectionState::Login).unwrap();
+        assert!(state.transition_to(ConnectionState::Status).is_err());
+        state.transition_to(ConnectionState::Configuration).unwrap();
+        state.transition_to(ConnectionState::Play).unwrap();
+        state.transition_to(ConnectionState::Configuration).unwrap();
+        state.transition_to(ConnectionState::Play).unwrap();
+        assert!(state.transition_to(ConnectionState::Login).is_err());
+    }
+}
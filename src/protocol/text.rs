@@ -0,0 +1,159 @@
+//! Legacy formatting codes
+//!
+//! Configuration values such as the MOTD use the legacy format, where `§`
+//! (or `&`, which is easier to type) followed by a code character changes
+//! the color or style of the text after it. [`legacy_to_json`] converts such
+//! strings into JSON text components.
+
+use serde_json::{Map, Value};
+
+/// Characters that introduce a formatting code
+const CODE_PREFIXES: [char; 2] = ['§', '&'];
+
+/// Style built up by formatting codes
+#[derive(Debug, Clone, Default)]
+struct Style {
+    color: Option<&'static str>,
+    obfuscated: bool,
+    bold: bool,
+    strikethrough: bool,
+    underlined: bool,
+    italic: bool,
+}
+
+impl Style {
+    /// Apply a formatting code, returning `false` if it is not one
+    fn apply(&mut self, code: char) -> bool {
+        if let Some(color) = color_name(code) {
+            // Colors reset any styles, as in vanilla
+            *self = Self {
+                color: Some(color),
+                ..Self::default()
+            };
+            return true;
+        }
+        match code.to_ascii_lowercase() {
+            'k' => self.obfuscated = true,
+            'l' => self.bold = true,
+            'm' => self.strikethrough = true,
+            'n' => self.underlined = true,
+            'o' => self.italic = true,
+            'r' => *self = Self::default(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Build a component for text in this style
+    fn component(&self, text: String) -> Value {
+        let mut component = Map::new();
+        component.insert("text".to_string(), Value::String(text));
+        if let Some(color) = self.color {
+            component.insert("color".to_string(), color.into());
+        }
+        let flags = [
+            ("obfuscated", self.obfuscated),
+            ("bold", self.bold),
+            ("strikethrough", self.strikethrough),
+            ("underlined", self.underlined),
+            ("italic", self.italic),
+        ];
+        for (name, _) in flags.iter().filter(|(_, set)| *set) {
+            component.insert(name.to_string(), Value::Bool(true));
+        }
+        Value::Object(component)
+    }
+}
+
+/// Name of the color a code selects
+fn color_name(code: char) -> Option<&'static str> {
+    Some(match code.to_ascii_lowercase() {
+        '0' => "black",
+        '1' => "dark_blue",
+        '2' => "dark_green",
+        '3' => "dark_aqua",
+        '4' => "dark_red",
+        '5' => "dark_purple",
+        '6' => "gold",
+        '7' => "gray",
+        '8' => "dark_gray",
+        '9' => "blue",
+        'a' => "green",
+        'b' => "aqua",
+        'c' => "red",
+        'd' => "light_purple",
+        'e' => "yellow",
+        'f' => "white",
+        _ => return None,
+    })
+}
+
+/// Convert text with legacy formatting codes into a JSON text component
+///
+/// A prefix that is not followed by a valid code is kept as text, so an
+/// ordinary `&` is left alone.
+pub fn legacy_to_json(text: &str) -> Value {
+    let mut parts = Vec::new();
+    let mut style = Style::default();
+    let mut current = String::new();
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut next_style = style.clone();
+        let is_code =
+            CODE_PREFIXES.contains(&c) && chars.peek().is_some_and(|&code| next_style.apply(code));
+        if !is_code {
+            current.push(c);
+            continue;
+        }
+
+        chars.next();
+        if !current.is_empty() {
+            parts.push(style.component(std::mem::take(&mut current)));
+        }
+        style = next_style;
+    }
+    if !current.is_empty() {
+        parts.push(style.component(current));
+    }
+
+    match parts.len() {
+        0 => serde_json::json!({ "text": "" }),
+        1 => parts.remove(0),
+        _ => serde_json::json!({ "text": "", "extra": parts }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_legacy_to_json() {
+        assert_eq!(
+            legacy_to_json("Plain & simple"),
+            json!({ "text": "Plain & simple" })
+        );
+        assert_eq!(legacy_to_json(""), json!({ "text": "" }));
+
+        assert_eq!(
+            legacy_to_json("§6Gold §lBold&r\n&cRed"),
+            json!({
+                "text": "",
+                "extra": [
+                    { "text": "Gold ", "color": "gold" },
+                    { "text": "Bold", "color": "gold", "bold": true },
+                    { "text": "\n" },
+                    { "text": "Red", "color": "red" },
+                ]
+            })
+        );
+
+        // A color resets earlier styles
+        assert_eq!(
+            legacy_to_json("&l&AGreen"),
+            json!({ "text": "Green", "color": "green" })
+        );
+    }
+}
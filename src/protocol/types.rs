@@ -5,6 +5,7 @@
 
 use crate::error::{Result, ServerError};
 use serde_json::Value as JsonValue;
+use std::fmt;
 use std::io::{Read, Write};
 use uuid::Uuid;
 
@@ -266,7 +267,7 @@ impl From<McString> for String {
 }
 
 /// A Minecraft position (3D coordinates packed into a single i64)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Position {
     /// X coordinate
     pub x: i32,
@@ -387,24 +388,39 @@ pub fn write_int<W: Write>(value: i32, writer: &mut W) -> Result<()> {
     Ok(())
 }
 
+/// Read a VarInt array length, rejecting negative and oversized ones
+pub fn read_length<R: Read>(reader: &mut R, max: usize) -> Result<usize> {
+    let length = VarInt::read(reader)?.0;
+    usize::try_from(length)
+        .ok()
+        .filter(|&length| length <= max)
+        .ok_or_else(|| ServerError::Protocol(format!("Invalid array length {}", length)))
+}
+
+/// Read exactly `length` bytes
+///
+/// The buffer grows as bytes arrive rather than being allocated up front,
+/// so a peer announcing a long array cannot make us allocate it without
+/// sending it.
+pub fn read_bytes<R: Read>(reader: &mut R, length: usize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < length {
+        return Err(ServerError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+    }
+    Ok(bytes)
+}
+
 /// A byte array with VarInt length prefix
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ByteArray(pub Vec<u8>);
 
 impl ByteArray {
     /// Read a byte array from a reader
+    ///
+    /// The length is capped at the largest possible packet.
     pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
-        let length = VarInt::read(reader)?;
-
-        if length.0 < 0 {
-            return Err(ServerError::Protocol(
-                "Negative byte array length".to_string(),
-            ));
-        }
-
-        let mut bytes = vec![0u8; length.0 as usize];
-        reader.read_exact(&mut bytes)?;
-        Ok(ByteArray(bytes))
+        Self::read_with_max_length(reader, crate::protocol::MAX_UNCOMPRESSED_PACKET_SIZE)
     }
 
     /// Write a byte array to a writer
@@ -431,9 +447,7 @@ impl ByteArray {
             )));
         }
 
-        let mut bytes = vec![0u8; length.0 as usize];
-        reader.read_exact(&mut bytes)?;
-        Ok(ByteArray(bytes))
+        Ok(ByteArray(read_bytes(reader, length.0 as usize)?))
     }
 }
 
@@ -471,6 +485,60 @@ impl JsonTextComponent {
         mc_string.write(writer)
     }
 
+    /// Read a text component sent as network NBT (play state packets)
+    pub fn read_nbt<R: Read>(reader: &mut R) -> Result<Self> {
+        let tag = crate::protocol::nbt::read_network(reader)?;
+        Ok(JsonTextComponent(tag.to_json().to_string()))
+    }
+
+    /// Write this component as network NBT (play state packets)
+    pub fn write_nbt<W: Write>(&self, writer: &mut W) -> Result<()> {
+        use crate::protocol::nbt::{Tag, write_network};
+
+        let value = serde_json::from_str::<JsonValue>(&self.0)
+            .unwrap_or_else(|_| JsonValue::String(self.0.clone()));
+        let tag = Tag::from_json(&value).unwrap_or_else(|| Tag::String(String::new()));
+        write_network(&tag, writer)
+    }
+
+    /// Parse a JSON text component written by a user, such as the message
+    /// of `/tellraw`
+    ///
+    /// Returns `None` unless the JSON is a string, number, boolean, non-empty
+    /// array of components, or an object with content (`text`, `translate`,
+    /// `keybind`, `selector`, `score` or `nbt`) whose `extra` and `with`
+    /// lists are components too.
+    pub fn parse(json: &str) -> Option<Self> {
+        fn is_component(value: &JsonValue) -> bool {
+            let components = |value: &JsonValue| {
+                value
+                    .as_array()
+                    .is_some_and(|parts| parts.iter().all(is_component))
+            };
+            match value {
+                JsonValue::String(_) | JsonValue::Number(_) | JsonValue::Bool(_) => true,
+                JsonValue::Array(parts) => !parts.is_empty() && components(value),
+                JsonValue::Object(map) => {
+                    let content = ["text", "translate", "keybind", "selector", "nbt"]
+                        .iter()
+                        .filter_map(|key| map.get(*key))
+                        .chain(map.get("score").filter(|score| score.is_object()))
+                        .next();
+                    content.is_some_and(|content| content.is_string() || content.is_object())
+                        && map.get("extra").is_none_or(|extra| {
+                            extra.as_array().is_some_and(|parts| !parts.is_empty())
+                                && components(extra)
+                        })
+                        && map.get("with").is_none_or(components)
+                }
+                JsonValue::Null => false,
+            }
+        }
+
+        let value = serde_json::from_str::<JsonValue>(json).ok()?;
+        is_component(&value).then(|| JsonTextComponent(value.to_string()))
+    }
+
     /// Create a simple text component
     pub fn text(text: &str) -> Self {
         let json = serde_json::json!({
@@ -478,6 +546,93 @@ impl JsonTextComponent {
         });
         JsonTextComponent(json.to_string())
     }
+
+    /// Create a colored text component (e.g. "red", "gold")
+    pub fn colored(text: &str, color: &str) -> Self {
+        let json = serde_json::json!({
+            "text": text,
+            "color": color
+        });
+        JsonTextComponent(json.to_string())
+    }
+
+    /// Create a component translated by the client, with text shown if the key is unknown
+    pub fn translatable(key: &str, fallback: &str) -> Self {
+        let json = serde_json::json!({
+            "translate": key,
+            "fallback": fallback
+        });
+        JsonTextComponent(json.to_string())
+    }
+
+    /// Copy this component with a color
+    pub fn with_color(&self, color: &str) -> Self {
+        let mut json = serde_json::from_str::<JsonValue>(&self.0)
+            .unwrap_or_else(|_| JsonValue::String(self.0.clone()));
+        if !json.is_object() {
+            json = serde_json::json!({ "text": "", "extra": [json] });
+        }
+        json["color"] = color.into();
+        JsonTextComponent(json.to_string())
+    }
+
+    /// Copy this component with another one appended to it
+    pub fn append(&self, other: &JsonTextComponent) -> Self {
+        let parse = |component: &JsonTextComponent| {
+            serde_json::from_str::<JsonValue>(&component.0)
+                .unwrap_or_else(|_| JsonValue::String(component.0.clone()))
+        };
+        JsonTextComponent(JsonValue::Array(vec![parse(self), parse(other)]).to_string())
+    }
+
+    /// Extract the plain text of this component, ignoring formatting
+    ///
+    /// Translated components are rendered in the server's language (see
+    /// [`crate::lang`]).
+    pub fn to_plain_text(&self) -> String {
+        fn collect(value: &JsonValue, out: &mut String) {
+            match value {
+                JsonValue::String(s) => out.push_str(s),
+                JsonValue::Array(parts) => parts.iter().for_each(|p| collect(p, out)),
+                JsonValue::Object(map) => {
+                    if let Some(key) = map.get("translate").and_then(JsonValue::as_str) {
+                        let language = crate::lang::server_language();
+                        let template = language
+                            .get(key)
+                            .or_else(|| map.get("fallback").and_then(JsonValue::as_str))
+                            .unwrap_or(key);
+                        let args: Vec<String> = map
+                            .get("with")
+                            .and_then(JsonValue::as_array)
+                            .into_iter()
+                            .flatten()
+                            .map(|arg| {
+                                let mut text = String::new();
+                                collect(arg, &mut text);
+                                text
+                            })
+                            .collect();
+                        out.push_str(&crate::lang::format(template, &args));
+                    } else if let Some(text) = map.get("text") {
+                        collect(text, out);
+                    }
+                    if let Some(extra) = map.get("extra") {
+                        collect(extra, out);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        match serde_json::from_str::<JsonValue>(&self.0) {
+            Ok(value) => {
+                let mut out = String::new();
+                collect(&value, &mut out);
+                out
+            }
+            Err(_) => self.0.clone(),
+        }
+    }
 }
 
 impl From<String> for JsonTextComponent {
@@ -493,7 +648,7 @@ impl From<&str> for JsonTextComponent {
 }
 
 /// An identifier (namespaced string)
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Identifier(pub String);
 
 impl Identifier {
@@ -514,6 +669,15 @@ impl Identifier {
         Identifier(format!("{}:{}", namespace, path))
     }
 
+    /// Parse an identifier, adding the `minecraft` namespace if it has none
+    pub fn parse(value: &str) -> Self {
+        if value.contains(':') {
+            Identifier(value.to_string())
+        } else {
+            Identifier::new("minecraft", value)
+        }
+    }
+
     /// Get the namespace part
     pub fn namespace(&self) -> &str {
         self.0.split(':').next().unwrap_or("")
@@ -525,6 +689,12 @@ impl Identifier {
     }
 }
 
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 impl From<String> for Identifier {
     fn from(value: String) -> Self {
         Identifier(value)
@@ -726,6 +896,15 @@ mod tests {
 
         assert_eq!(byte_array, decoded);
         assert_eq!(test_data, decoded.0);
+
+        // A long array that never arrives is an error, not an allocation
+        let mut truncated = Vec::new();
+        VarInt(1_000_000).write(&mut truncated).unwrap();
+        truncated.extend_from_slice(&[1, 2, 3]);
+        let error = ByteArray::read(&mut Cursor::new(truncated)).unwrap_err();
+        assert!(
+            matches!(error, ServerError::Io(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof)
+        );
     }
 
     #[test]
@@ -739,6 +918,14 @@ mod tests {
         let decoded = JsonTextComponent::read(&mut cursor).unwrap();
 
         assert_eq!(component, decoded);
+
+        let parsed = JsonTextComponent::parse(r#"["Hi ", {"text": "there", "color": "red"}]"#);
+        assert_eq!(parsed.unwrap().to_plain_text(), "Hi there");
+        assert!(JsonTextComponent::parse(r#"{"translate": "a", "with": [1, true]}"#).is_some());
+        assert!(JsonTextComponent::parse(r#"{"color": "red"}"#).is_none());
+        assert!(JsonTextComponent::parse(r#"{"text": "a", "extra": []}"#).is_none());
+        assert!(JsonTextComponent::parse("[]").is_none());
+        assert!(JsonTextComponent::parse("{").is_none());
     }
 
     #[test]
@@ -0,0 +1,474 @@
+//! Rhai scripting
+//!
+//! Every `.rhai` file in the `scripts/` directory is run once when the
+//! server starts, after plugins are enabled. Scripts react to the game
+//! through the functions registered here:
+//!
+//! - `on(event, |event| ...)`: call a function for each event with that
+//!   name (or `"*"` for all), passing the event as a map (see
+//!   [`ServerEvent::to_json`])
+//! - `after(ticks, || ...)` and `every(ticks, || ...)`: run a function on
+//!   the main thread later or repeatedly; both return a timer ID
+//! - `cancel(timer)`: stop a timer
+//! - `players()`: the online players as maps with `name`, `uuid`, `x`, `y`
+//!   and `z`
+//! - `broadcast(text)` and `send_message(player, text)`, where `player` is
+//!   a name or UUID
+//! - `set_block(x, y, z, block)`: place a block by name on the next tick
+//!
+//! `print` writes to the server log. Each call into a script is limited in
+//! the number of operations it may run, so a runaway loop is stopped rather
+//! than stalling the server.
+
+use crate::error::{Result, ServerError};
+use crate::event::{ListenerId, ServerEvent};
+use crate::protocol::types::{JsonTextComponent, Position};
+use crate::server::ServerState;
+use crate::server::scheduler::TaskHandle;
+use rhai::{AST, Array, Dynamic, Engine, FnPtr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+
+/// Directory scripts are loaded from
+pub const SCRIPT_DIRECTORY: &str = "scripts";
+
+/// Extension of script files
+const SCRIPT_EXTENSION: &str = "rhai";
+
+/// Operations a single call into a script may run before it is stopped
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Lock a mutex, ignoring poisoning
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// What the functions registered with a script's engine act on
+struct ScriptHost {
+    /// Script name, the file name without its extension
+    name: String,
+    /// Owner of the script's scheduled tasks
+    owner: String,
+    /// The running server
+    server: Weak<ServerState>,
+    /// The script itself, once it has been compiled
+    script: OnceLock<Weak<Script>>,
+    /// Event handlers by event name
+    handlers: Mutex<Vec<(String, FnPtr)>>,
+    /// Timers started by the script, indexed by timer ID
+    timers: Mutex<Vec<TaskHandle>>,
+}
+
+impl ScriptHost {
+    /// Run a script function on the main thread after `delay` ticks, and
+    /// then every `period` ticks if one is given
+    fn start_timer(&self, delay: i64, period: Option<i64>, function: FnPtr) -> i64 {
+        let Some(server) = self.server.upgrade() else {
+            return -1;
+        };
+        let script = self.script.get().cloned().unwrap_or_default();
+        let delay = delay.max(0) as u64;
+        let handle = match period {
+            Some(period) => server.scheduler.schedule_repeating(
+                Some(&self.owner),
+                delay,
+                period.max(1) as u64,
+                move |_, _| {
+                    if let Some(script) = script.upgrade() {
+                        script.call(&function, ());
+                    }
+                },
+            ),
+            None => server
+                .scheduler
+                .schedule_later(Some(&self.owner), delay, move |_, _| {
+                    if let Some(script) = script.upgrade() {
+                        script.call(&function, ());
+                    }
+                }),
+        };
+        let mut timers = lock(&self.timers);
+        timers.push(handle);
+        timers.len() as i64 - 1
+    }
+
+    /// Stop a timer, returning whether the ID was valid
+    fn cancel_timer(&self, id: i64) -> bool {
+        let timers = lock(&self.timers);
+        match usize::try_from(id).ok().and_then(|id| timers.get(id)) {
+            Some(handle) => {
+                handle.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The online players as script maps
+    fn players(&self) -> Array {
+        let Some(server) = self.server.upgrade() else {
+            return Array::new();
+        };
+        server
+            .players
+            .map_players(|player| {
+                serde_json::json!({
+                    "name": player.username,
+                    "uuid": player.uuid.to_string(),
+                    "x": player.position.x,
+                    "y": player.position.y,
+                    "z": player.position.z,
+                })
+            })
+            .into_iter()
+            .filter_map(|player| rhai::serde::to_dynamic(player).ok())
+            .collect()
+    }
+
+    /// Send a chat message to a player by name or UUID
+    fn send_message(&self, target: &str, text: &str) -> bool {
+        let Some(server) = self.server.upgrade() else {
+            return false;
+        };
+        let uuid = server
+            .players
+            .map_players(|player| {
+                let matches = player.username.eq_ignore_ascii_case(target)
+                    || player.uuid.to_string() == target;
+                matches.then_some(player.uuid)
+            })
+            .into_iter()
+            .flatten()
+            .next();
+        match uuid {
+            Some(uuid) => {
+                server.send_message(&uuid, JsonTextComponent::text(text));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Queue a block change for the next tick
+    fn set_block(&self, x: i64, y: i64, z: i64, block: &str) -> bool {
+        let Some(server) = self.server.upgrade() else {
+            return false;
+        };
+        let (Ok(x), Ok(y), Ok(z)) = (i32::try_from(x), i32::try_from(y), i32::try_from(z)) else {
+            return false;
+        };
+        let Some(block_id) = server.blocks.get_block_id(block) else {
+            return false;
+        };
+        server
+            .scheduler
+            .schedule_later(Some(&self.owner), 0, move |_, world| {
+                world.set_block(Position::new(x, y, z), block_id);
+            });
+        true
+    }
+}
+
+/// A compiled script
+struct Script {
+    /// Engine with the server API registered
+    engine: Engine,
+    /// The compiled script
+    ast: AST,
+    /// State shared with the registered functions
+    host: Arc<ScriptHost>,
+}
+
+impl Script {
+    /// Compile a script against a server
+    fn compile(server: &Arc<ServerState>, name: &str, source: &str) -> Result<Arc<Self>> {
+        let host = Arc::new(ScriptHost {
+            name: name.to_string(),
+            owner: format!("script:{}", name),
+            server: Arc::downgrade(server),
+            script: OnceLock::new(),
+            handlers: Mutex::new(Vec::new()),
+            timers: Mutex::new(Vec::new()),
+        });
+        let engine = create_engine(&host);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| ServerError::Script(format!("{}: {}", name, e)))?;
+
+        let script = Arc::new(Self { engine, ast, host });
+        let _ = script.host.script.set(Arc::downgrade(&script));
+        Ok(script)
+    }
+
+    /// Run the script's top-level statements
+    fn run(&self) -> Result<()> {
+        self.engine
+            .run_ast(&self.ast)
+            .map_err(|e| ServerError::Script(format!("{}: {}", self.host.name, e)))
+    }
+
+    /// Call a script function, logging any error
+    fn call(&self, function: &FnPtr, args: impl rhai::FuncArgs) {
+        if let Err(e) = function.call::<Dynamic>(&self.engine, &self.ast, args) {
+            tracing::error!("Script {} failed: {}", self.host.name, e);
+        }
+    }
+
+    /// Pass an event to the script's handlers for it
+    fn handle_event(&self, event: &ServerEvent) {
+        let handlers: Vec<FnPtr> = lock(&self.host.handlers)
+            .iter()
+            .filter(|(name, _)| name == "*" || name == event.name())
+            .map(|(_, handler)| handler.clone())
+            .collect();
+        if handlers.is_empty() {
+            return;
+        }
+
+        let event = match rhai::serde::to_dynamic(event.to_json()) {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::error!("Could not pass {} to scripts: {}", event.name(), e);
+                return;
+            }
+        };
+        for handler in handlers {
+            self.call(&handler, (event.clone(),));
+        }
+    }
+}
+
+/// Create a sandboxed engine with the server API registered
+fn create_engine(host: &Arc<ScriptHost>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.disable_symbol("eval");
+
+    let name = host.name.clone();
+    engine.on_print(move |text| tracing::info!("[{}] {}", name, text));
+    let name = host.name.clone();
+    engine.on_debug(move |text, _, _| tracing::debug!("[{}] {}", name, text));
+
+    let h = Arc::clone(host);
+    engine.register_fn("on", move |event: &str, handler: FnPtr| {
+        lock(&h.handlers).push((event.to_string(), handler));
+    });
+    let h = Arc::clone(host);
+    engine.register_fn("after", move |ticks: i64, function: FnPtr| {
+        h.start_timer(ticks, None, function)
+    });
+    let h = Arc::clone(host);
+    engine.register_fn("every", move |ticks: i64, function: FnPtr| {
+        h.start_timer(ticks, Some(ticks), function)
+    });
+    let h = Arc::clone(host);
+    engine.register_fn("cancel", move |timer: i64| h.cancel_timer(timer));
+    let h = Arc::clone(host);
+    engine.register_fn("players", move || h.players());
+    let h = Arc::clone(host);
+    engine.register_fn("broadcast", move |text: &str| {
+        if let Some(server) = h.server.upgrade() {
+            server.broadcast_message(JsonTextComponent::text(text));
+        }
+    });
+    let h = Arc::clone(host);
+    engine.register_fn("send_message", move |target: &str, text: &str| {
+        h.send_message(target, text)
+    });
+    let h = Arc::clone(host);
+    engine.register_fn("set_block", move |x: i64, y: i64, z: i64, block: &str| {
+        h.set_block(x, y, z, block)
+    });
+    engine
+}
+
+/// A script that has been run
+struct LoadedScript {
+    /// The script
+    script: Arc<Script>,
+    /// Subscription delivering events to it
+    listener: ListenerId,
+}
+
+/// Loads scripts and removes their handlers and timers again
+pub struct ScriptManager {
+    /// Directory scripts are loaded from
+    directory: PathBuf,
+    /// Loaded scripts in load order
+    scripts: Mutex<Vec<LoadedScript>>,
+}
+
+impl ScriptManager {
+    /// Create a manager loading scripts from the given directory
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            scripts: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Run every script in the script directory
+    ///
+    /// Scripts that fail to compile or run are logged and skipped. Returns
+    /// the number of scripts loaded.
+    pub fn load_directory(&self, server: &Arc<ServerState>) -> usize {
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return 0,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to read script directory {}: {}",
+                    self.directory.display(),
+                    e
+                );
+                return 0;
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == SCRIPT_EXTENSION))
+            .collect();
+        paths.sort();
+
+        let mut loaded = 0;
+        for path in paths {
+            match self.load_file(server, &path) {
+                Ok(()) => loaded += 1,
+                Err(e) => tracing::error!("Could not load script {}: {}", path.display(), e),
+            }
+        }
+        loaded
+    }
+
+    /// Run a single script file
+    pub fn load_file(&self, server: &Arc<ServerState>, path: &Path) -> Result<()> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| ServerError::Script("Script file name is not valid UTF-8".into()))?;
+        let source = std::fs::read_to_string(path)?;
+        self.load(server, name, &source)
+    }
+
+    /// Compile and run a script
+    ///
+    /// If the script fails while running, the handlers and timers it set up
+    /// until then are removed again.
+    pub fn load(&self, server: &Arc<ServerState>, name: &str, source: &str) -> Result<()> {
+        if self.scripts().iter().any(|loaded| loaded == name) {
+            return Err(ServerError::Script(format!(
+                "A script named {} is already loaded",
+                name
+            )));
+        }
+
+        let script = Script::compile(server, name, source)?;
+        let weak = Arc::downgrade(&script);
+        let listener = server.events.subscribe(Arc::new(move |event| {
+            if let Some(script) = weak.upgrade() {
+                script.handle_event(event);
+            }
+        }));
+        let loaded = LoadedScript { script, listener };
+        if let Err(e) = loaded.script.run() {
+            Self::cleanup(server, &loaded);
+            return Err(e);
+        }
+
+        tracing::info!("Loaded script {}", name);
+        lock(&self.scripts).push(loaded);
+        Ok(())
+    }
+
+    /// Remove every script's event handlers and timers
+    pub fn unload_all(&self, server: &ServerState) {
+        let scripts = std::mem::take(&mut *lock(&self.scripts));
+        for loaded in scripts.iter().rev() {
+            Self::cleanup(server, loaded);
+        }
+    }
+
+    /// Names of the loaded scripts in load order
+    pub fn scripts(&self) -> Vec<String> {
+        lock(&self.scripts)
+            .iter()
+            .map(|loaded| loaded.script.host.name.clone())
+            .collect()
+    }
+
+    /// Remove the event handler and timers of a script
+    fn cleanup(server: &ServerState, loaded: &LoadedScript) {
+        server.events.unsubscribe(loaded.listener);
+        server.scheduler.cancel_plugin(&loaded.script.host.owner);
+    }
+}
+
+impl Default for ScriptManager {
+    fn default() -> Self {
+        Self::new(SCRIPT_DIRECTORY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::protocol::types::McUuid;
+
+    #[test]
+    fn test_script_lifecycle() {
+        let server = Arc::new(ServerState::new(ServerConfig::default()));
+        let source = r#"
+            let joins = 0;
+            on("player_join", |event| {
+                joins += 1;
+                set_block(0, 64, 0, "minecraft:stone");
+                print(`${event.username} joined (${joins})`);
+            });
+            let timer = every(20, || broadcast("tick"));
+            after(5, || cancel(timer));
+        "#;
+        server.scripts.load(&server, "greeter", source).unwrap();
+        assert_eq!(server.scripts.scripts(), vec!["greeter"]);
+        assert!(server.scripts.load(&server, "greeter", "").is_err());
+        assert_eq!(server.scheduler.pending_count(), 2);
+
+        server.events.publish(&ServerEvent::ServerStarted);
+        assert_eq!(server.scheduler.pending_count(), 2);
+        server.events.publish(&ServerEvent::PlayerJoin {
+            username: "Steve".to_string(),
+            uuid: McUuid::nil(),
+        });
+        assert_eq!(server.scheduler.pending_count(), 3);
+
+        server.scripts.unload_all(&server);
+        assert!(server.scripts.scripts().is_empty());
+        assert_eq!(server.scheduler.pending_count(), 0);
+        assert_eq!(server.events.listener_count(), 0);
+    }
+
+    #[test]
+    fn test_script_errors() {
+        let server = Arc::new(ServerState::new(ServerConfig::default()));
+
+        // Syntax errors and runaway loops are reported, and leave nothing behind
+        assert!(server.scripts.load(&server, "broken", "let = ;").is_err());
+        let runaway = r#"
+            every(1, || ());
+            loop {}
+        "#;
+        assert!(server.scripts.load(&server, "runaway", runaway).is_err());
+        assert!(server.scripts.scripts().is_empty());
+        assert_eq!(server.scheduler.pending_count(), 0);
+        assert_eq!(server.events.listener_count(), 0);
+
+        // Unknown blocks and players are refused
+        let source = r#"
+            if set_block(0, 64, 0, "minecraft:nonsense") { throw "placed"; }
+            if send_message("nobody", "hi") { throw "sent"; }
+            if players().len() != 0 { throw "players"; }
+        "#;
+        server.scripts.load(&server, "checks", source).unwrap();
+    }
+}
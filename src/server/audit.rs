@@ -0,0 +1,439 @@
+//! Audit log
+//!
+//! Moderation tooling needs to know who did what and when: logins and
+//! logouts, commands, kicks, bans and operator changes. Each is appended to
+//! `logs/audit.jsonl` as one JSON object per line, with a `time` and a
+//! `type` field, for example
+//!
+//! ```json
+//! {"time":"2026-10-15T18:04:11Z","type":"ban","player":"Griefer","uuid":"...","source":"Server","reason":"Banned by an operator."}
+//! ```
+//!
+//! Once the file grows past its maximum size it is renamed to
+//! `audit.jsonl.1`, older files moving up one number, and a new file is
+//! started. Login addresses are left out when `log-ips` is `false`.
+
+use crate::error::{Result, ServerError};
+use crate::protocol::types::McUuid;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Default file the audit log is written to
+pub const AUDIT_FILE: &str = "logs/audit.jsonl";
+
+/// Entries returned by a query unless it asks for fewer
+pub const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// Audit log settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditConfig {
+    /// Whether the audit log is written
+    pub enabled: bool,
+    /// File the audit log is written to
+    pub path: PathBuf,
+    /// Bytes the file may grow to before it is rotated
+    pub max_size: u64,
+    /// Rotated files kept (0 keeps none)
+    pub keep: usize,
+}
+
+impl AuditConfig {
+    /// Settings that write no audit log
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: PathBuf::from(AUDIT_FILE),
+            max_size: 10 * 1024 * 1024,
+            keep: 5,
+        }
+    }
+}
+
+/// Something worth auditing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A player joined
+    Login {
+        /// Player name
+        player: String,
+        /// Player UUID
+        uuid: McUuid,
+        /// Address the player connected from, unless `log-ips` is off
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ip: Option<String>,
+    },
+    /// A player left
+    Logout {
+        /// Player name
+        player: String,
+        /// Player UUID
+        uuid: McUuid,
+    },
+    /// A command was run
+    Command {
+        /// Who ran the command
+        source: String,
+        /// Command line, without the leading slash
+        command: String,
+        /// Whether the command succeeded
+        success: bool,
+    },
+    /// A player was kicked
+    Kick {
+        /// Player name
+        player: String,
+        /// Who kicked the player
+        source: String,
+        /// Reason shown to the player
+        reason: String,
+    },
+    /// A player was banned
+    Ban {
+        /// Player name
+        player: String,
+        /// Player UUID
+        uuid: McUuid,
+        /// Who banned the player
+        source: String,
+        /// Reason shown to the player
+        reason: String,
+    },
+    /// A ban was removed
+    Pardon {
+        /// Player name
+        player: String,
+        /// Who removed the ban
+        source: String,
+    },
+    /// A player was made an operator
+    Op {
+        /// Player name
+        player: String,
+        /// Player UUID
+        uuid: McUuid,
+        /// Who made the player an operator
+        source: String,
+        /// Permission level granted
+        level: u8,
+    },
+    /// A player stopped being an operator
+    Deop {
+        /// Player name
+        player: String,
+        /// Player UUID
+        uuid: McUuid,
+        /// Who removed the operator
+        source: String,
+    },
+}
+
+impl AuditEvent {
+    /// Name of the event's `type` field
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuditEvent::Login { .. } => "login",
+            AuditEvent::Logout { .. } => "logout",
+            AuditEvent::Command { .. } => "command",
+            AuditEvent::Kick { .. } => "kick",
+            AuditEvent::Ban { .. } => "ban",
+            AuditEvent::Pardon { .. } => "pardon",
+            AuditEvent::Op { .. } => "op",
+            AuditEvent::Deop { .. } => "deop",
+        }
+    }
+
+    /// Whether a player, by name, is the subject or the source of the event
+    pub fn involves(&self, name: &str) -> bool {
+        let (player, source) = match self {
+            AuditEvent::Login { player, .. } | AuditEvent::Logout { player, .. } => {
+                (Some(player), None)
+            }
+            AuditEvent::Command { source, .. } => (None, Some(source)),
+            AuditEvent::Kick { player, source, .. }
+            | AuditEvent::Ban { player, source, .. }
+            | AuditEvent::Pardon { player, source }
+            | AuditEvent::Op { player, source, .. }
+            | AuditEvent::Deop { player, source, .. } => (Some(player), Some(source)),
+        };
+        [player, source]
+            .into_iter()
+            .flatten()
+            .any(|candidate| candidate.eq_ignore_ascii_case(name))
+    }
+}
+
+/// An audited event and when it happened
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the event happened, as `yyyy-MM-ddTHH:mm:ssZ` in UTC
+    pub time: String,
+    /// What happened
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+impl AuditEntry {
+    /// Create an entry for an event happening now
+    pub fn now(event: AuditEvent) -> Self {
+        let time = time::OffsetDateTime::now_utc()
+            .format(time::macros::format_description!(
+                "[year]-[month]-[day]T[hour]:[minute]:[second]Z"
+            ))
+            .unwrap_or_default();
+        Self { time, event }
+    }
+}
+
+/// Filters for reading the audit log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditQuery {
+    /// Only events involving this player, as subject or source
+    pub player: Option<String>,
+    /// Only events of this type, such as `ban`
+    pub kind: Option<String>,
+    /// Only events at or after this time, or a prefix of one such as
+    /// `2026-10-15`
+    pub since: Option<String>,
+    /// Most recent entries returned
+    pub limit: usize,
+}
+
+impl AuditQuery {
+    /// Whether an entry passes the filters
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.player
+            .as_deref()
+            .is_none_or(|player| entry.event.involves(player))
+            && self
+                .kind
+                .as_deref()
+                .is_none_or(|kind| entry.event.kind() == kind)
+            && self
+                .since
+                .as_deref()
+                .is_none_or(|since| entry.time.as_str() >= since)
+    }
+}
+
+impl Default for AuditQuery {
+    fn default() -> Self {
+        Self {
+            player: None,
+            kind: None,
+            since: None,
+            limit: DEFAULT_QUERY_LIMIT,
+        }
+    }
+}
+
+/// Open audit file and its size
+#[derive(Debug)]
+struct OpenFile {
+    /// File appended to
+    file: File,
+    /// Bytes in the file
+    size: u64,
+}
+
+/// The audit log of a server
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    /// Settings, or `None` if nothing is recorded
+    config: Option<AuditConfig>,
+    /// Whether login addresses are recorded
+    log_ips: bool,
+    /// File being appended to, opened on the first event
+    file: Mutex<Option<OpenFile>>,
+}
+
+impl AuditLog {
+    /// Create an audit log that records nothing
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Create an audit log with the given settings
+    pub fn new(config: AuditConfig, log_ips: bool) -> Self {
+        Self {
+            config: config.enabled.then_some(config),
+            log_ips,
+            file: Mutex::new(None),
+        }
+    }
+
+    /// Record an event, logging a warning if it cannot be written
+    pub fn record(&self, mut event: AuditEvent) {
+        let Some(config) = &self.config else {
+            return;
+        };
+        if let AuditEvent::Login { ip, .. } = &mut event {
+            if !self.log_ips {
+                *ip = None;
+            }
+        }
+        let entry = AuditEntry::now(event);
+        if let Err(e) = self.append(config, &entry) {
+            tracing::warn!("Failed to write the audit log: {}", e);
+        }
+    }
+
+    /// Read the most recent entries passing a query's filters, oldest first
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let Some(config) = &self.config else {
+            return Ok(Vec::new());
+        };
+        // Hold the file so it is not rotated while being read
+        let _file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut entries = Vec::new();
+        let paths = (1..=config.keep)
+            .rev()
+            .map(|n| rotated_path(&config.path, n))
+            .chain(std::iter::once(config.path.clone()));
+        for path in paths {
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for line in BufReader::new(file).lines() {
+                // Skip lines cut short by a crash
+                let Ok(entry) = serde_json::from_str::<AuditEntry>(&line?) else {
+                    continue;
+                };
+                if query.matches(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+        let skip = entries.len().saturating_sub(query.limit);
+        entries.drain(..skip);
+        Ok(entries)
+    }
+
+    /// Append an entry, rotating the file first if it would grow too large
+    fn append(&self, config: &AuditConfig, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|e| ServerError::Storage(format!("Failed to encode audit entry: {}", e)))?;
+        line.push('\n');
+
+        let mut open = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let size = match open.as_ref() {
+            Some(open) => open.size,
+            None => std::fs::metadata(&config.path).map_or(0, |metadata| metadata.len()),
+        };
+        if size > 0 && size + line.len() as u64 > config.max_size {
+            *open = None;
+            rotate(config)?;
+        }
+        if open.is_none() {
+            if let Some(parent) = config.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&config.path)?;
+            let size = file.metadata()?.len();
+            *open = Some(OpenFile { file, size });
+        }
+
+        if let Some(open) = open.as_mut() {
+            open.file.write_all(line.as_bytes())?;
+            open.size += line.len() as u64;
+        }
+        Ok(())
+    }
+}
+
+/// Path of the `n`th most recent rotated file
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Move the current file and the rotated ones up one number, dropping the
+/// oldest
+fn rotate(config: &AuditConfig) -> Result<()> {
+    if config.keep == 0 {
+        return Ok(std::fs::remove_file(&config.path)?);
+    }
+    for n in (1..config.keep).rev() {
+        let from = rotated_path(&config.path, n);
+        if from.exists() {
+            std::fs::rename(from, rotated_path(&config.path, n + 1))?;
+        }
+    }
+    std::fs::rename(&config.path, rotated_path(&config.path, 1))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_rotate_and_query() {
+        let dir = std::env::temp_dir().join(format!("obsidium-audit-{}", McUuid::new_v4()));
+        let config = AuditConfig {
+            enabled: true,
+            path: dir.join("audit.jsonl"),
+            max_size: 300,
+            keep: 2,
+        };
+        let audit = AuditLog::new(config.clone(), false);
+        let uuid = McUuid::new_v4();
+        for _ in 0..4 {
+            audit.record(AuditEvent::Login {
+                player: "Steve".to_string(),
+                uuid,
+                ip: Some("127.0.0.1".to_string()),
+            });
+        }
+        audit.record(AuditEvent::Command {
+            source: "Server".to_string(),
+            command: "ban Steve".to_string(),
+            success: true,
+        });
+        assert!(rotated_path(&config.path, 1).exists());
+
+        let logins = audit
+            .query(&AuditQuery {
+                kind: Some("login".to_string()),
+                ..AuditQuery::default()
+            })
+            .unwrap();
+        assert!(!logins.is_empty());
+        assert!(
+            logins
+                .iter()
+                .all(|entry| matches!(entry.event, AuditEvent::Login { ip: None, .. }))
+        );
+
+        let by_server = AuditQuery {
+            player: Some("server".to_string()),
+            limit: 1,
+            ..AuditQuery::default()
+        };
+        let entries = audit.query(&by_server).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].event.kind(), "command");
+
+        assert!(AuditLog::disabled().query(&by_server).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,504 @@
+//! World backups
+//!
+//! A backup is a copy of the world directory in the backup directory, named
+//! after the world and the time it was taken: either a plain directory or a
+//! gzip-compressed tar archive. The world is saved and flushed first, and
+//! automatic saving is paused while files are copied so the backup is a
+//! consistent snapshot. Copying runs on a blocking thread, so the tick loop
+//! keeps running; progress is logged and shown by `/backup status`.
+//!
+//! Once a backup is written, the oldest backups of the world beyond the
+//! configured count or age are removed.
+
+use crate::error::{Result, ServerError};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Directory backups are written to by default
+pub const DEFAULT_BACKUP_DIR: &str = "backups";
+
+/// Backups kept by default
+pub const DEFAULT_KEEP: usize = 5;
+
+/// Suffix of backups still being written
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Size of a tar block
+const TAR_BLOCK: usize = 512;
+
+/// How backups are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackupFormat {
+    /// A plain copy of the world directory
+    #[default]
+    Copy,
+    /// A gzip-compressed tar archive
+    TarGz,
+}
+
+impl BackupFormat {
+    /// Look up a format by its configuration name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "copy" => Some(Self::Copy),
+            "tar.gz" => Some(Self::TarGz),
+            _ => None,
+        }
+    }
+
+    /// Configuration name of the format
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Copy => "copy",
+            Self::TarGz => "tar.gz",
+        }
+    }
+
+    /// File name extension of backups in this format
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Copy => "",
+            Self::TarGz => ".tar.gz",
+        }
+    }
+}
+
+/// Backup settings, from the `[backups]` section of obsidium.toml
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupConfig {
+    /// Directory backups are written to
+    pub directory: PathBuf,
+    /// Interval between scheduled backups (zero disables them)
+    pub interval: Duration,
+    /// Backups kept per world (zero keeps all)
+    pub keep: usize,
+    /// Age after which backups are removed (zero keeps them forever)
+    pub max_age: Duration,
+    /// How backups are stored
+    pub format: BackupFormat,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from(DEFAULT_BACKUP_DIR),
+            interval: Duration::ZERO,
+            keep: DEFAULT_KEEP,
+            max_age: Duration::ZERO,
+            format: BackupFormat::Copy,
+        }
+    }
+}
+
+/// A finished backup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupReport {
+    /// Where the backup was written
+    pub path: PathBuf,
+    /// Files backed up
+    pub files: usize,
+    /// Bytes read from the world directory
+    pub bytes: u64,
+    /// Old backups removed afterwards
+    pub removed: usize,
+    /// Time taken to copy the files
+    pub duration: Duration,
+}
+
+/// Files copied by the running backup
+#[derive(Debug, Default)]
+pub struct BackupProgress {
+    /// Files copied so far
+    done: AtomicUsize,
+    /// Files to copy
+    total: AtomicUsize,
+}
+
+impl BackupProgress {
+    /// Files copied so far and files to copy
+    pub fn get(&self) -> (usize, usize) {
+        (
+            self.done.load(Ordering::Relaxed),
+            self.total.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Tracks requested, running and finished backups
+#[derive(Debug, Default)]
+pub struct BackupManager {
+    /// Whether a backup is running
+    running: AtomicBool,
+    /// Whether a backup should start on the next tick
+    requested: AtomicBool,
+    /// Progress of the running backup
+    progress: Arc<BackupProgress>,
+    /// Outcome of the last backup
+    last: Mutex<Option<std::result::Result<BackupReport, String>>>,
+}
+
+impl BackupManager {
+    /// Create a manager with no backups taken
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask for a backup on the next tick
+    ///
+    /// Returns `false` if a backup is running or requested already.
+    pub fn request(&self) -> bool {
+        !self.is_running() && !self.requested.swap(true, Ordering::Relaxed)
+    }
+
+    /// Take a pending request, returning whether there was one
+    pub fn take_request(&self) -> bool {
+        self.requested.swap(false, Ordering::Relaxed)
+    }
+
+    /// Whether a backup is running
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Files copied and files to copy by the running backup
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.is_running().then(|| self.progress.get())
+    }
+
+    /// Outcome of the last finished backup
+    pub fn last_result(&self) -> Option<std::result::Result<BackupReport, String>> {
+        self.last.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Mark a backup as started, returning its progress, or `None` if one
+    /// is running already
+    pub(crate) fn begin(&self) -> Option<Arc<BackupProgress>> {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        self.progress.done.store(0, Ordering::Relaxed);
+        self.progress.total.store(0, Ordering::Relaxed);
+        Some(Arc::clone(&self.progress))
+    }
+
+    /// Record the outcome of the running backup
+    pub(crate) fn finish(&self, result: &Result<BackupReport>) {
+        *self.last.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(result.as_ref().cloned().map_err(ToString::to_string));
+        self.running.store(false, Ordering::Release);
+    }
+}
+
+/// Back up a world directory, then remove old backups
+///
+/// This blocks until every file is copied and should run on a blocking
+/// thread.
+pub fn create(
+    world_dir: &Path,
+    config: &BackupConfig,
+    progress: &BackupProgress,
+) -> Result<BackupReport> {
+    let started = Instant::now();
+    let world_name = world_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("world")
+        .to_string();
+    let files = list_files(world_dir)?;
+    progress.total.store(files.len(), Ordering::Relaxed);
+    fs::create_dir_all(&config.directory)?;
+
+    let path = backup_path(&config.directory, &world_name, config.format)?;
+    let mut partial = path.clone().into_os_string();
+    partial.push(PARTIAL_SUFFIX);
+    let partial = PathBuf::from(partial);
+    let written = match config.format {
+        BackupFormat::Copy => copy_files(world_dir, &files, &partial, progress),
+        BackupFormat::TarGz => archive_files(world_dir, &world_name, &files, &partial, progress),
+    };
+    let written = written.and_then(|bytes| {
+        fs::rename(&partial, &path)?;
+        Ok(bytes)
+    });
+    let bytes = match written {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&partial).or_else(|_| fs::remove_file(&partial));
+            return Err(e);
+        }
+    };
+
+    let removed = prune(&config.directory, &world_name, config.keep, config.max_age)?;
+    Ok(BackupReport {
+        path,
+        files: files.len(),
+        bytes,
+        removed,
+        duration: started.elapsed(),
+    })
+}
+
+/// Every file below a directory, relative to it, in a stable order
+fn list_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut directories = vec![PathBuf::new()];
+    while let Some(relative) = directories.pop() {
+        for entry in fs::read_dir(root.join(&relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                directories.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Unused path for a new backup, named after the world and the time
+fn backup_path(directory: &Path, world_name: &str, format: BackupFormat) -> Result<PathBuf> {
+    let timestamp = time::OffsetDateTime::now_utc()
+        .format(time::macros::format_description!(
+            "[year][month][day]-[hour][minute][second]-[subsecond digits:3]"
+        ))
+        .map_err(|e| ServerError::Storage(format!("Cannot format backup time: {}", e)))?;
+    let extension = format.extension();
+    let mut path = directory.join(format!("{}-{}{}", world_name, timestamp, extension));
+    let mut attempt = 1;
+    while path.exists() {
+        attempt += 1;
+        path = directory.join(format!(
+            "{}-{}-{}{}",
+            world_name, timestamp, attempt, extension
+        ));
+    }
+    Ok(path)
+}
+
+/// Count a copied file, logging every quarter of the way
+fn advance(progress: &BackupProgress) {
+    let done = progress.done.fetch_add(1, Ordering::Relaxed) + 1;
+    let total = progress.total.load(Ordering::Relaxed);
+    let quarter = total.div_ceil(4).max(1);
+    if done.is_multiple_of(quarter) && done < total {
+        tracing::info!(
+            "Backup {}% done ({}/{} files)",
+            done * 100 / total,
+            done,
+            total
+        );
+    }
+}
+
+/// Copy files into a new directory, returning the bytes copied
+fn copy_files(
+    world_dir: &Path,
+    files: &[PathBuf],
+    destination: &Path,
+    progress: &BackupProgress,
+) -> Result<u64> {
+    let mut bytes = 0;
+    for file in files {
+        let target = destination.join(file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        bytes += fs::copy(world_dir.join(file), target)?;
+        advance(progress);
+    }
+    Ok(bytes)
+}
+
+/// Write files into a new gzip-compressed tar archive below a directory
+/// named after the world, returning the bytes archived
+fn archive_files(
+    world_dir: &Path,
+    world_name: &str,
+    files: &[PathBuf],
+    destination: &Path,
+    progress: &BackupProgress,
+) -> Result<u64> {
+    let mut archive = GzEncoder::new(
+        BufWriter::new(File::create(destination)?),
+        Compression::default(),
+    );
+    let mut bytes = 0;
+    for file in files {
+        let name = Path::new(world_name).join(file);
+        let name = name.to_string_lossy().replace('\\', "/");
+        let mut source = File::open(world_dir.join(file))?;
+        let metadata = source.metadata()?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or(0, |age| age.as_secs());
+        write_tar_entry(&mut archive, &name, &mut source, metadata.len(), modified)?;
+        bytes += metadata.len();
+        advance(progress);
+    }
+    archive.write_all(&[0; TAR_BLOCK * 2])?;
+    archive.finish()?.flush()?;
+    Ok(bytes)
+}
+
+/// Write a file to a tar archive with a ustar header
+fn write_tar_entry<W: Write>(
+    out: &mut W,
+    name: &str,
+    source: &mut File,
+    size: u64,
+    modified: u64,
+) -> io::Result<()> {
+    let (prefix, name) = split_tar_name(name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Path too long for tar: {}", name),
+        )
+    })?;
+    let mut header = [0u8; TAR_BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644)?;
+    write_octal(&mut header[108..116], 0)?;
+    write_octal(&mut header[116..124], 0)?;
+    write_octal(&mut header[124..136], size)?;
+    write_octal(&mut header[136..148], modified)?;
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].fill(b' ');
+    let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    out.write_all(&header)?;
+
+    let copied = io::copy(&mut source.take(size), out)?;
+    if copied != size {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("{} changed while it was archived", name),
+        ));
+    }
+    let padding = (TAR_BLOCK - size as usize % TAR_BLOCK) % TAR_BLOCK;
+    out.write_all(&[0; TAR_BLOCK][..padding])
+}
+
+/// Split a path into a ustar prefix of up to 155 bytes and a name of up
+/// to 100 bytes, cutting at a slash
+fn split_tar_name(path: &str) -> Option<(&str, &str)> {
+    if path.len() <= 100 {
+        return Some(("", path));
+    }
+    path.match_indices('/')
+        .map(|(index, _)| (&path[..index], &path[index + 1..]))
+        .find(|(prefix, name)| prefix.len() <= 155 && name.len() <= 100 && !name.is_empty())
+}
+
+/// Write a zero-padded octal number ending in a NUL byte into a header field
+fn write_octal(field: &mut [u8], value: u64) -> io::Result<()> {
+    let digits = format!("{:0width$o}\0", value, width = field.len() - 1);
+    if digits.len() != field.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} does not fit in a tar header", value),
+        ));
+    }
+    field.copy_from_slice(digits.as_bytes());
+    Ok(())
+}
+
+/// Remove the oldest backups of a world beyond `keep`, and those older
+/// than `max_age`, returning how many were removed
+fn prune(directory: &Path, world_name: &str, keep: usize, max_age: Duration) -> Result<usize> {
+    let prefix = format!("{}-", world_name);
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(&prefix) && !name.ends_with(PARTIAL_SUFFIX) {
+            let age = entry
+                .metadata()?
+                .modified()
+                .ok()
+                .and_then(|time| time.elapsed().ok())
+                .unwrap_or_default();
+            backups.push((name, entry.path(), age));
+        }
+    }
+    // Names end in the time the backup was taken, so they sort oldest first
+    backups.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let excess = if keep == 0 {
+        0
+    } else {
+        backups.len().saturating_sub(keep)
+    };
+    let mut removed = 0;
+    for (index, (_, path, age)) in backups.iter().enumerate() {
+        let expired = !max_age.is_zero() && *age > max_age;
+        // Never remove the newest backup
+        if (index < excess || expired) && index + 1 < backups.len() {
+            if path.is_dir() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::McUuid;
+    use flate2::read::GzDecoder;
+
+    #[test]
+    fn test_backups_are_written_and_pruned() {
+        let root = std::env::temp_dir().join(format!("obsidium-backup-{}", McUuid::new_v4()));
+        let world = root.join("world");
+        fs::create_dir_all(world.join("region")).unwrap();
+        fs::write(world.join("level.dat"), b"level").unwrap();
+        fs::write(world.join("region").join("r.0.0.mca"), vec![7; 1000]).unwrap();
+
+        let mut config = BackupConfig {
+            directory: root.join("backups"),
+            keep: 2,
+            ..BackupConfig::default()
+        };
+        let progress = BackupProgress::default();
+        let copy = create(&world, &config, &progress).unwrap();
+        assert_eq!((copy.files, copy.bytes), (2, 1005));
+        assert_eq!(progress.get(), (2, 2));
+        assert_eq!(fs::read(copy.path.join("level.dat")).unwrap(), b"level");
+
+        config.format = BackupFormat::TarGz;
+        let archive = create(&world, &config, &progress).unwrap();
+        assert!(archive.path.to_string_lossy().ends_with(".tar.gz"));
+        let mut tar = Vec::new();
+        GzDecoder::new(File::open(&archive.path).unwrap())
+            .read_to_end(&mut tar)
+            .unwrap();
+        assert_eq!(&tar[..15], b"world/level.dat");
+        assert_eq!(&tar[257..263], b"ustar\0");
+        assert_eq!(&tar[TAR_BLOCK..TAR_BLOCK + 5], b"level");
+        assert_eq!(tar.len(), TAR_BLOCK * 7);
+
+        let third = create(&world, &config, &progress).unwrap();
+        assert_eq!(third.removed, 1);
+        assert!(!copy.path.exists());
+        assert!(archive.path.exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
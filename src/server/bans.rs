@@ -0,0 +1,316 @@
+//! Player and IP bans
+//!
+//! Bans are stored in `banned-players.json` and `banned-ips.json` next to
+//! server.properties using the vanilla format, so the files can be shared
+//! with vanilla servers and edited by existing tools. Banned players and players connecting from a
+//! banned address are refused during login.
+
+use crate::error::{Result, ServerError};
+use crate::lang;
+use crate::protocol::types::{JsonTextComponent, McUuid};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// File bans are stored in
+pub const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+
+/// File IP bans are stored in
+pub const BANNED_IPS_FILE: &str = "banned-ips.json";
+
+/// Expiry value of a permanent ban
+const FOREVER: &str = "forever";
+
+/// Default ban reason, as used by vanilla
+pub const DEFAULT_REASON: &str = "Banned by an operator.";
+
+/// A banned player
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanEntry {
+    /// Player UUID
+    pub uuid: McUuid,
+    /// Player name when the ban was created
+    pub name: String,
+    /// When the ban was created (`yyyy-MM-dd HH:mm:ss Z`)
+    pub created: String,
+    /// Who created the ban
+    pub source: String,
+    /// When the ban expires, or `forever`
+    pub expires: String,
+    /// Reason shown to the player
+    pub reason: String,
+}
+
+impl BanEntry {
+    /// Create a permanent ban created now
+    pub fn new(uuid: McUuid, name: &str, source: &str, reason: Option<&str>) -> Self {
+        Self {
+            uuid,
+            name: name.to_string(),
+            created: now(),
+            source: source.to_string(),
+            expires: FOREVER.to_string(),
+            reason: reason.unwrap_or(DEFAULT_REASON).to_string(),
+        }
+    }
+
+    /// Message shown to the player when they are refused
+    pub fn message(&self) -> JsonTextComponent {
+        refusal(
+            "multiplayer.disconnect.banned.reason",
+            &self.reason,
+            &self.expires,
+        )
+    }
+}
+
+/// A banned address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpBanEntry {
+    /// Banned address
+    pub ip: IpAddr,
+    /// When the ban was created (`yyyy-MM-dd HH:mm:ss Z`)
+    pub created: String,
+    /// Who created the ban
+    pub source: String,
+    /// When the ban expires, or `forever`
+    pub expires: String,
+    /// Reason shown to players connecting from the address
+    pub reason: String,
+}
+
+impl IpBanEntry {
+    /// Create a permanent ban created now
+    pub fn new(ip: IpAddr, source: &str, reason: Option<&str>) -> Self {
+        Self {
+            ip: ip.to_canonical(),
+            created: now(),
+            source: source.to_string(),
+            expires: FOREVER.to_string(),
+            reason: reason.unwrap_or(DEFAULT_REASON).to_string(),
+        }
+    }
+
+    /// Message shown to players connecting from the address
+    pub fn message(&self) -> JsonTextComponent {
+        refusal(
+            "multiplayer.disconnect.banned_ip.reason",
+            &self.reason,
+            &self.expires,
+        )
+    }
+}
+
+/// An entry of a ban list
+pub trait Ban: Clone + Serialize + DeserializeOwned {
+    /// What is banned, such as a player's UUID
+    type Target: PartialEq;
+
+    /// What the entry bans
+    fn target(&self) -> &Self::Target;
+
+    /// Whether `name` names the entry, for pardoning by name
+    fn is_named(&self, name: &str) -> bool;
+}
+
+impl Ban for BanEntry {
+    type Target = McUuid;
+
+    fn target(&self) -> &McUuid {
+        &self.uuid
+    }
+
+    fn is_named(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+}
+
+impl Ban for IpBanEntry {
+    type Target = IpAddr;
+
+    fn target(&self) -> &IpAddr {
+        &self.ip
+    }
+
+    fn is_named(&self, name: &str) -> bool {
+        name.parse::<IpAddr>()
+            .is_ok_and(|ip| ip.to_canonical() == self.ip)
+    }
+}
+
+/// A list of banned players or addresses
+#[derive(Debug)]
+pub struct BanList<E = BanEntry> {
+    /// File the list is saved to (`None` keeps it in memory only)
+    path: Option<PathBuf>,
+    /// Bans in the order they were created
+    entries: RwLock<Vec<E>>,
+}
+
+impl<E> Default for BanList<E> {
+    fn default() -> Self {
+        Self {
+            path: None,
+            entries: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+impl<E: Ban> BanList<E> {
+    /// Create an empty ban list that is not saved to disk
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the ban list from a file, starting empty if it does not exist
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| ServerError::Storage(format!("Invalid {}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path: Some(path),
+            entries: RwLock::new(entries),
+        })
+    }
+
+    /// Get the ban of a player or address, if they are banned
+    pub fn get(&self, target: &E::Target) -> Option<E> {
+        self.read()
+            .iter()
+            .find(|entry| entry.target() == target)
+            .cloned()
+    }
+
+    /// All bans in the order they were created
+    pub fn entries(&self) -> Vec<E> {
+        self.read().clone()
+    }
+
+    /// Add a ban, replacing any existing ban of the same target, and save
+    /// the list
+    pub fn ban(&self, entry: E) -> Result<()> {
+        let mut entries = self.write();
+        entries.retain(|existing| existing.target() != entry.target());
+        entries.push(entry);
+        self.save(&entries)
+    }
+
+    /// Remove a ban by player name or address, returning whether one
+    /// existed
+    pub fn pardon(&self, name: &str) -> Result<bool> {
+        let mut entries = self.write();
+        let before = entries.len();
+        entries.retain(|entry| !entry.is_named(name));
+        if entries.len() == before {
+            return Ok(false);
+        }
+        self.save(&entries)?;
+        Ok(true)
+    }
+
+    /// Write the list to its file
+    fn save(&self, entries: &[E]) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(entries)
+            .map_err(|e| ServerError::Storage(format!("Failed to encode bans: {}", e)))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<E>> {
+        self.entries.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<E>> {
+        self.entries.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Current time in the format bans are dated with
+fn now() -> String {
+    time::OffsetDateTime::now_utc()
+        .format(time::macros::format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second] +0000"
+        ))
+        .unwrap_or_default()
+}
+
+/// Message refusing a banned player, with the expiry of a temporary ban
+fn refusal(key: &str, reason: &str, expires: &str) -> JsonTextComponent {
+    let message = lang::translate(key, &[reason]);
+    if expires == FOREVER {
+        return message;
+    }
+    message.append(&lang::translate(
+        "multiplayer.disconnect.banned.expiration",
+        &[expires],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ban_and_pardon() {
+        let path = std::env::temp_dir().join(format!("obsidium-bans-{}.json", McUuid::new_v4()));
+        let uuid = McUuid::new_v4();
+
+        let bans = BanList::load(&path).unwrap();
+        assert!(bans.get(&uuid).is_none());
+        bans.ban(BanEntry::new(uuid, "Griefer", "Server", None))
+            .unwrap();
+        assert_eq!(bans.get(&uuid).unwrap().reason, DEFAULT_REASON);
+
+        let reloaded = BanList::<BanEntry>::load(&path).unwrap();
+        assert_eq!(reloaded.entries(), bans.entries());
+        assert!(reloaded.pardon("griefer").unwrap());
+        assert!(!reloaded.pardon("griefer").unwrap());
+        assert!(
+            BanList::<BanEntry>::load(&path)
+                .unwrap()
+                .get(&uuid)
+                .is_none()
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_ip_bans() {
+        let path = std::env::temp_dir().join(format!("obsidium-ip-bans-{}.json", McUuid::new_v4()));
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+
+        let bans = BanList::load(&path).unwrap();
+        bans.ban(IpBanEntry::new(ip, "Server", Some("Griefing")))
+            .unwrap();
+        let mapped: IpAddr = "::ffff:192.0.2.1".parse().unwrap();
+        assert_eq!(bans.get(&mapped.to_canonical()).unwrap().reason, "Griefing");
+
+        // The file uses vanilla's format
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json[0]["ip"], "192.0.2.1");
+        assert_eq!(json[0]["expires"], FOREVER);
+
+        assert!(!bans.pardon("192.0.2.2").unwrap());
+        assert!(bans.pardon("192.0.2.1").unwrap());
+        assert!(
+            BanList::<IpBanEntry>::load(&path)
+                .unwrap()
+                .get(&ip)
+                .is_none()
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,95 @@
+//! Interactive server console
+//!
+//! Lines typed on standard input are executed as commands on behalf of the
+//! [`ConsoleSender`]. Stdin is read on a dedicated OS thread because a
+//! blocking read would otherwise keep the async runtime from shutting down.
+
+use crate::command::ConsoleSender;
+use crate::server::ServerState;
+use std::io::BufRead;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Spawn the console reader thread and the task that executes its commands
+///
+/// The task ends when stdin is closed or the server shuts down.
+pub fn spawn(state: Arc<ServerState>) -> tokio::task::JoinHandle<()> {
+    let (line_sender, line_receiver) = mpsc::unbounded_channel::<String>();
+
+    let reader = std::thread::Builder::new()
+        .name("console".to_string())
+        .spawn(move || {
+            let stdin = std::io::stdin();
+            for line in stdin.lock().lines() {
+                let Ok(line) = line else { break };
+                if line_sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+    if let Err(e) = reader {
+        tracing::warn!("Failed to start console input thread: {}", e);
+    }
+
+    tokio::spawn(run(state, line_receiver))
+}
+
+/// Execute console lines as commands until input closes or the server
+/// shuts down
+async fn run(state: Arc<ServerState>, mut lines: mpsc::UnboundedReceiver<String>) {
+    let sender = ConsoleSender;
+    let mut shutdown = state.subscribe_shutdown();
+
+    loop {
+        tokio::select! {
+            line = lines.recv() => {
+                let Some(line) = line else {
+                    tracing::debug!("Console input closed");
+                    break;
+                };
+
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                let _ = state.execute_command(&sender, line).await;
+            }
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+
+    #[tokio::test]
+    async fn test_console_commands() {
+        let state = Arc::new(ServerState::new(ServerConfig::default()));
+        let mut chat = state.subscribe_chat();
+        let (lines, receiver) = mpsc::unbounded_channel();
+        let console = tokio::spawn(run(Arc::clone(&state), receiver));
+
+        // Blank lines are skipped and commands run as the console
+        lines.send("   ".to_string()).unwrap();
+        lines.send("  say hello there ".to_string()).unwrap();
+        let message = chat.recv().await.unwrap();
+        assert_eq!(message.to_plain_text(), "[Server] hello there");
+        assert!(!state.is_shutting_down());
+
+        // Stopping the server also ends the console task
+        lines.send("stop".to_string()).unwrap();
+        console.await.unwrap();
+        assert!(state.is_shutting_down());
+
+        // So does closing the input
+        let state = Arc::new(ServerState::new(ServerConfig::default()));
+        let (lines, receiver) = mpsc::unbounded_channel::<String>();
+        drop(lines);
+        run(Arc::clone(&state), receiver).await;
+        assert!(!state.is_shutting_down());
+    }
+}
@@ -0,0 +1,184 @@
+//! Text filtering
+//!
+//! Text written by players passes through a [`ChatFilter`] before other
+//! players see it. The filter decides which parts of the text are hidden
+//! and the result is sent as the protocol's filter mask, so only clients
+//! that asked for filtering hide anything.
+//!
+//! The built-in [`RegexFilter`] hides every match of a list of regular
+//! expressions read from the file named by `text-filtering-config`, one
+//! per line. Plugins can install their own backend with
+//! [`TextFilter::set`].
+
+use crate::error::{Result, ServerError};
+use crate::protocol::packets::play::FilterMask;
+use crate::protocol::types::McUuid;
+use async_trait::async_trait;
+use regex::Regex;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// Decides which parts of player-written text are hidden
+#[async_trait]
+pub trait ChatFilter: Send + Sync {
+    /// Filter text written by a player
+    async fn filter(&self, sender: &McUuid, text: &str) -> FilterMask;
+}
+
+/// Filter hiding every match of a list of regular expressions
+#[derive(Debug, Clone, Default)]
+pub struct RegexFilter {
+    /// Patterns whose matches are hidden
+    patterns: Vec<Regex>,
+}
+
+impl RegexFilter {
+    /// Create a filter from patterns
+    pub fn new<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    ServerError::Configuration(format!(
+                        "Invalid text filter pattern '{}': {}",
+                        pattern, e
+                    ))
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Load patterns from a file, one per line
+    ///
+    /// Blank lines and lines starting with `#` are skipped. Use `(?i)` for
+    /// patterns that should ignore case.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            ServerError::Configuration(format!(
+                "Failed to read text filter {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::new(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#')),
+        )
+    }
+
+    /// Number of patterns
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Whether there are no patterns
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Mask hiding every match in some text
+    pub fn mask(&self, text: &str) -> FilterMask {
+        // The mask counts UTF-16 code units, as the client does
+        let mut units = Vec::with_capacity(text.len() + 1);
+        let mut unit = 0;
+        for c in text.chars() {
+            units.extend(std::iter::repeat_n(unit, c.len_utf8()));
+            unit += c.len_utf16();
+        }
+        units.push(unit);
+
+        let hidden = self
+            .patterns
+            .iter()
+            .flat_map(|pattern| pattern.find_iter(text))
+            .flat_map(|found| units[found.start()]..units[found.end()]);
+        FilterMask::hiding(hidden, unit)
+    }
+}
+
+#[async_trait]
+impl ChatFilter for RegexFilter {
+    async fn filter(&self, _sender: &McUuid, text: &str) -> FilterMask {
+        self.mask(text)
+    }
+}
+
+/// The server's current text filter, if any
+#[derive(Default)]
+pub struct TextFilter {
+    /// Backend text is passed through (`None` lets everything through)
+    backend: RwLock<Option<Arc<dyn ChatFilter>>>,
+}
+
+impl TextFilter {
+    /// Create a text filter that lets everything through
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a text filter from the `text-filtering-config` setting
+    ///
+    /// An empty setting disables filtering.
+    pub fn from_config(config: &str) -> Result<Self> {
+        let filter = Self::new();
+        if !config.is_empty() {
+            let regex = RegexFilter::load(config)?;
+            tracing::info!("Loaded {} text filter patterns", regex.len());
+            filter.set(Some(Arc::new(regex)));
+        }
+        Ok(filter)
+    }
+
+    /// Replace the backend (`None` disables filtering)
+    pub fn set(&self, backend: Option<Arc<dyn ChatFilter>>) {
+        *self.backend.write().unwrap_or_else(|e| e.into_inner()) = backend;
+    }
+
+    /// Whether a backend is installed
+    pub fn is_enabled(&self) -> bool {
+        self.backend
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// Filter text written by a player
+    pub async fn filter(&self, sender: &McUuid, text: &str) -> FilterMask {
+        let backend = self
+            .backend
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        match backend {
+            Some(backend) => backend.filter(sender, text).await,
+            None => FilterMask::PassThrough,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regex_filter_masks_matches() {
+        let filter = RegexFilter::new(["(?i)darn", "heck"]).unwrap();
+        assert_eq!(filter.mask("hello there"), FilterMask::PassThrough);
+        assert_eq!(filter.mask("DARN"), FilterMask::FullyFiltered);
+
+        let mask = filter.mask("oh darn, é heck");
+        assert_eq!(mask.apply("oh darn, é heck").unwrap(), "oh ####, é ####");
+        assert_eq!(FilterMask::FullyFiltered.apply("darn"), None);
+
+        // Characters outside the BMP take two UTF-16 code units
+        let mask = filter.mask("😀heck");
+        assert_eq!(mask, FilterMask::PartiallyFiltered(vec![0b111100]));
+        assert_eq!(mask.apply("😀heck").unwrap(), "😀####");
+
+        assert!(RegexFilter::new(["("]).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! Handle to a running server
+//!
+//! [`MinecraftServer::start`](crate::server::MinecraftServer::start) runs the
+//! server in the background and returns a [`ServerHandle`], which lets tests
+//! and applications embedding Obsidium control it without owning the main
+//! loop.
+
+use crate::command::{CommandResult, CommandSender};
+use crate::error::Result;
+use crate::protocol::types::JsonTextComponent;
+use crate::server::ServerState;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// Controls a server started in the background
+///
+/// Dropping the handle leaves the server running; call
+/// [`ServerHandle::stop`] and [`ServerHandle::wait`] to shut it down.
+pub struct ServerHandle {
+    /// State shared with the server
+    state: Arc<ServerState>,
+    /// Address the server accepts connections on
+    local_addr: SocketAddr,
+    /// The server's main loop
+    task: JoinHandle<Result<()>>,
+}
+
+impl ServerHandle {
+    /// Create a handle for a server main loop
+    pub(crate) fn new(
+        state: Arc<ServerState>,
+        local_addr: SocketAddr,
+        task: JoinHandle<Result<()>>,
+    ) -> Self {
+        Self {
+            state,
+            local_addr,
+            task,
+        }
+    }
+
+    /// Address the server accepts connections on
+    ///
+    /// Useful when the server was bound to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// State shared with the server, for anything the handle does not cover
+    pub fn state(&self) -> &Arc<ServerState> {
+        &self.state
+    }
+
+    /// Ask the server to shut down
+    ///
+    /// Returns immediately; use [`ServerHandle::wait`] to wait for the world
+    /// to be saved.
+    pub fn stop(&self) {
+        self.state.request_shutdown();
+    }
+
+    /// Broadcast a chat message to all players
+    pub fn broadcast(&self, message: JsonTextComponent) {
+        self.state.broadcast_message(message);
+    }
+
+    /// Number of connected players
+    pub fn player_count(&self) -> usize {
+        self.state.players.player_count()
+    }
+
+    /// Execute a command line on behalf of a sender
+    pub async fn execute_command(
+        &self,
+        sender: &dyn CommandSender,
+        command: &str,
+    ) -> CommandResult {
+        self.state.execute_command(sender, command).await
+    }
+
+    /// Check whether a shutdown has been requested
+    pub fn is_stopping(&self) -> bool {
+        self.state.is_shutting_down()
+    }
+
+    /// Wait for the server to shut down and save the world
+    ///
+    /// A panic in the server's main loop is resumed on the caller.
+    pub async fn wait(self) -> Result<()> {
+        match self.task.await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::AdminSender;
+    use crate::config::ServerConfig;
+    use crate::server::MinecraftServer;
+    use crate::server::audit::AuditConfig;
+    use crate::server::usercache::USERCACHE_FILE;
+
+    #[tokio::test]
+    async fn test_start_and_stop() {
+        let level = std::env::temp_dir().join(format!("obsidium-handle-{}", uuid::Uuid::new_v4()));
+        let config = ServerConfig::new()
+            .with_bind_address("127.0.0.1:0".parse().unwrap())
+            .with_online_mode(false)
+            .with_level_name(level.to_string_lossy().into_owned())
+            .with_usercache_path(Some(level.join(USERCACHE_FILE)))
+            .with_max_tick_time(None)
+            .with_audit(AuditConfig::disabled());
+
+        let server = MinecraftServer::new(config).await.unwrap();
+        let handle = server.start().await.unwrap();
+        assert_ne!(handle.local_addr().port(), 0);
+        assert!(
+            tokio::net::TcpStream::connect(handle.local_addr())
+                .await
+                .is_ok()
+        );
+        assert_eq!(handle.player_count(), 0);
+
+        let sender = AdminSender::new();
+        assert!(handle.execute_command(&sender, "seed").await.is_ok());
+        assert!(sender.take_output()[0].contains("Seed"));
+
+        handle.stop();
+        assert!(handle.is_stopping());
+        handle.wait().await.unwrap();
+
+        let _ = std::fs::remove_dir_all(level);
+    }
+}
@@ -3,35 +3,99 @@
 //! This module contains the core server logic that ties together all
 //! the other modules to create a functioning Minecraft server.
 
-use crate::config::ServerConfig;
+use crate::clock::SharedClock;
+use crate::config::secrets::Secret;
+use crate::config::{RuntimeSettings, ServerConfig};
 use crate::error::{Result, ServerError};
-use crate::game::{player::PlayerManager, world::World};
-use crate::network::{Connection, ServerListener};
+use crate::event::ServerEvent;
+use crate::game::Difficulty;
+use crate::game::entity::EntityType;
+use crate::game::inventory::Hand;
+use crate::game::movement::Movement;
+use crate::game::player::{AddPlayerOutcome, OutboundReceiver, Player};
+use crate::game::settings::ClientSettings;
+use crate::game::world::budget::ChunkView;
+use crate::game::world::dimension::Dimension;
+use crate::game::world::simulation::SimulationArea;
+use crate::game::world::spawn;
+use crate::game::world::{ChunkPacket, ChunkPosition};
+use crate::lang::{DEFAULT_LANGUAGE, LANG_DIR, Language};
+use crate::network::velocity::{self, ForwardedPlayer};
+use crate::network::{
+    Connection, KeepAlive, MalformedPackets, PacketRateLimiter, ProxyHeader, ServerListener,
+};
+use crate::protocol::encryption::{SECRET_LENGTH, random_bytes};
+use crate::protocol::frame::encode_offloaded;
 use crate::protocol::packets::{
-    Packet,
+    DynPacket, Packet, ServerboundPacket, check_serverbound_id,
+    configuration::{
+        AcknowledgeFinishConfigurationPacket, ClientInformationPacket,
+        ClientboundPluginMessagePacket, ConfigurationDisconnectPacket, FinishConfigurationPacket,
+        PluginMessagePacket, ResourcePackResponsePacket, ResourcePackStatus,
+    },
     handshaking::HandshakePacket,
-    login::{LoginAcknowledgedPacket, LoginStartPacket, LoginSuccessPacket, SetCompressionPacket},
-    play::LoginPlayPacket,
+    login::{
+        EncryptionRequestPacket, EncryptionResponsePacket, LoginAcknowledgedPacket,
+        LoginDisconnectPacket, LoginPluginRequestPacket, LoginPluginResponsePacket,
+        LoginStartPacket, LoginSuccessPacket, SetCompressionPacket,
+    },
+    play::{
+        AcknowledgeBlockChangePacket, ChangeDifficultyRequestPacket, ChatCommandPacket,
+        ChatMessagePacket, ClickContainerPacket, CloseContainerPacket,
+        ConfigurationAcknowledgedPacket, DisconnectPacket, GameEventPacket, InteractPacket,
+        LockDifficultyPacket, LoginPlayPacket, MoveVehiclePacket, PlayClientInformationPacket,
+        PlayerAbilitiesPacket, PlayerActionPacket, PlayerCommandPacket, PlayerInputPacket,
+        PlayerOnGroundPacket, PlayerPositionAndRotationPacket, PlayerPositionPacket,
+        PlayerRotationPacket, SelectTradePacket, ServerboundKeepAlivePacket,
+        ServerboundPlayerAbilitiesPacket, SetCenterChunkPacket, SetChunkCacheRadiusPacket,
+        SetCreativeModeSlotPacket, SetHeldItemPacket, SetSimulationDistancePacket,
+        StartConfigurationPacket, TeleportToEntityPacket, UseItemOnPacket, UseItemPacket,
+    },
     status::{
         Description, PingRequestPacket, PingResponsePacket, PlayersInfo, ServerStatus,
-        StatusRequestPacket, StatusResponsePacket, VersionInfo,
+        StatusRequestPacket, StatusResponsePacket,
     },
 };
-use crate::protocol::{ConnectionState, MINECRAFT_VERSION, PROTOCOL_VERSION};
+use crate::protocol::types::{JsonTextComponent, McString};
+use crate::protocol::{ConnectionState, McUuid, VarInt};
+use crate::server::audit::AuditEvent;
+use crate::server::profiler;
+use crate::server::profiles::{GameProfile, server_hash};
+use crate::server::state::KickRequest;
+use crate::server::tick::{TICK_DURATION, TickScheduler};
+use crate::server::version::AdvertisedVersion;
+use crate::server::watchdog::{self, TickStage};
+use crate::server::whitelist::not_whitelisted_message;
+use crate::server::{ServerHandle, ServerState};
+use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::{RwLock, mpsc};
-use tokio::time::{Duration, interval};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+/// How long a login waits for an older session of the same player to end
+const DUPLICATE_LOGIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Length of the verify token sent in an Encryption Request, as in vanilla
+const VERIFY_TOKEN_LENGTH: usize = 4;
+
+/// Ticks between tab list latency updates, as in vanilla
+const LATENCY_UPDATE_INTERVAL: i64 = 600;
 
 /// Main Minecraft server
 pub struct MinecraftServer {
-    /// Server configuration
-    config: ServerConfig,
-    /// Player manager
-    players: Arc<PlayerManager>,
-    /// Main world
-    world: Arc<RwLock<World>>,
+    /// State shared with connections and commands
+    state: Arc<ServerState>,
     /// Server status
     status: ServerStatus,
+    /// When the world was last saved automatically
+    last_autosave: Instant,
+    /// When the last scheduled backup was started
+    last_backup: Instant,
+    /// Whether the watchdog may exit the process when the server stalls
+    exit_on_crash: bool,
 }
 
 impl MinecraftServer {
@@ -80,38 +144,116 @@ impl MinecraftServer {
 
         // Create server status
         let status = ServerStatus {
-            version: VersionInfo {
-                name: MINECRAFT_VERSION.to_string(),
-                protocol: PROTOCOL_VERSION,
-            },
+            version: config.advertised_version.version_info(None),
             players: PlayersInfo {
                 max: config.max_players,
                 online: 0, // TODO: Update dynamically
                 sample: None,
             },
-            description: Description::Text(config.motd.clone()),
+            description: Description::from_motd(&config.motd),
             favicon,
             enforces_secure_chat: false,
         };
 
+        let language = Language::load(&config.language, Path::new(LANG_DIR)).unwrap_or_else(|e| {
+            tracing::warn!("{}; using {}", e, DEFAULT_LANGUAGE);
+            Language::en_us()
+        });
+        crate::lang::set_server_language(language);
+
+        let state = ServerState::load(config)?;
         Ok(Self {
-            config,
-            players: Arc::new(PlayerManager::new()),
-            world: Arc::new(RwLock::new(World::new("world".to_string(), 12345))),
+            last_autosave: state.clock.now(),
+            last_backup: state.clock.now(),
+            state: Arc::new(state),
             status,
+            exit_on_crash: false,
         })
     }
 
-    /// Start the server
-    pub async fn run(mut self) -> Result<()> {
+    /// Measure time with the given clock instead of the system clock
+    ///
+    /// Connections use it for login deadlines, keep-alives and rate limits;
+    /// ticks are still paced in real time.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        match Arc::get_mut(&mut self.state) {
+            Some(state) => {
+                state.set_clock(clock);
+                self.last_autosave = state.clock.now();
+                self.last_backup = state.clock.now();
+            }
+            None => tracing::warn!("The clock cannot be changed while the server runs"),
+        }
+        self
+    }
+
+    /// Let the watchdog exit the process when a tick stalls, as the
+    /// dedicated server does
+    ///
+    /// Off by default, so a server embedded in another application stops
+    /// with [`ServerError::Crashed`] instead of taking the application down.
+    pub fn with_exit_on_crash(mut self, exit_on_crash: bool) -> Self {
+        self.exit_on_crash = exit_on_crash;
+        self
+    }
+
+    /// Run the server until it is stopped by Ctrl+C or a command
+    ///
+    /// Commands typed on the console are executed while the server runs.
+    pub async fn run(self) -> Result<()> {
+        let state = Arc::clone(&self.state);
+        let mut shutdown = state.subscribe_shutdown();
+        let handle = self.start().await?;
+
+        // Start reading commands from the console
+        let console_handle = crate::server::console::spawn(Arc::clone(&state));
+
+        tokio::select! {
+            // Handle shutdown signal
+            result = tokio::signal::ctrl_c() => {
+                match result {
+                    Ok(()) => tracing::info!("Received Ctrl+C signal"),
+                    Err(e) => tracing::error!("Error listening for shutdown signal: {}", e),
+                }
+                handle.stop();
+            }
+
+            // Shutdown requested by a command
+            _ = shutdown.changed() => {}
+        }
+
+        let result = handle.wait().await;
+        console_handle.abort();
+        result
+    }
+
+    /// Start the server in the background
+    ///
+    /// The listener is bound before this returns, so bind errors are
+    /// reported here. Unlike [`MinecraftServer::run`], neither the console
+    /// nor Ctrl+C is handled; use the returned handle to stop the server.
+    pub async fn start(self) -> Result<ServerHandle> {
         tracing::info!("Obsidium Minecraft Server v{}", env!("CARGO_PKG_VERSION"));
-        tracing::debug!("Starting server on {}", self.config.bind_address);
+        tracing::debug!("Starting server on {}", self.state.config.bind_address);
+
+        if self.state.config.online_mode {
+            tracing::info!("Generating keypair");
+            self.state.server_key()?;
+        }
+
+        // Generate terrain ahead of time before players can join
+        if let Some(radius) = self.state.config.pregen_radius {
+            self.state.pregenerate(radius).await?;
+        }
 
         // Create connection sender for the listener
-        let (connection_sender, mut connection_receiver) = mpsc::unbounded_channel();
+        let (connection_sender, connection_receiver) = mpsc::unbounded_channel();
 
         // Start the network listener
-        let listener = ServerListener::new(self.config.clone(), connection_sender).await?;
+        let listener = ServerListener::new(self.state.config.clone(), connection_sender)
+            .await?
+            .with_clock(Arc::clone(&self.state.clock))
+            .with_packet_counters(Arc::clone(&self.state.packet_counters));
         let listener_addr = listener.local_addr()?;
         tracing::debug!("Server listening on {}", listener_addr);
 
@@ -122,136 +264,675 @@ impl MinecraftServer {
             }
         });
 
-        // Create update timer
-        let mut update_timer = interval(Duration::from_millis(50)); // 20 TPS
+        // Subscribe before returning so a stop through the handle is never missed
+        let state = Arc::clone(&self.state);
+        let shutdown = state.subscribe_shutdown();
+        let task = tokio::spawn(self.serve(listener_handle, connection_receiver, shutdown));
+        Ok(ServerHandle::new(state, listener_addr, task))
+    }
+
+    /// Run the main loop until a shutdown is requested, then save and clean up
+    async fn serve(
+        mut self,
+        listener_handle: JoinHandle<()>,
+        mut connection_receiver: mpsc::UnboundedReceiver<Connection>,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> Result<()> {
+        // Start the HTTP admin API if enabled
+        let admin_handle = crate::admin::spawn(Arc::clone(&self.state));
+
+        // Serve the resource pack if the server hosts it
+        let resource_pack_handle = crate::server::resource_pack::spawn(Arc::clone(&self.state));
+
+        // Apply edits to the configuration files while running
+        let reload_handle = crate::server::reload::spawn(Arc::clone(&self.state));
+
+        // Watch for ticks that exceed max-tick-time
+        let watchdog_handle = watchdog::spawn(Arc::clone(&self.state), self.exit_on_crash);
+
+        // Schedule game ticks at 20 TPS
+        let mut scheduler = TickScheduler::new(self.state.clock.as_ref(), TICK_DURATION);
+
+        // Load and enable plugins
+        if self.state.config.plugins_enabled {
+            self.state.plugins.load_directory();
+            self.state.plugins.enable_all(&self.state);
+            #[cfg(feature = "scripting")]
+            self.state.scripts.load_directory(&self.state);
+        }
 
         tracing::info!("Server started successfully!");
+        self.state.events.publish(&ServerEvent::ServerStarted);
 
         // Main server loop
         loop {
             tokio::select! {
-                // Handle shutdown signal
-                result = tokio::signal::ctrl_c() => {
-                    match result {
-                        Ok(()) => {
-                            tracing::info!("Received Ctrl+C signal, Shutting down server...");
-                        }
-                        Err(e) => {
-                            tracing::error!("Error listening for shutdown signal: {}", e);
-                        }
-                    }
+                // Handle shutdown requested by a command or the handle
+                _ = shutdown.changed() => {
+                    tracing::info!("Shutting down server...");
                     break;
                 }
 
                 // Handle new connections
                 Some(connection) = connection_receiver.recv() => {
-                    let players = Arc::clone(&self.players);
-                    let world = Arc::clone(&self.world);
+                    let state = Arc::clone(&self.state);
                     let status = self.status.clone();
-                    let config = self.config.clone();
 
-                    tokio::spawn(async move {
-                        if let Err(e) = Self::handle_connection(connection, players, world, status, config).await {
-                            tracing::error!("Connection error: {}", e);
+                    let span = tracing::info_span!(
+                        "connection",
+                        client = %connection.logged_addr(),
+                        player = tracing::field::Empty,
+                    );
+                    tokio::spawn(
+                        async move {
+                            if let Err(e) = Self::handle_connection(connection, state, status).await {
+                                tracing::error!("Connection error: {}", e);
+                            }
                         }
-                    });
+                        .instrument(span),
+                    );
                 }
 
                 // Update world and game logic
-                _ = update_timer.tick() => {
-                    let mut world = self.world.write().await;
-                    world.update(0.05); // 50ms delta
+                _ = tokio::time::sleep_until(scheduler.next_tick().into()) => {
+                    let started = self.state.clock.now();
+                    let number = self.state.tick_stats.tick_count() + 1;
+                    self.state.tick_progress.begin(number);
+                    self.tick().instrument(tracing::debug_span!("tick", number)).await;
+                    if let Some((stage, duration)) = self.state.tick_progress.end() {
+                        self.state.profiler.record_stage(stage, duration);
+                    }
+                    let finished = self.state.clock.now();
+
+                    self.state.tick_stats.record(finished, finished - started);
+                    self.state.profiler.record_tick(finished - started);
+                    if self.state.profiler.is_due() {
+                        self.finish_profile().await;
+                    }
 
-                    // Update player count in status
-                    self.status.players.online = self.players.player_count().await as u32;
+                    // Ticks are paced in real time, whatever the clock says
+                    let skipped = scheduler.advance(Instant::now());
+                    if skipped > 0 {
+                        tracing::warn!(
+                            "Can't keep up! Is the server overloaded? Running {}ms or {} ticks behind",
+                            skipped * TICK_DURATION.as_millis() as u64,
+                            skipped
+                        );
+                    }
                 }
             }
         }
 
-        // Abort the listener task
+        self.state.events.publish(&ServerEvent::ServerStopping);
+        #[cfg(feature = "scripting")]
+        self.state.scripts.unload_all(&self.state);
+        self.state.plugins.disable_all(&self.state);
+
+        // Abort the listener and background tasks
+        self.state.request_shutdown();
         listener_handle.abort();
+        if let Some(handle) = admin_handle {
+            handle.abort();
+        }
+        if let Some(handle) = resource_pack_handle {
+            handle.abort();
+        }
+        if let Some(handle) = reload_handle {
+            handle.abort();
+        }
 
         // Log current player count
-        let player_count = self.players.player_count().await;
+        let player_count = self.state.players.player_count();
         if player_count > 0 {
             tracing::info!("Disconnecting {} connected player(s)...", player_count);
         }
 
+        tracing::info!("Saving the world...");
+        if let Err(e) = self.state.save_all(true).await {
+            tracing::error!("Failed to save the world: {}", e);
+        }
+
+        if let Some(handle) = watchdog_handle {
+            let _ = handle.join();
+        }
+
+        if let Some(reason) = self.state.crash_reason() {
+            return Err(ServerError::Crashed(reason));
+        }
         tracing::info!("Server shutdown complete");
         Ok(())
     }
 
+    /// Run a single game tick
+    async fn tick(&mut self) {
+        let simulation_distance = self.state.settings().simulation_distance;
+        let mut area = SimulationArea::new(simulation_distance);
+        let mut nether_area = SimulationArea::new(simulation_distance);
+        self.state.players.for_each_player_mut(|player| {
+            let position = player.position;
+            let chunk = ChunkPosition::from_world_coords(position.x, position.z);
+            match player.dimension {
+                Dimension::Overworld => area.add_player(chunk),
+                Dimension::Nether => nether_area.add_player(chunk),
+            }
+        });
+
+        let mut world = self
+            .state
+            .world
+            .write()
+            .instrument(stage_span(TickStage::AcquireWorld))
+            .await;
+        let spawn_radius = world.game_rules().spawn_chunk_radius;
+        if spawn_radius > 0 {
+            area.add_chunks(
+                spawn::spawn_chunk(world.spawn_position()),
+                spawn_radius as i32,
+            );
+        }
+        let weather = world.weather();
+        self.enter_stage(TickStage::WorldUpdate);
+        stage_span(TickStage::WorldUpdate)
+            .in_scope(|| world.update(TICK_DURATION.as_secs_f64(), &area));
+        self.enter_stage(TickStage::Explosions);
+        stage_span(TickStage::Explosions).in_scope(|| self.state.tick_explosions(&mut world));
+        self.enter_stage(TickStage::Sleeping);
+        stage_span(TickStage::Sleeping).in_scope(|| self.state.tick_sleeping(&mut world));
+        self.enter_stage(TickStage::Hunger);
+        stage_span(TickStage::Hunger).in_scope(|| self.state.tick_hunger(&world));
+        self.enter_stage(TickStage::Spectators);
+        stage_span(TickStage::Spectators).in_scope(|| self.state.tick_spectators(&world));
+        self.enter_stage(TickStage::Portals);
+        self.state
+            .tick_nether(TICK_DURATION.as_secs_f64(), &nether_area)
+            .instrument(stage_span(TickStage::Portals))
+            .await;
+        self.state
+            .tick_portals(&mut world)
+            .instrument(stage_span(TickStage::Portals))
+            .await;
+        self.enter_stage(TickStage::Chunks);
+        self.state
+            .tick_chunks(&mut world)
+            .instrument(stage_span(TickStage::Chunks))
+            .await;
+        stage_span(TickStage::Chunks).in_scope(|| self.state.tick_pregen(&mut world));
+        self.enter_stage(TickStage::Tasks);
+        stage_span(TickStage::Tasks)
+            .in_scope(|| self.state.scheduler.run_pending(&self.state, &mut world));
+
+        self.enter_stage(TickStage::Broadcast);
+        self.state
+            .profiler
+            .record_loaded_chunks(world.loaded_chunk_count());
+        stage_span(TickStage::Broadcast).in_scope(|| {
+            self.state.broadcast_world_changes(&mut world);
+            if world.weather() != weather {
+                self.state.broadcast_weather(world.weather());
+            }
+
+            // Keep client clocks in sync once per second
+            if world.world_age() % 20 == 0 {
+                self.state.broadcast_time(&world);
+            }
+        });
+        let update_latencies = world.world_age() % LATENCY_UPDATE_INTERVAL == 0;
+        drop(world);
+        if update_latencies {
+            stage_span(TickStage::Broadcast).in_scope(|| self.state.broadcast_latencies());
+        }
+
+        self.enter_stage(TickStage::Autosave);
+        self.autosave()
+            .instrument(stage_span(TickStage::Autosave))
+            .await;
+        self.start_backup();
+    }
+
+    /// Move the running tick to its next stage, timing the last one
+    fn enter_stage(&self, stage: TickStage) {
+        if let Some((finished, duration)) = self.state.tick_progress.enter(stage) {
+            self.state.profiler.record_stage(finished, duration);
+        }
+    }
+
+    /// Close a profiling window that reached its end and save the report
+    async fn finish_profile(&self) {
+        let Some((report, saved)) = profiler::finish(&self.state).await else {
+            return;
+        };
+        for line in report.summary() {
+            tracing::info!("{}", line);
+        }
+        match saved {
+            Ok(path) => tracing::info!("Profile saved to {}", path.display()),
+            Err(e) => tracing::error!("Failed to save the profile: {}", e),
+        }
+    }
+
+    /// Save the world if the autosave interval has elapsed
+    async fn autosave(&mut self) {
+        let interval = self.state.config.autosave_interval;
+        if interval.is_zero() || self.state.clock.since(self.last_autosave) < interval {
+            return;
+        }
+        self.last_autosave = self.state.clock.now();
+
+        if !self.state.is_saving_enabled() {
+            return;
+        }
+        // Chunks are written off the tick loop; only copying them waits for it
+        let state = Arc::clone(&self.state);
+        tokio::spawn(async move {
+            match state.autosave().await {
+                Some(Ok(saved)) => tracing::debug!("Autosaved {} chunk(s)", saved),
+                Some(Err(e)) => tracing::error!("Autosave failed: {}", e),
+                None => {}
+            }
+        });
+    }
+
+    /// Start a backup in the background if one was requested or the backup
+    /// interval has elapsed
+    fn start_backup(&mut self) {
+        let interval = self.state.config.backups.interval;
+        let due = !interval.is_zero() && self.state.clock.since(self.last_backup) >= interval;
+        if due {
+            self.last_backup = self.state.clock.now();
+        }
+        if !(self.state.backups.take_request() || due) || self.state.backups.is_running() {
+            return;
+        }
+
+        let state = Arc::clone(&self.state);
+        tokio::spawn(async move {
+            tracing::info!("Starting world backup");
+            match state.backup().await {
+                Ok(report) => tracing::info!(
+                    "Backed up {} file(s) to {} in {:.1}s, removed {} old backup(s)",
+                    report.files,
+                    report.path.display(),
+                    report.duration.as_secs_f64(),
+                    report.removed
+                ),
+                Err(e) => tracing::error!("Backup failed: {}", e),
+            }
+        });
+    }
+
     /// Handle an individual connection
     async fn handle_connection(
         mut connection: Connection,
-        players: Arc<PlayerManager>,
-        _world: Arc<RwLock<World>>,
+        state: Arc<ServerState>,
         status: ServerStatus,
-        config: ServerConfig,
     ) -> Result<()> {
-        tracing::debug!("Handling connection from {}", connection.peer_addr());
+        if !Self::accept(&mut connection, &state).await {
+            return Ok(());
+        }
+
+        let mut outbound = None;
+        let mut kicks = state.subscribe_kicks();
+        let mut reconfigurations = state.subscribe_reconfigurations();
+        let mut settings = state.subscribe_settings();
+        let mut applied = settings.borrow_and_update().clone();
+        let login_timeout = state.config.login_timeout;
+        let mut malformed = MalformedPackets::new().with_clock(Arc::clone(&state.clock));
+        let mut rate_limiter =
+            PacketRateLimiter::new(state.config.packet_limits).with_clock(Arc::clone(&state.clock));
+        let mut keep_alive = KeepAlive::new().with_clock(Arc::clone(&state.clock));
 
         loop {
-            // Read packet
-            let (packet_id, data) = match connection.read_packet().await {
-                Ok((pid, pdata)) => {
-                    tracing::debug!(
-                        "Received packet ID: 0x{:02X}, data length: {}, state: {:?}",
-                        pid.0,
-                        pdata.len(),
-                        connection.state()
-                    );
-                    (pid, pdata)
-                }
-                Err(e) => {
-                    tracing::debug!("Connection closed: {}", e);
-                    break;
+            // Read packet, forwarding broadcasts until asked to reconfigure
+            let in_play = connection.state() == ConnectionState::Play && outbound.is_some();
+            let login_time_left = login_time_left(&connection, login_timeout);
+            let (packet_id, data) = tokio::select! {
+                result = connection.read_packet() => match result {
+                    Ok(packet) => packet,
+                    Err(e) => {
+                        tracing::debug!("Connection closed: {}", e);
+                        break;
+                    }
+                },
+
+                packet = recv_outbound(&mut outbound) => {
+                    if !Self::forward_outbound(&mut connection, &state, &mut outbound, packet).await? {
+                        break;
+                    }
+                    continue;
                 }
-            };
 
-            let should_break = match connection.state() {
-                ConnectionState::Handshaking => {
-                    Self::handle_handshaking_packet(&mut connection, packet_id, &data)?;
-                    false
+                () = tokio::time::sleep(keep_alive.time_left()), if in_play => {
+                    if !Self::send_keep_alive(&mut connection, &mut keep_alive).await {
+                        break;
+                    }
+                    continue;
                 }
-                ConnectionState::Status => {
-                    Self::handle_status_packet(&mut connection, packet_id, &data, &status).await?
+
+                Ok(()) = settings.changed(), if in_play => {
+                    let current = settings.borrow_and_update().clone();
+                    Self::update_distances(&mut connection, &state, &mut applied, current).await?;
+                    continue;
                 }
-                ConnectionState::Login => {
-                    Self::handle_login_packet(&mut connection, packet_id, &data, &config, &players)
-                        .await?;
-                    false
+
+                () = tokio::time::sleep(login_time_left.unwrap_or_default()),
+                    if login_time_left.is_some() =>
+                {
+                    let error = slow_login(&connection);
+                    Self::close_with_error(&mut connection, &error).await;
+                    break;
                 }
-                ConnectionState::Configuration => {
-                    Self::handle_configuration_packet(&mut connection, packet_id, &data, &config)
-                        .await?;
-                    false
+
+                Ok(kick) = kicks.recv() => {
+                    if Self::apply_kick(&mut connection, &state, kick).await {
+                        break;
+                    }
+                    continue;
                 }
-                ConnectionState::Play => {
-                    Self::handle_play_packet(packet_id);
-                    false
+
+                Ok(uuid) = reconfigurations.recv(), if in_play => {
+                    Self::start_configuration(&mut connection, &state, &mut outbound, uuid).await?;
+                    continue;
                 }
             };
 
-            if should_break {
+            let previous_state = connection.state();
+            let span = tracing::debug_span!(
+                "packet",
+                state = previous_state.as_str(),
+                id = packet_id.0,
+                len = data.len(),
+            );
+            let handled = Self::handle_packet(
+                &mut connection,
+                packet_id,
+                &data,
+                &state,
+                &status,
+                &mut rate_limiter,
+                &mut keep_alive,
+            )
+            .instrument(span)
+            .await;
+            if previous_state == ConnectionState::Configuration
+                && connection.state() == ConnectionState::Play
+            {
+                // Only deliver packets sent from now on
+                outbound = state.players.open_outbound(&connection.peer_addr());
+                applied = settings.borrow_and_update().clone();
+            }
+            if previous_state == ConnectionState::Login {
+                // Kicks meant for an older session of the player that just
+                // logged in are not for this connection
+                kicks = kicks.resubscribe();
+            }
+
+            if !Self::settle(&mut connection, handled, previous_state, &mut malformed).await {
                 break;
             }
         }
 
-        // Remove player when connection closes
-        players.remove_player(connection.peer_addr()).await;
-
+        Self::remove_connection(&connection, &state).await;
         Ok(())
     }
 
+    /// Send the next packet queued for the player, or close the connection
+    /// if their queue overflowed
+    ///
+    /// Returns whether the connection is still open.
+    async fn forward_outbound(
+        connection: &mut Connection,
+        state: &ServerState,
+        outbound: &mut Option<OutboundReceiver>,
+        packet: Option<Arc<dyn DynPacket>>,
+    ) -> Result<bool> {
+        if let Some(packet) = packet {
+            Self::write_outbound(connection, packet.as_ref()).await?;
+            return Ok(true);
+        }
+        // The queue was closed because it overflowed, unless the player
+        // logged in again and this session is about to be kicked
+        *outbound = None;
+        if !state.players.is_connected(&connection.peer_addr()) {
+            return Ok(true);
+        }
+        let overflow = crate::lang::translate("disconnect.overflow", &[]);
+        Self::close_with_error(connection, &ServerError::Kicked(overflow)).await;
+        Ok(false)
+    }
+
+    /// Send a packet queued for the player
+    ///
+    /// Packets that fail to encode are logged and skipped.
+    async fn write_outbound(connection: &mut Connection, packet: &dyn DynPacket) -> Result<()> {
+        match packet.encode() {
+            Ok(raw) => connection.write_raw_packet(&raw).await,
+            Err(e) => {
+                tracing::error!("Failed to encode {}: {}", packet.name(), e);
+                Ok(())
+            }
+        }
+    }
+
+    /// Disconnect the player if a kick is meant for this connection,
+    /// returning whether it was
+    async fn apply_kick(
+        connection: &mut Connection,
+        state: &ServerState,
+        kick: KickRequest,
+    ) -> bool {
+        let meant = match kick.connection {
+            Some(addr) => addr == connection.peer_addr(),
+            None => state
+                .players
+                .with_player_by_addr(&connection.peer_addr(), |player| player.uuid)
+                .is_some_and(|uuid| uuid == kick.uuid),
+        };
+        if !meant {
+            return false;
+        }
+        Self::close_with_error(connection, &ServerError::Kicked(kick.reason)).await;
+        true
+    }
+
+    /// Send a player back to the configuration state if the request is for
+    /// this connection
+    ///
+    /// Packets already queued for the player are sent first; the queue then
+    /// stays closed until the player is back in play.
+    async fn start_configuration(
+        connection: &mut Connection,
+        state: &ServerState,
+        outbound: &mut Option<OutboundReceiver>,
+        uuid: McUuid,
+    ) -> Result<()> {
+        let addr = connection.peer_addr();
+        let requested = state.players.with_player_mut(&addr, |player| {
+            player.reconfiguring = player.uuid == uuid;
+            player.reconfiguring
+        });
+        if requested != Some(true) {
+            return Ok(());
+        }
+
+        state.players.close_outbound(&addr);
+        if let Some(mut receiver) = outbound.take() {
+            while let Ok(packet) = receiver.try_recv() {
+                Self::write_outbound(connection, packet.as_ref()).await?;
+            }
+        }
+        tracing::debug!(
+            "Sending {} back to the configuration state",
+            connection.logged_addr()
+        );
+        connection.write_packet(&StartConfigurationPacket).await
+    }
+
+    /// Send the keep-alive that is due, closing the connection if the last
+    /// one went unanswered or sending fails
+    ///
+    /// Returns whether the connection is still open.
+    async fn send_keep_alive(connection: &mut Connection, keep_alive: &mut KeepAlive) -> bool {
+        let sent = match keep_alive.poll() {
+            Ok(Some(packet)) => connection.write_packet(&packet).await,
+            Ok(None) => Ok(()),
+            Err(e) => {
+                tracing::info!("Disconnecting {}: timed out", connection.logged_addr());
+                Err(e)
+            }
+        };
+        match sent {
+            Ok(()) => true,
+            Err(e) => {
+                Self::close_with_error(connection, &e).await;
+                false
+            }
+        }
+    }
+
+    /// Act on the outcome of handling a packet, closing the connection on
+    /// an error it does not survive
+    ///
+    /// Returns whether the connection is still open.
+    async fn settle(
+        connection: &mut Connection,
+        handled: Result<bool>,
+        state: ConnectionState,
+        malformed_packets: &mut MalformedPackets,
+    ) -> bool {
+        match handled {
+            Ok(closed) => !closed,
+            Err(e) if Self::tolerate(&e, state, malformed_packets) => {
+                tracing::warn!("Ignoring packet from {}: {}", connection.logged_addr(), e);
+                true
+            }
+            Err(e) => {
+                Self::close_with_error(connection, &e).await;
+                false
+            }
+        }
+    }
+
+    /// Whether a connection survives an error from handling one of its packets
+    ///
+    /// Play packets the server cannot decode are skipped. Only truncated
+    /// ones are counted, and a player is disconnected once they keep coming;
+    /// anything else ends the connection right away.
+    fn tolerate(
+        error: &ServerError,
+        state: ConnectionState,
+        malformed_packets: &mut MalformedPackets,
+    ) -> bool {
+        match error {
+            ServerError::PacketDecode { truncated, .. } if state == ConnectionState::Play => {
+                !truncated || malformed_packets.receive()
+            }
+            _ => false,
+        }
+    }
+
+    /// Save and remove the player of a closed connection
+    async fn remove_connection(connection: &Connection, state: &ServerState) {
+        // Save the player before removing them, as a new session of the
+        // same player may be waiting for the removal to load their data
+        state.leave_bed(&connection.peer_addr()).await;
+        state.dismount(&connection.peer_addr()).await;
+        state.close_container(&connection.peer_addr(), None);
+        if let Some(player) = state.players.get_player_by_addr(&connection.peer_addr()) {
+            state.save_player_data(&player);
+        }
+        if let Some(player) = state.players.remove_player(connection.peer_addr()) {
+            state.chunk_queue.lock().await.remove(&player.uuid);
+            state
+                .world
+                .write()
+                .await
+                .entities_mut()
+                .release_entity_id(player.entity_id);
+            state.broadcast_player_removed(player.uuid);
+            if connection.state() == ConnectionState::Play || player.reconfiguring {
+                state.audit.record(AuditEvent::Logout {
+                    player: player.username.clone(),
+                    uuid: player.uuid,
+                });
+                state.announce_quit(&player);
+            }
+        }
+    }
+
+    /// Handle a packet according to the connection's state
+    ///
+    /// Returns `true` if the connection should be closed.
+    async fn handle_packet(
+        connection: &mut Connection,
+        packet_id: crate::protocol::VarInt,
+        data: &[u8],
+        state: &ServerState,
+        status: &ServerStatus,
+        rate_limiter: &mut PacketRateLimiter,
+        keep_alive: &mut KeepAlive,
+    ) -> Result<bool> {
+        tracing::debug!(
+            "Received packet ID: 0x{:02X}, data length: {}, state: {:?}",
+            packet_id.0,
+            data.len(),
+            connection.state()
+        );
+        check_serverbound_id(connection.state(), packet_id.0)?;
+        // The clock may have passed the deadline while waiting for the packet
+        if login_time_left(connection, state.config.login_timeout) == Some(Duration::ZERO) {
+            return Err(slow_login(connection));
+        }
+        match connection.state() {
+            ConnectionState::Handshaking => {
+                let version = &state.config.advertised_version;
+                Self::handle_handshaking_packet(connection, packet_id, data, version)?;
+                Ok(false)
+            }
+            ConnectionState::Status => {
+                Self::handle_status_packet(connection, packet_id, data, status, state).await
+            }
+            ConnectionState::Login => {
+                Self::handle_login_packet(connection, packet_id, data, state).await
+            }
+            ConnectionState::Configuration => {
+                Self::handle_configuration_packet(connection, packet_id, data, state).await?;
+                Ok(false)
+            }
+            ConnectionState::Play => {
+                if let Some(category) = rate_limiter.receive(packet_id.0) {
+                    tracing::warn!(
+                        "Kicking {}: too many {:?} packets",
+                        connection.logged_addr(),
+                        category
+                    );
+                    let reason = crate::lang::translate("disconnect.spam", &[]);
+                    return Err(ServerError::Kicked(reason));
+                }
+                if packet_id.0 == ServerboundKeepAlivePacket::ID {
+                    let response = ServerboundKeepAlivePacket::decode(connection.state(), data)?;
+                    let latency = keep_alive.receive(response.keep_alive_id)?;
+                    state
+                        .players
+                        .with_player_mut(&connection.peer_addr(), |player| {
+                            player.set_latency(latency);
+                        });
+                    return Ok(false);
+                }
+                Self::handle_play_packet(connection, packet_id, data, state).await?;
+                Ok(false)
+            }
+        }
+    }
+
     /// Handle handshaking state packets
     fn handle_handshaking_packet(
         connection: &mut Connection,
         packet_id: crate::protocol::VarInt,
         data: &[u8],
+        advertised: &AdvertisedVersion,
     ) -> Result<()> {
         if packet_id.0 == HandshakePacket::ID {
-            let handshake = HandshakePacket::read(&mut std::io::Cursor::new(data))?;
+            let handshake = HandshakePacket::decode(connection.state(), data)?;
 
             tracing::debug!(
                 "Handshake: version={}, address={}, port={}, next_state={}",
@@ -264,18 +945,30 @@ impl MinecraftServer {
             connection.set_protocol_version(handshake.protocol_version.0);
 
             match handshake.next_state.0 {
-                1 => connection.set_state(ConnectionState::Status),
-                2 => connection.set_state(ConnectionState::Login),
+                // The status response carries the advertised protocol, which
+                // is how the server list flags a mismatch
+                1 => connection.set_state(ConnectionState::Status)?,
+                2 => connection.set_state(ConnectionState::Login)?,
                 3 => {
                     // Transfer intent - for now, treat as login
                     // TODO: Implement proper transfer handling
-                    connection.set_state(ConnectionState::Login);
+                    connection.set_state(ConnectionState::Login)?;
                     tracing::debug!("Transfer intent received, treating as login for now");
                 }
                 _ => {
                     return Err(ServerError::Protocol("Invalid next state".to_string()));
                 }
             }
+
+            let version = handshake.protocol_version.0;
+            if connection.state() == ConnectionState::Login && !advertised.accepts(version) {
+                tracing::info!(
+                    "Disconnecting {}: protocol {} is not supported",
+                    connection.logged_addr(),
+                    version
+                );
+                return Err(ServerError::Kicked(outdated_message(version, advertised)));
+            }
         }
         Ok(())
     }
@@ -286,16 +979,25 @@ impl MinecraftServer {
         packet_id: crate::protocol::VarInt,
         data: &[u8],
         status: &ServerStatus,
+        state: &ServerState,
     ) -> Result<bool> {
         if packet_id.0 == StatusRequestPacket::ID {
-            // Send status response
+            // Fill in the player counts and reloadable settings as they are now
+            let mut status = status.clone();
+            status.players = state.status_players();
+            status.description = Description::from_motd(&state.settings().motd);
+            status.version = state
+                .config
+                .advertised_version
+                .version_info(connection.protocol_version());
+
             let json = status.to_json()?;
             let response = StatusResponsePacket {
                 json_response: json.into(),
             };
             connection.write_packet(&response).await?;
         } else if packet_id.0 == PingRequestPacket::ID {
-            let ping = PingRequestPacket::read(&mut std::io::Cursor::new(data))?;
+            let ping = PingRequestPacket::decode(connection.state(), data)?;
             let pong = PingResponsePacket {
                 payload: ping.payload,
             };
@@ -305,50 +1007,455 @@ impl MinecraftServer {
         Ok(false)
     }
 
+    /// Start handling a connection, reading the client address a trusted
+    /// proxy forwards
+    ///
+    /// Connections that do not come through a trusted proxy are refused if
+    /// forwarding is required. Returns whether the connection may go on.
+    async fn accept(connection: &mut Connection, state: &ServerState) -> bool {
+        let forwarding = &state.config.forwarding;
+        tracing::debug!("Handling connection from {}", connection.logged_addr());
+        if !forwarding.is_trusted(connection.peer_addr().ip()) {
+            if forwarding.required {
+                tracing::info!(
+                    "Refusing connection from {}: not from a trusted proxy",
+                    connection.logged_addr()
+                );
+            }
+            return !forwarding.required;
+        }
+
+        let timeout = state.config.login_timeout;
+        let header = if timeout.is_zero() {
+            connection.read_proxy_header().await
+        } else {
+            tokio::time::timeout(timeout, connection.read_proxy_header())
+                .await
+                .unwrap_or_else(|_| {
+                    Err(ServerError::Protocol(
+                        "no PROXY protocol header".to_string(),
+                    ))
+                })
+        };
+        match header {
+            Ok(ProxyHeader::Forwarded(_)) => {
+                tracing::Span::current().record("client", connection.logged_addr());
+                true
+            }
+            Ok(ProxyHeader::Local) => true,
+            Err(e) => {
+                tracing::info!(
+                    "Refusing connection from {}: {}",
+                    connection.logged_addr(),
+                    e
+                );
+                false
+            }
+        }
+    }
+
+    /// Refuse a login from an address that tried to log in too recently
+    ///
+    /// This is checked before authenticating, so a flood of logins cannot
+    /// flood the session server. Behind a Velocity proxy every login comes
+    /// from the proxy, which throttles logins itself, so none are throttled.
+    fn check_throttle(
+        connection: &Connection,
+        login_start: &LoginStartPacket,
+        state: &ServerState,
+    ) -> Result<()> {
+        if state.config.forwarding_secret.is_some()
+            || state.login_throttle.try_attempt(connection.client_ip())
+        {
+            return Ok(());
+        }
+        tracing::info!(
+            "Disconnecting {} ({}): login throttled",
+            login_start.name.0,
+            connection.logged_addr()
+        );
+        Err(ServerError::Kicked(crate::lang::translate(
+            "obsidium.disconnect.throttled",
+            &[],
+        )))
+    }
+
+    /// Refuse a login that is banned or not whitelisted, and end any older
+    /// session of the same player
+    async fn admit(
+        connection: &Connection,
+        profile: &GameProfile,
+        state: &ServerState,
+    ) -> Result<()> {
+        let name = &profile.name;
+        let addr = connection.logged_addr();
+        if let Some(ban) = state.bans.get(&profile.uuid) {
+            tracing::info!("Disconnecting {} ({}): banned", name, addr);
+            return Err(ServerError::Kicked(ban.message()));
+        }
+        if let Some(ban) = state.ip_bans.get(&connection.client_ip().to_canonical()) {
+            tracing::info!("Disconnecting {} ({}): address banned", name, addr);
+            return Err(ServerError::Kicked(ban.message()));
+        }
+
+        if state.settings().whitelist && !state.whitelist.contains(&profile.uuid) {
+            tracing::info!("Disconnecting {} ({}): not whitelisted", name, addr);
+            return Err(ServerError::Kicked(not_whitelisted_message()));
+        }
+
+        let Some(existing) = state.players.find_player(&profile.uuid, name) else {
+            return Ok(());
+        };
+        tracing::info!(
+            "{} logged in from {} while online, ending the old session",
+            name,
+            addr
+        );
+        let reason = crate::lang::translate("multiplayer.disconnect.duplicate_login", &[]);
+        state.kick_player(&existing.uuid, reason.clone());
+        if !state
+            .players
+            .wait_until_removed(&existing.uuid, DUPLICATE_LOGIN_TIMEOUT)
+            .await
+        {
+            tracing::info!("Disconnecting {} ({}): old session did not end", name, addr);
+            return Err(ServerError::Kicked(reason));
+        }
+        Ok(())
+    }
+
+    /// Disconnect an older session of a player that logged in again while
+    /// it was still being admitted
+    ///
+    /// The older session's connection no longer has a player, so it is
+    /// cleaned up here rather than when it closes.
+    async fn end_replaced_session(
+        state: &ServerState,
+        player: Box<Player>,
+        addr: Option<SocketAddr>,
+    ) {
+        tracing::info!(
+            "{} logged in again while being admitted, ending the old session",
+            player.username
+        );
+        state.save_player_data(&player);
+        state
+            .world
+            .write()
+            .await
+            .entities_mut()
+            .release_entity_id(player.entity_id);
+        if let Some(addr) = addr {
+            let reason = crate::lang::translate("multiplayer.disconnect.duplicate_login", &[]);
+            state.kick_connection(player.uuid, addr, reason);
+        }
+    }
+
+    /// Find out who is logging in
+    ///
+    /// An online-mode server exchanges a shared secret with the client,
+    /// encrypts the connection with it and asks the session server who the
+    /// player is; the profile it returns replaces the name and UUID the
+    /// client sent. Behind a Velocity proxy with a forwarding secret, players
+    /// are who the proxy says they are. Offline, players are who they say
+    /// they are.
+    async fn authenticate(
+        connection: &mut Connection,
+        login_start: &LoginStartPacket,
+        state: &ServerState,
+    ) -> Result<GameProfile> {
+        let name = &login_start.name.0;
+        if let Some(ref secret) = state.config.forwarding_secret {
+            return Self::read_forwarded_player(connection, secret, state).await;
+        }
+        if !state.config.online_mode {
+            return Ok(GameProfile {
+                uuid: login_start.player_uuid,
+                name: name.clone(),
+                properties: Vec::new(),
+            });
+        }
+
+        let key = state.server_key()?;
+        let mut verify_token = [0u8; VERIFY_TOKEN_LENGTH];
+        random_bytes(&mut verify_token)?;
+        connection
+            .write_packet(&EncryptionRequestPacket {
+                server_id: McString(String::new()),
+                public_key: key.public_key().der().to_vec(),
+                verify_token: verify_token.to_vec(),
+                should_authenticate: true,
+            })
+            .await?;
+
+        let response: EncryptionResponsePacket =
+            Self::read_login_response(connection, state).await?;
+        if key.decrypt(&response.verify_token)? != verify_token {
+            return Err(ServerError::Authentication(
+                "Verify token does not match".to_string(),
+            ));
+        }
+        let secret: [u8; SECRET_LENGTH] = key
+            .decrypt(&response.shared_secret)?
+            .try_into()
+            .map_err(|_| ServerError::Authentication("Invalid shared secret".to_string()))?;
+        connection.enable_encryption(&secret);
+
+        let hash = server_hash("", &secret, key.public_key().der());
+        match state.profiles.has_joined(name, &hash).await {
+            Ok(Some(profile)) => Ok(profile),
+            Ok(None) => Err(ServerError::Authentication(format!(
+                "{} did not join through the session server",
+                name
+            ))),
+            Err(e) => {
+                tracing::warn!("Failed to authenticate {}: {}", name, e);
+                Err(ServerError::Kicked(crate::lang::translate(
+                    "multiplayer.disconnect.authservers_down",
+                    &[],
+                )))
+            }
+        }
+    }
+
+    /// Ask the Velocity proxy the player connects through who they are,
+    /// taking the address it forwards as the client's
+    async fn read_forwarded_player(
+        connection: &mut Connection,
+        secret: &Secret,
+        state: &ServerState,
+    ) -> Result<GameProfile> {
+        let message_id = VarInt(0);
+        connection
+            .write_packet(&LoginPluginRequestPacket {
+                message_id,
+                channel: velocity::CHANNEL.into(),
+                data: vec![velocity::MODERN_DEFAULT],
+            })
+            .await?;
+
+        let response: LoginPluginResponsePacket =
+            Self::read_login_response(connection, state).await?;
+        let data = match response.data {
+            Some(data) if response.message_id == message_id => data,
+            _ => {
+                return Err(ServerError::Kicked(crate::lang::translate(
+                    "obsidium.disconnect.velocity_required",
+                    &[],
+                )));
+            }
+        };
+        let player = ForwardedPlayer::verify(secret, &data)?;
+        connection.set_forwarded_ip(player.address);
+        tracing::Span::current().record("client", connection.logged_addr());
+        Ok(player.profile)
+    }
+
+    /// Wait for the client's answer to a login request, within what is left
+    /// of the login timeout
+    async fn read_login_response<P: ServerboundPacket>(
+        connection: &mut Connection,
+        state: &ServerState,
+    ) -> Result<P> {
+        let login_timeout = state.config.login_timeout;
+        let time_left = login_timeout.saturating_sub(connection.uptime());
+        let read = connection.read_packet();
+        let result = if login_timeout.is_zero() {
+            Some(read.await)
+        } else {
+            tokio::time::timeout(time_left, read).await.ok()
+        };
+        let Some(result) = result else {
+            return Err(slow_login(connection));
+        };
+        let (packet_id, data) = result?;
+        if packet_id.0 != P::ID {
+            return Err(ServerError::Protocol(format!(
+                "Expected packet 0x{:02X}, got packet 0x{:02X}",
+                P::ID,
+                packet_id.0
+            )));
+        }
+        P::decode(connection.state(), &data)
+    }
+
+    /// Authenticate and admit a player starting to log in, and finish the
+    /// login
+    async fn handle_login_start(
+        connection: &mut Connection,
+        data: &[u8],
+        state: &ServerState,
+    ) -> Result<()> {
+        let login_start = LoginStartPacket::decode(connection.state(), data)?;
+        Self::check_throttle(connection, &login_start, state)?;
+        let profile = Self::authenticate(connection, &login_start, state).await?;
+        Self::admit(connection, &profile, state).await?;
+
+        tracing::Span::current().record("player", profile.name.as_str());
+        tracing::info!(
+            "Player {} ({}) logging in from {}",
+            profile.name,
+            profile.uuid,
+            connection.logged_addr()
+        );
+
+        // Create player, reserving its slot before the login completes
+        let mut player = crate::game::player::Player::new(profile.uuid, profile.name.clone());
+        player.ip = Some(connection.client_ip().to_canonical());
+        let entity_id = state.world.write().await.entities_mut().next_entity_id();
+        player.entity_id = entity_id;
+        player.set_game_mode(state.config.game_mode);
+        state.place_at_spawn(&mut player).await;
+        state.load_player_data(&mut player);
+
+        let max_players = state.settings().max_players as usize;
+        let bypass_limit = state.ops.bypasses_player_limit(&profile.uuid);
+        match state.players.try_add_player(
+            player,
+            connection.peer_addr(),
+            max_players,
+            bypass_limit,
+        ) {
+            AddPlayerOutcome::Added => {}
+            AddPlayerOutcome::Replaced { player, addr } => {
+                Self::end_replaced_session(state, player, addr).await;
+            }
+            AddPlayerOutcome::Full => {
+                tracing::info!(
+                    "Disconnecting {} ({}): server is full",
+                    profile.name,
+                    connection.logged_addr()
+                );
+                state
+                    .world
+                    .write()
+                    .await
+                    .entities_mut()
+                    .release_entity_id(entity_id);
+                return Err(ServerError::Kicked(server_full_message()));
+            }
+        }
+
+        // Enable compression if configured
+        if let Some(threshold) = state.config.compression_threshold {
+            let compression_packet = SetCompressionPacket {
+                threshold: (threshold as i32).into(),
+            };
+            connection.write_packet(&compression_packet).await?;
+            connection.enable_compression(threshold)?;
+        }
+
+        // Send login success, with the skin the session server returned
+        state.profiles.remember(profile.uuid, &profile.name);
+        let properties = if state.config.online_mode || !profile.properties.is_empty() {
+            profile.properties
+        } else {
+            state.profiles.skin(profile.uuid, &profile.name).await
+        };
+        let login_success = LoginSuccessPacket {
+            uuid: profile.uuid,
+            username: profile.name.into(),
+            properties,
+        };
+        connection.write_packet(&login_success).await
+    }
+
     /// Handle login state packets
     async fn handle_login_packet(
         connection: &mut Connection,
         packet_id: crate::protocol::VarInt,
         data: &[u8],
-        config: &ServerConfig,
-        players: &Arc<PlayerManager>,
-    ) -> Result<()> {
+        state: &ServerState,
+    ) -> Result<bool> {
         if packet_id.0 == LoginStartPacket::ID {
-            let login_start = LoginStartPacket::read(&mut std::io::Cursor::new(data))?;
+            Self::handle_login_start(connection, data, state).await?;
+        } else if packet_id.0 == LoginAcknowledgedPacket::ID {
+            LoginAcknowledgedPacket::decode(connection.state(), data)?;
+            connection.set_state(ConnectionState::Configuration)?;
+            tracing::info!("Player logged in successfully, transitioning to configuration state");
 
-            tracing::info!(
-                "Player {} ({}) logging in from {}",
-                login_start.name.0,
-                login_start.player_uuid,
-                connection.peer_addr()
-            );
+            let brand = PluginMessagePacket::brand(SERVER_BRAND);
+            connection
+                .write_packet(&ClientboundPluginMessagePacket(brand))
+                .await?;
+            Self::send_configuration(connection, state).await?;
+        }
+        Ok(false)
+    }
 
-            // Enable compression if configured
-            if let Some(threshold) = config.compression_threshold {
-                let compression_packet = SetCompressionPacket {
-                    threshold: (threshold as i32).into(),
-                };
-                connection.write_packet(&compression_packet).await?;
-                connection.enable_compression(threshold)?;
-            } // Send login success
-            let login_success = LoginSuccessPacket {
-                uuid: login_start.player_uuid,
-                username: login_start.name.clone(),
-                properties: Vec::new(),
-            };
-            connection.write_packet(&login_success).await?;
+    /// Send what a client is configured with in the configuration state
+    ///
+    /// Configuration finishes once the client is done with the resource
+    /// pack, or right away without one.
+    async fn send_configuration(connection: &mut Connection, state: &ServerState) -> Result<()> {
+        let settings = state.settings();
+        match settings.resource_pack {
+            Some(ref pack) => {
+                let prompt = settings.resource_pack_prompt.clone();
+                let packet = pack.packet(settings.require_resource_pack, prompt);
+                connection.write_packet(&packet).await
+            }
+            None => connection.write_packet(&FinishConfigurationPacket).await,
+        }
+    }
 
-            // Create player
-            let player =
-                crate::game::player::Player::new(login_start.player_uuid, login_start.name.0);
+    /// Move a player who acknowledged a request to reconfigure to the
+    /// configuration state
+    async fn enter_configuration(connection: &mut Connection, state: &ServerState) -> Result<()> {
+        let requested = state
+            .players
+            .with_player_mut(&connection.peer_addr(), |player| {
+                player.resource_pack_status = None;
+                player.reconfiguring
+            });
+        if requested != Some(true) {
+            return Err(ServerError::Protocol(
+                "Configuration acknowledged without being requested".to_string(),
+            ));
+        }
+        connection.set_state(ConnectionState::Configuration)?;
+        Self::send_configuration(connection, state).await
+    }
 
-            players.add_player(player, connection.peer_addr()).await;
+    /// End a connection because of an error
+    ///
+    /// The player is told why if the error has a
+    /// [disconnect reason](ServerError::disconnect_reason); otherwise the
+    /// connection is just dropped.
+    async fn close_with_error(connection: &mut Connection, error: &ServerError) {
+        let Some(reason) = error.disconnect_reason() else {
+            tracing::debug!("Connection {} closed: {}", connection.logged_addr(), error);
+            return;
+        };
 
-            connection.set_state(ConnectionState::Configuration);
+        if !matches!(error, ServerError::Kicked(_)) {
+            tracing::warn!("Disconnecting {}: {}", connection.logged_addr(), error);
+        }
+        if let Err(e) = Self::disconnect(connection, reason).await {
+            tracing::debug!(
+                "Failed to send disconnect to {}: {}",
+                connection.logged_addr(),
+                e
+            );
+        }
+    }
 
-            tracing::info!("Player logged in successfully, transitioning to configuration state");
+    /// Send a disconnect packet appropriate for the connection's state
+    async fn disconnect(connection: &mut Connection, reason: JsonTextComponent) -> Result<()> {
+        match connection.state() {
+            ConnectionState::Login => {
+                connection
+                    .write_packet(&LoginDisconnectPacket { reason })
+                    .await
+            }
+            ConnectionState::Configuration => {
+                connection
+                    .write_packet(&ConfigurationDisconnectPacket { reason })
+                    .await
+            }
+            ConnectionState::Play => connection.write_packet(&DisconnectPacket { reason }).await,
+            ConnectionState::Handshaking | ConnectionState::Status => Ok(()),
         }
-        Ok(())
     }
 
     /// Handle configuration state packets
@@ -356,48 +1463,616 @@ impl MinecraftServer {
         connection: &mut Connection,
         packet_id: crate::protocol::VarInt,
         data: &[u8],
-        config: &ServerConfig,
+        state: &ServerState,
     ) -> Result<()> {
-        if packet_id.0 == LoginAcknowledgedPacket::ID {
-            let _login_ack = LoginAcknowledgedPacket::read(&mut std::io::Cursor::new(data))?;
-
-            tracing::debug!("Login acknowledged received in configuration state");
-
-            // Send finish configuration packet
-            use crate::protocol::packets::configuration::FinishConfigurationPacket;
-            let finish_config = FinishConfigurationPacket;
-            connection.write_packet(&finish_config).await?;
-
-            tracing::debug!("Finish configuration packet sent");
-        } else if packet_id.0 == 0x02 {
-            // Acknowledge Finish Configuration packet
-            use crate::protocol::packets::configuration::AcknowledgeFinishConfigurationPacket;
-            let _ack_finish =
-                AcknowledgeFinishConfigurationPacket::read(&mut std::io::Cursor::new(data))?;
+        if packet_id.0 == PluginMessagePacket::ID {
+            let message = PluginMessagePacket::decode(connection.state(), data)?;
+            if let Some(brand) = message.read_brand() {
+                tracing::debug!("{} uses client brand {}", connection.logged_addr(), brand);
+            }
+        } else if packet_id.0 == AcknowledgeFinishConfigurationPacket::ID {
+            AcknowledgeFinishConfigurationPacket::decode(connection.state(), data)?;
 
             tracing::debug!(
                 "Acknowledge finish configuration received, transitioning to play state"
             );
 
-            connection.set_state(ConnectionState::Play);
+            connection.set_state(ConnectionState::Play)?;
 
             // Send login play packet after transitioning to play state
-            let login_play = LoginPlayPacket::from_server_config(config, 1);
+            let settings = state.settings();
+            let mut login_play = LoginPlayPacket::from_server_config(&state.config, 1);
+            let generator = state.world.read().await.generator();
+            login_play.is_flat = generator.is_flat();
+            login_play.is_debug = generator.is_debug();
+            if state.nether.is_some() {
+                login_play
+                    .dimension_names
+                    .push(Dimension::Nether.name().into());
+            }
+            login_play.max_players = VarInt(settings.max_players as i32);
+            login_play.view_distance = VarInt(settings.view_distance as i32);
+            login_play.simulation_distance = VarInt(i32::from(settings.simulation_distance));
+            let mut returning = false;
+            if let Some((entity_id, view_distance, game_mode, reconfigured)) = state
+                .players
+                .with_player_mut(&connection.peer_addr(), |player| {
+                    (
+                        player.entity_id,
+                        player.effective_view_distance(settings.view_distance),
+                        player.game_mode,
+                        std::mem::take(&mut player.reconfiguring),
+                    )
+                })
+            {
+                returning = reconfigured;
+                login_play.entity_id = entity_id;
+                login_play.view_distance = VarInt(view_distance as i32);
+                login_play.game_mode = game_mode.id();
+            }
             connection.write_packet(&login_play).await?;
 
+            for packet in state.world_state_packets().await {
+                connection.write_dyn_packet(packet.as_ref()).await?;
+            }
+            for packet in state.entity_packets().await {
+                connection.write_dyn_packet(packet.as_ref()).await?;
+            }
+            connection
+                .write_packet(&state.recipes_packet().await)
+                .await?;
+            Self::send_chunks(connection, state, login_play.view_distance.0 as u8).await?;
+
             tracing::info!("Login play packet sent, player is now in play state");
+
+            if let Some(player) = state.players.get_player_by_addr(&connection.peer_addr()) {
+                Self::send_player_state(connection, state, &player).await?;
+                state.send_commands(&player.uuid);
+                // A player back from reconfiguring never left the others
+                if returning {
+                    return Ok(());
+                }
+                let joined = state
+                    .player_list_packet(std::slice::from_ref(&player))
+                    .await;
+                state.broadcast_packet(joined);
+                state.audit.record(AuditEvent::Login {
+                    player: player.username.clone(),
+                    uuid: player.uuid,
+                    ip: Some(connection.client_ip().to_string()),
+                });
+                state.announce_join(&player);
+            }
+        } else if packet_id.0 == ClientInformationPacket::ID {
+            let information = ClientInformationPacket::decode(connection.state(), data)?;
+            Self::apply_client_information(connection, state, &information);
+        } else if packet_id.0 == ResourcePackResponsePacket::ID {
+            let response = ResourcePackResponsePacket::decode(connection.state(), data)?;
+            Self::handle_resource_pack_response(connection, state, &response).await?;
         }
         Ok(())
     }
 
+    /// Send a player entering play their position, abilities, equipment
+    /// and the tab list, and show their appearance to everyone
+    async fn send_player_state(
+        connection: &mut Connection,
+        state: &ServerState,
+        player: &Player,
+    ) -> Result<()> {
+        connection
+            .write_packet(&state.teleport_packet(player))
+            .await?;
+        connection
+            .write_packet(&PlayerAbilitiesPacket::from(&player.abilities))
+            .await?;
+        if let Some(settings) = player.settings() {
+            state.broadcast_appearance(player.entity_id, settings);
+        }
+        state.broadcast_equipment(player, &player.inventory.visible_equipment());
+        for packet in state.equipment_packets(&player.uuid) {
+            connection.write_packet(&packet).await?;
+        }
+        let everyone = state.players.get_all_players();
+        connection
+            .write_packet(&state.player_list_packet(&everyone).await)
+            .await
+    }
+
+    /// Record a client's progress with the resource pack, finishing the
+    /// configuration once it is done with the pack
+    async fn handle_resource_pack_response(
+        connection: &mut Connection,
+        state: &ServerState,
+        response: &ResourcePackResponsePacket,
+    ) -> Result<()> {
+        let settings = state.settings();
+        if settings
+            .resource_pack
+            .as_ref()
+            .is_none_or(|pack| pack.id != response.uuid)
+        {
+            return Ok(());
+        }
+        let status = ResourcePackStatus::try_from(response.result)?;
+        tracing::debug!(
+            "{} reported resource pack status {:?}",
+            connection.logged_addr(),
+            status
+        );
+
+        let previous = state
+            .players
+            .with_player_mut(&connection.peer_addr(), |player| {
+                player.resource_pack_status.replace(status)
+            })
+            .flatten();
+        if !status.is_final() || previous.is_some_and(ResourcePackStatus::is_final) {
+            return Ok(());
+        }
+
+        // Like vanilla, only declining a required pack is fatal
+        if status == ResourcePackStatus::Declined && settings.require_resource_pack {
+            tracing::info!(
+                "Disconnecting {}: declined the required resource pack",
+                connection.logged_addr()
+            );
+            let reason =
+                crate::lang::translate("multiplayer.requiredTexturePrompt.disconnect", &[]);
+            return Err(ServerError::Kicked(reason));
+        }
+        connection.write_packet(&FinishConfigurationPacket).await
+    }
+
     /// Handle play state packets
-    fn handle_play_packet(packet_id: crate::protocol::VarInt) {
-        // Handle play packets
+    async fn handle_play_packet(
+        connection: &mut Connection,
+        packet_id: crate::protocol::VarInt,
+        data: &[u8],
+        state: &ServerState,
+    ) -> Result<()> {
         tracing::debug!("Received play packet ID: 0x{:02X}", packet_id.0);
 
-        // TODO: Implement play packet handlers
-        // For now, just log them
+        if Self::handle_inventory_packet(connection, packet_id, data, state).await?
+            || Self::handle_movement_packet(connection, packet_id, data, state).await?
+        {
+            return Ok(());
+        }
+        if packet_id.0 == PlayClientInformationPacket::ID {
+            let information = PlayClientInformationPacket::decode(connection.state(), data)?;
+            if let Some(view_distance) =
+                Self::apply_client_information(connection, state, &information.0)
+            {
+                let radius = SetChunkCacheRadiusPacket {
+                    view_distance: VarInt(view_distance as i32),
+                };
+                connection.write_packet(&radius).await?;
+            }
+        } else if packet_id.0 == UseItemOnPacket::ID {
+            let use_item = UseItemOnPacket::decode(connection.state(), data)?;
+            state
+                .use_item_on(&connection.peer_addr(), use_item.location, use_item.face.0)
+                .await;
+            let ack = AcknowledgeBlockChangePacket {
+                sequence: use_item.sequence,
+            };
+            connection.write_packet(&ack).await?;
+        } else if packet_id.0 == PlayerCommandPacket::ID {
+            let command = PlayerCommandPacket::decode(connection.state(), data)?;
+            let addr = connection.peer_addr();
+            match command.action.0 {
+                PlayerCommandPacket::LEAVE_BED => state.leave_bed(&addr).await,
+                PlayerCommandPacket::START_SPRINTING => state.set_sprinting(&addr, true),
+                PlayerCommandPacket::STOP_SPRINTING => state.set_sprinting(&addr, false),
+                _ => {}
+            }
+        } else if packet_id.0 == InteractPacket::ID {
+            let interact = InteractPacket::decode(connection.state(), data)?;
+            Self::handle_interact(connection, state, &interact).await;
+        } else if packet_id.0 == PlayerActionPacket::ID {
+            let action = PlayerActionPacket::decode(connection.state(), data)?;
+            Self::handle_player_action(connection, state, &action).await?;
+        } else if packet_id.0 == ChangeDifficultyRequestPacket::ID {
+            let request = ChangeDifficultyRequestPacket::decode(connection.state(), data)?;
+            if let Some(&difficulty) = Difficulty::ALL.get(request.difficulty as usize) {
+                let addr = connection.peer_addr();
+                if let Err(e) = state.request_difficulty(&addr, difficulty).await {
+                    tracing::warn!(
+                        "Failed to save difficulty set by {}: {}",
+                        connection.logged_addr(),
+                        e
+                    );
+                }
+            }
+        } else if packet_id.0 == LockDifficultyPacket::ID {
+            let lock = LockDifficultyPacket::decode(connection.state(), data)?;
+            state
+                .request_difficulty_lock(&connection.peer_addr(), lock.locked)
+                .await;
+        } else if packet_id.0 == ChatMessagePacket::ID {
+            let chat = ChatMessagePacket::decode(connection.state(), data)?;
+            state.send_chat(&connection.peer_addr(), &chat).await?;
+        } else if packet_id.0 == ChatCommandPacket::ID {
+            let packet = ChatCommandPacket::decode(connection.state(), data)?;
+            state
+                .run_player_command(&connection.peer_addr(), &packet.command.0)
+                .await;
+        } else if packet_id.0 == ConfigurationAcknowledgedPacket::ID {
+            ConfigurationAcknowledgedPacket::decode(connection.state(), data)?;
+            Self::enter_configuration(connection, state).await?;
+        } else if packet_id.0 == TeleportToEntityPacket::ID {
+            let packet = TeleportToEntityPacket::decode(connection.state(), data)?;
+            state.teleport_to_player(&connection.peer_addr(), &packet.target);
+        }
+
+        // TODO: Implement the remaining play packet handlers
+        Ok(())
     }
+
+    /// Handle a player right-clicking or attacking an entity
+    ///
+    /// Vanilla clients send a right-click on an entity both as a click on a
+    /// point of it and as a plain click, so armor stands react to the first
+    /// and everything else to the second.
+    async fn handle_interact(
+        connection: &Connection,
+        state: &ServerState,
+        interact: &InteractPacket,
+    ) {
+        let addr = connection.peer_addr();
+        let target = interact.entity_id.0;
+        let hand = interact
+            .hand
+            .and_then(|hand| Hand::from_id(hand.0))
+            .unwrap_or(Hand::Main);
+        let entity_type = state.entity_type(target).await;
+        let used = match (interact.kind.0, entity_type) {
+            (InteractPacket::ATTACK, _) => {
+                state.attack_entity(&addr, target).await;
+                return;
+            }
+            (InteractPacket::INTERACT_AT, Some(EntityType::ArmorStand)) => {
+                let height = interact.target.map_or(0.0, |point| point[1]);
+                state.use_decoration(&addr, target, hand, height).await
+            }
+            (InteractPacket::INTERACT, Some(EntityType::ItemFrame)) => {
+                state.use_decoration(&addr, target, hand, 0.0).await
+            }
+            (_, Some(EntityType::ArmorStand | EntityType::ItemFrame)) => return,
+            (InteractPacket::INTERACT, _) if state.is_villager(target).await => {
+                state.open_trading(&addr, target).await;
+                return;
+            }
+            (InteractPacket::INTERACT, _) => {
+                if let Some(Err(e)) = state.mount_vehicle(&addr, target).await {
+                    tracing::debug!("{} cannot ride {}: {}", connection.logged_addr(), target, e);
+                }
+                return;
+            }
+            _ => return,
+        };
+        if let Some(Err(e)) = used {
+            tracing::debug!("{} cannot use {}: {}", connection.logged_addr(), target, e);
+        }
+    }
+
+    /// Handle a player digging, swapping hands or no longer using an item
+    async fn handle_player_action(
+        connection: &mut Connection,
+        state: &ServerState,
+        action: &PlayerActionPacket,
+    ) -> Result<()> {
+        let addr = connection.peer_addr();
+        let finished = match action.status.0 {
+            PlayerActionPacket::START_DIGGING => Some(false),
+            PlayerActionPacket::FINISH_DIGGING => Some(true),
+            PlayerActionPacket::SWAP_HANDS => {
+                state.swap_hands(&addr);
+                None
+            }
+            PlayerActionPacket::RELEASE_USE_ITEM => {
+                state.release_item(&addr);
+                None
+            }
+            _ => None,
+        };
+        if let Some(finished) = finished {
+            state.dig_block(&addr, action.location, finished).await;
+            let ack = AcknowledgeBlockChangePacket {
+                sequence: action.sequence,
+            };
+            connection.write_packet(&ack).await?;
+        }
+        Ok(())
+    }
+
+    /// Handle play state packets that move or turn the player or their
+    /// vehicle
+    ///
+    /// Returns whether the packet was one of them.
+    async fn handle_movement_packet(
+        connection: &mut Connection,
+        packet_id: crate::protocol::VarInt,
+        data: &[u8],
+        state: &ServerState,
+    ) -> Result<bool> {
+        let addr = connection.peer_addr();
+        if packet_id.0 == PlayerPositionPacket::ID {
+            let packet = PlayerPositionPacket::decode(connection.state(), data)?;
+            state.move_player(&addr, Movement::from(&packet)).await;
+        } else if packet_id.0 == PlayerPositionAndRotationPacket::ID {
+            let packet = PlayerPositionAndRotationPacket::decode(connection.state(), data)?;
+            state.move_player(&addr, Movement::from(&packet)).await;
+        } else if packet_id.0 == PlayerRotationPacket::ID {
+            let packet = PlayerRotationPacket::decode(connection.state(), data)?;
+            state.move_player(&addr, Movement::from(&packet)).await;
+        } else if packet_id.0 == PlayerOnGroundPacket::ID {
+            let packet = PlayerOnGroundPacket::decode(connection.state(), data)?;
+            state.move_player(&addr, Movement::from(&packet)).await;
+        } else if packet_id.0 == ServerboundPlayerAbilitiesPacket::ID {
+            let packet = ServerboundPlayerAbilitiesPacket::decode(connection.state(), data)?;
+            state.set_flying(&addr, packet.flying);
+        } else if packet_id.0 == PlayerInputPacket::ID {
+            let input = PlayerInputPacket::decode(connection.state(), data)?;
+            state.apply_player_input(&addr, &input).await;
+        } else if packet_id.0 == MoveVehiclePacket::ID {
+            let packet = MoveVehiclePacket::decode(connection.state(), data)?;
+            state.move_vehicle(&addr, &packet).await;
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Handle play state packets that change the player's inventory
+    ///
+    /// Returns whether the packet was one of them.
+    async fn handle_inventory_packet(
+        connection: &mut Connection,
+        packet_id: crate::protocol::VarInt,
+        data: &[u8],
+        state: &ServerState,
+    ) -> Result<bool> {
+        let addr = connection.peer_addr();
+        if packet_id.0 == SetHeldItemPacket::ID {
+            let held = SetHeldItemPacket::decode(connection.state(), data)?;
+            state.select_hotbar_slot(&addr, held.slot);
+        } else if packet_id.0 == SetCreativeModeSlotPacket::ID {
+            let packet = SetCreativeModeSlotPacket::decode(connection.state(), data)?;
+            state.set_creative_slot(&addr, packet.slot, packet.item);
+        } else if packet_id.0 == UseItemPacket::ID {
+            let packet = UseItemPacket::decode(connection.state(), data)?;
+            if let Some(hand) = Hand::from_id(packet.hand.0) {
+                state.use_item(&addr, hand);
+            }
+            let ack = AcknowledgeBlockChangePacket {
+                sequence: packet.sequence,
+            };
+            connection.write_packet(&ack).await?;
+        } else if packet_id.0 == SelectTradePacket::ID {
+            let packet = SelectTradePacket::decode(connection.state(), data)?;
+            let index = usize::try_from(packet.selected.0).unwrap_or(usize::MAX);
+            state.select_trade(&addr, index).await;
+        } else if packet_id.0 == ClickContainerPacket::ID {
+            let packet = ClickContainerPacket::decode(connection.state(), data)?;
+            state.click_container(&addr, &packet).await;
+        } else if packet_id.0 == CloseContainerPacket::ID {
+            let packet = CloseContainerPacket::decode(connection.state(), data)?;
+            state.close_container(&addr, Some(packet.window_id.0));
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Store a client's settings on its player
+    ///
+    /// Players in game are shown with their new skin layers and main hand.
+    /// Returns the player's new effective view distance if it changed.
+    fn apply_client_information(
+        connection: &Connection,
+        state: &ServerState,
+        information: &ClientInformationPacket,
+    ) -> Option<u8> {
+        let server_view_distance = state.settings().view_distance;
+        let settings = ClientSettings::from(information);
+
+        let (uuid, entity_id, appearance_changed, view_distance) =
+            state
+                .players
+                .with_player_mut(&connection.peer_addr(), |player| {
+                    let previous = player.effective_view_distance(server_view_distance);
+                    let appearance_changed = player.settings().is_none_or(|old| {
+                        old.skin_parts != settings.skin_parts || old.main_hand != settings.main_hand
+                    });
+                    player.set_settings(settings.clone());
+                    let current = player.effective_view_distance(server_view_distance);
+
+                    tracing::debug!(
+                        "{} requested view distance {}, using {}",
+                        player.username,
+                        settings.view_distance,
+                        current
+                    );
+                    (
+                        player.uuid,
+                        player.entity_id,
+                        appearance_changed,
+                        (current != previous).then_some(current),
+                    )
+                })?;
+
+        if appearance_changed && connection.state() == ConnectionState::Play {
+            state.broadcast_appearance(entity_id, &settings);
+        }
+        state
+            .events
+            .publish(&ServerEvent::PlayerSettingsChanged { uuid, settings });
+        view_distance
+    }
+
+    /// Send a joining player the chunks within their view distance
+    ///
+    /// Chunks are sent nearest first. Their packets come from the world's
+    /// cache, so chunks already sent to another player are not serialized
+    /// or compressed again. Chunks not loaded yet count against the world's
+    /// load budget, so many players joining at once are spread over ticks.
+    async fn send_chunks(
+        connection: &mut Connection,
+        state: &ServerState,
+        view_distance: u8,
+    ) -> Result<()> {
+        let Some((uuid, position, dimension)) = state
+            .players
+            .with_player_by_addr(&connection.peer_addr(), |player| {
+                (player.uuid, player.position, player.dimension)
+            })
+        else {
+            return Ok(());
+        };
+        let center = ChunkPosition::from_world_coords(position.x, position.z);
+        let view = ChunkView {
+            dimension,
+            center,
+            radius: i32::from(view_distance),
+        };
+        state.chunk_queue.lock().await.set_view(uuid, view);
+
+        connection
+            .write_packet(&GameEventPacket::new(
+                GameEventPacket::START_WAITING_FOR_LEVEL_CHUNKS,
+                0.0,
+            ))
+            .await?;
+        connection
+            .write_packet(&SetCenterChunkPacket {
+                chunk_x: VarInt(center.x),
+                chunk_z: VarInt(center.z),
+            })
+            .await?;
+
+        let threshold = connection.compression_threshold();
+        for position in view.chunks() {
+            // Wait for a later tick once this one's load budget is used up
+            let lookup = loop {
+                let mut world = state.world.write().await;
+                if world.can_load_chunk(position) {
+                    break world.lookup_chunk_packet(position, threshold)?;
+                }
+                drop(world);
+                tokio::time::sleep(TICK_DURATION).await;
+            };
+            let packet = match lookup {
+                ChunkPacket::Cached(packet) => packet,
+                ChunkPacket::Uncached { packet, revision } => {
+                    // Compress without holding the world lock
+                    let packet = Arc::new(encode_offloaded(packet, threshold).await?);
+                    let mut world = state.world.write().await;
+                    world.cache_chunk_packet(position, revision, Arc::clone(&packet));
+                    packet
+                }
+            };
+            connection.write_encoded(&packet).await?;
+        }
+        Ok(())
+    }
+
+    /// Tell a player about a change of the server's view or simulation
+    /// distance, replacing the `applied` settings with the `current` ones
+    ///
+    /// Nothing is sent for a distance that is unchanged for the player.
+    async fn update_distances(
+        connection: &mut Connection,
+        state: &ServerState,
+        applied: &mut RuntimeSettings,
+        current: RuntimeSettings,
+    ) -> Result<()> {
+        let previous = std::mem::replace(applied, current);
+        let current = &*applied;
+        if current.simulation_distance != previous.simulation_distance {
+            let distance = SetSimulationDistancePacket {
+                simulation_distance: VarInt(i32::from(current.simulation_distance)),
+            };
+            connection.write_packet(&distance).await?;
+        }
+        let Some((effective, was)) =
+            state
+                .players
+                .with_player_by_addr(&connection.peer_addr(), |player| {
+                    (
+                        player.effective_view_distance(current.view_distance),
+                        player.effective_view_distance(previous.view_distance),
+                    )
+                })
+        else {
+            return Ok(());
+        };
+
+        if effective != was {
+            let radius = SetChunkCacheRadiusPacket {
+                view_distance: VarInt(effective as i32),
+            };
+            connection.write_packet(&radius).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Brand shown in the client's debug screen
+const SERVER_BRAND: &str = "Obsidium";
+
+/// Span timing one stage of a tick
+fn stage_span(stage: TickStage) -> tracing::Span {
+    tracing::debug_span!("tick_stage", stage = stage.name())
+}
+
+/// Receive the next packet queued for a player, waiting forever if their queue is not open yet
+async fn recv_outbound(outbound: &mut Option<OutboundReceiver>) -> Option<Arc<dyn DynPacket>> {
+    match outbound {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Time a connection has left to log in, or `None` if it is not subject
+/// to the login timeout
+///
+/// Configuration may take long while a resource pack downloads, and a
+/// player sent back to it has long finished logging in.
+fn login_time_left(connection: &Connection, login_timeout: Duration) -> Option<Duration> {
+    let logging_in = matches!(
+        connection.state(),
+        ConnectionState::Handshaking | ConnectionState::Status | ConnectionState::Login
+    );
+    (logging_in && !login_timeout.is_zero())
+        .then(|| login_timeout.saturating_sub(connection.uptime()))
+}
+
+/// Kick for a connection that took too long to log in
+fn slow_login(connection: &Connection) -> ServerError {
+    tracing::info!(
+        "Disconnecting {}: took too long to log in",
+        connection.logged_addr()
+    );
+    ServerError::Kicked(crate::lang::translate(
+        "multiplayer.disconnect.slow_login",
+        &[],
+    ))
+}
+
+/// Disconnect reason shown to players joining a full server
+fn server_full_message() -> JsonTextComponent {
+    crate::lang::translate("multiplayer.disconnect.server_full", &[])
+}
+
+/// Disconnect reason shown to clients speaking an unsupported protocol
+fn outdated_message(protocol_version: i32, advertised: &AdvertisedVersion) -> JsonTextComponent {
+    let key = if advertised.is_outdated_client(protocol_version) {
+        "multiplayer.disconnect.outdated_client"
+    } else {
+        "multiplayer.disconnect.outdated_server"
+    };
+    crate::lang::translate(key, &[&advertised.name])
 }
 
 impl Drop for MinecraftServer {
@@ -2,6 +2,26 @@
 //!
 //! This module contains the main server logic and orchestration.
 
+pub mod audit;
+pub mod backup;
+pub mod bans;
+pub mod console;
+pub mod filter;
+pub mod handle;
 pub mod minecraft;
+pub mod ops;
+pub mod profiler;
+pub mod profiles;
+pub mod reload;
+pub mod resource_pack;
+pub mod scheduler;
+pub mod state;
+pub mod tick;
+pub mod usercache;
+pub mod version;
+pub mod watchdog;
+pub mod whitelist;
 
+pub use handle::ServerHandle;
 pub use minecraft::MinecraftServer;
+pub use state::ServerState;
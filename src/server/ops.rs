@@ -0,0 +1,138 @@
+//! Server operators
+//!
+//! Operators are stored in `ops.json` using the vanilla format. Besides a
+//! permission level, each operator can be allowed to join a full server.
+
+use crate::error::{Result, ServerError};
+use crate::protocol::types::McUuid;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// File operators are stored in
+pub const OPS_FILE: &str = "ops.json";
+
+/// An operator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpEntry {
+    /// Player UUID
+    pub uuid: McUuid,
+    /// Player name when they were made an operator
+    pub name: String,
+    /// Permission level (1-4)
+    pub level: u8,
+    /// Whether the operator may join when the server is full
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+/// The list of server operators
+#[derive(Debug, Default)]
+pub struct OpList {
+    /// File the list is saved to (`None` keeps it in memory only)
+    path: Option<PathBuf>,
+    /// Operators
+    entries: RwLock<Vec<OpEntry>>,
+}
+
+impl OpList {
+    /// Create an empty operator list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the operator list from a file, starting empty if it does not exist
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| ServerError::Storage(format!("Invalid {}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path: Some(path),
+            entries: RwLock::new(entries),
+        })
+    }
+
+    /// Get an operator by UUID
+    pub fn get(&self, uuid: &McUuid) -> Option<OpEntry> {
+        self.entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|entry| entry.uuid == *uuid)
+            .cloned()
+    }
+
+    /// Check whether a player may join even when the server is full
+    pub fn bypasses_player_limit(&self, uuid: &McUuid) -> bool {
+        self.get(uuid).is_some_and(|op| op.bypasses_player_limit)
+    }
+
+    /// Make a player an operator, replacing their previous entry, and save
+    /// the list
+    pub fn op(&self, entry: OpEntry) -> Result<()> {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|existing| existing.uuid != entry.uuid);
+        entries.push(entry);
+        self.save(&entries)
+    }
+
+    /// Remove an operator, returning whether they were one
+    pub fn deop(&self, uuid: &McUuid) -> Result<bool> {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let before = entries.len();
+        entries.retain(|entry| entry.uuid != *uuid);
+        if entries.len() == before {
+            return Ok(false);
+        }
+        self.save(&entries)?;
+        Ok(true)
+    }
+
+    /// Write the list to its file
+    fn save(&self, entries: &[OpEntry]) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(entries)
+            .map_err(|e| ServerError::Storage(format!("Failed to encode ops: {}", e)))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_vanilla_format() {
+        let path = std::env::temp_dir().join(format!("obsidium-ops-{}.json", McUuid::new_v4()));
+        let (admin, helper) = (McUuid::new_v4(), McUuid::new_v4());
+        let json = format!(
+            r#"[
+                {{"uuid": "{}", "name": "Admin", "level": 4, "bypassesPlayerLimit": true}},
+                {{"uuid": "{}", "name": "Helper", "level": 2}}
+            ]"#,
+            admin, helper
+        );
+        std::fs::write(&path, json).unwrap();
+
+        let ops = OpList::load(&path).unwrap();
+        assert_eq!(ops.get(&admin).unwrap().level, 4);
+        assert!(ops.bypasses_player_limit(&admin));
+        assert!(!ops.bypasses_player_limit(&helper));
+        assert!(!ops.bypasses_player_limit(&McUuid::new_v4()));
+
+        assert!(ops.deop(&helper).unwrap());
+        assert!(!ops.deop(&helper).unwrap());
+        assert!(OpList::load(&path).unwrap().get(&helper).is_none());
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,720 @@
+//! Built-in profiler
+//!
+//! `/profile start` opens a profiling window and `/profile stop` (or the end
+//! of the window) closes it. While it runs, the [`Profiler`] adds up how
+//! long each [`TickStage`] takes and samples the loaded chunk count every
+//! tick. Counters that run all the time (packets, chunk loads and CPU time
+//! per thread) are read at both ends of the window. The resulting
+//! [`ProfileReport`] is written to `profiles/` as text or JSON.
+//!
+//! Chunks are generated synchronously when they are loaded, so there is no
+//! generation queue to sample; the report counts the chunks generated and
+//! read during the window instead, along with the loads the chunk budgets
+//! put off and the chunks they unloaded.
+
+use crate::clock::{self, SharedClock};
+use crate::game::world::ChunkLoadCounts;
+use crate::network::stats::PacketCounts;
+use crate::server::ServerState;
+use crate::server::watchdog::TickStage;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Directory profile reports are written to
+pub const PROFILE_DIR: &str = "profiles";
+
+/// Longest profiling window that can be requested
+pub const MAX_PROFILE_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// File format of a profile report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// JSON, for tools
+    Json,
+}
+
+impl ReportFormat {
+    /// Parse a format from its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" => Some(ReportFormat::Text),
+            "json" => Some(ReportFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// File extension of reports in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Text => "txt",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+/// Counters read at both ends of a profiling window
+#[derive(Debug, Clone, Default)]
+pub struct ProfileCounters {
+    /// Packets sent and received by every connection
+    pub packets: PacketCounts,
+    /// Chunks read, generated, deferred and evicted
+    pub chunk_loads: ChunkLoadCounts,
+    /// CPU time used by the process, where supported
+    pub process_cpu: Option<Duration>,
+    /// CPU time used by each thread name, where supported
+    pub thread_cpu: HashMap<String, Duration>,
+}
+
+impl ProfileCounters {
+    /// Read the counters of a running server
+    pub async fn sample(state: &ServerState) -> Self {
+        Self {
+            packets: state.packet_counters.snapshot(),
+            chunk_loads: state.world.read().await.chunk_loads(),
+            process_cpu: process_cpu_time(),
+            thread_cpu: thread_cpu_times(),
+        }
+    }
+}
+
+/// Count, total and maximum of a set of durations
+#[derive(Debug, Clone, Copy, Default)]
+struct Timing {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl Timing {
+    fn add(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    fn average(&self) -> Duration {
+        match u32::try_from(self.count) {
+            Ok(0) => Duration::ZERO,
+            Ok(count) => self.total / count,
+            Err(_) => self.total.div_f64(self.count as f64),
+        }
+    }
+}
+
+/// A profiling window in progress
+#[derive(Debug)]
+struct Session {
+    /// When the window opened
+    started: Instant,
+    /// Wall-clock time the window opened, for the report
+    started_at: time::OffsetDateTime,
+    /// How long the window lasts, if it ends by itself
+    window: Option<Duration>,
+    /// Format the report is written in
+    format: ReportFormat,
+    /// Counters when the window opened
+    baseline: ProfileCounters,
+    /// Duration of whole ticks
+    ticks: Timing,
+    /// Duration of each stage, in the order of [`TickStage::ALL`]
+    stages: [Timing; TickStage::ALL.len()],
+    /// Smallest loaded chunk count seen
+    min_chunks: usize,
+    /// Largest loaded chunk count seen
+    max_chunks: usize,
+    /// Sum of the loaded chunk counts seen, for the average
+    total_chunks: u64,
+    /// Number of loaded chunk counts seen
+    chunk_samples: u64,
+}
+
+/// Collects timings while a profiling window is open
+#[derive(Debug)]
+pub struct Profiler {
+    session: Mutex<Option<Session>>,
+    /// Source of the current time
+    clock: SharedClock,
+}
+
+impl Profiler {
+    /// Create a profiler with no window open
+    pub fn new() -> Self {
+        Self {
+            session: Mutex::new(None),
+            clock: clock::system(),
+        }
+    }
+
+    /// Time windows with the given clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Open a profiling window, unless one is already open
+    ///
+    /// Without a `window`, profiling continues until [`Profiler::stop`].
+    pub fn start(
+        &self,
+        window: Option<Duration>,
+        format: ReportFormat,
+        baseline: ProfileCounters,
+    ) -> bool {
+        let mut session = self.lock();
+        if session.is_some() {
+            return false;
+        }
+        *session = Some(Session {
+            started: self.clock.now(),
+            started_at: time::OffsetDateTime::now_utc(),
+            window,
+            format,
+            baseline,
+            ticks: Timing::default(),
+            stages: [Timing::default(); TickStage::ALL.len()],
+            min_chunks: usize::MAX,
+            max_chunks: 0,
+            total_chunks: 0,
+            chunk_samples: 0,
+        });
+        true
+    }
+
+    /// Check whether a window is open
+    pub fn is_running(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// Check whether the open window has reached its end
+    pub fn is_due(&self) -> bool {
+        self.lock().as_ref().is_some_and(|session| {
+            session
+                .window
+                .is_some_and(|window| self.clock.since(session.started) >= window)
+        })
+    }
+
+    /// Add the time a tick spent in one stage
+    pub fn record_stage(&self, stage: TickStage, duration: Duration) {
+        if let Some(session) = self.lock().as_mut() {
+            if let Some(index) = TickStage::ALL.iter().position(|s| *s == stage) {
+                session.stages[index].add(duration);
+            }
+        }
+    }
+
+    /// Add the duration of a finished tick
+    pub fn record_tick(&self, duration: Duration) {
+        if let Some(session) = self.lock().as_mut() {
+            session.ticks.add(duration);
+        }
+    }
+
+    /// Add a sample of the number of loaded chunks
+    pub fn record_loaded_chunks(&self, loaded_chunks: usize) {
+        if let Some(session) = self.lock().as_mut() {
+            session.chunk_samples += 1;
+            session.min_chunks = session.min_chunks.min(loaded_chunks);
+            session.max_chunks = session.max_chunks.max(loaded_chunks);
+            session.total_chunks += loaded_chunks as u64;
+        }
+    }
+
+    /// Close the window, building its report from the counters now
+    ///
+    /// Returns `None` if no window was open.
+    pub fn stop(&self, counters: ProfileCounters) -> Option<(ProfileReport, ReportFormat)> {
+        let session = self.lock().take()?;
+        let elapsed = self.clock.since(session.started);
+        Some((
+            ProfileReport::new(&session, elapsed, &counters),
+            session.format,
+        ))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Session>> {
+        self.session.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Close the profiling window of a server and save its report
+///
+/// Returns `None` if no window was open.
+pub async fn finish(state: &ServerState) -> Option<(ProfileReport, std::io::Result<PathBuf>)> {
+    if !state.profiler.is_running() {
+        return None;
+    }
+    let counters = ProfileCounters::sample(state).await;
+    let (report, format) = state.profiler.stop(counters)?;
+    let saved = report.save(format);
+    Some((report, saved))
+}
+
+/// Timings of one tick stage in a report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageReport {
+    /// Name of the stage
+    pub stage: &'static str,
+    /// Time spent in the stage over the window, in milliseconds
+    pub total_ms: f64,
+    /// Average time per tick, in milliseconds
+    pub average_ms: f64,
+    /// Longest time in a single tick, in milliseconds
+    pub max_ms: f64,
+    /// Share of the total tick time, in percent
+    pub percent: f64,
+}
+
+/// CPU time used by the threads of one name
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThreadReport {
+    /// Thread name; runtime workers share one
+    pub name: String,
+    /// CPU time used over the window, in milliseconds
+    pub cpu_ms: f64,
+    /// CPU time as a share of the window, in percent of one core
+    pub percent: f64,
+}
+
+/// Chunk activity in a report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChunkReport {
+    /// Chunks generated during the window
+    pub generated: u64,
+    /// Chunks read from storage during the window
+    pub read: u64,
+    /// Chunk loads put off to a later tick by the load budget
+    pub deferred: u64,
+    /// Chunks unloaded to stay under the loaded chunk limit
+    pub evicted: u64,
+    /// Fewest chunks loaded during a tick
+    pub loaded_min: usize,
+    /// Average chunks loaded during a tick
+    pub loaded_average: f64,
+    /// Most chunks loaded during a tick
+    pub loaded_max: usize,
+}
+
+/// Network activity in a report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PacketReport {
+    /// Packets received during the window
+    pub received: u64,
+    /// Packets sent during the window
+    pub sent: u64,
+    /// Bytes received during the window
+    pub bytes_received: u64,
+    /// Bytes sent during the window
+    pub bytes_sent: u64,
+    /// Packets received per second
+    pub received_per_second: f64,
+    /// Packets sent per second
+    pub sent_per_second: f64,
+}
+
+/// What a profiling window measured
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileReport {
+    /// When the window opened, in RFC 3339 format
+    pub started: String,
+    /// Length of the window in seconds
+    pub duration_secs: f64,
+    /// Ticks run during the window
+    pub ticks: u64,
+    /// Average ticks per second
+    pub tps: f64,
+    /// Average milliseconds per tick
+    pub mspt_average: f64,
+    /// Longest tick in milliseconds
+    pub mspt_max: f64,
+    /// Timings of each tick stage
+    pub stages: Vec<StageReport>,
+    /// CPU time used by the whole process, in percent of one core, where supported
+    pub process_cpu_percent: Option<f64>,
+    /// CPU time used per thread name, busiest first, where supported
+    pub threads: Vec<ThreadReport>,
+    /// Chunk activity
+    pub chunks: ChunkReport,
+    /// Network activity
+    pub packets: PacketReport,
+}
+
+impl ProfileReport {
+    /// Build the report of a closed window
+    fn new(session: &Session, elapsed: Duration, counters: &ProfileCounters) -> Self {
+        let secs = elapsed.as_secs_f64();
+        let per_second = |count: u64| if secs > 0.0 { count as f64 / secs } else { 0.0 };
+        let percent_of = |part: Duration, whole: Duration| {
+            if whole.is_zero() {
+                0.0
+            } else {
+                part.as_secs_f64() / whole.as_secs_f64() * 100.0
+            }
+        };
+
+        let stages = TickStage::ALL
+            .iter()
+            .zip(&session.stages)
+            .map(|(stage, timing)| StageReport {
+                stage: stage.name(),
+                total_ms: millis(timing.total),
+                average_ms: millis(timing.average()),
+                max_ms: millis(timing.max),
+                percent: percent_of(timing.total, session.ticks.total),
+            })
+            .collect();
+
+        let mut threads: Vec<ThreadReport> = counters
+            .thread_cpu
+            .iter()
+            .map(|(name, cpu)| {
+                let before = session.baseline.thread_cpu.get(name).copied();
+                let used = cpu.saturating_sub(before.unwrap_or_default());
+                ThreadReport {
+                    name: name.clone(),
+                    cpu_ms: millis(used),
+                    percent: percent_of(used, elapsed),
+                }
+            })
+            .filter(|thread| thread.cpu_ms > 0.0)
+            .collect();
+        threads.sort_by(|a, b| b.cpu_ms.total_cmp(&a.cpu_ms));
+
+        let process_cpu_percent = match (session.baseline.process_cpu, counters.process_cpu) {
+            (Some(before), Some(after)) => Some(percent_of(after.saturating_sub(before), elapsed)),
+            _ => None,
+        };
+
+        let ticks = session.ticks.count;
+        let packets = counters.packets.since(&session.baseline.packets);
+        Self {
+            started: session
+                .started_at
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_default(),
+            duration_secs: secs,
+            ticks,
+            tps: per_second(ticks),
+            mspt_average: millis(session.ticks.average()),
+            mspt_max: millis(session.ticks.max),
+            stages,
+            process_cpu_percent,
+            threads,
+            chunks: ChunkReport {
+                generated: counters
+                    .chunk_loads
+                    .generated
+                    .saturating_sub(session.baseline.chunk_loads.generated),
+                read: counters
+                    .chunk_loads
+                    .read
+                    .saturating_sub(session.baseline.chunk_loads.read),
+                deferred: counters
+                    .chunk_loads
+                    .deferred
+                    .saturating_sub(session.baseline.chunk_loads.deferred),
+                evicted: counters
+                    .chunk_loads
+                    .evicted
+                    .saturating_sub(session.baseline.chunk_loads.evicted),
+                loaded_min: if session.chunk_samples == 0 {
+                    0
+                } else {
+                    session.min_chunks
+                },
+                loaded_average: if session.chunk_samples == 0 {
+                    0.0
+                } else {
+                    session.total_chunks as f64 / session.chunk_samples as f64
+                },
+                loaded_max: session.max_chunks,
+            },
+            packets: PacketReport {
+                received: packets.received,
+                sent: packets.sent,
+                bytes_received: packets.bytes_received,
+                bytes_sent: packets.bytes_sent,
+                received_per_second: per_second(packets.received),
+                sent_per_second: per_second(packets.sent),
+            },
+        }
+    }
+
+    /// The stage that took the largest share of tick time, if any ran
+    pub fn slowest_stage(&self) -> Option<&StageReport> {
+        self.stages
+            .iter()
+            .filter(|stage| stage.total_ms > 0.0)
+            .max_by(|a, b| a.total_ms.total_cmp(&b.total_ms))
+    }
+
+    /// Short summary for the player or console that stopped the profiler
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Profiled {:.1}s over {} ticks: {:.2} TPS, MSPT {:.2} avg / {:.2} max",
+            self.duration_secs, self.ticks, self.tps, self.mspt_average, self.mspt_max
+        )];
+        if let Some(stage) = self.slowest_stage() {
+            lines.push(format!(
+                "Slowest stage: {} ({:.1}% of tick time)",
+                stage.stage, stage.percent
+            ));
+        }
+        lines.push(format!(
+            "Packets: {:.1}/s in, {:.1}/s out; chunks generated: {}",
+            self.packets.received_per_second, self.packets.sent_per_second, self.chunks.generated
+        ));
+        lines
+    }
+
+    /// Render the report as text
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "---- Obsidium Profile ----");
+        let _ = writeln!(text, "Started: {}", self.started);
+        let _ = writeln!(text, "Duration: {:.2}s", self.duration_secs);
+        let _ = writeln!(text, "Ticks: {} ({:.2} TPS)", self.ticks, self.tps);
+        let _ = writeln!(
+            text,
+            "MSPT (avg/max): {:.2}/{:.2}",
+            self.mspt_average, self.mspt_max
+        );
+        let _ = writeln!(text);
+
+        let _ = writeln!(text, "-- Tick Stages --");
+        for stage in &self.stages {
+            let _ = writeln!(
+                text,
+                "{}: {:.2}ms total, {:.3}ms avg, {:.2}ms max ({:.1}%)",
+                stage.stage, stage.total_ms, stage.average_ms, stage.max_ms, stage.percent
+            );
+        }
+        let _ = writeln!(text);
+
+        let _ = writeln!(text, "-- CPU --");
+        match self.process_cpu_percent {
+            Some(percent) => {
+                let _ = writeln!(text, "Process: {:.1}% of one core", percent);
+            }
+            None => {
+                let _ = writeln!(text, "Process: not available on this platform");
+            }
+        }
+        for thread in &self.threads {
+            let _ = writeln!(
+                text,
+                "{}: {:.0}ms ({:.1}%)",
+                thread.name, thread.cpu_ms, thread.percent
+            );
+        }
+        let _ = writeln!(text);
+
+        let chunks = &self.chunks;
+        let _ = writeln!(text, "-- Chunks --");
+        let _ = writeln!(text, "Generated: {}", chunks.generated);
+        let _ = writeln!(text, "Read from storage: {}", chunks.read);
+        let _ = writeln!(text, "Loads deferred by budget: {}", chunks.deferred);
+        let _ = writeln!(text, "Evicted: {}", chunks.evicted);
+        let _ = writeln!(
+            text,
+            "Loaded (min/avg/max): {}/{:.1}/{}",
+            chunks.loaded_min, chunks.loaded_average, chunks.loaded_max
+        );
+        let _ = writeln!(text);
+
+        let packets = &self.packets;
+        let _ = writeln!(text, "-- Packets --");
+        let _ = writeln!(
+            text,
+            "Received: {} ({:.1}/s, {} bytes)",
+            packets.received, packets.received_per_second, packets.bytes_received
+        );
+        let _ = writeln!(
+            text,
+            "Sent: {} ({:.1}/s, {} bytes)",
+            packets.sent, packets.sent_per_second, packets.bytes_sent
+        );
+        text
+    }
+
+    /// Render the report as JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Write the report to the profile directory
+    pub fn save(&self, format: ReportFormat) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(PROFILE_DIR)?;
+        let timestamp = time::OffsetDateTime::now_utc()
+            .format(time::macros::format_description!(
+                "[year]-[month]-[day]_[hour].[minute].[second]"
+            ))
+            .unwrap_or_default();
+        let path = PathBuf::from(PROFILE_DIR).join(format!(
+            "profile-{}.{}",
+            timestamp,
+            format.extension()
+        ));
+        let contents = match format {
+            ReportFormat::Text => self.to_text(),
+            ReportFormat::Json => self.to_json(),
+        };
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+/// A duration in fractional milliseconds
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// CPU time used by the process so far
+#[cfg(unix)]
+fn process_cpu_time() -> Option<Duration> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: getrusage only writes to the struct it is given.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    // SAFETY: getrusage succeeded, so the struct is initialized.
+    let usage = unsafe { usage.assume_init() };
+    let time = |t: libc::timeval| {
+        Duration::from_secs(t.tv_sec as u64) + Duration::from_micros(t.tv_usec as u64)
+    };
+    Some(time(usage.ru_utime) + time(usage.ru_stime))
+}
+
+/// CPU time used by the process so far
+#[cfg(not(unix))]
+fn process_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// CPU time used so far by the live threads of the process, by thread name
+#[cfg(target_os = "linux")]
+fn thread_cpu_times() -> HashMap<String, Duration> {
+    // SAFETY: sysconf has no preconditions.
+    let ticks_per_second = match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => return HashMap::new(),
+    };
+
+    let mut times = HashMap::new();
+    let Ok(tasks) = std::fs::read_dir("/proc/self/task") else {
+        return times;
+    };
+    for task in tasks.flatten() {
+        let path = task.path();
+        let Ok(stat) = std::fs::read_to_string(path.join("stat")) else {
+            continue;
+        };
+        let name = std::fs::read_to_string(path.join("comm")).unwrap_or_default();
+        // The name in `stat` may contain spaces, so split after it; user and
+        // system time are the 12th and 13th fields from there
+        let Some((_, fields)) = stat.rsplit_once(')') else {
+            continue;
+        };
+        let mut fields = fields.split_whitespace().skip(11);
+        let (Some(user), Some(system)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let ticks = user.parse::<u64>().unwrap_or(0) + system.parse::<u64>().unwrap_or(0);
+        let cpu = Duration::from_secs_f64(ticks as f64 / ticks_per_second as f64);
+        *times.entry(name.trim().to_string()).or_default() += cpu;
+    }
+    times
+}
+
+/// CPU time used so far by the live threads of the process, by thread name
+#[cfg(not(target_os = "linux"))]
+fn thread_cpu_times() -> HashMap<String, Duration> {
+    HashMap::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn test_profile_report() {
+        let clock = ManualClock::new();
+        let profiler = Profiler::new().with_clock(clock.shared());
+        assert!(profiler.stop(ProfileCounters::default()).is_none());
+
+        let baseline = ProfileCounters {
+            packets: PacketCounts {
+                received: 10,
+                sent: 20,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let window = Some(Duration::from_secs(2));
+        assert!(profiler.start(window, ReportFormat::Json, baseline));
+        assert!(!profiler.start(None, ReportFormat::Text, ProfileCounters::default()));
+
+        for chunks in [100, 110, 120, 130] {
+            profiler.record_stage(TickStage::WorldUpdate, Duration::from_millis(3));
+            profiler.record_stage(TickStage::Broadcast, Duration::from_millis(1));
+            profiler.record_loaded_chunks(chunks);
+            profiler.record_tick(Duration::from_millis(4));
+        }
+        assert!(!profiler.is_due());
+        clock.advance(Duration::from_secs(2));
+        assert!(profiler.is_due());
+
+        let counters = ProfileCounters {
+            packets: PacketCounts {
+                received: 30,
+                sent: 60,
+                ..Default::default()
+            },
+            chunk_loads: ChunkLoadCounts {
+                read: 0,
+                generated: 5,
+                deferred: 2,
+                evicted: 0,
+            },
+            ..Default::default()
+        };
+        let (report, format) = profiler.stop(counters).unwrap();
+        assert_eq!(format, ReportFormat::Json);
+        assert!(!profiler.is_running());
+
+        assert_eq!(report.ticks, 4);
+        assert!((report.tps - 2.0).abs() < 1e-9);
+        assert!((report.mspt_average - 4.0).abs() < 1e-9);
+        let slowest = report.slowest_stage().unwrap();
+        assert_eq!(slowest.stage, TickStage::WorldUpdate.name());
+        assert!((slowest.percent - 75.0).abs() < 1e-9);
+        assert_eq!(report.chunks.generated, 5);
+        assert_eq!(report.chunks.deferred, 2);
+        assert_eq!(
+            (report.chunks.loaded_min, report.chunks.loaded_max),
+            (100, 130)
+        );
+        assert!((report.chunks.loaded_average - 115.0).abs() < 1e-9);
+        assert_eq!((report.packets.received, report.packets.sent), (20, 40));
+        assert!((report.packets.sent_per_second - 20.0).abs() < 1e-9);
+
+        assert!(report.to_text().contains("world update: 12.00ms total"));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["stages"][1]["stage"], "world update");
+        assert_eq!(json["chunks"]["loaded_max"], 130);
+    }
+}
@@ -0,0 +1,705 @@
+//! Player profile lookup
+//!
+//! [`ProfileResolver`] finds players by name whether or not they have ever
+//! joined, for commands such as `/whitelist add` and `/ban`, and fetches
+//! the skins of joining players. Names are answered from the
+//! [user cache](UserCache) when possible. Otherwise an online-mode server
+//! asks the Mojang API, while an offline-mode server derives the UUID from
+//! the name as vanilla does.
+//!
+//! Online-mode logins are authenticated here too: once the client and
+//! server share a secret, [`ProfileResolver::has_joined`] asks the session
+//! server whether the player announced joining with the matching
+//! [server hash](server_hash).
+//!
+//! Where the Mojang API is reached is set by `profile-api` in the
+//! `[network]` section of obsidium.toml: `mojang` to connect to
+//! api.mojang.com and sessionserver.mojang.com over TLS, or the `http://` or
+//! `https://` base URL of a proxy that forwards `/users/profiles/minecraft/`
+//! and `/session/minecraft/` to them. An online-mode server uses `mojang`
+//! when none is set; an offline one without it only finds cached players,
+//! and players join without skins. Profile lookups are kept within the rate
+//! Mojang allows.
+
+use crate::clock::{self, SharedClock};
+use crate::error::{Result, ServerError};
+use crate::protocol::packets::login::Property;
+use crate::protocol::types::McUuid;
+use crate::server::usercache::UserCache;
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+/// `profile-api` value that reaches Mojang's servers directly
+pub const MOJANG_API: &str = "mojang";
+
+/// Base URL of Mojang's name lookups
+const MOJANG_PROFILES_URL: &str = "https://api.mojang.com";
+
+/// Base URL of Mojang's session server
+const MOJANG_SESSIONS_URL: &str = "https://sessionserver.mojang.com";
+
+/// Most API requests sent per [`RATE_LIMIT_WINDOW`], as allowed by Mojang
+const RATE_LIMIT: usize = 600;
+
+/// Window [`RATE_LIMIT`] applies to
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(600);
+
+/// How long to wait for the API
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest API response read
+const MAX_RESPONSE_SIZE: u64 = 64 * 1024;
+
+/// A player's UUID, name and profile properties such as their skin
+#[derive(Debug, Clone)]
+pub struct GameProfile {
+    /// Player UUID
+    pub uuid: McUuid,
+    /// Player name
+    pub name: String,
+    /// Signed properties, such as `textures`
+    pub properties: Vec<Property>,
+}
+
+/// Where name lookups and session server requests are sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileApi {
+    /// Base URL of name lookups
+    profiles: ApiEndpoint,
+    /// Base URL of the session server
+    sessions: ApiEndpoint,
+}
+
+impl ProfileApi {
+    /// Parse a `profile-api` setting: [`MOJANG_API`], or the base URL of a
+    /// proxy serving both name lookups and the session server
+    pub fn parse(value: &str) -> Option<Self> {
+        if value.trim().eq_ignore_ascii_case(MOJANG_API) {
+            return Some(Self {
+                profiles: ApiEndpoint::parse(MOJANG_PROFILES_URL)?,
+                sessions: ApiEndpoint::parse(MOJANG_SESSIONS_URL)?,
+            });
+        }
+        let endpoint = ApiEndpoint::parse(value)?;
+        Some(Self {
+            profiles: endpoint.clone(),
+            sessions: endpoint,
+        })
+    }
+}
+
+/// An HTTP or HTTPS base URL the Mojang API is reached through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiEndpoint {
+    /// Whether requests are sent over TLS
+    tls: bool,
+    /// Host name or address of the server
+    host: String,
+    /// Port of the server
+    port: u16,
+    /// Path prefixed to every request, without a trailing slash
+    prefix: String,
+}
+
+impl ApiEndpoint {
+    /// Parse an `http://` or `https://host[:port][/prefix]` URL
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+        let (tls, rest, default_port) = match url.strip_prefix("https://") {
+            Some(rest) => (true, rest, 443),
+            None => (false, url.strip_prefix("http://")?, 80),
+        };
+        let (authority, prefix) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (authority, default_port),
+        };
+        if host.is_empty() {
+            return None;
+        }
+        Some(Self {
+            tls,
+            host: host.to_string(),
+            port,
+            prefix: prefix.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Send a GET request, returning the JSON body or `None` if nothing was found
+    async fn get(&self, path: &str) -> Result<Option<serde_json::Value>> {
+        // HTTP/1.0 keeps the response from being chunked
+        let request = format!(
+            "GET {}{} HTTP/1.0\r\nHost: {}:{}\r\nAccept: application/json\r\n\r\n",
+            self.prefix, path, self.host, self.port
+        );
+        let request = async {
+            let stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+            if !self.tls {
+                return exchange(stream, &request).await;
+            }
+            let name = ServerName::try_from(self.host.clone())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            let stream = tls_connector().connect(name, stream).await?;
+            exchange(stream, &request).await
+        };
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, request)
+            .await
+            .map_err(|_| lookup_error("the profile API timed out"))??;
+
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+        let status = head.split_whitespace().nth(1).unwrap_or_default();
+        match status {
+            "204" | "404" => Ok(None),
+            "429" => Err(lookup_error("the profile API is rate limited")),
+            status if status.starts_with('2') => serde_json::from_str(body)
+                .map(Some)
+                .map_err(|e| lookup_error(&format!("invalid profile API response: {}", e))),
+            _ => Err(lookup_error(&format!(
+                "the profile API replied {:?}",
+                head.lines().next().unwrap_or_default()
+            ))),
+        }
+    }
+}
+
+/// Send a request and read the whole response
+async fn exchange<S>(mut stream: S, request: &str) -> std::io::Result<Vec<u8>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = Vec::new();
+    match (&mut stream)
+        .take(MAX_RESPONSE_SIZE)
+        .read_to_end(&mut response)
+        .await
+    {
+        // Many servers close TLS connections without a close_notify
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof && !response.is_empty() => {}
+        result => {
+            result?;
+        }
+    }
+    Ok(response)
+}
+
+/// TLS client trusting the Mozilla root certificates, built once
+fn tls_connector() -> TlsConnector {
+    static CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+    let config = CONFIG.get_or_init(|| {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        Arc::new(
+            ClientConfig::builder()
+                .with_root_certificates(roots)
+                .with_no_client_auth(),
+        )
+    });
+    TlsConnector::from(Arc::clone(config))
+}
+
+/// Profile as returned by the Mojang API
+#[derive(Debug, Deserialize)]
+struct ApiProfile {
+    /// UUID without dashes
+    id: String,
+    /// Player name
+    name: String,
+    /// Profile properties, only returned by the session server
+    #[serde(default)]
+    properties: Vec<ApiProperty>,
+}
+
+/// Profile property as returned by the session server
+#[derive(Debug, Deserialize)]
+struct ApiProperty {
+    name: String,
+    value: String,
+    signature: Option<String>,
+}
+
+impl ApiProfile {
+    /// Convert to a profile, failing on a malformed UUID
+    fn into_profile(self) -> Result<GameProfile> {
+        let uuid = McUuid::parse_str(&self.id)
+            .map_err(|e| lookup_error(&format!("invalid UUID {}: {}", self.id, e)))?;
+        Ok(GameProfile {
+            uuid,
+            name: self.name,
+            properties: self
+                .properties
+                .into_iter()
+                .map(|property| Property {
+                    name: property.name.into(),
+                    value: property.value.into(),
+                    signature: property.signature.map(Into::into),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Looks up player profiles, caching what it finds
+#[derive(Debug)]
+pub struct ProfileResolver {
+    /// Names and UUIDs of known players
+    cache: UserCache,
+    /// Where the Mojang API is reached, if anywhere
+    api: Option<ProfileApi>,
+    /// Whether players are authenticated, so their UUIDs are Mojang's
+    online_mode: bool,
+    /// Source of the current time for rate limiting
+    clock: SharedClock,
+    /// When recent API requests were sent, oldest first
+    requests: Mutex<VecDeque<Instant>>,
+    /// Skin properties by lowercase name, including players without one
+    skins: Mutex<HashMap<String, Vec<Property>>>,
+}
+
+impl ProfileResolver {
+    /// Create a resolver backed by a user cache
+    pub fn new(cache: UserCache, api: Option<ProfileApi>, online_mode: bool) -> Self {
+        Self {
+            cache,
+            api,
+            online_mode,
+            clock: clock::system(),
+            requests: Mutex::new(VecDeque::new()),
+            skins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Measure the rate limit with the given clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Names and UUIDs of known players
+    pub fn cache(&self) -> &UserCache {
+        &self.cache
+    }
+
+    /// Remember a player who joined
+    pub fn remember(&self, uuid: McUuid, name: &str) {
+        if let Err(e) = self.cache.add(uuid, name) {
+            tracing::warn!("Failed to save the user cache: {}", e);
+        }
+    }
+
+    /// Find a player's UUID and correctly cased name
+    ///
+    /// Returns `None` if no player has that name. The profile carries no
+    /// properties.
+    pub async fn find_by_name(&self, name: &str) -> Result<Option<GameProfile>> {
+        if let Some(entry) = self.cache.get_by_name(name) {
+            return Ok(Some(GameProfile {
+                uuid: entry.uuid,
+                name: entry.name,
+                properties: Vec::new(),
+            }));
+        }
+        if !self.online_mode {
+            return Ok(Some(GameProfile {
+                uuid: offline_uuid(name),
+                name: name.to_string(),
+                properties: Vec::new(),
+            }));
+        }
+
+        let profile = self.lookup_name(name).await?;
+        if let Some(ref profile) = profile {
+            self.remember(profile.uuid, &profile.name);
+        }
+        Ok(profile)
+    }
+
+    /// Skin properties of a joining player, empty if they have none or
+    /// the API is unavailable
+    ///
+    /// Offline-mode UUIDs are not Mojang's, so the player is looked up by
+    /// name there.
+    pub async fn skin(&self, uuid: McUuid, name: &str) -> Vec<Property> {
+        let key = name.to_ascii_lowercase();
+        if let Some(skin) = self.lock_skins().get(&key) {
+            return skin.clone();
+        }
+        if self.api.is_none() {
+            return Vec::new();
+        }
+
+        let skin = async {
+            let uuid = if self.online_mode {
+                Some(uuid)
+            } else {
+                self.lookup_name(name).await?.map(|profile| profile.uuid)
+            };
+            match uuid {
+                Some(uuid) => self.lookup_profile(uuid).await,
+                None => Ok(None),
+            }
+        };
+        match skin.await {
+            Ok(profile) => {
+                let skin = profile.map_or_else(Vec::new, |profile| profile.properties);
+                self.lock_skins().insert(key, skin.clone());
+                skin
+            }
+            Err(e) => {
+                tracing::warn!("Failed to look up the skin of {}: {}", name, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Ask the session server whether a player announced joining with a
+    /// server hash, returning their profile with its signed properties
+    ///
+    /// Returns `None` if they did not, so the player is not who they claim
+    /// to be. Fails if the session server cannot be reached, including when
+    /// no API is configured. Logins are not counted against the profile
+    /// lookup rate limit.
+    pub async fn has_joined(&self, name: &str, server_hash: &str) -> Result<Option<GameProfile>> {
+        let Some(ref api) = self.api else {
+            return Err(lookup_error(
+                "no profile-api is set to reach the session server",
+            ));
+        };
+        let path = format!(
+            "/session/minecraft/hasJoined?username={}&serverId={}",
+            query_escape(name),
+            query_escape(server_hash)
+        );
+        let Some(json) = api.sessions.get(&path).await? else {
+            return Ok(None);
+        };
+        let profile = serde_json::from_value::<ApiProfile>(json)
+            .map_err(|e| lookup_error(&format!("invalid profile: {}", e)))?
+            .into_profile()?;
+        self.remember(profile.uuid, &profile.name);
+        self.lock_skins().insert(
+            profile.name.to_ascii_lowercase(),
+            profile.properties.clone(),
+        );
+        Ok(Some(profile))
+    }
+
+    /// Look up a name with the API
+    async fn lookup_name(&self, name: &str) -> Result<Option<GameProfile>> {
+        let valid = !name.is_empty()
+            && name.len() <= 16
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Ok(None);
+        }
+        self.request(false, &format!("/users/profiles/minecraft/{}", name))
+            .await
+    }
+
+    /// Look up a profile with its properties with the API
+    async fn lookup_profile(&self, uuid: McUuid) -> Result<Option<GameProfile>> {
+        self.request(
+            true,
+            &format!(
+                "/session/minecraft/profile/{}?unsigned=false",
+                uuid.simple()
+            ),
+        )
+        .await
+    }
+
+    /// Send an API request for a profile, within the rate limit, to the
+    /// session server or the name lookup API
+    async fn request(&self, session: bool, path: &str) -> Result<Option<GameProfile>> {
+        let Some(ref api) = self.api else {
+            return Ok(None);
+        };
+        let api = if session {
+            &api.sessions
+        } else {
+            &api.profiles
+        };
+        if !self.try_reserve_request() {
+            return Err(lookup_error("too many profile lookups, try again later"));
+        }
+        match api.get(path).await? {
+            Some(json) => serde_json::from_value::<ApiProfile>(json)
+                .map_err(|e| lookup_error(&format!("invalid profile: {}", e)))?
+                .into_profile()
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Count a request against the rate limit, returning whether it may be sent
+    fn try_reserve_request(&self) -> bool {
+        let now = self.clock.now();
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        while requests
+            .front()
+            .is_some_and(|sent| now.saturating_duration_since(*sent) >= RATE_LIMIT_WINDOW)
+        {
+            requests.pop_front();
+        }
+        if requests.len() >= RATE_LIMIT {
+            return false;
+        }
+        requests.push_back(now);
+        true
+    }
+
+    fn lock_skins(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<Property>>> {
+        self.skins.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// UUID an offline-mode server gives a player, as vanilla does
+///
+/// This is the name-based (version 3) UUID of `OfflinePlayer:<name>`.
+pub fn offline_uuid(name: &str) -> McUuid {
+    name_uuid(format!("OfflinePlayer:{}", name).as_bytes())
+}
+
+/// Name-based (version 3) UUID of some bytes, like Java's `UUID.nameUUIDFromBytes`
+pub fn name_uuid(name: &[u8]) -> McUuid {
+    let mut bytes = md5(name);
+    bytes[6] = (bytes[6] & 0x0F) | 0x30;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+    McUuid::from_bytes(bytes)
+}
+
+/// MD5 digest of some bytes, needed only for name-based UUIDs
+fn md5(input: &[u8]) -> [u8; 16] {
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5,
+        9, 14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10,
+        15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    let constants: Vec<u32> = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32)
+        .collect();
+
+    let mut message = input.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((input.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476];
+    for chunk in message.chunks_exact(64) {
+        let words: Vec<u32> = chunk
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(constants[i])
+                .wrapping_add(words[g])
+                .rotate_left(SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        for (value, added) in state.iter_mut().zip([a, b, c, d]) {
+            *value = value.wrapping_add(added);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_le_bytes());
+    }
+    digest
+}
+
+/// Hash identifying a login to the session server, as vanilla computes it
+///
+/// This is the SHA-1 of the server ID, the shared secret and the DER
+/// encoded public key, printed as a signed hexadecimal number the way
+/// Java's `BigInteger` does.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut digest: [u8; 20] = Sha1::new()
+        .chain_update(server_id)
+        .chain_update(shared_secret)
+        .chain_update(public_key)
+        .finalize()
+        .into();
+
+    // A set top bit makes the number negative: print its two's complement
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            let (sum, overflow) = (!*byte).overflowing_add(u8::from(carry));
+            *byte = sum;
+            carry = overflow;
+        }
+    }
+    let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    let hex = hex.trim_start_matches('0');
+    match (negative, hex.is_empty()) {
+        (_, true) => "0".to_string(),
+        (true, false) => format!("-{}", hex),
+        (false, false) => hex.to_string(),
+    }
+}
+
+/// Percent-encode a query parameter value
+fn query_escape(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Error for a failed profile lookup
+fn lookup_error(message: &str) -> ServerError {
+    ServerError::Io(std::io::Error::other(message.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_offline_uuid() {
+        let hex =
+            |bytes: [u8; 16]| -> String { bytes.iter().map(|b| format!("{b:02x}")).collect() };
+        assert_eq!(hex(md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(md5(b"abc")), "900150983cd24fb0d6963f7d28e17f72");
+
+        let uuid = offline_uuid("Steve");
+        assert_eq!(uuid.get_version_num(), 3);
+        assert_eq!(uuid, offline_uuid("Steve"));
+        assert_ne!(uuid, offline_uuid("steve"));
+    }
+
+    #[test]
+    fn test_server_hash() {
+        // Digests of plain names, as published with the protocol
+        assert_eq!(
+            server_hash("Notch", &[], &[]),
+            "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+        );
+        assert_eq!(
+            server_hash("jeb_", &[], &[]),
+            "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"
+        );
+        assert_eq!(
+            server_hash("simon", &[], &[]),
+            "88e16a1019277b15d58faf0541e11910eb756f6"
+        );
+        assert_eq!(query_escape("a b&c_1"), "a%20b%26c_1");
+    }
+
+    #[tokio::test]
+    async fn test_lookup_and_cache() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let uuid = McUuid::new_v4();
+        let server = tokio::spawn(async move {
+            // Answer one name lookup, then one profile lookup
+            for body in [
+                format!(r#"{{"id":"{}","name":"Steve"}}"#, uuid.simple()),
+                format!(
+                    r#"{{"id":"{}","name":"Steve","properties":[{{"name":"textures","value":"e30=","signature":"sig"}}]}}"#,
+                    uuid.simple()
+                ),
+            ] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await.unwrap();
+                let response = format!("HTTP/1.0 200 OK\r\n\r\n{}", body);
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let api = ProfileApi::parse(&format!("http://127.0.0.1:{}/", port));
+        let resolver = ProfileResolver::new(UserCache::new(), api, true);
+        let profile = resolver.find_by_name("steve").await.unwrap().unwrap();
+        assert_eq!((profile.uuid, profile.name.as_str()), (uuid, "Steve"));
+        // Answered from the cache this time
+        assert_eq!(
+            resolver.find_by_name("STEVE").await.unwrap().unwrap().uuid,
+            uuid
+        );
+
+        let skin = resolver.skin(uuid, "Steve").await;
+        assert_eq!(skin[0].name.0, "textures");
+        assert_eq!(resolver.skin(uuid, "Steve").await.len(), 1);
+        server.await.unwrap();
+
+        let offline = ProfileResolver::new(UserCache::new(), None, false);
+        let profile = offline.find_by_name("Alex").await.unwrap().unwrap();
+        assert_eq!(profile.uuid, offline_uuid("Alex"));
+        assert!(offline.skin(profile.uuid, "Alex").await.is_empty());
+    }
+
+    #[test]
+    fn test_parse_profile_api() {
+        let proxy = ProfileApi::parse("http://proxy:8080/mojang/").unwrap();
+        assert_eq!(proxy.profiles, proxy.sessions);
+        assert_eq!(
+            (proxy.profiles.port, proxy.profiles.prefix.as_str()),
+            (8080, "/mojang")
+        );
+        assert!(!proxy.profiles.tls);
+
+        let mojang = ProfileApi::parse("Mojang").unwrap();
+        assert!(mojang.profiles.tls && mojang.sessions.tls);
+        assert_eq!(mojang.profiles.host, "api.mojang.com");
+        assert_eq!(mojang.sessions.host, "sessionserver.mojang.com");
+        assert_eq!(mojang.sessions.port, 443);
+
+        assert!(ApiEndpoint::parse("https://proxy").unwrap().tls);
+        assert!(ProfileApi::parse("ftp://proxy").is_none());
+        assert!(ProfileApi::parse("https://:443").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_https_requests_use_tls() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut record_type = [0; 1];
+            stream.read_exact(&mut record_type).await.unwrap();
+            record_type[0]
+        });
+
+        // A plain server cannot complete the handshake, but it sees one
+        let api = ApiEndpoint::parse(&format!("https://localhost:{}", port)).unwrap();
+        assert!(api.get("/").await.is_err());
+        // 0x16 starts a TLS handshake record
+        assert_eq!(server.await.unwrap(), 0x16);
+    }
+}
@@ -0,0 +1,75 @@
+//! Automatic reloading of server.properties and obsidium.toml
+//!
+//! The configuration files are polled for changes to their modification
+//! times, so edits take effect within a few seconds without running
+//! /reloadconfig.
+
+use crate::config::ReloadReport;
+use crate::server::ServerState;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+/// How often the configuration files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Start watching the configuration files for changes
+///
+/// Returns `None` if the server was not started from a properties file or
+/// hot reloading is disabled. The task ends once a shutdown has been
+/// requested.
+pub fn spawn(state: Arc<ServerState>) -> Option<JoinHandle<()>> {
+    if !state.config.hot_reload {
+        return None;
+    }
+    let properties_path = state.config.properties_path.clone()?;
+    let mut paths = vec![properties_path];
+    paths.extend(state.config.overrides_path.clone());
+
+    Some(tokio::spawn(async move {
+        let mut last_modified: Vec<_> = paths.iter().map(|path| modified(path)).collect();
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut shutdown = state.subscribe_shutdown();
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.changed() => break,
+            }
+
+            let current: Vec<_> = paths.iter().map(|path| modified(path)).collect();
+            // A missing properties file is usually being rewritten
+            if current[0].is_none() || current == last_modified {
+                continue;
+            }
+            last_modified = current;
+
+            match state.reload_config().await {
+                Ok(report) => log_report(&report),
+                Err(e) => tracing::warn!("Failed to reload the configuration: {}", e),
+            }
+        }
+    }))
+}
+
+/// Modification time of a file, if it can be read
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Log what an automatic reload changed
+fn log_report(report: &ReloadReport) {
+    if !report.applied.is_empty() {
+        tracing::info!(
+            "Reloaded the configuration, applied: {}",
+            report.applied.join(", ")
+        );
+    }
+    if !report.requires_restart.is_empty() {
+        tracing::warn!(
+            "Changes to {} require a restart to take effect",
+            report.requires_restart.join(", ")
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Server resource pack
+//!
+//! The pack offered to players during configuration is set with the
+//! `resource-pack` keys of server.properties. Instead of hosting the zip
+//! elsewhere, operators can set `file` in the `[resource-pack]` section of
+//! obsidium.toml: the server then serves the pack over plain HTTP on its own
+//! port, and fills in its URL and SHA-1 automatically.
+
+use crate::error::{Result, ServerError};
+use crate::network::http::{self, Request};
+use crate::protocol::packets::configuration::AddResourcePackPacket;
+use crate::protocol::types::{JsonTextComponent, McString, McUuid};
+use crate::server::ServerState;
+use crate::server::profiles::name_uuid;
+use sha1::{Digest, Sha1};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::BufReader;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Port the built-in host listens on unless configured
+pub const DEFAULT_HOST_PORT: u16 = 25566;
+
+/// How long a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A resource pack offered to players
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourcePack {
+    /// Pack ID, which clients use to cache the pack
+    pub id: McUuid,
+    /// URL the pack is downloaded from
+    pub url: String,
+    /// Lowercase hex SHA-1 of the pack, or empty to skip the check
+    pub hash: String,
+}
+
+impl ResourcePack {
+    /// Create a pack whose ID is derived from its URL, as vanilla does
+    pub fn new(url: &str, hash: &str) -> Self {
+        Self {
+            id: name_uuid(url.as_bytes()),
+            url: url.to_string(),
+            hash: hash.to_ascii_lowercase(),
+        }
+    }
+
+    /// Use a fixed pack ID
+    pub fn with_id(mut self, id: McUuid) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// The packet offering this pack to a client
+    pub fn packet(
+        &self,
+        required: bool,
+        prompt: Option<JsonTextComponent>,
+    ) -> AddResourcePackPacket {
+        AddResourcePackPacket {
+            uuid: self.id,
+            url: McString(self.url.clone()),
+            hash: McString(self.hash.clone()),
+            forced: required,
+            prompt,
+        }
+    }
+}
+
+/// Built-in resource pack host settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourcePackHostConfig {
+    /// Zip file that is served
+    pub file: PathBuf,
+    /// Address the host listens on
+    pub bind_address: SocketAddr,
+    /// Host name or address players download the pack from
+    pub public_host: String,
+}
+
+/// A pack served by the built-in host
+#[derive(Debug)]
+pub struct HostedPack {
+    /// The pack as offered to players
+    pack: ResourcePack,
+    /// Contents of the zip file
+    data: Vec<u8>,
+    /// Address the host listens on
+    bind_address: SocketAddr,
+}
+
+impl HostedPack {
+    /// Read and hash the pack file
+    pub fn load(config: &ResourcePackHostConfig) -> Result<Self> {
+        let data = std::fs::read(&config.file).map_err(|e| {
+            ServerError::Configuration(format!(
+                "Failed to read resource pack {}: {}",
+                config.file.display(),
+                e
+            ))
+        })?;
+        let hash: String = Sha1::digest(&data)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        // IPv6 addresses must be bracketed in URLs
+        let host = match config.public_host.parse::<IpAddr>() {
+            Ok(IpAddr::V6(ip)) => format!("[{}]", ip),
+            _ => config.public_host.clone(),
+        };
+        // The hash is part of the URL so clients never reuse an outdated copy
+        let url = format!(
+            "http://{}:{}/{}.zip",
+            host,
+            config.bind_address.port(),
+            hash
+        );
+
+        Ok(Self {
+            pack: ResourcePack::new(&url, &hash),
+            data,
+            bind_address: config.bind_address,
+        })
+    }
+
+    /// The pack as offered to players
+    pub fn pack(&self) -> &ResourcePack {
+        &self.pack
+    }
+
+    /// Name of the file in request paths
+    fn file_name(&self) -> String {
+        format!("{}.zip", self.pack.hash)
+    }
+}
+
+/// Start the built-in host if a pack is hosted
+///
+/// Returns `None` if no pack is hosted. The task ends once a shutdown has
+/// been requested.
+pub fn spawn(state: Arc<ServerState>) -> Option<JoinHandle<()>> {
+    let pack = state.hosted_pack.clone()?;
+    let shutdown = state.subscribe_shutdown();
+
+    Some(tokio::spawn(async move {
+        let listener = match TcpListener::bind(pack.bind_address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to bind the resource pack host to {}: {}",
+                    pack.bind_address,
+                    e
+                );
+                return;
+            }
+        };
+        tracing::info!(
+            "Hosting resource pack {} ({} bytes) on {}",
+            pack.pack.hash,
+            pack.data.len(),
+            pack.bind_address
+        );
+        serve(listener, pack, shutdown).await;
+    }))
+}
+
+/// Serve the pack until a shutdown is requested
+async fn serve(listener: TcpListener, pack: Arc<HostedPack>, mut shutdown: watch::Receiver<bool>) {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, addr)) => {
+                    let pack = Arc::clone(&pack);
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_client(stream, &pack).await {
+                            tracing::debug!("Resource pack download by {} failed: {}", addr, e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept resource pack client: {}", e),
+            },
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+/// Answer a single download request
+async fn handle_client(stream: TcpStream, pack: &HostedPack) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let request = match tokio::time::timeout(REQUEST_TIMEOUT, Request::read(&mut reader)).await {
+        Ok(Ok(request)) => request,
+        Ok(Err(_)) => return http::write_response(&mut writer, 400, None, &[]).await,
+        Err(_) => return Ok(()),
+    };
+
+    if request.method != "GET" {
+        return http::write_response(&mut writer, 405, None, &[]).await;
+    }
+    if request.segments != [pack.file_name()] {
+        return http::write_response(&mut writer, 404, None, &[]).await;
+    }
+    http::write_response(&mut writer, 200, Some("application/zip"), &pack.data).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn get(addr: SocketAddr, path: &str) -> Vec<u8> {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_host_pack() {
+        let file = std::env::temp_dir().join(format!("obsidium-pack-{}.zip", McUuid::new_v4()));
+        std::fs::write(&file, b"abc").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let pack = HostedPack::load(&ResourcePackHostConfig {
+            file: file.clone(),
+            bind_address: addr,
+            public_host: "mc.example.com".to_string(),
+        })
+        .unwrap();
+        let _ = std::fs::remove_file(file);
+
+        let hash = "a9993e364706816aba3e25717850c26c9cd0d89d";
+        assert_eq!(pack.pack().hash, hash);
+        assert_eq!(
+            pack.pack().url,
+            format!("http://mc.example.com:{}/{}.zip", addr.port(), hash)
+        );
+        assert_eq!(pack.pack().id, ResourcePack::new(&pack.pack().url, hash).id);
+
+        let (_stop, shutdown) = watch::channel(false);
+        tokio::spawn(serve(listener, Arc::new(pack), shutdown));
+
+        let response = get(addr, &format!("/{}.zip", hash)).await;
+        assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(b"\r\n\r\nabc"));
+        let response = get(addr, "/outdated.zip").await;
+        assert!(response.starts_with(b"HTTP/1.1 404 Not Found\r\n"));
+    }
+}
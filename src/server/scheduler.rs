@@ -0,0 +1,377 @@
+//! Task scheduler
+//!
+//! Lets game code and plugins run work on the main tick loop: once after a
+//! delay, repeatedly with a fixed period, or once an asynchronous operation
+//! has finished. Scheduled tasks run during the tick while the world lock is
+//! held, so they can change the world directly. Delays and periods are
+//! measured in ticks.
+
+use crate::game::world::World;
+use crate::server::ServerState;
+use std::future::Future;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A task run once on the main thread
+type OnceTask = Box<dyn FnOnce(&ServerState, &mut World) + Send>;
+
+/// A task run repeatedly on the main thread
+type RepeatingTask = Box<dyn FnMut(&ServerState, &mut World) + Send>;
+
+/// Identifies a scheduled task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+/// Handle used to cancel a scheduled task
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+    /// ID of the task
+    id: TaskId,
+    /// Set once the task is cancelled
+    cancelled: Arc<AtomicBool>,
+    /// The background part of an asynchronous task
+    abort: Option<tokio::task::AbortHandle>,
+}
+
+impl TaskHandle {
+    /// Get the task's ID
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// Cancel the task
+    ///
+    /// A task that has not run yet never runs, and a repeating task does
+    /// not run again. The background part of an asynchronous task is
+    /// aborted at its next await point.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        if let Some(abort) = &self.abort {
+            abort.abort();
+        }
+    }
+
+    /// Whether the task was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Work a queued task performs
+enum Work {
+    /// Run once
+    Once(OnceTask),
+    /// Run every `period` ticks
+    Repeating {
+        /// Ticks between runs
+        period: u64,
+        /// The task
+        task: RepeatingTask,
+    },
+    /// Waiting for an asynchronous operation to finish
+    Waiting,
+}
+
+/// A task in the queue
+struct Entry {
+    /// Handle shared with the scheduling code
+    handle: TaskHandle,
+    /// Plugin that scheduled the task
+    owner: Option<String>,
+    /// Tick the task is due on
+    due: u64,
+    /// What to run
+    work: Work,
+}
+
+/// Queue shared with the background part of asynchronous tasks
+#[derive(Default)]
+struct Queue {
+    /// Number of ticks run so far
+    tick: u64,
+    /// Scheduled tasks in scheduling order
+    entries: Vec<Entry>,
+}
+
+/// Runs tasks on the main tick loop
+#[derive(Default)]
+pub struct Scheduler {
+    /// Scheduled tasks
+    queue: Arc<Mutex<Queue>>,
+    /// Next task ID to hand out
+    next_id: AtomicU64,
+}
+
+impl Scheduler {
+    /// Create a scheduler without tasks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run a task once after `delay` ticks
+    ///
+    /// A delay of 0 runs the task on the next tick.
+    pub fn run_later<F>(&self, delay: u64, task: F) -> TaskHandle
+    where
+        F: FnOnce(&ServerState, &mut World) + Send + 'static,
+    {
+        self.schedule_later(None, delay, task)
+    }
+
+    /// Run a task every `period` ticks, starting after `delay` ticks
+    ///
+    /// A period of 0 is treated as 1.
+    pub fn run_repeating<F>(&self, delay: u64, period: u64, task: F) -> TaskHandle
+    where
+        F: FnMut(&ServerState, &mut World) + Send + 'static,
+    {
+        self.schedule_repeating(None, delay, period, task)
+    }
+
+    /// Run a future in the background and pass its output to `callback` on
+    /// the main thread
+    ///
+    /// The callback runs on the first tick after the future completes. Must
+    /// be called from within the Tokio runtime.
+    pub fn run_async<T, Fut, F>(&self, future: Fut, callback: F) -> TaskHandle
+    where
+        T: Send + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+        F: FnOnce(&ServerState, &mut World, T) + Send + 'static,
+    {
+        self.schedule_async(None, future, callback)
+    }
+
+    /// Cancel all tasks scheduled by a plugin, returning how many there were
+    pub fn cancel_plugin(&self, plugin: &str) -> usize {
+        let mut queue = self.lock();
+        let before = queue.entries.len();
+        queue.entries.retain(|entry| {
+            if entry.owner.as_deref() != Some(plugin) {
+                return true;
+            }
+            entry.handle.cancel();
+            false
+        });
+        before - queue.entries.len()
+    }
+
+    /// Number of tasks waiting to run, including running repeating tasks
+    pub fn pending_count(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Run `delay` ticks from now on behalf of an owner
+    pub(crate) fn schedule_later<F>(&self, owner: Option<&str>, delay: u64, task: F) -> TaskHandle
+    where
+        F: FnOnce(&ServerState, &mut World) + Send + 'static,
+    {
+        self.push(owner, delay, Work::Once(Box::new(task)))
+    }
+
+    /// Run repeatedly on behalf of an owner
+    pub(crate) fn schedule_repeating<F>(
+        &self,
+        owner: Option<&str>,
+        delay: u64,
+        period: u64,
+        task: F,
+    ) -> TaskHandle
+    where
+        F: FnMut(&ServerState, &mut World) + Send + 'static,
+    {
+        let work = Work::Repeating {
+            period: period.max(1),
+            task: Box::new(task),
+        };
+        self.push(owner, delay, work)
+    }
+
+    /// Run a future on behalf of an owner
+    pub(crate) fn schedule_async<T, Fut, F>(
+        &self,
+        owner: Option<&str>,
+        future: Fut,
+        callback: F,
+    ) -> TaskHandle
+    where
+        T: Send + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+        F: FnOnce(&ServerState, &mut World, T) + Send + 'static,
+    {
+        let id = self.next_id();
+        let shared = Arc::clone(&self.queue);
+        // Hold the lock until the entry is queued, so a future that finishes
+        // immediately still finds it
+        let mut queue = self.lock();
+        let background = tokio::spawn(async move {
+            let output = future.await;
+            let mut queue = shared.lock().unwrap_or_else(|e| e.into_inner());
+            let tick = queue.tick;
+            // The entry is gone if the task was cancelled in the meantime
+            if let Some(entry) = queue.entries.iter_mut().find(|e| e.handle.id == id) {
+                entry.due = tick + 1;
+                entry.work =
+                    Work::Once(Box::new(move |state, world| callback(state, world, output)));
+            }
+        });
+
+        let handle = TaskHandle {
+            id,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            abort: Some(background.abort_handle()),
+        };
+        queue.entries.push(Entry {
+            handle: handle.clone(),
+            owner: owner.map(str::to_string),
+            due: u64::MAX,
+            work: Work::Waiting,
+        });
+        handle
+    }
+
+    /// Advance to the next tick and run every task due on it
+    ///
+    /// Tasks may schedule further tasks; those run on a later tick. A
+    /// panicking task is logged and, if it repeats, cancelled.
+    pub fn run_pending(&self, state: &ServerState, world: &mut World) {
+        let (tick, due) = {
+            let mut queue = self.lock();
+            queue.tick += 1;
+            let tick = queue.tick;
+            queue.entries.retain(|entry| !entry.handle.is_cancelled());
+
+            let (due, waiting): (Vec<Entry>, Vec<Entry>) = std::mem::take(&mut queue.entries)
+                .into_iter()
+                .partition(|entry| entry.due <= tick && !matches!(entry.work, Work::Waiting));
+            queue.entries = waiting;
+            (tick, due)
+        };
+
+        let mut repeating = Vec::new();
+        for mut entry in due {
+            // Earlier tasks this tick may have cancelled later ones
+            if entry.handle.is_cancelled() {
+                continue;
+            }
+            match entry.work {
+                Work::Once(task) => {
+                    if catch_unwind(AssertUnwindSafe(|| task(state, world))).is_err() {
+                        tracing::error!("Scheduled task {:?} panicked", entry.handle.id);
+                    }
+                }
+                Work::Repeating {
+                    period,
+                    ref mut task,
+                } => {
+                    if catch_unwind(AssertUnwindSafe(|| task(state, world))).is_err() {
+                        tracing::error!(
+                            "Repeating task {:?} panicked and was cancelled",
+                            entry.handle.id
+                        );
+                        entry.handle.cancel();
+                        continue;
+                    }
+                    entry.due = tick + period;
+                    repeating.push(entry);
+                }
+                Work::Waiting => {}
+            }
+        }
+
+        if !repeating.is_empty() {
+            self.lock().entries.extend(repeating);
+        }
+    }
+
+    /// Queue a task
+    fn push(&self, owner: Option<&str>, delay: u64, work: Work) -> TaskHandle {
+        let handle = TaskHandle {
+            id: self.next_id(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            abort: None,
+        };
+        let mut queue = self.lock();
+        let due = queue.tick + delay.max(1);
+        queue.entries.push(Entry {
+            handle: handle.clone(),
+            owner: owner.map(str::to_string),
+            due,
+            work,
+        });
+        handle
+    }
+
+    fn next_id(&self) -> TaskId {
+        TaskId(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use std::sync::atomic::AtomicUsize;
+
+    #[tokio::test]
+    async fn test_scheduling_and_cancellation() {
+        let state = ServerState::new(ServerConfig::default());
+        let mut world = World::new("world".to_string(), 0);
+        let scheduler = Scheduler::new();
+        let once = Arc::new(AtomicUsize::new(0));
+        let repeats = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&once);
+        scheduler.run_later(2, move |_, _| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        let counter = Arc::clone(&repeats);
+        let repeating = scheduler.run_repeating(1, 2, move |_, _| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        let cancelled = scheduler.run_later(1, |_, world| world.set_world_age(99));
+        cancelled.cancel();
+
+        scheduler.run_pending(&state, &mut world);
+        assert_eq!(once.load(Ordering::Relaxed), 0);
+        assert_eq!(repeats.load(Ordering::Relaxed), 1);
+        assert_ne!(world.world_age(), 99);
+
+        for _ in 0..4 {
+            scheduler.run_pending(&state, &mut world);
+        }
+        assert_eq!(once.load(Ordering::Relaxed), 1);
+        assert_eq!(repeats.load(Ordering::Relaxed), 3);
+
+        repeating.cancel();
+        scheduler.run_pending(&state, &mut world);
+        scheduler.run_pending(&state, &mut world);
+        assert_eq!(repeats.load(Ordering::Relaxed), 3);
+        assert_eq!(scheduler.pending_count(), 0);
+
+        let (sender, receiver) = tokio::sync::oneshot::channel::<i64>();
+        scheduler.run_async(
+            async move { receiver.await.unwrap_or(0) },
+            |_, world, age| world.set_world_age(age),
+        );
+        scheduler.run_pending(&state, &mut world);
+        assert_eq!(scheduler.pending_count(), 1);
+
+        sender.send(42).unwrap();
+        while scheduler
+            .lock()
+            .entries
+            .iter()
+            .any(|e| matches!(e.work, Work::Waiting))
+        {
+            tokio::task::yield_now().await;
+        }
+        scheduler.run_pending(&state, &mut world);
+        assert_eq!(world.world_age(), 42);
+    }
+}
@@ -0,0 +1,3800 @@
+//! Shared server state
+//!
+//! This module defines the state shared between the main server loop,
+//! connection tasks and commands.
+
+use crate::clock::{self, SharedClock};
+use crate::command::{CommandDispatcher, CommandResult, CommandSender, PlayerSender, SenderKind};
+use crate::config::{ReloadReport, RuntimeSettings, ServerConfig, ServerProperties};
+use crate::error::{Result, ServerError};
+use crate::event::{EventBus, ServerEvent};
+use crate::game::Difficulty;
+use crate::game::chat::ChatVerdict;
+use crate::game::datapack::{DATAPACKS_DIR, DataPackManager, DataPackSelection, PackPosition};
+use crate::game::enchantment::{self, DamageKind, Enchantment, Enchantments};
+use crate::game::entity::breeding::{self, BreedError, Fed};
+use crate::game::entity::decoration::{
+    self, ArmorStandEntity, DecorationChange, DecorationError, ItemFrameEntity,
+};
+use crate::game::entity::explosive::{self, ExplosiveEntity, ExplosiveKind};
+use crate::game::entity::mob::{FIST_DAMAGE, MAX_ATTACK_DISTANCE, MobEntity};
+use crate::game::entity::summon;
+use crate::game::entity::vehicle::{self, VehicleEntity};
+use crate::game::entity::villager::{self, TradeOffer, Trading};
+use crate::game::entity::{
+    CustomName, Entity, EntityId, EntityPosition, EntityRotation, EntityType, MobType, VehicleType,
+};
+use crate::game::food;
+use crate::game::inventory::{
+    EquipmentSlot, Hand, Inventory, ItemStack, OFFHAND_SLOT, SLOT_COUNT, STORAGE_SLOTS,
+};
+use crate::game::mining;
+use crate::game::movement::{self, Movement};
+use crate::game::player::{GameMode, Player, PlayerAbilities, PlayerPosition, PlayerRotation};
+use crate::game::playerdata::{PlayerData, PlayerDataStore};
+use crate::game::settings::ClientSettings;
+use crate::game::world::bed::{self, SleepError};
+use crate::game::world::budget::{ChunkQueue, ChunkView};
+use crate::game::world::config::{Seed, WorldConfig};
+use crate::game::world::dimension::Dimension;
+use crate::game::world::explosion::Explosion;
+use crate::game::world::multiblock::{self, MultiBlockKind};
+use crate::game::world::portal;
+use crate::game::world::pregen::{self, PregenManager, PregenProgress, PregenUpdate};
+use crate::game::world::registry::{BlockRegistry, ItemRegistry};
+use crate::game::world::simulation::SimulationArea;
+use crate::game::world::spawn;
+use crate::game::world::storage::WorldStorage;
+use crate::game::world::{ChunkPosition, Direction, SaveOutcome, Weather};
+use crate::game::{player::PlayerManager, world::World};
+use crate::network::{LoginThrottle, PacketCounters};
+use crate::plugin::PluginManager;
+use crate::protocol::metadata::{
+    MetadataEntry, MetadataValue, Pose, armor_stand_flags, flags, index,
+};
+use crate::protocol::nbt::Compound;
+use crate::protocol::packets::DynPacket;
+use crate::protocol::packets::play::{
+    BlockChangePacket, ChangeDifficultyPacket, ChatDecoration, ChatMessagePacket, ChunkDataPacket,
+    ClickContainerPacket, EntityAnimationPacket, EntityEventPacket, EntityPositionSyncPacket,
+    ExplodePacket, FilterMask, GameEventPacket, HashedStack, LoginPlayPacket, MerchantOffer,
+    MerchantOffersPacket, MoveVehiclePacket, OpenScreenPacket, PlayerAbilitiesPacket,
+    PlayerChatMessagePacket, PlayerInfoEntry, PlayerInfoRemovePacket, PlayerInfoUpdatePacket,
+    PlayerInputPacket, RemoveEntitiesPacket, RespawnPacket, SetCameraPacket, SetCenterChunkPacket,
+    SetContainerContentPacket, SetContainerSlotPacket, SetDefaultSpawnPositionPacket,
+    SetEntityMetadataPacket, SetEquipmentPacket, SetHeadRotationPacket, SetHealthPacket,
+    SetPassengersPacket, SoundEffectPacket, SpawnEntityPacket, SynchronizePlayerPositionPacket,
+    SystemChatMessagePacket, UnloadChunkPacket, UpdateEntityPositionAndRotationPacket,
+    UpdateEntityPositionPacket, UpdateEntityRotationPacket, UpdateRecipesPacket,
+    UpdateSectionBlocksPacket, UpdateTimePacket,
+};
+use crate::protocol::packets::status::{PlayerSample, PlayersInfo};
+use crate::protocol::rsa::RsaPrivateKey;
+use crate::protocol::types::{JsonTextComponent, McString, McUuid, Position, VarInt};
+use crate::server::audit::{AuditEvent, AuditLog};
+use crate::server::backup::{self, BackupManager, BackupProgress, BackupReport};
+use crate::server::bans::{BANNED_IPS_FILE, BANNED_PLAYERS_FILE, BanList, IpBanEntry};
+use crate::server::filter::TextFilter;
+use crate::server::ops::{OPS_FILE, OpList};
+use crate::server::profiler::Profiler;
+use crate::server::profiles::{ProfileApi, ProfileResolver};
+use crate::server::resource_pack::HostedPack;
+use crate::server::scheduler::Scheduler;
+use crate::server::tick::TickStats;
+use crate::server::usercache::UserCache;
+use crate::server::watchdog::TickProgress;
+use crate::server::whitelist::{WHITELIST_FILE, Whitelist};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{Mutex, RwLock, broadcast, watch};
+
+/// Capacity of the chat broadcast channel
+const CHAT_CHANNEL_CAPACITY: usize = 256;
+
+/// Name shown in the server list for players who disabled listings, as used by vanilla
+const ANONYMOUS_PLAYER_NAME: &str = "Anonymous Player";
+
+/// Capacity of the kick request channel
+const KICK_CHANNEL_CAPACITY: usize = 64;
+
+/// Capacity of the reconfiguration request channel
+const RECONFIGURE_CHANNEL_CAPACITY: usize = 64;
+
+/// Seed of new worlds without one in their world.toml
+const DEFAULT_SEED: i64 = 12345;
+
+/// Operator level needed to change or lock the difficulty from the client
+const DIFFICULTY_PERMISSION_LEVEL: u8 = 2;
+
+/// Furthest from a player's eyes a block can be dug, with some leeway
+const MAX_DIG_DISTANCE: f64 = 6.0;
+
+/// Eye height of a standing player
+const EYE_HEIGHT: f64 = 1.62;
+
+/// A request to disconnect a player
+#[derive(Debug, Clone)]
+pub struct KickRequest {
+    /// Player to disconnect
+    pub uuid: McUuid,
+    /// Only disconnect the session on this connection
+    pub connection: Option<SocketAddr>,
+    /// Reason shown to the player
+    pub reason: JsonTextComponent,
+}
+
+/// State shared by every part of a running server
+pub struct ServerState {
+    /// Configuration the server was started with
+    ///
+    /// Settings that can be reloaded at runtime must be read through
+    /// [`ServerState::settings`] instead.
+    pub config: ServerConfig,
+    /// Player manager
+    pub players: Arc<PlayerManager>,
+    /// Main world
+    pub world: Arc<RwLock<World>>,
+    /// The nether, unless `allow-nether` is off
+    pub nether: Option<Arc<RwLock<World>>>,
+    /// Chunks waiting to be sent to players, as the chunk budgets allow
+    pub chunk_queue: Mutex<ChunkQueue>,
+    /// Block registry
+    pub blocks: Arc<BlockRegistry>,
+    /// Item registry
+    pub items: Arc<ItemRegistry>,
+    /// Data packs and the recipes, loot tables, tags and functions they provide
+    pub datapacks: RwLock<DataPackManager>,
+    /// Command dispatcher
+    pub commands: CommandDispatcher,
+    /// Server event bus
+    pub events: EventBus,
+    /// Loaded plugins
+    pub plugins: PluginManager,
+    /// Loaded scripts
+    #[cfg(feature = "scripting")]
+    pub scripts: crate::script::ScriptManager,
+    /// Tasks run on the main tick loop
+    pub scheduler: Scheduler,
+    /// Persistent per-player data
+    pub player_data: PlayerDataStore,
+    /// Banned players
+    pub bans: BanList,
+    /// Banned addresses
+    pub ip_bans: BanList<IpBanEntry>,
+    /// Names, UUIDs and skins of players, whether or not they are online
+    pub profiles: ProfileResolver,
+    /// Players allowed to join while the whitelist is enabled
+    pub whitelist: Whitelist,
+    /// Server operators
+    pub ops: OpList,
+    /// Source of the current time for timeouts and statistics
+    pub clock: SharedClock,
+    /// Tick timing statistics
+    pub tick_stats: TickStats,
+    /// Progress of the running tick, watched by the watchdog
+    pub tick_progress: TickProgress,
+    /// Profiler started by `/profile`
+    pub profiler: Profiler,
+    /// Packets sent and received by every connection
+    pub packet_counters: Arc<PacketCounters>,
+    /// Login attempts of each address, to refuse logins that come too fast
+    pub login_throttle: LoginThrottle,
+    /// Resource pack served by the built-in host, if any
+    pub hosted_pack: Option<Arc<HostedPack>>,
+    /// Key pair online-mode logins exchange secrets with, generated when
+    /// first needed
+    server_key: OnceLock<RsaPrivateKey>,
+    /// Settings that can change while the server runs
+    settings: watch::Sender<RuntimeSettings>,
+    /// Requested, running and finished world backups
+    pub backups: BackupManager,
+    /// Terrain pre-generation started by `/pregen`
+    pub pregen: PregenManager,
+    /// Filter applied to text written by players
+    pub text_filter: TextFilter,
+    /// Record of logins, commands and moderation actions
+    pub audit: AuditLog,
+    /// Whether automatic saving is enabled (toggled by /save-on and /save-off)
+    saving_enabled: AtomicBool,
+    /// Held while the world is saved, so saves never overlap
+    save_lock: Mutex<()>,
+    /// ID of the next teleport sent to a client
+    next_teleport_id: AtomicI32,
+    /// Server-wide chat messages, delivered to every player in the play state
+    chat: broadcast::Sender<JsonTextComponent>,
+    /// Requests to disconnect players, checked by every connection
+    kicks: broadcast::Sender<KickRequest>,
+    /// Players to send back to the configuration state, checked by every
+    /// connection
+    reconfigurations: broadcast::Sender<McUuid>,
+    /// Set to `true` once a shutdown has been requested
+    shutdown: watch::Sender<bool>,
+    /// Why the watchdog stopped the server, if it did
+    crash: std::sync::Mutex<Option<String>>,
+}
+
+impl ServerState {
+    /// Create the shared state for a server with the given configuration
+    ///
+    /// The world is kept in memory only; use [`ServerState::load`] to read
+    /// and persist it on disk.
+    pub fn new(config: ServerConfig) -> Self {
+        let mut world = World::new(config.level_name.clone(), 12345);
+        world.set_difficulty(config.difficulty);
+        let nether = new_nether(&config, &world);
+        Self::with_world(
+            config,
+            Arc::new(BlockRegistry::new()),
+            world,
+            nether,
+            DataPackManager::new(),
+        )
+    }
+
+    /// Create the shared state, loading the world from its directory
+    pub fn load(config: ServerConfig) -> Result<Self> {
+        let blocks = Arc::new(BlockRegistry::new());
+        let storage = || {
+            WorldStorage::new(&config.level_name, Arc::clone(&blocks))
+                .with_compression(config.region_compression)
+                .with_sync_writes(config.sync_chunk_writes)
+        };
+
+        // world.toml settings take precedence over level.dat and server.properties
+        let world_config = WorldConfig::load(&config.level_name)?;
+        let seed = world_config
+            .as_ref()
+            .and_then(|world_config| world_config.seed.as_ref())
+            .map_or(DEFAULT_SEED, Seed::value);
+
+        let mut world = World::new(config.level_name.clone(), seed);
+        world.set_difficulty(config.difficulty);
+        world.set_generator(config.level_type);
+        let mut world = world.with_storage(storage())?;
+        if let Some(ref world_config) = world_config {
+            world_config.apply_to(&mut world)?;
+        }
+        let spawn_chunks: Vec<_> = spawn::spawn_chunks(
+            world.spawn_position(),
+            world.game_rules().spawn_chunk_radius,
+        )
+        .collect();
+        let total = spawn_chunks.len();
+        pregen::pregenerate(
+            &mut world,
+            spawn_chunks,
+            total,
+            clock::system().as_ref(),
+            |progress| progress.log("Preparing spawn area"),
+        )?;
+
+        // A new world starts with the packs chosen in server.properties
+        let selection = world
+            .data_packs()
+            .cloned()
+            .unwrap_or_else(|| DataPackSelection {
+                enabled: config.initial_enabled_packs.clone(),
+                disabled: config.initial_disabled_packs.clone(),
+            });
+        let datapacks = DataPackManager::load(
+            Path::new(&config.level_name).join(DATAPACKS_DIR),
+            &selection,
+        );
+        world.set_data_packs(datapacks.selection());
+        let nether = new_nether(&config, &world)
+            .map(|nether| nether.with_storage(storage().with_dimension(Dimension::Nether)))
+            .transpose()?;
+
+        let mut state = Self::with_world(config, blocks, world, nether, datapacks);
+        state.bans = BanList::load(state.config.data_file(BANNED_PLAYERS_FILE))?;
+        state.ip_bans = BanList::load(state.config.data_file(BANNED_IPS_FILE))?;
+        state.profiles = profile_resolver(
+            &state.config,
+            UserCache::load(state.config.usercache_file()),
+        );
+        state.whitelist = Whitelist::load(state.config.data_file(WHITELIST_FILE))?;
+        state.ops = OpList::load(state.config.data_file(OPS_FILE))?;
+        state.text_filter = TextFilter::from_config(&state.config.text_filtering_config)?;
+        state.audit = AuditLog::new(state.config.audit.clone(), state.config.log_ips);
+        if let Some(ref host) = state.config.resource_pack_host {
+            let hosted = HostedPack::load(host)?;
+            state
+                .settings
+                .send_modify(|settings| settings.resource_pack = Some(hosted.pack().clone()));
+            state.hosted_pack = Some(Arc::new(hosted));
+        }
+        Ok(state)
+    }
+
+    /// Create the shared state around an existing world, its nether and
+    /// its loaded data packs
+    fn with_world(
+        config: ServerConfig,
+        blocks: Arc<BlockRegistry>,
+        world: World,
+        nether: Option<World>,
+        datapacks: DataPackManager,
+    ) -> Self {
+        let commands = CommandDispatcher::new();
+        crate::command::builtin::register_all(&commands);
+
+        let player_data = PlayerDataStore::new(Path::new(&config.level_name).join("playerdata"));
+        let settings = RuntimeSettings::from_config(&config);
+        let login_throttle = LoginThrottle::new(config.login_throttle);
+        let profiles = profile_resolver(&config, UserCache::new());
+        let world = world.with_chunk_budgets(config.chunk_budgets);
+        let nether = nether.map(|nether| Arc::new(RwLock::new(nether)));
+
+        Self {
+            config,
+            players: Arc::new(PlayerManager::new()),
+            world: Arc::new(RwLock::new(world)),
+            nether,
+            chunk_queue: Mutex::new(ChunkQueue::new()),
+            blocks,
+            items: Arc::new(ItemRegistry::new()),
+            datapacks: RwLock::new(datapacks),
+            commands,
+            events: EventBus::new(),
+            plugins: PluginManager::default(),
+            #[cfg(feature = "scripting")]
+            scripts: crate::script::ScriptManager::default(),
+            scheduler: Scheduler::new(),
+            player_data,
+            bans: BanList::new(),
+            ip_bans: BanList::new(),
+            profiles,
+            whitelist: Whitelist::new(),
+            ops: OpList::new(),
+            clock: clock::system(),
+            tick_stats: TickStats::new(),
+            tick_progress: TickProgress::new(),
+            profiler: Profiler::new(),
+            packet_counters: Arc::new(PacketCounters::new()),
+            login_throttle,
+            hosted_pack: None,
+            server_key: OnceLock::new(),
+            settings: watch::channel(settings).0,
+            backups: BackupManager::new(),
+            pregen: PregenManager::new(),
+            text_filter: TextFilter::new(),
+            audit: AuditLog::disabled(),
+            saving_enabled: AtomicBool::new(true),
+            save_lock: Mutex::new(()),
+            next_teleport_id: AtomicI32::new(0),
+            chat: broadcast::channel(CHAT_CHANNEL_CAPACITY).0,
+            kicks: broadcast::channel(KICK_CHANNEL_CAPACITY).0,
+            reconfigurations: broadcast::channel(RECONFIGURE_CHANNEL_CAPACITY).0,
+            shutdown: watch::channel(false).0,
+            crash: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Measure time with the given clock instead of the system clock
+    ///
+    /// Tick statistics, progress, the profiler and the login throttle are
+    /// reset to use it too.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.set_clock(clock);
+        self
+    }
+
+    /// Measure time with the given clock, as [`with_clock`](Self::with_clock)
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.login_throttle =
+            LoginThrottle::new(self.config.login_throttle).with_clock(Arc::clone(&clock));
+        self.tick_stats = TickStats::new().with_clock(Arc::clone(&clock));
+        self.tick_progress = TickProgress::new().with_clock(Arc::clone(&clock));
+        self.profiler = Profiler::new().with_clock(Arc::clone(&clock));
+        self.clock = clock;
+    }
+
+    /// Execute a command line on behalf of a sender, reporting errors to it
+    pub async fn execute_command(&self, sender: &dyn CommandSender, input: &str) -> CommandResult {
+        let result = self.commands.dispatch(self, sender, input).await;
+        self.audit.record(AuditEvent::Command {
+            source: sender.name(),
+            command: input.to_string(),
+            success: result.is_ok(),
+        });
+        result
+    }
+
+    /// Permission level of a player: their operator level, or 0
+    pub fn permission_level(&self, uuid: &McUuid) -> u8 {
+        self.ops.get(uuid).map_or(0, |op| op.level)
+    }
+
+    /// Run a command typed by the player on a connection, sending them its
+    /// feedback
+    pub async fn run_player_command(&self, addr: &SocketAddr, input: &str) {
+        let Some(player) = self.players.get_player_by_addr(addr) else {
+            return;
+        };
+        let sender = PlayerSender::new(&player, self.permission_level(&player.uuid));
+        let _ = self.execute_command(&sender, input).await;
+        for message in sender.take_messages() {
+            self.send_message(&player.uuid, message);
+        }
+    }
+
+    /// Send a player the graph of the commands they may use, after joining
+    /// or a change of their permission level
+    pub fn send_commands(&self, uuid: &McUuid) {
+        let Some(player) = self.players.get_player(uuid) else {
+            return;
+        };
+        let sender = PlayerSender::new(&player, self.permission_level(uuid));
+        self.players
+            .send_packet(uuid, self.commands.declare_commands(&sender));
+    }
+
+    /// Broadcast a chat message to all players and echo it to the console
+    pub fn broadcast_message(&self, message: JsonTextComponent) {
+        self.broadcast_packet(SystemChatMessagePacket {
+            content: message.clone(),
+            overlay: false,
+        });
+        self.echo_chat(message);
+    }
+
+    /// Publish the join events of a player entering play and broadcast
+    /// their join message
+    pub fn announce_join(&self, player: &Player) {
+        if player.first_join {
+            self.events.publish(&ServerEvent::PlayerFirstJoin {
+                username: player.username.clone(),
+                uuid: player.uuid,
+            });
+        }
+        self.events.publish(&ServerEvent::PlayerJoin {
+            username: player.username.clone(),
+            uuid: player.uuid,
+        });
+        let online = self.players.player_count();
+        let max = self.settings().max_players;
+        let messages = &self.config.join_messages;
+        if let Some(message) =
+            messages.join_message(&player.username, online, max, player.first_join)
+        {
+            self.broadcast_message(message);
+        }
+    }
+
+    /// Publish the quit event of a player who left play and broadcast
+    /// their quit message
+    pub fn announce_quit(&self, player: &Player) {
+        self.events.publish(&ServerEvent::PlayerQuit {
+            username: player.username.clone(),
+            uuid: player.uuid,
+        });
+        let online = self.players.player_count();
+        let max = self.settings().max_players;
+        let messages = &self.config.join_messages;
+        if let Some(message) = messages.quit_message(&player.username, online, max) {
+            self.broadcast_message(message);
+        }
+    }
+
+    /// Send a chat message to one player
+    pub fn send_message(&self, uuid: &McUuid, message: JsonTextComponent) {
+        self.players.send_packet(
+            uuid,
+            SystemChatMessagePacket {
+                content: message,
+                overlay: false,
+            },
+        );
+    }
+
+    /// Echo chat to the console and chat subscribers, without sending it to
+    /// players
+    fn echo_chat(&self, message: JsonTextComponent) {
+        tracing::info!("{}", message.to_plain_text());
+        // Sending only fails when nobody is subscribed, which is fine
+        let _ = self.chat.send(message);
+    }
+
+    /// Subscribe to server-wide chat messages
+    pub fn subscribe_chat(&self) -> broadcast::Receiver<JsonTextComponent> {
+        self.chat.subscribe()
+    }
+
+    /// Send a chat message from a player to everyone
+    ///
+    /// The message passes through the text filter first. Players who asked
+    /// for filtering are sent the filter mask; everyone else, including the
+    /// sender, sees the message as written. Messages with formatting codes
+    /// or control characters are refused as in vanilla, which disconnects
+    /// the sender. Messages breaking the [chat limits](crate::game::chat)
+    /// are dropped with a warning to the sender.
+    pub async fn send_chat(&self, addr: &SocketAddr, chat: &ChatMessagePacket) -> Result<()> {
+        let message = &chat.message.0;
+        if message
+            .chars()
+            .any(|c| c == '§' || c < ' ' || c == '\u{7f}')
+        {
+            return Err(ServerError::Kicked(crate::lang::translate(
+                "multiplayer.disconnect.illegal_characters",
+                &[],
+            )));
+        }
+        let now = self.clock.now();
+        let Some((sender, username, verdict, index)) =
+            self.players.with_player_mut(addr, |player| {
+                let verdict = player
+                    .chat_guard
+                    .check(&self.config.chat_limits, message, now);
+                if verdict == ChatVerdict::Allowed {
+                    player.messages_sent += 1;
+                }
+                (
+                    player.uuid,
+                    player.username.clone(),
+                    verdict,
+                    player.messages_sent - 1,
+                )
+            })
+        else {
+            return Ok(());
+        };
+        match verdict {
+            ChatVerdict::Allowed => {}
+            ChatVerdict::Warn(e) => {
+                tracing::debug!("Dropped chat message from {}: {}", username, e);
+                self.send_message(&sender, e.warning());
+                return Ok(());
+            }
+            ChatVerdict::Kick(e) => {
+                tracing::info!("Kicking {} for chat spam: {}", username, e);
+                return Err(ServerError::Kicked(crate::lang::translate(
+                    "disconnect.spam",
+                    &[],
+                )));
+            }
+        }
+
+        let filter = self.text_filter.filter(&sender, message).await;
+        if filter.is_filtered() {
+            tracing::debug!("Filtered chat message from {}", username);
+        }
+        // Players are sent the signed message below instead
+        self.echo_chat(crate::lang::translate(
+            "chat.type.text",
+            &[&username, message],
+        ));
+
+        let mut packets = Vec::new();
+        self.players.for_each_player_mut(|player| {
+            let filtered = player.uuid != sender
+                && player
+                    .settings()
+                    .is_some_and(|settings| settings.text_filtering);
+            packets.push((
+                player.uuid,
+                PlayerChatMessagePacket {
+                    global_index: VarInt(player.messages_received),
+                    sender,
+                    index: VarInt(index),
+                    signature: None,
+                    message: chat.message.clone(),
+                    timestamp: chat.timestamp,
+                    salt: chat.salt,
+                    unsigned_content: None,
+                    filter: if filtered {
+                        filter.clone()
+                    } else {
+                        FilterMask::PassThrough
+                    },
+                    chat: ChatDecoration::chat(),
+                    narration: ChatDecoration::chat_narration(),
+                    sender_name: JsonTextComponent::text(&username),
+                    target_name: None,
+                },
+            ));
+            player.messages_received += 1;
+        });
+        for (uuid, packet) in packets {
+            self.players.send_packet(&uuid, packet);
+        }
+        Ok(())
+    }
+
+    /// Broadcast a packet to all players in the play state
+    pub fn broadcast_packet<P: DynPacket + 'static>(&self, packet: P) {
+        self.players.broadcast(packet);
+    }
+
+    /// Show the skin layers and main hand of a player's settings to everyone
+    pub fn broadcast_appearance(&self, entity_id: EntityId, settings: &ClientSettings) {
+        self.broadcast_packet(appearance_metadata(entity_id, settings));
+    }
+
+    /// Tab list entries adding players, with their skins and latency
+    pub async fn player_list_packet(&self, players: &[Player]) -> PlayerInfoUpdatePacket {
+        let mut entries = Vec::with_capacity(players.len());
+        for player in players {
+            let mut entry = player_info_entry(player);
+            entry.name = McString(player.username.clone());
+            entry.properties = self.profiles.skin(player.uuid, &player.username).await;
+            entries.push(entry);
+        }
+        PlayerInfoUpdatePacket {
+            actions: PlayerInfoUpdatePacket::ADD_PLAYER
+                | PlayerInfoUpdatePacket::UPDATE_GAME_MODE
+                | PlayerInfoUpdatePacket::UPDATE_LISTED
+                | PlayerInfoUpdatePacket::UPDATE_LATENCY,
+            entries,
+        }
+    }
+
+    /// Show the current latency of every player in everyone's tab list
+    pub fn broadcast_latencies(&self) {
+        let entries = self.players.map_players(player_info_entry);
+        if entries.is_empty() {
+            return;
+        }
+        self.broadcast_packet(PlayerInfoUpdatePacket {
+            actions: PlayerInfoUpdatePacket::UPDATE_LATENCY,
+            entries,
+        });
+    }
+
+    /// Remove a player who left from everyone's tab list
+    pub fn broadcast_player_removed(&self, uuid: McUuid) {
+        self.broadcast_packet(PlayerInfoRemovePacket { uuids: vec![uuid] });
+    }
+
+    /// Disconnect an online player, returning whether they were online
+    pub fn kick_player(&self, uuid: &McUuid, reason: JsonTextComponent) -> bool {
+        if self.players.with_player(uuid, |_| ()).is_none() {
+            return false;
+        }
+        // Sending only fails when nobody is connected
+        let _ = self.kicks.send(KickRequest {
+            uuid: *uuid,
+            connection: None,
+            reason,
+        });
+        true
+    }
+
+    /// Disconnect the session of a player on one connection, even after a
+    /// newer session took the player's place
+    pub fn kick_connection(&self, uuid: McUuid, addr: SocketAddr, reason: JsonTextComponent) {
+        // Sending only fails when nobody is connected
+        let _ = self.kicks.send(KickRequest {
+            uuid,
+            connection: Some(addr),
+            reason,
+        });
+    }
+
+    /// Subscribe to requests to disconnect players
+    pub fn subscribe_kicks(&self) -> broadcast::Receiver<KickRequest> {
+        self.kicks.subscribe()
+    }
+
+    /// Send every player back to the configuration state, so they are
+    /// offered the current resource pack
+    pub fn reconfigure_all(&self) {
+        for player in self.players.get_all_players() {
+            self.reconfigure_player(&player.uuid);
+        }
+    }
+
+    /// Send an online player back to the configuration state, returning
+    /// whether they were online
+    ///
+    /// The player is sent the resource pack again and then rejoins the
+    /// world.
+    pub fn reconfigure_player(&self, uuid: &McUuid) -> bool {
+        if self.players.with_player(uuid, |_| ()).is_none() {
+            return false;
+        }
+        // Sending only fails when nobody is connected
+        let _ = self.reconfigurations.send(*uuid);
+        true
+    }
+
+    /// Subscribe to requests to send players back to the configuration
+    /// state
+    pub fn subscribe_reconfigurations(&self) -> broadcast::Receiver<McUuid> {
+        self.reconfigurations.subscribe()
+    }
+
+    /// Settings currently in effect
+    pub fn settings(&self) -> RuntimeSettings {
+        self.settings.borrow().clone()
+    }
+
+    /// Key pair online-mode logins exchange secrets with
+    ///
+    /// The key is generated on first use; the server does so while
+    /// starting so the first login does not wait for it.
+    pub fn server_key(&self) -> Result<&RsaPrivateKey> {
+        if let Some(key) = self.server_key.get() {
+            return Ok(key);
+        }
+        let key = RsaPrivateKey::generate()?;
+        Ok(self.server_key.get_or_init(|| key))
+    }
+
+    /// Player counts and sample shown in the server list
+    ///
+    /// Players who disabled server listings in their client settings are
+    /// shown anonymously, and no sample is sent if online players are hidden.
+    pub fn status_players(&self) -> PlayersInfo {
+        let settings = self.settings();
+        let online = self.players.player_count();
+        let sample = (!settings.hide_online_players).then(|| {
+            let players = self.players.map_players(sample_entry);
+            player_sample(&players, settings.player_sample_size as usize)
+        });
+
+        PlayersInfo {
+            max: settings.max_players,
+            online: online as u32,
+            sample,
+        }
+    }
+
+    /// Subscribe to changes of the runtime settings
+    pub fn subscribe_settings(&self) -> watch::Receiver<RuntimeSettings> {
+        self.settings.subscribe()
+    }
+
+    /// Re-read server.properties, obsidium.toml and the whitelist, applying what can change at runtime
+    ///
+    /// Besides the [`RuntimeSettings`], a changed difficulty is applied as
+    /// if set by /difficulty. Other changed properties are only reported.
+    /// Command-line arguments keep taking precedence over the files.
+    pub async fn reload_config(&self) -> Result<ReloadReport> {
+        let Some(ref path) = self.config.properties_path else {
+            return Err(ServerError::Configuration(
+                "The server was not started from a server.properties file".to_string(),
+            ));
+        };
+        let mut reloaded = ServerConfig::from_properties(ServerProperties::load_from_file(path)?)?;
+        if let Some(ref overrides_path) = self.config.overrides_path {
+            reloaded = reloaded.with_overrides_file(overrides_path)?;
+        }
+        let reloaded = reloaded.with_cli_args(self.config.cli_args.clone());
+        reloaded.validate()?;
+        self.whitelist.reload()?;
+
+        let mut report = ReloadReport::compare(&self.settings(), &self.config, &reloaded);
+        {
+            let mut world = self.world.write().await;
+            if world.difficulty() != reloaded.difficulty {
+                world.set_difficulty(reloaded.difficulty);
+                self.broadcast_packet(difficulty_packet(&world));
+                report.applied.push("difficulty");
+            }
+        }
+        let mut settings = RuntimeSettings::from_config(&reloaded);
+        let current = self.settings();
+        if self.config.resource_pack_host.is_some() {
+            settings.resource_pack = current.resource_pack.clone();
+        }
+        let pack_changed = current.resource_pack_changed(&settings);
+        self.settings.send_if_modified(|current| {
+            let changed = *current != settings;
+            *current = settings;
+            changed
+        });
+        if pack_changed {
+            self.reconfigure_all();
+        }
+
+        Ok(report)
+    }
+
+    /// Change the view distance until the server restarts or the
+    /// configuration is reloaded
+    ///
+    /// Connections tell their players the new distance, and the next tick
+    /// sends players the chunks that came into sight.
+    pub fn set_view_distance(&self, distance: u8) {
+        self.settings.send_if_modified(|settings| {
+            std::mem::replace(&mut settings.view_distance, distance) != distance
+        });
+    }
+
+    /// Change the simulation distance until the server restarts or the
+    /// configuration is reloaded
+    ///
+    /// The next tick simulates the new area, and connections tell their
+    /// players the new distance.
+    pub fn set_simulation_distance(&self, distance: u8) {
+        self.settings.send_if_modified(|settings| {
+            std::mem::replace(&mut settings.simulation_distance, distance) != distance
+        });
+    }
+
+    /// Change the world difficulty
+    ///
+    /// The change is sent to every player and written back to
+    /// server.properties when the configuration was loaded from a file.
+    pub async fn set_difficulty(&self, difficulty: Difficulty) -> Result<()> {
+        let mut world = self.world.write().await;
+        world.set_difficulty(difficulty);
+        self.broadcast_packet(difficulty_packet(&world));
+        drop(world);
+
+        if let Some(ref path) = self.config.properties_path {
+            let mut props = ServerProperties::load_from_file_or_default(path)?;
+            props.set_difficulty(difficulty);
+            props.save_to_file(path)?;
+        }
+
+        tracing::info!("Difficulty set to {}", difficulty.name());
+        Ok(())
+    }
+
+    /// Change the difficulty as the player on a connection asked
+    ///
+    /// Like vanilla, only operators may, and only while the difficulty is
+    /// not locked.
+    pub async fn request_difficulty(
+        &self,
+        addr: &SocketAddr,
+        difficulty: Difficulty,
+    ) -> Result<()> {
+        if !self.may_change_difficulty(addr) {
+            return Ok(());
+        }
+        let locked = self.world.read().await.is_difficulty_locked();
+        if locked {
+            tracing::debug!("Ignoring difficulty change: the difficulty is locked");
+            return Ok(());
+        }
+        self.set_difficulty(difficulty).await
+    }
+
+    /// Lock or unlock the difficulty as the player on a connection asked
+    pub async fn request_difficulty_lock(&self, addr: &SocketAddr, locked: bool) {
+        if !self.may_change_difficulty(addr) {
+            return;
+        }
+        let mut world = self.world.write().await;
+        world.set_difficulty_locked(locked);
+        self.broadcast_packet(difficulty_packet(&world));
+        tracing::info!("Difficulty {}", if locked { "locked" } else { "unlocked" });
+    }
+
+    /// Whether the player on a connection is an operator allowed to change
+    /// the difficulty
+    fn may_change_difficulty(&self, addr: &SocketAddr) -> bool {
+        let Some(player) = self.players.get_player_by_addr(addr) else {
+            return false;
+        };
+        self.ops
+            .get(&player.uuid)
+            .is_some_and(|op| op.level >= DIFFICULTY_PERMISSION_LEVEL)
+    }
+
+    /// Packet telling clients about the recipes of the enabled data packs
+    pub async fn recipes_packet(&self) -> UpdateRecipesPacket {
+        let datapacks = self.datapacks.read().await;
+        let registries = datapacks.registries();
+        registries
+            .recipes
+            .update_packet(&registries.tags, |item| self.items.get_item_id(&item.0))
+    }
+
+    /// Enable a data pack, saving the choice and sending the new recipes to every player
+    ///
+    /// Returns `false` if the pack is unknown or already enabled.
+    pub async fn enable_datapack(&self, id: &str, position: PackPosition) -> bool {
+        let enabled = self.datapacks.write().await.enable(id, position);
+        if enabled {
+            self.datapacks_changed().await;
+        }
+        enabled
+    }
+
+    /// Disable a data pack, saving the choice and sending the new recipes to every player
+    ///
+    /// Returns `false` if the pack is not enabled.
+    pub async fn disable_datapack(&self, id: &str) -> bool {
+        let disabled = self.datapacks.write().await.disable(id);
+        if disabled {
+            self.datapacks_changed().await;
+        }
+        disabled
+    }
+
+    /// Store the data pack selection in the world and resend the recipes
+    async fn datapacks_changed(&self) {
+        let selection = self.datapacks.read().await.selection();
+        self.world.write().await.set_data_packs(selection);
+        self.broadcast_packet(self.recipes_packet().await);
+    }
+
+    /// Send the current world time to all players
+    pub fn broadcast_time(&self, world: &World) {
+        self.broadcast_packet(time_packet(world));
+    }
+
+    /// Move the world spawn, keeping the chunks around it loaded, and tell
+    /// every player where it is now
+    ///
+    /// Chunks around the old spawn stay loaded until the server restarts.
+    pub async fn set_world_spawn(&self, position: Position, angle: f32) {
+        let mut world = self.world.write().await;
+        world.set_spawn_position(position);
+        world.set_spawn_angle(angle);
+        spawn::load_spawn_chunks(&mut world);
+        self.broadcast_packet(world_spawn_packet(&world));
+    }
+
+    /// Generate the terrain within `radius` chunks of the world spawn ahead
+    /// of time, logging progress and publishing
+    /// [`ServerEvent::PregenProgress`]
+    ///
+    /// The world is locked until generation finishes.
+    pub async fn pregenerate(&self, radius: u32) -> Result<PregenProgress> {
+        let mut world = self.world.write().await;
+        let center = spawn::spawn_chunk(world.spawn_position());
+        pregen::pregenerate(
+            &mut world,
+            pregen::spiral(center, radius),
+            pregen::chunk_count(radius),
+            self.clock.as_ref(),
+            |progress| {
+                progress.log("Pre-generating terrain");
+                self.events.publish(&ServerEvent::PregenProgress {
+                    done: progress.done,
+                    total: progress.total,
+                    chunks_per_second: progress.chunks_per_second(),
+                });
+            },
+        )
+    }
+
+    /// Advance the pre-generation started by `/pregen` unless ticks run
+    /// slow, and tell whoever started it how it goes
+    pub fn tick_pregen(&self, world: &mut World) {
+        let slow = self
+            .tick_stats
+            .mspt()
+            .is_some_and(|mspt| mspt.average > pregen::MAX_MSPT);
+        if slow {
+            return;
+        }
+        if let Some(update) = self.pregen.step(world, self.clock.as_ref()) {
+            self.report_pregen(&update);
+        }
+    }
+
+    /// Report the progress of a running pre-generation to its issuer
+    fn report_pregen(&self, update: &PregenUpdate) {
+        let progress = &update.progress;
+        self.events.publish(&ServerEvent::PregenProgress {
+            done: progress.done,
+            total: progress.total,
+            chunks_per_second: progress.chunks_per_second(),
+        });
+        let message = match &update.error {
+            Some(e) => crate::lang::translate(
+                "obsidium.pregen.failed",
+                &[&progress.done.to_string(), &e.to_string()],
+            ),
+            None if progress.is_done() => crate::lang::translate(
+                "obsidium.pregen.done",
+                &[
+                    &progress.done.to_string(),
+                    &format!("{:.1}", progress.elapsed.as_secs_f64()),
+                ],
+            ),
+            None => progress.status(),
+        };
+        tracing::info!("{}", message.to_plain_text());
+        if let SenderKind::Player(uuid) = update.issuer {
+            self.send_message(&uuid, message);
+        }
+    }
+
+    /// Put a joining player at the world spawn, facing the spawn angle
+    pub async fn place_at_spawn(&self, player: &mut Player) {
+        let world = self.world.read().await;
+        let spawn = world.spawn_position();
+        player.position = PlayerPosition {
+            x: f64::from(spawn.x) + 0.5,
+            y: f64::from(spawn.y),
+            z: f64::from(spawn.z) + 0.5,
+        };
+        player.rotation.yaw = world.spawn_angle();
+    }
+
+    /// Send a weather change to all players
+    pub fn broadcast_weather(&self, weather: Weather) {
+        for packet in weather_packets(weather) {
+            self.broadcast_packet(packet);
+        }
+    }
+
+    /// Packets describing the world difficulty, time and weather, sent to joining players
+    pub async fn world_state_packets(&self) -> Vec<Box<dyn DynPacket>> {
+        let world = self.world.read().await;
+        let mut packets: Vec<Box<dyn DynPacket>> = vec![
+            Box::new(difficulty_packet(&world)),
+            Box::new(time_packet(&world)),
+            Box::new(world_spawn_packet(&world)),
+        ];
+        if world.weather().is_raining() {
+            for packet in weather_packets(world.weather()) {
+                packets.push(Box::new(packet));
+            }
+        }
+        packets
+    }
+
+    /// Restore a joining player's saved data, marking them as joining for
+    /// the first time if they have none
+    pub fn load_player_data(&self, player: &mut Player) {
+        match self.player_data.load(&player.uuid) {
+            Ok(Some(data)) => data.apply_to(player),
+            Ok(None) => player.first_join = true,
+            Err(e) => tracing::warn!("Failed to load data for {}: {}", player.username, e),
+        }
+    }
+
+    /// Save a player's persistent data
+    pub fn save_player_data(&self, player: &Player) {
+        if let Err(e) = self
+            .player_data
+            .save(&player.uuid, &PlayerData::from_player(player))
+        {
+            tracing::warn!("Failed to save data for {}: {}", player.username, e);
+        }
+    }
+
+    /// Save the world and the data of every online player
+    ///
+    /// Only modified chunks are written. With `flush`, chunk writes are
+    /// flushed to disk even if sync-chunk-writes is disabled. Returns the
+    /// number of chunks saved.
+    ///
+    /// Chunks are copied while the world is locked, then encoded and
+    /// written on a blocking thread, so the game keeps ticking meanwhile. A
+    /// save started during another waits for it to finish.
+    pub async fn save_all(&self, flush: bool) -> Result<usize> {
+        let _saving = self.save_lock.lock().await;
+        self.save_unlocked(flush).await
+    }
+
+    /// Save like [`ServerState::save_all`], unless automatic saving is
+    /// disabled by the time earlier saves are done
+    ///
+    /// Returns `None` if nothing was saved.
+    pub async fn autosave(&self) -> Option<Result<usize>> {
+        let _saving = self.save_lock.lock().await;
+        if !self.is_saving_enabled() {
+            return None;
+        }
+        Some(self.save_unlocked(false).await)
+    }
+
+    /// Save the worlds and players while holding the save lock
+    async fn save_unlocked(&self, flush: bool) -> Result<usize> {
+        let mut saved = save_world(&self.world, flush).await?;
+        if let Some(ref nether) = self.nether {
+            saved += save_world(nether, flush).await?;
+        }
+        for player in self.players.get_all_players() {
+            self.save_player_data(&player);
+        }
+        tracing::debug!("Saved {} modified chunk(s)", saved);
+        Ok(saved)
+    }
+
+    /// Back up the world directory
+    ///
+    /// The world is saved and flushed first, and automatic saving is paused
+    /// until the files are copied on a blocking thread. Fails if a backup
+    /// is running already.
+    pub async fn backup(&self) -> Result<BackupReport> {
+        let Some(progress) = self.backups.begin() else {
+            return Err(ServerError::Storage(
+                "A backup is already running".to_string(),
+            ));
+        };
+        let result = match self.save_all(true).await {
+            Ok(_) => self.copy_world(progress).await,
+            Err(e) => Err(e),
+        };
+        self.backups.finish(&result);
+        result
+    }
+
+    /// Copy the world directory into a backup with saving paused
+    async fn copy_world(&self, progress: Arc<BackupProgress>) -> Result<BackupReport> {
+        let was_enabled = self.set_saving_enabled(false);
+        let world_dir = PathBuf::from(&self.config.level_name);
+        let config = self.config.backups.clone();
+        let copied =
+            tokio::task::spawn_blocking(move || backup::create(&world_dir, &config, &progress))
+                .await;
+        self.set_saving_enabled(was_enabled);
+        copied.map_err(|e| ServerError::Storage(format!("Backup task failed: {}", e)))?
+    }
+
+    /// Check whether automatic saving is enabled
+    pub fn is_saving_enabled(&self) -> bool {
+        self.saving_enabled.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable automatic saving, returning the previous setting
+    pub fn set_saving_enabled(&self, enabled: bool) -> bool {
+        self.saving_enabled.swap(enabled, Ordering::Relaxed)
+    }
+
+    /// Let the player on a connection use their held item on a face of a
+    /// block
+    ///
+    /// Using a bed tries to sleep in it, and blocks with a use handler react
+    /// to being used. Otherwise flint and steel lights a nether portal in a
+    /// frame next to the face, and doors, beds, tall grass, armor stands and
+    /// item frames are placed against it.
+    pub async fn use_item_on(&self, addr: &SocketAddr, location: Position, face: i32) {
+        let Some(player) = self.players.get_player_by_addr(addr) else {
+            return;
+        };
+        let is_bed = player.dimension == Dimension::Overworld
+            && self.world.read().await.bed_at(location).is_some();
+        if is_bed {
+            if let Some(Err(e)) = self.use_bed(addr, location).await {
+                tracing::debug!("Cannot sleep: {}", e);
+            }
+            return;
+        }
+
+        let mut world = self.world_in(player.dimension).write().await;
+        let Some(block) = world.get_block(location) else {
+            return;
+        };
+        if let Some(handler) = self.blocks.use_handler(block) {
+            if handler(&mut world, location, block) {
+                self.broadcast_world_changes(&mut world);
+                return;
+            }
+        }
+        let held = player
+            .inventory
+            .held_item()
+            .and_then(|item| self.items.get_item(u32::try_from(item.item).ok()?));
+        if held.is_some_and(|item| item.name == "minecraft:flint_and_steel")
+            && player.game_mode != GameMode::Spectator
+            && adjacent(location, face).is_some_and(|inside| portal::light(&mut world, inside))
+        {
+            self.broadcast_world_changes(&mut world);
+        } else if let Some(kind) = held.and_then(|item| MultiBlockKind::from_item(&item.name)) {
+            self.place_multi_block(&mut world, addr, &player, kind, location, face);
+        } else if let Some(mob) = held.and_then(|item| summon::spawn_egg_mob(&item.name)) {
+            self.use_spawn_egg(&mut world, addr, &player, mob, location, face);
+        } else if player.dimension == Dimension::Overworld {
+            if let Some(item) = held {
+                self.place_decoration(&mut world, addr, &player, &item.name, location, face);
+            }
+        }
+    }
+
+    /// Place the armor stand or item frame a player holds against a face of
+    /// a block, taking it from their hand unless they are in creative mode
+    ///
+    /// Armor stands need the block next to the face and the one above it to
+    /// be air, and item frames just the block next to the face.
+    fn place_decoration(
+        &self,
+        world: &mut World,
+        addr: &SocketAddr,
+        player: &Player,
+        item: &str,
+        location: Position,
+        face: i32,
+    ) {
+        if !matches!(player.game_mode, GameMode::Survival | GameMode::Creative) {
+            return;
+        }
+        let Some(inside) = adjacent(location, face) else {
+            return;
+        };
+        let is_air = |position| world.get_block(position) == Some(0);
+        let above = Position::new(inside.x, inside.y + 1, inside.z);
+        let entity_id = match item {
+            "minecraft:armor_stand" if is_air(inside) && is_air(above) => {
+                let position = EntityPosition {
+                    x: f64::from(inside.x) + 0.5,
+                    y: f64::from(inside.y),
+                    z: f64::from(inside.z) + 0.5,
+                };
+                let yaw = decoration::placed_stand_yaw(player.rotation.yaw);
+                world
+                    .entities_mut()
+                    .add_entity(|id| Box::new(ArmorStandEntity::new(id, position, yaw)))
+            }
+            "minecraft:item_frame" if is_air(inside) => world
+                .entities_mut()
+                .add_entity(|id| Box::new(ItemFrameEntity::new(id, inside, face as u8))),
+            _ => return,
+        };
+        self.broadcast_spawn(world, entity_id);
+        if player.game_mode != GameMode::Creative {
+            self.take_held_item(addr);
+        }
+    }
+
+    /// Spawn the mob of the spawn egg a player holds next to a face of a
+    /// block, taking the egg from their hand unless they are in creative
+    /// mode
+    fn use_spawn_egg(
+        &self,
+        world: &mut World,
+        addr: &SocketAddr,
+        player: &Player,
+        mob: MobType,
+        location: Position,
+        face: i32,
+    ) {
+        if !matches!(player.game_mode, GameMode::Survival | GameMode::Creative) {
+            return;
+        }
+        let Some(inside) = adjacent(location, face) else {
+            return;
+        };
+        let position = EntityPosition {
+            x: f64::from(inside.x) + 0.5,
+            y: f64::from(inside.y),
+            z: f64::from(inside.z) + 0.5,
+        };
+        let summoned = self.summon_in(world, EntityType::Mob(mob), position, &Compound::new());
+        if summoned.is_some() && player.game_mode != GameMode::Creative {
+            self.take_held_item(addr);
+        }
+    }
+
+    /// Place the multi-block a player holds against a face of a block,
+    /// facing the way the player looks, and take it from their hand unless
+    /// they are in creative mode
+    fn place_multi_block(
+        &self,
+        world: &mut World,
+        addr: &SocketAddr,
+        player: &Player,
+        kind: MultiBlockKind,
+        location: Position,
+        face: i32,
+    ) {
+        if !matches!(player.game_mode, GameMode::Survival | GameMode::Creative) {
+            return;
+        }
+        let Some(origin) = adjacent(location, face) else {
+            return;
+        };
+        let facing = Direction::from_yaw(player.rotation.yaw);
+        if let Err(e) = multiblock::place(world, kind, origin, facing) {
+            tracing::debug!("{} cannot place {}: {}", player.username, kind.item(), e);
+            return;
+        }
+        self.broadcast_world_changes(world);
+        if player.game_mode != GameMode::Creative {
+            self.take_held_item(addr);
+        }
+    }
+
+    /// Use up one item in the main hand of the player on a connection,
+    /// showing the change to them and everyone else
+    fn take_held_item(&self, addr: &SocketAddr) {
+        let update = self.players.with_player_mut(addr, |player| {
+            player.inventory.take_one(Hand::Main);
+            let slot = player.inventory.hand_slot(Hand::Main);
+            (
+                player.clone(),
+                container_slot_packet(&mut player.inventory, slot),
+            )
+        });
+        if let Some((player, update)) = update {
+            self.players.send_packet(&player.uuid, update);
+            let held = player.inventory.item_in(Hand::Main);
+            self.broadcast_equipment(&player, &[(EquipmentSlot::MainHand, held)]);
+        }
+    }
+
+    /// Make the player on a connection use the bed at a position
+    ///
+    /// Returns `None` if the connection has no player.
+    pub async fn use_bed(
+        &self,
+        addr: &SocketAddr,
+        position: Position,
+    ) -> Option<std::result::Result<(), SleepError>> {
+        let mut world = self.world.write().await;
+        let (entity_id, result) = self.players.with_player_mut(addr, |player| {
+            (
+                player.entity_id,
+                bed::try_sleep(&mut world, player, position),
+            )
+        })?;
+
+        if let Ok(head) = result {
+            self.broadcast_packet(sleep_metadata(entity_id, Some(head)));
+        }
+        Some(result.map(|_| ()))
+    }
+
+    /// Wake up the player on a connection if they are sleeping
+    pub async fn leave_bed(&self, addr: &SocketAddr) {
+        let mut world = self.world.write().await;
+        let woken = self
+            .players
+            .with_player_mut(addr, |player| {
+                bed::wake_up(&mut world, player).then_some(player.entity_id)
+            })
+            .flatten();
+
+        if let Some(entity_id) = woken {
+            self.broadcast_wake_up(entity_id);
+        }
+    }
+
+    /// Apply the movement keys held by the player on a connection, showing
+    /// everyone whether they sneak
+    ///
+    /// Sneaking takes a riding player out of their vehicle and gives a
+    /// spectator their own view back.
+    pub async fn apply_player_input(&self, addr: &SocketAddr, input: &PlayerInputPacket) {
+        let changed = self
+            .players
+            .with_player_mut(addr, |player| {
+                movement::apply_input(player, input).then(|| movement_metadata(player))
+            })
+            .flatten();
+        if let Some(metadata) = changed {
+            self.broadcast_packet(metadata);
+        }
+        if input.holds(PlayerInputPacket::SNEAK) {
+            self.dismount(addr).await;
+            self.stop_spectating(addr);
+        }
+    }
+
+    /// Start or stop sprinting for the player on a connection
+    ///
+    /// A refused sprint is announced as well, so the client stops too.
+    pub fn set_sprinting(&self, addr: &SocketAddr, sprinting: bool) {
+        let changed = self
+            .players
+            .with_player_mut(addr, |player| {
+                let was_sprinting = player.sprinting;
+                let allowed = movement::set_sprinting(player, sprinting);
+                (!allowed || player.sprinting != was_sprinting).then(|| movement_metadata(player))
+            })
+            .flatten();
+        if let Some(metadata) = changed {
+            self.broadcast_packet(metadata);
+        }
+    }
+
+    /// Start or stop flying for the player on a connection as their client
+    /// reported
+    ///
+    /// A player who may not fly is sent their abilities again, which puts
+    /// their client back on the ground.
+    pub fn set_flying(&self, addr: &SocketAddr, flying: bool) {
+        let refused = self
+            .players
+            .with_player_mut(addr, |player| {
+                if flying && !player.abilities.allow_flying {
+                    return Some((player.uuid, PlayerAbilitiesPacket::from(&player.abilities)));
+                }
+                player.abilities.flying = flying;
+                None
+            })
+            .flatten();
+        if let Some((uuid, abilities)) = refused {
+            self.players.send_packet(&uuid, abilities);
+        }
+    }
+
+    /// Change the abilities of the player on a connection and send them to
+    /// their client
+    ///
+    /// Returns `false` if there is no player on the connection.
+    pub fn update_abilities(
+        &self,
+        addr: &SocketAddr,
+        update: impl FnOnce(&mut PlayerAbilities),
+    ) -> bool {
+        let updated = self.players.with_player_mut(addr, |player| {
+            update(&mut player.abilities);
+            (player.uuid, PlayerAbilitiesPacket::from(&player.abilities))
+        });
+        let Some((uuid, abilities)) = updated else {
+            return false;
+        };
+        self.players.send_packet(&uuid, abilities);
+        true
+    }
+
+    /// Change how fast the player on a connection flies
+    ///
+    /// Players start at [`DEFAULT_FLY_SPEED`](crate::game::player::DEFAULT_FLY_SPEED).
+    /// Returns `false` if there is no player on the connection.
+    pub fn set_fly_speed(&self, addr: &SocketAddr, speed: f32) -> bool {
+        self.update_abilities(addr, |abilities| abilities.fly_speed = speed)
+    }
+
+    /// Change how fast the player on a connection walks, which also widens
+    /// or narrows their field of view
+    ///
+    /// Players start at [`DEFAULT_WALK_SPEED`](crate::game::player::DEFAULT_WALK_SPEED).
+    /// Returns `false` if there is no player on the connection.
+    pub fn set_walk_speed(&self, addr: &SocketAddr, speed: f32) -> bool {
+        self.update_abilities(addr, |abilities| abilities.walk_speed = speed)
+    }
+
+    /// Move and turn the player on a connection as their client reported,
+    /// showing the move to everyone else
+    ///
+    /// Movement that fails [validation](movement::apply_movement) is
+    /// ignored, as is movement of spectators viewing the world through
+    /// another entity.
+    pub async fn move_player(&self, addr: &SocketAddr, movement: Movement) {
+        let moved = self
+            .players
+            .with_player_mut(addr, |player| {
+                if player.spectating.is_some() {
+                    return None;
+                }
+                let from = player.position;
+                let outcome = movement::apply_movement(player, &movement);
+                let shown = ShownMove {
+                    entity_id: player.entity_id,
+                    from,
+                    to: player.position,
+                    rotation: player.rotation,
+                };
+                Some((
+                    player.uuid,
+                    outcome,
+                    shown,
+                    movement_metadata(player),
+                    health_packet(player),
+                    player.dimension,
+                ))
+            })
+            .flatten();
+        let Some((uuid, outcome, shown, metadata, health, dimension)) = moved else {
+            return;
+        };
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                tracing::warn!("Ignoring move: {}", e);
+                return;
+            }
+        };
+        if outcome.stopped_sprinting {
+            self.broadcast_packet(metadata);
+        }
+        if outcome.food_changed {
+            self.players.send_packet(&uuid, health);
+        }
+        self.broadcast_move(&uuid, &movement, &shown);
+        // Farmland only exists in the overworld
+        let landed = outcome.landed.filter(|_| dimension == Dimension::Overworld);
+        if let Some(fall_distance) = landed {
+            let to = shown.to;
+            self.land_on(to.x, to.y, to.z, fall_distance).await;
+        }
+    }
+
+    /// Show everyone but a player how they moved and turned
+    ///
+    /// Short moves are sent relative to where the player was, longer ones
+    /// as the new position.
+    fn broadcast_move(&self, uuid: &McUuid, movement: &Movement, shown: &ShownMove) {
+        let entity_id = VarInt(shown.entity_id);
+        let on_ground = movement.on_ground;
+        let (yaw, pitch) = (shown.rotation.yaw, shown.rotation.pitch);
+        let (from, to) = (shown.from, shown.to);
+        let delta = [(from.x, to.x), (from.y, to.y), (from.z, to.z)]
+            .map(|(from, to)| position_delta(from, to));
+        let turned = movement.rotation.is_some();
+        match (movement.position.is_some(), delta) {
+            (false, _) if turned => self.players.broadcast_except(
+                uuid,
+                UpdateEntityRotationPacket {
+                    entity_id,
+                    yaw: protocol_angle(yaw),
+                    pitch: protocol_angle(pitch),
+                    on_ground,
+                },
+            ),
+            (false, _) => return,
+            (true, [Some(dx), Some(dy), Some(dz)]) if turned => self.players.broadcast_except(
+                uuid,
+                UpdateEntityPositionAndRotationPacket {
+                    entity_id,
+                    delta: [dx, dy, dz],
+                    yaw: protocol_angle(yaw),
+                    pitch: protocol_angle(pitch),
+                    on_ground,
+                },
+            ),
+            (true, [Some(dx), Some(dy), Some(dz)]) => self.players.broadcast_except(
+                uuid,
+                UpdateEntityPositionPacket {
+                    entity_id,
+                    delta: [dx, dy, dz],
+                    on_ground,
+                },
+            ),
+            (true, _) => self.players.broadcast_except(
+                uuid,
+                EntityPositionSyncPacket {
+                    entity_id,
+                    x: to.x,
+                    y: to.y,
+                    z: to.z,
+                    velocity: [0.0; 3],
+                    yaw,
+                    pitch,
+                    on_ground,
+                },
+            ),
+        }
+        if turned {
+            self.players
+                .broadcast_except(uuid, head_rotation_packet(shown.entity_id, yaw));
+        }
+    }
+
+    /// Let a player who fell onto the block below a position trample it
+    async fn land_on(&self, x: f64, y: f64, z: f64, fall_distance: f32) {
+        // Farmland is slightly lower than a full block
+        let below = Position::new(x.floor() as i32, (y - 0.2).floor() as i32, z.floor() as i32);
+        let mut world = self.world.write().await;
+        if let Some(uprooted) = world.trample(below, fall_distance) {
+            for broken in uprooted {
+                world.drop_items(broken.position, broken.drops);
+            }
+        }
+    }
+
+    /// Break a block for the player on a connection
+    ///
+    /// Creative players break blocks as soon as they start digging and get
+    /// no drops. Survival players break blocks that break instantly as soon
+    /// as they start digging, and others once they finish, if they dug for
+    /// long enough given the block and their tool. Digging too far away, in
+    /// adventure or spectator mode, or unbreakable blocks outside creative
+    /// mode is ignored.
+    pub async fn dig_block(&self, addr: &SocketAddr, position: Position, finished: bool) {
+        let Some(player) = self.players.get_player_by_addr(addr) else {
+            return;
+        };
+        let creative = player.game_mode == GameMode::Creative;
+        if (creative && finished)
+            || !matches!(player.game_mode, GameMode::Creative | GameMode::Survival)
+        {
+            return;
+        }
+        let (dx, dy, dz) = (
+            f64::from(position.x) + 0.5 - player.position.x,
+            f64::from(position.y) + 0.5 - (player.position.y + EYE_HEIGHT),
+            f64::from(position.z) + 0.5 - player.position.z,
+        );
+        if (dx * dx + dy * dy + dz * dz).sqrt() > MAX_DIG_DISTANCE {
+            tracing::debug!("{} dug a block too far away", player.username);
+            return;
+        }
+
+        let mut world = self.world_in(player.dimension).write().await;
+        if !creative && !self.dug_long_enough(addr, &player, &world, position, finished) {
+            return;
+        }
+        // Entities only live in the overworld
+        let drops = !creative && world.dimension() == Dimension::Overworld;
+        for broken in world.break_block(position) {
+            if drops {
+                world.drop_items(broken.position, broken.drops);
+            }
+        }
+    }
+
+    /// Whether a survival player may break a block now
+    ///
+    /// Starting to dig is remembered, and only breaks blocks that break
+    /// instantly. Finishing breaks the block if the player started digging
+    /// it long enough ago.
+    fn dug_long_enough(
+        &self,
+        addr: &SocketAddr,
+        player: &Player,
+        world: &World,
+        position: Position,
+        finished: bool,
+    ) -> bool {
+        let Some(block) = world
+            .get_block(position)
+            .and_then(|block| self.blocks.get_block(block))
+        else {
+            return false;
+        };
+        let held = player.inventory.held_item();
+        let tool = held
+            .and_then(|item| self.items.get_item(item.item as u32))
+            .map(|info| info.name.as_str());
+        let efficiency = held.map_or(0, |item| item.enchantments().level(Enchantment::Efficiency));
+        let Some(ticks) = mining::break_ticks(block, tool, efficiency) else {
+            return false;
+        };
+
+        let age = world.world_age();
+        if !finished {
+            self.players
+                .with_player_mut(addr, |player| player.digging = Some((position, age)));
+            return ticks == 0;
+        }
+        let dug = match player.digging {
+            Some((dug_at, started)) if dug_at == position => age - started + 1,
+            _ => 0,
+        };
+        if (dug as f32) < ticks as f32 * mining::BREAK_TOLERANCE {
+            tracing::debug!("{} finished digging a block too early", player.username);
+            return false;
+        }
+        true
+    }
+
+    /// Send the blocks that changed and the entities that were added or
+    /// removed in a world to all players
+    ///
+    /// Block changes and sounds only go to players in the world's dimension.
+    pub fn broadcast_world_changes(&self, world: &mut World) {
+        let dimension = world.dimension();
+        for death in world.take_deaths() {
+            self.events.publish(&ServerEvent::MobKilled {
+                entity_type: EntityType::Mob(death.kind).identifier().to_string(),
+                killer: death.killer,
+                drops: death.drops,
+                experience: death.experience,
+            });
+        }
+        for entity_id in world.take_spawned() {
+            self.broadcast_spawn(world, entity_id);
+        }
+        for changes in world.take_block_changes() {
+            if let [(position, block_id)] = changes.blocks[..] {
+                self.players.broadcast_in(
+                    dimension,
+                    BlockChangePacket {
+                        position,
+                        block_id: VarInt(block_id as i32),
+                    },
+                );
+            } else {
+                self.players.broadcast_in(
+                    dimension,
+                    UpdateSectionBlocksPacket::new(changes.section, &changes.blocks),
+                );
+            }
+        }
+        for sound in world.take_sounds() {
+            self.players.broadcast_in(
+                dimension,
+                SoundEffectPacket::block(sound.sound, sound.position, sound.pitch, sound.seed),
+            );
+        }
+        for entity_id in world.take_grown_up() {
+            if let Some(mob) = world
+                .entities()
+                .get_entity(entity_id)
+                .and_then(|e| e.as_mob())
+            {
+                self.broadcast_packet(age_metadata(mob));
+            }
+        }
+        let despawned = world.take_despawned();
+        if !despawned.is_empty() {
+            self.broadcast_packet(RemoveEntitiesPacket {
+                entity_ids: despawned.into_iter().map(VarInt).collect(),
+            });
+        }
+    }
+
+    /// Spawn an empty vehicle and show it to all players
+    pub async fn spawn_vehicle(&self, kind: VehicleType, x: f64, y: f64, z: f64) -> EntityId {
+        let position = EntityPosition { x, y, z };
+        let mut world = self.world.write().await;
+        let entity_id = world
+            .entities_mut()
+            .add_entity(|id| Box::new(VehicleEntity::new(id, kind, position)));
+        self.broadcast_spawn(&world, entity_id);
+        entity_id
+    }
+
+    /// Spawn primed TNT or a creeper and show it to all players
+    pub async fn spawn_explosive(&self, kind: ExplosiveKind, x: f64, y: f64, z: f64) -> EntityId {
+        let position = EntityPosition { x, y, z };
+        let mut world = self.world.write().await;
+        let entity_id = world
+            .entities_mut()
+            .add_entity(|id| Box::new(ExplosiveEntity::new(id, kind, position)));
+        self.broadcast_spawn(&world, entity_id);
+        entity_id
+    }
+
+    /// Spawn a mob and show it to all players
+    pub async fn spawn_mob(&self, kind: MobType, x: f64, y: f64, z: f64) -> EntityId {
+        let position = EntityPosition { x, y, z };
+        let mut world = self.world.write().await;
+        let entity_id = world
+            .entities_mut()
+            .add_entity(|id| Box::new(MobEntity::new(id, kind, position)));
+        self.broadcast_spawn(&world, entity_id);
+        entity_id
+    }
+
+    /// Let the player on a connection attack an entity
+    ///
+    /// Players out of reach cannot attack, and spectators
+    /// [spectate](Self::spectate_entity) the entity instead. Mobs killed this
+    /// way die on the next tick, crediting the player with the kill.
+    pub async fn attack_entity(&self, addr: &SocketAddr, target: EntityId) {
+        let Some(player) = self.players.get_player_by_addr(addr) else {
+            return;
+        };
+        if player.game_mode == GameMode::Spectator {
+            self.spectate_entity(addr, target).await;
+            return;
+        }
+        if matches!(
+            self.entity_type(target).await,
+            Some(EntityType::ArmorStand | EntityType::ItemFrame)
+        ) {
+            if let Some(Err(e)) = self.hit_decoration(addr, target).await {
+                tracing::debug!("{} cannot hit {}: {}", player.username, target, e);
+            }
+            return;
+        }
+        let mut world = self.world.write().await;
+        let Some(mob) = world
+            .entities_mut()
+            .get_entity_mut(target)
+            .and_then(|entity| entity.as_mob_mut())
+        else {
+            return;
+        };
+        let position = mob.position();
+        let (dx, dy, dz) = (
+            position.x - player.position.x,
+            position.y - player.position.y,
+            position.z - player.position.z,
+        );
+        if (dx * dx + dy * dy + dz * dz).sqrt() > MAX_ATTACK_DISTANCE {
+            tracing::debug!("{} attacked an entity too far away", player.username);
+            return;
+        }
+        let weapon = player
+            .inventory
+            .held_item()
+            .map(|item| *item.enchantments())
+            .unwrap_or_default();
+        let damage = FIST_DAMAGE + enchantment::attack_bonus(&weapon, Some(mob.kind()));
+        mob.damage(damage, Some(player.uuid));
+    }
+
+    /// Summon an entity of a [summonable](summon::summonable) type in the
+    /// overworld, applying the supported tags of `nbt`, and show it to all
+    /// players
+    ///
+    /// Returns `None` if the entity cannot be created.
+    pub async fn summon(
+        &self,
+        kind: EntityType,
+        position: EntityPosition,
+        nbt: &Compound,
+    ) -> Option<EntityId> {
+        let mut world = self.world.write().await;
+        self.summon_in(&mut world, kind, position, nbt)
+    }
+
+    /// Summon an entity in a world and show it to all players
+    fn summon_in(
+        &self,
+        world: &mut World,
+        kind: EntityType,
+        position: EntityPosition,
+        nbt: &Compound,
+    ) -> Option<EntityId> {
+        let entity_id = world
+            .entities_mut()
+            .try_add_entity(|id| summon::create(kind, id, position, nbt, &self.blocks))?;
+        self.broadcast_spawn(world, entity_id);
+        Some(entity_id)
+    }
+
+    /// Type of an entity, or `None` if it does not exist
+    pub async fn entity_type(&self, entity_id: EntityId) -> Option<EntityType> {
+        let world = self.world.read().await;
+        world
+            .entities()
+            .get_entity(entity_id)
+            .map(|e| e.entity_type())
+    }
+
+    /// Spawn an empty armor stand facing a yaw and show it to all players
+    pub async fn spawn_armor_stand(&self, x: f64, y: f64, z: f64, yaw: f32) -> EntityId {
+        let position = EntityPosition { x, y, z };
+        let mut world = self.world.write().await;
+        let entity_id = world
+            .entities_mut()
+            .add_entity(|id| Box::new(ArmorStandEntity::new(id, position, yaw)));
+        self.broadcast_spawn(&world, entity_id);
+        entity_id
+    }
+
+    /// Hang an empty item frame in a block, facing away from the block face
+    /// `facing`, and show it to all players
+    pub async fn spawn_item_frame(&self, block: Position, facing: u8) -> EntityId {
+        let mut world = self.world.write().await;
+        let entity_id = world
+            .entities_mut()
+            .add_entity(|id| Box::new(ItemFrameEntity::new(id, block, facing)));
+        self.broadcast_spawn(&world, entity_id);
+        entity_id
+    }
+
+    /// Change an armor stand's pose, size or arms, showing the change to all
+    /// players
+    ///
+    /// Returns `false` if the entity is not an armor stand.
+    pub async fn update_armor_stand(
+        &self,
+        entity_id: EntityId,
+        f: impl FnOnce(&mut ArmorStandEntity),
+    ) -> bool {
+        let mut world = self.world.write().await;
+        let Some(entity) = world.entities_mut().get_entity_mut(entity_id) else {
+            return false;
+        };
+        let Some(stand) = entity.as_armor_stand_mut() else {
+            return false;
+        };
+        f(stand);
+        if let Some(metadata) = decoration_metadata(entity.as_ref()) {
+            self.broadcast_packet(metadata);
+        }
+        true
+    }
+
+    /// Let the player on a connection use an armor stand or item frame with
+    /// the item in a hand
+    ///
+    /// `height` is how far above an armor stand's feet the player clicked.
+    /// Returns `None` if the connection has no player.
+    pub async fn use_decoration(
+        &self,
+        addr: &SocketAddr,
+        target: EntityId,
+        hand: Hand,
+        height: f32,
+    ) -> Option<std::result::Result<(), DecorationError>> {
+        let items = &self.items;
+        let mut world = self.world.write().await;
+        let (result, player, updates) = self.players.with_player_mut(addr, |player| {
+            let before = player.inventory.clone();
+            let held_slot = player
+                .inventory
+                .item_in(hand)
+                .and_then(|item| items.get_item(u32::try_from(item.item).ok()?))
+                .map_or(EquipmentSlot::MainHand, |item| {
+                    EquipmentSlot::for_item(&item.name)
+                });
+            let result = decoration::use_decoration(
+                world.entities_mut(),
+                player,
+                target,
+                hand,
+                held_slot,
+                height,
+            );
+            let updates = changed_slot_packets(&before, &mut player.inventory);
+            (result, player.clone(), updates)
+        })?;
+        let change = result.map(|change| {
+            self.show_decoration_change(&mut world, target, &change, &player, updates);
+        });
+        Some(change)
+    }
+
+    /// Let the player on a connection hit an armor stand or item frame
+    ///
+    /// Returns `None` if the connection has no player.
+    pub async fn hit_decoration(
+        &self,
+        addr: &SocketAddr,
+        target: EntityId,
+    ) -> Option<std::result::Result<(), DecorationError>> {
+        let items = &self.items;
+        let mut world = self.world.write().await;
+        let (result, player, updates) = self.players.with_player_mut(addr, |player| {
+            let before = player.inventory.clone();
+            let result = decoration::hit_decoration(world.entities_mut(), player, target, |item| {
+                max_stack_size(items, item)
+            });
+            let updates = changed_slot_packets(&before, &mut player.inventory);
+            (result, player.clone(), updates)
+        })?;
+        let change = result.map(|change| {
+            self.show_decoration_change(&mut world, target, &change, &player, updates);
+        });
+        Some(change)
+    }
+
+    /// Send a player the inventory slots that changed when they used or hit
+    /// a decoration, and show everyone how the decoration changed
+    fn show_decoration_change(
+        &self,
+        world: &mut World,
+        target: EntityId,
+        change: &DecorationChange,
+        player: &Player,
+        updates: Vec<SetContainerSlotPacket>,
+    ) {
+        if !updates.is_empty() {
+            for update in updates {
+                self.players.send_packet(&player.uuid, update);
+            }
+            let equipment = [Hand::Main, Hand::Off]
+                .map(|hand| (hand.equipment_slot(), player.inventory.item_in(hand)));
+            self.broadcast_equipment(player, &equipment);
+        }
+        let Some(entity) = world.entities().get_entity(target) else {
+            return;
+        };
+        if !entity.is_alive() {
+            let position = entity.position();
+            world.drop_items_at(position, change.broken.iter().cloned().collect());
+            self.broadcast_world_changes(world);
+            return;
+        }
+        if let Some(stand) = entity.as_armor_stand() {
+            let equipment: Vec<_> = change
+                .stand_slots
+                .iter()
+                .map(|&slot| (slot, stand.equipment(slot)))
+                .collect();
+            if !equipment.is_empty() {
+                self.broadcast_packet(equipment_packet(target, &equipment));
+            }
+        }
+        if change.frame_changed {
+            if let Some(metadata) = decoration_metadata(entity) {
+                self.broadcast_packet(metadata);
+            }
+        }
+    }
+
+    /// Let the player on a connection feed an animal an item
+    ///
+    /// Animals that go into love mode show hearts to all players. Returns
+    /// `None` if the connection has no player.
+    pub async fn feed_animal(
+        &self,
+        addr: &SocketAddr,
+        animal: EntityId,
+        item: &str,
+    ) -> Option<std::result::Result<Fed, BreedError>> {
+        let player = self.players.get_player_by_addr(addr)?;
+        if player.game_mode == GameMode::Spectator {
+            return Some(Err(BreedError::Spectating));
+        }
+        let mut world = self.world.write().await;
+        let position = world.entities().get_entity(animal)?.position();
+        let (dx, dy, dz) = (
+            position.x - player.position.x,
+            position.y - player.position.y,
+            position.z - player.position.z,
+        );
+        if (dx * dx + dy * dy + dz * dz).sqrt() > breeding::MAX_FEED_DISTANCE {
+            return Some(Err(BreedError::TooFar));
+        }
+        let result = breeding::feed(world.entities_mut(), animal, item);
+        if result == Ok(Fed::InLove) {
+            self.broadcast_packet(EntityEventPacket {
+                entity_id: animal,
+                event: EntityEventPacket::IN_LOVE,
+            });
+        }
+        Some(result)
+    }
+
+    /// Packets showing every entity and who rides which vehicle, sent to
+    /// joining players
+    pub async fn entity_packets(&self) -> Vec<Box<dyn DynPacket>> {
+        let world = self.world.read().await;
+        let mut packets: Vec<Box<dyn DynPacket>> = Vec::new();
+        for entity in world.entities().entities() {
+            if let Some(spawn) = spawn_packet(entity) {
+                packets.push(Box::new(spawn));
+            }
+            if let Some(baby) = entity.as_mob().filter(|mob| mob.is_baby()) {
+                packets.push(Box::new(age_metadata(baby)));
+            }
+            if entity.custom_name().is_some() {
+                packets.push(Box::new(custom_name_metadata(entity)));
+            }
+            if let Some(metadata) = decoration_metadata(entity) {
+                packets.push(Box::new(metadata));
+            }
+            if let Some(stand) = entity.as_armor_stand() {
+                let equipment = stand.visible_equipment();
+                if !equipment.is_empty() {
+                    packets.push(Box::new(equipment_packet(entity.entity_id(), &equipment)));
+                }
+            }
+            if let Some(vehicle) = entity.as_vehicle().filter(|v| !v.passengers().is_empty()) {
+                packets.push(Box::new(passengers_packet(vehicle)));
+            }
+        }
+        packets
+    }
+
+    /// Show a new entity to all players
+    fn broadcast_spawn(&self, world: &World, entity_id: EntityId) {
+        let Some(entity) = world.entities().get_entity(entity_id) else {
+            return;
+        };
+        if let Some(spawn) = spawn_packet(entity) {
+            self.broadcast_packet(spawn);
+        }
+        if let Some(baby) = entity.as_mob().filter(|mob| mob.is_baby()) {
+            self.broadcast_packet(age_metadata(baby));
+        }
+        if entity.custom_name().is_some() {
+            self.broadcast_packet(custom_name_metadata(entity));
+        }
+        if let Some(metadata) = decoration_metadata(entity) {
+            self.broadcast_packet(metadata);
+        }
+        if let Some(stand) = entity.as_armor_stand() {
+            let equipment = stand.visible_equipment();
+            if !equipment.is_empty() {
+                self.broadcast_packet(equipment_packet(entity_id, &equipment));
+            }
+        }
+    }
+
+    /// Name an entity, or remove its name with `None`, showing the change
+    /// to all players
+    ///
+    /// Returns `false` if the entity does not exist.
+    pub async fn set_custom_name(&self, entity_id: EntityId, name: Option<CustomName>) -> bool {
+        let mut world = self.world.write().await;
+        let Some(entity) = world.entities_mut().get_entity_mut(entity_id) else {
+            return false;
+        };
+        entity.set_custom_name(name);
+        self.broadcast_packet(custom_name_metadata(entity.as_ref()));
+        true
+    }
+
+    /// Packets showing the equipment of every player but one, sent to
+    /// joining players
+    pub fn equipment_packets(&self, except: &McUuid) -> Vec<SetEquipmentPacket> {
+        self.players
+            .map_players(|player| {
+                let equipment = player.inventory.visible_equipment();
+                (player.uuid != *except && !equipment.is_empty())
+                    .then(|| equipment_packet(player.entity_id, &equipment))
+            })
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Show a player's equipment to everyone else
+    pub fn broadcast_equipment(
+        &self,
+        player: &Player,
+        equipment: &[(EquipmentSlot, Option<ItemStack>)],
+    ) {
+        if !equipment.is_empty() {
+            self.players
+                .broadcast_except(&player.uuid, equipment_packet(player.entity_id, equipment));
+        }
+    }
+
+    /// Select a hotbar slot of the player on a connection
+    ///
+    /// Other players see the newly held item.
+    pub fn select_hotbar_slot(&self, addr: &SocketAddr, slot: i16) {
+        let Ok(slot) = u8::try_from(slot) else {
+            return;
+        };
+        let changed = self
+            .players
+            .with_player_mut(addr, |player| {
+                if player.eating.is_some_and(|(hand, _)| hand == Hand::Main) {
+                    player.eating = None;
+                }
+                player.inventory.select(slot).then(|| player.clone())
+            })
+            .flatten();
+        if let Some(player) = changed {
+            let held = player.inventory.held_item();
+            self.broadcast_equipment(&player, &[(EquipmentSlot::MainHand, held)]);
+        }
+    }
+
+    /// Put an item into an inventory slot of the player on a connection
+    ///
+    /// Other players see the change if the slot is part of the player's
+    /// visible equipment. Invalid stacks and slots are ignored.
+    pub fn set_inventory_slot(&self, addr: &SocketAddr, slot: usize, item: Option<ItemStack>) {
+        if item.is_some_and(|item| !item.is_valid()) {
+            return;
+        }
+        let changed = self
+            .players
+            .with_player_mut(addr, |player| {
+                let changed = player.inventory.set(slot, item)?;
+                Some((player.clone(), changed))
+            })
+            .flatten();
+        if let Some((player, changed)) = changed {
+            let item = player.inventory.equipment(changed);
+            self.broadcast_equipment(&player, &[(changed, item)]);
+        }
+    }
+
+    /// Swap the items in the hands of the player on a connection
+    pub fn swap_hands(&self, addr: &SocketAddr) {
+        let swapped = self
+            .players
+            .with_player_mut(addr, |player| {
+                if player.game_mode == GameMode::Spectator || !player.inventory.swap_hands() {
+                    return None;
+                }
+                player.eating = None;
+                let main = player.inventory.hand_slot(Hand::Main);
+                let updates = [
+                    container_slot_packet(&mut player.inventory, main),
+                    container_slot_packet(&mut player.inventory, OFFHAND_SLOT),
+                ];
+                Some((player.clone(), updates))
+            })
+            .flatten();
+        if let Some((player, updates)) = swapped {
+            for update in updates {
+                self.players.send_packet(&player.uuid, update);
+            }
+            let equipment = [Hand::Main, Hand::Off]
+                .map(|hand| (hand.equipment_slot(), player.inventory.item_in(hand)));
+            self.broadcast_equipment(&player, &equipment);
+        }
+    }
+
+    /// Start using the item in a hand of the player on a connection
+    ///
+    /// Only food can be used so far: the player starts eating it if they
+    /// are hungry, and finishes in [`food::EAT_TICKS`] ticks.
+    pub fn use_item(&self, addr: &SocketAddr, hand: Hand) {
+        let items = &self.items;
+        self.players.with_player_mut(addr, |player| {
+            let edible = player
+                .inventory
+                .item_in(hand)
+                .and_then(|item| items.get_item(u32::try_from(item.item).ok()?))
+                .and_then(|info| food::food_value(&info.name))
+                .is_some();
+            if edible && player.can_eat() && player.game_mode != GameMode::Spectator {
+                player.eating = Some((hand, food::EAT_TICKS));
+            }
+        });
+    }
+
+    /// Stop the player on a connection using an item
+    pub fn release_item(&self, addr: &SocketAddr) {
+        self.players
+            .with_player_mut(addr, |player| player.eating = None);
+    }
+
+    /// Apply a slot change made by a player in creative mode
+    ///
+    /// Changes from players in other game modes, and drops (slot -1), are
+    /// ignored.
+    pub fn set_creative_slot(&self, addr: &SocketAddr, slot: i16, item: Option<ItemStack>) {
+        let creative = self
+            .players
+            .with_player_mut(addr, |player| player.game_mode == GameMode::Creative);
+        if let (Some(true), Ok(slot)) = (creative, usize::try_from(slot)) {
+            self.set_inventory_slot(addr, slot, item);
+        }
+    }
+
+    /// Light the fuses of creepers near players and apply the explosions of
+    /// entities that blew up
+    pub fn tick_explosions(&self, world: &mut World) {
+        let mut targets = Vec::new();
+        self.players.for_each_player_mut(|player| {
+            if matches!(player.game_mode, GameMode::Survival | GameMode::Adventure) {
+                let position = player.position;
+                targets.push(EntityPosition {
+                    x: position.x,
+                    y: position.y,
+                    z: position.z,
+                });
+            }
+        });
+        explosive::ignite_creepers(world.entities_mut(), &targets);
+
+        for explosion in world.take_explosions() {
+            self.apply_explosion(world, &explosion);
+        }
+    }
+
+    /// Destroy the blocks an explosion reaches, hurt the players near it and
+    /// show it to everyone
+    fn apply_explosion(&self, world: &mut World, explosion: &Explosion) {
+        let outcome = world.explode(explosion, &self.blocks);
+        for entity_id in outcome.primed {
+            self.broadcast_spawn(world, entity_id);
+        }
+
+        // Spectators are not affected, creative players only pushed
+        let difficulty = world.difficulty();
+        let mut hits = Vec::new();
+        let world = &*world;
+        self.players.for_each_player_mut(|player| {
+            let position = EntityPosition {
+                x: player.position.x,
+                y: player.position.y,
+                z: player.position.z,
+            };
+            let impact = (player.game_mode != GameMode::Spectator)
+                .then(|| explosion.impact(world, &self.blocks, position))
+                .flatten();
+            let hurt = matches!(player.game_mode, GameMode::Survival | GameMode::Adventure);
+            let health = match impact {
+                Some(impact) if hurt => {
+                    let damage = if explosion.by_mob {
+                        difficulty.scale_mob_damage(impact.damage)
+                    } else {
+                        impact.damage
+                    };
+                    let armor: Vec<Enchantments> = player
+                        .inventory
+                        .armor()
+                        .map(|item| *item.enchantments())
+                        .collect();
+                    let damage =
+                        enchantment::apply_protection(damage, DamageKind::Explosion, &armor);
+                    player.set_health(player.health - damage);
+                    Some(health_packet(player))
+                }
+                _ => None,
+            };
+            hits.push((player.uuid, impact.map(|impact| impact.knockback), health));
+        });
+        for (uuid, knockback, health) in hits {
+            self.players
+                .send_packet(&uuid, explode_packet(explosion, knockback));
+            if let Some(health) = health {
+                self.players.send_packet(&uuid, health);
+            }
+        }
+    }
+
+    /// Put the player on a connection in a vehicle
+    ///
+    /// Returns `None` if the connection has no player.
+    pub async fn mount_vehicle(
+        &self,
+        addr: &SocketAddr,
+        vehicle_id: EntityId,
+    ) -> Option<std::result::Result<(), vehicle::RideError>> {
+        let mut world = self.world.write().await;
+        let result = self.players.with_player_mut(addr, |player| {
+            vehicle::mount(world.entities_mut(), player, vehicle_id)
+        })?;
+        if let Ok(left) = result {
+            for id in left.into_iter().chain([vehicle_id]) {
+                self.broadcast_passengers(&world, id);
+            }
+        }
+        Some(result.map(|_| ()))
+    }
+
+    /// Whether an entity is a mob that trades with players
+    pub async fn is_villager(&self, entity_id: EntityId) -> bool {
+        let world = self.world.read().await;
+        !villager_offers(&world, entity_id).is_empty()
+    }
+
+    /// Open the trading screen of a villager for the player on a connection
+    ///
+    /// Spectators, players out of reach and mobs without trades are ignored.
+    /// A trading screen the player already had open is closed first.
+    pub async fn open_trading(&self, addr: &SocketAddr, villager: EntityId) {
+        let world = self.world.read().await;
+        let Some(mob) = world
+            .entities()
+            .get_entity(villager)
+            .and_then(|entity| entity.as_mob())
+            .filter(|mob| !mob.offers().is_empty())
+        else {
+            return;
+        };
+        let position = mob.position();
+        let offers = merchant_offers(mob.offers());
+        drop(world);
+        let stack_size = |item| self.max_stack_size(item);
+        let opened = self
+            .players
+            .with_player_mut(addr, |player| {
+                let (dx, dy, dz) = (
+                    position.x - player.position.x,
+                    position.y - player.position.y,
+                    position.z - player.position.z,
+                );
+                if player.game_mode == GameMode::Spectator
+                    || (dx * dx + dy * dy + dz * dz).sqrt() > villager::MAX_TRADE_DISTANCE
+                {
+                    return None;
+                }
+                if let Some(mut previous) = player.trading.take() {
+                    lose_items(
+                        &player.username,
+                        &previous.return_payment(&mut player.inventory, stack_size),
+                    );
+                }
+                let window_id = player.next_window_id();
+                player.trading = Some(Trading::new(window_id, villager));
+                Some((player.uuid, window_id))
+            })
+            .flatten();
+        let Some((uuid, window_id)) = opened else {
+            return;
+        };
+        self.players.send_packet(
+            &uuid,
+            OpenScreenPacket {
+                window_id: VarInt(window_id),
+                window_type: VarInt(OpenScreenPacket::MERCHANT),
+                title: JsonTextComponent::translatable("entity.minecraft.villager", "Villager"),
+            },
+        );
+        self.players.send_packet(
+            &uuid,
+            MerchantOffersPacket {
+                window_id: VarInt(window_id),
+                offers,
+                level: VarInt(1),
+                experience: VarInt(0),
+                regular_villager: true,
+                can_restock: false,
+            },
+        );
+    }
+
+    /// Pick a trade on the trading screen of the player on a connection
+    pub async fn select_trade(&self, addr: &SocketAddr, index: usize) {
+        let world = self.world.read().await;
+        let stack_size = |item| self.max_stack_size(item);
+        let update = self
+            .players
+            .with_player_mut(addr, |player| {
+                let trading = player.trading.as_mut()?;
+                let offers = villager_offers(&world, trading.villager);
+                let left_over = trading.select(index, offers, &mut player.inventory, stack_size);
+                lose_items(&player.username, &left_over);
+                Some((player.uuid, merchant_content_packet(player, offers)?))
+            })
+            .flatten();
+        if let Some((uuid, packet)) = update {
+            self.players.send_packet(&uuid, packet);
+        }
+    }
+
+    /// Handle a click in the trading screen of the player on a connection
+    ///
+    /// Clicking the result makes the selected trade, putting the result
+    /// into the inventory if it fits. Any other click is undone, and the
+    /// whole screen is sent again so the client matches the server.
+    pub async fn click_merchant_slot(&self, addr: &SocketAddr, window_id: i32, slot: i16) {
+        let mut world = self.world.write().await;
+        let items = &self.items;
+        let update = self
+            .players
+            .with_player_mut(addr, |player| {
+                let trading = player
+                    .trading
+                    .as_mut()
+                    .filter(|t| t.window_id == window_id)?;
+                let villager = trading.villager;
+                let offers = world
+                    .entities_mut()
+                    .get_entity_mut(villager)
+                    .and_then(|entity| entity.as_mob_mut())
+                    .map(MobEntity::offers_mut)
+                    .unwrap_or_default();
+                if slot == villager::RESULT_SLOT {
+                    trading.trade(offers, &mut player.inventory, |item| {
+                        max_stack_size(items, item)
+                    });
+                }
+                let offers = villager_offers(&world, villager);
+                Some((player.uuid, merchant_content_packet(player, offers)?))
+            })
+            .flatten();
+        if let Some((uuid, packet)) = update {
+            self.players.send_packet(&uuid, packet);
+        }
+    }
+
+    /// Handle a click in a window of the player on a connection
+    ///
+    /// Clicks in the trading screen are applied, and the screen resent.
+    /// The server does not move items around the player's own inventory yet,
+    /// so a click there that the client expects to change anything, or that
+    /// was based on contents the client has not caught up with, gets the
+    /// whole inventory resent to undo the client's prediction.
+    pub async fn click_container(&self, addr: &SocketAddr, click: &ClickContainerPacket) {
+        if click.window_id.0 != SetContainerSlotPacket::PLAYER_INVENTORY {
+            self.click_merchant_slot(addr, click.window_id.0, click.slot)
+                .await;
+            return;
+        }
+        let resync = self
+            .players
+            .with_player_mut(addr, |player| {
+                if !click_desynced(&player.inventory, click) {
+                    return None;
+                }
+                tracing::debug!("Resynchronizing the inventory of {}", player.username);
+                Some((player.uuid, inventory_content_packet(&mut player.inventory)))
+            })
+            .flatten();
+        if let Some((uuid, packet)) = resync {
+            self.players.send_packet(&uuid, packet);
+        }
+    }
+
+    /// Close the trading screen of the player on a connection, returning
+    /// the payment to their inventory
+    ///
+    /// `None` closes whichever screen is open.
+    pub fn close_container(&self, addr: &SocketAddr, window_id: Option<i32>) {
+        let stack_size = |item| self.max_stack_size(item);
+        self.players.with_player_mut(addr, |player| {
+            let is_open = player
+                .trading
+                .as_ref()
+                .is_some_and(|trading| window_id.is_none_or(|id| id == trading.window_id));
+            if let Some(mut trading) = player.trading.take_if(|_| is_open) {
+                let left_over = trading.return_payment(&mut player.inventory, stack_size);
+                lose_items(&player.username, &left_over);
+            }
+        });
+    }
+
+    /// Maximum stack size of an item, by item ID
+    fn max_stack_size(&self, item: i32) -> i32 {
+        max_stack_size(&self.items, item)
+    }
+
+    /// World of a dimension, or the overworld if the nether is disabled
+    pub fn world_in(&self, dimension: Dimension) -> &Arc<RwLock<World>> {
+        match (dimension, &self.nether) {
+            (Dimension::Nether, Some(nether)) => nether,
+            _ => &self.world,
+        }
+    }
+
+    /// Update the nether and send its changes to the players in it
+    pub async fn tick_nether(&self, delta_time: f64, area: &SimulationArea) {
+        if let Some(nether) = &self.nether {
+            let mut nether = nether.write().await;
+            nether.update(delta_time, area);
+            self.broadcast_world_changes(&mut nether);
+        }
+    }
+
+    /// Send players who stood in a nether portal long enough through it
+    ///
+    /// Nothing happens while the nether is disabled. Players arrive at a
+    /// portal in the other dimension and get the chunks around it at once.
+    pub async fn tick_portals(&self, overworld: &mut World) {
+        let Some(nether) = &self.nether else {
+            return;
+        };
+        let mut nether = nether.write().await;
+        let view_distance = self.settings().view_distance;
+        let mut arrivals = Vec::new();
+        self.players.for_each_player_mut(|player| {
+            let world: &World = match player.dimension {
+                Dimension::Overworld => overworld,
+                Dimension::Nether => &nether,
+            };
+            let feet = Position::new(
+                player.position.x.floor() as i32,
+                player.position.y.floor() as i32,
+                player.position.z.floor() as i32,
+            );
+            let in_portal = player.game_mode != GameMode::Spectator
+                && world.get_block(feet).is_some_and(portal::is_portal);
+            let wait = match player.game_mode {
+                GameMode::Creative => 1,
+                _ => portal::PORTAL_WAIT,
+            };
+            if !player.portal.tick(in_portal, wait) {
+                return;
+            }
+            let from = player.dimension;
+            let target: &mut World = match from.portal_destination() {
+                Dimension::Overworld => overworld,
+                Dimension::Nether => &mut nether,
+            };
+            let position = player.position;
+            let arrival = portal::destination(target, from, position.x, position.y, position.z);
+            player.dimension = target.dimension();
+            player.digging = None;
+            player.set_position(
+                f64::from(arrival.x) + 0.5,
+                f64::from(arrival.y),
+                f64::from(arrival.z) + 0.5,
+            );
+            tracing::debug!("{} travelled to {}", player.username, target.name());
+            let packets = self.dimension_change_packets(player, target.dimension());
+            let center = ChunkPosition::from_world_coords(player.position.x, player.position.z);
+            let radius = i32::from(player.effective_view_distance(view_distance));
+            arrivals.push((player.uuid, player.dimension, center, radius, packets));
+        });
+        let mut queue = self.chunk_queue.lock().await;
+        for (uuid, dimension, center, radius, packets) in arrivals {
+            self.players.send_packets(&uuid, packets);
+            queue.enqueue(uuid, dimension, center, radius);
+        }
+    }
+
+    /// Send players the chunks queued for them, and unload chunks nobody
+    /// sees once too many are loaded
+    ///
+    /// Players who moved to another chunk or whose view distance changed
+    /// are queued the chunks that came into sight first. A player is sent
+    /// chunks until the world's load budget for the tick is used up or
+    /// their outbound queue holds as many packets as the chunk budgets
+    /// allow; the rest wait for later ticks.
+    pub async fn tick_chunks(&self, overworld: &mut World) {
+        let mut nether = match &self.nether {
+            Some(nether) => Some(nether.write().await),
+            None => None,
+        };
+        let view_distance = self.settings().view_distance;
+        let mut views = Vec::new();
+        self.players.for_each_player_mut(|player| {
+            let view = ChunkView {
+                dimension: player.dimension,
+                center: ChunkPosition::from_world_coords(player.position.x, player.position.z),
+                radius: i32::from(player.effective_view_distance(view_distance)),
+            };
+            views.push((player.uuid, view));
+        });
+
+        let budget = self.config.chunk_budgets.queued_per_player();
+        let mut queue = self.chunk_queue.lock().await;
+        for (uuid, view) in &views {
+            self.follow_view(&mut queue, *uuid, *view);
+        }
+        for (uuid, dimension) in queue.players() {
+            let world: &mut World = match (dimension, nether.as_deref_mut()) {
+                (Dimension::Nether, Some(nether)) => nether,
+                _ => &mut *overworld,
+            };
+            // Never queue more than fits, or the player is disconnected
+            let free = self.players.outbound_room(&uuid);
+            let room = budget.map_or(free, |budget| {
+                budget
+                    .saturating_sub(self.players.queued_packets(&uuid))
+                    .min(free)
+            });
+            let mut packets: Vec<Box<dyn DynPacket>> = Vec::new();
+            while packets.len() < room {
+                let Some(position) = queue.peek(&uuid) else {
+                    break;
+                };
+                if !world.can_load_chunk(position) {
+                    break;
+                }
+                queue.pop(&uuid);
+                match ChunkDataPacket::from_chunk(world.load_chunk(position)) {
+                    Ok(chunk) => packets.push(Box::new(chunk)),
+                    Err(e) => tracing::warn!("Failed to encode chunk {:?}: {}", position, e),
+                }
+            }
+            if !self.players.send_packets(&uuid, packets) {
+                queue.remove(&uuid);
+            }
+        }
+        drop(queue);
+
+        let in_dimension = |dimension: Dimension| -> Vec<(ChunkPosition, i32)> {
+            views
+                .iter()
+                .filter(|(_, view)| view.dimension == dimension)
+                .map(|(_, view)| (view.center, view.radius))
+                .collect()
+        };
+        overworld.evict_chunks(&in_dimension(Dimension::Overworld));
+        if let Some(nether) = nether.as_deref_mut() {
+            nether.evict_chunks(&in_dimension(Dimension::Nether));
+        }
+    }
+
+    /// Follow a player's view in the chunk queue, recentering it and
+    /// unloading the chunks that left it if they moved to another chunk
+    fn follow_view(&self, queue: &mut ChunkQueue, uuid: McUuid, view: ChunkView) {
+        let previous = queue.view(&uuid);
+        let forgotten = queue.follow(uuid, view);
+        let mut packets: Vec<Box<dyn DynPacket>> = Vec::new();
+        if previous.is_some_and(|p| p.dimension == view.dimension && p.center != view.center) {
+            packets.push(Box::new(SetCenterChunkPacket {
+                chunk_x: VarInt(view.center.x),
+                chunk_z: VarInt(view.center.z),
+            }));
+        }
+        packets.extend(forgotten.into_iter().map(|position| {
+            Box::new(UnloadChunkPacket {
+                chunk_z: position.z,
+                chunk_x: position.x,
+            }) as Box<dyn DynPacket>
+        }));
+        if !packets.is_empty() {
+            self.players.send_packets(&uuid, packets);
+        }
+    }
+
+    /// Packets moving a player into a dimension, at their position
+    ///
+    /// The chunks around them follow through the chunk queue.
+    fn dimension_change_packets(
+        &self,
+        player: &Player,
+        dimension: Dimension,
+    ) -> Vec<Box<dyn DynPacket>> {
+        let login = LoginPlayPacket::from_server_config(&self.config, player.entity_id);
+        let mut packets: Vec<Box<dyn DynPacket>> = vec![
+            Box::new(RespawnPacket {
+                dimension_type: VarInt(dimension.type_id()),
+                dimension_name: dimension.name().into(),
+                hashed_seed: login.hashed_seed,
+                game_mode: player.game_mode.id(),
+                previous_game_mode: -1,
+                is_debug: dimension == Dimension::Overworld && login.is_debug,
+                is_flat: dimension == Dimension::Overworld && login.is_flat,
+                death_location: None,
+                portal_cooldown: VarInt(0),
+                sea_level: login.sea_level,
+                data_kept: RespawnPacket::KEEP_ALL,
+            }),
+            Box::new(self.teleport_packet(player)),
+            Box::new(GameEventPacket::new(
+                GameEventPacket::START_WAITING_FOR_LEVEL_CHUNKS,
+                0.0,
+            )),
+        ];
+        let center = ChunkPosition::from_world_coords(player.position.x, player.position.z);
+        packets.push(Box::new(SetCenterChunkPacket {
+            chunk_x: VarInt(center.x),
+            chunk_z: VarInt(center.z),
+        }));
+        packets
+    }
+
+    /// Take the player on a connection out of their vehicle, if they ride one
+    pub async fn dismount(&self, addr: &SocketAddr) {
+        let mut world = self.world.write().await;
+        let left = self
+            .players
+            .with_player_mut(addr, |player| {
+                vehicle::dismount(world.entities_mut(), player)
+            })
+            .flatten();
+        if let Some(vehicle_id) = left {
+            self.broadcast_passengers(&world, vehicle_id);
+        }
+    }
+
+    /// Change the game mode of the player on a connection, showing it in
+    /// everyone's tab list
+    ///
+    /// Spectators leave their vehicle and bed and are invisible to other
+    /// players; players leaving spectator mode get their own view back.
+    pub async fn set_game_mode(&self, addr: &SocketAddr, mode: GameMode) {
+        if mode == GameMode::Spectator {
+            self.dismount(addr).await;
+            self.leave_bed(addr).await;
+        }
+        let changed = self.players.with_player_mut(addr, |player| {
+            player.set_game_mode(mode);
+            let released = player.spectating.take().is_some();
+            (
+                player.uuid,
+                player_info_entry(player),
+                movement_metadata(player),
+                PlayerAbilitiesPacket::from(&player.abilities),
+                released.then(|| (player.entity_id, self.teleport_packet(player))),
+            )
+        });
+        let Some((uuid, entry, metadata, abilities, released)) = changed else {
+            return;
+        };
+        self.players.send_packet(
+            &uuid,
+            GameEventPacket::new(GameEventPacket::CHANGE_GAME_MODE, f32::from(mode.id())),
+        );
+        self.players.send_packet(&uuid, abilities);
+        self.broadcast_packet(PlayerInfoUpdatePacket {
+            actions: PlayerInfoUpdatePacket::UPDATE_GAME_MODE,
+            entries: vec![entry],
+        });
+        self.broadcast_packet(metadata);
+        if let Some((entity_id, teleport)) = released {
+            self.release_camera(&uuid, entity_id, teleport);
+        }
+    }
+
+    /// Let a spectator on a connection view the world through an entity or
+    /// another player
+    ///
+    /// The spectator is moved along with the entity until they sneak or it
+    /// disappears. Players who are not spectating are ignored.
+    pub async fn spectate_entity(&self, addr: &SocketAddr, target: EntityId) {
+        let Some(position) = self.entity_position(target).await else {
+            return;
+        };
+        let camera = self
+            .players
+            .with_player_mut(addr, |player| {
+                if player.game_mode != GameMode::Spectator || player.entity_id == target {
+                    return None;
+                }
+                player.spectating = Some(target);
+                player.set_position(position.x, position.y, position.z);
+                Some((player.uuid, self.teleport_packet(player)))
+            })
+            .flatten();
+        if let Some((uuid, teleport)) = camera {
+            self.players.send_packet(&uuid, teleport);
+            self.players.send_packet(
+                &uuid,
+                SetCameraPacket {
+                    camera_id: VarInt(target),
+                },
+            );
+        }
+    }
+
+    /// Give a spectator on a connection their own view back, where the
+    /// entity they spectated is now
+    pub fn stop_spectating(&self, addr: &SocketAddr) {
+        let released = self
+            .players
+            .with_player_mut(addr, |player| {
+                player.spectating.take()?;
+                Some((player.uuid, player.entity_id, self.teleport_packet(player)))
+            })
+            .flatten();
+        if let Some((uuid, entity_id, teleport)) = released {
+            self.release_camera(&uuid, entity_id, teleport);
+        }
+    }
+
+    /// Teleport a spectator on a connection to another player, as picked
+    /// from the spectator menu
+    ///
+    /// Players who are not spectating are ignored.
+    pub fn teleport_to_player(&self, addr: &SocketAddr, target: &McUuid) {
+        let Some(position) = self.players.with_player(target, |player| player.position) else {
+            return;
+        };
+        let teleported = self
+            .players
+            .with_player_mut(addr, |player| {
+                if player.game_mode != GameMode::Spectator {
+                    return None;
+                }
+                let released = player.spectating.take().map(|_| player.entity_id);
+                player.set_position(position.x, position.y, position.z);
+                Some((player.uuid, released, self.teleport_packet(player)))
+            })
+            .flatten();
+        let Some((uuid, released, teleport)) = teleported else {
+            return;
+        };
+        match released {
+            Some(entity_id) => self.release_camera(&uuid, entity_id, teleport),
+            None => {
+                self.players.send_packet(&uuid, teleport);
+            }
+        }
+    }
+
+    /// Position of an entity in the world or an online player's entity
+    async fn entity_position(&self, entity_id: EntityId) -> Option<EntityPosition> {
+        if let Some(entity) = self.world.read().await.entities().get_entity(entity_id) {
+            return Some(entity.position());
+        }
+        let mut found = None;
+        self.players.for_each_player_mut(|player| {
+            if player.entity_id == entity_id {
+                let position = player.position;
+                found = Some(EntityPosition {
+                    x: position.x,
+                    y: position.y,
+                    z: position.z,
+                });
+            }
+        });
+        found
+    }
+
+    /// Point a former spectator's camera back at themselves
+    fn release_camera(
+        &self,
+        uuid: &McUuid,
+        entity_id: EntityId,
+        teleport: SynchronizePlayerPositionPacket,
+    ) {
+        self.players.send_packet(uuid, teleport);
+        self.players.send_packet(
+            uuid,
+            SetCameraPacket {
+                camera_id: VarInt(entity_id),
+            },
+        );
+    }
+
+    /// Build a packet moving a player's client to where the server has them
+    pub fn teleport_packet(&self, player: &Player) -> SynchronizePlayerPositionPacket {
+        SynchronizePlayerPositionPacket {
+            teleport_id: VarInt(self.next_teleport_id.fetch_add(1, Ordering::Relaxed)),
+            x: player.position.x,
+            y: player.position.y,
+            z: player.position.z,
+            velocity: [0.0; 3],
+            yaw: player.rotation.yaw,
+            pitch: player.rotation.pitch,
+            flags: 0,
+        }
+    }
+
+    /// Move the vehicle the player on a connection steers, along with its
+    /// passengers
+    ///
+    /// Moves by players not steering, or that fail validation, are ignored.
+    pub async fn move_vehicle(&self, addr: &SocketAddr, packet: &MoveVehiclePacket) {
+        let position = EntityPosition {
+            x: packet.x,
+            y: packet.y,
+            z: packet.z,
+        };
+        let rotation = EntityRotation {
+            yaw: packet.yaw,
+            pitch: packet.pitch,
+        };
+        let mut world = self.world.write().await;
+        let steered = self.players.with_player_mut(addr, |player| {
+            vehicle::steer(
+                world.entities_mut(),
+                player,
+                position,
+                rotation,
+                packet.on_ground,
+            )
+        });
+        let vehicle_id = match steered {
+            Some(Ok(vehicle_id)) => vehicle_id,
+            Some(Err(e)) => {
+                tracing::warn!("Ignoring vehicle move: {}", e);
+                return;
+            }
+            None => return,
+        };
+
+        self.players.for_each_player_mut(|player| {
+            if player.vehicle == Some(vehicle_id) {
+                player.set_position(position.x, position.y, position.z);
+            }
+        });
+        self.broadcast_packet(EntityPositionSyncPacket {
+            entity_id: VarInt(vehicle_id),
+            x: position.x,
+            y: position.y,
+            z: position.z,
+            velocity: [0.0; 3],
+            yaw: rotation.yaw,
+            pitch: rotation.pitch,
+            on_ground: packet.on_ground,
+        });
+    }
+
+    /// Tell all players who rides a vehicle
+    fn broadcast_passengers(&self, world: &World, vehicle_id: EntityId) {
+        if let Some(vehicle) = world
+            .entities()
+            .get_entity(vehicle_id)
+            .and_then(|entity| entity.as_vehicle())
+        {
+            self.broadcast_packet(passengers_packet(vehicle));
+        }
+    }
+
+    /// Heal or starve players in survival and adventure mode as their food
+    /// level and the difficulty allow
+    pub fn tick_hunger(&self, world: &World) {
+        let difficulty = world.difficulty();
+        let mut changed = Vec::new();
+        self.players.for_each_player_mut(|player| {
+            let hungry = matches!(player.game_mode, GameMode::Survival | GameMode::Adventure);
+            if hungry && player.tick_hunger(difficulty) {
+                changed.push((player.uuid, health_packet(player)));
+            }
+        });
+        for (uuid, health) in changed {
+            self.players.send_packet(&uuid, health);
+        }
+        self.tick_eating();
+    }
+
+    /// Advance players who are eating, feeding those who are done
+    fn tick_eating(&self) {
+        let items = &self.items;
+        let mut finished = Vec::new();
+        self.players.for_each_player_mut(|player| {
+            let Some((hand, ticks)) = player.eating else {
+                return;
+            };
+            if ticks > 1 {
+                player.eating = Some((hand, ticks - 1));
+                return;
+            }
+            player.eating = None;
+            let Some(food) = player
+                .inventory
+                .item_in(hand)
+                .and_then(|item| items.get_item(u32::try_from(item.item).ok()?))
+                .and_then(|info| food::food_value(&info.name))
+            else {
+                return;
+            };
+            player.eat(food);
+            let update = (player.game_mode != GameMode::Creative).then(|| {
+                player.inventory.take_one(hand);
+                let slot = player.inventory.hand_slot(hand);
+                container_slot_packet(&mut player.inventory, slot)
+            });
+            finished.push((player.clone(), hand, health_packet(player), update));
+        });
+        for (player, hand, health, update) in finished {
+            self.players.send_packet(&player.uuid, health);
+            self.players.send_packet(
+                &player.uuid,
+                EntityEventPacket {
+                    entity_id: player.entity_id,
+                    event: EntityEventPacket::FINISHED_EATING,
+                },
+            );
+            if let Some(update) = update {
+                self.players.send_packet(&player.uuid, update);
+                let item = player.inventory.item_in(hand);
+                self.broadcast_equipment(&player, &[(hand.equipment_slot(), item)]);
+            }
+        }
+    }
+
+    /// Move spectators along with the entities they view the world
+    /// through, giving them their own view back once an entity is gone
+    pub fn tick_spectators(&self, world: &World) {
+        let mut positions = Vec::new();
+        self.players
+            .for_each_player_mut(|player| positions.push((player.entity_id, player.position)));
+        let mut released = Vec::new();
+        self.players.for_each_player_mut(|player| {
+            let Some(target) = player.spectating else {
+                return;
+            };
+            let position = match world.entities().get_entity(target) {
+                Some(entity) => Some(entity.position()),
+                None => positions
+                    .iter()
+                    .find(|(id, _)| *id == target)
+                    .map(|(_, position)| EntityPosition {
+                        x: position.x,
+                        y: position.y,
+                        z: position.z,
+                    }),
+            };
+            match position {
+                Some(position) => player.set_position(position.x, position.y, position.z),
+                None => {
+                    player.spectating = None;
+                    released.push((player.uuid, player.entity_id, self.teleport_packet(player)));
+                }
+            }
+        });
+        for (uuid, entity_id, teleport) in released {
+            self.release_camera(&uuid, entity_id, teleport);
+        }
+    }
+
+    /// Advance sleeping players and skip the night once enough are asleep
+    pub fn tick_sleeping(&self, world: &mut World) {
+        let mut anyone_asleep = false;
+        let mut slept = Vec::new();
+        self.players.for_each_player_mut(|player| {
+            if player.sleeping_at.is_some() {
+                player.sleep_ticks = player.sleep_ticks.saturating_add(1);
+                anyone_asleep = true;
+            }
+            slept.push(bed::has_slept(player));
+        });
+        if !anyone_asleep {
+            return;
+        }
+
+        let percentage = world.game_rules().players_sleeping_percentage;
+        if !bed::should_skip_night(slept, percentage) {
+            return;
+        }
+
+        bed::skip_night(world);
+        self.broadcast_time(world);
+        self.broadcast_weather(world.weather());
+
+        let mut woken = Vec::new();
+        self.players.for_each_player_mut(|player| {
+            if bed::wake_up(world, player) {
+                woken.push(player.entity_id);
+            }
+        });
+        for entity_id in woken {
+            self.broadcast_wake_up(entity_id);
+        }
+
+        tracing::info!("Skipped the night in {}", world.name());
+    }
+
+    /// Tell all players that an entity left its bed
+    fn broadcast_wake_up(&self, entity_id: EntityId) {
+        self.broadcast_packet(sleep_metadata(entity_id, None));
+        self.broadcast_packet(EntityAnimationPacket {
+            entity_id: VarInt(entity_id),
+            animation: EntityAnimationPacket::LEAVE_BED,
+        });
+    }
+
+    /// Request a graceful server shutdown
+    pub fn request_shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Record that the server crashed and request a shutdown
+    ///
+    /// The main loop then ends with [`ServerError::Crashed`].
+    pub fn crash(&self, reason: String) {
+        *self.crash.lock().unwrap_or_else(|e| e.into_inner()) = Some(reason);
+        self.request_shutdown();
+    }
+
+    /// Why the server crashed, if it did
+    pub fn crash_reason(&self) -> Option<String> {
+        self.crash.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Check whether a shutdown has been requested
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    /// Subscribe to shutdown requests
+    pub fn subscribe_shutdown(&self) -> watch::Receiver<bool> {
+        self.shutdown.subscribe()
+    }
+}
+
+/// Pick up to `size` players for the server list, starting at a random player
+fn player_sample(players: &[PlayerSample], size: usize) -> Vec<PlayerSample> {
+    if players.is_empty() {
+        return Vec::new();
+    }
+    let start = RandomState::new().build_hasher().finish() as usize % players.len();
+
+    players
+        .iter()
+        .cycle()
+        .skip(start)
+        .take(size.min(players.len()))
+        .cloned()
+        .collect()
+}
+
+/// How a player is shown in the server list sample
+fn sample_entry(player: &Player) -> PlayerSample {
+    if player.allows_listing() {
+        PlayerSample {
+            name: player.username.clone(),
+            id: player.uuid.to_string(),
+        }
+    } else {
+        PlayerSample {
+            name: ANONYMOUS_PLAYER_NAME.to_string(),
+            id: McUuid::nil().to_string(),
+        }
+    }
+}
+
+/// Create the nether of a world, unless `allow-nether` is off
+fn new_nether(config: &ServerConfig, world: &World) -> Option<World> {
+    config.allow_nether.then(|| {
+        let mut nether = World::new(format!("{}_nether", config.level_name), world.seed())
+            .with_dimension(Dimension::Nether)
+            .with_chunk_budgets(config.chunk_budgets);
+        nether.set_difficulty(world.difficulty());
+        nether
+    })
+}
+
+/// Write a world's modified chunks on a blocking thread, returning how
+/// many were written
+async fn save_world(world: &RwLock<World>, flush: bool) -> Result<usize> {
+    let Some(save) = world.write().await.begin_save(flush) else {
+        return Ok(0);
+    };
+    let outcome = tokio::task::spawn_blocking(move || save.write())
+        .await
+        .unwrap_or_else(|e| {
+            SaveOutcome::failed(ServerError::Storage(format!("Save task failed: {}", e)))
+        });
+    world.write().await.finish_save(outcome)
+}
+
+/// Create the profile resolver a configuration asks for
+fn profile_resolver(config: &ServerConfig, cache: UserCache) -> ProfileResolver {
+    let api = config.profile_api().and_then(ProfileApi::parse);
+    ProfileResolver::new(cache, api, config.online_mode)
+}
+
+/// Build the packet announcing a world's difficulty
+fn difficulty_packet(world: &World) -> ChangeDifficultyPacket {
+    ChangeDifficultyPacket {
+        difficulty: world.difficulty().id(),
+        locked: world.is_difficulty_locked(),
+    }
+}
+
+/// Build the packet synchronizing a world's time
+fn time_packet(world: &World) -> UpdateTimePacket {
+    UpdateTimePacket {
+        world_age: world.world_age(),
+        time_of_day: world.time_of_day(),
+        time_of_day_increasing: world.game_rules().do_daylight_cycle,
+    }
+}
+
+/// Build the packet telling clients where the world spawn is
+fn world_spawn_packet(world: &World) -> SetDefaultSpawnPositionPacket {
+    SetDefaultSpawnPositionPacket {
+        location: world.spawn_position(),
+        angle: world.spawn_angle(),
+    }
+}
+
+/// Convert an angle in degrees to 256ths of a full turn
+fn protocol_angle(degrees: f32) -> u8 {
+    (degrees.rem_euclid(360.0) / 360.0 * 256.0) as u32 as u8
+}
+
+/// Where a player moved from and to, as shown to other players
+struct ShownMove {
+    /// Entity ID of the player
+    entity_id: EntityId,
+    /// Position before the move
+    from: PlayerPosition,
+    /// Position after the move
+    to: PlayerPosition,
+    /// Rotation after the move
+    rotation: PlayerRotation,
+}
+
+/// Change in a coordinate as sent in relative move packets, in 4096ths of a
+/// block, or `None` if it is too far for them
+fn position_delta(from: f64, to: f64) -> Option<i16> {
+    let delta = (to * 4096.0).round() as i64 - (from * 4096.0).round() as i64;
+    i16::try_from(delta).ok()
+}
+
+/// Build the packet turning an entity's head
+fn head_rotation_packet(entity_id: EntityId, yaw: f32) -> SetHeadRotationPacket {
+    SetHeadRotationPacket {
+        entity_id: VarInt(entity_id),
+        head_yaw: protocol_angle(yaw),
+    }
+}
+
+/// Build the packet telling clients whether a mob is a baby
+fn age_metadata(mob: &MobEntity) -> SetEntityMetadataPacket {
+    SetEntityMetadataPacket {
+        entity_id: VarInt(mob.entity_id()),
+        metadata: vec![MetadataEntry::new(
+            index::BABY,
+            MetadataValue::Boolean(mob.is_baby()),
+        )],
+    }
+}
+
+/// Build the packet showing an entity's custom name, or hiding it if the
+/// entity has none
+fn custom_name_metadata(entity: &dyn Entity) -> SetEntityMetadataPacket {
+    let name = entity.custom_name();
+    SetEntityMetadataPacket {
+        entity_id: VarInt(entity.entity_id()),
+        metadata: vec![
+            MetadataEntry::new(
+                index::CUSTOM_NAME,
+                MetadataValue::OptionalTextComponent(name.map(|name| name.text.clone())),
+            ),
+            MetadataEntry::new(
+                index::CUSTOM_NAME_VISIBLE,
+                MetadataValue::Boolean(name.is_some_and(|name| name.always_visible)),
+            ),
+        ],
+    }
+}
+
+/// Build the packet showing an entity, if clients are shown its type
+fn spawn_packet(entity: &dyn Entity) -> Option<SpawnEntityPacket> {
+    let entity_type = entity.entity_type().protocol_id()?;
+    let position = entity.position();
+    let rotation = entity.rotation();
+    Some(SpawnEntityPacket {
+        entity_id: VarInt(entity.entity_id()),
+        uuid: entity.uuid().unwrap_or_else(McUuid::nil),
+        entity_type: VarInt(entity_type),
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: protocol_angle(rotation.pitch),
+        yaw: protocol_angle(rotation.yaw),
+        head_yaw: protocol_angle(rotation.yaw),
+        data: VarInt(spawn_data(entity)),
+        velocity: [0; 3],
+    })
+}
+
+/// Extra data clients need to spawn an entity: the direction an item frame
+/// faces, or the block state of a falling block
+fn spawn_data(entity: &dyn Entity) -> i32 {
+    if let Some(frame) = entity.as_item_frame() {
+        i32::from(frame.facing())
+    } else if let Some(falling) = entity.as_falling_block() {
+        falling.block() as i32
+    } else {
+        0
+    }
+}
+
+/// Build the packet showing how an armor stand or item frame looks
+fn decoration_metadata(entity: &dyn Entity) -> Option<SetEntityMetadataPacket> {
+    let metadata = if let Some(stand) = entity.as_armor_stand() {
+        let mut stand_flags = 0;
+        if stand.is_small() {
+            stand_flags |= armor_stand_flags::SMALL;
+        }
+        if stand.has_arms() {
+            stand_flags |= armor_stand_flags::ARMS;
+        }
+        let pose = stand.pose();
+        let rotations = [
+            pose.head,
+            pose.body,
+            pose.left_arm,
+            pose.right_arm,
+            pose.left_leg,
+            pose.right_leg,
+        ];
+        let mut metadata = vec![MetadataEntry::new(
+            index::ARMOR_STAND_FLAGS,
+            MetadataValue::Byte(stand_flags),
+        )];
+        for (offset, rotation) in (0..).zip(rotations) {
+            metadata.push(MetadataEntry::new(
+                index::ARMOR_STAND_HEAD + offset,
+                MetadataValue::Rotations(rotation),
+            ));
+        }
+        metadata
+    } else {
+        let frame = entity.as_item_frame()?;
+        vec![
+            MetadataEntry::new(index::ITEM_FRAME_ITEM, MetadataValue::Slot(frame.item())),
+            MetadataEntry::new(
+                index::ITEM_FRAME_ROTATION,
+                MetadataValue::VarInt(i32::from(frame.item_rotation())),
+            ),
+        ]
+    };
+    Some(SetEntityMetadataPacket {
+        entity_id: VarInt(entity.entity_id()),
+        metadata,
+    })
+}
+
+/// Build the packet showing an explosion to a player, pushed by `knockback`
+fn explode_packet(explosion: &Explosion, knockback: Option<[f64; 3]>) -> ExplodePacket {
+    let particle = if explosion.power < 2.0 {
+        ExplodePacket::EXPLOSION
+    } else {
+        ExplodePacket::EXPLOSION_EMITTER
+    };
+    ExplodePacket {
+        x: explosion.center.x,
+        y: explosion.center.y,
+        z: explosion.center.z,
+        player_knockback: knockback,
+        particle: VarInt(particle),
+        sound: McString(ExplodePacket::EXPLODE_SOUND.to_string()),
+        sound_range: None,
+    }
+}
+
+/// Build the packet listing who rides a vehicle
+fn passengers_packet(vehicle: &VehicleEntity) -> SetPassengersPacket {
+    SetPassengersPacket {
+        entity_id: VarInt(vehicle.entity_id()),
+        passengers: vehicle.passengers().iter().map(|&id| VarInt(id)).collect(),
+    }
+}
+
+/// Build the game events describing a weather state
+fn weather_packets(weather: Weather) -> [GameEventPacket; 3] {
+    let (event, rain, thunder) = match weather {
+        Weather::Clear => (GameEventPacket::END_RAINING, 0.0, 0.0),
+        Weather::Rain => (GameEventPacket::BEGIN_RAINING, 1.0, 0.0),
+        Weather::Thunder => (GameEventPacket::BEGIN_RAINING, 1.0, 1.0),
+    };
+    [
+        GameEventPacket::new(event, 0.0),
+        GameEventPacket::new(GameEventPacket::RAIN_LEVEL_CHANGE, rain),
+        GameEventPacket::new(GameEventPacket::THUNDER_LEVEL_CHANGE, thunder),
+    ]
+}
+
+/// Build the metadata update for an entity falling asleep in (or leaving) a bed
+fn sleep_metadata(entity_id: EntityId, bed: Option<Position>) -> SetEntityMetadataPacket {
+    let pose = if bed.is_some() {
+        Pose::Sleeping
+    } else {
+        Pose::Standing
+    };
+    SetEntityMetadataPacket {
+        entity_id: VarInt(entity_id),
+        metadata: vec![
+            MetadataEntry::new(index::POSE, MetadataValue::Pose(pose)),
+            MetadataEntry::new(
+                index::SLEEPING_POSITION,
+                MetadataValue::OptionalPosition(bed),
+            ),
+        ],
+    }
+}
+
+/// Build the metadata update for whether a player sneaks, sprints and is
+/// invisible as a spectator
+fn movement_metadata(player: &Player) -> SetEntityMetadataPacket {
+    let mut entity_flags = 0;
+    if player.sneaking {
+        entity_flags |= flags::CROUCHING;
+    }
+    if player.sprinting {
+        entity_flags |= flags::SPRINTING;
+    }
+    if player.game_mode == GameMode::Spectator {
+        entity_flags |= flags::INVISIBLE;
+    }
+    let mut metadata = vec![MetadataEntry::new(
+        index::FLAGS,
+        MetadataValue::Byte(entity_flags),
+    )];
+    // Sleeping players keep lying down
+    if player.sleeping_at.is_none() {
+        let pose = if player.sneaking {
+            Pose::Sneaking
+        } else {
+            Pose::Standing
+        };
+        metadata.push(MetadataEntry::new(index::POSE, MetadataValue::Pose(pose)));
+    }
+    SetEntityMetadataPacket {
+        entity_id: VarInt(player.entity_id),
+        metadata,
+    }
+}
+
+/// Build the health and hunger update for a player
+fn health_packet(player: &Player) -> SetHealthPacket {
+    SetHealthPacket {
+        health: player.health,
+        food: VarInt(player.food),
+        saturation: player.saturation,
+    }
+}
+
+/// Tab list entry of a player, filled in for every action but adding them
+fn player_info_entry(player: &Player) -> PlayerInfoEntry {
+    PlayerInfoEntry {
+        uuid: player.uuid,
+        name: McString(String::new()),
+        properties: Vec::new(),
+        game_mode: VarInt(i32::from(player.game_mode.id())),
+        listed: true,
+        latency: VarInt(player.latency().as_millis().min(i32::MAX as u128) as i32),
+        display_name: None,
+    }
+}
+
+/// Build the metadata update for the skin layers and main hand of a player
+fn appearance_metadata(entity_id: EntityId, settings: &ClientSettings) -> SetEntityMetadataPacket {
+    SetEntityMetadataPacket {
+        entity_id: VarInt(entity_id),
+        metadata: vec![
+            MetadataEntry::new(
+                index::DISPLAYED_SKIN_PARTS,
+                MetadataValue::Byte(settings.skin_parts.0 as i8),
+            ),
+            MetadataEntry::new(
+                index::MAIN_HAND,
+                MetadataValue::Byte(settings.main_hand.id() as i8),
+            ),
+        ],
+    }
+}
+
+/// Build the update of a slot of a player's inventory window
+fn container_slot_packet(inventory: &mut Inventory, slot: usize) -> SetContainerSlotPacket {
+    SetContainerSlotPacket {
+        window_id: VarInt(SetContainerSlotPacket::PLAYER_INVENTORY),
+        state_id: VarInt(inventory.next_state_id()),
+        slot: slot as i16,
+        item: inventory.get(slot),
+    }
+}
+
+/// Build the full contents of a player's inventory window
+fn inventory_content_packet(inventory: &mut Inventory) -> SetContainerContentPacket {
+    SetContainerContentPacket {
+        window_id: VarInt(SetContainerSlotPacket::PLAYER_INVENTORY),
+        state_id: VarInt(inventory.next_state_id()),
+        slots: (0..SLOT_COUNT).map(|slot| inventory.get(slot)).collect(),
+        carried: None,
+    }
+}
+
+/// Whether a click in the player inventory window disagrees with the
+/// server's contents: it was based on an old version of them, or the client
+/// expects a slot or the cursor to hold something they do not
+///
+/// Stacks are compared by item and count, as clients only send hashes of
+/// the other components.
+fn click_desynced(inventory: &Inventory, click: &ClickContainerPacket) -> bool {
+    let matches = |stack: Option<ItemStack>, hashed: Option<&HashedStack>| match (stack, hashed) {
+        (None, None) => true,
+        (Some(stack), Some(hashed)) => stack.item == hashed.item.0 && stack.count == hashed.count.0,
+        _ => false,
+    };
+    click.state_id.0 != inventory.state_id()
+        || click.carried.is_some()
+        || click.changed_slots.iter().any(|(slot, hashed)| {
+            usize::try_from(*slot)
+                .ok()
+                .filter(|&slot| slot < SLOT_COUNT)
+                .is_none_or(|slot| !matches(inventory.get(slot), hashed.as_ref()))
+        })
+}
+
+/// Packets sending the player inventory slots that differ from `before`
+fn changed_slot_packets(
+    before: &Inventory,
+    inventory: &mut Inventory,
+) -> Vec<SetContainerSlotPacket> {
+    (0..SLOT_COUNT)
+        .filter(|&slot| before.get(slot) != inventory.get(slot))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|slot| container_slot_packet(inventory, slot))
+        .collect()
+}
+
+/// Block next to a face of another block, by face ID
+fn adjacent(position: Position, face: i32) -> Option<Position> {
+    let Position { x, y, z } = position;
+    match face {
+        0 => Some(Position::new(x, y - 1, z)),
+        1 => Some(Position::new(x, y + 1, z)),
+        2 => Some(Position::new(x, y, z - 1)),
+        3 => Some(Position::new(x, y, z + 1)),
+        4 => Some(Position::new(x - 1, y, z)),
+        5 => Some(Position::new(x + 1, y, z)),
+        _ => None,
+    }
+}
+
+/// Trades of a villager, or none if it is gone
+fn villager_offers(world: &World, villager: EntityId) -> &[TradeOffer] {
+    world
+        .entities()
+        .get_entity(villager)
+        .and_then(|entity| entity.as_mob())
+        .map_or(&[], MobEntity::offers)
+}
+
+/// Trades as sent to clients
+fn merchant_offers(offers: &[TradeOffer]) -> Vec<MerchantOffer> {
+    offers
+        .iter()
+        .map(|offer| MerchantOffer {
+            input: offer.cost,
+            output: offer.result,
+            second_input: offer.second_cost,
+            disabled: offer.is_out_of_stock(),
+            uses: offer.uses,
+            max_uses: offer.max_uses,
+            experience: offer.experience,
+            special_price: 0,
+            price_multiplier: offer.price_multiplier,
+            demand: 0,
+        })
+        .collect()
+}
+
+/// Build the full contents of a player's trading screen: its own slots,
+/// then the player's inventory
+fn merchant_content_packet(
+    player: &mut Player,
+    offers: &[TradeOffer],
+) -> Option<SetContainerContentPacket> {
+    let trading = player.trading.as_ref()?;
+    let mut slots = trading.slots(offers).to_vec();
+    slots.extend(STORAGE_SLOTS.map(|slot| player.inventory.get(slot)));
+    Some(SetContainerContentPacket {
+        window_id: VarInt(trading.window_id),
+        state_id: VarInt(player.inventory.next_state_id()),
+        slots,
+        carried: None,
+    })
+}
+
+/// Maximum stack size of an item, by item ID
+fn max_stack_size(items: &ItemRegistry, item: i32) -> i32 {
+    u32::try_from(item)
+        .ok()
+        .and_then(|id| items.get_item(id))
+        .map_or(64, |info| info.max_stack_size as i32)
+}
+
+/// Log items a player lost because they did not fit into their inventory
+fn lose_items(username: &str, items: &[ItemStack]) {
+    for stack in items {
+        tracing::warn!(
+            "{} lost {} of item {}: their inventory is full",
+            username,
+            stack.count,
+            stack.item
+        );
+    }
+}
+
+/// Build the equipment update for some slots of an entity
+fn equipment_packet(
+    entity_id: EntityId,
+    equipment: &[(EquipmentSlot, Option<ItemStack>)],
+) -> SetEquipmentPacket {
+    SetEquipmentPacket {
+        entity_id: VarInt(entity_id),
+        equipment: equipment
+            .iter()
+            .map(|&(slot, item)| (slot.id(), item))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::RconSender;
+    use crate::game::LevelType;
+    use crate::game::inventory::HOTBAR_START;
+    use crate::game::player::{OUTBOUND_CHANNEL_CAPACITY, OutboundReceiver};
+    use crate::game::world::budget::ChunkBudgets;
+    use crate::protocol::packets::Packet;
+
+    #[test]
+    fn test_position_delta() {
+        assert_eq!(position_delta(10.0, 10.5), Some(2048));
+        assert_eq!(position_delta(0.0, -7.9), Some(-32358));
+        assert_eq!(position_delta(0.0, 8.0), None);
+        assert_eq!(protocol_angle(-90.0), 192);
+    }
+
+    #[test]
+    fn test_player_sample() {
+        let mut players: Vec<Player> = (0..5)
+            .map(|i| Player::new(McUuid::new_v4(), format!("Player{}", i)))
+            .collect();
+        players[0].set_settings(ClientSettings {
+            allows_listing: false,
+            ..ClientSettings::default()
+        });
+        let players: Vec<PlayerSample> = players.iter().map(sample_entry).collect();
+
+        assert!(player_sample(&[], 12).is_empty());
+        assert_eq!(player_sample(&players, 3).len(), 3);
+
+        let sample = player_sample(&players, 12);
+        assert_eq!(sample.len(), players.len());
+        assert!(sample.iter().any(|p| p.name == ANONYMOUS_PLAYER_NAME));
+        assert!(!sample.iter().any(|p| p.name == "Player0"));
+    }
+
+    #[test]
+    fn test_click_desync() {
+        let mut inventory = Inventory::new();
+        inventory.set(HOTBAR_START, Some(ItemStack::new(1, 10)));
+        let packet = inventory_content_packet(&mut inventory);
+        assert_eq!(packet.slots.len(), SLOT_COUNT);
+        let hashed = |count| HashedStack {
+            item: VarInt(1),
+            count: VarInt(count),
+            added: Vec::new(),
+            removed: Vec::new(),
+        };
+        let click =
+            |state_id, changed_slots: Vec<(i16, Option<HashedStack>)>| ClickContainerPacket {
+                window_id: VarInt(0),
+                state_id,
+                slot: HOTBAR_START as i16,
+                button: 0,
+                mode: VarInt(0),
+                changed_slots,
+                carried: None,
+            };
+
+        let slot = HOTBAR_START as i16;
+        assert!(!click_desynced(
+            &inventory,
+            &click(packet.state_id, vec![(slot, Some(hashed(10)))])
+        ));
+        assert!(click_desynced(
+            &inventory,
+            &click(packet.state_id, vec![(slot, None)])
+        ));
+        assert!(click_desynced(
+            &inventory,
+            &click(packet.state_id, vec![(slot, Some(hashed(5)))])
+        ));
+        assert!(click_desynced(&inventory, &click(VarInt(0), Vec::new())));
+        assert!(click_desynced(
+            &inventory,
+            &click(packet.state_id, vec![(99, None)])
+        ));
+        let mut carrying = click(packet.state_id, Vec::new());
+        carrying.carried = Some(hashed(10));
+        assert!(click_desynced(&inventory, &carrying));
+    }
+
+    /// Plain text of the system chat messages queued for a player
+    fn system_chat(receiver: &mut OutboundReceiver) -> Vec<String> {
+        std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|packet| packet.encode().unwrap())
+            .filter(|raw| raw.id == SystemChatMessagePacket::ID)
+            .map(|raw| {
+                let packet =
+                    SystemChatMessagePacket::read(&mut std::io::Cursor::new(raw.data)).unwrap();
+                packet.content.to_plain_text()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_server_chat() {
+        let server = ServerState::new(ServerConfig::default());
+        let steve_addr = SocketAddr::from(([127, 0, 0, 1], 40001));
+        let alex_addr = SocketAddr::from(([127, 0, 0, 1], 40002));
+        let steve = Player::new(McUuid::new_v4(), "Steve".to_string());
+        let alex = Player::new(McUuid::new_v4(), "Alex".to_string());
+        server.players.add_player(steve, steve_addr);
+        server.players.add_player(alex, alex_addr);
+        let mut steve_rx = server.players.open_outbound(&steve_addr).unwrap();
+        let mut alex_rx = server.players.open_outbound(&alex_addr).unwrap();
+        let mut console = server.subscribe_chat();
+
+        // Console chat reaches every player and is echoed
+        let sender = RconSender::new();
+        let execute = |input: &'static str| server.commands.execute(&server, &sender, input);
+        assert_eq!(execute("say Hello").await, Ok(1));
+        assert_eq!(execute("me waves").await, Ok(1));
+        for receiver in [&mut steve_rx, &mut alex_rx] {
+            assert_eq!(system_chat(receiver), ["[Rcon] Hello", "* Rcon waves"]);
+        }
+        assert_eq!(console.try_recv().unwrap().to_plain_text(), "[Rcon] Hello");
+        assert_eq!(console.try_recv().unwrap().to_plain_text(), "* Rcon waves");
+
+        // /tellraw only reaches its targets and is not broadcast as chat
+        let tellraw = r#"tellraw Steve ["Psst, ", {"text": "Steve", "bold": true}]"#;
+        assert_eq!(execute(tellraw).await, Ok(1));
+        assert_eq!(system_chat(&mut steve_rx), ["Psst, Steve"]);
+        assert!(system_chat(&mut alex_rx).is_empty());
+        assert!(console.try_recv().is_err());
+        assert_eq!(execute(r#"tellraw @a "Hi all""#).await, Ok(2));
+        assert_eq!(system_chat(&mut steve_rx), ["Hi all"]);
+        assert_eq!(system_chat(&mut alex_rx), ["Hi all"]);
+
+        assert!(execute(r#"tellraw @a {"color": "red"}"#).await.is_err());
+        assert!(execute("me").await.is_err());
+        assert!(system_chat(&mut steve_rx).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_chunk_budget_fits_the_queue() {
+        let unlimited = ChunkBudgets {
+            loads_per_tick: 0,
+            queued_per_player: 0,
+            max_loaded: 0,
+        };
+        let server = ServerState::new(
+            ServerConfig::default()
+                .with_view_distance(17)
+                .with_chunk_budgets(unlimited),
+        );
+        let mut world = World::new("queue".to_string(), 0).with_chunk_budgets(unlimited);
+        world.set_generator(LevelType::Flat);
+        let addr = SocketAddr::from(([127, 0, 0, 1], 40001));
+        let steve = Player::new(McUuid::new_v4(), "Steve".to_string());
+        let (uuid, dimension) = (steve.uuid, steve.dimension);
+        let center = ChunkPosition::from_world_coords(steve.position.x, steve.position.z);
+        server.players.add_player(steve, addr);
+        let mut receiver = server.players.open_outbound(&addr).unwrap();
+        server
+            .chunk_queue
+            .lock()
+            .await
+            .enqueue(uuid, dimension, center, 17);
+
+        // 35 by 35 chunks do not fit in the queue at once; the rest wait
+        // for room instead of being dropped
+        let mut chunks = std::collections::HashSet::new();
+        for _ in 0..3 {
+            server.tick_chunks(&mut world).await;
+            let packets: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+            assert!(packets.len() <= OUTBOUND_CHANNEL_CAPACITY);
+            chunks.extend(
+                packets
+                    .iter()
+                    .map(|packet| packet.encode().unwrap())
+                    .filter(|raw| raw.id == ChunkDataPacket::ID)
+                    .map(|raw| raw.data),
+            );
+        }
+        assert_eq!(chunks.len(), 35 * 35);
+    }
+}
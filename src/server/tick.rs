@@ -0,0 +1,259 @@
+//! Server tick scheduling and statistics
+//!
+//! The server runs game logic at a fixed rate of 20 ticks per second. The
+//! [`TickScheduler`] decides when the next tick is due, catching up on
+//! missed ticks when the server falls behind, and [`TickStats`] records
+//! how long ticks take and how many actually ran.
+
+use crate::clock::{self, Clock, SharedClock, SystemClock};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Target ticks per second
+pub const TICKS_PER_SECOND: u32 = 20;
+
+/// Target duration of a single tick
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+
+/// How far behind schedule the server may fall before skipping ticks
+pub const MAX_CATCH_UP: Duration = Duration::from_secs(2);
+
+/// Number of recent tick durations kept for MSPT statistics (5 seconds)
+const MSPT_SAMPLES: usize = 100;
+
+/// Longest window TPS can be queried for
+const MAX_TPS_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+/// Decides when ticks should run
+///
+/// Ticks are scheduled on a fixed timeline, so a slow tick is followed by
+/// shorter waits until the server is back on schedule.
+#[derive(Debug)]
+pub struct TickScheduler {
+    /// When the next tick is due
+    next_tick: Instant,
+    /// Target duration of a tick
+    tick_duration: Duration,
+}
+
+impl TickScheduler {
+    /// Create a scheduler whose first tick is due immediately, by `clock`
+    pub fn new(clock: &dyn Clock, tick_duration: Duration) -> Self {
+        Self {
+            next_tick: clock.now(),
+            tick_duration,
+        }
+    }
+
+    /// When the next tick is due
+    pub fn next_tick(&self) -> Instant {
+        self.next_tick
+    }
+
+    /// Schedule the tick after the one that just ran
+    ///
+    /// If the server is more than [`MAX_CATCH_UP`] behind, the missed ticks
+    /// are dropped instead of being run back to back, and the number of
+    /// skipped ticks is returned.
+    pub fn advance(&mut self, now: Instant) -> u64 {
+        self.next_tick += self.tick_duration;
+
+        let behind = now.saturating_duration_since(self.next_tick);
+        if behind <= MAX_CATCH_UP {
+            return 0;
+        }
+
+        let skipped = (behind.as_nanos() / self.tick_duration.as_nanos()) as u64;
+        self.next_tick = now;
+        skipped
+    }
+}
+
+impl Default for TickScheduler {
+    fn default() -> Self {
+        Self::new(&SystemClock, TICK_DURATION)
+    }
+}
+
+/// Snapshot of tick timing statistics
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MsptSummary {
+    /// Average milliseconds per tick
+    pub average: f64,
+    /// Fastest tick in milliseconds
+    pub min: f64,
+    /// Slowest tick in milliseconds
+    pub max: f64,
+}
+
+/// Mutable part of [`TickStats`]
+#[derive(Debug)]
+struct TickHistory {
+    /// When the first tick was recorded
+    started: Option<Instant>,
+    /// Total ticks recorded
+    total: u64,
+    /// Completion times of ticks within [`MAX_TPS_WINDOW`]
+    completed: VecDeque<Instant>,
+    /// Durations of the most recent ticks
+    durations: VecDeque<Duration>,
+}
+
+/// Rolling tick statistics (TPS and MSPT)
+#[derive(Debug)]
+pub struct TickStats {
+    history: Mutex<TickHistory>,
+    /// Source of the current time for TPS windows
+    clock: SharedClock,
+}
+
+impl TickStats {
+    /// Create empty statistics
+    pub fn new() -> Self {
+        Self {
+            history: Mutex::new(TickHistory {
+                started: None,
+                total: 0,
+                completed: VecDeque::new(),
+                durations: VecDeque::with_capacity(MSPT_SAMPLES),
+            }),
+            clock: clock::system(),
+        }
+    }
+
+    /// Measure TPS windows with the given clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record a tick that finished at `finished` after running for `duration`
+    pub fn record(&self, finished: Instant, duration: Duration) {
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+
+        history.started.get_or_insert(finished - duration);
+        history.total += 1;
+
+        history.completed.push_back(finished);
+        while history
+            .completed
+            .front()
+            .is_some_and(|t| finished.duration_since(*t) > MAX_TPS_WINDOW)
+        {
+            history.completed.pop_front();
+        }
+
+        if history.durations.len() == MSPT_SAMPLES {
+            history.durations.pop_front();
+        }
+        history.durations.push_back(duration);
+    }
+
+    /// Total number of ticks recorded
+    pub fn tick_count(&self) -> u64 {
+        self.history.lock().unwrap_or_else(|e| e.into_inner()).total
+    }
+
+    /// Average ticks per second over the given window (at most 15 minutes)
+    ///
+    /// Right after startup the window is shortened to the time the server
+    /// has been ticking. The result never exceeds [`TICKS_PER_SECOND`].
+    pub fn tps(&self, window: Duration) -> f64 {
+        let now = self.clock.now();
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(started) = history.started else {
+            return TICKS_PER_SECOND as f64;
+        };
+
+        let window = window
+            .min(MAX_TPS_WINDOW)
+            .min(now.saturating_duration_since(started));
+        if window < TICK_DURATION {
+            return TICKS_PER_SECOND as f64;
+        }
+
+        let ticks = history
+            .completed
+            .iter()
+            .rev()
+            .take_while(|t| now.saturating_duration_since(**t) <= window)
+            .count();
+
+        (ticks as f64 / window.as_secs_f64()).min(TICKS_PER_SECOND as f64)
+    }
+
+    /// Milliseconds per tick over the last 100 ticks, if any ran
+    pub fn mspt(&self) -> Option<MsptSummary> {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        if history.durations.is_empty() {
+            return None;
+        }
+
+        let millis = history.durations.iter().map(|d| d.as_secs_f64() * 1000.0);
+        let (sum, min, max) = millis.fold((0.0, f64::MAX, 0.0f64), |(sum, min, max), ms| {
+            (sum + ms, min.min(ms), max.max(ms))
+        });
+
+        Some(MsptSummary {
+            average: sum / history.durations.len() as f64,
+            min,
+            max,
+        })
+    }
+}
+
+impl Default for TickStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn test_scheduler_catch_up() {
+        let clock = ManualClock::new();
+        let mut scheduler = TickScheduler::new(&clock, TICK_DURATION);
+        let start = scheduler.next_tick();
+        assert_eq!(start, clock.now());
+
+        // A slow tick keeps the fixed timeline so the next ticks run early
+        assert_eq!(scheduler.advance(start + Duration::from_millis(120)), 0);
+        assert_eq!(scheduler.next_tick(), start + TICK_DURATION);
+
+        // Falling too far behind skips the missed ticks
+        let late = start + Duration::from_secs(5);
+        assert_eq!(scheduler.advance(late), 98);
+        assert_eq!(scheduler.next_tick(), late);
+    }
+
+    #[test]
+    fn test_tps_and_mspt() {
+        let clock = ManualClock::new();
+        let stats = TickStats::new().with_clock(clock.shared());
+        assert_eq!(stats.mspt(), None);
+
+        let start = clock.now();
+        // 10 seconds at half speed: one tick every 100ms
+        for i in 1..=100 {
+            stats.record(
+                start + Duration::from_millis(100 * i),
+                Duration::from_millis(if i % 2 == 0 { 10 } else { 30 }),
+            );
+        }
+
+        clock.advance(Duration::from_secs(10));
+        let tps = stats.tps(Duration::from_secs(60));
+        assert!((tps - 10.0).abs() < 0.2, "tps was {}", tps);
+        assert_eq!(stats.tick_count(), 100);
+
+        let mspt = stats.mspt().unwrap();
+        assert!((mspt.average - 20.0).abs() < 1e-9);
+        assert!((mspt.min - 10.0).abs() < 1e-9);
+        assert!((mspt.max - 30.0).abs() < 1e-9);
+    }
+}
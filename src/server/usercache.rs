@@ -0,0 +1,212 @@
+//! Cache of player names and UUIDs
+//!
+//! The cache is stored in `usercache.json` using the vanilla format, next to
+//! server.properties unless configured otherwise. It remembers every player
+//! who joins and every profile looked up with the Mojang API, so names can
+//! be resolved without asking the API again. Entries expire after a month,
+//! and only the most recently used ones are kept.
+
+use crate::error::{Result, ServerError};
+use crate::protocol::types::McUuid;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Name of the file the cache is stored in
+pub const USERCACHE_FILE: &str = "usercache.json";
+
+/// Most entries kept, as in vanilla
+const MAX_ENTRIES: usize = 1000;
+
+/// How long an entry stays valid, as in vanilla
+const EXPIRY: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Format of expiry times (`yyyy-MM-dd HH:mm:ss Z`)
+const TIME_FORMAT: &[time::format_description::FormatItem<'static>] =
+    time::macros::format_description!("[year]-[month]-[day] [hour]:[minute]:[second] +0000");
+
+/// A cached player
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserCacheEntry {
+    /// Player name
+    pub name: String,
+    /// Player UUID
+    pub uuid: McUuid,
+    /// When the entry expires (`yyyy-MM-dd HH:mm:ss Z`)
+    #[serde(rename = "expiresOn")]
+    pub expires_on: String,
+}
+
+impl UserCacheEntry {
+    /// Whether the entry expired before `now`, formatted like `expires_on`
+    ///
+    /// Times are compared without their offset, which vanilla sets to the
+    /// server's time zone; being off by a few hours doesn't matter here.
+    fn is_expired(&self, now: &str) -> bool {
+        self.expires_on.get(..19).unwrap_or_default() < now.get(..19).unwrap_or_default()
+    }
+}
+
+/// The cache of player names and UUIDs, most recently used last
+#[derive(Debug, Default)]
+pub struct UserCache {
+    /// File the cache is saved to (`None` keeps it in memory only)
+    path: Option<PathBuf>,
+    /// Cached players
+    entries: RwLock<Vec<UserCacheEntry>>,
+}
+
+impl UserCache {
+    /// Create an empty cache that is not saved to disk
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the cache from a file, starting empty if it does not exist
+    ///
+    /// A damaged cache is only logged, as it can always be rebuilt.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Failed to read {}: {}", path.display(), e);
+                Vec::new()
+            }
+        };
+
+        Self {
+            path: Some(path),
+            entries: RwLock::new(entries),
+        }
+    }
+
+    /// Find a player by name, ignoring case
+    pub fn get_by_name(&self, name: &str) -> Option<UserCacheEntry> {
+        self.find(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Find a player by UUID
+    pub fn get_by_uuid(&self, uuid: &McUuid) -> Option<UserCacheEntry> {
+        self.find(|entry| entry.uuid == *uuid)
+    }
+
+    /// Remember a player, replacing older entries with their name or UUID,
+    /// and save the cache
+    pub fn add(&self, uuid: McUuid, name: &str) -> Result<()> {
+        let expires_on = format_time(time::OffsetDateTime::now_utc() + EXPIRY);
+        let mut entries = self.write();
+        entries.retain(|entry| entry.uuid != uuid && !entry.name.eq_ignore_ascii_case(name));
+        entries.push(UserCacheEntry {
+            name: name.to_string(),
+            uuid,
+            expires_on,
+        });
+        let excess = entries.len().saturating_sub(MAX_ENTRIES);
+        entries.drain(..excess);
+        self.save(&entries)
+    }
+
+    /// Number of cached players, including expired ones
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Whether no players are cached
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Find an entry that has not expired
+    fn find(&self, matches: impl Fn(&UserCacheEntry) -> bool) -> Option<UserCacheEntry> {
+        let now = format_time(time::OffsetDateTime::now_utc());
+        self.read()
+            .iter()
+            .rev()
+            .find(|entry| matches(entry))
+            .filter(|entry| !entry.is_expired(&now))
+            .cloned()
+    }
+
+    /// Write the cache to its file
+    fn save(&self, entries: &[UserCacheEntry]) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(entries)
+            .map_err(|e| ServerError::Storage(format!("Failed to encode user cache: {}", e)))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<UserCacheEntry>> {
+        self.entries.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<UserCacheEntry>> {
+        self.entries.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Format a time like vanilla's user cache
+fn format_time(time: time::OffsetDateTime) -> String {
+    time.format(TIME_FORMAT).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+
+    #[test]
+    fn test_cache_players() {
+        let path =
+            std::env::temp_dir().join(format!("obsidium-usercache-{}.json", McUuid::new_v4()));
+        let steve = McUuid::new_v4();
+        let alex = McUuid::new_v4();
+
+        let cache = UserCache::load(&path);
+        cache.add(steve, "Steve").unwrap();
+        cache.add(alex, "Alex").unwrap();
+        assert_eq!(cache.get_by_name("steve").unwrap().uuid, steve);
+        assert_eq!(cache.get_by_uuid(&alex).unwrap().name, "Alex");
+
+        // A renamed player replaces their old entry
+        cache.add(steve, "Herobrine").unwrap();
+        assert!(cache.get_by_name("Steve").is_none());
+        assert_eq!(cache.len(), 2);
+
+        let reloaded = UserCache::load(&path);
+        assert_eq!(reloaded.get_by_name("Herobrine").unwrap().uuid, steve);
+
+        let expired = UserCacheEntry {
+            name: "Notch".to_string(),
+            uuid: McUuid::new_v4(),
+            expires_on: "2009-05-17 00:00:00 +0100".to_string(),
+        };
+        std::fs::write(&path, serde_json::to_string(&[expired]).unwrap()).unwrap();
+        assert!(UserCache::load(&path).get_by_name("Notch").is_none());
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_cache_file_location() {
+        let config = ServerConfig::new();
+        assert_eq!(config.usercache_file(), PathBuf::from(USERCACHE_FILE));
+
+        let config = config.with_properties_path(Some(PathBuf::from("server/server.properties")));
+        assert_eq!(
+            config.usercache_file(),
+            PathBuf::from("server/usercache.json")
+        );
+
+        let config = config.with_usercache_path(Some(PathBuf::from("cache/users.json")));
+        assert_eq!(config.usercache_file(), PathBuf::from("cache/users.json"));
+    }
+}
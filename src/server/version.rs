@@ -0,0 +1,97 @@
+//! Advertised version
+//!
+//! The server list shows the version name and protocol number from the
+//! status response, and marks the server as incompatible when the protocol
+//! differs from the client's. Modded setups, and servers behind a proxy
+//! that translates between protocols, may want to appear differently than
+//! the protocol spoken here.
+//!
+//! The `[status]` section of obsidium.toml sets the name and protocol
+//! shown, and a range of accepted protocols. A client speaking an accepted
+//! protocol is shown its own protocol, so the server looks compatible, and
+//! may log in. Clients speaking anything but [`PROTOCOL_VERSION`] need a
+//! proxy translating for them.
+
+use crate::protocol::packets::status::VersionInfo;
+use crate::protocol::{MINECRAFT_VERSION, PROTOCOL_VERSION, is_supported_protocol};
+use std::ops::RangeInclusive;
+
+/// Version shown in the server list and the protocols clients may use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdvertisedVersion {
+    /// Version name shown in the server list
+    pub name: String,
+    /// Protocol number shown to clients outside the accepted range
+    pub protocol: i32,
+    /// Protocols shown as compatible and allowed to log in
+    pub accepted: RangeInclusive<i32>,
+}
+
+impl AdvertisedVersion {
+    /// Parse an accepted protocol range: a single protocol number, or two
+    /// separated by a dash, such as `767-771`
+    pub fn parse_range(range: &str) -> Option<RangeInclusive<i32>> {
+        let (min, max) = range.split_once('-').unwrap_or((range, range));
+        let (min, max) = (min.trim().parse().ok()?, max.trim().parse().ok()?);
+        (min <= max).then_some(min..=max)
+    }
+
+    /// Whether clients speaking a protocol may log in
+    pub fn accepts(&self, protocol: i32) -> bool {
+        is_supported_protocol(protocol) || self.accepted.contains(&protocol)
+    }
+
+    /// Version for the status response to a client, given the protocol
+    /// from its handshake
+    pub fn version_info(&self, client_protocol: Option<i32>) -> VersionInfo {
+        VersionInfo {
+            name: self.name.clone(),
+            protocol: client_protocol
+                .filter(|&protocol| self.accepts(protocol))
+                .unwrap_or(self.protocol),
+        }
+    }
+
+    /// Whether a refused protocol is older than every accepted one
+    pub fn is_outdated_client(&self, protocol: i32) -> bool {
+        protocol < (*self.accepted.start()).min(PROTOCOL_VERSION)
+    }
+}
+
+impl Default for AdvertisedVersion {
+    fn default() -> Self {
+        Self {
+            name: MINECRAFT_VERSION.to_string(),
+            protocol: PROTOCOL_VERSION,
+            accepted: PROTOCOL_VERSION..=PROTOCOL_VERSION,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advertised_version() {
+        let version = AdvertisedVersion::default();
+        assert_eq!(version.version_info(Some(47)).protocol, PROTOCOL_VERSION);
+        assert!(!version.accepts(PROTOCOL_VERSION - 1));
+
+        let version = AdvertisedVersion {
+            name: "Proxy 1.21.x".to_string(),
+            protocol: 767,
+            accepted: AdvertisedVersion::parse_range("767 - 770").unwrap(),
+        };
+        assert_eq!(version.version_info(Some(769)).protocol, 769);
+        assert_eq!(version.version_info(Some(47)).protocol, 767);
+        assert_eq!(version.version_info(None).name, "Proxy 1.21.x");
+        assert!(version.accepts(PROTOCOL_VERSION));
+        assert!(version.is_outdated_client(766));
+        assert!(!version.is_outdated_client(772));
+
+        assert_eq!(AdvertisedVersion::parse_range("771"), Some(771..=771));
+        assert_eq!(AdvertisedVersion::parse_range("771-767"), None);
+        assert_eq!(AdvertisedVersion::parse_range("new"), None);
+    }
+}
@@ -0,0 +1,435 @@
+//! Tick watchdog
+//!
+//! The main loop reports tick progress to [`TickProgress`]. A dedicated OS
+//! thread checks it periodically and, if a single tick runs longer than
+//! `max-tick-time`, writes a crash report to `crash-reports/` and (by
+//! default) stops the server. The watchdog runs outside the async runtime so
+//! it keeps working even when the runtime itself is blocked.
+//!
+//! Only the dedicated server binary exits the process, as the vanilla
+//! watchdog does, since a stalled tick may never return. A server embedded
+//! through [`ServerHandle`](crate::server::ServerHandle) must not take its
+//! host down with it: there the watchdog requests a shutdown instead, and
+//! the main loop reports [`ServerError::Crashed`](crate::error::ServerError)
+//! once the tick returns.
+
+use crate::clock::{self, SharedClock};
+use crate::server::ServerState;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Longest time between two watchdog checks
+const MAX_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Directory crash reports are written to
+const CRASH_REPORT_DIR: &str = "crash-reports";
+
+/// Exit code used when the watchdog stops the server
+const CRASH_EXIT_CODE: i32 = 1;
+
+/// Part of the tick the main loop is currently running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickStage {
+    /// Waiting for the world lock
+    AcquireWorld,
+    /// Updating entities, time and weather
+    WorldUpdate,
+    /// Lighting creepers and applying explosions
+    Explosions,
+    /// Advancing sleeping players
+    Sleeping,
+    /// Healing and starving players
+    Hunger,
+    /// Moving spectators along with the entities they view the world through
+    Spectators,
+    /// Updating the nether and sending players through portals
+    Portals,
+    /// Sending queued chunks and unloading chunks nobody sees
+    Chunks,
+    /// Running scheduled tasks
+    Tasks,
+    /// Sending time and weather updates
+    Broadcast,
+    /// Saving the world
+    Autosave,
+}
+
+impl TickStage {
+    /// Every stage, in the order a tick runs them
+    pub const ALL: [TickStage; 11] = [
+        TickStage::AcquireWorld,
+        TickStage::WorldUpdate,
+        TickStage::Explosions,
+        TickStage::Sleeping,
+        TickStage::Hunger,
+        TickStage::Spectators,
+        TickStage::Portals,
+        TickStage::Chunks,
+        TickStage::Tasks,
+        TickStage::Broadcast,
+        TickStage::Autosave,
+    ];
+
+    /// Get a human-readable name for this stage
+    pub fn name(&self) -> &'static str {
+        match self {
+            TickStage::AcquireWorld => "acquiring world lock",
+            TickStage::WorldUpdate => "world update",
+            TickStage::Explosions => "explosions",
+            TickStage::Sleeping => "sleeping players",
+            TickStage::Hunger => "hunger",
+            TickStage::Spectators => "spectators",
+            TickStage::Portals => "nether portals",
+            TickStage::Chunks => "chunk sending",
+            TickStage::Tasks => "scheduled tasks",
+            TickStage::Broadcast => "broadcasting world state",
+            TickStage::Autosave => "autosave",
+        }
+    }
+}
+
+/// What the watchdog does when a tick takes too long
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatchdogAction {
+    /// Write a crash report and keep running
+    Report,
+    /// Write a crash report and stop the server (vanilla behavior)
+    ///
+    /// The dedicated server exits with a non-zero code so that a supervisor
+    /// (such as systemd or a restart script) can restart it.
+    #[default]
+    Crash,
+}
+
+impl WatchdogAction {
+    /// Parse an action from its configuration name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "report" => Some(WatchdogAction::Report),
+            "crash" => Some(WatchdogAction::Crash),
+            _ => None,
+        }
+    }
+
+    /// Get the configuration name of this action
+    pub fn name(&self) -> &'static str {
+        match self {
+            WatchdogAction::Report => "report",
+            WatchdogAction::Crash => "crash",
+        }
+    }
+}
+
+/// The tick currently being run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunningTick {
+    /// Tick number, starting at 1
+    pub number: u64,
+    /// When the tick started
+    pub started: Instant,
+    /// Stage the tick is in
+    pub stage: TickStage,
+    /// When the tick entered its stage
+    pub stage_started: Instant,
+}
+
+/// Progress of the tick being run, shared with the watchdog
+#[derive(Debug)]
+pub struct TickProgress {
+    current: Mutex<Option<RunningTick>>,
+    /// Source of the current time for tick start times
+    clock: SharedClock,
+}
+
+impl TickProgress {
+    /// Create progress with no tick running
+    pub fn new() -> Self {
+        Self {
+            current: Mutex::new(None),
+            clock: clock::system(),
+        }
+    }
+
+    /// Time ticks with the given clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record the start of a tick
+    pub fn begin(&self, number: u64) {
+        let now = self.clock.now();
+        *self.lock() = Some(RunningTick {
+            number,
+            started: now,
+            stage: TickStage::AcquireWorld,
+            stage_started: now,
+        });
+    }
+
+    /// Record that the running tick entered a new stage
+    ///
+    /// Returns the stage it left and how long that stage ran.
+    pub fn enter(&self, stage: TickStage) -> Option<(TickStage, Duration)> {
+        let now = self.clock.now();
+        let mut current = self.lock();
+        let tick = current.as_mut()?;
+        let finished = (
+            tick.stage,
+            now.saturating_duration_since(tick.stage_started),
+        );
+        tick.stage = stage;
+        tick.stage_started = now;
+        Some(finished)
+    }
+
+    /// Record the end of the running tick
+    ///
+    /// Returns its last stage and how long that stage ran.
+    pub fn end(&self) -> Option<(TickStage, Duration)> {
+        let now = self.clock.now();
+        let tick = self.lock().take()?;
+        Some((
+            tick.stage,
+            now.saturating_duration_since(tick.stage_started),
+        ))
+    }
+
+    /// Get the running tick, if any
+    pub fn current(&self) -> Option<RunningTick> {
+        *self.lock()
+    }
+
+    /// How long a tick has been running
+    pub fn running_for(&self, tick: &RunningTick) -> Duration {
+        self.clock.since(tick.started)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<RunningTick>> {
+        self.current.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for TickProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Start the watchdog thread
+///
+/// With `exit_process`, a crash exits the process; otherwise it stops the
+/// server through [`ServerState::crash`]. Returns `None` if the watchdog is
+/// disabled (`max-tick-time` is not positive). The thread exits once a
+/// shutdown has been requested.
+pub fn spawn(state: Arc<ServerState>, exit_process: bool) -> Option<JoinHandle<()>> {
+    let max_tick_time = state.config.max_tick_time?;
+    let action = state.config.watchdog_action;
+    let interval = max_tick_time.min(MAX_CHECK_INTERVAL);
+
+    let handle = std::thread::Builder::new()
+        .name("Server Watchdog".to_string())
+        .spawn(move || {
+            let mut reported = None;
+            while !state.is_shutting_down() {
+                std::thread::sleep(interval);
+
+                let Some(tick) = state.tick_progress.current() else {
+                    continue;
+                };
+                let running_for = state.tick_progress.running_for(&tick);
+                if running_for <= max_tick_time || reported == Some(tick.number) {
+                    continue;
+                }
+                reported = Some(tick.number);
+
+                let report = crash_report(&state, &tick, max_tick_time);
+                tracing::error!(
+                    "A single server tick took {:.2} seconds (should be max {:.2})",
+                    running_for.as_secs_f64(),
+                    max_tick_time.as_secs_f64()
+                );
+                if action == WatchdogAction::Crash {
+                    tracing::error!("Considering it to be crashed, server will forcibly shutdown.");
+                }
+                match write_crash_report(&report) {
+                    Ok(path) => {
+                        tracing::error!("This crash report has been saved to: {}", path.display())
+                    }
+                    Err(e) => tracing::error!("Failed to save crash report: {}\n{}", e, report),
+                }
+
+                if action == WatchdogAction::Crash {
+                    let reason = format!(
+                        "tick {} ran for {:.2}s in stage {}",
+                        tick.number,
+                        running_for.as_secs_f64(),
+                        tick.stage.name()
+                    );
+                    crash(&state, reason, exit_process);
+                }
+            }
+        });
+
+    match handle {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            tracing::error!("Failed to start the watchdog: {}", e);
+            None
+        }
+    }
+}
+
+/// Stop a stalled server, exiting the process if asked to
+fn crash(state: &ServerState, reason: String, exit_process: bool) {
+    if exit_process {
+        std::process::exit(CRASH_EXIT_CODE);
+    }
+    state.crash(reason);
+}
+
+/// Build the diagnostic report for a tick that ran too long
+pub fn crash_report(state: &ServerState, tick: &RunningTick, max_tick_time: Duration) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "---- Minecraft Crash Report ----");
+    let _ = writeln!(report, "// Watching Server");
+    let _ = writeln!(report);
+    let _ = writeln!(
+        report,
+        "Time: {}",
+        time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default()
+    );
+    let _ = writeln!(report, "Description: Watching Server");
+    let _ = writeln!(report);
+    let _ = writeln!(report, "-- Tick --");
+    let _ = writeln!(report, "Tick number: {}", tick.number);
+    let _ = writeln!(report, "Current stage: {}", tick.stage.name());
+    let _ = writeln!(
+        report,
+        "Running for: {:.2}s (max-tick-time {}ms)",
+        state.tick_progress.running_for(tick).as_secs_f64(),
+        max_tick_time.as_millis()
+    );
+    let _ = writeln!(report);
+
+    let _ = writeln!(report, "-- Performance --");
+    for (label, secs) in [("1m", 60), ("5m", 300), ("15m", 900)] {
+        let _ = writeln!(
+            report,
+            "TPS ({}): {:.2}",
+            label,
+            state.tick_stats.tps(Duration::from_secs(secs))
+        );
+    }
+    match state.tick_stats.mspt() {
+        Some(mspt) => {
+            let _ = writeln!(
+                report,
+                "MSPT (avg/min/max): {:.2}/{:.2}/{:.2}",
+                mspt.average, mspt.min, mspt.max
+            );
+        }
+        None => {
+            let _ = writeln!(report, "MSPT: no completed ticks");
+        }
+    }
+    let _ = writeln!(report, "Completed ticks: {}", state.tick_stats.tick_count());
+    let _ = writeln!(report);
+
+    let _ = writeln!(report, "-- Threads --");
+    let _ = writeln!(
+        report,
+        "Task backtraces are not available: async tasks cannot be inspected from another thread."
+    );
+    let _ = writeln!(report, "Watchdog thread:");
+    let _ = writeln!(report, "{}", std::backtrace::Backtrace::force_capture());
+    let _ = writeln!(report);
+
+    let _ = writeln!(report, "-- System Details --");
+    let _ = writeln!(report, "Obsidium version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        report,
+        "Minecraft version: {}",
+        crate::protocol::MINECRAFT_VERSION
+    );
+    let _ = writeln!(
+        report,
+        "Operating system: {} ({})",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    report
+}
+
+/// Write a crash report to the crash report directory
+fn write_crash_report(report: &str) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(CRASH_REPORT_DIR)?;
+    let timestamp = time::OffsetDateTime::now_utc()
+        .format(time::macros::format_description!(
+            "[year]-[month]-[day]_[hour].[minute].[second]"
+        ))
+        .unwrap_or_default();
+    let path = PathBuf::from(CRASH_REPORT_DIR).join(format!("crash-{}-server.txt", timestamp));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::config::ServerConfig;
+
+    #[test]
+    fn test_tick_progress_and_report() {
+        let clock = ManualClock::new();
+        let state = ServerState::new(ServerConfig::default()).with_clock(clock.shared());
+        assert_eq!(state.tick_progress.current(), None);
+
+        state.tick_progress.begin(7);
+        clock.advance(Duration::from_millis(40));
+        assert_eq!(
+            state.tick_progress.enter(TickStage::Autosave),
+            Some((TickStage::AcquireWorld, Duration::from_millis(40)))
+        );
+        let tick = state.tick_progress.current().unwrap();
+        assert_eq!((tick.number, tick.stage), (7, TickStage::Autosave));
+
+        clock.advance(Duration::from_millis(2500));
+        assert_eq!(
+            state.tick_progress.running_for(&tick),
+            Duration::from_millis(2540)
+        );
+        let report = crash_report(&state, &tick, Duration::from_secs(60));
+        assert!(report.contains("Tick number: 7"));
+        assert!(report.contains("Current stage: autosave"));
+        assert!(report.contains("Running for: 2.54s"));
+
+        assert_eq!(
+            state.tick_progress.end(),
+            Some((TickStage::Autosave, Duration::from_millis(2500)))
+        );
+        assert_eq!(state.tick_progress.current(), None);
+        assert_eq!(
+            WatchdogAction::from_name("REPORT"),
+            Some(WatchdogAction::Report)
+        );
+    }
+
+    #[test]
+    fn test_crash_stops_embedded_server() {
+        let state = ServerState::new(ServerConfig::default());
+        assert_eq!(state.crash_reason(), None);
+        crash(&state, "tick 3 ran for 61.00s".to_string(), false);
+        assert!(state.is_shutting_down());
+        assert_eq!(
+            state.crash_reason().as_deref(),
+            Some("tick 3 ran for 61.00s")
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Player whitelist
+//!
+//! The whitelist is stored in `whitelist.json` using the vanilla format.
+//! While the `white-list` setting is enabled, only players on it may join.
+
+use crate::error::{Result, ServerError};
+use crate::lang;
+use crate::protocol::types::{JsonTextComponent, McUuid};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// File the whitelist is stored in
+pub const WHITELIST_FILE: &str = "whitelist.json";
+
+/// Message shown to players who are not whitelisted
+pub fn not_whitelisted_message() -> JsonTextComponent {
+    lang::translate("multiplayer.disconnect.not_whitelisted", &[])
+}
+
+/// A whitelisted player
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    /// Player UUID
+    pub uuid: McUuid,
+    /// Player name when they were added
+    pub name: String,
+}
+
+/// The list of players allowed to join while the whitelist is enabled
+#[derive(Debug, Default)]
+pub struct Whitelist {
+    /// File the list is read from (`None` keeps it in memory only)
+    path: Option<PathBuf>,
+    /// Whitelisted players
+    entries: RwLock<Vec<WhitelistEntry>>,
+}
+
+impl Whitelist {
+    /// Create an empty whitelist that is not read from disk
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the whitelist from a file, starting empty if it does not exist
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let whitelist = Self {
+            path: Some(path.as_ref().to_path_buf()),
+            entries: RwLock::default(),
+        };
+        whitelist.reload()?;
+        Ok(whitelist)
+    }
+
+    /// Read the whitelist file again, keeping the current entries on error
+    pub fn reload(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let entries = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| ServerError::Storage(format!("Invalid {}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        *self.entries.write().unwrap_or_else(|e| e.into_inner()) = entries;
+        Ok(())
+    }
+
+    /// Check whether a player is whitelisted
+    pub fn contains(&self, uuid: &McUuid) -> bool {
+        self.read().iter().any(|entry| entry.uuid == *uuid)
+    }
+
+    /// All whitelisted players
+    pub fn entries(&self) -> Vec<WhitelistEntry> {
+        self.read().clone()
+    }
+
+    /// Add a player and save the list, returning whether they were not on it
+    pub fn add(&self, entry: WhitelistEntry) -> Result<bool> {
+        let mut entries = self.write();
+        if entries.iter().any(|existing| existing.uuid == entry.uuid) {
+            return Ok(false);
+        }
+        entries.push(entry);
+        self.save(&entries)?;
+        Ok(true)
+    }
+
+    /// Remove a player by name and save the list, returning whether they were on it
+    pub fn remove(&self, name: &str) -> Result<bool> {
+        let mut entries = self.write();
+        let before = entries.len();
+        entries.retain(|entry| !entry.name.eq_ignore_ascii_case(name));
+        if entries.len() == before {
+            return Ok(false);
+        }
+        self.save(&entries)?;
+        Ok(true)
+    }
+
+    /// Write the list to its file
+    fn save(&self, entries: &[WhitelistEntry]) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(entries)
+            .map_err(|e| ServerError::Storage(format!("Failed to encode whitelist: {}", e)))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<WhitelistEntry>> {
+        self.entries.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<WhitelistEntry>> {
+        self.entries.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_and_reload() {
+        let path =
+            std::env::temp_dir().join(format!("obsidium-whitelist-{}.json", McUuid::new_v4()));
+        let uuid = McUuid::new_v4();
+
+        let whitelist = Whitelist::load(&path).unwrap();
+        assert!(!whitelist.contains(&uuid));
+
+        let entries = vec![WhitelistEntry {
+            uuid,
+            name: "Steve".to_string(),
+        }];
+        std::fs::write(&path, serde_json::to_string(&entries).unwrap()).unwrap();
+        whitelist.reload().unwrap();
+        assert!(whitelist.contains(&uuid));
+
+        assert!(!whitelist.add(entries[0].clone()).unwrap());
+        assert!(whitelist.remove("steve").unwrap());
+        assert!(!whitelist.contains(&uuid));
+        assert!(whitelist.add(entries[0].clone()).unwrap());
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(whitelist.reload().is_err());
+        assert_eq!(whitelist.entries(), entries);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,293 @@
+//! Trace export
+//!
+//! The server opens [`tracing`] spans around the work worth timing: each
+//! connection (`connection`), each packet it handles (`packet`), each game
+//! tick (`tick`) and its stages (`tick_stage`), and chunk loading and
+//! generation (`load_chunk`). The layer built here sends finished spans to an
+//! OpenTelemetry collector as OTLP/HTTP JSON with `opentelemetry-otlp`, so
+//! latency spikes can be traced in production.
+//!
+//! Export is off unless `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` (a full URL,
+//! such as `http://localhost:4318/v1/traces`) or
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` (a base URL, to which `/v1/traces` is
+//! appended) is set. Only plain `http://` endpoints are supported; run a
+//! collector next to the server to forward spans elsewhere. The service is
+//! named after `OTEL_SERVICE_NAME`, `obsidium` by default, and
+//! `OBSIDIUM_TRACE_FILTER` selects the exported spans with the `RUST_LOG`
+//! syntax, `obsidium=debug` by default.
+//!
+//! Spans are batched on a background thread. When the collector can't keep
+//! up, new spans are dropped rather than slowing the server down, and failed
+//! exports are logged at most once per [`WARNING_INTERVAL`].
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{Protocol, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{
+    BatchConfigBuilder, BatchSpanProcessor, SdkTracerProvider, SpanData, SpanExporter, Tracer,
+};
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Default service name reported to the collector
+pub const DEFAULT_SERVICE_NAME: &str = "obsidium";
+
+/// Default filter selecting the exported spans
+pub const DEFAULT_TRACE_FILTER: &str = "obsidium=debug";
+
+/// Shortest time between two warnings about failed exports
+pub const WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Finished spans waiting to be exported before new ones are dropped
+const QUEUE_CAPACITY: usize = 8192;
+
+/// Most spans sent in one request
+const MAX_BATCH_SIZE: usize = 512;
+
+/// How often queued spans are sent by default
+const DEFAULT_EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long to wait for the collector
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A layer exporting finished spans to an OTLP/HTTP collector
+pub type OtlpLayer<S> = OpenTelemetryLayer<S, Tracer>;
+
+/// Create a layer configured from the environment, if export is enabled
+///
+/// An error is returned rather than logged, since the layer is built
+/// before the subscriber that would log it is installed.
+pub fn layer_from_env<S>() -> io::Result<Option<OtlpLayer<S>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let enabled = [
+        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+    ]
+    .iter()
+    .any(|var| std::env::var_os(var).is_some());
+    if !enabled {
+        return Ok(None);
+    }
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE_NAME.to_string());
+    let provider = tracer_provider(None, service_name, DEFAULT_EXPORT_INTERVAL)?;
+    Ok(Some(layer(&provider)))
+}
+
+/// Build a tracer provider exporting to `endpoint`, or to the endpoint the
+/// environment sets if `None`, every `interval`
+///
+/// Shutting the provider down sends the spans still queued.
+pub fn tracer_provider(
+    endpoint: Option<&str>,
+    service_name: impl Into<String>,
+    interval: Duration,
+) -> io::Result<SdkTracerProvider> {
+    let mut builder = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpJson)
+        .with_timeout(REQUEST_TIMEOUT);
+    if let Some(endpoint) = endpoint {
+        builder = builder.with_endpoint(endpoint);
+    }
+    let exporter = builder.build().map_err(io::Error::other)?;
+
+    let batch = BatchConfigBuilder::default()
+        .with_max_queue_size(QUEUE_CAPACITY)
+        .with_max_export_batch_size(MAX_BATCH_SIZE)
+        .with_scheduled_delay(interval)
+        .build();
+    let processor = BatchSpanProcessor::builder(WarnOnFailure::new(exporter))
+        .with_batch_config(batch)
+        .build();
+    Ok(SdkTracerProvider::builder()
+        .with_span_processor(processor)
+        .with_resource(
+            Resource::builder_empty()
+                .with_service_name(service_name.into())
+                .build(),
+        )
+        .build())
+}
+
+/// A layer recording spans with a tracer of `provider`
+pub fn layer<S>(provider: &SdkTracerProvider) -> OtlpLayer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(DEFAULT_SERVICE_NAME))
+        .with_tracked_inactivity(false)
+}
+
+/// Failed exports since the last warning
+#[derive(Debug, Default)]
+struct Failures {
+    /// When the last warning was logged
+    last_warning: Option<Instant>,
+    /// Spans that failed to export since then
+    spans: usize,
+    /// Whether the last export failed
+    failing: bool,
+}
+
+/// Wraps an exporter, logging its failures with a rate limit
+#[derive(Debug)]
+struct WarnOnFailure<E> {
+    /// The exporter sending spans
+    inner: E,
+    /// Failures not logged yet
+    failures: Mutex<Failures>,
+}
+
+impl<E> WarnOnFailure<E> {
+    fn new(inner: E) -> Self {
+        Self {
+            inner,
+            failures: Mutex::default(),
+        }
+    }
+
+    /// Note the outcome of exporting `count` spans
+    fn report(&self, count: usize, result: &OTelSdkResult) {
+        let Ok(mut failures) = self.failures.lock() else {
+            return;
+        };
+        match result {
+            Ok(()) => {
+                if failures.failing {
+                    tracing::info!("Exporting traces again");
+                }
+                failures.failing = false;
+            }
+            Err(e) => {
+                failures.failing = true;
+                failures.spans += count;
+                let due = failures
+                    .last_warning
+                    .is_none_or(|last| last.elapsed() >= WARNING_INTERVAL);
+                if due {
+                    tracing::warn!("Failed to export {} span(s): {}", failures.spans, e);
+                    failures.last_warning = Some(Instant::now());
+                    failures.spans = 0;
+                }
+            }
+        }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for WarnOnFailure<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let count = batch.len();
+        let result = self.inner.export(batch).await;
+        self.report(count, &result);
+        result
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_export_spans() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let collector = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.set_read_timeout(Some(REQUEST_TIMEOUT)).unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            // The exporter closes its side only after reading the reply, so
+            // read until the whole body has arrived
+            loop {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length: usize = head
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim())
+                        })
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let endpoint = format!("http://127.0.0.1:{port}/v1/traces");
+        let provider = tracer_provider(Some(&endpoint), "test", Duration::from_secs(60)).unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer(&provider));
+        tracing::subscriber::with_default(subscriber, || {
+            let tick = tracing::info_span!("tick", number = 7u64);
+            let _tick = tick.enter();
+            let stage = tracing::info_span!("tick_stage", stage = "world_update");
+            stage.in_scope(|| {});
+        });
+        provider.shutdown().unwrap();
+
+        let request = collector.join().unwrap();
+        assert!(request.starts_with("POST /v1/traces HTTP/1.1\r\n"));
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        let body: Value = serde_json::from_str(body).unwrap();
+        let resource = &body["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "test"
+        );
+        let spans = resource["scopeSpans"][0]["spans"].as_array().unwrap();
+        assert_eq!(spans.len(), 2);
+        let (stage, tick) = (&spans[0], &spans[1]);
+        assert_eq!(stage["name"], "tick_stage");
+        assert_eq!(tick["name"], "tick");
+        assert_eq!(stage["traceId"], tick["traceId"]);
+        assert_eq!(stage["parentSpanId"], tick["spanId"]);
+        let attribute = |span: &Value, key: &str| {
+            span["attributes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|attribute| attribute["key"] == key)
+                .map(|attribute| attribute["value"].clone())
+        };
+        assert_eq!(
+            attribute(stage, "stage").unwrap()["stringValue"],
+            "world_update"
+        );
+        assert!(attribute(tick, "number").is_some());
+    }
+}
@@ -0,0 +1,1412 @@
+//! Generated protocol values
+//!
+//! [`Arbitrary`] builds random values from a seeded [`Rng`], so a failing
+//! case can be reproduced from its seed. Generated values are always valid
+//! on the wire: string lengths stay within protocol limits, positions fit
+//! their packed encoding, and presence flags agree with the data they
+//! announce. Integers favour edge cases such as zero and the extremes,
+//! which is where hand-written codecs tend to break.
+
+use crate::game::enchantment::{Enchantment, Enchantments};
+use crate::game::inventory::{ItemStack, MAX_STACK_SIZE};
+use crate::protocol::McUuid;
+use crate::protocol::metadata::{METADATA_END, MetadataEntry, MetadataValue, Pose};
+use crate::protocol::packets::configuration::{
+    AcknowledgeFinishConfigurationPacket, AddResourcePackPacket, ClientInformationPacket,
+    ClientboundPluginMessagePacket, ConfigurationDisconnectPacket, FinishConfigurationPacket,
+    PluginMessagePacket, RegistryDataPacket, RegistryEntry, ResourcePackResponsePacket,
+};
+use crate::protocol::packets::handshaking::{HandshakePacket, LegacyServerListPingPacket};
+use crate::protocol::packets::login::{
+    EncryptionRequestPacket, EncryptionResponsePacket, LoginAcknowledgedPacket,
+    LoginDisconnectPacket, LoginStartPacket, LoginSuccessPacket, Property, SetCompressionPacket,
+};
+use crate::protocol::packets::play::{
+    AcknowledgeBlockChangePacket, BlockChangePacket, ChangeDifficultyPacket,
+    ChangeDifficultyRequestPacket, ChatCommandPacket, ChatDecoration, ChatMessagePacket,
+    ChunkDataPacket, ClickContainerPacket, CloseContainerPacket, CommandNode, CommandNodeKind,
+    CommandsPacket, ConfigurationAcknowledgedPacket, DisconnectPacket, EntityAnimationPacket,
+    EntityEventPacket, EntityPositionSyncPacket, ExplodePacket, FilterMask, GameEventPacket,
+    HashedStack, Heightmap, IdSet, InteractPacket, KeepAlivePacket, LightData,
+    LockDifficultyPacket, LoginPlayPacket, MerchantOffer, MerchantOffersPacket, MoveVehiclePacket,
+    OpenScreenPacket, PlayClientInformationPacket, PlayClientboundPluginMessagePacket,
+    PlayPluginMessagePacket, PlayerAbilitiesPacket, PlayerActionPacket, PlayerChatMessagePacket,
+    PlayerCommandPacket, PlayerInfoEntry, PlayerInfoRemovePacket, PlayerInfoUpdatePacket,
+    PlayerInputPacket, PlayerOnGroundPacket, PlayerPositionAndRotationPacket, PlayerPositionPacket,
+    PlayerRotationPacket, PropertySet, RemoveEntitiesPacket, RespawnPacket, SelectTradePacket,
+    ServerboundKeepAlivePacket, ServerboundPlayerAbilitiesPacket, SetCameraPacket,
+    SetCenterChunkPacket, SetChunkCacheRadiusPacket, SetContainerContentPacket,
+    SetContainerSlotPacket, SetCreativeModeSlotPacket, SetDefaultSpawnPositionPacket,
+    SetEntityMetadataPacket, SetEquipmentPacket, SetHeadRotationPacket, SetHealthPacket,
+    SetHeldItemPacket, SetPassengersPacket, SetSimulationDistancePacket, SoundEffectPacket,
+    SpawnEntityPacket, StartConfigurationPacket, StonecutterEntry, SynchronizePlayerPositionPacket,
+    SystemChatMessagePacket, TeleportToEntityPacket, UnloadChunkPacket,
+    UpdateEntityPositionAndRotationPacket, UpdateEntityPositionPacket, UpdateEntityRotationPacket,
+    UpdateRecipesPacket, UpdateSectionBlocksPacket, UpdateTimePacket, UseItemOnPacket,
+    UseItemPacket,
+};
+use crate::protocol::packets::status::{
+    PingRequestPacket, PingResponsePacket, StatusRequestPacket, StatusResponsePacket,
+};
+use crate::protocol::types::{Identifier, JsonTextComponent, McString, Position, VarInt};
+
+/// Longest string generated by default, in bytes
+const MAX_STRING_BYTES: usize = 48;
+
+/// Longest collection generated
+const MAX_ITEMS: u64 = 6;
+
+/// Characters strings are built from, including multi-byte and escaped ones
+const CHARACTERS: &[char] = &[
+    'a', 'b', 'z', 'A', 'Z', '0', '9', ' ', '_', ':', '/', '"', '\\', '\n', '\0', 'é', 'ß', '€',
+    '語', '🦀',
+];
+
+/// Deterministic pseudo-random number generator (SplitMix64)
+#[derive(Debug, Clone)]
+pub struct Rng {
+    /// Current state
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator from a seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random number below `bound`, which must not be zero
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Random number in an inclusive range
+    pub fn range(&mut self, min: i64, max: i64) -> i64 {
+        let span = max.wrapping_sub(min) as u64;
+        match span.checked_add(1) {
+            Some(bound) => min.wrapping_add(self.below(bound) as i64),
+            None => self.next_u64() as i64,
+        }
+    }
+
+    /// Random boolean
+    pub fn bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    /// Random bytes, up to `max_len` of them
+    pub fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+        let len = self.below(max_len as u64 + 1) as usize;
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+
+    /// Random string of at most `max_bytes` bytes once UTF-8 encoded
+    pub fn string(&mut self, max_bytes: usize) -> String {
+        let target = self.below(max_bytes as u64 + 1) as usize;
+        let mut string = String::new();
+        loop {
+            let c = CHARACTERS[self.below(CHARACTERS.len() as u64) as usize];
+            if string.len() + c.len_utf8() > target {
+                return string;
+            }
+            string.push(c);
+        }
+    }
+
+    /// Random value of any [`Arbitrary`] type
+    pub fn arbitrary<T: Arbitrary>(&mut self) -> T {
+        T::arbitrary(self)
+    }
+
+    /// Random integer in a range, favouring its ends and zero
+    fn edgy(&mut self, min: i64, max: i64) -> i64 {
+        match self.below(8) {
+            0 => min,
+            1 => max,
+            2 if min <= 0 && max >= 0 => 0,
+            _ => self.range(min, max),
+        }
+    }
+}
+
+/// Types that can be generated at random
+pub trait Arbitrary: Sized {
+    /// Generate a value
+    fn arbitrary(rng: &mut Rng) -> Self;
+}
+
+impl Arbitrary for bool {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.bool()
+    }
+}
+
+/// Implement [`Arbitrary`] for integers, favouring edge cases
+macro_rules! arbitrary_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl Arbitrary for $ty {
+                fn arbitrary(rng: &mut Rng) -> Self {
+                    rng.edgy(<$ty>::MIN as i64, <$ty>::MAX as i64) as $ty
+                }
+            }
+        )*
+    };
+}
+
+arbitrary_integer!(u8, i8, u16, i16, i32, i64);
+
+impl Arbitrary for f32 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        // Any bit pattern, NaNs included, must survive the wire unchanged
+        f32::from_bits(rng.next_u64() as u32)
+    }
+}
+
+impl Arbitrary for f64 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        f64::from_bits(rng.next_u64())
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.bool().then(|| T::arbitrary(rng))
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Vec<T> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        (0..rng.below(MAX_ITEMS + 1))
+            .map(|_| T::arbitrary(rng))
+            .collect()
+    }
+}
+
+impl Arbitrary for VarInt {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        VarInt(rng.arbitrary())
+    }
+}
+
+impl Arbitrary for McString {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        McString(rng.string(MAX_STRING_BYTES))
+    }
+}
+
+impl Arbitrary for Identifier {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Identifier(rng.string(MAX_STRING_BYTES))
+    }
+}
+
+impl Arbitrary for McUuid {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        McUuid::from_u64_pair(rng.next_u64(), rng.next_u64())
+    }
+}
+
+impl Arbitrary for Position {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        // X and Z are packed into 26 bits, Y into 12
+        let horizontal = (1 << 25) - 1;
+        Position::new(
+            rng.edgy(-horizontal - 1, horizontal) as i32,
+            rng.edgy(-2048, 2047) as i32,
+            rng.edgy(-horizontal - 1, horizontal) as i32,
+        )
+    }
+}
+
+impl Arbitrary for JsonTextComponent {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let text = rng.string(MAX_STRING_BYTES);
+        if rng.bool() {
+            JsonTextComponent::text(&text)
+        } else {
+            JsonTextComponent::colored(&text, "gold")
+        }
+    }
+}
+
+impl Arbitrary for Pose {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Pose::from_id(rng.below(8) as i32).unwrap_or_default()
+    }
+}
+
+impl Arbitrary for MetadataValue {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        match rng.below(10) {
+            0 => MetadataValue::Byte(rng.arbitrary()),
+            1 => MetadataValue::VarInt(rng.arbitrary()),
+            2 => MetadataValue::Float(rng.arbitrary()),
+            3 => MetadataValue::String(rng.string(MAX_STRING_BYTES)),
+            4 => MetadataValue::Boolean(rng.arbitrary()),
+            5 => MetadataValue::OptionalPosition(rng.arbitrary()),
+            6 => MetadataValue::OptionalTextComponent(rng.arbitrary()),
+            7 => MetadataValue::Slot(rng.arbitrary()),
+            8 => MetadataValue::Rotations([rng.arbitrary(), rng.arbitrary(), rng.arbitrary()]),
+            _ => MetadataValue::Pose(rng.arbitrary()),
+        }
+    }
+}
+
+impl Arbitrary for MetadataEntry {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let index = rng.below(u64::from(METADATA_END)) as u8;
+        MetadataEntry::new(index, rng.arbitrary())
+    }
+}
+
+impl Arbitrary for Property {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Property {
+            name: rng.arbitrary(),
+            value: rng.arbitrary(),
+            signature: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for RegistryEntry {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let data: Option<Vec<u8>> = rng.bool().then(|| rng.bytes(32));
+        RegistryEntry {
+            entry_id: rng.arbitrary(),
+            has_data: data.is_some(),
+            data,
+        }
+    }
+}
+
+impl Arbitrary for HandshakePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        HandshakePacket {
+            protocol_version: rng.arbitrary(),
+            server_address: McString(rng.string(255)),
+            server_port: rng.arbitrary(),
+            next_state: VarInt(rng.range(1, 3) as i32),
+        }
+    }
+}
+
+impl Arbitrary for LegacyServerListPingPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        LegacyServerListPingPacket {
+            payload: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for StatusRequestPacket {
+    fn arbitrary(_rng: &mut Rng) -> Self {
+        StatusRequestPacket
+    }
+}
+
+impl Arbitrary for StatusResponsePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        StatusResponsePacket {
+            json_response: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PingRequestPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PingRequestPacket {
+            payload: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PingResponsePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PingResponsePacket {
+            payload: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for LoginDisconnectPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        LoginDisconnectPacket {
+            reason: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for LoginStartPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        LoginStartPacket {
+            name: McString(rng.string(16)),
+            player_uuid: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for EncryptionRequestPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        EncryptionRequestPacket {
+            server_id: McString(rng.string(EncryptionRequestPacket::MAX_SERVER_ID_LENGTH)),
+            public_key: rng.bytes(EncryptionRequestPacket::MAX_FIELD_LENGTH),
+            verify_token: rng.bytes(EncryptionRequestPacket::MAX_FIELD_LENGTH),
+            should_authenticate: rng.bool(),
+        }
+    }
+}
+
+impl Arbitrary for EncryptionResponsePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        EncryptionResponsePacket {
+            shared_secret: rng.bytes(EncryptionResponsePacket::MAX_FIELD_LENGTH),
+            verify_token: rng.bytes(EncryptionResponsePacket::MAX_FIELD_LENGTH),
+        }
+    }
+}
+
+impl Arbitrary for LoginSuccessPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        LoginSuccessPacket {
+            uuid: rng.arbitrary(),
+            username: McString(rng.string(16)),
+            properties: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetCompressionPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetCompressionPacket {
+            threshold: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for LoginAcknowledgedPacket {
+    fn arbitrary(_rng: &mut Rng) -> Self {
+        LoginAcknowledgedPacket
+    }
+}
+
+impl Arbitrary for ConfigurationDisconnectPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ConfigurationDisconnectPacket {
+            reason: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for FinishConfigurationPacket {
+    fn arbitrary(_rng: &mut Rng) -> Self {
+        FinishConfigurationPacket
+    }
+}
+
+impl Arbitrary for AcknowledgeFinishConfigurationPacket {
+    fn arbitrary(_rng: &mut Rng) -> Self {
+        AcknowledgeFinishConfigurationPacket
+    }
+}
+
+impl Arbitrary for RegistryDataPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        RegistryDataPacket {
+            registry_id: rng.arbitrary(),
+            entries: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for ClientInformationPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ClientInformationPacket {
+            locale: McString(rng.string(16)),
+            view_distance: rng.arbitrary(),
+            chat_mode: rng.arbitrary(),
+            chat_colors: rng.arbitrary(),
+            displayed_skin_parts: rng.arbitrary(),
+            main_hand: rng.arbitrary(),
+            enable_text_filtering: rng.arbitrary(),
+            allow_server_listings: rng.arbitrary(),
+            particle_status: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PluginMessagePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PluginMessagePacket {
+            channel: rng.arbitrary(),
+            data: rng.bytes(64),
+        }
+    }
+}
+
+impl Arbitrary for ClientboundPluginMessagePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ClientboundPluginMessagePacket(rng.arbitrary())
+    }
+}
+
+impl Arbitrary for AddResourcePackPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        AddResourcePackPacket {
+            uuid: rng.arbitrary(),
+            url: rng.arbitrary(),
+            hash: McString(rng.string(AddResourcePackPacket::MAX_HASH_LENGTH)),
+            forced: rng.arbitrary(),
+            prompt: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for ResourcePackResponsePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ResourcePackResponsePacket {
+            uuid: rng.arbitrary(),
+            result: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for KeepAlivePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        KeepAlivePacket {
+            keep_alive_id: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for ServerboundKeepAlivePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ServerboundKeepAlivePacket {
+            keep_alive_id: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerInfoEntry {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerInfoEntry {
+            uuid: rng.arbitrary(),
+            name: McString(rng.string(16)),
+            properties: rng.arbitrary(),
+            game_mode: VarInt(rng.range(0, 3) as i32),
+            listed: rng.bool(),
+            latency: rng.arbitrary(),
+            display_name: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerInfoUpdatePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let supported = PlayerInfoUpdatePacket::ADD_PLAYER
+            | PlayerInfoUpdatePacket::UPDATE_GAME_MODE
+            | PlayerInfoUpdatePacket::UPDATE_LISTED
+            | PlayerInfoUpdatePacket::UPDATE_LATENCY
+            | PlayerInfoUpdatePacket::UPDATE_DISPLAY_NAME;
+        PlayerInfoUpdatePacket {
+            actions: rng.next_u64() as u8 & supported,
+            entries: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerInfoRemovePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerInfoRemovePacket {
+            uuids: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SpawnEntityPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SpawnEntityPacket {
+            entity_id: rng.arbitrary(),
+            uuid: rng.arbitrary(),
+            entity_type: rng.arbitrary(),
+            x: rng.arbitrary(),
+            y: rng.arbitrary(),
+            z: rng.arbitrary(),
+            pitch: rng.next_u64() as u8,
+            yaw: rng.next_u64() as u8,
+            head_yaw: rng.next_u64() as u8,
+            data: rng.arbitrary(),
+            velocity: [
+                rng.next_u64() as i16,
+                rng.next_u64() as i16,
+                rng.next_u64() as i16,
+            ],
+        }
+    }
+}
+
+impl Arbitrary for SetPassengersPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetPassengersPacket {
+            entity_id: rng.arbitrary(),
+            passengers: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for EntityPositionSyncPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        EntityPositionSyncPacket {
+            entity_id: rng.arbitrary(),
+            x: rng.arbitrary(),
+            y: rng.arbitrary(),
+            z: rng.arbitrary(),
+            velocity: [rng.arbitrary(), rng.arbitrary(), rng.arbitrary()],
+            yaw: rng.arbitrary(),
+            pitch: rng.arbitrary(),
+            on_ground: rng.bool(),
+        }
+    }
+}
+
+impl Arbitrary for MoveVehiclePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        MoveVehiclePacket {
+            x: rng.arbitrary(),
+            y: rng.arbitrary(),
+            z: rng.arbitrary(),
+            yaw: rng.arbitrary(),
+            pitch: rng.arbitrary(),
+            on_ground: rng.bool(),
+        }
+    }
+}
+
+impl Arbitrary for InteractPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let kind = rng.range(0, 2) as i32;
+        InteractPacket {
+            entity_id: rng.arbitrary(),
+            kind: VarInt(kind),
+            target: (kind == InteractPacket::INTERACT_AT)
+                .then(|| [rng.arbitrary(), rng.arbitrary(), rng.arbitrary()]),
+            hand: (kind != InteractPacket::ATTACK).then(|| VarInt(rng.range(0, 1) as i32)),
+            sneaking: rng.bool(),
+        }
+    }
+}
+
+impl Arbitrary for ExplodePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ExplodePacket {
+            x: rng.arbitrary(),
+            y: rng.arbitrary(),
+            z: rng.arbitrary(),
+            player_knockback: rng
+                .bool()
+                .then(|| [rng.arbitrary(), rng.arbitrary(), rng.arbitrary()]),
+            particle: rng.arbitrary(),
+            sound: McString(rng.string(64)),
+            sound_range: rng.bool().then(|| rng.arbitrary()),
+        }
+    }
+}
+
+impl Arbitrary for RemoveEntitiesPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        RemoveEntitiesPacket {
+            entity_ids: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for EntityEventPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        EntityEventPacket {
+            entity_id: rng.arbitrary(),
+            event: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerActionPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerActionPacket {
+            status: rng.arbitrary(),
+            location: rng.arbitrary(),
+            face: rng.arbitrary(),
+            sequence: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for AcknowledgeBlockChangePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        AcknowledgeBlockChangePacket {
+            sequence: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SynchronizePlayerPositionPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SynchronizePlayerPositionPacket {
+            teleport_id: rng.arbitrary(),
+            x: rng.arbitrary(),
+            y: rng.arbitrary(),
+            z: rng.arbitrary(),
+            velocity: [rng.arbitrary(), rng.arbitrary(), rng.arbitrary()],
+            yaw: rng.arbitrary(),
+            pitch: rng.arbitrary(),
+            flags: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetCameraPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetCameraPacket {
+            camera_id: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for TeleportToEntityPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        TeleportToEntityPacket {
+            target: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for DisconnectPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        DisconnectPacket {
+            reason: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SystemChatMessagePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SystemChatMessagePacket {
+            content: rng.arbitrary(),
+            overlay: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for StartConfigurationPacket {
+    fn arbitrary(_rng: &mut Rng) -> Self {
+        StartConfigurationPacket
+    }
+}
+
+impl Arbitrary for ConfigurationAcknowledgedPacket {
+    fn arbitrary(_rng: &mut Rng) -> Self {
+        ConfigurationAcknowledgedPacket
+    }
+}
+
+impl Arbitrary for ChatMessagePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ChatMessagePacket {
+            message: McString(rng.string(ChatMessagePacket::MAX_MESSAGE_LENGTH)),
+            timestamp: rng.arbitrary(),
+            salt: rng.arbitrary(),
+            signature: rng
+                .bool()
+                .then(|| rng.bytes(ChatMessagePacket::SIGNATURE_LENGTH)),
+            message_count: rng.arbitrary(),
+            acknowledged: rng.bytes(ChatMessagePacket::ACKNOWLEDGED_LENGTH),
+        }
+    }
+}
+
+impl Arbitrary for ChatCommandPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ChatCommandPacket {
+            command: McString(rng.string(64)),
+        }
+    }
+}
+
+impl Arbitrary for FilterMask {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        match rng.below(3) {
+            0 => FilterMask::PassThrough,
+            1 => FilterMask::FullyFiltered,
+            _ => {
+                let words = rng.below(4) as usize;
+                FilterMask::PartiallyFiltered((0..words).map(|_| rng.next_u64() as i64).collect())
+            }
+        }
+    }
+}
+
+impl Arbitrary for ChatDecoration {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let count = rng.below(4) as usize;
+        ChatDecoration {
+            translation_key: McString(rng.string(32)),
+            parameters: (0..count).map(|_| VarInt(rng.range(0, 2) as i32)).collect(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerChatMessagePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerChatMessagePacket {
+            global_index: rng.arbitrary(),
+            sender: rng.arbitrary(),
+            index: rng.arbitrary(),
+            signature: rng.bool().then(|| {
+                (0..ChatMessagePacket::SIGNATURE_LENGTH)
+                    .map(|_| rng.next_u64() as u8)
+                    .collect()
+            }),
+            message: McString(rng.string(ChatMessagePacket::MAX_MESSAGE_LENGTH)),
+            timestamp: rng.arbitrary(),
+            salt: rng.arbitrary(),
+            unsigned_content: rng.bool().then(|| rng.arbitrary()),
+            filter: rng.arbitrary(),
+            chat: rng.arbitrary(),
+            narration: rng.arbitrary(),
+            sender_name: rng.arbitrary(),
+            target_name: rng.bool().then(|| rng.arbitrary()),
+        }
+    }
+}
+
+impl Arbitrary for ItemStack {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let mut enchantments = Enchantments::new();
+        for enchantment in Enchantment::ALL {
+            if rng.bool() {
+                enchantments.set(enchantment, rng.range(0, 255) as u8);
+            }
+        }
+        ItemStack::new(
+            rng.range(0, i64::from(i32::MAX)) as i32,
+            rng.range(1, i64::from(MAX_STACK_SIZE)) as i32,
+        )
+        .with_enchantments(enchantments)
+    }
+}
+
+impl Arbitrary for SetEquipmentPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let count = rng.range(1, 8) as usize;
+        SetEquipmentPacket {
+            entity_id: rng.arbitrary(),
+            equipment: (0..count)
+                .map(|_| (rng.below(8) as u8, rng.arbitrary()))
+                .collect(),
+        }
+    }
+}
+
+impl Arbitrary for SetHeldItemPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetHeldItemPacket {
+            slot: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetCreativeModeSlotPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetCreativeModeSlotPacket {
+            slot: rng.arbitrary(),
+            item: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for UseItemPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        UseItemPacket {
+            hand: rng.arbitrary(),
+            sequence: rng.arbitrary(),
+            yaw: rng.arbitrary(),
+            pitch: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetContainerSlotPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetContainerSlotPacket {
+            window_id: rng.arbitrary(),
+            state_id: rng.arbitrary(),
+            slot: rng.arbitrary(),
+            item: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SoundEffectPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SoundEffectPacket {
+            sound: rng.arbitrary(),
+            fixed_range: rng.arbitrary(),
+            category: rng.arbitrary(),
+            x: rng.arbitrary(),
+            y: rng.arbitrary(),
+            z: rng.arbitrary(),
+            volume: rng.arbitrary(),
+            pitch: rng.arbitrary(),
+            seed: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for OpenScreenPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        OpenScreenPacket {
+            window_id: rng.arbitrary(),
+            window_type: rng.arbitrary(),
+            title: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for MerchantOffer {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        // Costs carry no components
+        let cost = |rng: &mut Rng| ItemStack::new(rng.arbitrary(), rng.arbitrary());
+        MerchantOffer {
+            input: cost(rng),
+            output: rng.arbitrary(),
+            second_input: rng.bool().then(|| cost(rng)),
+            disabled: rng.arbitrary(),
+            uses: rng.arbitrary(),
+            max_uses: rng.arbitrary(),
+            experience: rng.arbitrary(),
+            special_price: rng.arbitrary(),
+            price_multiplier: rng.arbitrary(),
+            demand: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for MerchantOffersPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        MerchantOffersPacket {
+            window_id: rng.arbitrary(),
+            offers: rng.arbitrary(),
+            level: rng.arbitrary(),
+            experience: rng.arbitrary(),
+            regular_villager: rng.arbitrary(),
+            can_restock: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SelectTradePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SelectTradePacket {
+            selected: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for HashedStack {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        HashedStack {
+            item: rng.arbitrary(),
+            count: rng.arbitrary(),
+            added: (0..rng.below(MAX_ITEMS + 1))
+                .map(|_| (rng.arbitrary(), rng.arbitrary()))
+                .collect(),
+            removed: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for ClickContainerPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ClickContainerPacket {
+            window_id: rng.arbitrary(),
+            state_id: rng.arbitrary(),
+            slot: rng.arbitrary(),
+            button: rng.arbitrary(),
+            mode: rng.arbitrary(),
+            changed_slots: (0..rng.below(MAX_ITEMS + 1))
+                .map(|_| (rng.arbitrary(), rng.arbitrary()))
+                .collect(),
+            carried: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for CloseContainerPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        CloseContainerPacket {
+            window_id: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetContainerContentPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetContainerContentPacket {
+            window_id: rng.arbitrary(),
+            state_id: rng.arbitrary(),
+            slots: rng.arbitrary(),
+            carried: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for CommandNode {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let kind = match rng.below(3) {
+            0 => CommandNodeKind::Root,
+            1 => CommandNodeKind::Literal(rng.arbitrary()),
+            _ => CommandNodeKind::StringArgument {
+                name: rng.arbitrary(),
+                behavior: rng.arbitrary(),
+            },
+        };
+        CommandNode {
+            kind,
+            executable: rng.arbitrary(),
+            children: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for CommandsPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        CommandsPacket {
+            nodes: rng.arbitrary(),
+            root_index: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerInputPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerInputPacket {
+            flags: rng.next_u64() as u8,
+        }
+    }
+}
+
+impl Arbitrary for SetHealthPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetHealthPacket {
+            health: rng.arbitrary(),
+            food: rng.arbitrary(),
+            saturation: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerPositionPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerPositionPacket {
+            x: rng.arbitrary(),
+            y: rng.arbitrary(),
+            z: rng.arbitrary(),
+            on_ground: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerPositionAndRotationPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerPositionAndRotationPacket {
+            x: rng.arbitrary(),
+            y: rng.arbitrary(),
+            z: rng.arbitrary(),
+            yaw: rng.arbitrary(),
+            pitch: rng.arbitrary(),
+            on_ground: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerRotationPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerRotationPacket {
+            yaw: rng.arbitrary(),
+            pitch: rng.arbitrary(),
+            on_ground: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerOnGroundPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerOnGroundPacket {
+            on_ground: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for ServerboundPlayerAbilitiesPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ServerboundPlayerAbilitiesPacket {
+            flying: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerAbilitiesPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerAbilitiesPacket {
+            flags: rng.below(16) as u8,
+            flying_speed: rng.arbitrary(),
+            field_of_view_modifier: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for UpdateEntityPositionPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        UpdateEntityPositionPacket {
+            entity_id: rng.arbitrary(),
+            delta: [rng.arbitrary(), rng.arbitrary(), rng.arbitrary()],
+            on_ground: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for UpdateEntityPositionAndRotationPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        UpdateEntityPositionAndRotationPacket {
+            entity_id: rng.arbitrary(),
+            delta: [rng.arbitrary(), rng.arbitrary(), rng.arbitrary()],
+            yaw: rng.arbitrary(),
+            pitch: rng.arbitrary(),
+            on_ground: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for UpdateEntityRotationPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        UpdateEntityRotationPacket {
+            entity_id: rng.arbitrary(),
+            yaw: rng.arbitrary(),
+            pitch: rng.arbitrary(),
+            on_ground: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetHeadRotationPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetHeadRotationPacket {
+            entity_id: rng.arbitrary(),
+            head_yaw: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for BlockChangePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        BlockChangePacket {
+            position: rng.arbitrary(),
+            block_id: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for UpdateSectionBlocksPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let horizontal = 1 << 21;
+        let vertical = 1 << 19;
+        UpdateSectionBlocksPacket {
+            section: [
+                rng.range(-horizontal, horizontal - 1) as i32,
+                rng.range(-vertical, vertical - 1) as i32,
+                rng.range(-horizontal, horizontal - 1) as i32,
+            ],
+            blocks: (0..rng.below(8))
+                .map(|_| (rng.below(4096) as u16, rng.below(1 << 20) as u32))
+                .collect(),
+        }
+    }
+}
+
+impl Arbitrary for LoginPlayPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let death: Option<(McString, Position)> =
+            rng.bool().then(|| (rng.arbitrary(), rng.arbitrary()));
+        LoginPlayPacket {
+            entity_id: rng.arbitrary(),
+            is_hardcore: rng.arbitrary(),
+            dimension_names: rng.arbitrary(),
+            max_players: rng.arbitrary(),
+            view_distance: rng.arbitrary(),
+            simulation_distance: rng.arbitrary(),
+            reduced_debug_info: rng.arbitrary(),
+            enable_respawn_screen: rng.arbitrary(),
+            do_limited_crafting: rng.arbitrary(),
+            dimension_type: rng.arbitrary(),
+            dimension_name: rng.arbitrary(),
+            hashed_seed: rng.arbitrary(),
+            game_mode: rng.arbitrary(),
+            previous_game_mode: rng.arbitrary(),
+            is_debug: rng.arbitrary(),
+            is_flat: rng.arbitrary(),
+            has_death_location: death.is_some(),
+            death_dimension_name: death.as_ref().map(|(name, _)| name.clone()),
+            death_location: death.map(|(_, position)| position),
+            portal_cooldown: rng.arbitrary(),
+            sea_level: rng.arbitrary(),
+            enforces_secure_chat: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for RespawnPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        RespawnPacket {
+            dimension_type: rng.arbitrary(),
+            dimension_name: rng.arbitrary(),
+            hashed_seed: rng.arbitrary(),
+            game_mode: rng.arbitrary(),
+            previous_game_mode: rng.arbitrary(),
+            is_debug: rng.arbitrary(),
+            is_flat: rng.arbitrary(),
+            death_location: rng.bool().then(|| (rng.arbitrary(), rng.arbitrary())),
+            portal_cooldown: rng.arbitrary(),
+            sea_level: rng.arbitrary(),
+            data_kept: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for ChangeDifficultyPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ChangeDifficultyPacket {
+            difficulty: rng.arbitrary(),
+            locked: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for ChangeDifficultyRequestPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ChangeDifficultyRequestPacket {
+            difficulty: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for LockDifficultyPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        LockDifficultyPacket {
+            locked: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for UpdateTimePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        UpdateTimePacket {
+            world_age: rng.arbitrary(),
+            time_of_day: rng.arbitrary(),
+            time_of_day_increasing: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for GameEventPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        GameEventPacket::new(rng.arbitrary(), rng.arbitrary())
+    }
+}
+
+impl Arbitrary for PlayClientInformationPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayClientInformationPacket(rng.arbitrary())
+    }
+}
+
+impl Arbitrary for PlayPluginMessagePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayPluginMessagePacket(rng.arbitrary())
+    }
+}
+
+impl Arbitrary for PlayClientboundPluginMessagePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayClientboundPluginMessagePacket(rng.arbitrary())
+    }
+}
+
+impl Arbitrary for SetSimulationDistancePacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetSimulationDistancePacket {
+            simulation_distance: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetChunkCacheRadiusPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetChunkCacheRadiusPacket {
+            view_distance: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for Heightmap {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Heightmap {
+            kind: rng.arbitrary(),
+            data: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for LightData {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        // Real arrays hold 2048 bytes; short ones keep truncation checks fast
+        let arrays = |rng: &mut Rng| (0..rng.below(3)).map(|_| rng.bytes(64)).collect::<Vec<_>>();
+        LightData {
+            sky_light_mask: rng.arbitrary(),
+            block_light_mask: rng.arbitrary(),
+            empty_sky_light_mask: rng.arbitrary(),
+            empty_block_light_mask: rng.arbitrary(),
+            sky_light: arrays(rng),
+            block_light: arrays(rng),
+        }
+    }
+}
+
+impl Arbitrary for ChunkDataPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ChunkDataPacket {
+            chunk_x: rng.arbitrary(),
+            chunk_z: rng.arbitrary(),
+            heightmaps: rng.arbitrary(),
+            data: rng.bytes(256),
+            light: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetCenterChunkPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetCenterChunkPacket {
+            chunk_x: rng.arbitrary(),
+            chunk_z: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for UnloadChunkPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        UnloadChunkPacket {
+            chunk_z: rng.arbitrary(),
+            chunk_x: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetDefaultSpawnPositionPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetDefaultSpawnPositionPacket {
+            location: rng.arbitrary(),
+            angle: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for SetEntityMetadataPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SetEntityMetadataPacket {
+            entity_id: rng.arbitrary(),
+            metadata: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for EntityAnimationPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        EntityAnimationPacket {
+            entity_id: rng.arbitrary(),
+            animation: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for PlayerCommandPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PlayerCommandPacket {
+            entity_id: rng.arbitrary(),
+            action: rng.arbitrary(),
+            jump_boost: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for UseItemOnPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        UseItemOnPacket {
+            hand: rng.arbitrary(),
+            location: rng.arbitrary(),
+            face: rng.arbitrary(),
+            cursor_x: rng.arbitrary(),
+            cursor_y: rng.arbitrary(),
+            cursor_z: rng.arbitrary(),
+            inside_block: rng.arbitrary(),
+            world_border_hit: rng.arbitrary(),
+            sequence: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for IdSet {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        if rng.bool() {
+            IdSet::Tag(rng.arbitrary())
+        } else {
+            IdSet::Ids(rng.arbitrary())
+        }
+    }
+}
+
+impl Arbitrary for PropertySet {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PropertySet {
+            id: rng.arbitrary(),
+            items: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for StonecutterEntry {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        StonecutterEntry {
+            ingredient: rng.arbitrary(),
+            result_item: rng.arbitrary(),
+            result_count: rng.arbitrary(),
+        }
+    }
+}
+
+impl Arbitrary for UpdateRecipesPacket {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        UpdateRecipesPacket {
+            property_sets: rng.arbitrary(),
+            stonecutter_recipes: rng.arbitrary(),
+        }
+    }
+}
@@ -0,0 +1,995 @@
+//! Headless protocol client
+//!
+//! [`TestClient`] speaks just enough of the protocol to drive a server
+//! through the server list ping and a login into the play state. It
+//! answers an online-mode server's Encryption Request, but does not
+//! announce the join to the session server, so such a server has to be
+//! pointed at a `profile-api` that vouches for the player. Given the
+//! forwarding secret, it answers like a Velocity proxy.
+
+use crate::config::secrets::Secret;
+use crate::error::{Result, ServerError};
+use crate::network::Connection;
+use crate::network::velocity::{self, ForwardedPlayer};
+use crate::protocol::encryption::{SECRET_LENGTH, random_bytes};
+use crate::protocol::packets::Packet;
+use crate::protocol::packets::configuration::{
+    AcknowledgeFinishConfigurationPacket, AddResourcePackPacket, ConfigurationDisconnectPacket,
+    FinishConfigurationPacket, PluginMessagePacket, ResourcePackResponsePacket, ResourcePackStatus,
+};
+use crate::protocol::packets::handshaking::{HandshakePacket, NextState};
+use crate::protocol::packets::login::{
+    EncryptionRequestPacket, EncryptionResponsePacket, LoginAcknowledgedPacket,
+    LoginDisconnectPacket, LoginPluginRequestPacket, LoginPluginResponsePacket, LoginStartPacket,
+    LoginSuccessPacket, SetCompressionPacket,
+};
+use crate::protocol::packets::play::{
+    ConfigurationAcknowledgedPacket, DisconnectPacket, LoginPlayPacket, StartConfigurationPacket,
+};
+use crate::protocol::packets::status::{
+    PingRequestPacket, PingResponsePacket, ServerStatus, StatusRequestPacket, StatusResponsePacket,
+};
+use crate::protocol::rsa::RsaPublicKey;
+use crate::protocol::{ConnectionState, McUuid, PROTOCOL_VERSION, VarInt};
+use crate::server::profiles::GameProfile;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+/// How long to wait for the server before failing
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Brand the client reports to the server
+pub const CLIENT_BRAND: &str = "obsidium-test";
+
+/// How a login attempt ended
+#[derive(Debug)]
+pub enum LoginOutcome {
+    /// The player reached the play state
+    Joined(Box<LoginPlayPacket>),
+    /// The server disconnected the player, with the reason as plain text
+    Disconnected(String),
+}
+
+/// A minimal client connected to a server
+pub struct TestClient {
+    /// Connection to the server
+    connection: Connection,
+    /// UUID sent when logging in
+    uuid: McUuid,
+    /// Protocol version sent in the handshake
+    protocol_version: i32,
+    /// How the client answers resource pack offers
+    resource_pack_status: ResourcePackStatus,
+    /// How long to wait for each packet
+    timeout: Duration,
+    /// Secret and client address to forward like a Velocity proxy
+    velocity: Option<(Secret, IpAddr)>,
+}
+
+impl TestClient {
+    /// Open a connection to a server
+    pub async fn connect(addr: SocketAddr) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        Ok(Self::from_stream(stream, addr))
+    }
+
+    /// Open a connection to a server like a proxy would for a client at
+    /// `client`, starting with a PROXY protocol header
+    pub async fn connect_via_proxy(addr: SocketAddr, client: SocketAddr) -> Result<Self> {
+        let mut stream = TcpStream::connect(addr).await?;
+        let family = if client.is_ipv4() { "TCP4" } else { "TCP6" };
+        let header = format!(
+            "PROXY {} {} {} {} {}\r\n",
+            family,
+            client.ip(),
+            addr.ip(),
+            client.port(),
+            addr.port()
+        );
+        stream.write_all(header.as_bytes()).await?;
+        Ok(Self::from_stream(stream, addr))
+    }
+
+    /// Wrap a stream connected to a server
+    fn from_stream(stream: TcpStream, addr: SocketAddr) -> Self {
+        Self {
+            connection: Connection::new(stream, addr),
+            uuid: McUuid::new_v4(),
+            protocol_version: PROTOCOL_VERSION,
+            resource_pack_status: ResourcePackStatus::Loaded,
+            timeout: DEFAULT_TIMEOUT,
+            velocity: None,
+        }
+    }
+
+    /// Set the UUID sent when logging in
+    ///
+    /// A random UUID is used by default.
+    pub fn with_uuid(mut self, uuid: McUuid) -> Self {
+        self.uuid = uuid;
+        self
+    }
+
+    /// Set the protocol version sent in the handshake
+    ///
+    /// The server's own version is used by default.
+    pub fn with_protocol_version(mut self, protocol_version: i32) -> Self {
+        self.protocol_version = protocol_version;
+        self
+    }
+
+    /// Set how the client answers resource pack offers
+    ///
+    /// Packs are accepted and reported as loaded by default.
+    pub fn with_resource_pack_status(mut self, status: ResourcePackStatus) -> Self {
+        self.resource_pack_status = status;
+        self
+    }
+
+    /// Set how long to wait for each packet
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Forward the player like a Velocity proxy for a client at `address`
+    ///
+    /// Otherwise Velocity's player info requests go unanswered, like a
+    /// vanilla client's.
+    pub fn with_velocity_forwarding(mut self, secret: Secret, address: IpAddr) -> Self {
+        self.velocity = Some((secret, address));
+        self
+    }
+
+    /// UUID sent when logging in
+    pub fn uuid(&self) -> McUuid {
+        self.uuid
+    }
+
+    /// The underlying connection, for anything the client does not cover
+    pub fn connection(&mut self) -> &mut Connection {
+        &mut self.connection
+    }
+
+    /// Fetch a server's status as shown in the server list
+    pub async fn status(addr: SocketAddr) -> Result<ServerStatus> {
+        let mut client = Self::connect(addr).await?;
+        client.handshake(NextState::Status).await?;
+        client.send(&StatusRequestPacket).await?;
+        let response: StatusResponsePacket = client.expect_packet().await?;
+        ServerStatus::from_json(&response.json_response.0)
+    }
+
+    /// Measure the round trip of a server list ping
+    pub async fn ping(addr: SocketAddr) -> Result<Duration> {
+        let mut client = Self::connect(addr).await?;
+        client.handshake(NextState::Status).await?;
+
+        let payload = 0x0B51_D1A3;
+        let started = Instant::now();
+        client.send(&PingRequestPacket { payload }).await?;
+        let response: PingResponsePacket = client.expect_packet().await?;
+        if response.payload != payload {
+            return Err(ServerError::Protocol(format!(
+                "Ping payload {} does not match {}",
+                response.payload, payload
+            )));
+        }
+        Ok(started.elapsed())
+    }
+
+    /// Log in and go through configuration to play
+    ///
+    /// Packets the client has no use for, such as registry data, are
+    /// skipped.
+    pub async fn login(&mut self, username: &str) -> Result<LoginOutcome> {
+        self.handshake(NextState::Login).await?;
+        self.send(&LoginStartPacket {
+            name: username.into(),
+            player_uuid: self.uuid,
+        })
+        .await?;
+
+        loop {
+            let (id, data) = self.read_packet().await?;
+            match id.0 {
+                LoginDisconnectPacket::ID => {
+                    let packet: LoginDisconnectPacket = decode(&data)?;
+                    return Ok(LoginOutcome::Disconnected(packet.reason.to_plain_text()));
+                }
+                EncryptionRequestPacket::ID => {
+                    let packet: EncryptionRequestPacket = decode(&data)?;
+                    self.answer_encryption_request(&packet).await?;
+                }
+                LoginPluginRequestPacket::ID => {
+                    let packet: LoginPluginRequestPacket = decode(&data)?;
+                    self.answer_plugin_request(&packet, username).await?;
+                }
+                SetCompressionPacket::ID => {
+                    let packet: SetCompressionPacket = decode(&data)?;
+                    if let Ok(threshold) = u32::try_from(packet.threshold.0) {
+                        self.connection.enable_compression(threshold)?;
+                    }
+                }
+                LoginSuccessPacket::ID => break,
+                id => return Err(unexpected(id, ConnectionState::Login)),
+            }
+        }
+
+        self.send(&LoginAcknowledgedPacket).await?;
+        self.connection.set_state(ConnectionState::Configuration)?;
+        self.send(&PluginMessagePacket::brand(CLIENT_BRAND)).await?;
+        self.configure().await
+    }
+
+    /// Wait to be sent back to the configuration state while playing and go
+    /// through it to play again
+    pub async fn reconfigure(&mut self) -> Result<LoginOutcome> {
+        self.expect_packet::<StartConfigurationPacket>().await?;
+        self.send(&ConfigurationAcknowledgedPacket).await?;
+        self.connection.set_state(ConnectionState::Configuration)?;
+        self.configure().await
+    }
+
+    /// Go through the configuration state to play
+    async fn configure(&mut self) -> Result<LoginOutcome> {
+        loop {
+            let (id, data) = self.read_packet().await?;
+            match id.0 {
+                ConfigurationDisconnectPacket::ID => {
+                    let packet: ConfigurationDisconnectPacket = decode(&data)?;
+                    return Ok(LoginOutcome::Disconnected(packet.reason.to_plain_text()));
+                }
+                FinishConfigurationPacket::ID => break,
+                AddResourcePackPacket::ID => {
+                    let packet: AddResourcePackPacket = decode(&data)?;
+                    self.answer_resource_pack(packet.uuid).await?;
+                }
+                _ => {}
+            }
+        }
+
+        self.send(&AcknowledgeFinishConfigurationPacket).await?;
+        self.connection.set_state(ConnectionState::Play)?;
+        loop {
+            let (id, data) = self.read_packet().await?;
+            match id.0 {
+                DisconnectPacket::ID => {
+                    let packet: DisconnectPacket = decode(&data)?;
+                    return Ok(LoginOutcome::Disconnected(packet.reason.to_plain_text()));
+                }
+                LoginPlayPacket::ID => return Ok(LoginOutcome::Joined(Box::new(decode(&data)?))),
+                _ => {}
+            }
+        }
+    }
+
+    /// Send a packet to the server
+    pub async fn send<P: Packet>(&mut self, packet: &P) -> Result<()> {
+        let timeout = self.timeout;
+        within(timeout, self.connection.write_packet(packet)).await
+    }
+
+    /// Read the next packet, failing if none arrives in time
+    pub async fn read_packet(&mut self) -> Result<(VarInt, Vec<u8>)> {
+        let timeout = self.timeout;
+        within(timeout, self.connection.read_packet()).await
+    }
+
+    /// Read packets until one with `P`'s ID arrives, skipping the others
+    ///
+    /// Packet IDs depend on the connection state, so this is only
+    /// meaningful for packets of the state the client is in.
+    pub async fn expect_packet<P: Packet>(&mut self) -> Result<P> {
+        loop {
+            let (id, data) = self.read_packet().await?;
+            if id.0 == P::ID {
+                return decode(&data);
+            }
+        }
+    }
+
+    /// Wait to be disconnected while playing, returning the reason
+    pub async fn expect_disconnect(&mut self) -> Result<String> {
+        let packet: DisconnectPacket = self.expect_packet().await?;
+        Ok(packet.reason.to_plain_text())
+    }
+
+    /// Log in, asserting that the player reaches the play state
+    pub async fn assert_joins(addr: SocketAddr, username: &str) -> Self {
+        let mut client = Self::connect(addr).await.expect("failed to connect");
+        match client.login(username).await.expect("login failed") {
+            LoginOutcome::Joined(_) => client,
+            LoginOutcome::Disconnected(reason) => {
+                unreachable!("{} was disconnected: {}", username, reason)
+            }
+        }
+    }
+
+    /// Log in, asserting that the server refuses the player with a reason
+    /// containing `expected`
+    pub async fn assert_refused(addr: SocketAddr, username: &str, expected: &str) {
+        let mut client = Self::connect(addr).await.expect("failed to connect");
+        match client.login(username).await.expect("login failed") {
+            LoginOutcome::Disconnected(reason) => assert!(
+                reason.contains(expected),
+                "{} was disconnected with '{}', expected '{}'",
+                username,
+                reason,
+                expected
+            ),
+            LoginOutcome::Joined(_) => unreachable!("{} was allowed to join", username),
+        }
+    }
+
+    /// Send a fresh shared secret encrypted with the server's key and
+    /// encrypt the connection with it, like a real client
+    async fn answer_encryption_request(&mut self, request: &EncryptionRequestPacket) -> Result<()> {
+        let key = RsaPublicKey::from_der(&request.public_key)?;
+        let mut secret = [0u8; SECRET_LENGTH];
+        random_bytes(&mut secret)?;
+        self.send(&EncryptionResponsePacket {
+            shared_secret: key.encrypt(&secret)?,
+            verify_token: key.encrypt(&request.verify_token)?,
+        })
+        .await?;
+        self.connection.enable_encryption(&secret);
+        Ok(())
+    }
+
+    /// Send the player info a Velocity proxy would, or say the request was
+    /// not understood
+    async fn answer_plugin_request(
+        &mut self,
+        request: &LoginPluginRequestPacket,
+        username: &str,
+    ) -> Result<()> {
+        let data = match self.velocity {
+            Some((ref secret, address)) if request.channel.0 == velocity::CHANNEL => {
+                let player = ForwardedPlayer {
+                    address,
+                    profile: GameProfile {
+                        uuid: self.uuid,
+                        name: username.to_string(),
+                        properties: Vec::new(),
+                    },
+                };
+                Some(player.sign(secret)?)
+            }
+            _ => None,
+        };
+        self.send(&LoginPluginResponsePacket {
+            message_id: request.message_id,
+            data,
+        })
+        .await
+    }
+
+    /// Answer a resource pack offer, reporting progress like a real client
+    async fn answer_resource_pack(&mut self, uuid: McUuid) -> Result<()> {
+        let mut statuses = vec![self.resource_pack_status];
+        if self.resource_pack_status != ResourcePackStatus::Declined {
+            statuses.insert(0, ResourcePackStatus::Accepted);
+        }
+        for status in statuses {
+            self.send(&ResourcePackResponsePacket {
+                uuid,
+                result: status.into(),
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Send the handshake for the next state
+    async fn handshake(&mut self, next_state: NextState) -> Result<()> {
+        let addr = self.connection.peer_addr();
+        self.send(&HandshakePacket {
+            protocol_version: VarInt(self.protocol_version),
+            server_address: addr.ip().to_string().into(),
+            server_port: addr.port(),
+            next_state: VarInt(next_state as i32),
+        })
+        .await?;
+        self.connection.set_state(match next_state {
+            NextState::Status => ConnectionState::Status,
+            NextState::Login | NextState::Transfer => ConnectionState::Login,
+        })
+    }
+}
+
+/// Decode a packet body
+fn decode<P: Packet>(data: &[u8]) -> Result<P> {
+    P::read(&mut std::io::Cursor::new(data))
+}
+
+/// Describe a packet the client did not expect
+fn unexpected(id: i32, state: ConnectionState) -> ServerError {
+    ServerError::Protocol(format!(
+        "Unexpected packet 0x{:02X} in {:?} state",
+        id, state
+    ))
+}
+
+/// Run a network operation, failing if it takes longer than `timeout`
+async fn within<T>(timeout: Duration, operation: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(timeout, operation)
+        .await
+        .unwrap_or_else(|_| {
+            Err(ServerError::Io(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("no response from the server within {:?}", timeout),
+            )))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::command::RconSender;
+    use crate::config::ServerConfig;
+    use crate::config::properties::ServerProperties;
+    use crate::game::world::ChunkPosition;
+    use crate::game::world::budget::ChunkView;
+    use crate::game::world::dimension::Dimension;
+    use crate::network::ForwardingConfig;
+    use crate::network::rate_limit::MAX_MALFORMED_PACKETS;
+    use crate::protocol::MINECRAFT_VERSION;
+    use crate::protocol::packets::RawPacket;
+    use crate::protocol::packets::play::{
+        ChunkDataPacket, PlayerCommandPacket, PlayerPositionPacket, SetCenterChunkPacket,
+        SetChunkCacheRadiusPacket, SetSimulationDistancePacket, UnloadChunkPacket,
+    };
+    use crate::server::MinecraftServer;
+    use crate::server::audit::AuditConfig;
+    use crate::server::bans::BANNED_IPS_FILE;
+    use crate::server::resource_pack::ResourcePack;
+    use crate::server::usercache::USERCACHE_FILE;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_status_and_login() {
+        let level = std::env::temp_dir().join(format!("obsidium-testing-{}", uuid::Uuid::new_v4()));
+        let config = ServerConfig::new()
+            .with_bind_address("127.0.0.1:0".parse().unwrap())
+            .with_level_name(level.to_string_lossy().into_owned())
+            .with_usercache_path(Some(level.join(USERCACHE_FILE)))
+            .with_online_mode(false)
+            .with_max_players(1)
+            .with_compression_threshold(Some(64))
+            .with_view_distance(2)
+            .with_plugins_enabled(false)
+            .with_hot_reload(false)
+            .with_max_tick_time(None)
+            .with_login_throttle(Duration::ZERO)
+            .with_audit(AuditConfig::disabled());
+        let handle = MinecraftServer::new(config)
+            .await
+            .unwrap()
+            .start()
+            .await
+            .unwrap();
+        let addr = handle.local_addr();
+
+        let status = TestClient::status(addr).await.unwrap();
+        assert_eq!(status.version.protocol, PROTOCOL_VERSION);
+        assert_eq!(status.players.online, 0);
+        assert!(TestClient::ping(addr).await.is_ok());
+
+        let mut client = TestClient::assert_joins(addr, "Steve").await;
+        assert_eq!(handle.player_count(), 1);
+        TestClient::assert_refused(addr, "Alex", "Server is full").await;
+        let mut outdated = TestClient::connect(addr)
+            .await
+            .unwrap()
+            .with_protocol_version(PROTOCOL_VERSION - 1);
+        match outdated.login("Alex").await.unwrap() {
+            LoginOutcome::Disconnected(reason) => assert!(reason.starts_with("Outdated client")),
+            LoginOutcome::Joined(_) => unreachable!("an outdated client joined"),
+        }
+        let mut newer = TestClient::connect(addr)
+            .await
+            .unwrap()
+            .with_protocol_version(PROTOCOL_VERSION + 1);
+        match newer.login("Alex").await.unwrap() {
+            LoginOutcome::Disconnected(reason) => {
+                assert_eq!(
+                    reason,
+                    format!("Outdated server! I'm still on {}", MINECRAFT_VERSION)
+                );
+            }
+            LoginOutcome::Joined(_) => unreachable!("a newer client joined"),
+        }
+
+        // An outdated client can still ping, and sees our version in the list
+        let mut pinging = TestClient::connect(addr)
+            .await
+            .unwrap()
+            .with_protocol_version(PROTOCOL_VERSION - 1);
+        pinging.handshake(NextState::Status).await.unwrap();
+        pinging.send(&StatusRequestPacket).await.unwrap();
+        let response: StatusResponsePacket = pinging.expect_packet().await.unwrap();
+        let status = ServerStatus::from_json(&response.json_response.0).unwrap();
+        assert_eq!(status.version.protocol, PROTOCOL_VERSION);
+        assert_eq!(status.version.name, MINECRAFT_VERSION);
+
+        // Logging in again ends the older session
+        let mut again = TestClient::connect(addr)
+            .await
+            .unwrap()
+            .with_uuid(client.uuid());
+        assert!(matches!(
+            again.login("Steve").await.unwrap(),
+            LoginOutcome::Joined(_)
+        ));
+        assert_eq!(
+            client.expect_disconnect().await.unwrap(),
+            "You logged in from another location"
+        );
+        assert_eq!(handle.player_count(), 1);
+        let mut client = again;
+
+        // Repeated malformed packets are answered with a protocol error
+        let truncated = RawPacket {
+            id: PlayerCommandPacket::ID,
+            data: Vec::new(),
+        };
+        for _ in 0..MAX_MALFORMED_PACKETS {
+            client
+                .connection()
+                .write_raw_packet(&truncated)
+                .await
+                .unwrap();
+        }
+        let reason = client.expect_disconnect().await.unwrap();
+        assert_eq!(reason, "Network Protocol Error");
+        while handle.player_count() > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        handle.stop();
+        handle.wait().await.unwrap();
+        let _ = std::fs::remove_dir_all(level);
+    }
+
+    #[tokio::test]
+    async fn test_login_timeout() {
+        let level = std::env::temp_dir().join(format!("obsidium-testing-{}", uuid::Uuid::new_v4()));
+        let config = ServerConfig::new()
+            .with_bind_address("127.0.0.1:0".parse().unwrap())
+            .with_online_mode(false)
+            .with_level_name(level.to_string_lossy().into_owned())
+            .with_usercache_path(Some(level.join(USERCACHE_FILE)))
+            .with_view_distance(2)
+            .with_plugins_enabled(false)
+            .with_hot_reload(false)
+            .with_max_tick_time(None)
+            .with_login_throttle(Duration::ZERO)
+            .with_audit(AuditConfig::disabled());
+        let login_timeout = config.login_timeout;
+        let clock = ManualClock::new();
+        let handle = MinecraftServer::new(config)
+            .await
+            .unwrap()
+            .with_clock(clock.shared())
+            .start()
+            .await
+            .unwrap();
+        let addr = handle.local_addr();
+
+        // A connection that has not logged in by the deadline is closed
+        // when it next sends something
+        let mut slow = TestClient::connect(addr).await.unwrap();
+        slow.handshake(NextState::Status).await.unwrap();
+        slow.send(&StatusRequestPacket).await.unwrap();
+        slow.expect_packet::<StatusResponsePacket>().await.unwrap();
+        clock.advance(login_timeout + Duration::from_secs(1));
+        slow.send(&PingRequestPacket { payload: 1 }).await.unwrap();
+        assert!(slow.read_packet().await.is_err());
+
+        // The deadline counts from when each connection opened
+        let mut client = TestClient::assert_joins(addr, "Steve").await;
+        clock.advance(login_timeout + Duration::from_secs(1));
+        assert!(handle.state().reconfigure_player(&client.uuid()));
+        assert!(matches!(
+            client.reconfigure().await.unwrap(),
+            LoginOutcome::Joined(_)
+        ));
+        assert_eq!(handle.player_count(), 1);
+
+        handle.stop();
+        handle.wait().await.unwrap();
+        let _ = std::fs::remove_dir_all(level);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_forwarding() {
+        let level = std::env::temp_dir().join(format!("obsidium-testing-{}", uuid::Uuid::new_v4()));
+        let config = ServerConfig::new()
+            .with_bind_address("127.0.0.1:0".parse().unwrap())
+            .with_online_mode(false)
+            .with_level_name(level.to_string_lossy().into_owned())
+            .with_properties_path(Some(level.join("server.properties")))
+            .with_view_distance(2)
+            .with_plugins_enabled(false)
+            .with_hot_reload(false)
+            .with_max_tick_time(None)
+            .with_forwarding(ForwardingConfig {
+                trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+                required: true,
+            })
+            .with_audit(AuditConfig::disabled());
+        let throttle = config.login_throttle;
+        let clock = ManualClock::new();
+        let handle = MinecraftServer::new(config)
+            .await
+            .unwrap()
+            .with_clock(clock.shared())
+            .start()
+            .await
+            .unwrap();
+        let addr = handle.local_addr();
+        let alice: SocketAddr = "192.0.2.1:50000".parse().unwrap();
+        let bob: SocketAddr = "192.0.2.2:50000".parse().unwrap();
+        let login = |client: SocketAddr, name: &'static str| async move {
+            let mut proxied = TestClient::connect_via_proxy(addr, client).await.unwrap();
+            let outcome = proxied.login(name).await.unwrap();
+            (proxied, outcome)
+        };
+
+        // Logins are throttled by the forwarded address, not the proxy's
+        let (mut steve, outcome) = login(alice, "Steve").await;
+        assert!(matches!(outcome, LoginOutcome::Joined(_)));
+        let (_alex, outcome) = login(bob, "Alex").await;
+        assert!(matches!(outcome, LoginOutcome::Joined(_)));
+        match login(alice, "Notch").await.1 {
+            LoginOutcome::Disconnected(reason) => assert!(reason.contains("throttled")),
+            LoginOutcome::Joined(_) => unreachable!("a throttled address joined"),
+        }
+        let player = handle.state().players.get_player(&steve.uuid());
+        assert_eq!(player.unwrap().ip, Some(alice.ip()));
+
+        // IP bans apply to the forwarded address
+        let state = handle.state();
+        let sender = RconSender::new();
+        assert_eq!(
+            state.commands.execute(state, &sender, "ban-ip Steve").await,
+            Ok(1)
+        );
+        assert!(
+            steve
+                .expect_disconnect()
+                .await
+                .unwrap()
+                .contains("IP address is banned")
+        );
+        assert_eq!(handle.player_count(), 1);
+        clock.advance(throttle);
+        match login(alice, "Notch").await.1 {
+            LoginOutcome::Disconnected(reason) => assert!(reason.contains("IP address is banned")),
+            LoginOutcome::Joined(_) => unreachable!("a banned address joined"),
+        }
+        assert!(level.join(BANNED_IPS_FILE).exists());
+
+        // Connections without a forwarded address are refused
+        let mut unforwarded = TestClient::connect(addr).await.unwrap();
+        unforwarded.handshake(NextState::Status).await.unwrap();
+        unforwarded.send(&StatusRequestPacket).await.unwrap();
+        assert!(unforwarded.read_packet().await.is_err());
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.2:0".parse().unwrap()).unwrap();
+        let mut untrusted = socket.connect(addr).await.unwrap();
+        let _ = untrusted
+            .write_all(b"PROXY TCP4 192.0.2.3 127.0.0.1 50000 25565\r\n")
+            .await;
+        assert!(matches!(untrusted.read(&mut [0; 1]).await, Ok(0) | Err(_)));
+
+        handle.stop();
+        handle.wait().await.unwrap();
+        let _ = std::fs::remove_dir_all(level);
+    }
+
+    #[tokio::test]
+    async fn test_velocity_forwarding() {
+        let level = std::env::temp_dir().join(format!("obsidium-testing-{}", uuid::Uuid::new_v4()));
+        let secret = Secret::new("hunter2").unwrap();
+        let config = ServerConfig::new()
+            .with_bind_address("127.0.0.1:0".parse().unwrap())
+            .with_online_mode(false)
+            .with_level_name(level.to_string_lossy().into_owned())
+            .with_properties_path(Some(level.join("server.properties")))
+            .with_view_distance(2)
+            .with_plugins_enabled(false)
+            .with_hot_reload(false)
+            .with_max_tick_time(None)
+            .with_forwarding_secret(Some(secret.clone()))
+            .with_audit(AuditConfig::disabled());
+        let handle = MinecraftServer::new(config)
+            .await
+            .unwrap()
+            .start()
+            .await
+            .unwrap();
+        let addr = handle.local_addr();
+
+        // The player is who the proxy says, connecting from where it says
+        let client: IpAddr = "192.0.2.1".parse().unwrap();
+        let mut proxied = TestClient::connect(addr)
+            .await
+            .unwrap()
+            .with_velocity_forwarding(secret, client);
+        assert!(matches!(
+            proxied.login("Steve").await.unwrap(),
+            LoginOutcome::Joined(_)
+        ));
+        let player = handle.state().players.get_player(&proxied.uuid());
+        assert_eq!(player.unwrap().ip, Some(client));
+
+        // Clients that do not come through the proxy are refused
+        TestClient::assert_refused(addr, "Alex", "connect with Velocity").await;
+        let mut forged = TestClient::connect(addr)
+            .await
+            .unwrap()
+            .with_velocity_forwarding(Secret::new("hunter3").unwrap(), client);
+        match forged.login("Notch").await.unwrap() {
+            LoginOutcome::Disconnected(reason) => assert!(reason.contains("verify username")),
+            LoginOutcome::Joined(_) => unreachable!("a forged player joined"),
+        }
+        assert_eq!(handle.player_count(), 1);
+
+        handle.stop();
+        handle.wait().await.unwrap();
+        let _ = std::fs::remove_dir_all(level);
+    }
+
+    #[tokio::test]
+    async fn test_required_resource_pack() {
+        let level = std::env::temp_dir().join(format!("obsidium-testing-{}", uuid::Uuid::new_v4()));
+        let properties_path = level.join("server.properties");
+        let pack = ResourcePack::new("http://127.0.0.1:1/pack.zip", "");
+        let config = ServerConfig::new()
+            .with_properties_path(Some(properties_path.clone()))
+            .with_bind_address("127.0.0.1:0".parse().unwrap())
+            .with_online_mode(false)
+            .with_level_name(level.to_string_lossy().into_owned())
+            .with_usercache_path(Some(level.join(USERCACHE_FILE)))
+            .with_view_distance(2)
+            .with_plugins_enabled(false)
+            .with_hot_reload(false)
+            .with_max_tick_time(None)
+            .with_login_throttle(Duration::ZERO)
+            .with_audit(AuditConfig::disabled())
+            .with_resource_pack(Some(pack), true);
+        let clock = ManualClock::new();
+        let handle = MinecraftServer::new(config)
+            .await
+            .unwrap()
+            .with_clock(clock.shared())
+            .start()
+            .await
+            .unwrap();
+        let addr = handle.local_addr();
+
+        let mut declining = TestClient::connect(addr)
+            .await
+            .unwrap()
+            .with_resource_pack_status(ResourcePackStatus::Declined);
+        match declining.login("Alex").await.unwrap() {
+            LoginOutcome::Disconnected(reason) => {
+                assert_eq!(reason, "Server requires a custom resource pack")
+            }
+            LoginOutcome::Joined(_) => unreachable!("a player declining the pack joined"),
+        }
+        let mut client = TestClient::assert_joins(addr, "Steve").await;
+
+        // A player sent back to configuration is offered the pack again,
+        // however long ago they logged in
+        clock.advance(ServerConfig::new().login_timeout * 2);
+        assert!(handle.state().reconfigure_player(&client.uuid()));
+        assert!(matches!(
+            client.reconfigure().await.unwrap(),
+            LoginOutcome::Joined(_)
+        ));
+        assert_eq!(handle.player_count(), 1);
+
+        // Changing the pack in server.properties offers players the new one
+        let mut properties = ServerProperties::new();
+        properties.set_online_mode(false);
+        properties.set_resource_pack("http://127.0.0.1:1/new.zip");
+        properties.set_require_resource_pack(true);
+        std::fs::create_dir_all(&level).unwrap();
+        properties.save_to_file(&properties_path).unwrap();
+        let report = handle.state().reload_config().await.unwrap();
+        assert!(report.applied.contains(&"resource-pack"));
+        assert!(matches!(
+            client.reconfigure().await.unwrap(),
+            LoginOutcome::Joined(_)
+        ));
+        let offered = handle.state().settings().resource_pack.unwrap();
+        assert_eq!(offered.url, "http://127.0.0.1:1/new.zip");
+        assert_eq!(handle.player_count(), 1);
+
+        handle.stop();
+        handle.wait().await.unwrap();
+        let _ = std::fs::remove_dir_all(level);
+    }
+
+    /// Chunks a client was sent or told to unload, and where its view was
+    /// last centered
+    #[derive(Debug, Default)]
+    struct ChunkUpdates {
+        loaded: Vec<ChunkPosition>,
+        unloaded: Vec<ChunkPosition>,
+        center: Option<ChunkPosition>,
+    }
+
+    /// Read packets until `count` chunks arrived
+    async fn receive_chunks(client: &mut TestClient, count: usize) -> ChunkUpdates {
+        let mut updates = ChunkUpdates::default();
+        while updates.loaded.len() < count {
+            let (id, data) = client.read_packet().await.unwrap();
+            match id.0 {
+                ChunkDataPacket::ID => {
+                    let chunk: ChunkDataPacket = decode(&data).unwrap();
+                    updates
+                        .loaded
+                        .push(ChunkPosition::new(chunk.chunk_x, chunk.chunk_z));
+                }
+                UnloadChunkPacket::ID => {
+                    let chunk: UnloadChunkPacket = decode(&data).unwrap();
+                    updates
+                        .unloaded
+                        .push(ChunkPosition::new(chunk.chunk_x, chunk.chunk_z));
+                }
+                SetCenterChunkPacket::ID => {
+                    let center: SetCenterChunkPacket = decode(&data).unwrap();
+                    updates.center = Some(ChunkPosition::new(center.chunk_x.0, center.chunk_z.0));
+                }
+                _ => {}
+            }
+        }
+        updates
+    }
+
+    #[tokio::test]
+    async fn test_runtime_distances() {
+        let level = std::env::temp_dir().join(format!("obsidium-testing-{}", uuid::Uuid::new_v4()));
+        let config = ServerConfig::new()
+            .with_bind_address("127.0.0.1:0".parse().unwrap())
+            .with_online_mode(false)
+            .with_level_name(level.to_string_lossy().into_owned())
+            .with_usercache_path(Some(level.join(USERCACHE_FILE)))
+            .with_view_distance(2)
+            .with_simulation_distance(2)
+            .with_plugins_enabled(false)
+            .with_hot_reload(false)
+            .with_max_tick_time(None)
+            .with_login_throttle(Duration::ZERO)
+            .with_audit(AuditConfig::disabled());
+        let handle = MinecraftServer::new(config)
+            .await
+            .unwrap()
+            .start()
+            .await
+            .unwrap();
+        let state = handle.state();
+        let mut client = TestClient::assert_joins(handle.local_addr(), "Steve").await;
+        let joined = receive_chunks(&mut client, 25).await;
+        let center = joined.center.unwrap();
+        let view = |center, radius| ChunkView {
+            dimension: Dimension::Overworld,
+            center,
+            radius,
+        };
+        assert!(joined.loaded.iter().all(|p| view(center, 2).contains(*p)));
+
+        // A new simulation distance is pushed to players
+        state.set_simulation_distance(6);
+        let distance: SetSimulationDistancePacket = client.expect_packet().await.unwrap();
+        assert_eq!(distance.simulation_distance.0, 6);
+
+        // A wider view sends just the ring of chunks that came into sight
+        state.set_view_distance(3);
+        let radius: SetChunkCacheRadiusPacket = client.expect_packet().await.unwrap();
+        assert_eq!(radius.view_distance.0, 3);
+        let widened = receive_chunks(&mut client, 24).await;
+        assert!(widened.loaded.iter().all(|p| !view(center, 2).contains(*p)));
+        assert!(widened.loaded.iter().all(|p| view(center, 3).contains(*p)));
+
+        // Moving a chunk east recenters the view, unloads the west column
+        // and sends the east one
+        let position = state.players.get_player(&client.uuid()).unwrap().position;
+        client
+            .send(&PlayerPositionPacket {
+                x: position.x + 16.0,
+                y: position.y,
+                z: position.z,
+                on_ground: true,
+            })
+            .await
+            .unwrap();
+        let moved = receive_chunks(&mut client, 7).await;
+        assert_eq!(
+            moved.center,
+            Some(ChunkPosition::new(center.x + 1, center.z))
+        );
+        assert_eq!(moved.unloaded.len(), 7);
+        assert!(moved.unloaded.iter().all(|p| p.x == center.x - 3));
+        assert!(moved.loaded.iter().all(|p| p.x == center.x + 4));
+
+        handle.stop();
+        handle.wait().await.unwrap();
+        let _ = std::fs::remove_dir_all(level);
+    }
+
+    #[tokio::test]
+    async fn test_online_login() {
+        // A session server that vouches for Notch only
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let uuid = McUuid::new_v4();
+        let session = tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    break;
+                };
+                let mut request = [0; 1024];
+                let length = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..length]);
+                assert!(request.contains("/session/minecraft/hasJoined?username="));
+                assert!(request.contains("&serverId="));
+                let body = if request.contains("username=Notch&") {
+                    format!(
+                        r#"{{"id":"{}","name":"Notch","properties":[{{"name":"textures","value":"e30=","signature":"sig"}}]}}"#,
+                        uuid.simple()
+                    )
+                } else {
+                    String::new()
+                };
+                let status = if body.is_empty() {
+                    "204 No Content"
+                } else {
+                    "200 OK"
+                };
+                let response = format!("HTTP/1.0 {}\r\n\r\n{}", status, body);
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let level = std::env::temp_dir().join(format!("obsidium-testing-{}", uuid::Uuid::new_v4()));
+        let config = ServerConfig::new()
+            .with_bind_address("127.0.0.1:0".parse().unwrap())
+            .with_level_name(level.to_string_lossy().into_owned())
+            .with_usercache_path(Some(level.join(USERCACHE_FILE)))
+            .with_profile_api(Some(format!("http://127.0.0.1:{}/", port)))
+            .with_compression_threshold(Some(64))
+            .with_view_distance(2)
+            .with_plugins_enabled(false)
+            .with_hot_reload(false)
+            .with_max_tick_time(None)
+            .with_login_throttle(Duration::ZERO)
+            .with_audit(AuditConfig::disabled());
+        let handle = MinecraftServer::new(config)
+            .await
+            .unwrap()
+            .start()
+            .await
+            .unwrap();
+        let addr = handle.local_addr();
+
+        // The session server's UUID wins over the one the client claims
+        let _client = TestClient::assert_joins(addr, "Notch").await;
+        assert!(handle.state().players.get_player(&uuid).is_some());
+        TestClient::assert_refused(addr, "Herobrine", "Failed to verify username").await;
+
+        handle.stop();
+        handle.wait().await.unwrap();
+        session.abort();
+        let _ = std::fs::remove_dir_all(level);
+    }
+}
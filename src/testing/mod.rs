@@ -0,0 +1,45 @@
+//! Test support
+//!
+//! Helpers for testing the server and code built on it:
+//!
+//! - [`client`] - a headless client that drives a server through the
+//!   server list ping and a login, so integration tests can
+//!   exercise the whole login flow against a server started in-process with
+//!   [`MinecraftServer::start`](crate::server::MinecraftServer::start)
+//! - [`arbitrary`] - seeded generators for protocol types and packets
+//! - [`roundtrip`] - a harness checking that packet codecs agree with
+//!   themselves on generated packets
+//! - [`replay`] - replays recorded packet dumps against a server
+//!
+//! ```rust,no_run
+//! use obsidium::config::ServerConfig;
+//! use obsidium::server::MinecraftServer;
+//! use obsidium::testing::TestClient;
+//!
+//! # async fn example() -> obsidium::Result<()> {
+//! let config = ServerConfig::new()
+//!     .with_bind_address("127.0.0.1:0".parse().unwrap())
+//!     .with_online_mode(false);
+//! let handle = MinecraftServer::new(config).await?.start().await?;
+//!
+//! let status = TestClient::status(handle.local_addr()).await?;
+//! assert_eq!(status.players.online, 0);
+//!
+//! let client = TestClient::assert_joins(handle.local_addr(), "Steve").await;
+//! assert_eq!(handle.player_count(), 1);
+//! # drop(client);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod arbitrary;
+pub mod client;
+pub mod replay;
+pub mod roundtrip;
+
+pub use arbitrary::{Arbitrary, Rng};
+pub use client::{LoginOutcome, TestClient};
+pub use replay::{ReplayReport, Replayer};
+pub use roundtrip::{
+    assert_roundtrip, assert_roundtrip_open_ended, check_roundtrip, check_roundtrip_open_ended,
+};
@@ -0,0 +1,360 @@
+//! Packet replay
+//!
+//! [`Replayer`] plays the client side of a [`PacketDump`] against a running
+//! server, so a session recorded with `--dump-packets` can be reproduced
+//! deterministically. Serverbound packets are sent as recorded, optionally
+//! at the recorded pace. Clientbound packets that change what the client
+//! must do next (compression, login success, the end of configuration, the
+//! start of play and disconnects) are waited for before going on, and
+//! everything else the server sends is read and counted.
+//!
+//! To feed recorded packets to a handler in isolation instead, iterate over
+//! [`PacketDump::serverbound`] and decode entries with
+//! [`DumpEntry::decode`].
+
+use crate::error::{Result, ServerError};
+use crate::network::dump::{Direction, DumpEntry, PacketDump};
+use crate::protocol::packets::Packet;
+use crate::protocol::packets::configuration::{
+    ConfigurationDisconnectPacket, FinishConfigurationPacket,
+};
+use crate::protocol::packets::login::{
+    LoginDisconnectPacket, LoginSuccessPacket, SetCompressionPacket,
+};
+use crate::protocol::packets::play::{DisconnectPacket, LoginPlayPacket};
+use crate::protocol::{ConnectionState, PROTOCOL_VERSION};
+use crate::testing::client::{DEFAULT_TIMEOUT, TestClient};
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How long to keep reading after the last packet by default
+pub const DEFAULT_SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// What happened during a replay
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Packets sent to the server
+    pub sent: usize,
+    /// Packets received from the server
+    pub received: usize,
+    /// Reason given by the server for disconnecting, if it did
+    pub disconnect_reason: Option<String>,
+    /// Dump line being replayed when the server closed the connection
+    pub closed_at_line: Option<usize>,
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sent {} packet(s), received {}",
+            self.sent, self.received
+        )?;
+        if let Some(ref reason) = self.disconnect_reason {
+            write!(f, "; disconnected: {}", reason)?;
+        }
+        if let Some(line) = self.closed_at_line {
+            write!(f, "; connection closed at line {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Replays the client side of a packet dump
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    /// Dump to replay
+    dump: PacketDump,
+    /// Whether to keep the recorded time between packets
+    realtime: bool,
+    /// How long to wait for each packet the server must send
+    timeout: Duration,
+    /// How long to keep reading after the last packet
+    settle_time: Duration,
+}
+
+impl Replayer {
+    /// Create a replayer for a dump
+    pub fn new(dump: PacketDump) -> Self {
+        Self {
+            dump,
+            realtime: true,
+            timeout: DEFAULT_TIMEOUT,
+            settle_time: DEFAULT_SETTLE_TIME,
+        }
+    }
+
+    /// Keep the recorded time between packets, or send them as fast as
+    /// possible
+    pub fn with_realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+
+    /// Set how long to wait for each packet the server must send
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set how long to keep reading after the last packet
+    pub fn with_settle_time(mut self, settle_time: Duration) -> Self {
+        self.settle_time = settle_time;
+        self
+    }
+
+    /// Replay the dump against the server at `addr`
+    ///
+    /// Fails if the server does not send a packet the recorded client
+    /// waited for; the server closing the connection is reported instead.
+    pub async fn run(&self, addr: SocketAddr) -> Result<ReplayReport> {
+        if self.dump.protocol.is_some_and(|p| p != PROTOCOL_VERSION) {
+            tracing::warn!(
+                "Replaying a dump recorded with protocol {:?} against protocol {}",
+                self.dump.protocol,
+                PROTOCOL_VERSION
+            );
+        }
+
+        let mut session = Session {
+            client: TestClient::connect(addr).await?.with_timeout(self.timeout),
+            report: ReplayReport::default(),
+            pending: Vec::new(),
+        };
+        let started = Instant::now();
+
+        for entry in &self.dump.entries {
+            let outcome = match entry.direction {
+                Direction::Serverbound => {
+                    let due = if self.realtime {
+                        started + entry.elapsed
+                    } else {
+                        Instant::now()
+                    };
+                    session.send(entry, due).await
+                }
+                Direction::Clientbound if is_sync_point(entry.state, entry.packet.id) => {
+                    session.wait_for(entry).await
+                }
+                Direction::Clientbound => Ok(()),
+            };
+            if let Err(e) = outcome {
+                if is_closed(&e) {
+                    session.report.closed_at_line = Some(entry.line);
+                    return Ok(session.report);
+                }
+                return Err(e);
+            }
+        }
+
+        let state = self
+            .dump
+            .entries
+            .last()
+            .map_or(ConnectionState::Handshaking, |entry| entry.state);
+        if let Err(e) = session
+            .drain(state, Instant::now() + self.settle_time)
+            .await
+        {
+            if !is_closed(&e) {
+                return Err(e);
+            }
+        }
+        Ok(session.report)
+    }
+}
+
+/// A replay in progress
+struct Session {
+    /// Connection to the server
+    client: TestClient,
+    /// What happened so far
+    report: ReplayReport,
+    /// Packets waited for that arrived before their turn
+    pending: Vec<(ConnectionState, i32)>,
+}
+
+impl Session {
+    /// Send a recorded packet once it is due, reading from the server until
+    /// then
+    async fn send(&mut self, entry: &DumpEntry, due: Instant) -> Result<()> {
+        self.drain(entry.state, due).await?;
+        self.client
+            .connection()
+            .write_raw_packet(&entry.packet)
+            .await?;
+        self.report.sent += 1;
+        Ok(())
+    }
+
+    /// Read from the server until it sends the recorded packet
+    async fn wait_for(&mut self, entry: &DumpEntry) -> Result<()> {
+        let expected = (entry.state, entry.packet.id);
+        loop {
+            if let Some(index) = self.pending.iter().position(|p| *p == expected) {
+                self.pending.remove(index);
+                return Ok(());
+            }
+            let (id, data) = self.client.read_packet().await.map_err(|e| {
+                if is_closed(&e) {
+                    return e;
+                }
+                ServerError::Protocol(format!(
+                    "Line {}: the server did not send {} packet 0x{:02X}: {}",
+                    entry.line,
+                    entry.state.as_str(),
+                    entry.packet.id,
+                    e
+                ))
+            })?;
+            self.receive(entry.state, id.0, &data)?;
+        }
+    }
+
+    /// Read whatever the server sends until `deadline`
+    async fn drain(&mut self, state: ConnectionState, deadline: Instant) -> Result<()> {
+        loop {
+            // A deadline in the past still reads packets already received
+            let read = tokio::time::timeout_at(deadline, self.client.connection().read_packet());
+            match read.await {
+                Ok(packet) => {
+                    let (id, data) = packet?;
+                    self.receive(state, id.0, &data)?;
+                }
+                Err(_) => return Ok(()),
+            }
+        }
+    }
+
+    /// Handle a packet from the server
+    fn receive(&mut self, state: ConnectionState, id: i32, data: &[u8]) -> Result<()> {
+        self.report.received += 1;
+        if is_sync_point(state, id) {
+            self.pending.push((state, id));
+        }
+
+        match (state, id) {
+            (ConnectionState::Login, SetCompressionPacket::ID) => {
+                let packet: SetCompressionPacket = decode(data)?;
+                if let Ok(threshold) = u32::try_from(packet.threshold.0) {
+                    self.client.connection().enable_compression(threshold)?;
+                }
+            }
+            (ConnectionState::Login, LoginDisconnectPacket::ID) => {
+                let packet: LoginDisconnectPacket = decode(data)?;
+                self.report.disconnect_reason = Some(packet.reason.to_plain_text());
+            }
+            (ConnectionState::Configuration, ConfigurationDisconnectPacket::ID) => {
+                let packet: ConfigurationDisconnectPacket = decode(data)?;
+                self.report.disconnect_reason = Some(packet.reason.to_plain_text());
+            }
+            (ConnectionState::Play, DisconnectPacket::ID) => {
+                let packet: DisconnectPacket = decode(data)?;
+                self.report.disconnect_reason = Some(packet.reason.to_plain_text());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Check whether the client must wait for a clientbound packet
+fn is_sync_point(state: ConnectionState, id: i32) -> bool {
+    match state {
+        ConnectionState::Login => matches!(
+            id,
+            SetCompressionPacket::ID | LoginSuccessPacket::ID | LoginDisconnectPacket::ID
+        ),
+        ConnectionState::Configuration => matches!(
+            id,
+            FinishConfigurationPacket::ID | ConfigurationDisconnectPacket::ID
+        ),
+        ConnectionState::Play => matches!(id, LoginPlayPacket::ID | DisconnectPacket::ID),
+        ConnectionState::Handshaking | ConnectionState::Status => false,
+    }
+}
+
+/// Check whether an error means the server closed the connection
+fn is_closed(error: &ServerError) -> bool {
+    matches!(
+        error,
+        ServerError::Io(e) if matches!(
+            e.kind(),
+            std::io::ErrorKind::UnexpectedEof
+                | std::io::ErrorKind::ConnectionReset
+                | std::io::ErrorKind::BrokenPipe
+        )
+    )
+}
+
+/// Decode a packet body
+fn decode<P: Packet>(data: &[u8]) -> Result<P> {
+    P::read(&mut std::io::Cursor::new(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::server::MinecraftServer;
+    use crate::server::audit::AuditConfig;
+    use crate::server::usercache::USERCACHE_FILE;
+
+    #[tokio::test]
+    async fn test_replay_recorded_login() {
+        let root = std::env::temp_dir().join(format!("obsidium-replay-{}", uuid::Uuid::new_v4()));
+        let dumps = root.join("dumps");
+        let config = ServerConfig::new()
+            .with_bind_address("127.0.0.1:0".parse().unwrap())
+            .with_level_name(root.join("world").to_string_lossy().into_owned())
+            .with_usercache_path(Some(root.join(USERCACHE_FILE)))
+            .with_online_mode(false)
+            .with_compression_threshold(Some(64))
+            .with_view_distance(2)
+            .with_plugins_enabled(false)
+            .with_hot_reload(false)
+            .with_max_tick_time(None)
+            .with_login_throttle(Duration::ZERO)
+            .with_audit(AuditConfig::disabled())
+            .with_packet_dump_dir(Some(dumps.clone()));
+        let handle = MinecraftServer::new(config)
+            .await
+            .unwrap()
+            .start()
+            .await
+            .unwrap();
+        let addr = handle.local_addr();
+
+        drop(TestClient::assert_joins(addr, "Steve").await);
+        while handle.player_count() > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let path = std::fs::read_dir(&dumps)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let dump = PacketDump::load(path).unwrap();
+        let serverbound = dump.serverbound().count();
+        assert!(serverbound >= 4);
+
+        let report = Replayer::new(dump)
+            .with_realtime(false)
+            .with_settle_time(Duration::from_millis(50))
+            .run(addr)
+            .await
+            .unwrap();
+        assert_eq!(report.sent, serverbound);
+        assert!(report.received > 0);
+        assert_eq!(report.disconnect_reason, None);
+        assert_eq!(report.closed_at_line, None);
+
+        handle.stop();
+        handle.wait().await.unwrap();
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
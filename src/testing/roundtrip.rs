@@ -0,0 +1,276 @@
+//! Packet roundtrip harness
+//!
+//! [`check_roundtrip`] encodes a packet and checks that the codec agrees
+//! with itself:
+//!
+//! - decoding consumes exactly the bytes that were written
+//! - encoding the decoded packet reproduces those bytes, which catches
+//!   fields read in a different order than they are written
+//! - every truncation of the encoding is rejected rather than decoded as
+//!   something else
+//!
+//! Packets whose last field takes up the rest of the packet, such as plugin
+//! messages, accept truncations by design; [`check_roundtrip_open_ended`]
+//! skips that last check for them.
+//!
+//! [`assert_roundtrip`] runs the check on generated packets. Generation is
+//! seeded from [`SEED_ENV`] when set, so a failure can be replayed with the
+//! seed it reports.
+
+use crate::protocol::packets::Packet;
+use crate::testing::arbitrary::{Arbitrary, Rng};
+use std::fmt::Debug;
+use std::io::Cursor;
+
+/// Number of packets [`assert_roundtrip`] checks per type by default
+pub const DEFAULT_CASES: usize = 256;
+
+/// Environment variable overriding the generation seed
+pub const SEED_ENV: &str = "OBSIDIUM_TEST_SEED";
+
+/// Seed used when [`SEED_ENV`] is not set
+const DEFAULT_SEED: u64 = 0x0B51_D1A3;
+
+/// Seed for generated test cases
+pub fn seed() -> u64 {
+    std::env::var(SEED_ENV)
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(DEFAULT_SEED)
+}
+
+/// Check that a packet survives encoding and decoding
+///
+/// Returns a description of the first problem found.
+pub fn check_roundtrip<P: Packet>(packet: &P) -> Result<(), String> {
+    let encoded = check_codec(packet)?;
+    for len in 0..encoded.len() {
+        if P::read(&mut Cursor::new(&encoded[..len])).is_ok() {
+            return Err(format!(
+                "decoding accepted the first {} of {} bytes",
+                len,
+                encoded.len()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that a packet whose last field takes up the rest of the packet
+/// survives encoding and decoding
+pub fn check_roundtrip_open_ended<P: Packet>(packet: &P) -> Result<(), String> {
+    check_codec(packet).map(|_| ())
+}
+
+/// Check `cases` generated packets of one type
+///
+/// Panics with the packet, the problem and the seed on the first failure.
+pub fn assert_roundtrip<P: Packet + Arbitrary + Debug>(cases: usize) {
+    assert_generated(cases, check_roundtrip::<P>);
+}
+
+/// Check `cases` generated packets of one open-ended type
+pub fn assert_roundtrip_open_ended<P: Packet + Arbitrary + Debug>(cases: usize) {
+    assert_generated(cases, check_roundtrip_open_ended::<P>);
+}
+
+/// Run a check on `cases` generated packets
+fn assert_generated<P, F>(cases: usize, check: F)
+where
+    P: Arbitrary + Debug,
+    F: Fn(&P) -> Result<(), String>,
+{
+    let seed = seed();
+    let mut rng = Rng::new(seed);
+    for case in 0..cases {
+        let packet = P::arbitrary(&mut rng);
+        assert_eq!(
+            check(&packet),
+            Ok(()),
+            "{} roundtrip failed on case {} ({}={})\n{:#?}",
+            std::any::type_name::<P>(),
+            case,
+            SEED_ENV,
+            seed,
+            packet
+        );
+    }
+}
+
+/// Check that decoding reads back exactly what was encoded, returning the
+/// encoding
+fn check_codec<P: Packet>(packet: &P) -> Result<Vec<u8>, String> {
+    let encoded = encode(packet)?;
+
+    let mut cursor = Cursor::new(encoded.as_slice());
+    let decoded = P::read(&mut cursor).map_err(|e| format!("decoding failed: {}", e))?;
+    let consumed = cursor.position() as usize;
+    if consumed != encoded.len() {
+        return Err(format!(
+            "decoding consumed {} of {} bytes",
+            consumed,
+            encoded.len()
+        ));
+    }
+
+    let reencoded = encode(&decoded)?;
+    if reencoded != encoded {
+        return Err(format!(
+            "re-encoding differs:\n  written:    {:02X?}\n  re-encoded: {:02X?}",
+            encoded, reencoded
+        ));
+    }
+
+    Ok(encoded)
+}
+
+/// Encode a packet body
+fn encode<P: Packet>(packet: &P) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    packet
+        .write(&mut buffer)
+        .map_err(|e| format!("encoding failed: {}", e))?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::packets::configuration::*;
+    use crate::protocol::packets::handshaking::*;
+    use crate::protocol::packets::login::*;
+    use crate::protocol::packets::play::*;
+    use crate::protocol::packets::status::*;
+
+    #[test]
+    fn test_all_packets_roundtrip() {
+        assert_roundtrip::<HandshakePacket>(DEFAULT_CASES);
+        assert_roundtrip::<LegacyServerListPingPacket>(DEFAULT_CASES);
+
+        assert_roundtrip::<StatusRequestPacket>(DEFAULT_CASES);
+        assert_roundtrip::<StatusResponsePacket>(DEFAULT_CASES);
+        assert_roundtrip::<PingRequestPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PingResponsePacket>(DEFAULT_CASES);
+
+        assert_roundtrip::<LoginDisconnectPacket>(DEFAULT_CASES);
+        assert_roundtrip::<LoginStartPacket>(DEFAULT_CASES);
+        assert_roundtrip::<EncryptionRequestPacket>(DEFAULT_CASES);
+        assert_roundtrip::<EncryptionResponsePacket>(DEFAULT_CASES);
+        assert_roundtrip::<LoginSuccessPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetCompressionPacket>(DEFAULT_CASES);
+        assert_roundtrip::<LoginAcknowledgedPacket>(DEFAULT_CASES);
+
+        assert_roundtrip::<ConfigurationDisconnectPacket>(DEFAULT_CASES);
+        assert_roundtrip::<FinishConfigurationPacket>(DEFAULT_CASES);
+        assert_roundtrip::<AcknowledgeFinishConfigurationPacket>(DEFAULT_CASES);
+        assert_roundtrip::<RegistryDataPacket>(DEFAULT_CASES);
+        assert_roundtrip::<ClientInformationPacket>(DEFAULT_CASES);
+        assert_roundtrip_open_ended::<PluginMessagePacket>(DEFAULT_CASES);
+        assert_roundtrip_open_ended::<ClientboundPluginMessagePacket>(DEFAULT_CASES);
+        assert_roundtrip::<AddResourcePackPacket>(DEFAULT_CASES);
+        assert_roundtrip::<ResourcePackResponsePacket>(DEFAULT_CASES);
+
+        assert_roundtrip::<KeepAlivePacket>(DEFAULT_CASES);
+        assert_roundtrip::<ServerboundKeepAlivePacket>(DEFAULT_CASES);
+        assert_roundtrip::<DisconnectPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SystemChatMessagePacket>(DEFAULT_CASES);
+        assert_roundtrip::<StartConfigurationPacket>(DEFAULT_CASES);
+        assert_roundtrip::<ConfigurationAcknowledgedPacket>(DEFAULT_CASES);
+        assert_roundtrip::<ChatMessagePacket>(DEFAULT_CASES);
+        assert_roundtrip::<ChatCommandPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerPositionPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerPositionAndRotationPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerRotationPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerOnGroundPacket>(DEFAULT_CASES);
+        assert_roundtrip::<ServerboundPlayerAbilitiesPacket>(DEFAULT_CASES);
+        assert_roundtrip::<BlockChangePacket>(DEFAULT_CASES);
+        assert_roundtrip::<UpdateSectionBlocksPacket>(DEFAULT_CASES);
+        assert_roundtrip::<LoginPlayPacket>(DEFAULT_CASES);
+        assert_roundtrip::<ChangeDifficultyPacket>(DEFAULT_CASES);
+        assert_roundtrip::<ChangeDifficultyRequestPacket>(DEFAULT_CASES);
+        assert_roundtrip::<LockDifficultyPacket>(DEFAULT_CASES);
+        assert_roundtrip::<UpdateTimePacket>(DEFAULT_CASES);
+        assert_roundtrip::<GameEventPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerAbilitiesPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayClientInformationPacket>(DEFAULT_CASES);
+        assert_roundtrip_open_ended::<PlayPluginMessagePacket>(DEFAULT_CASES);
+        assert_roundtrip_open_ended::<PlayClientboundPluginMessagePacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetChunkCacheRadiusPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetSimulationDistancePacket>(DEFAULT_CASES);
+        assert_roundtrip::<ChunkDataPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetCenterChunkPacket>(DEFAULT_CASES);
+        assert_roundtrip::<UnloadChunkPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetDefaultSpawnPositionPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetEntityMetadataPacket>(DEFAULT_CASES);
+        assert_roundtrip::<EntityAnimationPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerCommandPacket>(DEFAULT_CASES);
+        assert_roundtrip::<UseItemOnPacket>(DEFAULT_CASES);
+        assert_roundtrip::<UpdateRecipesPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerInfoUpdatePacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerInfoRemovePacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerInputPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetHealthPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SpawnEntityPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetPassengersPacket>(DEFAULT_CASES);
+        assert_roundtrip::<EntityPositionSyncPacket>(DEFAULT_CASES);
+        assert_roundtrip::<UpdateEntityPositionPacket>(DEFAULT_CASES);
+        assert_roundtrip::<UpdateEntityPositionAndRotationPacket>(DEFAULT_CASES);
+        assert_roundtrip::<UpdateEntityRotationPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetHeadRotationPacket>(DEFAULT_CASES);
+        assert_roundtrip::<MoveVehiclePacket>(DEFAULT_CASES);
+        assert_roundtrip::<InteractPacket>(DEFAULT_CASES);
+        assert_roundtrip::<ExplodePacket>(DEFAULT_CASES);
+        assert_roundtrip::<RemoveEntitiesPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerActionPacket>(DEFAULT_CASES);
+        assert_roundtrip::<AcknowledgeBlockChangePacket>(DEFAULT_CASES);
+        assert_roundtrip::<SynchronizePlayerPositionPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetCameraPacket>(DEFAULT_CASES);
+        assert_roundtrip::<TeleportToEntityPacket>(DEFAULT_CASES);
+        assert_roundtrip::<PlayerChatMessagePacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetEquipmentPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetHeldItemPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetCreativeModeSlotPacket>(DEFAULT_CASES);
+        assert_roundtrip::<UseItemPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetContainerSlotPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SoundEffectPacket>(DEFAULT_CASES);
+        assert_roundtrip::<OpenScreenPacket>(DEFAULT_CASES);
+        assert_roundtrip::<RespawnPacket>(DEFAULT_CASES);
+        assert_roundtrip::<MerchantOffersPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SelectTradePacket>(DEFAULT_CASES);
+        assert_roundtrip::<ClickContainerPacket>(DEFAULT_CASES);
+        assert_roundtrip::<CloseContainerPacket>(DEFAULT_CASES);
+        assert_roundtrip::<SetContainerContentPacket>(DEFAULT_CASES);
+        assert_roundtrip::<CommandsPacket>(DEFAULT_CASES);
+        assert_roundtrip::<EntityEventPacket>(DEFAULT_CASES);
+    }
+
+    /// Writes its fields in one order and reads them in the other
+    #[derive(Debug)]
+    struct SwappedPacket {
+        flag: u8,
+        value: i64,
+    }
+
+    impl Packet for SwappedPacket {
+        const ID: i32 = 0x00;
+        const STATE: crate::protocol::ConnectionState = crate::protocol::ConnectionState::Play;
+
+        fn read<R: std::io::Read>(reader: &mut R) -> crate::Result<Self> {
+            let value = crate::protocol::types::read_long(reader)?;
+            let flag = crate::protocol::types::read_unsigned_byte(reader)?;
+            Ok(SwappedPacket { flag, value })
+        }
+
+        fn write<W: std::io::Write>(&self, writer: &mut W) -> crate::Result<()> {
+            crate::protocol::types::write_unsigned_byte(self.flag, writer)?;
+            crate::protocol::types::write_long(self.value, writer)
+        }
+    }
+
+    #[test]
+    fn test_detects_field_order_bugs() {
+        let packet = SwappedPacket { flag: 1, value: 2 };
+        let problem = check_roundtrip(&packet).unwrap_err();
+        assert!(problem.contains("re-encoding differs"));
+    }
+}